@@ -32,6 +32,16 @@ pub enum TwoFactorError {
 
 pub type TwoFactorResult<T> = Result<T, TwoFactorError>;
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(TwoFactorError, {
+    Self::InvalidCode => (UNPROCESSABLE_ENTITY, "2fa.invalid_code"),
+    Self::BackupCodeNotFound => (UNPROCESSABLE_ENTITY, "2fa.invalid_backup_code"),
+    Self::DeviceNotTrusted => (FORBIDDEN, "2fa.device_not_trusted"),
+    Self::InvalidSecret => (INTERNAL_SERVER_ERROR, "2fa.invalid_secret"),
+    Self::TotpError(_) => (INTERNAL_SERVER_ERROR, "2fa.totp"),
+    Self::QrCodeError(_) => (INTERNAL_SERVER_ERROR, "2fa.qr_code"),
+});
+
 /// TOTP manager for 2FA
 pub struct TotpManager {
     issuer: String,
@@ -1,6 +1,7 @@
 //! Admin Panel Generator for RustForge
 //!
-//! This crate provides automatic CRUD interface generation.
+//! This crate provides automatic CRUD interface generation, record actions
+//! and dashboard widgets.
 
 use async_trait::async_trait;
 use axum::{
@@ -10,6 +11,7 @@ use axum::{
     routing::{get, post},
     Json, Router,
 };
+use rf_pagination::{PageBounds, PageParams, Paginated};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
@@ -35,6 +37,14 @@ pub enum AdminError {
 
 pub type AdminResult<T> = Result<T, AdminError>;
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(AdminError, {
+    Self::ResourceNotFound(_) => (NOT_FOUND, "admin.resource_not_found"),
+    Self::ValidationError(_) => (BAD_REQUEST, "admin.validation"),
+    Self::AuthorizationError(_) => (FORBIDDEN, "admin.forbidden"),
+    Self::DatabaseError(_) => (INTERNAL_SERVER_ERROR, "admin.database"),
+});
+
 impl IntoResponse for AdminError {
     fn into_response(self) -> Response {
         let status = match self {
@@ -128,6 +138,19 @@ pub struct ListParams {
     pub order: Option<String>,
 }
 
+impl ListParams {
+    /// The requested page, validated like the pages of other list endpoints
+    pub fn page_params(&self) -> AdminResult<PageParams> {
+        let bounds = PageBounds::default();
+        PageParams::new(
+            self.page.map_or(1, i64::from),
+            self.per_page.map_or(bounds.default_per_page, i64::from),
+            bounds,
+        )
+        .map_err(|e| AdminError::ValidationError(e.to_string()))
+    }
+}
+
 /// Admin resource trait
 #[async_trait]
 pub trait AdminResource: Send + Sync + 'static {
@@ -164,6 +187,71 @@ pub trait AdminResource: Send + Sync + 'static {
     fn icon(&self) -> Option<&str> {
         None
     }
+
+    /// Actions offered on a single record, besides editing and deleting
+    fn actions(&self) -> Vec<ResourceAction> {
+        Vec::new()
+    }
+
+    /// Run action `action` on record `id`
+    async fn run_action(&self, action: &str, _id: &str) -> AdminResult<serde_json::Value> {
+        Err(AdminError::ResourceNotFound(format!(
+            "{}/actions/{}",
+            self.name(),
+            action
+        )))
+    }
+}
+
+/// Action on a single record, e.g. "retry"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceAction {
+    pub name: String,
+    pub label: String,
+    /// Ask for confirmation before running it
+    pub confirm: bool,
+}
+
+impl ResourceAction {
+    pub fn new(name: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            label: label.into(),
+            confirm: false,
+        }
+    }
+
+    pub fn confirm(mut self) -> Self {
+        self.confirm = true;
+        self
+    }
+}
+
+/// How the dashboard renders a widget's data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WidgetKind {
+    /// Single values, as an object of label to value
+    Metric,
+    /// Series over time, as an array of points
+    Chart,
+    /// Rows, as an array of objects
+    Table,
+}
+
+/// Dashboard widget trait
+#[async_trait]
+pub trait AdminWidget: Send + Sync + 'static {
+    /// Get widget name
+    fn name(&self) -> &str;
+
+    /// Get widget label (for display)
+    fn label(&self) -> &str;
+
+    fn kind(&self) -> WidgetKind;
+
+    /// Current data, shaped as described by [`WidgetKind`]
+    async fn data(&self) -> AdminResult<serde_json::Value>;
 }
 
 /// List response
@@ -189,10 +277,24 @@ impl AdminList {
     }
 }
 
+impl From<Paginated<serde_json::Value>> for AdminList {
+    fn from(page: Paginated<serde_json::Value>) -> Self {
+        let paginator = page.paginator;
+        Self {
+            data: page.items,
+            total: paginator.total.max(0) as u64,
+            page: paginator.current_page as u32,
+            per_page: paginator.per_page as u32,
+            last_page: paginator.last_page as u32,
+        }
+    }
+}
+
 /// Admin panel
 pub struct AdminPanel {
     title: String,
     resources: HashMap<String, Arc<dyn AdminResource>>,
+    widgets: Vec<Arc<dyn AdminWidget>>,
 }
 
 impl AdminPanel {
@@ -201,6 +303,7 @@ impl AdminPanel {
         Self {
             title: "Admin Panel".to_string(),
             resources: HashMap::new(),
+            widgets: Vec::new(),
         }
     }
 
@@ -216,6 +319,12 @@ impl AdminPanel {
         self
     }
 
+    /// Add a widget to the dashboard, after the ones added before
+    pub fn widget(mut self, widget: Arc<dyn AdminWidget>) -> Self {
+        self.widgets.push(widget);
+        self
+    }
+
     /// Build the admin panel router
     pub fn build(self) -> Router {
         let state = Arc::new(self);
@@ -230,6 +339,12 @@ impl AdminPanel {
             .route("/resources/:resource/:id/edit", get(resource_edit_form_handler))
             .route("/resources/:resource/:id", post(resource_update_handler))
             .route("/resources/:resource/:id/delete", post(resource_delete_handler))
+            .route(
+                "/resources/:resource/:id/actions/:action",
+                post(resource_action_handler),
+            )
+            .route("/widgets", get(widgets_handler))
+            .route("/widgets/:widget", get(widget_handler))
             .with_state(state)
     }
 }
@@ -290,6 +405,7 @@ async fn resources_handler(
                 "label": r.label(),
                 "menu_group": r.menu_group(),
                 "icon": r.icon(),
+                "actions": r.actions(),
             })
         })
         .collect();
@@ -307,6 +423,7 @@ async fn resource_list_handler(
         .get(&resource_name)
         .ok_or_else(|| AdminError::ResourceNotFound(resource_name.clone()))?;
 
+    params.page_params()?;
     let list = resource.list(params).await?;
     Ok(Json(list))
 }
@@ -396,6 +513,55 @@ async fn resource_delete_handler(
     Ok(StatusCode::NO_CONTENT)
 }
 
+async fn resource_action_handler(
+    Path((resource_name, id, action)): Path<(String, String, String)>,
+    axum::extract::State(panel): axum::extract::State<Arc<AdminPanel>>,
+) -> Result<impl IntoResponse, AdminError> {
+    let resource = panel
+        .resources
+        .get(&resource_name)
+        .ok_or_else(|| AdminError::ResourceNotFound(resource_name.clone()))?;
+
+    let result = resource.run_action(&action, &id).await?;
+    Ok(Json(result))
+}
+
+async fn widgets_handler(
+    axum::extract::State(panel): axum::extract::State<Arc<AdminPanel>>,
+) -> impl IntoResponse {
+    let widgets: Vec<_> = panel
+        .widgets
+        .iter()
+        .map(|w| {
+            serde_json::json!({
+                "name": w.name(),
+                "label": w.label(),
+                "kind": w.kind(),
+            })
+        })
+        .collect();
+
+    Json(widgets)
+}
+
+async fn widget_handler(
+    Path(widget_name): Path<String>,
+    axum::extract::State(panel): axum::extract::State<Arc<AdminPanel>>,
+) -> Result<impl IntoResponse, AdminError> {
+    let widget = panel
+        .widgets
+        .iter()
+        .find(|w| w.name() == widget_name)
+        .ok_or_else(|| AdminError::ResourceNotFound(widget_name.clone()))?;
+
+    Ok(Json(serde_json::json!({
+        "name": widget.name(),
+        "label": widget.label(),
+        "kind": widget.kind(),
+        "data": widget.data().await?,
+    })))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -530,6 +696,23 @@ mod tests {
         assert_eq!(list.total, 2);
     }
 
+    #[test]
+    fn test_list_pagination() {
+        let params: ListParams = serde_json::from_str(r#"{"page": 2, "per_page": 2}"#).unwrap();
+        let page_params = params.page_params().unwrap();
+        let items = vec![serde_json::json!({"id": 3})];
+        let list = AdminList::from(Paginated::new(items, page_params.paginator(3)));
+        assert_eq!((list.page, list.per_page, list.last_page, list.total), (2, 2, 2, 3));
+
+        let params: ListParams = serde_json::from_str(r#"{"per_page": 1000}"#).unwrap();
+        assert!(matches!(
+            params.page_params(),
+            Err(AdminError::ValidationError(_))
+        ));
+        let params: ListParams = serde_json::from_str(r#"{"page": 0}"#).unwrap();
+        assert!(params.page_params().is_err());
+    }
+
     #[tokio::test]
     async fn test_resource_get() {
         let resource = TestResource;
@@ -586,6 +769,62 @@ mod tests {
         assert_eq!(resource.icon(), Some("user"));
     }
 
+    struct TestWidget;
+
+    #[async_trait]
+    impl AdminWidget for TestWidget {
+        fn name(&self) -> &str {
+            "signups"
+        }
+
+        fn label(&self) -> &str {
+            "Signups"
+        }
+
+        fn kind(&self) -> WidgetKind {
+            WidgetKind::Metric
+        }
+
+        async fn data(&self) -> AdminResult<serde_json::Value> {
+            Ok(serde_json::json!({ "today": 12 }))
+        }
+    }
+
+    async fn send(app: &Router, method: &str, uri: &str) -> (StatusCode, serde_json::Value) {
+        use tower::ServiceExt;
+
+        let req = axum::http::Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        let status = res.status();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_widgets_and_actions() {
+        let app = AdminPanel::new()
+            .resource(Arc::new(TestResource))
+            .widget(Arc::new(TestWidget))
+            .build();
+
+        let (_, widgets) = send(&app, "GET", "/widgets").await;
+        assert_eq!(widgets[0]["kind"], "metric");
+        let (status, widget) = send(&app, "GET", "/widgets/signups").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(widget["data"]["today"], 12);
+        let (status, _) = send(&app, "GET", "/widgets/unknown").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = send(&app, "POST", "/resources/users/1/actions/ban").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
     #[test]
     fn test_field_types() {
         let text = FieldType::Text;
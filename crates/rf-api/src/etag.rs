@@ -0,0 +1,156 @@
+//! ETags and conditional GET requests
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+/// Middleware adding ETags to GET responses and answering matching
+/// `If-None-Match` requests with `304 Not Modified`
+///
+/// Successful responses without an ETag get one hashed from their body;
+/// streamed bodies of unknown size are left alone. ETags set by handlers,
+/// e.g. from a model's version, are kept.
+///
+/// ```
+/// use axum::{middleware, routing::get, Router};
+///
+/// let app: Router = Router::new()
+///     .route("/posts", get(|| async { "posts" }))
+///     .layer(middleware::from_fn(rf_api::etag));
+/// ```
+pub async fn etag(req: Request, next: Next) -> Response {
+    if !matches!(*req.method(), Method::GET | Method::HEAD) {
+        return next.run(req).await;
+    }
+    let if_none_match = req.headers().get(header::IF_NONE_MATCH).cloned();
+
+    let mut response = next.run(req).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    if !response.headers().contains_key(header::ETAG) {
+        if response.body().size_hint().exact().is_none() {
+            return response;
+        }
+        let (mut parts, body) = response.into_parts();
+        let body = match axum::body::to_bytes(body, usize::MAX).await {
+            Ok(body) => body,
+            Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        };
+        let hash = Sha256::digest(&body);
+        let tag = format!("\"{}\"", hex::encode(&hash[..16]));
+        if let Ok(value) = HeaderValue::from_str(&tag) {
+            parts.headers.insert(header::ETAG, value);
+        }
+        response = Response::from_parts(parts, Body::from(body));
+    }
+
+    let etag = response.headers().get(header::ETAG);
+    match (if_none_match, etag) {
+        (Some(candidates), Some(etag)) if matches(&candidates, etag) => {
+            not_modified(response.headers())
+        }
+        _ => response,
+    }
+}
+
+/// Whether `If-None-Match` matches `etag`, comparing weakly
+fn matches(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+    let (Ok(candidates), Ok(etag)) = (if_none_match.to_str(), etag.to_str()) else {
+        return false;
+    };
+    let etag = etag.trim_start_matches("W/");
+    candidates
+        .split(',')
+        .map(str::trim)
+        .any(|c| c == "*" || c.trim_start_matches("W/") == etag)
+}
+
+/// `304 Not Modified` with the caching headers of the full response
+fn not_modified(headers: &HeaderMap) -> Response {
+    let mut response = StatusCode::NOT_MODIFIED.into_response();
+    for name in [
+        header::ETAG,
+        header::CACHE_CONTROL,
+        header::CONTENT_LOCATION,
+        header::DATE,
+        header::EXPIRES,
+        header::VARY,
+        header::LAST_MODIFIED,
+    ] {
+        if let Some(value) = headers.get(&name) {
+            response.headers_mut().insert(name, value.clone());
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/posts",
+                get(|| async { "posts" }).post(|| async { "created" }),
+            )
+            .route(
+                "/versioned",
+                get(|| async { ([(header::ETAG, "W/\"v7\"")], "post") }),
+            )
+            .layer(middleware::from_fn(etag))
+    }
+
+    async fn request(uri: &str, if_none_match: Option<&str>) -> Response {
+        let mut req = Request::get(uri);
+        if let Some(tag) = if_none_match {
+            req = req.header(header::IF_NONE_MATCH, tag);
+        }
+        app()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_conditional_get() {
+        let response = request("/posts", None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let tag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body.as_ref(), b"posts");
+
+        let response = request("/posts", Some(&format!("\"other\", {}", tag))).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], tag.as_str());
+
+        assert_eq!(
+            request("/posts", Some("\"other\"")).await.status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            request("/versioned", Some("\"v7\"")).await.status(),
+            StatusCode::NOT_MODIFIED
+        );
+
+        let response = app()
+            .oneshot(Request::post("/posts").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(!response.headers().contains_key(header::ETAG));
+    }
+}
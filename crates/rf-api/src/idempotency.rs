@@ -0,0 +1,613 @@
+//! Idempotency keys for unsafe requests
+
+use crate::Problem;
+use axum::{
+    body::{Body, HttpBody},
+    extract::Request,
+    http::{header, request::Parts, HeaderName, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use rf_cache::Cache;
+use rf_middleware::{Middleware, MiddlewareService, Next};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{sync::Arc, time::Duration};
+use tower::Layer;
+
+/// Header carrying the key of a request
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// Header marking responses replayed from the cache
+pub const IDEMPOTENT_REPLAYED: &str = "idempotent-replayed";
+
+/// Longest accepted key
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Who a request is from, keys are only shared within it
+type Scope = dyn Fn(&Parts) -> Option<String> + Send + Sync;
+
+/// What is stored under a key
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+enum Entry {
+    /// The first request is still being handled
+    InProgress {
+        fingerprint: String,
+        /// Tells this request's lock from one taken by a retry after it
+        /// expired
+        owner: String,
+    },
+    /// Response of the first request
+    Completed {
+        fingerprint: String,
+        status: u16,
+        headers: Vec<(String, String)>,
+        /// Hex encoded
+        body: String,
+    },
+}
+
+/// Layer making retried `POST` and `PATCH` requests safe
+///
+/// The response to the first request with an `Idempotency-Key` header is
+/// stored in rf-cache, and returned for every retry with the same key
+/// instead of running the handler again. A key reused for a different
+/// request (method, path or body) is rejected with
+/// `422 Unprocessable Entity`, a retry while the first request is still
+/// running with `409 Conflict`.
+///
+/// Keys belong to the caller: by default the `Authorization` header, or
+/// the `Cookie` header without one, so the same key sent by two users
+/// never replays one user's response to the other. [`scope`] sets how
+/// callers are told apart. `Set-Cookie` headers are never stored or
+/// replayed.
+///
+/// Server errors aren't stored, so requests failing that way can be
+/// retried with the same key. Neither are responses that stream or are
+/// larger than [`response_limit`]; they release the key as well.
+///
+/// [`scope`]: IdempotencyLayer::scope
+/// [`response_limit`]: IdempotencyLayer::response_limit
+///
+/// ```
+/// use axum::{routing::post, Router};
+/// use rf_api::IdempotencyLayer;
+/// use rf_cache::MemoryCache;
+/// use std::time::Duration;
+///
+/// let app: Router = Router::new()
+///     .route("/payments", post(|| async { "Paid" }))
+///     .layer(IdempotencyLayer::new(MemoryCache::new()).ttl(Duration::from_secs(24 * 3600)));
+/// ```
+pub struct IdempotencyLayer<C> {
+    inner: Arc<Inner<C>>,
+}
+
+struct Inner<C> {
+    cache: C,
+    ttl: Duration,
+    lock_ttl: Duration,
+    methods: Vec<Method>,
+    required: bool,
+    body_limit: usize,
+    response_limit: usize,
+    scope: Arc<Scope>,
+}
+
+impl<C: Cache> IdempotencyLayer<C> {
+    pub fn new(cache: C) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                cache,
+                ttl: Duration::from_secs(24 * 60 * 60),
+                lock_ttl: Duration::from_secs(60),
+                methods: vec![Method::POST, Method::PATCH],
+                required: false,
+                body_limit: 2 * 1024 * 1024,
+                response_limit: 2 * 1024 * 1024,
+                scope: Arc::new(caller),
+            }),
+        }
+    }
+
+    /// How long responses are kept for retries (default: 24 hours)
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.inner_mut().ttl = ttl;
+        self
+    }
+
+    /// How long a key stays locked by a request that never finishes, e.g.
+    /// because the instance died (default: one minute)
+    ///
+    /// A request running longer lets a retry take the key over; its own
+    /// response then neither replaces the retry's lock nor releases it.
+    pub fn lock_ttl(mut self, ttl: Duration) -> Self {
+        self.inner_mut().lock_ttl = ttl;
+        self
+    }
+
+    /// Methods keys are honored for (default: `POST` and `PATCH`)
+    pub fn methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        self.inner_mut().methods = methods.into_iter().collect();
+        self
+    }
+
+    /// Reject requests without a key with `400 Bad Request` (default: off)
+    pub fn required(mut self, required: bool) -> Self {
+        self.inner_mut().required = required;
+        self
+    }
+
+    /// Largest request body accepted with a key (default: 2 MiB)
+    pub fn body_limit(mut self, limit: usize) -> Self {
+        self.inner_mut().body_limit = limit;
+        self
+    }
+
+    /// Largest response body stored for retries (default: 2 MiB)
+    pub fn response_limit(mut self, limit: usize) -> Self {
+        self.inner_mut().response_limit = limit;
+        self
+    }
+
+    /// Tell callers apart by what `scope` returns for a request, e.g. a
+    /// user id put in the extensions by an authentication layer
+    ///
+    /// Requests it returns `None` for share one scope.
+    pub fn scope(
+        mut self,
+        scope: impl Fn(&Parts) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.inner_mut().scope = Arc::new(scope);
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner<C> {
+        Arc::get_mut(&mut self.inner).expect("IdempotencyLayer is configured before use")
+    }
+}
+
+impl<C> Clone for IdempotencyLayer<C> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<C: Cache + 'static> IdempotencyLayer<C> {
+    async fn respond(&self, req: Request, next: Next) -> Response {
+        let config = &self.inner;
+        let key = req
+            .headers()
+            .get(IDEMPOTENCY_KEY)
+            .map(|key| key.to_str().unwrap_or_default().to_string());
+        let key = match key {
+            Some(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key,
+            Some(_) => {
+                return Problem::bad_request(format!(
+                    "The Idempotency-Key header must have 1 to {} visible characters",
+                    MAX_KEY_LENGTH
+                ))
+                .into_response()
+            }
+            None if config.required => {
+                return Problem::bad_request("The Idempotency-Key header is required")
+                    .into_response()
+            }
+            None => return next.run(req).await,
+        };
+
+        let (parts, body) = req.into_parts();
+        let Ok(body) = axum::body::to_bytes(body, config.body_limit).await else {
+            return Problem::new(StatusCode::PAYLOAD_TOO_LARGE).into_response();
+        };
+        let fingerprint = fingerprint(&parts, &body);
+        let cache_key = cache_key((config.scope)(&parts).as_deref(), &key);
+        let req = Request::from_parts(parts, Body::from(body));
+
+        let lock = Entry::InProgress {
+            fingerprint: fingerprint.clone(),
+            owner: uuid::Uuid::new_v4().to_string(),
+        };
+        let locked = match config.cache.add(&cache_key, &lock, config.lock_ttl).await {
+            Ok(locked) => locked,
+            Err(e) => return unavailable(e),
+        };
+        if !locked {
+            return match config.cache.get::<Entry>(&cache_key).await {
+                Ok(Some(entry)) => replay(entry, &fingerprint),
+                // Expired in between, the client may retry
+                Ok(None) => in_progress(),
+                Err(e) => unavailable(e),
+            };
+        }
+
+        let response = next.run(req).await;
+        if response.status().is_server_error() {
+            self.release(&cache_key, &lock).await;
+            return response;
+        }
+
+        // Streamed and large bodies go out as they are
+        let size = HttpBody::size_hint(response.body()).exact();
+        if !size.is_some_and(|size| size <= config.response_limit as u64) {
+            self.release(&cache_key, &lock).await;
+            return response;
+        }
+
+        let (parts, body) = response.into_parts();
+        let Ok(body) = axum::body::to_bytes(body, config.response_limit).await else {
+            self.release(&cache_key, &lock).await;
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        };
+        let entry = Entry::Completed {
+            fingerprint,
+            status: parts.status.as_u16(),
+            headers: parts
+                .headers
+                .iter()
+                .filter(|(name, _)| *name != header::SET_COOKIE)
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.into())))
+                .collect(),
+            body: hex::encode(&body),
+        };
+        // A lock that expired while the handler ran may belong to a retry
+        // by now, whose response is the one to keep
+        let stored = match config
+            .cache
+            .compare_and_set(&cache_key, &lock, &entry, config.ttl)
+            .await
+        {
+            Ok(true) => Ok(true),
+            Ok(false) => config.cache.add(&cache_key, &entry, config.ttl).await,
+            Err(e) => Err(e),
+        };
+        match stored {
+            Ok(true) => {}
+            Ok(false) => tracing::warn!(
+                key = %key,
+                "Idempotency key was taken by a retry before the response was stored"
+            ),
+            Err(e) => {
+                tracing::warn!(key = %key, error = %e, "Failed to store idempotent response")
+            }
+        }
+        Response::from_parts(parts, Body::from(body))
+    }
+
+    /// Let retries run the request again, unless the lock expired and was
+    /// taken by one already
+    async fn release(&self, cache_key: &str, lock: &Entry) {
+        if let Err(e) = self.inner.cache.compare_and_delete(cache_key, lock).await {
+            tracing::warn!(key = %cache_key, error = %e, "Failed to release idempotency key");
+        }
+    }
+}
+
+/// Default scope: the caller's credentials
+fn caller(parts: &Parts) -> Option<String> {
+    let credentials = parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .or_else(|| parts.headers.get(header::COOKIE))?;
+    Some(String::from_utf8_lossy(credentials.as_bytes()).into_owned())
+}
+
+/// Cache key of `key` sent by the caller `scope`
+///
+/// The scope is hashed so credentials don't end up in cache keys.
+fn cache_key(scope: Option<&str>, key: &str) -> String {
+    match scope {
+        Some(scope) => format!(
+            "idempotency:{}:{}",
+            hex::encode(&Sha256::digest(scope.as_bytes())[..16]),
+            key
+        ),
+        None => format!("idempotency:{}", key),
+    }
+}
+
+/// Hash of everything a retry must repeat
+fn fingerprint(parts: &Parts, body: &[u8]) -> String {
+    let mut hash = Sha256::new();
+    hash.update(parts.method.as_str());
+    hash.update([0]);
+    hash.update(parts.uri.to_string());
+    hash.update([0]);
+    hash.update(body);
+    hex::encode(hash.finalize())
+}
+
+/// Answer a retry from what the first request left
+fn replay(entry: Entry, fingerprint: &str) -> Response {
+    let (Entry::InProgress {
+        fingerprint: stored,
+        ..
+    }
+    | Entry::Completed {
+        fingerprint: stored,
+        ..
+    }) = &entry;
+    if stored != fingerprint {
+        return Problem::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .detail("The Idempotency-Key was already used for a different request")
+            .into_response();
+    }
+    let Entry::Completed {
+        status,
+        headers,
+        body,
+        ..
+    } = entry
+    else {
+        return in_progress();
+    };
+
+    let mut response = Response::new(Body::from(hex::decode(body).unwrap_or_default()));
+    *response.status_mut() = StatusCode::from_u16(status).unwrap_or(StatusCode::OK);
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            response.headers_mut().append(name, value);
+        }
+    }
+    response
+        .headers_mut()
+        .insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    response
+}
+
+fn in_progress() -> Response {
+    Problem::new(StatusCode::CONFLICT)
+        .detail("A request with this Idempotency-Key is still being processed")
+        .into_response()
+}
+
+fn unavailable(error: rf_cache::CacheError) -> Response {
+    tracing::error!(error = %error, "Idempotency store unavailable");
+    Problem::new(StatusCode::SERVICE_UNAVAILABLE).into_response()
+}
+
+impl<C: Cache + 'static> Middleware for IdempotencyLayer<C> {
+    async fn handle(self, req: Request, next: Next) -> Response {
+        if !self.inner.methods.contains(req.method()) {
+            return next.run(req).await;
+        }
+        self.respond(req, next).await
+    }
+}
+
+impl<S, C> Layer<S> for IdempotencyLayer<C> {
+    type Service = IdempotencyService<S, C>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+/// Service created by [`IdempotencyLayer`]
+pub type IdempotencyService<S, C> = MiddlewareService<IdempotencyLayer<C>, S>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::to_bytes, routing::post, Router};
+    use rf_cache::MemoryCache;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tower::ServiceExt;
+
+    fn app(layer: IdempotencyLayer<MemoryCache>) -> (Router, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&calls);
+        let failing = Arc::new(AtomicU32::new(0));
+        let app = Router::new()
+            .route(
+                "/payments",
+                post(move |body: String| async move {
+                    let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                    (StatusCode::CREATED, format!("payment {} of {}", n, body))
+                }),
+            )
+            .route(
+                "/login",
+                post(|| async { ([(header::SET_COOKIE, "session=alice")], "Logged in") }),
+            )
+            .route(
+                "/stream",
+                post(|| async {
+                    let chunks = futures::stream::iter([Ok::<_, std::io::Error>("chunk")]);
+                    Body::from_stream(chunks)
+                }),
+            )
+            .route(
+                "/flaky",
+                post(move || async move {
+                    match failing.fetch_add(1, Ordering::SeqCst) {
+                        0 => StatusCode::BAD_GATEWAY,
+                        _ => StatusCode::OK,
+                    }
+                }),
+            )
+            .layer(layer);
+        (app, calls)
+    }
+
+    async fn send(app: &Router, uri: &str, key: Option<&str>, body: &str) -> Response {
+        send_as(app, None, uri, key, body).await
+    }
+
+    async fn send_as(
+        app: &Router,
+        cookie: Option<&str>,
+        uri: &str,
+        key: Option<&str>,
+        body: &str,
+    ) -> Response {
+        let mut req = Request::builder().method("POST").uri(uri);
+        if let Some(key) = key {
+            req = req.header(IDEMPOTENCY_KEY, key);
+        }
+        if let Some(cookie) = cookie {
+            req = req.header(header::COOKIE, cookie);
+        }
+        let req = req.body(Body::from(body.to_string())).unwrap();
+        app.clone().oneshot(req).await.unwrap()
+    }
+
+    async fn text(res: Response) -> String {
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_retries_replay_first_response() {
+        let (app, calls) = app(IdempotencyLayer::new(MemoryCache::new()));
+
+        let first = send(&app, "/payments", Some("k1"), "10 CHF").await;
+        assert_eq!(first.status(), StatusCode::CREATED);
+        assert!(first.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        assert_eq!(text(first).await, "payment 1 of 10 CHF");
+
+        let retry = send(&app, "/payments", Some("k1"), "10 CHF").await;
+        assert_eq!(retry.status(), StatusCode::CREATED);
+        assert_eq!(retry.headers()[IDEMPOTENT_REPLAYED], "true");
+        assert_eq!(text(retry).await, "payment 1 of 10 CHF");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Other keys and requests without a key run the handler
+        send(&app, "/payments", Some("k2"), "10 CHF").await;
+        send(&app, "/payments", None, "10 CHF").await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_reused_key_conflicts() {
+        let (app, calls) = app(IdempotencyLayer::new(MemoryCache::new()));
+        send(&app, "/payments", Some("k1"), "10 CHF").await;
+
+        let reused = send(&app, "/payments", Some("k1"), "99 CHF").await;
+        assert_eq!(reused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_while_in_progress_conflicts() {
+        let (parts, _) = Request::builder()
+            .method("POST")
+            .uri("/payments")
+            .body(())
+            .unwrap()
+            .into_parts();
+        let lock = Entry::InProgress {
+            fingerprint: fingerprint(&parts, b"10 CHF"),
+            owner: "other".into(),
+        };
+        let cache = MemoryCache::new();
+        cache
+            .set(&cache_key(None, "k1"), &lock, Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        let (app, calls) = app(IdempotencyLayer::new(cache));
+        let res = send(&app, "/payments", Some("k1"), "10 CHF").await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_expired_lock_is_not_taken_back() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&calls);
+        let app = Router::new()
+            .route(
+                "/payments",
+                post(move || async move {
+                    let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    format!("payment {}", n)
+                }),
+            )
+            .layer(IdempotencyLayer::new(MemoryCache::new()).lock_ttl(Duration::from_millis(200)));
+
+        // The first request outlives its lock, a retry takes the key over
+        let first = tokio::spawn({
+            let app = app.clone();
+            async move { text(send(&app, "/payments", Some("k1"), "").await).await }
+        });
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        let second = tokio::spawn({
+            let app = app.clone();
+            async move { text(send(&app, "/payments", Some("k1"), "").await).await }
+        });
+
+        // The first one finishing leaves the retry's lock alone
+        assert_eq!(first.await.unwrap(), "payment 1");
+        let res = send(&app, "/payments", Some("k1"), "").await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+
+        assert_eq!(second.await.unwrap(), "payment 2");
+        let res = send(&app, "/payments", Some("k1"), "").await;
+        assert_eq!(res.headers()[IDEMPOTENT_REPLAYED], "true");
+        assert_eq!(text(res).await, "payment 2");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_are_not_stored() {
+        let (app, _) = app(IdempotencyLayer::new(MemoryCache::new()));
+
+        let first = send(&app, "/flaky", Some("k1"), "").await;
+        assert_eq!(first.status(), StatusCode::BAD_GATEWAY);
+        let retry = send(&app, "/flaky", Some("k1"), "").await;
+        assert_eq!(retry.status(), StatusCode::OK);
+        assert!(retry.headers().get(IDEMPOTENT_REPLAYED).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_required_and_invalid_keys() {
+        let (app, calls) = app(IdempotencyLayer::new(MemoryCache::new()).required(true));
+
+        let res = send(&app, "/payments", None, "").await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let res = send(&app, "/payments", Some(&"k".repeat(256)), "").await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_scoped_to_the_caller() {
+        let (app, calls) = app(IdempotencyLayer::new(MemoryCache::new()));
+
+        let alice = send_as(&app, Some("session=alice"), "/payments", Some("k1"), "10 CHF").await;
+        assert_eq!(text(alice).await, "payment 1 of 10 CHF");
+        let bob = send_as(&app, Some("session=bob"), "/payments", Some("k1"), "10 CHF").await;
+        assert!(bob.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        assert_eq!(text(bob).await, "payment 2 of 10 CHF");
+
+        let retry = send_as(&app, Some("session=alice"), "/payments", Some("k1"), "10 CHF").await;
+        assert_eq!(text(retry).await, "payment 1 of 10 CHF");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cookies_are_not_replayed() {
+        let (app, _) = app(IdempotencyLayer::new(MemoryCache::new()));
+
+        let first = send(&app, "/login", Some("k1"), "").await;
+        assert!(first.headers().contains_key(header::SET_COOKIE));
+        let retry = send(&app, "/login", Some("k1"), "").await;
+        assert_eq!(retry.headers()[IDEMPOTENT_REPLAYED], "true");
+        assert!(!retry.headers().contains_key(header::SET_COOKIE));
+    }
+
+    #[tokio::test]
+    async fn test_streamed_responses_are_passed_through() {
+        let (app, _) = app(IdempotencyLayer::new(MemoryCache::new()));
+
+        let first = send(&app, "/stream", Some("k1"), "").await;
+        assert_eq!(text(first).await, "chunk");
+        let retry = send(&app, "/stream", Some("k1"), "").await;
+        assert!(retry.headers().get(IDEMPOTENT_REPLAYED).is_none());
+        assert_eq!(text(retry).await, "chunk");
+    }
+}
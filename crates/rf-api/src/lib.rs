@@ -0,0 +1,63 @@
+//! API response tooling for RustForge
+//!
+//! - **Resources**: [`Resource`] turns models into JSON, with fields
+//!   selected by `?fields=` and relationships included by `?include=`
+//! - **Envelopes**: [`ApiResponse`] wraps data as `{"data": ..}`, with
+//!   pagination `meta` and `links` for pages, e.g. of rf-pagination's `Paginated`
+//! - **Errors**: [`Problem`] renders RFC 7807 `application/problem+json`,
+//!   every rf-error `FrameworkError` converts to one with [`ToProblem`];
+//!   [`map_problems`] turns every other error response into one
+//! - **ETags**: [`etag`] answers conditional GET requests with
+//!   `304 Not Modified`
+//! - **Idempotency**: `IdempotencyLayer` replays the stored response to
+//!   retried requests with the same `Idempotency-Key` (feature `cache`)
+//!
+//! # Example
+//!
+//! ```
+//! use axum::{extract::Path, middleware, routing::get, Router};
+//! use rf_api::{
+//!     etag, map_problems, ApiResponse, ApiResult, Fields, Problem, ProblemMapper, Resource,
+//!     ResourceContext,
+//! };
+//!
+//! struct Post {
+//!     id: u64,
+//!     title: String,
+//! }
+//!
+//! impl Resource for Post {
+//!     fn fields(&self, fields: &mut Fields<'_>) {
+//!         fields.field("id", self.id).field("title", &self.title);
+//!     }
+//! }
+//!
+//! async fn show_post(ctx: ResourceContext, Path(id): Path<u64>) -> ApiResult<ApiResponse> {
+//!     if id != 1 {
+//!         return Err(Problem::not_found(format!("Post {} not found", id)));
+//!     }
+//!     let post = Post { id, title: "Hello".into() };
+//!     Ok(ctx.one(&post))
+//! }
+//!
+//! let app: Router = Router::new()
+//!     .route("/posts/{id}", get(show_post))
+//!     .layer(middleware::from_fn(etag))
+//!     .layer(middleware::from_fn_with_state(ProblemMapper::new(), map_problems));
+//! ```
+
+mod etag;
+#[cfg(feature = "cache")]
+mod idempotency;
+mod mapper;
+mod problem;
+mod resource;
+mod response;
+
+pub use etag::etag;
+#[cfg(feature = "cache")]
+pub use idempotency::{IdempotencyLayer, IdempotencyService, IDEMPOTENCY_KEY, IDEMPOTENT_REPLAYED};
+pub use mapper::{map_problems, ProblemMapper};
+pub use problem::{ApiResult, Problem, ProblemResultExt, ToProblem, PROBLEM_JSON};
+pub use resource::{Fields, Resource, ResourceContext};
+pub use response::ApiResponse;
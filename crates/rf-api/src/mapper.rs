@@ -0,0 +1,221 @@
+//! Global mapping of error responses to problem details
+
+use crate::{Problem, PROBLEM_JSON};
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+
+/// Largest error body read for mapping; bigger bodies are dropped
+const MAX_ERROR_BODY: usize = 64 * 1024;
+
+/// Turns every error response into problem details
+///
+/// Error responses of handlers, extractors and other middleware that
+/// aren't `application/problem+json` yet are rewritten: problem-shaped
+/// JSON keeps its members, a `message`, `error` or `detail` member or a
+/// plain text body becomes the `detail`, and the request path the
+/// `instance`. Details of server errors are logged and hidden from
+/// clients unless exposed.
+///
+/// # Example
+///
+/// ```
+/// use axum::{middleware, routing::get, Router};
+/// use rf_api::{map_problems, ProblemMapper};
+///
+/// let app: Router = Router::new()
+///     .route("/orders", get(|| async { "orders" }))
+///     .layer(middleware::from_fn_with_state(ProblemMapper::new(), map_problems));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ProblemMapper {
+    expose_server_errors: bool,
+}
+
+impl ProblemMapper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show details of server errors to clients, e.g. in development
+    pub fn expose_server_errors(mut self, expose: bool) -> Self {
+        self.expose_server_errors = expose;
+        self
+    }
+
+    /// Rewrite `response` to `path` as problem details
+    pub async fn map(&self, path: &str, response: Response) -> Response {
+        let status = response.status();
+        if !(status.is_client_error() || status.is_server_error()) || is_problem(&response) {
+            return response;
+        }
+
+        let (mut parts, body) = response.into_parts();
+        let body = axum::body::to_bytes(body, MAX_ERROR_BODY)
+            .await
+            .unwrap_or_default();
+
+        let mut problem = match serde_json::from_slice::<Value>(&body) {
+            Ok(json) if json.get("title").is_some() && json.get("status").is_some() => {
+                serde_json::from_value(json).unwrap_or_else(|_| Problem::new(status))
+            }
+            Ok(json) => {
+                let detail = ["message", "error", "detail"]
+                    .iter()
+                    .find_map(|key| json.get(*key).and_then(Value::as_str));
+                match detail {
+                    Some(detail) => Problem::new(status).detail(detail),
+                    None => Problem::new(status),
+                }
+            }
+            Err(_) => match std::str::from_utf8(&body).map(str::trim) {
+                Ok(text) if !text.is_empty() => Problem::new(status).detail(text),
+                _ => Problem::new(status),
+            },
+        };
+        problem.status = status.as_u16();
+        if problem.instance.is_none() {
+            problem.instance = Some(path.to_string());
+        }
+
+        if status.is_server_error() && !self.expose_server_errors {
+            if let Some(detail) = problem.detail.take() {
+                tracing::error!(status = status.as_u16(), path, detail, "Server error");
+            }
+        }
+
+        let mapped = problem.into_response();
+        parts.headers.remove(header::CONTENT_LENGTH);
+        parts
+            .headers
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        Response::from_parts(parts, Body::new(mapped.into_body()))
+    }
+}
+
+fn is_problem(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(PROBLEM_JSON.as_bytes()))
+}
+
+/// Middleware applying a [`ProblemMapper`], for
+/// [`from_fn_with_state`](axum::middleware::from_fn_with_state)
+pub async fn map_problems(
+    State(mapper): State<ProblemMapper>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path().to_string();
+    let response = next.run(req).await;
+    mapper.map(&path, response).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        http::StatusCode,
+        middleware,
+        routing::{get, post},
+        Json, Router,
+    };
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    async fn call(mapper: ProblemMapper, uri: &str) -> (Response, Value) {
+        let app = Router::new()
+            .route(
+                "/text",
+                get(|| async { (StatusCode::FORBIDDEN, "Not your order") }),
+            )
+            .route(
+                "/json",
+                get(|| async {
+                    (
+                        StatusCode::CONFLICT,
+                        [(header::RETRY_AFTER, "30")],
+                        Json(json!({"error": "Locked"})),
+                    )
+                }),
+            )
+            .route(
+                "/validation",
+                get(|| async {
+                    (
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        Json(json!({
+                            "type": "validation-failed",
+                            "title": "Validation Failed",
+                            "status": 422,
+                            "errors": {"email": ["required"]}
+                        })),
+                    )
+                }),
+            )
+            .route(
+                "/crash",
+                get(|| async { (StatusCode::INTERNAL_SERVER_ERROR, "connection refused") }),
+            )
+            .route("/ok", post(|| async { "created" }))
+            .layer(middleware::from_fn_with_state(mapper, map_problems));
+
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let (parts, body) = response.into_parts();
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        let json = serde_json::from_slice(&body).unwrap_or(Value::Null);
+        (Response::from_parts(parts, Body::empty()), json)
+    }
+
+    #[tokio::test]
+    async fn test_maps_errors() {
+        let (response, json) = call(ProblemMapper::new(), "/text").await;
+        assert_eq!(response.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+        assert_eq!(
+            json,
+            json!({
+                "type": "about:blank",
+                "title": "Forbidden",
+                "status": 403,
+                "detail": "Not your order",
+                "instance": "/text"
+            })
+        );
+
+        let (response, json) = call(ProblemMapper::new(), "/json").await;
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+        assert_eq!(json["detail"], "Locked");
+
+        let (_, json) = call(ProblemMapper::new(), "/validation").await;
+        assert_eq!(json["type"], "validation-failed");
+        assert_eq!(json["errors"], json!({"email": ["required"]}));
+
+        let (response, json) = call(ProblemMapper::new(), "/missing").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(json["title"], "Not Found");
+
+        // Method not allowed keeps its Allow header
+        let (response, _) = call(ProblemMapper::new(), "/ok").await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert!(response.headers().contains_key(header::ALLOW));
+    }
+
+    #[tokio::test]
+    async fn test_hides_server_errors() {
+        let (_, json) = call(ProblemMapper::new(), "/crash").await;
+        assert_eq!(json["title"], "Internal Server Error");
+        assert!(json.get("detail").is_none());
+
+        let (_, json) = call(ProblemMapper::new().expose_server_errors(true), "/crash").await;
+        assert_eq!(json["detail"], "connection refused");
+    }
+}
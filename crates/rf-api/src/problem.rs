@@ -0,0 +1,57 @@
+//! RFC 7807 problem details
+
+use rf_error::FrameworkError;
+pub use rf_error::{Problem, PROBLEM_JSON};
+
+/// Result of API handlers
+pub type ApiResult<T> = Result<T, Problem>;
+
+/// An error with a problem details representation
+///
+/// ```
+/// use axum::http::StatusCode;
+/// use rf_api::{ApiResult, Problem, ProblemResultExt, ToProblem};
+///
+/// #[derive(Debug, thiserror::Error)]
+/// enum OrderError {
+///     #[error("Order {0} not found")]
+///     NotFound(u64),
+/// }
+///
+/// impl ToProblem for OrderError {
+///     fn to_problem(&self) -> Problem {
+///         match self {
+///             OrderError::NotFound(_) => Problem::not_found(self.to_string()),
+///         }
+///     }
+/// }
+///
+/// fn find(id: u64) -> Result<String, OrderError> {
+///     Err(OrderError::NotFound(id))
+/// }
+///
+/// async fn show_order() -> ApiResult<String> {
+///     find(7).problem()
+/// }
+/// ```
+pub trait ToProblem {
+    fn to_problem(&self) -> Problem;
+}
+
+/// Convert the error of a result into a [`Problem`]
+pub trait ProblemResultExt<T> {
+    fn problem(self) -> ApiResult<T>;
+}
+
+impl<T, E: ToProblem> ProblemResultExt<T> for Result<T, E> {
+    fn problem(self) -> ApiResult<T> {
+        self.map_err(|e| e.to_problem())
+    }
+}
+
+/// Every framework error, e.g. rf-pagination's invalid page errors
+impl<E: FrameworkError> ToProblem for E {
+    fn to_problem(&self) -> Problem {
+        FrameworkError::to_problem(self)
+    }
+}
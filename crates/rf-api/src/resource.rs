@@ -0,0 +1,375 @@
+//! Transforming models into API resources
+
+use crate::ApiResponse;
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use rf_pagination::Paginator;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+
+/// A model's API representation
+///
+/// ```
+/// use rf_api::{Fields, Resource, ResourceContext};
+///
+/// struct User {
+///     id: u64,
+///     name: String,
+///     email: String,
+///     email_public: bool,
+/// }
+///
+/// struct Post {
+///     id: u64,
+///     title: String,
+///     author: User,
+/// }
+///
+/// impl Resource for User {
+///     fn fields(&self, fields: &mut Fields<'_>) {
+///         fields
+///             .field("id", self.id)
+///             .field("name", &self.name)
+///             .field_when(self.email_public, "email", &self.email);
+///     }
+/// }
+///
+/// impl Resource for Post {
+///     fn fields(&self, fields: &mut Fields<'_>) {
+///         fields
+///             .field("id", self.id)
+///             .field("title", &self.title)
+///             .include("author", || Some(&self.author));
+///     }
+/// }
+///
+/// let post = Post {
+///     id: 1,
+///     title: "Hello".into(),
+///     author: User {
+///         id: 2,
+///         name: "Ada".into(),
+///         email: "ada@example.com".into(),
+///         email_public: false,
+///     },
+/// };
+///
+/// // GET /posts/1?fields=title&include=author
+/// let ctx = ResourceContext::from_query("fields=title&include=author");
+/// assert_eq!(
+///     ctx.render(&post),
+///     serde_json::json!({"title": "Hello", "author": {"id": 2, "name": "Ada"}})
+/// );
+/// ```
+pub trait Resource {
+    /// Add the fields of the resource
+    fn fields(&self, fields: &mut Fields<'_>);
+}
+
+impl<R: Resource + ?Sized> Resource for &R {
+    fn fields(&self, fields: &mut Fields<'_>) {
+        (**self).fields(fields)
+    }
+}
+
+/// Fields of a resource being rendered
+///
+/// Fields not selected with `?fields=` and relationships not requested
+/// with `?include=` are skipped without computing their value.
+pub struct Fields<'a> {
+    ctx: &'a ResourceContext,
+    prefix: String,
+    map: Map<String, Value>,
+}
+
+impl<'a> Fields<'a> {
+    /// Add field `name`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` doesn't serialize to JSON.
+    pub fn field(&mut self, name: &str, value: impl Serialize) -> &mut Self {
+        self.field_with(name, || value)
+    }
+
+    /// Add field `name`, computing its value only if selected
+    pub fn field_with<V: Serialize>(&mut self, name: &str, value: impl FnOnce() -> V) -> &mut Self {
+        if self.ctx.selects(&self.prefix, name) {
+            let value = serde_json::to_value(value()).expect("resource field must serialize");
+            self.map.insert(name.to_string(), value);
+        }
+        self
+    }
+
+    /// Add field `name` if `condition` holds, e.g. for the user's permissions
+    pub fn field_when(&mut self, condition: bool, name: &str, value: impl Serialize) -> &mut Self {
+        if condition {
+            self.field(name, value);
+        }
+        self
+    }
+
+    /// Add relationship `name` if requested with `?include=`
+    pub fn include<R: Resource>(
+        &mut self,
+        name: &str,
+        resource: impl FnOnce() -> Option<R>,
+    ) -> &mut Self {
+        let path = format!("{}{}", self.prefix, name);
+        if self.ctx.includes(&path) {
+            let value = resource()
+                .map(|r| self.ctx.render_at(&r, &path))
+                .unwrap_or(Value::Null);
+            self.map.insert(name.to_string(), value);
+        }
+        self
+    }
+
+    /// Add relationship `name` with many resources if requested with
+    /// `?include=`
+    pub fn include_many<R: Resource, I: IntoIterator<Item = R>>(
+        &mut self,
+        name: &str,
+        resources: impl FnOnce() -> I,
+    ) -> &mut Self {
+        let path = format!("{}{}", self.prefix, name);
+        if self.ctx.includes(&path) {
+            let values = resources()
+                .into_iter()
+                .map(|r| self.ctx.render_at(&r, &path))
+                .collect();
+            self.map.insert(name.to_string(), Value::Array(values));
+        }
+        self
+    }
+
+    /// The context rendering the resource
+    pub fn context(&self) -> &'a ResourceContext {
+        self.ctx
+    }
+}
+
+/// Requested fields and relationships, from `?fields=` and `?include=`
+///
+/// Both take comma-separated lists, where dotted paths reach into
+/// relationships: `?include=comments.author&fields=id,title,comments.body`.
+/// A level without selected fields renders all of them. As extractor it
+/// reads the query string of the request.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceContext {
+    fields: Option<HashSet<String>>,
+    includes: HashSet<String>,
+}
+
+impl ResourceContext {
+    /// Create a context rendering all fields and no relationships
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a context from a query string
+    pub fn from_query(query: &str) -> Self {
+        let params: HashMap<String, String> = axum::http::Uri::builder()
+            .path_and_query(format!("/?{}", query))
+            .build()
+            .ok()
+            .and_then(|uri| Query::try_from_uri(&uri).ok())
+            .map(|Query(params)| params)
+            .unwrap_or_default();
+        Self::from_params(&params)
+    }
+
+    fn from_params(params: &HashMap<String, String>) -> Self {
+        let mut ctx = Self::new();
+        if let Some(fields) = params.get("fields") {
+            ctx = ctx.fields(split(fields));
+        }
+        if let Some(includes) = params.get("include") {
+            ctx = ctx.include(split(includes));
+        }
+        ctx
+    }
+
+    /// Render only `fields`
+    pub fn fields<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.fields
+            .get_or_insert_with(HashSet::new)
+            .extend(fields.into_iter().map(Into::into));
+        self
+    }
+
+    /// Render the relationships at `paths`, and those leading to them
+    pub fn include<S: Into<String>>(mut self, paths: impl IntoIterator<Item = S>) -> Self {
+        for path in paths {
+            let path = path.into();
+            for (i, _) in path.match_indices('.') {
+                self.includes.insert(path[..i].to_string());
+            }
+            self.includes.insert(path);
+        }
+        self
+    }
+
+    /// Whether the relationship at `path` is requested
+    pub fn includes(&self, path: &str) -> bool {
+        self.includes.contains(path)
+    }
+
+    /// Render `resource` as JSON object
+    pub fn render<R: Resource + ?Sized>(&self, resource: &R) -> Value {
+        self.render_at(resource, "")
+    }
+
+    /// `{"data": resource}`
+    pub fn one<R: Resource + ?Sized>(&self, resource: &R) -> ApiResponse {
+        ApiResponse::new(self.render(resource))
+    }
+
+    /// `{"data": [resources]}`
+    pub fn collection<R: Resource>(&self, resources: impl IntoIterator<Item = R>) -> ApiResponse {
+        ApiResponse::new(self.render_all(resources))
+    }
+
+    /// `{"data": [resources], "meta": {..}, "links": {..}}` for the page of
+    /// `paginator`, linking pages of `url`
+    pub fn paginated<R: Resource>(
+        &self,
+        resources: impl IntoIterator<Item = R>,
+        paginator: Paginator,
+        url: &str,
+    ) -> ApiResponse {
+        ApiResponse::paginated(self.render_all(resources), paginator, url)
+    }
+
+    fn render_all<R: Resource>(&self, resources: impl IntoIterator<Item = R>) -> Value {
+        Value::Array(resources.into_iter().map(|r| self.render(&r)).collect())
+    }
+
+    fn render_at<R: Resource + ?Sized>(&self, resource: &R, path: &str) -> Value {
+        let mut fields = Fields {
+            ctx: self,
+            prefix: if path.is_empty() {
+                String::new()
+            } else {
+                format!("{}.", path)
+            },
+            map: Map::new(),
+        };
+        resource.fields(&mut fields);
+        Value::Object(fields.map)
+    }
+
+    /// Whether field `name` below `prefix` is selected
+    fn selects(&self, prefix: &str, name: &str) -> bool {
+        let Some(fields) = &self.fields else {
+            return true;
+        };
+        let mut level = fields
+            .iter()
+            .filter_map(|f| f.strip_prefix(prefix))
+            .filter(|f| !f.contains('.'))
+            .peekable();
+        level.peek().is_none() || level.any(|f| f == name)
+    }
+}
+
+fn split(list: &str) -> impl Iterator<Item = String> + '_ {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ResourceContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let params = Query::<HashMap<String, String>>::try_from_uri(&parts.uri)
+            .map(|Query(params)| params)
+            .unwrap_or_default();
+        Ok(Self::from_params(&params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct Comment {
+        body: &'static str,
+        author: &'static str,
+    }
+
+    struct Author(&'static str);
+
+    struct Post {
+        id: u64,
+        title: &'static str,
+        comments: Vec<Comment>,
+    }
+
+    impl Resource for Author {
+        fn fields(&self, fields: &mut Fields<'_>) {
+            fields
+                .field("name", self.0)
+                .field_with("initial", || &self.0[..1]);
+        }
+    }
+
+    impl Resource for Comment {
+        fn fields(&self, fields: &mut Fields<'_>) {
+            fields
+                .field("body", self.body)
+                .include("author", || Some(Author(self.author)));
+        }
+    }
+
+    impl Resource for Post {
+        fn fields(&self, fields: &mut Fields<'_>) {
+            fields
+                .field("id", self.id)
+                .field("title", self.title)
+                .include_many("comments", || &self.comments);
+        }
+    }
+
+    fn post() -> Post {
+        Post {
+            id: 1,
+            title: "Hello",
+            comments: vec![Comment {
+                body: "Nice",
+                author: "Bob",
+            }],
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let ctx = ResourceContext::new();
+        assert_eq!(ctx.render(&post()), json!({"id": 1, "title": "Hello"}));
+
+        let ctx =
+            ResourceContext::from_query("include=comments.author&fields=id,comments.author.name");
+        assert_eq!(
+            ctx.render(&post()),
+            json!({
+                "id": 1,
+                "comments": [{"body": "Nice", "author": {"name": "Bob"}}]
+            })
+        );
+
+        let ctx = ResourceContext::new().include(["comments"]);
+        assert!(ctx.includes("comments"));
+        assert!(!ctx.includes("comments.author"));
+        assert_eq!(
+            ctx.collection([post(), post()]).data[1]["comments"],
+            json!([{"body": "Nice"}])
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Response envelopes
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use rf_pagination::{Paginated, PaginationLinks, PaginationMeta, Paginator};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// `{"data": .., "meta": .., "links": ..}` envelope of API responses
+///
+/// `meta` and `links` are left out when empty.
+///
+/// ```
+/// use rf_api::ApiResponse;
+/// use rf_pagination::Paginator;
+///
+/// let paginator = Paginator::new(45, 20, 2).unwrap();
+/// let response = ApiResponse::paginated(vec![1, 2, 3], paginator, "/posts?sort=new&page=2");
+///
+/// let links = response.links.as_ref().unwrap();
+/// assert_eq!(links.next.as_deref(), Some("/posts?sort=new&page=3"));
+/// assert_eq!(response.meta["total"], 45);
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiResponse {
+    pub data: Value,
+
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub meta: Map<String, Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<PaginationLinks>,
+
+    #[serde(skip)]
+    status: StatusCode,
+}
+
+impl ApiResponse {
+    /// Wrap `data`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` doesn't serialize to JSON.
+    pub fn new(data: impl Serialize) -> Self {
+        Self {
+            data: serde_json::to_value(data).expect("response data must serialize"),
+            meta: Map::new(),
+            links: None,
+            status: StatusCode::OK,
+        }
+    }
+
+    /// Wrap the page of `paginator`, with pagination meta and links to the
+    /// pages of `url`
+    ///
+    /// `url` is usually the request URI; its `page` parameter is replaced
+    /// and others kept.
+    pub fn paginated(data: impl Serialize, paginator: Paginator, url: &str) -> Self {
+        let links = PaginationLinks::new(url, &paginator);
+        let meta = match serde_json::to_value(PaginationMeta::from(paginator)) {
+            Ok(Value::Object(meta)) => meta,
+            _ => Map::new(),
+        };
+
+        Self {
+            meta,
+            links: Some(links),
+            ..Self::new(data)
+        }
+    }
+
+    /// Wrap `page`, with pagination meta and, given its URL, links
+    pub fn page<T: Serialize>(page: Paginated<T>) -> Self {
+        let links = page.links();
+        let meta = match serde_json::to_value(page.meta()) {
+            Ok(Value::Object(meta)) => meta,
+            _ => Map::new(),
+        };
+
+        Self {
+            meta,
+            links,
+            ..Self::new(page.items)
+        }
+    }
+
+    /// Add a `meta` member
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` doesn't serialize to JSON.
+    pub fn meta(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).expect("response meta must serialize");
+        self.meta.insert(name.into(), value);
+        self
+    }
+
+    /// Set the status (default: 200)
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+}
+
+impl IntoResponse for ApiResponse {
+    fn into_response(self) -> Response {
+        (self.status, Json(&self)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rf_pagination::page_url;
+    use serde_json::json;
+
+    #[test]
+    fn test_paginated() {
+        let paginator = Paginator::new(3, 2, 1).unwrap();
+        let response = ApiResponse::paginated(["a", "b"], paginator, "/tags").meta("version", 2);
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "data": ["a", "b"],
+                "meta": {
+                    "total": 3,
+                    "per_page": 2,
+                    "current_page": 1,
+                    "last_page": 2,
+                    "from": 1,
+                    "to": 2,
+                    "version": 2
+                },
+                "links": {
+                    "first": "/tags?page=1",
+                    "last": "/tags?page=2",
+                    "prev": null,
+                    "next": "/tags?page=2"
+                }
+            })
+        );
+
+        assert_eq!(page_url("/tags?page=4&q=rust", 5), "/tags?q=rust&page=5");
+        let page = Paginated::new(vec!["a", "b"], Paginator::new(3, 2, 1).unwrap());
+        assert_eq!(
+            serde_json::to_value(ApiResponse::page(page.url("/tags")).meta("version", 2)).unwrap(),
+            serde_json::to_value(&response).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(ApiResponse::new(json!({"id": 1}))).unwrap(),
+            json!({"data": {"id": 1}})
+        );
+    }
+}
@@ -28,6 +28,13 @@ pub enum AuditError {
 
 pub type AuditResult<T> = Result<T, AuditError>;
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(AuditError, {
+    Self::QueryError(_) => (BAD_REQUEST, "audit.invalid_query"),
+    Self::StorageError(_) => (INTERNAL_SERVER_ERROR, "audit.storage"),
+    Self::SerializationError(_) => (INTERNAL_SERVER_ERROR, "audit.serialization"),
+});
+
 /// Audit action types
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AuditAction {
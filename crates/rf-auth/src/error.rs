@@ -0,0 +1,118 @@
+//! Authentication errors
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use thiserror::Error;
+
+/// Authentication errors
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Invalid credentials")]
+    InvalidCredentials,
+
+    #[error("Unauthenticated")]
+    Unauthenticated,
+
+    #[error("Missing ability: {0}")]
+    Forbidden(String),
+
+    #[error("Token expired")]
+    TokenExpired,
+
+    #[error("Invalid token: {0}")]
+    InvalidToken(String),
+
+    /// A refresh token was used twice; its whole family is revoked
+    #[error("Refresh token reused")]
+    TokenReused,
+
+    #[error("Password hashing failed: {0}")]
+    HashingFailed(String),
+
+    #[error("Configuration error: {0}")]
+    ConfigError(String),
+
+    #[error("Session error: {0}")]
+    SessionError(String),
+
+    #[error("Storage error: {0}")]
+    StorageError(String),
+}
+
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(AuthError, {
+    Self::InvalidCredentials => (UNAUTHORIZED, "auth.invalid_credentials"),
+    Self::Unauthenticated => (UNAUTHORIZED, "auth.unauthenticated"),
+    Self::Forbidden(_) => (FORBIDDEN, "auth.forbidden"),
+    Self::TokenExpired => (UNAUTHORIZED, "auth.token_expired"),
+    Self::InvalidToken(_) | Self::TokenReused => (UNAUTHORIZED, "auth.invalid_token"),
+    Self::HashingFailed(_) => (INTERNAL_SERVER_ERROR, "auth.hashing"),
+    Self::ConfigError(_) => (INTERNAL_SERVER_ERROR, "auth.config"),
+    Self::SessionError(_) => (INTERNAL_SERVER_ERROR, "auth.session"),
+    Self::StorageError(_) => (INTERNAL_SERVER_ERROR, "auth.storage"),
+});
+
+/// Result type for authentication operations
+pub type AuthResult<T> = Result<T, AuthError>;
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: &'static str,
+    message: String,
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let (status, error) = match &self {
+            AuthError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "invalid_credentials"),
+            AuthError::Unauthenticated => (StatusCode::UNAUTHORIZED, "unauthenticated"),
+            AuthError::Forbidden(_) => (StatusCode::FORBIDDEN, "forbidden"),
+            AuthError::TokenExpired => (StatusCode::UNAUTHORIZED, "token_expired"),
+            AuthError::InvalidToken(_) | AuthError::TokenReused => {
+                (StatusCode::UNAUTHORIZED, "invalid_token")
+            }
+            AuthError::HashingFailed(_)
+            | AuthError::ConfigError(_)
+            | AuthError::SessionError(_)
+            | AuthError::StorageError(_) => {
+                tracing::error!(error = %self, "Authentication failed");
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: "server_error",
+                        message: "Internal server error".to_string(),
+                    }),
+                )
+                    .into_response();
+            }
+        };
+
+        let body = Json(ErrorResponse {
+            error,
+            message: self.to_string(),
+        });
+        (status, body).into_response()
+    }
+}
+
+#[cfg(all(test, feature = "rf-error"))]
+mod tests {
+    use super::*;
+    use rf_error::FrameworkError;
+
+    #[test]
+    fn test_problem() {
+        let problem = FrameworkError::to_problem(&AuthError::TokenExpired);
+        assert_eq!(problem.status_code(), StatusCode::UNAUTHORIZED);
+        assert_eq!(problem.detail.as_deref(), Some("Token expired"));
+        assert_eq!(problem.extensions["code"], "auth.token_expired");
+
+        let error = AuthError::StorageError("connection refused".into());
+        assert_eq!(FrameworkError::status(&error), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.public_message(), rf_error::INTERNAL_ERROR_MESSAGE);
+    }
+}
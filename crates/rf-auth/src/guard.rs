@@ -0,0 +1,343 @@
+//! Guards resolving the current user of a request
+
+use crate::tokens::{AccessTokens, PersonalAccessToken, TOKEN_PREFIX};
+use crate::{AuthError, AuthResult, Authenticatable, JwtManager, UserProvider};
+use axum::extract::FromRequestParts;
+use axum::http::{header, request::Parts};
+use std::ops::Deref;
+use std::sync::Arc;
+use tower_sessions::Session;
+
+/// Session key holding the id of the logged in user
+pub const SESSION_KEY: &str = "auth.user_id";
+
+/// Login state kept in a tower-sessions [`Session`]
+pub struct SessionGuard;
+
+impl SessionGuard {
+    /// Log a user in
+    ///
+    /// The session id is renewed to prevent session fixation.
+    pub async fn login<U: Authenticatable>(session: &Session, user: &U) -> AuthResult<()> {
+        session.cycle_id().await.map_err(session_error)?;
+        session
+            .insert(SESSION_KEY, user.auth_id())
+            .await
+            .map_err(session_error)
+    }
+
+    /// Log the user out, discarding the session
+    pub async fn logout(session: &Session) -> AuthResult<()> {
+        session.flush().await.map_err(session_error)
+    }
+
+    /// Id of the logged in user
+    pub async fn id(session: &Session) -> AuthResult<Option<String>> {
+        session.get(SESSION_KEY).await.map_err(session_error)
+    }
+}
+
+fn session_error(e: tower_sessions::session::Error) -> AuthError {
+    AuthError::SessionError(e.to_string())
+}
+
+/// Guard that authenticated the current user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Guard {
+    Session,
+    Jwt,
+    /// Personal access token
+    Token,
+}
+
+/// Guards and user provider of an application
+///
+/// Add it to the router as an extension, next to rf-session's `SessionLayer`
+/// when using sessions; handlers then take a [`CurrentUser`].
+///
+/// ```ignore
+/// use axum::{routing::get, Extension, Router};
+/// use rf_auth::{AccessTokens, Auth, CurrentUser, JwtManager};
+///
+/// async fn profile(user: CurrentUser<User>) -> String {
+///     user.name.clone()
+/// }
+///
+/// let auth = Auth::new(UserRepository::new(pool))
+///     .jwt(JwtManager::from_env()?)
+///     .tokens(AccessTokens::new(token_repository));
+///
+/// let app = Router::new()
+///     .route("/profile", get(profile))
+///     .layer(Extension(auth))
+///     .layer(SessionManagerLayer::new(session_store));
+/// ```
+pub struct Auth<U> {
+    provider: Arc<dyn UserProvider<User = U>>,
+    jwt: Option<Arc<JwtManager>>,
+    tokens: Option<AccessTokens>,
+    session: bool,
+}
+
+impl<U> Clone for Auth<U> {
+    fn clone(&self) -> Self {
+        Self {
+            provider: Arc::clone(&self.provider),
+            jwt: self.jwt.clone(),
+            tokens: self.tokens.clone(),
+            session: self.session,
+        }
+    }
+}
+
+impl<U: Authenticatable> Auth<U> {
+    /// Authenticate users from `provider`, with the session guard enabled
+    pub fn new(provider: impl UserProvider<User = U>) -> Self {
+        Self {
+            provider: Arc::new(provider),
+            jwt: None,
+            tokens: None,
+            session: true,
+        }
+    }
+
+    /// Accept JWT access tokens as `Authorization: Bearer` header
+    pub fn jwt(mut self, jwt: JwtManager) -> Self {
+        self.jwt = Some(Arc::new(jwt));
+        self
+    }
+
+    /// Accept personal access tokens as `Authorization: Bearer` header
+    pub fn tokens(mut self, tokens: AccessTokens) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    /// Ignore the session, e.g. for a stateless API
+    pub fn without_session(mut self) -> Self {
+        self.session = false;
+        self
+    }
+
+    /// The JWT manager, to issue tokens on login
+    pub fn jwt_manager(&self) -> Option<&JwtManager> {
+        self.jwt.as_deref()
+    }
+
+    /// Resolve the user of a request
+    ///
+    /// A bearer token is checked by the token or JWT guard; requests
+    /// without one fall back to the session.
+    pub async fn authenticate(&self, parts: &Parts) -> AuthResult<CurrentUser<U>> {
+        let bearer = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        let (id, guard, token) = match bearer {
+            Some(bearer) if bearer.starts_with(TOKEN_PREFIX) => {
+                let tokens = self.tokens.as_ref().ok_or(AuthError::Unauthenticated)?;
+                let token = tokens.authenticate(bearer).await?;
+                (token.user_id.clone(), Guard::Token, Some(token))
+            }
+            Some(bearer) => {
+                let jwt = self.jwt.as_ref().ok_or(AuthError::Unauthenticated)?;
+                (jwt.verify(bearer)?.sub, Guard::Jwt, None)
+            }
+            None => {
+                let session = parts
+                    .extensions
+                    .get::<Session>()
+                    .filter(|_| self.session)
+                    .ok_or(AuthError::Unauthenticated)?;
+                let id = SessionGuard::id(session)
+                    .await?
+                    .ok_or(AuthError::Unauthenticated)?;
+                (id, Guard::Session, None)
+            }
+        };
+
+        let user = self
+            .provider
+            .find_by_id(&id)
+            .await?
+            .ok_or(AuthError::Unauthenticated)?;
+        Ok(CurrentUser { user, guard, token })
+    }
+}
+
+/// The authenticated user of a request
+///
+/// Extracting it rejects unauthenticated requests with 401. It derefs to
+/// the user.
+#[derive(Debug, Clone)]
+pub struct CurrentUser<U> {
+    pub user: U,
+    pub guard: Guard,
+    token: Option<PersonalAccessToken>,
+}
+
+impl<U> CurrentUser<U> {
+    /// The personal access token of the request, if authenticated by one
+    pub fn token(&self) -> Option<&PersonalAccessToken> {
+        self.token.as_ref()
+    }
+
+    /// Check an ability
+    ///
+    /// Session and JWT logins can do everything; personal access tokens
+    /// only what they were granted.
+    pub fn can(&self, ability: &str) -> bool {
+        self.token.as_ref().is_none_or(|token| token.can(ability))
+    }
+
+    /// Require an ability, failing with 403
+    pub fn authorize(&self, ability: &str) -> AuthResult<()> {
+        if self.can(ability) {
+            Ok(())
+        } else {
+            Err(AuthError::Forbidden(ability.to_string()))
+        }
+    }
+}
+
+impl<U> Deref for CurrentUser<U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        &self.user
+    }
+}
+
+impl<U, S> FromRequestParts<S> for CurrentUser<U>
+where
+    U: Authenticatable,
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let auth = parts.extensions.get::<Auth<U>>().cloned().ok_or_else(|| {
+            AuthError::ConfigError("Auth extension is missing from the router".to_string())
+        })?;
+        auth.authenticate(parts).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryTokenRepository;
+    use async_trait::async_trait;
+    use axum::http::Request;
+    use tower_sessions::MemoryStore;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct User {
+        id: u32,
+    }
+
+    impl Authenticatable for User {
+        fn auth_id(&self) -> String {
+            self.id.to_string()
+        }
+    }
+
+    struct Users;
+
+    #[async_trait]
+    impl UserProvider for Users {
+        type User = User;
+
+        async fn find_by_id(&self, id: &str) -> AuthResult<Option<User>> {
+            Ok(id.parse().ok().filter(|id| *id < 100).map(|id| User { id }))
+        }
+    }
+
+    fn parts(auth: &Auth<User>, bearer: Option<&str>, session: Option<Session>) -> Parts {
+        let mut request = Request::builder();
+        if let Some(bearer) = bearer {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", bearer));
+        }
+        let (mut parts, _) = request.body(()).unwrap().into_parts();
+        parts.extensions.insert(auth.clone());
+        if let Some(session) = session {
+            parts.extensions.insert(session);
+        }
+        parts
+    }
+
+    async fn current_user(parts: &mut Parts) -> AuthResult<CurrentUser<User>> {
+        CurrentUser::from_request_parts(parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_session_guard() {
+        let auth = Auth::new(Users);
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+
+        let mut guest = parts(&auth, None, Some(session.clone()));
+        assert!(matches!(
+            current_user(&mut guest).await,
+            Err(AuthError::Unauthenticated)
+        ));
+
+        SessionGuard::login(&session, &User { id: 7 })
+            .await
+            .unwrap();
+        let user = current_user(&mut parts(&auth, None, Some(session.clone())))
+            .await
+            .unwrap();
+        assert_eq!(user.id, 7);
+        assert_eq!(user.guard, Guard::Session);
+        assert!(user.can("anything"));
+
+        let stateless = auth.clone().without_session();
+        assert!(
+            current_user(&mut parts(&stateless, None, Some(session.clone())))
+                .await
+                .is_err()
+        );
+
+        SessionGuard::logout(&session).await.unwrap();
+        assert!(current_user(&mut parts(&auth, None, Some(session)))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bearer_guards() {
+        let jwt = JwtManager::new("test-secret-with-at-least-32-bytes").unwrap();
+        let pair = jwt.issue("7").await.unwrap();
+        let unknown_user = jwt.issue("500").await.unwrap();
+        let tokens = AccessTokens::new(Arc::new(MemoryTokenRepository::new()));
+        let (_, plain) = tokens.create("8", "CLI", &["posts:*"], None).await.unwrap();
+
+        let auth = Auth::new(Users).jwt(jwt).tokens(tokens);
+
+        let user = current_user(&mut parts(&auth, Some(&pair.access_token), None))
+            .await
+            .unwrap();
+        assert_eq!((user.id, user.guard), (7, Guard::Jwt));
+
+        let user = current_user(&mut parts(&auth, Some(&plain), None))
+            .await
+            .unwrap();
+        assert_eq!((user.id, user.guard), (8, Guard::Token));
+        assert!(user.authorize("posts:create").is_ok());
+        assert!(matches!(
+            user.authorize("users:delete"),
+            Err(AuthError::Forbidden(_))
+        ));
+
+        assert!(current_user(&mut parts(&auth, Some("garbage"), None))
+            .await
+            .is_err());
+        assert!(
+            current_user(&mut parts(&auth, Some(&unknown_user.access_token), None))
+                .await
+                .is_err()
+        );
+    }
+}
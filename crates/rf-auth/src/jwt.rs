@@ -0,0 +1,431 @@
+//! JSON Web Tokens with rotating refresh tokens
+
+use crate::{AuthError, AuthResult};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Minimum length of the signing secret in bytes
+const MIN_SECRET_LEN: usize = 32;
+
+/// Whether a token grants access or renews it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    Access,
+    Refresh,
+}
+
+/// JWT claims
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claims {
+    /// The user's [`auth_id`](crate::Authenticatable::auth_id)
+    pub sub: String,
+    pub exp: i64,
+    pub iat: i64,
+    pub jti: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    pub kind: TokenKind,
+    /// Shared by all refresh tokens rotated from the same login
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub family: Option<String>,
+}
+
+/// Access and refresh token handed to the client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    /// Lifetime of the access token in seconds
+    pub expires_in: u64,
+}
+
+/// Issued refresh token, as kept by a [`RefreshTokenStore`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RefreshTokenRecord {
+    pub jti: String,
+    pub family: String,
+    pub subject: String,
+    pub expires_at: DateTime<Utc>,
+    pub used: bool,
+}
+
+/// Storage for issued refresh tokens
+#[async_trait]
+pub trait RefreshTokenStore: Send + Sync {
+    /// Remember an issued token
+    async fn save(&self, record: RefreshTokenRecord) -> AuthResult<()>;
+
+    /// Mark a token used, returning it as it was before
+    async fn use_token(&self, jti: &str) -> AuthResult<Option<RefreshTokenRecord>>;
+
+    /// Forget all tokens of a family
+    async fn revoke_family(&self, family: &str) -> AuthResult<()>;
+}
+
+/// In-memory refresh token store
+#[derive(Default)]
+pub struct MemoryRefreshTokenStore {
+    tokens: Mutex<HashMap<String, RefreshTokenRecord>>,
+}
+
+impl MemoryRefreshTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RefreshTokenStore for MemoryRefreshTokenStore {
+    async fn save(&self, record: RefreshTokenRecord) -> AuthResult<()> {
+        let mut tokens = self.tokens.lock().await;
+        // Drop expired tokens while we hold the lock
+        let now = Utc::now();
+        tokens.retain(|_, token| token.expires_at > now);
+        tokens.insert(record.jti.clone(), record);
+        Ok(())
+    }
+
+    async fn use_token(&self, jti: &str) -> AuthResult<Option<RefreshTokenRecord>> {
+        let mut tokens = self.tokens.lock().await;
+        Ok(tokens.get_mut(jti).map(|token| {
+            let before = token.clone();
+            token.used = true;
+            before
+        }))
+    }
+
+    async fn revoke_family(&self, family: &str) -> AuthResult<()> {
+        self.tokens
+            .lock()
+            .await
+            .retain(|_, token| token.family != family);
+        Ok(())
+    }
+}
+
+/// Issues and verifies HS256 tokens
+///
+/// Refresh tokens rotate: each one can be exchanged once for a new pair.
+/// Presenting a used refresh token again means it leaked, so the whole
+/// family of tokens from that login is revoked.
+///
+/// ```
+/// use rf_auth::JwtManager;
+///
+/// # async fn example() -> rf_auth::AuthResult<()> {
+/// let jwt = JwtManager::new("a-secret-of-at-least-32-bytes-length!")?;
+///
+/// let pair = jwt.issue("42").await?;
+/// assert_eq!(jwt.verify(&pair.access_token)?.sub, "42");
+///
+/// let renewed = jwt.refresh(&pair.refresh_token).await?;
+/// assert_eq!(jwt.verify(&renewed.access_token)?.sub, "42");
+/// // The old refresh token is spent
+/// assert!(jwt.refresh(&pair.refresh_token).await.is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub struct JwtManager {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    previous: Vec<DecodingKey>,
+    access_ttl: Duration,
+    refresh_ttl: Duration,
+    issuer: Option<String>,
+    store: Arc<dyn RefreshTokenStore>,
+}
+
+impl JwtManager {
+    /// Create a manager signing with `secret`, which must be at least 32
+    /// bytes long
+    pub fn new(secret: &str) -> AuthResult<Self> {
+        check_secret(secret)?;
+
+        Ok(Self {
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+            previous: Vec::new(),
+            access_ttl: Duration::from_secs(15 * 60),
+            refresh_ttl: Duration::from_secs(30 * 24 * 60 * 60),
+            issuer: None,
+            store: Arc::new(MemoryRefreshTokenStore::new()),
+        })
+    }
+
+    /// Create a manager from `JWT_SECRET`, with the access token lifetime
+    /// in seconds from `JWT_EXPIRATION` if set and secrets used before a
+    /// rotation from `JWT_PREVIOUS_SECRETS` (comma separated)
+    pub fn from_env() -> AuthResult<Self> {
+        let secret = std::env::var("JWT_SECRET")
+            .map_err(|_| AuthError::ConfigError("JWT_SECRET is not set".to_string()))?;
+        let mut manager = Self::new(&secret)?;
+
+        let previous = std::env::var("JWT_PREVIOUS_SECRETS").unwrap_or_default();
+        for secret in previous.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            manager = manager.previous_secret(secret)?;
+        }
+
+        if let Ok(expiration) = std::env::var("JWT_EXPIRATION") {
+            let seconds = expiration
+                .parse()
+                .map_err(|_| AuthError::ConfigError("Invalid JWT_EXPIRATION".to_string()))?;
+            manager.access_ttl = Duration::from_secs(seconds);
+        }
+        Ok(manager)
+    }
+
+    /// Keep accepting tokens signed with a secret used before a rotation;
+    /// new tokens are always signed with the current secret
+    ///
+    /// The secret must be at least 32 bytes long, like the current one.
+    pub fn previous_secret(mut self, secret: &str) -> AuthResult<Self> {
+        check_secret(secret)?;
+        self.previous.push(DecodingKey::from_secret(secret.as_bytes()));
+        Ok(self)
+    }
+
+    /// Lifetime of access tokens (default: 15 minutes)
+    pub fn access_ttl(mut self, ttl: Duration) -> Self {
+        self.access_ttl = ttl;
+        self
+    }
+
+    /// Lifetime of refresh tokens (default: 30 days)
+    pub fn refresh_ttl(mut self, ttl: Duration) -> Self {
+        self.refresh_ttl = ttl;
+        self
+    }
+
+    /// Set and require the `iss` claim
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Keep refresh tokens in `store` instead of memory, so they survive
+    /// restarts and are shared between instances
+    pub fn store(mut self, store: Arc<dyn RefreshTokenStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Issue a token pair for a new login of `subject`
+    pub async fn issue(&self, subject: &str) -> AuthResult<TokenPair> {
+        self.issue_in_family(subject, Uuid::new_v4().to_string())
+            .await
+    }
+
+    async fn issue_in_family(&self, subject: &str, family: String) -> AuthResult<TokenPair> {
+        let access = self.claims(subject, TokenKind::Access, self.access_ttl, None);
+        let refresh = self.claims(subject, TokenKind::Refresh, self.refresh_ttl, Some(family));
+
+        self.store
+            .save(RefreshTokenRecord {
+                jti: refresh.jti.clone(),
+                family: refresh.family.clone().unwrap_or_default(),
+                subject: subject.to_string(),
+                expires_at: DateTime::from_timestamp(refresh.exp, 0).unwrap_or_default(),
+                used: false,
+            })
+            .await?;
+
+        Ok(TokenPair {
+            access_token: self.encode(&access)?,
+            refresh_token: self.encode(&refresh)?,
+            token_type: "Bearer".to_string(),
+            expires_in: self.access_ttl.as_secs(),
+        })
+    }
+
+    /// Verify an access token
+    pub fn verify(&self, token: &str) -> AuthResult<Claims> {
+        self.decode(token, TokenKind::Access)
+    }
+
+    /// Exchange a refresh token for a new pair
+    pub async fn refresh(&self, refresh_token: &str) -> AuthResult<TokenPair> {
+        let claims = self.decode(refresh_token, TokenKind::Refresh)?;
+        let record = self
+            .store
+            .use_token(&claims.jti)
+            .await?
+            .ok_or_else(|| AuthError::InvalidToken("Refresh token revoked".to_string()))?;
+
+        if record.used {
+            tracing::warn!(subject = %record.subject, "Refresh token reused, revoking its family");
+            self.store.revoke_family(&record.family).await?;
+            return Err(AuthError::TokenReused);
+        }
+
+        self.issue_in_family(&record.subject, record.family).await
+    }
+
+    /// Revoke a refresh token and all tokens rotated from the same login,
+    /// e.g. on logout
+    pub async fn revoke(&self, refresh_token: &str) -> AuthResult<()> {
+        let claims = self.decode(refresh_token, TokenKind::Refresh)?;
+        match claims.family {
+            Some(family) => self.store.revoke_family(&family).await,
+            None => Ok(()),
+        }
+    }
+
+    fn claims(
+        &self,
+        subject: &str,
+        kind: TokenKind,
+        ttl: Duration,
+        family: Option<String>,
+    ) -> Claims {
+        let now = Utc::now().timestamp();
+        Claims {
+            sub: subject.to_string(),
+            exp: now + i64::try_from(ttl.as_secs()).unwrap_or(i64::MAX - now),
+            iat: now,
+            jti: Uuid::new_v4().to_string(),
+            iss: self.issuer.clone(),
+            kind,
+            family,
+        }
+    }
+
+    fn encode(&self, claims: &Claims) -> AuthResult<String> {
+        jsonwebtoken::encode(&Header::new(Algorithm::HS256), claims, &self.encoding)
+            .map_err(|e| AuthError::InvalidToken(e.to_string()))
+    }
+
+    fn decode(&self, token: &str, kind: TokenKind) -> AuthResult<Claims> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+
+        let mut result = jsonwebtoken::decode::<Claims>(token, &self.decoding, &validation);
+        for key in &self.previous {
+            match &result {
+                Err(e) if *e.kind() == ErrorKind::InvalidSignature => {
+                    result = jsonwebtoken::decode::<Claims>(token, key, &validation);
+                }
+                _ => break,
+            }
+        }
+
+        let claims = result
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => AuthError::TokenExpired,
+                _ => AuthError::InvalidToken(e.to_string()),
+            })?
+            .claims;
+
+        if claims.kind != kind {
+            return Err(AuthError::InvalidToken(format!(
+                "Expected {:?} token",
+                kind
+            )));
+        }
+        Ok(claims)
+    }
+}
+
+fn check_secret(secret: &str) -> AuthResult<()> {
+    if secret.len() < MIN_SECRET_LEN {
+        return Err(AuthError::ConfigError(format!(
+            "JWT secret must be at least {} bytes long",
+            MIN_SECRET_LEN
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "test-secret-with-at-least-32-bytes";
+
+    #[test]
+    fn test_secret_length() {
+        assert!(matches!(
+            JwtManager::new("secret"),
+            Err(AuthError::ConfigError(_))
+        ));
+        assert!(matches!(
+            JwtManager::new(SECRET)
+                .unwrap()
+                .previous_secret("old-secret"),
+            Err(AuthError::ConfigError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_token_kinds() {
+        let jwt = JwtManager::new(SECRET).unwrap().issuer("rustforge");
+        let pair = jwt.issue("42").await.unwrap();
+
+        let claims = jwt.verify(&pair.access_token).unwrap();
+        assert_eq!(claims.sub, "42");
+        assert_eq!(claims.iss.as_deref(), Some("rustforge"));
+        // A refresh token is no access token
+        assert!(jwt.verify(&pair.refresh_token).is_err());
+
+        let other = JwtManager::new(SECRET).unwrap().issuer("other");
+        assert!(other.verify(&pair.access_token).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_previous_secret() {
+        const OLD: &str = "old-secret-with-at-least-32-bytes!";
+        let token = JwtManager::new(OLD).unwrap().issue("42").await.unwrap();
+
+        let rotated = JwtManager::new(SECRET)
+            .unwrap()
+            .previous_secret(OLD)
+            .unwrap();
+        assert_eq!(rotated.verify(&token.access_token).unwrap().sub, "42");
+        assert!(JwtManager::new(SECRET)
+            .unwrap()
+            .verify(&token.access_token)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expired_token() {
+        let jwt = JwtManager::new(SECRET).unwrap();
+        let mut claims = jwt.claims("42", TokenKind::Access, Duration::ZERO, None);
+        claims.exp -= 3600;
+        let token = jwt.encode(&claims).unwrap();
+
+        assert!(matches!(jwt.verify(&token), Err(AuthError::TokenExpired)));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotation_and_reuse() {
+        let jwt = JwtManager::new(SECRET).unwrap();
+        let first = jwt.issue("42").await.unwrap();
+
+        let second = jwt.refresh(&first.refresh_token).await.unwrap();
+        assert_eq!(jwt.verify(&second.access_token).unwrap().sub, "42");
+
+        // Replaying the spent token revokes the family, including the
+        // token issued by the rotation
+        assert!(matches!(
+            jwt.refresh(&first.refresh_token).await,
+            Err(AuthError::TokenReused)
+        ));
+        assert!(jwt.refresh(&second.refresh_token).await.is_err());
+
+        // Logout
+        let other = jwt.issue("42").await.unwrap();
+        jwt.revoke(&other.refresh_token).await.unwrap();
+        assert!(jwt.refresh(&other.refresh_token).await.is_err());
+    }
+}
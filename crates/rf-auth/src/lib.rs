@@ -0,0 +1,64 @@
+//! # rf-auth: Authentication for RustForge
+//!
+//! Guards resolving the user of a request, password hashing and the token
+//! flows around accounts.
+//!
+//! ## Features
+//!
+//! - **Password Hashing**: Argon2id with rehash detection
+//! - **Session Guard**: Logins kept in a tower-sessions session
+//! - **JWT Guard**: Access tokens with rotating refresh tokens and reuse
+//!   detection
+//! - **Personal Access Tokens**: Long-lived API tokens with abilities
+//! - **One-Time Tokens**: Password reset and email verification flows
+//! - **`CurrentUser` Extractor**: The authenticated user in axum handlers
+//!
+//! ## Quick Start
+//!
+//! ```ignore
+//! use axum::{routing::{get, post}, Extension, Json, Router};
+//! use rf_auth::{Auth, AuthResult, CurrentUser, JwtManager, PasswordHasher, TokenPair};
+//!
+//! async fn login(
+//!     Extension(auth): Extension<Auth<User>>,
+//!     Json(credentials): Json<Credentials>,
+//! ) -> AuthResult<Json<TokenPair>> {
+//!     let user = find_user(&credentials.email).await?;
+//!     if !PasswordHasher::default().verify(&credentials.password, &user.password_hash)? {
+//!         return Err(rf_auth::AuthError::InvalidCredentials);
+//!     }
+//!     let jwt = auth.jwt_manager().expect("JWT guard is configured");
+//!     Ok(Json(jwt.issue(&user.auth_id()).await?))
+//! }
+//!
+//! async fn profile(user: CurrentUser<User>) -> String {
+//!     format!("Hello, {}", user.name)
+//! }
+//!
+//! let auth = Auth::new(UserRepository::new(pool)).jwt(JwtManager::from_env()?);
+//! let app = Router::new()
+//!     .route("/login", post(login))
+//!     .route("/profile", get(profile))
+//!     .layer(Extension(auth));
+//! ```
+
+mod error;
+mod guard;
+mod jwt;
+mod password;
+mod tokens;
+mod user;
+mod verification;
+
+pub use error::{AuthError, AuthResult};
+pub use guard::{Auth, CurrentUser, Guard, SessionGuard, SESSION_KEY};
+pub use jwt::{
+    Claims, JwtManager, MemoryRefreshTokenStore, RefreshTokenRecord, RefreshTokenStore, TokenKind,
+    TokenPair,
+};
+pub use password::PasswordHasher;
+pub use tokens::{
+    AccessTokens, MemoryTokenRepository, PersonalAccessToken, TokenRepository, TOKEN_PREFIX,
+};
+pub use user::{Authenticatable, UserProvider};
+pub use verification::{MemoryOneTimeTokenStore, OneTimeToken, OneTimeTokenStore, TokenBroker};
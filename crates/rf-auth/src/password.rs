@@ -0,0 +1,102 @@
+//! Password hashing
+
+use crate::{AuthError, AuthResult};
+use argon2::password_hash::{PasswordHash, PasswordHasher as _, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::rngs::OsRng;
+
+/// Argon2id password hasher
+///
+/// Hashes are PHC strings (`$argon2id$v=19$m=19456,t=2,p=1$...`) carrying
+/// their own salt and parameters, so they stay verifiable after the
+/// parameters change; [`needs_rehash`](Self::needs_rehash) tells when to
+/// upgrade a stored hash on the next login.
+///
+/// ```
+/// use rf_auth::PasswordHasher;
+///
+/// let hasher = PasswordHasher::default();
+/// let hash = hasher.hash("correct horse battery staple").unwrap();
+/// assert!(hasher.verify("correct horse battery staple", &hash).unwrap());
+/// assert!(!hasher.verify("wrong", &hash).unwrap());
+/// ```
+#[derive(Clone)]
+pub struct PasswordHasher {
+    params: Params,
+}
+
+impl PasswordHasher {
+    /// Use custom Argon2 parameters: memory in KiB, iterations and
+    /// parallelism
+    pub fn new(memory_kib: u32, iterations: u32, parallelism: u32) -> AuthResult<Self> {
+        let params = Params::new(memory_kib, iterations, parallelism, None)
+            .map_err(|e| AuthError::ConfigError(e.to_string()))?;
+        Ok(Self { params })
+    }
+
+    fn argon2(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+
+    /// Hash a password with a random salt
+    pub fn hash(&self, password: &str) -> AuthResult<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        self.argon2()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| AuthError::HashingFailed(e.to_string()))
+    }
+
+    /// Check a password against a stored hash
+    pub fn verify(&self, password: &str, hash: &str) -> AuthResult<bool> {
+        let parsed =
+            PasswordHash::new(hash).map_err(|e| AuthError::HashingFailed(e.to_string()))?;
+        Ok(self
+            .argon2()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok())
+    }
+
+    /// Whether a stored hash uses other parameters than this hasher
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return true;
+        };
+        let Ok(params) = Params::try_from(&parsed) else {
+            return true;
+        };
+
+        parsed.algorithm != Algorithm::Argon2id.ident()
+            || params.m_cost() != self.params.m_cost()
+            || params.t_cost() != self.params.t_cost()
+            || params.p_cost() != self.params.p_cost()
+    }
+}
+
+impl Default for PasswordHasher {
+    /// OWASP recommended Argon2id parameters
+    fn default() -> Self {
+        Self {
+            params: Params::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needs_rehash() {
+        let weak = PasswordHasher::new(8, 1, 1).unwrap();
+        let hash = weak.hash("secret password").unwrap();
+
+        assert!(!weak.needs_rehash(&hash));
+        assert!(PasswordHasher::default().needs_rehash(&hash));
+        // Old parameters still verify
+        assert!(PasswordHasher::default()
+            .verify("secret password", &hash)
+            .unwrap());
+        assert!(PasswordHasher::default().verify("x", "not a hash").is_err());
+    }
+}
@@ -0,0 +1,274 @@
+//! Personal access tokens with abilities
+
+use crate::{AuthError, AuthResult};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Prefix of plain text tokens, telling them apart from JWTs
+pub const TOKEN_PREFIX: &str = "rfpat_";
+
+/// Long-lived API token of a user
+///
+/// Only a SHA-256 hash of the token is stored; the plain text token is
+/// shown once, when it is created.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersonalAccessToken {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub token_hash: String,
+    /// Granted abilities like `posts:read`; `*` grants all, `posts:*` all
+    /// abilities starting with `posts:`
+    pub abilities: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl PersonalAccessToken {
+    /// Check if the token grants an ability
+    pub fn can(&self, ability: &str) -> bool {
+        self.abilities.iter().any(|granted| {
+            granted == "*"
+                || granted == ability
+                || granted
+                    .strip_suffix('*')
+                    .is_some_and(|prefix| ability.starts_with(prefix))
+        })
+    }
+
+    /// Check if token is expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| Utc::now() > expires_at)
+    }
+}
+
+/// Storage for personal access tokens
+#[async_trait]
+pub trait TokenRepository: Send + Sync {
+    async fn save(&self, token: PersonalAccessToken) -> AuthResult<()>;
+
+    async fn find_by_hash(&self, token_hash: &str) -> AuthResult<Option<PersonalAccessToken>>;
+
+    /// Record that a token was used
+    async fn touch(&self, id: &str, at: DateTime<Utc>) -> AuthResult<()>;
+
+    async fn delete(&self, id: &str) -> AuthResult<()>;
+
+    /// Tokens of a user, e.g. to list them in the account settings
+    async fn for_user(&self, user_id: &str) -> AuthResult<Vec<PersonalAccessToken>>;
+}
+
+/// In-memory token repository
+#[derive(Default)]
+pub struct MemoryTokenRepository {
+    tokens: RwLock<HashMap<String, PersonalAccessToken>>,
+}
+
+impl MemoryTokenRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TokenRepository for MemoryTokenRepository {
+    async fn save(&self, token: PersonalAccessToken) -> AuthResult<()> {
+        self.tokens.write().await.insert(token.id.clone(), token);
+        Ok(())
+    }
+
+    async fn find_by_hash(&self, token_hash: &str) -> AuthResult<Option<PersonalAccessToken>> {
+        let tokens = self.tokens.read().await;
+        Ok(tokens
+            .values()
+            .find(|t| t.token_hash == token_hash)
+            .cloned())
+    }
+
+    async fn touch(&self, id: &str, at: DateTime<Utc>) -> AuthResult<()> {
+        if let Some(token) = self.tokens.write().await.get_mut(id) {
+            token.last_used_at = Some(at);
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> AuthResult<()> {
+        self.tokens.write().await.remove(id);
+        Ok(())
+    }
+
+    async fn for_user(&self, user_id: &str) -> AuthResult<Vec<PersonalAccessToken>> {
+        let tokens = self.tokens.read().await;
+        Ok(tokens
+            .values()
+            .filter(|t| t.user_id == user_id)
+            .cloned()
+            .collect())
+    }
+}
+
+/// Creates and checks personal access tokens
+///
+/// ```
+/// use rf_auth::{AccessTokens, MemoryTokenRepository};
+/// use std::sync::Arc;
+///
+/// # async fn example() -> rf_auth::AuthResult<()> {
+/// let tokens = AccessTokens::new(Arc::new(MemoryTokenRepository::new()));
+/// let (token, plain) = tokens.create("42", "CI", &["deploy:*"], None).await?;
+///
+/// let found = tokens.authenticate(&plain).await?;
+/// assert_eq!(found.id, token.id);
+/// assert!(found.can("deploy:production"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct AccessTokens {
+    repository: Arc<dyn TokenRepository>,
+}
+
+impl AccessTokens {
+    pub fn new(repository: Arc<dyn TokenRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Create a token, returning it with its plain text value
+    pub async fn create(
+        &self,
+        user_id: &str,
+        name: &str,
+        abilities: &[&str],
+        expires_in: Option<Duration>,
+    ) -> AuthResult<(PersonalAccessToken, String)> {
+        let plain = format!(
+            "{}{}",
+            TOKEN_PREFIX,
+            Alphanumeric.sample_string(&mut rand::thread_rng(), 40)
+        );
+        let now = Utc::now();
+        let expires_at = expires_in
+            .map(|ttl| {
+                chrono::Duration::from_std(ttl)
+                    .map(|ttl| now + ttl)
+                    .map_err(|e| AuthError::ConfigError(e.to_string()))
+            })
+            .transpose()?;
+
+        let token = PersonalAccessToken {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            name: name.to_string(),
+            token_hash: hash_token(&plain),
+            abilities: abilities.iter().map(|a| a.to_string()).collect(),
+            created_at: now,
+            last_used_at: None,
+            expires_at,
+        };
+        self.repository.save(token.clone()).await?;
+        Ok((token, plain))
+    }
+
+    /// Find the token for a plain text value, recording its use
+    pub async fn authenticate(&self, plain: &str) -> AuthResult<PersonalAccessToken> {
+        let token = self
+            .repository
+            .find_by_hash(&hash_token(plain))
+            .await?
+            .ok_or_else(|| AuthError::InvalidToken("Unknown access token".to_string()))?;
+
+        if token.is_expired() {
+            return Err(AuthError::TokenExpired);
+        }
+
+        self.repository.touch(&token.id, Utc::now()).await?;
+        Ok(token)
+    }
+
+    /// Revoke a token
+    pub async fn revoke(&self, id: &str) -> AuthResult<()> {
+        self.repository.delete(id).await
+    }
+
+    /// Tokens of a user
+    pub async fn for_user(&self, user_id: &str) -> AuthResult<Vec<PersonalAccessToken>> {
+        self.repository.for_user(user_id).await
+    }
+}
+
+/// SHA-256 hash of a token in hex, as stored
+pub(crate) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(abilities: &[&str]) -> PersonalAccessToken {
+        PersonalAccessToken {
+            id: "1".to_string(),
+            user_id: "42".to_string(),
+            name: "test".to_string(),
+            token_hash: String::new(),
+            abilities: abilities.iter().map(|a| a.to_string()).collect(),
+            created_at: Utc::now(),
+            last_used_at: None,
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn test_abilities() {
+        assert!(token(&["*"]).can("posts:delete"));
+        assert!(token(&["posts:*"]).can("posts:delete"));
+        assert!(!token(&["posts:*"]).can("users:delete"));
+        assert!(token(&["posts:read"]).can("posts:read"));
+        assert!(!token(&["posts:read"]).can("posts:write"));
+        assert!(!token(&[]).can("posts:read"));
+    }
+
+    #[tokio::test]
+    async fn test_create_and_authenticate() {
+        let repository = Arc::new(MemoryTokenRepository::new());
+        let tokens = AccessTokens::new(repository.clone());
+
+        let (token, plain) = tokens.create("42", "CLI", &["*"], None).await.unwrap();
+        assert!(plain.starts_with(TOKEN_PREFIX));
+        assert_ne!(token.token_hash, plain);
+
+        let found = tokens.authenticate(&plain).await.unwrap();
+        assert_eq!(found.user_id, "42");
+        let stored = tokens.for_user("42").await.unwrap();
+        assert!(stored[0].last_used_at.is_some());
+
+        assert!(tokens.authenticate("rfpat_wrong").await.is_err());
+        tokens.revoke(&token.id).await.unwrap();
+        assert!(tokens.authenticate(&plain).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expired_token() {
+        let tokens = AccessTokens::new(Arc::new(MemoryTokenRepository::new()));
+        let (_, plain) = tokens
+            .create("42", "CLI", &["*"], Some(Duration::ZERO))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        assert!(matches!(
+            tokens.authenticate(&plain).await,
+            Err(AuthError::TokenExpired)
+        ));
+    }
+}
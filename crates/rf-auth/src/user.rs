@@ -0,0 +1,21 @@
+//! Users known to the guards
+
+use crate::AuthResult;
+use async_trait::async_trait;
+
+/// A user that can be logged in
+pub trait Authenticatable: Clone + Send + Sync + 'static {
+    /// Identifier stored in sessions and token subjects
+    fn auth_id(&self) -> String;
+}
+
+/// Loads users by the identifier the guards stored
+///
+/// Implemented by the application, usually on top of its user repository.
+#[async_trait]
+pub trait UserProvider: Send + Sync + 'static {
+    type User: Authenticatable;
+
+    /// Find a user by [`Authenticatable::auth_id`]
+    async fn find_by_id(&self, id: &str) -> AuthResult<Option<Self::User>>;
+}
@@ -0,0 +1,190 @@
+//! One-time tokens for password resets and email verification
+
+use crate::tokens::hash_token;
+use crate::{AuthError, AuthResult};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::distributions::{Alphanumeric, DistString};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// One-time token, as kept by a [`OneTimeTokenStore`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OneTimeToken {
+    pub purpose: String,
+    pub user_id: String,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Storage for one-time tokens
+#[async_trait]
+pub trait OneTimeTokenStore: Send + Sync {
+    /// Store a token, replacing earlier tokens of the user for the same
+    /// purpose
+    async fn put(&self, token: OneTimeToken) -> AuthResult<()>;
+
+    /// Remove and return a token
+    async fn take(&self, purpose: &str, token_hash: &str) -> AuthResult<Option<OneTimeToken>>;
+}
+
+/// In-memory one-time token store
+#[derive(Default)]
+pub struct MemoryOneTimeTokenStore {
+    tokens: Mutex<HashMap<(String, String), OneTimeToken>>,
+}
+
+impl MemoryOneTimeTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl OneTimeTokenStore for MemoryOneTimeTokenStore {
+    async fn put(&self, token: OneTimeToken) -> AuthResult<()> {
+        let mut tokens = self.tokens.lock().await;
+        tokens.retain(|_, t| !(t.purpose == token.purpose && t.user_id == token.user_id));
+        tokens.insert((token.purpose.clone(), token.token_hash.clone()), token);
+        Ok(())
+    }
+
+    async fn take(&self, purpose: &str, token_hash: &str) -> AuthResult<Option<OneTimeToken>> {
+        let mut tokens = self.tokens.lock().await;
+        Ok(tokens.remove(&(purpose.to_string(), token_hash.to_string())))
+    }
+}
+
+/// Issues and redeems one-time tokens for a purpose
+///
+/// Tokens are sent to the user, e.g. in a password reset link, and are
+/// valid once until they expire. Requesting a new token invalidates the
+/// previous one.
+///
+/// ```
+/// use rf_auth::{MemoryOneTimeTokenStore, PasswordHasher, TokenBroker};
+/// use std::sync::Arc;
+///
+/// # async fn example() -> rf_auth::AuthResult<()> {
+/// let resets = TokenBroker::password_reset(Arc::new(MemoryOneTimeTokenStore::new()));
+///
+/// // "Forgot password": mail a link containing the token
+/// let token = resets.create("42").await?;
+///
+/// // The link is followed: redeem the token and store the new password
+/// let user_id = resets.redeem(&token).await?;
+/// let password_hash = PasswordHasher::default().hash("new password")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct TokenBroker {
+    store: Arc<dyn OneTimeTokenStore>,
+    purpose: String,
+    ttl: Duration,
+}
+
+impl TokenBroker {
+    pub fn new(
+        store: Arc<dyn OneTimeTokenStore>,
+        purpose: impl Into<String>,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            store,
+            purpose: purpose.into(),
+            ttl,
+        }
+    }
+
+    /// Broker for password reset tokens, valid for one hour
+    pub fn password_reset(store: Arc<dyn OneTimeTokenStore>) -> Self {
+        Self::new(store, "password_reset", Duration::from_secs(60 * 60))
+    }
+
+    /// Broker for email verification tokens, valid for one day
+    pub fn email_verification(store: Arc<dyn OneTimeTokenStore>) -> Self {
+        Self::new(
+            store,
+            "email_verification",
+            Duration::from_secs(24 * 60 * 60),
+        )
+    }
+
+    /// How long tokens are valid
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Create a token for a user, returning its plain text value
+    pub async fn create(&self, user_id: &str) -> AuthResult<String> {
+        let plain = Alphanumeric.sample_string(&mut rand::thread_rng(), 64);
+        let ttl = chrono::Duration::from_std(self.ttl)
+            .map_err(|e| AuthError::ConfigError(e.to_string()))?;
+
+        self.store
+            .put(OneTimeToken {
+                purpose: self.purpose.clone(),
+                user_id: user_id.to_string(),
+                token_hash: hash_token(&plain),
+                expires_at: Utc::now() + ttl,
+            })
+            .await?;
+        Ok(plain)
+    }
+
+    /// Redeem a token, returning the user it was created for
+    pub async fn redeem(&self, plain: &str) -> AuthResult<String> {
+        let token = self
+            .store
+            .take(&self.purpose, &hash_token(plain))
+            .await?
+            .ok_or_else(|| AuthError::InvalidToken("Unknown or used token".to_string()))?;
+
+        if Utc::now() > token.expires_at {
+            return Err(AuthError::TokenExpired);
+        }
+        Ok(token.user_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_token_broker() {
+        let store = Arc::new(MemoryOneTimeTokenStore::new());
+        let resets = TokenBroker::password_reset(store.clone());
+        let verification = TokenBroker::email_verification(store);
+
+        let first = resets.create("42").await.unwrap();
+        let second = resets.create("42").await.unwrap();
+        let verify = verification.create("42").await.unwrap();
+
+        // Superseded by the second token
+        assert!(resets.redeem(&first).await.is_err());
+        // Tokens are bound to their purpose
+        assert!(verification.redeem(&second).await.is_err());
+
+        assert_eq!(resets.redeem(&second).await.unwrap(), "42");
+        assert!(resets.redeem(&second).await.is_err());
+        assert_eq!(verification.redeem(&verify).await.unwrap(), "42");
+    }
+
+    #[tokio::test]
+    async fn test_expired_token() {
+        let store = Arc::new(MemoryOneTimeTokenStore::new());
+        let resets = TokenBroker::password_reset(store).ttl(Duration::ZERO);
+        let token = resets.create("42").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        assert!(matches!(
+            resets.redeem(&token).await,
+            Err(AuthError::TokenExpired)
+        ));
+    }
+}
@@ -0,0 +1,62 @@
+//! Compressed tar archives
+
+use crate::{BackupFile, BackupResult};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Read;
+
+/// Pack files into a `.tar.gz` archive
+pub fn pack(files: &[BackupFile]) -> BackupResult<Vec<u8>> {
+    let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    let modified = chrono::Utc::now().timestamp().max(0) as u64;
+
+    for file in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(file.contents.len() as u64);
+        header.set_mode(0o600);
+        header.set_mtime(modified);
+        archive.append_data(&mut header, &file.path, file.contents.as_slice())?;
+    }
+
+    Ok(archive.into_inner()?.finish()?)
+}
+
+/// Unpack a `.tar.gz` archive from [`pack`]
+pub fn unpack(archive: &[u8]) -> BackupResult<Vec<BackupFile>> {
+    let mut archive = tar::Archive::new(GzDecoder::new(archive));
+    let mut files = Vec::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let mut contents = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut contents)?;
+        files.push(BackupFile { path, contents });
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack() {
+        let files = vec![
+            BackupFile {
+                path: "database/app.sql".into(),
+                contents: b"CREATE TABLE users (id INT);".repeat(100),
+            },
+            BackupFile {
+                path: "files/uploads/empty.txt".into(),
+                contents: Vec::new(),
+            },
+        ];
+
+        let archive = pack(&files).unwrap();
+        assert!(archive.len() < files[0].contents.len());
+        assert_eq!(unpack(&archive).unwrap(), files);
+        assert!(unpack(b"not an archive").is_err());
+    }
+}
@@ -0,0 +1,462 @@
+//! Backup runs
+
+use crate::archive::{pack, unpack};
+use crate::{
+    BackupError, BackupFile, BackupNotification, BackupResult, BackupSource, BackupStatus,
+    RetentionPolicy, StoredBackup,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rf_encryption::Encrypter;
+use rf_notifications::{Channel, Notifiable, NotificationManager};
+use rf_storage::Filesystem;
+use std::sync::Arc;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d-%H-%M-%S";
+const ARCHIVE_EXTENSION: &str = ".tar.gz";
+const ENCRYPTED_EXTENSION: &str = ".tar.gz.enc";
+
+/// Outcome of a successful backup run
+#[derive(Debug, Clone)]
+pub struct BackupReport {
+    /// Path of the archive on the destination disk
+    pub path: String,
+
+    /// Number of files in the archive
+    pub files: usize,
+
+    /// Size of the stored archive in bytes
+    pub size: u64,
+
+    pub encrypted: bool,
+
+    /// Old backups deleted by the retention policy
+    pub deleted: Vec<String>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+struct Notifier {
+    manager: Arc<NotificationManager>,
+    notifiable: Arc<dyn Notifiable>,
+    channels: Vec<Channel>,
+}
+
+/// Backs up databases and files into a compressed, optionally encrypted
+/// archive on a destination disk
+///
+/// Archives are stored as `<directory>/<name>-<timestamp>.tar.gz`, with an
+/// `.enc` suffix when encrypted. After each run the retention policy
+/// deletes old archives and the notifiable is told whether the run
+/// succeeded.
+///
+/// ```
+/// use rf_backup::{Backup, DiskFiles, PostgresDump, RetentionPolicy};
+/// use rf_encryption::Encrypter;
+/// use rf_storage::MemoryStorage;
+/// use std::sync::Arc;
+///
+/// # fn example(
+/// #     uploads: Arc<MemoryStorage>,
+/// #     s3: MemoryStorage,
+/// # ) -> rf_encryption::EncryptionResult<()> {
+/// let backup = Backup::new("app", s3)
+///     .source(PostgresDump::new("postgres://localhost/app"))
+///     .source(DiskFiles::new("uploads", uploads))
+///     .encrypt_with(Arc::new(Encrypter::from_env()?))
+///     .retention(RetentionPolicy::new(7, 4, 12));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Backup {
+    name: String,
+    directory: String,
+    sources: Vec<Arc<dyn BackupSource>>,
+    destination: Arc<dyn Filesystem>,
+    encrypter: Option<Arc<Encrypter>>,
+    retention: Option<RetentionPolicy>,
+    notifier: Option<Notifier>,
+}
+
+impl Backup {
+    /// Backup `name`, stored on `destination` below a directory of the same
+    /// name
+    pub fn new(name: impl Into<String>, destination: impl Filesystem + 'static) -> Self {
+        Self::with_destination(name, Arc::new(destination))
+    }
+
+    /// Backup to an already shared disk
+    pub fn with_destination(name: impl Into<String>, destination: Arc<dyn Filesystem>) -> Self {
+        let name = name.into();
+        Self {
+            directory: name.clone(),
+            name,
+            sources: Vec::new(),
+            destination,
+            encrypter: None,
+            retention: None,
+            notifier: None,
+        }
+    }
+
+    /// Store archives below `directory` instead
+    pub fn directory(mut self, directory: impl Into<String>) -> Self {
+        self.directory = directory.into().trim_matches('/').to_string();
+        self
+    }
+
+    /// Add a database or files to back up
+    pub fn source(mut self, source: impl BackupSource + 'static) -> Self {
+        self.sources.push(Arc::new(source));
+        self
+    }
+
+    /// Encrypt archives before they leave the machine
+    pub fn encrypt_with(mut self, encrypter: Arc<Encrypter>) -> Self {
+        self.encrypter = Some(encrypter);
+        self
+    }
+
+    /// Delete old archives after each run
+    pub fn retention(mut self, policy: RetentionPolicy) -> Self {
+        self.retention = Some(policy);
+        self
+    }
+
+    /// Notify `notifiable` by mail after each run
+    pub fn notify(
+        mut self,
+        manager: Arc<NotificationManager>,
+        notifiable: Arc<dyn Notifiable>,
+    ) -> Self {
+        self.notifier = Some(Notifier {
+            manager,
+            notifiable,
+            channels: vec![Channel::Email],
+        });
+        self
+    }
+
+    /// Send notifications via `channels` instead of mail
+    pub fn notify_via(mut self, channels: Vec<Channel>) -> Self {
+        if let Some(notifier) = &mut self.notifier {
+            notifier.channels = channels;
+        }
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Create an archive, apply the retention policy and notify
+    pub async fn run(&self) -> BackupResult<BackupReport> {
+        let result = self.create().await;
+        match &result {
+            Ok(report) => {
+                tracing::info!(
+                    backup = %self.name,
+                    path = %report.path,
+                    size = report.size,
+                    "Backup stored"
+                );
+                self.send(BackupStatus::Succeeded(report.clone())).await;
+            }
+            Err(e) => {
+                tracing::error!(backup = %self.name, error = %e, "Backup failed");
+                self.send(BackupStatus::Failed(e.to_string())).await;
+            }
+        }
+        result
+    }
+
+    /// Stored archives, newest first
+    pub async fn list(&self) -> BackupResult<Vec<StoredBackup>> {
+        let prefix = format!("{}-", self.name);
+        let mut backups: Vec<StoredBackup> = self
+            .destination
+            .list(&self.directory)
+            .await?
+            .into_iter()
+            .filter_map(|path| {
+                let file = path.rsplit('/').next()?;
+                let timestamp = file
+                    .strip_prefix(&prefix)?
+                    .strip_suffix(ENCRYPTED_EXTENSION)
+                    .or_else(|| file.strip_prefix(&prefix)?.strip_suffix(ARCHIVE_EXTENSION))?;
+                let created_at = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+                    .ok()?
+                    .and_utc();
+                Some(StoredBackup {
+                    path: path.trim_start_matches('/').to_string(),
+                    created_at,
+                })
+            })
+            .collect();
+
+        backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+        Ok(backups)
+    }
+
+    /// Delete archives the retention policy doesn't keep, returning their
+    /// paths
+    pub async fn clean(&self) -> BackupResult<Vec<String>> {
+        let Some(policy) = self.retention else {
+            return Ok(Vec::new());
+        };
+
+        let backups = self.list().await?;
+        let mut deleted = Vec::new();
+        for backup in policy.expired(&backups) {
+            self.destination.delete(&backup.path).await?;
+            deleted.push(backup.path.clone());
+        }
+        Ok(deleted)
+    }
+
+    /// Download, decrypt and unpack an archive, e.g. to restore it
+    pub async fn open(&self, path: &str) -> BackupResult<Vec<BackupFile>> {
+        let contents = self.destination.get(path).await?;
+        let archive = if path.ends_with(ENCRYPTED_EXTENSION) {
+            let encrypter = self.encrypter.as_ref().ok_or_else(|| {
+                BackupError::Config(format!("{} is encrypted, but no key is set", path))
+            })?;
+            let payload = String::from_utf8(contents).map_err(|_| {
+                BackupError::Config(format!("{} is not an encrypted archive", path))
+            })?;
+            encrypter.decrypt_bytes(&payload)?
+        } else {
+            contents
+        };
+
+        tokio::task::spawn_blocking(move || unpack(&archive))
+            .await
+            .map_err(|e| BackupError::Archive(std::io::Error::other(e)))?
+    }
+
+    async fn create(&self) -> BackupResult<BackupReport> {
+        if self.sources.is_empty() {
+            return Err(BackupError::Config(format!(
+                "backup {} has no sources",
+                self.name
+            )));
+        }
+
+        let created_at = Utc::now();
+        let mut files = Vec::new();
+        for source in &self.sources {
+            tracing::debug!(backup = %self.name, source = source.name(), "Collecting");
+            files.extend(source.collect().await?);
+        }
+        let count = files.len();
+
+        let archive = tokio::task::spawn_blocking(move || pack(&files))
+            .await
+            .map_err(|e| BackupError::Archive(std::io::Error::other(e)))??;
+        let (contents, extension) = match &self.encrypter {
+            Some(encrypter) => (
+                encrypter.encrypt_bytes(&archive)?.into_bytes(),
+                ENCRYPTED_EXTENSION,
+            ),
+            None => (archive, ARCHIVE_EXTENSION),
+        };
+
+        let path = format!(
+            "{}/{}-{}{}",
+            self.directory,
+            self.name,
+            created_at.format(TIMESTAMP_FORMAT),
+            extension
+        );
+        let size = contents.len() as u64;
+        self.destination.put(&path, contents).await?;
+
+        Ok(BackupReport {
+            path,
+            files: count,
+            size,
+            encrypted: self.encrypter.is_some(),
+            deleted: self.clean().await?,
+            created_at,
+        })
+    }
+
+    async fn send(&self, status: BackupStatus) {
+        let Some(notifier) = &self.notifier else {
+            return;
+        };
+        let notification = BackupNotification {
+            backup: self.name.clone(),
+            status,
+            channels: notifier.channels.clone(),
+        };
+        // A failing notification must not fail the backup itself
+        if let Err(e) = notifier
+            .manager
+            .send(&notification, notifier.notifiable.as_ref())
+            .await
+        {
+            tracing::warn!(backup = %self.name, error = %e, "Failed to send backup notification");
+        }
+    }
+}
+
+#[cfg(feature = "scheduler")]
+#[async_trait::async_trait]
+impl rf_scheduler::Task for Backup {
+    async fn run(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Backup::run(self).await?;
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BackupFile, SqliteCopy};
+    use async_trait::async_trait;
+    use rf_encryption::AppKey;
+    use rf_notifications::DatabaseChannel;
+    use rf_storage::MemoryStorage;
+
+    struct Files(Vec<BackupFile>);
+
+    #[async_trait]
+    impl BackupSource for Files {
+        fn name(&self) -> &str {
+            "files"
+        }
+
+        async fn collect(&self) -> BackupResult<Vec<BackupFile>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    struct Admin;
+
+    impl Notifiable for Admin {
+        fn email(&self) -> Option<String> {
+            Some("ops@example.com".to_string())
+        }
+
+        fn id(&self) -> String {
+            "admin".to_string()
+        }
+    }
+
+    fn files() -> Files {
+        Files(vec![BackupFile {
+            path: "database/app.sql".into(),
+            contents: b"INSERT INTO users VALUES (1);".to_vec(),
+        }])
+    }
+
+    #[tokio::test]
+    async fn test_run_and_open() {
+        let disk = MemoryStorage::new();
+        let backup = Backup::new("app", disk.clone()).source(files());
+
+        let report = backup.run().await.unwrap();
+        assert!(report.path.starts_with("app/app-"));
+        assert!(report.path.ends_with(".tar.gz"));
+        assert_eq!(report.files, 1);
+        assert_eq!(disk.size(&report.path).await.unwrap(), report.size);
+
+        assert_eq!(backup.list().await.unwrap()[0].path, report.path);
+        assert_eq!(backup.open(&report.path).await.unwrap(), files().0);
+    }
+
+    #[tokio::test]
+    async fn test_encryption() {
+        let disk = MemoryStorage::new();
+        let encrypter = Arc::new(Encrypter::new(
+            AppKey::parse("backup-key-for-the-tests").unwrap(),
+            Vec::new(),
+        ));
+        let backup = Backup::new("app", disk.clone())
+            .directory("/backups/")
+            .source(files())
+            .encrypt_with(encrypter);
+
+        let report = backup.run().await.unwrap();
+        assert!(report.encrypted);
+        assert!(report.path.starts_with("backups/app-"));
+        assert!(report.path.ends_with(".tar.gz.enc"));
+        assert_eq!(backup.open(&report.path).await.unwrap(), files().0);
+
+        let unencrypted = Backup::new("app", disk).directory("backups");
+        assert!(matches!(
+            unencrypted.open(&report.path).await,
+            Err(BackupError::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retention() {
+        let disk = MemoryStorage::new();
+        for day in 1..=5 {
+            let path = format!("app/app-2024-01-0{}-03-00-00.tar.gz", day);
+            disk.put(&path, Vec::new()).await.unwrap();
+        }
+        disk.put("app/other-2024-01-01-03-00-00.tar.gz", Vec::new())
+            .await
+            .unwrap();
+
+        let backup = Backup::new("app", disk.clone())
+            .source(files())
+            .retention(RetentionPolicy::new(3, 0, 0));
+        let report = backup.run().await.unwrap();
+
+        // Today's run and the two newest older ones are kept
+        assert_eq!(
+            report.deleted,
+            vec![
+                "app/app-2024-01-03-03-00-00.tar.gz",
+                "app/app-2024-01-02-03-00-00.tar.gz",
+                "app/app-2024-01-01-03-00-00.tar.gz",
+            ]
+        );
+        assert_eq!(backup.list().await.unwrap().len(), 3);
+        assert!(disk
+            .exists("app/other-2024-01-01-03-00-00.tar.gz")
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_notifications() {
+        let channel = Arc::new(DatabaseChannel::new());
+        let mut manager = NotificationManager::new();
+        manager.register_channel(Channel::Database, channel.clone());
+        let manager = Arc::new(manager);
+
+        let backup = Backup::new("app", MemoryStorage::new())
+            .source(files())
+            .notify(manager.clone(), Arc::new(Admin))
+            .notify_via(vec![Channel::Database]);
+        backup.run().await.unwrap();
+
+        let failing = Backup::new("db", MemoryStorage::new())
+            .source(SqliteCopy::new("/nonexistent/app.sqlite"))
+            .notify(manager, Arc::new(Admin))
+            .notify_via(vec![Channel::Database]);
+        assert!(matches!(failing.run().await, Err(BackupError::Dump(_))));
+
+        let sent = channel.get_notifications("admin").await;
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].title, "Backup app succeeded");
+        assert_eq!(sent[1].title, "Backup db failed");
+        assert_eq!(sent[1].data["succeeded"], false);
+    }
+
+    #[tokio::test]
+    async fn test_no_sources() {
+        assert!(matches!(
+            Backup::new("app", MemoryStorage::new()).run().await,
+            Err(BackupError::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,27 @@
+//! Backup errors
+
+use rf_encryption::EncryptionError;
+use rf_storage::StorageError;
+use thiserror::Error;
+
+/// Backup errors
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("Dump failed: {0}")]
+    Dump(String),
+
+    #[error("Archive error: {0}")]
+    Archive(#[from] std::io::Error),
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+
+    #[error("Encryption error: {0}")]
+    Encryption(#[from] EncryptionError),
+}
+
+/// Result type for backup operations
+pub type BackupResult<T> = Result<T, BackupError>;
@@ -0,0 +1,32 @@
+//! Database and file backups for RustForge
+//!
+//! - **Sources**: [`PostgresDump`] and [`MySqlDump`] run `pg_dump` and
+//!   `mysqldump`, [`SqliteCopy`] copies SQLite files and [`DiskFiles`]
+//!   archives the files of an rf-storage disk
+//! - **Archives**: sources are packed into one `.tar.gz`, encrypted with
+//!   rf-encryption if configured, and uploaded to a destination disk such
+//!   as S3
+//! - **Retention**: [`RetentionPolicy`] keeps the newest backup of the last
+//!   N days, weeks and months
+//! - **Notifications**: success and failure are sent via rf-notifications
+//!
+//! # Scheduling
+//!
+//! With the `scheduler` feature a [`Backup`] is an `rf_scheduler::Task`:
+//!
+//! ```ignore
+//! scheduler.job(backup).daily_at("03:00");
+//! ```
+
+pub mod archive;
+mod backup;
+mod error;
+mod notification;
+mod retention;
+mod source;
+
+pub use backup::{Backup, BackupReport};
+pub use error::{BackupError, BackupResult};
+pub use notification::{BackupNotification, BackupStatus};
+pub use retention::{RetentionPolicy, StoredBackup};
+pub use source::{BackupFile, BackupSource, DiskFiles, MySqlDump, PostgresDump, SqliteCopy};
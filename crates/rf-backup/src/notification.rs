@@ -0,0 +1,96 @@
+//! Success and failure notifications
+
+use crate::BackupReport;
+use rf_notifications::{
+    Channel, DatabaseNotification, MailMessage, Notifiable, Notification, NotificationResult,
+    PushMessage, SmsMessage,
+};
+
+/// Outcome of a backup run
+#[derive(Debug, Clone)]
+pub enum BackupStatus {
+    Succeeded(BackupReport),
+    Failed(String),
+}
+
+/// Notification sent after a backup run
+#[derive(Debug, Clone)]
+pub struct BackupNotification {
+    pub backup: String,
+    pub status: BackupStatus,
+    pub(crate) channels: Vec<Channel>,
+}
+
+impl BackupNotification {
+    pub fn succeeded(&self) -> bool {
+        matches!(self.status, BackupStatus::Succeeded(_))
+    }
+
+    fn subject(&self) -> String {
+        match &self.status {
+            BackupStatus::Succeeded(_) => format!("Backup {} succeeded", self.backup),
+            BackupStatus::Failed(_) => format!("Backup {} failed", self.backup),
+        }
+    }
+
+    fn body(&self) -> String {
+        match &self.status {
+            BackupStatus::Succeeded(report) => format!(
+                "Stored {} ({} files, {} bytes); {} old backups deleted.",
+                report.path,
+                report.files,
+                report.size,
+                report.deleted.len()
+            ),
+            BackupStatus::Failed(error) => format!("The backup failed: {}", error),
+        }
+    }
+}
+
+impl Notification for BackupNotification {
+    fn via(&self, _notifiable: &dyn Notifiable) -> Vec<Channel> {
+        self.channels.clone()
+    }
+
+    fn to_mail(&self, notifiable: &dyn Notifiable) -> NotificationResult<MailMessage> {
+        let mut message = MailMessage::new().subject(self.subject()).body(self.body());
+        if let Some(email) = notifiable.email() {
+            message = message.to(email);
+        }
+        Ok(message)
+    }
+
+    fn to_sms(&self, notifiable: &dyn Notifiable) -> NotificationResult<SmsMessage> {
+        Ok(SmsMessage::new(
+            notifiable.phone().unwrap_or_default(),
+            self.subject(),
+        ))
+    }
+
+    fn to_push(&self, _notifiable: &dyn Notifiable) -> NotificationResult<PushMessage> {
+        Ok(PushMessage::new(self.subject(), self.body()).data("backup", &self.backup))
+    }
+
+    fn to_database(
+        &self,
+        _notifiable: &dyn Notifiable,
+    ) -> NotificationResult<DatabaseNotification> {
+        let data = match &self.status {
+            BackupStatus::Succeeded(report) => serde_json::json!({
+                "backup": self.backup,
+                "succeeded": true,
+                "path": report.path,
+                "size": report.size,
+            }),
+            BackupStatus::Failed(error) => serde_json::json!({
+                "backup": self.backup,
+                "succeeded": false,
+                "error": error,
+            }),
+        };
+        Ok(DatabaseNotification::new()
+            .title(self.subject())
+            .body(self.body())
+            .data(data))
+    }
+}
@@ -0,0 +1,140 @@
+//! Which backups to keep
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// A backup on the destination disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredBackup {
+    pub path: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Keep the newest backup of each of the last `daily` days, `weekly` weeks
+/// and `monthly` months; delete the rest
+///
+/// The newest backup is always kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub daily: usize,
+    pub weekly: usize,
+    pub monthly: usize,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            daily: 7,
+            weekly: 4,
+            monthly: 12,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn new(daily: usize, weekly: usize, monthly: usize) -> Self {
+        Self {
+            daily,
+            weekly,
+            monthly,
+        }
+    }
+
+    /// The backups to delete
+    pub fn expired<'a>(&self, backups: &'a [StoredBackup]) -> Vec<&'a StoredBackup> {
+        let mut sorted: Vec<&StoredBackup> = backups.iter().collect();
+        sorted.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+
+        let mut days = HashSet::new();
+        let mut weeks = HashSet::new();
+        let mut months = HashSet::new();
+        let mut expired = Vec::new();
+
+        for (i, backup) in sorted.into_iter().enumerate() {
+            let date = backup.created_at.date_naive();
+            let week = date.iso_week();
+            // Evaluate every bucket, so a backup counts towards all of them
+            let keep = [
+                keep_first(&mut days, date, self.daily),
+                keep_first(&mut weeks, (week.year(), week.week()), self.weekly),
+                keep_first(&mut months, (date.year(), date.month()), self.monthly),
+            ];
+            if i > 0 && !keep.contains(&true) {
+                expired.push(backup);
+            }
+        }
+        expired
+    }
+}
+
+/// Whether `key` is new and there's room for it
+fn keep_first<K: std::hash::Hash + Eq>(seen: &mut HashSet<K>, key: K, limit: usize) -> bool {
+    seen.len() < limit && seen.insert(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn backups(days: i64) -> Vec<StoredBackup> {
+        // Two backups a day, counting back from Sunday 2024-03-31
+        let start = Utc.with_ymd_and_hms(2024, 3, 31, 18, 0, 0).unwrap();
+        (0..days * 2)
+            .map(|i| StoredBackup {
+                path: format!("backup-{}", i),
+                created_at: start - Duration::hours(12 * i),
+            })
+            .collect()
+    }
+
+    fn kept(policy: RetentionPolicy, backups: &[StoredBackup]) -> Vec<String> {
+        let expired = policy.expired(backups);
+        backups
+            .iter()
+            .filter(|b| !expired.contains(b))
+            .map(|b| b.created_at.format("%Y-%m-%d %H").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_daily() {
+        let backups = backups(5);
+        assert_eq!(
+            kept(RetentionPolicy::new(3, 0, 0), &backups),
+            vec!["2024-03-31 18", "2024-03-30 18", "2024-03-29 18"]
+        );
+    }
+
+    #[test]
+    fn test_weekly_and_monthly() {
+        let backups = backups(70);
+        let kept = kept(RetentionPolicy::new(2, 3, 3), &backups);
+        assert_eq!(
+            kept,
+            vec![
+                // Days and the week ending on Sunday 03-31, and March
+                "2024-03-31 18",
+                "2024-03-30 18",
+                // Newest of the two weeks before
+                "2024-03-24 18",
+                "2024-03-17 18",
+                // February and January
+                "2024-02-29 18",
+                "2024-01-31 18",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_newest_is_always_kept() {
+        let backups = backups(2);
+        assert_eq!(
+            RetentionPolicy::new(0, 0, 0).expired(&backups).len(),
+            backups.len() - 1
+        );
+        assert!(RetentionPolicy::default().expired(&[]).is_empty());
+    }
+}
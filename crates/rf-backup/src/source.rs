@@ -0,0 +1,482 @@
+//! What gets backed up
+
+use crate::{BackupError, BackupResult};
+use async_trait::async_trait;
+use percent_encoding::percent_decode_str;
+use rf_storage::Filesystem;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::process::Command;
+
+/// A file in a backup archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupFile {
+    /// Path inside the archive
+    pub path: String,
+    pub contents: Vec<u8>,
+}
+
+/// A database or set of files to back up
+#[async_trait]
+pub trait BackupSource: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    /// The files to put into the archive
+    async fn collect(&self) -> BackupResult<Vec<BackupFile>>;
+}
+
+/// PostgreSQL database dumped with `pg_dump`
+pub struct PostgresDump {
+    name: String,
+    url: String,
+    binary: String,
+    args: Vec<String>,
+}
+
+impl PostgresDump {
+    /// Dump the database at a connection URL
+    ///
+    /// A password in the URL is passed in the environment rather than on
+    /// the command line.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            name: "postgres".to_string(),
+            url: url.into(),
+            binary: "pg_dump".to_string(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Name of the dump file, without extension (default: `postgres`)
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Path of `pg_dump`, if it's not on the `PATH`
+    pub fn binary(mut self, binary: impl Into<String>) -> Self {
+        self.binary = binary.into();
+        self
+    }
+
+    /// Pass an extra argument, e.g. `--exclude-table=sessions`
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+}
+
+#[async_trait]
+impl BackupSource for PostgresDump {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn collect(&self) -> BackupResult<Vec<BackupFile>> {
+        let (url, password) = split_password(&self.url);
+        let mut command = Command::new(&self.binary);
+        command
+            .arg("--no-owner")
+            .arg("--no-acl")
+            .args(&self.args)
+            .arg(format!("--dbname={}", url));
+        if let Some(password) = password {
+            command.env("PGPASSWORD", password);
+        }
+
+        Ok(vec![BackupFile {
+            path: format!("database/{}.sql", self.name),
+            contents: dump(command, &self.binary).await?,
+        }])
+    }
+}
+
+/// MySQL or MariaDB database dumped with `mysqldump`
+pub struct MySqlDump {
+    name: String,
+    database: String,
+    host: String,
+    port: u16,
+    user: Option<String>,
+    password: Option<String>,
+    binary: String,
+    args: Vec<String>,
+}
+
+impl MySqlDump {
+    /// Dump a database on `localhost:3306`
+    pub fn new(database: impl Into<String>) -> Self {
+        let database = database.into();
+        Self {
+            name: database.clone(),
+            database,
+            host: "localhost".to_string(),
+            port: 3306,
+            user: None,
+            password: None,
+            binary: "mysqldump".to_string(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Name of the dump file, without extension (default: the database)
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Password, passed in the environment rather than on the command line
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Path of `mysqldump`, if it's not on the `PATH`
+    pub fn binary(mut self, binary: impl Into<String>) -> Self {
+        self.binary = binary.into();
+        self
+    }
+
+    /// Pass an extra argument, e.g. `--ignore-table=app.sessions`
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+}
+
+#[async_trait]
+impl BackupSource for MySqlDump {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn collect(&self) -> BackupResult<Vec<BackupFile>> {
+        let mut command = Command::new(&self.binary);
+        command
+            .arg("--single-transaction")
+            .arg("--routines")
+            .arg(format!("--host={}", self.host))
+            .arg(format!("--port={}", self.port));
+        if let Some(user) = &self.user {
+            command.arg(format!("--user={}", user));
+        }
+        if let Some(password) = &self.password {
+            command.env("MYSQL_PWD", password);
+        }
+        command.args(&self.args).arg(&self.database);
+
+        Ok(vec![BackupFile {
+            path: format!("database/{}.sql", self.name),
+            contents: dump(command, &self.binary).await?,
+        }])
+    }
+}
+
+/// SQLite database file, copied as is
+///
+/// Copy while nothing writes to the database, or back up a copy made with
+/// `VACUUM INTO`, to get a consistent file.
+pub struct SqliteCopy {
+    name: String,
+    path: PathBuf,
+}
+
+impl SqliteCopy {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "database.sqlite".to_string()),
+            path,
+        }
+    }
+}
+
+#[async_trait]
+impl BackupSource for SqliteCopy {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn collect(&self) -> BackupResult<Vec<BackupFile>> {
+        let contents = tokio::fs::read(&self.path).await.map_err(|e| {
+            BackupError::Dump(format!("failed to read {}: {}", self.path.display(), e))
+        })?;
+
+        Ok(vec![BackupFile {
+            path: format!("database/{}", self.name),
+            contents,
+        }])
+    }
+}
+
+/// Files of a storage disk, e.g. user uploads
+pub struct DiskFiles {
+    name: String,
+    disk: Arc<dyn Filesystem>,
+    prefix: String,
+}
+
+impl DiskFiles {
+    /// All files of `disk`, archived below `files/<name>/`
+    pub fn new(name: impl Into<String>, disk: Arc<dyn Filesystem>) -> Self {
+        Self {
+            name: name.into(),
+            disk,
+            prefix: String::new(),
+        }
+    }
+
+    /// Only back up files below `prefix`
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+#[async_trait]
+impl BackupSource for DiskFiles {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn collect(&self) -> BackupResult<Vec<BackupFile>> {
+        // Some disks list directories next to files, so descend into
+        // entries that can't be read
+        let mut pending = vec![self.prefix.clone()];
+        let mut seen = HashSet::new();
+        let mut files = Vec::new();
+
+        while let Some(directory) = pending.pop() {
+            for path in self.disk.list(&directory).await? {
+                if !seen.insert(path.clone()) {
+                    continue;
+                }
+                match self.disk.get(&path).await {
+                    Ok(contents) => files.push(BackupFile {
+                        path: format!("files/{}/{}", self.name, path.trim_start_matches('/')),
+                        contents,
+                    }),
+                    Err(_) => pending.push(path),
+                }
+            }
+        }
+
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+}
+
+/// Take the password out of a connection URL, as `ps` shows the arguments
+/// of running processes to every user
+///
+/// Looks at the user info and at a `password` query parameter, which
+/// libpq reads as well. Anything that isn't a URL is returned as is.
+fn split_password(url: &str) -> (String, Option<String>) {
+    let Some(scheme_end) = url.find("://").map(|i| i + 3) else {
+        return (url.to_string(), None);
+    };
+    let (address, query) = match url.split_once('?') {
+        Some((address, query)) => (address, Some(query)),
+        None => (url, None),
+    };
+    let decode = |value: &str| percent_decode_str(value).decode_utf8_lossy().into_owned();
+
+    let mut password = None;
+    let authority_end = address[scheme_end..]
+        .find('/')
+        .map_or(address.len(), |i| scheme_end + i);
+    let mut stripped = match address[scheme_end..authority_end].rsplit_once('@') {
+        Some((user_info, host)) => match user_info.split_once(':') {
+            Some((user, secret)) => {
+                password = Some(decode(secret));
+                format!(
+                    "{}{}@{}{}",
+                    &url[..scheme_end],
+                    user,
+                    host,
+                    &address[authority_end..]
+                )
+            }
+            None => address.to_string(),
+        },
+        None => address.to_string(),
+    };
+
+    if let Some(query) = query {
+        let mut params = Vec::new();
+        for param in query.split('&') {
+            match param.strip_prefix("password=") {
+                Some(secret) => password = Some(decode(secret)),
+                None => params.push(param),
+            }
+        }
+        if !params.is_empty() {
+            stripped.push('?');
+            stripped.push_str(&params.join("&"));
+        }
+    }
+    (stripped, password)
+}
+
+async fn dump(mut command: Command, binary: &str) -> BackupResult<Vec<u8>> {
+    let output = command
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| BackupError::Dump(format!("failed to run {}: {}", binary, e)))?;
+
+    if !output.status.success() {
+        return Err(BackupError::Dump(format!(
+            "{} exited with {}: {}",
+            binary,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rf_storage::{LocalStorage, MemoryStorage};
+
+    #[tokio::test]
+    async fn test_dump_commands() {
+        // `echo` stands in for the dump tools and prints their arguments
+        let files = PostgresDump::new("postgres://localhost/app")
+            .binary("echo")
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(files[0].path, "database/postgres.sql");
+        assert_eq!(
+            String::from_utf8_lossy(&files[0].contents).trim(),
+            "--no-owner --no-acl --dbname=postgres://localhost/app"
+        );
+
+        // The password goes into PGPASSWORD
+        let files = PostgresDump::new("postgres://app:s%40cret@db:5432/app?sslmode=require")
+            .binary("echo")
+            .collect()
+            .await
+            .unwrap();
+        let args = String::from_utf8(files[0].contents.clone()).unwrap();
+        assert!(!args.contains("s%40cret") && !args.contains("s@cret"));
+        assert!(args.contains("--dbname=postgres://app@db:5432/app?sslmode=require"));
+
+        let files = PostgresDump::new("postgres://app@db/app?password=secret&sslmode=require")
+            .binary("echo")
+            .collect()
+            .await
+            .unwrap();
+        let args = String::from_utf8(files[0].contents.clone()).unwrap();
+        assert!(!args.contains("secret"));
+        assert!(args.contains("--dbname=postgres://app@db/app?sslmode=require"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("pg_dump");
+        std::fs::write(&script, "#!/bin/sh\necho \"$PGPASSWORD\"\n").unwrap();
+        std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+        let files = PostgresDump::new("postgres://app:secret@db/app")
+            .binary(script.to_string_lossy())
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&files[0].contents).trim(), "secret");
+
+        let files = MySqlDump::new("app")
+            .user("root")
+            .password("secret")
+            .binary("echo")
+            .collect()
+            .await
+            .unwrap();
+        let args = String::from_utf8(files[0].contents.clone()).unwrap();
+        assert!(args.contains("--user=root"));
+        assert!(!args.contains("secret"));
+        assert!(args.trim_end().ends_with("app"));
+
+        assert!(matches!(
+            PostgresDump::new("x").binary("false").collect().await,
+            Err(BackupError::Dump(_))
+        ));
+        assert!(PostgresDump::new("x")
+            .binary("/nonexistent/pg_dump")
+            .collect()
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.sqlite");
+        std::fs::write(&path, b"SQLite format 3").unwrap();
+
+        let files = SqliteCopy::new(&path).collect().await.unwrap();
+        assert_eq!(files[0].path, "database/app.sqlite");
+        assert_eq!(files[0].contents, b"SQLite format 3");
+        assert!(SqliteCopy::new(dir.path().join("missing.sqlite"))
+            .collect()
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_disk_files() {
+        let memory = MemoryStorage::new();
+        memory.put("avatars/1.png", b"png".to_vec()).await.unwrap();
+        memory.put("docs/a.pdf", b"pdf".to_vec()).await.unwrap();
+
+        let files = DiskFiles::new("uploads", Arc::new(memory.clone()))
+            .prefix("avatars")
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "files/uploads/avatars/1.png");
+
+        // Local disks list directories, which are walked
+        let dir = tempfile::tempdir().unwrap();
+        let local = LocalStorage::new(dir.path(), "http://localhost")
+            .await
+            .unwrap();
+        local.put("a.txt", b"a".to_vec()).await.unwrap();
+        local
+            .put("nested/deeper/b.txt", b"b".to_vec())
+            .await
+            .unwrap();
+
+        let files = DiskFiles::new("local", Arc::new(local))
+            .collect()
+            .await
+            .unwrap();
+        let paths: Vec<_> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["files/local/a.txt", "files/local/nested/deeper/b.txt"]
+        );
+    }
+}
@@ -3,58 +3,235 @@
 use crate::{Cache, CacheError, CacheResult, MemoryCache};
 use async_trait::async_trait;
 use serde::{de::DeserializeOwned, Serialize};
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::sync::RwLock;
+
+type WarmFuture = Pin<Box<dyn Future<Output = CacheResult<()>> + Send>>;
+type WarmFn<C> = Arc<dyn Fn(Arc<C>) -> WarmFuture + Send + Sync>;
+type FailureHook = Arc<dyn Fn(&str, &CacheError) + Send + Sync>;
+
+/// Last outcome of warming one key
+#[derive(Debug, Clone, Default)]
+pub struct WarmingStatus {
+    pub key: String,
+    pub last_success: Option<SystemTime>,
+    pub last_error: Option<String>,
+    /// Failures since the last success
+    pub consecutive_failures: u32,
+}
 
-/// Cache warmer for preloading cache
-pub struct CacheWarmer {
-    cache: MemoryCache,
-    tasks: Vec<WarmingTask>,
+/// Result of one warming pass
+#[derive(Debug, Default)]
+pub struct WarmingReport {
+    pub refreshed: Vec<String>,
+    pub failed: Vec<(String, CacheError)>,
+}
+
+impl WarmingReport {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Cache warmer for preloading and refreshing critical entries
+///
+/// Every registered key is loaded on startup and refreshed on its own
+/// interval, ahead of its TTL, so the entry never goes cold. Failed loads are
+/// logged, passed to the [`CacheWarmer::on_failure`] hook and retried sooner;
+/// the previous value stays cached until it expires.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_cache::{advanced::CacheWarmer, CacheError, MemoryCache};
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), CacheError> {
+/// let handle = CacheWarmer::new(MemoryCache::new())
+///     .warm_every("pricing", Duration::from_secs(600), Duration::from_secs(300), || async {
+///         Ok::<_, CacheError>(vec![9.99, 19.99])
+///     })
+///     .on_failure(|key, error| eprintln!("warming {} failed: {}", key, error))
+///     .spawn();
+///
+/// for status in handle.status().await {
+///     println!("{}: {:?}", status.key, status.last_success);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct CacheWarmer<C: Cache = MemoryCache> {
+    cache: Arc<C>,
+    tasks: Vec<WarmingTask<C>>,
+    status: Arc<RwLock<HashMap<String, WarmingStatus>>>,
+    on_failure: Option<FailureHook>,
 }
 
-struct WarmingTask {
+struct WarmingTask<C> {
     key: String,
-    ttl: Duration,
-    task: Box<dyn Fn() -> std::pin::Pin<Box<dyn std::future::Future<Output = CacheResult<Vec<u8>>> + Send>> + Send + Sync>,
+    refresh: Duration,
+    task: WarmFn<C>,
 }
 
-impl CacheWarmer {
+impl<C: Cache + 'static> CacheWarmer<C> {
     /// Create new cache warmer
-    pub fn new(cache: MemoryCache) -> Self {
+    pub fn new(cache: C) -> Self {
         Self {
-            cache,
+            cache: Arc::new(cache),
             tasks: Vec::new(),
+            status: Arc::new(RwLock::new(HashMap::new())),
+            on_failure: None,
         }
     }
 
-    /// Add warming task
-    pub fn warm<T, F, Fut>(mut self, key: &str, ttl: Duration, f: F) -> Self
+    /// Add warming task, refreshed after 80% of its TTL
+    pub fn warm<T, F, Fut>(self, key: &str, ttl: Duration, f: F) -> Self
     where
-        T: Serialize + Send + 'static,
+        T: Serialize + Send + Sync + 'static,
         F: Fn() -> Fut + Send + Sync + 'static,
-        Fut: std::future::Future<Output = CacheResult<T>> + Send + 'static,
+        Fut: Future<Output = CacheResult<T>> + Send + 'static,
     {
-        let key = key.to_string();
-        let task = Box::new(move || {
+        self.warm_every(key, ttl, ttl.mul_f64(0.8), f)
+    }
+
+    /// Add warming task with an explicit refresh interval
+    pub fn warm_every<T, F, Fut>(mut self, key: &str, ttl: Duration, refresh: Duration, f: F) -> Self
+    where
+        T: Serialize + Send + Sync + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = CacheResult<T>> + Send + 'static,
+    {
+        let cache_key = key.to_string();
+        let task: WarmFn<C> = Arc::new(move |cache: Arc<C>| {
             let fut = f();
+            let key = cache_key.clone();
             Box::pin(async move {
                 let value = fut.await?;
-                serde_json::to_vec(&value).map_err(|e| CacheError::Serialization(e.to_string()))
-            }) as std::pin::Pin<Box<dyn std::future::Future<Output = CacheResult<Vec<u8>>> + Send>>
+                cache.set(&key, &value, ttl).await
+            })
         });
 
-        self.tasks.push(WarmingTask { key, ttl, task });
+        self.tasks.push(WarmingTask {
+            key: key.to_string(),
+            refresh: refresh.max(Duration::from_millis(1)),
+            task,
+        });
         self
     }
 
-    /// Start warming (runs once)
+    /// Called with the key and error whenever a load fails
+    pub fn on_failure<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, &CacheError) + Send + Sync + 'static,
+    {
+        self.on_failure = Some(Arc::new(hook));
+        self
+    }
+
+    /// Warm every registered key once
+    pub async fn warm_all(&self) -> WarmingReport {
+        let mut report = WarmingReport::default();
+        for task in &self.tasks {
+            match self.run(task).await {
+                Ok(()) => report.refreshed.push(task.key.clone()),
+                Err(e) => report.failed.push((task.key.clone(), e)),
+            }
+        }
+        report
+    }
+
+    /// Start warming (runs once), failing with the first error
     pub async fn start(self) -> CacheResult<()> {
-        for task in self.tasks {
-            let data = (task.task)().await?;
-            let value: serde_json::Value = serde_json::from_slice(&data)
-                .map_err(|e| CacheError::Deserialization(e.to_string()))?;
-            self.cache.set(&task.key, &value, task.ttl).await?;
+        match self.warm_all().await.failed.into_iter().next() {
+            Some((_, e)) => Err(e),
+            None => Ok(()),
         }
-        Ok(())
+    }
+
+    /// Warm every key now, then keep refreshing them in the background
+    ///
+    /// Failed keys are retried after a quarter of their refresh interval.
+    pub fn spawn(self) -> WarmerHandle {
+        let status = self.status.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut next_run: Vec<Instant> = vec![Instant::now(); self.tasks.len()];
+
+            loop {
+                let Some(due) = next_run.iter().min().copied() else {
+                    return;
+                };
+                tokio::time::sleep_until(due.into()).await;
+
+                let now = Instant::now();
+                for (task, next) in self.tasks.iter().zip(next_run.iter_mut()) {
+                    if *next > now {
+                        continue;
+                    }
+                    let delay = match self.run(task).await {
+                        Ok(()) => task.refresh,
+                        Err(_) => task.refresh / 4,
+                    };
+                    *next = Instant::now() + delay;
+                }
+            }
+        });
+
+        WarmerHandle { status, handle }
+    }
+
+    async fn run(&self, task: &WarmingTask<C>) -> CacheResult<()> {
+        let result = (task.task)(self.cache.clone()).await;
+
+        let mut status = self.status.write().await;
+        let entry = status.entry(task.key.clone()).or_insert_with(|| WarmingStatus {
+            key: task.key.clone(),
+            ..Default::default()
+        });
+
+        match &result {
+            Ok(()) => {
+                entry.last_success = Some(SystemTime::now());
+                entry.last_error = None;
+                entry.consecutive_failures = 0;
+            }
+            Err(e) => {
+                entry.last_error = Some(e.to_string());
+                entry.consecutive_failures += 1;
+                tracing::warn!(key = %task.key, error = %e, failures = entry.consecutive_failures, "Cache warming failed");
+                if let Some(hook) = &self.on_failure {
+                    hook(&task.key, e);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Handle to a running [`CacheWarmer`]
+pub struct WarmerHandle {
+    status: Arc<RwLock<HashMap<String, WarmingStatus>>>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl WarmerHandle {
+    /// Status of every key warmed so far, sorted by key
+    pub async fn status(&self) -> Vec<WarmingStatus> {
+        let mut status: Vec<_> = self.status.read().await.values().cloned().collect();
+        status.sort_by(|a, b| a.key.cmp(&b.key));
+        status
+    }
+
+    /// Stop refreshing
+    pub fn stop(self) {
+        self.handle.abort();
     }
 }
 
@@ -174,7 +351,7 @@ mod rand {
     use std::cell::Cell;
 
     thread_local! {
-        static STATE: Cell<u64> = Cell::new(1);
+        static STATE: Cell<u64> = const { Cell::new(1) };
     }
 
     pub fn random() -> f64 {
@@ -192,6 +369,7 @@ mod rand {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
 
     #[tokio::test]
     async fn test_multi_level_cache() {
@@ -236,6 +414,42 @@ mod tests {
         assert_eq!(value2, Some("value2".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_warmer_refreshes_and_reports_failures() {
+        let cache = MemoryCache::new();
+        let loads = Arc::new(AtomicU32::new(0));
+        let failures = Arc::new(AtomicU32::new(0));
+
+        let counter = loads.clone();
+        let failed = failures.clone();
+        let handle = CacheWarmer::new(cache.clone())
+            .warm_every("pricing", Duration::from_secs(60), Duration::from_millis(20), move || {
+                let load = counter.fetch_add(1, Ordering::SeqCst);
+                async move { Ok::<_, CacheError>(load) }
+            })
+            .warm("broken", Duration::from_secs(60), || async {
+                Err::<u32, _>(CacheError::Backend("database down".to_string()))
+            })
+            .on_failure(move |_, _| {
+                failed.fetch_add(1, Ordering::SeqCst);
+            })
+            .spawn();
+
+        tokio::time::sleep(Duration::from_millis(70)).await;
+
+        assert!(loads.load(Ordering::SeqCst) >= 3);
+        let value: Option<u32> = cache.get("pricing").await.unwrap();
+        assert!(value.unwrap() >= 2);
+        assert_eq!(cache.get::<u32>("broken").await.unwrap(), None);
+        assert!(failures.load(Ordering::SeqCst) >= 1);
+
+        let status = handle.status().await;
+        assert_eq!(status[0].key, "broken");
+        assert!(status[0].last_error.is_some());
+        assert!(status[1].last_success.is_some());
+        handle.stop();
+    }
+
     #[tokio::test]
     async fn test_probabilistic_cache() {
         let cache = MemoryCache::new();
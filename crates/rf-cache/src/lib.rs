@@ -5,11 +5,17 @@
 //! ## Features
 //!
 //! - **Basic Caching**: Get, Set, Delete operations
+//! - **Batch Operations**: `get_many`, `set_many` and `delete_many` in one round trip
 //! - **Cache Tags**: Group related cache entries
 //! - **Tag Invalidation**: Flush all entries with a tag
+//! - **Namespaces**: Versioned key prefixes flushed in O(1)
 //! - **Stampede Prevention**: Prevent cache stampedes with locking
 //! - **TTL Support**: Time-to-live for cache entries
-//! - **Memory Backend**: In-memory caching for development
+//! - **Memory Backend**: In-memory caching with LRU/LFU size limits
+//! - **Serialization**: JSON by default, bincode/MessagePack and zstd compression opt-in
+//! - **Request Cache**: Per-request memoization layer for axum
+//! - **Response Cache**: GET responses cached per route, busted by tag
+//! - **Tiered Cache**: In-process L1 over Redis with cross-instance invalidation
 //!
 //! ## Quick Start
 //!
@@ -21,13 +27,13 @@
 //! let cache = MemoryCache::new();
 //!
 //! // Basic operations
-//! cache.set("key", "value", Duration::from_secs(60)).await?;
+//! cache.set("key", &"value", Duration::from_secs(60)).await?;
 //! let value: Option<String> = cache.get("key").await?;
 //! cache.delete("key").await?;
 //!
 //! // With tags
 //! cache.tags(&["users", "user:123"])
-//!     .set("user:123:profile", "data", Duration::from_secs(3600))
+//!     .set("user:123:profile", &"data", Duration::from_secs(3600))
 //!     .await?;
 //!
 //! // Invalidate by tag
@@ -53,6 +59,39 @@ use thiserror::Error;
 use tokio::sync::{Mutex, RwLock};
 
 pub mod advanced;
+mod memory;
+mod namespace;
+mod request;
+#[cfg(feature = "axum")]
+mod response;
+pub mod serializer;
+pub mod tiered;
+
+#[cfg(feature = "redis-backend")]
+mod redis;
+
+pub use memory::{EvictionPolicy, MemoryCacheConfig, MemoryCacheStats};
+use memory::Store;
+pub use namespace::Namespace;
+pub use request::RequestCache;
+#[cfg(feature = "axum")]
+pub use request::{RequestCacheLayer, RequestCacheService};
+#[cfg(feature = "axum")]
+pub use response::{ResponseCache, ResponseCacheLayer, ResponseCacheService};
+pub use serializer::{CacheSerializer, JsonSerializer};
+#[cfg(feature = "bincode")]
+pub use serializer::BincodeSerializer;
+#[cfg(feature = "zstd")]
+pub use serializer::Compressed;
+#[cfg(feature = "msgpack")]
+pub use serializer::MessagePackSerializer;
+pub use tiered::{
+    Invalidation, InvalidationBus, InvalidationMessage, LocalInvalidationBus, TierStats,
+    TieredCache, TieredCacheStats,
+};
+
+#[cfg(feature = "redis-backend")]
+pub use redis::{RedisCache, RedisInvalidationBus};
 
 /// Cache errors
 #[derive(Debug, Error)]
@@ -70,6 +109,14 @@ pub enum CacheError {
     LockFailed,
 }
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(CacheError, {
+    Self::Serialization(_) => (INTERNAL_SERVER_ERROR, "cache.serialization"),
+    Self::Deserialization(_) => (INTERNAL_SERVER_ERROR, "cache.deserialization"),
+    Self::Backend(_) => (INTERNAL_SERVER_ERROR, "cache.backend"),
+    Self::LockFailed => (SERVICE_UNAVAILABLE, "cache.lock_failed"),
+});
+
 /// Result type for cache operations
 pub type CacheResult<T> = Result<T, CacheError>;
 
@@ -87,15 +134,131 @@ pub trait Cache: Send + Sync {
         ttl: Duration,
     ) -> CacheResult<()>;
 
+    /// Set value only if the key is absent, returning whether it was set
+    ///
+    /// The memory and Redis backends do this atomically, so it can serve as
+    /// a lock; the default implementation checks and sets separately.
+    async fn add<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        if self.exists(key).await? {
+            return Ok(false);
+        }
+        self.set(key, value, ttl).await?;
+        Ok(true)
+    }
+
+    /// Replace the value of `key` if it is `current`, returning whether it
+    /// was replaced
+    ///
+    /// Values are compared in serialized form. The memory and Redis
+    /// backends do this atomically, so it can renew a lock only its holder
+    /// may renew; the default implementation compares and sets separately.
+    async fn compare_and_set<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let current =
+            serde_json::to_value(current).map_err(|e| CacheError::Serialization(e.to_string()))?;
+        if self.get::<serde_json::Value>(key).await? != Some(current) {
+            return Ok(false);
+        }
+        self.set(key, value, ttl).await?;
+        Ok(true)
+    }
+
+    /// Delete `key` if its value is `current`, returning whether it was
+    /// deleted
+    ///
+    /// Atomic where [`Cache::compare_and_set`] is, so a lock holder whose
+    /// lock expired doesn't delete the next holder's.
+    async fn compare_and_delete<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+    ) -> CacheResult<bool> {
+        let current =
+            serde_json::to_value(current).map_err(|e| CacheError::Serialization(e.to_string()))?;
+        if self.get::<serde_json::Value>(key).await? != Some(current) {
+            return Ok(false);
+        }
+        self.delete(key).await?;
+        Ok(true)
+    }
+
     /// Delete value from cache
     async fn delete(&self, key: &str) -> CacheResult<()>;
 
     /// Check if key exists
     async fn exists(&self, key: &str) -> CacheResult<bool>;
 
+    /// Time `key` has left to live, `None` if it doesn't exist
+    ///
+    /// Keys without an expiry, and every key of a backend that doesn't
+    /// report expiries, live for [`Duration::MAX`].
+    async fn ttl(&self, key: &str) -> CacheResult<Option<Duration>> {
+        Ok(self.exists(key).await?.then_some(Duration::MAX))
+    }
+
+    /// Time several keys have left to live; missing keys are left out
+    async fn ttl_many(&self, keys: &[&str]) -> CacheResult<HashMap<String, Duration>> {
+        let mut ttls = HashMap::with_capacity(keys.len());
+        for key in keys {
+            if let Some(ttl) = self.ttl(key).await? {
+                ttls.insert(key.to_string(), ttl);
+            }
+        }
+        Ok(ttls)
+    }
+
     /// Clear all cache entries
     async fn flush(&self) -> CacheResult<()>;
 
+    /// Get several values at once; only hits are included in the result
+    async fn get_many<T: DeserializeOwned + Send>(
+        &self,
+        keys: &[&str],
+    ) -> CacheResult<HashMap<String, T>> {
+        let mut values = HashMap::with_capacity(keys.len());
+        for key in keys {
+            if let Some(value) = self.get(key).await? {
+                values.insert(key.to_string(), value);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Set several values with the same TTL
+    async fn set_many<T: Serialize + Sync>(
+        &self,
+        items: &[(&str, T)],
+        ttl: Duration,
+    ) -> CacheResult<()> {
+        for (key, value) in items {
+            self.set(key, value, ttl).await?;
+        }
+        Ok(())
+    }
+
+    /// Delete several values
+    async fn delete_many(&self, keys: &[&str]) -> CacheResult<()> {
+        for key in keys {
+            self.delete(key).await?;
+        }
+        Ok(())
+    }
+
+    /// Scope keys to a namespace that can be flushed on its own
+    fn namespace(&self, name: &str) -> Namespace<'_, Self> {
+        Namespace::new(self, name)
+    }
+
     /// Get or set (remember pattern)
     async fn remember<T, F, Fut>(
         &self,
@@ -118,46 +281,101 @@ pub trait Cache: Send + Sync {
     }
 }
 
-/// Cache entry with TTL
-#[derive(Clone)]
-struct CacheEntry {
-    data: Vec<u8>,
-    expires_at: std::time::Instant,
+/// In-memory cache implementation
+///
+/// Unbounded by default; use [`MemoryCache::with_config`] to cap entries or
+/// bytes with LRU/LFU eviction, and [`MemoryCache::spawn_sweeper`] to remove
+/// expired entries that are never read again.
+///
+/// Values are stored as JSON unless another [`CacheSerializer`] is chosen
+/// with [`MemoryCache::with_serializer`].
+pub struct MemoryCache<S: CacheSerializer = JsonSerializer> {
+    store: Arc<RwLock<Store>>,
+    tags: Arc<RwLock<HashMap<String, HashSet<String>>>>,
+    locks: Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>,
+    serializer: Arc<S>,
 }
 
-impl CacheEntry {
-    fn new(data: Vec<u8>, ttl: Duration) -> Self {
+impl<S: CacheSerializer> Clone for MemoryCache<S> {
+    fn clone(&self) -> Self {
         Self {
-            data,
-            expires_at: std::time::Instant::now() + ttl,
+            store: self.store.clone(),
+            tags: self.tags.clone(),
+            locks: self.locks.clone(),
+            serializer: self.serializer.clone(),
         }
     }
-
-    fn is_expired(&self) -> bool {
-        std::time::Instant::now() > self.expires_at
-    }
-}
-
-/// In-memory cache implementation
-#[derive(Clone)]
-pub struct MemoryCache {
-    entries: Arc<RwLock<HashMap<String, CacheEntry>>>,
-    tags: Arc<RwLock<HashMap<String, HashSet<String>>>>,
-    locks: Arc<Mutex<HashMap<String, Arc<Mutex<()>>>>>,
 }
 
 impl MemoryCache {
-    /// Create new memory cache
+    /// Create new unbounded memory cache
     pub fn new() -> Self {
+        Self::with_config(MemoryCacheConfig::default())
+    }
+
+    /// Create new memory cache with size limits
+    pub fn with_config(config: MemoryCacheConfig) -> Self {
         Self {
-            entries: Arc::new(RwLock::new(HashMap::new())),
+            store: Arc::new(RwLock::new(Store::new(config))),
             tags: Arc::new(RwLock::new(HashMap::new())),
             locks: Arc::new(Mutex::new(HashMap::new())),
+            serializer: Arc::new(JsonSerializer),
         }
     }
+}
+
+impl<S: CacheSerializer> MemoryCache<S> {
+    /// Use a different value serializer
+    ///
+    /// Entries are not converted, so choose the serializer before storing
+    /// anything.
+    ///
+    /// ```
+    /// use rf_cache::{JsonSerializer, MemoryCache, MemoryCacheConfig};
+    ///
+    /// let cache = MemoryCache::with_config(MemoryCacheConfig::default().max_entries(1000))
+    ///     .with_serializer(JsonSerializer);
+    /// ```
+    pub fn with_serializer<S2: CacheSerializer>(self, serializer: S2) -> MemoryCache<S2> {
+        MemoryCache {
+            store: self.store,
+            tags: self.tags,
+            locks: self.locks,
+            serializer: Arc::new(serializer),
+        }
+    }
+
+    /// Entry count, size and hit/eviction statistics
+    pub async fn stats(&self) -> MemoryCacheStats {
+        self.store.read().await.stats()
+    }
+
+    /// Remove expired entries now; returns how many were removed
+    pub async fn purge_expired(&self) -> usize {
+        self.store.write().await.purge_expired()
+    }
+
+    /// Remove expired entries every `interval` until the cache is dropped
+    pub fn spawn_sweeper(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let store = Arc::downgrade(&self.store);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let Some(store) = store.upgrade() else {
+                    return;
+                };
+                let purged = store.write().await.purge_expired();
+                if purged > 0 {
+                    tracing::debug!(purged, "Swept expired cache entries");
+                }
+            }
+        })
+    }
 
     /// Create tagged cache
-    pub fn tags(&self, tags: &[&str]) -> TaggedCache {
+    pub fn tags(&self, tags: &[&str]) -> TaggedCache<S> {
         TaggedCache {
             cache: self.clone(),
             tags: tags.iter().map(|s| s.to_string()).collect(),
@@ -236,23 +454,11 @@ impl Default for MemoryCache {
 }
 
 #[async_trait]
-impl Cache for MemoryCache {
+impl<S: CacheSerializer> Cache for MemoryCache<S> {
     async fn get<T: DeserializeOwned + Send>(&self, key: &str) -> CacheResult<Option<T>> {
-        let entries = self.entries.read().await;
-
-        if let Some(entry) = entries.get(key) {
-            if entry.is_expired() {
-                drop(entries);
-                self.delete(key).await?;
-                return Ok(None);
-            }
+        let data = self.store.write().await.get(key);
 
-            let value = serde_json::from_slice(&entry.data)
-                .map_err(|e| CacheError::Deserialization(e.to_string()))?;
-            Ok(Some(value))
-        } else {
-            Ok(None)
-        }
+        data.map(|data| self.serializer.deserialize(&data)).transpose()
     }
 
     async fn set<T: Serialize + Sync>(
@@ -261,48 +467,144 @@ impl Cache for MemoryCache {
         value: &T,
         ttl: Duration,
     ) -> CacheResult<()> {
-        let data =
-            serde_json::to_vec(value).map_err(|e| CacheError::Serialization(e.to_string()))?;
-
-        let entry = CacheEntry::new(data, ttl);
+        let data = self.serializer.serialize(value)?;
 
-        let mut entries = self.entries.write().await;
-        entries.insert(key.to_string(), entry);
+        self.store.write().await.insert(key, data, ttl);
 
         Ok(())
     }
 
+    async fn add<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let data = self.serializer.serialize(value)?;
+
+        let mut store = self.store.write().await;
+        if store.contains(key) {
+            return Ok(false);
+        }
+        store.insert(key, data, ttl);
+        Ok(true)
+    }
+
+    async fn compare_and_set<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let current = self.serializer.serialize(current)?;
+        let data = self.serializer.serialize(value)?;
+
+        let mut store = self.store.write().await;
+        if !store.holds(key, &current) {
+            return Ok(false);
+        }
+        store.insert(key, data, ttl);
+        Ok(true)
+    }
+
+    async fn compare_and_delete<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+    ) -> CacheResult<bool> {
+        let current = self.serializer.serialize(current)?;
+
+        let mut store = self.store.write().await;
+        if !store.holds(key, &current) {
+            return Ok(false);
+        }
+        store.remove(key);
+        Ok(true)
+    }
+
     async fn delete(&self, key: &str) -> CacheResult<()> {
-        let mut entries = self.entries.write().await;
-        entries.remove(key);
+        self.store.write().await.remove(key);
         Ok(())
     }
 
     async fn exists(&self, key: &str) -> CacheResult<bool> {
-        let entries = self.entries.read().await;
-        if let Some(entry) = entries.get(key) {
-            Ok(!entry.is_expired())
-        } else {
-            Ok(false)
-        }
+        Ok(self.store.read().await.contains(key))
+    }
+
+    async fn ttl(&self, key: &str) -> CacheResult<Option<Duration>> {
+        Ok(self.store.read().await.remaining(key))
+    }
+
+    async fn ttl_many(&self, keys: &[&str]) -> CacheResult<HashMap<String, Duration>> {
+        let store = self.store.read().await;
+        Ok(keys
+            .iter()
+            .filter_map(|key| store.remaining(key).map(|ttl| (key.to_string(), ttl)))
+            .collect())
     }
 
     async fn flush(&self) -> CacheResult<()> {
-        let mut entries = self.entries.write().await;
-        entries.clear();
+        self.store.write().await.clear();
         let mut tags = self.tags.write().await;
         tags.clear();
         Ok(())
     }
+
+    async fn get_many<T: DeserializeOwned + Send>(
+        &self,
+        keys: &[&str],
+    ) -> CacheResult<HashMap<String, T>> {
+        let found: Vec<(&str, Vec<u8>)> = {
+            let mut store = self.store.write().await;
+            keys.iter()
+                .filter_map(|key| store.get(key).map(|data| (*key, data)))
+                .collect()
+        };
+
+        found
+            .into_iter()
+            .map(|(key, data)| {
+                self.serializer
+                    .deserialize(&data)
+                    .map(|value| (key.to_string(), value))
+            })
+            .collect()
+    }
+
+    async fn set_many<T: Serialize + Sync>(
+        &self,
+        items: &[(&str, T)],
+        ttl: Duration,
+    ) -> CacheResult<()> {
+        let serialized = items
+            .iter()
+            .map(|(key, value)| self.serializer.serialize(value).map(|data| (*key, data)))
+            .collect::<CacheResult<Vec<_>>>()?;
+
+        let mut store = self.store.write().await;
+        for (key, data) in serialized {
+            store.insert(key, data, ttl);
+        }
+        Ok(())
+    }
+
+    async fn delete_many(&self, keys: &[&str]) -> CacheResult<()> {
+        let mut store = self.store.write().await;
+        for key in keys {
+            store.remove(key);
+        }
+        Ok(())
+    }
 }
 
 /// Tagged cache
-pub struct TaggedCache {
-    cache: MemoryCache,
+pub struct TaggedCache<S: CacheSerializer = JsonSerializer> {
+    cache: MemoryCache<S>,
     tags: Vec<String>,
 }
 
-impl TaggedCache {
+impl<S: CacheSerializer> TaggedCache<S> {
     /// Set value with tags
     pub async fn set<T: Serialize + Sync>(
         &self,
@@ -334,6 +636,22 @@ impl TaggedCache {
     }
 }
 
+/// Process-unique identifier (pid, clock and counter)
+pub(crate) fn unique_id() -> String {
+    static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!(
+        "{}-{}-{}",
+        std::process::id(),
+        nanos,
+        NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -390,6 +708,36 @@ mod tests {
         assert!(!cache.exists("key").await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_add() {
+        let cache = MemoryCache::new();
+
+        assert!(cache.add("lock", &"a", Duration::from_millis(50)).await.unwrap());
+        assert!(!cache.add("lock", &"b", Duration::from_secs(60)).await.unwrap());
+        assert_eq!(cache.get::<String>("lock").await.unwrap().unwrap(), "a");
+
+        // Expired keys count as absent
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(cache.add("lock", &"b", Duration::from_secs(60)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_set() {
+        let cache = MemoryCache::new();
+        let ttl = Duration::from_secs(60);
+
+        assert!(!cache.compare_and_set("lock", &"a", &"a", ttl).await.unwrap());
+        cache.set("lock", &"a", ttl).await.unwrap();
+        assert!(!cache.compare_and_set("lock", &"b", &"b", ttl).await.unwrap());
+        assert!(cache.compare_and_set("lock", &"a", &"b", ttl).await.unwrap());
+        assert_eq!(cache.get::<String>("lock").await.unwrap().unwrap(), "b");
+
+        assert!(!cache.compare_and_delete("lock", &"a").await.unwrap());
+        assert!(cache.exists("lock").await.unwrap());
+        assert!(cache.compare_and_delete("lock", &"b").await.unwrap());
+        assert!(!cache.exists("lock").await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_flush() {
         let cache = MemoryCache::new();
@@ -409,6 +757,25 @@ mod tests {
         assert!(!cache.exists("key2").await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_batch_operations() {
+        let cache = MemoryCache::new();
+
+        cache
+            .set_many(&[("a", 1), ("b", 2), ("c", 3)], Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        let values: HashMap<String, i32> = cache.get_many(&["a", "c", "missing"]).await.unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values["a"], 1);
+        assert_eq!(values["c"], 3);
+
+        cache.delete_many(&["a", "b"]).await.unwrap();
+        let values: HashMap<String, i32> = cache.get_many(&["a", "b", "c"]).await.unwrap();
+        assert_eq!(values.keys().collect::<Vec<_>>(), vec!["c"]);
+    }
+
     #[tokio::test]
     async fn test_remember() {
         let cache = MemoryCache::new();
@@ -459,6 +826,39 @@ mod tests {
         assert_eq!(value, None);
     }
 
+    #[tokio::test]
+    async fn test_bounded_cache_stats() {
+        let cache = MemoryCache::with_config(MemoryCacheConfig::default().max_entries(2));
+
+        for key in ["a", "b", "c"] {
+            cache.set(key, &key, Duration::from_secs(60)).await.unwrap();
+        }
+        let _: Option<String> = cache.get("c").await.unwrap();
+        let _: Option<String> = cache.get("a").await.unwrap();
+
+        let stats = cache.stats().await;
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.hit_rate(), 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_sweeper_removes_expired() {
+        let cache = MemoryCache::new();
+        cache
+            .set("key", &"value", Duration::from_millis(10))
+            .await
+            .unwrap();
+
+        let sweeper = cache.spawn_sweeper(Duration::from_millis(20));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        sweeper.abort();
+
+        let stats = cache.stats().await;
+        assert_eq!(stats.entries, 0);
+        assert_eq!(stats.bytes, 0);
+    }
+
     #[tokio::test]
     async fn test_remember_with_lock() {
         let cache = MemoryCache::new();
@@ -0,0 +1,353 @@
+//! Bounded storage behind [`crate::MemoryCache`]
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+
+/// Which entry to drop when the cache is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Least recently used
+    #[default]
+    Lru,
+    /// Least frequently used, ties broken by recency
+    Lfu,
+}
+
+/// Limits of a [`crate::MemoryCache`]
+///
+/// # Example
+///
+/// ```
+/// use rf_cache::{EvictionPolicy, MemoryCache, MemoryCacheConfig};
+///
+/// let cache = MemoryCache::with_config(
+///     MemoryCacheConfig::default()
+///         .max_entries(10_000)
+///         .max_bytes(64 * 1024 * 1024)
+///         .eviction(EvictionPolicy::Lfu),
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryCacheConfig {
+    /// Maximum number of entries (unbounded if `None`)
+    pub max_entries: Option<usize>,
+    /// Maximum size of keys and serialized values (unbounded if `None`)
+    pub max_bytes: Option<usize>,
+    pub eviction: EvictionPolicy,
+}
+
+impl MemoryCacheConfig {
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn eviction(mut self, eviction: EvictionPolicy) -> Self {
+        self.eviction = eviction;
+        self
+    }
+}
+
+/// Snapshot of [`crate::MemoryCache`] statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MemoryCacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped to stay within the configured limits
+    pub evictions: u64,
+    /// Expired entries removed on read or by the sweeper
+    pub expired: u64,
+}
+
+impl MemoryCacheStats {
+    /// Fraction of reads that found a live entry
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// Cache entry with TTL
+#[derive(Clone)]
+pub(crate) struct CacheEntry {
+    data: Vec<u8>,
+    expires_at: Instant,
+    tick: u64,
+    frequency: u64,
+}
+
+impl CacheEntry {
+    fn is_expired(&self) -> bool {
+        Instant::now() > self.expires_at
+    }
+
+    fn size(&self, key: &str) -> usize {
+        key.len() + self.data.len()
+    }
+}
+
+/// Entries plus an eviction index ordered by policy rank
+pub(crate) struct Store {
+    config: MemoryCacheConfig,
+    entries: HashMap<String, CacheEntry>,
+    order: BTreeMap<(u64, u64), String>,
+    bytes: usize,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    expired: u64,
+}
+
+impl Store {
+    pub(crate) fn new(config: MemoryCacheConfig) -> Self {
+        Self {
+            config,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            bytes: 0,
+            tick: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+            expired: 0,
+        }
+    }
+
+    fn rank(&self, entry: &CacheEntry) -> (u64, u64) {
+        match self.config.eviction {
+            EvictionPolicy::Lru => (0, entry.tick),
+            EvictionPolicy::Lfu => (entry.frequency, entry.tick),
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// Whether a live entry exists, without touching recency or stats
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        self.entries.get(key).is_some_and(|entry| !entry.is_expired())
+    }
+
+    /// Time a live entry has left, without touching recency or stats
+    pub(crate) fn remaining(&self, key: &str) -> Option<Duration> {
+        self.entries
+            .get(key)
+            .filter(|entry| !entry.is_expired())
+            .map(|entry| entry.expires_at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether a live entry holds exactly `data`, without touching recency
+    /// or stats
+    pub(crate) fn holds(&self, key: &str, data: &[u8]) -> bool {
+        self.entries
+            .get(key)
+            .is_some_and(|entry| !entry.is_expired() && entry.data == data)
+    }
+
+    /// Read an entry, recording the hit or miss and its use
+    pub(crate) fn get(&mut self, key: &str) -> Option<Vec<u8>> {
+        match self.entries.get(key) {
+            None => {
+                self.misses += 1;
+                return None;
+            }
+            Some(entry) if entry.is_expired() => {
+                self.remove(key);
+                self.expired += 1;
+                self.misses += 1;
+                return None;
+            }
+            Some(_) => {}
+        }
+
+        let tick = self.next_tick();
+        let mut entry = self.entries.remove(key)?;
+        self.order.remove(&self.rank(&entry));
+        entry.tick = tick;
+        entry.frequency += 1;
+        self.order.insert(self.rank(&entry), key.to_string());
+
+        let data = entry.data.clone();
+        self.entries.insert(key.to_string(), entry);
+        self.hits += 1;
+        Some(data)
+    }
+
+    /// Insert an entry and evict until within limits
+    ///
+    /// Values larger than `max_bytes` on their own are not stored.
+    pub(crate) fn insert(&mut self, key: &str, data: Vec<u8>, ttl: Duration) {
+        self.remove(key);
+
+        let tick = self.next_tick();
+        let entry = CacheEntry {
+            data,
+            expires_at: Instant::now() + ttl,
+            tick,
+            frequency: 1,
+        };
+
+        let size = entry.size(key);
+        if self.config.max_bytes.is_some_and(|max| size > max) {
+            return;
+        }
+
+        self.bytes += size;
+        self.order.insert(self.rank(&entry), key.to_string());
+        self.entries.insert(key.to_string(), entry);
+        self.evict(key);
+    }
+
+    fn over_limit(&self) -> bool {
+        self.config.max_entries.is_some_and(|max| self.entries.len() > max)
+            || self.config.max_bytes.is_some_and(|max| self.bytes > max)
+    }
+
+    /// Evict by rank, sparing the entry just written so a fresh LFU entry
+    /// isn't dropped before it had a chance to be read
+    fn evict(&mut self, protected: &str) {
+        while self.over_limit() {
+            let victim = self
+                .order
+                .values()
+                .find(|key| key.as_str() != protected)
+                .cloned();
+            let Some(key) = victim else {
+                break;
+            };
+            self.remove(&key);
+            self.evictions += 1;
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&self.rank(&entry));
+            self.bytes -= entry.size(key);
+        }
+    }
+
+    /// Remove every expired entry; returns how many were removed
+    pub(crate) fn purge_expired(&mut self) -> usize {
+        let expired: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.is_expired())
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in &expired {
+            self.remove(key);
+        }
+        self.expired += expired.len() as u64;
+        expired.len()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.bytes = 0;
+    }
+
+    pub(crate) fn stats(&self) -> MemoryCacheStats {
+        MemoryCacheStats {
+            entries: self.entries.len(),
+            bytes: self.bytes,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            expired: self.expired,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(config: MemoryCacheConfig) -> Store {
+        Store::new(config)
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut store = store(MemoryCacheConfig::default().max_entries(2));
+        let ttl = Duration::from_secs(60);
+
+        store.insert("a", vec![1], ttl);
+        store.insert("b", vec![2], ttl);
+        store.get("a");
+        store.insert("c", vec![3], ttl);
+
+        assert!(store.contains("a"));
+        assert!(!store.contains("b"));
+        assert_eq!(store.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_lfu_eviction() {
+        let mut store = store(
+            MemoryCacheConfig::default()
+                .max_entries(2)
+                .eviction(EvictionPolicy::Lfu),
+        );
+        let ttl = Duration::from_secs(60);
+
+        store.insert("a", vec![1], ttl);
+        store.insert("b", vec![2], ttl);
+        store.get("a");
+        store.get("a");
+        store.get("b");
+        store.insert("c", vec![3], ttl);
+
+        assert!(store.contains("a"));
+        assert!(!store.contains("b"));
+        assert!(store.contains("c"));
+    }
+
+    #[test]
+    fn test_byte_limit() {
+        let mut store = store(MemoryCacheConfig::default().max_bytes(10));
+        let ttl = Duration::from_secs(60);
+
+        store.insert("a", vec![0; 4], ttl);
+        store.insert("b", vec![0; 4], ttl);
+        assert_eq!(store.stats().bytes, 10);
+
+        store.insert("c", vec![0; 4], ttl);
+        assert_eq!(store.stats().entries, 2);
+        assert_eq!(store.stats().bytes, 10);
+
+        // Too large to ever fit
+        store.insert("d", vec![0; 32], ttl);
+        assert!(!store.contains("d"));
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let mut store = store(MemoryCacheConfig::default());
+        store.insert("a", vec![1], Duration::ZERO);
+        store.insert("b", vec![1], Duration::from_secs(60));
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(store.purge_expired(), 1);
+        assert_eq!(store.stats().entries, 1);
+        assert_eq!(store.stats().expired, 1);
+    }
+}
@@ -0,0 +1,325 @@
+//! Versioned key namespaces
+//!
+//! Keys in a namespace are stored as `{namespace}:{version}:{key}`. Flushing a
+//! namespace replaces its version token, which orphans every existing entry in
+//! O(1) instead of scanning keys; the orphans simply expire with their TTL.
+
+use crate::{Cache, CacheResult};
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::HashMap, time::Duration};
+
+/// How long a namespace version token is kept
+const VERSION_TTL: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// View of a cache scoped to one namespace
+///
+/// Created with [`Cache::namespace`].
+///
+/// # Example
+///
+/// ```
+/// use rf_cache::{Cache, MemoryCache};
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), rf_cache::CacheError> {
+/// let cache = MemoryCache::new();
+/// let tenant = cache.namespace("tenant:42");
+///
+/// tenant.set("settings", &"dark", Duration::from_secs(3600)).await?;
+///
+/// // Invalidates everything cached for tenant 42, leaving other tenants alone
+/// tenant.flush().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Namespace<'a, C: Cache + ?Sized> {
+    cache: &'a C,
+    name: String,
+}
+
+impl<'a, C: Cache + ?Sized> Namespace<'a, C> {
+    pub(crate) fn new(cache: &'a C, name: &str) -> Self {
+        Self {
+            cache,
+            name: name.to_string(),
+        }
+    }
+
+    /// Namespace name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version_key(&self) -> String {
+        format!("__ns:{}:version", self.name)
+    }
+
+    /// Current version token, created on first use
+    ///
+    /// A missing token (never set, evicted or expired) is replaced with a new
+    /// one, so entries written under a lost token can never resurface. The
+    /// token is created with [`Cache::add`], so callers racing to create it
+    /// all end up with the one that was stored.
+    pub async fn version(&self) -> CacheResult<String> {
+        let version_key = self.version_key();
+        loop {
+            if let Some(version) = self.cache.get::<String>(&version_key).await? {
+                return Ok(version);
+            }
+            let version = crate::unique_id();
+            if self.cache.add(&version_key, &version, VERSION_TTL).await? {
+                return Ok(version);
+            }
+        }
+    }
+
+    async fn bump(&self) -> CacheResult<String> {
+        let version = crate::unique_id();
+        self.cache
+            .set(&self.version_key(), &version, VERSION_TTL)
+            .await?;
+        Ok(version)
+    }
+
+    fn key(&self, version: &str, key: &str) -> String {
+        format!("{}:{}:{}", self.name, version, key)
+    }
+
+    async fn keys(&self, keys: &[&str]) -> CacheResult<Vec<String>> {
+        let version = self.version().await?;
+        Ok(keys.iter().map(|key| self.key(&version, key)).collect())
+    }
+}
+
+#[async_trait]
+impl<C: Cache + ?Sized> Cache for Namespace<'_, C> {
+    async fn get<T: DeserializeOwned + Send>(&self, key: &str) -> CacheResult<Option<T>> {
+        let version = self.version().await?;
+        self.cache.get(&self.key(&version, key)).await
+    }
+
+    async fn set<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<()> {
+        let version = self.version().await?;
+        self.cache.set(&self.key(&version, key), value, ttl).await
+    }
+
+    async fn add<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let version = self.version().await?;
+        self.cache.add(&self.key(&version, key), value, ttl).await
+    }
+
+    async fn compare_and_set<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let version = self.version().await?;
+        let key = self.key(&version, key);
+        self.cache.compare_and_set(&key, current, value, ttl).await
+    }
+
+    async fn compare_and_delete<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+    ) -> CacheResult<bool> {
+        let version = self.version().await?;
+        let key = self.key(&version, key);
+        self.cache.compare_and_delete(&key, current).await
+    }
+
+    async fn delete(&self, key: &str) -> CacheResult<()> {
+        let version = self.version().await?;
+        self.cache.delete(&self.key(&version, key)).await
+    }
+
+    async fn exists(&self, key: &str) -> CacheResult<bool> {
+        let version = self.version().await?;
+        self.cache.exists(&self.key(&version, key)).await
+    }
+
+    async fn ttl(&self, key: &str) -> CacheResult<Option<Duration>> {
+        let version = self.version().await?;
+        self.cache.ttl(&self.key(&version, key)).await
+    }
+
+    async fn ttl_many(&self, keys: &[&str]) -> CacheResult<HashMap<String, Duration>> {
+        let scoped = self.keys(keys).await?;
+        let refs: Vec<&str> = scoped.iter().map(String::as_str).collect();
+        let mut found = self.cache.ttl_many(&refs).await?;
+
+        Ok(keys
+            .iter()
+            .zip(&scoped)
+            .filter_map(|(key, scoped)| found.remove(scoped).map(|ttl| (key.to_string(), ttl)))
+            .collect())
+    }
+
+    /// Invalidate the whole namespace by switching to a new version
+    async fn flush(&self) -> CacheResult<()> {
+        self.bump().await?;
+        Ok(())
+    }
+
+    async fn get_many<T: DeserializeOwned + Send>(
+        &self,
+        keys: &[&str],
+    ) -> CacheResult<HashMap<String, T>> {
+        let scoped = self.keys(keys).await?;
+        let refs: Vec<&str> = scoped.iter().map(String::as_str).collect();
+        let mut found = self.cache.get_many(&refs).await?;
+
+        Ok(keys
+            .iter()
+            .zip(&scoped)
+            .filter_map(|(key, scoped)| found.remove(scoped).map(|value| (key.to_string(), value)))
+            .collect())
+    }
+
+    async fn set_many<T: Serialize + Sync>(
+        &self,
+        items: &[(&str, T)],
+        ttl: Duration,
+    ) -> CacheResult<()> {
+        let version = self.version().await?;
+        let scoped: Vec<String> = items.iter().map(|(key, _)| self.key(&version, key)).collect();
+        let items: Vec<(&str, &T)> = scoped
+            .iter()
+            .zip(items)
+            .map(|(key, (_, value))| (key.as_str(), value))
+            .collect();
+        self.cache.set_many(&items, ttl).await
+    }
+
+    async fn delete_many(&self, keys: &[&str]) -> CacheResult<()> {
+        let scoped = self.keys(keys).await?;
+        let refs: Vec<&str> = scoped.iter().map(String::as_str).collect();
+        self.cache.delete_many(&refs).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryCache;
+
+    /// Cache with latency on reads, so concurrent first uses all miss
+    struct SlowReads(MemoryCache);
+
+    #[async_trait]
+    impl Cache for SlowReads {
+        async fn get<T: DeserializeOwned + Send>(&self, key: &str) -> CacheResult<Option<T>> {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            self.0.get(key).await
+        }
+
+        async fn set<T: Serialize + Sync>(
+            &self,
+            key: &str,
+            value: &T,
+            ttl: Duration,
+        ) -> CacheResult<()> {
+            self.0.set(key, value, ttl).await
+        }
+
+        async fn add<T: Serialize + Sync>(
+            &self,
+            key: &str,
+            value: &T,
+            ttl: Duration,
+        ) -> CacheResult<bool> {
+            self.0.add(key, value, ttl).await
+        }
+
+        async fn delete(&self, key: &str) -> CacheResult<()> {
+            self.0.delete(key).await
+        }
+
+        async fn exists(&self, key: &str) -> CacheResult<bool> {
+            self.0.exists(key).await
+        }
+
+        async fn flush(&self) -> CacheResult<()> {
+            self.0.flush().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_namespace_flush_is_isolated() {
+        let cache = MemoryCache::new();
+        let ttl = Duration::from_secs(60);
+
+        cache.namespace("tenant:1").set("key", &1, ttl).await.unwrap();
+        cache.namespace("tenant:2").set("key", &2, ttl).await.unwrap();
+        cache.set("key", &0, ttl).await.unwrap();
+
+        cache.namespace("tenant:1").flush().await.unwrap();
+
+        assert_eq!(cache.namespace("tenant:1").get::<i32>("key").await.unwrap(), None);
+        assert_eq!(cache.namespace("tenant:2").get::<i32>("key").await.unwrap(), Some(2));
+        assert_eq!(cache.get::<i32>("key").await.unwrap(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_namespace_batch_operations() {
+        let cache = MemoryCache::new();
+        let tenant = cache.namespace("tenant:1");
+
+        tenant
+            .set_many(&[("a", 1), ("b", 2)], Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        let values: HashMap<String, i32> = tenant.get_many(&["a", "b", "c"]).await.unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values["b"], 2);
+
+        tenant.delete_many(&["a"]).await.unwrap();
+        assert!(!tenant.exists("a").await.unwrap());
+        assert!(tenant.exists("b").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_namespace_compare_and_set() {
+        let cache = MemoryCache::new();
+        let tenant = cache.namespace("tenant:1");
+        let ttl = Duration::from_secs(60);
+
+        tenant.set("lock", &"a", ttl).await.unwrap();
+        cache.set("lock", &"b", ttl).await.unwrap();
+
+        assert!(!tenant.compare_and_set("lock", &"b", &"c", ttl).await.unwrap());
+        assert!(tenant.compare_and_set("lock", &"a", &"c", ttl).await.unwrap());
+        assert_eq!(tenant.get::<String>("lock").await.unwrap().unwrap(), "c");
+        assert_eq!(cache.get::<String>("lock").await.unwrap().unwrap(), "b");
+
+        assert!(tenant.compare_and_delete("lock", &"c").await.unwrap());
+        assert!(!tenant.exists("lock").await.unwrap());
+        assert!(cache.exists("lock").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_first_use_agrees_on_version() {
+        let cache = SlowReads(MemoryCache::new());
+        let (a, b) = (cache.namespace("tenant:1"), cache.namespace("tenant:1"));
+
+        let (first, second) = tokio::join!(a.version(), b.version());
+        assert_eq!(first.unwrap(), second.unwrap());
+
+        a.set("key", &1, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(b.get::<i32>("key").await.unwrap(), Some(1));
+    }
+}
@@ -0,0 +1,495 @@
+//! Redis cache backend and invalidation bus
+
+use crate::{
+    tiered::{InvalidationBus, InvalidationMessage},
+    Cache, CacheError, CacheResult, CacheSerializer, JsonSerializer,
+};
+use async_trait::async_trait;
+use deadpool_redis::{Config, Pool, Runtime};
+use futures::StreamExt;
+use redis::AsyncCommands;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::sync::broadcast;
+
+/// `SET key value PX ttl` if `key` holds the expected value
+const COMPARE_AND_SET: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    redis.call("SET", KEYS[1], ARGV[2], "PX", ARGV[3])
+    return 1
+end
+return 0
+"#;
+
+/// `DEL key` if `key` holds the expected value
+const COMPARE_AND_DELETE: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+/// Redis-backed cache
+///
+/// Values are stored under `{prefix}:{key}` with a Redis expiry, as JSON
+/// unless another serializer is chosen with [`RedisCache::with_serializer`].
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_cache::{Cache, RedisCache};
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), rf_cache::CacheError> {
+/// let cache = RedisCache::new("redis://localhost").await?;
+/// cache.set("key", &"value", Duration::from_secs(60)).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RedisCache<S: CacheSerializer = JsonSerializer> {
+    pool: Pool,
+    prefix: String,
+    serializer: Arc<S>,
+}
+
+impl<S: CacheSerializer> Clone for RedisCache<S> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            prefix: self.prefix.clone(),
+            serializer: self.serializer.clone(),
+        }
+    }
+}
+
+impl RedisCache {
+    /// Create new Redis cache with the default `cache` prefix
+    ///
+    /// # Arguments
+    ///
+    /// * `redis_url` - Redis connection URL (e.g., "redis://localhost:6379")
+    pub async fn new(redis_url: &str) -> CacheResult<Self> {
+        Self::with_prefix(redis_url, "cache").await
+    }
+
+    /// Create new Redis cache with a custom key prefix
+    pub async fn with_prefix(redis_url: &str, prefix: &str) -> CacheResult<Self> {
+        let pool = connect(redis_url).await?;
+        Ok(Self {
+            pool,
+            prefix: prefix.to_string(),
+            serializer: Arc::new(JsonSerializer),
+        })
+    }
+}
+
+impl<S: CacheSerializer> RedisCache<S> {
+    /// Use a different value serializer
+    ///
+    /// Existing entries are not converted; use a new prefix when switching.
+    pub fn with_serializer<S2: CacheSerializer>(self, serializer: S2) -> RedisCache<S2> {
+        RedisCache {
+            pool: self.pool,
+            prefix: self.prefix,
+            serializer: Arc::new(serializer),
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}:{}", self.prefix, key)
+    }
+
+    async fn conn(&self) -> CacheResult<deadpool_redis::Connection> {
+        self.pool.get().await.map_err(backend_error)
+    }
+}
+
+#[async_trait]
+impl<S: CacheSerializer> Cache for RedisCache<S> {
+    async fn get<T: DeserializeOwned + Send>(&self, key: &str) -> CacheResult<Option<T>> {
+        let mut conn = self.conn().await?;
+        let raw: Option<Vec<u8>> = conn.get(self.key(key)).await.map_err(backend_error)?;
+
+        raw.map(|data| self.serializer.deserialize(&data)).transpose()
+    }
+
+    async fn set<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<()> {
+        let data = self.serializer.serialize(value)?;
+
+        let mut conn = self.conn().await?;
+        let _: () = conn
+            .pset_ex(self.key(key), data, (ttl.as_millis() as u64).max(1))
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn add<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let data = self.serializer.serialize(value)?;
+
+        let mut conn = self.conn().await?;
+        let reply: Option<String> = redis::cmd("SET")
+            .arg(self.key(key))
+            .arg(data)
+            .arg("NX")
+            .arg("PX")
+            .arg((ttl.as_millis() as u64).max(1))
+            .query_async(&mut conn)
+            .await
+            .map_err(backend_error)?;
+        Ok(reply.is_some())
+    }
+
+    async fn compare_and_set<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let current = self.serializer.serialize(current)?;
+        let data = self.serializer.serialize(value)?;
+
+        let mut conn = self.conn().await?;
+        let replaced: i64 = redis::Script::new(COMPARE_AND_SET)
+            .key(self.key(key))
+            .arg(current)
+            .arg(data)
+            .arg((ttl.as_millis() as u64).max(1))
+            .invoke_async(&mut conn)
+            .await
+            .map_err(backend_error)?;
+        Ok(replaced == 1)
+    }
+
+    async fn compare_and_delete<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+    ) -> CacheResult<bool> {
+        let current = self.serializer.serialize(current)?;
+
+        let mut conn = self.conn().await?;
+        let deleted: i64 = redis::Script::new(COMPARE_AND_DELETE)
+            .key(self.key(key))
+            .arg(current)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(backend_error)?;
+        Ok(deleted == 1)
+    }
+
+    async fn delete(&self, key: &str) -> CacheResult<()> {
+        let mut conn = self.conn().await?;
+        let _: () = conn.del(self.key(key)).await.map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> CacheResult<bool> {
+        let mut conn = self.conn().await?;
+        conn.exists(self.key(key)).await.map_err(backend_error)
+    }
+
+    async fn ttl(&self, key: &str) -> CacheResult<Option<Duration>> {
+        let mut conn = self.conn().await?;
+        let ttl: i64 = conn.pttl(self.key(key)).await.map_err(backend_error)?;
+        // -2: missing, -1: no expiry
+        Ok(match ttl {
+            -2 => None,
+            -1 => Some(Duration::MAX),
+            ms => Some(Duration::from_millis(ms.max(0) as u64)),
+        })
+    }
+
+    async fn ttl_many(&self, keys: &[&str]) -> CacheResult<HashMap<String, Duration>> {
+        if keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut pipe = redis::pipe();
+        for key in keys {
+            pipe.pttl(self.key(key));
+        }
+        let mut conn = self.conn().await?;
+        let ttls: Vec<i64> = pipe.query_async(&mut conn).await.map_err(backend_error)?;
+
+        Ok(keys
+            .iter()
+            .zip(ttls)
+            .filter_map(|(key, ttl)| match ttl {
+                -2 => None,
+                -1 => Some((key.to_string(), Duration::MAX)),
+                ms => Some((key.to_string(), Duration::from_millis(ms.max(0) as u64))),
+            })
+            .collect())
+    }
+
+    async fn get_many<T: DeserializeOwned + Send>(
+        &self,
+        keys: &[&str],
+    ) -> CacheResult<HashMap<String, T>> {
+        if keys.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut conn = self.conn().await?;
+        let prefixed: Vec<String> = keys.iter().map(|key| self.key(key)).collect();
+        let raw: Vec<Option<Vec<u8>>> = redis::cmd("MGET")
+            .arg(&prefixed)
+            .query_async(&mut conn)
+            .await
+            .map_err(backend_error)?;
+
+        keys.iter()
+            .zip(raw)
+            .filter_map(|(key, data)| data.map(|data| (key, data)))
+            .map(|(key, data)| {
+                self.serializer
+                    .deserialize(&data)
+                    .map(|value| (key.to_string(), value))
+            })
+            .collect()
+    }
+
+    async fn set_many<T: Serialize + Sync>(
+        &self,
+        items: &[(&str, T)],
+        ttl: Duration,
+    ) -> CacheResult<()> {
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for (key, value) in items {
+            let data = self.serializer.serialize(value)?;
+            pipe.pset_ex(self.key(key), data, (ttl.as_millis() as u64).max(1)).ignore();
+        }
+
+        let mut conn = self.conn().await?;
+        let _: () = pipe.query_async(&mut conn).await.map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn delete_many(&self, keys: &[&str]) -> CacheResult<()> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.conn().await?;
+        let prefixed: Vec<String> = keys.iter().map(|key| self.key(key)).collect();
+        let _: () = conn.del(prefixed).await.map_err(backend_error)?;
+        Ok(())
+    }
+
+    /// Delete every key under this cache's prefix
+    async fn flush(&self) -> CacheResult<()> {
+        let mut conn = self.conn().await?;
+        let pattern = format!("{}:*", self.prefix);
+        let mut cursor: u64 = 0;
+
+        loop {
+            let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(500)
+                .query_async(&mut conn)
+                .await
+                .map_err(backend_error)?;
+
+            if !keys.is_empty() {
+                let _: () = conn.del(keys).await.map_err(backend_error)?;
+            }
+            if next == 0 {
+                return Ok(());
+            }
+            cursor = next;
+        }
+    }
+}
+
+/// Invalidation bus over Redis pub/sub
+///
+/// Messages are JSON-encoded [`InvalidationMessage`]s. The subscriber
+/// reconnects on failure and emits a flush on every (re-)subscription, since
+/// messages may have been missed while disconnected.
+pub struct RedisInvalidationBus {
+    pool: Pool,
+    channel: String,
+    sender: broadcast::Sender<InvalidationMessage>,
+    subscriber: tokio::task::JoinHandle<()>,
+}
+
+impl RedisInvalidationBus {
+    /// Connect and subscribe to `channel`
+    pub async fn new(redis_url: &str, channel: &str) -> CacheResult<Self> {
+        let pool = connect(redis_url).await?;
+        let client = redis::Client::open(redis_url).map_err(backend_error)?;
+        let (sender, _) = broadcast::channel(1024);
+
+        let subscriber = tokio::spawn(subscribe(client, channel.to_string(), sender.clone()));
+
+        Ok(Self {
+            pool,
+            channel: channel.to_string(),
+            sender,
+            subscriber,
+        })
+    }
+}
+
+impl Drop for RedisInvalidationBus {
+    fn drop(&mut self) {
+        self.subscriber.abort();
+    }
+}
+
+#[async_trait]
+impl InvalidationBus for RedisInvalidationBus {
+    async fn publish(&self, message: &InvalidationMessage) -> CacheResult<()> {
+        let payload =
+            serde_json::to_string(message).map_err(|e| CacheError::Serialization(e.to_string()))?;
+
+        let mut conn = self.pool.get().await.map_err(backend_error)?;
+        let _: () = conn
+            .publish(&self.channel, payload)
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<InvalidationMessage> {
+        self.sender.subscribe()
+    }
+}
+
+async fn connect(redis_url: &str) -> CacheResult<Pool> {
+    let cfg = Config::from_url(redis_url);
+    let pool = cfg
+        .create_pool(Some(Runtime::Tokio1))
+        .map_err(backend_error)?;
+
+    // Test connection
+    let mut conn = pool.get().await.map_err(backend_error)?;
+    redis::cmd("PING")
+        .query_async::<_, String>(&mut conn)
+        .await
+        .map_err(backend_error)?;
+
+    Ok(pool)
+}
+
+fn backend_error(e: impl std::fmt::Display) -> CacheError {
+    CacheError::Backend(e.to_string())
+}
+
+async fn subscribe(
+    client: redis::Client,
+    channel: String,
+    sender: broadcast::Sender<InvalidationMessage>,
+) {
+    loop {
+        match listen(&client, &channel, &sender).await {
+            Ok(()) => tracing::warn!("Cache invalidation stream closed, reconnecting"),
+            Err(e) => tracing::warn!(error = %e, "Cache invalidation subscription failed"),
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+async fn listen(
+    client: &redis::Client,
+    channel: &str,
+    sender: &broadcast::Sender<InvalidationMessage>,
+) -> redis::RedisResult<()> {
+    let mut pubsub = client.get_async_connection().await?.into_pubsub();
+    pubsub.subscribe(channel).await?;
+    let _ = sender.send(InvalidationMessage {
+        origin: String::new(),
+        invalidation: crate::tiered::Invalidation::Flush,
+    });
+
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let payload: String = msg.get_payload()?;
+        match serde_json::from_str(&payload) {
+            Ok(message) => {
+                let _ = sender.send(message);
+            }
+            Err(e) => tracing::warn!(error = %e, "Ignoring malformed cache invalidation"),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TieredCache;
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_redis_cache() {
+        let cache = RedisCache::with_prefix("redis://localhost", "rf_cache_test")
+            .await
+            .unwrap();
+
+        cache.set("key", &"value", Duration::from_secs(60)).await.unwrap();
+        assert_eq!(cache.get::<String>("key").await.unwrap(), Some("value".to_string()));
+
+        let ttl = Duration::from_secs(60);
+        let left = cache.ttl("key").await.unwrap().unwrap();
+        assert!(left > Duration::from_secs(50) && left <= ttl);
+        assert_eq!(cache.ttl("missing").await.unwrap(), None);
+
+        // Sub-second TTLs aren't rounded up to a second
+        cache.set("short", &1, Duration::from_millis(200)).await.unwrap();
+        cache
+            .set_many(&[("a", 1), ("b", 2)], Duration::from_millis(200))
+            .await
+            .unwrap();
+        let ttls = cache.ttl_many(&["short", "a", "b", "missing"]).await.unwrap();
+        assert_eq!(ttls.len(), 3);
+        assert!(ttls.values().all(|left| *left <= Duration::from_millis(200)));
+
+        assert!(!cache.compare_and_set("key", &"other", &"new", ttl).await.unwrap());
+        assert!(cache.compare_and_set("key", &"value", &"new", ttl).await.unwrap());
+        assert!(!cache.compare_and_delete("key", &"value").await.unwrap());
+        assert!(cache.compare_and_delete("key", &"new").await.unwrap());
+
+        cache.flush().await.unwrap();
+        assert!(!cache.exists("key").await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_tiered_over_redis() {
+        let url = "redis://localhost";
+        let l2 = RedisCache::with_prefix(url, "rf_cache_tiered").await.unwrap();
+        let a = TieredCache::new(l2.clone())
+            .with_bus(Arc::new(RedisInvalidationBus::new(url, "rf_cache_test").await.unwrap()));
+        let b = TieredCache::new(l2)
+            .with_bus(Arc::new(RedisInvalidationBus::new(url, "rf_cache_test").await.unwrap()));
+
+        a.set("key", &1, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(b.get::<i32>("key").await.unwrap(), Some(1));
+
+        a.set("key", &2, Duration::from_secs(60)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(b.get::<i32>("key").await.unwrap(), Some(2));
+    }
+}
@@ -0,0 +1,284 @@
+//! Request-scoped memoization
+//!
+//! A [`RequestCache`] lives for one HTTP request: loaders run at most once per
+//! key, so repeated lookups across middleware, extractors and handlers share
+//! one result. Nothing is serialized and nothing outlives the request.
+
+use crate::{Cache, CacheResult};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::OnceCell;
+
+type Slot = Arc<OnceCell<Arc<dyn Any + Send + Sync>>>;
+
+/// Per-request memoization of loader results
+///
+/// Entries are keyed by key and value type, so the same key can be used for
+/// different types without collisions. Concurrent lookups of the same key wait
+/// for the first loader instead of running their own; failed loads are not
+/// memoized.
+///
+/// # Example
+///
+/// ```
+/// use rf_cache::{CacheError, RequestCache};
+///
+/// # async fn example() -> Result<(), CacheError> {
+/// let memo = RequestCache::new();
+///
+/// let user: String = memo.get_or_load("user:1", || async { Ok("alice".to_string()) }).await?;
+/// // Served from the request cache, the loader doesn't run again
+/// let again: String = memo.get_or_load("user:1", || async { Ok("bob".to_string()) }).await?;
+/// assert_eq!(user, again);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct RequestCache {
+    slots: Arc<Mutex<HashMap<(TypeId, String), Slot>>>,
+}
+
+impl RequestCache {
+    /// Create new empty request cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn slot<T: 'static>(&self, key: &str) -> Slot {
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        slots
+            .entry((TypeId::of::<T>(), key.to_string()))
+            .or_default()
+            .clone()
+    }
+
+    /// Memoized value, loading it on first use
+    pub async fn get_or_load<T, F, Fut>(&self, key: &str, f: F) -> CacheResult<T>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = CacheResult<T>>,
+    {
+        let slot = self.slot::<T>(key);
+        let value = slot
+            .get_or_try_init(|| async {
+                let value = f().await?;
+                Ok::<_, crate::CacheError>(Arc::new(value) as Arc<dyn Any + Send + Sync>)
+            })
+            .await?;
+
+        Ok(downcast::<T>(value).clone())
+    }
+
+    /// Memoize on top of a shared cache
+    ///
+    /// Checks this request first, then `cache`, and only then runs `f`,
+    /// storing the result in both.
+    pub async fn remember<C, T, F, Fut>(&self, cache: &C, key: &str, ttl: Duration, f: F) -> CacheResult<T>
+    where
+        C: Cache + ?Sized,
+        T: Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+        F: FnOnce() -> Fut + Send,
+        Fut: Future<Output = CacheResult<T>> + Send,
+    {
+        self.get_or_load(key, || cache.remember(key, ttl, f)).await
+    }
+
+    /// Memoized value, if already loaded
+    pub fn get<T: Clone + Send + Sync + 'static>(&self, key: &str) -> Option<T> {
+        let slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        slots
+            .get(&(TypeId::of::<T>(), key.to_string()))
+            .and_then(|slot| slot.get())
+            .map(|value| downcast::<T>(value).clone())
+    }
+
+    /// Store a value, replacing any memoized one
+    pub fn insert<T: Send + Sync + 'static>(&self, key: &str, value: T) {
+        let cell = OnceCell::new_with(Some(Arc::new(value) as Arc<dyn Any + Send + Sync>));
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        slots.insert((TypeId::of::<T>(), key.to_string()), Arc::new(cell));
+    }
+
+    /// Forget a memoized value, e.g. after the handler changed it
+    pub fn forget<T: 'static>(&self, key: &str) {
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        slots.remove(&(TypeId::of::<T>(), key.to_string()));
+    }
+
+    /// Number of memoized values
+    pub fn len(&self) -> usize {
+        let slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        slots.values().filter(|slot| slot.initialized()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Slots are keyed by `TypeId`, so the downcast can't fail
+fn downcast<T: 'static>(value: &Arc<dyn Any + Send + Sync>) -> &T {
+    value
+        .downcast_ref::<T>()
+        .expect("request cache slot holds a value of its key's type")
+}
+
+#[cfg(feature = "axum")]
+pub use self::layer::{RequestCacheLayer, RequestCacheService};
+
+#[cfg(feature = "axum")]
+mod layer {
+    use super::RequestCache;
+    use axum::{
+        extract::{FromRequestParts, Request},
+        http::{request::Parts, StatusCode},
+        response::Response,
+    };
+    use rf_middleware::{Middleware, MiddlewareService, Next};
+    use tower::Layer;
+
+    /// Layer giving every request a fresh [`RequestCache`]
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// async fn show(memo: RequestCache, State(db): State<Db>) -> Json<User> {
+    ///     let user = memo.get_or_load("user:1", || db.find_user(1)).await?;
+    ///     // ...
+    /// }
+    ///
+    /// let app = Router::new().route("/", get(show)).layer(RequestCacheLayer);
+    /// ```
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct RequestCacheLayer;
+
+    impl Middleware for RequestCacheLayer {
+        async fn handle(self, mut req: Request, next: Next) -> Response {
+            req.extensions_mut().insert(RequestCache::new());
+            next.run(req).await
+        }
+    }
+
+    impl<S> Layer<S> for RequestCacheLayer {
+        type Service = RequestCacheService<S>;
+
+        fn layer(&self, inner: S) -> Self::Service {
+            MiddlewareService::new(*self, inner)
+        }
+    }
+
+    /// Service created by [`RequestCacheLayer`]
+    pub type RequestCacheService<S> = MiddlewareService<RequestCacheLayer, S>;
+
+    impl<S> FromRequestParts<S> for RequestCache
+    where
+        S: Send + Sync,
+    {
+        type Rejection = (StatusCode, &'static str);
+
+        async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+            parts.extensions.get::<RequestCache>().cloned().ok_or((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "RequestCacheLayer is not installed",
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryCache;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn test_loader_runs_once() {
+        let memo = RequestCache::new();
+        let calls = AtomicU32::new(0);
+
+        for _ in 0..3 {
+            let value: u32 = memo
+                .get_or_load("answer", || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(42)
+                })
+                .await
+                .unwrap();
+            assert_eq!(value, 42);
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(memo.get::<u32>("answer"), Some(42));
+        // Same key, different type
+        assert_eq!(memo.get::<String>("answer"), None);
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_not_memoized() {
+        let memo = RequestCache::new();
+
+        let failed: CacheResult<u32> = memo
+            .get_or_load("key", || async { Err(crate::CacheError::LockFailed) })
+            .await;
+        assert!(failed.is_err());
+
+        let value: u32 = memo.get_or_load("key", || async { Ok(1) }).await.unwrap();
+        assert_eq!(value, 1);
+    }
+
+    #[tokio::test]
+    async fn test_remember_composes_with_shared_cache() {
+        let shared = MemoryCache::new();
+        shared.set("user:1", &"cached".to_string(), Duration::from_secs(60)).await.unwrap();
+        let memo = RequestCache::new();
+
+        let value: String = memo
+            .remember(&shared, "user:1", Duration::from_secs(60), || async {
+                Ok("loaded".to_string())
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, "cached");
+
+        // Even after the shared entry changes, the request keeps its view
+        shared.set("user:1", &"changed".to_string(), Duration::from_secs(60)).await.unwrap();
+        let value: String = memo
+            .remember(&shared, "user:1", Duration::from_secs(60), || async {
+                Ok("loaded".to_string())
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, "cached");
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn test_layer_provides_fresh_cache_per_request() {
+        use axum::{body::Body, extract::Request, routing::get, Router};
+        use tower::ServiceExt;
+
+        async fn handler(memo: RequestCache) -> String {
+            let first: u32 = memo.get_or_load("n", || async { Ok(1) }).await.unwrap();
+            let second: u32 = memo.get_or_load("n", || async { Ok(2) }).await.unwrap();
+            format!("{}{}", first, second)
+        }
+
+        let app = Router::new().route("/", get(handler)).layer(RequestCacheLayer);
+        for _ in 0..2 {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert_eq!(&body[..], b"11");
+        }
+    }
+}
@@ -0,0 +1,506 @@
+//! HTTP response caching
+//!
+//! GET responses are stored under a hash of the URI, the configured vary
+//! headers and the versions of the route's tags. Busting a tag replaces
+//! its version, like flushing a [`Namespace`](crate::Namespace), so every
+//! response cached under it is missed from then on.
+
+use crate::{Cache, CacheResult};
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use rf_middleware::{Middleware, MiddlewareService, Next};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tower::Layer;
+
+/// Largest response body stored; bigger responses are passed through
+const MAX_BODY: usize = 8 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize)]
+struct CachedResponse {
+    headers: Vec<(String, String)>,
+    /// Hex encoded
+    body: String,
+    /// Unix timestamp, for the `Age` header
+    stored_at: u64,
+}
+
+/// Shared response cache, for the route layers and for busting tags
+///
+/// Keep it in the router state so mutation handlers can bust the tags of
+/// the responses they change.
+///
+/// ```
+/// use axum::{extract::State, routing::get, Router};
+/// use rf_cache::{MemoryCache, ResponseCache};
+/// use std::time::Duration;
+///
+/// async fn create_post(State(responses): State<ResponseCache<MemoryCache>>) -> &'static str {
+///     // After storing the post
+///     responses.bust(&["posts:list"]).await.ok();
+///     "Created"
+/// }
+///
+/// let responses = ResponseCache::new(MemoryCache::new());
+/// let app: Router = Router::new()
+///     .route(
+///         "/posts",
+///         get(|| async { "All posts" })
+///             .layer(responses.layer().ttl(Duration::from_secs(300)).tags(&["posts:list"]))
+///             .post(create_post),
+///     )
+///     .with_state(responses);
+/// ```
+pub struct ResponseCache<C> {
+    cache: Arc<C>,
+}
+
+impl<C> Clone for ResponseCache<C> {
+    fn clone(&self) -> Self {
+        Self {
+            cache: Arc::clone(&self.cache),
+        }
+    }
+}
+
+impl<C: Cache> ResponseCache<C> {
+    pub fn new(cache: C) -> Self {
+        Self {
+            cache: Arc::new(cache),
+        }
+    }
+
+    /// Layer caching the responses of the routes it wraps, for one minute
+    /// unless configured otherwise
+    pub fn layer(&self) -> ResponseCacheLayer<C> {
+        ResponseCacheLayer {
+            responses: self.clone(),
+            config: Arc::new(RouteConfig {
+                ttl: Duration::from_secs(60),
+                tags: vec![],
+                vary: vec![],
+                client_max_age: None,
+            }),
+        }
+    }
+
+    /// Drop every response cached under any of `tags`
+    pub async fn bust(&self, tags: &[&str]) -> CacheResult<()> {
+        for tag in tags {
+            self.cache.namespace(&tag_namespace(tag)).flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Cache key of `req` under the current versions of `tags`
+    async fn key(
+        &self,
+        uri: &Uri,
+        headers: &HeaderMap,
+        config: &RouteConfig,
+    ) -> CacheResult<String> {
+        let mut hash = Sha256::new();
+        hash.update(uri.to_string());
+        for name in &config.vary {
+            hash.update([0]);
+            hash.update(name.as_str());
+            hash.update(b": ");
+            for value in headers.get_all(name) {
+                hash.update(value.as_bytes());
+            }
+        }
+        for tag in &config.tags {
+            let version = self.cache.namespace(&tag_namespace(tag)).version().await?;
+            hash.update([0]);
+            hash.update(&version);
+        }
+        Ok(format!("response:{}", hex::encode(hash.finalize())))
+    }
+}
+
+fn tag_namespace(tag: &str) -> String {
+    format!("response-tag:{}", tag)
+}
+
+struct RouteConfig {
+    ttl: Duration,
+    tags: Vec<String>,
+    vary: Vec<HeaderName>,
+    client_max_age: Option<Duration>,
+}
+
+/// Layer caching successful GET responses of a route
+///
+/// Created with [`ResponseCache::layer`]. Responses marked `no-store`,
+/// `no-cache` or `private`, and responses setting cookies, are not stored;
+/// `s-maxage` overrides the TTL. Requests with an `Authorization` or
+/// `Cookie` header bypass the cache unless that header is a vary header,
+/// so pages rendered for a session are never served to other users.
+/// Requests sent with `Cache-Control: no-cache` refresh the stored
+/// response.
+///
+/// Stored responses get an ETag, so clients can revalidate with
+/// `If-None-Match`.
+pub struct ResponseCacheLayer<C> {
+    responses: ResponseCache<C>,
+    config: Arc<RouteConfig>,
+}
+
+impl<C> Clone for ResponseCacheLayer<C> {
+    fn clone(&self) -> Self {
+        Self {
+            responses: self.responses.clone(),
+            config: Arc::clone(&self.config),
+        }
+    }
+}
+
+impl<C: Cache + 'static> ResponseCacheLayer<C> {
+    /// How long responses are kept
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.config_mut().ttl = ttl;
+        self
+    }
+
+    /// Tags the responses are cached under, e.g. `posts:list`
+    pub fn tags(mut self, tags: &[&str]) -> Self {
+        self.config_mut().tags = tags.iter().map(|tag| tag.to_string()).collect();
+        self
+    }
+
+    /// Cache a response per value of the request header `name`, e.g.
+    /// `Accept-Language`
+    pub fn vary(mut self, name: HeaderName) -> Self {
+        self.config_mut().vary.push(name);
+        self
+    }
+
+    /// Let browsers and CDNs cache responses as well, with
+    /// `Cache-Control: public, max-age=..` on responses without one
+    pub fn client_max_age(mut self, max_age: Duration) -> Self {
+        self.config_mut().client_max_age = Some(max_age);
+        self
+    }
+
+    fn config_mut(&mut self) -> &mut RouteConfig {
+        Arc::get_mut(&mut self.config).expect("ResponseCacheLayer is configured before use")
+    }
+
+    /// Whether `req` may be answered from the cache
+    fn cacheable(&self, req: &Request) -> bool {
+        req.method() == Method::GET
+            && [header::AUTHORIZATION, header::COOKIE]
+                .iter()
+                .all(|name| !req.headers().contains_key(name) || self.config.vary.contains(name))
+    }
+
+    async fn respond(&self, req: Request, next: Next) -> Response {
+        let key = match self
+            .responses
+            .key(req.uri(), req.headers(), &self.config)
+            .await
+        {
+            Ok(key) => key,
+            Err(e) => {
+                tracing::warn!(error = %e, "Response cache unavailable");
+                return next.run(req).await;
+            }
+        };
+        let if_none_match = req.headers().get(header::IF_NONE_MATCH).cloned();
+
+        if !has_directive(req.headers(), "no-cache") {
+            match self.responses.cache.get::<CachedResponse>(&key).await {
+                Ok(Some(cached)) => return hit(cached, if_none_match.as_ref()),
+                Ok(None) => {}
+                Err(e) => tracing::warn!(error = %e, "Failed to read cached response"),
+            }
+        }
+
+        let mut response = next.run(req).await;
+        if let Some(max_age) = self.config.client_max_age {
+            if !response.headers().contains_key(header::CACHE_CONTROL) {
+                let value = format!("public, max-age={}", max_age.as_secs());
+                if let Ok(value) = HeaderValue::from_str(&value) {
+                    response.headers_mut().insert(header::CACHE_CONTROL, value);
+                }
+                // Shared caches must key the response like we do
+                add_vary(response.headers_mut(), &self.config.vary);
+            }
+        }
+        let Some(ttl) = self.storable(&response) else {
+            return response;
+        };
+
+        let (mut parts, body) = response.into_parts();
+        let body = match axum::body::to_bytes(body, MAX_BODY).await {
+            Ok(body) => body,
+            Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        };
+        if !parts.headers.contains_key(header::ETAG) {
+            let tag = format!("\"{}\"", hex::encode(&Sha256::digest(&body)[..16]));
+            if let Ok(value) = HeaderValue::from_str(&tag) {
+                parts.headers.insert(header::ETAG, value);
+            }
+        }
+
+        let cached = CachedResponse {
+            headers: parts
+                .headers
+                .iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.into())))
+                .collect(),
+            body: hex::encode(&body),
+            stored_at: now(),
+        };
+        if let Err(e) = self.responses.cache.set(&key, &cached, ttl).await {
+            tracing::warn!(error = %e, "Failed to cache response");
+        }
+        Response::from_parts(parts, Body::from(body))
+    }
+
+    /// TTL for `response`, `None` if it mustn't be stored
+    fn storable(&self, response: &Response) -> Option<Duration> {
+        let headers = response.headers();
+        if response.status() != StatusCode::OK
+            || headers.contains_key(header::SET_COOKIE)
+            || ["no-store", "no-cache", "private"]
+                .iter()
+                .any(|directive| has_directive(headers, directive))
+        {
+            return None;
+        }
+        // Streamed bodies of unknown size are left alone
+        axum::body::HttpBody::size_hint(response.body()).exact()?;
+
+        let s_maxage = cache_control(headers)
+            .filter_map(|directive| directive.strip_prefix("s-maxage=")?.parse().ok())
+            .next();
+        let ttl = s_maxage.map_or(self.config.ttl, Duration::from_secs);
+        (!ttl.is_zero()).then_some(ttl)
+    }
+}
+
+/// Lowercase `Cache-Control` directives
+fn cache_control(headers: &HeaderMap) -> impl Iterator<Item = String> + '_ {
+    headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|directive| directive.trim().to_ascii_lowercase())
+}
+
+fn has_directive(headers: &HeaderMap, directive: &str) -> bool {
+    cache_control(headers).any(|d| d == directive)
+}
+
+/// List `names` in the `Vary` header, keeping the names already there
+fn add_vary(headers: &mut HeaderMap, names: &[HeaderName]) {
+    let listed: Vec<String> = headers
+        .get_all(header::VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect();
+    for name in names {
+        if !listed.iter().any(|listed| listed == name.as_str()) {
+            headers.append(header::VARY, HeaderValue::from(name.clone()));
+        }
+    }
+}
+
+/// Answer from a stored response
+fn hit(cached: CachedResponse, if_none_match: Option<&HeaderValue>) -> Response {
+    let mut headers = HeaderMap::new();
+    for (name, value) in cached.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            headers.append(name, value);
+        }
+    }
+    let age = now().saturating_sub(cached.stored_at);
+    headers.insert(header::AGE, HeaderValue::from(age));
+
+    let etag = headers.get(header::ETAG);
+    if let (Some(etag), Some(candidates)) = (etag, if_none_match) {
+        let matches = candidates.to_str().is_ok_and(|candidates| {
+            candidates
+                .split(',')
+                .any(|candidate| candidate.trim() == "*" || candidate.trim() == etag)
+        });
+        if matches {
+            let mut response = StatusCode::NOT_MODIFIED.into_response();
+            for name in [header::ETAG, header::CACHE_CONTROL, header::AGE] {
+                if let Some(value) = headers.get(&name) {
+                    response.headers_mut().insert(name, value.clone());
+                }
+            }
+            return response;
+        }
+    }
+
+    let mut response = Response::new(Body::from(hex::decode(cached.body).unwrap_or_default()));
+    *response.headers_mut() = headers;
+    response
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl<C: Cache + 'static> Middleware for ResponseCacheLayer<C> {
+    async fn handle(self, req: Request, next: Next) -> Response {
+        if !self.cacheable(&req) {
+            return next.run(req).await;
+        }
+        self.respond(req, next).await
+    }
+}
+
+impl<S, C> Layer<S> for ResponseCacheLayer<C> {
+    type Service = ResponseCacheService<S, C>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+/// Service created by [`ResponseCacheLayer`]
+pub type ResponseCacheService<S, C> = MiddlewareService<ResponseCacheLayer<C>, S>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryCache;
+    use axum::{body::to_bytes, routing::get, Router};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tower::ServiceExt;
+
+    fn app(layer: ResponseCacheLayer<MemoryCache>) -> (Router, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let (posts, private) = (Arc::clone(&calls), Arc::clone(&calls));
+        let app = Router::new()
+            .route(
+                "/posts",
+                get(move || async move {
+                    format!("posts v{}", posts.fetch_add(1, Ordering::SeqCst) + 1)
+                }),
+            )
+            .route(
+                "/me",
+                get(move || async move {
+                    private.fetch_add(1, Ordering::SeqCst);
+                    ([(header::CACHE_CONTROL, "private")], "me")
+                }),
+            )
+            .layer(layer);
+        (app, calls)
+    }
+
+    async fn get_page(app: &Router, uri: &str, headers: &[(HeaderName, &str)]) -> Response {
+        let mut req = Request::builder().uri(uri);
+        for (name, value) in headers {
+            req = req.header(name, *value);
+        }
+        app.clone()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn text(response: Response) -> String {
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_cached_per_uri() {
+        let responses = ResponseCache::new(MemoryCache::new());
+        let (app, calls) = app(responses.layer());
+
+        let first = get_page(&app, "/posts", &[]).await;
+        assert!(first.headers().contains_key(header::ETAG));
+        assert!(!first.headers().contains_key(header::AGE));
+        assert_eq!(text(first).await, "posts v1");
+
+        let hit = get_page(&app, "/posts", &[]).await;
+        assert_eq!(hit.headers()[header::AGE], "0");
+        assert_eq!(text(hit).await, "posts v1");
+
+        assert_eq!(
+            text(get_page(&app, "/posts?page=2", &[]).await).await,
+            "posts v2"
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Private responses and authorized or session requests aren't cached
+        get_page(&app, "/me", &[]).await;
+        get_page(&app, "/me", &[]).await;
+        get_page(&app, "/posts", &[(header::AUTHORIZATION, "Bearer t")]).await;
+        let session = get_page(&app, "/posts", &[(header::COOKIE, "session=alice")]).await;
+        assert!(!session.headers().contains_key(header::AGE));
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn test_bust_tags() {
+        let responses = ResponseCache::new(MemoryCache::new());
+        let (app, _) = app(responses.layer().tags(&["posts:list"]));
+
+        assert_eq!(text(get_page(&app, "/posts", &[]).await).await, "posts v1");
+        responses.bust(&["comments"]).await.unwrap();
+        assert_eq!(text(get_page(&app, "/posts", &[]).await).await, "posts v1");
+
+        responses.bust(&["posts:list"]).await.unwrap();
+        assert_eq!(text(get_page(&app, "/posts", &[]).await).await, "posts v2");
+    }
+
+    #[tokio::test]
+    async fn test_vary_and_revalidation() {
+        let responses = ResponseCache::new(MemoryCache::new());
+        let layer = responses
+            .layer()
+            .vary(header::ACCEPT_LANGUAGE)
+            .client_max_age(Duration::from_secs(30));
+        let (app, _) = app(layer);
+
+        let first = get_page(&app, "/posts", &[(header::ACCEPT_LANGUAGE, "de")]).await;
+        assert_eq!(first.headers()[header::CACHE_CONTROL], "public, max-age=30");
+        assert_eq!(first.headers()[header::VARY], "accept-language");
+        let etag = first.headers()[header::ETAG].to_str().unwrap().to_string();
+
+        let other = get_page(&app, "/posts", &[(header::ACCEPT_LANGUAGE, "fr")]).await;
+        assert_eq!(text(other).await, "posts v2");
+
+        let headers = [
+            (header::ACCEPT_LANGUAGE, "de"),
+            (header::IF_NONE_MATCH, etag.as_str()),
+        ];
+        let not_modified = get_page(&app, "/posts", &headers).await;
+        assert_eq!(not_modified.status(), StatusCode::NOT_MODIFIED);
+
+        let refreshed = get_page(
+            &app,
+            "/posts",
+            &[
+                (header::ACCEPT_LANGUAGE, "de"),
+                (header::CACHE_CONTROL, "no-cache"),
+            ],
+        )
+        .await;
+        assert_eq!(text(refreshed).await, "posts v3");
+        let hit = get_page(&app, "/posts", &[(header::ACCEPT_LANGUAGE, "de")]).await;
+        assert_eq!(text(hit).await, "posts v3");
+    }
+}
@@ -0,0 +1,212 @@
+//! Pluggable value serialization
+//!
+//! JSON stays the default so existing cache contents remain readable. Binary
+//! formats are opt-in per cache instance:
+//!
+//! | Serializer | Feature | Notes |
+//! |------------|---------|-------|
+//! | [`JsonSerializer`] | - | default, human readable |
+//! | `BincodeSerializer` | `bincode` | smallest and fastest, not self-describing |
+//! | `MessagePackSerializer` | `msgpack` | compact and self-describing |
+//! | `Compressed<S>` | `zstd` | zstd-compresses payloads above a threshold |
+
+use crate::{CacheError, CacheResult};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Converts cached values to and from bytes
+pub trait CacheSerializer: Send + Sync + 'static {
+    fn serialize<T: Serialize + ?Sized>(&self, value: &T) -> CacheResult<Vec<u8>>;
+
+    fn deserialize<T: DeserializeOwned>(&self, data: &[u8]) -> CacheResult<T>;
+}
+
+/// JSON serialization (default)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonSerializer;
+
+impl CacheSerializer for JsonSerializer {
+    fn serialize<T: Serialize + ?Sized>(&self, value: &T) -> CacheResult<Vec<u8>> {
+        serde_json::to_vec(value).map_err(|e| CacheError::Serialization(e.to_string()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, data: &[u8]) -> CacheResult<T> {
+        serde_json::from_slice(data).map_err(|e| CacheError::Deserialization(e.to_string()))
+    }
+}
+
+/// Bincode serialization
+///
+/// Bincode is not self-describing: it can't be read back as
+/// `serde_json::Value`, so don't use it as the L2 of a
+/// [`crate::TieredCache`], and changing a cached struct's fields requires a
+/// flush.
+#[cfg(feature = "bincode")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BincodeSerializer;
+
+#[cfg(feature = "bincode")]
+impl CacheSerializer for BincodeSerializer {
+    fn serialize<T: Serialize + ?Sized>(&self, value: &T) -> CacheResult<Vec<u8>> {
+        bincode::serialize(value).map_err(|e| CacheError::Serialization(e.to_string()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, data: &[u8]) -> CacheResult<T> {
+        bincode::deserialize(data).map_err(|e| CacheError::Deserialization(e.to_string()))
+    }
+}
+
+/// MessagePack serialization
+///
+/// Structs are encoded as maps, so fields can be added without a flush.
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagePackSerializer;
+
+#[cfg(feature = "msgpack")]
+impl CacheSerializer for MessagePackSerializer {
+    fn serialize<T: Serialize + ?Sized>(&self, value: &T) -> CacheResult<Vec<u8>> {
+        rmp_serde::to_vec_named(value).map_err(|e| CacheError::Serialization(e.to_string()))
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, data: &[u8]) -> CacheResult<T> {
+        rmp_serde::from_slice(data).map_err(|e| CacheError::Deserialization(e.to_string()))
+    }
+}
+
+/// zstd compression on top of another serializer
+///
+/// Payloads of at least `threshold` bytes are compressed. A one-byte header
+/// records whether a payload is compressed, so the threshold can be changed
+/// without flushing.
+///
+/// # Example
+///
+/// ```ignore
+/// let cache = MemoryCache::new().with_serializer(Compressed::new(BincodeSerializer, 1024));
+/// ```
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone, Copy)]
+pub struct Compressed<S> {
+    inner: S,
+    threshold: usize,
+    level: i32,
+}
+
+#[cfg(feature = "zstd")]
+impl<S: CacheSerializer> Compressed<S> {
+    const RAW: u8 = 0;
+    const ZSTD: u8 = 1;
+
+    /// Compress payloads of at least `threshold` bytes with zstd level 3
+    pub fn new(inner: S, threshold: usize) -> Self {
+        Self {
+            inner,
+            threshold,
+            level: 3,
+        }
+    }
+
+    /// Set the zstd compression level (1-22)
+    pub fn level(mut self, level: i32) -> Self {
+        self.level = level;
+        self
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<S: CacheSerializer> CacheSerializer for Compressed<S> {
+    fn serialize<T: Serialize + ?Sized>(&self, value: &T) -> CacheResult<Vec<u8>> {
+        let data = self.inner.serialize(value)?;
+
+        let mut out = Vec::with_capacity(data.len() + 1);
+        if data.len() >= self.threshold {
+            out.push(Self::ZSTD);
+            zstd::stream::copy_encode(&data[..], &mut out, self.level)
+                .map_err(|e| CacheError::Serialization(e.to_string()))?;
+        } else {
+            out.push(Self::RAW);
+            out.extend_from_slice(&data);
+        }
+        Ok(out)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, data: &[u8]) -> CacheResult<T> {
+        match data.split_first() {
+            Some((&Self::RAW, payload)) => self.inner.deserialize(payload),
+            Some((&Self::ZSTD, payload)) => {
+                let decoded = zstd::stream::decode_all(payload)
+                    .map_err(|e| CacheError::Deserialization(e.to_string()))?;
+                self.inner.deserialize(&decoded)
+            }
+            _ => Err(CacheError::Deserialization(
+                "missing or unknown compression header".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Price {
+        sku: String,
+        cents: u32,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Pricing {
+        currency: String,
+        prices: Vec<Price>,
+    }
+
+    fn pricing() -> Pricing {
+        Pricing {
+            currency: "CHF".to_string(),
+            prices: (0..256)
+                .map(|i| Price {
+                    sku: format!("SKU-{}", i),
+                    cents: 1999,
+                })
+                .collect(),
+        }
+    }
+
+    fn round_trip<S: CacheSerializer>(serializer: &S) -> usize {
+        let data = serializer.serialize(&pricing()).unwrap();
+        let decoded: Pricing = serializer.deserialize(&data).unwrap();
+        assert_eq!(decoded, pricing());
+        data.len()
+    }
+
+    #[test]
+    fn test_json() {
+        round_trip(&JsonSerializer);
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_bincode_is_smaller_than_json() {
+        assert!(round_trip(&BincodeSerializer) < round_trip(&JsonSerializer));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack() {
+        assert!(round_trip(&MessagePackSerializer) < round_trip(&JsonSerializer));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_compression_threshold() {
+        let compressed = Compressed::new(JsonSerializer, 64);
+        assert!(round_trip(&compressed) < round_trip(&JsonSerializer));
+
+        // Small payloads are stored as-is behind the header
+        let data = compressed.serialize(&1).unwrap();
+        assert_eq!(data, vec![0, b'1']);
+        assert_eq!(compressed.deserialize::<i32>(&data).unwrap(), 1);
+    }
+}
@@ -0,0 +1,904 @@
+//! Two-tier caching: bounded in-process L1 in front of a shared L2
+//!
+//! Writes go to both tiers and are announced on an [`InvalidationBus`] so
+//! other instances evict their L1 copy. The L1 TTL bounds staleness should an
+//! invalidation message be lost.
+
+use crate::{Cache, CacheError, CacheResult};
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::broadcast;
+
+/// What a peer should evict from its L1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "key", rename_all = "snake_case")]
+pub enum Invalidation {
+    Key(String),
+    /// Several keys changed by one batch write
+    Keys(Vec<String>),
+    Flush,
+}
+
+/// Invalidation published by one cache instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvalidationMessage {
+    /// Instance that made the change; it ignores its own messages
+    pub origin: String,
+    pub invalidation: Invalidation,
+}
+
+/// Transport for L1 invalidations between instances
+#[async_trait]
+pub trait InvalidationBus: Send + Sync {
+    /// Announce a change to every subscriber
+    async fn publish(&self, message: &InvalidationMessage) -> CacheResult<()>;
+
+    /// Receive announcements, including this instance's own
+    fn subscribe(&self) -> broadcast::Receiver<InvalidationMessage>;
+}
+
+/// In-process invalidation bus
+///
+/// Connects several [`TieredCache`]s in the same process, mostly useful for
+/// tests; use `RedisInvalidationBus` across instances.
+#[derive(Clone)]
+pub struct LocalInvalidationBus {
+    sender: broadcast::Sender<InvalidationMessage>,
+}
+
+impl LocalInvalidationBus {
+    /// Create new local bus
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        Self { sender }
+    }
+}
+
+impl Default for LocalInvalidationBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl InvalidationBus for LocalInvalidationBus {
+    async fn publish(&self, message: &InvalidationMessage) -> CacheResult<()> {
+        // No subscribers is not an error
+        let _ = self.sender.send(message.clone());
+        Ok(())
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<InvalidationMessage> {
+        self.sender.subscribe()
+    }
+}
+
+/// Hit/miss counters for one tier
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TierStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl TierStats {
+    /// Fraction of lookups served by this tier
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// Statistics of a [`TieredCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TieredCacheStats {
+    pub l1: TierStats,
+    pub l2: TierStats,
+    /// L1 entries dropped to stay within capacity
+    pub evictions: u64,
+    /// Invalidations received from other instances
+    pub invalidations: u64,
+    /// Entries currently held in L1
+    pub l1_entries: usize,
+}
+
+#[derive(Default)]
+struct Counters {
+    l1_hits: AtomicU64,
+    l1_misses: AtomicU64,
+    l2_hits: AtomicU64,
+    l2_misses: AtomicU64,
+    evictions: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+struct L1Entry {
+    value: serde_json::Value,
+    expires_at: Instant,
+    tick: u64,
+}
+
+/// LRU map; `order` indexes keys by their last use
+struct Lru {
+    capacity: usize,
+    tick: u64,
+    /// Bumped by every write and invalidation, so a value read from L2
+    /// before one isn't put back into L1 after it
+    generation: u64,
+    entries: HashMap<String, L1Entry>,
+    order: BTreeMap<u64, String>,
+}
+
+impl Lru {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            generation: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn get(&mut self, key: &str) -> Option<serde_json::Value> {
+        let expired = self.entries.get(key)?.expires_at <= Instant::now();
+        if expired {
+            self.remove(key);
+            return None;
+        }
+
+        let tick = self.next_tick();
+        let entry = self.entries.get_mut(key)?;
+        self.order.remove(&entry.tick);
+        self.order.insert(tick, key.to_string());
+        entry.tick = tick;
+        Some(entry.value.clone())
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.entries
+            .get(key)
+            .is_some_and(|entry| entry.expires_at > Instant::now())
+    }
+
+    /// Insert and return the number of evicted entries
+    fn insert(&mut self, key: &str, value: serde_json::Value, ttl: Duration) -> u64 {
+        if self.capacity == 0 {
+            return 0;
+        }
+
+        self.remove(key);
+        let tick = self.next_tick();
+        self.order.insert(tick, key.to_string());
+        self.entries.insert(
+            key.to_string(),
+            L1Entry {
+                value,
+                expires_at: Instant::now() + ttl,
+                tick,
+            },
+        );
+
+        let mut evicted = 0;
+        while self.entries.len() > self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+            evicted += 1;
+        }
+        evicted
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.tick);
+        }
+    }
+
+    /// Remove a key that changed elsewhere
+    fn invalidate(&mut self, key: &str) {
+        self.generation += 1;
+        self.remove(key);
+    }
+
+    fn clear(&mut self) {
+        self.generation += 1;
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+/// Two-tier cache: a bounded in-memory LRU (L1) in front of any [`Cache`] (L2)
+///
+/// Values are stored as JSON in L1, so any L2 that round-trips JSON values
+/// (e.g. `RedisCache` or [`crate::MemoryCache`]) can be used. The L2 must use
+/// a self-describing serializer: JSON or MessagePack, not bincode.
+///
+/// # Example
+///
+/// ```no_run
+/// # #[cfg(feature = "redis-backend")]
+/// # async fn example() -> Result<(), rf_cache::CacheError> {
+/// use rf_cache::*;
+/// use std::{sync::Arc, time::Duration};
+///
+/// let l2 = RedisCache::new("redis://localhost").await?;
+/// let bus = RedisInvalidationBus::new("redis://localhost", "cache:invalidate").await?;
+///
+/// let cache = TieredCache::new(l2)
+///     .capacity(10_000)
+///     .l1_ttl(Duration::from_secs(30))
+///     .with_bus(Arc::new(bus));
+///
+/// cache.set("pricing", &vec![9.99, 19.99], Duration::from_secs(300)).await?;
+/// println!("L1 hit rate: {}", cache.stats().l1.hit_rate());
+/// # Ok(())
+/// # }
+/// ```
+pub struct TieredCache<C: Cache> {
+    l1: Arc<Mutex<Lru>>,
+    l2: C,
+    l1_ttl: Duration,
+    origin: String,
+    bus: Option<Arc<dyn InvalidationBus>>,
+    counters: Arc<Counters>,
+    listener: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl<C: Cache> TieredCache<C> {
+    /// Create new tiered cache with 1000 L1 entries and a 60s L1 TTL
+    pub fn new(l2: C) -> Self {
+        Self {
+            l1: Arc::new(Mutex::new(Lru::new(1000))),
+            l2,
+            l1_ttl: Duration::from_secs(60),
+            origin: crate::unique_id(),
+            bus: None,
+            counters: Arc::new(Counters::default()),
+            listener: None,
+        }
+    }
+
+    /// Set the maximum number of L1 entries
+    pub fn capacity(self, capacity: usize) -> Self {
+        self.l1().capacity = capacity;
+        self
+    }
+
+    /// Set the maximum time an entry stays in L1
+    pub fn l1_ttl(mut self, ttl: Duration) -> Self {
+        self.l1_ttl = ttl;
+        self
+    }
+
+    /// Publish and receive invalidations on `bus`
+    ///
+    /// Spawns a listener task, so this must be called within a Tokio runtime.
+    pub fn with_bus(mut self, bus: Arc<dyn InvalidationBus>) -> Self {
+        if let Some(listener) = self.listener.take() {
+            listener.abort();
+        }
+
+        self.listener = Some(tokio::spawn(listen(
+            bus.subscribe(),
+            self.origin.clone(),
+            Arc::downgrade(&self.l1),
+            self.counters.clone(),
+        )));
+        self.bus = Some(bus);
+        self
+    }
+
+    /// The L2 cache
+    pub fn inner(&self) -> &C {
+        &self.l2
+    }
+
+    /// Hit/miss statistics per tier
+    pub fn stats(&self) -> TieredCacheStats {
+        let c = &self.counters;
+        TieredCacheStats {
+            l1: TierStats {
+                hits: c.l1_hits.load(Ordering::Relaxed),
+                misses: c.l1_misses.load(Ordering::Relaxed),
+            },
+            l2: TierStats {
+                hits: c.l2_hits.load(Ordering::Relaxed),
+                misses: c.l2_misses.load(Ordering::Relaxed),
+            },
+            evictions: c.evictions.load(Ordering::Relaxed),
+            invalidations: c.invalidations.load(Ordering::Relaxed),
+            l1_entries: self.l1().entries.len(),
+        }
+    }
+
+    /// Drop a key from this instance's L1 only
+    pub fn evict_local(&self, key: &str) {
+        self.l1().invalidate(key);
+    }
+
+    fn l1(&self) -> std::sync::MutexGuard<'_, Lru> {
+        self.l1.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Put a written value into L1
+    fn fill_l1(&self, key: &str, value: serde_json::Value, ttl: Duration) {
+        let evicted = {
+            let mut l1 = self.l1();
+            l1.generation += 1;
+            l1.insert(key, value, ttl.min(self.l1_ttl))
+        };
+        self.counters.evictions.fetch_add(evicted, Ordering::Relaxed);
+    }
+
+    /// Put values read from L2 into L1, unless a write or invalidation
+    /// happened since `generation`, which they may predate
+    fn fill_l1_read(&self, values: Vec<(&str, serde_json::Value, Duration)>, generation: u64) {
+        let evicted = {
+            let mut l1 = self.l1();
+            if l1.generation != generation {
+                return;
+            }
+            values
+                .into_iter()
+                .map(|(key, value, ttl)| l1.insert(key, value, ttl.min(self.l1_ttl)))
+                .sum()
+        };
+        self.counters.evictions.fetch_add(evicted, Ordering::Relaxed);
+    }
+
+    /// Copy a value read from L2 into L1, for no longer than it has left in
+    /// L2
+    ///
+    /// Failing to get the TTL only skips the copy; the read succeeded.
+    async fn backfill(&self, key: &str, value: &serde_json::Value, generation: u64) {
+        match self.l2.ttl(key).await {
+            Ok(Some(ttl)) => self.fill_l1_read(vec![(key, value.clone(), ttl)], generation),
+            // Expired since it was read
+            Ok(None) => {}
+            Err(e) => tracing::warn!(key, error = %e, "Failed to get TTL for L1 copy"),
+        }
+    }
+
+    /// [`backfill`](Self::backfill) several values, fetching their TTLs in
+    /// one go
+    async fn backfill_many(&self, values: &HashMap<String, serde_json::Value>, generation: u64) {
+        let keys: Vec<&str> = values.keys().map(String::as_str).collect();
+        match self.l2.ttl_many(&keys).await {
+            Ok(ttls) => {
+                let values = ttls
+                    .iter()
+                    .filter_map(|(key, ttl)| {
+                        let value = values.get(key)?;
+                        Some((key.as_str(), value.clone(), *ttl))
+                    })
+                    .collect();
+                self.fill_l1_read(values, generation);
+            }
+            Err(e) => tracing::warn!(error = %e, "Failed to get TTLs for L1 copies"),
+        }
+    }
+
+    /// Tell peers to evict; a lost message is bounded by the L1 TTL, so
+    /// failures are logged rather than failing the write
+    async fn announce(&self, invalidation: Invalidation) {
+        let Some(bus) = &self.bus else {
+            return;
+        };
+
+        let message = InvalidationMessage {
+            origin: self.origin.clone(),
+            invalidation,
+        };
+        if let Err(e) = bus.publish(&message).await {
+            tracing::warn!(error = %e, "Failed to publish cache invalidation");
+        }
+    }
+}
+
+impl<C: Cache> Drop for TieredCache<C> {
+    fn drop(&mut self) {
+        if let Some(listener) = &self.listener {
+            listener.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl<C: Cache> Cache for TieredCache<C> {
+    async fn get<T: DeserializeOwned + Send>(&self, key: &str) -> CacheResult<Option<T>> {
+        let (cached, generation) = {
+            let mut l1 = self.l1();
+            (l1.get(key), l1.generation)
+        };
+        let value = match cached {
+            Some(value) => {
+                self.counters.l1_hits.fetch_add(1, Ordering::Relaxed);
+                value
+            }
+            None => {
+                self.counters.l1_misses.fetch_add(1, Ordering::Relaxed);
+                match self.l2.get::<serde_json::Value>(key).await? {
+                    Some(value) => {
+                        self.counters.l2_hits.fetch_add(1, Ordering::Relaxed);
+                        self.backfill(key, &value, generation).await;
+                        value
+                    }
+                    None => {
+                        self.counters.l2_misses.fetch_add(1, Ordering::Relaxed);
+                        return Ok(None);
+                    }
+                }
+            }
+        };
+
+        T::deserialize(&value)
+            .map(Some)
+            .map_err(|e| CacheError::Deserialization(e.to_string()))
+    }
+
+    async fn set<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<()> {
+        let value =
+            serde_json::to_value(value).map_err(|e| CacheError::Serialization(e.to_string()))?;
+
+        self.l2.set(key, &value, ttl).await?;
+        self.fill_l1(key, value, ttl);
+        self.announce(Invalidation::Key(key.to_string())).await;
+        Ok(())
+    }
+
+    /// Atomic as far as the second tier is
+    async fn add<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let value =
+            serde_json::to_value(value).map_err(|e| CacheError::Serialization(e.to_string()))?;
+
+        if !self.l2.add(key, &value, ttl).await? {
+            return Ok(false);
+        }
+        self.fill_l1(key, value, ttl);
+        self.announce(Invalidation::Key(key.to_string())).await;
+        Ok(true)
+    }
+
+    /// Atomic as far as the second tier is
+    async fn compare_and_set<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let current =
+            serde_json::to_value(current).map_err(|e| CacheError::Serialization(e.to_string()))?;
+        let value =
+            serde_json::to_value(value).map_err(|e| CacheError::Serialization(e.to_string()))?;
+
+        if !self.l2.compare_and_set(key, &current, &value, ttl).await? {
+            return Ok(false);
+        }
+        self.fill_l1(key, value, ttl);
+        self.announce(Invalidation::Key(key.to_string())).await;
+        Ok(true)
+    }
+
+    /// Atomic as far as the second tier is
+    async fn compare_and_delete<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+    ) -> CacheResult<bool> {
+        let current =
+            serde_json::to_value(current).map_err(|e| CacheError::Serialization(e.to_string()))?;
+
+        if !self.l2.compare_and_delete(key, &current).await? {
+            return Ok(false);
+        }
+        self.l1().invalidate(key);
+        self.announce(Invalidation::Key(key.to_string())).await;
+        Ok(true)
+    }
+
+    async fn delete(&self, key: &str) -> CacheResult<()> {
+        self.l2.delete(key).await?;
+        self.l1().invalidate(key);
+        self.announce(Invalidation::Key(key.to_string())).await;
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> CacheResult<bool> {
+        if self.l1().contains(key) {
+            return Ok(true);
+        }
+        self.l2.exists(key).await
+    }
+
+    async fn ttl(&self, key: &str) -> CacheResult<Option<Duration>> {
+        self.l2.ttl(key).await
+    }
+
+    async fn ttl_many(&self, keys: &[&str]) -> CacheResult<HashMap<String, Duration>> {
+        self.l2.ttl_many(keys).await
+    }
+
+    async fn flush(&self) -> CacheResult<()> {
+        self.l2.flush().await?;
+        self.l1().clear();
+        self.announce(Invalidation::Flush).await;
+        Ok(())
+    }
+
+    async fn get_many<T: DeserializeOwned + Send>(
+        &self,
+        keys: &[&str],
+    ) -> CacheResult<HashMap<String, T>> {
+        let mut found = HashMap::with_capacity(keys.len());
+        let mut misses = Vec::new();
+        let generation = {
+            let mut l1 = self.l1();
+            for key in keys {
+                match l1.get(key) {
+                    Some(value) => {
+                        found.insert(key.to_string(), value);
+                    }
+                    None => misses.push(*key),
+                }
+            }
+            l1.generation
+        };
+
+        let l1_hits = found.len() as u64;
+        self.counters.l1_hits.fetch_add(l1_hits, Ordering::Relaxed);
+        self.counters.l1_misses.fetch_add(misses.len() as u64, Ordering::Relaxed);
+
+        if !misses.is_empty() {
+            let from_l2 = self.l2.get_many::<serde_json::Value>(&misses).await?;
+            let l2_hits = from_l2.len() as u64;
+            self.counters.l2_hits.fetch_add(l2_hits, Ordering::Relaxed);
+            self.counters
+                .l2_misses
+                .fetch_add(misses.len() as u64 - l2_hits, Ordering::Relaxed);
+
+            self.backfill_many(&from_l2, generation).await;
+            found.extend(from_l2);
+        }
+
+        found
+            .into_iter()
+            .map(|(key, value)| {
+                T::deserialize(&value)
+                    .map(|value| (key, value))
+                    .map_err(|e| CacheError::Deserialization(e.to_string()))
+            })
+            .collect()
+    }
+
+    async fn set_many<T: Serialize + Sync>(
+        &self,
+        items: &[(&str, T)],
+        ttl: Duration,
+    ) -> CacheResult<()> {
+        let values = items
+            .iter()
+            .map(|(key, value)| {
+                serde_json::to_value(value)
+                    .map(|value| (*key, value))
+                    .map_err(|e| CacheError::Serialization(e.to_string()))
+            })
+            .collect::<CacheResult<Vec<_>>>()?;
+
+        self.l2.set_many(&values, ttl).await?;
+        let keys = values.iter().map(|(key, _)| key.to_string()).collect();
+        for (key, value) in values {
+            self.fill_l1(key, value, ttl);
+        }
+        self.announce(Invalidation::Keys(keys)).await;
+        Ok(())
+    }
+
+    async fn delete_many(&self, keys: &[&str]) -> CacheResult<()> {
+        self.l2.delete_many(keys).await?;
+        {
+            let mut l1 = self.l1();
+            for key in keys {
+                l1.invalidate(key);
+            }
+        }
+        self.announce(Invalidation::Keys(
+            keys.iter().map(|key| key.to_string()).collect(),
+        ))
+        .await;
+        Ok(())
+    }
+}
+
+/// Apply peers' invalidations to L1 until the cache is dropped
+async fn listen(
+    mut receiver: broadcast::Receiver<InvalidationMessage>,
+    origin: String,
+    l1: Weak<Mutex<Lru>>,
+    counters: Arc<Counters>,
+) {
+    loop {
+        let message = match receiver.recv().await {
+            Ok(message) => message,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                // Can't tell which keys were missed, so nothing in L1 is trustworthy
+                tracing::warn!(missed, "Cache invalidations lagged, clearing L1");
+                InvalidationMessage {
+                    origin: String::new(),
+                    invalidation: Invalidation::Flush,
+                }
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+
+        if message.origin == origin {
+            continue;
+        }
+
+        let Some(l1) = l1.upgrade() else {
+            return;
+        };
+        let mut l1 = l1.lock().unwrap_or_else(|e| e.into_inner());
+        match &message.invalidation {
+            Invalidation::Key(key) => l1.invalidate(key),
+            Invalidation::Keys(keys) => keys.iter().for_each(|key| l1.invalidate(key)),
+            Invalidation::Flush => l1.clear(),
+        }
+        counters.invalidations.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryCache;
+
+    #[tokio::test]
+    async fn test_reads_fill_l1() {
+        let l2 = MemoryCache::new();
+        l2.set("key", &"value", Duration::from_secs(60)).await.unwrap();
+        let cache = TieredCache::new(l2);
+
+        let value: Option<String> = cache.get("key").await.unwrap();
+        assert_eq!(value, Some("value".to_string()));
+        let value: Option<String> = cache.get("key").await.unwrap();
+        assert_eq!(value, Some("value".to_string()));
+        let missing: Option<String> = cache.get("missing").await.unwrap();
+        assert_eq!(missing, None);
+
+        let stats = cache.stats();
+        assert_eq!(stats.l1, TierStats { hits: 1, misses: 2 });
+        assert_eq!(stats.l2, TierStats { hits: 1, misses: 1 });
+    }
+
+    #[tokio::test]
+    async fn test_l1_copies_expire_with_l2() {
+        let l2 = MemoryCache::new();
+        l2.set("short", &1, Duration::from_millis(50)).await.unwrap();
+        l2.set("many", &2, Duration::from_millis(50)).await.unwrap();
+        let cache = TieredCache::new(l2).l1_ttl(Duration::from_secs(60));
+
+        assert_eq!(cache.get::<i32>("short").await.unwrap(), Some(1));
+        let values: HashMap<String, i32> = cache.get_many(&["many"]).await.unwrap();
+        assert_eq!(values.len(), 1);
+        assert_eq!(cache.stats().l1_entries, 2);
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert_eq!(cache.get::<i32>("short").await.unwrap(), None);
+        let values: HashMap<String, i32> = cache.get_many(&["many"]).await.unwrap();
+        assert!(values.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_many_spans_tiers() {
+        let l2 = MemoryCache::new();
+        l2.set("cold", &2, Duration::from_secs(60)).await.unwrap();
+        let cache = TieredCache::new(l2);
+        cache.set("hot", &1, Duration::from_secs(60)).await.unwrap();
+
+        let values: HashMap<String, i32> =
+            cache.get_many(&["hot", "cold", "missing"]).await.unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values["cold"], 2);
+
+        let stats = cache.stats();
+        assert_eq!(stats.l1, TierStats { hits: 1, misses: 2 });
+        assert_eq!(stats.l2, TierStats { hits: 1, misses: 1 });
+        assert_eq!(stats.l1_entries, 2);
+    }
+
+    #[tokio::test]
+    async fn test_l1_is_bounded() {
+        let cache = TieredCache::new(MemoryCache::new()).capacity(2);
+
+        for key in ["a", "b", "c"] {
+            cache.set(key, &key, Duration::from_secs(60)).await.unwrap();
+        }
+
+        let stats = cache.stats();
+        assert_eq!(stats.l1_entries, 2);
+        assert_eq!(stats.evictions, 1);
+
+        // Evicted from L1 but still served by L2
+        let value: Option<String> = cache.get("a").await.unwrap();
+        assert_eq!(value, Some("a".to_string()));
+        assert_eq!(cache.stats().l2.hits, 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalidation_bus_evicts_peers() {
+        let l2 = MemoryCache::new();
+        let bus = Arc::new(LocalInvalidationBus::new());
+        let a = TieredCache::new(l2.clone()).with_bus(bus.clone());
+        let b = TieredCache::new(l2).with_bus(bus);
+
+        a.set("key", &1, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(b.get::<i32>("key").await.unwrap(), Some(1));
+
+        a.set("key", &2, Duration::from_secs(60)).await.unwrap();
+        tokio::task::yield_now().await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        assert_eq!(b.get::<i32>("key").await.unwrap(), Some(2));
+        assert_eq!(b.stats().invalidations, 2);
+        assert_eq!(a.stats().invalidations, 0);
+    }
+
+    #[tokio::test]
+    async fn test_batch_writes_announce_once() {
+        let l2 = MemoryCache::new();
+        let bus = Arc::new(LocalInvalidationBus::new());
+        let a = TieredCache::new(l2.clone()).with_bus(bus.clone());
+        let b = TieredCache::new(l2).with_bus(bus);
+        let ttl = Duration::from_secs(60);
+
+        a.set_many(&[("x", 1), ("y", 2)], ttl).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let values: HashMap<String, i32> = b.get_many(&["x", "y"]).await.unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(b.stats().l1_entries, 2);
+
+        a.set_many(&[("x", 3), ("y", 4)], ttl).await.unwrap();
+        a.delete_many(&["x"]).await.unwrap();
+        tokio::task::yield_now().await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        assert_eq!(b.stats().invalidations, 3);
+        let values: HashMap<String, i32> = b.get_many(&["x", "y"]).await.unwrap();
+        assert_eq!(values.get("y"), Some(&4));
+        assert!(!values.contains_key("x"));
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_set_goes_through_l2() {
+        let l2 = MemoryCache::new();
+        let cache = TieredCache::new(l2.clone());
+        let ttl = Duration::from_secs(60);
+
+        cache.set("lock", &"a", ttl).await.unwrap();
+        // Another instance took over the key behind this L1
+        l2.set("lock", &"b", ttl).await.unwrap();
+
+        assert!(!cache.compare_and_set("lock", &"a", &"c", ttl).await.unwrap());
+        assert!(cache.compare_and_set("lock", &"b", &"c", ttl).await.unwrap());
+        assert_eq!(cache.get::<String>("lock").await.unwrap().unwrap(), "c");
+
+        assert!(!cache.compare_and_delete("lock", &"b").await.unwrap());
+        assert!(cache.compare_and_delete("lock", &"c").await.unwrap());
+        assert_eq!(cache.get::<String>("lock").await.unwrap(), None);
+    }
+
+    /// L2 that is slow or failing to report TTLs, so writes can land
+    /// between a read and its L1 copy
+    struct SlowTtl {
+        inner: MemoryCache,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Cache for SlowTtl {
+        async fn get<T: DeserializeOwned + Send>(&self, key: &str) -> CacheResult<Option<T>> {
+            self.inner.get(key).await
+        }
+
+        async fn set<T: Serialize + Sync>(
+            &self,
+            key: &str,
+            value: &T,
+            ttl: Duration,
+        ) -> CacheResult<()> {
+            self.inner.set(key, value, ttl).await
+        }
+
+        async fn delete(&self, key: &str) -> CacheResult<()> {
+            self.inner.delete(key).await
+        }
+
+        async fn exists(&self, key: &str) -> CacheResult<bool> {
+            self.inner.exists(key).await
+        }
+
+        async fn ttl(&self, key: &str) -> CacheResult<Option<Duration>> {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            if self.fail {
+                return Err(CacheError::Backend("connection reset".into()));
+            }
+            self.inner.ttl(key).await
+        }
+
+        async fn flush(&self) -> CacheResult<()> {
+            self.inner.flush().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reads_racing_writes_dont_fill_l1() {
+        let l2 = MemoryCache::new();
+        let ttl = Duration::from_secs(60);
+        l2.set("key", &"old", ttl).await.unwrap();
+        l2.set("other", &"old", ttl).await.unwrap();
+        let cache = TieredCache::new(SlowTtl {
+            inner: l2,
+            fail: false,
+        });
+
+        let write = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            cache.set("key", &"new", ttl).await.unwrap();
+        };
+        let (read, ()) = tokio::join!(cache.get::<String>("key"), write);
+        assert_eq!(read.unwrap().as_deref(), Some("old"));
+        assert_eq!(cache.get::<String>("key").await.unwrap().as_deref(), Some("new"));
+
+        let invalidate = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            cache.evict_local("other");
+        };
+        let (read, ()) = tokio::join!(cache.get_many::<String>(&["other"]), invalidate);
+        assert_eq!(read.unwrap()["other"], "old");
+        assert_eq!(cache.stats().l1_entries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failing_ttl_still_returns_reads() {
+        let l2 = MemoryCache::new();
+        l2.set("key", &1, Duration::from_secs(60)).await.unwrap();
+        let cache = TieredCache::new(SlowTtl {
+            inner: l2,
+            fail: true,
+        });
+
+        assert_eq!(cache.get::<i32>("key").await.unwrap(), Some(1));
+        let values: HashMap<String, i32> = cache.get_many(&["key"]).await.unwrap();
+        assert_eq!(values["key"], 1);
+        assert_eq!(cache.stats().l1_entries, 0);
+    }
+}
@@ -0,0 +1,367 @@
+//! Case conversion and inflection
+//!
+//! Names are split into words at `_`, `-`, whitespace and case changes.
+//! A run of capitals is one word, so acronyms survive:
+//! `HTTPRequest` is `http_request`, `IOError` is `io_error`. Digits stay
+//! with the word before them (`Oauth2Client` is `oauth2_client`).
+//!
+//! The same conversions are available in templates as the `snake_case`,
+//! `kebab_case`, `screaming_snake_case`, `camel_case`, `pascal_case`,
+//! `plural` and `singular` helpers.
+
+use handlebars::{handlebars_helper, Handlebars};
+
+/// Words of an identifier, e.g. `["HTTP", "Request"]` for `HTTPRequest`
+pub fn words(s: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut chars = s.char_indices().peekable();
+    let mut prev: Option<char> = None;
+
+    while let Some((i, c)) = chars.next() {
+        if !c.is_alphanumeric() {
+            if let Some(begin) = start.take() {
+                words.push(&s[begin..i]);
+            }
+            prev = None;
+            continue;
+        }
+
+        if let (Some(begin), Some(p)) = (start, prev) {
+            let next_lower = chars.peek().is_some_and(|(_, n)| n.is_lowercase());
+            let boundary = c.is_uppercase()
+                && (p.is_lowercase() || p.is_numeric() || (p.is_uppercase() && next_lower));
+            if boundary {
+                words.push(&s[begin..i]);
+                start = Some(i);
+            }
+        }
+        start.get_or_insert(i);
+        prev = Some(c);
+    }
+    if let Some(begin) = start {
+        words.push(&s[begin..]);
+    }
+    words
+}
+
+/// `HTTPRequest` → `http_request`
+pub fn to_snake_case(s: &str) -> String {
+    join_lower(s, "_")
+}
+
+/// `HTTPRequest` → `http-request`
+pub fn to_kebab_case(s: &str) -> String {
+    join_lower(s, "-")
+}
+
+/// `HTTPRequest` → `HTTP_REQUEST`
+pub fn to_screaming_snake_case(s: &str) -> String {
+    words(s)
+        .iter()
+        .map(|word| word.to_uppercase())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// `http_request` → `HttpRequest`
+pub fn to_pascal_case(s: &str) -> String {
+    words(s).into_iter().map(capitalize).collect()
+}
+
+/// `http_request` → `httpRequest`
+pub fn to_camel_case(s: &str) -> String {
+    words(s)
+        .into_iter()
+        .enumerate()
+        .map(|(i, word)| {
+            if i == 0 {
+                word.to_lowercase()
+            } else {
+                capitalize(word)
+            }
+        })
+        .collect()
+}
+
+/// Table of a model: `BlogPost` is stored in `blog_posts`
+pub fn to_table_name(model: &str) -> String {
+    pluralize(&to_snake_case(model))
+}
+
+/// Model of a table: `blog_posts` holds `BlogPost`s
+pub fn to_model_name(table: &str) -> String {
+    to_pascal_case(&singularize(&to_snake_case(table)))
+}
+
+/// Plural of the last word: `category` → `categories`, `blog_person` →
+/// `blog_people`
+///
+/// Words that already look plural are left alone.
+pub fn pluralize(s: &str) -> String {
+    inflect(s, |word| {
+        if let Some((_, plural)) = IRREGULAR.iter().find(|(singular, _)| *singular == word) {
+            return plural.to_string();
+        }
+        if IRREGULAR.iter().any(|(_, plural)| *plural == word) {
+            return word.to_string();
+        }
+
+        if let Some(stem) = word.strip_suffix("is") {
+            return format!("{}es", stem);
+        }
+        if ["ss", "us", "x", "z", "ch", "sh"]
+            .iter()
+            .any(|suffix| word.ends_with(suffix))
+        {
+            return format!("{}es", word);
+        }
+        if word.ends_with('s') {
+            return word.to_string();
+        }
+        if let Some(stem) = word.strip_suffix('y') {
+            if !stem.ends_with(VOWELS) {
+                return format!("{}ies", stem);
+            }
+        }
+        format!("{}s", word)
+    })
+}
+
+/// Singular of the last word: `categories` → `category`, `blog_people` →
+/// `blog_person`
+pub fn singularize(s: &str) -> String {
+    inflect(s, |word| {
+        if let Some((singular, _)) = IRREGULAR.iter().find(|(_, plural)| *plural == word) {
+            return singular.to_string();
+        }
+        if IRREGULAR.iter().any(|(singular, _)| *singular == word) {
+            return word.to_string();
+        }
+
+        for (suffix, replacement) in [
+            ("sses", "ss"),
+            ("uses", "us"),
+            ("xes", "x"),
+            ("zzes", "zz"),
+            ("ches", "ch"),
+            ("shes", "sh"),
+            ("ies", "y"),
+        ] {
+            if let Some(stem) = word.strip_suffix(suffix) {
+                return format!("{}{}", stem, replacement);
+            }
+        }
+        if word.ends_with("ss") || word.ends_with("us") || word.ends_with("is") {
+            return word.to_string();
+        }
+        word.strip_suffix('s').unwrap_or(word).to_string()
+    })
+}
+
+/// Register the conversions as template helpers
+pub fn register_helpers(handlebars: &mut Handlebars) {
+    handlebars_helper!(snake_case: |s: str| to_snake_case(s));
+    handlebars_helper!(kebab_case: |s: str| to_kebab_case(s));
+    handlebars_helper!(screaming_snake_case: |s: str| to_screaming_snake_case(s));
+    handlebars_helper!(camel_case: |s: str| to_camel_case(s));
+    handlebars_helper!(pascal_case: |s: str| to_pascal_case(s));
+    handlebars_helper!(plural: |s: str| pluralize(s));
+    handlebars_helper!(singular: |s: str| singularize(s));
+
+    handlebars.register_helper("snake_case", Box::new(snake_case));
+    handlebars.register_helper("kebab_case", Box::new(kebab_case));
+    handlebars.register_helper("screaming_snake_case", Box::new(screaming_snake_case));
+    handlebars.register_helper("camel_case", Box::new(camel_case));
+    handlebars.register_helper("pascal_case", Box::new(pascal_case));
+    handlebars.register_helper("plural", Box::new(plural));
+    handlebars.register_helper("singular", Box::new(singular));
+}
+
+const VOWELS: [char; 5] = ['a', 'e', 'i', 'o', 'u'];
+
+/// Singular and plural forms that don't follow the suffix rules; words
+/// with the same form for both are uncountable
+const IRREGULAR: &[(&str, &str)] = &[
+    ("person", "people"),
+    ("man", "men"),
+    ("woman", "women"),
+    ("child", "children"),
+    ("tooth", "teeth"),
+    ("foot", "feet"),
+    ("mouse", "mice"),
+    ("goose", "geese"),
+    ("ox", "oxen"),
+    ("criterion", "criteria"),
+    ("index", "indices"),
+    ("matrix", "matrices"),
+    ("vertex", "vertices"),
+    ("quiz", "quizzes"),
+    ("movie", "movies"),
+    ("cookie", "cookies"),
+    ("house", "houses"),
+    ("cause", "causes"),
+    ("course", "courses"),
+    ("response", "responses"),
+    ("cache", "caches"),
+    ("knife", "knives"),
+    ("wife", "wives"),
+    ("life", "lives"),
+    ("half", "halves"),
+    ("shelf", "shelves"),
+    ("wolf", "wolves"),
+    ("leaf", "leaves"),
+    ("thief", "thieves"),
+    ("archive", "archives"),
+    ("sheep", "sheep"),
+    ("fish", "fish"),
+    ("deer", "deer"),
+    ("series", "series"),
+    ("species", "species"),
+    ("news", "news"),
+    ("information", "information"),
+    ("equipment", "equipment"),
+    ("metadata", "metadata"),
+    ("media", "media"),
+    ("feedback", "feedback"),
+];
+
+/// Apply `f` to the lowercased last word of `s`, keeping the rest and the
+/// word's leading capital
+fn inflect(s: &str, f: impl Fn(&str) -> String) -> String {
+    let tail = s
+        .rfind(|c: char| !c.is_alphanumeric())
+        .map_or(s, |i| &s[i + 1..]);
+    // Last word of a Pascal or camel case name, e.g. `Person` in `BlogPerson`
+    let Some(word) = words(tail).pop() else {
+        return s.to_string();
+    };
+    let head = &s[..s.len() - word.len()];
+
+    let inflected = f(&word.to_lowercase());
+    let inflected = if word.chars().all(|c| !c.is_lowercase()) && word.len() > 1 {
+        inflected.to_uppercase()
+    } else if word.starts_with(char::is_uppercase) {
+        capitalize(&inflected)
+    } else {
+        inflected
+    };
+    format!("{}{}", head, inflected)
+}
+
+fn join_lower(s: &str, separator: &str) -> String {
+    words(s)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words() {
+        assert_eq!(words("HTTPRequest"), ["HTTP", "Request"]);
+        assert_eq!(words("userID"), ["user", "ID"]);
+        assert_eq!(words("Oauth2Client"), ["Oauth2", "Client"]);
+        assert_eq!(words("  post--controller_"), ["post", "controller"]);
+        assert!(words("").is_empty());
+    }
+
+    #[test]
+    fn test_to_snake_case() {
+        assert_eq!(to_snake_case("UserModel"), "user_model");
+        assert_eq!(to_snake_case("PostController"), "post_controller");
+        assert_eq!(to_snake_case("HTTPRequest"), "http_request");
+        assert_eq!(to_snake_case("IOError"), "io_error");
+        assert_eq!(to_snake_case("already_snake"), "already_snake");
+        assert_eq!(to_snake_case("kebab-case"), "kebab_case");
+    }
+
+    #[test]
+    fn test_to_pascal_case() {
+        assert_eq!(to_pascal_case("user_model"), "UserModel");
+        assert_eq!(to_pascal_case("post-controller"), "PostController");
+        assert_eq!(to_pascal_case("my_test_name"), "MyTestName");
+        assert_eq!(to_pascal_case("UserAccount"), "UserAccount");
+        assert_eq!(to_pascal_case("HTTPRequest"), "HttpRequest");
+    }
+
+    #[test]
+    fn test_other_cases() {
+        assert_eq!(to_kebab_case("HTTPRequest"), "http-request");
+        assert_eq!(to_screaming_snake_case("maxRetryCount"), "MAX_RETRY_COUNT");
+        assert_eq!(to_camel_case("user_id"), "userId");
+        assert_eq!(to_camel_case("HTTPRequest"), "httpRequest");
+    }
+
+    #[test]
+    fn test_pluralize() {
+        assert_eq!(pluralize("post"), "posts");
+        assert_eq!(pluralize("category"), "categories");
+        assert_eq!(pluralize("day"), "days");
+        assert_eq!(pluralize("address"), "addresses");
+        assert_eq!(pluralize("status"), "statuses");
+        assert_eq!(pluralize("box"), "boxes");
+        assert_eq!(pluralize("branch"), "branches");
+        assert_eq!(pluralize("analysis"), "analyses");
+        assert_eq!(pluralize("knife"), "knives");
+        assert_eq!(pluralize("shelf"), "shelves");
+        assert_eq!(pluralize("blog_person"), "blog_people");
+        assert_eq!(pluralize("BlogPerson"), "BlogPeople");
+        assert_eq!(pluralize("sheep"), "sheep");
+        assert_eq!(pluralize("posts"), "posts");
+        assert_eq!(pluralize("people"), "people");
+    }
+
+    #[test]
+    fn test_singularize() {
+        assert_eq!(singularize("posts"), "post");
+        assert_eq!(singularize("categories"), "category");
+        assert_eq!(singularize("addresses"), "address");
+        assert_eq!(singularize("statuses"), "status");
+        assert_eq!(singularize("boxes"), "box");
+        assert_eq!(singularize("knives"), "knife");
+        assert_eq!(singularize("shelves"), "shelf");
+        assert_eq!(singularize("movies"), "movie");
+        assert_eq!(singularize("blog_people"), "blog_person");
+        assert_eq!(singularize("status"), "status");
+        assert_eq!(singularize("post"), "post");
+    }
+
+    #[test]
+    fn test_table_and_model_names() {
+        assert_eq!(to_table_name("BlogPost"), "blog_posts");
+        assert_eq!(to_table_name("Category"), "categories");
+        assert_eq!(to_table_name("HTTPLog"), "http_logs");
+        assert_eq!(to_model_name("blog_posts"), "BlogPost");
+        assert_eq!(to_model_name("categories"), "Category");
+    }
+
+    #[test]
+    fn test_helpers() {
+        let mut handlebars = Handlebars::new();
+        register_helpers(&mut handlebars);
+
+        let rendered = handlebars
+            .render_template(
+                "{{snake_case name}} {{pascal_case (singular table)}} {{plural (kebab_case name)}}",
+                &serde_json::json!({"name": "HTTPRequest", "table": "categories"}),
+            )
+            .unwrap();
+        assert_eq!(rendered, "http_request Category http-requests");
+    }
+}
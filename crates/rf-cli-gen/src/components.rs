@@ -0,0 +1,667 @@
+//! Application component generators
+//!
+//! Skeletons for queue jobs, events and their listeners, tower middleware,
+//! notifications and authorization policies. Every kind appends its suffix
+//! to the type and file names, whether or not the given name already ends
+//! with it:
+//!
+//! ```text
+//! make:job send_welcome_email       src/jobs/send_welcome_email_job.rs                SendWelcomeEmailJob
+//! make:event user_registered        src/events/user_registered_event.rs              UserRegisteredEvent
+//! make:listener send_welcome_email  src/listeners/send_welcome_email_listener.rs      SendWelcomeEmailListener
+//! make:middleware audit             src/middleware/audit_middleware.rs                AuditMiddleware, AuditLayer
+//! make:notification invoice_paid    src/notifications/invoice_paid_notification.rs    InvoicePaidNotification
+//! make:policy post                  src/policies/post_policy.rs                       PostPolicy
+//! ```
+//!
+//! Each generated file carries a test module exercising the skeleton. Jobs,
+//! events and notifications get the config's fields as their payload.
+//! Listeners handle `crate::events::{event}_event` where `event` comes from
+//! the config data and defaults to the listener's name; policies guard
+//! `crate::models::{model}` for `crate::models::{user}`, defaulting to the
+//! policy's name and `user`.
+
+use crate::{
+    case, to_pascal_case, to_snake_case, wire, write_file, GeneratorConfig, GeneratorError,
+    GeneratorResult, TemplateData,
+};
+use handlebars::Handlebars;
+use std::path::PathBuf;
+
+/// Kind of component to generate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentKind {
+    /// `rf_jobs::Job`
+    Job,
+    /// `rf_events::Event`
+    Event,
+    /// `rf_events::EventListenerFor` an event
+    Listener,
+    /// tower `Layer` and `Service` pair
+    Middleware,
+    /// `rf_notifications::Notification`
+    Notification,
+    /// Authorization rules for a model
+    Policy,
+}
+
+impl ComponentKind {
+    /// All kinds
+    pub const ALL: [Self; 6] = [
+        Self::Job,
+        Self::Event,
+        Self::Listener,
+        Self::Middleware,
+        Self::Notification,
+        Self::Policy,
+    ];
+
+    /// Type name suffix, e.g. `Job`
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Job => "Job",
+            Self::Event => "Event",
+            Self::Listener => "Listener",
+            Self::Middleware => "Middleware",
+            Self::Notification => "Notification",
+            Self::Policy => "Policy",
+        }
+    }
+
+    /// Conventional module directory below `src`, e.g. `jobs`
+    pub fn dir(self) -> &'static str {
+        match self {
+            Self::Job => "jobs",
+            Self::Event => "events",
+            Self::Listener => "listeners",
+            Self::Middleware => "middleware",
+            Self::Notification => "notifications",
+            Self::Policy => "policies",
+        }
+    }
+
+    fn template(self) -> &'static str {
+        match self {
+            Self::Job => "job",
+            Self::Event => "event",
+            Self::Listener => "listener",
+            Self::Middleware => "middleware",
+            Self::Notification => "notification",
+            Self::Policy => "policy",
+        }
+    }
+}
+
+/// Snake case name with the kind's suffix, and its Pascal case base name
+///
+/// `SendEmail`, `send_email` and `send_email_job` all give
+/// `("send_email_job", "SendEmail")` for jobs.
+fn component_names(name: &str, kind: ComponentKind) -> (String, String) {
+    let suffix = format!("_{}", kind.suffix().to_lowercase());
+    let snake = to_snake_case(name);
+    let base = snake.strip_suffix(&suffix).unwrap_or(&snake);
+    (format!("{}{}", base, suffix), to_pascal_case(base))
+}
+
+/// Generator for jobs, events, listeners, middleware, notifications and
+/// policies
+pub struct ComponentGenerator {
+    handlebars: Handlebars<'static>,
+    kind: ComponentKind,
+}
+
+impl ComponentGenerator {
+    /// Create a generator for one kind of component
+    pub fn new(kind: ComponentKind) -> Self {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(handlebars::no_escape);
+        case::register_helpers(&mut handlebars);
+
+        handlebars
+            .register_template_string(
+                "job",
+                r#"
+//! {{pascal_name}}
+//! Generated at {{timestamp}}
+
+use async_trait::async_trait;
+use rf_jobs::{Job, JobContext, JobResult};
+use serde::{Deserialize, Serialize};
+
+/// Queued job
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct {{pascal_name}} {
+{{#each fields}}
+    pub {{name}}: {{rust_type}},
+{{/each}}
+}
+
+impl {{pascal_name}} {
+    /// Create a new job
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Job for {{pascal_name}} {
+    async fn handle(&self, ctx: JobContext) -> JobResult {
+        ctx.log("Handling {{snake_name}}");
+        // TODO: Implement
+        Ok(())
+    }
+
+    fn queue(&self) -> &str {
+        "default"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_{{snake_name}}_handle() {
+        let job = {{pascal_name}}::new();
+        let ctx = JobContext::new(
+            uuid::Uuid::new_v4(),
+            job.queue().to_string(),
+            1,
+            job.max_attempts(),
+            chrono::Utc::now(),
+        );
+        assert!(job.handle(ctx).await.is_ok());
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        handlebars
+            .register_template_string(
+                "event",
+                r#"
+//! {{pascal_name}}
+//! Generated at {{timestamp}}
+
+use rf_events::Event;
+use serde::{Deserialize, Serialize};
+
+/// Domain event
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct {{pascal_name}} {
+{{#each fields}}
+    pub {{name}}: {{rust_type}},
+{{/each}}
+}
+
+impl Event for {{pascal_name}} {
+    fn name(&self) -> &'static str {
+        "{{base_snake}}"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rf_events::EventDispatcher;
+
+    #[tokio::test]
+    async fn test_{{snake_name}}_dispatch() {
+        let event = {{pascal_name}}::default();
+        assert_eq!(event.name(), "{{base_snake}}");
+
+        let dispatcher = EventDispatcher::new();
+        assert!(dispatcher.dispatch(event).await.is_ok());
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        handlebars
+            .register_template_string(
+                "listener",
+                r#"
+//! {{pascal_name}}
+//! Generated at {{timestamp}}
+
+use crate::events::{{event_module}}::{{event}};
+use async_trait::async_trait;
+use rf_events::{EventListenerFor, EventResult};
+
+/// Listener for [`{{event}}`]
+#[derive(Debug, Clone, Default)]
+pub struct {{pascal_name}};
+
+impl {{pascal_name}} {
+    /// Create a new listener
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl EventListenerFor<{{event}}> for {{pascal_name}} {
+    async fn handle(&self, _event: &{{event}}) -> EventResult<()> {
+        // TODO: Implement
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rf_events::EventDispatcher;
+
+    #[tokio::test]
+    async fn test_{{snake_name}}_handle() {
+        let listener = {{pascal_name}}::new();
+        assert!(listener.handle(&{{event}}::default()).await.is_ok());
+
+        let dispatcher = EventDispatcher::new();
+        dispatcher.listen::<{{event}}, _>(listener).await;
+        assert!(dispatcher.dispatch({{event}}::default()).await.is_ok());
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        handlebars
+            .register_template_string(
+                "middleware",
+                r#"
+//! {{pascal_name}}
+//! Generated at {{timestamp}}
+
+use axum::{extract::Request, response::Response};
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Layer applying [`{{pascal_name}}`]
+#[derive(Debug, Clone, Default)]
+pub struct {{base_name}}Layer;
+
+impl {{base_name}}Layer {
+    /// Create a new layer
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for {{base_name}}Layer {
+    type Service = {{pascal_name}}<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        {{pascal_name}} { inner }
+    }
+}
+
+/// Middleware service
+#[derive(Debug, Clone)]
+pub struct {{pascal_name}}<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for {{pascal_name}}<S>
+where
+    S: Service<Request, Response = Response>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // TODO: Inspect or modify the request
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_{{snake_name}}_passes_through() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer({{base_name}}Layer::new());
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        handlebars
+            .register_template_string(
+                "notification",
+                r#"
+//! {{pascal_name}}
+//! Generated at {{timestamp}}
+
+use rf_notifications::{
+    Channel, MailMessage, Notifiable, Notification, NotificationError, NotificationResult,
+};
+
+/// Notification
+#[derive(Debug, Clone, Default)]
+pub struct {{pascal_name}} {
+{{#each fields}}
+    pub {{name}}: {{rust_type}},
+{{/each}}
+}
+
+impl Notification for {{pascal_name}} {
+    fn via(&self, _notifiable: &dyn Notifiable) -> Vec<Channel> {
+        vec![Channel::Email]
+    }
+
+    fn to_mail(&self, notifiable: &dyn Notifiable) -> NotificationResult<MailMessage> {
+        let email = notifiable.email().ok_or_else(|| {
+            NotificationError::RoutingError(format!("{} has no email address", notifiable.id()))
+        })?;
+
+        // TODO: Write the message
+        Ok(MailMessage::new()
+            .to(email)
+            .subject("{{base_name}}")
+            .body(""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Recipient;
+
+    impl Notifiable for Recipient {
+        fn email(&self) -> Option<String> {
+            Some("user@example.com".to_string())
+        }
+
+        fn id(&self) -> String {
+            "1".to_string()
+        }
+    }
+
+    #[test]
+    fn test_{{snake_name}}_to_mail() {
+        let notification = {{pascal_name}}::default();
+        assert_eq!(notification.via(&Recipient), vec![Channel::Email]);
+
+        let mail = notification.to_mail(&Recipient).unwrap();
+        assert_eq!(mail.to, vec!["user@example.com".to_string()]);
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        handlebars
+            .register_template_string(
+                "policy",
+                r#"
+//! {{pascal_name}}
+//! Generated at {{timestamp}}
+
+use crate::models::{{model_module}}::{{model}};
+{{#unless same_model}}
+use crate::models::{{user_module}}::{{user}};
+{{/unless}}
+
+/// Authorization rules for [`{{model}}`]
+///
+/// Reads are allowed and writes denied until the rules are filled in.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct {{pascal_name}};
+
+impl {{pascal_name}} {
+    /// Whether the user may list records
+    pub fn view_any(&self, _user: &{{user}}) -> bool {
+        true
+    }
+
+    /// Whether the user may see a record
+    pub fn view(&self, _user: &{{user}}, _{{model_var}}: &{{model}}) -> bool {
+        true
+    }
+
+    /// Whether the user may create records
+    pub fn create(&self, _user: &{{user}}) -> bool {
+        false
+    }
+
+    /// Whether the user may update a record
+    pub fn update(&self, _user: &{{user}}, _{{model_var}}: &{{model}}) -> bool {
+        false
+    }
+
+    /// Whether the user may delete a record
+    pub fn delete(&self, _user: &{{user}}, _{{model_var}}: &{{model}}) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_{{snake_name}}_defaults() {
+        let policy = {{pascal_name}};
+        let user = {{user}}::default();
+        let {{model_var}} = {{model}}::default();
+
+        assert!(policy.view_any(&user));
+        assert!(policy.view(&user, &{{model_var}}));
+        assert!(!policy.create(&user));
+        assert!(!policy.update(&user, &{{model_var}}));
+        assert!(!policy.delete(&user, &{{model_var}}));
+    }
+}
+"#,
+            )
+            .unwrap();
+
+        Self { handlebars, kind }
+    }
+
+    /// Kind of component this generator creates
+    pub fn kind(&self) -> ComponentKind {
+        self.kind
+    }
+
+    /// Generate a component file
+    pub async fn generate(&self, config: GeneratorConfig) -> GeneratorResult<PathBuf> {
+        let (snake_name, base_name) = component_names(&config.name, self.kind);
+        let mut data = TemplateData::from_config(&config);
+        data.pascal_name = to_pascal_case(&snake_name);
+        data.snake_name = snake_name;
+
+        let mut value =
+            serde_json::to_value(&data).map_err(|e| GeneratorError::Template(e.to_string()))?;
+        let base_snake = to_snake_case(&base_name);
+        let option = |key: &str| config.data.get(key).and_then(|v| v.as_str());
+
+        match self.kind {
+            ComponentKind::Listener => {
+                let event = option("event").unwrap_or(&base_snake);
+                let (module, _) = component_names(event, ComponentKind::Event);
+                value["event"] = to_pascal_case(&module).into();
+                value["event_module"] = module.into();
+            }
+            ComponentKind::Policy => {
+                let model = to_snake_case(option("model").unwrap_or(&base_snake));
+                let user = to_snake_case(option("user").unwrap_or("user"));
+                let model_var = if model == user { "record" } else { &model };
+                value["model"] = to_pascal_case(&model).into();
+                value["model_var"] = model_var.into();
+                value["same_model"] = (model == user).into();
+                value["user"] = to_pascal_case(&user).into();
+                value["user_module"] = user.into();
+                value["model_module"] = model.into();
+            }
+            _ => {}
+        }
+        value["base_name"] = base_name.into();
+        value["base_snake"] = base_snake.into();
+
+        let content = self
+            .handlebars
+            .render(self.kind.template(), &value)
+            .map_err(|e| GeneratorError::Template(e.to_string()))?;
+
+        let file_path = config.output_dir.join(format!("{}.rs", data.snake_name));
+
+        write_file(&file_path, &content, config.force).await?;
+        if config.wire {
+            wire::wire_module(&file_path).await?;
+        }
+        Ok(file_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Field;
+    use tokio::fs;
+
+    #[test]
+    fn test_component_names() {
+        assert_eq!(
+            component_names("send_email", ComponentKind::Job),
+            ("send_email_job".to_string(), "SendEmail".to_string())
+        );
+        assert_eq!(
+            component_names("send-email-job", ComponentKind::Job),
+            ("send_email_job".to_string(), "SendEmail".to_string())
+        );
+        assert_eq!(
+            component_names("SendEmailJob", ComponentKind::Job),
+            ("send_email_job".to_string(), "SendEmail".to_string())
+        );
+        assert_eq!(
+            component_names("audit", ComponentKind::Middleware),
+            ("audit_middleware".to_string(), "Audit".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_all_kinds() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src");
+        fs::create_dir_all(&src).await.unwrap();
+        fs::write(src.join("lib.rs"), "pub mod models;\n")
+            .await
+            .unwrap();
+
+        for kind in ComponentKind::ALL {
+            let config = GeneratorConfig::new("order_shipped", src.join(kind.dir()));
+            let path = ComponentGenerator::new(kind)
+                .generate(config)
+                .await
+                .unwrap();
+
+            let stem = format!("order_shipped_{}", kind.suffix().to_lowercase());
+            assert_eq!(path, src.join(kind.dir()).join(format!("{}.rs", stem)));
+
+            let content = fs::read_to_string(&path).await.unwrap();
+            assert!(content.contains(&format!("pub struct OrderShipped{}", kind.suffix())));
+            assert!(content.contains("#[cfg(test)]\nmod tests {"));
+
+            let module = fs::read_to_string(src.join(kind.dir()).join("mod.rs"))
+                .await
+                .unwrap();
+            assert_eq!(module, format!("pub mod {};\n", stem));
+        }
+
+        let lib = fs::read_to_string(src.join("lib.rs")).await.unwrap();
+        assert_eq!(
+            lib,
+            "pub mod models;\npub mod jobs;\npub mod events;\npub mod listeners;\npub mod middleware;\npub mod notifications;\npub mod policies;\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_job_fields() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = GeneratorConfig::new("send_invoice_job", temp_dir.path())
+            .with_fields(Field::parse_list("invoice_id:bigint email:string").unwrap());
+
+        let path = ComponentGenerator::new(ComponentKind::Job)
+            .generate(config)
+            .await
+            .unwrap();
+
+        let content = fs::read_to_string(&path).await.unwrap();
+        assert!(content.contains(
+            "pub struct SendInvoiceJob {\n    pub invoice_id: i64,\n    pub email: String,\n}"
+        ));
+        assert!(content.contains("impl Job for SendInvoiceJob {"));
+    }
+
+    #[tokio::test]
+    async fn test_listener_event() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = GeneratorConfig::new("send_welcome_email", temp_dir.path())
+            .with_data(serde_json::json!({"event": "user_registered"}))
+            .no_wire();
+
+        let path = ComponentGenerator::new(ComponentKind::Listener)
+            .generate(config)
+            .await
+            .unwrap();
+
+        let content = fs::read_to_string(&path).await.unwrap();
+        assert!(content.contains("use crate::events::user_registered_event::UserRegisteredEvent;"));
+        assert!(content
+            .contains("impl EventListenerFor<UserRegisteredEvent> for SendWelcomeEmailListener {"));
+        assert!(!temp_dir.path().join("mod.rs").exists());
+    }
+
+    #[tokio::test]
+    async fn test_policy_models() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let generator = ComponentGenerator::new(ComponentKind::Policy);
+
+        let path = generator
+            .generate(GeneratorConfig::new("blog_post", temp_dir.path()).no_wire())
+            .await
+            .unwrap();
+        let content = fs::read_to_string(&path).await.unwrap();
+        assert!(content
+            .contains("use crate::models::blog_post::BlogPost;\nuse crate::models::user::User;\n"));
+        assert!(content.contains(
+            "pub fn update(&self, _user: &User, _blog_post: &BlogPost) -> bool {\n        false"
+        ));
+
+        // A policy for the user model itself imports it once
+        let path = generator
+            .generate(
+                GeneratorConfig::new("account", temp_dir.path())
+                    .with_data(serde_json::json!({"model": "user"}))
+                    .no_wire(),
+            )
+            .await
+            .unwrap();
+        let content = fs::read_to_string(&path).await.unwrap();
+        assert_eq!(content.matches("use crate::models::user::User;").count(), 1);
+        assert!(content.contains("pub struct AccountPolicy;"));
+        assert!(content.contains("_record: &User"));
+    }
+}
@@ -2,6 +2,24 @@
 //!
 //! This crate provides code scaffolding and generation tools.
 
+pub mod case;
+mod components;
+mod migration;
+mod request;
+mod scaffold;
+mod schema;
+pub mod wire;
+
+pub use case::{
+    pluralize, singularize, to_camel_case, to_kebab_case, to_model_name, to_pascal_case,
+    to_screaming_snake_case, to_snake_case, to_table_name,
+};
+pub use components::{ComponentGenerator, ComponentKind};
+pub use migration::{Driver, Migration, MigrationGenerator, OnDelete};
+pub use request::RequestGenerator;
+pub use scaffold::{ScaffoldGenerator, ScaffoldOutput};
+pub use schema::{Field, FieldType};
+
 use handlebars::Handlebars;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
@@ -22,6 +40,12 @@ pub enum GeneratorError {
 
     #[error("Invalid name: {0}")]
     InvalidName(String),
+
+    #[error("Invalid field: {0}")]
+    InvalidField(String),
+
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
 }
 
 pub type GeneratorResult<T> = Result<T, GeneratorError>;
@@ -37,6 +61,16 @@ pub struct GeneratorConfig {
     pub data: serde_json::Value,
     /// Overwrite existing files
     pub force: bool,
+    /// Fields of the generated model or table
+    #[serde(default)]
+    pub fields: Vec<Field>,
+    /// Declare generated modules and register routes, see [`wire`]
+    #[serde(default = "default_wire")]
+    pub wire: bool,
+}
+
+fn default_wire() -> bool {
+    true
 }
 
 impl GeneratorConfig {
@@ -47,6 +81,8 @@ impl GeneratorConfig {
             output_dir: output_dir.into(),
             data: serde_json::json!({}),
             force: false,
+            fields: Vec::new(),
+            wire: true,
         }
     }
 
@@ -56,11 +92,23 @@ impl GeneratorConfig {
         self
     }
 
+    /// Set the fields, e.g. from [`Field::parse_list`]
+    pub fn with_fields(mut self, fields: Vec<Field>) -> Self {
+        self.fields = fields;
+        self
+    }
+
     /// Enable file overwriting
     pub fn force(mut self) -> Self {
         self.force = true;
         self
     }
+
+    /// Leave the module tree and router alone (`--no-wire`)
+    pub fn no_wire(mut self) -> Self {
+        self.wire = false;
+        self
+    }
 }
 
 /// Template data for generation
@@ -72,6 +120,10 @@ pub struct TemplateData {
     pub snake_name: String,
     /// Pascal case name
     pub pascal_name: String,
+    /// Database table, e.g. `blog_posts`
+    pub table_name: String,
+    /// Fields from the config
+    pub fields: Vec<TemplateField>,
     /// Timestamp
     pub timestamp: String,
     /// Custom data
@@ -87,6 +139,8 @@ impl TemplateData {
         let pascal_name = to_pascal_case(&name);
 
         Self {
+            table_name: to_table_name(&name),
+            fields: config.fields.iter().map(TemplateField::from).collect(),
             name,
             snake_name,
             pascal_name,
@@ -96,15 +150,142 @@ impl TemplateData {
     }
 }
 
+/// Field as seen by templates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateField {
+    pub name: String,
+    /// Rust type, `Option` if nullable
+    pub rust_type: String,
+    /// Rust type without `Option`
+    pub base_type: String,
+    pub nullable: bool,
+    pub unique: bool,
+    /// Rust expression of the field's default value
+    pub default_expr: String,
+    /// Arguments of the `#[serde(...)]` attribute in models
+    pub serde: Option<String>,
+    /// Arguments of the `#[sea_orm(...)]` attribute in SeaORM entities
+    pub sea_orm: Option<String>,
+    /// Arguments of the `#[validate(...)]` attribute in requests
+    pub validate: Option<String>,
+}
+
+impl From<&Field> for TemplateField {
+    fn from(field: &Field) -> Self {
+        let mut rules = Vec::new();
+        if matches!(field.ty, FieldType::String) {
+            if field.name.contains("email") {
+                rules.push("email".to_string());
+            }
+            let min = if field.nullable { "" } else { "min = 1, " };
+            rules.push(format!("length({}max = 255)", min));
+        }
+
+        let mut serde = Vec::new();
+        if field.nullable {
+            serde.push("default, skip_serializing_if = \"Option::is_none\"");
+        }
+        if ["password", "secret", "token"]
+            .iter()
+            .any(|sensitive| field.name.contains(sensitive))
+        {
+            serde.push("skip_serializing");
+        }
+
+        let mut sea_orm = Vec::new();
+        if matches!(field.ty, FieldType::Text) {
+            sea_orm.push("column_type = \"Text\"");
+        }
+        if field.unique {
+            sea_orm.push("unique");
+        }
+        if field.nullable {
+            sea_orm.push("nullable");
+        }
+
+        Self {
+            name: field.name.clone(),
+            rust_type: field.rust_type(),
+            base_type: field.ty.rust_type().to_string(),
+            nullable: field.nullable,
+            unique: field.unique,
+            default_expr: default_expr(field),
+            serde: (!serde.is_empty()).then(|| serde.join(", ")),
+            sea_orm: (!sea_orm.is_empty()).then(|| sea_orm.join(", ")),
+            validate: (!rules.is_empty()).then(|| rules.join(", ")),
+        }
+    }
+}
+
+/// Rust expression for the DSL default of a field
+fn default_expr(field: &Field) -> String {
+    let Some(value) = field.default.as_deref() else {
+        return if field.nullable {
+            "None".to_string()
+        } else {
+            "Default::default()".to_string()
+        };
+    };
+
+    let expr = match field.ty {
+        FieldType::String | FieldType::Text => format!("{:?}.to_string()", value),
+        FieldType::Integer | FieldType::BigInteger | FieldType::References(_)
+            if value.parse::<i64>().is_ok() =>
+        {
+            value.to_string()
+        }
+        FieldType::Float if value.parse::<f64>().is_ok() => {
+            if value.contains('.') {
+                value.to_string()
+            } else {
+                format!("{}.0", value)
+            }
+        }
+        FieldType::Decimal if value.parse::<f64>().is_ok() => {
+            format!("{:?}.parse().unwrap()", value)
+        }
+        FieldType::Boolean => matches!(value, "true" | "1").to_string(),
+        FieldType::DateTime if value.eq_ignore_ascii_case("now") => {
+            "chrono::Utc::now()".to_string()
+        }
+        FieldType::Date if value.eq_ignore_ascii_case("now") => {
+            "chrono::Utc::now().date_naive()".to_string()
+        }
+        _ => "Default::default()".to_string(),
+    };
+
+    if field.nullable {
+        format!("Some({})", expr)
+    } else {
+        expr
+    }
+}
+
+/// Persistence library generated models target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Orm {
+    /// Plain struct deriving `sqlx::FromRow`, with a builder
+    #[default]
+    Sqlx,
+    /// SeaORM entity module
+    SeaOrm,
+}
+
 /// Model generator
+///
+/// Generates a struct with the config's fields, `id` and timestamps matching
+/// [`MigrationGenerator::create_table`].
 pub struct ModelGenerator {
     handlebars: Handlebars<'static>,
+    orm: Orm,
 }
 
 impl ModelGenerator {
     /// Create a new model generator
     pub fn new() -> Self {
         let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(handlebars::no_escape);
+        case::register_helpers(&mut handlebars);
 
         // Register model template
         handlebars
@@ -116,21 +297,70 @@ impl ModelGenerator {
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct {{pascal_name}} {
     pub id: i64,
+{{#each fields}}
+{{#if serde}}
+    #[serde({{serde}})]
+{{/if}}
+    pub {{name}}: {{rust_type}},
+{{else}}
     // Add your fields here
+{{/each}}
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
 impl {{pascal_name}} {
     /// Create a new {{name}}
     pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a {{name}} field by field
+    pub fn builder() -> {{pascal_name}}Builder {
+        {{pascal_name}}Builder::default()
+    }
+}
+
+impl Default for {{pascal_name}} {
+    fn default() -> Self {
+        let now = chrono::Utc::now();
         Self {
             id: 0,
+{{#each fields}}
+            {{name}}: {{default_expr}},
+{{/each}}
+            created_at: now,
+            updated_at: now,
         }
     }
 }
 
+/// Builder for [`{{pascal_name}}`]
+#[derive(Debug, Clone, Default)]
+pub struct {{pascal_name}}Builder {
+    inner: {{pascal_name}},
+}
+
+impl {{pascal_name}}Builder {
+{{#each fields}}
+    pub fn {{name}}(mut self, {{name}}: impl Into<{{base_type}}>) -> Self {
+{{#if nullable}}
+        self.inner.{{name}} = Some({{name}}.into());
+{{else}}
+        self.inner.{{name}} = {{name}}.into();
+{{/if}}
+        self
+    }
+
+{{/each}}
+    pub fn build(self) -> {{pascal_name}} {
+        self.inner
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,29 +370,108 @@ mod tests {
         let {{snake_name}} = {{pascal_name}}::new();
         assert_eq!({{snake_name}}.id, 0);
     }
+
+    #[test]
+    fn test_{{snake_name}}_builder() {
+        let {{snake_name}} = {{pascal_name}}::builder().build();
+        assert_eq!({{snake_name}}.id, 0);
+    }
 }
 "#,
             )
             .unwrap();
 
-        Self { handlebars }
+        handlebars
+            .register_template_string(
+                "sea_orm_model",
+                r#"
+//! {{pascal_name}} entity
+//! Generated at {{timestamp}}
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "{{table_name}}")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+{{#each fields}}
+{{#if sea_orm}}
+    #[sea_orm({{sea_orm}})]
+{{/if}}
+{{#if serde}}
+    #[serde({{serde}})]
+{{/if}}
+    pub {{name}}: {{rust_type}},
+{{/each}}
+    pub created_at: DateTimeUtc,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+"#,
+            )
+            .unwrap();
+
+        Self {
+            handlebars,
+            orm: Orm::default(),
+        }
+    }
+
+    /// Generate models for another persistence library
+    pub fn with_orm(mut self, orm: Orm) -> Self {
+        self.orm = orm;
+        self
     }
 
     /// Generate a model file
     pub async fn generate(&self, config: GeneratorConfig) -> GeneratorResult<PathBuf> {
         let data = TemplateData::from_config(&config);
+        let template = match self.orm {
+            Orm::Sqlx => "model",
+            Orm::SeaOrm => "sea_orm_model",
+        };
         let content = self
             .handlebars
-            .render("model", &data)
+            .render(template, &data)
             .map_err(|e| GeneratorError::Template(e.to_string()))?;
 
-        let file_path = config
-            .output_dir
-            .join(format!("{}.rs", data.snake_name));
+        let file_path = config.output_dir.join(format!("{}.rs", data.snake_name));
 
         write_file(&file_path, &content, config.force).await?;
+        if config.wire {
+            wire::wire_module(&file_path).await?;
+        }
         Ok(file_path)
     }
+
+    /// Generate the model and the migration creating its table
+    ///
+    /// Returns the model path followed by the migration's up and down files.
+    pub async fn generate_with_migration(
+        &self,
+        config: GeneratorConfig,
+        driver: Driver,
+        migrations_dir: impl Into<PathBuf>,
+    ) -> GeneratorResult<Vec<PathBuf>> {
+        let migration_config = GeneratorConfig {
+            output_dir: migrations_dir.into(),
+            ..config.clone()
+        };
+
+        let mut paths = vec![self.generate(config).await?];
+        paths.extend(
+            MigrationGenerator::new(driver)
+                .generate(migration_config)
+                .await?,
+        );
+        Ok(paths)
+    }
 }
 
 impl Default for ModelGenerator {
@@ -180,6 +489,8 @@ impl ControllerGenerator {
     /// Create a new controller generator
     pub fn new() -> Self {
         let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(handlebars::no_escape);
+        case::register_helpers(&mut handlebars);
 
         handlebars
             .register_template_string(
@@ -245,6 +556,139 @@ mod tests {
         let _router = {{snake_name}}_routes();
     }
 }
+"#,
+            )
+            .unwrap();
+
+        handlebars
+            .register_template_string(
+                "crud",
+                r#"
+//! {{pascal_name}} controller
+//! Generated at {{timestamp}}
+
+use crate::models::{{snake_name}}::{{pascal_name}};
+use crate::requests::{{snake_name}}_request::{Create{{pascal_name}}Request, Update{{pascal_name}}Request};
+use axum::{
+    extract::{FromRef, Path, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use rf_validation::ValidatedJson;
+use sqlx::{{sql.pool}};
+
+pub fn {{snake_name}}_routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    {{sql.pool}}: FromRef<S>,
+{
+    Router::new()
+        .route("/{{table_name}}", get(index).post(store))
+        .route("/{{table_name}}/{id}", get(show).put(update).delete(destroy))
+}
+
+/// List all {{table_name}}
+async fn index(State(pool): State<{{sql.pool}}>) -> Result<Json<Vec<{{pascal_name}}>>, StatusCode> {
+    let rows = sqlx::query_as::<_, {{pascal_name}}>("{{sql.select_all}}")
+        .fetch_all(&pool)
+        .await
+        .map_err(db_error)?;
+    Ok(Json(rows))
+}
+
+/// Create a new {{snake_name}}
+async fn store(
+    State(pool): State<{{sql.pool}}>,
+    ValidatedJson(input): ValidatedJson<Create{{pascal_name}}Request>,
+) -> Result<(StatusCode, Json<{{pascal_name}}>), StatusCode> {
+{{#if sql.returning}}
+    let id: i64 = sqlx::query_scalar("{{sql.insert}}")
+{{#each fields}}
+        .bind(input.{{name}})
+{{/each}}
+        .fetch_one(&pool)
+        .await
+        .map_err(db_error)?;
+{{else}}
+    let id = sqlx::query("{{sql.insert}}")
+{{#each fields}}
+        .bind(input.{{name}})
+{{/each}}
+        .execute(&pool)
+        .await
+        .map_err(db_error)?
+        .last_insert_id() as i64;
+{{/if}}
+    let {{snake_name}} = find(&pool, id).await?;
+    Ok((StatusCode::CREATED, Json({{snake_name}})))
+}
+
+/// Show a single {{snake_name}}
+async fn show(
+    State(pool): State<{{sql.pool}}>,
+    Path(id): Path<i64>,
+) -> Result<Json<{{pascal_name}}>, StatusCode> {
+    find(&pool, id).await.map(Json)
+}
+
+/// Update a {{snake_name}}; fields missing from the request are kept
+async fn update(
+    State(pool): State<{{sql.pool}}>,
+    Path(id): Path<i64>,
+    ValidatedJson(input): ValidatedJson<Update{{pascal_name}}Request>,
+) -> Result<Json<{{pascal_name}}>, StatusCode> {
+    sqlx::query("{{sql.update}}")
+{{#each fields}}
+        .bind(input.{{name}})
+{{/each}}
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(db_error)?;
+    find(&pool, id).await.map(Json)
+}
+
+/// Delete a {{snake_name}}
+async fn destroy(
+    State(pool): State<{{sql.pool}}>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, StatusCode> {
+    let result = sqlx::query("{{sql.delete}}")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(db_error)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn find(pool: &{{sql.pool}}, id: i64) -> Result<{{pascal_name}}, StatusCode> {
+    sqlx::query_as::<_, {{pascal_name}}>("{{sql.select_one}}")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+fn db_error(error: sqlx::Error) -> StatusCode {
+    tracing::error!(%error, "{{snake_name}} query failed");
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_{{snake_name}}_routes() {
+        let _router: Router<{{sql.pool}}> = {{snake_name}}_routes();
+    }
+}
 "#,
             )
             .unwrap();
@@ -265,8 +709,87 @@ mod tests {
             .join(format!("{}_controller.rs", data.snake_name));
 
         write_file(&file_path, &content, config.force).await?;
+        if config.wire {
+            wire::wire_module(&file_path).await?;
+            wire::wire_routes(&file_path, &format!("{}_routes", data.snake_name)).await?;
+        }
         Ok(file_path)
     }
+
+    /// Generate a controller with sqlx-backed CRUD handlers
+    ///
+    /// Expects the model in `crate::models` and the request structs of
+    /// [`RequestGenerator`] in `crate::requests`.
+    pub async fn generate_crud(
+        &self,
+        config: GeneratorConfig,
+        driver: Driver,
+    ) -> GeneratorResult<PathBuf> {
+        let data = TemplateData::from_config(&config);
+        let mut value =
+            serde_json::to_value(&data).map_err(|e| GeneratorError::Template(e.to_string()))?;
+        value["sql"] = crud_sql(driver, &data.table_name, &config.fields);
+
+        let content = self
+            .handlebars
+            .render("crud", &value)
+            .map_err(|e| GeneratorError::Template(e.to_string()))?;
+
+        let file_path = config
+            .output_dir
+            .join(format!("{}_controller.rs", data.snake_name));
+
+        write_file(&file_path, &content, config.force).await?;
+        if config.wire {
+            wire::wire_module(&file_path).await?;
+            wire::wire_routes(&file_path, &format!("{}_routes", data.snake_name)).await?;
+        }
+        Ok(file_path)
+    }
+}
+
+/// Queries of the CRUD controller
+fn crud_sql(driver: Driver, table: &str, fields: &[Field]) -> serde_json::Value {
+    let columns: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+    let values: Vec<String> = (1..=fields.len()).map(|n| driver.placeholder(n)).collect();
+    let assignments: Vec<String> = columns
+        .iter()
+        .zip(&values)
+        .map(|(column, value)| format!("{} = COALESCE({}, {})", column, value, column))
+        .chain(std::iter::once(
+            "updated_at = CURRENT_TIMESTAMP".to_string(),
+        ))
+        .collect();
+    let returning = driver != Driver::MySql;
+
+    let mut insert = if fields.is_empty() && returning {
+        format!("INSERT INTO {} DEFAULT VALUES", table)
+    } else {
+        format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table,
+            columns.join(", "),
+            values.join(", ")
+        )
+    };
+    if returning {
+        insert.push_str(" RETURNING id");
+    }
+
+    serde_json::json!({
+        "pool": driver.pool_type(),
+        "returning": returning,
+        "select_all": format!("SELECT * FROM {} ORDER BY id", table),
+        "select_one": format!("SELECT * FROM {} WHERE id = {}", table, driver.placeholder(1)),
+        "insert": insert,
+        "update": format!(
+            "UPDATE {} SET {} WHERE id = {}",
+            table,
+            assignments.join(", "),
+            driver.placeholder(fields.len() + 1)
+        ),
+        "delete": format!("DELETE FROM {} WHERE id = {}", table, driver.placeholder(1)),
+    })
 }
 
 impl Default for ControllerGenerator {
@@ -284,6 +807,7 @@ impl TestGenerator {
     /// Create a new test generator
     pub fn new() -> Self {
         let mut handlebars = Handlebars::new();
+        case::register_helpers(&mut handlebars);
 
         handlebars
             .register_template_string(
@@ -338,42 +862,7 @@ impl Default for TestGenerator {
     }
 }
 
-/// Utility functions
-
-fn to_snake_case(s: &str) -> String {
-    let mut result = String::new();
-    let mut prev_upper = false;
-
-    for (i, c) in s.chars().enumerate() {
-        if c.is_uppercase() {
-            if i > 0 && !prev_upper {
-                result.push('_');
-            }
-            result.push(c.to_lowercase().next().unwrap());
-            prev_upper = true;
-        } else {
-            result.push(c);
-            prev_upper = false;
-        }
-    }
-
-    result
-}
-
-fn to_pascal_case(s: &str) -> String {
-    s.split(&['_', '-'][..])
-        .filter(|s| !s.is_empty())
-        .map(|word| {
-            let mut chars = word.chars();
-            match chars.next() {
-                None => String::new(),
-                Some(first) => {
-                    first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase()
-                }
-            }
-        })
-        .collect()
-}
+// Utility functions
 
 async fn write_file(path: &Path, content: &str, force: bool) -> GeneratorResult<()> {
     // Check if file exists
@@ -395,20 +884,6 @@ async fn write_file(path: &Path, content: &str, force: bool) -> GeneratorResult<
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_to_snake_case() {
-        assert_eq!(to_snake_case("UserModel"), "user_model");
-        assert_eq!(to_snake_case("PostController"), "post_controller");
-        assert_eq!(to_snake_case("HTTPRequest"), "h_t_t_p_request");
-    }
-
-    #[test]
-    fn test_to_pascal_case() {
-        assert_eq!(to_pascal_case("user_model"), "UserModel");
-        assert_eq!(to_pascal_case("post-controller"), "PostController");
-        assert_eq!(to_pascal_case("my_test_name"), "MyTestName");
-    }
-
     #[test]
     fn test_generator_config() {
         let config = GeneratorConfig::new("User", "src/models")
@@ -432,6 +907,57 @@ mod tests {
         assert!(content.contains("pub struct User"));
     }
 
+    #[tokio::test]
+    async fn test_model_generator_fields() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let fields = Field::parse_list(
+            "email:string:unique password:string nickname:string? score:float:default=1 active:bool:default=true role:string:default=member",
+        )
+        .unwrap();
+        let config = GeneratorConfig::new("User", temp_dir.path()).with_fields(fields);
+
+        let paths = ModelGenerator::new()
+            .generate_with_migration(config, Driver::Postgres, temp_dir.path().join("migrations"))
+            .await
+            .unwrap();
+        assert_eq!(paths.len(), 3);
+
+        let content = fs::read_to_string(&paths[0]).await.unwrap();
+        assert!(content.contains("    pub email: String,\n"));
+        assert!(content.contains("    #[serde(skip_serializing)]\n    pub password: String,\n"));
+        assert!(content.contains(
+            "    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub nickname: Option<String>,\n"
+        ));
+        assert!(content.contains("            score: 1.0,\n"));
+        assert!(content.contains("            active: true,\n"));
+        assert!(content.contains("            role: \"member\".to_string(),\n"));
+        assert!(content.contains("            nickname: None,\n"));
+        assert!(content.contains(
+            "    pub fn nickname(mut self, nickname: impl Into<String>) -> Self {\n        self.inner.nickname = Some(nickname.into());"
+        ));
+
+        let migration = fs::read_to_string(&paths[1]).await.unwrap();
+        assert!(migration.contains("\"email\" VARCHAR(255) NOT NULL UNIQUE"));
+    }
+
+    #[tokio::test]
+    async fn test_sea_orm_model_generator() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let fields = Field::parse_list("slug:string:unique body:text?").unwrap();
+        let config = GeneratorConfig::new("BlogPost", temp_dir.path()).with_fields(fields);
+
+        let path = ModelGenerator::new()
+            .with_orm(Orm::SeaOrm)
+            .generate(config)
+            .await
+            .unwrap();
+
+        let content = fs::read_to_string(&path).await.unwrap();
+        assert!(content.contains("#[sea_orm(table_name = \"blog_posts\")]"));
+        assert!(content.contains("    #[sea_orm(unique)]\n    pub slug: String,\n"));
+        assert!(content.contains("    #[sea_orm(column_type = \"Text\", nullable)]\n"));
+    }
+
     #[tokio::test]
     async fn test_controller_generator() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -445,6 +971,34 @@ mod tests {
         assert!(content.contains("post_routes"));
     }
 
+    #[tokio::test]
+    async fn test_controller_wiring() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src");
+        fs::create_dir_all(&src).await.unwrap();
+        fs::write(
+            src.join("lib.rs"),
+            "pub fn app() -> Router {\n    Router::new()\n        // rustforge:routes\n}\n",
+        )
+        .await
+        .unwrap();
+
+        let config = GeneratorConfig::new("Post", src.join("controllers"));
+        ControllerGenerator::new()
+            .generate(config.clone().no_wire())
+            .await
+            .unwrap();
+        assert!(!src.join("controllers/mod.rs").exists());
+
+        ControllerGenerator::new()
+            .generate(config.force())
+            .await
+            .unwrap();
+        let lib = fs::read_to_string(src.join("lib.rs")).await.unwrap();
+        assert!(lib.starts_with("pub mod controllers;\n\npub fn app()"));
+        assert!(lib.contains(".merge(crate::controllers::post_controller::post_routes())"));
+    }
+
     #[tokio::test]
     async fn test_test_generator() {
         let temp_dir = tempfile::tempdir().unwrap();
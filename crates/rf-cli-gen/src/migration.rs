@@ -0,0 +1,478 @@
+//! Migration generator
+//!
+//! Produces reversible, timestamped SQL migrations in the sqlx layout:
+//! `20240101120000_create_posts_table.up.sql` and `.down.sql`.
+
+use crate::{
+    schema::{Field, FieldType},
+    to_table_name, write_file, GeneratorConfig, GeneratorError, GeneratorResult,
+};
+use std::{fmt, path::PathBuf, str::FromStr};
+
+/// Database the SQL is generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Driver {
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl FromStr for Driver {
+    type Err = GeneratorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "postgres" | "postgresql" | "pg" => Ok(Self::Postgres),
+            "mysql" | "mariadb" => Ok(Self::MySql),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => Err(GeneratorError::Unsupported(format!(
+                "database driver '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl Driver {
+    /// sqlx pool type of the driver
+    pub(crate) fn pool_type(self) -> &'static str {
+        match self {
+            Self::Postgres => "PgPool",
+            Self::MySql => "MySqlPool",
+            Self::Sqlite => "SqlitePool",
+        }
+    }
+
+    /// Bind parameter `n`, counting from 1
+    pub(crate) fn placeholder(self, n: usize) -> String {
+        match self {
+            Self::Postgres => format!("${}", n),
+            Self::MySql | Self::Sqlite => "?".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Driver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Postgres => "postgres",
+            Self::MySql => "mysql",
+            Self::Sqlite => "sqlite",
+        })
+    }
+}
+
+/// What happens to rows when a referenced row is deleted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnDelete {
+    Cascade,
+    SetNull,
+    Restrict,
+}
+
+impl OnDelete {
+    fn sql(self) -> &'static str {
+        match self {
+            Self::Cascade => "CASCADE",
+            Self::SetNull => "SET NULL",
+            Self::Restrict => "RESTRICT",
+        }
+    }
+}
+
+/// SQL of one migration, in both directions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    /// Name without timestamp, e.g. `create_posts_table`
+    pub name: String,
+    pub up: String,
+    pub down: String,
+}
+
+/// Migration generator
+pub struct MigrationGenerator {
+    driver: Driver,
+}
+
+impl MigrationGenerator {
+    /// Create a generator for a database driver
+    pub fn new(driver: Driver) -> Self {
+        Self { driver }
+    }
+
+    /// Generate a `create_{table}_table` migration from the config's fields
+    ///
+    /// The table is named after `config.name`, so `Post` creates `posts`.
+    /// Returns the paths of the up and down files.
+    pub async fn generate(&self, config: GeneratorConfig) -> GeneratorResult<Vec<PathBuf>> {
+        let table = to_table_name(&config.name);
+        let migration = self.create_table(&table, &config.fields);
+        self.write(&config, &migration).await
+    }
+
+    /// Write a migration as timestamped up and down files
+    pub async fn write(
+        &self,
+        config: &GeneratorConfig,
+        migration: &Migration,
+    ) -> GeneratorResult<Vec<PathBuf>> {
+        let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S");
+        let base = format!("{}_{}", timestamp, migration.name);
+
+        let up = config.output_dir.join(format!("{}.up.sql", base));
+        let down = config.output_dir.join(format!("{}.down.sql", base));
+        write_file(&up, &migration.up, config.force).await?;
+        write_file(&down, &migration.down, config.force).await?;
+        Ok(vec![up, down])
+    }
+
+    /// Create a table with an `id` key, the fields and timestamps
+    ///
+    /// References get a foreign key and an index, `index` fields an index.
+    pub fn create_table(&self, table: &str, fields: &[Field]) -> Migration {
+        let mut columns = vec![format!("    {}", self.id_column())];
+        let mut constraints = Vec::new();
+        let mut indexes = Vec::new();
+
+        for field in fields {
+            columns.push(format!("    {}", self.column(field)));
+
+            if let FieldType::References(target) = &field.ty {
+                let on_delete = if field.nullable {
+                    OnDelete::SetNull
+                } else {
+                    OnDelete::Cascade
+                };
+                constraints.push(format!(
+                    "    {}",
+                    self.foreign_key_constraint(table, &field.name, target, on_delete)
+                ));
+            }
+            if field.index || matches!(field.ty, FieldType::References(_)) {
+                indexes.push(self.create_index(table, &[&field.name], false).up);
+            }
+        }
+
+        let timestamp = self.timestamp_type();
+        for column in ["created_at", "updated_at"] {
+            columns.push(format!(
+                "    {} {} NOT NULL DEFAULT CURRENT_TIMESTAMP",
+                self.quote(column),
+                timestamp
+            ));
+        }
+
+        columns.extend(constraints);
+        let mut up = format!(
+            "CREATE TABLE {} (\n{}\n);\n",
+            self.quote(table),
+            columns.join(",\n")
+        );
+        for index in indexes {
+            up.push_str(&index);
+        }
+
+        Migration {
+            name: format!("create_{}_table", table),
+            up,
+            // Indexes and constraints go with the table
+            down: format!("DROP TABLE IF EXISTS {};\n", self.quote(table)),
+        }
+    }
+
+    /// Add an index on columns of an existing table
+    pub fn create_index(&self, table: &str, columns: &[&str], unique: bool) -> Migration {
+        let name = index_name(table, columns, unique);
+        let columns: Vec<String> = columns.iter().map(|c| self.quote(c)).collect();
+
+        let up = format!(
+            "CREATE {}INDEX {} ON {} ({});\n",
+            if unique { "UNIQUE " } else { "" },
+            self.quote(&name),
+            self.quote(table),
+            columns.join(", ")
+        );
+        let down = match self.driver {
+            Driver::MySql => format!(
+                "DROP INDEX {} ON {};\n",
+                self.quote(&name),
+                self.quote(table)
+            ),
+            Driver::Postgres | Driver::Sqlite => {
+                format!("DROP INDEX IF EXISTS {};\n", self.quote(&name))
+            }
+        };
+
+        Migration {
+            name: format!("add_{}", name),
+            up,
+            down,
+        }
+    }
+
+    /// Add a foreign key to an existing column
+    ///
+    /// SQLite cannot alter constraints of existing tables; declare the
+    /// column as a `references` field when creating the table instead.
+    pub fn add_foreign_key(
+        &self,
+        table: &str,
+        column: &str,
+        references: &str,
+        on_delete: OnDelete,
+    ) -> GeneratorResult<Migration> {
+        let name = foreign_key_name(table, column);
+        let drop = match self.driver {
+            Driver::Postgres => "DROP CONSTRAINT",
+            Driver::MySql => "DROP FOREIGN KEY",
+            Driver::Sqlite => {
+                return Err(GeneratorError::Unsupported(
+                    "adding foreign keys to existing SQLite tables".to_string(),
+                ))
+            }
+        };
+
+        Ok(Migration {
+            name: format!("add_{}", name),
+            up: format!(
+                "ALTER TABLE {} ADD {};\n",
+                self.quote(table),
+                self.foreign_key_constraint(table, column, references, on_delete)
+            ),
+            down: format!(
+                "ALTER TABLE {} {} {};\n",
+                self.quote(table),
+                drop,
+                self.quote(&name)
+            ),
+        })
+    }
+
+    fn id_column(&self) -> String {
+        let id = self.quote("id");
+        match self.driver {
+            Driver::Postgres => format!("{} BIGSERIAL PRIMARY KEY", id),
+            Driver::MySql => format!("{} BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY", id),
+            Driver::Sqlite => format!("{} INTEGER PRIMARY KEY AUTOINCREMENT", id),
+        }
+    }
+
+    fn column(&self, field: &Field) -> String {
+        let mut column = format!("{} {}", self.quote(&field.name), self.sql_type(&field.ty));
+        if !field.nullable {
+            column.push_str(" NOT NULL");
+        }
+        if field.unique {
+            column.push_str(" UNIQUE");
+        }
+        if let Some(default) = &field.default {
+            column.push_str(" DEFAULT ");
+            column.push_str(&self.default_value(&field.ty, default));
+        }
+        column
+    }
+
+    fn sql_type(&self, ty: &FieldType) -> &'static str {
+        use Driver::*;
+
+        match (ty, self.driver) {
+            (FieldType::String, Sqlite) => "TEXT",
+            (FieldType::String, _) => "VARCHAR(255)",
+            (FieldType::Text, _) => "TEXT",
+            (FieldType::Integer, MySql) => "INT",
+            (FieldType::Integer, _) => "INTEGER",
+            (FieldType::BigInteger, Sqlite) => "INTEGER",
+            (FieldType::BigInteger, _) => "BIGINT",
+            (FieldType::Float, Postgres) => "DOUBLE PRECISION",
+            (FieldType::Float, MySql) => "DOUBLE",
+            (FieldType::Float, Sqlite) => "REAL",
+            (FieldType::Decimal, MySql) => "DECIMAL(10, 2)",
+            (FieldType::Decimal, _) => "NUMERIC(10, 2)",
+            (FieldType::Boolean, Postgres) => "BOOLEAN",
+            (FieldType::Boolean, MySql) => "TINYINT(1)",
+            (FieldType::Boolean, Sqlite) => "INTEGER",
+            (FieldType::Date, Sqlite) => "TEXT",
+            (FieldType::Date, _) => "DATE",
+            (FieldType::DateTime, _) => self.timestamp_type(),
+            (FieldType::Uuid, Postgres) => "UUID",
+            (FieldType::Uuid, MySql) => "CHAR(36)",
+            (FieldType::Uuid, Sqlite) => "TEXT",
+            (FieldType::Json, Postgres) => "JSONB",
+            (FieldType::Json, MySql) => "JSON",
+            (FieldType::Json, Sqlite) => "TEXT",
+            // Must match the type of the referenced `id`
+            (FieldType::References(_), Postgres) => "BIGINT",
+            (FieldType::References(_), MySql) => "BIGINT UNSIGNED",
+            (FieldType::References(_), Sqlite) => "INTEGER",
+        }
+    }
+
+    fn timestamp_type(&self) -> &'static str {
+        match self.driver {
+            Driver::Postgres => "TIMESTAMPTZ",
+            Driver::MySql => "TIMESTAMP",
+            Driver::Sqlite => "TEXT",
+        }
+    }
+
+    fn default_value(&self, ty: &FieldType, value: &str) -> String {
+        match ty {
+            FieldType::Boolean => {
+                let truthy = matches!(value, "true" | "1");
+                match (self.driver, truthy) {
+                    (Driver::Postgres, true) => "TRUE".to_string(),
+                    (Driver::Postgres, false) => "FALSE".to_string(),
+                    (_, true) => "1".to_string(),
+                    (_, false) => "0".to_string(),
+                }
+            }
+            FieldType::Integer
+            | FieldType::BigInteger
+            | FieldType::Float
+            | FieldType::Decimal
+            | FieldType::References(_) => value.to_string(),
+            FieldType::Date | FieldType::DateTime if value.eq_ignore_ascii_case("now") => {
+                "CURRENT_TIMESTAMP".to_string()
+            }
+            _ => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+
+    fn foreign_key_constraint(
+        &self,
+        table: &str,
+        column: &str,
+        references: &str,
+        on_delete: OnDelete,
+    ) -> String {
+        format!(
+            "CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({}) ON DELETE {}",
+            self.quote(&foreign_key_name(table, column)),
+            self.quote(column),
+            self.quote(references),
+            self.quote("id"),
+            on_delete.sql()
+        )
+    }
+
+    fn quote(&self, identifier: &str) -> String {
+        match self.driver {
+            Driver::MySql => format!("`{}`", identifier),
+            Driver::Postgres | Driver::Sqlite => format!("\"{}\"", identifier),
+        }
+    }
+}
+
+fn index_name(table: &str, columns: &[&str], unique: bool) -> String {
+    let prefix = if unique { "uniq" } else { "idx" };
+    format!("{}_{}_{}", prefix, table, columns.join("_"))
+}
+
+fn foreign_key_name(table: &str, column: &str) -> String {
+    format!("fk_{}_{}", table, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::fs;
+
+    fn fields() -> Vec<Field> {
+        Field::parse_list("title:string:unique body:text? views:int:default=0 published:bool:default=false author:references(users) slug:string:index")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_create_table_postgres() {
+        let migration = MigrationGenerator::new(Driver::Postgres).create_table("posts", &fields());
+
+        assert_eq!(migration.name, "create_posts_table");
+        assert_eq!(
+            migration.up,
+            r#"CREATE TABLE "posts" (
+    "id" BIGSERIAL PRIMARY KEY,
+    "title" VARCHAR(255) NOT NULL UNIQUE,
+    "body" TEXT,
+    "views" INTEGER NOT NULL DEFAULT 0,
+    "published" BOOLEAN NOT NULL DEFAULT FALSE,
+    "author_id" BIGINT NOT NULL,
+    "slug" VARCHAR(255) NOT NULL,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT "fk_posts_author_id" FOREIGN KEY ("author_id") REFERENCES "users" ("id") ON DELETE CASCADE
+);
+CREATE INDEX "idx_posts_author_id" ON "posts" ("author_id");
+CREATE INDEX "idx_posts_slug" ON "posts" ("slug");
+"#
+        );
+        assert_eq!(migration.down, "DROP TABLE IF EXISTS \"posts\";\n");
+    }
+
+    #[test]
+    fn test_driver_specific_sql() {
+        let mysql = MigrationGenerator::new(Driver::MySql).create_table("posts", &fields());
+        assert!(mysql
+            .up
+            .contains("`id` BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY"));
+        assert!(mysql
+            .up
+            .contains("`published` TINYINT(1) NOT NULL DEFAULT 0"));
+        assert!(mysql.up.contains("`author_id` BIGINT UNSIGNED NOT NULL"));
+
+        let sqlite = MigrationGenerator::new(Driver::Sqlite).create_table("posts", &fields());
+        assert!(sqlite
+            .up
+            .contains("\"id\" INTEGER PRIMARY KEY AUTOINCREMENT"));
+        assert!(sqlite.up.contains("\"title\" TEXT NOT NULL UNIQUE"));
+        assert!(sqlite.up.contains("REFERENCES \"users\" (\"id\")"));
+    }
+
+    #[test]
+    fn test_index_and_foreign_key_helpers() {
+        let mysql = MigrationGenerator::new(Driver::MySql);
+
+        let index = mysql.create_index("users", &["email", "tenant_id"], true);
+        assert_eq!(
+            index.up,
+            "CREATE UNIQUE INDEX `uniq_users_email_tenant_id` ON `users` (`email`, `tenant_id`);\n"
+        );
+        assert_eq!(
+            index.down,
+            "DROP INDEX `uniq_users_email_tenant_id` ON `users`;\n"
+        );
+
+        let fk = mysql
+            .add_foreign_key("posts", "author_id", "users", OnDelete::SetNull)
+            .unwrap();
+        assert!(fk.up.contains("ON DELETE SET NULL"));
+        assert_eq!(
+            fk.down,
+            "ALTER TABLE `posts` DROP FOREIGN KEY `fk_posts_author_id`;\n"
+        );
+
+        let sqlite = MigrationGenerator::new(Driver::Sqlite);
+        assert!(sqlite
+            .add_foreign_key("posts", "author_id", "users", OnDelete::Cascade)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_generate_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = GeneratorConfig::new("BlogPost", temp_dir.path()).with_fields(fields());
+
+        let paths = MigrationGenerator::new("pg".parse().unwrap())
+            .generate(config)
+            .await
+            .unwrap();
+
+        let up = paths[0].file_name().unwrap().to_str().unwrap();
+        assert!(up.ends_with("_create_blog_posts_table.up.sql"), "{}", up);
+        assert_eq!(
+            up.len(),
+            "20240101120000".len() + "_create_blog_posts_table.up.sql".len()
+        );
+        let down = fs::read_to_string(&paths[1]).await.unwrap();
+        assert_eq!(down, "DROP TABLE IF EXISTS \"blog_posts\";\n");
+    }
+}
@@ -0,0 +1,86 @@
+//! Request generator
+//!
+//! Validated payload structs for creating and updating a model, used by the
+//! CRUD controller through `rf_validation::ValidatedJson`.
+
+use crate::{
+    case, wire, write_file, GeneratorConfig, GeneratorError, GeneratorResult, TemplateData,
+};
+use handlebars::Handlebars;
+use std::path::PathBuf;
+
+/// Request generator
+pub struct RequestGenerator {
+    handlebars: Handlebars<'static>,
+}
+
+impl RequestGenerator {
+    /// Create a new request generator
+    pub fn new() -> Self {
+        let mut handlebars = Handlebars::new();
+        handlebars.register_escape_fn(handlebars::no_escape);
+        case::register_helpers(&mut handlebars);
+
+        handlebars
+            .register_template_string(
+                "request",
+                r#"
+//! {{pascal_name}} requests
+//! Generated at {{timestamp}}
+
+use rf_validation::Validate;
+use serde::Deserialize;
+
+/// Payload to create a {{snake_name}}
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct Create{{pascal_name}}Request {
+{{#each fields}}
+{{#if validate}}
+    #[validate({{validate}})]
+{{/if}}
+    pub {{name}}: {{rust_type}},
+{{/each}}
+}
+
+/// Payload to update a {{snake_name}}; missing fields are left unchanged
+#[derive(Debug, Clone, Default, Deserialize, Validate)]
+pub struct Update{{pascal_name}}Request {
+{{#each fields}}
+{{#if validate}}
+    #[validate({{validate}})]
+{{/if}}
+    pub {{name}}: Option<{{base_type}}>,
+{{/each}}
+}
+"#,
+            )
+            .unwrap();
+
+        Self { handlebars }
+    }
+
+    /// Generate a request file
+    pub async fn generate(&self, config: GeneratorConfig) -> GeneratorResult<PathBuf> {
+        let data = TemplateData::from_config(&config);
+        let content = self
+            .handlebars
+            .render("request", &data)
+            .map_err(|e| GeneratorError::Template(e.to_string()))?;
+
+        let file_path = config
+            .output_dir
+            .join(format!("{}_request.rs", data.snake_name));
+
+        write_file(&file_path, &content, config.force).await?;
+        if config.wire {
+            wire::wire_module(&file_path).await?;
+        }
+        Ok(file_path)
+    }
+}
+
+impl Default for RequestGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
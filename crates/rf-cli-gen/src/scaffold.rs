@@ -0,0 +1,242 @@
+//! Scaffold generator
+//!
+//! `rustforge make:scaffold Post title:string body:text` in one call: model,
+//! migration, request structs, CRUD controller and tests, wired into the
+//! module tree and the router. Paths are relative to the project root:
+//!
+//! ```text
+//! src/models/post.rs                      + pub mod post;
+//! src/requests/post_request.rs            + pub mod post_request;
+//! src/controllers/post_controller.rs      + pub mod post_controller;
+//! src/routes.rs                           + .merge(post_routes())
+//! migrations/<timestamp>_create_posts_table.{up,down}.sql
+//! tests/post_test.rs
+//! ```
+//!
+//! Modules and routes are wired as described in [`wire`](crate::wire),
+//! unless the config opts out with `no_wire`.
+
+use crate::wire;
+use crate::{
+    ControllerGenerator, Driver, GeneratorConfig, GeneratorResult, MigrationGenerator,
+    ModelGenerator, RequestGenerator, TemplateData, TestGenerator,
+};
+use std::path::PathBuf;
+
+/// Files touched by a scaffold
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScaffoldOutput {
+    /// Generated files
+    pub created: Vec<PathBuf>,
+    /// Module and router files updated to wire the new code in
+    pub patched: Vec<PathBuf>,
+}
+
+impl ScaffoldOutput {
+    fn patch(&mut self, path: PathBuf) {
+        if !self.patched.contains(&path) {
+            self.patched.push(path);
+        }
+    }
+}
+
+/// Scaffold generator
+pub struct ScaffoldGenerator {
+    driver: Driver,
+    models: ModelGenerator,
+    migrations: MigrationGenerator,
+    requests: RequestGenerator,
+    controllers: ControllerGenerator,
+    tests: TestGenerator,
+}
+
+impl ScaffoldGenerator {
+    /// Create a scaffold generator for a database driver
+    pub fn new(driver: Driver) -> Self {
+        Self {
+            driver,
+            models: ModelGenerator::new(),
+            migrations: MigrationGenerator::new(driver),
+            requests: RequestGenerator::new(),
+            controllers: ControllerGenerator::new(),
+            tests: TestGenerator::new(),
+        }
+    }
+
+    /// Generate and wire a resource
+    ///
+    /// `config.output_dir` is the project root.
+    pub async fn generate(&self, config: GeneratorConfig) -> GeneratorResult<ScaffoldOutput> {
+        let root = config.output_dir.clone();
+        let src = root.join("src");
+        let data = TemplateData::from_config(&config);
+        // Wired below, so the changed files can be reported
+        let in_dir = |dir: PathBuf| GeneratorConfig {
+            output_dir: dir,
+            wire: false,
+            ..config.clone()
+        };
+
+        let mut output = ScaffoldOutput::default();
+        output
+            .created
+            .push(self.models.generate(in_dir(src.join("models"))).await?);
+        output.created.extend(
+            self.migrations
+                .generate(in_dir(root.join("migrations")))
+                .await?,
+        );
+        output
+            .created
+            .push(self.requests.generate(in_dir(src.join("requests"))).await?);
+        output.created.push(
+            self.controllers
+                .generate_crud(in_dir(src.join("controllers")), self.driver)
+                .await?,
+        );
+        output
+            .created
+            .push(self.tests.generate(in_dir(root.join("tests"))).await?);
+
+        if !config.wire {
+            return Ok(output);
+        }
+
+        let generated: Vec<PathBuf> = output
+            .created
+            .iter()
+            .filter(|path| path.starts_with(&src))
+            .cloned()
+            .collect();
+        for file in &generated {
+            for patched in wire::wire_module(file).await? {
+                output.patch(patched);
+            }
+        }
+
+        let controller = src
+            .join("controllers")
+            .join(format!("{}_controller.rs", data.snake_name));
+        let routes_fn = format!("{}_routes", data.snake_name);
+        if let Some(router) = wire::wire_routes(&controller, &routes_fn).await? {
+            output.patch(router);
+        }
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Field;
+    use tokio::fs;
+
+    const ROUTES: &str = "use axum::Router;
+
+pub fn routes() -> Router<sqlx::PgPool> {
+    Router::new()
+        // rustforge:routes
+}
+";
+
+    #[tokio::test]
+    async fn test_scaffold() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).await.unwrap();
+        fs::write(root.join("src/lib.rs"), "pub mod routes;\n")
+            .await
+            .unwrap();
+        fs::write(root.join("src/routes.rs"), ROUTES).await.unwrap();
+
+        let config = GeneratorConfig::new("Post", root)
+            .with_fields(Field::parse_list("title:string body:text?").unwrap());
+        let output = ScaffoldGenerator::new(Driver::Postgres)
+            .generate(config.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(output.created.len(), 6);
+        assert!(output.created.iter().all(|path| path.exists()));
+
+        let model = fs::read_to_string(root.join("src/models/post.rs"))
+            .await
+            .unwrap();
+        assert!(model.contains("    pub title: String,\n"));
+        assert!(model.contains("    pub body: Option<String>,\n"));
+
+        let request = fs::read_to_string(root.join("src/requests/post_request.rs"))
+            .await
+            .unwrap();
+        assert!(
+            request.contains("    #[validate(length(min = 1, max = 255))]\n    pub title: String,")
+        );
+        assert!(request.contains("    pub body: Option<String>,\n}"));
+
+        let controller = fs::read_to_string(root.join("src/controllers/post_controller.rs"))
+            .await
+            .unwrap();
+        assert!(controller.contains(
+            "sqlx::query_scalar(\"INSERT INTO posts (title, body) VALUES ($1, $2) RETURNING id\")"
+        ));
+        assert!(controller.contains(
+            "UPDATE posts SET title = COALESCE($1, title), body = COALESCE($2, body), updated_at = CURRENT_TIMESTAMP WHERE id = $3"
+        ));
+        assert!(
+            controller.contains(".route(\"/posts/{id}\", get(show).put(update).delete(destroy))")
+        );
+
+        let lib = fs::read_to_string(root.join("src/lib.rs")).await.unwrap();
+        assert_eq!(
+            lib,
+            "pub mod routes;\npub mod models;\npub mod requests;\npub mod controllers;\n"
+        );
+        let models = fs::read_to_string(root.join("src/models/mod.rs"))
+            .await
+            .unwrap();
+        assert_eq!(models, "pub mod post;\n");
+        let routes = fs::read_to_string(root.join("src/routes.rs"))
+            .await
+            .unwrap();
+        assert!(routes.contains(
+            "        .merge(crate::controllers::post_controller::post_routes())\n        // rustforge:routes\n"
+        ));
+
+        // Wiring is idempotent
+        let output = ScaffoldGenerator::new(Driver::Postgres)
+            .generate(config.force())
+            .await
+            .unwrap();
+        assert!(output.patched.is_empty());
+        let again = fs::read_to_string(root.join("src/routes.rs"))
+            .await
+            .unwrap();
+        assert_eq!(routes, again);
+    }
+
+    #[tokio::test]
+    async fn test_mysql_controller() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = GeneratorConfig::new("Comment", temp_dir.path())
+            .with_fields(Field::parse_list("post:references body:text").unwrap());
+
+        let output = ScaffoldGenerator::new(Driver::MySql)
+            .generate(config)
+            .await
+            .unwrap();
+        // No crate root or router to patch, module files are created
+        assert_eq!(output.patched.len(), 3);
+
+        let controller = fs::read_to_string(
+            temp_dir
+                .path()
+                .join("src/controllers/comment_controller.rs"),
+        )
+        .await
+        .unwrap();
+        assert!(controller.contains("use sqlx::MySqlPool;"));
+        assert!(controller.contains("INSERT INTO comments (post_id, body) VALUES (?, ?)\")"));
+        assert!(controller.contains(".last_insert_id() as i64;"));
+    }
+}
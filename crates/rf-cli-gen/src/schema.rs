@@ -0,0 +1,211 @@
+//! Schema DSL for generator field lists
+//!
+//! Fields are written as `name:type[?][:modifier...]`, separated by
+//! whitespace, e.g. `--fields "name:string email:string:unique age:int?"`.
+//!
+//! - Types: `string`, `text`, `int`, `bigint`, `float`, `decimal`, `bool`,
+//!   `date`, `datetime`, `uuid`, `json` and `references`
+//! - `?` after the type makes the column nullable
+//! - Modifiers: `unique`, `index` and `default=<value>`
+//!
+//! `author:references` becomes an `author_id` column with a foreign key to
+//! `authors`; `author:references(users)` names the table explicitly.
+
+use crate::{pluralize, to_snake_case, GeneratorError, GeneratorResult};
+use serde::{Deserialize, Serialize};
+
+/// Column type of a field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    String,
+    Text,
+    Integer,
+    BigInteger,
+    Float,
+    Decimal,
+    Boolean,
+    Date,
+    DateTime,
+    Uuid,
+    Json,
+    /// Foreign key to the `id` of a table
+    References(String),
+}
+
+impl FieldType {
+    /// Rust type of the column, as used in models and requests
+    pub fn rust_type(&self) -> &'static str {
+        match self {
+            Self::String | Self::Text => "String",
+            Self::Integer => "i32",
+            Self::BigInteger | Self::References(_) => "i64",
+            Self::Float => "f64",
+            Self::Decimal => "rust_decimal::Decimal",
+            Self::Boolean => "bool",
+            Self::Date => "chrono::NaiveDate",
+            Self::DateTime => "chrono::DateTime<chrono::Utc>",
+            Self::Uuid => "uuid::Uuid",
+            Self::Json => "serde_json::Value",
+        }
+    }
+
+    fn parse(ty: &str, field: &str) -> GeneratorResult<Self> {
+        if let Some(table) = ty
+            .strip_prefix("references(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            return Ok(Self::References(table.to_string()));
+        }
+
+        Ok(match ty {
+            "string" | "str" => Self::String,
+            "text" => Self::Text,
+            "int" | "integer" => Self::Integer,
+            "bigint" => Self::BigInteger,
+            "float" | "double" => Self::Float,
+            "decimal" => Self::Decimal,
+            "bool" | "boolean" => Self::Boolean,
+            "date" => Self::Date,
+            "datetime" | "timestamp" => Self::DateTime,
+            "uuid" => Self::Uuid,
+            "json" => Self::Json,
+            "references" | "belongs_to" => Self::References(pluralize(&to_snake_case(field))),
+            _ => {
+                return Err(GeneratorError::InvalidField(format!(
+                    "unknown type '{}' for field '{}'",
+                    ty, field
+                )))
+            }
+        })
+    }
+}
+
+/// One field of a generated model or table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Field {
+    /// Column name; `_id` is appended for references
+    pub name: String,
+    pub ty: FieldType,
+    pub nullable: bool,
+    pub unique: bool,
+    pub index: bool,
+    /// Default value as written in the DSL
+    pub default: Option<String>,
+}
+
+impl Field {
+    /// A required field without modifiers
+    pub fn new(name: impl Into<String>, ty: FieldType) -> Self {
+        Self {
+            name: name.into(),
+            ty,
+            nullable: false,
+            unique: false,
+            index: false,
+            default: None,
+        }
+    }
+
+    /// Parse one field, e.g. `email:string:unique`
+    pub fn parse(spec: &str) -> GeneratorResult<Self> {
+        let mut parts = spec.split(':');
+        let name = parts.next().unwrap_or_default();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(GeneratorError::InvalidField(format!(
+                "invalid field name in '{}'",
+                spec
+            )));
+        }
+
+        let ty = parts.next().ok_or_else(|| {
+            GeneratorError::InvalidField(format!("missing type for field '{}'", name))
+        })?;
+        let (ty, nullable) = match ty.strip_suffix('?') {
+            Some(ty) => (ty, true),
+            None => (ty, false),
+        };
+        let ty = FieldType::parse(ty, name.strip_suffix("_id").unwrap_or(name))?;
+
+        let name = match ty {
+            FieldType::References(_) if !name.ends_with("_id") => format!("{}_id", name),
+            _ => name.to_string(),
+        };
+        let mut field = Self::new(name, ty);
+        field.nullable = nullable;
+
+        for modifier in parts {
+            match modifier {
+                "unique" => field.unique = true,
+                "index" => field.index = true,
+                _ => match modifier.strip_prefix("default=") {
+                    Some(value) => field.default = Some(value.to_string()),
+                    None => {
+                        return Err(GeneratorError::InvalidField(format!(
+                            "unknown modifier '{}' for field '{}'",
+                            modifier, field.name
+                        )))
+                    }
+                },
+            }
+        }
+
+        Ok(field)
+    }
+
+    /// Rust type of the field, `Option` if nullable
+    pub fn rust_type(&self) -> String {
+        if self.nullable {
+            format!("Option<{}>", self.ty.rust_type())
+        } else {
+            self.ty.rust_type().to_string()
+        }
+    }
+
+    /// Parse a whitespace separated field list
+    pub fn parse_list(spec: &str) -> GeneratorResult<Vec<Self>> {
+        spec.split_whitespace().map(Self::parse).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fields() {
+        let fields =
+            Field::parse_list("name:string email:string:unique age:int? active:bool:default=true")
+                .unwrap();
+
+        assert_eq!(fields[0], Field::new("name", FieldType::String));
+        assert!(fields[1].unique);
+        assert!(fields[2].nullable);
+        assert_eq!(fields[2].ty, FieldType::Integer);
+        assert_eq!(fields[3].default.as_deref(), Some("true"));
+    }
+
+    #[test]
+    fn test_parse_references() {
+        let field = Field::parse("author:references(users)?").unwrap();
+        assert_eq!(field.name, "author_id");
+        assert_eq!(field.ty, FieldType::References("users".to_string()));
+        assert!(field.nullable);
+
+        let field = Field::parse("author_id:references:index").unwrap();
+        assert_eq!(field.name, "author_id");
+        assert_eq!(field.ty, FieldType::References("authors".to_string()));
+        assert!(field.index);
+
+        let field = Field::parse("category:references").unwrap();
+        assert_eq!(field.ty, FieldType::References("categories".to_string()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Field::parse("name").is_err());
+        assert!(Field::parse("name:varchar").is_err());
+        assert!(Field::parse("name:string:primary").is_err());
+        assert!(Field::parse("na-me:string").is_err());
+    }
+}
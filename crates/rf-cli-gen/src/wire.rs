@@ -0,0 +1,333 @@
+//! Wiring generated code into a crate
+//!
+//! Generators declare their output in the module tree and register
+//! controller routes with the application router. Patching is textual and
+//! idempotent: a module that is already declared or a route that is already
+//! merged is left alone, so generators can be re-run with `force`.
+//!
+//! Routes are inserted in front of a `// rustforge:routes` marker line in
+//! `src/routes.rs`, `src/main.rs` or `src/lib.rs`. Without a marker they are
+//! merged right after the first `Router::new()` of those files. Disable
+//! wiring with [`GeneratorConfig::no_wire`](crate::GeneratorConfig::no_wire)
+//! (the CLI's `--no-wire`).
+
+use crate::GeneratorResult;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Marker line routes are inserted before
+pub const ROUTES_MARKER: &str = "// rustforge:routes";
+
+const CRATE_ROOTS: [&str; 2] = ["lib.rs", "main.rs"];
+
+/// Declare a generated file in its parent module
+///
+/// The declaration goes into the directory's `mod.rs`, its `dir.rs` sibling
+/// or the crate root. If none exists, `mod.rs` is created and declared in
+/// the parent module in turn. Returns the files that changed.
+pub async fn wire_module(file: &Path) -> GeneratorResult<Vec<PathBuf>> {
+    let (Some(dir), Some(module)) = (file.parent(), module_name(file)) else {
+        return Ok(Vec::new());
+    };
+
+    if let Some(module_file) = module_file(dir).await {
+        let changed = register_module(&module_file, &module).await?;
+        return Ok(changed.then_some(module_file).into_iter().collect());
+    }
+
+    let mod_rs = dir.join("mod.rs");
+    register_module(&mod_rs, &module).await?;
+    let mut patched = vec![mod_rs];
+
+    // Declare the new module directory one level up, without creating more
+    if let (Some(parent), Some(name)) = (dir.parent(), module_name(dir)) {
+        if let Some(parent_file) = module_file(parent).await {
+            if register_module(&parent_file, &name).await? {
+                patched.push(parent_file);
+            }
+        }
+    }
+    Ok(patched)
+}
+
+/// Merge a controller's routes into the application router
+///
+/// `routes_fn` is the function in `controller` returning its router.
+/// Returns the router file if it changed.
+pub async fn wire_routes(controller: &Path, routes_fn: &str) -> GeneratorResult<Option<PathBuf>> {
+    let Some(src) = crate_src(controller).await else {
+        return Ok(None);
+    };
+    let Some(path) = module_path(&src, controller) else {
+        return Ok(None);
+    };
+    let call = format!(".merge({}::{}())", path, routes_fn);
+
+    let mut candidates = Vec::new();
+    for file in ["routes.rs", "main.rs", "lib.rs"] {
+        if let Ok(content) = fs::read_to_string(src.join(file)).await {
+            if content.contains(&call) {
+                return Ok(None);
+            }
+            candidates.push((src.join(file), content));
+        }
+    }
+
+    let marked = candidates.iter().find_map(|(file, content)| {
+        insert_before_marker(content, &call).map(|patched| (file, patched))
+    });
+    let patched = marked.or_else(|| {
+        candidates.iter().find_map(|(file, content)| {
+            insert_after_router(content, &call).map(|patched| (file, patched))
+        })
+    });
+
+    match patched {
+        Some((file, content)) => {
+            fs::write(file, content).await?;
+            Ok(Some(file.clone()))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Declare `pub mod {module};` in a module file, creating it if needed
+///
+/// The declaration goes after the last existing one. Returns whether the
+/// file changed.
+pub async fn register_module(path: &Path, module: &str) -> GeneratorResult<bool> {
+    let content = fs::read_to_string(path).await.unwrap_or_default();
+    let declaration = format!("pub mod {};", module);
+
+    let declared = content.lines().any(|line| {
+        let line = line.trim();
+        line.strip_prefix("pub ")
+            .or(line.strip_prefix("pub(crate) "))
+            .unwrap_or(line)
+            == format!("mod {};", module)
+    });
+    if declared {
+        return Ok(false);
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let last_declaration = lines.iter().rposition(|line| {
+        let line = line.strip_prefix("pub ").unwrap_or(line);
+        line.starts_with("mod ") && line.ends_with(';')
+    });
+
+    let mut patched = lines;
+    match last_declaration {
+        Some(index) => patched.insert(index + 1, &declaration),
+        None => {
+            // First declaration: after the inner docs and attributes
+            let mut index = patched
+                .iter()
+                .position(|line| !line.starts_with("//!") && !line.starts_with("#!["))
+                .unwrap_or(patched.len());
+            if index > 0 && patched.get(index).is_some_and(|line| line.is_empty()) {
+                index += 1;
+            }
+            if patched.get(index).is_some_and(|line| !line.is_empty()) {
+                patched.insert(index, "");
+            }
+            patched.insert(index, &declaration);
+        }
+    }
+    let mut patched = patched.join("\n");
+    patched.push('\n');
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(path, patched).await?;
+    Ok(true)
+}
+
+/// Existing file declaring the modules of `dir`
+async fn module_file(dir: &Path) -> Option<PathBuf> {
+    let mut candidates: Vec<PathBuf> = CRATE_ROOTS.iter().map(|root| dir.join(root)).collect();
+    candidates.push(dir.join("mod.rs"));
+    if let (Some(parent), Some(name)) = (dir.parent(), module_name(dir)) {
+        candidates.push(parent.join(format!("{}.rs", name)));
+    }
+
+    for candidate in candidates {
+        if exists(&candidate).await {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+/// Closest ancestor directory holding the crate root
+async fn crate_src(file: &Path) -> Option<PathBuf> {
+    for dir in file.ancestors().skip(1) {
+        for root in CRATE_ROOTS {
+            if exists(&dir.join(root)).await {
+                return Some(dir.to_path_buf());
+            }
+        }
+        if exists(&dir.join("Cargo.toml")).await {
+            break;
+        }
+    }
+    None
+}
+
+/// `crate::controllers::post_controller` for `src/controllers/post_controller.rs`
+fn module_path(src: &Path, file: &Path) -> Option<String> {
+    let relative = file.strip_prefix(src).ok()?.with_extension("");
+    let mut path = vec!["crate".to_string()];
+    for part in relative.iter() {
+        let part = part.to_str()?;
+        if part != "mod" {
+            path.push(part.to_string());
+        }
+    }
+    Some(path.join("::"))
+}
+
+fn module_name(path: &Path) -> Option<String> {
+    path.file_stem()?.to_str().map(String::from)
+}
+
+fn insert_before_marker(content: &str, call: &str) -> Option<String> {
+    let mut patched = String::with_capacity(content.len() + call.len() + 1);
+    let mut inserted = false;
+    for line in content.split_inclusive('\n') {
+        if !inserted && line.trim() == ROUTES_MARKER {
+            patched.push_str(indentation(line));
+            patched.push_str(call);
+            patched.push('\n');
+            inserted = true;
+        }
+        patched.push_str(line);
+    }
+    inserted.then_some(patched)
+}
+
+fn insert_after_router(content: &str, call: &str) -> Option<String> {
+    const ROUTER: &str = "Router::new()";
+
+    let at = content.find(ROUTER)? + ROUTER.len();
+    let line_start = content[..at].rfind('\n').map_or(0, |i| i + 1);
+    let indent = indentation(&content[line_start..]);
+
+    Some(format!(
+        "{}\n{}    {}{}",
+        &content[..at],
+        indent,
+        call,
+        &content[at..]
+    ))
+}
+
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+async fn exists(path: &Path) -> bool {
+    fs::try_exists(path).await.unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).await.unwrap();
+        fs::write(path, content).await.unwrap();
+    }
+
+    async fn read(path: &Path) -> String {
+        fs::read_to_string(path).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_wire_module() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src");
+        write(
+            &src.join("lib.rs"),
+            "//! App\n\npub mod config;\nmod db;\n\nuse std::fmt;\n",
+        )
+        .await;
+        write(&src.join("handlers.rs"), "pub mod home;\n").await;
+
+        // New directory: mod.rs is created and declared in the crate root
+        let patched = wire_module(&src.join("models/post.rs")).await.unwrap();
+        assert_eq!(patched, [src.join("models/mod.rs"), src.join("lib.rs")]);
+        assert_eq!(read(&src.join("models/mod.rs")).await, "pub mod post;\n");
+        assert_eq!(
+            read(&src.join("lib.rs")).await,
+            "//! App\n\npub mod config;\nmod db;\npub mod models;\n\nuse std::fmt;\n"
+        );
+
+        // Existing mod.rs and `dir.rs` module files are patched
+        let patched = wire_module(&src.join("models/user.rs")).await.unwrap();
+        assert_eq!(patched, [src.join("models/mod.rs")]);
+        assert_eq!(
+            read(&src.join("models/mod.rs")).await,
+            "pub mod post;\npub mod user;\n"
+        );
+
+        wire_module(&src.join("handlers/about.rs")).await.unwrap();
+        assert_eq!(
+            read(&src.join("handlers.rs")).await,
+            "pub mod home;\npub mod about;\n"
+        );
+
+        // Idempotent
+        assert!(wire_module(&src.join("models/post.rs"))
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(wire_module(&src.join("db.rs")).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wire_routes_at_marker() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src");
+        write(&src.join("main.rs"), "fn main() {}\n").await;
+        write(
+            &src.join("routes.rs"),
+            "pub fn routes() -> Router {\n    Router::new()\n        .route(\"/\", get(home))\n        // rustforge:routes\n}\n",
+        )
+        .await;
+
+        let controller = src.join("controllers/post_controller.rs");
+        let patched = wire_routes(&controller, "post_routes").await.unwrap();
+        assert_eq!(patched, Some(src.join("routes.rs")));
+        assert_eq!(
+            read(&src.join("routes.rs")).await,
+            "pub fn routes() -> Router {\n    Router::new()\n        .route(\"/\", get(home))\n        .merge(crate::controllers::post_controller::post_routes())\n        // rustforge:routes\n}\n"
+        );
+
+        assert_eq!(wire_routes(&controller, "post_routes").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_wire_routes_without_marker() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src");
+        write(
+            &src.join("main.rs"),
+            "async fn main() {\n    let app = Router::new().route(\"/\", get(home));\n}\n",
+        )
+        .await;
+
+        wire_routes(&src.join("api/users.rs"), "user_routes")
+            .await
+            .unwrap();
+        assert_eq!(
+            read(&src.join("main.rs")).await,
+            "async fn main() {\n    let app = Router::new()\n        .merge(crate::api::users::user_routes()).route(\"/\", get(home));\n}\n"
+        );
+
+        // Nothing to patch outside of a crate
+        let outside = temp_dir.path().join("elsewhere/post_controller.rs");
+        assert_eq!(wire_routes(&outside, "post_routes").await.unwrap(), None);
+    }
+}
@@ -0,0 +1,159 @@
+//! `config:show` printing the application's configuration
+
+use crate::{Command, ConsoleError, ConsoleResult, Input, Kernel, Output};
+use async_trait::async_trait;
+use clap::Arg;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Keys whose values are masked, matched against the last part of a key
+const SENSITIVE: [&str; 6] = ["password", "secret", "token", "key", "dsn", "credentials"];
+
+struct ShowConfig {
+    config: Value,
+}
+
+#[async_trait]
+impl Command for ShowConfig {
+    fn name(&self) -> &str {
+        "config:show"
+    }
+
+    fn description(&self) -> &str {
+        "Show the configuration, with secrets masked"
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        vec![Arg::new("key").help("Dotted key to show, e.g. `database.pool.max`")]
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let key = input.value("key").unwrap_or_default();
+        let value = key
+            .split('.')
+            .filter(|part| !part.is_empty())
+            .try_fold(&self.config, |value, part| match value {
+                Value::Object(map) => map.get(part),
+                Value::Array(items) => part.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => None,
+            })
+            .ok_or_else(|| ConsoleError::failed(format!("No configuration at \"{}\"", key)))?;
+
+        let mut rows = Vec::new();
+        flatten(key.trim_matches('.'), value, &mut rows);
+        output.table(&["Key", "Value"], &rows);
+        Ok(())
+    }
+}
+
+/// `value` as one row per leaf, keyed by its dotted path
+fn flatten(key: &str, value: &Value, rows: &mut Vec<[String; 2]>) {
+    let child = |name: &str| match key {
+        "" => name.to_string(),
+        key => format!("{}.{}", key, name),
+    };
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (name, value) in map {
+                flatten(&child(name), value, rows);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (i, value) in items.iter().enumerate() {
+                flatten(&child(&i.to_string()), value, rows);
+            }
+        }
+        _ if is_sensitive(key) && !value.is_null() => {
+            rows.push([key.to_string(), "********".to_string()])
+        }
+        Value::String(text) => rows.push([key.to_string(), text.clone()]),
+        value => rows.push([key.to_string(), value.to_string()]),
+    }
+}
+
+fn is_sensitive(key: &str) -> bool {
+    let name = key.rsplit('.').next().unwrap_or(key).to_ascii_lowercase();
+    SENSITIVE
+        .iter()
+        .any(|sensitive| name == *sensitive || name.ends_with(&format!("_{}", sensitive)))
+}
+
+impl Kernel {
+    /// Register `config:show` printing `config`, e.g. the application's
+    /// settings struct, as of now
+    ///
+    /// Values of keys such as `password` or `api_key` are masked.
+    pub fn config(self, config: &impl Serialize) -> Self {
+        let config = serde_json::to_value(config)
+            .unwrap_or_else(|e| Value::String(format!("The configuration can't be shown: {}", e)));
+        self.command(ShowConfig { config })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Database {
+        url: String,
+        password: String,
+        pool: Vec<u32>,
+    }
+
+    #[derive(Serialize)]
+    struct Settings {
+        name: String,
+        debug: bool,
+        stripe_key: Option<String>,
+        database: Database,
+    }
+
+    fn kernel() -> Kernel {
+        Kernel::new("shop").config(&Settings {
+            name: "Shop".to_string(),
+            debug: false,
+            stripe_key: Some("sk_live_1".to_string()),
+            database: Database {
+                url: "postgres://db/shop".to_string(),
+                password: "hunter2".to_string(),
+                pool: vec![5, 20],
+            },
+        })
+    }
+
+    async fn show(kernel: &Kernel, args: &[&str]) -> String {
+        let mut output = Output::buffered();
+        let args = args.iter().map(|arg| arg.to_string()).collect();
+        kernel.call("config:show", args, &mut output).await.unwrap();
+        // Cells are padded to the widest one
+        output
+            .contents()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[tokio::test]
+    async fn test_config_show() {
+        let kernel = kernel();
+        let output = show(&kernel, &[]).await;
+        assert!(output.contains("| database.pool.1 | 20 |"));
+        assert!(output.contains("| database.password | ******** |"));
+        assert!(output.contains("| stripe_key | ******** |"));
+        assert!(!output.contains("hunter2") && !output.contains("sk_live"));
+
+        let output = show(&kernel, &["database.url"]).await;
+        assert!(output.contains("| database.url | postgres://db/shop |"));
+        assert!(!output.contains("debug"));
+
+        let result = kernel
+            .call(
+                "config:show",
+                vec!["cache".to_string()],
+                &mut Output::buffered(),
+            )
+            .await;
+        assert!(matches!(result, Err(ConsoleError::Failed(_))));
+    }
+}
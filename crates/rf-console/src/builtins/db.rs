@@ -0,0 +1,432 @@
+//! `db:query` and `db:execute` running SQL, and `<table>:find`,
+//! `<table>:list` and `<table>:count` reading models through their
+//! rf-orm-lite repository
+
+use crate::{Command, ConsoleError, ConsoleResult, Input, Kernel, Output};
+use async_trait::async_trait;
+use clap::{Arg, ArgAction};
+use rf_orm_lite::sqlx::any::AnyRow;
+use rf_orm_lite::sqlx::{Column, Row, TypeInfo, ValueRef};
+use rf_orm_lite::{Connection, Db, Model, Query, Value};
+use serde::Serialize;
+use std::marker::PhantomData;
+
+/// Query parameter typed in a command: integers, floats, `true`, `false`
+/// and `null` are bound as such, anything else as text
+fn binding(arg: &str) -> Value {
+    match arg {
+        "null" | "NULL" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => arg
+            .parse::<i64>()
+            .map(Value::Int)
+            .or_else(|_| arg.parse::<f64>().map(Value::Float))
+            .unwrap_or_else(|_| Value::Text(arg.to_string())),
+    }
+}
+
+/// Cell of column `i`, trying the types the Any driver decodes
+fn cell(row: &AnyRow, i: usize) -> String {
+    fn get<'r, T>(row: &'r AnyRow, i: usize) -> Option<Option<T>>
+    where
+        T: rf_orm_lite::sqlx::Decode<'r, rf_orm_lite::sqlx::Any>
+            + rf_orm_lite::sqlx::Type<rf_orm_lite::sqlx::Any>,
+    {
+        row.try_get::<Option<T>, _>(i).ok()
+    }
+
+    // The `Any` driver's `is_null()` is always false, NULLs have type NULL
+    if row
+        .try_get_raw(i)
+        .is_ok_and(|value| value.type_info().name() == "NULL")
+    {
+        return "NULL".to_string();
+    }
+    let text = get::<i64>(row, i)
+        .map(|value| value.map(|value| value.to_string()))
+        .or_else(|| get::<f64>(row, i).map(|value| value.map(|value| value.to_string())))
+        .or_else(|| get::<String>(row, i))
+        .or_else(|| get::<bool>(row, i).map(|value| value.map(|value| value.to_string())))
+        .or_else(|| {
+            get::<Vec<u8>>(row, i)
+                .map(|value| value.map(|bytes| format!("<{} bytes>", bytes.len())))
+        });
+    match text {
+        Some(Some(text)) => text,
+        Some(None) => "NULL".to_string(),
+        None => "?".to_string(),
+    }
+}
+
+fn sql_arguments() -> Vec<Arg> {
+    vec![
+        Arg::new("sql").required(true).help("Statement, quoted"),
+        Arg::new("bindings")
+            .num_args(0..)
+            .help("Values of the statement's placeholders"),
+    ]
+}
+
+fn bindings(input: &Input) -> Vec<Value> {
+    input.values("bindings").into_iter().map(binding).collect()
+}
+
+struct DbQuery {
+    db: Db,
+}
+
+#[async_trait]
+impl Command for DbQuery {
+    fn name(&self) -> &str {
+        "db:query"
+    }
+
+    fn description(&self) -> &str {
+        "Run a query and show the rows it returns"
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        sql_arguments()
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let sql = input.value("sql").unwrap_or_default();
+        let rows = self
+            .db
+            .fetch_all(sql, bindings(input))
+            .await
+            .map_err(ConsoleError::failed)?;
+
+        let Some(first) = rows.first() else {
+            output.comment("No rows");
+            return Ok(());
+        };
+        let headers: Vec<&str> = first.columns().iter().map(|column| column.name()).collect();
+        let cells: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| (0..row.len()).map(|i| cell(row, i)).collect())
+            .collect();
+        output.table(&headers, &cells);
+        output.comment(&format!("{} rows", rows.len()));
+        Ok(())
+    }
+}
+
+struct DbExecute {
+    db: Db,
+}
+
+#[async_trait]
+impl Command for DbExecute {
+    fn name(&self) -> &str {
+        "db:execute"
+    }
+
+    fn description(&self) -> &str {
+        "Run a statement changing data"
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        let mut arguments = sql_arguments();
+        arguments.push(
+            Arg::new("force")
+                .long("force")
+                .action(ArgAction::SetTrue)
+                .help("Confirm running the statement"),
+        );
+        arguments
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        if !input.flag("force") {
+            return Err(ConsoleError::InvalidArguments(
+                "db:execute changes data, run it again with --force".to_string(),
+            ));
+        }
+        let sql = input.value("sql").unwrap_or_default();
+        let result = self
+            .db
+            .execute(sql, bindings(input))
+            .await
+            .map_err(ConsoleError::failed)?;
+        output.success(&format!("{} rows affected", result.rows_affected()));
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+    Find,
+    List,
+    Count,
+}
+
+struct ModelCommand<T> {
+    name: String,
+    action: Action,
+    db: Db,
+    model: PhantomData<fn() -> T>,
+}
+
+impl<T: Model> ModelCommand<T> {
+    /// Query filtered by the `--where column=value` options
+    fn query(input: &Input) -> ConsoleResult<Query<T>> {
+        input
+            .values("where")
+            .into_iter()
+            .try_fold(T::query(), |query, condition| {
+                let (column, value) = condition.split_once('=').ok_or_else(|| {
+                    ConsoleError::InvalidArguments(format!(
+                        "Invalid condition \"{}\", expected column=value",
+                        condition
+                    ))
+                })?;
+                Ok(query.where_eq(column.trim(), binding(value.trim())))
+            })
+    }
+}
+
+/// Fields of `model` as (name, value) pairs
+fn fields(model: &impl Serialize) -> ConsoleResult<Vec<(String, String)>> {
+    match serde_json::to_value(model).map_err(ConsoleError::failed)? {
+        serde_json::Value::Object(map) => Ok(map
+            .into_iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    serde_json::Value::String(text) => text,
+                    value => value.to_string(),
+                };
+                (name, value)
+            })
+            .collect()),
+        value => Ok(vec![(String::new(), value.to_string())]),
+    }
+}
+
+#[async_trait]
+impl<T: Model + Serialize> Command for ModelCommand<T> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        match self.action {
+            Action::Find => "Show a record by its primary key",
+            Action::List => "List records",
+            Action::Count => "Count records",
+        }
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        let condition = Arg::new("where")
+            .long("where")
+            .action(ArgAction::Append)
+            .help("Only records where column=value, repeatable");
+        match self.action {
+            Action::Find => vec![Arg::new("id").required(true)],
+            Action::List => vec![
+                condition,
+                Arg::new("limit")
+                    .long("limit")
+                    .default_value("20")
+                    .help("Most records to show"),
+            ],
+            Action::Count => vec![condition],
+        }
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let repository = self.db.repository::<T>();
+        match self.action {
+            Action::Find => {
+                let id = input.parse::<i64>("id")?.unwrap_or_default();
+                let model = repository
+                    .find(id)
+                    .await
+                    .map_err(ConsoleError::failed)?
+                    .ok_or_else(|| {
+                        ConsoleError::failed(format!("No {} record with ID {}", T::TABLE, id))
+                    })?;
+                let rows: Vec<[String; 2]> = fields(&model)?
+                    .into_iter()
+                    .map(|(name, value)| [name, value])
+                    .collect();
+                output.table(&["Field", "Value"], &rows);
+            }
+            Action::List => {
+                let limit = input.parse::<u64>("limit")?.unwrap_or(20);
+                let models = repository
+                    .get(Self::query(input)?.limit(limit))
+                    .await
+                    .map_err(ConsoleError::failed)?;
+                let Some(first) = models.first() else {
+                    output.comment("No records");
+                    return Ok(());
+                };
+                let headers: Vec<String> =
+                    fields(first)?.into_iter().map(|(name, _)| name).collect();
+                let rows = models
+                    .iter()
+                    .map(|model| Ok(fields(model)?.into_iter().map(|(_, value)| value).collect()))
+                    .collect::<ConsoleResult<Vec<Vec<String>>>>()?;
+                let headers: Vec<&str> = headers.iter().map(String::as_str).collect();
+                output.table(&headers, &rows);
+            }
+            Action::Count => {
+                let count = repository
+                    .count(Self::query(input)?)
+                    .await
+                    .map_err(ConsoleError::failed)?;
+                output.line(&count.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Kernel {
+    /// Register `db:query` and `db:execute` running SQL on `db`
+    ///
+    /// `db:execute` only runs with `--force`, as tinker sessions often run
+    /// against production.
+    pub fn database(self, db: Db) -> Self {
+        self.command(DbQuery { db: db.clone() })
+            .command(DbExecute { db })
+    }
+
+    /// Register `<table>:find`, `<table>:list` and `<table>:count` reading
+    /// `T` through its repository, e.g. `users:list --where role=admin`
+    pub fn model<T: Model + Serialize>(self, db: Db) -> Self {
+        [
+            (Action::Find, "find"),
+            (Action::List, "list"),
+            (Action::Count, "count"),
+        ]
+        .into_iter()
+        .fold(self, |kernel, (action, name)| {
+            kernel.command(ModelCommand::<T> {
+                name: format!("{}:{}", T::TABLE, name),
+                action,
+                db: db.clone(),
+                model: PhantomData,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+    struct User {
+        id: Option<i64>,
+        email: String,
+        role: String,
+    }
+
+    impl Model for User {
+        const TABLE: &'static str = "users";
+
+        fn id(&self) -> Option<i64> {
+            self.id
+        }
+
+        fn values(&self) -> Vec<(&'static str, Value)> {
+            vec![
+                ("email", self.email.clone().into()),
+                ("role", self.role.clone().into()),
+            ]
+        }
+    }
+
+    async fn kernel() -> (Kernel, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+        let db = Db::connect(&url).await.unwrap();
+        db.execute(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL, role TEXT NOT NULL)",
+            vec![],
+        )
+        .await
+        .unwrap();
+        let kernel = Kernel::new("shop").database(db.clone()).model::<User>(db);
+        (kernel, dir)
+    }
+
+    async fn run(kernel: &Kernel, line: &str) -> (ConsoleResult<()>, String) {
+        let mut args = crate::kernel::split_line(line).unwrap();
+        let name = args.remove(0);
+        let mut output = Output::buffered();
+        let result = kernel.call(&name, args, &mut output).await;
+        // Cells are padded to the widest one
+        let output = output.contents();
+        (
+            result,
+            output.split_whitespace().collect::<Vec<_>>().join(" "),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_db_commands() {
+        let (kernel, _dir) = kernel().await;
+        let insert = "INSERT INTO users (email, role) VALUES (?, ?), (?, ?)";
+        let (result, _) = run(
+            &kernel,
+            &format!(
+                "db:execute '{}' ada@example.com admin bob@example.com user",
+                insert
+            ),
+        )
+        .await;
+        assert!(matches!(result, Err(ConsoleError::InvalidArguments(_))));
+
+        let line = format!(
+            "db:execute '{}' ada@example.com admin bob@example.com user --force",
+            insert
+        );
+        let (result, output) = run(&kernel, &line).await;
+        result.unwrap();
+        assert_eq!(output, "2 rows affected");
+
+        let (result, output) = run(
+            &kernel,
+            "db:query 'SELECT id, email, NULL AS note FROM users WHERE id > ? ORDER BY id' 0",
+        )
+        .await;
+        result.unwrap();
+        assert!(output.contains("| id | email | note |"));
+        assert!(output.contains("| 2 | bob@example.com | NULL |"));
+        assert!(output.ends_with("2 rows"));
+    }
+
+    #[tokio::test]
+    async fn test_model_commands() {
+        let (kernel, _dir) = kernel().await;
+        let (result, _) = run(
+            &kernel,
+            "db:execute \"INSERT INTO users (email, role) VALUES ('ada@example.com', 'admin'), ('bob@example.com', 'user')\" --force",
+        )
+        .await;
+        result.unwrap();
+
+        let (result, output) = run(&kernel, "users:find 1").await;
+        result.unwrap();
+        assert!(output.contains("| email | ada@example.com |"));
+        let (result, _) = run(&kernel, "users:find 9").await;
+        assert!(matches!(result, Err(ConsoleError::Failed(_))));
+
+        let (result, output) = run(&kernel, "users:list --where role=user").await;
+        result.unwrap();
+        assert!(output.contains("| email | id | role |"));
+        assert!(output.contains("| bob@example.com | 2 | user |"));
+        assert!(!output.contains("ada@"));
+
+        let (result, output) = run(&kernel, "users:count").await;
+        result.unwrap();
+        assert_eq!(output, "2");
+        let (result, _) = run(&kernel, "users:count --where role").await;
+        assert!(matches!(result, Err(ConsoleError::InvalidArguments(_))));
+    }
+}
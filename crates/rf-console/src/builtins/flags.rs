@@ -0,0 +1,166 @@
+//! `flags:*` inspecting rf-feature-flags
+
+use crate::{Command, ConsoleError, ConsoleResult, Input, Kernel, Output};
+use async_trait::async_trait;
+use clap::{Arg, ArgAction};
+use rf_feature_flags::{FeatureFlags, FlagContext};
+
+struct ListFlags {
+    flags: FeatureFlags,
+}
+
+#[async_trait]
+impl Command for ListFlags {
+    fn name(&self) -> &str {
+        "flags:list"
+    }
+
+    fn description(&self) -> &str {
+        "List the feature flags and their targeting"
+    }
+
+    async fn handle(&self, _input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let mut flags = self.flags.list().await.map_err(ConsoleError::failed)?;
+        if flags.is_empty() {
+            output.info("No feature flags");
+            return Ok(());
+        }
+
+        flags.sort_by(|a, b| a.name.cmp(&b.name));
+        let rows: Vec<[String; 5]> = flags
+            .into_iter()
+            .map(|flag| {
+                [
+                    flag.name,
+                    if flag.enabled { "yes" } else { "no" }.to_string(),
+                    flag.percentage
+                        .map(|percentage| format!("{}%", percentage))
+                        .unwrap_or_default(),
+                    flag.user_ids.join(", "),
+                    flag.groups.join(", "),
+                ]
+            })
+            .collect();
+        output.table(&["Flag", "Enabled", "Rollout", "Users", "Groups"], &rows);
+        Ok(())
+    }
+}
+
+struct CheckFlag {
+    flags: FeatureFlags,
+}
+
+#[async_trait]
+impl Command for CheckFlag {
+    fn name(&self) -> &str {
+        "flags:check"
+    }
+
+    fn description(&self) -> &str {
+        "Evaluate a feature flag for a user, tenant or groups"
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        vec![
+            Arg::new("flag").required(true),
+            Arg::new("user").long("user"),
+            Arg::new("tenant").long("tenant"),
+            Arg::new("group").long("group").action(ArgAction::Append),
+        ]
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let flag = input.value("flag").unwrap_or_default();
+        let mut context = FlagContext::new();
+        if let Some(user) = input.value("user") {
+            context = context.user(user);
+        }
+        if let Some(tenant) = input.value("tenant") {
+            context = context.tenant(tenant);
+        }
+        for group in input.values("group") {
+            context = context.group(group);
+        }
+
+        let enabled = self
+            .flags
+            .evaluate(flag, &context)
+            .await
+            .map_err(ConsoleError::failed)?;
+        if enabled {
+            output.success(&format!("{} is enabled", flag));
+        } else {
+            output.warning(&format!("{} is disabled", flag));
+        }
+        Ok(())
+    }
+}
+
+impl Kernel {
+    /// Register `flags:list` and `flags:check <flag>` inspecting `flags`
+    pub fn feature_flags(self, flags: FeatureFlags) -> Self {
+        self.command(ListFlags {
+            flags: flags.clone(),
+        })
+        .command(CheckFlag { flags })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rf_feature_flags::FlagConfig;
+
+    #[tokio::test]
+    async fn test_flag_commands() {
+        let flags = FeatureFlags::new();
+        flags
+            .set_config(
+                FlagConfig::new("new_checkout")
+                    .percentage(25.0)
+                    .for_users(vec!["42".to_string()])
+                    .for_groups(vec!["beta".to_string()]),
+            )
+            .await
+            .unwrap();
+        let kernel = Kernel::new("shop").feature_flags(flags);
+
+        let mut output = Output::buffered();
+        kernel
+            .call("flags:list", vec![], &mut output)
+            .await
+            .unwrap();
+        let table = output
+            .contents()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(
+            table.contains("| new_checkout | no | 25% | 42 | beta |"),
+            "{}",
+            table
+        );
+
+        let check = |args: &[&str]| {
+            let args = args.iter().map(|arg| arg.to_string()).collect();
+            async {
+                let mut output = Output::buffered();
+                kernel.call("flags:check", args, &mut output).await.unwrap();
+                output.contents()
+            }
+        };
+        assert_eq!(
+            check(&["new_checkout", "--user", "42"]).await,
+            "new_checkout is enabled\n"
+        );
+        assert_eq!(
+            check(&["new_checkout", "--group", "staff", "--group", "beta"]).await,
+            "new_checkout is enabled\n"
+        );
+        assert_eq!(
+            check(&["new_checkout", "--group", "staff"]).await,
+            "new_checkout is disabled\n"
+        );
+        assert_eq!(check(&["unknown"]).await, "unknown is disabled\n");
+    }
+}
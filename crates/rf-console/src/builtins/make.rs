@@ -0,0 +1,225 @@
+//! `make:*` commands generating code with rf-cli-gen
+
+use crate::{Command, ConsoleError, ConsoleResult, Input, Kernel, Output};
+use async_trait::async_trait;
+use clap::{Arg, ArgAction};
+use rf_cli_gen::{
+    ComponentGenerator, ComponentKind, ControllerGenerator, Driver, Field, GeneratorConfig,
+    MigrationGenerator, ModelGenerator, TestGenerator,
+};
+use std::path::PathBuf;
+
+/// What a `make:*` command generates
+#[derive(Clone, Copy)]
+enum Target {
+    Model,
+    Controller,
+    Migration,
+    Test,
+    Component(ComponentKind),
+}
+
+impl Target {
+    fn label(self) -> &'static str {
+        match self {
+            Target::Model => "Model",
+            Target::Controller => "Controller",
+            Target::Migration => "Migration",
+            Target::Test => "Test",
+            Target::Component(kind) => kind.suffix(),
+        }
+    }
+
+    /// Default directory of the generated files
+    fn dir(self) -> String {
+        match self {
+            Target::Model => "src/models".to_string(),
+            Target::Controller => "src/controllers".to_string(),
+            Target::Migration => "migrations".to_string(),
+            Target::Test => "tests".to_string(),
+            Target::Component(kind) => format!("src/{}", kind.dir()),
+        }
+    }
+}
+
+struct Make {
+    target: Target,
+    name: String,
+    description: String,
+}
+
+impl Make {
+    fn new(target: Target) -> Self {
+        let label = target.label();
+        Self {
+            target,
+            name: format!("make:{}", label.to_lowercase()),
+            description: format!("Create a new {}", label.to_lowercase()),
+        }
+    }
+}
+
+#[async_trait]
+impl Command for Make {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        let mut arguments = vec![
+            Arg::new("name").required(true).help(format!(
+                "Name of the {}",
+                self.target.label().to_lowercase()
+            )),
+            Arg::new("fields")
+                .long("fields")
+                .help("Fields like \"title:string published:bool\""),
+            Arg::new("path")
+                .long("path")
+                .help(format!("Output directory [default: {}]", self.target.dir())),
+            Arg::new("force")
+                .long("force")
+                .action(ArgAction::SetTrue)
+                .help("Overwrite existing files"),
+            Arg::new("no-wire")
+                .long("no-wire")
+                .action(ArgAction::SetTrue)
+                .help("Leave the module tree and router alone"),
+        ];
+        if matches!(self.target, Target::Model | Target::Migration) {
+            arguments.push(
+                Arg::new("driver")
+                    .long("driver")
+                    .default_value("postgres")
+                    .help("Database of the migration"),
+            );
+        }
+        if matches!(self.target, Target::Model) {
+            arguments.push(
+                Arg::new("migration")
+                    .long("migration")
+                    .short('m')
+                    .action(ArgAction::SetTrue)
+                    .help("Also create a migration for the table"),
+            );
+        }
+        arguments
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let name = input.value("name").unwrap_or_default();
+        let dir = input
+            .value("path")
+            .map_or_else(|| PathBuf::from(self.target.dir()), PathBuf::from);
+        let mut config = GeneratorConfig::new(name, dir);
+        if let Some(fields) = input.value("fields") {
+            let fields = Field::parse_list(fields)
+                .map_err(|e| ConsoleError::InvalidArguments(e.to_string()))?;
+            config = config.with_fields(fields);
+        }
+        if input.flag("force") {
+            config = config.force();
+        }
+        if input.flag("no-wire") {
+            config = config.no_wire();
+        }
+        let driver = input.parse::<Driver>("driver")?.unwrap_or(Driver::Postgres);
+
+        let paths = match self.target {
+            Target::Model if input.flag("migration") => {
+                ModelGenerator::new()
+                    .generate_with_migration(config, driver, Target::Migration.dir())
+                    .await
+            }
+            Target::Model => ModelGenerator::new()
+                .generate(config)
+                .await
+                .map(|p| vec![p]),
+            Target::Controller => ControllerGenerator::new()
+                .generate(config)
+                .await
+                .map(|p| vec![p]),
+            Target::Migration => MigrationGenerator::new(driver).generate(config).await,
+            Target::Test => TestGenerator::new().generate(config).await.map(|p| vec![p]),
+            Target::Component(kind) => ComponentGenerator::new(kind)
+                .generate(config)
+                .await
+                .map(|p| vec![p]),
+        }
+        .map_err(ConsoleError::failed)?;
+
+        for path in paths {
+            output.info(&format!("Created {}", path.display()));
+        }
+        Ok(())
+    }
+}
+
+impl Kernel {
+    /// Register `make:model`, `make:controller`, `make:migration`,
+    /// `make:test` and a command per [`ComponentKind`]
+    pub(crate) fn make_commands(self) -> Self {
+        [
+            Target::Model,
+            Target::Controller,
+            Target::Migration,
+            Target::Test,
+        ]
+        .into_iter()
+        .chain(ComponentKind::ALL.map(Target::Component))
+        .fold(self, |kernel, target| kernel.command(Make::new(target)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_make_commands() {
+        let kernel = Kernel::new("shop");
+        for name in ["make:model", "make:controller", "make:job", "make:policy"] {
+            assert!(kernel.has(name), "{} is missing", name);
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("models");
+        let args = |extra: &[&str]| -> Vec<String> {
+            let mut args = vec!["BlogPost", "--path", path.to_str().unwrap(), "--no-wire"];
+            args.extend(extra);
+            args.into_iter().map(String::from).collect()
+        };
+
+        let mut output = Output::buffered();
+        kernel
+            .call(
+                "make:model",
+                args(&["--fields", "title:string"]),
+                &mut output,
+            )
+            .await
+            .unwrap();
+        let model = path.join("blog_post.rs");
+        assert_eq!(output.contents(), format!("Created {}\n", model.display()));
+        assert!(std::fs::read_to_string(model)
+            .unwrap()
+            .contains("pub title: String"));
+
+        // Existing files are kept without --force
+        let result = kernel.call("make:model", args(&[]), &mut output).await;
+        assert!(matches!(result, Err(ConsoleError::Failed(_))));
+        kernel
+            .call("make:model", args(&["--force"]), &mut output)
+            .await
+            .unwrap();
+
+        let result = kernel
+            .call("make:migration", args(&["--driver", "oracle"]), &mut output)
+            .await;
+        assert!(matches!(result, Err(ConsoleError::InvalidArguments(_))));
+    }
+}
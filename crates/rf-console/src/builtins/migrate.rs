@@ -0,0 +1,211 @@
+//! `migrate*` commands running rf-migrate migrations
+
+use crate::{Command, ConsoleError, ConsoleResult, Input, Kernel, Output, Style};
+use async_trait::async_trait;
+use clap::Arg;
+use rf_migrate::{AnyPool, Migration, Migrator};
+use std::sync::Arc;
+
+#[derive(Clone, Copy)]
+enum Action {
+    Up,
+    Rollback,
+    Redo,
+    Status,
+    Fresh,
+}
+
+struct Migrate {
+    action: Action,
+    migrator: Arc<Migrator>,
+    database_url: Arc<str>,
+}
+
+impl Migrate {
+    async fn connect(&self, input: &Input) -> ConsoleResult<AnyPool> {
+        let url = input.value("database").unwrap_or(&self.database_url);
+        rf_migrate::connect(url).await.map_err(ConsoleError::failed)
+    }
+}
+
+#[async_trait]
+impl Command for Migrate {
+    fn name(&self) -> &str {
+        match self.action {
+            Action::Up => "migrate",
+            Action::Rollback => "migrate:rollback",
+            Action::Redo => "migrate:redo",
+            Action::Status => "migrate:status",
+            Action::Fresh => "migrate:fresh",
+        }
+    }
+
+    fn description(&self) -> &str {
+        match self.action {
+            Action::Up => "Run pending migrations",
+            Action::Rollback => "Roll back the latest migrations",
+            Action::Redo => "Roll back the latest migrations and run them again",
+            Action::Status => "Show which migrations ran",
+            Action::Fresh => "Drop all tables and run all migrations",
+        }
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        let mut arguments = vec![Arg::new("database")
+            .long("database")
+            .help("Database URL instead of the configured one")];
+        if matches!(self.action, Action::Rollback | Action::Redo) {
+            arguments.push(
+                Arg::new("steps")
+                    .long("steps")
+                    .default_value("1")
+                    .help("Number of migrations"),
+            );
+        }
+        arguments
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let pool = self.connect(input).await?;
+        let steps = input.parse::<usize>("steps")?.unwrap_or(1);
+        let migrator = &self.migrator;
+
+        let (migrations, done, verb) = match self.action {
+            Action::Up => (migrator.up(&pool).await, "Migrated", "migrate"),
+            Action::Rollback => (
+                migrator.down(&pool, steps).await,
+                "Rolled back",
+                "roll back",
+            ),
+            Action::Redo => (migrator.redo(&pool, steps).await, "Migrated", "redo"),
+            Action::Fresh => {
+                let migrations = migrator.fresh(&pool).await;
+                if migrations.is_ok() {
+                    output.comment("Dropped all tables");
+                }
+                (migrations, "Migrated", "migrate")
+            }
+            Action::Status => return status(migrator, &pool, output).await,
+        };
+        report(
+            migrations.map_err(ConsoleError::failed)?,
+            done,
+            verb,
+            output,
+        );
+        Ok(())
+    }
+}
+
+fn report(migrations: Vec<&Migration>, done: &str, verb: &str, output: &mut Output) {
+    if migrations.is_empty() {
+        output.comment(&format!("Nothing to {}", verb));
+    }
+    for migration in migrations {
+        let done = output.paint(done, Style::Info);
+        output.line(&format!("{} {}", done, migration));
+    }
+}
+
+async fn status(migrator: &Migrator, pool: &AnyPool, output: &mut Output) -> ConsoleResult<()> {
+    let statuses = migrator.status(pool).await.map_err(ConsoleError::failed)?;
+    if statuses.is_empty() {
+        output.comment("No migrations");
+        return Ok(());
+    }
+
+    let rows: Vec<Vec<String>> = statuses
+        .iter()
+        .map(|status| {
+            let state = match status.batch {
+                _ if status.missing => output.paint("Missing", Style::Error),
+                Some(_) => output.paint("Ran", Style::Info),
+                None => output.paint("Pending", Style::Comment),
+            };
+            let batch = status.batch.map(|b| b.to_string()).unwrap_or_default();
+            vec![format!("{}_{}", status.version, status.name), state, batch]
+        })
+        .collect();
+    output.table(&["Migration", "Status", "Batch"], &rows);
+    Ok(())
+}
+
+impl Kernel {
+    /// Register `migrate`, `migrate:rollback`, `migrate:redo`,
+    /// `migrate:status` and `migrate:fresh` running `migrator` on the
+    /// database at `database_url`
+    pub fn migrations(self, migrator: Migrator, database_url: impl Into<String>) -> Self {
+        let migrator = Arc::new(migrator);
+        let database_url: Arc<str> = database_url.into().into();
+        [
+            Action::Up,
+            Action::Rollback,
+            Action::Redo,
+            Action::Status,
+            Action::Fresh,
+        ]
+        .into_iter()
+        .fold(self, |kernel, action| {
+            kernel.command(Migrate {
+                action,
+                migrator: Arc::clone(&migrator),
+                database_url: Arc::clone(&database_url),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_migrate_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+        let kernel = Kernel::new("shop").migrations(
+            Migrator::new()
+                .migration(
+                    Migration::new(1, "create_users", "CREATE TABLE users (id INTEGER)")
+                        .down("DROP TABLE users"),
+                )
+                .migration(
+                    Migration::new(2, "create_posts", "CREATE TABLE posts (id INTEGER)")
+                        .down("DROP TABLE posts"),
+                ),
+            url,
+        );
+
+        let run = |name: &'static str, args: &[&str]| {
+            let args = args.iter().map(|arg| arg.to_string()).collect();
+            let kernel = &kernel;
+            async move {
+                let mut output = Output::buffered();
+                kernel.call(name, args, &mut output).await.unwrap();
+                output.contents()
+            }
+        };
+
+        assert_eq!(
+            run("migrate", &[]).await,
+            "Migrated 1_create_users\nMigrated 2_create_posts\n"
+        );
+        assert_eq!(run("migrate", &[]).await, "Nothing to migrate\n");
+        assert_eq!(
+            run("migrate:rollback", &["--steps", "1"]).await,
+            "Rolled back 2_create_posts\n"
+        );
+        assert_eq!(
+            run("migrate:status", &[]).await,
+            "+----------------+---------+-------+\n\
+             | Migration      | Status  | Batch |\n\
+             +----------------+---------+-------+\n\
+             | 1_create_users | Ran     | 1     |\n\
+             | 2_create_posts | Pending |       |\n\
+             +----------------+---------+-------+\n"
+        );
+        assert!(run("migrate:fresh", &[])
+            .await
+            .starts_with("Dropped all tables\n"));
+    }
+}
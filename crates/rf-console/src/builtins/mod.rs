@@ -0,0 +1,27 @@
+//! Commands of other RustForge crates, each behind its feature
+
+mod config;
+
+#[cfg(feature = "db")]
+mod db;
+
+#[cfg(feature = "flags")]
+mod flags;
+
+#[cfg(feature = "make")]
+mod make;
+
+#[cfg(feature = "migrate")]
+mod migrate;
+
+#[cfg(feature = "notifications")]
+mod notifications;
+
+#[cfg(feature = "queue")]
+mod queue;
+
+#[cfg(feature = "schedule")]
+mod schedule;
+
+#[cfg(feature = "queue")]
+pub use queue::JobDispatcher;
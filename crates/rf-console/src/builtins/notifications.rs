@@ -0,0 +1,200 @@
+//! `notify:test` sending a test notification through rf-notifications
+
+use crate::{Command, ConsoleError, ConsoleResult, Input, Kernel, Output};
+use async_trait::async_trait;
+use clap::{builder::PossibleValuesParser, Arg};
+use rf_notifications::{
+    Channel, DatabaseNotification, MailMessage, Notifiable, Notification, NotificationManager,
+    NotificationResult, PushMessage, SmsMessage,
+};
+use std::sync::Arc;
+
+/// Whoever the test notification goes to, on the one channel tested
+struct Recipient {
+    to: String,
+}
+
+impl Notifiable for Recipient {
+    fn email(&self) -> Option<String> {
+        Some(self.to.clone())
+    }
+
+    fn phone(&self) -> Option<String> {
+        Some(self.to.clone())
+    }
+
+    fn push_token(&self) -> Option<String> {
+        Some(self.to.clone())
+    }
+
+    fn id(&self) -> String {
+        self.to.clone()
+    }
+}
+
+struct TestNotification {
+    channel: Channel,
+    subject: String,
+    message: String,
+}
+
+impl Notification for TestNotification {
+    fn via(&self, _notifiable: &dyn Notifiable) -> Vec<Channel> {
+        vec![self.channel.clone()]
+    }
+
+    fn to_mail(&self, notifiable: &dyn Notifiable) -> NotificationResult<MailMessage> {
+        Ok(MailMessage::new()
+            .to(notifiable.email().unwrap_or_default())
+            .subject(&self.subject)
+            .body(&self.message))
+    }
+
+    fn to_sms(&self, notifiable: &dyn Notifiable) -> NotificationResult<SmsMessage> {
+        Ok(SmsMessage::new(
+            notifiable.phone().unwrap_or_default(),
+            &self.message,
+        ))
+    }
+
+    fn to_push(&self, _notifiable: &dyn Notifiable) -> NotificationResult<PushMessage> {
+        Ok(PushMessage::new(&self.subject, &self.message))
+    }
+
+    fn to_database(
+        &self,
+        _notifiable: &dyn Notifiable,
+    ) -> NotificationResult<DatabaseNotification> {
+        Ok(DatabaseNotification::new()
+            .title(&self.subject)
+            .body(&self.message))
+    }
+}
+
+struct NotifyTest {
+    manager: Arc<NotificationManager>,
+}
+
+#[async_trait]
+impl Command for NotifyTest {
+    fn name(&self) -> &str {
+        "notify:test"
+    }
+
+    fn description(&self) -> &str {
+        "Send a test notification through a channel"
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        vec![
+            Arg::new("channel")
+                .required(true)
+                .value_parser(PossibleValuesParser::new([
+                    "mail", "sms", "push", "database",
+                ])),
+            Arg::new("to")
+                .required(true)
+                .help("Email address, phone number, push token or user ID"),
+            Arg::new("subject")
+                .long("subject")
+                .default_value("Test notification"),
+            Arg::new("message")
+                .long("message")
+                .default_value("This is a test notification."),
+        ]
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let channel = match input.value("channel") {
+            Some("sms") => Channel::Sms,
+            Some("push") => Channel::Push,
+            Some("database") => Channel::Database,
+            _ => Channel::Email,
+        };
+        let notification = TestNotification {
+            channel,
+            subject: input.value("subject").unwrap_or_default().to_string(),
+            message: input.value("message").unwrap_or_default().to_string(),
+        };
+        let to = input.value("to").unwrap_or_default();
+        self.manager
+            .send(&notification, &Recipient { to: to.to_string() })
+            .await
+            .map_err(ConsoleError::failed)?;
+        output.success(&format!("Sent a test notification to {}", to));
+        Ok(())
+    }
+}
+
+impl Kernel {
+    /// Register `notify:test <channel> <to>` sending through `manager`'s
+    /// channels
+    pub fn notifications(self, manager: Arc<NotificationManager>) -> Self {
+        self.command(NotifyTest { manager })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rf_notifications::ChannelHandler;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Outbox(Mutex<Vec<SmsMessage>>);
+
+    #[async_trait]
+    impl ChannelHandler for Outbox {
+        async fn send(
+            &self,
+            notification: &dyn Notification,
+            notifiable: &dyn Notifiable,
+        ) -> NotificationResult<()> {
+            let message = notification.to_sms(notifiable)?;
+            self.0.lock().unwrap().push(message);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notify_test() {
+        let outbox = Arc::new(Outbox::default());
+        let mut manager = NotificationManager::new();
+        manager.register_channel(Channel::Sms, outbox.clone());
+        let kernel = Kernel::new("shop").notifications(Arc::new(manager));
+
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect();
+        let mut output = Output::buffered();
+        kernel
+            .call(
+                "notify:test",
+                args(&["sms", "+41790000000", "--message", "Ping"]),
+                &mut output,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            output.contents(),
+            "Sent a test notification to +41790000000\n"
+        );
+        let sent = outbox.0.lock().unwrap().clone();
+        assert_eq!(
+            (sent[0].to.as_str(), sent[0].body.as_str()),
+            ("+41790000000", "Ping")
+        );
+
+        // No mail channel registered
+        let result = kernel
+            .call(
+                "notify:test",
+                args(&["mail", "ada@example.com"]),
+                &mut output,
+            )
+            .await;
+        assert!(matches!(result, Err(ConsoleError::Failed(_))));
+        let result = kernel
+            .call("notify:test", args(&["fax", "1234"]), &mut output)
+            .await;
+        assert!(matches!(result, Err(ConsoleError::InvalidArguments(_))));
+    }
+}
@@ -0,0 +1,491 @@
+//! `queue:work` processing rf-queue jobs and `failed:*` managing the jobs
+//! that failed for good
+
+use crate::{Command, ConsoleError, ConsoleResult, Input, Kernel, Output, Style};
+use async_trait::async_trait;
+use clap::{Arg, ArgAction};
+use rf_queue::{FailedJobs, Job, JobMetadata, Queue, QueueError, Worker};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+struct QueueWork {
+    // Taken on the first run, as workers are started once
+    worker: Mutex<Option<Worker>>,
+}
+
+#[async_trait]
+impl Command for QueueWork {
+    fn name(&self) -> &str {
+        "queue:work"
+    }
+
+    fn description(&self) -> &str {
+        "Process queued jobs until interrupted"
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        vec![
+            Arg::new("queue")
+                .long("queue")
+                .help("Comma separated queues to process, e.g. `high,default`"),
+            Arg::new("concurrency")
+                .long("concurrency")
+                .help("Number of jobs processed at once"),
+        ]
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let concurrency = input.parse::<usize>("concurrency")?;
+        let mut worker = self
+            .worker
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .ok_or_else(|| ConsoleError::failed("The queue worker is already running"))?;
+
+        if let Some(queues) = input.value("queue") {
+            worker = worker.queues(queues.split(',').map(|q| q.trim().to_string()).collect());
+        }
+        if let Some(concurrency) = concurrency {
+            worker = worker.concurrency(concurrency);
+        }
+
+        output.info("Processing jobs, press Ctrl+C to stop");
+        worker
+            .start_with_shutdown(async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await
+            .map_err(ConsoleError::failed)?;
+        output.info("Worker stopped");
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+    List,
+    Retry,
+    Prune,
+}
+
+struct Failed {
+    action: Action,
+    failed: FailedJobs,
+}
+
+#[async_trait]
+impl Command for Failed {
+    fn name(&self) -> &str {
+        match self.action {
+            Action::List => "failed:list",
+            Action::Retry => "failed:retry",
+            Action::Prune => "failed:prune",
+        }
+    }
+
+    fn description(&self) -> &str {
+        match self.action {
+            Action::List => "List the jobs that failed for good",
+            Action::Retry => "Push failed jobs back to their queue",
+            Action::Prune => "Delete old failed jobs",
+        }
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        match self.action {
+            Action::List => Vec::new(),
+            Action::Retry => vec![
+                Arg::new("id")
+                    .num_args(0..)
+                    .required_unless_present("all")
+                    .help("IDs of the jobs to retry"),
+                Arg::new("all")
+                    .long("all")
+                    .action(ArgAction::SetTrue)
+                    .help("Retry all failed jobs"),
+            ],
+            Action::Prune => vec![Arg::new("older-than")
+                .long("older-than")
+                .default_value("30d")
+                .help("Age of the jobs to delete, e.g. `12h` or `30d`")],
+        }
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        match self.action {
+            Action::List => list(&self.failed, output).await,
+            Action::Retry => {
+                if input.flag("all") {
+                    let count = self
+                        .failed
+                        .retry_all()
+                        .await
+                        .map_err(ConsoleError::failed)?;
+                    output.info(&format!("Pushed {} failed jobs back to their queue", count));
+                    return Ok(());
+                }
+                for id in input.values("id") {
+                    self.failed.retry(id).await.map_err(ConsoleError::failed)?;
+                    let done = output.paint("Retried", Style::Info);
+                    output.line(&format!("{} {}", done, id));
+                }
+                Ok(())
+            }
+            Action::Prune => {
+                let older_than = input
+                    .parse::<humantime::Duration>("older-than")?
+                    .unwrap_or_default();
+                let count = self
+                    .failed
+                    .prune(older_than.into())
+                    .await
+                    .map_err(ConsoleError::failed)?;
+                output.info(&format!("Deleted {} failed jobs", count));
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn list(failed: &FailedJobs, output: &mut Output) -> ConsoleResult<()> {
+    let jobs = failed.list().await.map_err(ConsoleError::failed)?;
+    if jobs.is_empty() {
+        output.comment("No failed jobs");
+        return Ok(());
+    }
+
+    let rows: Vec<Vec<String>> = jobs
+        .iter()
+        .map(|job| {
+            vec![
+                job.id.clone(),
+                job.job_type.clone(),
+                job.queue.clone(),
+                job.attempts.to_string(),
+                job.failed_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                job.exception.lines().next().unwrap_or_default().to_string(),
+            ]
+        })
+        .collect();
+    output.table(
+        &["ID", "Job", "Queue", "Attempts", "Failed At", "Error"],
+        &rows,
+    );
+    Ok(())
+}
+
+/// Creates a job's metadata from its JSON payload
+type Decoder = fn(&str) -> Result<JobMetadata, QueueError>;
+
+/// Job types `queue:dispatch` can push to a queue, by the name of their
+/// type
+///
+/// ```
+/// # use rf_console::{async_trait, Kernel};
+/// # use rf_queue::{Job, MemoryQueue, QueueError};
+/// # use std::sync::Arc;
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct SendWelcomeEmail {
+///     user_id: i64,
+/// }
+/// # #[async_trait]
+/// # impl Job for SendWelcomeEmail {
+/// #     async fn handle(&self) -> Result<(), QueueError> { Ok(()) }
+/// #     fn job_type(&self) -> &'static str { "send_welcome_email" }
+/// # }
+///
+/// // tinker: queue:dispatch SendWelcomeEmail '{"user_id": 7}' --delay 5m
+/// let kernel = Kernel::new("shop").dispatcher(
+///     rf_console::JobDispatcher::new(Arc::new(MemoryQueue::new())).job::<SendWelcomeEmail>(),
+/// );
+/// ```
+pub struct JobDispatcher {
+    queue: Arc<dyn Queue>,
+    jobs: BTreeMap<String, Decoder>,
+}
+
+impl JobDispatcher {
+    pub fn new(queue: Arc<dyn Queue>) -> Self {
+        Self {
+            queue,
+            jobs: BTreeMap::new(),
+        }
+    }
+
+    /// Allow dispatching `J`, named like its type without the module path
+    pub fn job<J: Job + 'static>(mut self) -> Self {
+        let name = std::any::type_name::<J>();
+        let name = name.rsplit("::").next().unwrap_or(name);
+        self.jobs.insert(name.to_string(), |payload| {
+            let job: J = serde_json::from_str(payload)
+                .map_err(|e| QueueError::SerializationError(e.to_string()))?;
+            JobMetadata::new(&job)
+        });
+        self
+    }
+}
+
+struct Dispatch {
+    dispatcher: JobDispatcher,
+}
+
+#[async_trait]
+impl Command for Dispatch {
+    fn name(&self) -> &str {
+        "queue:dispatch"
+    }
+
+    fn description(&self) -> &str {
+        "Push a job to its queue"
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        vec![
+            Arg::new("job").required(true).help("Type of the job"),
+            Arg::new("payload")
+                .default_value("null")
+                .help("The job as JSON, e.g. '{\"user_id\": 7}'"),
+            Arg::new("queue")
+                .long("queue")
+                .help("Queue to push to instead of the job's own"),
+            Arg::new("delay")
+                .long("delay")
+                .help("Run the job after this long, e.g. `10m`"),
+        ]
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let name = input.value("job").unwrap_or_default();
+        let decode = self.dispatcher.jobs.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.dispatcher.jobs.keys().map(String::as_str).collect();
+            ConsoleError::InvalidArguments(format!(
+                "Unknown job \"{}\", dispatchable jobs: {}",
+                name,
+                known.join(", ")
+            ))
+        })?;
+
+        let mut metadata = decode(input.value("payload").unwrap_or("null"))
+            .map_err(|e| ConsoleError::InvalidArguments(format!("Invalid payload: {}", e)))?;
+        if let Some(queue) = input.value("queue") {
+            metadata.queue = queue.to_string();
+        }
+        if let Some(delay) = input.parse::<humantime::Duration>("delay")? {
+            metadata.delay(delay.into()).map_err(ConsoleError::failed)?;
+        }
+        let queue = metadata.queue.clone();
+        let id = self
+            .dispatcher
+            .queue
+            .push(metadata)
+            .await
+            .map_err(ConsoleError::failed)?;
+        output.success(&format!("Dispatched {} to \"{}\" as {}", name, queue, id));
+        Ok(())
+    }
+}
+
+struct Size {
+    queue: Arc<dyn Queue>,
+}
+
+#[async_trait]
+impl Command for Size {
+    fn name(&self) -> &str {
+        "queue:size"
+    }
+
+    fn description(&self) -> &str {
+        "Count the jobs waiting in queues"
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        vec![Arg::new("queue")
+            .num_args(0..)
+            .default_value("default")
+            .help("Queues to count")]
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let mut rows = Vec::new();
+        for queue in input.values("queue") {
+            let size = self.queue.size(queue).await.map_err(ConsoleError::failed)?;
+            rows.push([queue.to_string(), size.to_string()]);
+        }
+        output.table(&["Queue", "Jobs"], &rows);
+        Ok(())
+    }
+}
+
+impl Kernel {
+    /// Register `queue:work` running `worker`
+    pub fn queue_worker(self, worker: Worker) -> Self {
+        self.command(QueueWork {
+            worker: Mutex::new(Some(worker)),
+        })
+    }
+
+    /// Register `failed:list`, `failed:retry <id>...|--all` and
+    /// `failed:prune --older-than 30d` on the jobs of `failed`
+    pub fn failed_jobs(self, failed: FailedJobs) -> Self {
+        [Action::List, Action::Retry, Action::Prune]
+            .into_iter()
+            .fold(self, |kernel, action| {
+                kernel.command(Failed {
+                    action,
+                    failed: failed.clone(),
+                })
+            })
+    }
+
+    /// Register `queue:dispatch <job> [payload]` pushing the jobs of
+    /// `dispatcher`, and `queue:size [queue]...`
+    pub fn dispatcher(self, dispatcher: JobDispatcher) -> Self {
+        let queue = Arc::clone(&dispatcher.queue);
+        self.command(Dispatch { dispatcher })
+            .command(Size { queue })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rf_queue::{
+        FailedJob, FailedJobStore, Job, JobMetadata, MemoryFailedJobStore, MemoryQueue, Queue,
+        QueueError,
+    };
+    use std::sync::Arc;
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Resize;
+
+    #[async_trait]
+    impl Job for Resize {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "resize"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_commands() {
+        let store = Arc::new(MemoryFailedJobStore::new());
+        let queue = Arc::new(MemoryQueue::new());
+        let kernel = Kernel::new("shop").failed_jobs(FailedJobs::new(store.clone(), queue.clone()));
+
+        let mut ids = Vec::new();
+        for days in [0, 0, 45] {
+            let mut job = FailedJob::new(JobMetadata::new(&Resize).unwrap(), "Out of memory");
+            job.failed_at -= chrono::Duration::days(days);
+            ids.push(job.id.clone());
+            store.log(job).await.unwrap();
+        }
+
+        let run = |name: &'static str, args: &[&str]| {
+            let args = args.iter().map(|arg| arg.to_string()).collect();
+            let kernel = &kernel;
+            async move {
+                let mut output = Output::buffered();
+                kernel.call(name, args, &mut output).await.unwrap();
+                output.contents()
+            }
+        };
+
+        let list = run("failed:list", &[]).await;
+        assert_eq!(list.matches("Out of memory").count(), 3);
+        assert_eq!(
+            run("failed:prune", &["--older-than", "30d"]).await,
+            "Deleted 1 failed jobs\n"
+        );
+        assert_eq!(
+            run("failed:retry", &[&ids[0]]).await,
+            format!("Retried {}\n", ids[0])
+        );
+        assert_eq!(
+            run("failed:retry", &["--all"]).await,
+            "Pushed 1 failed jobs back to their queue\n"
+        );
+        assert_eq!(queue.size("default").await.unwrap(), 2);
+        assert_eq!(run("failed:list", &[]).await, "No failed jobs\n");
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct SendWelcomeEmail {
+        user_id: i64,
+    }
+
+    #[async_trait]
+    impl Job for SendWelcomeEmail {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "send_welcome_email"
+        }
+
+        fn queue(&self) -> &str {
+            "mail"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_commands() {
+        let queue = Arc::new(MemoryQueue::new());
+        let kernel = Kernel::new("shop").dispatcher(
+            JobDispatcher::new(queue.clone())
+                .job::<Resize>()
+                .job::<SendWelcomeEmail>(),
+        );
+        let call = |args: &[&str]| {
+            let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            let name = args.remove(0);
+            let kernel = &kernel;
+            async move {
+                let mut output = Output::buffered();
+                let result = kernel.call(&name, args, &mut output).await;
+                (result, output.contents())
+            }
+        };
+
+        let (result, output) =
+            call(&["queue:dispatch", "SendWelcomeEmail", r#"{"user_id": 7}"#]).await;
+        result.unwrap();
+        assert!(output.starts_with("Dispatched SendWelcomeEmail to \"mail\" as "));
+        call(&[
+            "queue:dispatch",
+            "Resize",
+            "--queue",
+            "images",
+            "--delay",
+            "1h",
+        ])
+        .await
+        .0
+        .unwrap();
+
+        let job = queue.reserve("mail").await.unwrap().unwrap();
+        assert_eq!(job.job_type, "send_welcome_email");
+        assert_eq!(job.data, br#"{"user_id":7}"#);
+        assert_eq!(queue.size("images").await.unwrap(), 1);
+        assert!(queue.reserve("images").await.unwrap().is_none());
+
+        let (result, _) = call(&["queue:dispatch", "SendWelcomeEmail", "{}"]).await;
+        assert!(
+            matches!(result, Err(ConsoleError::InvalidArguments(m)) if m.starts_with("Invalid payload"))
+        );
+        let (result, _) = call(&["queue:dispatch", "Deploy"]).await;
+        assert!(
+            matches!(result, Err(ConsoleError::InvalidArguments(m)) if m.ends_with("Resize, SendWelcomeEmail"))
+        );
+
+        let (result, output) = call(&["queue:size", "images", "mail"]).await;
+        result.unwrap();
+        assert!(output.contains("| images | 1    |"));
+        assert!(output.contains("| mail   | 0    |"));
+    }
+}
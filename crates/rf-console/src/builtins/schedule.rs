@@ -0,0 +1,223 @@
+//! `schedule:run` running rf-scheduler tasks and `schedule:history` listing
+//! their runs
+
+use crate::{Command, ConsoleError, ConsoleResult, Input, Kernel, Output};
+use async_trait::async_trait;
+use clap::Arg;
+use rf_scheduler::{HistoryQuery, RunHistory, RunStatus, Scheduler};
+use std::sync::{Arc, Mutex};
+
+struct ScheduleRun {
+    // Taken on the first run, as the scheduler is started once
+    scheduler: Mutex<Option<Scheduler>>,
+}
+
+#[async_trait]
+impl Command for ScheduleRun {
+    fn name(&self) -> &str {
+        "schedule:run"
+    }
+
+    fn description(&self) -> &str {
+        "Run scheduled tasks until interrupted"
+    }
+
+    async fn handle(&self, _: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let scheduler = self
+            .scheduler
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .ok_or_else(|| ConsoleError::failed("The scheduler is already running"))?;
+
+        output.info("Running scheduled tasks, press Ctrl+C to stop");
+        scheduler
+            .start_with_shutdown(async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await
+            .map_err(ConsoleError::failed)?;
+        output.info("Scheduler stopped");
+        Ok(())
+    }
+}
+
+struct ScheduleHistory {
+    history: Arc<dyn RunHistory>,
+}
+
+#[async_trait]
+impl Command for ScheduleHistory {
+    fn name(&self) -> &str {
+        "schedule:history"
+    }
+
+    fn description(&self) -> &str {
+        "List the runs of scheduled tasks"
+    }
+
+    fn arguments(&self) -> Vec<Arg> {
+        vec![
+            Arg::new("task").help("Only list runs of this task"),
+            Arg::new("status")
+                .long("status")
+                .value_parser(["succeeded", "failed", "skipped"])
+                .help("Only list runs with this status"),
+            Arg::new("since")
+                .long("since")
+                .help("Only list runs scheduled within this time, e.g. `24h` or `7d`"),
+            Arg::new("limit")
+                .long("limit")
+                .default_value("20")
+                .help("Number of runs listed at most"),
+        ]
+    }
+
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+        let mut query = HistoryQuery::new().limit(input.parse("limit")?.unwrap_or(20));
+        if let Some(task) = input.value("task") {
+            query = query.task(task);
+        }
+        if let Some(status) = input.parse::<RunStatus>("status")? {
+            query = query.status(status);
+        }
+        if let Some(since) = input.parse::<humantime::Duration>("since")? {
+            let since = chrono::Duration::from_std(since.into())
+                .map_err(|e| ConsoleError::InvalidArguments(format!("Invalid since: {}", e)))?;
+            query = query.since(chrono::Utc::now() - since);
+        }
+
+        let runs = self.history.query(&query).await;
+        if runs.is_empty() {
+            output.comment("No runs recorded");
+            return Ok(());
+        }
+
+        let time = |at: chrono::DateTime<chrono::Utc>| at.format("%Y-%m-%d %H:%M:%S").to_string();
+        let rows: Vec<Vec<String>> = runs
+            .iter()
+            .map(|run| {
+                vec![
+                    run.task.clone(),
+                    time(run.scheduled_for),
+                    time(run.started_at),
+                    format!("{:.2}s", run.duration().num_milliseconds() as f64 / 1000.0),
+                    run.outcome.status().as_str().to_string(),
+                    run.outcome.reason().unwrap_or_default().to_string(),
+                ]
+            })
+            .collect();
+        output.table(
+            &[
+                "Task",
+                "Scheduled For",
+                "Started At",
+                "Duration",
+                "Status",
+                "Reason",
+            ],
+            &rows,
+        );
+        Ok(())
+    }
+}
+
+impl Kernel {
+    /// Register `schedule:run` running `scheduler` and `schedule:history`
+    /// listing the runs it records
+    pub fn scheduler(self, scheduler: Scheduler) -> Self {
+        let history = scheduler.run_history();
+        self.command(ScheduleRun {
+            scheduler: Mutex::new(Some(scheduler)),
+        })
+        .command(ScheduleHistory { history })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use rf_scheduler::{MemoryRunHistory, RunOutcome, Task, TaskRun};
+
+    struct Noop;
+
+    #[async_trait]
+    impl Task for Noop {
+        async fn run(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "noop"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_schedule_run_fails_on_invalid_schedule() {
+        let scheduler = Scheduler::new();
+        scheduler.job(Noop).cron("not a cron");
+        let kernel = Kernel::new("shop").scheduler(scheduler);
+        assert!(kernel.has("schedule:run"));
+
+        let mut output = Output::buffered();
+        let result = kernel.call("schedule:run", vec![], &mut output).await;
+        assert!(matches!(result, Err(ConsoleError::Failed(_))));
+
+        // The scheduler was used up by the first run
+        let result = kernel.call("schedule:run", vec![], &mut output).await;
+        assert!(result.unwrap_err().to_string().contains("already running"));
+    }
+
+    #[tokio::test]
+    async fn test_schedule_history() {
+        let history = MemoryRunHistory::default();
+        let at = |h| Utc.with_ymd_and_hms(2024, 1, 15, h, 0, 0).unwrap();
+        for (hour, outcome) in [
+            (1, RunOutcome::Succeeded),
+            (2, RunOutcome::Failed("Disk full".to_string())),
+        ] {
+            history
+                .record(TaskRun {
+                    task: "backup".to_string(),
+                    scheduled_for: at(hour),
+                    started_at: at(hour),
+                    finished_at: at(hour) + chrono::Duration::milliseconds(1500),
+                    outcome,
+                    output: None,
+                })
+                .await;
+        }
+        let kernel = Kernel::new("shop").scheduler(Scheduler::new().history(history));
+
+        let mut output = Output::buffered();
+        let args = vec![
+            "backup".to_string(),
+            "--status".to_string(),
+            "failed".to_string(),
+        ];
+        kernel
+            .call("schedule:history", args, &mut output)
+            .await
+            .unwrap();
+        let listed = output.contents();
+        assert!(listed.contains("2024-01-15 02:00:00"));
+        assert!(listed.contains("1.50s"));
+        assert!(listed.contains("Disk full"));
+        assert!(!listed.contains("01:00:00"));
+
+        let mut output = Output::buffered();
+        let args = vec!["report".to_string()];
+        kernel
+            .call("schedule:history", args, &mut output)
+            .await
+            .unwrap();
+        assert_eq!(output.contents(), "No runs recorded\n");
+
+        let args = vec!["--status".to_string(), "lost".to_string()];
+        assert!(kernel
+            .call("schedule:history", args, &mut output)
+            .await
+            .is_err());
+    }
+}
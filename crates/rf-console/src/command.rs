@@ -0,0 +1,123 @@
+//! Commands and their input
+
+use crate::{ConsoleError, ConsoleResult, Output};
+use async_trait::async_trait;
+use clap::ArgMatches;
+use std::str::FromStr;
+
+/// A console command, e.g. `cache:clear`
+///
+/// Arguments and options are declared as clap [`Arg`](clap::Arg)s and read
+/// from the [`Input`] when the command runs.
+///
+/// ```
+/// use rf_console::{async_trait, Arg, ArgAction, Command, ConsoleResult, Input, Output};
+///
+/// struct Greet;
+///
+/// #[async_trait]
+/// impl Command for Greet {
+///     fn name(&self) -> &str {
+///         "greet"
+///     }
+///
+///     fn description(&self) -> &str {
+///         "Greet someone"
+///     }
+///
+///     fn arguments(&self) -> Vec<Arg> {
+///         vec![
+///             Arg::new("name").required(true),
+///             Arg::new("shout").long("shout").action(ArgAction::SetTrue),
+///         ]
+///     }
+///
+///     async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+///         let greeting = format!("Hello {}", input.value("name").unwrap_or_default());
+///         match input.flag("shout") {
+///             true => output.line(&greeting.to_uppercase()),
+///             false => output.line(&greeting),
+///         }
+///         Ok(())
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait Command: Send + Sync {
+    /// Name to call the command by, namespaced with `:`
+    fn name(&self) -> &str;
+
+    /// One line shown by `list`
+    fn description(&self) -> &str {
+        ""
+    }
+
+    /// Arguments and options
+    fn arguments(&self) -> Vec<clap::Arg> {
+        Vec::new()
+    }
+
+    /// Run the command
+    async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()>;
+}
+
+/// Parsed arguments and options of a command
+#[derive(Debug, Clone)]
+pub struct Input {
+    matches: ArgMatches,
+}
+
+impl Input {
+    pub(crate) fn new(matches: ArgMatches) -> Self {
+        Self { matches }
+    }
+
+    /// Value of an argument or option
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.matches
+            .try_get_one::<String>(name)
+            .ok()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    /// Values of an argument or option taking several
+    pub fn values(&self, name: &str) -> Vec<&str> {
+        self.matches
+            .try_get_many::<String>(name)
+            .ok()
+            .flatten()
+            .map(|values| values.map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether a flag (`ArgAction::SetTrue`) is set
+    pub fn flag(&self, name: &str) -> bool {
+        self.matches
+            .try_get_one::<bool>(name)
+            .ok()
+            .flatten()
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Value of an argument or option parsed as `T`
+    pub fn parse<T>(&self, name: &str) -> ConsoleResult<Option<T>>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.value(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|e| ConsoleError::InvalidArguments(format!("Invalid {}: {}", name, e)))
+            })
+            .transpose()
+    }
+
+    /// The underlying clap matches
+    pub fn matches(&self) -> &ArgMatches {
+        &self.matches
+    }
+}
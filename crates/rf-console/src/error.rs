@@ -0,0 +1,32 @@
+//! Console errors
+
+use thiserror::Error;
+
+/// Error of a command's own work
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Console errors
+#[derive(Debug, Error)]
+pub enum ConsoleError {
+    #[error("Command \"{0}\" is not defined")]
+    UnknownCommand(String),
+
+    #[error("{0}")]
+    InvalidArguments(String),
+
+    #[error("{0}")]
+    Failed(BoxError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl ConsoleError {
+    /// Wrap the error of a command
+    pub fn failed(error: impl Into<BoxError>) -> Self {
+        ConsoleError::Failed(error.into())
+    }
+}
+
+/// Result type for console commands
+pub type ConsoleResult<T> = Result<T, ConsoleError>;
@@ -0,0 +1,537 @@
+//! Console kernel: registers, lists and runs commands
+
+use crate::{Command, ConsoleError, ConsoleResult, Input, Output};
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+use tokio::io::BufReader;
+
+/// Commands handled by the kernel itself, with their descriptions
+const BUILTINS: [(&str, &str); 3] = [
+    ("help", "Show the help of a command"),
+    ("list", "List commands"),
+    ("tinker", "Run commands interactively"),
+];
+
+/// Console kernel
+///
+/// Holds the application's commands next to the built-in `list`, `help`
+/// and `tinker`. Commands of enabled features (`make:*`, `migrate`,
+/// `queue:work`, `schedule:run`) are registered on the kernel too, see
+/// the crate docs.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_console::Kernel;
+/// # use rf_console::{async_trait, Command, ConsoleResult, Input, Output};
+/// # struct SendReports;
+/// # #[async_trait]
+/// # impl Command for SendReports {
+/// #     fn name(&self) -> &str { "reports:send" }
+/// #     async fn handle(&self, _: &Input, _: &mut Output) -> ConsoleResult<()> { Ok(()) }
+/// # }
+///
+/// #[tokio::main]
+/// async fn main() -> std::process::ExitCode {
+///     Kernel::new("shop")
+///         .version(env!("CARGO_PKG_VERSION"))
+///         .command(SendReports)
+///         .run()
+///         .await
+/// }
+/// ```
+pub struct Kernel {
+    name: String,
+    version: Option<String>,
+    commands: BTreeMap<String, Box<dyn Command>>,
+}
+
+impl Kernel {
+    /// Create a kernel for application `name`
+    pub fn new(name: impl Into<String>) -> Self {
+        let kernel = Self {
+            name: name.into(),
+            version: None,
+            commands: BTreeMap::new(),
+        };
+
+        #[cfg(feature = "make")]
+        let kernel = kernel.make_commands();
+
+        kernel
+    }
+
+    /// Set the version shown by `list` and `--version`
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Register a command, replacing one of the same name
+    pub fn command(mut self, command: impl Command + 'static) -> Self {
+        self.commands
+            .insert(command.name().to_string(), Box::new(command));
+        self
+    }
+
+    /// Names and descriptions of all commands, sorted by name
+    pub fn commands(&self) -> Vec<(&str, &str)> {
+        let mut commands: Vec<(&str, &str)> = self
+            .commands
+            .values()
+            .map(|command| (command.name(), command.description()))
+            .chain(BUILTINS)
+            .collect();
+        commands.sort();
+        commands.dedup_by_key(|(name, _)| *name);
+        commands
+    }
+
+    /// Whether command `name` exists
+    pub fn has(&self, name: &str) -> bool {
+        self.commands.contains_key(name) || BUILTINS.iter().any(|(builtin, _)| *builtin == name)
+    }
+
+    /// Run the command given on the command line
+    pub async fn run(&self) -> ExitCode {
+        self.run_with(std::env::args()).await
+    }
+
+    /// Run the command in `args`, the first of which is the binary name
+    ///
+    /// Errors are printed; the exit code tells whether the command
+    /// succeeded.
+    pub async fn run_with<I, S>(&self, args: I) -> ExitCode
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut args = args.into_iter().skip(1).map(Into::into);
+        let mut output = Output::stdout();
+
+        let name = match args.next() {
+            None => "list".to_string(),
+            Some(arg) if arg == "-h" || arg == "--help" => "list".to_string(),
+            Some(arg) if arg == "-V" || arg == "--version" => {
+                output.line(&self.title(&output));
+                return ExitCode::SUCCESS;
+            }
+            Some(name) => name,
+        };
+
+        match self.call(&name, args.collect(), &mut output).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                self.report(&e, &mut output);
+                ExitCode::FAILURE
+            }
+        }
+    }
+
+    /// Run command `name` with `args`, e.g. from another command
+    pub async fn call(
+        &self,
+        name: &str,
+        args: Vec<String>,
+        output: &mut Output,
+    ) -> ConsoleResult<()> {
+        if name == "tinker" {
+            if let Some(script) = args.first() {
+                return self.run_script(script, output).await;
+            }
+            let stdin = BufReader::new(tokio::io::stdin());
+            return self.tinker(stdin, output).await;
+        }
+        self.dispatch(name, args, output).await
+    }
+
+    /// Run a command other than `tinker`
+    pub(crate) async fn dispatch(
+        &self,
+        name: &str,
+        args: Vec<String>,
+        output: &mut Output,
+    ) -> ConsoleResult<()> {
+        match name {
+            "list" => {
+                self.list(args.first().map(String::as_str), output);
+                return Ok(());
+            }
+            "help" => {
+                let name = args.first().map_or("help", String::as_str);
+                let help = self.help(name)?;
+                output.write(&help);
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let command = self
+            .commands
+            .get(name)
+            .ok_or_else(|| ConsoleError::UnknownCommand(name.to_string()))?;
+        let matches = match clap_command(command.as_ref()).try_get_matches_from(args) {
+            Ok(matches) => matches,
+            Err(e) if e.kind() == clap::error::ErrorKind::DisplayHelp => {
+                output.write(&e.render().to_string());
+                return Ok(());
+            }
+            Err(e) => return Err(ConsoleError::InvalidArguments(e.render().to_string())),
+        };
+        command.handle(&Input::new(matches), output).await
+    }
+
+    /// Help of command `name`
+    fn help(&self, name: &str) -> ConsoleResult<String> {
+        if let Some(command) = self.commands.get(name) {
+            return Ok(clap_command(command.as_ref()).render_help().to_string());
+        }
+        let (name, description) = BUILTINS
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .ok_or_else(|| ConsoleError::UnknownCommand(name.to_string()))?;
+        let argument = match *name {
+            "help" => Some(clap::Arg::new("command").help("Command to show the help of")),
+            "list" => {
+                Some(clap::Arg::new("namespace").help("Only list commands of this namespace"))
+            }
+            "tinker" => Some(
+                clap::Arg::new("script").help("File of commands to run instead of reading them"),
+            ),
+            _ => None,
+        };
+        Ok(clap::Command::new(*name)
+            .about(*description)
+            .no_binary_name(true)
+            .args(argument)
+            .render_help()
+            .to_string())
+    }
+
+    /// Print the commands, grouped by namespace
+    fn list(&self, namespace: Option<&str>, output: &mut Output) {
+        let mut commands: Vec<(&str, &str)> = self
+            .commands()
+            .into_iter()
+            .filter(|(name, _)| namespace.is_none_or(|namespace| namespace_of(name) == namespace))
+            .collect();
+        // Commands without a namespace first
+        commands.sort_by_key(|(name, _)| (namespace_of(name), *name));
+        let width = commands
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+
+        output.line(&self.title(output));
+        output.newline();
+        output.comment("Usage:");
+        output.line("  command [arguments] [options]");
+        output.newline();
+        match namespace {
+            Some(namespace) => {
+                output.comment(&format!("Available commands for \"{}\":", namespace))
+            }
+            None => output.comment("Available commands:"),
+        }
+
+        let mut group = "";
+        for (name, description) in commands {
+            let namespace = namespace_of(name);
+            if namespace != group {
+                output.comment(&format!(" {}", namespace));
+                group = namespace;
+            }
+            let name = output.paint(&format!("{:<width$}", name), crate::Style::Info);
+            output.line(format!("  {}  {}", name, description).trim_end());
+        }
+    }
+
+    /// Print an error, suggesting commands for unknown ones
+    pub(crate) fn report(&self, error: &ConsoleError, output: &mut Output) {
+        // Clap's messages end with a newline
+        output.error(error.to_string().trim_end());
+
+        if let ConsoleError::UnknownCommand(name) = error {
+            let suggestions = self.suggestions(name);
+            if !suggestions.is_empty() {
+                output.newline();
+                output.comment("Did you mean one of these?");
+                for suggestion in suggestions {
+                    output.line(&format!("  {}", suggestion));
+                }
+            }
+        }
+    }
+
+    /// Commands in the namespace of `name` or containing it
+    fn suggestions(&self, name: &str) -> Vec<&str> {
+        let namespace = namespace_of(name);
+        self.commands()
+            .into_iter()
+            .map(|(command, _)| command)
+            .filter(|command| {
+                command.contains(name)
+                    || (!namespace.is_empty() && namespace_of(command) == namespace)
+            })
+            .collect()
+    }
+
+    fn title(&self, output: &Output) -> String {
+        match &self.version {
+            Some(version) => format!(
+                "{} {}",
+                self.name,
+                output.paint(version, crate::Style::Info)
+            ),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// Clap command parsing the arguments of `command`
+fn clap_command(command: &dyn Command) -> clap::Command {
+    clap::Command::new(command.name().to_string())
+        .about(command.description().to_string())
+        .no_binary_name(true)
+        .args(command.arguments())
+}
+
+/// `make` of `make:model`; empty for commands without a namespace
+fn namespace_of(name: &str) -> &str {
+    name.split_once(':').map_or("", |(namespace, _)| namespace)
+}
+
+/// Split a line into arguments on whitespace, keeping quoted ones together
+pub(crate) fn split_line(line: &str) -> ConsoleResult<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote = None;
+
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => args.extend(current.take()),
+            (None, c) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(ConsoleError::InvalidArguments(
+            "Unterminated quote".to_string(),
+        ));
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use clap::{Arg, ArgAction};
+
+    struct Greet;
+
+    #[async_trait]
+    impl Command for Greet {
+        fn name(&self) -> &str {
+            "greet"
+        }
+
+        fn description(&self) -> &str {
+            "Greet someone"
+        }
+
+        fn arguments(&self) -> Vec<Arg> {
+            vec![
+                Arg::new("name").required(true),
+                Arg::new("times").long("times").default_value("1"),
+                Arg::new("shout").long("shout").action(ArgAction::SetTrue),
+            ]
+        }
+
+        async fn handle(&self, input: &Input, output: &mut Output) -> ConsoleResult<()> {
+            let mut greeting = format!("Hello {}", input.value("name").unwrap());
+            if input.flag("shout") {
+                greeting = greeting.to_uppercase();
+            }
+            for _ in 0..input.parse::<u32>("times")?.unwrap() {
+                output.line(&greeting);
+            }
+            Ok(())
+        }
+    }
+
+    struct ClearCache;
+
+    #[async_trait]
+    impl Command for ClearCache {
+        fn name(&self) -> &str {
+            "cache:clear"
+        }
+
+        fn description(&self) -> &str {
+            "Flush the cache"
+        }
+
+        async fn handle(&self, _: &Input, output: &mut Output) -> ConsoleResult<()> {
+            output.success("Cache cleared");
+            Ok(())
+        }
+    }
+
+    fn kernel() -> Kernel {
+        Kernel::new("shop")
+            .version("1.2.0")
+            .command(Greet)
+            .command(ClearCache)
+    }
+
+    async fn call(kernel: &Kernel, line: &str) -> (ConsoleResult<()>, String) {
+        let mut args = split_line(line).unwrap();
+        let name = args.remove(0);
+        let mut output = Output::buffered();
+        let result = kernel.call(&name, args, &mut output).await;
+        (result, output.contents())
+    }
+
+    #[tokio::test]
+    async fn test_call_parses_arguments() {
+        let kernel = kernel();
+        let (result, output) = call(&kernel, "greet 'Ada Lovelace' --times 2 --shout").await;
+        result.unwrap();
+        assert_eq!(output, "HELLO ADA LOVELACE\nHELLO ADA LOVELACE\n");
+
+        let (result, _) = call(&kernel, "greet").await;
+        assert!(matches!(result, Err(ConsoleError::InvalidArguments(_))));
+
+        let (result, _) = call(&kernel, "greet Ada --times many").await;
+        match result {
+            Err(ConsoleError::InvalidArguments(message)) => assert!(message.contains("times")),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let (result, output) = call(&kernel, "greet --help").await;
+        result.unwrap();
+        assert!(output.contains("Greet someone"));
+        assert!(output.contains("--shout"));
+    }
+
+    #[tokio::test]
+    async fn test_list() {
+        let kernel = kernel();
+        assert!(kernel.has("cache:clear"));
+        assert!(kernel.has("tinker"));
+        assert!(!kernel.has("cache"));
+
+        let (result, output) = call(&kernel, "list").await;
+        result.unwrap();
+        assert!(output.starts_with("shop 1.2.0\n"));
+        // Names are padded to the longest one, which depends on the features
+        let output: Vec<String> = output
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        let output = output.join("\n");
+        assert!(output.contains(
+            "Available commands:\ngreet Greet someone\nhelp Show the help of a command\n\
+             list List commands\ntinker Run commands interactively\ncache\n\
+             cache:clear Flush the cache"
+        ));
+
+        let (_, output) = call(&kernel, "list cache").await;
+        assert!(output.contains("Available commands for \"cache\":\n cache\n  cache:clear"));
+        assert!(!output.contains("greet"));
+    }
+
+    #[tokio::test]
+    async fn test_help_and_unknown_commands() {
+        let kernel = kernel();
+        let (result, output) = call(&kernel, "help greet").await;
+        result.unwrap();
+        assert!(output.contains("Usage: greet [OPTIONS] <name>"));
+
+        let (result, output) = call(&kernel, "help list").await;
+        result.unwrap();
+        assert!(output.contains("[namespace]"));
+
+        let (result, _) = call(&kernel, "cache:flush").await;
+        assert!(matches!(result, Err(ConsoleError::UnknownCommand(_))));
+        assert_eq!(kernel.suggestions("cache:flush"), vec!["cache:clear"]);
+        assert_eq!(kernel.suggestions("gree"), vec!["greet"]);
+        assert!(kernel.suggestions("deploy").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tinker() {
+        let kernel = kernel();
+        let mut output = Output::buffered();
+        let input = &b"greet Ada\n\ncache:flush\ntinker\ncache:clear\nexit\ngreet Bob\n"[..];
+        kernel.tinker(input, &mut output).await.unwrap();
+
+        let output = output.contents();
+        assert!(output.contains("> Hello Ada\n> > Command \"cache:flush\" is not defined\n"));
+        assert!(output.contains("Did you mean one of these?\n  cache:clear\n"));
+        assert!(output.contains("> Already in tinker\n> Cache cleared\n> "));
+        assert!(!output.contains("Bob"));
+    }
+
+    #[tokio::test]
+    async fn test_tinker_variables() {
+        let kernel = kernel();
+        let mut output = Output::buffered();
+        let input = &b"# Greetings\nset name 'Ada Lovelace'\ngreet \"$name\" --times=${times}\nset times 2\ngreet ${name}! --times $times\necho $$5\nvars\n"[..];
+        kernel.tinker(input, &mut output).await.unwrap();
+
+        let output = output.contents();
+        assert!(output.contains("> > > Undefined variable $times\n"));
+        assert!(output.contains("> Hello Ada Lovelace!\nHello Ada Lovelace!\n"));
+        assert!(output.contains("Command \"echo\" is not defined"));
+        let vars = output.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert!(vars.contains("| name | Ada Lovelace |"));
+        assert!(vars.contains("| times | 2 |"));
+    }
+
+    #[tokio::test]
+    async fn test_tinker_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("greet.tinker");
+        std::fs::write(
+            &path,
+            "set name Ada\ngreet $name\n\ncache:flush\ngreet Bob\n",
+        )
+        .unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let kernel = kernel();
+        let mut output = Output::buffered();
+        let error = kernel.run_script(&path, &mut output).await.unwrap_err();
+        assert!(matches!(error, ConsoleError::Failed(_)));
+        let message = error.to_string();
+        assert!(message.starts_with(&format!(
+            "{}:4: Command \"cache:flush\" is not defined",
+            path
+        )));
+        assert_eq!(output.contents(), "Hello Ada\n");
+
+        // `shop tinker <script>`
+        let (result, output) = call(&kernel, &format!("tinker {}", path)).await;
+        assert!(result.is_err());
+        assert_eq!(output, "Hello Ada\n");
+    }
+
+    #[test]
+    fn test_split_line() {
+        assert_eq!(
+            split_line(r#"  make:model "Blog Post" --fields='title:string body:text' "#).unwrap(),
+            vec!["make:model", "Blog Post", "--fields=title:string body:text"]
+        );
+        assert_eq!(split_line("greet ''").unwrap(), vec!["greet", ""]);
+        assert!(split_line("greet \"Ada").is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! Console commands for RustForge applications
+//!
+//! An artisan-style command line: applications implement [`Command`] for
+//! their own commands and register them on a [`Kernel`], which parses
+//! arguments with clap and runs the command given on the command line.
+//!
+//! # Features
+//!
+//! - `list` grouping all commands by namespace, `help <command>` and
+//!   `tinker` running commands interactively or from a script, with
+//!   `set`/`$name` variables
+//! - `config:show` printing the configuration with secrets masked
+//! - Colored [`Output`] with tables and progress bars, honoring `NO_COLOR`
+//! - `make:model`, `make:controller`, `make:migration`, `make:test`,
+//!   `make:job`, … with rf-cli-gen (feature `make`)
+//! - `migrate`, `migrate:rollback`, `migrate:redo`, `migrate:status` and
+//!   `migrate:fresh` with rf-migrate (feature `migrate`)
+//! - `queue:work`, `queue:dispatch`, `queue:size`, `failed:list`,
+//!   `failed:retry` and `failed:prune` with rf-queue (feature `queue`)
+//! - `schedule:run` and `schedule:history` with rf-scheduler (feature
+//!   `schedule`)
+//! - `db:query`, `db:execute` and `<table>:find`, `<table>:list`,
+//!   `<table>:count` for models with rf-orm-lite (feature `db`)
+//! - `notify:test` with rf-notifications (feature `notifications`)
+//! - `flags:list` and `flags:check` with rf-feature-flags (feature `flags`)
+//!
+//! # Example
+//!
+//! ```
+//! use rf_console::{async_trait, Command, ConsoleResult, Input, Kernel, Output};
+//!
+//! struct ClearCache;
+//!
+//! #[async_trait]
+//! impl Command for ClearCache {
+//!     fn name(&self) -> &str {
+//!         "cache:clear"
+//!     }
+//!
+//!     fn description(&self) -> &str {
+//!         "Flush the application cache"
+//!     }
+//!
+//!     async fn handle(&self, _input: &Input, output: &mut Output) -> ConsoleResult<()> {
+//!         let mut progress = output.progress(3);
+//!         for _store in ["views", "routes", "config"] {
+//!             progress.advance(1);
+//!         }
+//!         progress.finish();
+//!         output.success("Cache cleared");
+//!         Ok(())
+//!     }
+//! }
+//!
+//! # async fn example() {
+//! let kernel = Kernel::new("shop").command(ClearCache);
+//!
+//! let mut output = Output::buffered();
+//! kernel.call("cache:clear", vec![], &mut output).await.unwrap();
+//! assert!(output.contents().ends_with("Cache cleared\n"));
+//!
+//! // `shop cache:clear`, `shop list`, ...
+//! kernel.run().await;
+//! # }
+//! ```
+
+mod builtins;
+mod command;
+mod error;
+mod kernel;
+mod output;
+mod tinker;
+
+pub use command::{Command, Input};
+pub use error::{BoxError, ConsoleError, ConsoleResult};
+pub use kernel::Kernel;
+pub use output::{Output, ProgressBar, Style};
+
+#[cfg(feature = "queue")]
+pub use builtins::JobDispatcher;
+
+pub use async_trait::async_trait;
+pub use clap::{self, Arg, ArgAction};
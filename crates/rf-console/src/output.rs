@@ -0,0 +1,309 @@
+//! Colored output, tables and progress bars
+
+use std::io::{IsTerminal, Write};
+
+/// How a piece of text is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Green
+    Info,
+    /// Bold green
+    Success,
+    /// Yellow
+    Comment,
+    /// Bold yellow
+    Warning,
+    /// Bold red
+    Error,
+    Bold,
+    Dim,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Info => "32",
+            Style::Success => "1;32",
+            Style::Comment => "33",
+            Style::Warning => "1;33",
+            Style::Error => "1;31",
+            Style::Bold => "1",
+            Style::Dim => "2",
+        }
+    }
+}
+
+enum Target {
+    Stdout,
+    Buffer(Vec<u8>),
+}
+
+/// Where commands write to
+///
+/// Colors are used on terminals unless `NO_COLOR` is set. A buffered
+/// output collects everything for tests or [`Kernel::call`](crate::Kernel::call).
+pub struct Output {
+    target: Target,
+    colors: bool,
+}
+
+impl Output {
+    /// Output to stdout
+    pub fn stdout() -> Self {
+        Self {
+            target: Target::Stdout,
+            colors: std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        }
+    }
+
+    /// Output collected in memory, without colors
+    pub fn buffered() -> Self {
+        Self {
+            target: Target::Buffer(Vec::new()),
+            colors: false,
+        }
+    }
+
+    /// Force colors on or off
+    pub fn colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Everything written to a buffered output
+    pub fn contents(&self) -> String {
+        match &self.target {
+            Target::Stdout => String::new(),
+            Target::Buffer(buffer) => String::from_utf8_lossy(buffer).into_owned(),
+        }
+    }
+
+    /// `text` in `style`, if colors are enabled
+    pub fn paint(&self, text: &str, style: Style) -> String {
+        if self.colors {
+            format!("\x1b[{}m{}\x1b[0m", style.code(), text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Write text as is
+    pub fn write(&mut self, text: &str) {
+        // Like `println!`, output errors (e.g. a closed pipe) are ignored
+        let _ = match &mut self.target {
+            Target::Stdout => {
+                let mut stdout = std::io::stdout().lock();
+                stdout
+                    .write_all(text.as_bytes())
+                    .and_then(|_| stdout.flush())
+            }
+            Target::Buffer(buffer) => buffer.write_all(text.as_bytes()),
+        };
+    }
+
+    /// Write a line
+    pub fn line(&mut self, text: &str) {
+        self.write(text);
+        self.write("\n");
+    }
+
+    /// Write a line in `style`
+    pub fn styled(&mut self, text: &str, style: Style) {
+        let text = self.paint(text, style);
+        self.line(&text);
+    }
+
+    pub fn newline(&mut self) {
+        self.write("\n");
+    }
+
+    pub fn info(&mut self, text: &str) {
+        self.styled(text, Style::Info);
+    }
+
+    pub fn success(&mut self, text: &str) {
+        self.styled(text, Style::Success);
+    }
+
+    pub fn comment(&mut self, text: &str) {
+        self.styled(text, Style::Comment);
+    }
+
+    pub fn warning(&mut self, text: &str) {
+        self.styled(text, Style::Warning);
+    }
+
+    pub fn error(&mut self, text: &str) {
+        self.styled(text, Style::Error);
+    }
+
+    /// Write a table with bold headers
+    pub fn table<R: AsRef<[String]>>(&mut self, headers: &[&str], rows: &[R]) {
+        let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+        for row in rows {
+            for (i, cell) in row.as_ref().iter().enumerate() {
+                let width = cell.chars().count();
+                match widths.get_mut(i) {
+                    Some(max) => *max = (*max).max(width),
+                    None => widths.push(width),
+                }
+            }
+        }
+
+        let border: String = widths
+            .iter()
+            .map(|width| format!("+{}", "-".repeat(width + 2)))
+            .chain(["+".to_string()])
+            .collect();
+        let line = |cells: Vec<String>| -> String {
+            cells
+                .iter()
+                .map(|cell| format!("| {} ", cell))
+                .chain(["|".to_string()])
+                .collect()
+        };
+
+        self.line(&border);
+        let header = widths
+            .iter()
+            .enumerate()
+            .map(|(i, width)| self.paint(&pad(headers.get(i).unwrap_or(&""), *width), Style::Bold))
+            .collect();
+        self.line(&line(header));
+        self.line(&border);
+        for row in rows {
+            let row = row.as_ref();
+            let cells = widths
+                .iter()
+                .enumerate()
+                .map(|(i, width)| pad(row.get(i).map_or("", String::as_str), *width))
+                .collect();
+            self.line(&line(cells));
+        }
+        self.line(&border);
+    }
+
+    /// Progress bar over `total` steps
+    pub fn progress(&mut self, total: u64) -> ProgressBar<'_> {
+        let mut progress = ProgressBar {
+            output: self,
+            total,
+            current: 0,
+        };
+        progress.render();
+        progress
+    }
+}
+
+/// Progress bar redrawn on its line, e.g.
+/// ` 3/10 [########--------------------]  30%`
+pub struct ProgressBar<'a> {
+    output: &'a mut Output,
+    total: u64,
+    current: u64,
+}
+
+impl ProgressBar<'_> {
+    const WIDTH: u64 = 28;
+
+    /// Advance by `steps`
+    pub fn advance(&mut self, steps: u64) {
+        self.set(self.current + steps);
+    }
+
+    /// Jump to `current`
+    pub fn set(&mut self, current: u64) {
+        self.current = current.min(self.total);
+        self.render();
+    }
+
+    /// Complete the bar and end its line
+    pub fn finish(mut self) {
+        self.set(self.total);
+        self.output.newline();
+    }
+
+    fn render(&mut self) {
+        let ratio = if self.total == 0 {
+            1.0
+        } else {
+            self.current as f64 / self.total as f64
+        };
+        let done = (ratio * Self::WIDTH as f64) as usize;
+        let filled = format!(
+            "{}{}",
+            "#".repeat(done),
+            "-".repeat(Self::WIDTH as usize - done)
+        );
+        let width = self.total.to_string().len();
+        let line = format!(
+            "\r {:>width$}/{} [{}] {:>3}%",
+            self.current,
+            self.total,
+            self.output.paint(&filled, Style::Info),
+            (ratio * 100.0) as u64,
+            width = width
+        );
+        self.output.write(&line);
+    }
+}
+
+fn pad(text: &str, width: usize) -> String {
+    format!(
+        "{}{}",
+        text,
+        " ".repeat(width.saturating_sub(text.chars().count()))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table() {
+        let mut output = Output::buffered();
+        output.table(
+            &["Name", "Status"],
+            &[
+                vec!["create_users".to_string(), "Ran".to_string()],
+                vec!["create_posts".to_string()],
+            ],
+        );
+        assert_eq!(
+            output.contents(),
+            "+--------------+--------+\n\
+             | Name         | Status |\n\
+             +--------------+--------+\n\
+             | create_users | Ran    |\n\
+             | create_posts |        |\n\
+             +--------------+--------+\n"
+        );
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut output = Output::buffered();
+        let mut progress = output.progress(4);
+        progress.advance(1);
+        progress.advance(5);
+        progress.finish();
+
+        let contents = output.contents();
+        let frames: Vec<&str> = contents.split('\r').filter(|f| !f.is_empty()).collect();
+        assert_eq!(frames[0], " 0/4 [----------------------------]   0%");
+        assert_eq!(frames[1], " 1/4 [#######---------------------]  25%");
+        assert_eq!(
+            frames.last(),
+            Some(&" 4/4 [############################] 100%\n")
+        );
+    }
+
+    #[test]
+    fn test_colors() {
+        let mut output = Output::buffered().colors(true);
+        output.error("failed");
+        assert_eq!(output.contents(), "\x1b[1;31mfailed\x1b[0m\n");
+        assert_eq!(Output::buffered().paint("plain", Style::Bold), "plain");
+    }
+}
@@ -0,0 +1,165 @@
+//! `tinker`: running commands interactively or from a script
+//!
+//! Besides commands, a session understands
+//!
+//! - `set <name> <value>` defining a variable, used as `$name` or
+//!   `${name}` in later lines (`$$` is a literal `$`)
+//! - `vars` listing the variables
+//! - `# ...` comments
+//! - `exit` or `quit`
+
+use crate::kernel::split_line;
+use crate::{ConsoleError, ConsoleResult, Kernel, Output};
+use std::collections::BTreeMap;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// Variables of a tinker session
+#[derive(Debug, Default)]
+struct Session {
+    vars: BTreeMap<String, String>,
+}
+
+impl Session {
+    /// `arg` with variables replaced by their values
+    fn expand(&self, arg: &str) -> ConsoleResult<String> {
+        let mut expanded = String::with_capacity(arg.len());
+        let mut chars = arg.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '$' {
+                expanded.push(c);
+                continue;
+            }
+            if chars.next_if_eq(&'$').is_some() {
+                expanded.push('$');
+                continue;
+            }
+
+            let braced = chars.next_if_eq(&'{').is_some();
+            let mut name = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                name.push(c);
+            }
+            if braced && chars.next_if_eq(&'}').is_none() {
+                return Err(ConsoleError::InvalidArguments(format!(
+                    "Unterminated variable ${{{}",
+                    name
+                )));
+            }
+            if name.is_empty() {
+                expanded.push('$');
+                continue;
+            }
+            let value = self.vars.get(&name).ok_or_else(|| {
+                ConsoleError::InvalidArguments(format!("Undefined variable ${}", name))
+            })?;
+            expanded.push_str(value);
+        }
+        Ok(expanded)
+    }
+}
+
+/// What to do after a line
+enum Step {
+    Continue,
+    Exit,
+}
+
+impl Kernel {
+    /// Read commands line by line from `input` and run them until `exit`
+    ///
+    /// Failing commands are reported without ending the session.
+    pub async fn tinker(
+        &self,
+        input: impl AsyncBufRead + Unpin,
+        output: &mut Output,
+    ) -> ConsoleResult<()> {
+        output
+            .comment("Type a command with its arguments, `list`, `set <name> <value>` or `exit`.");
+        let mut session = Session::default();
+        let mut lines = input.lines();
+        loop {
+            output.write("> ");
+            let Some(line) = lines.next_line().await? else {
+                output.newline();
+                return Ok(());
+            };
+            match self.eval(&mut session, &line, output).await {
+                Ok(Step::Continue) => {}
+                Ok(Step::Exit) => return Ok(()),
+                Err(e) => self.report(&e, output),
+            }
+        }
+    }
+
+    /// Run the tinker commands in the file at `path`, stopping at the first
+    /// failing one
+    ///
+    /// This is what `tinker <script>` does, e.g. for a checked-in
+    /// maintenance script run against production.
+    pub async fn run_script(&self, path: &str, output: &mut Output) -> ConsoleResult<()> {
+        let script = std::fs::read_to_string(path)?;
+        let mut session = Session::default();
+        for (number, line) in script.lines().enumerate() {
+            match self.eval(&mut session, line, output).await {
+                Ok(Step::Continue) => {}
+                Ok(Step::Exit) => break,
+                Err(e) => {
+                    return Err(ConsoleError::failed(format!(
+                        "{}:{}: {}",
+                        path,
+                        number + 1,
+                        e.to_string().trim_end()
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run one line of a session
+    async fn eval(
+        &self,
+        session: &mut Session,
+        line: &str,
+        output: &mut Output,
+    ) -> ConsoleResult<Step> {
+        let line = line.trim();
+        if line.starts_with('#') {
+            return Ok(Step::Continue);
+        }
+        let mut args = split_line(line)?.into_iter();
+        let Some(name) = args.next() else {
+            return Ok(Step::Continue);
+        };
+        let args = args
+            .map(|arg| session.expand(&arg))
+            .collect::<ConsoleResult<Vec<_>>>()?;
+
+        match name.as_str() {
+            "exit" | "quit" => return Ok(Step::Exit),
+            "tinker" => output.warning("Already in tinker"),
+            "set" => {
+                let [var, value] = <[String; 2]>::try_from(args).map_err(|_| {
+                    ConsoleError::InvalidArguments("Usage: set <name> <value>".to_string())
+                })?;
+                if var.is_empty() || !var.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    return Err(ConsoleError::InvalidArguments(format!(
+                        "Invalid variable name \"{}\"",
+                        var
+                    )));
+                }
+                session.vars.insert(var, value);
+            }
+            "vars" => {
+                let rows: Vec<[String; 2]> = session
+                    .vars
+                    .iter()
+                    .map(|(name, value)| [name.clone(), value.clone()])
+                    .collect();
+                output.table(&["Variable", "Value"], &rows);
+            }
+            name => self.dispatch(name, args, output).await?,
+        }
+        Ok(Step::Continue)
+    }
+}
@@ -0,0 +1,196 @@
+//! Cache hits and misses on the timeline
+
+use crate::profile::EntryKind;
+use crate::recorder::{is_profiling, record};
+use async_trait::async_trait;
+use rf_cache::{Cache, CacheResult};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Cache adding each operation to the timeline of the profiled request
+///
+/// ```
+/// use rf_cache::MemoryCache;
+/// use rf_debugbar::ProfiledCache;
+///
+/// let cache = ProfiledCache::new(MemoryCache::new());
+/// ```
+pub struct ProfiledCache<C> {
+    inner: C,
+}
+
+impl<C: Cache> ProfiledCache<C> {
+    pub fn new(inner: C) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+fn recorded(operation: &str, key: &str, hit: Option<bool>, started: Instant) {
+    record(
+        EntryKind::cache(operation, key, hit),
+        Some(started.elapsed()),
+    );
+}
+
+#[async_trait]
+impl<C: Cache> Cache for ProfiledCache<C> {
+    async fn get<T: DeserializeOwned + Send>(&self, key: &str) -> CacheResult<Option<T>> {
+        let started = Instant::now();
+        let value = self.inner.get(key).await?;
+        recorded("get", key, Some(value.is_some()), started);
+        Ok(value)
+    }
+
+    async fn set<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<()> {
+        let started = Instant::now();
+        self.inner.set(key, value, ttl).await?;
+        recorded("set", key, None, started);
+        Ok(())
+    }
+
+    async fn add<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let started = Instant::now();
+        let added = self.inner.add(key, value, ttl).await?;
+        recorded("add", key, None, started);
+        Ok(added)
+    }
+
+    async fn compare_and_set<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let started = Instant::now();
+        let replaced = self.inner.compare_and_set(key, current, value, ttl).await?;
+        recorded("compare_and_set", key, None, started);
+        Ok(replaced)
+    }
+
+    async fn compare_and_delete<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+    ) -> CacheResult<bool> {
+        let started = Instant::now();
+        let deleted = self.inner.compare_and_delete(key, current).await?;
+        recorded("compare_and_delete", key, None, started);
+        Ok(deleted)
+    }
+
+    async fn delete(&self, key: &str) -> CacheResult<()> {
+        let started = Instant::now();
+        self.inner.delete(key).await?;
+        recorded("delete", key, None, started);
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> CacheResult<bool> {
+        let started = Instant::now();
+        let exists = self.inner.exists(key).await?;
+        recorded("exists", key, Some(exists), started);
+        Ok(exists)
+    }
+
+    async fn ttl(&self, key: &str) -> CacheResult<Option<Duration>> {
+        let started = Instant::now();
+        let ttl = self.inner.ttl(key).await?;
+        recorded("ttl", key, Some(ttl.is_some()), started);
+        Ok(ttl)
+    }
+
+    async fn flush(&self) -> CacheResult<()> {
+        let started = Instant::now();
+        self.inner.flush().await?;
+        recorded("flush", "*", None, started);
+        Ok(())
+    }
+
+    async fn get_many<T: DeserializeOwned + Send>(
+        &self,
+        keys: &[&str],
+    ) -> CacheResult<HashMap<String, T>> {
+        let values = self.inner.get_many(keys).await?;
+        if is_profiling() {
+            for key in keys {
+                record(
+                    EntryKind::cache("get_many", *key, Some(values.contains_key(*key))),
+                    None,
+                );
+            }
+        }
+        Ok(values)
+    }
+
+    async fn set_many<T: Serialize + Sync>(
+        &self,
+        items: &[(&str, T)],
+        ttl: Duration,
+    ) -> CacheResult<()> {
+        let started = Instant::now();
+        self.inner.set_many(items, ttl).await?;
+        if is_profiling() {
+            let keys: Vec<&str> = items.iter().map(|(key, _)| *key).collect();
+            recorded("set_many", &keys.join(", "), None, started);
+        }
+        Ok(())
+    }
+
+    async fn delete_many(&self, keys: &[&str]) -> CacheResult<()> {
+        let started = Instant::now();
+        self.inner.delete_many(keys).await?;
+        recorded("delete_many", &keys.join(", "), None, started);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::Recorder;
+    use rf_cache::MemoryCache;
+
+    #[tokio::test]
+    async fn test_records_hits_and_misses() {
+        let cache = ProfiledCache::new(MemoryCache::new());
+        let recorder = Recorder::new();
+        recorder
+            .scope(async {
+                let ttl = Duration::from_secs(60);
+                assert_eq!(cache.get::<String>("user:1").await.unwrap(), None);
+                cache.set("user:1", &"Ada", ttl).await.unwrap();
+                assert!(cache.get::<String>("user:1").await.unwrap().is_some());
+            })
+            .await;
+
+        let kinds: Vec<_> = recorder
+            .timeline()
+            .into_iter()
+            .map(|entry| entry.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                EntryKind::cache("get", "user:1", Some(false)),
+                EntryKind::cache("set", "user:1", None),
+                EntryKind::cache("get", "user:1", Some(true)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,373 @@
+//! Profiling middleware and debug routes
+
+use crate::profile::{millis, MemoryUsage, Profile, ProfileSummary};
+use crate::recorder::Recorder;
+use crate::toolbar;
+use axum::{
+    body::{Body, HttpBody},
+    extract::{Path, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::Utc;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Header carrying the ID of a profiled request's [`Profile`]
+pub const REQUEST_ID_HEADER: &str = "x-debug-request-id";
+
+/// Largest HTML page the toolbar is injected into
+const MAX_HTML: usize = 8 * 1024 * 1024;
+
+/// Profiles requests and keeps the latest profiles
+///
+/// Meant for development only: profiles hold SQL bindings, cache keys and
+/// URLs, so don't enable it where others can reach `/debug/requests`. It's
+/// disabled unless turned on with [`enabled`](Self::enabled) or by the
+/// environment, see [`from_env`](Self::from_env).
+///
+/// ```
+/// use axum::{middleware, routing::get, Router};
+/// use rf_debugbar::{profile_requests, Debugbar};
+///
+/// let debugbar = Debugbar::from_env();
+/// let app: Router = Router::new()
+///     .route("/", get(|| async { "home" }))
+///     .layer(middleware::from_fn_with_state(debugbar.clone(), profile_requests))
+///     .merge(debugbar.router());
+/// ```
+#[derive(Clone)]
+pub struct Debugbar {
+    enabled: bool,
+    toolbar: bool,
+    capacity: usize,
+    profiles: Arc<Mutex<VecDeque<Profile>>>,
+    #[cfg(feature = "orm")]
+    query_log: Option<rf_orm_lite::QueryLog>,
+}
+
+impl Debugbar {
+    /// Create a disabled debugbar keeping the last 100 profiles
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            toolbar: true,
+            capacity: 100,
+            profiles: Arc::default(),
+            #[cfg(feature = "orm")]
+            query_log: None,
+        }
+    }
+
+    /// Create a debugbar enabled when `APP_DEBUG` is `true` or `APP_ENV` is
+    /// `local` or `development`
+    pub fn from_env() -> Self {
+        let debug = std::env::var("APP_DEBUG").is_ok_and(|debug| debug == "true" || debug == "1");
+        let development = std::env::var("APP_ENV")
+            .is_ok_and(|environment| environment == "local" || environment == "development");
+        Self::new().enabled(debug || development)
+    }
+
+    /// Profile requests and serve the debug routes only if `enabled`
+    /// (default: false)
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Whether to inject the toolbar into HTML pages (default: true)
+    pub fn toolbar(mut self, toolbar: bool) -> Self {
+        self.toolbar = toolbar;
+        self
+    }
+
+    /// Keep the last `capacity` profiles
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Warn about slow and N+1 queries of each request, as found by `log`
+    ///
+    /// `log` has to listen to the database too, see
+    /// [`QueryLog`](rf_orm_lite::QueryLog).
+    #[cfg(feature = "orm")]
+    pub fn query_log(mut self, log: rf_orm_lite::QueryLog) -> Self {
+        self.query_log = Some(log);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Kept profiles, latest first
+    pub fn profiles(&self) -> Vec<Profile> {
+        self.profiles.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn profile(&self, id: &str) -> Option<Profile> {
+        let profiles = self.profiles.lock().unwrap();
+        profiles.iter().find(|profile| profile.id == id).cloned()
+    }
+
+    /// Handle the request with `recorder` as the current recorder,
+    /// returning the response and the warnings about its queries
+    async fn run(&self, recorder: &Recorder, req: Request, next: Next) -> (Response, Vec<String>) {
+        #[cfg(feature = "orm")]
+        if let Some(log) = &self.query_log {
+            let (response, report) = log.scope(recorder.scope(next.run(req))).await;
+            return (response, report.warnings());
+        }
+        (recorder.scope(next.run(req)).await, Vec::new())
+    }
+
+    fn keep(&self, profile: Profile) {
+        let mut profiles = self.profiles.lock().unwrap();
+        profiles.push_front(profile);
+        profiles.truncate(self.capacity);
+    }
+
+    /// `GET /debug/requests` listing the kept profiles and
+    /// `GET /debug/requests/{id}` returning one, empty when disabled
+    pub fn router(&self) -> Router {
+        if !self.enabled {
+            return Router::new();
+        }
+        Router::new()
+            .route("/debug/requests", get(list_profiles))
+            .route("/debug/requests/{id}", get(show_profile))
+            .with_state(self.clone())
+    }
+}
+
+impl Default for Debugbar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn list_profiles(State(debugbar): State<Debugbar>) -> Json<Vec<ProfileSummary>> {
+    let profiles = debugbar.profiles.lock().unwrap();
+    Json(profiles.iter().map(Profile::summary).collect())
+}
+
+async fn show_profile(State(debugbar): State<Debugbar>, Path(id): Path<String>) -> Response {
+    match debugbar.profile(&id) {
+        Some(profile) => Json(profile).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Middleware profiling each request with a [`Debugbar`]
+///
+/// Records the request's timeline, keeps its [`Profile`], sets the
+/// [`REQUEST_ID_HEADER`] and injects the toolbar into HTML pages. Requests
+/// to `/debug/` aren't profiled.
+pub async fn profile_requests(
+    State(debugbar): State<Debugbar>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if !debugbar.enabled || req.uri().path().starts_with("/debug/") {
+        return next.run(req).await;
+    }
+
+    let method = req.method().to_string();
+    let uri = req.uri().to_string();
+    let started_at = Utc::now();
+    let memory_before = MemoryUsage::sample();
+    let recorder = Recorder::new();
+    let (mut response, warnings) = debugbar.run(&recorder, req, next).await;
+
+    let profile = Profile {
+        id: uuid::Uuid::new_v4().to_string(),
+        method,
+        uri,
+        status: response.status().as_u16(),
+        started_at,
+        duration_ms: millis(recorder.elapsed()),
+        memory: memory_before
+            .zip(MemoryUsage::sample())
+            .map(|(before_bytes, after_bytes)| MemoryUsage {
+                before_bytes,
+                after_bytes,
+            }),
+        timeline: recorder.timeline(),
+        warnings,
+    };
+    if let Ok(id) = HeaderValue::from_str(&profile.id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, id);
+    }
+    if debugbar.toolbar && is_html(&response) {
+        response = inject_toolbar(response, &profile).await;
+    }
+    debugbar.keep(profile);
+    response
+}
+
+/// Whether the response is an uncompressed HTML page small enough to inject into
+///
+/// Streamed pages, whose size isn't known upfront, are left alone.
+fn is_html(response: &Response) -> bool {
+    let headers = response.headers();
+    let html = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/html"));
+    let small = response
+        .body()
+        .size_hint()
+        .exact()
+        .is_some_and(|length| length <= MAX_HTML as u64);
+    html && small && !headers.contains_key(header::CONTENT_ENCODING)
+}
+
+async fn inject_toolbar(response: Response, profile: &Profile) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let page = match axum::body::to_bytes(body, MAX_HTML).await {
+        Ok(page) => page,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to read the page to inject the debugbar into");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let page = toolbar::inject(&String::from_utf8_lossy(&page), profile);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{record, template, EntryKind};
+    use axum::{middleware, response::Html};
+    use tower::ServiceExt;
+
+    async fn orders() -> Html<String> {
+        record(
+            EntryKind::Query {
+                sql: "SELECT * FROM orders WHERE customer_id = $1".to_string(),
+                bindings: vec![7.into()],
+                failed: false,
+            },
+            Some(std::time::Duration::from_millis(2)),
+        );
+        record(EntryKind::cache("get", "orders:7", Some(false)), None);
+        Html(template("orders/index", || {
+            "<html><body><h1>Orders</h1></body></html>".to_string()
+        }))
+    }
+
+    fn app(debugbar: &Debugbar) -> Router {
+        Router::new()
+            .route("/orders", get(orders))
+            .route("/api/orders", get(|| async { Json(vec![1, 2]) }))
+            .layer(middleware::from_fn_with_state(
+                debugbar.clone(),
+                profile_requests,
+            ))
+            .merge(debugbar.router())
+    }
+
+    async fn get_json(app: Router, uri: &str) -> serde_json::Value {
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_profiles_requests() {
+        let debugbar = Debugbar::new().enabled(true);
+        let response = app(&debugbar)
+            .oneshot(Request::get("/orders").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let id = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let page = String::from_utf8(body.to_vec()).unwrap();
+        assert!(page.contains("id=\"rf-debugbar\""));
+        assert!(page.ends_with("</body></html>"));
+
+        let requests = get_json(app(&debugbar), "/debug/requests").await;
+        assert_eq!(requests[0]["id"], id.as_str());
+        assert_eq!(requests[0]["uri"], "/orders");
+        assert_eq!(requests[0]["queries"], 1);
+        assert_eq!(requests[0]["cache_misses"], 1);
+        assert_eq!(requests[0]["templates"], 1);
+
+        let profile = get_json(app(&debugbar), &format!("/debug/requests/{}", id)).await;
+        let timeline = profile["timeline"].as_array().unwrap();
+        assert_eq!(timeline[0]["type"], "query");
+        assert_eq!(timeline[0]["bindings"], serde_json::json!([7]));
+        assert_eq!(timeline[1]["key"], "orders:7");
+        assert_eq!(timeline[2]["name"], "orders/index");
+    }
+
+    #[tokio::test]
+    async fn test_leaves_json_alone() {
+        let debugbar = Debugbar::new().enabled(true).capacity(1);
+        for _ in 0..2 {
+            let body = get_json(app(&debugbar), "/api/orders").await;
+            assert_eq!(body, serde_json::json!([1, 2]));
+        }
+        assert_eq!(debugbar.profiles().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_leaves_large_pages_alone() {
+        let debugbar = Debugbar::new().enabled(true);
+        let page = format!("<html><body>{}</body></html>", "x".repeat(MAX_HTML));
+        let app = Router::new()
+            .route("/report", get(move || async move { Html(page) }))
+            .layer(middleware::from_fn_with_state(
+                debugbar.clone(),
+                profile_requests,
+            ));
+
+        let response = app
+            .oneshot(Request::get("/report").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key(REQUEST_ID_HEADER));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body.len(), MAX_HTML + "<html><body></body></html>".len());
+    }
+
+    #[tokio::test]
+    async fn test_disabled() {
+        assert!(!Debugbar::default().is_enabled());
+
+        let debugbar = Debugbar::new();
+        let response = app(&debugbar)
+            .oneshot(Request::get("/orders").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(!response.headers().contains_key(REQUEST_ID_HEADER));
+
+        let response = app(&debugbar)
+            .oneshot(Request::get("/debug/requests").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(debugbar.profiles().is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+//! Dispatched events on the timeline
+
+use crate::profile::EntryKind;
+use crate::recorder::record;
+use async_trait::async_trait;
+use rf_events::{Event, EventResult, WildcardListener};
+
+/// Wildcard listener adding each dispatched event to the timeline of the
+/// profiled request
+///
+/// ```
+/// use rf_debugbar::EventCollector;
+/// use rf_events::EventDispatcher;
+///
+/// # async fn example() {
+/// let dispatcher = EventDispatcher::new();
+/// dispatcher.listen_any("*", EventCollector).await;
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EventCollector;
+
+#[async_trait]
+impl WildcardListener for EventCollector {
+    async fn handle(&self, event: &dyn Event) -> EventResult<()> {
+        record(
+            EntryKind::Event {
+                name: event.name().to_string(),
+            },
+            None,
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::Recorder;
+    use rf_events::EventDispatcher;
+
+    struct OrderShipped;
+
+    impl Event for OrderShipped {
+        fn name(&self) -> &'static str {
+            "OrderShipped"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_records_events() {
+        let dispatcher = EventDispatcher::new();
+        dispatcher.listen_any("*", EventCollector).await;
+
+        let recorder = Recorder::new();
+        recorder
+            .scope(async { dispatcher.dispatch(OrderShipped).await.unwrap() })
+            .await;
+
+        let timeline = recorder.timeline();
+        assert_eq!(
+            timeline[0].kind,
+            EntryKind::Event {
+                name: "OrderShipped".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Request profiling and debug toolbar for RustForge development
+//!
+//! Records a timeline of what each request did and serves it as JSON and
+//! as a toolbar at the bottom of server-rendered pages.
+//!
+//! # Features
+//!
+//! - Per-request timeline of queries, cache operations, rendered templates
+//!   and dispatched events, plus duration and process memory, see
+//!   [`profile_requests`]
+//! - `/debug/requests` listing the latest requests and
+//!   `/debug/requests/{id}` with a request's full [`Profile`]
+//! - Toolbar injected into HTML responses
+//! - rf-orm-lite queries with bindings and durations, and warnings about
+//!   slow and N+1 queries (feature `orm`)
+//! - rf-cache hits and misses (feature `cache`)
+//! - rf-events dispatches (feature `events`)
+//! - Templates and custom entries through [`template`] and [`record`]
+//!
+//! Entries are recorded on the task handling the request; work spawned onto
+//! other tasks doesn't show up.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use axum::{middleware, response::Html, routing::get, Router};
+//! use rf_debugbar::{profile_requests, Debugbar};
+//!
+//! async fn home() -> Html<String> {
+//!     Html(rf_debugbar::template("home", || {
+//!         "<html><body>Welcome</body></html>".to_string()
+//!     }))
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     // Enabled with APP_DEBUG=true or APP_ENV=local
+//!     let debugbar = Debugbar::from_env();
+//!
+//!     let app: Router = Router::new()
+//!         .route("/", get(home))
+//!         .layer(middleware::from_fn_with_state(debugbar.clone(), profile_requests))
+//!         .merge(debugbar.router());
+//!     let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await?;
+//!     axum::serve(listener, app).await?;
+//!     Ok(())
+//! }
+//! ```
+
+mod debugbar;
+mod profile;
+mod recorder;
+mod toolbar;
+
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "events")]
+mod events;
+#[cfg(feature = "orm")]
+mod orm;
+
+pub use debugbar::{profile_requests, Debugbar, REQUEST_ID_HEADER};
+pub use profile::{Entry, EntryKind, MemoryUsage, Profile, ProfileSummary};
+pub use recorder::{is_profiling, record, template};
+
+#[cfg(feature = "cache")]
+pub use cache::ProfiledCache;
+#[cfg(feature = "events")]
+pub use events::EventCollector;
+#[cfg(feature = "orm")]
+pub use orm::QueryCollector;
@@ -0,0 +1,134 @@
+//! Database queries on the timeline
+
+use crate::profile::EntryKind;
+use crate::recorder::{is_profiling, record};
+use rf_orm_lite::{ExecutedQuery, QueryListener, Value};
+use serde_json::json;
+
+/// Query listener adding each query, with its bindings and duration, to the
+/// timeline of the profiled request
+///
+/// With a [`QueryLog`](rf_orm_lite::QueryLog) listening too and given to
+/// [`Debugbar::query_log`](crate::Debugbar::query_log), slow and N+1 queries
+/// show up as warnings.
+///
+/// ```no_run
+/// use rf_debugbar::{Debugbar, QueryCollector};
+/// use rf_orm_lite::{Db, QueryLog};
+///
+/// # async fn example() -> rf_orm_lite::OrmResult<()> {
+/// let log = QueryLog::new();
+/// let db = Db::connect("postgres://localhost/app")
+///     .await?
+///     .listen(QueryCollector)
+///     .listen(log);
+/// let debugbar = Debugbar::from_env().query_log(log);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueryCollector;
+
+impl QueryListener for QueryCollector {
+    fn query(&self, query: &ExecutedQuery<'_>) {
+        if !is_profiling() {
+            return;
+        }
+        record(
+            EntryKind::Query {
+                sql: query.sql.to_string(),
+                bindings: query.values.iter().map(binding).collect(),
+                failed: query.failed,
+            },
+            Some(query.duration),
+        );
+    }
+}
+
+fn binding(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Bool(value) => json!(value),
+        Value::Int(value) => json!(value),
+        Value::Float(value) => json!(value),
+        Value::Text(value) => json!(value),
+        Value::Bytes(value) => json!(format!("<{} bytes>", value.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::Recorder;
+    use rf_orm_lite::{Connection, Db};
+
+    #[tokio::test]
+    async fn test_records_queries() {
+        let db = Db::connect("sqlite::memory:")
+            .await
+            .unwrap()
+            .listen(QueryCollector);
+
+        let recorder = Recorder::new();
+        recorder
+            .scope(async {
+                db.fetch_all("SELECT ? + 1", vec![41.into()]).await.unwrap();
+            })
+            .await;
+        db.fetch_all("SELECT 1", vec![]).await.unwrap();
+
+        let timeline = recorder.timeline();
+        assert_eq!(timeline.len(), 1);
+        assert_eq!(
+            timeline[0].kind,
+            EntryKind::Query {
+                sql: "SELECT ? + 1".to_string(),
+                bindings: vec![json!(41)],
+                failed: false,
+            }
+        );
+        assert!(timeline[0].duration_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_warns_about_repeated_queries() {
+        use crate::{profile_requests, Debugbar};
+        use axum::{body::Body, extract::Request, middleware, routing::get, Router};
+        use rf_orm_lite::QueryLog;
+        use tower::ServiceExt;
+
+        let log = QueryLog::new().repeat_limit(2);
+        let db = Db::connect("sqlite::memory:")
+            .await
+            .unwrap()
+            .listen(QueryCollector)
+            .listen(log);
+        let debugbar = Debugbar::new().enabled(true).query_log(log);
+        let app = Router::new()
+            .route(
+                "/posts",
+                get(move || async move {
+                    for post in 1..=3 {
+                        db.fetch_all("SELECT ? AS post_id", vec![post.into()])
+                            .await
+                            .unwrap();
+                    }
+                }),
+            )
+            .layer(middleware::from_fn_with_state(
+                debugbar.clone(),
+                profile_requests,
+            ));
+
+        app.oneshot(Request::get("/posts").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let profile = &debugbar.profiles()[0];
+        assert_eq!(profile.summary().queries, 3);
+        assert_eq!(
+            profile.warnings,
+            ["Possible N+1 query, ran 3 times: SELECT ? AS post_id"]
+        );
+    }
+}
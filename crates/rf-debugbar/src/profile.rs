@@ -0,0 +1,152 @@
+//! Profiles of handled requests
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Everything recorded while a request was handled
+#[derive(Debug, Clone, Serialize)]
+pub struct Profile {
+    pub id: String,
+    pub method: String,
+    pub uri: String,
+    pub status: u16,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: f64,
+    /// Resident memory of the process before and after the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryUsage>,
+    /// Entries in the order they happened
+    pub timeline: Vec<Entry>,
+    /// Problems found, e.g. slow and N+1 queries
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Resident memory of the process, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MemoryUsage {
+    pub before_bytes: u64,
+    pub after_bytes: u64,
+}
+
+impl MemoryUsage {
+    /// Resident memory of the process now, if the platform reports it
+    pub(crate) fn sample() -> Option<u64> {
+        use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+        let pid = sysinfo::get_current_pid().ok()?;
+        let mut system = System::new();
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            false,
+            ProcessRefreshKind::new().with_memory(),
+        );
+        system.process(pid).map(|process| process.memory())
+    }
+}
+
+/// One thing that happened during a request
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Entry {
+    /// When it started, after the request started
+    pub offset_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<f64>,
+    #[serde(flatten)]
+    pub kind: EntryKind,
+}
+
+/// What happened, serialized with a `type` field
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EntryKind {
+    Query {
+        sql: String,
+        bindings: Vec<serde_json::Value>,
+        failed: bool,
+    },
+    Cache {
+        operation: String,
+        key: String,
+        /// Whether a read found the key
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hit: Option<bool>,
+    },
+    Template {
+        name: String,
+    },
+    Event {
+        name: String,
+    },
+}
+
+impl EntryKind {
+    pub fn cache(operation: impl Into<String>, key: impl Into<String>, hit: Option<bool>) -> Self {
+        EntryKind::Cache {
+            operation: operation.into(),
+            key: key.into(),
+            hit,
+        }
+    }
+}
+
+/// Counts of a [`Profile`], as listed by `/debug/requests`
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileSummary {
+    pub id: String,
+    pub method: String,
+    pub uri: String,
+    pub status: u16,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: f64,
+    pub queries: usize,
+    pub query_ms: f64,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+    pub templates: usize,
+    pub events: usize,
+    pub warnings: usize,
+}
+
+impl Profile {
+    pub fn summary(&self) -> ProfileSummary {
+        let mut summary = ProfileSummary {
+            id: self.id.clone(),
+            method: self.method.clone(),
+            uri: self.uri.clone(),
+            status: self.status,
+            started_at: self.started_at,
+            duration_ms: self.duration_ms,
+            queries: 0,
+            query_ms: 0.0,
+            cache_hits: 0,
+            cache_misses: 0,
+            templates: 0,
+            events: 0,
+            warnings: self.warnings.len(),
+        };
+        for entry in &self.timeline {
+            match entry.kind {
+                EntryKind::Query { .. } => {
+                    summary.queries += 1;
+                    summary.query_ms += entry.duration_ms.unwrap_or_default();
+                }
+                EntryKind::Cache {
+                    hit: Some(true), ..
+                } => summary.cache_hits += 1,
+                EntryKind::Cache {
+                    hit: Some(false), ..
+                } => summary.cache_misses += 1,
+                EntryKind::Cache { hit: None, .. } => {}
+                EntryKind::Template { .. } => summary.templates += 1,
+                EntryKind::Event { .. } => summary.events += 1,
+            }
+        }
+        summary
+    }
+}
+
+pub(crate) fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -0,0 +1,114 @@
+//! Timeline of the request being profiled
+
+use crate::profile::{millis, Entry, EntryKind};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+tokio::task_local! {
+    static RECORDER: Recorder;
+}
+
+/// Timeline shared by everything running on the request's task
+#[derive(Clone)]
+pub(crate) struct Recorder {
+    started: Instant,
+    timeline: Arc<Mutex<Vec<Entry>>>,
+}
+
+impl Recorder {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            timeline: Arc::default(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Run `future` with this recorder as the current one
+    pub(crate) async fn scope<F: Future>(&self, future: F) -> F::Output {
+        RECORDER.scope(self.clone(), future).await
+    }
+
+    pub(crate) fn timeline(&self) -> Vec<Entry> {
+        let mut timeline = self.timeline.lock().unwrap().clone();
+        timeline.sort_by(|a, b| a.offset_ms.total_cmp(&b.offset_ms));
+        timeline
+    }
+}
+
+/// Whether a request is being profiled on this task
+///
+/// Collectors check this before doing work only needed for the timeline.
+pub fn is_profiling() -> bool {
+    RECORDER.try_with(|_| ()).is_ok()
+}
+
+/// Add an entry to the timeline of the request being profiled, if any
+///
+/// `duration` is how long the entry took until now, so the entry is placed
+/// at the time it started.
+pub fn record(kind: EntryKind, duration: Option<Duration>) {
+    let _ = RECORDER.try_with(|recorder| {
+        let offset = recorder
+            .started
+            .elapsed()
+            .saturating_sub(duration.unwrap_or_default());
+        recorder.timeline.lock().unwrap().push(Entry {
+            offset_ms: millis(offset),
+            duration_ms: duration.map(millis),
+            kind,
+        });
+    });
+}
+
+/// Render a template with `render`, adding it to the timeline
+///
+/// ```
+/// let html = rf_debugbar::template("orders/index", || "<ul></ul>".to_string());
+/// ```
+pub fn template<T>(name: &str, render: impl FnOnce() -> T) -> T {
+    let started = Instant::now();
+    let output = render();
+    record(
+        EntryKind::Template {
+            name: name.to_string(),
+        },
+        Some(started.elapsed()),
+    );
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record() {
+        let event = || EntryKind::Event {
+            name: "OrderShipped".to_string(),
+        };
+        record(event(), None);
+        assert!(!is_profiling());
+
+        let recorder = Recorder::new();
+        recorder
+            .scope(async {
+                assert!(is_profiling());
+                std::thread::sleep(Duration::from_millis(5));
+                record(event(), None);
+                template("orders/show", || ());
+            })
+            .await;
+
+        let timeline = recorder.timeline();
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(timeline[0].kind, event());
+        assert!(timeline[0].offset_ms >= 5.0);
+        assert!(matches!(&timeline[1].kind, EntryKind::Template { name } if name == "orders/show"));
+        assert!(timeline[1].duration_ms.is_some());
+    }
+}
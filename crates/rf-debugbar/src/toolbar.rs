@@ -0,0 +1,178 @@
+//! Toolbar injected into HTML pages
+
+use crate::profile::{EntryKind, Profile};
+use std::fmt::Write;
+
+/// Insert the toolbar of `profile` before the closing body tag of `page`,
+/// or append it
+pub(crate) fn inject(page: &str, profile: &Profile) -> String {
+    let toolbar = render(profile);
+    let end = page
+        .to_ascii_lowercase()
+        .rfind("</body>")
+        .unwrap_or(page.len());
+    let mut injected = String::with_capacity(page.len() + toolbar.len());
+    injected.push_str(&page[..end]);
+    injected.push_str(&toolbar);
+    injected.push_str(&page[end..]);
+    injected
+}
+
+fn render(profile: &Profile) -> String {
+    let summary = profile.summary();
+    let mut html = String::from(
+        "<div id=\"rf-debugbar\" style=\"position:fixed;bottom:0;left:0;right:0;\
+         z-index:2147483647;max-height:50vh;overflow:auto;background:#1f2430;\
+         color:#e6e6e6;font:12px/1.5 monospace;border-top:2px solid #e0533d\">\
+         <details><summary style=\"padding:4px 8px;cursor:pointer\">",
+    );
+    let _ = write!(
+        html,
+        "{} {} &middot; {} &middot; {:.1} ms &middot; {} queries ({:.1} ms) &middot; \
+         cache {} hits / {} misses &middot; {} templates &middot; {} events",
+        escape(&profile.method),
+        escape(&profile.uri),
+        profile.status,
+        profile.duration_ms,
+        summary.queries,
+        summary.query_ms,
+        summary.cache_hits,
+        summary.cache_misses,
+        summary.templates,
+        summary.events,
+    );
+    if !profile.warnings.is_empty() {
+        let _ = write!(
+            html,
+            " &middot; <strong style=\"color:#f5a623\">{} warnings</strong>",
+            profile.warnings.len()
+        );
+    }
+    if let Some(memory) = profile.memory {
+        let _ = write!(
+            html,
+            " &middot; {:.1} MB",
+            memory.after_bytes as f64 / (1024.0 * 1024.0)
+        );
+    }
+    let _ = write!(
+        html,
+        " &middot; <a href=\"/debug/requests/{}\" style=\"color:#8ab4f8\">JSON</a></summary>\
+         <table style=\"width:100%;border-collapse:collapse\">",
+        escape(&profile.id)
+    );
+
+    for warning in &profile.warnings {
+        let _ = write!(
+            html,
+            "<tr><td colspan=\"4\" style=\"padding:2px 8px;color:#f5a623\">{}</td></tr>",
+            escape(warning)
+        );
+    }
+
+    for entry in &profile.timeline {
+        let (kind, detail) = match &entry.kind {
+            EntryKind::Query {
+                sql,
+                bindings,
+                failed,
+            } => {
+                let mut detail = escape(sql);
+                if !bindings.is_empty() {
+                    let bindings = serde_json::Value::from(bindings.clone());
+                    let _ = write!(detail, " <em>{}</em>", escape(&bindings.to_string()));
+                }
+                if *failed {
+                    detail.push_str(" <strong>failed</strong>");
+                }
+                ("query", detail)
+            }
+            EntryKind::Cache {
+                operation,
+                key,
+                hit,
+            } => {
+                let outcome = match hit {
+                    Some(true) => " hit",
+                    Some(false) => " miss",
+                    None => "",
+                };
+                (
+                    "cache",
+                    format!("{} {}{}", escape(operation), escape(key), outcome),
+                )
+            }
+            EntryKind::Template { name } => ("template", escape(name)),
+            EntryKind::Event { name } => ("event", escape(name)),
+        };
+        let duration = entry
+            .duration_ms
+            .map(|duration| format!("{:.2} ms", duration))
+            .unwrap_or_default();
+        let _ = write!(
+            html,
+            "<tr style=\"border-top:1px solid #2f3545\"><td style=\"padding:2px 8px\">+{:.2} ms</td>\
+             <td>{}</td><td>{}</td><td style=\"width:100%\">{}</td></tr>",
+            entry.offset_ms, duration, kind, detail
+        );
+    }
+    html.push_str("</table></details></div>");
+    html
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::Entry;
+    use chrono::Utc;
+
+    #[test]
+    fn test_inject() {
+        let profile = Profile {
+            id: "1".to_string(),
+            method: "GET".to_string(),
+            uri: "/search?q=<script>".to_string(),
+            status: 200,
+            started_at: Utc::now(),
+            duration_ms: 12.5,
+            memory: None,
+            timeline: vec![Entry {
+                offset_ms: 1.0,
+                duration_ms: Some(0.5),
+                kind: EntryKind::Query {
+                    sql: "SELECT * FROM posts WHERE title LIKE ?".to_string(),
+                    bindings: vec!["%<b>%".into()],
+                    failed: false,
+                },
+            }],
+            warnings: vec![
+                "Possible N+1 query, ran 6 times: SELECT * FROM tags WHERE post_id = ?".to_string(),
+            ],
+        };
+
+        let page = inject("<HTML><BODY>Results</BODY></HTML>", &profile);
+        assert!(page.starts_with("<HTML><BODY>Results<div id=\"rf-debugbar\""));
+        assert!(page.ends_with("</div></BODY></HTML>"));
+        assert!(page.contains("/search?q=&lt;script&gt;"));
+        assert!(page.contains("&quot;%&lt;b&gt;%&quot;"));
+        assert!(!page.contains("<script>"));
+        assert!(page.contains("1 warnings"));
+
+        assert!(inject("<p>Fragment</p>", &profile).ends_with("</div>"));
+    }
+}
@@ -0,0 +1,32 @@
+//! Constant-time comparison
+
+use subtle::ConstantTimeEq;
+
+/// Compare secrets such as tokens or signatures without leaking through
+/// timing how many leading bytes match
+///
+/// Only the length may leak, which is fine for hashes and signatures of a
+/// fixed length.
+///
+/// ```
+/// use rf_encryption::constant_time_eq;
+///
+/// assert!(constant_time_eq("token", "token"));
+/// assert!(!constant_time_eq("token", "tokem"));
+/// ```
+pub fn constant_time_eq(a: impl AsRef<[u8]>, b: impl AsRef<[u8]>) -> bool {
+    a.as_ref().ct_eq(b.as_ref()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(vec![1, 2, 3], [1, 2, 3]));
+        assert!(!constant_time_eq("abc", "abd"));
+        assert!(!constant_time_eq("abc", "abcd"));
+    }
+}
@@ -0,0 +1,122 @@
+//! Encryption facade
+
+use crate::{AppKey, Encrypter, EncryptionResult, Signer};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Encryption and signing keyed from one application key
+///
+/// Clones share their keys, so one instance can live in the application
+/// state.
+///
+/// ```
+/// use rf_encryption::{AppKey, Crypt};
+///
+/// let crypt = Crypt::new(&AppKey::generate(), &[]).unwrap();
+/// let token = crypt.encrypt(&("user", 42)).unwrap();
+/// assert_eq!(crypt.decrypt::<(String, u32)>(&token).unwrap(), ("user".into(), 42));
+/// ```
+#[derive(Clone)]
+pub struct Crypt {
+    encrypter: Arc<Encrypter>,
+    signer: Signer,
+}
+
+impl Crypt {
+    /// Facade from an application key and keys used before a rotation
+    pub fn new(key: &str, previous: &[&str]) -> EncryptionResult<Self> {
+        let previous = previous
+            .iter()
+            .map(|key| AppKey::parse(key))
+            .collect::<EncryptionResult<Vec<_>>>()?;
+        Ok(Self::from_keys(AppKey::parse(key)?, previous))
+    }
+
+    /// Facade keyed from `APP_KEY` and `APP_PREVIOUS_KEYS`
+    pub fn from_env() -> EncryptionResult<Self> {
+        let (key, previous) = AppKey::from_env()?;
+        Ok(Self::from_keys(key, previous))
+    }
+
+    fn from_keys(key: AppKey, previous: Vec<AppKey>) -> Self {
+        Self {
+            encrypter: Arc::new(Encrypter::new(key.clone(), previous.clone())),
+            signer: Signer::new(key, previous),
+        }
+    }
+
+    pub fn encrypter(&self) -> &Encrypter {
+        &self.encrypter
+    }
+
+    pub fn signer(&self) -> &Signer {
+        &self.signer
+    }
+
+    /// See [`Encrypter::encrypt_string`]
+    pub fn encrypt_string(&self, value: &str) -> EncryptionResult<String> {
+        self.encrypter.encrypt_string(value)
+    }
+
+    /// See [`Encrypter::decrypt_string`]
+    pub fn decrypt_string(&self, payload: &str) -> EncryptionResult<String> {
+        self.encrypter.decrypt_string(payload)
+    }
+
+    /// See [`Encrypter::encrypt`]
+    pub fn encrypt<T: Serialize + ?Sized>(&self, value: &T) -> EncryptionResult<String> {
+        self.encrypter.encrypt(value)
+    }
+
+    /// See [`Encrypter::decrypt`]
+    pub fn decrypt<T: DeserializeOwned>(&self, payload: &str) -> EncryptionResult<T> {
+        self.encrypter.decrypt(payload)
+    }
+
+    /// See [`Signer::sign`]
+    pub fn sign(&self, value: &str) -> String {
+        self.signer.sign(value)
+    }
+
+    /// See [`Signer::verify`]
+    pub fn verify(&self, value: &str, signature: &str) -> EncryptionResult<()> {
+        self.signer.verify(value, signature)
+    }
+
+    /// See [`Signer::signed_route`]
+    pub fn signed_route(&self, url: &str, expires_in: Duration) -> String {
+        self.signer.signed_route(url, expires_in)
+    }
+
+    /// See [`Signer::verify_route`]
+    pub fn verify_route(&self, url: &str) -> EncryptionResult<()> {
+        self.signer.verify_route(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_covers_encryption_and_signing() {
+        let old = Crypt::new("the-old-application-key", &[]).unwrap();
+        let payload = old.encrypt_string("value").unwrap();
+        let signature = old.sign("value");
+
+        let rotated = Crypt::new("the-new-application-key", &["the-old-application-key"]).unwrap();
+        assert_eq!(rotated.decrypt_string(&payload).unwrap(), "value");
+        assert!(rotated.verify("value", &signature).is_ok());
+
+        assert!(Crypt::new("the-new-application-key", &["short"]).is_err());
+    }
+
+    #[test]
+    fn test_signed_route() {
+        let crypt = Crypt::new("current-key-for-the-tests", &[]).unwrap();
+        let route = crypt.signed_route("/a", Duration::from_secs(60));
+        assert!(crypt.verify_route(&route).is_ok());
+        assert!(crypt.verify_route("/a?expires=1&signature=00").is_err());
+    }
+}
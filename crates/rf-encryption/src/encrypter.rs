@@ -0,0 +1,261 @@
+//! AES-256-GCM encryption with key rotation
+
+use crate::key::{key_id, AppKey};
+use crate::{EncryptionError, EncryptionResult};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Payload format version
+const VERSION: &str = "v1";
+
+/// Length of an AES-GCM nonce in bytes
+const NONCE_LEN: usize = 12;
+
+struct CipherKey {
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+impl CipherKey {
+    fn new(key: &AppKey) -> Self {
+        let derived = key.derive("encryption");
+        Self {
+            id: key_id(&derived),
+            cipher: Aes256Gcm::new(&derived.into()),
+        }
+    }
+}
+
+/// Encrypts and decrypts strings and serde values
+///
+/// Payloads look like `v1.<key id>.<nonce and ciphertext>` and are safe to
+/// put in URLs and cookies. The key id picks the key for decryption, so
+/// after rotating `APP_KEY` payloads encrypted with a previous key still
+/// decrypt, and [`Encrypter::rotate`] re-encrypts them with the current one.
+///
+/// ```
+/// use rf_encryption::{AppKey, Encrypter};
+///
+/// let encrypter = Encrypter::new(AppKey::parse(&AppKey::generate()).unwrap(), Vec::new());
+/// let payload = encrypter.encrypt_string("4111 1111 1111 1111").unwrap();
+/// assert_eq!(encrypter.decrypt_string(&payload).unwrap(), "4111 1111 1111 1111");
+/// ```
+pub struct Encrypter {
+    current: CipherKey,
+    previous: Vec<CipherKey>,
+}
+
+impl Encrypter {
+    /// Encrypter with a current key and keys used before a rotation
+    pub fn new(key: AppKey, previous: Vec<AppKey>) -> Self {
+        Self {
+            current: CipherKey::new(&key),
+            previous: previous.iter().map(CipherKey::new).collect(),
+        }
+    }
+
+    /// Encrypter keyed from `APP_KEY` and `APP_PREVIOUS_KEYS`
+    pub fn from_env() -> EncryptionResult<Self> {
+        let (key, previous) = AppKey::from_env()?;
+        Ok(Self::new(key, previous))
+    }
+
+    /// Id of the current key, as found in new payloads
+    pub fn key_id(&self) -> &str {
+        &self.current.id
+    }
+
+    /// Encrypt bytes with the current key
+    pub fn encrypt_bytes(&self, plaintext: &[u8]) -> EncryptionResult<String> {
+        let header = format!("{}.{}", VERSION, self.current.id);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .current
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: header.as_bytes(),
+                },
+            )
+            .map_err(|_| EncryptionError::EncryptionFailed)?;
+
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        Ok(format!("{}.{}", header, URL_SAFE_NO_PAD.encode(data)))
+    }
+
+    /// Decrypt and verify a payload from [`Encrypter::encrypt_bytes`]
+    pub fn decrypt_bytes(&self, payload: &str) -> EncryptionResult<Vec<u8>> {
+        let (header, key, data) = self.parse(payload)?;
+        if data.len() < NONCE_LEN {
+            return Err(EncryptionError::MalformedPayload(
+                "payload too short".into(),
+            ));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().expect("split at the nonce length");
+
+        key.cipher
+            .decrypt(
+                &Nonce::from(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header.as_bytes(),
+                },
+            )
+            .map_err(|_| EncryptionError::DecryptionFailed)
+    }
+
+    /// Encrypt a string
+    pub fn encrypt_string(&self, value: &str) -> EncryptionResult<String> {
+        self.encrypt_bytes(value.as_bytes())
+    }
+
+    /// Decrypt a string from [`Encrypter::encrypt_string`]
+    pub fn decrypt_string(&self, payload: &str) -> EncryptionResult<String> {
+        String::from_utf8(self.decrypt_bytes(payload)?)
+            .map_err(|_| EncryptionError::MalformedPayload("plaintext is not UTF-8".into()))
+    }
+
+    /// Encrypt a value as JSON
+    pub fn encrypt<T: Serialize + ?Sized>(&self, value: &T) -> EncryptionResult<String> {
+        self.encrypt_bytes(&serde_json::to_vec(value)?)
+    }
+
+    /// Decrypt a value from [`Encrypter::encrypt`]
+    pub fn decrypt<T: DeserializeOwned>(&self, payload: &str) -> EncryptionResult<T> {
+        Ok(serde_json::from_slice(&self.decrypt_bytes(payload)?)?)
+    }
+
+    /// Whether a payload was encrypted with a previous key
+    pub fn needs_rotation(&self, payload: &str) -> bool {
+        payload
+            .split('.')
+            .nth(1)
+            .is_some_and(|id| id != self.current.id)
+    }
+
+    /// Re-encrypt a payload with the current key
+    pub fn rotate(&self, payload: &str) -> EncryptionResult<String> {
+        self.encrypt_bytes(&self.decrypt_bytes(payload)?)
+    }
+
+    fn parse<'a>(&self, payload: &'a str) -> EncryptionResult<(&'a str, &CipherKey, Vec<u8>)> {
+        let (header, data) = payload
+            .rsplit_once('.')
+            .ok_or_else(|| EncryptionError::MalformedPayload("missing header".into()))?;
+        let (version, id) = header
+            .split_once('.')
+            .ok_or_else(|| EncryptionError::MalformedPayload("missing key id".into()))?;
+        if version != VERSION {
+            return Err(EncryptionError::MalformedPayload(format!(
+                "unsupported version {}",
+                version
+            )));
+        }
+
+        let key = std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|key| key.id == id)
+            .ok_or_else(|| EncryptionError::UnknownKey(id.to_string()))?;
+        let data = URL_SAFE_NO_PAD
+            .decode(data)
+            .map_err(|e| EncryptionError::MalformedPayload(e.to_string()))?;
+
+        Ok((header, key, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    fn key(key: &str) -> AppKey {
+        AppKey::parse(key).unwrap()
+    }
+
+    fn encrypter() -> Encrypter {
+        Encrypter::new(key("current-key-for-the-tests"), Vec::new())
+    }
+
+    #[test]
+    fn test_string_roundtrip() {
+        let encrypter = encrypter();
+        let payload = encrypter.encrypt_string("secret value").unwrap();
+
+        assert!(payload.starts_with(&format!("v1.{}.", encrypter.key_id())));
+        assert!(!payload.contains("secret"));
+        assert_ne!(payload, encrypter.encrypt_string("secret value").unwrap());
+        assert_eq!(encrypter.decrypt_string(&payload).unwrap(), "secret value");
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Card {
+            number: String,
+            cvc: u16,
+        }
+
+        let card = Card {
+            number: "4111".into(),
+            cvc: 123,
+        };
+        let encrypter = encrypter();
+        let payload = encrypter.encrypt(&card).unwrap();
+        assert_eq!(encrypter.decrypt::<Card>(&payload).unwrap(), card);
+        assert!(matches!(
+            encrypter.decrypt::<u32>(&payload),
+            Err(EncryptionError::Serialization(_))
+        ));
+    }
+
+    #[test]
+    fn test_rotation() {
+        let old = Encrypter::new(key("the-old-application-key"), Vec::new());
+        let payload = old.encrypt_string("value").unwrap();
+
+        let rotated = Encrypter::new(
+            key("the-new-application-key"),
+            vec![key("the-old-application-key")],
+        );
+        assert!(rotated.needs_rotation(&payload));
+        assert_eq!(rotated.decrypt_string(&payload).unwrap(), "value");
+
+        let payload = rotated.rotate(&payload).unwrap();
+        assert!(!rotated.needs_rotation(&payload));
+        assert!(matches!(
+            old.decrypt_string(&payload),
+            Err(EncryptionError::UnknownKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let encrypter = encrypter();
+        let payload = encrypter.encrypt_string("value").unwrap();
+
+        let mut tampered = payload.clone().into_bytes();
+        let i = tampered.len() - 5;
+        tampered[i] = if tampered[i] == b'A' { b'B' } else { b'A' };
+        assert!(matches!(
+            encrypter.decrypt_string(&String::from_utf8(tampered).unwrap()),
+            Err(EncryptionError::DecryptionFailed)
+        ));
+
+        assert!(matches!(
+            encrypter.decrypt_string(&payload.replacen("v1", "v2", 1)),
+            Err(EncryptionError::MalformedPayload(_))
+        ));
+        assert!(encrypter.decrypt_string("garbage").is_err());
+        assert!(encrypter
+            .decrypt_string(&format!("v1.{}.AAAA", encrypter.key_id()))
+            .is_err());
+    }
+}
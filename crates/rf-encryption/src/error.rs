@@ -0,0 +1,48 @@
+//! Encryption errors
+
+use thiserror::Error;
+
+/// Encryption and signing errors
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error("APP_KEY is not set")]
+    MissingKey,
+
+    #[error("Invalid application key: {0}")]
+    InvalidKey(String),
+
+    #[error("Malformed payload: {0}")]
+    MalformedPayload(String),
+
+    #[error("Payload was encrypted with an unknown key {0}")]
+    UnknownKey(String),
+
+    #[error("Encryption failed")]
+    EncryptionFailed,
+
+    #[error("Payload failed integrity check")]
+    DecryptionFailed,
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Signed link expired")]
+    Expired,
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(EncryptionError, {
+    Self::MalformedPayload(_) => (BAD_REQUEST, "encryption.malformed_payload"),
+    Self::UnknownKey(_) | Self::DecryptionFailed => (BAD_REQUEST, "encryption.invalid_payload"),
+    Self::InvalidSignature(_) => (FORBIDDEN, "encryption.invalid_signature"),
+    Self::Expired => (FORBIDDEN, "encryption.expired"),
+    Self::MissingKey | Self::InvalidKey(_) => (INTERNAL_SERVER_ERROR, "encryption.key"),
+    Self::EncryptionFailed => (INTERNAL_SERVER_ERROR, "encryption.failed"),
+    Self::Serialization(_) => (INTERNAL_SERVER_ERROR, "encryption.serialization"),
+});
+
+/// Result type for encryption operations
+pub type EncryptionResult<T> = Result<T, EncryptionError>;
@@ -0,0 +1,119 @@
+//! Application keys
+
+use crate::{EncryptionError, EncryptionResult};
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+/// Length of a generated key in bytes
+pub const KEY_LENGTH: usize = 32;
+
+/// Minimum length of an application key in bytes
+pub const MIN_KEY_LENGTH: usize = 16;
+
+/// Length of a key id in bytes
+const KEY_ID_LEN: usize = 8;
+
+/// Secret key material, e.g. from `APP_KEY`
+///
+/// Keys are either raw strings or `base64:` followed by base64 encoded
+/// bytes, as written by [`AppKey::generate`]. Encryption and signing use
+/// keys derived per purpose, never the material itself.
+#[derive(Clone)]
+pub struct AppKey {
+    material: Vec<u8>,
+}
+
+impl AppKey {
+    /// Parse a key
+    pub fn parse(key: &str) -> EncryptionResult<Self> {
+        let material = match key.strip_prefix("base64:") {
+            Some(encoded) => base64::engine::general_purpose::STANDARD
+                .decode(encoded.trim())
+                .map_err(|e| EncryptionError::InvalidKey(e.to_string()))?,
+            None => key.as_bytes().to_vec(),
+        };
+        if material.len() < MIN_KEY_LENGTH {
+            return Err(EncryptionError::InvalidKey(format!(
+                "keys must be at least {} bytes",
+                MIN_KEY_LENGTH
+            )));
+        }
+        Ok(Self { material })
+    }
+
+    /// A new random key, formatted for `APP_KEY`
+    pub fn generate() -> String {
+        let mut key = [0u8; KEY_LENGTH];
+        OsRng.fill_bytes(&mut key);
+        format!(
+            "base64:{}",
+            base64::engine::general_purpose::STANDARD.encode(key)
+        )
+    }
+
+    /// Current key from `APP_KEY` and previous ones from
+    /// `APP_PREVIOUS_KEYS` (comma separated)
+    pub fn from_env() -> EncryptionResult<(Self, Vec<Self>)> {
+        let key = std::env::var("APP_KEY").map_err(|_| EncryptionError::MissingKey)?;
+        let previous = std::env::var("APP_PREVIOUS_KEYS").unwrap_or_default();
+        let previous = previous
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(Self::parse)
+            .collect::<EncryptionResult<_>>()?;
+
+        Ok((Self::parse(&key)?, previous))
+    }
+
+    /// Key for `purpose`, so one use of the key can't be turned into another
+    pub(crate) fn derive(&self, purpose: &str) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"rustforge:");
+        hasher.update(purpose.as_bytes());
+        hasher.update(b":");
+        hasher.update(&self.material);
+        hasher.finalize().into()
+    }
+}
+
+impl std::fmt::Debug for AppKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppKey").finish_non_exhaustive()
+    }
+}
+
+/// Short public id of a derived key, stored with payloads to pick the key
+/// for decryption
+pub(crate) fn key_id(derived: &[u8]) -> String {
+    hex::encode(&Sha256::digest(derived)[..KEY_ID_LEN])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let generated = AppKey::generate();
+        assert!(generated.starts_with("base64:"));
+        assert_eq!(
+            AppKey::parse(&generated).unwrap().material.len(),
+            KEY_LENGTH
+        );
+
+        assert!(AppKey::parse("a-raw-key-of-sufficient-length").is_ok());
+        assert!(AppKey::parse("short").is_err());
+        assert!(AppKey::parse("base64:not base64!").is_err());
+        assert_ne!(AppKey::generate(), AppKey::generate());
+    }
+
+    #[test]
+    fn test_derive_separates_purposes() {
+        let key = AppKey::parse("a-raw-key-of-sufficient-length").unwrap();
+        assert_ne!(key.derive("encryption"), key.derive("signing"));
+        assert_eq!(key.derive("signing"), key.derive("signing"));
+        assert_eq!(key_id(&key.derive("signing")).len(), KEY_ID_LEN * 2);
+    }
+}
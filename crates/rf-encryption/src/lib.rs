@@ -0,0 +1,63 @@
+//! Application-layer encryption and signing for RustForge
+//!
+//! - **Encryption**: [`Encrypter`] seals strings and serde values with
+//!   AES-256-GCM; payloads carry a key id, so rotating `APP_KEY` keeps old
+//!   payloads readable via `APP_PREVIOUS_KEYS`
+//! - **Signing**: [`Signer`] signs values and cookies with HMAC-SHA256 and
+//!   creates tamper-proof, expiring links with [`Signer::signed_route`]
+//! - **Comparison**: [`constant_time_eq`] for tokens and signatures
+//! - **Signed routes**: `ValidSignature` and `SignatureLayer` reject
+//!   tampered and expired links (feature `axum`)
+//!
+//! [`Crypt`] bundles both, keyed from the environment.
+//!
+//! # Signed links
+//!
+//! Links handed out by mail or for downloads carry everything they grant
+//! in path and query, and are checked when followed:
+//!
+//! - Password resets: sign `/password/reset?token=..` with the token of
+//!   rf-auth's `TokenBroker`, so guessed or edited links are turned away
+//!   before the token is looked up
+//! - Export downloads: sign `/exports/{id}` for a few minutes and serve
+//!   the files behind `SignatureLayer`
+//! - Unsubscribe links: sign `/unsubscribe/{user}` for weeks, so the link
+//!   in a newsletter works without logging in
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rf_encryption::Crypt;
+//! use std::time::Duration;
+//!
+//! # fn main() -> Result<(), rf_encryption::EncryptionError> {
+//! let crypt = Crypt::from_env()?;
+//!
+//! let token = crypt.encrypt_string("api-token")?;
+//! assert_eq!(crypt.decrypt_string(&token)?, "api-token");
+//!
+//! let link = crypt.signed_route("https://example.com/invites/7", Duration::from_secs(86400));
+//! crypt.verify_route(&link)?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod compare;
+mod crypt;
+mod encrypter;
+mod error;
+mod key;
+mod signer;
+
+#[cfg(feature = "axum")]
+mod middleware;
+
+pub use compare::constant_time_eq;
+pub use crypt::Crypt;
+pub use encrypter::Encrypter;
+pub use error::{EncryptionError, EncryptionResult};
+pub use key::{AppKey, KEY_LENGTH, MIN_KEY_LENGTH};
+pub use signer::Signer;
+
+#[cfg(feature = "axum")]
+pub use middleware::{SignatureLayer, SignatureService, ValidSignature};
@@ -0,0 +1,245 @@
+//! Signed route checks for axum
+
+use crate::{Crypt, EncryptionError, Signer};
+use axum::{
+    extract::{FromRef, FromRequestParts, OriginalUri, Request},
+    http::{request::Parts, Extensions, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use rf_middleware::{Middleware, MiddlewareService, Next};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tower::Layer;
+
+impl IntoResponse for EncryptionError {
+    fn into_response(self) -> Response {
+        let (status, error, message) = match self {
+            EncryptionError::InvalidSignature(_) => (
+                StatusCode::FORBIDDEN,
+                "invalid_signature",
+                "The link is invalid",
+            ),
+            EncryptionError::Expired => (
+                StatusCode::FORBIDDEN,
+                "link_expired",
+                "The link has expired",
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "encryption_error",
+                "The request could not be processed",
+            ),
+        };
+        let body = serde_json::json!({ "error": error, "message": message });
+        (status, Json(body)).into_response()
+    }
+}
+
+/// Proof that the request URL carries a valid signature from
+/// [`Signer::signed_route`]
+///
+/// Needs the [`Signer`] (or a [`Crypt`]) in the router state. Tampered links are rejected
+/// with `403 Forbidden`, as are expired ones. The full request path is
+/// checked, including the prefix of routers mounted with `Router::nest`.
+///
+/// ```
+/// use axum::{extract::Path, routing::get, Router};
+/// use rf_encryption::{AppKey, Signer, ValidSignature};
+///
+/// async fn unsubscribe(_: ValidSignature, Path(user): Path<u64>) -> String {
+///     format!("User {} unsubscribed", user)
+/// }
+///
+/// let signer = Signer::new(AppKey::parse(&AppKey::generate()).unwrap(), Vec::new());
+/// let app: Router = Router::new()
+///     .route("/unsubscribe/{user}", get(unsubscribe))
+///     .with_state(signer);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ValidSignature {
+    expires: u64,
+}
+
+impl ValidSignature {
+    /// When the link expires
+    pub fn expires_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.expires)
+    }
+
+    /// Check the URL the client requested
+    ///
+    /// `Router::nest` strips its prefix from the request URI, but the URL
+    /// was signed with it; the router keeps the original as [`OriginalUri`].
+    fn verify(
+        signer: &Signer,
+        extensions: &Extensions,
+        uri: &Uri,
+    ) -> Result<Self, EncryptionError> {
+        let uri = extensions
+            .get::<OriginalUri>()
+            .map_or(uri, |original| &original.0);
+        let target = uri.path_and_query().map_or("/", |target| target.as_str());
+        let expires = signer.route_expiry(target)?;
+        Ok(Self { expires })
+    }
+}
+
+impl<S> FromRequestParts<S> for ValidSignature
+where
+    Signer: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = EncryptionError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(valid) = parts.extensions.get::<ValidSignature>() {
+            return Ok(*valid);
+        }
+        Self::verify(&Signer::from_ref(state), &parts.extensions, &parts.uri)
+    }
+}
+
+impl FromRef<Crypt> for Signer {
+    fn from_ref(crypt: &Crypt) -> Self {
+        crypt.signer().clone()
+    }
+}
+
+/// Layer accepting only requests with a valid signed URL, e.g. for a group
+/// of download routes
+///
+/// ```
+/// use axum::{routing::get, Router};
+/// use rf_encryption::{AppKey, SignatureLayer, Signer};
+///
+/// let signer = Signer::new(AppKey::parse(&AppKey::generate()).unwrap(), Vec::new());
+/// let downloads: Router = Router::new()
+///     .route("/exports/{id}", get(|| async { "report.csv" }))
+///     .route("/invoices/{id}", get(|| async { "invoice.pdf" }))
+///     .layer(SignatureLayer::new(signer));
+/// ```
+#[derive(Clone)]
+pub struct SignatureLayer {
+    signer: Signer,
+}
+
+impl SignatureLayer {
+    pub fn new(signer: Signer) -> Self {
+        Self { signer }
+    }
+}
+
+impl Middleware for SignatureLayer {
+    async fn handle(self, mut req: Request, next: Next) -> Response {
+        match ValidSignature::verify(&self.signer, req.extensions(), req.uri()) {
+            Ok(valid) => {
+                // Handlers taking `ValidSignature` don't check again
+                req.extensions_mut().insert(valid);
+                next.run(req).await
+            }
+            Err(e) => e.into_response(),
+        }
+    }
+}
+
+impl<S> Layer<S> for SignatureLayer {
+    type Service = SignatureService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+/// Service created by [`SignatureLayer`]
+pub type SignatureService<S> = MiddlewareService<SignatureLayer, S>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AppKey;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn signer() -> Signer {
+        Signer::new(
+            AppKey::parse("current-key-for-the-tests").unwrap(),
+            Vec::new(),
+        )
+    }
+
+    async fn status(app: &Router, uri: &str) -> StatusCode {
+        let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        app.clone().oneshot(req).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_extractor() {
+        let app = Router::new()
+            .route(
+                "/unsubscribe/{user}",
+                get(|valid: ValidSignature| async move {
+                    assert!(valid.expires_at() > SystemTime::now());
+                    "Bye"
+                }),
+            )
+            .with_state(signer());
+        let url = signer().signed_route(
+            "https://example.com/unsubscribe/42",
+            Duration::from_secs(60),
+        );
+        let uri = url.trim_start_matches("https://example.com");
+
+        assert_eq!(status(&app, uri).await, StatusCode::OK);
+        assert_eq!(status(&app, "/unsubscribe/42").await, StatusCode::FORBIDDEN);
+        let other = uri.replace("/42?", "/43?");
+        assert_eq!(status(&app, &other).await, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_layer() {
+        let app: Router = Router::new()
+            .route("/exports/{id}", get(|| async { "report.csv" }))
+            .layer(SignatureLayer::new(signer()));
+        let uri = signer().signed_route("/exports/7?format=csv", Duration::from_secs(60));
+
+        assert_eq!(status(&app, &uri).await, StatusCode::OK);
+        let tampered = uri.replace("format=csv", "format=xlsx");
+        assert_eq!(status(&app, &tampered).await, StatusCode::FORBIDDEN);
+
+        let expired = "/exports/7?expires=1";
+        let expired = format!("{}&signature={}", expired, signer().sign(expired));
+        let req = Request::builder().uri(expired).body(Body::empty()).unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("link_expired"));
+    }
+
+    #[tokio::test]
+    async fn test_nested_routers_check_the_full_path() {
+        let downloads = Router::new()
+            .route("/exports/{id}", get(|| async { "report.csv" }))
+            .layer(SignatureLayer::new(signer()));
+        let links = Router::new()
+            .route(
+                "/unsubscribe/{user}",
+                get(|_: ValidSignature| async { "Bye" }),
+            )
+            .with_state(signer());
+        let app: Router = Router::new()
+            .nest("/downloads", downloads)
+            .nest("/links", links);
+
+        let uri = signer().signed_route("/downloads/exports/7", Duration::from_secs(60));
+        assert_eq!(status(&app, &uri).await, StatusCode::OK);
+        let uri = signer().signed_route("/links/unsubscribe/42", Duration::from_secs(60));
+        assert_eq!(status(&app, &uri).await, StatusCode::OK);
+
+        // Signed without the prefix, the link is for a different URL
+        let uri = signer().signed_route("/exports/7", Duration::from_secs(60));
+        let uri = format!("/downloads{}", uri);
+        assert_eq!(status(&app, &uri).await, StatusCode::FORBIDDEN);
+    }
+}
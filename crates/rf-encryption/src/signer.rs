@@ -0,0 +1,231 @@
+//! HMAC signing of values, cookies and URLs
+
+use crate::key::AppKey;
+use crate::{EncryptionError, EncryptionResult};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signs values with HMAC-SHA256 so clients can read but not forge them
+///
+/// Signatures made with a previous key still verify after rotating
+/// `APP_KEY`; new ones always use the current key.
+///
+/// ```
+/// use rf_encryption::{AppKey, Signer};
+/// use std::time::Duration;
+///
+/// let signer = Signer::new(AppKey::parse(&AppKey::generate()).unwrap(), Vec::new());
+/// let url = signer.signed_route(
+///     "https://example.com/unsubscribe?user=42",
+///     Duration::from_secs(3600),
+/// );
+///
+/// assert!(url.contains("&expires="));
+/// assert!(signer.verify_route(&url).is_ok());
+/// assert!(signer.verify_route(&url.replace("user=42", "user=43")).is_err());
+/// ```
+#[derive(Clone)]
+pub struct Signer {
+    keys: Vec<[u8; 32]>,
+}
+
+impl Signer {
+    /// Signer with a current key and keys used before a rotation
+    pub fn new(key: AppKey, previous: Vec<AppKey>) -> Self {
+        Self {
+            keys: std::iter::once(&key)
+                .chain(&previous)
+                .map(|key| key.derive("signing"))
+                .collect(),
+        }
+    }
+
+    /// Signer keyed from `APP_KEY` and `APP_PREVIOUS_KEYS`
+    pub fn from_env() -> EncryptionResult<Self> {
+        let (key, previous) = AppKey::from_env()?;
+        Ok(Self::new(key, previous))
+    }
+
+    /// Hex encoded signature of `value`
+    pub fn sign(&self, value: &str) -> String {
+        hex::encode(self.mac(&self.keys[0], value).finalize().into_bytes())
+    }
+
+    /// Check a signature from [`Signer::sign`]
+    pub fn verify(&self, value: &str, signature: &str) -> EncryptionResult<()> {
+        let signature = hex::decode(signature)
+            .map_err(|_| EncryptionError::InvalidSignature("malformed signature".into()))?;
+        // Try every key, so the time taken doesn't reveal which one matched
+        let valid = self.keys.iter().fold(false, |valid, key| {
+            self.mac(key, value).verify_slice(&signature).is_ok() | valid
+        });
+        if valid {
+            Ok(())
+        } else {
+            Err(EncryptionError::InvalidSignature(
+                "signature mismatch".into(),
+            ))
+        }
+    }
+
+    /// Cookie value `value.signature`; the signature covers the cookie
+    /// name, so a value can't be moved to another cookie
+    pub fn sign_cookie(&self, name: &str, value: &str) -> String {
+        format!("{}.{}", value, self.sign(&cookie_input(name, value)))
+    }
+
+    /// The value of a cookie from [`Signer::sign_cookie`]
+    pub fn verify_cookie(&self, name: &str, cookie: &str) -> EncryptionResult<String> {
+        let (value, signature) = cookie
+            .rsplit_once('.')
+            .ok_or_else(|| EncryptionError::InvalidSignature("missing signature".into()))?;
+        self.verify(&cookie_input(name, value), signature)?;
+        Ok(value.to_string())
+    }
+
+    /// `url` with `expires` and `signature` query parameters, valid for
+    /// `expires_in`
+    ///
+    /// The signature covers path and query but not scheme and host, so
+    /// links stay valid behind proxies. Any change to the path, the query
+    /// or the expiry invalidates the link.
+    pub fn signed_route(&self, url: &str, expires_in: Duration) -> String {
+        let expires = now() + expires_in.as_secs();
+        let separator = if url.contains('?') { '&' } else { '?' };
+        let url = format!("{}{}expires={}", url, separator, expires);
+        let signature = self.sign(target(&url));
+        format!("{}&signature={}", url, signature)
+    }
+
+    /// Check a link from [`Signer::signed_route`]
+    ///
+    /// Takes the full URL or just path and query, e.g. the URI of a request.
+    pub fn verify_route(&self, url: &str) -> EncryptionResult<()> {
+        self.route_expiry(url).map(|_| ())
+    }
+
+    /// Check a signed link, returning its expiry as Unix timestamp
+    pub(crate) fn route_expiry(&self, url: &str) -> EncryptionResult<u64> {
+        let (signed, signature) = target(url)
+            .rsplit_once("&signature=")
+            .ok_or_else(|| EncryptionError::InvalidSignature("missing signature".into()))?;
+        self.verify(signed, signature)?;
+
+        let expires: u64 = signed
+            .rsplit_once("expires=")
+            .and_then(|(_, expires)| expires.parse().ok())
+            .ok_or_else(|| EncryptionError::InvalidSignature("missing expiry".into()))?;
+        if expires < now() {
+            return Err(EncryptionError::Expired);
+        }
+        Ok(expires)
+    }
+
+    fn mac(&self, key: &[u8], value: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(value.as_bytes());
+        mac
+    }
+}
+
+fn cookie_input(name: &str, value: &str) -> String {
+    format!("cookie:{}\n{}", name, value)
+}
+
+/// Path and query of a URL
+fn target(url: &str) -> &str {
+    match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |i| &rest[i..]),
+        None => url,
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer(key: &str) -> Signer {
+        Signer::new(AppKey::parse(key).unwrap(), Vec::new())
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = signer("current-key-for-the-tests");
+        let signature = signer.sign("user:42");
+
+        assert!(signer.verify("user:42", &signature).is_ok());
+        assert!(signer.verify("user:43", &signature).is_err());
+        assert!(signer.verify("user:42", "zz").is_err());
+        assert!(self::signer("another-key-for-the-tests")
+            .verify("user:42", &signature)
+            .is_err());
+    }
+
+    #[test]
+    fn test_previous_keys_verify() {
+        let old = signer("the-old-application-key");
+        let signature = old.sign("value");
+
+        let rotated = Signer::new(
+            AppKey::parse("the-new-application-key").unwrap(),
+            vec![AppKey::parse("the-old-application-key").unwrap()],
+        );
+        assert!(rotated.verify("value", &signature).is_ok());
+        assert_ne!(rotated.sign("value"), signature);
+    }
+
+    #[test]
+    fn test_cookies() {
+        let signer = signer("current-key-for-the-tests");
+        let cookie = signer.sign_cookie("remember", "user.42");
+
+        assert_eq!(
+            signer.verify_cookie("remember", &cookie).unwrap(),
+            "user.42"
+        );
+        assert!(signer.verify_cookie("session", &cookie).is_err());
+        assert!(signer
+            .verify_cookie("remember", &cookie.replacen("42", "43", 1))
+            .is_err());
+        assert!(signer.verify_cookie("remember", "unsigned").is_err());
+    }
+
+    #[test]
+    fn test_signed_route() {
+        let signer = signer("current-key-for-the-tests");
+        let url = signer.signed_route("https://example.com/downloads/1", Duration::from_secs(60));
+        assert!(url.starts_with("https://example.com/downloads/1?expires="));
+
+        // Verifies as full URL and as request URI
+        assert!(signer.verify_route(&url).is_ok());
+        assert!(signer.verify_route(target(&url)).is_ok());
+
+        assert!(signer.verify_route(&url.replace("/1?", "/2?")).is_err());
+        assert!(signer.verify_route(&format!("{}&admin=1", url)).is_err());
+        assert!(signer
+            .verify_route("https://example.com/downloads/1?expires=9999999999")
+            .is_err());
+    }
+
+    #[test]
+    fn test_expired_route() {
+        let signer = signer("current-key-for-the-tests");
+        let url = format!("/downloads/1?expires={}", now() - 1);
+        let url = format!("{}&signature={}", url, signer.sign(&url));
+
+        assert!(matches!(
+            signer.verify_route(&url),
+            Err(EncryptionError::Expired)
+        ));
+    }
+}
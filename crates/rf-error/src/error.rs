@@ -0,0 +1,269 @@
+//! The framework error trait and the error type of handlers
+
+use crate::Problem;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Message of server errors, whose details stay in the logs
+pub const INTERNAL_ERROR_MESSAGE: &str = "An internal error occurred";
+
+/// An error of the framework or application with a consistent HTTP
+/// representation
+///
+/// Only [`code`](Self::code) is required; errors are server errors unless
+/// [`status`](Self::status) says otherwise.
+///
+/// ```
+/// use axum::http::StatusCode;
+/// use rf_error::FrameworkError;
+///
+/// #[derive(Debug, thiserror::Error)]
+/// enum OrderError {
+///     #[error("Order {0} not found")]
+///     NotFound(u64),
+///     #[error("Payment provider failed: {0}")]
+///     Payment(String),
+/// }
+///
+/// impl FrameworkError for OrderError {
+///     fn status(&self) -> StatusCode {
+///         match self {
+///             OrderError::NotFound(_) => StatusCode::NOT_FOUND,
+///             OrderError::Payment(_) => StatusCode::BAD_GATEWAY,
+///         }
+///     }
+///
+///     fn code(&self) -> &'static str {
+///         match self {
+///             OrderError::NotFound(_) => "order.not_found",
+///             OrderError::Payment(_) => "order.payment_failed",
+///         }
+///     }
+/// }
+///
+/// let problem = OrderError::Payment("card declined".into()).to_problem();
+/// assert_eq!(problem.status, 502);
+/// assert_eq!(problem.detail.as_deref(), Some("An internal error occurred"));
+/// assert_eq!(problem.extensions["code"], "order.payment_failed");
+/// ```
+pub trait FrameworkError: std::error::Error + Send + Sync + 'static {
+    /// HTTP status of the response
+    fn status(&self) -> StatusCode {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+
+    /// Stable, machine-readable code, e.g. `auth.token_expired`
+    fn code(&self) -> &'static str;
+
+    /// Message safe to show to clients
+    ///
+    /// The error message for client errors, a generic message for server
+    /// errors.
+    fn public_message(&self) -> String {
+        if self.status().is_server_error() {
+            INTERNAL_ERROR_MESSAGE.to_string()
+        } else {
+            self.to_string()
+        }
+    }
+
+    /// Extra members of the problem details, e.g. the invalid fields
+    fn metadata(&self) -> Map<String, Value> {
+        Map::new()
+    }
+
+    /// Problem details with the status, public message, code and metadata
+    fn to_problem(&self) -> Problem {
+        let mut problem = Problem::new(self.status())
+            .detail(self.public_message())
+            .extension("code", self.code());
+        problem.extensions.extend(self.metadata());
+        problem
+    }
+}
+
+impl FrameworkError for Problem {
+    fn status(&self) -> StatusCode {
+        self.status_code()
+    }
+
+    fn code(&self) -> &'static str {
+        "problem"
+    }
+
+    fn to_problem(&self) -> Problem {
+        self.clone()
+    }
+}
+
+/// Any [`FrameworkError`], rendered as problem details
+///
+/// Handlers returning [`AppResult`] can use `?` on the errors of every
+/// framework crate. Server errors are logged with their full message
+/// before their details are hidden from the client.
+///
+/// ```
+/// use axum::{extract::Path, Json};
+/// use rf_error::{AppResult, Problem};
+///
+/// async fn show_user(Path(id): Path<u64>) -> AppResult<Json<String>> {
+///     if id == 0 {
+///         return Err(Problem::not_found("User 0 doesn't exist").into());
+///     }
+///     Ok(Json(format!("User {}", id)))
+/// }
+/// ```
+pub struct AppError {
+    inner: Box<dyn FrameworkError>,
+}
+
+impl AppError {
+    pub fn new(error: impl FrameworkError) -> Self {
+        Self {
+            inner: Box::new(error),
+        }
+    }
+
+    pub fn inner(&self) -> &dyn FrameworkError {
+        &*self.inner
+    }
+
+    /// The wrapped error, if it is an `E`
+    pub fn downcast_ref<E: FrameworkError>(&self) -> Option<&E> {
+        let inner: &(dyn std::error::Error + 'static) = &*self.inner;
+        inner.downcast_ref()
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.inner.status()
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.inner.code()
+    }
+
+    pub fn to_problem(&self) -> Problem {
+        self.inner.to_problem()
+    }
+}
+
+impl<E: FrameworkError> From<E> for AppError {
+    fn from(error: E) -> Self {
+        Self::new(error)
+    }
+}
+
+impl fmt::Debug for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.inner, f)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.inner.source()
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        if self.status().is_server_error() {
+            tracing::error!(code = self.code(), error = %self.inner, "Request failed");
+        }
+        self.to_problem().into_response()
+    }
+}
+
+/// Result of handlers
+pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, thiserror::Error)]
+    enum ShopError {
+        #[error("Only {0} items left")]
+        OutOfStock(u32),
+        #[error("Database password is hunter2")]
+        Database,
+    }
+
+    impl FrameworkError for ShopError {
+        fn status(&self) -> StatusCode {
+            match self {
+                ShopError::OutOfStock(_) => StatusCode::CONFLICT,
+                ShopError::Database => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        }
+
+        fn code(&self) -> &'static str {
+            match self {
+                ShopError::OutOfStock(_) => "shop.out_of_stock",
+                ShopError::Database => "shop.database",
+            }
+        }
+
+        fn metadata(&self) -> Map<String, Value> {
+            match self {
+                ShopError::OutOfStock(left) => json!({ "available": left })
+                    .as_object()
+                    .cloned()
+                    .unwrap_or_default(),
+                ShopError::Database => Map::new(),
+            }
+        }
+    }
+
+    async fn body(error: AppError) -> (StatusCode, Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_problem_response() {
+        let (status, json) = body(ShopError::OutOfStock(2).into()).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(
+            json,
+            json!({
+                "type": "about:blank",
+                "title": "Conflict",
+                "status": 409,
+                "detail": "Only 2 items left",
+                "code": "shop.out_of_stock",
+                "available": 2
+            })
+        );
+
+        let (status, json) = body(ShopError::Database.into()).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(json["detail"], INTERNAL_ERROR_MESSAGE);
+        assert_eq!(json["code"], "shop.database");
+    }
+
+    #[tokio::test]
+    async fn test_problem_passes_through() {
+        let problem = Problem::new(StatusCode::GONE).detail("Moved away");
+        let error = AppError::from(problem.clone());
+        assert!(error.downcast_ref::<Problem>().is_some());
+        assert!(error.downcast_ref::<ShopError>().is_none());
+
+        let (status, json) = body(error).await;
+        assert_eq!(status, StatusCode::GONE);
+        assert_eq!(serde_json::from_value::<Problem>(json).unwrap(), problem);
+    }
+}
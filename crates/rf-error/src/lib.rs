@@ -0,0 +1,66 @@
+//! Structured errors and problem details responses for RustForge
+//!
+//! Every framework error implements [`FrameworkError`], giving it an HTTP
+//! status, a stable code, a message safe to show to clients and extra
+//! metadata. Handlers return [`AppResult`], use `?` on any of them, and
+//! respond with RFC 7807 `application/problem+json`:
+//!
+//! ```json
+//! {
+//!   "type": "about:blank",
+//!   "title": "Unauthorized",
+//!   "status": 401,
+//!   "detail": "Token expired",
+//!   "code": "auth.token_expired"
+//! }
+//! ```
+//!
+//! Server errors are logged and answered with a generic message, so
+//! connection strings or SQL never reach the client.
+//!
+//! # Framework errors
+//!
+//! Framework crates implement [`FrameworkError`] for their errors behind
+//! their own `rf-error` feature, e.g. `rf-auth/rf-error` for rf-auth's
+//! `AuthError`, mostly with [`framework_error!`]. Errors that never reach
+//! an HTTP response aren't covered: those of tooling crates like
+//! rf-console or rf-cli-gen, and rf-graphql's, which become GraphQL errors.
+//!
+//! # Example
+//!
+//! ```
+//! use axum::{extract::Path, routing::get, Json, Router};
+//! use rf_error::{AppResult, FrameworkError, Problem};
+//! use axum::http::StatusCode;
+//!
+//! #[derive(Debug, thiserror::Error)]
+//! #[error("Invoice {0} is already paid")]
+//! struct AlreadyPaid(u64);
+//!
+//! impl FrameworkError for AlreadyPaid {
+//!     fn status(&self) -> StatusCode {
+//!         StatusCode::CONFLICT
+//!     }
+//!
+//!     fn code(&self) -> &'static str {
+//!         "billing.already_paid"
+//!     }
+//! }
+//!
+//! async fn pay(Path(id): Path<u64>) -> AppResult<Json<u64>> {
+//!     if id == 0 {
+//!         return Err(Problem::not_found("No such invoice").into());
+//!     }
+//!     Err(AlreadyPaid(id))?
+//! }
+//!
+//! let app: Router = Router::new().route("/invoices/{id}/pay", get(pay));
+//! ```
+
+mod error;
+mod macros;
+mod problem;
+
+pub use axum::http::StatusCode;
+pub use error::{AppError, AppResult, FrameworkError, INTERNAL_ERROR_MESSAGE};
+pub use problem::{Problem, PROBLEM_JSON};
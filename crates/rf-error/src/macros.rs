@@ -0,0 +1,79 @@
+//! Implementing [`FrameworkError`](crate::FrameworkError) for error enums
+
+/// Implement [`FrameworkError`](crate::FrameworkError) with the status and
+/// code of each variant
+///
+/// Framework crates use it behind their `rf-error` feature:
+///
+/// ```
+/// #[derive(Debug, thiserror::Error)]
+/// pub enum InvoiceError {
+///     #[error("Invoice {0} not found")]
+///     NotFound(u64),
+///     #[error("Database error: {0}")]
+///     Database(String),
+/// }
+///
+/// rf_error::framework_error!(InvoiceError, {
+///     Self::NotFound(_) => (NOT_FOUND, "invoice.not_found"),
+///     Self::Database(_) => (INTERNAL_SERVER_ERROR, "invoice.database"),
+/// });
+///
+/// use rf_error::FrameworkError;
+/// assert_eq!(InvoiceError::NotFound(7).code(), "invoice.not_found");
+/// ```
+#[macro_export]
+macro_rules! framework_error {
+    (
+        $error:ty,
+        { $($pattern:pat => ($status:ident, $code:literal)),+ $(,)? }
+    ) => {
+        // Variants behind features of the crate may be missing
+        #[allow(unreachable_patterns)]
+        impl $crate::FrameworkError for $error {
+            fn status(&self) -> $crate::StatusCode {
+                match self {
+                    $($pattern => $crate::StatusCode::$status,)+
+                }
+            }
+
+            fn code(&self) -> &'static str {
+                match self {
+                    $($pattern => $code,)+
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FrameworkError, StatusCode};
+
+    #[derive(Debug, thiserror::Error)]
+    enum ImportError {
+        #[error("Row {0} is invalid")]
+        InvalidRow(usize),
+        #[error("Disk full")]
+        Io,
+    }
+
+    crate::framework_error!(ImportError, {
+        Self::InvalidRow(_) => (UNPROCESSABLE_ENTITY, "import.invalid_row"),
+        Self::Io => (INTERNAL_SERVER_ERROR, "import.io"),
+    });
+
+    #[test]
+    fn test_framework_error_macro() {
+        let problem = ImportError::InvalidRow(3).to_problem();
+        assert_eq!(problem.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(problem.detail.as_deref(), Some("Row 3 is invalid"));
+        assert_eq!(problem.extensions["code"], "import.invalid_row");
+
+        assert_eq!(ImportError::Io.code(), "import.io");
+        assert_eq!(
+            ImportError::Io.public_message(),
+            crate::INTERNAL_ERROR_MESSAGE
+        );
+    }
+}
@@ -0,0 +1,166 @@
+//! RFC 7807 problem details
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// Content type of problem details
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Problem details of a failed request (RFC 7807)
+///
+/// Handlers return it as error; it renders as `application/problem+json`.
+/// [`FrameworkError`](crate::FrameworkError)s turn into one with
+/// [`to_problem`](crate::FrameworkError::to_problem).
+///
+/// ```
+/// use axum::http::StatusCode;
+/// use rf_error::Problem;
+///
+/// async fn show_order() -> Result<String, Problem> {
+///     Err(Problem::new(StatusCode::CONFLICT)
+///         .type_uri("https://example.com/problems/out-of-stock")
+///         .title("Out of stock")
+///         .detail("Only 2 items left")
+///         .extension("available", 2))
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Error, Serialize, Deserialize)]
+#[error("{title}")]
+pub struct Problem {
+    /// URI identifying the problem type (default: `about:blank`)
+    #[serde(rename = "type", default = "about_blank")]
+    pub type_uri: String,
+
+    pub title: String,
+
+    pub status: u16,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+
+    /// URI of this occurrence, e.g. the request path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+
+    /// Extension members, e.g. field errors
+    #[serde(flatten)]
+    pub extensions: Box<Map<String, Value>>,
+}
+
+fn about_blank() -> String {
+    "about:blank".to_string()
+}
+
+impl Problem {
+    /// Create a problem titled after `status`
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            type_uri: about_blank(),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail: None,
+            instance: None,
+            extensions: Box::default(),
+        }
+    }
+
+    pub fn bad_request(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST).detail(detail)
+    }
+
+    pub fn not_found(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND).detail(detail)
+    }
+
+    pub fn internal() -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Set the problem type URI
+    pub fn type_uri(mut self, type_uri: impl Into<String>) -> Self {
+        self.type_uri = type_uri.into();
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    pub fn instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Add an extension member
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` doesn't serialize to JSON.
+    pub fn extension(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).expect("problem extension must serialize");
+        self.extensions.insert(name.into(), value);
+        self
+    }
+
+    /// HTTP status; 500 if `status` is invalid
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let mut response = (self.status_code(), Json(&self)).into_response();
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_response() {
+        let problem = Problem::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .detail("Email is taken")
+            .instance("/users")
+            .extension("errors", json!({"email": ["taken"]}));
+
+        let response = problem.clone().into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "type": "about:blank",
+                "title": "Unprocessable Entity",
+                "status": 422,
+                "detail": "Email is taken",
+                "instance": "/users",
+                "errors": {"email": ["taken"]}
+            })
+        );
+        assert_eq!(serde_json::from_value::<Problem>(json).unwrap(), problem);
+    }
+}
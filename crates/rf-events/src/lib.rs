@@ -1,6 +1,66 @@
 //! Event System for RustForge
 //!
 //! This crate provides event dispatching and listener management.
+//!
+//! - **Typed listeners**: [`EventListenerFor`] run in process, by priority
+//! - **Wildcard listeners**: [`WildcardListener`] receive every event whose
+//!   name matches a pattern, e.g. for logging with [`EventLogger`]
+//! - **Queued listeners**: run in the background by an rf-queue worker
+//!   (`queue` feature)
+//! - **Event store**: persist events as [`StoredEvent`]s for auditing and
+//!   event sourcing
+//! - **Model events**: [`ModelCreated`], [`ModelUpdated`] and
+//!   [`ModelDeleted`] for reacting to model changes, e.g. search indexing,
+//!   and [`ModelCreating`], [`ModelUpdating`] and [`ModelDeleting`] before
+//!   them, as dispatched by rf-orm-lite
+//!
+//! ```
+//! use async_trait::async_trait;
+//! use rf_events::{
+//!     Event, EventDispatcher, EventListenerFor, EventLogger, EventResult, EventStore,
+//!     MemoryEventStore,
+//! };
+//! use serde::{Deserialize, Serialize};
+//! use std::sync::Arc;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct UserRegistered {
+//!     user_id: u64,
+//! }
+//!
+//! impl Event for UserRegistered {
+//!     fn name(&self) -> &'static str {
+//!         "user.registered"
+//!     }
+//!
+//!     fn aggregate_id(&self) -> Option<String> {
+//!         Some(format!("user-{}", self.user_id))
+//!     }
+//! }
+//!
+//! struct SendWelcomeEmail;
+//!
+//! #[async_trait]
+//! impl EventListenerFor<UserRegistered> for SendWelcomeEmail {
+//!     async fn handle(&self, event: &UserRegistered) -> EventResult<()> {
+//!         println!("Welcome, user {}", event.user_id);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! # async fn example() -> EventResult<()> {
+//! let store = Arc::new(MemoryEventStore::new());
+//! let dispatcher = EventDispatcher::new().store(store.clone());
+//!
+//! dispatcher.listen(SendWelcomeEmail).await;
+//! dispatcher.listen_any("user.*", EventLogger).await;
+//! dispatcher.persist::<UserRegistered>().await;
+//!
+//! dispatcher.dispatch(UserRegistered { user_id: 1 }).await?;
+//! assert_eq!(store.load("user-1").await?.len(), 1);
+//! # Ok(())
+//! # }
+//! ```
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -12,6 +72,20 @@ use std::{
 use thiserror::Error;
 use tokio::sync::RwLock;
 
+mod model;
+#[cfg(feature = "queue")]
+mod queued;
+mod store;
+mod wildcard;
+
+pub use model::{
+    ModelCreated, ModelCreating, ModelDeleted, ModelDeleting, ModelUpdated, ModelUpdating,
+};
+#[cfg(feature = "queue")]
+pub use queued::{EventWorkerExt, ListenerJob};
+pub use store::{EventStore, MemoryEventStore, StoredEvent};
+pub use wildcard::{EventLogger, WildcardListener};
+
 /// Event errors
 #[derive(Debug, Error)]
 pub enum EventError {
@@ -20,16 +94,37 @@ pub enum EventError {
 
     #[error("Dispatch error: {0}")]
     DispatchError(String),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(String),
+
+    #[error("Event store error: {0}")]
+    StoreError(String),
 }
 
 pub type EventResult<T> = Result<T, EventError>;
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(EventError, {
+    Self::ListenerError(_) => (INTERNAL_SERVER_ERROR, "events.listener"),
+    Self::DispatchError(_) => (INTERNAL_SERVER_ERROR, "events.dispatch"),
+    Self::SerializationError(_) => (INTERNAL_SERVER_ERROR, "events.serialization"),
+    Self::StoreError(_) => (INTERNAL_SERVER_ERROR, "events.store"),
+});
+
 /// Event trait that all events must implement
 pub trait Event: Send + Sync + 'static {
     /// Get the event name
     fn name(&self) -> &'static str {
         std::any::type_name::<Self>()
     }
+
+    /// Aggregate the event belongs to, e.g. the id of the changed model
+    ///
+    /// Events of an aggregate are versioned together in the [`EventStore`].
+    fn aggregate_id(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Event listener trait
@@ -86,9 +181,30 @@ pub trait EventListenerFor<E: Event>: Send + Sync + 'static {
     }
 }
 
+type EventSerializer = fn(&(dyn Any + Send + Sync)) -> Option<EventResult<StoredEvent>>;
+
+fn serialize_event<E: Event + Serialize>(
+    event: &(dyn Any + Send + Sync),
+) -> Option<EventResult<StoredEvent>> {
+    event.downcast_ref::<E>().map(StoredEvent::new)
+}
+
 /// Event dispatcher
+///
+/// Dispatching an event appends it to the event store if its type is
+/// persisted, then runs the matching wildcard listeners and finally the
+/// listeners of its type. The first error stops the dispatch.
 pub struct EventDispatcher {
     listeners: Arc<RwLock<HashMap<TypeId, Vec<Box<dyn EventListener>>>>>,
+    wildcards: Arc<RwLock<Vec<(String, Box<dyn WildcardListener>)>>>,
+    persisted: Arc<RwLock<HashMap<TypeId, EventSerializer>>>,
+    store: Option<Arc<dyn EventStore>>,
+    #[cfg(feature = "queue")]
+    queue: Option<Arc<dyn rf_queue::Queue>>,
+    #[cfg(feature = "queue")]
+    queue_name: String,
+    #[cfg(feature = "queue")]
+    queued: Arc<RwLock<HashMap<String, Box<dyn queued::QueuedHandler>>>>,
 }
 
 impl EventDispatcher {
@@ -96,32 +212,159 @@ impl EventDispatcher {
     pub fn new() -> Self {
         Self {
             listeners: Arc::new(RwLock::new(HashMap::new())),
+            wildcards: Arc::new(RwLock::new(Vec::new())),
+            persisted: Arc::new(RwLock::new(HashMap::new())),
+            store: None,
+            #[cfg(feature = "queue")]
+            queue: None,
+            #[cfg(feature = "queue")]
+            queue_name: "default".to_string(),
+            #[cfg(feature = "queue")]
+            queued: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Store events of the types registered with [`persist`](Self::persist)
+    pub fn store(mut self, store: Arc<dyn EventStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Push queued listeners onto `queue`
+    #[cfg(feature = "queue")]
+    pub fn queue(mut self, queue: Arc<dyn rf_queue::Queue>) -> Self {
+        self.queue = Some(queue);
+        self
+    }
+
+    /// Queue name for queued listeners (default: "default")
+    #[cfg(feature = "queue")]
+    pub fn queue_name(mut self, name: impl Into<String>) -> Self {
+        self.queue_name = name.into();
+        self
+    }
+
     /// Register an event listener
     pub async fn listen<E: Event, L: EventListenerFor<E>>(&self, listener: L) {
-        let mut listeners = self.listeners.write().await;
-        let type_id = TypeId::of::<E>();
-
         let boxed: Box<dyn EventListener> = Box::new(TypedListener::new(listener));
+        self.add_listener(TypeId::of::<E>(), boxed).await;
+    }
 
-        listeners
-            .entry(type_id)
-            .or_insert_with(Vec::new)
-            .push(boxed);
+    /// Register a listener run in the background by an rf-queue worker
+    ///
+    /// Dispatching the event pushes a [`ListenerJob`] carrying the
+    /// serialized event. Workers run it once set up with
+    /// [`EventWorkerExt::event_listeners`] on a dispatcher with the same
+    /// queued listeners.
+    ///
+    /// ```no_run
+    /// # use async_trait::async_trait;
+    /// # use rf_events::{Event, EventListenerFor, EventResult};
+    /// # use serde::{Deserialize, Serialize};
+    /// # #[derive(Serialize, Deserialize)]
+    /// # struct OrderShipped;
+    /// # impl Event for OrderShipped {}
+    /// # struct NotifyCustomer;
+    /// # #[async_trait]
+    /// # impl EventListenerFor<OrderShipped> for NotifyCustomer {
+    /// #     async fn handle(&self, _event: &OrderShipped) -> EventResult<()> { Ok(()) }
+    /// # }
+    /// use rf_events::{EventDispatcher, EventWorkerExt};
+    /// use rf_queue::{MemoryQueue, Queue, Worker};
+    /// use std::sync::Arc;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let queue: Arc<dyn Queue> = Arc::new(MemoryQueue::new());
+    /// let dispatcher = Arc::new(EventDispatcher::new().queue(queue.clone()));
+    /// dispatcher.listen_queued(NotifyCustomer).await?;
+    ///
+    /// dispatcher.dispatch(OrderShipped).await?;
+    ///
+    /// Worker::new(queue).event_listeners(dispatcher).start().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "queue")]
+    pub async fn listen_queued<E, L>(&self, listener: L) -> EventResult<()>
+    where
+        E: Event + Serialize + serde::de::DeserializeOwned,
+        L: EventListenerFor<E>,
+    {
+        let queue = self.queue.clone().ok_or_else(|| {
+            EventError::DispatchError("No queue configured for queued listeners".to_string())
+        })?;
+
+        let boxed: Box<dyn EventListener> = Box::new(queued::QueuedListener::<E, L>::new(
+            queue,
+            self.queue_name.clone(),
+            listener.priority(),
+        ));
+        self.add_listener(TypeId::of::<E>(), boxed).await;
+
+        let handler = queued::TypedQueuedHandler::<E, L>::new(listener);
+        self.queued
+            .write()
+            .await
+            .insert(std::any::type_name::<L>().to_string(), Box::new(handler));
+        Ok(())
+    }
+
+    /// Run the queued listener of a [`ListenerJob`]
+    #[cfg(feature = "queue")]
+    pub async fn handle_queued(&self, job: &ListenerJob) -> EventResult<()> {
+        let queued = self.queued.read().await;
+        let handler = queued.get(&job.listener).ok_or_else(|| {
+            EventError::DispatchError(format!("Unknown queued listener: {}", job.listener))
+        })?;
+        handler.handle(job.payload.clone()).await
+    }
+
+    /// Register a listener for every event whose name matches `pattern`,
+    /// where `*` stands for any text
+    pub async fn listen_any<L: WildcardListener>(&self, pattern: &str, listener: L) {
+        self.wildcards
+            .write()
+            .await
+            .push((pattern.to_string(), Box::new(listener)));
+    }
+
+    /// Append events of type `E` to the event store when dispatched
+    pub async fn persist<E: Event + Serialize>(&self) {
+        self.persisted
+            .write()
+            .await
+            .insert(TypeId::of::<E>(), serialize_event::<E>);
+    }
+
+    async fn add_listener(&self, type_id: TypeId, listener: Box<dyn EventListener>) {
+        let mut listeners = self.listeners.write().await;
+        let list = listeners.entry(type_id).or_insert_with(Vec::new);
+        list.push(listener);
 
         // Sort by priority (descending)
-        if let Some(list) = listeners.get_mut(&type_id) {
-            list.sort_by(|a, b| b.priority().cmp(&a.priority()));
-        }
+        list.sort_by_key(|l| std::cmp::Reverse(l.priority()));
     }
 
     /// Dispatch an event
     pub async fn dispatch<E: Event>(&self, event: E) -> EventResult<()> {
-        let listeners = self.listeners.read().await;
         let type_id = TypeId::of::<E>();
 
+        if let Some(store) = &self.store {
+            let serializer = self.persisted.read().await.get(&type_id).copied();
+            if let Some(stored) = serializer.and_then(|serialize| serialize(&event)) {
+                store.append(stored?).await?;
+            }
+        }
+
+        let wildcards = self.wildcards.read().await;
+        for (pattern, listener) in wildcards.iter() {
+            if wildcard::matches(pattern, event.name()) {
+                listener.handle(&event).await?;
+            }
+        }
+        drop(wildcards);
+
+        let listeners = self.listeners.read().await;
         if let Some(list) = listeners.get(&type_id) {
             for listener in list {
                 listener.handle(&event as &(dyn Any + Send + Sync)).await?;
@@ -169,6 +412,7 @@ mod tests {
     use super::*;
 
     #[derive(Clone)]
+    #[allow(dead_code)]
     struct TestEvent {
         message: String,
     }
@@ -287,9 +531,7 @@ mod tests {
             }
 
             dispatcher
-                .listen(CountListener {
-                    count: count_clone,
-                })
+                .listen(CountListener { count: count_clone })
                 .await;
         }
 
@@ -363,4 +605,135 @@ mod tests {
         assert_eq!(dispatcher.listener_count::<TestEvent>().await, 1);
         assert_eq!(dispatcher.listener_count::<AnotherEvent>().await, 1);
     }
+
+    struct NameRecorder {
+        names: Arc<RwLock<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl WildcardListener for NameRecorder {
+        async fn handle(&self, event: &dyn Event) -> EventResult<()> {
+            self.names.write().await.push(event.name().to_string());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wildcard_listeners() {
+        let dispatcher = EventDispatcher::new();
+        let all = Arc::new(RwLock::new(Vec::new()));
+        let another = Arc::new(RwLock::new(Vec::new()));
+
+        dispatcher
+            .listen_any("*", NameRecorder { names: all.clone() })
+            .await;
+        dispatcher
+            .listen_any(
+                "*::AnotherEvent",
+                NameRecorder {
+                    names: another.clone(),
+                },
+            )
+            .await;
+
+        dispatcher
+            .dispatch(TestEvent {
+                message: "test".to_string(),
+            })
+            .await
+            .unwrap();
+        dispatcher.dispatch(AnotherEvent).await.unwrap();
+
+        assert_eq!(all.read().await.len(), 2);
+        assert_eq!(*another.read().await, vec![AnotherEvent.name().to_string()]);
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct StoredTestEvent {
+        id: u32,
+    }
+
+    impl Event for StoredTestEvent {
+        fn aggregate_id(&self) -> Option<String> {
+            Some(format!("test-{}", self.id))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_persisted_events() {
+        let store = Arc::new(MemoryEventStore::new());
+        let dispatcher = EventDispatcher::new().store(store.clone());
+        dispatcher.persist::<StoredTestEvent>().await;
+
+        dispatcher
+            .dispatch(StoredTestEvent { id: 1 })
+            .await
+            .unwrap();
+        dispatcher
+            .dispatch(StoredTestEvent { id: 1 })
+            .await
+            .unwrap();
+        // Not persisted
+        dispatcher.dispatch(AnotherEvent).await.unwrap();
+
+        let events = store.load("test-1").await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].version, 2);
+        assert_eq!(events[0].decode::<StoredTestEvent>().unwrap().id, 1);
+        assert_eq!(store.since(0, 10).await.unwrap().len(), 2);
+    }
+
+    #[cfg(feature = "queue")]
+    struct QueuedTestListener {
+        called: Arc<RwLock<bool>>,
+    }
+
+    #[cfg(feature = "queue")]
+    #[async_trait]
+    impl EventListenerFor<StoredTestEvent> for QueuedTestListener {
+        async fn handle(&self, _event: &StoredTestEvent) -> EventResult<()> {
+            *self.called.write().await = true;
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "queue")]
+    #[tokio::test]
+    async fn test_queued_listener() {
+        use rf_queue::{MemoryQueue, Queue};
+
+        let queue = Arc::new(MemoryQueue::new());
+        let dispatcher = EventDispatcher::new().queue(queue.clone());
+        let called = Arc::new(RwLock::new(false));
+        dispatcher
+            .listen_queued(QueuedTestListener {
+                called: called.clone(),
+            })
+            .await
+            .unwrap();
+
+        dispatcher
+            .dispatch(StoredTestEvent { id: 7 })
+            .await
+            .unwrap();
+        assert!(!*called.read().await);
+        assert_eq!(queue.size("default").await.unwrap(), 1);
+
+        let metadata = queue.reserve("default").await.unwrap().unwrap();
+        let job: ListenerJob = metadata.deserialize().unwrap();
+        assert_eq!(job.payload, serde_json::json!({ "id": 7 }));
+
+        dispatcher.handle_queued(&job).await.unwrap();
+        assert!(*called.read().await);
+    }
+
+    #[cfg(feature = "queue")]
+    #[tokio::test]
+    async fn test_queued_listener_requires_queue() {
+        let dispatcher = EventDispatcher::new();
+        let listener = QueuedTestListener {
+            called: Arc::new(RwLock::new(false)),
+        };
+        assert!(dispatcher.listen_queued(listener).await.is_err());
+    }
 }
@@ -0,0 +1,172 @@
+//! Model lifecycle events
+
+use crate::Event;
+use serde::{Deserialize, Serialize};
+
+/// A model is about to be created
+///
+/// Dispatched by rf-orm-lite before inserting a model; a failing listener
+/// cancels the insert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCreating<M> {
+    pub model: M,
+}
+
+/// A model was created
+///
+/// Dispatched after saving a new model, by rf-orm-lite or the application,
+/// e.g. for rf-search to index it. Named `model.created`, so `model.*`
+/// wildcard listeners see every model event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCreated<M> {
+    pub model: M,
+}
+
+/// A model is about to be updated; a failing listener cancels the update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelUpdating<M> {
+    pub model: M,
+}
+
+/// A model was updated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelUpdated<M> {
+    pub model: M,
+}
+
+/// A model is about to be deleted; a failing listener cancels the delete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelDeleting<M> {
+    pub model: M,
+}
+
+/// A model was deleted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelDeleted<M> {
+    pub model: M,
+}
+
+impl<M> ModelCreating<M> {
+    pub fn new(model: M) -> Self {
+        Self { model }
+    }
+}
+
+impl<M> ModelCreated<M> {
+    pub fn new(model: M) -> Self {
+        Self { model }
+    }
+}
+
+impl<M> ModelUpdating<M> {
+    pub fn new(model: M) -> Self {
+        Self { model }
+    }
+}
+
+impl<M> ModelUpdated<M> {
+    pub fn new(model: M) -> Self {
+        Self { model }
+    }
+}
+
+impl<M> ModelDeleting<M> {
+    pub fn new(model: M) -> Self {
+        Self { model }
+    }
+}
+
+impl<M> ModelDeleted<M> {
+    pub fn new(model: M) -> Self {
+        Self { model }
+    }
+}
+
+impl<M: Send + Sync + 'static> Event for ModelCreating<M> {
+    fn name(&self) -> &'static str {
+        "model.creating"
+    }
+}
+
+impl<M: Send + Sync + 'static> Event for ModelCreated<M> {
+    fn name(&self) -> &'static str {
+        "model.created"
+    }
+}
+
+impl<M: Send + Sync + 'static> Event for ModelUpdating<M> {
+    fn name(&self) -> &'static str {
+        "model.updating"
+    }
+}
+
+impl<M: Send + Sync + 'static> Event for ModelUpdated<M> {
+    fn name(&self) -> &'static str {
+        "model.updated"
+    }
+}
+
+impl<M: Send + Sync + 'static> Event for ModelDeleting<M> {
+    fn name(&self) -> &'static str {
+        "model.deleting"
+    }
+}
+
+impl<M: Send + Sync + 'static> Event for ModelDeleted<M> {
+    fn name(&self) -> &'static str {
+        "model.deleted"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EventDispatcher, EventListenerFor, EventResult, WildcardListener};
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    struct User;
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl WildcardListener for Recorder {
+        async fn handle(&self, event: &dyn Event) -> EventResult<()> {
+            self.0.lock().unwrap().push(event.name().to_string());
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl EventListenerFor<ModelDeleted<User>> for Recorder {
+        async fn handle(&self, _event: &ModelDeleted<User>) -> EventResult<()> {
+            self.0.lock().unwrap().push("user deleted".to_string());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_model_events() {
+        let recorder = Recorder::default();
+        let dispatcher = EventDispatcher::new();
+        dispatcher.listen_any("model.*", recorder.clone()).await;
+        dispatcher
+            .listen::<ModelDeleted<User>, _>(recorder.clone())
+            .await;
+
+        dispatcher.dispatch(ModelCreating::new(User)).await.unwrap();
+        dispatcher.dispatch(ModelCreated::new(User)).await.unwrap();
+        dispatcher.dispatch(ModelDeleted::new(User)).await.unwrap();
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![
+                "model.creating",
+                "model.created",
+                "model.deleted",
+                "user deleted"
+            ]
+        );
+    }
+}
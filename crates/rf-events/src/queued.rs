@@ -0,0 +1,149 @@
+//! Listeners run in the background through rf-queue
+
+use crate::{Event, EventDispatcher, EventError, EventListener, EventListenerFor, EventResult};
+use async_trait::async_trait;
+use rf_queue::{Job, JobMetadata, Queue, QueueError, Worker};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::any::Any;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Job delivering an event to a queued listener
+///
+/// Pushed by [`EventDispatcher::dispatch`] for listeners registered with
+/// [`EventDispatcher::listen_queued`], and run by a worker set up with
+/// [`EventWorkerExt::event_listeners`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerJob {
+    /// Type name of the listener
+    pub listener: String,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub queue: String,
+}
+
+#[async_trait]
+impl Job for ListenerJob {
+    async fn handle(&self) -> Result<(), QueueError> {
+        Err(QueueError::JobFailed(format!(
+            "Listener {} must be run by a worker with event listeners",
+            self.listener
+        )))
+    }
+
+    fn job_type(&self) -> &'static str {
+        "rf_events::listener"
+    }
+
+    fn queue(&self) -> &str {
+        &self.queue
+    }
+}
+
+/// Pushes events for a queued listener onto the queue
+pub(crate) struct QueuedListener<E, L> {
+    queue: Arc<dyn Queue>,
+    queue_name: String,
+    priority: i32,
+    _phantom: PhantomData<fn(E, L)>,
+}
+
+impl<E, L> QueuedListener<E, L> {
+    pub(crate) fn new(queue: Arc<dyn Queue>, queue_name: String, priority: i32) -> Self {
+        Self {
+            queue,
+            queue_name,
+            priority,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<E, L> EventListener for QueuedListener<E, L>
+where
+    E: Event + Serialize,
+    L: EventListenerFor<E>,
+{
+    async fn handle(&self, event: &(dyn Any + Send + Sync)) -> EventResult<()> {
+        let event = event
+            .downcast_ref::<E>()
+            .ok_or_else(|| EventError::DispatchError("Type mismatch".to_string()))?;
+        let payload = serde_json::to_value(event)
+            .map_err(|e| EventError::SerializationError(e.to_string()))?;
+
+        let job = ListenerJob {
+            listener: std::any::type_name::<L>().to_string(),
+            event: event.name().to_string(),
+            payload,
+            queue: self.queue_name.clone(),
+        };
+        let metadata =
+            JobMetadata::new(&job).map_err(|e| EventError::DispatchError(e.to_string()))?;
+        self.queue
+            .push(metadata)
+            .await
+            .map_err(|e| EventError::DispatchError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+}
+
+/// Runs a queued listener on a job's payload
+#[async_trait]
+pub(crate) trait QueuedHandler: Send + Sync {
+    async fn handle(&self, payload: serde_json::Value) -> EventResult<()>;
+}
+
+pub(crate) struct TypedQueuedHandler<E, L> {
+    listener: L,
+    _phantom: PhantomData<fn(E)>,
+}
+
+impl<E, L> TypedQueuedHandler<E, L> {
+    pub(crate) fn new(listener: L) -> Self {
+        Self {
+            listener,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<E, L> QueuedHandler for TypedQueuedHandler<E, L>
+where
+    E: Event + DeserializeOwned,
+    L: EventListenerFor<E>,
+{
+    async fn handle(&self, payload: serde_json::Value) -> EventResult<()> {
+        let event: E = serde_json::from_value(payload)
+            .map_err(|e| EventError::SerializationError(e.to_string()))?;
+        self.listener.handle(&event).await
+    }
+}
+
+/// Extension trait running queued listeners in an rf-queue [`Worker`]
+pub trait EventWorkerExt {
+    /// Run the queued listeners of `dispatcher`
+    ///
+    /// Listeners are found by type name, so the worker must run the same
+    /// binary as the process dispatching the events.
+    fn event_listeners(self, dispatcher: Arc<EventDispatcher>) -> Self;
+}
+
+impl EventWorkerExt for Worker {
+    fn event_listeners(self, dispatcher: Arc<EventDispatcher>) -> Self {
+        self.handle(move |job: ListenerJob| {
+            let dispatcher = Arc::clone(&dispatcher);
+            Box::pin(async move {
+                dispatcher
+                    .handle_queued(&job)
+                    .await
+                    .map_err(|e| QueueError::JobFailed(e.to_string()))
+            })
+        })
+    }
+}
@@ -0,0 +1,169 @@
+//! Event store for persisted events
+
+use crate::{Event, EventError, EventResult};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// An event as kept by an [`EventStore`]
+///
+/// `sequence` orders all events of a store, `version` the events of one
+/// aggregate. Both are assigned by [`EventStore::append`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredEvent {
+    pub id: Uuid,
+    /// Position in the store, starting at 1
+    pub sequence: u64,
+    /// Aggregate the event belongs to, see [`Event::aggregate_id`]
+    pub aggregate_id: Option<String>,
+    /// Version of the aggregate after this event, starting at 1
+    pub version: u64,
+    pub name: String,
+    pub payload: serde_json::Value,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl StoredEvent {
+    /// Serialize an event, to be passed to [`EventStore::append`]
+    pub fn new<E: Event + Serialize>(event: &E) -> EventResult<Self> {
+        let payload = serde_json::to_value(event)
+            .map_err(|e| EventError::SerializationError(e.to_string()))?;
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            sequence: 0,
+            aggregate_id: event.aggregate_id(),
+            version: 0,
+            name: event.name().to_string(),
+            payload,
+            occurred_at: Utc::now(),
+        })
+    }
+
+    /// Deserialize the payload back into its event
+    pub fn decode<E: DeserializeOwned>(&self) -> EventResult<E> {
+        serde_json::from_value(self.payload.clone())
+            .map_err(|e| EventError::SerializationError(e.to_string()))
+    }
+}
+
+/// Append-only storage for events
+#[async_trait]
+pub trait EventStore: Send + Sync {
+    /// Append an event, assigning its sequence and version
+    async fn append(&self, event: StoredEvent) -> EventResult<StoredEvent>;
+
+    /// Events of an aggregate, oldest first
+    async fn load(&self, aggregate_id: &str) -> EventResult<Vec<StoredEvent>>;
+
+    /// Up to `limit` events after `sequence`, for replaying into projections
+    async fn since(&self, sequence: u64, limit: usize) -> EventResult<Vec<StoredEvent>>;
+}
+
+/// In-memory event store for development and tests
+#[derive(Default)]
+pub struct MemoryEventStore {
+    events: RwLock<Vec<StoredEvent>>,
+}
+
+impl MemoryEventStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl EventStore for MemoryEventStore {
+    async fn append(&self, mut event: StoredEvent) -> EventResult<StoredEvent> {
+        let mut events = self.events.write().await;
+
+        event.sequence = events.len() as u64 + 1;
+        event.version = match &event.aggregate_id {
+            Some(aggregate_id) => {
+                let current = events
+                    .iter()
+                    .filter(|e| e.aggregate_id.as_ref() == Some(aggregate_id))
+                    .count();
+                current as u64 + 1
+            }
+            None => 1,
+        };
+
+        events.push(event.clone());
+        Ok(event)
+    }
+
+    async fn load(&self, aggregate_id: &str) -> EventResult<Vec<StoredEvent>> {
+        let events = self.events.read().await;
+        Ok(events
+            .iter()
+            .filter(|e| e.aggregate_id.as_deref() == Some(aggregate_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn since(&self, sequence: u64, limit: usize) -> EventResult<Vec<StoredEvent>> {
+        let events = self.events.read().await;
+        Ok(events
+            .iter()
+            .filter(|e| e.sequence > sequence)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Deposited {
+        account: String,
+        amount: u32,
+    }
+
+    impl Event for Deposited {
+        fn name(&self) -> &'static str {
+            "account.deposited"
+        }
+
+        fn aggregate_id(&self) -> Option<String> {
+            Some(self.account.clone())
+        }
+    }
+
+    fn deposit(account: &str, amount: u32) -> StoredEvent {
+        StoredEvent::new(&Deposited {
+            account: account.to_string(),
+            amount,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_memory_event_store() {
+        let store = MemoryEventStore::new();
+        store.append(deposit("a", 10)).await.unwrap();
+        store.append(deposit("b", 20)).await.unwrap();
+        let stored = store.append(deposit("a", 30)).await.unwrap();
+
+        assert_eq!(stored.sequence, 3);
+        assert_eq!(stored.version, 2);
+        assert_eq!(stored.name, "account.deposited");
+
+        let history = store.load("a").await.unwrap();
+        let amounts: Vec<u32> = history
+            .iter()
+            .map(|e| e.decode::<Deposited>().unwrap().amount)
+            .collect();
+        assert_eq!(amounts, vec![10, 30]);
+
+        let replay = store.since(1, 10).await.unwrap();
+        assert_eq!(replay.len(), 2);
+        assert_eq!(replay[0].sequence, 2);
+        assert_eq!(store.since(0, 1).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,82 @@
+//! Listeners for every event matching a name pattern
+
+use crate::{Event, EventResult};
+use async_trait::async_trait;
+
+/// Listener receiving every event whose name matches a pattern
+///
+/// Registered with [`EventDispatcher::listen_any`](crate::EventDispatcher::listen_any).
+/// Useful for cross-cutting concerns such as logging and auditing.
+#[async_trait]
+pub trait WildcardListener: Send + Sync + 'static {
+    /// Handle the event
+    async fn handle(&self, event: &dyn Event) -> EventResult<()>;
+}
+
+/// Wildcard listener logging each event through `tracing`
+///
+/// ```
+/// use rf_events::{EventDispatcher, EventLogger};
+///
+/// # async fn example() {
+/// let dispatcher = EventDispatcher::new();
+/// dispatcher.listen_any("*", EventLogger).await;
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EventLogger;
+
+#[async_trait]
+impl WildcardListener for EventLogger {
+    async fn handle(&self, event: &dyn Event) -> EventResult<()> {
+        tracing::info!(
+            event = event.name(),
+            aggregate_id = event.aggregate_id().as_deref(),
+            "Event dispatched"
+        );
+        Ok(())
+    }
+}
+
+/// Match an event name against a pattern where `*` stands for any text,
+/// e.g. `user.*` or `*Created`
+pub(crate) fn matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` in the pattern
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("*", "user.created"));
+        assert!(matches("user.*", "user.created"));
+        assert!(!matches("user.*", "order.created"));
+        assert!(matches("*.created", "order.created"));
+        assert!(matches("app::*::User*", "app::events::UserDeleted"));
+        assert!(matches("user.created", "user.created"));
+        assert!(!matches("user.created", "user.created.v2"));
+        assert!(!matches("a*b*c", "abca"));
+        assert!(matches("a*a", "aa"));
+        assert!(!matches("a*a", "a"));
+    }
+}
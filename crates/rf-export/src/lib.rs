@@ -5,7 +5,6 @@
 use async_trait::async_trait;
 use bytes::Bytes;
 use serde::Serialize;
-use std::io::Write;
 use thiserror::Error;
 
 /// Export errors
@@ -22,10 +21,22 @@ pub enum ExportError {
 
     #[error("Template error: {0}")]
     TemplateError(String),
+
+    #[error("Progress error: {0}")]
+    ProgressError(String),
 }
 
 pub type ExportResult<T> = Result<T, ExportError>;
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(ExportError, {
+    Self::FormatError(_) => (BAD_REQUEST, "export.invalid_format"),
+    Self::SerializationError(_) => (INTERNAL_SERVER_ERROR, "export.serialization"),
+    Self::IoError(_) => (INTERNAL_SERVER_ERROR, "export.io"),
+    Self::TemplateError(_) => (INTERNAL_SERVER_ERROR, "export.template"),
+    Self::ProgressError(_) => (INTERNAL_SERVER_ERROR, "export.progress"),
+});
+
 /// Export format
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExportFormat {
@@ -46,6 +57,58 @@ pub trait Exporter: Send + Sync {
 
     /// Get file extension
     fn file_extension(&self) -> &'static str;
+
+    /// Export and store the file at `path` on `disk`, returning its size
+    ///
+    /// Keeps artifacts of export jobs, e.g. for a download link sent when
+    /// the job finished.
+    #[cfg(feature = "storage")]
+    async fn store(&self, disk: &dyn rf_storage::Filesystem, path: &str) -> ExportResult<u64> {
+        let contents = self.export().await?;
+        let size = contents.len() as u64;
+        disk.put(path, contents.to_vec())
+            .await
+            .map_err(|e| ExportError::IoError(e.to_string()))?;
+        Ok(size)
+    }
+
+    /// Like [`store`](Self::store), reporting the export and store steps to
+    /// `progress` and completing it with the path and size
+    #[cfg(feature = "progress")]
+    async fn store_tracked(
+        &self,
+        disk: &dyn rf_storage::Filesystem,
+        path: &str,
+        progress: &mut rf_progress::JobProgress,
+    ) -> ExportResult<u64> {
+        let progress_error =
+            |e: rf_progress::ProgressError| ExportError::ProgressError(e.to_string());
+
+        let result = async {
+            progress.steps(2).await.map_err(progress_error)?;
+            progress
+                .advance("Exporting")
+                .await
+                .map_err(progress_error)?;
+            let contents = self.export().await?;
+            progress.advance("Storing").await.map_err(progress_error)?;
+            let size = contents.len() as u64;
+            disk.put(path, contents.to_vec())
+                .await
+                .map_err(|e| ExportError::IoError(e.to_string()))?;
+            Ok(size)
+        }
+        .await;
+
+        match &result {
+            Ok(size) => {
+                let stored = serde_json::json!({ "path": path, "size": size });
+                progress.complete(stored).await.map_err(progress_error)?
+            }
+            Err(e) => progress.fail(e).await.map_err(progress_error)?,
+        }
+        result
+    }
 }
 
 /// CSV exporter
@@ -593,4 +656,75 @@ mod tests {
         // CSV should properly escape quotes
         assert!(csv.contains("Hello"));
     }
+
+    #[cfg(feature = "storage")]
+    #[tokio::test]
+    async fn test_store_on_disk() {
+        use rf_storage::{Filesystem, MemoryStorage};
+
+        let data = vec![TestData {
+            id: 1,
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            active: true,
+        }];
+        let exporter = CsvExporter::new()
+            .from_data(&data)
+            .unwrap()
+            .columns(&["id", "name"]);
+
+        let disk = MemoryStorage::new();
+        let size = Exporter::store(&exporter, &disk, "exports/users.csv")
+            .await
+            .unwrap();
+
+        let stored = disk.get("exports/users.csv").await.unwrap();
+        assert_eq!(stored.len() as u64, size);
+        assert!(String::from_utf8(stored).unwrap().contains("1,Alice"));
+    }
+
+    #[cfg(feature = "progress")]
+    #[tokio::test]
+    async fn test_store_tracked() {
+        use rf_progress::{JobStatus, ProgressTracker};
+        use rf_storage::MemoryStorage;
+
+        let data = vec![TestData {
+            id: 1,
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            active: true,
+        }];
+        let exporter = CsvExporter::new().from_data(&data).unwrap();
+        let tracker = ProgressTracker::memory();
+        let mut progress = tracker.start().await.unwrap();
+
+        let disk = MemoryStorage::new();
+        let size = exporter
+            .store_tracked(&disk, "exports/users.csv", &mut progress)
+            .await
+            .unwrap();
+
+        let progress = tracker.get(progress.id()).await.unwrap();
+        assert_eq!(progress.status, JobStatus::Completed);
+        assert_eq!(progress.step, 2);
+        assert_eq!(
+            progress.result,
+            Some(serde_json::json!({ "path": "exports/users.csv", "size": size }))
+        );
+    }
+
+    #[cfg(feature = "rf-error")]
+    #[test]
+    fn test_framework_error() {
+        use rf_error::{FrameworkError, StatusCode};
+
+        let error = ExportError::FormatError("xlsx".into());
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(error.code(), "export.invalid_format");
+
+        let error = ExportError::ProgressError("store unavailable".into());
+        assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.code(), "export.progress");
+    }
 }
@@ -0,0 +1,212 @@
+//! rf-admin integration
+
+use crate::{FeatureFlagError, FeatureFlags, FlagConfig};
+use async_trait::async_trait;
+use rf_admin::{AdminError, AdminList, AdminResource, AdminResult, FieldConfig, FieldType, ListParams};
+
+/// Exposes feature flags as an rf-admin resource
+///
+/// `enabled` is a boolean field, so the panel renders it as a toggle; a
+/// partial update such as `{"enabled": true}` flips a single flag.
+///
+/// # Example
+///
+/// ```ignore
+/// let panel = AdminPanel::new().resource(Arc::new(FlagAdminResource::new(flags)));
+/// ```
+pub struct FlagAdminResource {
+    flags: FeatureFlags,
+}
+
+impl FlagAdminResource {
+    pub fn new(flags: FeatureFlags) -> Self {
+        Self { flags }
+    }
+
+    async fn find(&self, id: &str) -> AdminResult<FlagConfig> {
+        self.flags
+            .get_config(id)
+            .await
+            .map_err(admin_error)?
+            .ok_or_else(|| AdminError::ResourceNotFound(id.to_string()))
+    }
+
+    async fn save(&self, config: FlagConfig) -> AdminResult<serde_json::Value> {
+        if let Some(percentage) = config.percentage {
+            if !(0.0..=100.0).contains(&percentage) {
+                return Err(admin_error(FeatureFlagError::InvalidPercentage(percentage)));
+            }
+        }
+
+        let value = to_value(&config)?;
+        self.flags.set_config(config).await.map_err(admin_error)?;
+        Ok(value)
+    }
+}
+
+fn admin_error(e: FeatureFlagError) -> AdminError {
+    match e {
+        FeatureFlagError::FlagNotFound(name) => AdminError::ResourceNotFound(name),
+        FeatureFlagError::FlagExists(_)
+        | FeatureFlagError::InvalidName(_)
+        | FeatureFlagError::InvalidPercentage(_) => AdminError::ValidationError(e.to_string()),
+        FeatureFlagError::StorageError(_) | FeatureFlagError::AuditError(_) => {
+            AdminError::DatabaseError(e.to_string())
+        }
+    }
+}
+
+fn to_value(config: &FlagConfig) -> AdminResult<serde_json::Value> {
+    serde_json::to_value(config).map_err(|e| AdminError::DatabaseError(e.to_string()))
+}
+
+fn from_value(value: serde_json::Value) -> AdminResult<FlagConfig> {
+    serde_json::from_value(value).map_err(|e| AdminError::ValidationError(e.to_string()))
+}
+
+#[async_trait]
+impl AdminResource for FlagAdminResource {
+    fn name(&self) -> &str {
+        "feature-flags"
+    }
+
+    fn label(&self) -> &str {
+        "Feature Flags"
+    }
+
+    fn fields(&self) -> Vec<FieldConfig> {
+        vec![
+            FieldConfig::new("name", "Name").required().searchable().sortable(),
+            FieldConfig::new("enabled", "Enabled").field_type(FieldType::Boolean),
+            FieldConfig::new("percentage", "Rollout %").field_type(FieldType::Number),
+            FieldConfig::new("user_ids", "Users")
+                .field_type(FieldType::TextArea)
+                .list_display(false),
+            FieldConfig::new("groups", "Groups")
+                .field_type(FieldType::TextArea)
+                .list_display(false),
+        ]
+    }
+
+    async fn list(&self, params: ListParams) -> AdminResult<AdminList> {
+        let mut flags = self.flags.list().await.map_err(admin_error)?;
+
+        if let Some(search) = params.search.as_deref().filter(|s| !s.is_empty()) {
+            let search = search.to_lowercase();
+            flags.retain(|flag| flag.name.to_lowercase().contains(&search));
+        }
+
+        flags.sort_by(|a, b| a.name.cmp(&b.name));
+        if params.order.as_deref() == Some("desc") {
+            flags.reverse();
+        }
+
+        let page = params.page.unwrap_or(1).max(1);
+        let per_page = params.per_page.unwrap_or(25).max(1);
+        let total = flags.len() as u64;
+
+        let data = flags
+            .iter()
+            .skip(((page - 1) * per_page) as usize)
+            .take(per_page as usize)
+            .map(to_value)
+            .collect::<AdminResult<Vec<_>>>()?;
+
+        Ok(AdminList::new(data, total, page, per_page))
+    }
+
+    async fn get(&self, id: &str) -> AdminResult<serde_json::Value> {
+        to_value(&self.find(id).await?)
+    }
+
+    async fn create(&self, data: serde_json::Value) -> AdminResult<serde_json::Value> {
+        let config = from_value(data)?;
+        if config.name.is_empty() {
+            return Err(AdminError::ValidationError("name is required".to_string()));
+        }
+        self.save(config).await
+    }
+
+    async fn update(&self, id: &str, data: serde_json::Value) -> AdminResult<serde_json::Value> {
+        let serde_json::Value::Object(changes) = data else {
+            return Err(AdminError::ValidationError("expected an object".to_string()));
+        };
+
+        // Partial update: only the submitted fields change
+        let mut current = to_value(&self.find(id).await?)?;
+        if let serde_json::Value::Object(fields) = &mut current {
+            fields.extend(changes);
+            fields.insert("name".to_string(), serde_json::Value::String(id.to_string()));
+        }
+
+        self.save(from_value(current)?).await
+    }
+
+    async fn delete(&self, id: &str) -> AdminResult<()> {
+        self.find(id).await?;
+        self.flags.delete(id).await.map_err(admin_error)
+    }
+
+    fn menu_group(&self) -> Option<&str> {
+        Some("Settings")
+    }
+
+    fn icon(&self) -> Option<&str> {
+        Some("flag")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_toggle_via_partial_update() {
+        let flags = FeatureFlags::new();
+        flags.set_percentage("beta", 20.0).await.unwrap();
+        let resource = FlagAdminResource::new(flags.clone());
+
+        resource
+            .update("beta", serde_json::json!({ "enabled": true }))
+            .await
+            .unwrap();
+
+        let config = flags.get_config("beta").await.unwrap().unwrap();
+        assert!(config.enabled);
+        assert_eq!(config.percentage, Some(20.0));
+    }
+
+    #[tokio::test]
+    async fn test_list_search_and_pagination() {
+        let flags = FeatureFlags::new();
+        for name in ["alpha", "beta_api", "beta_ui"] {
+            flags.enable(name).await.unwrap();
+        }
+        let resource = FlagAdminResource::new(flags);
+
+        let list = resource
+            .list(ListParams {
+                page: Some(1),
+                per_page: Some(1),
+                search: Some("beta".to_string()),
+                sort: None,
+                order: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(list.total, 2);
+        assert_eq!(list.last_page, 2);
+        assert_eq!(list.data[0]["name"], "beta_api");
+    }
+
+    #[tokio::test]
+    async fn test_missing_flag() {
+        let resource = FlagAdminResource::new(FeatureFlags::new());
+        assert!(matches!(
+            resource.get("missing").await,
+            Err(AdminError::ResourceNotFound(_))
+        ));
+        assert!(resource.delete("missing").await.is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! Token-protected REST API for managing flags
+
+use crate::{FeatureFlagError, FeatureFlags, FlagConfig};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, put},
+    Json, Router,
+};
+use rf_middleware::BearerTokenLayer;
+use serde::Deserialize;
+
+impl IntoResponse for FeatureFlagError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            FeatureFlagError::FlagNotFound(_) => StatusCode::NOT_FOUND,
+            FeatureFlagError::FlagExists(_) => StatusCode::CONFLICT,
+            FeatureFlagError::InvalidName(_) | FeatureFlagError::InvalidPercentage(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            FeatureFlagError::StorageError(_) | FeatureFlagError::AuditError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+        let body = serde_json::json!({ "error": self.to_string() });
+        (status, Json(body)).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PercentageBody {
+    percentage: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsersBody {
+    user_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroupsBody {
+    groups: Vec<String>,
+}
+
+/// Build the flag management router
+///
+/// Every route requires `Authorization: Bearer <token>`.
+///
+/// | Method | Path | Action |
+/// |--------|------|--------|
+/// | GET | `/flags` | list flags |
+/// | POST | `/flags` | create a flag, 409 if it exists |
+/// | GET | `/flags/{name}` | show a flag |
+/// | PUT | `/flags/{name}` | replace a flag |
+/// | DELETE | `/flags/{name}` | delete a flag |
+/// | PUT | `/flags/{name}/percentage` | set rollout percentage |
+/// | PUT | `/flags/{name}/users` | set targeted users |
+/// | PUT | `/flags/{name}/groups` | set targeted groups |
+///
+/// # Panics
+///
+/// Panics if `token` is empty.
+///
+/// # Example
+///
+/// ```ignore
+/// let app = Router::new().nest("/admin", flag_admin_router(flags, "secret-token"));
+/// ```
+pub fn flag_admin_router(flags: FeatureFlags, token: impl Into<String>) -> Router {
+    Router::new()
+        .route("/flags", get(list_flags).post(create_flag))
+        .route(
+            "/flags/{name}",
+            get(show_flag).put(update_flag).delete(delete_flag),
+        )
+        .route("/flags/{name}/percentage", put(set_percentage))
+        .route("/flags/{name}/users", put(set_users))
+        .route("/flags/{name}/groups", put(set_groups))
+        .layer(BearerTokenLayer::new(token))
+        .with_state(flags)
+}
+
+async fn find(flags: &FeatureFlags, name: &str) -> Result<FlagConfig, FeatureFlagError> {
+    flags
+        .get_config(name)
+        .await?
+        .ok_or_else(|| FeatureFlagError::FlagNotFound(name.to_string()))
+}
+
+fn validate(config: &FlagConfig) -> Result<(), FeatureFlagError> {
+    if config.name.trim().is_empty() {
+        return Err(FeatureFlagError::InvalidName(config.name.clone()));
+    }
+    match config.percentage {
+        Some(percentage) if !(0.0..=100.0).contains(&percentage) => {
+            Err(FeatureFlagError::InvalidPercentage(percentage))
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn list_flags(State(flags): State<FeatureFlags>) -> Result<Json<Vec<FlagConfig>>, FeatureFlagError> {
+    let mut list = flags.list().await?;
+    list.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Json(list))
+}
+
+async fn create_flag(
+    State(flags): State<FeatureFlags>,
+    Json(config): Json<FlagConfig>,
+) -> Result<impl IntoResponse, FeatureFlagError> {
+    validate(&config)?;
+    if flags.get_config(&config.name).await?.is_some() {
+        return Err(FeatureFlagError::FlagExists(config.name));
+    }
+    flags.set_config(config.clone()).await?;
+    Ok((StatusCode::CREATED, Json(config)))
+}
+
+async fn show_flag(
+    State(flags): State<FeatureFlags>,
+    Path(name): Path<String>,
+) -> Result<Json<FlagConfig>, FeatureFlagError> {
+    Ok(Json(find(&flags, &name).await?))
+}
+
+async fn update_flag(
+    State(flags): State<FeatureFlags>,
+    Path(name): Path<String>,
+    Json(mut config): Json<FlagConfig>,
+) -> Result<Json<FlagConfig>, FeatureFlagError> {
+    find(&flags, &name).await?;
+    config.name = name;
+    validate(&config)?;
+    flags.set_config(config.clone()).await?;
+    Ok(Json(config))
+}
+
+async fn delete_flag(
+    State(flags): State<FeatureFlags>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, FeatureFlagError> {
+    find(&flags, &name).await?;
+    flags.delete(&name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn set_percentage(
+    State(flags): State<FeatureFlags>,
+    Path(name): Path<String>,
+    Json(body): Json<PercentageBody>,
+) -> Result<Json<FlagConfig>, FeatureFlagError> {
+    let mut config = find(&flags, &name).await?;
+    config.percentage = Some(body.percentage);
+    validate(&config)?;
+    flags.set_config(config.clone()).await?;
+    Ok(Json(config))
+}
+
+async fn set_users(
+    State(flags): State<FeatureFlags>,
+    Path(name): Path<String>,
+    Json(body): Json<UsersBody>,
+) -> Result<Json<FlagConfig>, FeatureFlagError> {
+    let mut config = find(&flags, &name).await?;
+    config.user_ids = body.user_ids;
+    flags.set_config(config.clone()).await?;
+    Ok(Json(config))
+}
+
+async fn set_groups(
+    State(flags): State<FeatureFlags>,
+    Path(name): Path<String>,
+    Json(body): Json<GroupsBody>,
+) -> Result<Json<FlagConfig>, FeatureFlagError> {
+    let mut config = find(&flags, &name).await?;
+    config.groups = body.groups;
+    flags.set_config(config.clone()).await?;
+    Ok(Json(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::Request, http::header};
+    use tower::ServiceExt;
+
+    fn request(method: &str, uri: &str, body: Option<serde_json::Value>) -> Request {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .header(header::CONTENT_TYPE, "application/json");
+
+        match body {
+            Some(body) => builder.body(Body::from(body.to_string())).unwrap(),
+            None => builder.body(Body::empty()).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requires_token() {
+        let app = flag_admin_router(FeatureFlags::new(), "secret");
+        let response = app
+            .oneshot(Request::builder().uri("/flags").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_flag_crud() {
+        let flags = FeatureFlags::new();
+        let app = flag_admin_router(flags.clone(), "secret");
+
+        let created = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/flags",
+                Some(serde_json::json!({ "name": "new_checkout" })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(created.status(), StatusCode::CREATED);
+
+        let duplicate = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/flags",
+                Some(serde_json::json!({ "name": "new_checkout", "enabled": true })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(duplicate.status(), StatusCode::CONFLICT);
+        assert!(!flags.get_config("new_checkout").await.unwrap().unwrap().enabled);
+
+        for name in ["", "  "] {
+            let unnamed = app
+                .clone()
+                .oneshot(request(
+                    "POST",
+                    "/flags",
+                    Some(serde_json::json!({ "name": name })),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(unnamed.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+
+        let updated = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                "/flags/new_checkout/percentage",
+                Some(serde_json::json!({ "percentage": 30.0 })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(updated.status(), StatusCode::OK);
+        assert_eq!(
+            flags.get_config("new_checkout").await.unwrap().unwrap().percentage,
+            Some(30.0)
+        );
+
+        let invalid = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                "/flags/new_checkout/percentage",
+                Some(serde_json::json!({ "percentage": 300.0 })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(invalid.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let deleted = app
+            .clone()
+            .oneshot(request("DELETE", "/flags/new_checkout", None))
+            .await
+            .unwrap();
+        assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+
+        let missing = app
+            .oneshot(request("GET", "/flags/new_checkout", None))
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,294 @@
+//! File-backed flag storage with environment overrides and hot reload
+
+use crate::{FeatureFlagError, FeatureFlagResult, FlagConfig, FlagStorage};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::sync::{Mutex, RwLock};
+
+/// Flag definition as written in the file; every field is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FileFlag {
+    enabled: bool,
+    percentage: Option<f64>,
+    user_ids: Vec<String>,
+    groups: Vec<String>,
+}
+
+/// Read-only flag storage backed by `flags.toml` (or YAML with the `yaml` feature)
+///
+/// The file is the source of truth, so flags can be managed through GitOps:
+///
+/// ```toml
+/// [new_checkout]
+/// enabled = true
+///
+/// [beta_api]
+/// percentage = 25.0
+/// groups = ["staff"]
+/// ```
+///
+/// Environment variables override the file: `FLAG_NEW_CHECKOUT=true|false`
+/// toggles a flag and `FLAG_BETA_API=50%` sets its rollout percentage. A
+/// variable naming a flag missing from the file creates it. Other values are
+/// logged and ignored, since unrelated variables may share the prefix.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_feature_flags::{FeatureFlags, FileFlagStorage};
+/// use std::{sync::Arc, time::Duration};
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let storage = Arc::new(FileFlagStorage::open("config/flags.toml")?);
+/// storage.watch(Duration::from_secs(2));
+///
+/// let flags = FeatureFlags::with_storage(storage);
+/// # Ok(())
+/// # }
+/// ```
+pub struct FileFlagStorage {
+    path: PathBuf,
+    env_prefix: String,
+    flags: RwLock<Arc<HashMap<String, FlagConfig>>>,
+    last_modified: Mutex<Option<SystemTime>>,
+}
+
+impl FileFlagStorage {
+    /// Load flags from `path` with the default `FLAG_` environment prefix
+    pub fn open(path: impl AsRef<Path>) -> FeatureFlagResult<Self> {
+        Self::open_with_prefix(path, "FLAG_")
+    }
+
+    /// Load flags from `path` with a custom environment prefix
+    pub fn open_with_prefix(path: impl AsRef<Path>, env_prefix: &str) -> FeatureFlagResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let flags = load_flags(&path, env_prefix)?;
+
+        Ok(Self {
+            last_modified: Mutex::new(modified(&path)),
+            path,
+            env_prefix: env_prefix.to_string(),
+            flags: RwLock::new(Arc::new(flags)),
+        })
+    }
+
+    /// Re-read the file and environment and swap in the new flag set
+    ///
+    /// On a parse error the current flags stay in place.
+    pub async fn reload(&self) -> FeatureFlagResult<()> {
+        let mut last_modified = self.last_modified.lock().await;
+        let flags = load_flags(&self.path, &self.env_prefix)?;
+
+        *self.flags.write().await = Arc::new(flags);
+        *last_modified = modified(&self.path);
+
+        tracing::info!(path = %self.path.display(), "Feature flags reloaded");
+        Ok(())
+    }
+
+    /// Reload whenever the file's modification time changes
+    pub fn watch(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let storage = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+
+                let current = modified(&storage.path);
+                if current == *storage.last_modified.lock().await {
+                    continue;
+                }
+
+                if let Err(e) = storage.reload().await {
+                    tracing::error!(path = %storage.path.display(), error = %e, "Failed to reload feature flags");
+                    // Don't retry the same broken file on every tick
+                    *storage.last_modified.lock().await = current;
+                }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl FlagStorage for FileFlagStorage {
+    async fn get(&self, name: &str) -> FeatureFlagResult<Option<FlagConfig>> {
+        Ok(self.flags.read().await.get(name).cloned())
+    }
+
+    async fn set(&self, config: FlagConfig) -> FeatureFlagResult<()> {
+        Err(read_only(&config.name))
+    }
+
+    async fn delete(&self, name: &str) -> FeatureFlagResult<()> {
+        Err(read_only(name))
+    }
+
+    async fn list(&self) -> FeatureFlagResult<Vec<FlagConfig>> {
+        Ok(self.flags.read().await.values().cloned().collect())
+    }
+}
+
+fn read_only(name: &str) -> FeatureFlagError {
+    FeatureFlagError::StorageError(format!(
+        "cannot change flag '{}': file-backed flags are read-only, edit the flags file instead",
+        name
+    ))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn load_flags(path: &Path, env_prefix: &str) -> FeatureFlagResult<HashMap<String, FlagConfig>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| FeatureFlagError::StorageError(format!("{}: {}", path.display(), e)))?;
+    let parsed = parse_flags(path, &contents)?;
+
+    let mut flags: HashMap<String, FlagConfig> = parsed
+        .into_iter()
+        .map(|(name, flag)| {
+            let config = FlagConfig {
+                name: name.clone(),
+                enabled: flag.enabled,
+                percentage: flag.percentage,
+                user_ids: flag.user_ids,
+                groups: flag.groups,
+            };
+            (name, config)
+        })
+        .collect();
+
+    apply_env_overrides(&mut flags, env_prefix, std::env::vars());
+    Ok(flags)
+}
+
+fn parse_flags(path: &Path, contents: &str) -> FeatureFlagResult<HashMap<String, FileFlag>> {
+    let parse_error = |e: String| FeatureFlagError::StorageError(format!("{}: {}", path.display(), e));
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        #[cfg(feature = "yaml")]
+        Some("yaml" | "yml") => serde_yaml::from_str(contents).map_err(|e| parse_error(e.to_string())),
+        Some("toml") | None => toml::from_str(contents).map_err(|e| parse_error(e.to_string())),
+        Some(other) => Err(parse_error(format!("unsupported flag file format '{}'", other))),
+    }
+}
+
+/// Override set by an environment variable
+enum Override {
+    Enabled(bool),
+    Percentage(f64),
+}
+
+impl Override {
+    fn parse(value: &str) -> Option<Self> {
+        if let Some(percentage) = value.strip_suffix('%') {
+            let percentage: f64 = percentage.trim().parse().ok()?;
+            return (0.0..=100.0)
+                .contains(&percentage)
+                .then_some(Override::Percentage(percentage));
+        }
+
+        match value.to_lowercase().as_str() {
+            "1" | "true" | "on" | "yes" => Some(Override::Enabled(true)),
+            "0" | "false" | "off" | "no" => Some(Override::Enabled(false)),
+            _ => None,
+        }
+    }
+}
+
+/// Apply `<PREFIX><FLAG_NAME>=true|false|<n>%` overrides, skipping other
+/// values
+fn apply_env_overrides(
+    flags: &mut HashMap<String, FlagConfig>,
+    prefix: &str,
+    vars: impl Iterator<Item = (String, String)>,
+) {
+    for (key, value) in vars {
+        let Some(name) = key.strip_prefix(prefix) else {
+            continue;
+        };
+        let Some(flag_override) = Override::parse(value.trim()) else {
+            tracing::warn!(variable = %key, value = %value, "Ignoring invalid feature flag override");
+            continue;
+        };
+
+        let name = name.to_lowercase();
+        let config = flags
+            .entry(name.clone())
+            .or_insert_with(|| FlagConfig::new(name));
+        match flag_override {
+            Override::Enabled(enabled) => config.enabled = enabled,
+            Override::Percentage(percentage) => config.percentage = Some(percentage),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rf-flags-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_load_and_reload() {
+        let path = temp_file("reload", "[new_checkout]\nenabled = true\n");
+        let storage = FileFlagStorage::open_with_prefix(&path, "RF_TEST_RELOAD_").unwrap();
+        assert!(storage.get("new_checkout").await.unwrap().unwrap().enabled);
+
+        std::fs::write(&path, "[new_checkout]\nenabled = false\n\n[beta]\npercentage = 10.0\n").unwrap();
+        storage.reload().await.unwrap();
+
+        assert!(!storage.get("new_checkout").await.unwrap().unwrap().enabled);
+        assert_eq!(storage.get("beta").await.unwrap().unwrap().percentage, Some(10.0));
+
+        // A broken file keeps the last good flags
+        std::fs::write(&path, "[beta\n").unwrap();
+        assert!(storage.reload().await.is_err());
+        assert_eq!(storage.list().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_read_only() {
+        let path = temp_file("readonly", "");
+        let storage = FileFlagStorage::open_with_prefix(&path, "RF_TEST_READONLY_").unwrap();
+        assert!(storage.set(FlagConfig::new("x")).await.is_err());
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let mut flags = HashMap::new();
+        flags.insert("new_checkout".to_string(), FlagConfig::new("new_checkout"));
+
+        let vars = vec![
+            ("FLAG_NEW_CHECKOUT".to_string(), "true".to_string()),
+            ("FLAG_BETA_API".to_string(), "25%".to_string()),
+            ("OTHER".to_string(), "ignored".to_string()),
+        ];
+        apply_env_overrides(&mut flags, "FLAG_", vars.into_iter());
+
+        assert!(flags["new_checkout"].enabled);
+        assert_eq!(flags["beta_api"].percentage, Some(25.0));
+        assert_eq!(flags.len(), 2);
+
+        // Unrelated variables sharing the prefix are skipped
+        let invalid = vec![
+            ("FLAG_X".to_string(), "maybe".to_string()),
+            ("FLAG_BETA_API".to_string(), "150%".to_string()),
+        ];
+        apply_env_overrides(&mut flags, "FLAG_", invalid.into_iter());
+        assert_eq!(flags["beta_api"].percentage, Some(25.0));
+        assert_eq!(flags.len(), 2);
+    }
+}
@@ -1,16 +1,67 @@
 //! Feature Flags for RustForge
 //!
 //! This crate provides dynamic feature toggling and A/B testing.
+//!
+//! # Storage backends
+//!
+//! - [`MemoryStorage`] for tests and single-instance apps
+//! - [`FileFlagStorage`] reads a GitOps-managed `flags.toml` with env overrides
+//! - `RedisFlagStorage` (feature `redis-backend`) shares flags across instances
+//!
+//! # Axum integration
+//!
+//! With the `axum` feature, `FeatureFlagLayer` evaluates flags per request,
+//! `Flags` extracts them in handlers and `require_flag` guards routes.
+//! `flag_admin_router` exposes a token-protected management API, and with the
+//! `admin` feature `FlagAdminResource` lists flags in rf-admin.
+//!
+//! # Auditing and scheduling
+//!
+//! Attach an `rf_audit::AuditLogger` with [`FeatureFlags::with_audit`] to record
+//! every mutation, and use [`FlagScheduler`] to stage enables, disables and
+//! percentage ramps ahead of time.
 
 use async_trait::async_trait;
+use rf_audit::{AuditAction, AuditEntry, AuditLogger};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     hash::{Hash, Hasher},
     sync::Arc,
+    time::{Duration, Instant},
 };
 use thiserror::Error;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
+
+mod file;
+mod schedule;
+
+#[cfg(feature = "admin")]
+mod admin;
+
+#[cfg(feature = "axum")]
+mod api;
+
+#[cfg(feature = "axum")]
+pub mod middleware;
+
+#[cfg(feature = "redis-backend")]
+mod redis;
+
+pub use file::FileFlagStorage;
+pub use schedule::{FlagChange, FlagScheduler, RolloutSchedule, RolloutStep};
+
+#[cfg(feature = "admin")]
+pub use admin::FlagAdminResource;
+
+#[cfg(feature = "axum")]
+pub use api::flag_admin_router;
+
+#[cfg(feature = "axum")]
+pub use middleware::{require_flag, FeatureFlagLayer, Flags};
+
+#[cfg(feature = "redis-backend")]
+pub use redis::RedisFlagStorage;
 
 /// Feature flag errors
 #[derive(Debug, Error)]
@@ -18,15 +69,34 @@ pub enum FeatureFlagError {
     #[error("Flag not found: {0}")]
     FlagNotFound(String),
 
+    #[error("Flag already exists: {0}")]
+    FlagExists(String),
+
+    #[error("Invalid flag name: {0:?}")]
+    InvalidName(String),
+
     #[error("Storage error: {0}")]
     StorageError(String),
 
     #[error("Invalid percentage: {0}")]
     InvalidPercentage(f64),
+
+    #[error("Audit error: {0}")]
+    AuditError(String),
 }
 
 pub type FeatureFlagResult<T> = Result<T, FeatureFlagError>;
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(FeatureFlagError, {
+    Self::FlagNotFound(_) => (NOT_FOUND, "feature_flags.not_found"),
+    Self::FlagExists(_) => (CONFLICT, "feature_flags.exists"),
+    Self::InvalidName(_) => (UNPROCESSABLE_ENTITY, "feature_flags.invalid_name"),
+    Self::InvalidPercentage(_) => (UNPROCESSABLE_ENTITY, "feature_flags.invalid_percentage"),
+    Self::StorageError(_) => (INTERNAL_SERVER_ERROR, "feature_flags.storage"),
+    Self::AuditError(_) => (INTERNAL_SERVER_ERROR, "feature_flags.audit"),
+});
+
 /// Feature flag configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlagConfig {
@@ -34,15 +104,19 @@ pub struct FlagConfig {
     pub name: String,
 
     /// Is the flag enabled for all users
+    #[serde(default)]
     pub enabled: bool,
 
     /// Percentage rollout (0.0 to 100.0)
+    #[serde(default)]
     pub percentage: Option<f64>,
 
     /// Specific user IDs that have access
+    #[serde(default)]
     pub user_ids: Vec<String>,
 
     /// Specific user groups that have access
+    #[serde(default)]
     pub groups: Vec<String>,
 }
 
@@ -83,6 +157,35 @@ impl FlagConfig {
     }
 }
 
+/// Who a flag is evaluated for
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlagContext {
+    pub user_id: Option<String>,
+    pub tenant_id: Option<String>,
+    pub groups: Vec<String>,
+}
+
+impl FlagContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn user(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    pub fn tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    pub fn group(mut self, group: impl Into<String>) -> Self {
+        self.groups.push(group.into());
+        self
+    }
+}
+
 /// Feature flag storage trait
 #[async_trait]
 pub trait FlagStorage: Send + Sync {
@@ -97,17 +200,54 @@ pub trait FlagStorage: Send + Sync {
 
     /// List all flags
     async fn list(&self) -> FeatureFlagResult<Vec<FlagConfig>>;
+
+    /// Pending schedules of the [`FlagScheduler`]
+    ///
+    /// Schedules are kept next to the flags, so they survive restarts and
+    /// are shared by every instance. The default implementation keeps none,
+    /// and these methods fail.
+    async fn schedules(&self) -> FeatureFlagResult<Vec<RolloutSchedule>> {
+        Err(schedules_unsupported())
+    }
+
+    /// Save the schedule of a flag, replacing any pending one
+    async fn set_schedule(&self, _schedule: RolloutSchedule) -> FeatureFlagResult<()> {
+        Err(schedules_unsupported())
+    }
+
+    /// Delete the schedule of a flag, returning whether there was one
+    async fn delete_schedule(&self, _flag: &str) -> FeatureFlagResult<bool> {
+        Err(schedules_unsupported())
+    }
+
+    /// Take the scheduler lock for `ttl`, returning whether it was free
+    async fn lock_schedules(&self, _owner: &str, _ttl: Duration) -> FeatureFlagResult<bool> {
+        Err(schedules_unsupported())
+    }
+
+    /// Release the scheduler lock if `owner` holds it
+    async fn unlock_schedules(&self, _owner: &str) -> FeatureFlagResult<()> {
+        Err(schedules_unsupported())
+    }
+}
+
+fn schedules_unsupported() -> FeatureFlagError {
+    FeatureFlagError::StorageError("this flag storage doesn't keep schedules".to_string())
 }
 
 /// In-memory flag storage
 pub struct MemoryStorage {
     flags: Arc<RwLock<HashMap<String, FlagConfig>>>,
+    schedules: RwLock<HashMap<String, RolloutSchedule>>,
+    schedule_lock: Mutex<Option<(String, Instant)>>,
 }
 
 impl MemoryStorage {
     pub fn new() -> Self {
         Self {
             flags: Arc::new(RwLock::new(HashMap::new())),
+            schedules: RwLock::new(HashMap::new()),
+            schedule_lock: Mutex::new(None),
         }
     }
 }
@@ -141,24 +281,127 @@ impl FlagStorage for MemoryStorage {
         let flags = self.flags.read().await;
         Ok(flags.values().cloned().collect())
     }
+
+    async fn schedules(&self) -> FeatureFlagResult<Vec<RolloutSchedule>> {
+        Ok(self.schedules.read().await.values().cloned().collect())
+    }
+
+    async fn set_schedule(&self, schedule: RolloutSchedule) -> FeatureFlagResult<()> {
+        let mut schedules = self.schedules.write().await;
+        schedules.insert(schedule.flag.clone(), schedule);
+        Ok(())
+    }
+
+    async fn delete_schedule(&self, flag: &str) -> FeatureFlagResult<bool> {
+        Ok(self.schedules.write().await.remove(flag).is_some())
+    }
+
+    async fn lock_schedules(&self, owner: &str, ttl: Duration) -> FeatureFlagResult<bool> {
+        let mut lock = self.schedule_lock.lock().await;
+        if lock.as_ref().is_some_and(|(_, expires_at)| *expires_at > Instant::now()) {
+            return Ok(false);
+        }
+        *lock = Some((owner.to_string(), Instant::now() + ttl));
+        Ok(true)
+    }
+
+    async fn unlock_schedules(&self, owner: &str) -> FeatureFlagResult<()> {
+        let mut lock = self.schedule_lock.lock().await;
+        if lock.as_ref().is_some_and(|(holder, _)| holder == owner) {
+            *lock = None;
+        }
+        Ok(())
+    }
 }
 
+/// Audit model type used for flag mutations
+pub const AUDIT_MODEL_TYPE: &str = "FeatureFlag";
+
 /// Feature flags manager
+#[derive(Clone)]
 pub struct FeatureFlags {
     storage: Arc<dyn FlagStorage>,
+    audit: Option<Arc<AuditLogger>>,
+    actor: Option<i64>,
+    source: &'static str,
 }
 
 impl FeatureFlags {
     /// Create a new feature flags manager with memory storage
     pub fn new() -> Self {
-        Self {
-            storage: Arc::new(MemoryStorage::new()),
-        }
+        Self::with_storage(Arc::new(MemoryStorage::new()))
     }
 
     /// Create a feature flags manager with custom storage
     pub fn with_storage(storage: Arc<dyn FlagStorage>) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            audit: None,
+            actor: None,
+            source: "api",
+        }
+    }
+
+    /// Record every flag mutation in the given audit log
+    pub fn with_audit(mut self, audit: Arc<AuditLogger>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Handle whose mutations are attributed to `user_id` in the audit log
+    pub fn acting_as(&self, user_id: i64) -> Self {
+        let mut flags = self.clone();
+        flags.actor = Some(user_id);
+        flags
+    }
+
+    /// Handle whose mutations are tagged with `source` in the audit log
+    pub(crate) fn via(&self, source: &'static str) -> Self {
+        let mut flags = self.clone();
+        flags.source = source;
+        flags
+    }
+
+    /// Store a flag and record the change
+    async fn write(&self, config: FlagConfig) -> FeatureFlagResult<()> {
+        if self.audit.is_none() {
+            return self.storage.set(config).await;
+        }
+
+        let old = self.storage.get(&config.name).await?;
+        let name = config.name.clone();
+        let new_values = to_audit_value(&config)?;
+
+        self.storage.set(config).await?;
+
+        let action = if old.is_some() {
+            AuditAction::Updated
+        } else {
+            AuditAction::Created
+        };
+        let entry = AuditEntry::new(AUDIT_MODEL_TYPE, name, action).new_values(new_values);
+        let entry = match old {
+            Some(old) => entry.old_values(to_audit_value(&old)?),
+            None => entry,
+        };
+        self.record(entry).await
+    }
+
+    async fn record(&self, entry: AuditEntry) -> FeatureFlagResult<()> {
+        let Some(audit) = &self.audit else {
+            return Ok(());
+        };
+
+        let entry = entry.metadata("source", self.source);
+        let entry = match self.actor {
+            Some(user_id) => entry.user_id(user_id),
+            None => entry,
+        };
+
+        audit
+            .log(entry)
+            .await
+            .map_err(|e| FeatureFlagError::AuditError(e.to_string()))
     }
 
     /// Check if a flag is enabled for all
@@ -243,16 +486,61 @@ impl FeatureFlags {
         }
     }
 
+    /// Check if a flag is enabled for a full evaluation context
+    ///
+    /// Combines every targeting rule: global enable, user list, groups (the
+    /// tenant counts as group `tenant:<id>`) and percentage rollout, bucketed
+    /// by user id or, for anonymous requests, by tenant id.
+    pub async fn evaluate(&self, flag: &str, context: &FlagContext) -> FeatureFlagResult<bool> {
+        let Some(config) = self.storage.get(flag).await? else {
+            return Ok(false);
+        };
+
+        if config.enabled {
+            return Ok(true);
+        }
+
+        if let Some(user_id) = &context.user_id {
+            if config.user_ids.contains(user_id) {
+                return Ok(true);
+            }
+        }
+
+        let tenant_group = context.tenant_id.as_ref().map(|id| format!("tenant:{}", id));
+        if context
+            .groups
+            .iter()
+            .chain(tenant_group.iter())
+            .any(|group| config.groups.contains(group))
+        {
+            return Ok(true);
+        }
+
+        if let (Some(percentage), Some(key)) = (
+            config.percentage,
+            context.user_id.as_ref().or(context.tenant_id.as_ref()),
+        ) {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            format!("{}:{}", flag, key).hash(&mut hasher);
+            let hash = hasher.finish();
+
+            let user_percentage = (hash % 100) as f64;
+            return Ok(user_percentage < percentage);
+        }
+
+        Ok(false)
+    }
+
     /// Enable a flag for all users
     pub async fn enable(&self, flag: &str) -> FeatureFlagResult<()> {
         let config = FlagConfig::new(flag).enable();
-        self.storage.set(config).await
+        self.write(config).await
     }
 
     /// Disable a flag for all users
     pub async fn disable(&self, flag: &str) -> FeatureFlagResult<()> {
         let config = FlagConfig::new(flag).disable();
-        self.storage.set(config).await
+        self.write(config).await
     }
 
     /// Set percentage rollout
@@ -262,19 +550,19 @@ impl FeatureFlags {
         }
 
         let config = FlagConfig::new(flag).percentage(percentage);
-        self.storage.set(config).await
+        self.write(config).await
     }
 
     /// Enable for specific users
     pub async fn enable_for_users(&self, flag: &str, user_ids: Vec<String>) -> FeatureFlagResult<()> {
         let config = FlagConfig::new(flag).for_users(user_ids);
-        self.storage.set(config).await
+        self.write(config).await
     }
 
     /// Enable for specific groups
     pub async fn enable_for_groups(&self, flag: &str, groups: Vec<String>) -> FeatureFlagResult<()> {
         let config = FlagConfig::new(flag).for_groups(groups);
-        self.storage.set(config).await
+        self.write(config).await
     }
 
     /// Get flag configuration
@@ -284,12 +572,26 @@ impl FeatureFlags {
 
     /// Set flag configuration
     pub async fn set_config(&self, config: FlagConfig) -> FeatureFlagResult<()> {
-        self.storage.set(config).await
+        self.write(config).await
     }
 
     /// Delete a flag
     pub async fn delete(&self, flag: &str) -> FeatureFlagResult<()> {
-        self.storage.delete(flag).await
+        let old = match self.audit {
+            Some(_) => self.storage.get(flag).await?,
+            None => None,
+        };
+
+        self.storage.delete(flag).await?;
+
+        match old {
+            Some(old) => {
+                let entry = AuditEntry::new(AUDIT_MODEL_TYPE, flag, AuditAction::Deleted)
+                    .old_values(to_audit_value(&old)?);
+                self.record(entry).await
+            }
+            None => Ok(()),
+        }
     }
 
     /// List all flags
@@ -304,6 +606,10 @@ impl Default for FeatureFlags {
     }
 }
 
+fn to_audit_value(config: &FlagConfig) -> FeatureFlagResult<serde_json::Value> {
+    serde_json::to_value(config).map_err(|e| FeatureFlagError::AuditError(e.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -430,6 +736,54 @@ mod tests {
         assert!(flags.is_enabled_for_user("complex_flag", "any_user").await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_evaluate_context() {
+        let flags = FeatureFlags::new();
+        flags
+            .set_config(
+                FlagConfig::new("targeted")
+                    .for_users(vec!["alice".to_string()])
+                    .for_groups(vec!["staff".to_string(), "tenant:acme".to_string()]),
+            )
+            .await
+            .unwrap();
+
+        let eval = |ctx: FlagContext| {
+            let flags = flags.clone();
+            async move { flags.evaluate("targeted", &ctx).await.unwrap() }
+        };
+
+        assert!(eval(FlagContext::new().user("alice")).await);
+        assert!(eval(FlagContext::new().user("bob").group("staff")).await);
+        assert!(eval(FlagContext::new().tenant("acme")).await);
+        assert!(!eval(FlagContext::new().user("bob").tenant("globex")).await);
+        assert!(!flags.evaluate("missing", &FlagContext::new()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_mutations_are_audited() {
+        let audit = Arc::new(AuditLogger::new());
+        let flags = FeatureFlags::new().with_audit(audit.clone());
+
+        flags.acting_as(7).enable("audited").await.unwrap();
+        flags.acting_as(7).set_percentage("audited", 20.0).await.unwrap();
+        flags.delete("audited").await.unwrap();
+
+        let entries = audit.for_model(AUDIT_MODEL_TYPE, "audited").await.unwrap();
+        assert_eq!(entries.len(), 3);
+
+        let created = entries.iter().find(|e| e.action == AuditAction::Created).unwrap();
+        assert_eq!(created.user_id, Some(7));
+        assert!(created.old_values.is_none());
+
+        let updated = entries.iter().find(|e| e.action == AuditAction::Updated).unwrap();
+        assert_eq!(updated.old_values.as_ref().unwrap()["enabled"], true);
+        assert_eq!(updated.new_values.as_ref().unwrap()["percentage"], 20.0);
+
+        let deleted = entries.iter().find(|e| e.action == AuditAction::Deleted).unwrap();
+        assert_eq!(deleted.user_id, None);
+    }
+
     #[tokio::test]
     async fn test_consistent_hashing() {
         let flags = FeatureFlags::new();
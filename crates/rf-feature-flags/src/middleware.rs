@@ -0,0 +1,290 @@
+//! Axum integration
+//!
+//! [`FeatureFlagLayer`] evaluates a fixed set of flags once per request and
+//! hands the results to handlers as the [`Flags`] extractor, so a handler
+//! checking the same flag twice sees the same answer. [`require_flag`]
+//! hides whole routes behind a flag. Storage errors never fail a request:
+//! the flag is logged and treated as disabled.
+
+use crate::{FeatureFlags, FlagContext};
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use rf_middleware::{Middleware, MiddlewareService, Next};
+use std::{collections::HashMap, sync::Arc};
+use tower::Layer;
+
+type ContextResolver = Arc<dyn Fn(&Request) -> FlagContext + Send + Sync>;
+
+/// Flags evaluated for the current request
+///
+/// Inserted into request extensions by [`FeatureFlagLayer`] and usable as an
+/// extractor in handlers.
+///
+/// # Example
+///
+/// ```ignore
+/// async fn checkout(flags: Flags) -> &'static str {
+///     if flags.enabled("new_checkout") {
+///         "new checkout"
+///     } else {
+///         "old checkout"
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Flags {
+    values: Arc<HashMap<String, bool>>,
+    flags: FeatureFlags,
+    context: Arc<FlagContext>,
+}
+
+impl Flags {
+    /// Whether a flag evaluated by the layer is enabled
+    ///
+    /// Flags not configured on the layer are reported as disabled; use
+    /// [`Flags::check`] to evaluate them on demand.
+    pub fn enabled(&self, flag: &str) -> bool {
+        self.values.get(flag).copied().unwrap_or(false)
+    }
+
+    /// Whether any flag is enabled, evaluating it if the layer did not
+    pub async fn check(&self, flag: &str) -> bool {
+        if let Some(enabled) = self.values.get(flag) {
+            return *enabled;
+        }
+
+        evaluate_or_disable(&self.flags, flag, &self.context).await
+    }
+
+    /// Context the flags were evaluated for
+    pub fn context(&self) -> &FlagContext {
+        &self.context
+    }
+
+    /// All flags evaluated by the layer
+    pub fn all(&self) -> &HashMap<String, bool> {
+        &self.values
+    }
+}
+
+impl<S> FromRequestParts<S> for Flags
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Flags>().cloned().ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "FeatureFlagLayer is not installed",
+        ))
+    }
+}
+
+/// Failing storage must not take requests down: treat the flag as disabled
+async fn evaluate_or_disable(flags: &FeatureFlags, flag: &str, context: &FlagContext) -> bool {
+    match flags.evaluate(flag, context).await {
+        Ok(enabled) => enabled,
+        Err(e) => {
+            tracing::error!(flag = %flag, error = %e, "Feature flag evaluation failed");
+            false
+        }
+    }
+}
+
+/// Layer evaluating a set of flags for every request
+///
+/// The evaluation context is taken from a [`FlagContext`] request extension
+/// (typically inserted by authentication/tenancy middleware), or from a
+/// custom resolver.
+///
+/// # Example
+///
+/// ```ignore
+/// use rf_feature_flags::*;
+/// use axum::{Router, routing::get};
+///
+/// let app = Router::new()
+///     .route("/checkout", get(checkout))
+///     .route("/beta", get(beta).layer(require_flag("beta_api")))
+///     .layer(FeatureFlagLayer::new(flags, ["new_checkout", "beta_api"]));
+/// ```
+#[derive(Clone)]
+pub struct FeatureFlagLayer {
+    flags: FeatureFlags,
+    names: Arc<Vec<String>>,
+    resolver: ContextResolver,
+}
+
+impl FeatureFlagLayer {
+    /// Evaluate `names` for every request
+    pub fn new<I, N>(flags: FeatureFlags, names: I) -> Self
+    where
+        I: IntoIterator<Item = N>,
+        N: Into<String>,
+    {
+        Self {
+            flags,
+            names: Arc::new(names.into_iter().map(Into::into).collect()),
+            resolver: Arc::new(|req: &Request| {
+                req.extensions().get::<FlagContext>().cloned().unwrap_or_default()
+            }),
+        }
+    }
+
+    /// Set custom context resolution
+    pub fn with_context<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&Request) -> FlagContext + Send + Sync + 'static,
+    {
+        self.resolver = Arc::new(resolver);
+        self
+    }
+}
+
+impl Middleware for FeatureFlagLayer {
+    async fn handle(self, mut req: Request, next: Next) -> Response {
+        let context = (self.resolver)(&req);
+
+        let mut values = HashMap::with_capacity(self.names.len());
+        for name in self.names.iter() {
+            let enabled = evaluate_or_disable(&self.flags, name, &context).await;
+            values.insert(name.clone(), enabled);
+        }
+
+        req.extensions_mut().insert(Flags {
+            values: Arc::new(values),
+            flags: self.flags,
+            context: Arc::new(context),
+        });
+
+        next.run(req).await
+    }
+}
+
+impl<S> Layer<S> for FeatureFlagLayer {
+    type Service = FeatureFlagService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+/// Service created by [`FeatureFlagLayer`]
+pub type FeatureFlagService<S> = MiddlewareService<FeatureFlagLayer, S>;
+
+/// Route guard responding `404 Not Found` unless `flag` is enabled
+///
+/// Requires [`FeatureFlagLayer`] further out; flags it did not evaluate are
+/// checked on demand.
+pub fn require_flag(flag: impl Into<String>) -> RequireFlagLayer {
+    RequireFlagLayer {
+        flag: Arc::from(flag.into()),
+    }
+}
+
+/// Layer created by [`require_flag`]
+#[derive(Clone)]
+pub struct RequireFlagLayer {
+    flag: Arc<str>,
+}
+
+impl Middleware for RequireFlagLayer {
+    async fn handle(self, req: Request, next: Next) -> Response {
+        let enabled = match req.extensions().get::<Flags>() {
+            Some(flags) => flags.check(&self.flag).await,
+            None => {
+                tracing::warn!(flag = %self.flag, "require_flag used without FeatureFlagLayer");
+                false
+            }
+        };
+
+        if enabled {
+            next.run(req).await
+        } else {
+            StatusCode::NOT_FOUND.into_response()
+        }
+    }
+}
+
+impl<S> Layer<S> for RequireFlagLayer {
+    type Service = RequireFlagService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+/// Service created by [`RequireFlagLayer`]
+pub type RequireFlagService<S> = MiddlewareService<RequireFlagLayer, S>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn handler(flags: Flags) -> String {
+        format!("new_checkout={}", flags.enabled("new_checkout"))
+    }
+
+    async fn app() -> Router {
+        let flags = FeatureFlags::new();
+        flags.enable("new_checkout").await.unwrap();
+        flags
+            .enable_for_users("beta_api", vec!["alice".to_string()])
+            .await
+            .unwrap();
+
+        Router::new()
+            .route("/", get(handler))
+            .route("/beta", get(|| async { "beta" }).layer(require_flag("beta_api")))
+            .layer(
+                FeatureFlagLayer::new(flags, ["new_checkout"]).with_context(|req| {
+                    let user = req.headers().get("x-user").and_then(|v| v.to_str().ok());
+                    match user {
+                        Some(user) => FlagContext::new().user(user),
+                        None => FlagContext::new(),
+                    }
+                }),
+            )
+    }
+
+    #[tokio::test]
+    async fn test_extractor_sees_evaluated_flags() {
+        let response = app()
+            .await
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"new_checkout=true");
+    }
+
+    #[tokio::test]
+    async fn test_require_flag_guard() {
+        let anonymous = app()
+            .await
+            .oneshot(Request::builder().uri("/beta").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(anonymous.status(), StatusCode::NOT_FOUND);
+
+        let alice = app()
+            .await
+            .oneshot(
+                Request::builder()
+                    .uri("/beta")
+                    .header("x-user", "alice")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(alice.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,434 @@
+//! Redis-backed flag storage for multi-instance deployments
+
+use crate::{FeatureFlagError, FeatureFlagResult, FlagConfig, FlagStorage, RolloutSchedule};
+use async_trait::async_trait;
+use deadpool_redis::{Config, Pool, Runtime};
+use futures::StreamExt;
+use redis::AsyncCommands;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+
+/// Payload published to invalidate every cached flag
+const INVALIDATE_ALL: &str = "*";
+
+/// Delete the scheduler lock if it is still held by the caller
+const UNLOCK: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// Redis-backed flag storage
+///
+/// Flags live in a single Redis hash, and rollout schedules in a second
+/// one. Lookups are served from a small
+/// in-process cache; every mutation publishes the flag name on a pub/sub
+/// channel so all instances drop their cached copy immediately. The TTL only
+/// bounds staleness if an invalidation message is lost.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_feature_flags::{FeatureFlags, RedisFlagStorage};
+/// use std::sync::Arc;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let storage = RedisFlagStorage::new("redis://localhost").await?;
+/// let flags = FeatureFlags::with_storage(Arc::new(storage));
+///
+/// flags.enable("new_checkout").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RedisFlagStorage {
+    pool: Pool,
+    prefix: String,
+    cache: Arc<LocalCache>,
+    subscriber: tokio::task::JoinHandle<()>,
+}
+
+impl RedisFlagStorage {
+    /// Create new Redis flag storage with the default prefix and a 30s cache TTL
+    ///
+    /// # Arguments
+    ///
+    /// * `redis_url` - Redis connection URL (e.g., "redis://localhost:6379")
+    pub async fn new(redis_url: &str) -> FeatureFlagResult<Self> {
+        Self::with_options(redis_url, "feature_flags", Duration::from_secs(30)).await
+    }
+
+    /// Create new Redis flag storage with a custom key prefix and cache TTL
+    pub async fn with_options(
+        redis_url: &str,
+        prefix: &str,
+        cache_ttl: Duration,
+    ) -> FeatureFlagResult<Self> {
+        let cfg = Config::from_url(redis_url);
+        let pool = cfg
+            .create_pool(Some(Runtime::Tokio1))
+            .map_err(storage_error)?;
+
+        // Test connection
+        let mut conn = pool.get().await.map_err(storage_error)?;
+        redis::cmd("PING")
+            .query_async::<_, String>(&mut conn)
+            .await
+            .map_err(storage_error)?;
+
+        let client = redis::Client::open(redis_url).map_err(storage_error)?;
+        let cache = Arc::new(LocalCache::new(cache_ttl));
+        let subscriber = tokio::spawn(subscribe(
+            client,
+            format!("{}:invalidate", prefix),
+            cache.clone(),
+        ));
+
+        Ok(Self {
+            pool,
+            prefix: prefix.to_string(),
+            cache,
+            subscriber,
+        })
+    }
+
+    /// Redis hash holding all flags
+    fn flags_key(&self) -> String {
+        format!("{}:flags", self.prefix)
+    }
+
+    /// Redis hash holding rollout schedules
+    fn schedules_key(&self) -> String {
+        format!("{}:schedules", self.prefix)
+    }
+
+    /// Key of the scheduler lock
+    fn lock_key(&self) -> String {
+        format!("{}:scheduler_lock", self.prefix)
+    }
+
+    /// Pub/sub channel used for invalidation
+    fn channel(&self) -> String {
+        format!("{}:invalidate", self.prefix)
+    }
+
+    /// Drop the local copy and tell every other instance to do the same
+    async fn invalidate(&self, conn: &mut deadpool_redis::Connection, name: &str) -> FeatureFlagResult<()> {
+        self.cache.remove(name).await;
+
+        let _: () = conn
+            .publish(self.channel(), name)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(())
+    }
+}
+
+impl Drop for RedisFlagStorage {
+    fn drop(&mut self) {
+        self.subscriber.abort();
+    }
+}
+
+#[async_trait]
+impl FlagStorage for RedisFlagStorage {
+    async fn get(&self, name: &str) -> FeatureFlagResult<Option<FlagConfig>> {
+        if let Some(cached) = self.cache.get(name).await {
+            return Ok(cached);
+        }
+
+        // Taken before reading, so an invalidation arriving meanwhile keeps
+        // the possibly stale value out of the cache
+        let generation = self.cache.generation();
+        let mut conn = self.pool.get().await.map_err(storage_error)?;
+        let raw: Option<String> = conn
+            .hget(self.flags_key(), name)
+            .await
+            .map_err(storage_error)?;
+
+        let config = raw
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(storage_error)?;
+
+        // Unknown flags are cached too, so checks for them don't hit Redis
+        self.cache.insert(name, config.clone(), generation).await;
+        Ok(config)
+    }
+
+    async fn set(&self, config: FlagConfig) -> FeatureFlagResult<()> {
+        let mut conn = self.pool.get().await.map_err(storage_error)?;
+        let json = serde_json::to_string(&config).map_err(storage_error)?;
+
+        let _: () = conn
+            .hset(self.flags_key(), &config.name, json)
+            .await
+            .map_err(storage_error)?;
+
+        tracing::debug!(flag = %config.name, "Feature flag updated (Redis)");
+
+        self.invalidate(&mut conn, &config.name).await
+    }
+
+    async fn delete(&self, name: &str) -> FeatureFlagResult<()> {
+        let mut conn = self.pool.get().await.map_err(storage_error)?;
+
+        let _: () = conn
+            .hdel(self.flags_key(), name)
+            .await
+            .map_err(storage_error)?;
+
+        self.invalidate(&mut conn, name).await
+    }
+
+    async fn list(&self) -> FeatureFlagResult<Vec<FlagConfig>> {
+        let mut conn = self.pool.get().await.map_err(storage_error)?;
+        let values: Vec<String> = conn.hvals(self.flags_key()).await.map_err(storage_error)?;
+
+        values
+            .iter()
+            .map(|json| serde_json::from_str(json).map_err(storage_error))
+            .collect()
+    }
+
+    async fn schedules(&self) -> FeatureFlagResult<Vec<RolloutSchedule>> {
+        let mut conn = self.pool.get().await.map_err(storage_error)?;
+        let values: Vec<String> = conn
+            .hvals(self.schedules_key())
+            .await
+            .map_err(storage_error)?;
+
+        values
+            .iter()
+            .map(|json| serde_json::from_str(json).map_err(storage_error))
+            .collect()
+    }
+
+    async fn set_schedule(&self, schedule: RolloutSchedule) -> FeatureFlagResult<()> {
+        let mut conn = self.pool.get().await.map_err(storage_error)?;
+        let json = serde_json::to_string(&schedule).map_err(storage_error)?;
+
+        let _: () = conn
+            .hset(self.schedules_key(), &schedule.flag, json)
+            .await
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    async fn delete_schedule(&self, flag: &str) -> FeatureFlagResult<bool> {
+        let mut conn = self.pool.get().await.map_err(storage_error)?;
+        let deleted: i64 = conn
+            .hdel(self.schedules_key(), flag)
+            .await
+            .map_err(storage_error)?;
+        Ok(deleted == 1)
+    }
+
+    async fn lock_schedules(&self, owner: &str, ttl: Duration) -> FeatureFlagResult<bool> {
+        let mut conn = self.pool.get().await.map_err(storage_error)?;
+        let reply: Option<String> = redis::cmd("SET")
+            .arg(self.lock_key())
+            .arg(owner)
+            .arg("NX")
+            .arg("PX")
+            .arg((ttl.as_millis() as u64).max(1))
+            .query_async(&mut conn)
+            .await
+            .map_err(storage_error)?;
+        Ok(reply.is_some())
+    }
+
+    async fn unlock_schedules(&self, owner: &str) -> FeatureFlagResult<()> {
+        let mut conn = self.pool.get().await.map_err(storage_error)?;
+        let _: i64 = redis::Script::new(UNLOCK)
+            .key(self.lock_key())
+            .arg(owner)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(storage_error)?;
+        Ok(())
+    }
+}
+
+fn storage_error(e: impl std::fmt::Display) -> FeatureFlagError {
+    FeatureFlagError::StorageError(e.to_string())
+}
+
+/// Listen for invalidations, reconnecting on failure
+///
+/// Messages may be missed while disconnected, so the whole cache is dropped
+/// every time the subscription is (re-)established.
+async fn subscribe(client: redis::Client, channel: String, cache: Arc<LocalCache>) {
+    loop {
+        match listen(&client, &channel, &cache).await {
+            Ok(()) => tracing::warn!("Feature flag invalidation stream closed, reconnecting"),
+            Err(e) => tracing::warn!(error = %e, "Feature flag invalidation subscription failed"),
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+async fn listen(
+    client: &redis::Client,
+    channel: &str,
+    cache: &LocalCache,
+) -> redis::RedisResult<()> {
+    let mut pubsub = client.get_async_connection().await?.into_pubsub();
+    pubsub.subscribe(channel).await?;
+    cache.clear().await;
+
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let name: String = msg.get_payload()?;
+        if name == INVALIDATE_ALL {
+            cache.clear().await;
+        } else {
+            cache.remove(&name).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// In-process TTL cache of flag lookups
+pub(crate) struct LocalCache {
+    ttl: Duration,
+    entries: RwLock<HashMap<String, (Option<FlagConfig>, Instant)>>,
+    /// Bumped by every invalidation
+    generation: AtomicU64,
+}
+
+impl LocalCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Current generation, to pass to [`insert`](Self::insert) once the
+    /// flag is read
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Cached lookup result; `Some(None)` means the flag is known not to exist
+    pub(crate) async fn get(&self, name: &str) -> Option<Option<FlagConfig>> {
+        let entries = self.entries.read().await;
+        entries
+            .get(name)
+            .filter(|(_, expires_at)| *expires_at > Instant::now())
+            .map(|(config, _)| config.clone())
+    }
+
+    /// Cache a lookup, unless the cache was invalidated since `generation`
+    pub(crate) async fn insert(&self, name: &str, config: Option<FlagConfig>, generation: u64) {
+        let mut entries = self.entries.write().await;
+        if self.generation() != generation {
+            return;
+        }
+        entries.insert(name.to_string(), (config, Instant::now() + self.ttl));
+    }
+
+    pub(crate) async fn remove(&self, name: &str) {
+        let mut entries = self.entries.write().await;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        entries.remove(name);
+    }
+
+    pub(crate) async fn clear(&self) {
+        let mut entries = self.entries.write().await;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FeatureFlags;
+
+    #[tokio::test]
+    async fn test_local_cache_expiry() {
+        let cache = LocalCache::new(Duration::from_millis(20));
+        let generation = cache.generation();
+        cache
+            .insert("flag", Some(FlagConfig::new("flag").enable()), generation)
+            .await;
+        cache.insert("missing", None, generation).await;
+
+        assert!(cache.get("flag").await.unwrap().unwrap().enabled);
+        assert_eq!(cache.get("missing").await.map(|c| c.is_none()), Some(true));
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(cache.get("flag").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_local_cache_skips_reads_older_than_invalidation() {
+        let cache = LocalCache::new(Duration::from_secs(30));
+
+        // Read from Redis, then invalidated before the result is cached
+        let generation = cache.generation();
+        cache.remove("flag").await;
+        cache
+            .insert("flag", Some(FlagConfig::new("flag").enable()), generation)
+            .await;
+        assert!(cache.get("flag").await.is_none());
+
+        cache
+            .insert("flag", Some(FlagConfig::new("flag").enable()), cache.generation())
+            .await;
+        assert!(cache.get("flag").await.is_some());
+    }
+
+    // Note: These tests require a running Redis instance
+    // Run with: docker run -d -p 6379:6379 redis
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_redis_flag_roundtrip() {
+        let storage = RedisFlagStorage::with_options("redis://localhost", "test_flags", Duration::from_secs(30))
+            .await
+            .unwrap();
+        let flags = FeatureFlags::with_storage(Arc::new(storage));
+
+        flags.enable("redis_flag").await.unwrap();
+        assert!(flags.is_enabled("redis_flag").await.unwrap());
+
+        flags.delete("redis_flag").await.unwrap();
+        assert!(!flags.is_enabled("redis_flag").await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_invalidation_propagates() {
+        let ttl = Duration::from_secs(300);
+        let a = RedisFlagStorage::with_options("redis://localhost", "test_propagation", ttl)
+            .await
+            .unwrap();
+        let b = RedisFlagStorage::with_options("redis://localhost", "test_propagation", ttl)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        a.set(FlagConfig::new("shared").disable()).await.unwrap();
+        assert!(!b.get("shared").await.unwrap().unwrap().enabled);
+
+        // Despite the long TTL, instance b sees the change via pub/sub
+        a.set(FlagConfig::new("shared").enable()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(b.get("shared").await.unwrap().unwrap().enabled);
+
+        a.delete("shared").await.unwrap();
+    }
+}
@@ -0,0 +1,530 @@
+//! Scheduled flag changes and gradual rollouts
+
+use crate::{FeatureFlagError, FeatureFlagResult, FeatureFlags, FlagConfig, FlagStorage};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// A change applied to a flag by the scheduler
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FlagChange {
+    Enable,
+    Disable,
+    Percentage(f64),
+}
+
+/// A change due at a point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RolloutStep {
+    pub at: DateTime<Utc>,
+    pub change: FlagChange,
+}
+
+/// Ordered list of pending changes for one flag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloutSchedule {
+    pub flag: String,
+    pub steps: Vec<RolloutStep>,
+}
+
+impl RolloutSchedule {
+    pub fn new(flag: impl Into<String>) -> Self {
+        Self {
+            flag: flag.into(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Enable the flag for everyone at `at`
+    pub fn enable_at(self, at: DateTime<Utc>) -> Self {
+        self.step(at, FlagChange::Enable)
+    }
+
+    /// Disable the flag at `at`
+    pub fn disable_at(self, at: DateTime<Utc>) -> Self {
+        self.step(at, FlagChange::Disable)
+    }
+
+    /// Set the rollout percentage at `at`
+    pub fn percentage_at(self, at: DateTime<Utc>, percentage: f64) -> Self {
+        self.step(at, FlagChange::Percentage(percentage))
+    }
+
+    /// Gradual ramp: the first stage applies at `start`, the last at `start + over`,
+    /// and the stages in between are spread evenly
+    ///
+    /// ```
+    /// use rf_feature_flags::RolloutSchedule;
+    /// use std::time::Duration;
+    ///
+    /// // 10% now, 50% in 4 hours, 100% in 8 hours
+    /// let schedule = RolloutSchedule::new("new_checkout")
+    ///     .ramp(chrono::Utc::now(), &[10.0, 50.0, 100.0], Duration::from_secs(8 * 3600));
+    /// assert_eq!(schedule.steps.len(), 3);
+    /// ```
+    pub fn ramp(mut self, start: DateTime<Utc>, stages: &[f64], over: Duration) -> Self {
+        let intervals = stages.len().saturating_sub(1).max(1) as u32;
+        let step = chrono::Duration::from_std(over / intervals).unwrap_or_else(|_| chrono::Duration::zero());
+
+        for (i, percentage) in stages.iter().enumerate() {
+            self = self.percentage_at(start + step * i as i32, *percentage);
+        }
+        self
+    }
+
+    fn step(mut self, at: DateTime<Utc>, change: FlagChange) -> Self {
+        self.steps.push(RolloutStep { at, change });
+        self
+    }
+}
+
+/// Applies scheduled flag changes when they become due
+///
+/// Changes go through [`FeatureFlags`], so they are audited like any other
+/// mutation (tagged with source `scheduler`). Schedules are kept in the flag
+/// storage, see [`FlagStorage::schedules`], so they survive restarts. Every
+/// instance may run a scheduler: [`run_due`](Self::run_due) takes a lock in
+/// the storage, so one instance at a time applies the due steps. Adding and
+/// cancelling schedules takes the same lock, so a run never writes back a
+/// schedule changed while it was applying it.
+pub struct FlagScheduler {
+    flags: FeatureFlags,
+    owner: String,
+}
+
+/// How long a run may hold the scheduler lock, should it never release it
+const LOCK_TTL: Duration = Duration::from_secs(60);
+
+/// Pause between attempts to take the lock from a running scheduler
+const LOCK_RETRY: Duration = Duration::from_millis(50);
+
+static NEXT_OWNER: AtomicU64 = AtomicU64::new(0);
+
+impl FlagScheduler {
+    pub fn new(flags: &FeatureFlags) -> Self {
+        let owner = format!(
+            "{}-{}-{}",
+            std::process::id(),
+            Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+            NEXT_OWNER.fetch_add(1, Ordering::Relaxed)
+        );
+        Self {
+            flags: flags.via("scheduler"),
+            owner,
+        }
+    }
+
+    fn storage(&self) -> &dyn FlagStorage {
+        self.flags.storage.as_ref()
+    }
+
+    /// Add a schedule, replacing any pending schedule for the same flag
+    ///
+    /// Waits for a running scheduler to finish.
+    pub async fn schedule(&self, mut schedule: RolloutSchedule) -> FeatureFlagResult<()> {
+        for step in &schedule.steps {
+            if let FlagChange::Percentage(percentage) = step.change {
+                if !(0.0..=100.0).contains(&percentage) {
+                    return Err(FeatureFlagError::InvalidPercentage(percentage));
+                }
+            }
+        }
+        schedule.steps.sort_by_key(|step| step.at);
+        self.locked(self.storage().set_schedule(schedule)).await
+    }
+
+    /// Cancel the pending schedule for a flag
+    ///
+    /// Waits for a running scheduler to finish.
+    pub async fn cancel(&self, flag: &str) -> FeatureFlagResult<bool> {
+        self.locked(self.storage().delete_schedule(flag)).await
+    }
+
+    /// Schedules with their remaining steps, by flag name
+    pub async fn pending(&self) -> FeatureFlagResult<Vec<RolloutSchedule>> {
+        let mut schedules = self.storage().schedules().await?;
+        schedules.sort_by(|a, b| a.flag.cmp(&b.flag));
+        Ok(schedules)
+    }
+
+    /// Apply every step due at `now`; returns the number of applied steps
+    ///
+    /// Returns 0 without applying anything while another scheduler holds
+    /// the lock. A step that fails stays pending, along with the rest of its
+    /// schedule, and is retried on the next run. Other schedules still run;
+    /// the first failure is returned once they have.
+    pub async fn run_due(&self, now: DateTime<Utc>) -> FeatureFlagResult<usize> {
+        if !self.storage().lock_schedules(&self.owner, LOCK_TTL).await? {
+            tracing::debug!("Flag schedules are being run by another scheduler");
+            return Ok(0);
+        }
+
+        let result = self.apply_due(now).await;
+        self.unlock().await;
+        result
+    }
+
+    /// Run `change` holding the scheduler lock, waiting up to the lock's
+    /// TTL for another holder to release it
+    async fn locked<T>(
+        &self,
+        change: impl std::future::Future<Output = FeatureFlagResult<T>>,
+    ) -> FeatureFlagResult<T> {
+        let deadline = tokio::time::Instant::now() + LOCK_TTL;
+        while !self.storage().lock_schedules(&self.owner, LOCK_TTL).await? {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(FeatureFlagError::StorageError(
+                    "flag schedules are locked by a running scheduler".to_string(),
+                ));
+            }
+            tokio::time::sleep(LOCK_RETRY).await;
+        }
+
+        let result = change.await;
+        self.unlock().await;
+        result
+    }
+
+    async fn unlock(&self) {
+        // The lock expires on its own should this fail
+        if let Err(e) = self.storage().unlock_schedules(&self.owner).await {
+            tracing::warn!(error = %e, "Failed to release the flag scheduler lock");
+        }
+    }
+
+    async fn apply_due(&self, now: DateTime<Utc>) -> FeatureFlagResult<usize> {
+        let mut applied = 0;
+        let mut failure = None;
+
+        for mut schedule in self.storage().schedules().await? {
+            let pending = schedule.steps.len();
+            while let Some(step) = schedule.steps.first().filter(|step| step.at <= now) {
+                if let Err(e) = self.apply(&schedule.flag, &step.change).await {
+                    tracing::warn!(flag = %schedule.flag, error = %e, "Scheduled flag change failed");
+                    failure.get_or_insert(e);
+                    break;
+                }
+                schedule.steps.remove(0);
+                applied += 1;
+            }
+            if schedule.steps.len() == pending {
+                continue;
+            }
+
+            let saved = if schedule.steps.is_empty() {
+                self.storage()
+                    .delete_schedule(&schedule.flag)
+                    .await
+                    .map(|_| ())
+            } else {
+                self.storage().set_schedule(schedule.clone()).await
+            };
+            if let Err(e) = saved {
+                tracing::warn!(flag = %schedule.flag, error = %e, "Failed to save flag schedule");
+                failure.get_or_insert(e);
+            }
+        }
+
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(applied),
+        }
+    }
+
+    async fn apply(&self, flag: &str, change: &FlagChange) -> FeatureFlagResult<()> {
+        let mut config = self
+            .flags
+            .get_config(flag)
+            .await?
+            .unwrap_or_else(|| FlagConfig::new(flag));
+
+        match change {
+            FlagChange::Enable => config.enabled = true,
+            FlagChange::Disable => config.enabled = false,
+            FlagChange::Percentage(percentage) => config.percentage = Some(*percentage),
+        }
+
+        tracing::info!(flag = %flag, change = ?change, "Applying scheduled flag change");
+        self.flags.set_config(config).await
+    }
+
+    /// Run due steps every `interval` in the background
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                // Failures are logged per schedule
+                let _ = self.run_due(Utc::now()).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStorage;
+    use async_trait::async_trait;
+
+    /// Storage refusing to write one flag
+    struct ReadOnlyFlag(MemoryStorage, &'static str);
+
+    #[async_trait]
+    impl FlagStorage for ReadOnlyFlag {
+        async fn get(&self, name: &str) -> FeatureFlagResult<Option<FlagConfig>> {
+            self.0.get(name).await
+        }
+
+        async fn set(&self, config: FlagConfig) -> FeatureFlagResult<()> {
+            if config.name == self.1 {
+                return Err(FeatureFlagError::StorageError("read only".into()));
+            }
+            self.0.set(config).await
+        }
+
+        async fn delete(&self, name: &str) -> FeatureFlagResult<()> {
+            self.0.delete(name).await
+        }
+
+        async fn list(&self) -> FeatureFlagResult<Vec<FlagConfig>> {
+            self.0.list().await
+        }
+
+        async fn schedules(&self) -> FeatureFlagResult<Vec<RolloutSchedule>> {
+            self.0.schedules().await
+        }
+
+        async fn set_schedule(&self, schedule: RolloutSchedule) -> FeatureFlagResult<()> {
+            self.0.set_schedule(schedule).await
+        }
+
+        async fn delete_schedule(&self, flag: &str) -> FeatureFlagResult<bool> {
+            self.0.delete_schedule(flag).await
+        }
+
+        async fn lock_schedules(&self, owner: &str, ttl: Duration) -> FeatureFlagResult<bool> {
+            self.0.lock_schedules(owner, ttl).await
+        }
+
+        async fn unlock_schedules(&self, owner: &str) -> FeatureFlagResult<()> {
+            self.0.unlock_schedules(owner).await
+        }
+    }
+
+    /// Storage taking a while to write flags, so runs can be interleaved
+    struct SlowWrites(MemoryStorage);
+
+    #[async_trait]
+    impl FlagStorage for SlowWrites {
+        async fn get(&self, name: &str) -> FeatureFlagResult<Option<FlagConfig>> {
+            self.0.get(name).await
+        }
+
+        async fn set(&self, config: FlagConfig) -> FeatureFlagResult<()> {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.0.set(config).await
+        }
+
+        async fn delete(&self, name: &str) -> FeatureFlagResult<()> {
+            self.0.delete(name).await
+        }
+
+        async fn list(&self) -> FeatureFlagResult<Vec<FlagConfig>> {
+            self.0.list().await
+        }
+
+        async fn schedules(&self) -> FeatureFlagResult<Vec<RolloutSchedule>> {
+            self.0.schedules().await
+        }
+
+        async fn set_schedule(&self, schedule: RolloutSchedule) -> FeatureFlagResult<()> {
+            self.0.set_schedule(schedule).await
+        }
+
+        async fn delete_schedule(&self, flag: &str) -> FeatureFlagResult<bool> {
+            self.0.delete_schedule(flag).await
+        }
+
+        async fn lock_schedules(&self, owner: &str, ttl: Duration) -> FeatureFlagResult<bool> {
+            self.0.lock_schedules(owner, ttl).await
+        }
+
+        async fn unlock_schedules(&self, owner: &str) -> FeatureFlagResult<()> {
+            self.0.unlock_schedules(owner).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enable_and_disable_at() {
+        let flags = FeatureFlags::new();
+        let scheduler = FlagScheduler::new(&flags);
+        let now = Utc::now();
+
+        scheduler
+            .schedule(
+                RolloutSchedule::new("launch")
+                    .enable_at(now + chrono::Duration::hours(1))
+                    .disable_at(now + chrono::Duration::hours(2)),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(scheduler.run_due(now).await.unwrap(), 0);
+        assert!(!flags.is_enabled("launch").await.unwrap());
+
+        assert_eq!(scheduler.run_due(now + chrono::Duration::minutes(61)).await.unwrap(), 1);
+        assert!(flags.is_enabled("launch").await.unwrap());
+
+        assert_eq!(scheduler.run_due(now + chrono::Duration::hours(3)).await.unwrap(), 1);
+        assert!(!flags.is_enabled("launch").await.unwrap());
+        assert!(scheduler.pending().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ramp_schedule() {
+        let flags = FeatureFlags::new();
+        let scheduler = FlagScheduler::new(&flags);
+        let start = Utc::now();
+
+        scheduler
+            .schedule(RolloutSchedule::new("ramp").ramp(
+                start,
+                &[10.0, 50.0, 100.0],
+                Duration::from_secs(8 * 3600),
+            ))
+            .await
+            .unwrap();
+
+        scheduler.run_due(start).await.unwrap();
+        assert_eq!(flags.get_config("ramp").await.unwrap().unwrap().percentage, Some(10.0));
+
+        scheduler.run_due(start + chrono::Duration::hours(4)).await.unwrap();
+        assert_eq!(flags.get_config("ramp").await.unwrap().unwrap().percentage, Some(50.0));
+
+        scheduler.run_due(start + chrono::Duration::hours(8)).await.unwrap();
+        assert_eq!(flags.get_config("ramp").await.unwrap().unwrap().percentage, Some(100.0));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_and_cancelled_schedules() {
+        let flags = FeatureFlags::new();
+        let scheduler = FlagScheduler::new(&flags);
+        let now = Utc::now();
+
+        let invalid = RolloutSchedule::new("bad").percentage_at(now, 120.0);
+        assert!(scheduler.schedule(invalid).await.is_err());
+
+        scheduler
+            .schedule(RolloutSchedule::new("later").enable_at(now))
+            .await
+            .unwrap();
+        assert!(scheduler.cancel("later").await.unwrap());
+        assert_eq!(scheduler.run_due(now).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_during_run() {
+        let flags = FeatureFlags::with_storage(Arc::new(SlowWrites(MemoryStorage::new())));
+        let scheduler = FlagScheduler::new(&flags);
+        let now = Utc::now();
+        scheduler
+            .schedule(
+                RolloutSchedule::new("launch")
+                    .enable_at(now)
+                    .disable_at(now + chrono::Duration::hours(1)),
+            )
+            .await
+            .unwrap();
+
+        // Cancelled while the run applies the first step
+        let cancel = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            scheduler.cancel("launch").await
+        };
+        let (applied, cancelled) = tokio::join!(scheduler.run_due(now), cancel);
+        assert_eq!(applied.unwrap(), 1);
+        assert!(cancelled.unwrap());
+
+        // The run didn't write the remaining step back
+        assert!(scheduler.pending().await.unwrap().is_empty());
+        assert_eq!(
+            scheduler
+                .run_due(now + chrono::Duration::hours(2))
+                .await
+                .unwrap(),
+            0
+        );
+        assert!(flags.is_enabled("launch").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_failed_steps_stay_pending() {
+        let flags =
+            FeatureFlags::with_storage(Arc::new(ReadOnlyFlag(MemoryStorage::new(), "broken")));
+        let scheduler = FlagScheduler::new(&flags);
+        let now = Utc::now();
+
+        for flag in ["broken", "launch"] {
+            scheduler
+                .schedule(RolloutSchedule::new(flag).enable_at(now))
+                .await
+                .unwrap();
+        }
+
+        assert!(scheduler.run_due(now).await.is_err());
+        // The other schedule still ran
+        assert!(flags.is_enabled("launch").await.unwrap());
+
+        let pending = scheduler.pending().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].flag, "broken");
+        assert_eq!(pending[0].steps.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_schedules_are_shared_through_storage() {
+        let storage = Arc::new(MemoryStorage::new());
+        let flags = FeatureFlags::with_storage(storage.clone());
+        let now = Utc::now();
+
+        FlagScheduler::new(&flags)
+            .schedule(RolloutSchedule::new("launch").enable_at(now))
+            .await
+            .unwrap();
+
+        // A scheduler started later, e.g. after a restart, picks it up
+        let scheduler = FlagScheduler::new(&flags);
+        assert_eq!(scheduler.pending().await.unwrap().len(), 1);
+
+        // Another instance is running the schedules
+        assert!(storage.lock_schedules("other", LOCK_TTL).await.unwrap());
+        assert_eq!(scheduler.run_due(now).await.unwrap(), 0);
+        assert!(!flags.is_enabled("launch").await.unwrap());
+
+        storage.unlock_schedules("other").await.unwrap();
+        assert_eq!(scheduler.run_due(now).await.unwrap(), 1);
+        assert!(flags.is_enabled("launch").await.unwrap());
+        assert!(scheduler.pending().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_only_storage_keeps_no_schedules() {
+        let path = std::env::temp_dir()
+            .join(format!("rf-flags-schedules-{}.toml", std::process::id()));
+        std::fs::write(&path, "").unwrap();
+        let storage =
+            crate::FileFlagStorage::open_with_prefix(&path, "RF_TEST_SCHEDULES_").unwrap();
+        let scheduler = FlagScheduler::new(&FeatureFlags::with_storage(Arc::new(storage)));
+
+        let schedule = RolloutSchedule::new("launch").enable_at(Utc::now());
+        assert!(scheduler.schedule(schedule).await.is_err());
+        assert!(scheduler.run_due(Utc::now()).await.is_err());
+    }
+}
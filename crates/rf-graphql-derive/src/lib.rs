@@ -0,0 +1,195 @@
+//! Derive macros for rf-graphql
+//!
+//! Use them through rf-graphql's `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, LitStr, Type};
+
+/// Generate sqlx batch loaders for a row struct
+///
+/// The struct needs `Clone` and `sqlx::FromRow`. For the key field (`id`
+/// unless a field is marked `#[loader(key)]`) it generates `{Struct}Loader`,
+/// loading one row per key. For every field marked
+/// `#[loader(foreign_key)]` it generates `{Struct}By{Field}Loader`, loading
+/// all rows per key.
+///
+/// ```ignore
+/// #[derive(Clone, sqlx::FromRow, BatchLoader)]
+/// #[loader(table = "comments", order_by = "created_at")]
+/// struct Comment {
+///     id: i64,
+///     #[loader(foreign_key)]
+///     post_id: i64,
+///     body: String,
+/// }
+///
+/// let loaders = loader_context()
+///     .loader(CommentLoader::new(pool.clone()))
+///     .loader(CommentByPostIdLoader::new(pool));
+/// ```
+#[proc_macro_derive(BatchLoader, attributes(loader))]
+pub fn derive_batch_loader(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    batch_loader(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn batch_loader(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let mut table = None;
+    let mut order_by = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("loader")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("order_by") {
+                order_by = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else {
+                return Err(meta.error("expected `table` or `order_by`"));
+            }
+            Ok(())
+        })?;
+    }
+    let table = table.ok_or_else(|| {
+        Error::new(
+            Span::call_site(),
+            "missing `#[loader(table = \"...\")]` on the struct",
+        )
+    })?;
+    let mut select = format!("SELECT * FROM {}", table);
+    if let Some(order_by) = order_by {
+        select = format!("{} ORDER BY {}", select, order_by);
+    }
+
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(&input, "BatchLoader needs a struct"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input,
+            "BatchLoader needs a struct with named fields",
+        ));
+    };
+
+    let mut key = None;
+    let mut foreign_keys = Vec::new();
+    for field in &fields.named {
+        let ident = field.ident.clone().expect("named field");
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("loader")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") {
+                    key = Some((ident.clone(), field.ty.clone()));
+                } else if meta.path.is_ident("foreign_key") {
+                    foreign_keys.push((ident.clone(), field.ty.clone()));
+                } else {
+                    return Err(meta.error("expected `key` or `foreign_key`"));
+                }
+                Ok(())
+            })?;
+        }
+    }
+    let key = key.or_else(|| {
+        fields
+            .named
+            .iter()
+            .find(|field| field.ident.as_ref().is_some_and(|ident| ident == "id"))
+            .map(|field| (field.ident.clone().expect("named field"), field.ty.clone()))
+    });
+
+    let name = &input.ident;
+    let mut loaders = Vec::new();
+    if let Some((field, ty)) = key {
+        let loader = format_ident!("{}Loader", name);
+        loaders.push(loader_struct(
+            &input,
+            &loader,
+            &field,
+            &ty,
+            &select,
+            quote!(::rf_graphql::SqlLoader),
+            quote!(#name),
+        ));
+    }
+    for (field, ty) in foreign_keys {
+        let loader = format_ident!("{}By{}Loader", name, pascal_case(&field));
+        loaders.push(loader_struct(
+            &input,
+            &loader,
+            &field,
+            &ty,
+            &select,
+            quote!(::rf_graphql::SqlGroupLoader),
+            quote!(::std::vec::Vec<#name>),
+        ));
+    }
+    if loaders.is_empty() {
+        return Err(Error::new_spanned(
+            &input,
+            "BatchLoader needs an `id` field, `#[loader(key)]` or `#[loader(foreign_key)]`",
+        ));
+    }
+
+    Ok(quote!(#(#loaders)*))
+}
+
+fn loader_struct(
+    input: &DeriveInput,
+    loader: &Ident,
+    field: &Ident,
+    ty: &Type,
+    select: &str,
+    inner: TokenStream2,
+    value: TokenStream2,
+) -> TokenStream2 {
+    let vis = &input.vis;
+    let name = &input.ident;
+    let doc = format!("Loads `{}` rows by `{}`", name, field);
+    let column = field.to_string();
+
+    quote! {
+        #[doc = #doc]
+        #vis struct #loader(#inner<#ty, #name>);
+
+        impl #loader {
+            #vis fn new(pool: ::rf_graphql::sqlx::PgPool) -> Self {
+                Self(#inner::new(pool, #select, #column, |row: &#name| {
+                    ::std::clone::Clone::clone(&row.#field)
+                }))
+            }
+        }
+
+        impl ::rf_graphql::dataloader::Loader<#ty> for #loader {
+            type Value = #value;
+            type Error = ::std::sync::Arc<::rf_graphql::sqlx::Error>;
+
+            fn load(
+                &self,
+                keys: &[#ty],
+            ) -> impl ::std::future::Future<
+                Output = ::std::result::Result<
+                    ::std::collections::HashMap<#ty, Self::Value>,
+                    Self::Error,
+                >,
+            > + ::std::marker::Send {
+                ::rf_graphql::dataloader::Loader::load(&self.0, keys)
+            }
+        }
+    }
+}
+
+fn pascal_case(ident: &Ident) -> String {
+    ident
+        .to_string()
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
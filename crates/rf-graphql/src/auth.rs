@@ -0,0 +1,333 @@
+//! Authentication and authorization for resolvers
+//!
+//! The current user is an [`Identity`] in the request data. Routers from
+//! this crate take it from the request extensions, where the application's
+//! auth middleware put it, or resolve a bearer token with an
+//! [`Authenticator`] (see [`graphql_router_with_auth`](crate::graphql_router_with_auth)).
+//!
+//! Fields and mutations are protected with guards:
+//!
+//! ```
+//! use rf_graphql::{Authenticated, OwnerGuard, PermissionGuard, RoleGuard};
+//! use async_graphql::*;
+//!
+//! struct Mutation;
+//!
+//! #[Object]
+//! impl Mutation {
+//!     #[graphql(guard = "Authenticated")]
+//!     async fn like(&self, post_id: ID) -> bool {
+//!         true
+//!     }
+//!
+//!     #[graphql(guard = "RoleGuard::new(\"admin\").or(PermissionGuard::new(\"posts.delete\"))")]
+//!     async fn delete_post(&self, id: ID) -> bool {
+//!         true
+//!     }
+//!
+//!     #[graphql(guard = "OwnerGuard::new(user_id.to_string()).or_role(\"admin\")")]
+//!     async fn update_profile(&self, user_id: ID, name: String) -> bool {
+//!         true
+//!     }
+//! }
+//! ```
+//!
+//! Failed checks are errors with the `code` extension `UNAUTHENTICATED` or
+//! `FORBIDDEN`.
+
+use async_graphql::{Context, ErrorExtensions, Guard, Result};
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+/// The authenticated user of a request
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Identity {
+    pub id: String,
+    pub roles: HashSet<String>,
+    pub permissions: HashSet<String>,
+    /// Further claims of the token, e.g. the tenant
+    pub claims: HashMap<String, serde_json::Value>,
+}
+
+impl Identity {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn role(mut self, role: impl Into<String>) -> Self {
+        self.roles.insert(role.into());
+        self
+    }
+
+    pub fn permission(mut self, permission: impl Into<String>) -> Self {
+        self.permissions.insert(permission.into());
+        self
+    }
+
+    pub fn claim(mut self, name: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        self.claims.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.contains(role)
+    }
+
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.contains(permission)
+    }
+}
+
+/// Resolves bearer tokens to identities
+#[async_trait]
+pub trait Authenticator: Send + Sync + 'static {
+    /// The identity of `token`; fail with [`AuthError::Unauthenticated`]
+    /// for invalid or expired tokens
+    async fn authenticate(&self, token: &str) -> Result<Identity, AuthError>;
+}
+
+/// Authentication and authorization failures
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum AuthError {
+    #[error("Authentication required")]
+    Unauthenticated,
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+}
+
+impl AuthError {
+    /// Value of the `code` error extension
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::Unauthenticated => "UNAUTHENTICATED",
+            AuthError::Forbidden(_) => "FORBIDDEN",
+        }
+    }
+}
+
+impl ErrorExtensions for AuthError {
+    fn extend(&self) -> async_graphql::Error {
+        async_graphql::Error::new(self.to_string()).extend_with(|_, e| e.set("code", self.code()))
+    }
+}
+
+/// Authorization checks inside resolvers, e.g. after loading a resource
+pub trait ContextAuthExt {
+    /// The current user, if authenticated
+    fn identity(&self) -> Option<&Identity>;
+
+    /// The current user, or an `UNAUTHENTICATED` error
+    fn require_identity(&self) -> Result<&Identity>;
+
+    fn require_role(&self, role: &str) -> Result<&Identity>;
+
+    fn require_permission(&self, permission: &str) -> Result<&Identity>;
+
+    /// The current user if they are `owner_id`
+    fn require_owner(&self, owner_id: &str) -> Result<&Identity>;
+}
+
+impl ContextAuthExt for Context<'_> {
+    fn identity(&self) -> Option<&Identity> {
+        self.data_opt::<Identity>()
+    }
+
+    fn require_identity(&self) -> Result<&Identity> {
+        self.identity()
+            .ok_or_else(|| AuthError::Unauthenticated.extend())
+    }
+
+    fn require_role(&self, role: &str) -> Result<&Identity> {
+        let identity = self.require_identity()?;
+        if !identity.has_role(role) {
+            return Err(AuthError::Forbidden(format!("requires role {}", role)).extend());
+        }
+        Ok(identity)
+    }
+
+    fn require_permission(&self, permission: &str) -> Result<&Identity> {
+        let identity = self.require_identity()?;
+        if !identity.has_permission(permission) {
+            return Err(
+                AuthError::Forbidden(format!("requires permission {}", permission)).extend(),
+            );
+        }
+        Ok(identity)
+    }
+
+    fn require_owner(&self, owner_id: &str) -> Result<&Identity> {
+        let identity = self.require_identity()?;
+        if identity.id != owner_id {
+            return Err(AuthError::Forbidden("not the owner".into()).extend());
+        }
+        Ok(identity)
+    }
+}
+
+/// Requires an authenticated user
+pub struct Authenticated;
+
+impl Guard for Authenticated {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        ctx.require_identity().map(|_| ())
+    }
+}
+
+/// Requires a role
+pub struct RoleGuard {
+    role: String,
+}
+
+impl RoleGuard {
+    pub fn new(role: impl Into<String>) -> Self {
+        Self { role: role.into() }
+    }
+}
+
+impl Guard for RoleGuard {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        ctx.require_role(&self.role).map(|_| ())
+    }
+}
+
+/// Requires a permission
+pub struct PermissionGuard {
+    permission: String,
+}
+
+impl PermissionGuard {
+    pub fn new(permission: impl Into<String>) -> Self {
+        Self {
+            permission: permission.into(),
+        }
+    }
+}
+
+impl Guard for PermissionGuard {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        ctx.require_permission(&self.permission).map(|_| ())
+    }
+}
+
+/// Requires the user to own the resource, e.g. from a field argument
+pub struct OwnerGuard {
+    owner_id: String,
+    bypass_roles: Vec<String>,
+}
+
+impl OwnerGuard {
+    pub fn new(owner_id: impl Into<String>) -> Self {
+        Self {
+            owner_id: owner_id.into(),
+            bypass_roles: Vec::new(),
+        }
+    }
+
+    /// Let users with `role` pass regardless of ownership
+    pub fn or_role(mut self, role: impl Into<String>) -> Self {
+        self.bypass_roles.push(role.into());
+        self
+    }
+}
+
+impl Guard for OwnerGuard {
+    async fn check(&self, ctx: &Context<'_>) -> Result<()> {
+        let identity = ctx.require_identity()?;
+        if self.bypass_roles.iter().any(|role| identity.has_role(role)) {
+            return Ok(());
+        }
+        ctx.require_owner(&self.owner_id).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptySubscription, Object, Request, Schema, ID};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        #[graphql(guard = "Authenticated")]
+        async fn me(&self, ctx: &Context<'_>) -> Result<String> {
+            Ok(ctx.require_identity()?.id.clone())
+        }
+    }
+
+    struct Mutation;
+
+    #[Object]
+    impl Mutation {
+        #[graphql(guard = "RoleGuard::new(\"admin\").or(PermissionGuard::new(\"posts.delete\"))")]
+        async fn delete_post(&self, _id: ID) -> bool {
+            true
+        }
+
+        #[graphql(guard = "OwnerGuard::new(user_id.to_string()).or_role(\"admin\")")]
+        async fn rename(&self, user_id: ID, name: String) -> String {
+            format!("{}: {}", user_id.as_str(), name)
+        }
+    }
+
+    async fn execute(query: &str, identity: Option<Identity>) -> async_graphql::Response {
+        let schema = Schema::new(Query, Mutation, EmptySubscription);
+        let mut request = Request::new(query);
+        if let Some(identity) = identity {
+            request = request.data(identity);
+        }
+        schema.execute(request).await
+    }
+
+    fn code(response: &async_graphql::Response) -> String {
+        let extensions = response.errors[0].extensions.as_ref().unwrap();
+        extensions.get("code").unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_authenticated() {
+        let response = execute("{ me }", None).await;
+        assert_eq!(code(&response), "\"UNAUTHENTICATED\"");
+
+        let response = execute("{ me }", Some(Identity::new("42"))).await;
+        assert!(response.errors.is_empty());
+        assert_eq!(response.data.into_json().unwrap()["me"], "42");
+    }
+
+    #[tokio::test]
+    async fn test_roles_and_permissions() {
+        let mutation = r#"mutation { deletePost(id: "1") }"#;
+
+        let response = execute(mutation, Some(Identity::new("1"))).await;
+        assert_eq!(code(&response), "\"FORBIDDEN\"");
+
+        let response = execute(mutation, Some(Identity::new("1").role("admin"))).await;
+        assert!(response.errors.is_empty());
+
+        let editor = Identity::new("2").permission("posts.delete");
+        assert!(execute(mutation, Some(editor)).await.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_owner_guard() {
+        let mutation = r#"mutation { rename(userId: "7", name: "Ann") }"#;
+
+        assert!(execute(mutation, Some(Identity::new("7")))
+            .await
+            .errors
+            .is_empty());
+        let response = execute(mutation, Some(Identity::new("8"))).await;
+        assert_eq!(code(&response), "\"FORBIDDEN\"");
+        assert!(execute(mutation, Some(Identity::new("8").role("admin")))
+            .await
+            .errors
+            .is_empty());
+        let response = execute(mutation, None).await;
+        assert_eq!(code(&response), "\"UNAUTHENTICATED\"");
+    }
+}
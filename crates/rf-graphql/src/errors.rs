@@ -0,0 +1,373 @@
+//! Mapping domain errors to GraphQL errors
+//!
+//! A [`Problem`] knows its `code` extension and any structured details.
+//! Resolvers convert with [`ProblemResultExt::problem`]:
+//!
+//! ```ignore
+//! async fn tenant(&self, ctx: &Context<'_>, id: String) -> Result<Tenant> {
+//!     manager.get(&id).await.problem()
+//! }
+//! ```
+//!
+//! which produces
+//!
+//! ```json
+//! {"message": "Tenant not found", "path": ["tenant"], "extensions": {"code": "NOT_FOUND"}}
+//! ```
+//!
+//! Domain errors of other crates are mapped behind the `admin` and
+//! `tenancy` features; rf-validation maps its errors behind its own
+//! `graphql` feature. [`ErrorMasking`] hides the messages of internal
+//! errors from clients.
+
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute, NextSubscribe},
+    futures_util::{stream::BoxStream, StreamExt},
+    ErrorExtensionValues, ErrorExtensions, Response, ServerError,
+};
+use std::sync::Arc;
+
+use crate::AuthError;
+
+/// `code` of errors whose message must not reach clients
+pub const INTERNAL_SERVER_ERROR: &str = "INTERNAL_SERVER_ERROR";
+
+/// An error with a GraphQL representation
+pub trait Problem {
+    /// Value of the `code` extension, e.g. `NOT_FOUND`
+    fn code(&self) -> &'static str;
+
+    fn message(&self) -> String;
+
+    /// Structured extensions besides `code`
+    fn details(&self, _extensions: &mut ErrorExtensionValues) {}
+
+    fn to_graphql_error(&self) -> async_graphql::Error {
+        async_graphql::Error::new(self.message()).extend_with(|_, extensions| {
+            extensions.set("code", self.code());
+            self.details(extensions);
+        })
+    }
+}
+
+/// Convert the error of a result into a GraphQL error
+pub trait ProblemResultExt<T> {
+    fn problem(self) -> async_graphql::Result<T>;
+}
+
+impl<T, E: Problem> ProblemResultExt<T> for Result<T, E> {
+    fn problem(self) -> async_graphql::Result<T> {
+        self.map_err(|e| e.to_graphql_error())
+    }
+}
+
+impl Problem for AuthError {
+    fn code(&self) -> &'static str {
+        AuthError::code(self)
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(feature = "admin")]
+impl Problem for rf_admin::AdminError {
+    fn code(&self) -> &'static str {
+        use rf_admin::AdminError;
+
+        match self {
+            AdminError::ResourceNotFound(_) => "NOT_FOUND",
+            AdminError::ValidationError(_) => "BAD_USER_INPUT",
+            AdminError::DatabaseError(_) => INTERNAL_SERVER_ERROR,
+            AdminError::AuthorizationError(_) => "FORBIDDEN",
+        }
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(feature = "tenancy")]
+impl Problem for rf_tenancy::TenantError {
+    fn code(&self) -> &'static str {
+        use rf_tenancy::TenantError;
+
+        match self {
+            TenantError::NotFound => "NOT_FOUND",
+            TenantError::Unidentified | TenantError::InvalidIdentifier(_) => "BAD_REQUEST",
+            TenantError::CrossTenantAccess => "FORBIDDEN",
+            TenantError::Suspended(_) => "TENANT_SUSPENDED",
+            TenantError::AlreadyExists(_) | TenantError::InvalidTransition(_) => "CONFLICT",
+            TenantError::IdentificationFailed(_)
+            | TenantError::UnscopedQuery(_)
+            | TenantError::Provisioning(_)
+            | TenantError::InvalidSetting(_)
+            | TenantError::Registry(_) => INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+
+    fn details(&self, extensions: &mut ErrorExtensionValues) {
+        if let rf_tenancy::TenantError::Suspended(reason) = self {
+            let reason = match reason {
+                rf_tenancy::SuspendReason::Billing => "billing",
+                rf_tenancy::SuspendReason::Administrative => "administrative",
+            };
+            extensions.set("reason", reason);
+        }
+    }
+}
+
+/// Which error messages reach clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskingPolicy {
+    /// Return every message as is, for development
+    Expose,
+    /// Replace the messages of internal errors, i.e. errors without a
+    /// `code` or with `INTERNAL_SERVER_ERROR`
+    MaskInternal,
+}
+
+/// Schema extension applying a [`MaskingPolicy`] to resolver errors
+///
+/// Masked errors are logged with a correlation id, which the client gets
+/// in the `correlationId` extension:
+///
+/// ```
+/// use rf_graphql::{ErrorMasking, MaskingPolicy};
+/// use async_graphql::*;
+///
+/// # struct Query;
+/// # #[Object]
+/// # impl Query { async fn version(&self) -> i32 { 1 } }
+/// let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+///     .extension(ErrorMasking::new(MaskingPolicy::MaskInternal))
+///     .finish();
+/// ```
+pub struct ErrorMasking {
+    policy: MaskingPolicy,
+    message: Arc<str>,
+}
+
+impl ErrorMasking {
+    pub fn new(policy: MaskingPolicy) -> Self {
+        Self {
+            policy,
+            message: Arc::from("Internal server error"),
+        }
+    }
+
+    /// Message of masked errors (default: "Internal server error")
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = Arc::from(message.into());
+        self
+    }
+}
+
+impl ExtensionFactory for ErrorMasking {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(ErrorMaskingExtension {
+            policy: self.policy,
+            message: self.message.clone(),
+        })
+    }
+}
+
+#[derive(Clone)]
+struct ErrorMaskingExtension {
+    policy: MaskingPolicy,
+    message: Arc<str>,
+}
+
+impl ErrorMaskingExtension {
+    fn apply(&self, mut response: Response) -> Response {
+        if self.policy == MaskingPolicy::MaskInternal {
+            for error in &mut response.errors {
+                self.mask(error);
+            }
+        }
+        response
+    }
+
+    fn mask(&self, error: &mut ServerError) {
+        let extensions = error.extensions.get_or_insert_with(Default::default);
+        let internal = match extensions.get("code") {
+            Some(async_graphql::Value::String(code)) => code == INTERNAL_SERVER_ERROR,
+            Some(_) => false,
+            None => true,
+        };
+        if !internal || extensions.get("correlationId").is_some() {
+            return;
+        }
+
+        let correlation_id = uuid::Uuid::new_v4().to_string();
+        tracing::error!(
+            correlation_id = %correlation_id,
+            path = ?error.path,
+            "GraphQL error: {}",
+            error.message
+        );
+        *extensions = ErrorExtensionValues::default();
+        extensions.set("code", INTERNAL_SERVER_ERROR);
+        extensions.set("correlationId", correlation_id);
+        error.message = self.message.to_string();
+        error.source = None;
+    }
+}
+
+#[async_trait::async_trait]
+impl Extension for ErrorMaskingExtension {
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        self.apply(next.run(ctx, operation_name).await)
+    }
+
+    fn subscribe<'s>(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        stream: BoxStream<'s, Response>,
+        next: NextSubscribe<'_>,
+    ) -> BoxStream<'s, Response> {
+        let masking = self.clone();
+        next.run(ctx, stream)
+            .map(move |response| masking.apply(response))
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Result, Schema};
+
+    #[derive(Debug)]
+    enum OrderError {
+        Missing(u32),
+        Storage,
+    }
+
+    impl Problem for OrderError {
+        fn code(&self) -> &'static str {
+            match self {
+                OrderError::Missing(_) => "NOT_FOUND",
+                OrderError::Storage => INTERNAL_SERVER_ERROR,
+            }
+        }
+
+        fn message(&self) -> String {
+            match self {
+                OrderError::Missing(id) => format!("Order {} not found", id),
+                OrderError::Storage => "connection reset by peer".into(),
+            }
+        }
+
+        fn details(&self, extensions: &mut ErrorExtensionValues) {
+            if let OrderError::Missing(id) = self {
+                extensions.set("orderId", *id);
+            }
+        }
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn order(&self, id: u32) -> Result<u32> {
+            Err(OrderError::Missing(id)).problem()
+        }
+
+        async fn stats(&self) -> Result<u32> {
+            Err(OrderError::Storage).problem()
+        }
+
+        async fn legacy(&self) -> Result<u32> {
+            Err("pool timed out".into())
+        }
+
+        async fn me(&self) -> Result<u32> {
+            Err(AuthError::Unauthenticated).problem()
+        }
+    }
+
+    fn schema(policy: MaskingPolicy) -> Schema<Query, EmptyMutation, EmptySubscription> {
+        Schema::build(Query, EmptyMutation, EmptySubscription)
+            .extension(ErrorMasking::new(policy))
+            .finish()
+    }
+
+    fn extension(error: &ServerError, name: &str) -> Option<String> {
+        error
+            .extensions
+            .as_ref()?
+            .get(name)
+            .map(|value| value.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_problem_extensions() {
+        let response = schema(MaskingPolicy::MaskInternal)
+            .execute("{ order(id: 7) }")
+            .await;
+        let error = &response.errors[0];
+        assert_eq!(error.message, "Order 7 not found");
+        assert_eq!(extension(error, "code").unwrap(), "\"NOT_FOUND\"");
+        assert_eq!(extension(error, "orderId").unwrap(), "7");
+        assert!(extension(error, "correlationId").is_none());
+
+        let response = schema(MaskingPolicy::MaskInternal).execute("{ me }").await;
+        assert_eq!(
+            extension(&response.errors[0], "code").unwrap(),
+            "\"UNAUTHENTICATED\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_masking() {
+        for query in ["{ stats }", "{ legacy }"] {
+            let response = schema(MaskingPolicy::MaskInternal).execute(query).await;
+            let error = &response.errors[0];
+            assert_eq!(error.message, "Internal server error");
+            assert_eq!(
+                extension(error, "code").unwrap(),
+                "\"INTERNAL_SERVER_ERROR\""
+            );
+            assert!(extension(error, "correlationId").is_some());
+        }
+
+        // Request errors aren't resolver errors and stay readable
+        let response = schema(MaskingPolicy::MaskInternal)
+            .execute("{ missing }")
+            .await;
+        assert!(response.errors[0].message.contains("Unknown field"));
+
+        let response = schema(MaskingPolicy::Expose).execute("{ stats }").await;
+        assert_eq!(response.errors[0].message, "connection reset by peer");
+    }
+
+    #[cfg(feature = "tenancy")]
+    #[test]
+    fn test_tenant_error() {
+        use rf_tenancy::{SuspendReason, TenantError};
+
+        let error = TenantError::Suspended(SuspendReason::Billing).to_graphql_error();
+        let extensions = error.extensions.unwrap();
+        assert_eq!(
+            extensions.get("code").unwrap().to_string(),
+            "\"TENANT_SUSPENDED\""
+        );
+        assert_eq!(extensions.get("reason").unwrap().to_string(), "\"billing\"");
+        assert_eq!(
+            TenantError::Registry("down".into()).code(),
+            INTERNAL_SERVER_ERROR
+        );
+    }
+}
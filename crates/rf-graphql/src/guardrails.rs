@@ -0,0 +1,403 @@
+//! Production guardrails: depth and complexity limits, persisted queries
+//!
+//! Every field costs 1 unless it declares its own cost, either fixed or
+//! computed from its arguments and the cost of its selection:
+//!
+//! ```
+//! use rf_graphql::{Guardrails, PersistedQueries, SchemaBuilderExt};
+//! use rf_cache::MemoryCache;
+//! use async_graphql::*;
+//!
+//! struct Query;
+//!
+//! #[Object]
+//! impl Query {
+//!     #[graphql(complexity = 10)]
+//!     async fn report(&self) -> i32 {
+//!         42
+//!     }
+//!
+//!     #[graphql(complexity = "first as usize * child_complexity")]
+//!     async fn numbers(&self, first: i32) -> Vec<i32> {
+//!         (0..first).collect()
+//!     }
+//! }
+//!
+//! let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+//!     .guardrails(
+//!         Guardrails::new()
+//!             .max_depth(10)
+//!             .max_complexity(500)
+//!             .persisted_queries(PersistedQueries::new(MemoryCache::new())),
+//!     )
+//!     .finish();
+//! ```
+
+use async_graphql::{
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextPrepareRequest},
+    ErrorExtensions, ObjectType, Request, SchemaBuilder, ServerError, ServerResult,
+    SubscriptionType,
+};
+use async_trait::async_trait;
+use rf_cache::Cache;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// Limits applied to every operation of a schema
+#[derive(Default)]
+pub struct Guardrails {
+    max_depth: Option<usize>,
+    max_complexity: Option<usize>,
+    persisted_queries: Option<Box<dyn ExtensionFactory>>,
+}
+
+impl Guardrails {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject operations nested deeper than `depth`
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Reject operations whose summed field costs exceed `complexity`
+    pub fn max_complexity(mut self, complexity: usize) -> Self {
+        self.max_complexity = Some(complexity);
+        self
+    }
+
+    /// Accept queries by hash (Apollo's automatic persisted queries)
+    pub fn persisted_queries<C: Cache + 'static>(mut self, queries: PersistedQueries<C>) -> Self {
+        self.persisted_queries = Some(Box::new(queries));
+        self
+    }
+}
+
+/// Guardrails on the schema builder
+pub trait SchemaBuilderExt {
+    fn guardrails(self, guardrails: Guardrails) -> Self;
+}
+
+impl<Q, M, S> SchemaBuilderExt for SchemaBuilder<Q, M, S>
+where
+    Q: ObjectType + 'static,
+    M: ObjectType + 'static,
+    S: SubscriptionType + 'static,
+{
+    fn guardrails(mut self, guardrails: Guardrails) -> Self {
+        if let Some(depth) = guardrails.max_depth {
+            self = self.limit_depth(depth);
+        }
+        if let Some(complexity) = guardrails.max_complexity {
+            self = self.limit_complexity(complexity);
+        }
+        if let Some(persisted_queries) = guardrails.persisted_queries {
+            self = self.extension(BoxedExtension(persisted_queries));
+        }
+        self
+    }
+}
+
+struct BoxedExtension(Box<dyn ExtensionFactory>);
+
+impl ExtensionFactory for BoxedExtension {
+    fn create(&self) -> Arc<dyn Extension> {
+        self.0.create()
+    }
+}
+
+/// Automatic persisted queries stored in an [`rf_cache::Cache`]
+///
+/// Clients send the SHA-256 of a query in the `persistedQuery` extension.
+/// Unknown hashes fail with `PersistedQueryNotFound`, upon which the client
+/// retries with the full query, which is then stored for later requests.
+///
+/// With [`allow_only`](Self::allow_only), only the listed queries run at
+/// all, whether sent in full or by hash.
+pub struct PersistedQueries<C> {
+    inner: Arc<Inner<C>>,
+}
+
+struct Inner<C> {
+    cache: C,
+    ttl: Duration,
+    prefix: String,
+    allow_list: Option<HashMap<String, String>>,
+}
+
+impl<C: Cache> PersistedQueries<C> {
+    pub fn new(cache: C) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                cache,
+                ttl: Duration::from_secs(24 * 60 * 60),
+                prefix: "graphql:apq".to_string(),
+                allow_list: None,
+            }),
+        }
+    }
+
+    /// How long registered queries are kept (default: one day)
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.inner_mut().ttl = ttl;
+        self
+    }
+
+    /// Prefix of the cache keys (default: `graphql:apq`)
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.inner_mut().prefix = prefix.into();
+        self
+    }
+
+    /// Only run these queries, e.g. those extracted from the client at
+    /// build time; the cache isn't used in this mode
+    pub fn allow_only<I>(mut self, queries: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let queries = queries
+            .into_iter()
+            .map(|query| {
+                let query = query.into();
+                (query_hash(&query), query)
+            })
+            .collect();
+        self.inner_mut().allow_list = Some(queries);
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner<C> {
+        Arc::get_mut(&mut self.inner).expect("PersistedQueries is configured before use")
+    }
+}
+
+impl<C: Cache> Inner<C> {
+    fn key(&self, hash: &str) -> String {
+        format!("{}:{}", self.prefix, hash)
+    }
+
+    async fn lookup(&self, hash: &str) -> Option<String> {
+        if let Some(allow_list) = &self.allow_list {
+            return allow_list.get(hash).cloned();
+        }
+        match self.cache.get(&self.key(hash)).await {
+            Ok(query) => query,
+            Err(e) => {
+                tracing::warn!("Failed to load persisted query {}: {}", hash, e);
+                None
+            }
+        }
+    }
+
+    async fn register(&self, hash: &str, query: &str) -> ServerResult<()> {
+        if self.allow_list.is_some() {
+            return self.check_allowed(hash);
+        }
+        if let Err(e) = self.cache.set(&self.key(hash), &query, self.ttl).await {
+            tracing::warn!("Failed to store persisted query {}: {}", hash, e);
+        }
+        Ok(())
+    }
+
+    fn check_allowed(&self, hash: &str) -> ServerResult<()> {
+        match &self.allow_list {
+            Some(allow_list) if !allow_list.contains_key(hash) => Err(error(
+                "PersistedQueryNotAllowed",
+                "PERSISTED_QUERY_NOT_ALLOWED",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    async fn prepare(&self, mut request: Request) -> ServerResult<Request> {
+        let Some(value) = request.extensions.remove("persistedQuery") else {
+            self.check_allowed(&query_hash(&request.query))?;
+            return Ok(request);
+        };
+        let persisted: PersistedQuery = async_graphql::from_value(value)
+            .map_err(|_| ServerError::new("Invalid \"persistedQuery\" extension", None))?;
+        if persisted.version != 1 {
+            return Err(ServerError::new(
+                format!("Unsupported persisted query version {}", persisted.version),
+                None,
+            ));
+        }
+
+        if request.query.is_empty() {
+            request.query = self
+                .lookup(&persisted.sha256_hash)
+                .await
+                .ok_or_else(|| error("PersistedQueryNotFound", "PERSISTED_QUERY_NOT_FOUND"))?;
+        } else {
+            if query_hash(&request.query) != persisted.sha256_hash {
+                return Err(ServerError::new("Provided sha does not match query", None));
+            }
+            self.register(&persisted.sha256_hash, &request.query)
+                .await?;
+        }
+        Ok(request)
+    }
+}
+
+impl<C: Cache + 'static> ExtensionFactory for PersistedQueries<C> {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(PersistedQueriesExtension {
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+struct PersistedQueriesExtension<C> {
+    inner: Arc<Inner<C>>,
+}
+
+#[async_trait]
+impl<C: Cache + 'static> Extension for PersistedQueriesExtension<C> {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        let request = self.inner.prepare(request).await?;
+        next.run(ctx, request).await
+    }
+}
+
+#[derive(Deserialize)]
+struct PersistedQuery {
+    version: i32,
+    #[serde(rename = "sha256Hash")]
+    sha256_hash: String,
+}
+
+/// Hex SHA-256 of a query, as sent by Apollo clients
+fn query_hash(query: &str) -> String {
+    hex::encode(Sha256::digest(query.as_bytes()))
+}
+
+fn error(message: &str, code: &'static str) -> ServerError {
+    async_graphql::Error::new(message)
+        .extend_with(|_, e| e.set("code", code))
+        .into_server_error(Default::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{value, EmptyMutation, EmptySubscription, Object, Schema};
+    use rf_cache::MemoryCache;
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn hello(&self) -> &str {
+            "world"
+        }
+
+        #[graphql(complexity = 10)]
+        async fn report(&self) -> i32 {
+            42
+        }
+
+        async fn node(&self) -> Node {
+            Node
+        }
+    }
+
+    struct Node;
+
+    #[Object]
+    impl Node {
+        async fn child(&self) -> Node {
+            Node
+        }
+
+        async fn id(&self) -> i32 {
+            1
+        }
+    }
+
+    fn schema(guardrails: Guardrails) -> Schema<Query, EmptyMutation, EmptySubscription> {
+        Schema::build(Query, EmptyMutation, EmptySubscription)
+            .guardrails(guardrails)
+            .finish()
+    }
+
+    fn persisted(query: &str, hash: &str) -> Request {
+        let mut request = Request::new(query);
+        request.extensions.insert(
+            "persistedQuery".to_string(),
+            value!({ "version": 1, "sha256Hash": hash }),
+        );
+        request
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let schema = schema(Guardrails::new().max_depth(3).max_complexity(10));
+
+        assert!(schema
+            .execute("{ node { child { id } } }")
+            .await
+            .errors
+            .is_empty());
+        let response = schema.execute("{ node { child { child { id } } } }").await;
+        assert_eq!(response.errors[0].message, "Query is nested too deep.");
+
+        assert!(schema.execute("{ report }").await.errors.is_empty());
+        let response = schema.execute("{ report hello }").await;
+        assert_eq!(response.errors[0].message, "Query is too complex.");
+    }
+
+    #[tokio::test]
+    async fn test_automatic_persisted_queries() {
+        let schema =
+            schema(Guardrails::new().persisted_queries(PersistedQueries::new(MemoryCache::new())));
+        let hash = query_hash("{ hello }");
+
+        let response = schema.execute(persisted("", &hash)).await;
+        assert_eq!(response.errors[0].message, "PersistedQueryNotFound");
+
+        let response = schema.execute(persisted("{ hello }", &hash)).await;
+        assert_eq!(response.data, value!({ "hello": "world" }));
+
+        let response = schema.execute(persisted("", &hash)).await;
+        assert_eq!(response.data, value!({ "hello": "world" }));
+
+        let response = schema.execute(persisted("{ report }", &hash)).await;
+        assert_eq!(
+            response.errors[0].message,
+            "Provided sha does not match query"
+        );
+
+        // Plain queries are unaffected
+        assert!(schema.execute("{ report }").await.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_allow_list() {
+        let queries =
+            PersistedQueries::new(MemoryCache::new()).allow_only(["{ hello }", "{ report }"]);
+        let schema = schema(Guardrails::new().persisted_queries(queries));
+
+        let response = schema
+            .execute(persisted("", &query_hash("{ report }")))
+            .await;
+        assert_eq!(response.data, value!({ "report": 42 }));
+        assert!(schema.execute("{ hello }").await.errors.is_empty());
+
+        let response = schema.execute("{ node { id } }").await;
+        assert_eq!(response.errors[0].message, "PersistedQueryNotAllowed");
+        let hash = query_hash("{ node { id } }");
+        let response = schema.execute(persisted("{ node { id } }", &hash)).await;
+        assert_eq!(response.errors[0].message, "PersistedQueryNotAllowed");
+        let response = schema.execute(persisted("", &hash)).await;
+        assert_eq!(response.errors[0].message, "PersistedQueryNotFound");
+    }
+}
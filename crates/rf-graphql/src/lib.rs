@@ -7,10 +7,23 @@
 //!
 //! - **Schema Builder**: Easy schema construction
 //! - **Query/Mutation/Subscription**: All GraphQL operation types
-//! - **DataLoader**: N+1 query prevention
+//! - **DataLoader**: N+1 query prevention with [`loader_context`] and
+//!   batch loaders for sqlx ("sqlx" feature) and SeaORM ("sea-orm" feature)
+//! - **Pagination**: Relay connections with keyset cursors, see
+//!   [`ConnectionArgs`]
 //! - **Playground**: GraphQL playground UI
-//! - **Authentication**: Middleware support
-//! - **Error Handling**: Type-safe error handling
+//! - **Authentication**: [`Identity`] from middleware or bearer tokens, and
+//!   guards for roles, permissions and ownership
+//! - **Subscriptions**: WebSocket endpoint with `connection_init`
+//!   authentication, see [`GraphQLSubscriptions`]
+//! - **Guardrails**: depth and complexity limits, persisted queries and
+//!   allow-lists, see [`Guardrails`]
+//! - **Schema Snapshots**: SDL export and breaking-change detection, see
+//!   [`diff_sdl`]
+//! - **Uploads**: multipart file uploads into rf-upload ("upload" feature),
+//!   see [`UploadExt`]
+//! - **Error Handling**: domain errors mapped to coded errors with
+//!   [`Problem`], and masking of internal errors with [`ErrorMasking`]
 //!
 //! ## Quick Start
 //!
@@ -67,6 +80,54 @@
 //! # }
 //! ```
 
+// Lets derived code refer to `::rf_graphql` inside this crate too
+extern crate self as rf_graphql;
+
+mod auth;
+mod errors;
+mod guardrails;
+mod loader;
+mod pagination;
+mod sdl;
+#[cfg(feature = "sea-orm")]
+pub mod sea;
+#[cfg(feature = "sqlx")]
+pub mod sql;
+mod subscription;
+#[cfg(feature = "upload")]
+mod upload;
+
+pub use auth::{
+    AuthError, Authenticated, Authenticator, ContextAuthExt, Identity, OwnerGuard, PermissionGuard,
+    RoleGuard,
+};
+pub use errors::{
+    ErrorMasking, MaskingPolicy, Problem, ProblemResultExt, INTERNAL_SERVER_ERROR,
+};
+pub use guardrails::{Guardrails, PersistedQueries, SchemaBuilderExt};
+pub use loader::{loader_context, LoaderContext, LoaderContextExt};
+#[cfg(feature = "sqlx")]
+pub use pagination::paginate_query;
+#[cfg(feature = "sea-orm")]
+pub use pagination::paginate_select;
+pub use pagination::{
+    ConnectionArgs, CursorValue, Keyset, KeysetConnection, KeysetCursor, Page, PageInfo, SortOrder,
+};
+#[cfg(feature = "derive")]
+pub use rf_graphql_derive::BatchLoader;
+#[cfg(feature = "sea-orm")]
+pub use sea::{SeaGroupLoader, SeaLoader};
+#[cfg(feature = "sqlx")]
+pub use sql::{SqlGroupLoader, SqlLoader};
+pub use sdl::{check_sdl, diff_sdl, export_sdl, ChangeSeverity, SchemaChange, SchemaDiff, SdlError};
+#[cfg(feature = "cli")]
+pub use sdl::SchemaCommand;
+#[cfg(feature = "sqlx")]
+pub use sqlx;
+pub use subscription::{graphql_subscription_router, GraphQLSubscriptions};
+#[cfg(feature = "upload")]
+pub use upload::UploadExt;
+
 pub use async_graphql::{
     self, dataloader, Context, EmptyMutation, EmptySubscription, Error, ErrorExtensions,
     InputObject, Object, Result, Schema, SimpleObject, Subscription, ID,
@@ -76,9 +137,10 @@ pub use async_graphql_axum::{GraphQLRequest, GraphQLResponse, GraphQLSubscriptio
 
 use axum::{
     extract::State,
+    http::{header, HeaderMap},
     response::{Html, IntoResponse},
     routing::{get, post},
-    Router,
+    Extension, Router,
 };
 use std::sync::Arc;
 
@@ -87,6 +149,9 @@ pub type GraphQLSchema<Q, M, S> = Schema<Q, M, S>;
 
 /// Create a GraphQL router with query and mutation endpoints
 ///
+/// An [`Identity`] the auth middleware put into the request extensions is
+/// passed on to the resolvers.
+///
 /// # Example
 ///
 /// ```no_run
@@ -125,6 +190,52 @@ where
 /// GraphQL query/mutation handler
 async fn graphql_handler<Q, M, S>(
     State(schema): State<Arc<Schema<Q, M, S>>>,
+    identity: Option<Extension<Identity>>,
+    req: GraphQLRequest,
+) -> GraphQLResponse
+where
+    Q: ObjectType + 'static,
+    M: ObjectType + 'static,
+    S: SubscriptionType + 'static,
+{
+    let mut request = req.into_inner();
+    if let Some(Extension(identity)) = identity {
+        request = request.data(identity);
+    }
+    schema.execute(request).await.into()
+}
+
+struct AuthState<Q, M, S> {
+    schema: Schema<Q, M, S>,
+    authenticator: Arc<dyn Authenticator>,
+}
+
+/// Create a GraphQL router resolving `Authorization: Bearer` tokens
+///
+/// Requests without a token run anonymously; an invalid token fails the
+/// request with an `UNAUTHENTICATED` error.
+pub fn graphql_router_with_auth<Q, M, S>(
+    schema: Schema<Q, M, S>,
+    authenticator: impl Authenticator,
+) -> Router
+where
+    Q: ObjectType + 'static,
+    M: ObjectType + 'static,
+    S: SubscriptionType + 'static,
+{
+    let state = Arc::new(AuthState {
+        schema,
+        authenticator: Arc::new(authenticator),
+    });
+
+    Router::new()
+        .route("/graphql", post(graphql_auth_handler::<Q, M, S>))
+        .with_state(state)
+}
+
+async fn graphql_auth_handler<Q, M, S>(
+    State(state): State<Arc<AuthState<Q, M, S>>>,
+    headers: HeaderMap,
     req: GraphQLRequest,
 ) -> GraphQLResponse
 where
@@ -132,7 +243,28 @@ where
     M: ObjectType + 'static,
     S: SubscriptionType + 'static,
 {
-    schema.execute(req.into_inner()).await.into()
+    let mut request = req.into_inner();
+    if let Some(token) = bearer_token(&headers) {
+        match state.authenticator.authenticate(token).await {
+            Ok(identity) => request = request.data(identity),
+            Err(e) => {
+                let error = e.extend().into_server_error(Default::default());
+                return async_graphql::Response::from_errors(vec![error]).into();
+            }
+        }
+    }
+    state.schema.execute(request).await.into()
+}
+
+/// The token of an `Authorization: Bearer <token>` header
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
 }
 
 /// Create a GraphQL playground router
@@ -174,7 +306,7 @@ async fn graphql_playground() -> impl IntoResponse {
         window.addEventListener('load', function (event) {
             GraphQLPlayground.init(document.getElementById('root'), {
                 endpoint: '/graphql',
-                subscriptionEndpoint: '/graphql',
+                subscriptionEndpoint: '/graphql/ws',
                 settings: {
                     'request.credentials': 'same-origin'
                 }
@@ -399,6 +531,72 @@ mod tests {
         assert_eq!(data["user"]["name"], "Test User");
     }
 
+    struct TokenAuthenticator;
+
+    #[async_trait::async_trait]
+    impl Authenticator for TokenAuthenticator {
+        async fn authenticate(&self, token: &str) -> std::result::Result<Identity, AuthError> {
+            match token {
+                "secret" => Ok(Identity::new("7")),
+                _ => Err(AuthError::Unauthenticated),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_with_auth() {
+        use axum::{body::Body, http::Request as HttpRequest};
+        use tower::ServiceExt;
+
+        struct MeQuery;
+
+        #[Object]
+        impl MeQuery {
+            async fn me(&self, ctx: &Context<'_>) -> Option<String> {
+                ctx.identity().map(|identity| identity.id.clone())
+            }
+        }
+
+        let router = graphql_router_with_auth(
+            Schema::new(MeQuery, EmptyMutation, EmptySubscription),
+            TokenAuthenticator,
+        );
+        let send = |token: Option<&str>| {
+            let mut request =
+                HttpRequest::post("/graphql").header("content-type", "application/json");
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
+            }
+            let request = request.body(Body::from(r#"{"query": "{ me }"}"#)).unwrap();
+            let router = router.clone();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+            }
+        };
+
+        assert_eq!(send(Some("secret")).await["data"]["me"], "7");
+        assert!(send(None).await["data"]["me"].is_null());
+        let response = send(Some("wrong")).await;
+        assert_eq!(
+            response["errors"][0]["extensions"]["code"],
+            "UNAUTHENTICATED"
+        );
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(header::AUTHORIZATION, "Bearer abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), Some("abc"));
+        headers.insert(header::AUTHORIZATION, "Basic abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+    }
+
     #[tokio::test]
     async fn test_aliases() {
         let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription).finish();
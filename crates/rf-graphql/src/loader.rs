@@ -0,0 +1,171 @@
+//! Registering DataLoaders with a schema
+//!
+//! [`loader_context`] collects loaders, wraps each in a batching
+//! [`DataLoader`] and goes into the schema data. Resolvers fetch them by
+//! type with [`LoaderContextExt::loader`], so every field resolved in the
+//! same tick shares one query.
+//!
+//! ```
+//! use rf_graphql::dataloader::Loader;
+//! use rf_graphql::{loader_context, LoaderContextExt};
+//! use async_graphql::*;
+//! use std::collections::HashMap;
+//!
+//! struct NameLoader;
+//!
+//! impl Loader<u64> for NameLoader {
+//!     type Value = String;
+//!     type Error = String;
+//!
+//!     async fn load(&self, keys: &[u64]) -> Result<HashMap<u64, String>, String> {
+//!         Ok(keys.iter().map(|id| (*id, format!("User {}", id))).collect())
+//!     }
+//! }
+//!
+//! struct Query;
+//!
+//! #[Object]
+//! impl Query {
+//!     async fn name(&self, ctx: &Context<'_>, id: u64) -> Result<Option<String>> {
+//!         Ok(ctx.loader::<NameLoader>()?.load_one(id).await?)
+//!     }
+//! }
+//!
+//! let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+//!     .data(loader_context().loader(NameLoader))
+//!     .finish();
+//! ```
+
+use async_graphql::{dataloader::DataLoader, Context, Error, Result};
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    sync::Arc,
+};
+
+/// Start collecting loaders for the schema data
+pub fn loader_context() -> LoaderContext {
+    LoaderContext::default()
+}
+
+/// DataLoaders by loader type
+///
+/// Loaders only batch, they don't cache: the context lives in the schema
+/// data and is shared by all requests.
+#[derive(Clone, Default)]
+pub struct LoaderContext {
+    loaders: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl LoaderContext {
+    /// Register a loader, replacing one of the same type
+    ///
+    /// Batches are loaded on Tokio tasks.
+    pub fn loader<L: Send + Sync + 'static>(mut self, loader: L) -> Self {
+        let loader = DataLoader::new(loader, tokio::spawn);
+        self.loaders.insert(TypeId::of::<L>(), Arc::new(loader));
+        self
+    }
+
+    /// The DataLoader of loader type `L`
+    pub fn get<L: Send + Sync + 'static>(&self) -> Option<&DataLoader<L>> {
+        self.loaders
+            .get(&TypeId::of::<L>())
+            .and_then(|loader| loader.downcast_ref())
+    }
+}
+
+/// Loader access in resolvers
+pub trait LoaderContextExt {
+    /// The DataLoader of loader type `L` from the schema's [`LoaderContext`]
+    fn loader<L: Send + Sync + 'static>(&self) -> Result<&DataLoader<L>>;
+}
+
+impl LoaderContextExt for Context<'_> {
+    fn loader<L: Send + Sync + 'static>(&self) -> Result<&DataLoader<L>> {
+        self.data::<LoaderContext>()?
+            .get::<L>()
+            .ok_or_else(|| Error::new(format!("Loader `{}` is not registered", type_name::<L>())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{dataloader::Loader, EmptyMutation, EmptySubscription, Object, Schema};
+    use std::sync::Mutex;
+
+    /// Records every batch it is asked for
+    #[derive(Clone, Default)]
+    struct AuthorLoader {
+        batches: Arc<Mutex<Vec<Vec<u64>>>>,
+    }
+
+    impl Loader<u64> for AuthorLoader {
+        type Value = String;
+        type Error = Arc<String>;
+
+        async fn load(&self, keys: &[u64]) -> Result<HashMap<u64, String>, Self::Error> {
+            let mut keys = keys.to_vec();
+            keys.sort();
+            self.batches.lock().unwrap().push(keys.clone());
+            Ok(keys
+                .into_iter()
+                .filter(|id| *id != 0)
+                .map(|id| (id, format!("Author {}", id)))
+                .collect())
+        }
+    }
+
+    struct Post {
+        author_id: u64,
+    }
+
+    #[Object]
+    impl Post {
+        async fn author(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+            Ok(ctx
+                .loader::<AuthorLoader>()?
+                .load_one(self.author_id)
+                .await?)
+        }
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn posts(&self) -> Vec<Post> {
+            [1, 2, 1, 0]
+                .into_iter()
+                .map(|author_id| Post { author_id })
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batches_loads() {
+        let loader = AuthorLoader::default();
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .data(loader_context().loader(loader.clone()))
+            .finish();
+
+        let result = schema.execute("{ posts { author } }").await;
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let data = result.data.into_json().unwrap();
+        assert_eq!(data["posts"][0]["author"], "Author 1");
+        assert_eq!(data["posts"][2]["author"], "Author 1");
+        assert!(data["posts"][3]["author"].is_null());
+        assert_eq!(*loader.batches.lock().unwrap(), vec![vec![0, 1, 2]]);
+    }
+
+    #[tokio::test]
+    async fn test_unregistered_loader() {
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .data(loader_context())
+            .finish();
+
+        let result = schema.execute("{ posts { author } }").await;
+        assert!(result.errors[0].message.contains("is not registered"));
+    }
+}
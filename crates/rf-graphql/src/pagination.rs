@@ -0,0 +1,592 @@
+//! Relay cursor pagination
+//!
+//! [`ConnectionArgs`] validates the `first`/`after`/`last`/`before`
+//! arguments of a connection field. Rows implement [`Keyset`] to expose the
+//! values of the columns they are ordered by; their [`KeysetCursor`] is
+//! those values, base64-encoded, so the next page continues right after the
+//! last row even if rows were inserted meanwhile.
+//!
+//! `paginate_query` (feature "sqlx") and `paginate_select` (feature
+//! "sea-orm") run the page query and build the [`KeysetConnection`].
+//!
+//! ```ignore
+//! #[Object]
+//! impl Query {
+//!     async fn posts(
+//!         &self,
+//!         ctx: &Context<'_>,
+//!         after: Option<String>,
+//!         before: Option<String>,
+//!         first: Option<i32>,
+//!         last: Option<i32>,
+//!     ) -> Result<KeysetConnection<Post>> {
+//!         let args = ConnectionArgs::new(after, before, first, last);
+//!         paginate_query(ctx.data()?, &args, &["created_at", "id"], SortOrder::Desc, |q| {
+//!             q.push("SELECT * FROM posts");
+//!         })
+//!         .await
+//!     }
+//! }
+//! ```
+
+use async_graphql::{
+    connection::{Connection, CursorType, Edge, OpaqueCursor},
+    Error, OutputType, Result,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub use async_graphql::connection::PageInfo;
+
+/// Opaque cursor holding the keyset values of a row
+pub type KeysetCursor = OpaqueCursor<Vec<CursorValue>>;
+
+/// Connection of rows paginated by keyset
+pub type KeysetConnection<V> = Connection<KeysetCursor, V>;
+
+/// A column value in a cursor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorValue {
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bool(bool),
+    Timestamp(DateTime<Utc>),
+}
+
+impl From<i64> for CursorValue {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<i32> for CursorValue {
+    fn from(value: i32) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<f64> for CursorValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<String> for CursorValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<&str> for CursorValue {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+impl From<bool> for CursorValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<DateTime<Utc>> for CursorValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        Self::Timestamp(value)
+    }
+}
+
+/// Rows that can be paginated by keyset
+pub trait Keyset {
+    /// Values of the ordering columns, in the order the columns are passed
+    /// to the paginate function
+    fn keyset(&self) -> Vec<CursorValue>;
+}
+
+/// Direction of the keyset ordering, applied to all columns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// The `first`/`after`/`last`/`before` arguments of a connection field
+#[derive(Debug, Clone)]
+pub struct ConnectionArgs {
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub first: Option<i32>,
+    pub last: Option<i32>,
+    default_page_size: usize,
+    max_page_size: usize,
+}
+
+impl ConnectionArgs {
+    /// Arguments in the order of `async_graphql::connection::query`
+    pub fn new(
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> Self {
+        Self {
+            after,
+            before,
+            first,
+            last,
+            default_page_size: 20,
+            max_page_size: 100,
+        }
+    }
+
+    /// Page size without `first` or `last` (default: 20)
+    pub fn default_page_size(mut self, size: usize) -> Self {
+        self.default_page_size = size;
+        self
+    }
+
+    /// Largest accepted `first` or `last` (default: 100)
+    pub fn max_page_size(mut self, size: usize) -> Self {
+        self.max_page_size = size;
+        self
+    }
+
+    /// Check the arguments and decode the cursors
+    pub fn page(&self) -> Result<Page> {
+        if self.first.is_some() && self.last.is_some() {
+            return Err(Error::new(
+                "The \"first\" and \"last\" parameters cannot exist at the same time",
+            ));
+        }
+        let size = match (self.first, self.last) {
+            (Some(size), _) => self.size("first", size)?,
+            (_, Some(size)) => self.size("last", size)?,
+            _ => self.default_page_size,
+        };
+
+        Ok(Page {
+            after: decode("after", self.after.as_deref())?,
+            before: decode("before", self.before.as_deref())?,
+            size,
+            backward: self.last.is_some(),
+        })
+    }
+
+    fn size(&self, name: &str, size: i32) -> Result<usize> {
+        match usize::try_from(size) {
+            Ok(size) if size <= self.max_page_size => Ok(size),
+            Ok(_) => Err(Error::new(format!(
+                "The \"{}\" parameter must be at most {}",
+                name, self.max_page_size
+            ))),
+            Err(_) => Err(Error::new(format!(
+                "The \"{}\" parameter must be a non-negative number",
+                name
+            ))),
+        }
+    }
+}
+
+fn decode(name: &str, cursor: Option<&str>) -> Result<Option<Vec<CursorValue>>> {
+    cursor
+        .map(|cursor| {
+            KeysetCursor::decode_cursor(cursor)
+                .map(|cursor| cursor.0)
+                .map_err(|_| Error::new(format!("Invalid \"{}\" cursor", name)))
+        })
+        .transpose()
+}
+
+/// A validated page request
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    /// Keyset of the row the page starts after
+    pub after: Option<Vec<CursorValue>>,
+    /// Keyset of the row the page ends before
+    pub before: Option<Vec<CursorValue>>,
+    pub size: usize,
+    /// Paginating with `last`: the query runs in reverse order
+    pub backward: bool,
+}
+
+impl Page {
+    /// Rows to fetch: one more than the page size tells whether there are
+    /// more pages
+    pub fn limit(&self) -> usize {
+        self.size + 1
+    }
+
+    /// Order the query runs in: `order`, reversed when paginating backward
+    pub fn query_order(&self, order: SortOrder) -> SortOrder {
+        match (self.backward, order) {
+            (false, order) => order,
+            (true, SortOrder::Asc) => SortOrder::Desc,
+            (true, SortOrder::Desc) => SortOrder::Asc,
+        }
+    }
+
+    /// Build the connection from at most [`Page::limit`] rows fetched in
+    /// [`Page::query_order`]
+    pub fn connection<V: Keyset + OutputType>(&self, mut rows: Vec<V>) -> KeysetConnection<V> {
+        let more = rows.len() > self.size;
+        rows.truncate(self.size);
+        if self.backward {
+            rows.reverse();
+        }
+
+        let (has_previous_page, has_next_page) = if self.backward {
+            (more, self.before.is_some())
+        } else {
+            (self.after.is_some(), more)
+        };
+        let mut connection = Connection::new(has_previous_page, has_next_page);
+        connection.edges.extend(
+            rows.into_iter()
+                .map(|row| Edge::new(OpaqueCursor(row.keyset()), row)),
+        );
+        connection
+    }
+}
+
+#[cfg(any(feature = "sqlx", feature = "sea-orm"))]
+fn check_keyset(columns: usize, keyset: &[CursorValue]) -> Result<()> {
+    if keyset.len() != columns {
+        return Err(Error::new("Cursor doesn't match the ordering"));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "sqlx")]
+pub use pg::paginate_query;
+
+#[cfg(feature = "sqlx")]
+mod pg {
+    use super::*;
+    use sqlx::{postgres::PgRow, FromRow, PgPool, Postgres, QueryBuilder};
+
+    /// Fetch a page of the rows selected by `base`, ordered by `columns`
+    ///
+    /// `base` pushes the full select, with any filters, onto the query
+    /// builder; it is wrapped in a subquery, so the row filter and ordering
+    /// don't interfere with it. The `columns` must identify a row uniquely,
+    /// e.g. `["created_at", "id"]`.
+    pub async fn paginate_query<V, F>(
+        pool: &PgPool,
+        args: &ConnectionArgs,
+        columns: &[&str],
+        order: SortOrder,
+        base: F,
+    ) -> Result<KeysetConnection<V>>
+    where
+        V: Keyset + OutputType + for<'r> FromRow<'r, PgRow> + Send + Unpin,
+        F: FnOnce(&mut QueryBuilder<'_, Postgres>),
+    {
+        let page = args.page()?;
+        let mut query = QueryBuilder::new("SELECT * FROM (");
+        base(&mut query);
+        query.push(") AS page");
+        push_filter(&mut query, &page, columns, order)?;
+
+        let direction = match page.query_order(order) {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+        query.push(" ORDER BY ");
+        query.push(
+            columns
+                .iter()
+                .map(|column| format!("{} {}", column, direction))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        query.push(" LIMIT ").push_bind(page.limit() as i64);
+
+        let rows = query
+            .build_query_as::<V>()
+            .fetch_all(pool)
+            .await
+            .map_err(|e| {
+                tracing::error!("Pagination query failed: {}", e);
+                Error::new("Failed to load page")
+            })?;
+        Ok(page.connection(rows))
+    }
+
+    pub(super) fn push_filter(
+        query: &mut QueryBuilder<'_, Postgres>,
+        page: &Page,
+        columns: &[&str],
+        order: SortOrder,
+    ) -> Result<()> {
+        let bounds = [
+            (&page.after, if order == SortOrder::Asc { ">" } else { "<" }),
+            (
+                &page.before,
+                if order == SortOrder::Asc { "<" } else { ">" },
+            ),
+        ];
+        let mut keyword = " WHERE ";
+        for (keyset, operator) in bounds {
+            let Some(keyset) = keyset else { continue };
+            check_keyset(columns.len(), keyset)?;
+
+            query.push(format!(
+                "{}({}) {} (",
+                keyword,
+                columns.join(", "),
+                operator
+            ));
+            for (index, value) in keyset.iter().enumerate() {
+                if index > 0 {
+                    query.push(", ");
+                }
+                match value {
+                    CursorValue::Int(value) => query.push_bind(*value),
+                    CursorValue::Float(value) => query.push_bind(*value),
+                    CursorValue::Text(value) => query.push_bind(value.clone()),
+                    CursorValue::Bool(value) => query.push_bind(*value),
+                    CursorValue::Timestamp(value) => {
+                        query.push_bind(value.to_rfc3339()).push("::timestamptz")
+                    }
+                };
+            }
+            query.push(")");
+            keyword = " AND ";
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sea-orm")]
+pub use sea::paginate_select;
+
+#[cfg(feature = "sea-orm")]
+mod sea {
+    use super::*;
+    use sea_orm::{
+        sea_query::{Expr, SimpleExpr, Value},
+        ConnectionTrait, EntityTrait, Order, QueryFilter, QueryOrder, QuerySelect, Select,
+    };
+
+    /// Fetch a page of `select`, ordered by `columns`
+    ///
+    /// The `columns` must identify a row uniquely, e.g. `[CreatedAt, Id]`.
+    pub async fn paginate_select<E, C>(
+        db: &C,
+        select: Select<E>,
+        args: &ConnectionArgs,
+        columns: &[E::Column],
+        order: SortOrder,
+    ) -> Result<KeysetConnection<E::Model>>
+    where
+        E: EntityTrait,
+        E::Model: Keyset + OutputType,
+        C: ConnectionTrait,
+    {
+        let page = args.page()?;
+        let rows = page_select(select, &page, columns, order)?
+            .all(db)
+            .await
+            .map_err(|e| {
+                tracing::error!("Pagination query failed: {}", e);
+                Error::new("Failed to load page")
+            })?;
+        Ok(page.connection(rows))
+    }
+
+    pub(super) fn page_select<E: EntityTrait>(
+        mut select: Select<E>,
+        page: &Page,
+        columns: &[E::Column],
+        order: SortOrder,
+    ) -> Result<Select<E>> {
+        let tuple = || Expr::tuple(columns.iter().map(|column| Expr::col(*column).into()));
+        if let Some(after) = &page.after {
+            let values = values(columns.len(), after)?;
+            select = select.filter(match order {
+                SortOrder::Asc => tuple().gt(values),
+                SortOrder::Desc => tuple().lt(values),
+            });
+        }
+        if let Some(before) = &page.before {
+            let values = values(columns.len(), before)?;
+            select = select.filter(match order {
+                SortOrder::Asc => tuple().lt(values),
+                SortOrder::Desc => tuple().gt(values),
+            });
+        }
+
+        let direction = match page.query_order(order) {
+            SortOrder::Asc => Order::Asc,
+            SortOrder::Desc => Order::Desc,
+        };
+        for column in columns {
+            select = select.order_by(*column, direction.clone());
+        }
+        Ok(select.limit(page.limit() as u64))
+    }
+
+    fn values(columns: usize, keyset: &[CursorValue]) -> Result<SimpleExpr> {
+        check_keyset(columns, keyset)?;
+        Ok(Expr::tuple(keyset.iter().map(|value| {
+            let value: Value = match value {
+                CursorValue::Int(value) => (*value).into(),
+                CursorValue::Float(value) => (*value).into(),
+                CursorValue::Text(value) => value.clone().into(),
+                CursorValue::Bool(value) => (*value).into(),
+                CursorValue::Timestamp(value) => (*value).into(),
+            };
+            Expr::val(value).into()
+        }))
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::SimpleObject;
+
+    #[derive(Clone, SimpleObject)]
+    struct Post {
+        id: i64,
+    }
+
+    impl Keyset for Post {
+        fn keyset(&self) -> Vec<CursorValue> {
+            vec![self.id.into()]
+        }
+    }
+
+    fn cursor(id: i64) -> String {
+        OpaqueCursor(vec![CursorValue::Int(id)]).encode_cursor()
+    }
+
+    fn posts(ids: impl IntoIterator<Item = i64>) -> Vec<Post> {
+        ids.into_iter().map(|id| Post { id }).collect()
+    }
+
+    #[test]
+    fn test_connection_args() {
+        let page = ConnectionArgs::new(Some(cursor(3)), None, Some(2), None)
+            .page()
+            .unwrap();
+        assert_eq!(page.after, Some(vec![CursorValue::Int(3)]));
+        assert_eq!(page.limit(), 3);
+        assert!(!page.backward);
+
+        let page = ConnectionArgs::new(None, None, None, None)
+            .default_page_size(5)
+            .page()
+            .unwrap();
+        assert_eq!(page.size, 5);
+
+        for args in [
+            ConnectionArgs::new(None, None, Some(1), Some(1)),
+            ConnectionArgs::new(None, None, Some(-1), None),
+            ConnectionArgs::new(None, None, None, Some(101)),
+            ConnectionArgs::new(Some("nope".into()), None, None, None),
+        ] {
+            assert!(args.page().is_err());
+        }
+    }
+
+    #[test]
+    fn test_forward_page() {
+        let page = ConnectionArgs::new(Some(cursor(1)), None, Some(2), None)
+            .page()
+            .unwrap();
+        assert_eq!(page.query_order(SortOrder::Desc), SortOrder::Desc);
+
+        let connection = page.connection(posts([2, 3, 4]));
+        assert!(connection.has_previous_page);
+        assert!(connection.has_next_page);
+        assert_eq!(connection.edges.len(), 2);
+        assert_eq!(connection.edges[1].cursor.0, vec![CursorValue::Int(3)]);
+
+        let connection = page.connection(posts([2]));
+        assert!(!connection.has_next_page);
+    }
+
+    #[test]
+    fn test_backward_page() {
+        let page = ConnectionArgs::new(None, Some(cursor(10)), None, Some(2))
+            .page()
+            .unwrap();
+        assert_eq!(page.query_order(SortOrder::Asc), SortOrder::Desc);
+
+        // Fetched in reverse: 9, 8, 7
+        let connection = page.connection(posts([9, 8, 7]));
+        let ids: Vec<i64> = connection.edges.iter().map(|edge| edge.node.id).collect();
+        assert_eq!(ids, [8, 9]);
+        assert!(connection.has_previous_page);
+        assert!(connection.has_next_page);
+    }
+
+    #[cfg(feature = "sqlx")]
+    #[test]
+    fn test_sqlx_filter() {
+        let created_at: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        let after: KeysetCursor = OpaqueCursor(vec![created_at.into(), 5.into()]);
+        let after = after.encode_cursor();
+        // `before` has a single value, the ordering two columns
+        let page = ConnectionArgs::new(Some(after), Some(cursor(1)), None, None)
+            .page()
+            .unwrap();
+        let mut query = sqlx::QueryBuilder::new("SELECT * FROM posts");
+        assert!(
+            pg::push_filter(&mut query, &page, &["created_at", "id"], SortOrder::Desc).is_err()
+        );
+
+        let page = Page {
+            before: None,
+            ..page
+        };
+        let mut query = sqlx::QueryBuilder::new("SELECT * FROM posts");
+        pg::push_filter(&mut query, &page, &["created_at", "id"], SortOrder::Desc).unwrap();
+        assert_eq!(
+            query.sql(),
+            "SELECT * FROM posts WHERE (created_at, id) < ($1::timestamptz, $2)"
+        );
+    }
+
+    #[cfg(feature = "sea-orm")]
+    #[test]
+    fn test_sea_select() {
+        use sea_orm::{DbBackend, EntityTrait, QueryTrait};
+
+        mod post {
+            use sea_orm::entity::prelude::*;
+
+            #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+            #[sea_orm(table_name = "posts")]
+            pub struct Model {
+                #[sea_orm(primary_key)]
+                pub id: i64,
+                pub title: String,
+            }
+
+            #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+            pub enum Relation {}
+
+            impl ActiveModelBehavior for ActiveModel {}
+        }
+
+        let page = ConnectionArgs::new(Some(cursor(3)), Some(cursor(9)), None, Some(2))
+            .page()
+            .unwrap();
+        let select = sea::page_select(
+            post::Entity::find(),
+            &page,
+            &[post::Column::Id],
+            SortOrder::Asc,
+        )
+        .unwrap();
+        assert_eq!(
+            select.build(DbBackend::Postgres).to_string(),
+            r#"SELECT "posts"."id", "posts"."title" FROM "posts" WHERE ("id") > (3) AND ("id") < (9) ORDER BY "posts"."id" DESC LIMIT 3"#
+        );
+    }
+}
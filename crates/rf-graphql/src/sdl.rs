@@ -0,0 +1,721 @@
+//! Schema snapshots and breaking-change detection
+//!
+//! Commit the SDL of the schema with [`export_sdl`] and compare every build
+//! against it with [`check_sdl`]. Changes are classified like graphql-js
+//! does:
+//!
+//! - **Breaking**: existing operations fail, e.g. a removed field or a new
+//!   required argument
+//! - **Dangerous**: existing operations keep working but may behave
+//!   differently, e.g. a new enum value clients don't handle
+//! - **Safe**: everything else, e.g. new types and fields
+//!
+//! With the `cli` feature, [`SchemaCommand`] adds `export` and `check`
+//! subcommands to an application's clap CLI, so CI can run
+//! `app graphql check` and fail on accidental breaking changes.
+
+use async_graphql::{
+    parser::{
+        parse_schema,
+        types::{
+            BaseType, FieldDefinition, InputValueDefinition, Type, TypeDefinition, TypeKind,
+            TypeSystemDefinition,
+        },
+    },
+    Name, ObjectType, Positioned, Schema, SubscriptionType,
+};
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+use thiserror::Error;
+
+/// Schema snapshot errors
+#[derive(Debug, Error)]
+pub enum SdlError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid SDL: {0}")]
+    Parse(String),
+}
+
+/// Write the SDL of `schema` to `path`, creating parent directories
+pub fn export_sdl<Q, M, S>(schema: &Schema<Q, M, S>, path: impl AsRef<Path>) -> Result<(), SdlError>
+where
+    Q: ObjectType + 'static,
+    M: ObjectType + 'static,
+    S: SubscriptionType + 'static,
+{
+    let path = path.as_ref();
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, schema.sdl())?;
+    Ok(())
+}
+
+/// Compare `schema` against the snapshot at `path`
+pub fn check_sdl<Q, M, S>(
+    schema: &Schema<Q, M, S>,
+    path: impl AsRef<Path>,
+) -> Result<SchemaDiff, SdlError>
+where
+    Q: ObjectType + 'static,
+    M: ObjectType + 'static,
+    S: SubscriptionType + 'static,
+{
+    let snapshot = fs::read_to_string(path)?;
+    diff_sdl(&snapshot, &schema.sdl())
+}
+
+/// How a change affects existing clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeSeverity {
+    Breaking,
+    Dangerous,
+    Safe,
+}
+
+impl fmt::Display for ChangeSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            ChangeSeverity::Breaking => "BREAKING",
+            ChangeSeverity::Dangerous => "DANGEROUS",
+            ChangeSeverity::Safe => "SAFE",
+        })
+    }
+}
+
+/// One difference between two schemas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaChange {
+    pub severity: ChangeSeverity,
+    /// Schema coordinate, e.g. `User.email` or `Query.users(first:)`
+    pub path: String,
+    pub message: String,
+}
+
+/// Differences between two schemas, most severe first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    pub changes: Vec<SchemaChange>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.count(ChangeSeverity::Breaking) > 0
+    }
+
+    pub fn is_dangerous(&self) -> bool {
+        self.count(ChangeSeverity::Dangerous) > 0
+    }
+
+    pub fn count(&self, severity: ChangeSeverity) -> usize {
+        self.changes
+            .iter()
+            .filter(|change| change.severity == severity)
+            .count()
+    }
+
+    fn push(
+        &mut self,
+        severity: ChangeSeverity,
+        path: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.changes.push(SchemaChange {
+            severity,
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(
+                f,
+                "{:<9} {}: {}",
+                change.severity, change.path, change.message
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Classify the changes from the `old` to the `new` SDL
+pub fn diff_sdl(old: &str, new: &str) -> Result<SchemaDiff, SdlError> {
+    let old = types(old)?;
+    let new = types(new)?;
+    let mut diff = SchemaDiff::default();
+
+    for (name, old_type) in &old {
+        match new.get(name) {
+            Some(new_type) => diff_type(&mut diff, name, old_type, new_type),
+            None => diff.push(ChangeSeverity::Breaking, name, "type was removed"),
+        }
+    }
+    for name in new.keys().filter(|name| !old.contains_key(*name)) {
+        diff.push(ChangeSeverity::Safe, name, "type was added");
+    }
+
+    // Stable: most severe first, then by type name
+    diff.changes.sort_by_key(|change| change.severity);
+    Ok(diff)
+}
+
+fn types(sdl: &str) -> Result<BTreeMap<String, TypeDefinition>, SdlError> {
+    let document = parse_schema(sdl).map_err(|e| SdlError::Parse(e.to_string()))?;
+    Ok(document
+        .definitions
+        .into_iter()
+        .filter_map(|definition| match definition {
+            TypeSystemDefinition::Type(definition) => {
+                Some((definition.node.name.node.to_string(), definition.node))
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+fn kind_name(kind: &TypeKind) -> &'static str {
+    match kind {
+        TypeKind::Scalar => "scalar",
+        TypeKind::Object(_) => "object",
+        TypeKind::Interface(_) => "interface",
+        TypeKind::Union(_) => "union",
+        TypeKind::Enum(_) => "enum",
+        TypeKind::InputObject(_) => "input object",
+    }
+}
+
+fn diff_type(diff: &mut SchemaDiff, name: &str, old: &TypeDefinition, new: &TypeDefinition) {
+    match (&old.kind, &new.kind) {
+        (TypeKind::Scalar, TypeKind::Scalar) => {}
+        (TypeKind::Object(old), TypeKind::Object(new)) => {
+            diff_implements(diff, name, &old.implements, &new.implements);
+            diff_fields(diff, name, &old.fields, &new.fields);
+        }
+        (TypeKind::Interface(old), TypeKind::Interface(new)) => {
+            diff_implements(diff, name, &old.implements, &new.implements);
+            diff_fields(diff, name, &old.fields, &new.fields);
+        }
+        (TypeKind::Union(old), TypeKind::Union(new)) => {
+            let (removed, added) = names_diff(&old.members, &new.members);
+            for member in removed {
+                diff.push(
+                    ChangeSeverity::Breaking,
+                    name,
+                    format!("member {} was removed", member),
+                );
+            }
+            for member in added {
+                diff.push(
+                    ChangeSeverity::Dangerous,
+                    name,
+                    format!("member {} was added", member),
+                );
+            }
+        }
+        (TypeKind::Enum(old), TypeKind::Enum(new)) => {
+            let old_values: Vec<_> = old.values.iter().map(|v| &v.node.value).collect();
+            let new_values: Vec<_> = new.values.iter().map(|v| &v.node.value).collect();
+            let (removed, added) = names_diff(&old_values, &new_values);
+            for value in removed {
+                diff.push(
+                    ChangeSeverity::Breaking,
+                    format!("{}.{}", name, value),
+                    "enum value was removed",
+                );
+            }
+            for value in added {
+                diff.push(
+                    ChangeSeverity::Dangerous,
+                    format!("{}.{}", name, value),
+                    "enum value was added",
+                );
+            }
+        }
+        (TypeKind::InputObject(old), TypeKind::InputObject(new)) => {
+            diff_input_values(diff, name, &old.fields, &new.fields, "input field");
+        }
+        (old, new) => diff.push(
+            ChangeSeverity::Breaking,
+            name,
+            format!("kind changed from {} to {}", kind_name(old), kind_name(new)),
+        ),
+    }
+}
+
+fn diff_implements(
+    diff: &mut SchemaDiff,
+    name: &str,
+    old: &[Positioned<Name>],
+    new: &[Positioned<Name>],
+) {
+    let (removed, added) = names_diff(old, new);
+    for interface in removed {
+        diff.push(
+            ChangeSeverity::Breaking,
+            name,
+            format!("no longer implements {}", interface),
+        );
+    }
+    for interface in added {
+        diff.push(
+            ChangeSeverity::Dangerous,
+            name,
+            format!("now implements {}", interface),
+        );
+    }
+}
+
+fn diff_fields(
+    diff: &mut SchemaDiff,
+    type_name: &str,
+    old: &[Positioned<FieldDefinition>],
+    new: &[Positioned<FieldDefinition>],
+) {
+    for old_field in old {
+        let old_field = &old_field.node;
+        let path = format!("{}.{}", type_name, old_field.name.node);
+        let Some(new_field) = new.iter().find(|f| f.node.name.node == old_field.name.node) else {
+            diff.push(ChangeSeverity::Breaking, path, "field was removed");
+            continue;
+        };
+        let new_field = &new_field.node;
+
+        let (old_ty, new_ty) = (&old_field.ty.node, &new_field.ty.node);
+        if old_ty != new_ty {
+            let severity = if output_compatible(old_ty, new_ty) {
+                ChangeSeverity::Safe
+            } else {
+                ChangeSeverity::Breaking
+            };
+            diff.push(
+                severity,
+                &path,
+                format!("type changed from {} to {}", old_ty, new_ty),
+            );
+        }
+        diff_input_values(
+            diff,
+            &path,
+            &old_field.arguments,
+            &new_field.arguments,
+            "argument",
+        );
+    }
+    for new_field in new {
+        let name = &new_field.node.name.node;
+        if !old.iter().any(|f| &f.node.name.node == name) {
+            diff.push(
+                ChangeSeverity::Safe,
+                format!("{}.{}", type_name, name),
+                "field was added",
+            );
+        }
+    }
+}
+
+/// Arguments of a field or fields of an input object
+fn diff_input_values(
+    diff: &mut SchemaDiff,
+    parent: &str,
+    old: &[Positioned<InputValueDefinition>],
+    new: &[Positioned<InputValueDefinition>],
+    what: &str,
+) {
+    let path = |name: &str| match what {
+        "argument" => format!("{}({}:)", parent, name),
+        _ => format!("{}.{}", parent, name),
+    };
+
+    for old_value in old {
+        let old_value = &old_value.node;
+        let name = old_value.name.node.as_str();
+        let Some(new_value) = new.iter().find(|v| v.node.name.node == old_value.name.node) else {
+            diff.push(
+                ChangeSeverity::Breaking,
+                path(name),
+                format!("{} was removed", what),
+            );
+            continue;
+        };
+        let new_value = &new_value.node;
+
+        let (old_ty, new_ty) = (&old_value.ty.node, &new_value.ty.node);
+        if old_ty != new_ty {
+            let severity = if input_compatible(old_ty, new_ty) {
+                ChangeSeverity::Safe
+            } else {
+                ChangeSeverity::Breaking
+            };
+            diff.push(
+                severity,
+                path(name),
+                format!("type changed from {} to {}", old_ty, new_ty),
+            );
+        }
+
+        let old_default = old_value.default_value.as_ref().map(|v| &v.node);
+        let new_default = new_value.default_value.as_ref().map(|v| &v.node);
+        if old_default.is_some() && old_default != new_default {
+            let new_default = new_default.map_or("none".to_string(), ToString::to_string);
+            diff.push(
+                ChangeSeverity::Dangerous,
+                path(name),
+                format!("default value changed to {}", new_default),
+            );
+        }
+    }
+
+    for new_value in new {
+        let new_value = &new_value.node;
+        if old.iter().any(|v| v.node.name.node == new_value.name.node) {
+            continue;
+        }
+        let name = new_value.name.node.as_str();
+        if !new_value.ty.node.nullable && new_value.default_value.is_none() {
+            diff.push(
+                ChangeSeverity::Breaking,
+                path(name),
+                format!("required {} was added", what),
+            );
+        } else {
+            diff.push(
+                ChangeSeverity::Dangerous,
+                path(name),
+                format!("optional {} was added", what),
+            );
+        }
+    }
+}
+
+/// Outputs may become stricter: `String` to `String!` is safe
+fn output_compatible(old: &Type, new: &Type) -> bool {
+    if !old.nullable && new.nullable {
+        return false;
+    }
+    match (&old.base, &new.base) {
+        (BaseType::Named(old), BaseType::Named(new)) => old == new,
+        (BaseType::List(old), BaseType::List(new)) => output_compatible(old, new),
+        _ => false,
+    }
+}
+
+/// Inputs may become looser: `String!` to `String` is safe
+fn input_compatible(old: &Type, new: &Type) -> bool {
+    if old.nullable && !new.nullable {
+        return false;
+    }
+    match (&old.base, &new.base) {
+        (BaseType::Named(old), BaseType::Named(new)) => old == new,
+        (BaseType::List(old), BaseType::List(new)) => input_compatible(old, new),
+        _ => false,
+    }
+}
+
+/// Names only in `old` and only in `new`
+fn names_diff<'a, N: PartialEq>(old: &'a [N], new: &'a [N]) -> (Vec<&'a N>, Vec<&'a N>) {
+    let removed = old.iter().filter(|name| !new.contains(name)).collect();
+    let added = new.iter().filter(|name| !old.contains(name)).collect();
+    (removed, added)
+}
+
+/// `export` and `check` subcommands for an application's CLI
+///
+/// ```ignore
+/// #[derive(clap::Subcommand)]
+/// enum Command {
+///     Serve,
+///     /// GraphQL schema snapshots
+///     #[command(subcommand)]
+///     Graphql(rf_graphql::SchemaCommand),
+/// }
+///
+/// match cli.command {
+///     Command::Graphql(command) => return command.run(&build_schema()),
+///     // …
+/// }
+/// ```
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum SchemaCommand {
+    /// Write the schema SDL to a snapshot file
+    Export {
+        #[arg(default_value = "schema.graphql")]
+        path: std::path::PathBuf,
+    },
+    /// Compare the schema against a snapshot; fails on breaking changes
+    Check {
+        #[arg(default_value = "schema.graphql")]
+        path: std::path::PathBuf,
+        /// Fail on dangerous changes too
+        #[arg(long)]
+        strict: bool,
+    },
+}
+
+#[cfg(feature = "cli")]
+impl SchemaCommand {
+    /// Run the command, printing its report
+    pub fn run<Q, M, S>(&self, schema: &Schema<Q, M, S>) -> std::process::ExitCode
+    where
+        Q: ObjectType + 'static,
+        M: ObjectType + 'static,
+        S: SubscriptionType + 'static,
+    {
+        match self.execute(schema) {
+            Ok((report, true)) => {
+                print!("{}", report);
+                std::process::ExitCode::SUCCESS
+            }
+            Ok((report, false)) => {
+                eprint!("{}", report);
+                std::process::ExitCode::FAILURE
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::ExitCode::FAILURE
+            }
+        }
+    }
+
+    /// The report and whether the command passed
+    fn execute<Q, M, S>(&self, schema: &Schema<Q, M, S>) -> Result<(String, bool), SdlError>
+    where
+        Q: ObjectType + 'static,
+        M: ObjectType + 'static,
+        S: SubscriptionType + 'static,
+    {
+        match self {
+            SchemaCommand::Export { path } => {
+                export_sdl(schema, path)?;
+                Ok((format!("Schema written to {}\n", path.display()), true))
+            }
+            SchemaCommand::Check { path, strict } => {
+                let diff = check_sdl(schema, path)?;
+                let passed = !(diff.is_breaking() || *strict && diff.is_dangerous());
+                let summary = format!(
+                    "{} breaking, {} dangerous, {} safe changes\n",
+                    diff.count(ChangeSeverity::Breaking),
+                    diff.count(ChangeSeverity::Dangerous),
+                    diff.count(ChangeSeverity::Safe),
+                );
+                Ok((format!("{}{}", diff, summary), passed))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = r#"
+        type Query {
+            user(id: ID!): User
+            users(first: Int = 20): [User!]!
+            legacy: String
+        }
+
+        type User implements Node {
+            id: ID!
+            name: String
+            email: String!
+            role: Role!
+        }
+
+        interface Node {
+            id: ID!
+        }
+
+        enum Role {
+            ADMIN
+            EDITOR
+        }
+
+        union SearchResult = User
+
+        input UserFilter {
+            name: String
+            role: Role!
+        }
+
+        type Removed {
+            id: ID!
+        }
+    "#;
+
+    const NEW: &str = r#"
+        type Query {
+            user(id: ID!, tenant: String!): User
+            users(first: Int = 50, after: String): [User!]!
+            search(term: String!): [SearchResult!]!
+        }
+
+        type User {
+            id: ID!
+            name: String!
+            email: String
+            role: Role!
+        }
+
+        interface Node {
+            id: ID!
+        }
+
+        enum Role {
+            ADMIN
+            VIEWER
+        }
+
+        union SearchResult = User | Post
+
+        type Post {
+            id: ID!
+        }
+
+        input UserFilter {
+            name: String
+            role: Role
+            active: Boolean!
+        }
+
+        scalar Removed
+    "#;
+
+    fn find<'a>(diff: &'a SchemaDiff, path: &str) -> Vec<(&'a ChangeSeverity, &'a str)> {
+        diff.changes
+            .iter()
+            .filter(|change| change.path == path)
+            .map(|change| (&change.severity, change.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_sdl() {
+        use ChangeSeverity::*;
+
+        let diff = diff_sdl(OLD, NEW).unwrap();
+        assert!(diff.is_breaking());
+
+        assert_eq!(
+            find(&diff, "Query.legacy"),
+            [(&Breaking, "field was removed")]
+        );
+        assert_eq!(find(&diff, "Query.search"), [(&Safe, "field was added")]);
+        assert_eq!(
+            find(&diff, "Query.user(tenant:)"),
+            [(&Breaking, "required argument was added")]
+        );
+        assert_eq!(
+            find(&diff, "Query.users(first:)"),
+            [(&Dangerous, "default value changed to 50")]
+        );
+        assert_eq!(
+            find(&diff, "Query.users(after:)"),
+            [(&Dangerous, "optional argument was added")]
+        );
+        assert_eq!(
+            find(&diff, "User"),
+            [(&Breaking, "no longer implements Node")]
+        );
+        assert_eq!(
+            find(&diff, "User.name"),
+            [(&Safe, "type changed from String to String!")]
+        );
+        assert_eq!(
+            find(&diff, "User.email"),
+            [(&Breaking, "type changed from String! to String")]
+        );
+        assert_eq!(
+            find(&diff, "Role.EDITOR"),
+            [(&Breaking, "enum value was removed")]
+        );
+        assert_eq!(
+            find(&diff, "Role.VIEWER"),
+            [(&Dangerous, "enum value was added")]
+        );
+        assert_eq!(
+            find(&diff, "SearchResult"),
+            [(&Dangerous, "member Post was added")]
+        );
+        assert_eq!(find(&diff, "Post"), [(&Safe, "type was added")]);
+        assert_eq!(
+            find(&diff, "UserFilter.role"),
+            [(&Safe, "type changed from Role! to Role")]
+        );
+        assert_eq!(
+            find(&diff, "UserFilter.active"),
+            [(&Breaking, "required input field was added")]
+        );
+        assert_eq!(
+            find(&diff, "Removed"),
+            [(&Breaking, "kind changed from object to scalar")]
+        );
+
+        // Most severe first
+        assert!(diff
+            .changes
+            .windows(2)
+            .all(|pair| pair[0].severity <= pair[1].severity));
+    }
+
+    #[test]
+    fn test_unchanged_and_invalid_sdl() {
+        assert!(diff_sdl(OLD, OLD).unwrap().is_empty());
+        assert!(matches!(diff_sdl("type {", OLD), Err(SdlError::Parse(_))));
+    }
+
+    #[test]
+    fn test_export_and_check() {
+        use async_graphql::{EmptyMutation, EmptySubscription, Object};
+
+        struct Query;
+
+        #[Object]
+        impl Query {
+            async fn version(&self) -> i32 {
+                1
+            }
+        }
+
+        let dir = std::env::temp_dir().join(format!("rf-graphql-sdl-{}", std::process::id()));
+        let path = dir.join("snapshots/schema.graphql");
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+
+        export_sdl(&schema, &path).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("version: Int!"));
+        assert!(check_sdl(&schema, &path).unwrap().is_empty());
+
+        fs::write(&path, "type Query { version: Int! name: String }").unwrap();
+        let diff = check_sdl(&schema, &path).unwrap();
+        assert_eq!(find(&diff, "Query.name")[0].0, &ChangeSeverity::Breaking);
+
+        #[cfg(feature = "cli")]
+        {
+            let check = SchemaCommand::Check {
+                path: path.clone(),
+                strict: false,
+            };
+            let (report, passed) = check.execute(&schema).unwrap();
+            assert!(!passed);
+            assert!(report.contains("BREAKING  Query.name: field was removed"));
+            assert!(report.ends_with("1 breaking, 0 dangerous, 0 safe changes\n"));
+
+            let export = SchemaCommand::Export { path: path.clone() };
+            assert!(export.execute(&schema).unwrap().1);
+            assert!(check.execute(&schema).unwrap().1);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+//! Batch loaders for SeaORM
+//!
+//! ```ignore
+//! let loaders = loader_context()
+//!     .loader(SeaLoader::<user::Entity, i64>::new(db.clone(), user::Column::Id))
+//!     .loader(SeaGroupLoader::<post::Entity, i64>::new(db, post::Column::AuthorId));
+//! ```
+
+use async_graphql::dataloader::Loader;
+use sea_orm::{
+    sea_query::{Value, ValueType},
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, ModelTrait, QueryFilter, QueryOrder,
+    Select,
+};
+use std::{collections::HashMap, hash::Hash, marker::PhantomData, sync::Arc};
+
+/// Loads models of `E` by a unique column of key type `K`
+pub struct SeaLoader<E: EntityTrait, K> {
+    query: SeaQuery<E, K>,
+}
+
+/// Loads all models of `E` by a foreign key column of key type `K`
+pub struct SeaGroupLoader<E: EntityTrait, K> {
+    query: SeaQuery<E, K>,
+}
+
+struct SeaQuery<E: EntityTrait, K> {
+    db: DatabaseConnection,
+    column: E::Column,
+    order_by: Option<E::Column>,
+    _key: PhantomData<fn() -> K>,
+}
+
+impl<E: EntityTrait, K> SeaLoader<E, K> {
+    pub fn new(db: DatabaseConnection, column: E::Column) -> Self {
+        Self {
+            query: SeaQuery::new(db, column),
+        }
+    }
+}
+
+impl<E: EntityTrait, K> SeaGroupLoader<E, K> {
+    /// Keys without models load an empty list
+    pub fn new(db: DatabaseConnection, column: E::Column) -> Self {
+        Self {
+            query: SeaQuery::new(db, column),
+        }
+    }
+
+    /// Order the models of each key by `column`
+    pub fn order_by(mut self, column: E::Column) -> Self {
+        self.query.order_by = Some(column);
+        self
+    }
+}
+
+impl<E: EntityTrait, K> SeaQuery<E, K> {
+    fn new(db: DatabaseConnection, column: E::Column) -> Self {
+        Self {
+            db,
+            column,
+            order_by: None,
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<E, K> SeaQuery<E, K>
+where
+    E: EntityTrait,
+    K: Clone + Into<Value> + ValueType,
+{
+    fn select(&self, keys: &[K]) -> Select<E> {
+        let select = E::find().filter(self.column.is_in(keys.iter().cloned()));
+        match self.order_by {
+            Some(column) => select.order_by_asc(column),
+            None => select,
+        }
+    }
+
+    async fn fetch(&self, keys: &[K]) -> Result<Vec<(K, E::Model)>, Arc<DbErr>> {
+        let models = self.select(keys).all(&self.db).await.map_err(Arc::new)?;
+        models
+            .into_iter()
+            .map(|model| {
+                let key = K::try_from(model.get(self.column)).map_err(|_| {
+                    Arc::new(DbErr::Type(format!(
+                        "column {:?} doesn't hold the loader's key type",
+                        self.column
+                    )))
+                })?;
+                Ok((key, model))
+            })
+            .collect()
+    }
+}
+
+impl<E, K> Loader<K> for SeaLoader<E, K>
+where
+    E: EntityTrait,
+    E::Model: Sync,
+    K: Hash + Eq + Clone + Send + Sync + 'static + Into<Value> + ValueType,
+{
+    type Value = E::Model;
+    type Error = Arc<DbErr>;
+
+    async fn load(&self, keys: &[K]) -> Result<HashMap<K, E::Model>, Self::Error> {
+        Ok(self.query.fetch(keys).await?.into_iter().collect())
+    }
+}
+
+impl<E, K> Loader<K> for SeaGroupLoader<E, K>
+where
+    E: EntityTrait,
+    E::Model: Sync,
+    K: Hash + Eq + Clone + Send + Sync + 'static + Into<Value> + ValueType,
+{
+    type Value = Vec<E::Model>;
+    type Error = Arc<DbErr>;
+
+    async fn load(&self, keys: &[K]) -> Result<HashMap<K, Vec<E::Model>>, Self::Error> {
+        let mut groups: HashMap<K, Vec<E::Model>> =
+            keys.iter().map(|key| (key.clone(), Vec::new())).collect();
+        for (key, model) in self.query.fetch(keys).await? {
+            groups.entry(key).or_default().push(model);
+        }
+        Ok(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DbBackend, MockDatabase, QueryTrait};
+
+    mod comment {
+        use sea_orm::entity::prelude::*;
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+        #[sea_orm(table_name = "comments")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i64,
+            pub post_id: i64,
+            pub body: String,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    fn comment(id: i64, post_id: i64) -> comment::Model {
+        comment::Model {
+            id,
+            post_id,
+            body: format!("Comment {}", id),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sea_group_loader() {
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([vec![comment(1, 1), comment(2, 1), comment(3, 3)]])
+            .into_connection();
+        let loader = SeaGroupLoader::<comment::Entity, i64>::new(db, comment::Column::PostId)
+            .order_by(comment::Column::Id);
+
+        assert_eq!(
+            loader
+                .query
+                .select(&[1, 2, 3])
+                .build(DbBackend::Postgres)
+                .to_string(),
+            r#"SELECT "comments"."id", "comments"."post_id", "comments"."body" FROM "comments" WHERE "comments"."post_id" IN (1, 2, 3) ORDER BY "comments"."id" ASC"#
+        );
+
+        let groups = loader.load(&[1, 2, 3]).await.unwrap();
+        assert_eq!(groups[&1].len(), 2);
+        assert!(groups[&2].is_empty());
+        assert_eq!(groups[&3][0].body, "Comment 3");
+    }
+
+    #[tokio::test]
+    async fn test_sea_loader() {
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([vec![comment(1, 1), comment(2, 1)]])
+            .into_connection();
+        let loader = SeaLoader::<comment::Entity, i64>::new(db, comment::Column::Id);
+
+        let comments = loader.load(&[1, 2, 4]).await.unwrap();
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[&2].post_id, 1);
+    }
+}
@@ -0,0 +1,271 @@
+//! Batch loaders for sqlx (PostgreSQL)
+//!
+//! [`SqlLoader`] loads rows by a unique column, [`SqlGroupLoader`] loads all
+//! rows sharing a foreign key. Both turn a batch of keys into a single
+//! `SELECT … WHERE column IN (…)`.
+//!
+//! ```ignore
+//! let loaders = loader_context()
+//!     .loader(SqlLoader::new(pool.clone(), "SELECT * FROM users", "id", |u: &User| u.id))
+//!     .loader(SqlGroupLoader::new(pool, "SELECT * FROM posts", "author_id", |p: &Post| p.author_id));
+//! ```
+
+use async_graphql::dataloader::Loader;
+use sqlx::{postgres::PgRow, Encode, FromRow, PgPool, Postgres, QueryBuilder, Type};
+use std::{collections::HashMap, hash::Hash, marker::PhantomData, sync::Arc};
+
+/// Loads rows of type `V` by a unique key column
+pub struct SqlLoader<K, V> {
+    query: SqlQuery<K, V>,
+}
+
+/// Loads all rows of type `V` by a foreign key column
+pub struct SqlGroupLoader<K, V> {
+    query: SqlQuery<K, V>,
+}
+
+struct SqlQuery<K, V> {
+    pool: PgPool,
+    select: String,
+    column: String,
+    key: fn(&V) -> K,
+    _key: PhantomData<fn() -> K>,
+}
+
+impl<K, V> SqlLoader<K, V> {
+    /// Loader appending `WHERE column IN (…)` to `select`
+    ///
+    /// `select` must not have a `WHERE` clause, e.g. `SELECT * FROM users`.
+    /// `key` reads the column's value from a loaded row.
+    pub fn new(
+        pool: PgPool,
+        select: impl Into<String>,
+        column: impl Into<String>,
+        key: fn(&V) -> K,
+    ) -> Self {
+        Self {
+            query: SqlQuery::new(pool, select.into(), column.into(), key),
+        }
+    }
+}
+
+impl<K, V> SqlGroupLoader<K, V> {
+    /// Loader appending `WHERE column IN (…)` to `select`
+    ///
+    /// Keys without rows load an empty list. Add an `ORDER BY` to `select`
+    /// to order the rows of each key.
+    pub fn new(
+        pool: PgPool,
+        select: impl Into<String>,
+        column: impl Into<String>,
+        key: fn(&V) -> K,
+    ) -> Self {
+        Self {
+            query: SqlQuery::new(pool, select.into(), column.into(), key),
+        }
+    }
+}
+
+impl<K, V> SqlQuery<K, V> {
+    fn new(pool: PgPool, select: String, column: String, key: fn(&V) -> K) -> Self {
+        Self {
+            pool,
+            select,
+            column,
+            key,
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K, V> SqlQuery<K, V>
+where
+    K: Clone + Send + for<'q> Encode<'q, Postgres> + Type<Postgres>,
+    V: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+{
+    fn builder(&self, keys: &[K]) -> QueryBuilder<'_, Postgres> {
+        let (select, order) = split_order_by(&self.select);
+        let mut builder = QueryBuilder::new(select);
+        builder.push(format!(" WHERE {} IN (", self.column));
+        let mut separated = builder.separated(", ");
+        for key in keys {
+            separated.push_bind(key.clone());
+        }
+        builder.push(")");
+        if let Some(order) = order {
+            builder.push(" ").push(order);
+        }
+        builder
+    }
+
+    async fn fetch(&self, keys: &[K]) -> Result<Vec<V>, Arc<sqlx::Error>> {
+        self.builder(keys)
+            .build_query_as()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(Arc::new)
+    }
+}
+
+/// Split a trailing `ORDER BY` off `select` so the filter goes before it
+fn split_order_by(select: &str) -> (&str, Option<&str>) {
+    match select.to_ascii_uppercase().rfind(" ORDER BY ") {
+        Some(index) => (select[..index].trim_end(), Some(select[index..].trim())),
+        None => (select.trim_end(), None),
+    }
+}
+
+impl<K, V> Loader<K> for SqlLoader<K, V>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + for<'q> Encode<'q, Postgres> + Type<Postgres>,
+    V: for<'r> FromRow<'r, PgRow> + Clone + Send + Sync + Unpin + 'static,
+{
+    type Value = V;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(&self, keys: &[K]) -> Result<HashMap<K, V>, Self::Error> {
+        let rows = self.query.fetch(keys).await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| ((self.query.key)(&row), row))
+            .collect())
+    }
+}
+
+impl<K, V> Loader<K> for SqlGroupLoader<K, V>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static + for<'q> Encode<'q, Postgres> + Type<Postgres>,
+    V: for<'r> FromRow<'r, PgRow> + Clone + Send + Sync + Unpin + 'static,
+{
+    type Value = Vec<V>;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(&self, keys: &[K]) -> Result<HashMap<K, Vec<V>>, Self::Error> {
+        let mut groups: HashMap<K, Vec<V>> =
+            keys.iter().map(|key| (key.clone(), Vec::new())).collect();
+        for row in self.query.fetch(keys).await? {
+            groups.entry((self.query.key)(&row)).or_default().push(row);
+        }
+        Ok(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{loader_context, LoaderContextExt};
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+    use sqlx::Executor;
+
+    #[derive(Clone, sqlx::FromRow, SimpleObject)]
+    struct Comment {
+        id: i64,
+        post_id: i64,
+        body: String,
+    }
+
+    fn pool() -> PgPool {
+        PgPool::connect_lazy("postgres://localhost/rustforge_test").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_query() {
+        let loader = SqlGroupLoader::new(
+            pool(),
+            "SELECT * FROM comments ORDER BY id DESC",
+            "post_id",
+            |c: &Comment| c.post_id,
+        );
+        assert_eq!(
+            loader.query.builder(&[1, 2, 3]).sql(),
+            "SELECT * FROM comments WHERE post_id IN ($1, $2, $3) ORDER BY id DESC"
+        );
+        assert_eq!(
+            split_order_by("select * from comments order by id"),
+            ("select * from comments", Some("order by id"))
+        );
+    }
+
+    #[cfg(feature = "derive")]
+    #[tokio::test]
+    async fn test_derive_batch_loader() {
+        #[derive(Clone, sqlx::FromRow, crate::BatchLoader)]
+        #[loader(table = "reviews", order_by = "id")]
+        struct Review {
+            #[loader(key)]
+            uuid: String,
+            #[loader(foreign_key)]
+            product_id: i64,
+        }
+
+        let loaders = loader_context()
+            .loader(ReviewLoader::new(pool()))
+            .loader(ReviewByProductIdLoader::new(pool()));
+        let by_product = loaders.get::<ReviewByProductIdLoader>().unwrap();
+        assert_eq!(
+            by_product.loader().0.query.builder(&[7]).sql(),
+            "SELECT * FROM reviews WHERE product_id IN ($1) ORDER BY id"
+        );
+        let by_uuid = loaders.get::<ReviewLoader>().unwrap();
+        assert_eq!(
+            by_uuid.loader().0.query.builder(&["a".to_string()]).sql(),
+            "SELECT * FROM reviews WHERE uuid IN ($1) ORDER BY id"
+        );
+    }
+
+    struct Post {
+        id: i64,
+    }
+
+    #[Object]
+    impl Post {
+        async fn comments(
+            &self,
+            ctx: &async_graphql::Context<'_>,
+        ) -> async_graphql::Result<Vec<Comment>> {
+            let loader = ctx.loader::<SqlGroupLoader<i64, Comment>>()?;
+            Ok(loader.load_one(self.id).await?.unwrap_or_default())
+        }
+    }
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn posts(&self) -> Vec<Post> {
+            (1..=3).map(|id| Post { id }).collect()
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires PostgreSQL
+    async fn test_sql_group_loader() {
+        let pool = PgPool::connect("postgres://localhost/rustforge_test")
+            .await
+            .unwrap();
+        pool.execute(
+            "DROP TABLE IF EXISTS loader_comments;
+             CREATE TABLE loader_comments (id BIGINT PRIMARY KEY, post_id BIGINT NOT NULL, body TEXT NOT NULL);
+             INSERT INTO loader_comments VALUES (1, 1, 'first'), (2, 1, 'second'), (3, 3, 'third');",
+        )
+        .await
+        .unwrap();
+
+        let loaders = loader_context().loader(SqlGroupLoader::new(
+            pool,
+            "SELECT * FROM loader_comments ORDER BY id",
+            "post_id",
+            |c: &Comment| c.post_id,
+        ));
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .data(loaders)
+            .finish();
+
+        let result = schema.execute("{ posts { comments { body } } }").await;
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let data = result.data.into_json().unwrap();
+        assert_eq!(data["posts"][0]["comments"][1]["body"], "second");
+        assert_eq!(data["posts"][1]["comments"].as_array().unwrap().len(), 0);
+        assert_eq!(data["posts"][2]["comments"][0]["body"], "third");
+    }
+}
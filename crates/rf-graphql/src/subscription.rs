@@ -0,0 +1,331 @@
+//! Subscriptions over WebSockets
+//!
+//! [`GraphQLSubscriptions::router`] serves `GET /graphql/ws` with both the
+//! `graphql-transport-ws` and the legacy `graphql-ws` protocol. Clients
+//! authenticate in the `connection_init` payload, since browsers can't set
+//! headers on WebSocket requests:
+//!
+//! ```json
+//! {"type": "connection_init", "payload": {"Authorization": "Bearer <token>"}}
+//! ```
+//!
+//! The resulting [`Identity`], and whatever the connect hook adds, is
+//! available to every subscription of the connection.
+//!
+//! ```no_run
+//! use rf_graphql::*;
+//! use async_graphql::*;
+//! use async_graphql::futures_util::{self, Stream};
+//! use std::time::Duration;
+//!
+//! # struct Query;
+//! # #[Object]
+//! # impl Query { async fn version(&self) -> i32 { 1 } }
+//! struct Subscription;
+//!
+//! #[Subscription]
+//! impl Subscription {
+//!     #[graphql(guard = "Authenticated")]
+//!     async fn ticks(&self) -> impl Stream<Item = i32> {
+//!         futures_util::stream::iter(0..3)
+//!     }
+//! }
+//!
+//! #[derive(Clone)]
+//! struct Tenant(String);
+//!
+//! # fn example(authenticator: impl Authenticator) {
+//! let schema = Schema::new(Query, EmptyMutation, Subscription);
+//! let app = graphql_router(schema.clone()).merge(
+//!     GraphQLSubscriptions::new(schema)
+//!         .authenticator(authenticator)
+//!         .require_auth()
+//!         .keepalive(Duration::from_secs(30))
+//!         .on_connect(|_payload, identity, data| {
+//!             if let Some(tenant) = identity.and_then(|i| i.claims.get("tenant")) {
+//!                 data.insert(Tenant(tenant.to_string()));
+//!             }
+//!             Ok(())
+//!         })
+//!         .router(),
+//! );
+//! # }
+//! ```
+
+use crate::{bearer_token, AuthError, Authenticator, Identity};
+use async_graphql::{
+    http::ALL_WEBSOCKET_PROTOCOLS, Data, ErrorExtensions, ObjectType, Result, Schema,
+    SubscriptionType,
+};
+use async_graphql_axum::{GraphQLProtocol, GraphQLWebSocket};
+use axum::{
+    extract::{State, WebSocketUpgrade},
+    http::HeaderMap,
+    response::Response,
+    routing::get,
+    Extension, Router,
+};
+use std::{sync::Arc, time::Duration};
+
+type ConnectHook =
+    dyn Fn(&serde_json::Value, Option<&Identity>, &mut Data) -> Result<()> + Send + Sync;
+
+/// Create a GraphQL subscription router with default settings
+///
+/// Connections are anonymous unless the auth middleware put an
+/// [`Identity`] into the extensions of the upgrade request.
+pub fn graphql_subscription_router<Q, M, S>(schema: Schema<Q, M, S>) -> Router
+where
+    Q: ObjectType + 'static,
+    M: ObjectType + 'static,
+    S: SubscriptionType + 'static,
+{
+    GraphQLSubscriptions::new(schema).router()
+}
+
+/// Configurable WebSocket endpoint for subscriptions
+pub struct GraphQLSubscriptions<Q, M, S> {
+    schema: Schema<Q, M, S>,
+    connection: ConnectionConfig,
+}
+
+#[derive(Clone, Default)]
+struct ConnectionConfig {
+    authenticator: Option<Arc<dyn Authenticator>>,
+    require_auth: bool,
+    keepalive: Option<Duration>,
+    on_connect: Option<Arc<ConnectHook>>,
+}
+
+impl<Q, M, S> GraphQLSubscriptions<Q, M, S>
+where
+    Q: ObjectType + 'static,
+    M: ObjectType + 'static,
+    S: SubscriptionType + 'static,
+{
+    pub fn new(schema: Schema<Q, M, S>) -> Self {
+        Self {
+            schema,
+            connection: ConnectionConfig {
+                keepalive: Some(Duration::from_secs(30)),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Resolve the token of the `connection_init` payload
+    pub fn authenticator(mut self, authenticator: impl Authenticator) -> Self {
+        self.connection.authenticator = Some(Arc::new(authenticator));
+        self
+    }
+
+    /// Reject connections without an identity
+    pub fn require_auth(mut self) -> Self {
+        self.connection.require_auth = true;
+        self
+    }
+
+    /// Close connections that don't answer pings within `timeout`
+    /// (default: 30 seconds)
+    pub fn keepalive(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.connection.keepalive = timeout.into();
+        self
+    }
+
+    /// Add per-connection data, e.g. the tenant, from the `connection_init`
+    /// payload and the identity; an error rejects the connection
+    pub fn on_connect<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&serde_json::Value, Option<&Identity>, &mut Data) -> Result<()>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.connection.on_connect = Some(Arc::new(hook));
+        self
+    }
+
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/graphql/ws", get(subscription_handler::<Q, M, S>))
+            .with_state(Arc::new(self))
+    }
+}
+
+async fn subscription_handler<Q, M, S>(
+    State(subscriptions): State<Arc<GraphQLSubscriptions<Q, M, S>>>,
+    protocol: GraphQLProtocol,
+    identity: Option<Extension<Identity>>,
+    upgrade: WebSocketUpgrade,
+) -> Response
+where
+    Q: ObjectType + 'static,
+    M: ObjectType + 'static,
+    S: SubscriptionType + 'static,
+{
+    let schema = subscriptions.schema.clone();
+    let connection = subscriptions.connection.clone();
+    let identity = identity.map(|Extension(identity)| identity);
+
+    upgrade
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |socket| async move {
+            GraphQLWebSocket::new(socket, schema, protocol)
+                .keepalive_timeout(connection.keepalive)
+                .on_connection_init(move |payload| async move {
+                    connection.connection_data(&payload, identity).await
+                })
+                .serve()
+                .await
+        })
+}
+
+impl ConnectionConfig {
+    /// Data of a connection, from its `connection_init` payload and the
+    /// identity of the upgrade request
+    async fn connection_data(
+        &self,
+        payload: &serde_json::Value,
+        mut identity: Option<Identity>,
+    ) -> Result<Data> {
+        if let (Some(authenticator), Some(token)) = (&self.authenticator, payload_token(payload)) {
+            let authenticated = authenticator.authenticate(&token).await.map_err(|e| {
+                tracing::debug!("GraphQL subscription rejected: {}", e);
+                e.extend()
+            })?;
+            identity = Some(authenticated);
+        }
+        if self.require_auth && identity.is_none() {
+            return Err(AuthError::Unauthenticated.extend());
+        }
+
+        let mut data = Data::default();
+        if let Some(hook) = &self.on_connect {
+            hook(payload, identity.as_ref(), &mut data)?;
+        }
+        if let Some(identity) = identity {
+            data.insert(identity);
+        }
+        Ok(data)
+    }
+}
+
+/// Token of a `connection_init` payload: an `Authorization` bearer value
+/// or a plain `token`/`authToken`
+fn payload_token(payload: &serde_json::Value) -> Option<String> {
+    let payload = payload.as_object()?;
+    let authorization = payload
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("authorization"))
+        .and_then(|(_, value)| value.as_str());
+    if let Some(authorization) = authorization {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::AUTHORIZATION,
+            authorization.parse().ok()?,
+        );
+        return bearer_token(&headers).map(str::to_string);
+    }
+
+    ["token", "authToken"]
+        .iter()
+        .find_map(|key| payload.get(*key)?.as_str())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::any::TypeId;
+
+    struct TokenAuthenticator;
+
+    #[async_trait]
+    impl Authenticator for TokenAuthenticator {
+        async fn authenticate(&self, token: &str) -> std::result::Result<Identity, AuthError> {
+            match token {
+                "secret" => Ok(Identity::new("7").claim("tenant", "acme")),
+                _ => Err(AuthError::Unauthenticated),
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Tenant(String);
+
+    fn get<T: 'static>(data: &Data) -> Option<&T> {
+        data.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    #[test]
+    fn test_payload_token() {
+        assert_eq!(
+            payload_token(&json!({"Authorization": "Bearer abc"})).as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            payload_token(&json!({"authorization": "Bearer abc"})).as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            payload_token(&json!({"token": "abc"})).as_deref(),
+            Some("abc")
+        );
+        assert_eq!(payload_token(&json!({"Authorization": "abc"})), None);
+        assert_eq!(payload_token(&json!(null)), None);
+    }
+
+    #[tokio::test]
+    async fn test_connection_data() {
+        let connection = ConnectionConfig {
+            authenticator: Some(Arc::new(TokenAuthenticator)),
+            require_auth: true,
+            keepalive: None,
+            on_connect: Some(Arc::new(|payload, identity, data| {
+                let tenant = identity
+                    .and_then(|identity| identity.claims.get("tenant"))
+                    .or_else(|| payload.get("tenant"))
+                    .and_then(|tenant| tenant.as_str())
+                    .ok_or_else(|| async_graphql::Error::new("tenant required"))?;
+                data.insert(Tenant(tenant.to_string()));
+                Ok(())
+            })),
+        };
+
+        let data = connection
+            .connection_data(&json!({"Authorization": "Bearer secret"}), None)
+            .await
+            .unwrap();
+        assert_eq!(get::<Identity>(&data).unwrap().id, "7");
+        assert_eq!(get::<Tenant>(&data), Some(&Tenant("acme".into())));
+
+        let error = connection
+            .connection_data(&json!({"token": "wrong"}), None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.extensions.unwrap().get("code").unwrap().to_string(),
+            "\"UNAUTHENTICATED\""
+        );
+        assert!(connection.connection_data(&json!({}), None).await.is_err());
+
+        // Identity from the upgrade request, tenant from the payload
+        let data = connection
+            .connection_data(&json!({"tenant": "globex"}), Some(Identity::new("1")))
+            .await
+            .unwrap();
+        assert_eq!(get::<Identity>(&data).unwrap().id, "1");
+        assert_eq!(get::<Tenant>(&data), Some(&Tenant("globex".into())));
+    }
+
+    #[tokio::test]
+    async fn test_anonymous_connection() {
+        let data = ConnectionConfig::default()
+            .connection_data(&json!({"token": "ignored"}), None)
+            .await
+            .unwrap();
+        assert!(get::<Identity>(&data).is_none());
+    }
+}
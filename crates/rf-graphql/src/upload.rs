@@ -0,0 +1,231 @@
+//! File uploads in mutations with rf-upload
+//!
+//! Arguments of type [`Upload`] carry the files of a
+//! [GraphQL multipart request](https://github.com/jaydenseric/graphql-multipart-request-spec),
+//! which [`graphql_router`](crate::graphql_router) accepts. [`UploadExt`]
+//! turns them into rf-upload files, enforcing an [`UploadConfig`]:
+//!
+//! ```no_run
+//! use rf_graphql::UploadExt;
+//! use rf_upload::UploadConfig;
+//! use async_graphql::*;
+//!
+//! struct Mutation;
+//!
+//! #[Object]
+//! impl Mutation {
+//!     async fn upload_avatar(&self, ctx: &Context<'_>, file: Upload) -> Result<String> {
+//!         let config = UploadConfig {
+//!             allowed_mime_types: vec!["image/".into()],
+//!             max_size: Some(2 * 1024 * 1024),
+//!             ..Default::default()
+//!         };
+//!         let stored = file.store_on(ctx, "public", "avatars", &(&config).into()).await?;
+//!         Ok(stored.file.key().to_string())
+//!     }
+//! }
+//! ```
+//!
+//! Rejected files fail with a `BAD_USER_INPUT` error.
+
+use crate::{Problem, ProblemResultExt, INTERNAL_SERVER_ERROR};
+use async_graphql::{Context, ErrorExtensionValues, Result, Upload, UploadValue};
+use async_trait::async_trait;
+use mime::Mime;
+use rf_upload::{FieldRules, FileUpload, StreamedUpload, UploadConfig, UploadError};
+use tokio::io::AsyncReadExt;
+
+/// Conversion of uploads to rf-upload files
+#[async_trait]
+pub trait UploadExt {
+    /// Read the file into memory, checking the allowed types and maximum
+    /// size of `config`
+    async fn file_upload(&self, ctx: &Context<'_>, config: &UploadConfig) -> Result<FileUpload>;
+
+    /// Stream the file to `directory` on `disk` without buffering it
+    async fn store_on(
+        &self,
+        ctx: &Context<'_>,
+        disk: &str,
+        directory: &str,
+        rules: &FieldRules,
+    ) -> Result<StreamedUpload>;
+}
+
+#[async_trait]
+impl UploadExt for Upload {
+    async fn file_upload(&self, ctx: &Context<'_>, config: &UploadConfig) -> Result<FileUpload> {
+        let value = self.value(ctx)?;
+        let size = value.size()?;
+        if let Some(max_size) = config.max_size.filter(|max| size > *max) {
+            return Err(UploadError::FileTooLarge(size, max_size).to_graphql_error());
+        }
+
+        let mime_type = mime_type(&value).unwrap_or(mime::APPLICATION_OCTET_STREAM);
+        let filename = value.filename;
+        let mut content = Vec::with_capacity(size as usize);
+        tokio::fs::File::from_std(value.content)
+            .read_to_end(&mut content)
+            .await?;
+
+        let allowed: Vec<&str> = config
+            .allowed_mime_types
+            .iter()
+            .map(String::as_str)
+            .collect();
+        let upload = FileUpload::from_bytes(filename, mime_type, content);
+        upload.validate_mime_type(&allowed).problem()
+    }
+
+    async fn store_on(
+        &self,
+        ctx: &Context<'_>,
+        disk: &str,
+        directory: &str,
+        rules: &FieldRules,
+    ) -> Result<StreamedUpload> {
+        let value = self.value(ctx)?;
+        let mime_type = mime_type(&value);
+        let reader = tokio::fs::File::from_std(value.content);
+        FileUpload::stream_reader(reader, &value.filename, mime_type, disk, directory, rules)
+            .await
+            .problem()
+    }
+}
+
+/// The declared type of an upload
+fn mime_type(value: &UploadValue) -> Option<Mime> {
+    value.content_type.as_deref()?.parse().ok()
+}
+
+impl Problem for UploadError {
+    fn code(&self) -> &'static str {
+        match self {
+            UploadError::InvalidMimeType(_)
+            | UploadError::MimeMismatch(..)
+            | UploadError::Infected(_)
+            | UploadError::FileTooLarge(..)
+            | UploadError::NoFile
+            | UploadError::Invalid(_)
+            | UploadError::SizeMismatch(..)
+            | UploadError::InvalidChecksum(_) => "BAD_USER_INPUT",
+            UploadError::UploadNotFound(_) => "NOT_FOUND",
+            _ => INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn message(&self) -> String {
+        self.to_string()
+    }
+
+    fn details(&self, extensions: &mut ErrorExtensionValues) {
+        if let UploadError::FileTooLarge(size, max_size) = self {
+            extensions.set("size", *size);
+            extensions.set("maxSize", *max_size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::{EmptySubscription, Object, Request, Schema, Variables};
+    use rf_storage::MemoryStorage;
+    use rf_upload::Disks;
+    use std::io::{Seek, Write};
+
+    struct Query;
+
+    #[Object]
+    impl Query {
+        async fn version(&self) -> i32 {
+            1
+        }
+    }
+
+    fn config() -> UploadConfig {
+        UploadConfig {
+            allowed_mime_types: vec!["image/".into()],
+            max_size: Some(16),
+            ..Default::default()
+        }
+    }
+
+    struct Mutation;
+
+    #[Object]
+    impl Mutation {
+        async fn upload_avatar(&self, ctx: &Context<'_>, file: Upload) -> Result<String> {
+            let rules = FieldRules::from(&config());
+            let stored = file
+                .store_on(ctx, "graphql-uploads", "avatars", &rules)
+                .await?;
+            Ok(stored.file.key().to_string())
+        }
+
+        async fn avatar_size(&self, ctx: &Context<'_>, file: Upload) -> Result<u64> {
+            Ok(file.file_upload(ctx, &config()).await?.size())
+        }
+    }
+
+    fn request(mutation: &str, filename: &str, content_type: &str, content: &[u8]) -> Request {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(content).unwrap();
+        file.rewind().unwrap();
+
+        let query = format!("mutation($file: Upload!) {{ {}(file: $file) }}", mutation);
+        let mut request = Request::new(query)
+            .variables(Variables::from_json(serde_json::json!({ "file": null })));
+        request.set_upload(
+            "variables.file",
+            UploadValue {
+                filename: filename.to_string(),
+                content_type: Some(content_type.to_string()),
+                content: file,
+            },
+        );
+        request
+    }
+
+    fn code(response: &async_graphql::Response) -> String {
+        let extensions = response.errors[0].extensions.as_ref().unwrap();
+        extensions.get("code").unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_upload_mutations() {
+        Disks::register("graphql-uploads", MemoryStorage::new());
+        let schema = Schema::new(Query, Mutation, EmptySubscription);
+
+        let response = schema
+            .execute(request("uploadAvatar", "me.png", "image/png", b"png data"))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            response.data.into_json().unwrap()["uploadAvatar"],
+            "avatars/me.png"
+        );
+        let stored = Disks::get("graphql-uploads")
+            .unwrap()
+            .get("avatars/me.png")
+            .await;
+        assert_eq!(stored.unwrap(), b"png data");
+
+        let response = schema
+            .execute(request("uploadAvatar", "me.pdf", "application/pdf", b"pdf"))
+            .await;
+        assert_eq!(code(&response), "\"BAD_USER_INPUT\"");
+
+        let response = schema
+            .execute(request("avatarSize", "me.png", "image/png", b"png data"))
+            .await;
+        assert_eq!(response.data.into_json().unwrap()["avatarSize"], 8);
+
+        let response = schema
+            .execute(request("avatarSize", "big.png", "image/png", &[0; 32]))
+            .await;
+        assert_eq!(code(&response), "\"BAD_USER_INPUT\"");
+        let extensions = response.errors[0].extensions.as_ref().unwrap();
+        assert_eq!(extensions.get("maxSize").unwrap().to_string(), "16");
+    }
+}
@@ -3,6 +3,7 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
 
 /// Health check status
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -57,6 +58,9 @@ pub struct CheckResult {
     pub metadata: HashMap<String, serde_json::Value>,
     /// Timestamp
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// How long the check took, set by the [`HealthChecker`](crate::HealthChecker)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
 }
 
 impl CheckResult {
@@ -68,6 +72,7 @@ impl CheckResult {
             message: None,
             metadata: HashMap::new(),
             timestamp: chrono::Utc::now(),
+            duration_ms: None,
         }
     }
 
@@ -79,6 +84,7 @@ impl CheckResult {
             message: Some(message.into()),
             metadata: HashMap::new(),
             timestamp: chrono::Utc::now(),
+            duration_ms: None,
         }
     }
 
@@ -90,6 +96,7 @@ impl CheckResult {
             message: Some(message.into()),
             metadata: HashMap::new(),
             timestamp: chrono::Utc::now(),
+            duration_ms: None,
         }
     }
 
@@ -118,6 +125,11 @@ pub trait HealthCheck: Send + Sync {
     fn is_readiness(&self) -> bool {
         true
     }
+
+    /// Timeout of this check, overriding the checker's
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
 }
 
 /// Composite health response
@@ -47,9 +47,11 @@ impl MemoryCheck {
             critical_threshold,
         }
     }
+}
 
+impl Default for MemoryCheck {
     /// Default thresholds (80% warning, 95% critical)
-    pub fn default() -> Self {
+    fn default() -> Self {
         Self::new(0.8, 0.95)
     }
 }
@@ -124,9 +126,11 @@ impl DiskCheck {
             critical_threshold,
         }
     }
+}
 
+impl Default for DiskCheck {
     /// Default check for root (80% warning, 95% critical)
-    pub fn default() -> Self {
+    fn default() -> Self {
         Self::new("/", 0.8, 0.95)
     }
 }
@@ -188,94 +192,6 @@ impl HealthCheck for DiskCheck {
     }
 }
 
-/// Database connectivity check (requires "database" feature)
-#[cfg(feature = "database")]
-pub struct DatabaseCheck {
-    pool: sqlx::PgPool,
-}
-
-#[cfg(feature = "database")]
-impl DatabaseCheck {
-    pub fn new(pool: sqlx::PgPool) -> Self {
-        Self { pool }
-    }
-}
-
-#[cfg(feature = "database")]
-#[async_trait]
-impl HealthCheck for DatabaseCheck {
-    fn name(&self) -> &str {
-        "database"
-    }
-
-    async fn check(&self) -> CheckResult {
-        match sqlx::query("SELECT 1").fetch_one(&self.pool).await {
-            Ok(_) => CheckResult::healthy(self.name()),
-            Err(e) => CheckResult::unhealthy(self.name(), format!("Database query failed: {}", e)),
-        }
-    }
-
-    fn is_liveness(&self) -> bool {
-        false
-    }
-
-    fn is_readiness(&self) -> bool {
-        true
-    }
-}
-
-/// Redis connectivity check (requires "redis-check" feature)
-#[cfg(feature = "redis-check")]
-pub struct RedisCheck {
-    pool: deadpool_redis::Pool,
-}
-
-#[cfg(feature = "redis-check")]
-impl RedisCheck {
-    pub fn new(pool: deadpool_redis::Pool) -> Self {
-        Self { pool }
-    }
-
-    pub async fn from_url(redis_url: &str) -> Result<Self, crate::error::HealthError> {
-        use deadpool_redis::{Config, Runtime};
-
-        let cfg = Config::from_url(redis_url);
-        let pool = cfg
-            .create_pool(Some(Runtime::Tokio1))
-            .map_err(|e| crate::error::HealthError::RedisError(e.to_string()))?;
-
-        Ok(Self { pool })
-    }
-}
-
-#[cfg(feature = "redis-check")]
-#[async_trait]
-impl HealthCheck for RedisCheck {
-    fn name(&self) -> &str {
-        "redis"
-    }
-
-    async fn check(&self) -> CheckResult {
-        use redis::AsyncCommands;
-
-        match self.pool.get().await {
-            Ok(mut conn) => match redis::cmd("PING").query_async::<_, String>(&mut conn).await {
-                Ok(_) => CheckResult::healthy(self.name()),
-                Err(e) => CheckResult::unhealthy(self.name(), format!("Redis PING failed: {}", e)),
-            },
-            Err(e) => CheckResult::unhealthy(self.name(), format!("Redis connection failed: {}", e)),
-        }
-    }
-
-    fn is_liveness(&self) -> bool {
-        false
-    }
-
-    fn is_readiness(&self) -> bool {
-        true
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
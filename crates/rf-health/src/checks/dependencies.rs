@@ -0,0 +1,678 @@
+//! Checks of the services an application depends on
+//!
+//! Every check takes a timeout of its own with `with_timeout`, overriding the
+//! checker's, and reports degraded when the dependency answers slower than
+//! `degraded_after`. The measured latency is added as `latency_ms` metadata.
+
+use crate::checker::{CheckResult, HealthCheck, HealthStatus};
+use async_trait::async_trait;
+use serde_json::json;
+use std::future::Future;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+#[cfg(any(feature = "queue", feature = "storage"))]
+use std::sync::Arc;
+
+/// Timeout and latency threshold of a dependency check
+#[derive(Debug, Clone, Copy, Default)]
+struct Limits {
+    timeout: Option<Duration>,
+    degraded_after: Option<Duration>,
+}
+
+/// Run `probe`, unhealthy when it fails and degraded when it's slower than
+/// `degraded_after`
+async fn timed(
+    name: &str,
+    degraded_after: Option<Duration>,
+    probe: impl Future<Output = Result<CheckResult, String>>,
+) -> CheckResult {
+    let started = Instant::now();
+    let result = probe.await;
+    let latency = started.elapsed();
+
+    let result = match result {
+        Ok(result)
+            if result.status.is_healthy() && degraded_after.is_some_and(|max| latency > max) =>
+        {
+            CheckResult {
+                status: HealthStatus::Degraded,
+                message: Some(format!("Slow response: {} ms", latency.as_millis())),
+                ..result
+            }
+        }
+        Ok(result) => result,
+        Err(message) => CheckResult::unhealthy(name, message),
+    };
+    result.with_metadata("latency_ms", json!(latency.as_millis() as u64))
+}
+
+/// SMTP server check, connecting and reading the server's greeting
+pub struct SmtpCheck {
+    name: String,
+    address: String,
+    limits: Limits,
+}
+
+impl SmtpCheck {
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            name: "smtp".to_string(),
+            address: format!("{}:{}", host.into(), port),
+            limits: Limits::default(),
+        }
+    }
+
+    /// Report under `name` instead of "smtp"
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = Some(timeout);
+        self
+    }
+
+    /// Report degraded when the server takes longer than `latency` to greet
+    pub fn degraded_after(mut self, latency: Duration) -> Self {
+        self.limits.degraded_after = Some(latency);
+        self
+    }
+
+    async fn greet(&self) -> Result<CheckResult, String> {
+        let stream = TcpStream::connect(&self.address)
+            .await
+            .map_err(|e| format!("SMTP connection to {} failed: {}", self.address, e))?;
+        let mut stream = BufReader::new(stream);
+        let mut greeting = String::new();
+        stream
+            .read_line(&mut greeting)
+            .await
+            .map_err(|e| format!("SMTP greeting failed: {}", e))?;
+        let greeting = greeting.trim_end();
+        if !greeting.starts_with("220") {
+            return Err(format!("Unexpected SMTP greeting: {}", greeting));
+        }
+
+        let _ = stream.get_mut().write_all(b"QUIT\r\n").await;
+        Ok(CheckResult::healthy(&self.name).with_metadata("greeting", json!(greeting)))
+    }
+}
+
+#[async_trait]
+impl HealthCheck for SmtpCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self) -> CheckResult {
+        timed(&self.name, self.limits.degraded_after, self.greet()).await
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.limits.timeout
+    }
+}
+
+/// Database connectivity check (requires "database" feature)
+///
+/// Pings a connection of a Postgres pool, or of a MySQL or SQLite pool when
+/// sqlx is built with their features.
+#[cfg(feature = "database")]
+pub struct DatabaseCheck<DB: sqlx::Database = sqlx::Postgres> {
+    name: String,
+    pool: sqlx::Pool<DB>,
+    limits: Limits,
+}
+
+#[cfg(feature = "database")]
+impl<DB: sqlx::Database> DatabaseCheck<DB> {
+    pub fn new(pool: sqlx::Pool<DB>) -> Self {
+        Self {
+            name: "database".to_string(),
+            pool,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Report under `name` instead of "database"
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = Some(timeout);
+        self
+    }
+
+    /// Report degraded when a ping takes longer than `latency`
+    pub fn degraded_after(mut self, latency: Duration) -> Self {
+        self.limits.degraded_after = Some(latency);
+        self
+    }
+
+    async fn ping(&self) -> Result<CheckResult, String> {
+        use sqlx::Connection;
+
+        let mut connection = self
+            .pool
+            .acquire()
+            .await
+            .map_err(|e| format!("Database connection failed: {}", e))?;
+        connection
+            .ping()
+            .await
+            .map_err(|e| format!("Database ping failed: {}", e))?;
+        Ok(CheckResult::healthy(&self.name)
+            .with_metadata("pool_size", json!(self.pool.size()))
+            .with_metadata("idle_connections", json!(self.pool.num_idle())))
+    }
+}
+
+#[cfg(feature = "database")]
+#[async_trait]
+impl<DB: sqlx::Database> HealthCheck for DatabaseCheck<DB> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self) -> CheckResult {
+        timed(&self.name, self.limits.degraded_after, self.ping()).await
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.limits.timeout
+    }
+}
+
+/// Redis connectivity check (requires "redis-check" feature)
+#[cfg(feature = "redis-check")]
+pub struct RedisCheck {
+    name: String,
+    pool: deadpool_redis::Pool,
+    limits: Limits,
+}
+
+#[cfg(feature = "redis-check")]
+impl RedisCheck {
+    pub fn new(pool: deadpool_redis::Pool) -> Self {
+        Self {
+            name: "redis".to_string(),
+            pool,
+            limits: Limits::default(),
+        }
+    }
+
+    pub async fn from_url(redis_url: &str) -> Result<Self, crate::error::HealthError> {
+        use deadpool_redis::{Config, Runtime};
+
+        let cfg = Config::from_url(redis_url);
+        let pool = cfg
+            .create_pool(Some(Runtime::Tokio1))
+            .map_err(|e| crate::error::HealthError::RedisError(e.to_string()))?;
+
+        Ok(Self::new(pool))
+    }
+
+    /// Report under `name` instead of "redis"
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = Some(timeout);
+        self
+    }
+
+    /// Report degraded when a PING takes longer than `latency`
+    pub fn degraded_after(mut self, latency: Duration) -> Self {
+        self.limits.degraded_after = Some(latency);
+        self
+    }
+
+    async fn ping(&self) -> Result<CheckResult, String> {
+        let mut conn = self
+            .pool
+            .get()
+            .await
+            .map_err(|e| format!("Redis connection failed: {}", e))?;
+        redis::cmd("PING")
+            .query_async::<_, String>(&mut conn)
+            .await
+            .map_err(|e| format!("Redis PING failed: {}", e))?;
+        Ok(CheckResult::healthy(&self.name))
+    }
+}
+
+#[cfg(feature = "redis-check")]
+#[async_trait]
+impl HealthCheck for RedisCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self) -> CheckResult {
+        timed(&self.name, self.limits.degraded_after, self.ping()).await
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.limits.timeout
+    }
+}
+
+/// Queue backend check (requires "queue" feature)
+///
+/// Asks the backend for the size of its queues, adding them as `pending`
+/// metadata, and reports degraded when a backlog grows beyond
+/// [`degraded_above`](Self::degraded_above).
+#[cfg(feature = "queue")]
+pub struct QueueCheck {
+    name: String,
+    queue: Arc<dyn rf_queue::Queue>,
+    queues: Vec<String>,
+    max_pending: Option<usize>,
+    limits: Limits,
+}
+
+#[cfg(feature = "queue")]
+impl QueueCheck {
+    /// Check the "default" queue of `queue`
+    pub fn new(queue: Arc<dyn rf_queue::Queue>) -> Self {
+        Self {
+            name: "queue".to_string(),
+            queue,
+            queues: vec!["default".to_string()],
+            max_pending: None,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Check these queues instead of "default"
+    pub fn queues<I, S>(mut self, queues: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.queues = queues.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Report degraded when a queue holds more than `pending` jobs
+    pub fn degraded_above(mut self, pending: usize) -> Self {
+        self.max_pending = Some(pending);
+        self
+    }
+
+    /// Report under `name` instead of "queue"
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = Some(timeout);
+        self
+    }
+
+    /// Report degraded when the backend takes longer than `latency` to answer
+    pub fn degraded_after(mut self, latency: Duration) -> Self {
+        self.limits.degraded_after = Some(latency);
+        self
+    }
+
+    async fn sizes(&self) -> Result<CheckResult, String> {
+        let mut pending = serde_json::Map::new();
+        let mut backlog = None;
+        for queue in &self.queues {
+            let size = self
+                .queue
+                .size(queue)
+                .await
+                .map_err(|e| format!("Queue {} unavailable: {}", queue, e))?;
+            if backlog.is_none() && self.max_pending.is_some_and(|max| size > max) {
+                backlog = Some(format!("Backlog of {} jobs on queue {}", size, queue));
+            }
+            pending.insert(queue.clone(), json!(size));
+        }
+
+        let result = match backlog {
+            Some(message) => CheckResult::degraded(&self.name, message),
+            None => CheckResult::healthy(&self.name),
+        };
+        Ok(result.with_metadata("pending", pending.into()))
+    }
+}
+
+#[cfg(feature = "queue")]
+#[async_trait]
+impl HealthCheck for QueueCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self) -> CheckResult {
+        timed(&self.name, self.limits.degraded_after, self.sizes()).await
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.limits.timeout
+    }
+}
+
+/// Object storage check (requires "storage" feature)
+///
+/// Looks up a probe file on the disk, or with [`write`](Self::write) writes,
+/// reads back and deletes it to check write access too.
+#[cfg(feature = "storage")]
+pub struct StorageCheck {
+    name: String,
+    disk: Arc<dyn rf_storage::Filesystem>,
+    path: String,
+    write: bool,
+    limits: Limits,
+}
+
+#[cfg(feature = "storage")]
+impl StorageCheck {
+    pub fn new(disk: Arc<dyn rf_storage::Filesystem>) -> Self {
+        Self {
+            name: "storage".to_string(),
+            disk,
+            path: ".health-check".to_string(),
+            write: false,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Probe `path` instead of ".health-check"
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Round-trip the probe file instead of only looking it up
+    pub fn write(mut self) -> Self {
+        self.write = true;
+        self
+    }
+
+    /// Report under `name` instead of "storage"
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = Some(timeout);
+        self
+    }
+
+    /// Report degraded when the probe takes longer than `latency`
+    pub fn degraded_after(mut self, latency: Duration) -> Self {
+        self.limits.degraded_after = Some(latency);
+        self
+    }
+
+    async fn probe(&self) -> Result<CheckResult, String> {
+        let failed = |e: rf_storage::StorageError| format!("Storage probe failed: {}", e);
+        if !self.write {
+            self.disk.exists(&self.path).await.map_err(failed)?;
+            return Ok(CheckResult::healthy(&self.name));
+        }
+
+        let contents = chrono::Utc::now().to_rfc3339().into_bytes();
+        self.disk
+            .put(&self.path, contents.clone())
+            .await
+            .map_err(failed)?;
+        let read = self.disk.get(&self.path).await.map_err(failed)?;
+        self.disk.delete(&self.path).await.map_err(failed)?;
+        if read != contents {
+            return Err("Storage probe file read back differently".to_string());
+        }
+        Ok(CheckResult::healthy(&self.name))
+    }
+}
+
+#[cfg(feature = "storage")]
+#[async_trait]
+impl HealthCheck for StorageCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self) -> CheckResult {
+        timed(&self.name, self.limits.degraded_after, self.probe()).await
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.limits.timeout
+    }
+}
+
+/// Outbound HTTP dependency check (requires "http-check" feature)
+///
+/// Sends a GET request and expects a success status, or the status set with
+/// [`expect_status`](Self::expect_status).
+#[cfg(feature = "http-check")]
+pub struct HttpCheck {
+    name: String,
+    url: String,
+    client: reqwest::Client,
+    expected_status: Option<u16>,
+    limits: Limits,
+}
+
+#[cfg(feature = "http-check")]
+impl HttpCheck {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            name: "http".to_string(),
+            url: url.into(),
+            client: reqwest::Client::new(),
+            expected_status: None,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Send the request with `client`, e.g. for authentication headers
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Expect `status` instead of any success status
+    pub fn expect_status(mut self, status: u16) -> Self {
+        self.expected_status = Some(status);
+        self
+    }
+
+    /// Report under `name` instead of "http"
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = Some(timeout);
+        self
+    }
+
+    /// Report degraded when the response takes longer than `latency`
+    pub fn degraded_after(mut self, latency: Duration) -> Self {
+        self.limits.degraded_after = Some(latency);
+        self
+    }
+
+    async fn request(&self) -> Result<CheckResult, String> {
+        let status = self
+            .client
+            .get(&self.url)
+            .send()
+            .await
+            .map_err(|e| format!("Request to {} failed: {}", self.url, e))?
+            .status();
+        let expected = match self.expected_status {
+            Some(expected) => status.as_u16() == expected,
+            None => status.is_success(),
+        };
+        if !expected {
+            return Err(format!("{} responded with {}", self.url, status));
+        }
+        Ok(CheckResult::healthy(&self.name).with_metadata("status_code", json!(status.as_u16())))
+    }
+}
+
+#[cfg(feature = "http-check")]
+#[async_trait]
+impl HealthCheck for HttpCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn check(&self) -> CheckResult {
+        timed(&self.name, self.limits.degraded_after, self.request()).await
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.limits.timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Address of a server greeting every connection with `greeting`
+    async fn smtp_server(greeting: &'static str) -> (String, u16) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket.write_all(greeting.as_bytes()).await;
+            }
+        });
+        (address.ip().to_string(), address.port())
+    }
+
+    #[tokio::test]
+    async fn test_smtp_check() {
+        let (host, port) = smtp_server("220 mail.example.com ESMTP\r\n").await;
+        let result = SmtpCheck::new(host, port).check().await;
+
+        assert!(result.status.is_healthy());
+        assert_eq!(result.metadata["greeting"], "220 mail.example.com ESMTP");
+        assert!(result.metadata.contains_key("latency_ms"));
+
+        let (host, port) = smtp_server("554 No SMTP service here\r\n").await;
+        let result = SmtpCheck::new(host, port).with_name("mailer").check().await;
+
+        assert_eq!(result.name, "mailer");
+        assert!(result.status.is_unhealthy());
+        assert!(result.message.unwrap().contains("554"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_probe_is_degraded() {
+        let slow = async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            Ok(CheckResult::healthy("slow"))
+        };
+        let result = timed("slow", Some(Duration::from_millis(100)), slow).await;
+
+        assert!(result.status.is_degraded());
+        assert_eq!(result.metadata["latency_ms"], 300);
+
+        let failed = async { Err("Connection refused".to_string()) };
+        let result = timed("down", Some(Duration::from_millis(100)), failed).await;
+
+        assert!(result.status.is_unhealthy());
+        assert_eq!(result.message.as_deref(), Some("Connection refused"));
+    }
+
+    #[cfg(feature = "queue")]
+    #[tokio::test]
+    async fn test_queue_check() {
+        use rf_queue::{Job, JobMetadata, MemoryQueue, Queue, QueueError};
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Serialize, Deserialize)]
+        struct Ping;
+
+        #[async_trait]
+        impl Job for Ping {
+            async fn handle(&self) -> Result<(), QueueError> {
+                Ok(())
+            }
+
+            fn job_type(&self) -> &'static str {
+                "ping"
+            }
+        }
+
+        let queue = Arc::new(MemoryQueue::new());
+        for _ in 0..3 {
+            queue.push(JobMetadata::new(&Ping).unwrap()).await.unwrap();
+        }
+        let check = QueueCheck::new(queue.clone()).queues(["default", "mail"]);
+        let result = check.check().await;
+
+        assert!(result.status.is_healthy());
+        assert_eq!(result.metadata["pending"], json!({"default": 3, "mail": 0}));
+
+        let result = QueueCheck::new(queue).degraded_above(2).check().await;
+
+        assert!(result.status.is_degraded());
+        assert_eq!(
+            result.message.as_deref(),
+            Some("Backlog of 3 jobs on queue default")
+        );
+    }
+
+    #[cfg(feature = "storage")]
+    #[tokio::test]
+    async fn test_storage_check() {
+        use rf_storage::{Filesystem, MemoryStorage};
+
+        let disk = Arc::new(MemoryStorage::new());
+        let result = StorageCheck::new(disk.clone()).check().await;
+        assert!(result.status.is_healthy());
+
+        let result = StorageCheck::new(disk.clone()).write().check().await;
+        assert!(result.status.is_healthy());
+        assert!(!disk.exists(".health-check").await.unwrap());
+    }
+
+    #[cfg(feature = "http-check")]
+    #[tokio::test]
+    async fn test_http_check() {
+        use axum::{http::StatusCode, routing::get, Router};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let app = Router::new()
+            .route("/up", get(|| async { "ok" }))
+            .route("/down", get(|| async { StatusCode::SERVICE_UNAVAILABLE }));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let result = HttpCheck::new(format!("http://{}/up", address))
+            .check()
+            .await;
+        assert!(result.status.is_healthy());
+        assert_eq!(result.metadata["status_code"], 200);
+
+        let down = format!("http://{}/down", address);
+        let result = HttpCheck::new(&down).check().await;
+        assert!(result.status.is_unhealthy());
+
+        let result = HttpCheck::new(&down).expect_status(503).check().await;
+        assert!(result.status.is_healthy());
+    }
+}
@@ -1,11 +1,22 @@
 //! Built-in health check implementations
 
 mod checks_impl;
+mod dependencies;
 
 pub use checks_impl::{AlwaysHealthyCheck, DiskCheck, MemoryCheck};
+pub use dependencies::SmtpCheck;
 
 #[cfg(feature = "database")]
-pub use checks_impl::DatabaseCheck;
+pub use dependencies::DatabaseCheck;
 
 #[cfg(feature = "redis-check")]
-pub use checks_impl::RedisCheck;
+pub use dependencies::RedisCheck;
+
+#[cfg(feature = "queue")]
+pub use dependencies::QueueCheck;
+
+#[cfg(feature = "storage")]
+pub use dependencies::StorageCheck;
+
+#[cfg(feature = "http-check")]
+pub use dependencies::HttpCheck;
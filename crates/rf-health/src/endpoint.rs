@@ -1,6 +1,6 @@
 //! Axum endpoint integration
 
-use crate::checker::{HealthCheck, HealthResponse};
+use crate::checker::{CheckResult, HealthCheck, HealthResponse};
 use axum::{
     extract::State,
     http::StatusCode,
@@ -8,14 +8,25 @@ use axum::{
     routing::get,
     Json, Router,
 };
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
 
 /// Health checker that runs multiple checks
+///
+/// Checks run concurrently; a check that doesn't finish within the timeout
+/// counts as unhealthy, so a hanging dependency can't stall the probes.
+/// With [`cache_for`](Self::cache_for) results are reused for a while, so
+/// frequent probes don't hammer the dependencies.
 #[derive(Clone)]
 pub struct HealthChecker {
     checks: Arc<Vec<Arc<dyn HealthCheck>>>,
     liveness_checks: Arc<Vec<Arc<dyn HealthCheck>>>,
     readiness_checks: Arc<Vec<Arc<dyn HealthCheck>>>,
+    timeout: Duration,
+    cache_for: Option<Duration>,
+    cached: Arc<Mutex<HashMap<String, (Instant, CheckResult)>>>,
 }
 
 impl HealthChecker {
@@ -25,9 +36,26 @@ impl HealthChecker {
             checks: Arc::new(Vec::new()),
             liveness_checks: Arc::new(Vec::new()),
             readiness_checks: Arc::new(Vec::new()),
+            timeout: Duration::from_secs(5),
+            cache_for: None,
+            cached: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// Set the timeout of each check (default 5 seconds), unless the check
+    /// sets its own
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Reuse the result of each check for `window` instead of running it on
+    /// every request (default: not cached)
+    pub fn cache_for(mut self, window: Duration) -> Self {
+        self.cache_for = Some(window);
+        self
+    }
+
     /// Add a health check
     pub fn add_check(mut self, check: impl HealthCheck + 'static) -> Self {
         let check = Arc::new(check);
@@ -50,48 +78,58 @@ impl HealthChecker {
 
     /// Run all health checks
     pub async fn check_all(&self) -> HealthResponse {
-        let mut results = Vec::new();
-
-        for check in self.checks.iter() {
-            let result = check.check().await;
-            results.push(result);
-        }
-
-        HealthResponse::from_checks(results)
+        HealthResponse::from_checks(self.run(&self.checks).await)
     }
 
     /// Run liveness checks only
     pub async fn check_liveness(&self) -> HealthResponse {
-        let mut results = Vec::new();
-
-        for check in self.liveness_checks.iter() {
-            let result = check.check().await;
-            results.push(result);
-        }
-
         // If no liveness checks, return healthy
-        if results.is_empty() {
-            return HealthResponse::from_checks(vec![]);
-        }
-
-        HealthResponse::from_checks(results)
+        HealthResponse::from_checks(self.run(&self.liveness_checks).await)
     }
 
     /// Run readiness checks only
     pub async fn check_readiness(&self) -> HealthResponse {
-        let mut results = Vec::new();
-
-        for check in self.readiness_checks.iter() {
-            let result = check.check().await;
-            results.push(result);
-        }
-
         // If no readiness checks, return all checks
-        if results.is_empty() {
+        if self.readiness_checks.is_empty() {
             return self.check_all().await;
         }
 
-        HealthResponse::from_checks(results)
+        HealthResponse::from_checks(self.run(&self.readiness_checks).await)
+    }
+
+    async fn run(&self, checks: &[Arc<dyn HealthCheck>]) -> Vec<CheckResult> {
+        futures::future::join_all(checks.iter().map(|check| async move {
+            if let Some(result) = self.cached(check.name()) {
+                return result;
+            }
+
+            let timeout = check.timeout().unwrap_or(self.timeout);
+            let started = Instant::now();
+            let mut result = match tokio::time::timeout(timeout, check.check()).await {
+                Ok(result) => result,
+                Err(_) => {
+                    tracing::warn!(check = check.name(), ?timeout, "Health check timed out");
+                    CheckResult::unhealthy(check.name(), format!("Timed out after {:?}", timeout))
+                }
+            };
+            result.duration_ms = Some(started.elapsed().as_millis() as u64);
+
+            if self.cache_for.is_some() {
+                let mut cached = self.cached.lock().unwrap();
+                cached.insert(check.name().to_string(), (started, result.clone()));
+            }
+            result
+        }))
+        .await
+    }
+
+    fn cached(&self, name: &str) -> Option<CheckResult> {
+        let window = self.cache_for?;
+        let cached = self.cached.lock().unwrap();
+        cached
+            .get(name)
+            .filter(|(at, _)| at.elapsed() < window)
+            .map(|(_, result)| result.clone())
     }
 }
 
@@ -168,6 +206,96 @@ mod tests {
         assert!(response.status.is_healthy());
     }
 
+    struct SlowCheck;
+
+    #[async_trait::async_trait]
+    impl HealthCheck for SlowCheck {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn check(&self) -> CheckResult {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            CheckResult::healthy(self.name())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_check_timeout() {
+        let checker = HealthChecker::new()
+            .timeout(Duration::from_secs(1))
+            .add_check(AlwaysHealthyCheck::new("fast"))
+            .add_check(SlowCheck);
+
+        let response = checker.check_readiness().await;
+
+        assert!(response.status.is_unhealthy());
+        assert!(response.checks[0].status.is_healthy());
+        assert_eq!(response.checks[1].name, "slow");
+        assert!(response.checks[1].status.is_unhealthy());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_check_own_timeout() {
+        struct PatientCheck;
+
+        #[async_trait::async_trait]
+        impl HealthCheck for PatientCheck {
+            fn name(&self) -> &str {
+                "patient"
+            }
+
+            async fn check(&self) -> CheckResult {
+                tokio::time::sleep(Duration::from_secs(3)).await;
+                CheckResult::healthy(self.name())
+            }
+
+            fn timeout(&self) -> Option<Duration> {
+                Some(Duration::from_secs(10))
+            }
+        }
+
+        let checker = HealthChecker::new()
+            .timeout(Duration::from_secs(1))
+            .add_check(PatientCheck);
+
+        let response = checker.check_all().await;
+
+        assert!(response.status.is_healthy());
+        assert_eq!(response.checks[0].duration_ms, Some(3000));
+    }
+
+    struct CountingCheck(Arc<std::sync::atomic::AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl HealthCheck for CountingCheck {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        async fn check(&self) -> CheckResult {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            CheckResult::healthy(self.name())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cached_results() {
+        let runs = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let checker = HealthChecker::new()
+            .cache_for(Duration::from_secs(10))
+            .add_check(CountingCheck(runs.clone()));
+        let runs = || runs.load(std::sync::atomic::Ordering::SeqCst);
+
+        checker.check_all().await;
+        checker.check_readiness().await;
+        assert_eq!(runs(), 1);
+
+        tokio::time::advance(Duration::from_secs(11)).await;
+        checker.clone().check_all().await;
+        assert_eq!(runs(), 2);
+    }
+
     #[tokio::test]
     async fn test_empty_liveness() {
         let checker = HealthChecker::new()
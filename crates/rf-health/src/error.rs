@@ -21,5 +21,14 @@ pub enum HealthError {
     ConfigError(String),
 }
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(HealthError, {
+    Self::CheckFailed(_) => (SERVICE_UNAVAILABLE, "health.check_failed"),
+    Self::DatabaseError(_) => (SERVICE_UNAVAILABLE, "health.database"),
+    Self::RedisError(_) => (SERVICE_UNAVAILABLE, "health.redis"),
+    Self::SystemError(_) => (SERVICE_UNAVAILABLE, "health.system"),
+    Self::ConfigError(_) => (INTERNAL_SERVER_ERROR, "health.config"),
+});
+
 /// Result type for health checks
 pub type HealthResult<T> = Result<T, HealthError>;
@@ -1,8 +1,9 @@
 //! Health check system for RustForge
 //!
 //! Provides comprehensive health checking for production deployments, including:
-//! - Database connectivity checks
+//! - Database connectivity checks (Postgres, MySQL, SQLite)
 //! - Redis connectivity checks
+//! - Queue backend, SMTP, object storage and outbound HTTP checks
 //! - Disk space monitoring
 //! - Memory usage monitoring
 //! - Custom health checks
@@ -13,7 +14,10 @@
 //! - Multiple built-in health checks
 //! - Axum endpoint integration (`/health`, `/health/live`, `/health/ready`)
 //! - Thresholds for warning and critical states
-//! - Optional database and Redis support via feature flags
+//! - Optional database, Redis, queue, storage and HTTP checks via feature
+//!   flags (`database`, `redis-check`, `queue`, `storage`, `http-check`)
+//! - Per-check timeouts, degraded states for slow dependencies and cached
+//!   results
 //! - Detailed metadata in responses
 //!
 //! # Quick Start
@@ -99,6 +103,31 @@
 //! # }
 //! ```
 //!
+//! # Dependency Checks
+//!
+//! Checks of external services take their own timeout and report degraded
+//! when the service answers slowly. Cache the results so frequent probes
+//! don't hammer the services:
+//!
+//! ```no_run
+//! # #[cfg(all(feature = "queue", feature = "http-check"))]
+//! # fn example(queue: std::sync::Arc<dyn rf_queue::Queue>) {
+//! use rf_health::checks::{HttpCheck, QueueCheck, SmtpCheck};
+//! use rf_health::HealthChecker;
+//! use std::time::Duration;
+//!
+//! let checker = HealthChecker::new()
+//!     .cache_for(Duration::from_secs(10))
+//!     .add_check(SmtpCheck::new("mail.example.com", 25).degraded_after(Duration::from_secs(1)))
+//!     .add_check(QueueCheck::new(queue).degraded_above(10_000))
+//!     .add_check(
+//!         HttpCheck::new("https://payments.example.com/status")
+//!             .with_name("payments")
+//!             .with_timeout(Duration::from_secs(2)),
+//!     );
+//! # }
+//! ```
+//!
 //! # Response Format
 //!
 //! Health endpoints return JSON:
@@ -115,7 +144,8 @@
 //!         "used_bytes": 8589934592,
 //!         "usage_percent": 50.0
 //!       },
-//!       "timestamp": "2024-01-15T10:30:00Z"
+//!       "timestamp": "2024-01-15T10:30:00Z",
+//!       "duration_ms": 12
 //!     }
 //!   ],
 //!   "timestamp": "2024-01-15T10:30:00Z"
@@ -0,0 +1,168 @@
+//! Circuit breakers per host
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// When a circuit breaker opens and for how long
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+
+    /// How long the circuit stays open before a trial request is let
+    /// through
+    #[serde(with = "humantime_serde")]
+    pub reset_timeout: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            reset_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of a host's circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests pass
+    Closed,
+    /// Requests fail fast until the reset timeout passed
+    Open,
+    /// A single trial request decides whether to close or reopen
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Circuit {
+    config: CircuitBreakerConfig,
+    failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+}
+
+impl Circuit {
+    fn state(&self) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(at) if at.elapsed() < self.config.reset_timeout => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+}
+
+/// Circuit breakers of all hosts a client talks to
+///
+/// A host failing `failure_threshold` times in a row is cut off for
+/// `reset_timeout`, so callers fail fast instead of piling up on a service
+/// that is down. Server errors (5xx), timeouts and connection errors count
+/// as failures.
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+impl CircuitBreakers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check if a request to `host` may be sent
+    pub fn acquire(&self, host: &str, config: &CircuitBreakerConfig) -> bool {
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits.entry(host.to_string()).or_insert_with(|| Circuit {
+            config: config.clone(),
+            failures: 0,
+            opened_at: None,
+            trial_in_flight: false,
+        });
+
+        match circuit.state() {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen if circuit.trial_in_flight => false,
+            CircuitState::HalfOpen => {
+                circuit.trial_in_flight = true;
+                true
+            }
+        }
+    }
+
+    /// Record a successful request, closing the circuit
+    pub fn record_success(&self, host: &str) {
+        if let Some(circuit) = self.circuits.lock().unwrap().get_mut(host) {
+            circuit.failures = 0;
+            circuit.opened_at = None;
+            circuit.trial_in_flight = false;
+        }
+    }
+
+    /// Record a failed request, opening the circuit at the threshold or
+    /// when the trial request failed
+    pub fn record_failure(&self, host: &str) {
+        if let Some(circuit) = self.circuits.lock().unwrap().get_mut(host) {
+            circuit.failures += 1;
+            if circuit.trial_in_flight || circuit.failures >= circuit.config.failure_threshold {
+                if circuit.opened_at.is_none() || circuit.trial_in_flight {
+                    tracing::warn!(host, failures = circuit.failures, "Circuit breaker opened");
+                }
+                circuit.opened_at = Some(Instant::now());
+                circuit.trial_in_flight = false;
+            }
+        }
+    }
+
+    /// Current state of a host's circuit
+    pub fn state(&self, host: &str) -> CircuitState {
+        self.circuits
+            .lock()
+            .unwrap()
+            .get(host)
+            .map_or(CircuitState::Closed, Circuit::state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_circuit_breaker() {
+        let breakers = CircuitBreakers::new();
+        let config = CircuitBreakerConfig {
+            failure_threshold: 2,
+            reset_timeout: Duration::from_secs(10),
+        };
+
+        assert!(breakers.acquire("api", &config));
+        breakers.record_failure("api");
+        assert_eq!(breakers.state("api"), CircuitState::Closed);
+        breakers.record_failure("api");
+        assert_eq!(breakers.state("api"), CircuitState::Open);
+        assert!(!breakers.acquire("api", &config));
+        // Other hosts are unaffected
+        assert!(breakers.acquire("other", &config));
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(breakers.state("api"), CircuitState::HalfOpen);
+        assert!(breakers.acquire("api", &config));
+        // Only one trial request at a time
+        assert!(!breakers.acquire("api", &config));
+
+        // Failed trial reopens
+        breakers.record_failure("api");
+        assert_eq!(breakers.state("api"), CircuitState::Open);
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert!(breakers.acquire("api", &config));
+        breakers.record_success("api");
+        assert_eq!(breakers.state("api"), CircuitState::Closed);
+        assert!(breakers.acquire("api", &config));
+    }
+}
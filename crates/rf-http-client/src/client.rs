@@ -0,0 +1,716 @@
+//! Client sending requests with retries, circuit breaking and logging
+
+use crate::retry::retry_after;
+use crate::{
+    is_idempotent, CircuitBreakers, HttpClientConfig, HttpClientError, HttpResult, Redactor,
+    Request, ReqwestTransport, Response, RetryPolicy, ServiceConfig, Transport,
+};
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{Method, Url};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Outbound HTTP client
+///
+/// Cheap to clone; clones share the transport and circuit breakers.
+///
+/// ```no_run
+/// use rf_http_client::{HttpClient, HttpClientConfig, ServiceConfig};
+/// use std::time::Duration;
+///
+/// # async fn example() -> rf_http_client::HttpResult<()> {
+/// let client = HttpClient::new(HttpClientConfig::default().service(
+///     "github",
+///     ServiceConfig::new("https://api.github.com").timeout(Duration::from_secs(5)),
+/// ));
+///
+/// let user: serde_json::Value = client
+///     .service("github")?
+///     .get("users/octocat")
+///     .send()
+///     .await?
+///     .error_for_status()?
+///     .json()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct HttpClient {
+    transport: Arc<dyn Transport>,
+    services: Arc<HashMap<String, Arc<ServiceConfig>>>,
+    defaults: Arc<ServiceConfig>,
+    breakers: Arc<CircuitBreakers>,
+    redactor: Arc<Redactor>,
+    log_bodies: bool,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new(HttpClientConfig::default())
+    }
+}
+
+impl HttpClient {
+    /// Client for the configured services, sending over the network
+    pub fn new(config: HttpClientConfig) -> Self {
+        let redactor = config
+            .redact
+            .iter()
+            .fold(Redactor::default(), |redactor, name| redactor.with(name));
+
+        Self {
+            transport: Arc::new(ReqwestTransport::default()),
+            services: Arc::new(
+                config
+                    .services
+                    .into_iter()
+                    .map(|(name, service)| (name, Arc::new(service)))
+                    .collect(),
+            ),
+            defaults: Arc::new(config.defaults.unwrap_or_default()),
+            breakers: Arc::new(CircuitBreakers::new()),
+            redactor: Arc::new(redactor),
+            log_bodies: config.log_bodies,
+        }
+    }
+
+    /// Send over `transport`, e.g. an [`HttpFake`](crate::HttpFake)
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Arc::new(transport);
+        self
+    }
+
+    /// Redact secrets in logs with `redactor`
+    pub fn redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = Arc::new(redactor);
+        self
+    }
+
+    /// Circuit breakers of the hosts this client talks to
+    pub fn circuit_breakers(&self) -> &CircuitBreakers {
+        &self.breakers
+    }
+
+    /// Client of a configured service
+    pub fn service(&self, name: &str) -> HttpResult<ServiceClient> {
+        let config = self
+            .services
+            .get(name)
+            .cloned()
+            .ok_or_else(|| HttpClientError::UnknownService(name.to_string()))?;
+
+        Ok(ServiceClient {
+            client: self.clone(),
+            name: name.to_string(),
+            config,
+        })
+    }
+
+    /// `GET` an absolute URL
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    /// `POST` to an absolute URL
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+
+    /// `PUT` to an absolute URL
+    pub fn put(&self, url: &str) -> RequestBuilder {
+        self.request(Method::PUT, url)
+    }
+
+    /// `PATCH` an absolute URL
+    pub fn patch(&self, url: &str) -> RequestBuilder {
+        self.request(Method::PATCH, url)
+    }
+
+    /// `DELETE` an absolute URL
+    pub fn delete(&self, url: &str) -> RequestBuilder {
+        self.request(Method::DELETE, url)
+    }
+
+    /// Request to an absolute URL, using the default settings
+    pub fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let url = Url::parse(url).map_err(|e| format!("{}: {}", url, e));
+        RequestBuilder::new(self.clone(), None, self.defaults.clone(), method, url)
+    }
+
+    async fn execute(&self, request: Request, options: Options) -> HttpResult<Response> {
+        let Options {
+            service,
+            config,
+            retry,
+            timeout,
+            budget,
+            idempotent,
+        } = options;
+
+        let host = request.host();
+        let idempotent =
+            idempotent.unwrap_or_else(|| is_idempotent(&request.method, &request.headers));
+        let max_retries = if idempotent { retry.max_retries } else { 0 };
+        let deadline = Instant::now() + budget;
+        let mut attempt = 0;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(HttpClientError::Timeout(budget));
+            }
+            if !self.breakers.acquire(&host, &config.circuit_breaker) {
+                return Err(HttpClientError::CircuitOpen { host });
+            }
+
+            let attempt_timeout = timeout.min(remaining);
+            let started = Instant::now();
+            let result =
+                match tokio::time::timeout(attempt_timeout, self.transport.send(request.clone()))
+                    .await
+                {
+                    Ok(result) => result,
+                    Err(_) => Err(HttpClientError::Timeout(attempt_timeout)),
+                };
+            self.log(
+                service.as_deref(),
+                &request,
+                &result,
+                attempt,
+                started.elapsed(),
+            );
+
+            let retryable = match &result {
+                Ok(response) => {
+                    if response.status.is_server_error() {
+                        self.breakers.record_failure(&host);
+                    } else {
+                        self.breakers.record_success(&host);
+                    }
+                    retry.retries_status(response.status)
+                }
+                Err(e) if e.is_transient() => {
+                    self.breakers.record_failure(&host);
+                    true
+                }
+                // Not the host's fault, e.g. a stray request to a fake
+                Err(_) => {
+                    self.breakers.record_success(&host);
+                    false
+                }
+            };
+
+            if retryable && attempt < max_retries {
+                let delay = result
+                    .as_ref()
+                    .ok()
+                    .and_then(|response| retry_after(&response.headers))
+                    .unwrap_or_else(|| retry.delay(attempt));
+
+                if Instant::now() + delay < deadline {
+                    tracing::debug!(
+                        service = service.as_deref().unwrap_or("-"),
+                        url = %self.redactor.url(&request.url),
+                        delay_ms = delay.as_millis() as u64,
+                        attempt,
+                        "Retrying HTTP request"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                    continue;
+                }
+            }
+
+            return result.map(|mut response| {
+                response.request = Some((request.method.clone(), request.url.clone()));
+                response
+            });
+        }
+    }
+
+    fn log(
+        &self,
+        service: Option<&str>,
+        request: &Request,
+        result: &HttpResult<Response>,
+        attempt: u32,
+        elapsed: Duration,
+    ) {
+        let service = service.unwrap_or("-");
+        let url = self.redactor.url(&request.url);
+        let elapsed_ms = elapsed.as_millis() as u64;
+
+        match result {
+            Ok(response) => tracing::info!(
+                service,
+                method = %request.method,
+                url,
+                status = response.status.as_u16(),
+                elapsed_ms,
+                attempt,
+                "HTTP request"
+            ),
+            Err(e) => tracing::warn!(
+                service,
+                method = %request.method,
+                url,
+                error = %e,
+                elapsed_ms,
+                attempt,
+                "HTTP request failed"
+            ),
+        }
+
+        tracing::debug!(headers = ?self.redactor.headers(&request.headers), "HTTP request headers");
+        if self.log_bodies {
+            if let Some(body) = &request.body {
+                tracing::debug!(body = %self.redactor.body(body), "HTTP request body");
+            }
+            if let Ok(response) = result {
+                tracing::debug!(
+                    headers = ?self.redactor.headers(&response.headers),
+                    body = %self.redactor.body(&response.body),
+                    "HTTP response"
+                );
+            }
+        }
+    }
+}
+
+/// Client of a named service, resolving paths against its base URL
+#[derive(Clone)]
+pub struct ServiceClient {
+    client: HttpClient,
+    name: String,
+    config: Arc<ServiceConfig>,
+}
+
+impl ServiceClient {
+    /// Name of the service
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Settings of the service
+    pub fn config(&self) -> &ServiceConfig {
+        &self.config
+    }
+
+    /// `GET` a path
+    pub fn get(&self, path: &str) -> RequestBuilder {
+        self.request(Method::GET, path)
+    }
+
+    /// `POST` to a path
+    pub fn post(&self, path: &str) -> RequestBuilder {
+        self.request(Method::POST, path)
+    }
+
+    /// `PUT` to a path
+    pub fn put(&self, path: &str) -> RequestBuilder {
+        self.request(Method::PUT, path)
+    }
+
+    /// `PATCH` a path
+    pub fn patch(&self, path: &str) -> RequestBuilder {
+        self.request(Method::PATCH, path)
+    }
+
+    /// `DELETE` a path
+    pub fn delete(&self, path: &str) -> RequestBuilder {
+        self.request(Method::DELETE, path)
+    }
+
+    /// Request to a path relative to the base URL; absolute URLs are used
+    /// as is
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = join_url(&self.config.base_url, path);
+        RequestBuilder::new(
+            self.client.clone(),
+            Some(self.name.clone()),
+            self.config.clone(),
+            method,
+            url,
+        )
+    }
+}
+
+fn join_url(base_url: &str, path: &str) -> Result<Url, String> {
+    if let Ok(url) = Url::parse(path) {
+        return Ok(url);
+    }
+
+    let base = if base_url.ends_with('/') {
+        base_url.to_string()
+    } else {
+        format!("{}/", base_url)
+    };
+    Url::parse(&base)
+        .and_then(|base| base.join(path.trim_start_matches('/')))
+        .map_err(|e| format!("{} + {}: {}", base_url, path, e))
+}
+
+struct Options {
+    service: Option<String>,
+    config: Arc<ServiceConfig>,
+    retry: RetryPolicy,
+    timeout: Duration,
+    budget: Duration,
+    idempotent: Option<bool>,
+}
+
+/// Request under construction
+///
+/// Errors from building, like an invalid URL or header, are returned by
+/// [`send`](Self::send).
+pub struct RequestBuilder {
+    client: HttpClient,
+    url: Result<Url, String>,
+    method: Method,
+    headers: HeaderMap,
+    query: Vec<(String, String)>,
+    body: Option<Bytes>,
+    error: Option<HttpClientError>,
+    options: Options,
+}
+
+impl RequestBuilder {
+    fn new(
+        client: HttpClient,
+        service: Option<String>,
+        config: Arc<ServiceConfig>,
+        method: Method,
+        url: Result<Url, String>,
+    ) -> Self {
+        let mut builder = Self {
+            client,
+            url,
+            method,
+            headers: HeaderMap::new(),
+            query: Vec::new(),
+            body: None,
+            error: None,
+            options: Options {
+                service,
+                retry: config.retry.clone(),
+                timeout: config.timeout,
+                budget: config.budget,
+                idempotent: None,
+                config: config.clone(),
+            },
+        };
+        for (name, value) in &config.headers {
+            builder = builder.header(name, value);
+        }
+        builder
+    }
+
+    /// Set a header
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        match (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                self.headers.insert(name, value);
+            }
+            _ => {
+                self.error
+                    .get_or_insert(HttpClientError::InvalidHeader(name.to_string()));
+            }
+        }
+        self
+    }
+
+    /// Set the `Authorization` header to a bearer token
+    pub fn bearer_auth(self, token: &str) -> Self {
+        self.header(AUTHORIZATION.as_str(), &format!("Bearer {}", token))
+    }
+
+    /// Add query parameters
+    pub fn query(mut self, params: &[(&str, &str)]) -> Self {
+        self.query
+            .extend(params.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        self
+    }
+
+    /// Send `value` as JSON body
+    pub fn json<T: Serialize + ?Sized>(mut self, value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(body) => {
+                self.body = Some(body.into());
+                self.header(CONTENT_TYPE.as_str(), "application/json")
+            }
+            Err(e) => {
+                self.error.get_or_insert(e.into());
+                self
+            }
+        }
+    }
+
+    /// Set the raw body
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Timeout of a single attempt
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = timeout;
+        self
+    }
+
+    /// Total time for all attempts
+    pub fn budget(mut self, budget: Duration) -> Self {
+        self.options.budget = budget;
+        self
+    }
+
+    /// Override the retry policy of the service
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.options.retry = retry;
+        self
+    }
+
+    /// Declare whether the request may be retried, overriding the check of
+    /// the method and `Idempotency-Key` header
+    pub fn idempotent(mut self, idempotent: bool) -> Self {
+        self.options.idempotent = Some(idempotent);
+        self
+    }
+
+    /// Send the request, retrying as configured
+    pub async fn send(self) -> HttpResult<Response> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let mut url = self.url.map_err(HttpClientError::InvalidUrl)?;
+        if !self.query.is_empty() {
+            url.query_pairs_mut().extend_pairs(&self.query);
+        }
+
+        let request = Request {
+            method: self.method,
+            url,
+            headers: self.headers,
+            body: self.body,
+        };
+        self.client.execute(request, self.options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CircuitBreakerConfig, CircuitState, FakeResponse, HttpFake};
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    fn fake_client(fake: &HttpFake, service: ServiceConfig) -> HttpClient {
+        HttpClient::new(HttpClientConfig::default().service("api", service)).transport(fake.clone())
+    }
+
+    fn fast_retries(max_retries: u32) -> RetryPolicy {
+        RetryPolicy::new(max_retries).backoff(Duration::from_millis(10), Duration::from_millis(10))
+    }
+
+    #[test]
+    fn test_join_url() {
+        let join = |base, path| join_url(base, path).unwrap().to_string();
+
+        assert_eq!(join("https://a.com/v1", "users"), "https://a.com/v1/users");
+        assert_eq!(
+            join("https://a.com/v1/", "/users"),
+            "https://a.com/v1/users"
+        );
+        assert_eq!(join("https://a.com", "https://b.com/x"), "https://b.com/x");
+        assert!(join_url("not a url", "users").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_service_requests() {
+        let fake = HttpFake::new().post(
+            "https://a.com/v1/users*",
+            FakeResponse::json(201, json!({"id": 7})),
+        );
+        let client = fake_client(
+            &fake,
+            ServiceConfig::new("https://a.com/v1").header("Accept", "application/json"),
+        );
+
+        let response = client
+            .service("api")
+            .unwrap()
+            .post("users")
+            .query(&[("notify", "true")])
+            .bearer_auth("secret")
+            .json(&json!({"name": "alice"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.json::<serde_json::Value>().unwrap()["id"], 7);
+
+        let request = &fake.recorded()[0];
+        assert_eq!(request.url.as_str(), "https://a.com/v1/users?notify=true");
+        assert_eq!(request.headers["accept"], "application/json");
+        assert_eq!(request.headers["authorization"], "Bearer secret");
+        assert_eq!(
+            request.json::<serde_json::Value>().unwrap()["name"],
+            "alice"
+        );
+
+        assert!(matches!(
+            client.service("missing"),
+            Err(HttpClientError::UnknownService(_))
+        ));
+        assert!(matches!(
+            client.get("no url").send().await,
+            Err(HttpClientError::InvalidUrl(_))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retries_idempotent_requests() {
+        let fake = HttpFake::new().sequence(
+            "https://a.com/*",
+            [
+                FakeResponse::status(503),
+                FakeResponse::connection_error(),
+                FakeResponse::ok(),
+            ],
+        );
+        let client = fake_client(
+            &fake,
+            ServiceConfig::new("https://a.com").retry(fast_retries(2)),
+        );
+
+        let response = client
+            .service("api")
+            .unwrap()
+            .get("flaky")
+            .send()
+            .await
+            .unwrap();
+        assert!(response.is_success());
+        fake.assert_sent_count(3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_does_not_retry_non_idempotent_requests() {
+        let fake = HttpFake::new().sequence(
+            "https://a.com/*",
+            [FakeResponse::status(503), FakeResponse::ok()],
+        );
+        let client = fake_client(
+            &fake,
+            ServiceConfig::new("https://a.com").retry(fast_retries(2)),
+        );
+        let api = client.service("api").unwrap();
+
+        let response = api.post("orders").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.error_for_status().is_err());
+        fake.assert_sent_count(1);
+
+        // Unless made idempotent with a key
+        let fake = HttpFake::new().sequence(
+            "https://a.com/*",
+            [FakeResponse::status(503), FakeResponse::ok()],
+        );
+        let client = fake_client(
+            &fake,
+            ServiceConfig::new("https://a.com").retry(fast_retries(2)),
+        );
+        let response = client
+            .service("api")
+            .unwrap()
+            .post("orders")
+            .header("Idempotency-Key", "order-1")
+            .send()
+            .await
+            .unwrap();
+        assert!(response.is_success());
+        fake.assert_sent_count(2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_honors_retry_after() {
+        let fake = HttpFake::new().sequence(
+            "https://a.com/*",
+            [
+                FakeResponse::status(429).header("Retry-After", "3"),
+                FakeResponse::ok(),
+            ],
+        );
+        let client = fake_client(
+            &fake,
+            ServiceConfig::new("https://a.com").retry(fast_retries(1)),
+        );
+
+        let started = Instant::now();
+        client
+            .service("api")
+            .unwrap()
+            .get("limited")
+            .send()
+            .await
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(3));
+        fake.assert_sent_count(2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timeout_budget() {
+        let fake = HttpFake::new().on(
+            "https://a.com/*",
+            FakeResponse::ok().delay(Duration::from_secs(5)),
+        );
+        let service = ServiceConfig::new("https://a.com")
+            .timeout(Duration::from_secs(2))
+            .budget(Duration::from_secs(5))
+            .retry(fast_retries(10));
+        let client = fake_client(&fake, service);
+
+        let started = Instant::now();
+        let result = client.service("api").unwrap().get("slow").send().await;
+        assert!(matches!(result, Err(HttpClientError::Timeout(_))));
+        // Attempts of 2s, 2s and the remaining ~1s, never beyond the budget
+        assert!(started.elapsed() <= Duration::from_secs(5));
+        fake.assert_sent_count(3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_circuit_breaker_opens() {
+        let fake = HttpFake::new().on("https://a.com/*", FakeResponse::status(500));
+        let service = ServiceConfig::new("https://a.com")
+            .retry(RetryPolicy::none())
+            .circuit_breaker(CircuitBreakerConfig {
+                failure_threshold: 2,
+                reset_timeout: Duration::from_secs(30),
+            });
+        let client = fake_client(&fake, service);
+        let api = client.service("api").unwrap();
+
+        for _ in 0..2 {
+            api.get("broken").send().await.unwrap();
+        }
+        assert_eq!(
+            client.circuit_breakers().state("a.com:443"),
+            CircuitState::Open
+        );
+        assert!(matches!(
+            api.get("broken").send().await,
+            Err(HttpClientError::CircuitOpen { .. })
+        ));
+        fake.assert_sent_count(2);
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        api.get("broken").send().await.unwrap();
+        fake.assert_sent_count(3);
+    }
+}
@@ -0,0 +1,137 @@
+//! Configuration of outbound services
+
+use crate::{CircuitBreakerConfig, RetryPolicy};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Settings of a named service the application talks to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceConfig {
+    /// URL that request paths are resolved against
+    pub base_url: String,
+
+    /// Timeout of a single attempt
+    #[serde(default = "default_timeout", with = "humantime_serde")]
+    pub timeout: Duration,
+
+    /// Total time for all attempts including backoff; no retry is started
+    /// that couldn't finish within it
+    #[serde(default = "default_budget", with = "humantime_serde")]
+    pub budget: Duration,
+
+    /// Headers sent with every request, e.g. `Accept` or an API key
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    #[serde(default)]
+    pub retry: RetryPolicy,
+
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_budget() -> Duration {
+    Duration::from_secs(30)
+}
+
+impl Default for ServiceConfig {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl ServiceConfig {
+    /// Service at `base_url` with default timeouts, retries and circuit
+    /// breaker
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            timeout: default_timeout(),
+            budget: default_budget(),
+            headers: HashMap::new(),
+            retry: RetryPolicy::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+        }
+    }
+
+    /// Set the timeout of a single attempt
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the total time for all attempts
+    pub fn budget(mut self, budget: Duration) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Add a header sent with every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set the retry policy
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Set the circuit breaker settings
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = circuit_breaker;
+        self
+    }
+}
+
+/// Configuration of an [`HttpClient`](crate::HttpClient)
+///
+/// Usually loaded from the application config:
+///
+/// ```
+/// use rf_http_client::HttpClientConfig;
+///
+/// let config: HttpClientConfig = serde_json::from_str(r#"{
+///     "services": {
+///         "github": {
+///             "base_url": "https://api.github.com",
+///             "timeout": "5s",
+///             "headers": { "Accept": "application/vnd.github+json" },
+///             "retry": { "max_retries": 3, "base_delay": "200ms" }
+///         }
+///     }
+/// }"#).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpClientConfig {
+    /// Services by name
+    #[serde(default)]
+    pub services: HashMap<String, ServiceConfig>,
+
+    /// Settings for requests to absolute URLs, outside of any service
+    #[serde(default)]
+    pub defaults: Option<ServiceConfig>,
+
+    /// Additional header, query parameter and JSON field names to redact
+    /// in logs
+    #[serde(default)]
+    pub redact: Vec<String>,
+
+    /// Log request and response bodies at debug level
+    #[serde(default)]
+    pub log_bodies: bool,
+}
+
+impl HttpClientConfig {
+    /// Add a service
+    pub fn service(mut self, name: impl Into<String>, config: ServiceConfig) -> Self {
+        self.services.insert(name.into(), config);
+        self
+    }
+}
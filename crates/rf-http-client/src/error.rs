@@ -0,0 +1,80 @@
+//! Error types for outbound HTTP requests
+
+use reqwest::{Method, StatusCode};
+use thiserror::Error;
+
+/// Result type for outbound HTTP requests
+pub type HttpResult<T> = Result<T, HttpClientError>;
+
+/// Error types for outbound HTTP requests
+#[derive(Debug, Error)]
+pub enum HttpClientError {
+    /// Transport error from reqwest
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    /// Connection error, e.g. from a faked transport
+    #[error("Connection failed: {0}")]
+    Connection(String),
+
+    /// The circuit breaker of the host is open
+    #[error("Circuit breaker open for {host}")]
+    CircuitOpen { host: String },
+
+    /// The timeout budget of the request is spent
+    #[error("Request timed out after {0:?}")]
+    Timeout(std::time::Duration),
+
+    /// Non-success response, see [`Response::error_for_status`](crate::Response::error_for_status)
+    #[error("{method} {url} responded with {status}")]
+    Status {
+        method: Method,
+        url: String,
+        status: StatusCode,
+        body: String,
+    },
+
+    /// No service with this name is configured
+    #[error("Unknown HTTP service: {0}")]
+    UnknownService(String),
+
+    /// Invalid header name or value
+    #[error("Invalid header: {0}")]
+    InvalidHeader(String),
+
+    /// Invalid URL
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+
+    /// Request to a faked client that no fake matched
+    #[error("Stray request: {method} {url}")]
+    StrayRequest { method: Method, url: String },
+
+    /// Serialization error
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(HttpClientError, {
+    Self::Request(_) | Self::Connection(_) => (BAD_GATEWAY, "http_client.connection"),
+    Self::CircuitOpen { .. } => (SERVICE_UNAVAILABLE, "http_client.circuit_open"),
+    Self::Timeout(_) => (GATEWAY_TIMEOUT, "http_client.timeout"),
+    Self::Status { .. } => (BAD_GATEWAY, "http_client.status"),
+    Self::UnknownService(_) => (INTERNAL_SERVER_ERROR, "http_client.unknown_service"),
+    Self::InvalidHeader(_) => (INTERNAL_SERVER_ERROR, "http_client.invalid_header"),
+    Self::InvalidUrl(_) => (INTERNAL_SERVER_ERROR, "http_client.invalid_url"),
+    Self::StrayRequest { .. } => (INTERNAL_SERVER_ERROR, "http_client.stray_request"),
+    Self::Serialization(_) => (BAD_GATEWAY, "http_client.serialization"),
+});
+
+impl HttpClientError {
+    /// Whether the request may succeed when tried again
+    pub fn is_transient(&self) -> bool {
+        match self {
+            HttpClientError::Request(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            HttpClientError::Connection(_) | HttpClientError::Timeout(_) => true,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,417 @@
+//! Faked responses and recorded requests for tests
+
+use crate::{HttpClientError, HttpResult, Request, Response, Transport};
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::{Method, StatusCode};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Canned response of an [`HttpFake`]
+#[derive(Debug, Clone)]
+pub struct FakeResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    delay: Option<Duration>,
+    connection_error: bool,
+}
+
+impl FakeResponse {
+    /// Empty response with `status`
+    pub fn status(status: u16) -> Self {
+        Self {
+            status: StatusCode::from_u16(status).expect("valid status code"),
+            headers: HeaderMap::new(),
+            body: Bytes::new(),
+            delay: None,
+            connection_error: false,
+        }
+    }
+
+    /// Empty `200 OK`
+    pub fn ok() -> Self {
+        Self::status(200)
+    }
+
+    /// JSON response
+    pub fn json(status: u16, body: serde_json::Value) -> Self {
+        Self::status(status)
+            .header(CONTENT_TYPE.as_str(), "application/json")
+            .body(body.to_string())
+    }
+
+    /// Text response
+    pub fn text(status: u16, body: impl Into<String>) -> Self {
+        Self::status(status)
+            .header(CONTENT_TYPE.as_str(), "text/plain")
+            .body(body.into())
+    }
+
+    /// Fail as if the host was unreachable
+    pub fn connection_error() -> Self {
+        Self {
+            connection_error: true,
+            ..Self::ok()
+        }
+    }
+
+    /// Add a header
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(
+            HeaderName::from_bytes(name.as_bytes()).expect("valid header name"),
+            HeaderValue::from_str(value).expect("valid header value"),
+        );
+        self
+    }
+
+    /// Set the body
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Answer after `delay`, e.g. to run into timeouts
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+type Responder = Box<dyn Fn(&Request) -> FakeResponse + Send + Sync>;
+
+enum Answer {
+    /// Responses in order, repeating the last one
+    Sequence(Mutex<VecDeque<FakeResponse>>),
+    Dynamic(Responder),
+}
+
+struct Route {
+    method: Option<Method>,
+    pattern: String,
+    answer: Answer,
+}
+
+impl Route {
+    fn matches(&self, request: &Request) -> bool {
+        self.method.as_ref().is_none_or(|m| *m == request.method)
+            && wildcard_match(&self.pattern, request.url.as_str())
+    }
+
+    fn respond(&self, request: &Request) -> FakeResponse {
+        match &self.answer {
+            Answer::Sequence(responses) => {
+                let mut responses = responses.lock().unwrap();
+                if responses.len() > 1 {
+                    responses.pop_front().unwrap()
+                } else {
+                    responses.front().cloned().unwrap_or_else(FakeResponse::ok)
+                }
+            }
+            Answer::Dynamic(responder) => responder(request),
+        }
+    }
+}
+
+/// `*` matches any characters
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+#[derive(Default)]
+struct FakeState {
+    routes: Vec<Route>,
+    recorded: Vec<Request>,
+    prevent_stray: bool,
+}
+
+/// Transport answering with faked responses and recording all requests
+///
+/// URL patterns may contain `*` wildcards; the first matching fake wins.
+/// Requests matching no fake get an empty `200 OK`, unless
+/// [`prevent_stray_requests`](Self::prevent_stray_requests) is set.
+///
+/// ```
+/// use rf_http_client::{FakeResponse, HttpClient, HttpFake};
+/// use serde_json::json;
+///
+/// # async fn example() -> rf_http_client::HttpResult<()> {
+/// let fake = HttpFake::new()
+///     .get("https://api.github.com/users/*", FakeResponse::json(200, json!({"login": "octocat"})))
+///     .sequence("https://api.example.com/flaky", [FakeResponse::status(503), FakeResponse::ok()]);
+///
+/// let client = HttpClient::default().transport(fake.clone());
+/// let user: serde_json::Value = client.get("https://api.github.com/users/octocat").send().await?.json()?;
+///
+/// fake.assert_sent(|r| r.url.path() == "/users/octocat");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct HttpFake {
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl HttpFake {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn route(self, method: Option<Method>, pattern: &str, answer: Answer) -> Self {
+        self.state.lock().unwrap().routes.push(Route {
+            method,
+            pattern: pattern.to_string(),
+            answer,
+        });
+        self
+    }
+
+    /// Answer requests of any method matching `pattern`
+    pub fn on(self, pattern: &str, response: FakeResponse) -> Self {
+        self.sequence(pattern, [response])
+    }
+
+    /// Answer `GET` requests matching `pattern`
+    pub fn get(self, pattern: &str, response: FakeResponse) -> Self {
+        self.method(Method::GET, pattern, response)
+    }
+
+    /// Answer `POST` requests matching `pattern`
+    pub fn post(self, pattern: &str, response: FakeResponse) -> Self {
+        self.method(Method::POST, pattern, response)
+    }
+
+    /// Answer requests with `method` matching `pattern`
+    pub fn method(self, method: Method, pattern: &str, response: FakeResponse) -> Self {
+        let answer = Answer::Sequence(Mutex::new(VecDeque::from([response])));
+        self.route(Some(method), pattern, answer)
+    }
+
+    /// Answer with `responses` in order, repeating the last one
+    pub fn sequence(
+        self,
+        pattern: &str,
+        responses: impl IntoIterator<Item = FakeResponse>,
+    ) -> Self {
+        let answer = Answer::Sequence(Mutex::new(responses.into_iter().collect()));
+        self.route(None, pattern, answer)
+    }
+
+    /// Compute the response from the request
+    pub fn respond_with<F>(self, pattern: &str, responder: F) -> Self
+    where
+        F: Fn(&Request) -> FakeResponse + Send + Sync + 'static,
+    {
+        self.route(None, pattern, Answer::Dynamic(Box::new(responder)))
+    }
+
+    /// Fail requests that match no fake
+    pub fn prevent_stray_requests(self) -> Self {
+        self.state.lock().unwrap().prevent_stray = true;
+        self
+    }
+
+    /// All requests sent, in order
+    pub fn recorded(&self) -> Vec<Request> {
+        self.state.lock().unwrap().recorded.clone()
+    }
+
+    /// Assert that a request matching the predicate was sent
+    #[track_caller]
+    pub fn assert_sent<F>(&self, predicate: F)
+    where
+        F: Fn(&Request) -> bool,
+    {
+        if !self.recorded().iter().any(predicate) {
+            panic!(
+                "Expected a matching request to be sent. Sent: {}",
+                self.summary()
+            );
+        }
+    }
+
+    /// Assert that no request matching the predicate was sent
+    #[track_caller]
+    pub fn assert_not_sent<F>(&self, predicate: F)
+    where
+        F: Fn(&Request) -> bool,
+    {
+        if self.recorded().iter().any(predicate) {
+            panic!(
+                "Expected no matching request to be sent. Sent: {}",
+                self.summary()
+            );
+        }
+    }
+
+    /// Assert the number of sent requests
+    #[track_caller]
+    pub fn assert_sent_count(&self, count: usize) {
+        let sent = self.recorded().len();
+        if sent != count {
+            panic!(
+                "Expected {} requests to be sent, but {} were. Sent: {}",
+                count,
+                sent,
+                self.summary()
+            );
+        }
+    }
+
+    /// Assert that nothing was sent
+    #[track_caller]
+    pub fn assert_nothing_sent(&self) {
+        self.assert_sent_count(0);
+    }
+
+    fn summary(&self) -> String {
+        let recorded = self.recorded();
+        if recorded.is_empty() {
+            return "nothing".to_string();
+        }
+        recorded
+            .iter()
+            .map(|r| format!("{} {}", r.method, r.url))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[async_trait]
+impl Transport for HttpFake {
+    async fn send(&self, request: Request) -> HttpResult<Response> {
+        let response = {
+            let mut state = self.state.lock().unwrap();
+            state.recorded.push(request.clone());
+
+            match state.routes.iter().find(|route| route.matches(&request)) {
+                Some(route) => route.respond(&request),
+                None if state.prevent_stray => {
+                    return Err(HttpClientError::StrayRequest {
+                        method: request.method,
+                        url: request.url.to_string(),
+                    })
+                }
+                None => FakeResponse::ok(),
+            }
+        };
+
+        if let Some(delay) = response.delay {
+            tokio::time::sleep(delay).await;
+        }
+        if response.connection_error {
+            return Err(HttpClientError::Connection(format!(
+                "faked connection error for {}",
+                request.url
+            )));
+        }
+        Ok(Response::new(
+            response.status,
+            response.headers,
+            response.body,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Url;
+
+    fn request(method: Method, url: &str) -> Request {
+        Request::new(method, Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("https://a.com/*", "https://a.com/users/1"));
+        assert!(wildcard_match("*/users/*", "https://a.com/users/1"));
+        assert!(wildcard_match("https://a.com/x", "https://a.com/x"));
+        assert!(!wildcard_match("https://a.com/x", "https://a.com/xy"));
+        assert!(!wildcard_match("https://b.com/*", "https://a.com/users"));
+        assert!(wildcard_match("*", "anything"));
+    }
+
+    #[tokio::test]
+    async fn test_fake_routes() {
+        let fake = HttpFake::new()
+            .get(
+                "https://a.com/users/*",
+                FakeResponse::json(200, serde_json::json!({"id": 1})),
+            )
+            .sequence(
+                "https://a.com/flaky",
+                [FakeResponse::status(503), FakeResponse::status(204)],
+            )
+            .respond_with("https://a.com/echo", |r| {
+                FakeResponse::text(200, r.method.to_string())
+            });
+
+        let response = fake
+            .send(request(Method::GET, "https://a.com/users/1"))
+            .await
+            .unwrap();
+        assert_eq!(response.json::<serde_json::Value>().unwrap()["id"], 1);
+        // Method mismatch falls through to the default
+        let response = fake
+            .send(request(Method::POST, "https://a.com/users/1"))
+            .await
+            .unwrap();
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.body.is_empty());
+
+        let statuses = [
+            fake.send(request(Method::GET, "https://a.com/flaky"))
+                .await
+                .unwrap()
+                .status,
+            fake.send(request(Method::GET, "https://a.com/flaky"))
+                .await
+                .unwrap()
+                .status,
+            fake.send(request(Method::GET, "https://a.com/flaky"))
+                .await
+                .unwrap()
+                .status,
+        ];
+        assert_eq!(statuses.map(|s| s.as_u16()), [503, 204, 204]);
+
+        let echo = fake
+            .send(request(Method::PUT, "https://a.com/echo"))
+            .await
+            .unwrap();
+        assert_eq!(echo.text(), "PUT");
+
+        fake.assert_sent_count(6);
+        fake.assert_sent(|r| r.method == Method::PUT);
+        fake.assert_not_sent(|r| r.method == Method::DELETE);
+    }
+
+    #[tokio::test]
+    async fn test_prevent_stray_requests() {
+        let fake = HttpFake::new().prevent_stray_requests();
+
+        assert!(matches!(
+            fake.send(request(Method::GET, "https://a.com/")).await,
+            Err(HttpClientError::StrayRequest { .. })
+        ));
+    }
+}
@@ -0,0 +1,66 @@
+//! Resilient outbound HTTP client for RustForge
+//!
+//! # Features
+//!
+//! - Named services with base URLs, headers and timeouts from config
+//! - Automatic retries with jittered exponential backoff on idempotent
+//!   requests, honoring `Retry-After`
+//! - Circuit breaker per host
+//! - Timeout per attempt and a total budget across retries
+//! - Request/response logging with secrets redacted
+//! - Faked responses and recorded requests in tests
+//!
+//! # Quick Start
+//!
+//! ```
+//! use rf_http_client::{
+//!     FakeResponse, HttpClient, HttpClientConfig, HttpFake, RetryPolicy, ServiceConfig,
+//! };
+//! use serde_json::json;
+//!
+//! # async fn example() -> rf_http_client::HttpResult<()> {
+//! let config = HttpClientConfig::default().service(
+//!     "billing",
+//!     ServiceConfig::new("https://billing.internal/api")
+//!         .header("Accept", "application/json")
+//!         .retry(RetryPolicy::new(3)),
+//! );
+//!
+//! // In tests, answer with fakes instead of the network
+//! let fake = HttpFake::new().get("*/invoices/*", FakeResponse::json(200, json!({"total": 42})));
+//! let client = HttpClient::new(config).transport(fake.clone());
+//!
+//! let invoice: serde_json::Value = client
+//!     .service("billing")?
+//!     .get("invoices/17")
+//!     .bearer_auth("token")
+//!     .send()
+//!     .await?
+//!     .error_for_status()?
+//!     .json()?;
+//!
+//! assert_eq!(invoice["total"], 42);
+//! fake.assert_sent(|r| r.url.as_str() == "https://billing.internal/api/invoices/17");
+//! # Ok(())
+//! # }
+//! ```
+
+mod breaker;
+mod client;
+mod config;
+mod error;
+mod fake;
+mod redact;
+mod retry;
+mod transport;
+
+pub use breaker::{CircuitBreakerConfig, CircuitBreakers, CircuitState};
+pub use client::{HttpClient, RequestBuilder, ServiceClient};
+pub use config::{HttpClientConfig, ServiceConfig};
+pub use error::{HttpClientError, HttpResult};
+pub use fake::{FakeResponse, HttpFake};
+pub use redact::{Redactor, REDACTED};
+pub use retry::{is_idempotent, RetryPolicy};
+pub use transport::{Request, ReqwestTransport, Response, Transport};
+
+pub use reqwest::{header, Method, StatusCode, Url};
@@ -0,0 +1,159 @@
+//! Retries with jittered exponential backoff
+
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// When and how often to retry a request
+///
+/// Only idempotent requests are retried: `GET`, `HEAD`, `OPTIONS`, `PUT`,
+/// `DELETE`, `TRACE` and requests with an `Idempotency-Key` header. A
+/// `Retry-After` header of the response takes precedence over the backoff.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+
+    /// Delay before the first retry, doubled for every further one
+    #[serde(with = "humantime_serde")]
+    pub base_delay: Duration,
+
+    /// Upper bound of the delay
+    #[serde(with = "humantime_serde")]
+    pub max_delay: Duration,
+
+    /// Randomize delays ("full jitter"), so clients don't retry in lockstep
+    pub jitter: bool,
+
+    /// Response statuses that are retried
+    pub retry_statuses: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+            retry_statuses: vec![429, 502, 503, 504],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Retry up to `max_retries` times
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    /// Set the base and maximum delay
+    pub fn backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Enable or disable jitter
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Check if a response status is retried
+    pub fn retries_status(&self, status: StatusCode) -> bool {
+        self.retry_statuses.contains(&status.as_u16())
+    }
+
+    /// Delay before retry number `retry` (starting at 0)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+
+        if self.jitter && !delay.is_zero() {
+            rand::thread_rng().gen_range(Duration::ZERO..=delay)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Check if a request can safely be sent twice
+pub fn is_idempotent(method: &Method, headers: &HeaderMap) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE | Method::TRACE
+    ) || headers.contains_key("idempotency-key")
+}
+
+/// Delay requested by a `Retry-After` header in seconds
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::new(5)
+            .backoff(Duration::from_millis(100), Duration::from_millis(500))
+            .jitter(false);
+
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(1), Duration::from_millis(200));
+        assert_eq!(policy.delay(2), Duration::from_millis(400));
+        assert_eq!(policy.delay(3), Duration::from_millis(500));
+        assert_eq!(policy.delay(40), Duration::from_millis(500));
+
+        let jittered = policy.jitter(true);
+        for retry in 0..5 {
+            assert!(jittered.delay(retry) <= Duration::from_millis(500));
+        }
+    }
+
+    #[test]
+    fn test_idempotent() {
+        let mut headers = HeaderMap::new();
+        assert!(is_idempotent(&Method::GET, &headers));
+        assert!(is_idempotent(&Method::DELETE, &headers));
+        assert!(!is_idempotent(&Method::POST, &headers));
+        assert!(!is_idempotent(&Method::PATCH, &headers));
+
+        headers.insert("Idempotency-Key", "abc".parse().unwrap());
+        assert!(is_idempotent(&Method::POST, &headers));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, "3".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(3)));
+    }
+}
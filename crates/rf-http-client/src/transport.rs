@@ -0,0 +1,140 @@
+//! Requests, responses and the transport sending them
+
+use crate::{HttpClientError, HttpResult};
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode, Url};
+use serde::de::DeserializeOwned;
+
+/// Outbound request
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: Method,
+    pub url: Url,
+    pub headers: HeaderMap,
+    pub body: Option<Bytes>,
+}
+
+impl Request {
+    pub fn new(method: Method, url: Url) -> Self {
+        Self {
+            method,
+            url,
+            headers: HeaderMap::new(),
+            body: None,
+        }
+    }
+
+    /// Body parsed as JSON
+    pub fn json<T: DeserializeOwned>(&self) -> HttpResult<T> {
+        Ok(serde_json::from_slice(
+            self.body.as_deref().unwrap_or_default(),
+        )?)
+    }
+
+    /// Host and port, identifying the circuit breaker
+    pub fn host(&self) -> String {
+        match (self.url.host_str(), self.url.port_or_known_default()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            _ => self.url.to_string(),
+        }
+    }
+}
+
+/// Response with a buffered body
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+    /// Method and URL of the request, for error messages
+    pub(crate) request: Option<(Method, Url)>,
+}
+
+impl Response {
+    pub fn new(status: StatusCode, headers: HeaderMap, body: impl Into<Bytes>) -> Self {
+        Self {
+            status,
+            headers,
+            body: body.into(),
+            request: None,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+
+    /// Body as text
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Body parsed as JSON
+    pub fn json<T: DeserializeOwned>(&self) -> HttpResult<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    /// Turn a client or server error status into an error
+    pub fn error_for_status(self) -> HttpResult<Self> {
+        if self.status.is_client_error() || self.status.is_server_error() {
+            let (method, url) = self
+                .request
+                .map(|(method, url)| (method, url.to_string()))
+                .unwrap_or_default();
+            return Err(HttpClientError::Status {
+                method,
+                url,
+                status: self.status,
+                body: String::from_utf8_lossy(&self.body).into_owned(),
+            });
+        }
+        Ok(self)
+    }
+}
+
+/// Sends a single request, without retries or circuit breaking
+///
+/// [`ReqwestTransport`] talks to the network; [`HttpFake`](crate::HttpFake)
+/// answers with canned responses in tests.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn send(&self, request: Request) -> HttpResult<Response>;
+}
+
+/// Transport backed by a [`reqwest::Client`]
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl Transport for ReqwestTransport {
+    async fn send(&self, request: Request) -> HttpResult<Response> {
+        let mut builder = self
+            .client
+            .request(request.method, request.url)
+            .headers(request.headers);
+        if let Some(body) = request.body {
+            builder = builder.body(body);
+        }
+
+        let response = builder.send().await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+        Ok(Response::new(status, headers, body))
+    }
+}
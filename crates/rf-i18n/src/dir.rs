@@ -0,0 +1,211 @@
+//! Directory catalogs
+//!
+//! A catalog directory has one subdirectory per locale. Every file inside is
+//! a namespace named after its path, so `locales/de/auth.json` provides
+//! `auth.*` and `locales/de/admin/users.ftl` provides `admin.users.*`:
+//!
+//! ```text
+//! locales/
+//! ├── en/
+//! │   ├── auth.json
+//! │   └── shop.ftl
+//! └── de/
+//!     ├── auth.json
+//!     └── shop.po
+//! ```
+//!
+//! Supported formats are JSON, Fluent (`.ftl`) and gettext (`.po`, `.mo`);
+//! other files are ignored.
+
+use crate::{fluent, gettext, I18nError, I18nResult, TranslationCatalog};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Modification state of every file in a catalog directory
+pub(crate) type Fingerprint = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+/// Load one catalog per locale subdirectory of `dir`
+///
+/// Fails if the same key is defined by more than one file of a locale,
+/// listing every conflict.
+pub(crate) fn load(dir: &Path) -> I18nResult<HashMap<String, TranslationCatalog>> {
+    let mut catalogs = HashMap::new();
+    let mut conflicts = Vec::new();
+
+    for entry in read_dir(dir)? {
+        if !entry.is_dir() {
+            continue;
+        }
+        let Some(locale) = entry.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+
+        let mut translations = HashMap::new();
+        let mut origins: HashMap<String, PathBuf> = HashMap::new();
+
+        for file in files(&entry)? {
+            let Some(namespace) = namespace(&entry, &file) else {
+                continue;
+            };
+
+            for (key, value) in parse_file(&file, locale)? {
+                let key = format!("{}.{}", namespace, key);
+                if let Some(first) = origins.get(&key) {
+                    conflicts.push(format!(
+                        "{}: '{}' in {} and {}",
+                        locale,
+                        key,
+                        first.display(),
+                        file.display()
+                    ));
+                    continue;
+                }
+                origins.insert(key.clone(), file.clone());
+                translations.insert(key, value);
+            }
+        }
+
+        let mut catalog = TranslationCatalog::new(locale);
+        catalog.translations = translations;
+        catalogs.insert(locale.to_string(), catalog);
+    }
+
+    if !conflicts.is_empty() {
+        conflicts.sort();
+        return Err(I18nError::DuplicateKey(conflicts.join("; ")));
+    }
+    Ok(catalogs)
+}
+
+/// Current state of all catalog files, to detect changes
+pub(crate) fn fingerprint(dir: &Path) -> Fingerprint {
+    let mut state: Fingerprint = files(dir)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|path| {
+            let metadata = fs::metadata(&path).ok();
+            let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+            let len = metadata.map_or(0, |m| m.len());
+            (path, modified, len)
+        })
+        .collect();
+    state.sort();
+    state
+}
+
+fn read_dir(dir: &Path) -> I18nResult<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir).map_err(|e| io_error(dir, e))?;
+    let mut paths = entries
+        .map(|entry| entry.map(|e| e.path()).map_err(|e| io_error(dir, e)))
+        .collect::<I18nResult<Vec<_>>>()?;
+    paths.sort();
+    Ok(paths)
+}
+
+/// All files below `dir`, recursively
+fn files(dir: &Path) -> I18nResult<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    for path in read_dir(dir)? {
+        if path.is_dir() {
+            found.extend(files(&path)?);
+        } else {
+            found.push(path);
+        }
+    }
+    Ok(found)
+}
+
+/// `admin/users.json` below the locale directory becomes `admin.users`
+fn namespace(locale_dir: &Path, file: &Path) -> Option<String> {
+    let relative = file.strip_prefix(locale_dir).ok()?.with_extension("");
+    let parts: Option<Vec<&str>> = relative.iter().map(|part| part.to_str()).collect();
+    Some(parts?.join("."))
+}
+
+fn parse_file(path: &Path, locale: &str) -> I18nResult<Vec<(String, Value)>> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let in_file = |e: I18nError| I18nError::ParseError(format!("{}: {}", path.display(), e));
+
+    match extension {
+        "json" => {
+            let data: HashMap<String, Value> = serde_json::from_str(&read(path)?)
+                .map_err(|e| I18nError::ParseError(format!("{}: {}", path.display(), e)))?;
+            Ok(data.into_iter().collect())
+        }
+        "ftl" => fluent::parse(&read(path)?).map_err(in_file),
+        "po" => gettext::parse_po(&read(path)?, locale).map_err(in_file),
+        "mo" => {
+            let data = fs::read(path).map_err(|e| io_error(path, e))?;
+            gettext::parse_mo(&data, locale).map_err(in_file)
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn read(path: &Path) -> I18nResult<String> {
+    fs::read_to_string(path).map_err(|e| io_error(path, e))
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> I18nError {
+    I18nError::Io(format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Fresh catalog directory with the given files
+    pub(crate) fn temp_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rf-i18n-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_load_namespaces() {
+        let dir = temp_dir(
+            "load",
+            &[
+                ("en/auth.json", r#"{"login": "Log in", "errors": {"locked": "Locked"}}"#),
+                ("en/shop.ftl", "cart = Cart\n    .title = Your cart\n"),
+                ("en/admin/users.json", r#"{"title": "Users"}"#),
+                ("en/README.md", "ignored"),
+                ("de/auth.json", r#"{"login": "Anmelden"}"#),
+            ],
+        );
+
+        let catalogs = load(&dir).unwrap();
+        let en = &catalogs["en"];
+
+        assert_eq!(en.get("auth.login").unwrap(), "Log in");
+        assert_eq!(en.get("auth.errors.locked").unwrap(), "Locked");
+        assert_eq!(en.get("shop.cart.title").unwrap(), "Your cart");
+        assert_eq!(en.get("admin.users.title").unwrap(), "Users");
+        assert_eq!(catalogs["de"].get("auth.login").unwrap(), "Anmelden");
+    }
+
+    #[test]
+    fn test_duplicate_keys_are_reported() {
+        let dir = temp_dir(
+            "conflict",
+            &[
+                ("en/auth.json", r#"{"login": "Log in"}"#),
+                ("en/auth.ftl", "login = Sign in\n"),
+            ],
+        );
+
+        let err = load(&dir).unwrap_err().to_string();
+        assert!(err.contains("'auth.login'"), "{}", err);
+        assert!(err.contains("auth.ftl") && err.contains("auth.json"), "{}", err);
+    }
+}
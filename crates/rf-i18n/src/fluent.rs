@@ -0,0 +1,256 @@
+//! Mozilla Fluent (FTL) catalog loader
+//!
+//! Fluent messages are converted to the catalog's JSON representation:
+//!
+//! - `{ $name }` becomes the handlebars placeholder `{{name}}`
+//! - attributes are stored under `message.attribute`
+//! - a select expression becomes a plural object keyed by its variants, with
+//!   `[0]` mapped to `zero` and the default variant always available as `other`
+//! - term and message references are inlined
+
+use crate::{I18nError, I18nResult};
+use fluent_syntax::ast::{
+    Entry, Expression, InlineExpression, Pattern, PatternElement, VariantKey,
+};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Maximum depth of nested term and message references
+const MAX_DEPTH: usize = 16;
+
+/// Parse an FTL resource into catalog entries
+pub(crate) fn parse(source: &str) -> I18nResult<Vec<(String, Value)>> {
+    let resource = fluent_syntax::parser::parse(source).map_err(|(_, errors)| {
+        let error = &errors[0];
+        let line = source
+            .get(..error.pos.start)
+            .map_or(1, |before| before.matches('\n').count() + 1);
+        I18nError::ParseError(format!("Fluent syntax error on line {}: {}", line, error))
+    })?;
+
+    let mut messages = HashMap::new();
+    let mut terms = HashMap::new();
+    for entry in &resource.body {
+        match entry {
+            Entry::Message(message) => {
+                messages.insert(message.id.name, message);
+            }
+            Entry::Term(term) => {
+                terms.insert(term.id.name, term);
+            }
+            _ => {}
+        }
+    }
+
+    let converter = Converter {
+        messages: &messages,
+        terms: &terms,
+    };
+
+    let mut entries = Vec::new();
+    for entry in &resource.body {
+        let Entry::Message(message) = entry else {
+            continue;
+        };
+
+        if let Some(value) = &message.value {
+            entries.push((message.id.name.to_string(), converter.value(value)?));
+        }
+        for attribute in &message.attributes {
+            let key = format!("{}.{}", message.id.name, attribute.id.name);
+            entries.push((key, converter.value(&attribute.value)?));
+        }
+    }
+
+    Ok(entries)
+}
+
+struct Converter<'a, 's> {
+    messages: &'a HashMap<&'s str, &'a fluent_syntax::ast::Message<&'s str>>,
+    terms: &'a HashMap<&'s str, &'a fluent_syntax::ast::Term<&'s str>>,
+}
+
+impl<'s> Converter<'_, 's> {
+    /// Convert a top-level pattern, turning a select expression into plural forms
+    fn value(&self, pattern: &Pattern<&'s str>) -> I18nResult<Value> {
+        let select = pattern.elements.iter().position(|element| {
+            matches!(
+                element,
+                PatternElement::Placeable {
+                    expression: Expression::Select { .. }
+                }
+            )
+        });
+
+        let Some(index) = select else {
+            return Ok(Value::String(self.text(pattern, 0)?));
+        };
+
+        let prefix = self.elements(&pattern.elements[..index], 0)?;
+        let suffix = self.elements(&pattern.elements[index + 1..], 0)?;
+        let PatternElement::Placeable {
+            expression: Expression::Select { variants, .. },
+        } = &pattern.elements[index]
+        else {
+            unreachable!("position matched a select expression");
+        };
+
+        let mut forms = Map::new();
+        let mut default = None;
+        for variant in variants {
+            let key = match &variant.key {
+                VariantKey::Identifier { name } => name.to_string(),
+                VariantKey::NumberLiteral { value } if *value == "0" => "zero".to_string(),
+                VariantKey::NumberLiteral { value } => value.to_string(),
+            };
+            let text = format!("{}{}{}", prefix, self.text(&variant.value, 0)?, suffix);
+            if variant.default {
+                default = Some(text.clone());
+            }
+            forms.insert(key, Value::String(text));
+        }
+
+        if let Some(default) = default {
+            forms.entry("other").or_insert(Value::String(default));
+        }
+        Ok(Value::Object(forms))
+    }
+
+    fn text(&self, pattern: &Pattern<&'s str>, depth: usize) -> I18nResult<String> {
+        self.elements(&pattern.elements, depth)
+    }
+
+    fn elements(&self, elements: &[PatternElement<&'s str>], depth: usize) -> I18nResult<String> {
+        let mut out = String::new();
+        for element in elements {
+            match element {
+                PatternElement::TextElement { value } => out.push_str(value),
+                PatternElement::Placeable { expression } => {
+                    out.push_str(&self.expression(expression, depth)?)
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn expression(&self, expression: &Expression<&'s str>, depth: usize) -> I18nResult<String> {
+        match expression {
+            Expression::Inline(inline) => self.inline(inline, depth),
+            Expression::Select { .. } => Err(I18nError::ParseError(
+                "nested Fluent select expressions are not supported".to_string(),
+            )),
+        }
+    }
+
+    fn inline(&self, inline: &InlineExpression<&'s str>, depth: usize) -> I18nResult<String> {
+        if depth > MAX_DEPTH {
+            return Err(I18nError::ParseError(
+                "Fluent references nest too deeply".to_string(),
+            ));
+        }
+
+        match inline {
+            InlineExpression::StringLiteral { value } => Ok(value.to_string()),
+            InlineExpression::NumberLiteral { value } => Ok(value.to_string()),
+            InlineExpression::VariableReference { id } => Ok(format!("{{{{{}}}}}", id.name)),
+            // Formatting functions such as NUMBER($count) render their argument
+            InlineExpression::FunctionReference { id, arguments } => match arguments.positional.first() {
+                Some(argument) => self.inline(argument, depth + 1),
+                None => Err(I18nError::ParseError(format!(
+                    "Fluent function {} has no argument",
+                    id.name
+                ))),
+            },
+            InlineExpression::MessageReference { id, attribute } => {
+                let message = self.messages.get(id.name).ok_or_else(|| {
+                    I18nError::ParseError(format!("unknown Fluent message: {}", id.name))
+                })?;
+                let pattern = match attribute {
+                    Some(attribute) => message
+                        .attributes
+                        .iter()
+                        .find(|a| a.id.name == attribute.name)
+                        .map(|a| &a.value),
+                    None => message.value.as_ref(),
+                };
+                let pattern = pattern.ok_or_else(|| {
+                    I18nError::ParseError(format!("Fluent message {} has no such value", id.name))
+                })?;
+                self.text(pattern, depth + 1)
+            }
+            InlineExpression::TermReference { id, attribute, .. } => {
+                let term = self.terms.get(id.name).ok_or_else(|| {
+                    I18nError::ParseError(format!("unknown Fluent term: -{}", id.name))
+                })?;
+                let pattern = match attribute {
+                    Some(attribute) => term
+                        .attributes
+                        .iter()
+                        .find(|a| a.id.name == attribute.name)
+                        .map(|a| &a.value)
+                        .ok_or_else(|| {
+                            I18nError::ParseError(format!(
+                                "Fluent term -{} has no attribute {}",
+                                id.name, attribute.name
+                            ))
+                        })?,
+                    None => &term.value,
+                };
+                self.text(pattern, depth + 1)
+            }
+            InlineExpression::Placeable { expression } => self.expression(expression, depth + 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_map(source: &str) -> HashMap<String, Value> {
+        parse(source).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn test_messages_and_attributes() {
+        let entries = parse_map(
+            r#"
+-brand = RustForge
+
+welcome = Welcome to { -brand }, { $name }!
+login = Log in
+    .title = Sign in to your account
+"#,
+        );
+
+        assert_eq!(entries["welcome"], "Welcome to RustForge, {{name}}!");
+        assert_eq!(entries["login"], "Log in");
+        assert_eq!(entries["login.title"], "Sign in to your account");
+        assert!(!entries.contains_key("-brand"));
+    }
+
+    #[test]
+    fn test_select_becomes_plural_forms() {
+        let entries = parse_map(
+            r#"
+emails = You have { $count ->
+    [0] no emails
+    [one] one email
+   *[many] { NUMBER($count) } emails
+}.
+"#,
+        );
+
+        let forms = &entries["emails"];
+        assert_eq!(forms["zero"], "You have no emails.");
+        assert_eq!(forms["one"], "You have one email.");
+        assert_eq!(forms["many"], "You have {{count}} emails.");
+        assert_eq!(forms["other"], "You have {{count}} emails.");
+    }
+
+    #[test]
+    fn test_syntax_error_reports_line() {
+        let err = parse("ok = fine\nbroken = {").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+    }
+}
@@ -0,0 +1,554 @@
+//! gettext PO/MO catalog loaders
+//!
+//! Entries are keyed by `msgid`, or `msgctxt.msgid` when a context is set.
+//! Untranslated and fuzzy entries are skipped. Plural entries become plural
+//! objects: the header's `Plural-Forms` expression decides which `msgstr[n]`
+//! serves each CLDR category of the catalog's locale, and `%d` in plural forms
+//! is replaced with `{{count}}`.
+
+use crate::{I18nError, I18nResult, PluralRule};
+use serde_json::{Map, Value};
+
+const MO_MAGIC: u32 = 0x950412de;
+
+/// Plural categories, in the order their samples are searched
+const CATEGORIES: [PluralRule; 6] = [
+    PluralRule::Zero,
+    PluralRule::One,
+    PluralRule::Two,
+    PluralRule::Few,
+    PluralRule::Many,
+    PluralRule::Other,
+];
+
+#[derive(Debug, Default)]
+struct Entry {
+    context: Option<String>,
+    id: String,
+    plural_id: Option<String>,
+    strings: Vec<String>,
+    fuzzy: bool,
+}
+
+/// Parse a PO file into catalog entries for `locale`
+pub(crate) fn parse_po(source: &str, locale: &str) -> I18nResult<Vec<(String, Value)>> {
+    into_translations(read_po(source)?, locale)
+}
+
+/// Parse a compiled MO file into catalog entries for `locale`
+pub(crate) fn parse_mo(data: &[u8], locale: &str) -> I18nResult<Vec<(String, Value)>> {
+    into_translations(read_mo(data)?, locale)
+}
+
+/// Which field continuation lines append to
+#[derive(Clone, Copy)]
+enum Field {
+    Context,
+    Id,
+    PluralId,
+    Str(usize),
+}
+
+fn read_po(source: &str) -> I18nResult<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut entry = Entry::default();
+    let mut field = None;
+    let mut has_str = false;
+
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        let error = |message: &str| {
+            I18nError::ParseError(format!("PO error on line {}: {}", number + 1, message))
+        };
+
+        if line.is_empty() || line.starts_with("#~") {
+            continue;
+        }
+        if let Some(flags) = line.strip_prefix("#,") {
+            if has_str {
+                entries.push(std::mem::take(&mut entry));
+                has_str = false;
+            }
+            entry.fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('"') {
+            let value = unquote(line).ok_or_else(|| error("invalid string"))?;
+            match field.ok_or_else(|| error("string outside of an entry"))? {
+                Field::Context => entry.context.get_or_insert_with(String::new).push_str(&value),
+                Field::Id => entry.id.push_str(&value),
+                Field::PluralId => entry.plural_id.get_or_insert_with(String::new).push_str(&value),
+                Field::Str(index) => entry.strings[index].push_str(&value),
+            }
+            continue;
+        }
+
+        let (keyword, rest) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| error("expected keyword and string"))?;
+        let value = unquote(rest.trim()).ok_or_else(|| error("invalid string"))?;
+
+        // A new msgctxt/msgid after a msgstr starts the next entry
+        if has_str && matches!(keyword, "msgctxt" | "msgid") {
+            entries.push(std::mem::take(&mut entry));
+            has_str = false;
+        }
+
+        field = Some(match keyword {
+            "msgctxt" => {
+                entry.context = Some(value);
+                Field::Context
+            }
+            "msgid" => {
+                entry.id = value;
+                Field::Id
+            }
+            "msgid_plural" => {
+                entry.plural_id = Some(value);
+                Field::PluralId
+            }
+            "msgstr" => {
+                has_str = true;
+                entry.strings = vec![value];
+                Field::Str(0)
+            }
+            _ => {
+                let index = keyword
+                    .strip_prefix("msgstr[")
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .and_then(|index| index.parse::<usize>().ok())
+                    .ok_or_else(|| error(&format!("unknown keyword {}", keyword)))?;
+                has_str = true;
+                if entry.strings.len() <= index {
+                    entry.strings.resize(index + 1, String::new());
+                }
+                entry.strings[index] = value;
+                Field::Str(index)
+            }
+        });
+    }
+
+    if has_str {
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Unquote and unescape a PO string literal
+fn unquote(s: &str) -> Option<String> {
+    let inner = s.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            other => out.push(other),
+        }
+    }
+    Some(out)
+}
+
+fn read_mo(data: &[u8]) -> I18nResult<Vec<Entry>> {
+    let error = |message: &str| I18nError::ParseError(format!("MO error: {}", message));
+
+    let word = |offset: usize, big_endian: bool| -> I18nResult<u32> {
+        let bytes: [u8; 4] = data
+            .get(offset..offset + 4)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| error("unexpected end of file"))?;
+        Ok(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+
+    let big_endian = match word(0, false)? {
+        MO_MAGIC => false,
+        magic if magic.swap_bytes() == MO_MAGIC => true,
+        _ => return Err(error("not an MO file")),
+    };
+
+    let count = word(8, big_endian)? as usize;
+    let originals = word(12, big_endian)? as usize;
+    let translations = word(16, big_endian)? as usize;
+
+    let string = |table: usize, index: usize| -> I18nResult<&str> {
+        let length = word(table + index * 8, big_endian)? as usize;
+        let offset = word(table + index * 8 + 4, big_endian)? as usize;
+        let bytes = data
+            .get(offset..offset + length)
+            .ok_or_else(|| error("string out of bounds"))?;
+        std::str::from_utf8(bytes).map_err(|_| error("string is not UTF-8"))
+    };
+
+    let mut entries = Vec::with_capacity(count);
+    for index in 0..count {
+        let original = string(originals, index)?;
+        let (context, original) = match original.split_once('\u{4}') {
+            Some((context, original)) => (Some(context.to_string()), original),
+            None => (None, original),
+        };
+        let mut ids = original.split('\0');
+
+        entries.push(Entry {
+            context,
+            id: ids.next().unwrap_or_default().to_string(),
+            plural_id: ids.next().map(str::to_string),
+            strings: string(translations, index)?
+                .split('\0')
+                .map(str::to_string)
+                .collect(),
+            fuzzy: false,
+        });
+    }
+    Ok(entries)
+}
+
+fn into_translations(entries: Vec<Entry>, locale: &str) -> I18nResult<Vec<(String, Value)>> {
+    let plural_forms = entries
+        .iter()
+        .find(|entry| entry.id.is_empty() && entry.context.is_none())
+        .and_then(|header| header.strings.first())
+        .and_then(|header| {
+            header
+                .lines()
+                .find_map(|line| line.strip_prefix("Plural-Forms:"))
+                .map(str::to_string)
+        });
+    let plural = match plural_forms {
+        Some(forms) => PluralExpr::from_header(&forms)?,
+        // Germanic default: msgstr[0] for one, msgstr[1] otherwise
+        None => PluralExpr::parse("n != 1")?,
+    };
+
+    let mut translations = Vec::new();
+    for entry in entries {
+        if entry.id.is_empty() || entry.fuzzy || entry.strings.iter().all(String::is_empty) {
+            continue;
+        }
+
+        let key = match &entry.context {
+            Some(context) => format!("{}.{}", context, entry.id),
+            None => entry.id.clone(),
+        };
+
+        let value = if entry.plural_id.is_some() || entry.strings.len() > 1 {
+            plural_value(&entry.strings, &plural, locale)
+        } else {
+            Value::String(entry.strings.into_iter().next().unwrap_or_default())
+        };
+        translations.push((key, value));
+    }
+    Ok(translations)
+}
+
+/// Map each CLDR category of `locale` to the gettext form it selects
+fn plural_value(strings: &[String], plural: &PluralExpr, locale: &str) -> Value {
+    let mut forms = Map::new();
+
+    for category in CATEGORIES {
+        let Some(sample) = (0..1000).find(|&n| PluralRule::cardinal(locale, n) == category) else {
+            continue;
+        };
+        let index = (plural.eval(sample as u64) as usize).min(strings.len() - 1);
+        let text = strings[index].replace("%d", "{{count}}");
+        forms.insert(category.key().to_string(), Value::String(text));
+    }
+
+    if !forms.contains_key("other") {
+        let last = strings.last().cloned().unwrap_or_default();
+        forms.insert("other".to_string(), Value::String(last.replace("%d", "{{count}}")));
+    }
+    Value::Object(forms)
+}
+
+/// A `Plural-Forms` expression, e.g. `n%10==1 && n%100!=11 ? 0 : 1`
+#[derive(Debug)]
+enum PluralExpr {
+    N,
+    Number(u64),
+    Not(Box<PluralExpr>),
+    Binary(Box<PluralExpr>, Op, Box<PluralExpr>),
+    Ternary(Box<PluralExpr>, Box<PluralExpr>, Box<PluralExpr>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl PluralExpr {
+    /// Expression from a header value like `nplurals=2; plural=(n != 1);`
+    fn from_header(header: &str) -> I18nResult<Self> {
+        let expr = header
+            .split(';')
+            .find_map(|part| part.trim().strip_prefix("plural="))
+            .ok_or_else(|| I18nError::ParseError(format!("invalid Plural-Forms: {}", header)))?;
+        Self::parse(expr)
+    }
+
+    fn parse(source: &str) -> I18nResult<Self> {
+        let tokens: Vec<char> = source.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut parser = ExprParser { tokens, pos: 0 };
+        let expr = parser.ternary()?;
+
+        if parser.pos != parser.tokens.len() {
+            return Err(parser.error());
+        }
+        Ok(expr)
+    }
+
+    fn eval(&self, n: u64) -> u64 {
+        match self {
+            PluralExpr::N => n,
+            PluralExpr::Number(value) => *value,
+            PluralExpr::Not(expr) => u64::from(expr.eval(n) == 0),
+            PluralExpr::Ternary(cond, then, otherwise) => {
+                if cond.eval(n) != 0 {
+                    then.eval(n)
+                } else {
+                    otherwise.eval(n)
+                }
+            }
+            PluralExpr::Binary(left, op, right) => {
+                let (a, b) = (left.eval(n), right.eval(n));
+                match op {
+                    Op::Or => u64::from(a != 0 || b != 0),
+                    Op::And => u64::from(a != 0 && b != 0),
+                    Op::Eq => u64::from(a == b),
+                    Op::Ne => u64::from(a != b),
+                    Op::Lt => u64::from(a < b),
+                    Op::Le => u64::from(a <= b),
+                    Op::Gt => u64::from(a > b),
+                    Op::Ge => u64::from(a >= b),
+                    Op::Add => a.wrapping_add(b),
+                    Op::Sub => a.wrapping_sub(b),
+                    Op::Mul => a.wrapping_mul(b),
+                    Op::Div => a.checked_div(b).unwrap_or(0),
+                    Op::Rem => a.checked_rem(b).unwrap_or(0),
+                }
+            }
+        }
+    }
+}
+
+/// Recursive descent parser following C operator precedence
+struct ExprParser {
+    tokens: Vec<char>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn error(&self) -> I18nError {
+        let source: String = self.tokens.iter().collect();
+        I18nError::ParseError(format!("invalid plural expression: {}", source))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        let matches = s
+            .chars()
+            .enumerate()
+            .all(|(i, c)| self.tokens.get(self.pos + i) == Some(&c));
+        if matches {
+            self.pos += s.chars().count();
+        }
+        matches
+    }
+
+    fn ternary(&mut self) -> I18nResult<PluralExpr> {
+        let cond = self.binary(0)?;
+        if !self.eat("?") {
+            return Ok(cond);
+        }
+        let then = self.ternary()?;
+        if !self.eat(":") {
+            return Err(self.error());
+        }
+        let otherwise = self.ternary()?;
+        Ok(PluralExpr::Ternary(Box::new(cond), Box::new(then), Box::new(otherwise)))
+    }
+
+    /// Binary operators by precedence level, loosest first
+    const LEVELS: [&'static [(&'static str, Op)]; 6] = [
+        &[("||", Op::Or)],
+        &[("&&", Op::And)],
+        &[("==", Op::Eq), ("!=", Op::Ne)],
+        &[("<=", Op::Le), (">=", Op::Ge), ("<", Op::Lt), (">", Op::Gt)],
+        &[("+", Op::Add), ("-", Op::Sub)],
+        &[("*", Op::Mul), ("/", Op::Div), ("%", Op::Rem)],
+    ];
+
+    fn binary(&mut self, level: usize) -> I18nResult<PluralExpr> {
+        if level == Self::LEVELS.len() {
+            return self.unary();
+        }
+
+        let mut left = self.binary(level + 1)?;
+        'outer: loop {
+            for (symbol, op) in Self::LEVELS[level] {
+                if self.eat(symbol) {
+                    let right = self.binary(level + 1)?;
+                    left = PluralExpr::Binary(Box::new(left), *op, Box::new(right));
+                    continue 'outer;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn unary(&mut self) -> I18nResult<PluralExpr> {
+        if self.eat("!") {
+            return Ok(PluralExpr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let expr = self.ternary()?;
+            if !self.eat(")") {
+                return Err(self.error());
+            }
+            return Ok(expr);
+        }
+        if self.eat("n") {
+            return Ok(PluralExpr::N);
+        }
+
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits: String = self.tokens[start..self.pos].iter().collect();
+        digits.parse().map(PluralExpr::Number).map_err(|_| self.error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const RU_PO: &str = r#"
+msgid ""
+msgstr ""
+"Language: ru\n"
+"Plural-Forms: nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && "
+"n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);\n"
+
+# Greeting on the home page
+msgid "Welcome"
+msgstr "Добро пожаловать"
+
+msgctxt "menu"
+msgid "Open"
+msgstr "Открыть"
+
+#, fuzzy
+msgid "Close"
+msgstr "Закрыть"
+
+msgid "Untranslated"
+msgstr ""
+
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] "%d файл"
+msgstr[1] "%d файла"
+msgstr[2] "%d файлов"
+"#;
+
+    fn to_map(entries: Vec<(String, Value)>) -> HashMap<String, Value> {
+        entries.into_iter().collect()
+    }
+
+    #[test]
+    fn test_po_entries() {
+        let entries = to_map(parse_po(RU_PO, "ru").unwrap());
+
+        assert_eq!(entries["Welcome"], "Добро пожаловать");
+        assert_eq!(entries["menu.Open"], "Открыть");
+        assert!(!entries.contains_key("Close"));
+        assert!(!entries.contains_key("Untranslated"));
+        assert_eq!(entries.len(), 3);
+    }
+
+    #[test]
+    fn test_po_plural_forms_follow_header() {
+        let entries = to_map(parse_po(RU_PO, "ru").unwrap());
+        let forms = &entries["%d file"];
+
+        assert_eq!(forms["one"], "{{count}} файл");
+        assert_eq!(forms["few"], "{{count}} файла");
+        assert_eq!(forms["many"], "{{count}} файлов");
+    }
+
+    #[test]
+    fn test_plural_expression() {
+        let expr = PluralExpr::from_header("nplurals=3; plural=n==1 ? 0 : n==2 ? 1 : 2;").unwrap();
+        assert_eq!([1, 2, 5].map(|n| expr.eval(n)), [0, 1, 2]);
+
+        let expr = PluralExpr::parse("(n != 1)").unwrap();
+        assert_eq!([0, 1, 2].map(|n| expr.eval(n)), [1, 0, 1]);
+
+        assert!(PluralExpr::parse("n ==").is_err());
+    }
+
+    #[test]
+    fn test_mo() {
+        // Little-endian MO with a header and one plural entry
+        let originals = ["", "apple\0apples"];
+        let translations = ["Plural-Forms: nplurals=2; plural=(n != 1);\n", "Apfel\0Äpfel"];
+
+        let mut data = Vec::new();
+        let header_len = 28;
+        let table_len = originals.len() * 8;
+        let mut strings = Vec::new();
+        let mut offsets = Vec::new();
+        let strings_start = header_len + 2 * table_len;
+        for s in originals.iter().chain(&translations) {
+            offsets.push((s.len() as u32, (strings_start + strings.len()) as u32));
+            strings.extend_from_slice(s.as_bytes());
+            strings.push(0);
+        }
+
+        for word in [MO_MAGIC, 0, 2, header_len as u32, (header_len + table_len) as u32, 0, 0] {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+        for (length, offset) in offsets {
+            data.extend_from_slice(&length.to_le_bytes());
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        data.extend_from_slice(&strings);
+
+        let entries = to_map(parse_mo(&data, "de").unwrap());
+        assert_eq!(entries["apple"]["one"], "Apfel");
+        assert_eq!(entries["apple"]["other"], "Äpfel");
+
+        assert!(parse_mo(b"not an mo file", "de").is_err());
+    }
+}
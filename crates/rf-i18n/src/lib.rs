@@ -1,15 +1,52 @@
 //! Internationalization (i18n) System for RustForge
 //!
 //! This crate provides multi-language support with translation management.
+//! Catalogs can be loaded from JSON, Mozilla Fluent (FTL) and gettext PO/MO
+//! files; plural forms follow the CLDR rules of each locale. With the `axum`
+//! feature, `LocaleLayer` negotiates a [`Locale`] for every request and
+//! `LocalizedRouter` serves routes under translated paths.
 
 use handlebars::Handlebars;
 use serde_json::Value;
 use std::{
     collections::HashMap,
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
 };
 use thiserror::Error;
 
+mod dir;
+mod fluent;
+mod gettext;
+mod locale;
+#[cfg(feature = "axum")]
+mod middleware;
+mod plural;
+#[cfg(feature = "axum")]
+mod routes;
+mod store;
+mod tenant;
+
+pub use locale::{match_locale, negotiate, parse_accept_language, Locale};
+#[cfg(feature = "axum")]
+pub use middleware::{LocaleLayer, LocaleService, LocaleSource};
+pub use plural::{PluralOperands, PluralRule, PluralType};
+#[cfg(feature = "axum")]
+pub use routes::{LocalizedRouter, LocalizedRoutes, RouteMatch};
+#[cfg(feature = "database")]
+pub use store::SqlTranslationStore;
+pub use store::{MemoryTranslationStore, TranslationRecord, TranslationRevision, TranslationStore};
+pub use tenant::TenantOverrides;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json::json;
+}
+
 /// i18n errors
 #[derive(Debug, Error)]
 pub enum I18nError {
@@ -24,63 +61,29 @@ pub enum I18nError {
 
     #[error("Template error: {0}")]
     TemplateError(String),
-}
 
-pub type I18nResult<T> = Result<T, I18nError>;
-
-/// Pluralization rules
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum PluralRule {
-    Zero,
-    One,
-    Two,
-    Few,
-    Many,
-    Other,
-}
+    #[error("Duplicate translation key: {0}")]
+    DuplicateKey(String),
 
-impl PluralRule {
-    /// Get plural rule for English
-    pub fn for_english(count: i64) -> Self {
-        if count == 0 {
-            PluralRule::Zero
-        } else if count == 1 {
-            PluralRule::One
-        } else {
-            PluralRule::Other
-        }
-    }
+    #[error("IO error: {0}")]
+    Io(String),
 
-    /// Get plural rule for German
-    pub fn for_german(count: i64) -> Self {
-        if count == 1 {
-            PluralRule::One
-        } else {
-            PluralRule::Other
-        }
-    }
+    #[error("Store error: {0}")]
+    StoreError(String),
+}
 
-    /// Get plural rule for French
-    pub fn for_french(count: i64) -> Self {
-        if count == 0 || count == 1 {
-            PluralRule::One
-        } else {
-            PluralRule::Other
-        }
-    }
+pub type I18nResult<T> = Result<T, I18nError>;
 
-    /// Get plural rule key
-    pub fn key(&self) -> &'static str {
-        match self {
-            PluralRule::Zero => "zero",
-            PluralRule::One => "one",
-            PluralRule::Two => "two",
-            PluralRule::Few => "few",
-            PluralRule::Many => "many",
-            PluralRule::Other => "other",
-        }
-    }
-}
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(I18nError, {
+    Self::LocaleNotFound(_) => (NOT_FOUND, "i18n.locale_not_found"),
+    Self::TranslationNotFound(_) => (INTERNAL_SERVER_ERROR, "i18n.translation_not_found"),
+    Self::ParseError(_) => (INTERNAL_SERVER_ERROR, "i18n.parse"),
+    Self::TemplateError(_) => (INTERNAL_SERVER_ERROR, "i18n.template"),
+    Self::DuplicateKey(_) => (INTERNAL_SERVER_ERROR, "i18n.duplicate_key"),
+    Self::Io(_) => (INTERNAL_SERVER_ERROR, "i18n.io"),
+    Self::StoreError(_) => (INTERNAL_SERVER_ERROR, "i18n.store"),
+});
 
 /// Translation catalog
 #[derive(Debug, Clone)]
@@ -106,6 +109,41 @@ impl TranslationCatalog {
         Ok(self)
     }
 
+    /// Load translations from a Fluent (FTL) resource
+    ///
+    /// Messages and their attributes are added to the catalog; see the
+    /// `fluent` module for how placeables and select expressions map to
+    /// templates and plural forms.
+    ///
+    /// ```
+    /// use rf_i18n::TranslationCatalog;
+    ///
+    /// let catalog = TranslationCatalog::new("en")
+    ///     .load_fluent("hello = Hello, { $name }!\n    .title = Greeting")
+    ///     .unwrap();
+    /// assert_eq!(catalog.get("hello").unwrap(), "Hello, {{name}}!");
+    /// assert_eq!(catalog.get("hello.title").unwrap(), "Greeting");
+    /// ```
+    pub fn load_fluent(mut self, ftl: &str) -> I18nResult<Self> {
+        self.translations.extend(fluent::parse(ftl)?);
+        Ok(self)
+    }
+
+    /// Load translations from a gettext PO file
+    ///
+    /// Plural forms are mapped to this catalog's locale using the file's
+    /// `Plural-Forms` header.
+    pub fn load_po(mut self, po: &str) -> I18nResult<Self> {
+        self.translations.extend(gettext::parse_po(po, &self.locale)?);
+        Ok(self)
+    }
+
+    /// Load translations from a compiled gettext MO file
+    pub fn load_mo(mut self, mo: &[u8]) -> I18nResult<Self> {
+        self.translations.extend(gettext::parse_mo(mo, &self.locale)?);
+        Ok(self)
+    }
+
     /// Add a translation
     pub fn add(mut self, key: impl Into<String>, value: Value) -> Self {
         self.translations.insert(key.into(), value);
@@ -114,23 +152,39 @@ impl TranslationCatalog {
 
     /// Get a translation
     pub fn get(&self, key: &str) -> Option<&Value> {
-        // Support nested keys like "messages.welcome"
-        let parts: Vec<&str> = key.split('.').collect();
-        let mut current = self.translations.get(parts[0])?;
+        // Flat keys loaded from Fluent/gettext may contain dots themselves
+        if let Some(value) = self.translations.get(key) {
+            return Some(value);
+        }
 
-        for part in parts.iter().skip(1) {
-            current = current.get(part)?;
+        // Support nested keys like "messages.welcome", starting from the
+        // longest flat prefix ("auth.errors" in "auth.errors.locked")
+        let mut split = key.len();
+        while let Some(dot) = key[..split].rfind('.') {
+            split = dot;
+            if let Some(mut current) = self.translations.get(&key[..split]) {
+                for part in key[split + 1..].split('.') {
+                    current = current.get(part)?;
+                }
+                return Some(current);
+            }
         }
 
-        Some(current)
+        None
     }
 }
 
+type Catalogs = Arc<HashMap<String, TranslationCatalog>>;
+
 /// i18n instance
 pub struct I18n {
     locale: String,
     fallback_locale: String,
-    catalogs: Arc<HashMap<String, TranslationCatalog>>,
+    catalogs: RwLock<Catalogs>,
+    catalog_dir: Option<PathBuf>,
+    store: Option<Arc<dyn TranslationStore>>,
+    overrides: RwLock<Catalogs>,
+    store_version: AtomicI64,
     handlebars: Handlebars<'static>,
 }
 
@@ -140,7 +194,11 @@ impl I18n {
         Self {
             locale: locale.into(),
             fallback_locale: "en".to_string(),
-            catalogs: Arc::new(HashMap::new()),
+            catalogs: RwLock::new(Arc::new(HashMap::new())),
+            catalog_dir: None,
+            store: None,
+            overrides: RwLock::new(Arc::new(HashMap::new())),
+            store_version: AtomicI64::new(-1),
             handlebars: Handlebars::new(),
         }
     }
@@ -153,12 +211,248 @@ impl I18n {
 
     /// Add a translation catalog
     pub fn add_catalog(mut self, catalog: TranslationCatalog) -> Self {
-        let mut catalogs = (*self.catalogs).clone();
-        catalogs.insert(catalog.locale.clone(), catalog);
-        self.catalogs = Arc::new(catalogs);
+        let catalogs = self.catalogs.get_mut().unwrap_or_else(|e| e.into_inner());
+        Arc::make_mut(catalogs).insert(catalog.locale.clone(), catalog);
+        self
+    }
+
+    /// Load catalogs from a directory with one subdirectory per locale
+    ///
+    /// Each file is a namespace named after its path: `locales/en/auth.json`
+    /// provides `auth.*` keys. JSON, Fluent and gettext files are supported.
+    /// Fails if two files of a locale define the same key.
+    ///
+    /// ```no_run
+    /// use rf_i18n::I18n;
+    ///
+    /// # fn example() -> rf_i18n::I18nResult<()> {
+    /// let i18n = I18n::new("en").load_dir("locales/")?;
+    /// let title = i18n.t("auth.login.title", None)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_dir(mut self, dir: impl AsRef<Path>) -> I18nResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let loaded = dir::load(&dir)?;
+
+        let catalogs = self.catalogs.get_mut().unwrap_or_else(|e| e.into_inner());
+        Arc::make_mut(catalogs).extend(loaded);
+        self.catalog_dir = Some(dir);
+        Ok(self)
+    }
+
+    /// Re-read the catalog directory and swap in its catalogs
+    ///
+    /// Catalogs not backed by the directory are kept. On an error (a broken
+    /// file, a duplicate key) the current catalogs stay in place.
+    pub fn reload(&self) -> I18nResult<()> {
+        let Some(dir) = &self.catalog_dir else {
+            return Ok(());
+        };
+        let loaded = dir::load(dir)?;
+
+        let mut catalogs = self.catalogs.write().unwrap_or_else(|e| e.into_inner());
+        let mut updated = (**catalogs).clone();
+        updated.extend(loaded);
+        *catalogs = Arc::new(updated);
+        Ok(())
+    }
+
+    /// Reload whenever a file in the catalog directory changes
+    ///
+    /// Meant for development; call it on an `Arc`'d instance after
+    /// [`I18n::load_dir`]. Requires a Tokio runtime.
+    pub fn watch(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let i18n = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let Some(dir) = i18n.catalog_dir.clone() else {
+                return;
+            };
+            let mut last = dir::fingerprint(&dir);
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+
+                let current = dir::fingerprint(&dir);
+                if current == last {
+                    continue;
+                }
+                // Don't retry the same broken files on every tick
+                last = current;
+
+                match i18n.reload() {
+                    Ok(()) => tracing::info!(dir = %dir.display(), "Translations reloaded"),
+                    Err(e) => {
+                        tracing::error!(dir = %dir.display(), error = %e, "Failed to reload translations")
+                    }
+                }
+            }
+        })
+    }
+
+    /// Layer runtime-edited translations from `store` over the catalogs
+    ///
+    /// Stored translations are cached in memory; call [`I18n::sync_store`]
+    /// once at startup and use [`I18n::watch_store`] to pick up edits made by
+    /// other instances.
+    ///
+    /// ```
+    /// use rf_i18n::{I18n, MemoryTranslationStore, TranslationCatalog};
+    /// use std::sync::Arc;
+    ///
+    /// # async fn example() -> rf_i18n::I18nResult<()> {
+    /// let catalog = TranslationCatalog::new("en").load_json(r#"{"title": "Shop"}"#)?;
+    /// let i18n = I18n::new("en")
+    ///     .add_catalog(catalog)
+    ///     .with_store(Arc::new(MemoryTranslationStore::new()));
+    /// i18n.sync_store().await?;
+    ///
+    /// let revision = i18n.update_translation("en", "title", "Store".into(), Some("editor")).await?;
+    /// assert_eq!(i18n.t("title", None)?, "Store");
+    ///
+    /// i18n.remove_translation("en", "title", Some("editor")).await?;
+    /// assert_eq!(i18n.t("title", None)?, "Shop");
+    ///
+    /// i18n.rollback_translation("en", "title", revision.id, Some("editor")).await?;
+    /// assert_eq!(i18n.t("title", None)?, "Store");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_store(mut self, store: Arc<dyn TranslationStore>) -> Self {
+        self.store = Some(store);
         self
     }
 
+    /// Translation store, if configured
+    pub fn store(&self) -> Option<&Arc<dyn TranslationStore>> {
+        self.store.as_ref()
+    }
+
+    /// Reload stored translations if the store changed since the last sync
+    ///
+    /// Returns whether the cached translations were replaced.
+    pub async fn sync_store(&self) -> I18nResult<bool> {
+        let store = self.require_store()?;
+
+        let version = store.version().await?;
+        if version == self.store_version.load(Ordering::Acquire) {
+            return Ok(false);
+        }
+
+        let mut overrides: HashMap<String, TranslationCatalog> = HashMap::new();
+        for record in store.all().await? {
+            overrides
+                .entry(record.locale.clone())
+                .or_insert_with(|| TranslationCatalog::new(record.locale.clone()))
+                .translations
+                .insert(record.key, record.value);
+        }
+
+        *self.overrides.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(overrides);
+        self.store_version.store(version, Ordering::Release);
+        Ok(true)
+    }
+
+    /// Set a translation in the store and apply it immediately
+    pub async fn update_translation(
+        &self,
+        locale: &str,
+        key: &str,
+        value: Value,
+        author: Option<&str>,
+    ) -> I18nResult<TranslationRevision> {
+        let revision = self.require_store()?.set(locale, key, value, author).await?;
+        self.sync_store().await?;
+        Ok(revision)
+    }
+
+    /// Remove a stored translation, falling back to the catalogs again
+    pub async fn remove_translation(
+        &self,
+        locale: &str,
+        key: &str,
+        author: Option<&str>,
+    ) -> I18nResult<TranslationRevision> {
+        let revision = self.require_store()?.delete(locale, key, author).await?;
+        self.sync_store().await?;
+        Ok(revision)
+    }
+
+    /// Restore a key to the value it had after `revision`
+    pub async fn rollback_translation(
+        &self,
+        locale: &str,
+        key: &str,
+        revision: i64,
+        author: Option<&str>,
+    ) -> I18nResult<TranslationRevision> {
+        let revision = self
+            .require_store()?
+            .rollback(locale, key, revision, author)
+            .await?;
+        self.sync_store().await?;
+        Ok(revision)
+    }
+
+    /// Sync with the translation store periodically
+    ///
+    /// Picks up edits made through other instances. Requires a Tokio runtime.
+    pub fn watch_store(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let i18n = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = i18n.sync_store().await {
+                    tracing::error!(error = %e, "Failed to sync translations from store");
+                }
+            }
+        })
+    }
+
+    /// Manage the translation overrides of a tenant
+    ///
+    /// Overrides live in the translation store, so they are versioned and can
+    /// be rolled back like any other edit.
+    ///
+    /// ```
+    /// use rf_i18n::{I18n, MemoryTranslationStore, TranslationCatalog};
+    /// use std::sync::Arc;
+    ///
+    /// # async fn example() -> rf_i18n::I18nResult<()> {
+    /// let catalog = TranslationCatalog::new("en").load_json(r#"{"ticket": "Ticket"}"#)?;
+    /// let i18n = I18n::new("en")
+    ///     .add_catalog(catalog)
+    ///     .with_store(Arc::new(MemoryTranslationStore::new()));
+    ///
+    /// i18n.tenant("acme").set("en", "ticket", "Case".into(), Some("admin@acme")).await?;
+    ///
+    /// assert_eq!(i18n.translate_for_tenant("acme", "en", "ticket", None)?, "Case");
+    /// assert_eq!(i18n.translate_for_tenant("globex", "en", "ticket", None)?, "Ticket");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tenant<'a>(&'a self, tenant: &'a str) -> TenantOverrides<'a> {
+        TenantOverrides::new(self, tenant)
+    }
+
+    fn require_store(&self) -> I18nResult<&Arc<dyn TranslationStore>> {
+        self.store
+            .as_ref()
+            .ok_or_else(|| I18nError::StoreError("no translation store configured".to_string()))
+    }
+
+    fn catalogs(&self) -> Catalogs {
+        self.catalogs.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn overrides(&self) -> Catalogs {
+        self.overrides.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
     /// Get the current locale
     pub fn locale(&self) -> &str {
         &self.locale
@@ -169,19 +463,59 @@ impl I18n {
         self.locale = locale.into();
     }
 
+    /// Locales with a loaded catalog
+    pub fn available_locales(&self) -> Vec<String> {
+        self.catalogs().keys().cloned().collect()
+    }
+
     /// Translate a key
     pub fn t(&self, key: &str, data: Option<Value>) -> I18nResult<String> {
-        // Try current locale first
-        if let Some(catalog) = self.catalogs.get(&self.locale) {
-            if let Some(translation) = catalog.get(key) {
-                return self.render_translation(translation, data);
+        self.translate(&self.locale, key, data)
+    }
+
+    /// Translate a key into a specific locale, e.g. one negotiated per request
+    pub fn translate(&self, locale: &str, key: &str, data: Option<Value>) -> I18nResult<String> {
+        self.translate_scoped(None, locale, key, data)
+    }
+
+    /// Translate a key for a tenant, preferring the tenant's overrides
+    ///
+    /// Resolution order is tenant override, locale, then the same for the
+    /// fallback locale. See [`I18n::tenant`] for managing overrides.
+    pub fn translate_for_tenant(
+        &self,
+        tenant: &str,
+        locale: &str,
+        key: &str,
+        data: Option<Value>,
+    ) -> I18nResult<String> {
+        self.translate_scoped(Some(tenant), locale, key, data)
+    }
+
+    fn translate_scoped(
+        &self,
+        tenant: Option<&str>,
+        locale: &str,
+        key: &str,
+        data: Option<Value>,
+    ) -> I18nResult<String> {
+        let catalogs = self.catalogs();
+        let overrides = self.overrides();
+
+        // Try requested locale first, then the fallback locale; stored
+        // translations take precedence over the catalogs
+        for locale in [locale, self.fallback_locale.as_str()] {
+            if let Some(tenant) = tenant {
+                let scoped = tenant::scoped_locale(tenant, locale);
+                if let Some(translation) = overrides.get(&scoped).and_then(|c| c.get(key)) {
+                    return self.render_translation(translation, data);
+                }
             }
-        }
 
-        // Try fallback locale
-        if let Some(catalog) = self.catalogs.get(&self.fallback_locale) {
-            if let Some(translation) = catalog.get(key) {
-                return self.render_translation(translation, data);
+            for layer in [&overrides, &catalogs] {
+                if let Some(translation) = layer.get(locale).and_then(|c| c.get(key)) {
+                    return self.render_translation(translation, data);
+                }
             }
         }
 
@@ -189,17 +523,67 @@ impl I18n {
     }
 
     /// Translate with pluralization
+    ///
+    /// The plural form is chosen with the CLDR cardinal rules of the current
+    /// locale. An explicit `zero` form is used for a count of 0 when present,
+    /// even in locales whose rules have no zero category.
     pub fn t_plural(&self, key: &str, count: i64) -> I18nResult<String> {
-        let plural_rule = self.get_plural_rule(count);
+        self.translate_plural(&self.locale, key, count)
+    }
+
+    /// Translate with pluralization into a specific locale
+    pub fn translate_plural(&self, locale: &str, key: &str, count: i64) -> I18nResult<String> {
+        let plural_rule = PluralRule::cardinal(locale, count);
+        self.t_plural_form(None, locale, key, plural_rule, count)
+    }
+
+    /// Translate with pluralization for a tenant
+    pub fn translate_plural_for_tenant(
+        &self,
+        tenant: &str,
+        locale: &str,
+        key: &str,
+        count: i64,
+    ) -> I18nResult<String> {
+        let plural_rule = PluralRule::cardinal(locale, count);
+        self.t_plural_form(Some(tenant), locale, key, plural_rule, count)
+    }
+
+    /// Translate with ordinal pluralization ("1st", "2nd", "3rd")
+    ///
+    /// Looks up `{key}.{category}` using the CLDR ordinal rules of the
+    /// current locale, falling back to `{key}.other`.
+    pub fn t_ordinal(&self, key: &str, count: i64) -> I18nResult<String> {
+        let plural_rule = PluralRule::ordinal(&self.locale, count);
+        self.t_plural_form(None, &self.locale, key, plural_rule, count)
+    }
+
+    fn t_plural_form(
+        &self,
+        tenant: Option<&str>,
+        locale: &str,
+        key: &str,
+        plural_rule: PluralRule,
+        count: i64,
+    ) -> I18nResult<String> {
+        let data = serde_json::json!({ "count": count });
+
+        if count == 0 {
+            let zero_key = format!("{}.zero", key);
+            if let Ok(translation) = self.translate_scoped(tenant, locale, &zero_key, Some(data.clone())) {
+                return Ok(translation);
+            }
+        }
+
         let plural_key = format!("{}.{}", key, plural_rule.key());
 
         // Try to get plural-specific translation
-        match self.t(&plural_key, Some(serde_json::json!({ "count": count }))) {
+        match self.translate_scoped(tenant, locale, &plural_key, Some(data.clone())) {
             Ok(translation) => Ok(translation),
             Err(_) => {
                 // Fallback to "other" if specific rule not found
                 let other_key = format!("{}.other", key);
-                self.t(&other_key, Some(serde_json::json!({ "count": count })))
+                self.translate_scoped(tenant, locale, &other_key, Some(data))
             }
         }
     }
@@ -236,15 +620,6 @@ impl I18n {
         }
     }
 
-    /// Get plural rule for current locale
-    fn get_plural_rule(&self, count: i64) -> PluralRule {
-        match self.locale.as_str() {
-            "de" => PluralRule::for_german(count),
-            "fr" => PluralRule::for_french(count),
-            _ => PluralRule::for_english(count),
-        }
-    }
-
     /// Render translation with interpolation
     fn render_translation(&self, translation: &Value, data: Option<Value>) -> I18nResult<String> {
         match translation {
@@ -402,25 +777,119 @@ mod tests {
     }
 
     #[test]
-    fn test_plural_rules_english() {
-        assert_eq!(PluralRule::for_english(0), PluralRule::Zero);
-        assert_eq!(PluralRule::for_english(1), PluralRule::One);
-        assert_eq!(PluralRule::for_english(2), PluralRule::Other);
-        assert_eq!(PluralRule::for_english(100), PluralRule::Other);
+    fn test_plural_explicit_zero() {
+        let catalog = TranslationCatalog::new("en").add(
+            "items",
+            serde_json::json!({
+                "zero": "No items",
+                "one": "1 item",
+                "other": "{{count}} items"
+            }),
+        );
+        let i18n = I18n::new("en").add_catalog(catalog);
+
+        assert_eq!(i18n.t_plural("items", 0).unwrap(), "No items");
+        assert_eq!(i18n.t_plural("items", 2).unwrap(), "2 items");
+    }
+
+    #[test]
+    fn test_plural_russian() {
+        let catalog = TranslationCatalog::new("ru").add(
+            "files",
+            serde_json::json!({
+                "one": "{{count}} файл",
+                "few": "{{count}} файла",
+                "many": "{{count}} файлов",
+                "other": "{{count}} файла"
+            }),
+        );
+        let i18n = I18n::new("ru").add_catalog(catalog);
+
+        assert_eq!(i18n.t_plural("files", 21).unwrap(), "21 файл");
+        assert_eq!(i18n.t_plural("files", 3).unwrap(), "3 файла");
+        assert_eq!(i18n.t_plural("files", 11).unwrap(), "11 файлов");
     }
 
     #[test]
-    fn test_plural_rules_german() {
-        assert_eq!(PluralRule::for_german(1), PluralRule::One);
-        assert_eq!(PluralRule::for_german(0), PluralRule::Other);
-        assert_eq!(PluralRule::for_german(2), PluralRule::Other);
+    fn test_ordinal() {
+        let catalog = TranslationCatalog::new("en").add(
+            "place",
+            serde_json::json!({
+                "one": "{{count}}st",
+                "two": "{{count}}nd",
+                "few": "{{count}}rd",
+                "other": "{{count}}th"
+            }),
+        );
+        let i18n = I18n::new("en").add_catalog(catalog);
+
+        assert_eq!(i18n.t_ordinal("place", 1).unwrap(), "1st");
+        assert_eq!(i18n.t_ordinal("place", 12).unwrap(), "12th");
+        assert_eq!(i18n.t_ordinal("place", 23).unwrap(), "23rd");
     }
 
     #[test]
-    fn test_plural_rules_french() {
-        assert_eq!(PluralRule::for_french(0), PluralRule::One);
-        assert_eq!(PluralRule::for_french(1), PluralRule::One);
-        assert_eq!(PluralRule::for_french(2), PluralRule::Other);
+    fn test_plural_from_fluent() {
+        let catalog = TranslationCatalog::new("en")
+            .load_fluent("items = { $count ->\n    [one] One item\n   *[other] { $count } items\n}\n")
+            .unwrap();
+        let i18n = I18n::new("en").add_catalog(catalog);
+
+        assert_eq!(i18n.t_plural("items", 1).unwrap(), "One item");
+        assert_eq!(i18n.t_plural("items", 3).unwrap(), "3 items");
+    }
+
+    #[test]
+    fn test_catalog_from_po() {
+        let po = "msgid \"Save\"\nmsgstr \"Speichern\"\n\nmsgid \"Save as...\"\nmsgstr \"Speichern unter...\"\n";
+        let catalog = TranslationCatalog::new("de").load_po(po).unwrap();
+
+        assert_eq!(catalog.get("Save").unwrap(), "Speichern");
+        assert_eq!(catalog.get("Save as...").unwrap(), "Speichern unter...");
+    }
+
+    #[test]
+    fn test_load_dir_and_reload() {
+        let dir = dir::tests::temp_dir(
+            "reload",
+            &[
+                ("en/auth.json", r#"{"login": "Log in"}"#),
+                ("de/auth.json", r#"{"login": "Anmelden"}"#),
+            ],
+        );
+        let mut i18n = I18n::new("de").load_dir(&dir).unwrap();
+        assert_eq!(i18n.t("auth.login", None).unwrap(), "Anmelden");
+
+        std::fs::write(dir.join("de/auth.json"), r#"{"login": "Einloggen"}"#).unwrap();
+        i18n.reload().unwrap();
+        assert_eq!(i18n.t("auth.login", None).unwrap(), "Einloggen");
+
+        // A broken file keeps the last good catalogs
+        std::fs::write(dir.join("de/auth.json"), "{").unwrap();
+        assert!(i18n.reload().is_err());
+        assert_eq!(i18n.t("auth.login", None).unwrap(), "Einloggen");
+
+        i18n.set_locale("en");
+        assert_eq!(i18n.t("auth.login", None).unwrap(), "Log in");
+    }
+
+    #[tokio::test]
+    async fn test_watch_hot_swaps_catalogs() {
+        let dir = dir::tests::temp_dir("watch", &[("en/app.json", r#"{"name": "Forge"}"#)]);
+        let i18n = Arc::new(I18n::new("en").load_dir(&dir).unwrap());
+        let watcher = i18n.watch(Duration::from_millis(10));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        std::fs::write(dir.join("en/app.json"), r#"{"name": "RustForge"}"#).unwrap();
+
+        for _ in 0..100 {
+            if i18n.t("app.name", None).unwrap() == "RustForge" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(i18n.t("app.name", None).unwrap(), "RustForge");
+        watcher.abort();
     }
 
     #[test]
@@ -0,0 +1,214 @@
+//! Request locales and locale negotiation
+
+use crate::{I18n, I18nResult};
+use serde_json::Value;
+use std::{fmt, sync::Arc};
+
+/// The locale of the current request, bound to the translations
+///
+/// Set by `LocaleLayer` (feature `axum`) and available as an extractor.
+/// A locale bound to a tenant prefers that tenant's overrides, see
+/// [`I18n::tenant`]. Translation helpers never fail: a missing key renders as the key itself,
+/// which keeps templates readable while translations are incomplete.
+///
+/// # Example
+///
+/// ```
+/// use rf_i18n::{t, I18n, Locale, TranslationCatalog};
+/// use std::sync::Arc;
+///
+/// let de = TranslationCatalog::new("de")
+///     .load_json(r#"{"welcome": "Willkommen, {{name}}!"}"#)
+///     .unwrap();
+/// let i18n = Arc::new(I18n::new("en").add_catalog(de));
+///
+/// let locale = Locale::new("de", i18n);
+/// assert_eq!(t!(locale, "welcome", name = "Anna"), "Willkommen, Anna!");
+/// assert_eq!(t!(locale, "missing.key"), "missing.key");
+/// ```
+#[derive(Clone)]
+pub struct Locale {
+    locale: String,
+    tenant: Option<String>,
+    i18n: Arc<I18n>,
+}
+
+impl Locale {
+    pub fn new(locale: impl Into<String>, i18n: Arc<I18n>) -> Self {
+        Self {
+            locale: locale.into(),
+            tenant: None,
+            i18n,
+        }
+    }
+
+    /// Resolve translations with the overrides of `tenant`
+    pub fn with_tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    /// Locale identifier, e.g. `de-CH`
+    pub fn as_str(&self) -> &str {
+        &self.locale
+    }
+
+    /// Tenant whose overrides apply, if any
+    pub fn tenant(&self) -> Option<&str> {
+        self.tenant.as_deref()
+    }
+
+    /// Translations this locale is bound to
+    pub fn i18n(&self) -> &Arc<I18n> {
+        &self.i18n
+    }
+
+    /// Translate a key, falling back to the key itself
+    pub fn t(&self, key: &str) -> String {
+        self.or_key(key, self.try_t(key, None))
+    }
+
+    /// Translate a key with interpolation data
+    pub fn t_with(&self, key: &str, data: Value) -> String {
+        self.or_key(key, self.try_t(key, Some(data)))
+    }
+
+    /// Translate with pluralization
+    pub fn t_plural(&self, key: &str, count: i64) -> String {
+        let result = match &self.tenant {
+            Some(tenant) => self.i18n.translate_plural_for_tenant(tenant, &self.locale, key, count),
+            None => self.i18n.translate_plural(&self.locale, key, count),
+        };
+        self.or_key(key, result)
+    }
+
+    /// Translate, returning errors instead of falling back to the key
+    pub fn try_t(&self, key: &str, data: Option<Value>) -> I18nResult<String> {
+        match &self.tenant {
+            Some(tenant) => self.i18n.translate_for_tenant(tenant, &self.locale, key, data),
+            None => self.i18n.translate(&self.locale, key, data),
+        }
+    }
+
+    fn or_key(&self, key: &str, result: I18nResult<String>) -> String {
+        result.unwrap_or_else(|_| key.to_string())
+    }
+}
+
+impl fmt::Debug for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Locale")
+            .field("locale", &self.locale)
+            .field("tenant", &self.tenant)
+            .finish()
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.locale)
+    }
+}
+
+/// Translate with a [`Locale`]
+///
+/// `t!(locale, "key")` or `t!(locale, "key", name = value, ...)`. Values can
+/// be anything serializable.
+#[macro_export]
+macro_rules! t {
+    ($locale:expr, $key:expr $(,)?) => {
+        $locale.t($key)
+    };
+    ($locale:expr, $key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $locale.t_with($key, $crate::__private::json!({ $(stringify!($name): $value),+ }))
+    };
+}
+
+/// Best available locale for a requested one
+///
+/// Matches exactly first (ignoring case and `_` vs `-`), then by language:
+/// `de-CH` falls back to `de`, and `de` picks the first available `de-*`.
+pub fn match_locale<'a, S: AsRef<str>>(requested: &str, available: &'a [S]) -> Option<&'a str> {
+    let requested = normalize(requested);
+    let language = requested.split('-').next().unwrap_or_default();
+    if language.is_empty() || language == "*" {
+        return None;
+    }
+
+    let available = || available.iter().map(AsRef::as_ref);
+    available()
+        .find(|candidate| normalize(candidate) == requested)
+        .or_else(|| available().find(|candidate| normalize(candidate) == language))
+        .or_else(|| {
+            available().find(|candidate| normalize(candidate).split('-').next() == Some(language))
+        })
+}
+
+/// Locales of an `Accept-Language` header, most preferred first
+///
+/// Entries with `q=0` are dropped; equal weights keep header order.
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut weighted: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|part| {
+            let mut fields = part.split(';');
+            let tag = fields.next()?.trim();
+            if tag.is_empty() {
+                return None;
+            }
+            let quality = fields
+                .find_map(|field| field.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            (quality > 0.0).then(|| (tag.to_string(), quality))
+        })
+        .collect();
+
+    // Stable sort keeps header order for equal weights
+    weighted.sort_by(|a, b| b.1.total_cmp(&a.1));
+    weighted.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// Negotiate a locale from an `Accept-Language` header
+pub fn negotiate<'a, S: AsRef<str>>(accept_language: &str, available: &'a [S]) -> Option<&'a str> {
+    parse_accept_language(accept_language)
+        .iter()
+        .find_map(|requested| match_locale(requested, available))
+}
+
+fn normalize(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_accept_language() {
+        assert_eq!(
+            parse_accept_language("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7, *;q=0.5"),
+            ["fr-CH", "fr", "en", "de", "*"]
+        );
+        assert_eq!(parse_accept_language("en;q=0.5, de, it;q=0"), ["de", "en"]);
+        assert!(parse_accept_language("").is_empty());
+    }
+
+    #[test]
+    fn test_match_locale() {
+        let available = ["en", "de", "pt-BR"];
+
+        assert_eq!(match_locale("DE", &available), Some("de"));
+        assert_eq!(match_locale("de_CH", &available), Some("de"));
+        assert_eq!(match_locale("pt", &available), Some("pt-BR"));
+        assert_eq!(match_locale("fr", &available), None);
+        assert_eq!(match_locale("*", &available), None);
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let available = ["en", "de"];
+
+        assert_eq!(negotiate("fr-CH, de-CH;q=0.8, en;q=0.5", &available), Some("de"));
+        assert_eq!(negotiate("fr", &available), None);
+    }
+}
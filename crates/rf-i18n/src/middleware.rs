@@ -0,0 +1,315 @@
+//! Axum locale negotiation
+
+use crate::{locale::match_locale, negotiate, I18n, Locale};
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{header, request::Parts, StatusCode},
+    response::Response,
+};
+use rf_middleware::{Middleware, MiddlewareService, Next};
+use std::sync::Arc;
+use tower::Layer;
+
+type UserLocale = Arc<dyn Fn(&Request) -> Option<String> + Send + Sync>;
+type TenantResolver = Arc<dyn Fn(&Request) -> Option<String> + Send + Sync>;
+
+/// Where a request locale can come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocaleSource {
+    /// Query parameter, e.g. `?locale=de`
+    Query(String),
+    /// Cookie, e.g. `locale=de`
+    Cookie(String),
+    /// The authenticated user's stored preference, see [`LocaleLayer::user_locale`]
+    User,
+    /// The `Accept-Language` header
+    AcceptLanguage,
+}
+
+/// Layer negotiating the locale of every request
+///
+/// Sources are tried in order and the first one naming an available locale
+/// wins; requests matching none get the [`I18n`] default locale. The result
+/// is stored as a [`Locale`] in the request extensions, where handlers pick
+/// it up as an extractor.
+///
+/// With feature `tenancy` the locale is bound to the `rf_tenancy::Tenant` in
+/// the request extensions, so tenant overrides apply. Install the layer
+/// inside the tenant middleware, or use [`LocaleLayer::tenant_resolver`].
+///
+/// # Example
+///
+/// ```ignore
+/// use rf_i18n::{t, I18n, Locale, LocaleLayer, LocaleSource};
+///
+/// async fn home(locale: Locale) -> String {
+///     t!(locale, "welcome", name = "Anna")
+/// }
+///
+/// let layer = LocaleLayer::new(Arc::new(i18n))
+///     .sources([
+///         LocaleSource::Query("lang".into()),
+///         LocaleSource::User,
+///         LocaleSource::AcceptLanguage,
+///     ])
+///     .user_locale(|req| req.extensions().get::<AuthUser>()?.locale.clone());
+///
+/// let app = Router::new().route("/", get(home)).layer(layer);
+/// ```
+#[derive(Clone)]
+pub struct LocaleLayer {
+    i18n: Arc<I18n>,
+    sources: Arc<Vec<LocaleSource>>,
+    user_locale: Option<UserLocale>,
+    tenant: Option<TenantResolver>,
+}
+
+impl LocaleLayer {
+    /// Negotiate between the locales of `i18n`'s catalogs
+    ///
+    /// Sources default to the `locale` query parameter, the `locale` cookie,
+    /// the user preference and `Accept-Language`, in that order.
+    pub fn new(i18n: Arc<I18n>) -> Self {
+        Self {
+            i18n,
+            sources: Arc::new(vec![
+                LocaleSource::Query("locale".to_string()),
+                LocaleSource::Cookie("locale".to_string()),
+                LocaleSource::User,
+                LocaleSource::AcceptLanguage,
+            ]),
+            user_locale: None,
+            tenant: default_tenant_resolver(),
+        }
+    }
+
+    /// Set the sources to try, highest priority first
+    pub fn sources(mut self, sources: impl IntoIterator<Item = LocaleSource>) -> Self {
+        self.sources = Arc::new(sources.into_iter().collect());
+        self
+    }
+
+    /// Resolve the authenticated user's preferred locale
+    pub fn user_locale<F>(mut self, resolve: F) -> Self
+    where
+        F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+    {
+        self.user_locale = Some(Arc::new(resolve));
+        self
+    }
+
+    /// Resolve the tenant whose translation overrides apply
+    pub fn tenant_resolver<F>(mut self, resolve: F) -> Self
+    where
+        F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+    {
+        self.tenant = Some(Arc::new(resolve));
+        self
+    }
+
+    /// Negotiated locale for a request
+    pub fn negotiate(&self, req: &Request) -> Locale {
+        // Looked up per request so hot-reloaded locales are picked up
+        let mut available = self.i18n.available_locales();
+        available.sort();
+
+        let locale = self
+            .sources
+            .iter()
+            .find_map(|source| self.resolve(source, req, &available))
+            .unwrap_or_else(|| self.i18n.locale().to_string());
+
+        let locale = Locale::new(locale, self.i18n.clone());
+        match self.tenant.as_ref().and_then(|resolve| resolve(req)) {
+            Some(tenant) => locale.with_tenant(tenant),
+            None => locale,
+        }
+    }
+
+    fn resolve(&self, source: &LocaleSource, req: &Request, available: &[String]) -> Option<String> {
+        let matched = match source {
+            LocaleSource::Query(name) => {
+                let value = query_param(req, name)?;
+                match_locale(&value, available)
+            }
+            LocaleSource::Cookie(name) => {
+                let value = cookie(req, name)?;
+                match_locale(&value, available)
+            }
+            LocaleSource::User => {
+                let value = self.user_locale.as_ref().and_then(|resolve| resolve(req))?;
+                match_locale(&value, available)
+            }
+            LocaleSource::AcceptLanguage => {
+                let header = req.headers().get(header::ACCEPT_LANGUAGE)?.to_str().ok()?;
+                negotiate(header, available)
+            }
+        };
+        matched.map(String::from)
+    }
+}
+
+#[cfg(feature = "tenancy")]
+fn default_tenant_resolver() -> Option<TenantResolver> {
+    Some(Arc::new(|req: &Request| {
+        req.extensions()
+            .get::<rf_tenancy::Tenant>()
+            .map(|tenant| tenant.id().to_string())
+    }))
+}
+
+#[cfg(not(feature = "tenancy"))]
+fn default_tenant_resolver() -> Option<TenantResolver> {
+    None
+}
+
+fn query_param(req: &Request, name: &str) -> Option<String> {
+    req.uri()
+        .query()?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+fn cookie(req: &Request, name: &str) -> Option<String> {
+    req.headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.trim_matches('"').to_string())
+}
+
+impl Middleware for LocaleLayer {
+    async fn handle(self, mut req: Request, next: Next) -> Response {
+        let locale = self.negotiate(&req);
+        req.extensions_mut().insert(locale);
+        next.run(req).await
+    }
+}
+
+impl<S> Layer<S> for LocaleLayer {
+    type Service = LocaleService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+/// Service created by [`LocaleLayer`]
+pub type LocaleService<S> = MiddlewareService<LocaleLayer, S>;
+
+impl<S> FromRequestParts<S> for Locale
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Locale>().cloned().ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "LocaleLayer is not installed",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{t, TranslationCatalog};
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn i18n() -> Arc<I18n> {
+        let en = TranslationCatalog::new("en")
+            .load_json(r#"{"hello": "Hello, {{name}}!"}"#)
+            .unwrap();
+        let de = TranslationCatalog::new("de")
+            .load_json(r#"{"hello": "Hallo, {{name}}!"}"#)
+            .unwrap();
+        let fr = TranslationCatalog::new("fr")
+            .load_json(r#"{"hello": "Bonjour, {{name}} !"}"#)
+            .unwrap();
+        Arc::new(I18n::new("en").add_catalog(en).add_catalog(de).add_catalog(fr))
+    }
+
+    fn request(uri: &str, headers: &[(&str, &str)]) -> Request {
+        let mut builder = Request::builder().uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_source_priority() {
+        let layer = LocaleLayer::new(i18n()).user_locale(|req| {
+            req.headers()
+                .get("x-user-locale")
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        });
+        let negotiate = |req: Request| layer.negotiate(&req).as_str().to_string();
+
+        assert_eq!(negotiate(request("/", &[])), "en");
+        assert_eq!(negotiate(request("/", &[("accept-language", "it, de-CH;q=0.8")])), "de");
+        assert_eq!(
+            negotiate(request("/", &[("accept-language", "de"), ("x-user-locale", "fr")])),
+            "fr"
+        );
+        assert_eq!(
+            negotiate(request("/", &[("cookie", "theme=dark; locale=de"), ("x-user-locale", "fr")])),
+            "de"
+        );
+        // Unavailable locales are skipped
+        assert_eq!(negotiate(request("/?locale=it", &[("cookie", "locale=fr")])), "fr");
+        assert_eq!(negotiate(request("/?page=2&locale=de", &[("cookie", "locale=fr")])), "de");
+
+        let header_only = LocaleLayer::new(i18n()).sources([LocaleSource::AcceptLanguage]);
+        assert_eq!(header_only.negotiate(&request("/?locale=de", &[])).as_str(), "en");
+    }
+
+    #[test]
+    fn test_tenant_resolver() {
+        let layer = LocaleLayer::new(i18n()).tenant_resolver(|req| {
+            req.headers()
+                .get("x-tenant")
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        });
+
+        let locale = layer.negotiate(&request("/?locale=de", &[("x-tenant", "acme")]));
+        assert_eq!(locale.tenant(), Some("acme"));
+        assert_eq!(layer.negotiate(&request("/", &[])).tenant(), None);
+    }
+
+    #[cfg(feature = "tenancy")]
+    #[test]
+    fn test_tenant_from_request_context() {
+        let mut req = request("/", &[]);
+        req.extensions_mut().insert(rf_tenancy::Tenant::new("acme", "Acme"));
+
+        assert_eq!(LocaleLayer::new(i18n()).negotiate(&req).tenant(), Some("acme"));
+    }
+
+    #[tokio::test]
+    async fn test_locale_extractor() {
+        async fn hello(locale: Locale) -> String {
+            t!(locale, "hello", name = "Anna")
+        }
+
+        let app = Router::new()
+            .route("/", get(hello))
+            .layer(LocaleLayer::new(i18n()));
+
+        let response = app
+            .oneshot(request("/", &[("accept-language", "fr-FR,fr;q=0.9")]))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], "Bonjour, Anna !".as_bytes());
+    }
+}
@@ -0,0 +1,198 @@
+//! CLDR plural rules
+//!
+//! Plural categories are selected with the CLDR rule tables from
+//! `intl_pluralrules`, covering cardinal and ordinal rules for every CLDR
+//! locale. Locales without an exact table fall back to their language
+//! (`de-CH` uses `de`); unknown languages always select `other`.
+
+use intl_pluralrules::{PluralCategory, PluralRuleType, PluralRules};
+use unic_langid::LanguageIdentifier;
+
+pub use intl_pluralrules::operands::PluralOperands;
+
+/// Plural category, as defined by CLDR
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PluralRule {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+/// Kind of number a plural form is selected for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PluralType {
+    /// Quantities: "1 file", "2 files"
+    Cardinal,
+    /// Positions: "1st", "2nd", "3rd"
+    Ordinal,
+}
+
+impl PluralRule {
+    /// Cardinal category of `n` in `locale`
+    ///
+    /// `n` can be an integer, a float or a decimal string. Strings keep
+    /// visible fraction digits, which matter in some locales: in English
+    /// `"1"` is `one` but `"1.0"` is `other`.
+    ///
+    /// ```
+    /// use rf_i18n::PluralRule;
+    ///
+    /// assert_eq!(PluralRule::cardinal("ru", 21), PluralRule::One);
+    /// assert_eq!(PluralRule::cardinal("ru", 22), PluralRule::Few);
+    /// assert_eq!(PluralRule::cardinal("ru", 25), PluralRule::Many);
+    /// assert_eq!(PluralRule::cardinal("en", "1.0"), PluralRule::Other);
+    /// ```
+    pub fn cardinal<N: TryInto<PluralOperands>>(locale: &str, n: N) -> Self {
+        Self::select(locale, PluralType::Cardinal, n)
+    }
+
+    /// Ordinal category of `n` in `locale`
+    ///
+    /// ```
+    /// use rf_i18n::PluralRule;
+    ///
+    /// assert_eq!(PluralRule::ordinal("en", 22), PluralRule::Two);
+    /// assert_eq!(PluralRule::ordinal("en", 12), PluralRule::Other);
+    /// ```
+    pub fn ordinal<N: TryInto<PluralOperands>>(locale: &str, n: N) -> Self {
+        Self::select(locale, PluralType::Ordinal, n)
+    }
+
+    /// Category of `n` in `locale` for the given plural type
+    pub fn select<N: TryInto<PluralOperands>>(locale: &str, plural_type: PluralType, n: N) -> Self {
+        let Some(rules) = rules_for(locale, plural_type) else {
+            return PluralRule::Other;
+        };
+
+        match rules.select(n) {
+            Ok(category) => category.into(),
+            Err(_) => PluralRule::Other,
+        }
+    }
+
+    /// Whether CLDR has plural rules for `locale` (or its language)
+    pub fn is_supported(locale: &str) -> bool {
+        rules_for(locale, PluralType::Cardinal).is_some()
+    }
+
+    /// Get plural rule key
+    pub fn key(&self) -> &'static str {
+        match self {
+            PluralRule::Zero => "zero",
+            PluralRule::One => "one",
+            PluralRule::Two => "two",
+            PluralRule::Few => "few",
+            PluralRule::Many => "many",
+            PluralRule::Other => "other",
+        }
+    }
+}
+
+impl From<PluralCategory> for PluralRule {
+    fn from(category: PluralCategory) -> Self {
+        match category {
+            PluralCategory::ZERO => PluralRule::Zero,
+            PluralCategory::ONE => PluralRule::One,
+            PluralCategory::TWO => PluralRule::Two,
+            PluralCategory::FEW => PluralRule::Few,
+            PluralCategory::MANY => PluralRule::Many,
+            PluralCategory::OTHER => PluralRule::Other,
+        }
+    }
+}
+
+impl From<PluralType> for PluralRuleType {
+    fn from(plural_type: PluralType) -> Self {
+        match plural_type {
+            PluralType::Cardinal => PluralRuleType::CARDINAL,
+            PluralType::Ordinal => PluralRuleType::ORDINAL,
+        }
+    }
+}
+
+/// Rules for the most specific match of `locale`
+///
+/// Tries the full identifier first (`pt-PT` has its own rules), then the
+/// language alone.
+fn rules_for(locale: &str, plural_type: PluralType) -> Option<PluralRules> {
+    let langid: LanguageIdentifier = locale.replace('_', "-").parse().ok()?;
+
+    let mut candidates = vec![langid.clone()];
+    if langid.script.is_some() || langid.region.is_some() || langid.variants().len() > 0 {
+        candidates.push(LanguageIdentifier::from_parts(langid.language, None, None, &[]));
+    }
+
+    candidates
+        .into_iter()
+        .find_map(|candidate| PluralRules::create(candidate, plural_type.into()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cardinals(locale: &str, numbers: &[i64]) -> Vec<&'static str> {
+        numbers
+            .iter()
+            .map(|&n| PluralRule::cardinal(locale, n).key())
+            .collect()
+    }
+
+    #[test]
+    fn test_cardinal_rules() {
+        assert_eq!(cardinals("en", &[0, 1, 2]), ["other", "one", "other"]);
+        assert_eq!(cardinals("de", &[0, 1, 2]), ["other", "one", "other"]);
+        assert_eq!(cardinals("fr", &[0, 1, 2]), ["one", "one", "other"]);
+        assert_eq!(
+            cardinals("ru", &[1, 2, 5, 11, 21, 22, 112]),
+            ["one", "few", "many", "many", "one", "few", "many"]
+        );
+        assert_eq!(
+            cardinals("pl", &[1, 2, 5, 12, 22, 25]),
+            ["one", "few", "many", "many", "few", "many"]
+        );
+        assert_eq!(
+            cardinals("ar", &[0, 1, 2, 3, 11, 100]),
+            ["zero", "one", "two", "few", "many", "other"]
+        );
+        assert_eq!(cardinals("ja", &[0, 1, 2]), ["other", "other", "other"]);
+    }
+
+    #[test]
+    fn test_decimals() {
+        assert_eq!(PluralRule::cardinal("en", "1"), PluralRule::One);
+        assert_eq!(PluralRule::cardinal("en", "1.0"), PluralRule::Other);
+        assert_eq!(PluralRule::cardinal("ru", "1.5"), PluralRule::Other);
+        assert_eq!(PluralRule::cardinal("fr", 1.5), PluralRule::One);
+    }
+
+    #[test]
+    fn test_ordinal_rules() {
+        let ordinals: Vec<_> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 23, 101]
+            .iter()
+            .map(|&n| PluralRule::ordinal("en", n).key())
+            .collect();
+        assert_eq!(
+            ordinals,
+            ["one", "two", "few", "other", "other", "other", "other", "one", "two", "few", "one"]
+        );
+
+        assert_eq!(PluralRule::ordinal("fr", 1), PluralRule::One);
+        assert_eq!(PluralRule::ordinal("fr", 2), PluralRule::Other);
+    }
+
+    #[test]
+    fn test_locale_fallback() {
+        assert_eq!(PluralRule::cardinal("de-CH", 1), PluralRule::One);
+        assert_eq!(PluralRule::cardinal("ru_RU", 3), PluralRule::Few);
+        // pt-PT has its own table: 0 is "one" in Brazil, "other" in Portugal
+        assert_eq!(PluralRule::cardinal("pt", 0), PluralRule::One);
+        assert_eq!(PluralRule::cardinal("pt-PT", 0), PluralRule::Other);
+
+        assert!(!PluralRule::is_supported("xx"));
+        assert_eq!(PluralRule::cardinal("xx", 1), PluralRule::Other);
+    }
+}
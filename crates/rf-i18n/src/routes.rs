@@ -0,0 +1,496 @@
+//! Localized routes
+//!
+//! Registers one path per locale for the same handler, with static path
+//! segments translated through the catalogs, and generates URLs and
+//! `hreflang` alternates from route names.
+
+use crate::{I18n, Locale};
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{request::Parts, StatusCode},
+    response::Response,
+    routing::MethodRouter,
+    Extension, Router,
+};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rf_middleware::{Middleware, MiddlewareService, Next};
+use std::{collections::HashMap, fmt::Write, sync::Arc};
+use tower::Layer;
+
+/// Everything but the unreserved characters of RFC 3986
+const SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Router registering every route under each available locale
+///
+/// A route pattern such as `/products/{id}` is registered as
+/// `/{locale}/{slug}/{id}` for every locale, where the slug of each static
+/// segment is the translation of `routes.{segment}` (`routes.products =
+/// produkte`), or the segment itself if there is none. Handlers get a
+/// [`Locale`] matching the path and can extract the [`LocalizedRoutes`] to
+/// build links.
+///
+/// Slugs are translated when the router is built; routes do not change with
+/// hot-reloaded catalogs.
+///
+/// # Example
+///
+/// ```ignore
+/// use rf_i18n::{LocalizedRouter, LocalizedRoutes, Locale};
+///
+/// async fn product(locale: Locale, routes: LocalizedRoutes, Path(id): Path<String>) -> Html<String> {
+///     let head = routes.hreflang("product", &[("id", &id)], "https://shop.example");
+///     // ...
+/// }
+///
+/// let app: Router = LocalizedRouter::new(i18n)
+///     .route("products", "/products", get(products))
+///     .route("product", "/products/{id}", get(product))
+///     .into_router();
+/// ```
+pub struct LocalizedRouter<S = ()> {
+    i18n: Arc<I18n>,
+    prefix_default: bool,
+    routes: Vec<(String, String, MethodRouter<S>)>,
+}
+
+impl<S> LocalizedRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    /// Localize routes for the locales of `i18n`'s catalogs
+    pub fn new(i18n: Arc<I18n>) -> Self {
+        Self {
+            i18n,
+            prefix_default: true,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Serve the default locale without a locale prefix, e.g. `/products`
+    pub fn without_default_prefix(mut self) -> Self {
+        self.prefix_default = false;
+        self
+    }
+
+    /// Register a named route for every locale
+    pub fn route(mut self, name: &str, pattern: &str, method_router: MethodRouter<S>) -> Self {
+        self.routes
+            .push((name.to_string(), pattern.to_string(), method_router));
+        self
+    }
+
+    /// Route table for URL generation
+    pub fn routes(&self) -> LocalizedRoutes {
+        let mut locales = self.i18n.available_locales();
+        locales.sort();
+
+        let routes = self
+            .routes
+            .iter()
+            .map(|(name, pattern, _)| {
+                let paths = locales
+                    .iter()
+                    .map(|locale| (locale.clone(), self.localize(pattern, locale)))
+                    .collect();
+                (name.clone(), paths)
+            })
+            .collect();
+
+        LocalizedRoutes {
+            inner: Arc::new(RouteTable {
+                default_locale: self.i18n.locale().to_string(),
+                locales,
+                routes,
+            }),
+        }
+    }
+
+    /// Build the axum router
+    pub fn into_router(self) -> Router<S> {
+        let routes = self.routes();
+        let mut router = Router::new();
+
+        for (name, _, method_router) in self.routes {
+            for (locale, path) in &routes.inner.routes[&name] {
+                let layer = RouteLocaleLayer {
+                    locale: locale.clone(),
+                    i18n: self.i18n.clone(),
+                };
+                router = router.route(path, method_router.clone().layer(layer));
+            }
+        }
+
+        router.layer(Extension(routes))
+    }
+
+    /// Localized path of a pattern
+    fn localize(&self, pattern: &str, locale: &str) -> String {
+        let mut path = String::new();
+        if self.prefix_default || locale != self.i18n.locale() {
+            path.push('/');
+            path.push_str(locale);
+        }
+
+        for segment in pattern.split('/').filter(|s| !s.is_empty()) {
+            path.push('/');
+            if segment.starts_with('{') {
+                path.push_str(segment);
+            } else {
+                let key = format!("routes.{}", segment);
+                match self.i18n.translate(locale, &key, None) {
+                    Ok(slug) => path.push_str(&slug),
+                    Err(_) => path.push_str(segment),
+                }
+            }
+        }
+
+        if path.is_empty() {
+            path.push('/');
+        }
+        path
+    }
+}
+
+struct RouteTable {
+    default_locale: String,
+    locales: Vec<String>,
+    /// Route name to localized path pattern per locale
+    routes: HashMap<String, Vec<(String, String)>>,
+}
+
+/// Localized paths of all named routes
+///
+/// Built by [`LocalizedRouter`] and available as an extractor in its
+/// handlers.
+#[derive(Clone)]
+pub struct LocalizedRoutes {
+    inner: Arc<RouteTable>,
+}
+
+/// A path matched against the localized routes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteMatch {
+    pub name: String,
+    pub locale: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl LocalizedRoutes {
+    /// Locales every route is registered for
+    pub fn locales(&self) -> &[String] {
+        &self.inner.locales
+    }
+
+    /// Path of a route in a locale
+    ///
+    /// Parameters are percent-encoded; `/` is kept only in the value of a
+    /// wildcard parameter like `{*rest}`. Returns `None` for unknown routes
+    /// or locales and if a parameter of the pattern is missing.
+    pub fn url(&self, name: &str, locale: &str, params: &[(&str, &str)]) -> Option<String> {
+        let pattern = self.pattern(name, locale)?;
+
+        let segments: Option<Vec<String>> = pattern
+            .split('/')
+            .map(|segment| match param_name(segment) {
+                Some(param) => params
+                    .iter()
+                    .find(|(name, _)| *name == param)
+                    .map(|(_, value)| encode_param(segment, value)),
+                None => Some(segment.to_string()),
+            })
+            .collect();
+        Some(segments?.join("/"))
+    }
+
+    /// Path of a route in every locale
+    pub fn alternates(&self, name: &str, params: &[(&str, &str)]) -> Vec<(String, String)> {
+        self.inner
+            .locales
+            .iter()
+            .filter_map(|locale| Some((locale.clone(), self.url(name, locale, params)?)))
+            .collect()
+    }
+
+    /// `<link rel="alternate" hreflang>` tags of a route
+    ///
+    /// Includes an `x-default` entry pointing at the default locale.
+    /// Attribute values are HTML-escaped, `base_url` included.
+    pub fn hreflang(&self, name: &str, params: &[(&str, &str)], base_url: &str) -> String {
+        let base_url = base_url.trim_end_matches('/');
+        let mut html = String::new();
+
+        for (locale, path) in self.alternates(name, params) {
+            let _ = writeln!(
+                html,
+                r#"<link rel="alternate" hreflang="{}" href="{}" />"#,
+                escape(&locale),
+                escape(&format!("{}{}", base_url, path))
+            );
+        }
+        if let Some(path) = self.url(name, &self.inner.default_locale, params) {
+            let _ = writeln!(
+                html,
+                r#"<link rel="alternate" hreflang="x-default" href="{}" />"#,
+                escape(&format!("{}{}", base_url, path))
+            );
+        }
+        html
+    }
+
+    /// Route, locale and parameters of a request path
+    ///
+    /// Used to link the current page in other locales. Parameters are
+    /// percent-decoded.
+    pub fn match_path(&self, path: &str) -> Option<RouteMatch> {
+        let segments: Vec<&str> = path.split('/').collect();
+
+        for (name, paths) in &self.inner.routes {
+            for (locale, pattern) in paths {
+                let pattern: Vec<&str> = pattern.split('/').collect();
+                if pattern.len() != segments.len() {
+                    continue;
+                }
+
+                let mut params = Vec::new();
+                let matched = pattern.iter().zip(&segments).all(|(expected, actual)| {
+                    match param_name(expected) {
+                        Some(param) => {
+                            let value = percent_decode_str(actual).decode_utf8_lossy();
+                            params.push((param.to_string(), value.into_owned()));
+                            !actual.is_empty()
+                        }
+                        None => expected == actual,
+                    }
+                });
+                if matched {
+                    return Some(RouteMatch {
+                        name: name.clone(),
+                        locale: locale.clone(),
+                        params,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn pattern(&self, name: &str, locale: &str) -> Option<&str> {
+        self.inner
+            .routes
+            .get(name)?
+            .iter()
+            .find(|(l, _)| l == locale)
+            .map(|(_, path)| path.as_str())
+    }
+}
+
+/// `id` for `{id}`, `rest` for `{*rest}`
+fn param_name(segment: &str) -> Option<&str> {
+    let name = segment.strip_prefix('{')?.strip_suffix('}')?;
+    Some(name.trim_start_matches('*'))
+}
+
+/// Percent-encode the value of the parameter `segment`
+fn encode_param(segment: &str, value: &str) -> String {
+    if segment.starts_with("{*") {
+        value
+            .split('/')
+            .map(|part| utf8_percent_encode(part, SEGMENT).to_string())
+            .collect::<Vec<_>>()
+            .join("/")
+    } else {
+        utf8_percent_encode(value, SEGMENT).to_string()
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl<S> FromRequestParts<S> for LocalizedRoutes
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<LocalizedRoutes>().cloned().ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "LocalizedRouter is not installed",
+        ))
+    }
+}
+
+/// Sets the locale of a localized route, keeping the request tenant
+#[derive(Clone)]
+struct RouteLocaleLayer {
+    locale: String,
+    i18n: Arc<I18n>,
+}
+
+impl Middleware for RouteLocaleLayer {
+    async fn handle(self, mut req: Request, next: Next) -> Response {
+        let tenant = req
+            .extensions()
+            .get::<Locale>()
+            .and_then(|locale| locale.tenant().map(String::from));
+
+        let mut locale = Locale::new(self.locale, self.i18n);
+        if let Some(tenant) = tenant {
+            locale = locale.with_tenant(tenant);
+        }
+        req.extensions_mut().insert(locale);
+        next.run(req).await
+    }
+}
+
+impl<S> Layer<S> for RouteLocaleLayer {
+    type Service = MiddlewareService<Self, S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{t, TranslationCatalog};
+    use axum::{body::Body, extract::Path, routing::get};
+    use tower::ServiceExt;
+
+    fn i18n() -> Arc<I18n> {
+        let en = TranslationCatalog::new("en")
+            .load_json(r#"{"title": "Product {{id}}"}"#)
+            .unwrap();
+        let de = TranslationCatalog::new("de")
+            .load_json(r#"{"title": "Produkt {{id}}", "routes": {"products": "produkte", "reviews": "bewertungen"}}"#)
+            .unwrap();
+        Arc::new(I18n::new("en").add_catalog(en).add_catalog(de))
+    }
+
+    async fn product(locale: Locale, routes: LocalizedRoutes, Path(id): Path<String>) -> String {
+        let other = if locale.as_str() == "de" { "en" } else { "de" };
+        format!(
+            "{} {}",
+            t!(locale, "title", id = id),
+            routes.url("product", other, &[("id", &id)]).unwrap()
+        )
+    }
+
+    fn router() -> LocalizedRouter {
+        LocalizedRouter::new(i18n())
+            .route("home", "/", get(|| async { "home" }))
+            .route("product", "/products/{id}", get(product))
+            .route(
+                "reviews",
+                "/products/{id}/reviews",
+                get(|| async { "reviews" }),
+            )
+    }
+
+    async fn get_body(app: Router, uri: &str) -> (StatusCode, String) {
+        let response = app
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_localized_routes() {
+        let app = router().into_router();
+
+        let (_, body) = get_body(app.clone(), "/de/produkte/7").await;
+        assert_eq!(body, "Produkt 7 /en/products/7");
+        let (_, body) = get_body(app.clone(), "/en/products/7").await;
+        assert_eq!(body, "Product 7 /de/produkte/7");
+        let (_, body) = get_body(app.clone(), "/de").await;
+        assert_eq!(body, "home");
+
+        let (status, _) = get_body(app, "/de/products/7").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_without_default_prefix() {
+        let router = router().without_default_prefix();
+        let routes = router.routes();
+        assert_eq!(routes.url("home", "en", &[]).unwrap(), "/");
+        assert_eq!(
+            routes.url("product", "en", &[("id", "7")]).unwrap(),
+            "/products/7"
+        );
+
+        let (_, body) = get_body(router.into_router(), "/products/7").await;
+        assert_eq!(body, "Product 7 /de/produkte/7");
+    }
+
+    #[test]
+    fn test_url_generation() {
+        let routes = router().routes();
+
+        assert_eq!(
+            routes.url("reviews", "de", &[("id", "7")]).unwrap(),
+            "/de/produkte/7/bewertungen"
+        );
+        assert_eq!(routes.url("product", "de", &[]), None);
+        assert_eq!(routes.url("product", "fr", &[("id", "7")]), None);
+        assert_eq!(
+            routes.alternates("product", &[("id", "7")]),
+            [
+                ("de".to_string(), "/de/produkte/7".to_string()),
+                ("en".to_string(), "/en/products/7".to_string()),
+            ]
+        );
+
+        let matched = routes.match_path("/de/produkte/7").unwrap();
+        assert_eq!(matched.name, "product");
+        assert_eq!(matched.locale, "de");
+        assert_eq!(matched.params, [("id".to_string(), "7".to_string())]);
+        assert_eq!(routes.match_path("/de/products/7"), None);
+    }
+
+    #[test]
+    fn test_hreflang() {
+        let routes = router().routes();
+
+        assert_eq!(
+            routes.hreflang("product", &[("id", "7")], "https://shop.example/"),
+            concat!(
+                "<link rel=\"alternate\" hreflang=\"de\" href=\"https://shop.example/de/produkte/7\" />\n",
+                "<link rel=\"alternate\" hreflang=\"en\" href=\"https://shop.example/en/products/7\" />\n",
+                "<link rel=\"alternate\" hreflang=\"x-default\" href=\"https://shop.example/en/products/7\" />\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_hostile_parameters_are_encoded() {
+        let routes = router().routes();
+        let id = r#""/><script>alert(1)</script>"#;
+
+        let url = routes.url("product", "en", &[("id", id)]).unwrap();
+        assert_eq!(
+            url,
+            "/en/products/%22%2F%3E%3Cscript%3Ealert%281%29%3C%2Fscript%3E"
+        );
+        let matched = routes.match_path(&url).unwrap();
+        assert_eq!(matched.params, [("id".to_string(), id.to_string())]);
+
+        let html = routes.hreflang("product", &[("id", id)], "https://shop.example?a=1&b=\"");
+        assert!(!html.contains("<script"));
+        assert!(html.contains("href=\"https://shop.example?a=1&amp;b=&quot;/en/products/%22%2F%3E"));
+    }
+}
@@ -0,0 +1,459 @@
+//! Runtime-editable translations
+//!
+//! A [`TranslationStore`] holds translations edited at runtime, e.g. by
+//! content managers. [`I18n::with_store`](crate::I18n::with_store) layers
+//! them over the file catalogs: stored values win, everything else comes from
+//! the files. Every edit is recorded as a revision so it can be reviewed and
+//! rolled back.
+
+use crate::{I18nError, I18nResult};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+/// Current value of an edited translation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranslationRecord {
+    pub locale: String,
+    pub key: String,
+    pub value: Value,
+    /// Revision that produced this value
+    pub revision: i64,
+    pub updated_by: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One edit of a translation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranslationRevision {
+    /// Store-wide increasing revision number
+    pub id: i64,
+    pub locale: String,
+    pub key: String,
+    /// New value, `None` if the edit removed the override
+    pub value: Option<Value>,
+    pub author: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Storage for runtime-edited translations
+#[async_trait]
+pub trait TranslationStore: Send + Sync {
+    /// All current translations
+    async fn all(&self) -> I18nResult<Vec<TranslationRecord>>;
+
+    /// Current translation of a key
+    async fn get(&self, locale: &str, key: &str) -> I18nResult<Option<TranslationRecord>>;
+
+    /// Set a translation, recording a revision
+    async fn set(
+        &self,
+        locale: &str,
+        key: &str,
+        value: Value,
+        author: Option<&str>,
+    ) -> I18nResult<TranslationRevision>;
+
+    /// Remove a translation so the file catalogs apply again, recording a revision
+    async fn delete(&self, locale: &str, key: &str, author: Option<&str>) -> I18nResult<TranslationRevision>;
+
+    /// Revisions of a key, newest first
+    async fn history(&self, locale: &str, key: &str) -> I18nResult<Vec<TranslationRevision>>;
+
+    /// Latest revision number, 0 if nothing was edited yet
+    ///
+    /// Used to detect changes made by other instances.
+    async fn version(&self) -> I18nResult<i64>;
+
+    /// Restore the value a key had after `revision`, recording a new revision
+    async fn rollback(
+        &self,
+        locale: &str,
+        key: &str,
+        revision: i64,
+        author: Option<&str>,
+    ) -> I18nResult<TranslationRevision> {
+        let target = self
+            .history(locale, key)
+            .await?
+            .into_iter()
+            .find(|r| r.id == revision)
+            .ok_or_else(|| {
+                I18nError::StoreError(format!(
+                    "no revision {} of {} in locale {}",
+                    revision, key, locale
+                ))
+            })?;
+
+        match target.value {
+            Some(value) => self.set(locale, key, value, author).await,
+            None => self.delete(locale, key, author).await,
+        }
+    }
+}
+
+#[derive(Default)]
+struct MemoryState {
+    current: HashMap<(String, String), TranslationRecord>,
+    revisions: Vec<TranslationRevision>,
+}
+
+impl MemoryState {
+    fn record(&mut self, locale: &str, key: &str, value: Option<Value>, author: Option<&str>) -> TranslationRevision {
+        let revision = TranslationRevision {
+            id: self.revisions.len() as i64 + 1,
+            locale: locale.to_string(),
+            key: key.to_string(),
+            value,
+            author: author.map(String::from),
+            created_at: Utc::now(),
+        };
+        self.revisions.push(revision.clone());
+        revision
+    }
+}
+
+/// In-memory translation store for development and tests
+#[derive(Default)]
+pub struct MemoryTranslationStore {
+    state: RwLock<MemoryState>,
+}
+
+impl MemoryTranslationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TranslationStore for MemoryTranslationStore {
+    async fn all(&self) -> I18nResult<Vec<TranslationRecord>> {
+        Ok(self.state.read().await.current.values().cloned().collect())
+    }
+
+    async fn get(&self, locale: &str, key: &str) -> I18nResult<Option<TranslationRecord>> {
+        let state = self.state.read().await;
+        Ok(state.current.get(&(locale.to_string(), key.to_string())).cloned())
+    }
+
+    async fn set(
+        &self,
+        locale: &str,
+        key: &str,
+        value: Value,
+        author: Option<&str>,
+    ) -> I18nResult<TranslationRevision> {
+        let mut state = self.state.write().await;
+        let revision = state.record(locale, key, Some(value.clone()), author);
+
+        state.current.insert(
+            (locale.to_string(), key.to_string()),
+            TranslationRecord {
+                locale: locale.to_string(),
+                key: key.to_string(),
+                value,
+                revision: revision.id,
+                updated_by: revision.author.clone(),
+                updated_at: revision.created_at,
+            },
+        );
+        Ok(revision)
+    }
+
+    async fn delete(&self, locale: &str, key: &str, author: Option<&str>) -> I18nResult<TranslationRevision> {
+        let mut state = self.state.write().await;
+        state.current.remove(&(locale.to_string(), key.to_string()));
+        Ok(state.record(locale, key, None, author))
+    }
+
+    async fn history(&self, locale: &str, key: &str) -> I18nResult<Vec<TranslationRevision>> {
+        let state = self.state.read().await;
+        Ok(state
+            .revisions
+            .iter()
+            .rev()
+            .filter(|r| r.locale == locale && r.key == key)
+            .cloned()
+            .collect())
+    }
+
+    async fn version(&self) -> I18nResult<i64> {
+        Ok(self.state.read().await.revisions.len() as i64)
+    }
+}
+
+#[cfg(feature = "database")]
+pub use self::sql::SqlTranslationStore;
+
+#[cfg(feature = "database")]
+mod sql {
+    use super::*;
+    use sqlx::{PgPool, Row};
+
+    /// PostgreSQL translation store
+    ///
+    /// Uses the `translations` and `translation_revisions` tables, created by
+    /// [`SqlTranslationStore::migrate`]. Values are stored as JSON text.
+    pub struct SqlTranslationStore {
+        pool: PgPool,
+    }
+
+    impl SqlTranslationStore {
+        pub fn new(pool: PgPool) -> Self {
+            Self { pool }
+        }
+
+        /// Create the tables if they don't exist
+        pub async fn migrate(&self) -> I18nResult<()> {
+            for statement in [
+                "CREATE TABLE IF NOT EXISTS translations (
+                    locale TEXT NOT NULL,
+                    key TEXT NOT NULL,
+                    value TEXT NOT NULL,
+                    revision BIGINT NOT NULL,
+                    updated_by TEXT,
+                    updated_at TIMESTAMPTZ NOT NULL,
+                    PRIMARY KEY (locale, key)
+                )",
+                "CREATE TABLE IF NOT EXISTS translation_revisions (
+                    id BIGSERIAL PRIMARY KEY,
+                    locale TEXT NOT NULL,
+                    key TEXT NOT NULL,
+                    value TEXT,
+                    author TEXT,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                )",
+                "CREATE INDEX IF NOT EXISTS translation_revisions_key
+                    ON translation_revisions (locale, key, id)",
+            ] {
+                sqlx::query(statement)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(store_error)?;
+            }
+            Ok(())
+        }
+
+        async fn record(
+            &self,
+            tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+            locale: &str,
+            key: &str,
+            value: Option<&Value>,
+            author: Option<&str>,
+        ) -> I18nResult<TranslationRevision> {
+            let row = sqlx::query(
+                "INSERT INTO translation_revisions (locale, key, value, author)
+                 VALUES ($1, $2, $3, $4)
+                 RETURNING id, created_at",
+            )
+            .bind(locale)
+            .bind(key)
+            .bind(value.map(Value::to_string))
+            .bind(author)
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(store_error)?;
+
+            Ok(TranslationRevision {
+                id: row.get("id"),
+                locale: locale.to_string(),
+                key: key.to_string(),
+                value: value.cloned(),
+                author: author.map(String::from),
+                created_at: row.get("created_at"),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl TranslationStore for SqlTranslationStore {
+        async fn all(&self) -> I18nResult<Vec<TranslationRecord>> {
+            let rows = sqlx::query(
+                "SELECT locale, key, value, revision, updated_by, updated_at FROM translations",
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(store_error)?;
+
+            rows.iter().map(to_record).collect()
+        }
+
+        async fn get(&self, locale: &str, key: &str) -> I18nResult<Option<TranslationRecord>> {
+            let row = sqlx::query(
+                "SELECT locale, key, value, revision, updated_by, updated_at
+                 FROM translations WHERE locale = $1 AND key = $2",
+            )
+            .bind(locale)
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(store_error)?;
+
+            row.as_ref().map(to_record).transpose()
+        }
+
+        async fn set(
+            &self,
+            locale: &str,
+            key: &str,
+            value: Value,
+            author: Option<&str>,
+        ) -> I18nResult<TranslationRevision> {
+            let mut tx = self.pool.begin().await.map_err(store_error)?;
+            let revision = self.record(&mut tx, locale, key, Some(&value), author).await?;
+
+            sqlx::query(
+                "INSERT INTO translations (locale, key, value, revision, updated_by, updated_at)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (locale, key) DO UPDATE SET
+                    value = EXCLUDED.value,
+                    revision = EXCLUDED.revision,
+                    updated_by = EXCLUDED.updated_by,
+                    updated_at = EXCLUDED.updated_at",
+            )
+            .bind(locale)
+            .bind(key)
+            .bind(value.to_string())
+            .bind(revision.id)
+            .bind(author)
+            .bind(revision.created_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(store_error)?;
+
+            tx.commit().await.map_err(store_error)?;
+            Ok(revision)
+        }
+
+        async fn delete(&self, locale: &str, key: &str, author: Option<&str>) -> I18nResult<TranslationRevision> {
+            let mut tx = self.pool.begin().await.map_err(store_error)?;
+            let revision = self.record(&mut tx, locale, key, None, author).await?;
+
+            sqlx::query("DELETE FROM translations WHERE locale = $1 AND key = $2")
+                .bind(locale)
+                .bind(key)
+                .execute(&mut *tx)
+                .await
+                .map_err(store_error)?;
+
+            tx.commit().await.map_err(store_error)?;
+            Ok(revision)
+        }
+
+        async fn history(&self, locale: &str, key: &str) -> I18nResult<Vec<TranslationRevision>> {
+            let rows = sqlx::query(
+                "SELECT id, locale, key, value, author, created_at FROM translation_revisions
+                 WHERE locale = $1 AND key = $2 ORDER BY id DESC",
+            )
+            .bind(locale)
+            .bind(key)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(store_error)?;
+
+            rows.iter()
+                .map(|row| {
+                    let value: Option<String> = row.get("value");
+                    Ok(TranslationRevision {
+                        id: row.get("id"),
+                        locale: row.get("locale"),
+                        key: row.get("key"),
+                        value: value.as_deref().map(parse_value).transpose()?,
+                        author: row.get("author"),
+                        created_at: row.get("created_at"),
+                    })
+                })
+                .collect()
+        }
+
+        async fn version(&self) -> I18nResult<i64> {
+            let row = sqlx::query("SELECT COALESCE(MAX(id), 0) AS version FROM translation_revisions")
+                .fetch_one(&self.pool)
+                .await
+                .map_err(store_error)?;
+            Ok(row.get("version"))
+        }
+    }
+
+    fn to_record(row: &sqlx::postgres::PgRow) -> I18nResult<TranslationRecord> {
+        let value: String = row.get("value");
+        Ok(TranslationRecord {
+            locale: row.get("locale"),
+            key: row.get("key"),
+            value: parse_value(&value)?,
+            revision: row.get("revision"),
+            updated_by: row.get("updated_by"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    fn parse_value(value: &str) -> I18nResult<Value> {
+        serde_json::from_str(value).map_err(|e| I18nError::StoreError(e.to_string()))
+    }
+
+    fn store_error(e: sqlx::Error) -> I18nError {
+        I18nError::StoreError(e.to_string())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[tokio::test]
+        #[ignore] // Requires PostgreSQL
+        async fn test_sql_store() {
+            let pool = PgPool::connect("postgres://localhost/rustforge_test").await.unwrap();
+            let store = SqlTranslationStore::new(pool);
+            store.migrate().await.unwrap();
+
+            let first = store
+                .set("en", "test.greeting", Value::from("Hi"), Some("editor"))
+                .await
+                .unwrap();
+            store.set("en", "test.greeting", Value::from("Hello"), None).await.unwrap();
+            assert_eq!(store.get("en", "test.greeting").await.unwrap().unwrap().value, "Hello");
+
+            store.rollback("en", "test.greeting", first.id, None).await.unwrap();
+            assert_eq!(store.get("en", "test.greeting").await.unwrap().unwrap().value, "Hi");
+
+            store.delete("en", "test.greeting", None).await.unwrap();
+            assert!(store.get("en", "test.greeting").await.unwrap().is_none());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_revisions_and_rollback() {
+        let store = MemoryTranslationStore::new();
+
+        let first = store
+            .set("en", "welcome", Value::from("Hi"), Some("anna"))
+            .await
+            .unwrap();
+        store.set("en", "welcome", Value::from("Hello"), Some("ben")).await.unwrap();
+        store.delete("en", "welcome", Some("ben")).await.unwrap();
+        assert!(store.get("en", "welcome").await.unwrap().is_none());
+
+        let history = store.history("en", "welcome").await.unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].value, None);
+        assert_eq!(history[2].author.as_deref(), Some("anna"));
+
+        let restored = store.rollback("en", "welcome", first.id, Some("carl")).await.unwrap();
+        assert_eq!(restored.id, 4);
+        let record = store.get("en", "welcome").await.unwrap().unwrap();
+        assert_eq!(record.value, "Hi");
+        assert_eq!(record.updated_by.as_deref(), Some("carl"));
+        assert_eq!(store.version().await.unwrap(), 4);
+
+        assert!(store.rollback("en", "welcome", 99, None).await.is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! Per-tenant translation overrides
+//!
+//! Tenants can rename things without forking the catalogs: an override set
+//! for a tenant wins over the regular translation of its locale, and every
+//! key it does not override resolves as usual. Overrides are kept in the
+//! [`TranslationStore`](crate::TranslationStore) under a tenant-scoped locale
+//! (`tenant:{id}:{locale}`), so they share its revisions and rollback.
+
+use crate::{I18n, I18nResult, TranslationRecord, TranslationRevision};
+use serde_json::Value;
+
+const PREFIX: &str = "tenant:";
+
+/// Store locale holding a tenant's overrides for `locale`
+pub(crate) fn scoped_locale(tenant: &str, locale: &str) -> String {
+    format!("{}{}:{}", PREFIX, tenant, locale)
+}
+
+/// Tenant and locale of a tenant-scoped store locale
+fn split_scoped(scoped: &str) -> Option<(&str, &str)> {
+    scoped.strip_prefix(PREFIX)?.rsplit_once(':')
+}
+
+/// Translation overrides of one tenant, see [`I18n::tenant`]
+pub struct TenantOverrides<'a> {
+    i18n: &'a I18n,
+    tenant: &'a str,
+}
+
+impl<'a> TenantOverrides<'a> {
+    pub(crate) fn new(i18n: &'a I18n, tenant: &'a str) -> Self {
+        Self { i18n, tenant }
+    }
+
+    /// Tenant the overrides belong to
+    pub fn id(&self) -> &str {
+        self.tenant
+    }
+
+    /// Override a translation for this tenant and apply it immediately
+    pub async fn set(
+        &self,
+        locale: &str,
+        key: &str,
+        value: Value,
+        author: Option<&str>,
+    ) -> I18nResult<TranslationRevision> {
+        let revision = self
+            .i18n
+            .update_translation(&scoped_locale(self.tenant, locale), key, value, author)
+            .await?;
+        Ok(self.unscoped_revision(revision))
+    }
+
+    /// Remove an override, falling back to the regular translation
+    pub async fn remove(
+        &self,
+        locale: &str,
+        key: &str,
+        author: Option<&str>,
+    ) -> I18nResult<TranslationRevision> {
+        let revision = self
+            .i18n
+            .remove_translation(&scoped_locale(self.tenant, locale), key, author)
+            .await?;
+        Ok(self.unscoped_revision(revision))
+    }
+
+    /// Current overrides, optionally limited to one locale
+    ///
+    /// Records carry the plain locale, not the scoped store locale.
+    pub async fn list(&self, locale: Option<&str>) -> I18nResult<Vec<TranslationRecord>> {
+        let mut records: Vec<TranslationRecord> = self
+            .i18n
+            .require_store()?
+            .all()
+            .await?
+            .into_iter()
+            .filter_map(|mut record| {
+                let (tenant, plain) = split_scoped(&record.locale)?;
+                if tenant != self.tenant || locale.is_some_and(|l| l != plain) {
+                    return None;
+                }
+                record.locale = plain.to_string();
+                Some(record)
+            })
+            .collect();
+
+        records.sort_by(|a, b| (&a.locale, &a.key).cmp(&(&b.locale, &b.key)));
+        Ok(records)
+    }
+
+    /// Edits of an override, newest first
+    pub async fn history(&self, locale: &str, key: &str) -> I18nResult<Vec<TranslationRevision>> {
+        let history = self
+            .i18n
+            .require_store()?
+            .history(&scoped_locale(self.tenant, locale), key)
+            .await?;
+        Ok(history
+            .into_iter()
+            .map(|r| self.unscoped_revision(r))
+            .collect())
+    }
+
+    /// Restore an override to the value it had after `revision`
+    pub async fn rollback(
+        &self,
+        locale: &str,
+        key: &str,
+        revision: i64,
+        author: Option<&str>,
+    ) -> I18nResult<TranslationRevision> {
+        let revision = self
+            .i18n
+            .rollback_translation(&scoped_locale(self.tenant, locale), key, revision, author)
+            .await?;
+        Ok(self.unscoped_revision(revision))
+    }
+
+    fn unscoped_revision(&self, mut revision: TranslationRevision) -> TranslationRevision {
+        if let Some((_, plain)) = split_scoped(&revision.locale) {
+            revision.locale = plain.to_string();
+        }
+        revision
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryTranslationStore, TranslationCatalog};
+    use std::sync::Arc;
+
+    fn i18n() -> I18n {
+        let en = TranslationCatalog::new("en")
+            .load_json(r#"{"ticket": "Ticket", "tickets": {"one": "{{count}} ticket", "other": "{{count}} tickets"}, "home": "Home"}"#)
+            .unwrap();
+        let de = TranslationCatalog::new("de")
+            .load_json(r#"{"ticket": "Ticket"}"#)
+            .unwrap();
+        I18n::new("en")
+            .add_catalog(en)
+            .add_catalog(de)
+            .with_store(Arc::new(MemoryTranslationStore::new()))
+    }
+
+    #[test]
+    fn test_scoped_locale() {
+        assert_eq!(scoped_locale("acme", "de-CH"), "tenant:acme:de-CH");
+        assert_eq!(split_scoped("tenant:acme:de-CH"), Some(("acme", "de-CH")));
+        assert_eq!(split_scoped("tenant:a:b:en"), Some(("a:b", "en")));
+        assert_eq!(split_scoped("de-CH"), None);
+    }
+
+    #[tokio::test]
+    async fn test_resolution_order() {
+        let i18n = i18n();
+        let acme = i18n.tenant("acme");
+        acme.set("en", "ticket", "Case".into(), None).await.unwrap();
+        acme.set("en", "tickets.other", "{{count}} cases".into(), None)
+            .await
+            .unwrap();
+        acme.set("de", "ticket", "Fall".into(), None).await.unwrap();
+        i18n.update_translation("en", "home", "Start".into(), None)
+            .await
+            .unwrap();
+
+        // Tenant override wins, everything else resolves as usual
+        assert_eq!(
+            i18n.translate_for_tenant("acme", "en", "ticket", None)
+                .unwrap(),
+            "Case"
+        );
+        assert_eq!(
+            i18n.translate_for_tenant("acme", "en", "home", None)
+                .unwrap(),
+            "Start"
+        );
+        assert_eq!(
+            i18n.translate_for_tenant("acme", "de", "ticket", None)
+                .unwrap(),
+            "Fall"
+        );
+        assert_eq!(
+            i18n.translate_plural_for_tenant("acme", "en", "tickets", 3)
+                .unwrap(),
+            "3 cases"
+        );
+        assert_eq!(
+            i18n.translate_plural_for_tenant("acme", "en", "tickets", 1)
+                .unwrap(),
+            "1 ticket"
+        );
+
+        // The fallback locale's tenant override comes before its catalog
+        assert_eq!(
+            i18n.translate_for_tenant("acme", "fr", "ticket", None)
+                .unwrap(),
+            "Case"
+        );
+
+        // Other tenants and untenanted lookups are unaffected
+        assert_eq!(
+            i18n.translate_for_tenant("globex", "en", "ticket", None)
+                .unwrap(),
+            "Ticket"
+        );
+        assert_eq!(i18n.translate("en", "ticket", None).unwrap(), "Ticket");
+        assert_eq!(i18n.available_locales().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_manage_overrides() {
+        let i18n = i18n();
+        let acme = i18n.tenant("acme");
+
+        let first = acme
+            .set("en", "ticket", "Case".into(), Some("ann"))
+            .await
+            .unwrap();
+        assert_eq!(first.locale, "en");
+        acme.set("en", "ticket", "Issue".into(), Some("bob"))
+            .await
+            .unwrap();
+        acme.set("de", "ticket", "Fall".into(), None).await.unwrap();
+        i18n.tenant("globex")
+            .set("en", "ticket", "Order".into(), None)
+            .await
+            .unwrap();
+
+        let listed = acme.list(None).await.unwrap();
+        let listed: Vec<_> = listed
+            .iter()
+            .map(|r| (r.locale.as_str(), r.value.clone()))
+            .collect();
+        assert_eq!(
+            listed,
+            [("de", Value::from("Fall")), ("en", Value::from("Issue"))]
+        );
+        assert_eq!(acme.list(Some("en")).await.unwrap().len(), 1);
+
+        let history = acme.history("en", "ticket").await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].author.as_deref(), Some("bob"));
+
+        acme.rollback("en", "ticket", first.id, None).await.unwrap();
+        assert_eq!(
+            i18n.translate_for_tenant("acme", "en", "ticket", None)
+                .unwrap(),
+            "Case"
+        );
+
+        acme.remove("en", "ticket", None).await.unwrap();
+        assert_eq!(
+            i18n.translate_for_tenant("acme", "en", "ticket", None)
+                .unwrap(),
+            "Ticket"
+        );
+        assert_eq!(
+            i18n.translate_for_tenant("globex", "en", "ticket", None)
+                .unwrap(),
+            "Order"
+        );
+    }
+}
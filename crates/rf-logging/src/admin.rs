@@ -0,0 +1,183 @@
+//! Token-protected REST API for changing log levels
+
+use crate::{LevelSnapshot, LogLevels, LoggingError};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, put},
+    Json, Router,
+};
+use rf_middleware::BearerTokenLayer;
+use serde::Deserialize;
+
+impl IntoResponse for LoggingError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            LoggingError::InvalidLevel(_) | LoggingError::InvalidModule(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            LoggingError::Channel { .. } | LoggingError::Subscriber(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+        let body = serde_json::json!({ "error": self.to_string() });
+        (status, Json(body)).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LevelBody {
+    level: String,
+}
+
+/// Build the log level router
+///
+/// Every route requires `Authorization: Bearer <token>`. Changes apply
+/// immediately and last until the process restarts.
+///
+/// | Method | Path | Action |
+/// |--------|------|--------|
+/// | GET | `/levels` | show the default level and module overrides |
+/// | PUT | `/levels` | set the default level |
+/// | PUT | `/levels/{module}` | set the level of a module |
+/// | DELETE | `/levels/{module}` | remove the override of a module |
+///
+/// # Panics
+///
+/// Panics if `token` is empty.
+///
+/// # Example
+///
+/// ```ignore
+/// let logging = rf_logging::init(&config)?;
+/// let app = Router::new().nest("/admin/logging", log_level_router(logging.levels().clone(), "secret-token"));
+/// ```
+pub fn log_level_router(levels: LogLevels, token: impl Into<String>) -> Router {
+    Router::new()
+        .route("/levels", get(show_levels).put(set_default))
+        .route("/levels/{module}", put(set_module).delete(remove_module))
+        .layer(BearerTokenLayer::new(token))
+        .with_state(levels)
+}
+
+async fn show_levels(State(levels): State<LogLevels>) -> Json<LevelSnapshot> {
+    Json(levels.snapshot())
+}
+
+async fn set_default(
+    State(levels): State<LogLevels>,
+    Json(body): Json<LevelBody>,
+) -> Result<Json<LevelSnapshot>, LoggingError> {
+    levels.set_default(&body.level)?;
+    tracing::info!(level = %body.level, "Default log level changed");
+    Ok(Json(levels.snapshot()))
+}
+
+async fn set_module(
+    State(levels): State<LogLevels>,
+    Path(module): Path<String>,
+    Json(body): Json<LevelBody>,
+) -> Result<Json<LevelSnapshot>, LoggingError> {
+    levels.set(&module, &body.level)?;
+    tracing::info!(module = %module, level = %body.level, "Module log level changed");
+    Ok(Json(levels.snapshot()))
+}
+
+async fn remove_module(
+    State(levels): State<LogLevels>,
+    Path(module): Path<String>,
+) -> Result<StatusCode, LoggingError> {
+    if levels.remove(&module)? {
+        tracing::info!(module = %module, "Module log level override removed");
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Ok(StatusCode::NOT_FOUND)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::Request, http::header};
+    use std::collections::BTreeMap;
+    use tower::ServiceExt;
+
+    fn request(method: &str, uri: &str, token: &str, body: Option<&str>) -> Request {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(
+                body.map(|body| Body::from(body.to_string()))
+                    .unwrap_or_default(),
+            )
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_level_admin_api() {
+        let (_layer, levels) = LogLevels::new("info".to_string(), BTreeMap::new()).unwrap();
+        let app = log_level_router(levels.clone(), "secret");
+
+        let response = app
+            .clone()
+            .oneshot(request("GET", "/levels", "wrong", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                "/levels/app::billing",
+                "secret",
+                Some(r#"{"level":"debug"}"#),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(levels.snapshot().modules["app::billing"], "debug");
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "PUT",
+                "/levels",
+                "secret",
+                Some(r#"{"level":"chatty"}"#),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = app
+            .clone()
+            .oneshot(request("GET", "/levels", "secret", None))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let snapshot: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            snapshot,
+            serde_json::json!({ "default": "info", "modules": { "app::billing": "debug" } })
+        );
+
+        let response = app
+            .clone()
+            .oneshot(request("DELETE", "/levels/app::billing", "secret", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = app
+            .oneshot(request("DELETE", "/levels/app::billing", "secret", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,142 @@
+//! Layers writing to the configured channels
+
+use crate::config::{ChannelConfig, LogFormat, Rotation};
+use crate::error::{LoggingError, LoggingResult};
+use crate::format::{EventFormat, SpanFields};
+use crate::levels::FilterLayer;
+use crate::redact::Redactor;
+use crate::syslog::SyslogWriter;
+use tracing_appender::rolling::{self, RollingFileAppender};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::Layered;
+use tracing_subscriber::{fmt, Layer, Registry};
+
+/// Subscriber the channel layers are added to
+pub(crate) type Base = Layered<FilterLayer, Registry>;
+
+pub(crate) type BoxedLayer = Box<dyn Layer<Base> + Send + Sync>;
+
+/// The layer writing events to `channel`
+pub(crate) fn layer(
+    channel: &ChannelConfig,
+    redactor: &Redactor,
+    span_events: FmtSpan,
+) -> LoggingResult<BoxedLayer> {
+    let level = match channel.level() {
+        Some(level) => level
+            .parse::<LevelFilter>()
+            .map_err(|_| LoggingError::InvalidLevel(level.to_string()))?,
+        None => LevelFilter::TRACE,
+    };
+    let text = |format: LogFormat, writer: Writer, timestamps: bool| {
+        let ansi = matches!(writer, Writer::Stderr | Writer::Stdout) && format != LogFormat::Json;
+        formatted(format, writer, redactor, span_events.clone(), timestamps, ansi)
+    };
+
+    let layer = match channel {
+        ChannelConfig::Stderr { format, .. } => text(*format, Writer::Stderr, true),
+        ChannelConfig::Stdout { format, .. } => text(*format, Writer::Stdout, true),
+        ChannelConfig::File {
+            path,
+            prefix,
+            rotation,
+            max_files,
+            format,
+            ..
+        } => {
+            let rotation = match rotation {
+                Rotation::Minutely => rolling::Rotation::MINUTELY,
+                Rotation::Hourly => rolling::Rotation::HOURLY,
+                Rotation::Daily => rolling::Rotation::DAILY,
+                Rotation::Never => rolling::Rotation::NEVER,
+            };
+            let mut builder = RollingFileAppender::builder()
+                .rotation(rotation)
+                .filename_prefix(prefix);
+            if let Some(max_files) = max_files {
+                builder = builder.max_log_files(*max_files);
+            }
+            let appender = builder
+                .build(path)
+                .map_err(|e| LoggingError::channel("file", e))?;
+            text(*format, Writer::File(appender), true)
+        }
+        ChannelConfig::Syslog {
+            address, app_name, ..
+        } => {
+            let writer = SyslogWriter::connect(address.as_deref(), app_name.as_deref())?;
+            text(LogFormat::Compact, Writer::Syslog(writer), false)
+        }
+        #[cfg(feature = "otlp")]
+        ChannelConfig::Otlp {
+            endpoint,
+            service_name,
+            ..
+        } => {
+            let layer =
+                crate::otlp::OtlpLayer::new(endpoint, service_name.as_deref(), redactor.clone())?;
+            Box::new(layer)
+        }
+        #[cfg(not(feature = "otlp"))]
+        ChannelConfig::Otlp { .. } => {
+            return Err(LoggingError::channel(
+                "otlp",
+                "rf-logging was built without the `otlp` feature",
+            ))
+        }
+    };
+    Ok(Box::new(layer.with_filter(level)))
+}
+
+fn formatted(
+    format: LogFormat,
+    writer: Writer,
+    redactor: &Redactor,
+    span_events: FmtSpan,
+    timestamps: bool,
+    ansi: bool,
+) -> BoxedLayer {
+    let layer = fmt::layer()
+        .with_span_events(span_events)
+        .event_format(EventFormat {
+            format,
+            redactor: redactor.clone(),
+            timestamps,
+        })
+        .fmt_fields(SpanFields {
+            redactor: redactor.clone(),
+        })
+        .with_ansi(ansi)
+        .with_writer(writer);
+    Box::new(layer)
+}
+
+/// The destinations of formatted events
+enum Writer {
+    Stderr,
+    Stdout,
+    File(RollingFileAppender),
+    Syslog(SyslogWriter),
+}
+
+impl<'a> MakeWriter<'a> for Writer {
+    type Writer = Box<dyn std::io::Write + 'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        match self {
+            Writer::Stderr => Box::new(std::io::stderr()),
+            Writer::Stdout => Box::new(std::io::stdout()),
+            Writer::File(appender) => Box::new(appender.make_writer()),
+            Writer::Syslog(writer) => Box::new(writer.make_writer()),
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &tracing::Metadata<'_>) -> Self::Writer {
+        match self {
+            Writer::Syslog(writer) => Box::new(writer.make_writer_for(meta)),
+            _ => self.make_writer(),
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Logging configuration
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Field names redacted by default, matched case-insensitively
+pub const DEFAULT_REDACTED_FIELDS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "api_key",
+    "apikey",
+    "authorization",
+    "cookie",
+    "credit_card",
+    "card_number",
+    "cvv",
+    "ssn",
+    "private_key",
+];
+
+/// Logging configuration
+///
+/// Without `channels`, events go to stdout in `format` as before.
+///
+/// ```
+/// use rf_logging::LogConfig;
+///
+/// let config: LogConfig = serde_json::from_value(serde_json::json!({
+///     "level": "info",
+///     "channels": [
+///         { "driver": "stderr", "format": "pretty" },
+///         { "driver": "file", "path": "storage/logs", "rotation": "daily", "max_files": 14 },
+///         { "driver": "syslog", "level": "warn" }
+///     ],
+///     "levels": { "sqlx": "warn", "app::billing": "debug" }
+/// }))
+/// .unwrap();
+/// assert_eq!(config.channels.len(), 3);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Log level (trace, debug, info, warn, error)
+    pub level: String,
+
+    /// Output format
+    pub format: LogFormat,
+
+    /// Log to stdout
+    pub stdout: bool,
+
+    /// Optional file output
+    pub file: Option<PathBuf>,
+
+    /// Directory for log files (if file logging enabled)
+    pub log_dir: Option<PathBuf>,
+
+    /// Enable request ID tracking
+    pub request_id: bool,
+
+    /// Enable performance timing
+    pub timing: bool,
+
+    /// Where events are written; replaces `stdout`, `file` and `log_dir`
+    pub channels: Vec<ChannelConfig>,
+
+    /// Level per module path, e.g. `sqlx = "warn"`, changeable at runtime
+    /// through [`LogLevels`](crate::LogLevels)
+    pub levels: BTreeMap<String, String>,
+
+    /// Names of fields whose values are replaced by `[REDACTED]`
+    pub redact: Vec<String>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::Pretty,
+            stdout: true,
+            file: None,
+            log_dir: None,
+            request_id: true,
+            timing: true,
+            channels: Vec::new(),
+            levels: BTreeMap::new(),
+            redact: DEFAULT_REDACTED_FIELDS
+                .iter()
+                .map(|field| field.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl LogConfig {
+    /// The configured channels, or those described by `stdout`, `file` and
+    /// `log_dir` if there are none
+    pub fn effective_channels(&self) -> Vec<ChannelConfig> {
+        if !self.channels.is_empty() {
+            return self.channels.clone();
+        }
+
+        let mut channels = Vec::new();
+        if self.stdout {
+            channels.push(ChannelConfig::Stdout {
+                format: self.format,
+                level: None,
+            });
+        }
+        if let Some(file) = &self.file {
+            let dir = file
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map(PathBuf::from)
+                .or_else(|| self.log_dir.clone())
+                .unwrap_or_else(|| PathBuf::from("."));
+            let prefix = file
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(default_prefix);
+            channels.push(ChannelConfig::File {
+                path: dir,
+                prefix,
+                rotation: Rotation::Never,
+                max_files: None,
+                format: self.format,
+                level: None,
+            });
+        }
+        channels
+    }
+}
+
+/// Log output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// JSON format for production
+    Json,
+    /// Pretty format for development
+    Pretty,
+    /// Compact format
+    Compact,
+}
+
+/// A destination for log events, selected by `driver`
+///
+/// `level` further restricts the events of a channel; it can't let through
+/// events the global and module levels filter out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "driver", rename_all = "lowercase")]
+pub enum ChannelConfig {
+    Stderr {
+        #[serde(default = "pretty")]
+        format: LogFormat,
+        #[serde(default)]
+        level: Option<String>,
+    },
+    Stdout {
+        #[serde(default = "pretty")]
+        format: LogFormat,
+        #[serde(default)]
+        level: Option<String>,
+    },
+    /// Files in `path` named `<prefix>.<date>` for rotated files
+    File {
+        path: PathBuf,
+        #[serde(default = "default_prefix")]
+        prefix: String,
+        #[serde(default)]
+        rotation: Rotation,
+        /// Delete the oldest files beyond this many
+        #[serde(default)]
+        max_files: Option<usize>,
+        #[serde(default = "json")]
+        format: LogFormat,
+        #[serde(default)]
+        level: Option<String>,
+    },
+    /// RFC 5424 messages over UDP to `address`, or to the local
+    /// `/dev/log` socket without one
+    Syslog {
+        #[serde(default)]
+        address: Option<String>,
+        #[serde(default)]
+        app_name: Option<String>,
+        #[serde(default)]
+        level: Option<String>,
+    },
+    /// Log records exported over OTLP/gRPC (feature `otlp`)
+    Otlp {
+        #[serde(default = "default_otlp_endpoint")]
+        endpoint: String,
+        #[serde(default)]
+        service_name: Option<String>,
+        #[serde(default)]
+        level: Option<String>,
+    },
+}
+
+impl ChannelConfig {
+    pub fn driver(&self) -> &'static str {
+        match self {
+            ChannelConfig::Stderr { .. } => "stderr",
+            ChannelConfig::Stdout { .. } => "stdout",
+            ChannelConfig::File { .. } => "file",
+            ChannelConfig::Syslog { .. } => "syslog",
+            ChannelConfig::Otlp { .. } => "otlp",
+        }
+    }
+
+    pub fn level(&self) -> Option<&str> {
+        match self {
+            ChannelConfig::Stderr { level, .. }
+            | ChannelConfig::Stdout { level, .. }
+            | ChannelConfig::File { level, .. }
+            | ChannelConfig::Syslog { level, .. }
+            | ChannelConfig::Otlp { level, .. } => level.as_deref(),
+        }
+    }
+}
+
+/// How often log files are rotated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+fn pretty() -> LogFormat {
+    LogFormat::Pretty
+}
+
+fn json() -> LogFormat {
+    LogFormat::Json
+}
+
+fn default_prefix() -> String {
+    "app.log".to_string()
+}
+
+fn default_otlp_endpoint() -> String {
+    "http://localhost:4317".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_defaults() {
+        let channel: ChannelConfig =
+            serde_json::from_str(r#"{ "driver": "file", "path": "storage/logs" }"#).unwrap();
+        match channel {
+            ChannelConfig::File {
+                prefix,
+                rotation,
+                format,
+                max_files,
+                ..
+            } => {
+                assert_eq!(prefix, "app.log");
+                assert_eq!(rotation, Rotation::Daily);
+                assert_eq!(format, LogFormat::Json);
+                assert_eq!(max_files, None);
+            }
+            other => panic!("expected a file channel, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_effective_channels_from_legacy_fields() {
+        let config = LogConfig {
+            format: LogFormat::Compact,
+            file: Some(PathBuf::from("/var/log/app.log")),
+            ..Default::default()
+        };
+        let channels = config.effective_channels();
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].driver(), "stdout");
+        assert!(matches!(
+            &channels[1],
+            ChannelConfig::File { path, prefix, rotation: Rotation::Never, .. }
+                if path == &PathBuf::from("/var/log") && prefix == "app.log"
+        ));
+    }
+}
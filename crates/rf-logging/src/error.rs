@@ -0,0 +1,30 @@
+//! Logging errors
+
+use thiserror::Error;
+
+/// Result type for logging setup
+pub type LoggingResult<T> = Result<T, LoggingError>;
+
+#[derive(Debug, Error)]
+pub enum LoggingError {
+    #[error("Invalid log level or filter directive '{0}'")]
+    InvalidLevel(String),
+
+    #[error("Invalid module path '{0}'")]
+    InvalidModule(String),
+
+    #[error("Failed to open log channel '{channel}': {message}")]
+    Channel { channel: String, message: String },
+
+    #[error("Failed to install the subscriber: {0}")]
+    Subscriber(String),
+}
+
+impl LoggingError {
+    pub(crate) fn channel(channel: &str, error: impl std::fmt::Display) -> Self {
+        Self::Channel {
+            channel: channel.to_string(),
+            message: error.to_string(),
+        }
+    }
+}
@@ -0,0 +1,369 @@
+//! Event formatting with redaction
+
+use crate::config::LogFormat;
+use crate::redact::{Redactor, REDACTED};
+use serde_json::{Map, Value};
+use std::fmt::{self, Write as _};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::{FormatEvent, FormatFields, Writer};
+use tracing_subscriber::fmt::{FmtContext, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Fields of an event or span, with sensitive values redacted
+#[derive(Debug, Default)]
+pub(crate) struct Fields {
+    pub(crate) message: Option<String>,
+    pub(crate) values: Map<String, Value>,
+}
+
+impl Fields {
+    pub(crate) fn record(fields: &impl RecordFields, redactor: &Redactor) -> Self {
+        let mut visitor = FieldVisitor {
+            fields: Fields::default(),
+            redactor,
+        };
+        fields.record(&mut visitor);
+        visitor.fields
+    }
+}
+
+struct FieldVisitor<'a> {
+    fields: Fields,
+    redactor: &'a Redactor,
+}
+
+impl FieldVisitor<'_> {
+    fn insert(&mut self, field: &Field, value: Value) {
+        let name = field.name();
+        if name == "message" {
+            self.fields.message = Some(match value {
+                Value::String(message) => message,
+                other => other.to_string(),
+            });
+        } else if name.starts_with("log.") {
+            // Metadata of events from the `log` crate
+        } else if self.redactor.is_sensitive(name) {
+            self.fields
+                .values
+                .insert(name.to_string(), Value::from(REDACTED));
+        } else {
+            self.fields.values.insert(name.to_string(), value);
+        }
+    }
+}
+
+impl Visit for FieldVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.insert(field, Value::from(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, Value::from(format!("{:?}", value)));
+    }
+}
+
+/// Formats span fields as a redacted JSON object, which [`EventFormat`]
+/// renders in its own format
+#[derive(Debug, Clone)]
+pub(crate) struct SpanFields {
+    pub(crate) redactor: Redactor,
+}
+
+impl<'writer> FormatFields<'writer> for SpanFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let fields = Fields::record(&fields, &self.redactor);
+        write!(writer, "{}", Value::Object(fields.values))
+    }
+
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &tracing::span::Record<'_>,
+    ) -> fmt::Result {
+        let mut values = parse_span_fields(&current.fields);
+        values.extend(Fields::record(fields, &self.redactor).values);
+        current.fields = Value::Object(values).to_string();
+        Ok(())
+    }
+}
+
+fn parse_span_fields(fields: &str) -> Map<String, Value> {
+    match serde_json::from_str(fields) {
+        Ok(Value::Object(values)) => values,
+        _ => Map::new(),
+    }
+}
+
+/// Formats events as JSON lines, pretty or compact text
+#[derive(Debug, Clone)]
+pub(crate) struct EventFormat {
+    pub(crate) format: LogFormat,
+    pub(crate) redactor: Redactor,
+    /// Whether to start with a timestamp, left out for syslog whose header
+    /// has one
+    pub(crate) timestamps: bool,
+}
+
+/// A span the event happened in, outermost first
+struct SpanEntry {
+    name: &'static str,
+    fields: Map<String, Value>,
+}
+
+impl<S, N> FormatEvent<S, N> for EventFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let fields = Fields::record(event, &self.redactor);
+        let spans: Vec<SpanEntry> = ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| SpanEntry {
+                name: span.name(),
+                fields: span
+                    .extensions()
+                    .get::<FormattedFields<N>>()
+                    .map(|formatted| parse_span_fields(&formatted.fields))
+                    .unwrap_or_default(),
+            })
+            .collect();
+        let timestamp = self
+            .timestamps
+            .then(|| chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true));
+
+        match self.format {
+            LogFormat::Json => {
+                let mut line = Map::new();
+                if let Some(timestamp) = timestamp {
+                    line.insert("timestamp".to_string(), Value::from(timestamp));
+                }
+                line.insert("level".to_string(), Value::from(metadata.level().as_str()));
+                line.insert("target".to_string(), Value::from(metadata.target()));
+                if let Some(message) = fields.message {
+                    line.insert("message".to_string(), Value::from(message));
+                }
+                if !fields.values.is_empty() {
+                    line.insert("fields".to_string(), Value::Object(fields.values));
+                }
+                if !spans.is_empty() {
+                    let spans = spans
+                        .into_iter()
+                        .map(|span| {
+                            let mut entry = Map::new();
+                            entry.insert("name".to_string(), Value::from(span.name));
+                            entry.extend(span.fields);
+                            Value::Object(entry)
+                        })
+                        .collect();
+                    line.insert("spans".to_string(), Value::Array(spans));
+                }
+                writeln!(writer, "{}", Value::Object(line))
+            }
+            LogFormat::Pretty => {
+                if let Some(timestamp) = timestamp {
+                    write!(writer, "{} ", timestamp)?;
+                }
+                write_level(&mut writer, metadata.level())?;
+                writeln!(
+                    writer,
+                    " {}: {}",
+                    metadata.target(),
+                    fields.message.as_deref().unwrap_or_default()
+                )?;
+                if !fields.values.is_empty() {
+                    writeln!(writer, "    {}", text_fields(&fields.values, ": ", ", "))?;
+                }
+                for span in spans.iter().rev() {
+                    write!(writer, "    in {}", span.name)?;
+                    if !span.fields.is_empty() {
+                        write!(writer, " with {}", text_fields(&span.fields, ": ", ", "))?;
+                    }
+                    writeln!(writer)?;
+                }
+                Ok(())
+            }
+            LogFormat::Compact => {
+                if let Some(timestamp) = timestamp {
+                    write!(writer, "{} ", timestamp)?;
+                }
+                write_level(&mut writer, metadata.level())?;
+                write!(writer, " ")?;
+                for span in &spans {
+                    write!(writer, "{}", span.name)?;
+                    if !span.fields.is_empty() {
+                        write!(writer, "{{{}}}", text_fields(&span.fields, "=", " "))?;
+                    }
+                    write!(writer, ":")?;
+                }
+                if !spans.is_empty() {
+                    write!(writer, " ")?;
+                }
+                write!(writer, "{}:", metadata.target())?;
+                if let Some(message) = &fields.message {
+                    write!(writer, " {}", message)?;
+                }
+                if !fields.values.is_empty() {
+                    write!(writer, " {}", text_fields(&fields.values, "=", " "))?;
+                }
+                writeln!(writer)
+            }
+        }
+    }
+}
+
+fn write_level(writer: &mut Writer<'_>, level: &Level) -> fmt::Result {
+    if !writer.has_ansi_escapes() {
+        return write!(writer, "{:>5}", level.as_str());
+    }
+    let color = match *level {
+        Level::ERROR => "31",
+        Level::WARN => "33",
+        Level::INFO => "32",
+        Level::DEBUG => "34",
+        Level::TRACE => "35",
+    };
+    write!(writer, "\x1b[{}m{:>5}\x1b[0m", color, level.as_str())
+}
+
+/// `values` as `key<assign>value` pairs, strings unquoted
+fn text_fields(values: &Map<String, Value>, assign: &str, separator: &str) -> String {
+    let mut text = String::new();
+    for (i, (key, value)) in values.iter().enumerate() {
+        if i > 0 {
+            text.push_str(separator);
+        }
+        let _ = match value {
+            Value::String(value) => write!(text, "{}{}{}", key, assign, value),
+            value => write!(text, "{}{}{}", key, assign, value),
+        };
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn capture(format: LogFormat, log: impl FnOnce()) -> String {
+        let buffer = Buffer::default();
+        let redactor = Redactor::new(["password", "token", "api_key"]);
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .event_format(EventFormat {
+                    format,
+                    redactor: redactor.clone(),
+                    timestamps: false,
+                })
+                .fmt_fields(SpanFields { redactor })
+                .with_ansi(false)
+                .with_writer(move || writer.clone()),
+        );
+        tracing::subscriber::with_default(subscriber, log);
+        let output = buffer.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn login() {
+        let span = tracing::info_span!("request", request_id = "r-1", token = "t0k3n");
+        let _entered = span.enter();
+        tracing::info!(target: "app::auth", user = "ada", password = "hunter2", attempts = 2, "Logged in");
+    }
+
+    #[test]
+    fn test_json_redacts_event_and_span_fields() {
+        let output = capture(LogFormat::Json, login);
+        let line: Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], "app::auth");
+        assert_eq!(line["message"], "Logged in");
+        assert_eq!(line["fields"]["user"], "ada");
+        assert_eq!(line["fields"]["password"], REDACTED);
+        assert_eq!(line["fields"]["attempts"], 2);
+        assert_eq!(line["spans"][0]["name"], "request");
+        assert_eq!(line["spans"][0]["request_id"], "r-1");
+        assert_eq!(line["spans"][0]["token"], REDACTED);
+        assert!(!output.contains("hunter2") && !output.contains("t0k3n"));
+    }
+
+    #[test]
+    fn test_text_formats() {
+        let compact = capture(LogFormat::Compact, login);
+        assert_eq!(
+            compact,
+            " INFO request{request_id=r-1 token=[REDACTED]}: app::auth: Logged in \
+             attempts=2 password=[REDACTED] user=ada\n"
+        );
+
+        let pretty = capture(LogFormat::Pretty, login);
+        assert_eq!(
+            pretty,
+            " INFO app::auth: Logged in\n    attempts: 2, password: [REDACTED], user: ada\n    \
+             in request with request_id: r-1, token: [REDACTED]\n"
+        );
+    }
+
+    #[test]
+    fn test_recorded_span_fields_are_redacted() {
+        let output = capture(LogFormat::Compact, || {
+            let span = tracing::info_span!("job", api_key = tracing::field::Empty);
+            span.record("api_key", "k-123");
+            let _entered = span.enter();
+            tracing::warn!("Retrying");
+        });
+        assert!(output.contains("job{api_key=[REDACTED]}"));
+        assert!(!output.contains("k-123"));
+    }
+}
@@ -0,0 +1,181 @@
+//! Log levels changeable at runtime
+
+use crate::error::{LoggingError, LoggingResult};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// The global filter layer, reloaded when levels change
+pub(crate) type FilterLayer = reload::Layer<EnvFilter, Registry>;
+
+/// The default level and module overrides in effect
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LevelSnapshot {
+    pub default: String,
+    pub modules: BTreeMap<String, String>,
+}
+
+/// Handle to change the levels of an installed logger
+///
+/// ```no_run
+/// # fn example() -> rf_logging::LoggingResult<()> {
+/// let logging = rf_logging::init(&rf_logging::LogConfig::default())?;
+/// logging.levels().set("sqlx", "debug")?;
+/// logging.levels().remove("sqlx")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct LogLevels {
+    state: Arc<Mutex<LevelSnapshot>>,
+    handle: reload::Handle<EnvFilter, Registry>,
+}
+
+impl LogLevels {
+    /// Build the filter layer for `default` directives, like `RUST_LOG`,
+    /// and module overrides
+    pub(crate) fn new(
+        default: String,
+        modules: BTreeMap<String, String>,
+    ) -> LoggingResult<(FilterLayer, Self)> {
+        for (module, level) in &modules {
+            validate(module, level)?;
+        }
+        let snapshot = LevelSnapshot { default, modules };
+        let (layer, handle) = reload::Layer::new(filter(&snapshot)?);
+        let levels = Self {
+            state: Arc::new(Mutex::new(snapshot)),
+            handle,
+        };
+        Ok((layer, levels))
+    }
+
+    pub fn snapshot(&self) -> LevelSnapshot {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Set the level of `module` and its submodules
+    pub fn set(&self, module: &str, level: &str) -> LoggingResult<()> {
+        validate(module, level)?;
+        self.update(|snapshot| {
+            snapshot
+                .modules
+                .insert(module.to_string(), level.to_ascii_lowercase());
+        })
+    }
+
+    /// Remove the override of `module`, returning whether it had one
+    pub fn remove(&self, module: &str) -> LoggingResult<bool> {
+        let mut removed = false;
+        self.update(|snapshot| removed = snapshot.modules.remove(module).is_some())?;
+        Ok(removed)
+    }
+
+    /// Set the level of modules without an override
+    pub fn set_default(&self, level: &str) -> LoggingResult<()> {
+        parse_level(level)?;
+        self.update(|snapshot| snapshot.default = level.to_ascii_lowercase())
+    }
+
+    fn update(&self, change: impl FnOnce(&mut LevelSnapshot)) -> LoggingResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let mut snapshot = state.clone();
+        change(&mut snapshot);
+        self.handle
+            .reload(filter(&snapshot)?)
+            .map_err(|e| LoggingError::Subscriber(e.to_string()))?;
+        *state = snapshot;
+        Ok(())
+    }
+}
+
+fn parse_level(level: &str) -> LoggingResult<LevelFilter> {
+    level
+        .parse()
+        .map_err(|_| LoggingError::InvalidLevel(level.to_string()))
+}
+
+fn validate(module: &str, level: &str) -> LoggingResult<()> {
+    let valid = !module.is_empty()
+        && module
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == ':' || c == '-');
+    if !valid {
+        return Err(LoggingError::InvalidModule(module.to_string()));
+    }
+    parse_level(level).map(|_| ())
+}
+
+fn filter(snapshot: &LevelSnapshot) -> LoggingResult<EnvFilter> {
+    let mut directives = snapshot.default.clone();
+    for (module, level) in &snapshot.modules {
+        directives.push_str(&format!(",{}={}", module, level));
+    }
+    EnvFilter::try_new(&directives).map_err(|_| LoggingError::InvalidLevel(directives))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+    struct Count(Arc<AtomicUsize>);
+
+    impl<S: Subscriber> Layer<S> for Count {
+        fn on_event(&self, _event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_levels_change_at_runtime() {
+        let modules = BTreeMap::from([("app::db".to_string(), "warn".to_string())]);
+        let (layer, levels) = LogLevels::new("info".to_string(), modules).unwrap();
+        let count = Arc::new(AtomicUsize::new(0));
+        let subscriber = tracing_subscriber::registry()
+            .with(layer)
+            .with(Count(count.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "app::db::pool", "hidden");
+            tracing::debug!(target: "app::http", "hidden");
+            assert_eq!(count.load(Ordering::SeqCst), 0);
+
+            levels.set("app::db", "debug").unwrap();
+            tracing::debug!(target: "app::db::pool", "shown");
+            assert_eq!(count.load(Ordering::SeqCst), 1);
+
+            assert!(levels.remove("app::db").unwrap());
+            assert!(!levels.remove("app::db").unwrap());
+            levels.set_default("debug").unwrap();
+            tracing::debug!(target: "app::http", "shown");
+            assert_eq!(count.load(Ordering::SeqCst), 2);
+        });
+
+        assert_eq!(
+            levels.snapshot(),
+            LevelSnapshot {
+                default: "debug".to_string(),
+                modules: BTreeMap::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_levels() {
+        let (_layer, levels) = LogLevels::new("info".to_string(), BTreeMap::new()).unwrap();
+        assert!(matches!(
+            levels.set("app", "loud"),
+            Err(LoggingError::InvalidLevel(_))
+        ));
+        assert!(matches!(
+            levels.set("app=trace,hyper", "debug"),
+            Err(LoggingError::InvalidModule(_))
+        ));
+        assert!(levels.snapshot().modules.is_empty());
+    }
+}
@@ -1,120 +1,138 @@
 //! Advanced logging and tracing for RustForge
 //!
 //! This crate provides structured logging with:
+//! - Multiple channels: stderr/stdout, rotated files, syslog and OTLP
+//!   (feature `otlp`)
 //! - Multiple output formats (JSON, Pretty, Compact)
-//! - File and stdout sinks
+//! - Per-module levels changeable at runtime, also over HTTP (feature `axum`)
+//! - Redaction of sensitive fields such as passwords and tokens
 //! - Request ID tracking
 //! - Performance timing
 //! - Distributed tracing support
-
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use tracing_subscriber::{
-    fmt::{self, format::FmtSpan},
-    layer::SubscriberExt,
-    util::SubscriberInitExt,
-    EnvFilter,
-};
+//!
+//! ```no_run
+//! use rf_logging::{ChannelConfig, LogConfig, LogFormat};
+//!
+//! # fn example() -> rf_logging::LoggingResult<()> {
+//! let config = LogConfig {
+//!     channels: vec![
+//!         ChannelConfig::Stderr { format: LogFormat::Pretty, level: None },
+//!         ChannelConfig::File {
+//!             path: "storage/logs".into(),
+//!             prefix: "app.log".to_string(),
+//!             rotation: rf_logging::Rotation::Daily,
+//!             max_files: Some(14),
+//!             format: LogFormat::Json,
+//!             level: Some("info".to_string()),
+//!         },
+//!     ],
+//!     ..Default::default()
+//! };
+//! let logging = rf_logging::init(&config)?;
+//!
+//! rf_logging::info!(user = "ada", password = "hunter2", "Logged in"); // password=[REDACTED]
+//! logging.levels().set("sqlx", "debug")?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "axum")]
+mod admin;
+mod channel;
+mod config;
+mod error;
+mod format;
+mod levels;
+#[cfg(feature = "otlp")]
+mod otlp;
+mod redact;
+mod syslog;
+
+#[cfg(feature = "axum")]
+pub use admin::log_level_router;
+pub use config::{ChannelConfig, LogConfig, LogFormat, Rotation, DEFAULT_REDACTED_FIELDS};
+pub use error::{LoggingError, LoggingResult};
+pub use levels::{LevelSnapshot, LogLevels};
+pub use redact::{Redactor, REDACTED};
+
+use tracing_subscriber::{fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt};
 
 pub use tracing::{debug, error, info, instrument, span, trace, warn, Instrument, Span};
 pub use uuid::Uuid;
 
-/// Logging configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LogConfig {
-    /// Log level (trace, debug, info, warn, error)
-    pub level: String,
-
-    /// Output format
-    pub format: LogFormat,
-
-    /// Log to stdout
-    pub stdout: bool,
-
-    /// Optional file output
-    pub file: Option<PathBuf>,
-
-    /// Directory for log files (if file logging enabled)
-    pub log_dir: Option<PathBuf>,
-
-    /// Enable request ID tracking
-    pub request_id: bool,
-
-    /// Enable performance timing
-    pub timing: bool,
+/// An installed logger
+pub struct Logging {
+    levels: LogLevels,
+    #[cfg(feature = "otlp")]
+    otlp: bool,
 }
 
-impl Default for LogConfig {
-    fn default() -> Self {
-        Self {
-            level: "info".to_string(),
-            format: LogFormat::Pretty,
-            stdout: true,
-            file: None,
-            log_dir: None,
-            request_id: true,
-            timing: true,
-        }
+impl Logging {
+    /// Handle to change levels at runtime
+    pub fn levels(&self) -> &LogLevels {
+        &self.levels
     }
-}
 
-/// Log output format
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum LogFormat {
-    /// JSON format for production
-    Json,
-    /// Pretty format for development
-    Pretty,
-    /// Compact format
-    Compact,
+    /// Flush channels that buffer events; call before the process exits
+    pub fn shutdown(self) {
+        #[cfg(feature = "otlp")]
+        if self.otlp {
+            otlp::shutdown();
+        }
+    }
 }
 
-/// Initialize logging with configuration
-pub fn init_logging(config: LogConfig) -> Result<(), Box<dyn std::error::Error>> {
-    let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new(&config.level));
-
-    let registry = tracing_subscriber::registry().with(env_filter);
-
-    // Determine span events
+/// Build a subscriber writing to the configured channels without installing it
+///
+/// The global level is `RUST_LOG` if set, otherwise `config.level`, with
+/// `config.levels` overriding it per module.
+pub fn build(
+    config: &LogConfig,
+) -> LoggingResult<(impl tracing::Subscriber + Send + Sync + 'static, Logging)> {
+    let default = std::env::var(tracing_subscriber::EnvFilter::DEFAULT_ENV)
+        .ok()
+        .filter(|directives| !directives.trim().is_empty())
+        .unwrap_or_else(|| config.level.clone());
+    let (filter, levels) = LogLevels::new(default, config.levels.clone())?;
+
+    let redactor = Redactor::new(&config.redact);
     let span_events = if config.timing {
         FmtSpan::CLOSE
     } else {
         FmtSpan::NONE
     };
+    let channels = config.effective_channels();
+    let layers = channels
+        .iter()
+        .map(|channel| channel::layer(channel, &redactor, span_events.clone()))
+        .collect::<LoggingResult<Vec<_>>>()?;
+
+    let logging = Logging {
+        levels,
+        #[cfg(feature = "otlp")]
+        otlp: channels
+            .iter()
+            .any(|channel| matches!(channel, ChannelConfig::Otlp { .. })),
+    };
+    let subscriber = tracing_subscriber::registry().with(filter).with(layers);
+    Ok((subscriber, logging))
+}
 
-    match config.format {
-        LogFormat::Json => {
-            if config.stdout {
-                let layer = fmt::layer()
-                    .json()
-                    .with_span_events(span_events)
-                    .with_current_span(true)
-                    .with_target(true);
-                registry.with(layer).init();
-            }
-        }
-        LogFormat::Pretty => {
-            if config.stdout {
-                let layer = fmt::layer()
-                    .pretty()
-                    .with_span_events(span_events)
-                    .with_target(true);
-                registry.with(layer).init();
-            }
-        }
-        LogFormat::Compact => {
-            if config.stdout {
-                let layer = fmt::layer()
-                    .compact()
-                    .with_span_events(span_events)
-                    .with_target(true);
-                registry.with(layer).init();
-            }
-        }
-    }
+/// Install the configured logger as the global default
+///
+/// Also forwards records of the `log` crate. An OTLP channel has to be
+/// set up in a Tokio runtime.
+pub fn init(config: &LogConfig) -> LoggingResult<Logging> {
+    let (subscriber, logging) = build(config)?;
+    subscriber
+        .try_init()
+        .map_err(|e| LoggingError::Subscriber(e.to_string()))?;
+    Ok(logging)
+}
 
+/// Initialize logging with configuration
+pub fn init_logging(config: LogConfig) -> Result<(), Box<dyn std::error::Error>> {
+    init(&config)?;
     Ok(())
 }
 
@@ -220,6 +238,7 @@ macro_rules! log_data {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::PathBuf;
 
     #[test]
     fn test_log_config_default() {
@@ -280,11 +299,12 @@ mod tests {
             log_dir: None,
             request_id: true,
             timing: true,
+            ..Default::default()
         };
 
         // This should not panic
-        // Note: We can't actually test output without capturing stderr
-        // In real tests, you'd use a test subscriber
+        let (_subscriber, logging) = build(&config).unwrap();
+        assert!(logging.levels().snapshot().modules.is_empty());
     }
 
     #[test]
@@ -297,10 +317,60 @@ mod tests {
             log_dir: Some(PathBuf::from("/var/log")),
             request_id: false,
             timing: false,
+            ..Default::default()
         };
 
         assert_eq!(config.level, "warn");
         assert!(!config.stdout);
         assert!(config.file.is_some());
     }
+
+    #[test]
+    fn test_file_channel_with_module_levels() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = LogConfig {
+            level: "warn".to_string(),
+            timing: false,
+            channels: vec![ChannelConfig::File {
+                path: dir.path().to_path_buf(),
+                prefix: "app.log".to_string(),
+                rotation: Rotation::Never,
+                max_files: None,
+                format: LogFormat::Json,
+                level: None,
+            }],
+            levels: [("app::billing".to_string(), "info".to_string())].into(),
+            ..Default::default()
+        };
+        let (subscriber, logging) = build(&config).unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            info!(target: "app::billing", invoice = 7, api_key = "k-1", "Invoice paid");
+            info!(target: "app::http", "Request finished");
+            logging.levels().set("app::http", "info").unwrap();
+            info!(target: "app::http", "Request finished");
+        });
+
+        let log = std::fs::read_to_string(dir.path().join("app.log")).unwrap();
+        let lines: Vec<serde_json::Value> = log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["fields"]["invoice"], 7);
+        assert_eq!(lines[0]["fields"]["api_key"], REDACTED);
+        assert_eq!(lines[1]["target"], "app::http");
+    }
+
+    #[test]
+    fn test_invalid_channel_level() {
+        let config = LogConfig {
+            channels: vec![ChannelConfig::Stderr {
+                format: LogFormat::Compact,
+                level: Some("everything".to_string()),
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(build(&config), Err(LoggingError::InvalidLevel(_))));
+    }
 }
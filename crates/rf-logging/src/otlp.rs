@@ -0,0 +1,169 @@
+//! OTLP log export channel
+
+use crate::error::{LoggingError, LoggingResult};
+use crate::format::Fields;
+use crate::redact::Redactor;
+use opentelemetry::logs::{AnyValue, LogRecord, Logger as _, Severity};
+use opentelemetry::{Key, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::logs::{Config, Logger};
+use opentelemetry_sdk::{runtime, Resource};
+use serde_json::{Map, Value};
+use std::time::SystemTime;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Targets of the exporter's own dependencies, whose events would be
+/// exported again
+const IGNORED_TARGETS: &[&str] = &["h2", "hyper", "opentelemetry", "tonic", "tower"];
+
+/// Exports events as OTLP log records, with the fields of their spans
+pub(crate) struct OtlpLayer {
+    logger: Logger,
+    redactor: Redactor,
+}
+
+/// Redacted fields of a span, kept for its events
+struct SpanAttributes(Map<String, Value>);
+
+impl OtlpLayer {
+    /// Start a batch exporter to `endpoint`; has to run in a Tokio runtime
+    pub(crate) fn new(
+        endpoint: &str,
+        service_name: Option<&str>,
+        redactor: Redactor,
+    ) -> LoggingResult<Self> {
+        if tokio::runtime::Handle::try_current().is_err() {
+            return Err(LoggingError::channel(
+                "otlp",
+                "the exporter has to be started in a Tokio runtime",
+            ));
+        }
+
+        let service_name = service_name.unwrap_or(env!("CARGO_PKG_NAME")).to_string();
+        let logger = opentelemetry_otlp::new_pipeline()
+            .logging()
+            .with_log_config(
+                Config::default()
+                    .with_resource(Resource::new([KeyValue::new("service.name", service_name)])),
+            )
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(endpoint),
+            )
+            .install_batch(runtime::Tokio)
+            .map_err(|e| LoggingError::channel("otlp", e))?;
+        Ok(Self { logger, redactor })
+    }
+}
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let fields = Fields::record(attrs, &self.redactor);
+            span.extensions_mut().insert(SpanAttributes(fields.values));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let fields = Fields::record(values, &self.redactor);
+            if let Some(attributes) = span.extensions_mut().get_mut::<SpanAttributes>() {
+                attributes.0.extend(fields.values);
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let ignored = IGNORED_TARGETS.iter().any(|target| {
+            metadata
+                .target()
+                .strip_prefix(target)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        });
+        if ignored {
+            return;
+        }
+
+        let mut values = Map::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(attributes) = span.extensions().get::<SpanAttributes>() {
+                    values.extend(attributes.0.clone());
+                }
+            }
+        }
+        let fields = Fields::record(event, &self.redactor);
+        values.extend(fields.values);
+
+        let mut attributes: Vec<(Key, AnyValue)> = values
+            .into_iter()
+            .map(|(key, value)| (Key::from(key), any_value(value)))
+            .collect();
+        attributes.push((
+            Key::from("target"),
+            AnyValue::from(metadata.target().to_string()),
+        ));
+
+        let (severity, severity_text) = match *metadata.level() {
+            Level::ERROR => (Severity::Error, "ERROR"),
+            Level::WARN => (Severity::Warn, "WARN"),
+            Level::INFO => (Severity::Info, "INFO"),
+            Level::DEBUG => (Severity::Debug, "DEBUG"),
+            Level::TRACE => (Severity::Trace, "TRACE"),
+        };
+        let record = LogRecord::builder()
+            .with_timestamp(SystemTime::now())
+            .with_severity_number(severity)
+            .with_severity_text(severity_text)
+            .with_body(fields.message.unwrap_or_default())
+            .with_attributes(attributes)
+            .build();
+        self.logger.emit(record);
+    }
+}
+
+fn any_value(value: Value) -> AnyValue {
+    match value {
+        Value::String(value) => AnyValue::from(value),
+        Value::Bool(value) => AnyValue::from(value),
+        Value::Number(number) => match number.as_i64() {
+            Some(value) => AnyValue::from(value),
+            None => AnyValue::from(number.as_f64().unwrap_or_default()),
+        },
+        other => AnyValue::from(other.to_string()),
+    }
+}
+
+/// Flush and stop the exporter
+pub(crate) fn shutdown() {
+    opentelemetry::global::shutdown_logger_provider();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requires_tokio_runtime() {
+        let result = OtlpLayer::new("http://localhost:4317", None, Redactor::default());
+        assert!(matches!(result, Err(LoggingError::Channel { .. })));
+    }
+
+    #[test]
+    fn test_any_value() {
+        assert_eq!(any_value(Value::from(7)), AnyValue::from(7i64));
+        assert_eq!(any_value(Value::from(0.5)), AnyValue::from(0.5));
+        assert_eq!(
+            any_value(serde_json::json!(["a"])),
+            AnyValue::from("[\"a\"]".to_string())
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! Sensitive field redaction
+
+use std::sync::Arc;
+
+/// Replacement for the values of sensitive fields
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Decides which fields are sensitive
+///
+/// A field is sensitive if its name, lowercased, is one of the configured
+/// names or ends with `_<name>` or `.<name>`, so `token` also covers
+/// `access_token` but not `tokens_used`.
+///
+/// ```
+/// use rf_logging::Redactor;
+///
+/// let redactor = Redactor::new(["password", "token"]);
+/// assert!(redactor.is_sensitive("Password"));
+/// assert!(redactor.is_sensitive("access_token"));
+/// assert!(!redactor.is_sensitive("tokens_used"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    names: Arc<[String]>,
+}
+
+impl Redactor {
+    pub fn new<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            names: names
+                .into_iter()
+                .map(|name| name.as_ref().to_ascii_lowercase())
+                .collect(),
+        }
+    }
+
+    pub fn is_sensitive(&self, field: &str) -> bool {
+        let field = field.to_ascii_lowercase();
+        self.names.iter().any(|name| {
+            field == *name
+                || field
+                    .strip_suffix(name.as_str())
+                    .is_some_and(|rest| rest.ends_with('_') || rest.ends_with('.'))
+        })
+    }
+}
@@ -0,0 +1,182 @@
+//! RFC 5424 syslog channel
+
+use crate::error::{LoggingError, LoggingResult};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::Arc;
+use tracing::{Level, Metadata};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Facility of the messages: user-level
+const FACILITY: u8 = 1;
+
+/// Longest message sent, as many receivers drop larger datagrams
+const MAX_MESSAGE: usize = 8192;
+
+enum Transport {
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixDatagram),
+}
+
+impl Transport {
+    fn send(&self, datagram: &[u8]) -> io::Result<usize> {
+        match self {
+            Transport::Udp(socket) => socket.send(datagram),
+            #[cfg(unix)]
+            Transport::Unix(socket) => socket.send(datagram),
+        }
+    }
+}
+
+/// Sends each formatted event as one syslog datagram
+#[derive(Clone)]
+pub(crate) struct SyslogWriter {
+    transport: Arc<Transport>,
+    app_name: Arc<str>,
+}
+
+impl SyslogWriter {
+    /// Send to `address` over UDP, or to `/dev/log` without one
+    pub(crate) fn connect(address: Option<&str>, app_name: Option<&str>) -> LoggingResult<Self> {
+        let transport = match address {
+            Some(address) => {
+                Transport::Udp(udp(address).map_err(|e| LoggingError::channel("syslog", e))?)
+            }
+            #[cfg(unix)]
+            None => {
+                let socket = std::os::unix::net::UnixDatagram::unbound()
+                    .and_then(|socket| socket.connect("/dev/log").map(|_| socket))
+                    .map_err(|e| LoggingError::channel("syslog", e))?;
+                Transport::Unix(socket)
+            }
+            #[cfg(not(unix))]
+            None => return Err(LoggingError::channel("syslog", "an address is required")),
+        };
+
+        let app_name = app_name.map(str::to_string).unwrap_or_else(|| {
+            std::env::current_exe()
+                .ok()
+                .and_then(|exe| Some(exe.file_stem()?.to_string_lossy().into_owned()))
+                .unwrap_or_else(|| "rustforge".to_string())
+        });
+        Ok(Self {
+            transport: Arc::new(transport),
+            app_name: app_name.into(),
+        })
+    }
+
+    fn message(&self, level: Level) -> SyslogMessage {
+        let severity = match level {
+            Level::ERROR => 3,
+            Level::WARN => 4,
+            Level::INFO => 6,
+            Level::DEBUG | Level::TRACE => 7,
+        };
+        SyslogMessage {
+            writer: self.clone(),
+            priority: FACILITY * 8 + severity,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+fn udp(address: &str) -> io::Result<UdpSocket> {
+    let target = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address resolved"))?;
+    let local: SocketAddr = if target.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+    let socket = UdpSocket::bind(local)?;
+    socket.connect(target)?;
+    Ok(socket)
+}
+
+impl<'a> MakeWriter<'a> for SyslogWriter {
+    type Writer = SyslogMessage;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.message(Level::INFO)
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        self.message(*meta.level())
+    }
+}
+
+/// Buffers one formatted event and sends it when dropped
+pub(crate) struct SyslogMessage {
+    writer: SyslogWriter,
+    priority: u8,
+    buffer: Vec<u8>,
+}
+
+impl io::Write for SyslogMessage {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for SyslogMessage {
+    fn drop(&mut self) {
+        let text = String::from_utf8_lossy(&self.buffer);
+        let text = text.trim_end();
+        if text.is_empty() {
+            return;
+        }
+
+        let mut datagram = format!(
+            "<{}>1 {} - {} {} - - {}",
+            self.priority,
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            self.writer.app_name,
+            std::process::id(),
+            text
+        );
+        if datagram.len() > MAX_MESSAGE {
+            let mut end = MAX_MESSAGE;
+            while !datagram.is_char_boundary(end) {
+                end -= 1;
+            }
+            datagram.truncate(end);
+        }
+        // Nowhere to report a failure to log
+        let _ = self.writer.transport.send(datagram.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_sends_rfc5424_datagrams() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+        let address = receiver.local_addr().unwrap().to_string();
+        let writer = SyslogWriter::connect(Some(&address), Some("shop")).unwrap();
+
+        let mut message = writer.message(Level::WARN);
+        message.write_all(b"WARN app: Disk almost full\n").unwrap();
+        drop(message);
+
+        let mut datagram = [0; 1024];
+        let len = receiver.recv(&mut datagram).unwrap();
+        let datagram = std::str::from_utf8(&datagram[..len]).unwrap();
+        assert!(datagram.starts_with("<12>1 "), "{}", datagram);
+        assert!(datagram.contains(&format!(" - shop {} - - ", std::process::id())));
+        assert!(datagram.ends_with("WARN app: Disk almost full"));
+    }
+}
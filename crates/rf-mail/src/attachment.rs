@@ -13,6 +13,11 @@ pub struct Attachment {
 
     /// Attachment data
     pub data: Vec<u8>,
+
+    /// Content ID of an inline attachment, referenced as `cid:<id>` from
+    /// the HTML body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_id: Option<String>,
 }
 
 impl Attachment {
@@ -36,9 +41,31 @@ impl Attachment {
             filename: filename.into(),
             content_type: content_type.into(),
             data,
+            content_id: None,
         }
     }
 
+    /// Turn into an inline attachment, e.g. an image embedded in the HTML
+    /// body as `<img src="cid:logo">`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rf_mail::Attachment;
+    ///
+    /// let logo = Attachment::new("logo.png", "image/png", vec![0x89, 0x50]).inline("logo");
+    /// assert!(logo.is_inline());
+    /// ```
+    pub fn inline(mut self, content_id: impl Into<String>) -> Self {
+        self.content_id = Some(content_id.into());
+        self
+    }
+
+    /// Whether the attachment is embedded in the body
+    pub fn is_inline(&self) -> bool {
+        self.content_id.is_some()
+    }
+
     /// Create attachment from file path
     ///
     /// # Example
@@ -84,6 +111,15 @@ mod tests {
         assert_eq!(attachment.filename, "hello.txt");
         assert_eq!(attachment.content_type, "text/plain");
         assert_eq!(attachment.data, data);
+        assert!(!attachment.is_inline());
+    }
+
+    #[test]
+    fn test_attachment_inline() {
+        let attachment = Attachment::new("logo.png", "image/png", vec![1, 2, 3]).inline("logo");
+
+        assert_eq!(attachment.content_id, Some("logo".to_string()));
+        assert!(attachment.is_inline());
     }
 
     #[test]
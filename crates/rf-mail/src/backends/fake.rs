@@ -0,0 +1,254 @@
+//! Fake mailer backend with assertions for tests
+
+use crate::{MailError, Mailer, Message};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+
+/// Fake mailer recording messages, with assertions for tests
+///
+/// Swap it in for the real mailer and assert on what the code under test
+/// sent. Failed assertions panic with the messages that were sent.
+///
+/// # Example
+///
+/// ```
+/// use rf_mail::{FakeMailer, Mailer, MessageBuilder, Address};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mailer = FakeMailer::new();
+/// mailer.assert_nothing_sent();
+///
+/// let message = MessageBuilder::new()
+///     .from(Address::new("sender@example.com"))
+///     .to(Address::new("user@example.com"))
+///     .subject("Your invoice")
+///     .text("Hello")
+///     .build()?;
+/// mailer.send(&message).await?;
+///
+/// mailer.assert_sent_to("user@example.com");
+/// mailer.assert_sent(|m| m.subject.contains("invoice"));
+/// mailer.assert_sent_count(1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct FakeMailer {
+    sent: Arc<Mutex<Vec<Message>>>,
+}
+
+impl FakeMailer {
+    /// Create new fake mailer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get all sent messages
+    pub fn sent(&self) -> Vec<Message> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// Get sent messages matching a predicate
+    pub fn sent_matching<F>(&self, predicate: F) -> Vec<Message>
+    where
+        F: Fn(&Message) -> bool,
+    {
+        self.sent
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| predicate(m))
+            .cloned()
+            .collect()
+    }
+
+    /// Assert that a message matching the predicate was sent
+    #[track_caller]
+    pub fn assert_sent<F>(&self, predicate: F)
+    where
+        F: Fn(&Message) -> bool,
+    {
+        if self.sent_matching(predicate).is_empty() {
+            panic!(
+                "Expected a matching message to be sent. Sent: {}",
+                self.summary()
+            );
+        }
+    }
+
+    /// Assert that no message matching the predicate was sent
+    #[track_caller]
+    pub fn assert_not_sent<F>(&self, predicate: F)
+    where
+        F: Fn(&Message) -> bool,
+    {
+        let matching = self.sent_matching(predicate);
+        if !matching.is_empty() {
+            panic!(
+                "Expected no matching message, but {} were sent. Sent: {}",
+                matching.len(),
+                self.summary()
+            );
+        }
+    }
+
+    /// Assert that a message was sent to the email, as To, Cc or Bcc
+    #[track_caller]
+    pub fn assert_sent_to(&self, email: &str) {
+        if self.sent_matching(|m| is_recipient(m, email)).is_empty() {
+            panic!(
+                "Expected a message to be sent to {}. Sent: {}",
+                email,
+                self.summary()
+            );
+        }
+    }
+
+    /// Assert that no message was sent to the email
+    #[track_caller]
+    pub fn assert_not_sent_to(&self, email: &str) {
+        if !self.sent_matching(|m| is_recipient(m, email)).is_empty() {
+            panic!(
+                "Expected no message to be sent to {}. Sent: {}",
+                email,
+                self.summary()
+            );
+        }
+    }
+
+    /// Assert the number of sent messages
+    #[track_caller]
+    pub fn assert_sent_count(&self, count: usize) {
+        let sent = self.sent.lock().unwrap().len();
+        if sent != count {
+            panic!(
+                "Expected {} messages to be sent, but {} were. Sent: {}",
+                count,
+                sent,
+                self.summary()
+            );
+        }
+    }
+
+    /// Assert that nothing was sent
+    #[track_caller]
+    pub fn assert_nothing_sent(&self) {
+        self.assert_sent_count(0);
+    }
+
+    /// Forget all sent messages
+    pub fn clear(&self) {
+        self.sent.lock().unwrap().clear();
+    }
+
+    fn summary(&self) -> String {
+        let sent = self.sent.lock().unwrap();
+        if sent.is_empty() {
+            return "nothing".to_string();
+        }
+        sent.iter()
+            .map(|m| {
+                format!(
+                    "\"{}\" to [{}]",
+                    m.subject,
+                    m.to.iter()
+                        .map(|a| a.email.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+fn is_recipient(message: &Message, email: &str) -> bool {
+    message
+        .to
+        .iter()
+        .chain(&message.cc)
+        .chain(&message.bcc)
+        .any(|addr| addr.email == email)
+}
+
+#[async_trait]
+impl Mailer for FakeMailer {
+    async fn send(&self, message: &Message) -> Result<(), MailError> {
+        message.validate().map_err(MailError::InvalidMessage)?;
+        self.sent.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, Mailable, MessageBuilder, WelcomeEmail};
+
+    fn message(to: &str, subject: &str) -> Message {
+        MessageBuilder::new()
+            .from(Address::new("sender@example.com"))
+            .to(Address::new(to))
+            .bcc(Address::new("audit@example.com"))
+            .subject(subject)
+            .text("Hello")
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fake_assertions() {
+        let mailer = FakeMailer::new();
+        mailer.assert_nothing_sent();
+
+        mailer
+            .send(&message("a@example.com", "First"))
+            .await
+            .unwrap();
+        mailer
+            .send(&message("b@example.com", "Second"))
+            .await
+            .unwrap();
+
+        mailer.assert_sent_count(2);
+        mailer.assert_sent_to("a@example.com");
+        mailer.assert_sent_to("audit@example.com");
+        mailer.assert_not_sent_to("c@example.com");
+        mailer.assert_sent(|m| m.subject == "Second");
+        mailer.assert_not_sent(|m| m.subject == "Third");
+        assert_eq!(mailer.sent_matching(|m| m.subject == "First").len(), 1);
+
+        mailer.clear();
+        mailer.assert_nothing_sent();
+    }
+
+    #[tokio::test]
+    async fn test_fake_with_mailable() {
+        let mailer = FakeMailer::new();
+
+        WelcomeEmail {
+            to: Address::new("user@example.com"),
+            user_name: "Alice".into(),
+            app_name: "MyApp".into(),
+        }
+        .send(&mailer)
+        .await
+        .unwrap();
+
+        mailer.assert_sent(|m| {
+            m.subject == "Welcome to MyApp!" && m.to[0].email == "user@example.com"
+        });
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Expected a message to be sent to c@example.com")]
+    async fn test_fake_failed_assertion() {
+        let mailer = FakeMailer::new();
+        mailer
+            .send(&message("a@example.com", "First"))
+            .await
+            .unwrap();
+
+        mailer.assert_sent_to("c@example.com");
+    }
+}
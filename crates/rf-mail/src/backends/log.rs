@@ -0,0 +1,109 @@
+//! Log mailer backend for development
+
+use super::smtp::convert_to_lettre;
+use crate::{MailError, Mailer, Message};
+use async_trait::async_trait;
+use std::path::PathBuf;
+
+/// Mailer that logs messages instead of sending them
+///
+/// Optionally writes every message as `.eml` file to a directory, so it
+/// can be opened in a mail client.
+///
+/// # Example
+///
+/// ```
+/// use rf_mail::{LogMailer, Mailer, MessageBuilder, Address};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mailer = LogMailer::new().directory("storage/mail");
+///
+/// let message = MessageBuilder::new()
+///     .from(Address::new("sender@example.com"))
+///     .to(Address::new("recipient@example.com"))
+///     .subject("Test")
+///     .text("Hello")
+///     .build()?;
+///
+/// mailer.send(&message).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LogMailer {
+    directory: Option<PathBuf>,
+}
+
+impl LogMailer {
+    /// Create new log mailer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also write messages as `.eml` files to `directory`
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+}
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, message: &Message) -> Result<(), MailError> {
+        let raw = convert_to_lettre(message)?.formatted();
+
+        tracing::info!(
+            id = %message.id,
+            from = %message.from.format(),
+            to = ?message.to.iter().map(|a| a.format()).collect::<Vec<_>>(),
+            subject = %message.subject,
+            attachments = message.attachments.len(),
+            "Email logged"
+        );
+        tracing::debug!("{}", String::from_utf8_lossy(&raw));
+
+        if let Some(directory) = &self.directory {
+            tokio::fs::create_dir_all(directory).await?;
+            let file = format!(
+                "{}-{}.eml",
+                chrono::Utc::now().format("%Y%m%d%H%M%S"),
+                message.id
+            );
+            tokio::fs::write(directory.join(file), raw).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, MessageBuilder};
+
+    #[tokio::test]
+    async fn test_log_mailer_writes_eml() {
+        let directory = std::env::temp_dir().join(format!("rf-mail-{}", uuid::Uuid::new_v4()));
+        let mailer = LogMailer::new().directory(&directory);
+
+        let message = MessageBuilder::new()
+            .from(Address::new("sender@example.com"))
+            .to(Address::new("user@example.com"))
+            .subject("Logged")
+            .text("Hello")
+            .build()
+            .unwrap();
+
+        mailer.send(&message).await.unwrap();
+
+        let mut entries = std::fs::read_dir(&directory).unwrap();
+        let path = entries.next().unwrap().unwrap().path();
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(path
+            .to_string_lossy()
+            .ends_with(&format!("{}.eml", message.id)));
+        assert!(raw.contains("Subject: Logged"));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
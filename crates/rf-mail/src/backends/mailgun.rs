@@ -0,0 +1,153 @@
+//! Mailgun mailer backend
+
+use super::check_response;
+use super::smtp::convert_to_lettre;
+use crate::{MailError, Mailer, Message};
+use async_trait::async_trait;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+
+/// Mailgun configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailgunConfig {
+    /// Sending domain, e.g. `mg.example.com`
+    pub domain: String,
+
+    /// API key
+    pub secret: String,
+
+    /// API endpoint, `https://api.eu.mailgun.net` for the EU region
+    #[serde(default = "default_endpoint")]
+    pub endpoint: String,
+}
+
+fn default_endpoint() -> String {
+    "https://api.mailgun.net".into()
+}
+
+impl MailgunConfig {
+    /// Load from `MAILGUN_DOMAIN`, `MAILGUN_SECRET` and `MAILGUN_ENDPOINT`
+    pub fn from_env() -> Result<Self, MailError> {
+        let required = |name: &str| {
+            std::env::var(name).map_err(|_| MailError::ConfigError(format!("{} is not set", name)))
+        };
+
+        Ok(Self {
+            domain: required("MAILGUN_DOMAIN")?,
+            secret: required("MAILGUN_SECRET")?,
+            endpoint: std::env::var("MAILGUN_ENDPOINT").unwrap_or_else(|_| default_endpoint()),
+        })
+    }
+}
+
+/// Mailgun mailer backend
+///
+/// Messages are sent as raw MIME, so attachments and custom headers are
+/// kept.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_mail::{MailgunConfig, MailgunMailer};
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mailer = MailgunMailer::new(MailgunConfig::from_env()?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct MailgunMailer {
+    config: MailgunConfig,
+    client: reqwest::Client,
+}
+
+impl MailgunMailer {
+    /// Create new Mailgun mailer
+    pub fn new(config: MailgunConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "{}/v3/{}/messages.mime",
+            self.config.endpoint.trim_end_matches('/'),
+            self.config.domain
+        )
+    }
+}
+
+/// All recipients, as Bcc recipients aren't in the raw message
+fn recipients(message: &Message) -> Vec<String> {
+    message
+        .to
+        .iter()
+        .chain(&message.cc)
+        .chain(&message.bcc)
+        .map(|a| a.email.clone())
+        .collect()
+}
+
+#[async_trait]
+impl Mailer for MailgunMailer {
+    async fn send(&self, message: &Message) -> Result<(), MailError> {
+        let raw = convert_to_lettre(message)?.formatted();
+
+        let mut form = Form::new();
+        for recipient in recipients(message) {
+            form = form.text("to", recipient);
+        }
+        form = form.part("message", Part::bytes(raw).file_name("message.mime"));
+
+        let response = self
+            .client
+            .post(self.url())
+            .basic_auth("api", Some(&self.config.secret))
+            .multipart(form)
+            .send()
+            .await?;
+        check_response("Mailgun", response).await?;
+
+        tracing::info!(
+            to = ?message.to,
+            subject = %message.subject,
+            "Email sent via Mailgun"
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, MessageBuilder};
+
+    #[test]
+    fn test_url_and_recipients() {
+        let mailer = MailgunMailer::new(MailgunConfig {
+            domain: "mg.example.com".into(),
+            secret: "key".into(),
+            endpoint: "https://api.eu.mailgun.net/".into(),
+        });
+        assert_eq!(
+            mailer.url(),
+            "https://api.eu.mailgun.net/v3/mg.example.com/messages.mime"
+        );
+
+        let message = MessageBuilder::new()
+            .from(Address::new("sender@example.com"))
+            .to(Address::with_name("to@example.com", "To"))
+            .cc(Address::new("cc@example.com"))
+            .bcc(Address::new("bcc@example.com"))
+            .subject("Test")
+            .text("Hello")
+            .build()
+            .unwrap();
+        assert_eq!(
+            recipients(&message),
+            vec!["to@example.com", "cc@example.com", "bcc@example.com"]
+        );
+    }
+}
@@ -1,9 +1,40 @@
 //! Email backend implementations
 
+pub mod fake;
+pub mod log;
+#[cfg(feature = "mailgun")]
+pub mod mailgun;
 pub mod memory;
 pub mod mock;
+#[cfg(feature = "postmark")]
+pub mod postmark;
+#[cfg(feature = "ses")]
+pub mod ses;
 pub mod smtp;
 
+pub use fake::FakeMailer;
+pub use log::LogMailer;
+#[cfg(feature = "mailgun")]
+pub use mailgun::{MailgunConfig, MailgunMailer};
 pub use memory::MemoryMailer;
 pub use mock::MockMailer;
-pub use smtp::{SmtpConfig, SmtpMailer};
+#[cfg(feature = "postmark")]
+pub use postmark::{PostmarkConfig, PostmarkMailer};
+#[cfg(feature = "ses")]
+pub use ses::{SesConfig, SesMailer};
+pub use smtp::{SmtpConfig, SmtpEncryption, SmtpMailer};
+
+/// Turn an unsuccessful API response into an error with its body
+#[cfg(any(feature = "ses", feature = "mailgun", feature = "postmark"))]
+async fn check_response(driver: &str, response: reqwest::Response) -> Result<(), crate::MailError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    Err(crate::MailError::SendFailed(format!(
+        "{} responded with {}: {}",
+        driver, status, body
+    )))
+}
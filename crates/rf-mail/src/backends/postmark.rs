@@ -0,0 +1,188 @@
+//! Postmark mailer backend
+
+use super::check_response;
+use super::smtp::mailbox;
+use crate::{Address, MailError, Mailer, Message};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const API_URL: &str = "https://api.postmarkapp.com/email";
+
+/// Postmark configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostmarkConfig {
+    /// Server API token
+    pub token: String,
+
+    /// Message stream (default: the server's transactional stream)
+    #[serde(default)]
+    pub message_stream: Option<String>,
+}
+
+impl PostmarkConfig {
+    /// Load from `POSTMARK_TOKEN` and `POSTMARK_MESSAGE_STREAM`
+    pub fn from_env() -> Result<Self, MailError> {
+        Ok(Self {
+            token: std::env::var("POSTMARK_TOKEN")
+                .map_err(|_| MailError::ConfigError("POSTMARK_TOKEN is not set".into()))?,
+            message_stream: std::env::var("POSTMARK_MESSAGE_STREAM").ok(),
+        })
+    }
+}
+
+/// Postmark mailer backend
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_mail::{PostmarkConfig, PostmarkMailer};
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mailer = PostmarkMailer::new(PostmarkConfig::from_env()?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct PostmarkMailer {
+    config: PostmarkConfig,
+    client: reqwest::Client,
+}
+
+impl PostmarkMailer {
+    /// Create new Postmark mailer
+    pub fn new(config: PostmarkConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Request body of the email API
+    fn payload(&self, message: &Message) -> Result<serde_json::Value, MailError> {
+        let list = |addresses: &[Address]| {
+            addresses
+                .iter()
+                .map(|a| mailbox(a).map(|m| m.to_string()))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|list| list.join(", "))
+        };
+
+        let mut payload = json!({
+            "From": mailbox(&message.from)?.to_string(),
+            "To": list(&message.to)?,
+            "Subject": message.subject,
+            "Headers": message
+                .headers
+                .iter()
+                .map(|(name, value)| json!({ "Name": name, "Value": value }))
+                .collect::<Vec<_>>(),
+            "Attachments": message
+                .attachments
+                .iter()
+                .map(|a| {
+                    let mut attachment = json!({
+                        "Name": a.filename,
+                        "Content": BASE64.encode(&a.data),
+                        "ContentType": a.content_type,
+                    });
+                    if let Some(content_id) = &a.content_id {
+                        attachment["ContentID"] = json!(format!("cid:{}", content_id));
+                    }
+                    attachment
+                })
+                .collect::<Vec<_>>(),
+        });
+
+        if !message.cc.is_empty() {
+            payload["Cc"] = json!(list(&message.cc)?);
+        }
+        if !message.bcc.is_empty() {
+            payload["Bcc"] = json!(list(&message.bcc)?);
+        }
+        if let Some(reply_to) = &message.reply_to {
+            payload["ReplyTo"] = json!(mailbox(reply_to)?.to_string());
+        }
+        if let Some(html) = &message.html {
+            payload["HtmlBody"] = json!(html);
+        }
+        if let Some(text) = &message.text {
+            payload["TextBody"] = json!(text);
+        }
+        if let Some(stream) = &self.config.message_stream {
+            payload["MessageStream"] = json!(stream);
+        }
+        Ok(payload)
+    }
+}
+
+#[async_trait]
+impl Mailer for PostmarkMailer {
+    async fn send(&self, message: &Message) -> Result<(), MailError> {
+        let response = self
+            .client
+            .post(API_URL)
+            .header("Accept", "application/json")
+            .header("X-Postmark-Server-Token", &self.config.token)
+            .json(&self.payload(message)?)
+            .send()
+            .await?;
+        check_response("Postmark", response).await?;
+
+        tracing::info!(
+            to = ?message.to,
+            subject = %message.subject,
+            "Email sent via Postmark"
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Attachment, MessageBuilder};
+
+    #[test]
+    fn test_payload() {
+        let mailer = PostmarkMailer::new(PostmarkConfig {
+            token: "token".into(),
+            message_stream: Some("outbound".into()),
+        });
+
+        let message = MessageBuilder::new()
+            .from(Address::with_name("sender@example.com", "Sender"))
+            .to(Address::new("a@example.com"))
+            .to(Address::new("b@example.com"))
+            .subject("Test")
+            .html(r#"<img src="cid:logo">"#)
+            .embed(
+                "logo",
+                Attachment::new("logo.png", "image/png", b"png".to_vec()),
+            )
+            .header("X-Campaign", "launch")
+            .build()
+            .unwrap();
+
+        let payload = mailer.payload(&message).unwrap();
+        assert_eq!(payload["From"], "Sender <sender@example.com>");
+        assert_eq!(payload["To"], "a@example.com, b@example.com");
+        assert!(payload.get("Cc").is_none());
+        assert!(payload.get("TextBody").is_none());
+        assert_eq!(payload["MessageStream"], "outbound");
+        assert_eq!(
+            payload["Headers"],
+            json!([{ "Name": "X-Campaign", "Value": "launch" }])
+        );
+        assert_eq!(
+            payload["Attachments"],
+            json!([{
+                "Name": "logo.png",
+                "Content": "cG5n",
+                "ContentType": "image/png",
+                "ContentID": "cid:logo",
+            }])
+        );
+    }
+}
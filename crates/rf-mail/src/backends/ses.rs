@@ -0,0 +1,282 @@
+//! Amazon SES mailer backend
+
+use super::check_response;
+use super::smtp::{convert_to_lettre, mailbox};
+use crate::{MailError, Mailer, Message};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+const SEND_PATH: &str = "/v2/email/outbound-emails";
+
+/// Amazon SES configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SesConfig {
+    /// AWS region, e.g. `eu-central-1`
+    pub region: String,
+
+    /// AWS access key ID
+    pub access_key: String,
+
+    /// AWS secret access key
+    pub secret_key: String,
+
+    /// Session token of temporary credentials
+    #[serde(default)]
+    pub session_token: Option<String>,
+
+    /// Configuration set for event publishing
+    #[serde(default)]
+    pub configuration_set: Option<String>,
+
+    /// Custom API endpoint (default: `https://email.<region>.amazonaws.com`)
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+impl SesConfig {
+    /// Load from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`,
+    /// `AWS_SESSION_TOKEN`, `AWS_DEFAULT_REGION` and
+    /// `SES_CONFIGURATION_SET`
+    pub fn from_env() -> Result<Self, MailError> {
+        let required = |name: &str| {
+            std::env::var(name).map_err(|_| MailError::ConfigError(format!("{} is not set", name)))
+        };
+
+        Ok(Self {
+            region: std::env::var("AWS_DEFAULT_REGION").unwrap_or_else(|_| "us-east-1".into()),
+            access_key: required("AWS_ACCESS_KEY_ID")?,
+            secret_key: required("AWS_SECRET_ACCESS_KEY")?,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+            configuration_set: std::env::var("SES_CONFIGURATION_SET").ok(),
+            endpoint: None,
+        })
+    }
+}
+
+/// Amazon SES mailer backend, using the SES v2 API
+///
+/// Messages are sent as raw MIME, so attachments and custom headers are
+/// kept.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_mail::{SesConfig, SesMailer};
+///
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mailer = SesMailer::new(SesConfig::from_env()?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct SesMailer {
+    config: SesConfig,
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl SesMailer {
+    /// Create new SES mailer
+    pub fn new(config: SesConfig) -> Self {
+        let endpoint = config
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| format!("https://email.{}.amazonaws.com", config.region));
+
+        Self {
+            config,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Request body of the `SendEmail` call
+    fn payload(&self, message: &Message) -> Result<serde_json::Value, MailError> {
+        let raw = convert_to_lettre(message)?.formatted();
+        let emails = |addresses: &[crate::Address]| {
+            addresses
+                .iter()
+                .map(|a| mailbox(a).map(|m| m.to_string()))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let mut payload = json!({
+            "FromEmailAddress": mailbox(&message.from)?.to_string(),
+            // Listed explicitly, as Bcc recipients aren't in the raw message
+            "Destination": {
+                "ToAddresses": emails(&message.to)?,
+                "CcAddresses": emails(&message.cc)?,
+                "BccAddresses": emails(&message.bcc)?,
+            },
+            "Content": { "Raw": { "Data": BASE64.encode(raw) } },
+        });
+        if let Some(configuration_set) = &self.config.configuration_set {
+            payload["ConfigurationSetName"] = json!(configuration_set);
+        }
+        Ok(payload)
+    }
+
+    /// SigV4 headers for a `SendEmail` request with `body`
+    fn sign(&self, body: &[u8], now: DateTime<Utc>) -> Vec<(&'static str, String)> {
+        let host = self
+            .endpoint
+            .split_once("://")
+            .map_or(self.endpoint.as_str(), |(_, host)| host);
+        let date = now.format("%Y%m%d").to_string();
+        let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+        let mut headers = vec![
+            ("content-type", "application/json".to_string()),
+            ("host", host.to_string()),
+            ("x-amz-date", timestamp.clone()),
+        ];
+        if let Some(token) = &self.config.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_request = format!(
+            "POST\n{}\n\n{}\n{}\n{}",
+            SEND_PATH,
+            canonical_headers,
+            signed_headers,
+            hex::encode(Sha256::digest(body))
+        );
+
+        let scope = format!("{}/{}/ses/aws4_request", date, self.config.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            timestamp,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let signing_key = [date.as_str(), &self.config.region, "ses", "aws4_request"]
+            .iter()
+            .fold(
+                format!("AWS4{}", self.config.secret_key).into_bytes(),
+                |key, part| hmac(&key, part.as_bytes()),
+            );
+        let signature = hex::encode(hmac(&signing_key, string_to_sign.as_bytes()));
+
+        headers.retain(|(name, _)| *name != "host");
+        headers.push((
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                self.config.access_key, scope, signed_headers, signature
+            ),
+        ));
+        headers
+    }
+}
+
+#[async_trait]
+impl Mailer for SesMailer {
+    async fn send(&self, message: &Message) -> Result<(), MailError> {
+        let body = serde_json::to_vec(&self.payload(message)?)?;
+
+        let mut request = self.client.post(format!("{}{}", self.endpoint, SEND_PATH));
+        for (name, value) in self.sign(&body, Utc::now()) {
+            request = request.header(name, value);
+        }
+        check_response("SES", request.body(body).send().await?).await?;
+
+        tracing::info!(
+            to = ?message.to,
+            subject = %message.subject,
+            "Email sent via SES"
+        );
+
+        Ok(())
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, MessageBuilder};
+    use chrono::TimeZone;
+
+    fn mailer(session_token: Option<&str>) -> SesMailer {
+        SesMailer::new(SesConfig {
+            region: "eu-central-1".into(),
+            access_key: "AKIDEXAMPLE".into(),
+            secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+            session_token: session_token.map(String::from),
+            configuration_set: Some("tracking".into()),
+            endpoint: None,
+        })
+    }
+
+    #[test]
+    fn test_payload() {
+        let message = MessageBuilder::new()
+            .from(Address::with_name("sender@example.com", "Sender"))
+            .to(Address::new("to@example.com"))
+            .bcc(Address::new("bcc@example.com"))
+            .subject("Test")
+            .text("Hello")
+            .build()
+            .unwrap();
+
+        let payload = mailer(None).payload(&message).unwrap();
+        assert_eq!(payload["FromEmailAddress"], "Sender <sender@example.com>");
+        assert_eq!(
+            payload["Destination"]["ToAddresses"],
+            json!(["to@example.com"])
+        );
+        assert_eq!(
+            payload["Destination"]["BccAddresses"],
+            json!(["bcc@example.com"])
+        );
+        assert_eq!(payload["ConfigurationSetName"], "tracking");
+
+        let raw = BASE64
+            .decode(payload["Content"]["Raw"]["Data"].as_str().unwrap())
+            .unwrap();
+        assert!(String::from_utf8(raw).unwrap().contains("Subject: Test"));
+    }
+
+    #[test]
+    fn test_sign() {
+        let now = Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap();
+        let headers = mailer(Some("token")).sign(b"{}", now);
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v.as_str())
+        };
+
+        assert_eq!(header("x-amz-date"), Some("20240115T120000Z"));
+        assert_eq!(header("x-amz-security-token"), Some("token"));
+        assert_eq!(header("host"), None);
+
+        let authorization = header("authorization").unwrap();
+        assert!(authorization.starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240115/eu-central-1/ses/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date;x-amz-security-token, Signature="
+        ));
+        // Deterministic for the same request and time
+        assert_eq!(mailer(Some("token")).sign(b"{}", now), headers);
+        assert_ne!(mailer(Some("token")).sign(b"[]", now), headers);
+    }
+}
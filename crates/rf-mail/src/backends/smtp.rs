@@ -3,12 +3,30 @@
 use crate::{MailError, Mailer, Message};
 use async_trait::async_trait;
 use lettre::{
-    message::{header::ContentType, Mailbox, MultiPart, SinglePart},
+    message::{
+        header::{ContentType, HeaderName, HeaderValue},
+        Attachment as LettreAttachment, Mailbox, MultiPart, SinglePart,
+    },
     transport::smtp::authentication::Credentials,
     AsyncSmtpTransport, AsyncTransport, Message as LettreMessage, Tokio1Executor,
 };
 use serde::{Deserialize, Serialize};
 
+/// Connection security of an SMTP server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpEncryption {
+    /// Implicit TLS, usually port 465
+    #[default]
+    Tls,
+
+    /// Upgrade with STARTTLS, usually port 587
+    StartTls,
+
+    /// Plain text, for local servers like Mailpit only
+    None,
+}
+
 /// SMTP mailer configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SmtpConfig {
@@ -18,7 +36,11 @@ pub struct SmtpConfig {
     /// SMTP server port
     pub port: u16,
 
-    /// Username for authentication
+    /// Connection security
+    #[serde(default)]
+    pub encryption: SmtpEncryption,
+
+    /// Username for authentication (empty to skip authentication)
     pub username: String,
 
     /// Password for authentication
@@ -31,17 +53,72 @@ pub struct SmtpConfig {
     pub from_name: Option<String>,
 }
 
+impl SmtpConfig {
+    /// Load from `MAIL_HOST`, `MAIL_PORT`, `MAIL_ENCRYPTION` (`tls`,
+    /// `starttls` or `none`), `MAIL_USERNAME`, `MAIL_PASSWORD`,
+    /// `MAIL_FROM_ADDRESS` and `MAIL_FROM_NAME`
+    pub fn from_env() -> Result<Self, MailError> {
+        let encryption = match std::env::var("MAIL_ENCRYPTION").as_deref() {
+            Ok("tls") | Err(_) => SmtpEncryption::Tls,
+            Ok("starttls") => SmtpEncryption::StartTls,
+            Ok("none") | Ok("") => SmtpEncryption::None,
+            Ok(other) => {
+                return Err(MailError::ConfigError(format!(
+                    "Unknown MAIL_ENCRYPTION: {}",
+                    other
+                )))
+            }
+        };
+        let port = match std::env::var("MAIL_PORT") {
+            Ok(port) => port
+                .parse()
+                .map_err(|_| MailError::ConfigError(format!("Invalid MAIL_PORT: {}", port)))?,
+            Err(_) => match encryption {
+                SmtpEncryption::Tls => 465,
+                SmtpEncryption::StartTls => 587,
+                SmtpEncryption::None => 25,
+            },
+        };
+
+        Ok(Self {
+            host: std::env::var("MAIL_HOST").unwrap_or_else(|_| "localhost".into()),
+            port,
+            encryption,
+            username: std::env::var("MAIL_USERNAME").unwrap_or_default(),
+            password: std::env::var("MAIL_PASSWORD").unwrap_or_default(),
+            from_address: std::env::var("MAIL_FROM_ADDRESS")
+                .unwrap_or_else(|_| "noreply@example.com".into()),
+            from_name: std::env::var("MAIL_FROM_NAME").ok(),
+        })
+    }
+
+    /// Local [Mailpit](https://mailpit.axllent.org) instance, catching all
+    /// mail for development on `localhost:1025`
+    pub fn mailpit() -> Self {
+        Self {
+            host: "localhost".into(),
+            port: 1025,
+            encryption: SmtpEncryption::None,
+            username: String::new(),
+            password: String::new(),
+            from_address: "noreply@example.com".into(),
+            from_name: None,
+        }
+    }
+}
+
 /// SMTP mailer backend
 ///
 /// # Example
 ///
 /// ```no_run
-/// use rf_mail::{SmtpMailer, SmtpConfig, Mailer, MessageBuilder, Address};
+/// use rf_mail::{SmtpMailer, SmtpConfig, SmtpEncryption, Mailer, MessageBuilder, Address};
 ///
 /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
 /// let config = SmtpConfig {
 ///     host: "smtp.gmail.com".into(),
 ///     port: 587,
+///     encryption: SmtpEncryption::StartTls,
 ///     username: "user@gmail.com".into(),
 ///     password: "app_password".into(),
 ///     from_address: "noreply@example.com".into(),
@@ -68,14 +145,27 @@ pub struct SmtpMailer {
 impl SmtpMailer {
     /// Create new SMTP mailer
     pub async fn new(config: SmtpConfig) -> Result<Self, MailError> {
-        let credentials = Credentials::new(config.username.clone(), config.password.clone());
+        let builder = match config.encryption {
+            SmtpEncryption::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+            SmtpEncryption::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+            }
+            SmtpEncryption::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+            }
+        };
 
-        let transport = AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?
-            .port(config.port)
-            .credentials(credentials)
-            .build();
+        let mut builder = builder.port(config.port);
+        if !config.username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                config.username.clone(),
+                config.password.clone(),
+            ));
+        }
 
-        Ok(Self { transport })
+        Ok(Self {
+            transport: builder.build(),
+        })
     }
 }
 
@@ -97,97 +187,117 @@ impl Mailer for SmtpMailer {
 }
 
 /// Convert our Message to lettre's Message
-fn convert_to_lettre(message: &Message) -> Result<LettreMessage, MailError> {
-    // Parse from address
-    let from: Mailbox = if let Some(name) = &message.from.name {
-        format!("{} <{}>", name, message.from.email).parse()?
-    } else {
-        message.from.email.parse()?
-    };
+pub(crate) fn convert_to_lettre(message: &Message) -> Result<LettreMessage, MailError> {
+    let mut builder = LettreMessage::builder().from(mailbox(&message.from)?);
 
-    let mut builder = LettreMessage::builder().from(from);
-
-    // Add To addresses
     for to in &message.to {
-        let mailbox: Mailbox = if let Some(name) = &to.name {
-            format!("{} <{}>", name, to.email).parse()?
-        } else {
-            to.email.parse()?
-        };
-        builder = builder.to(mailbox);
+        builder = builder.to(mailbox(to)?);
     }
-
-    // Add CC addresses
     for cc in &message.cc {
-        let mailbox: Mailbox = if let Some(name) = &cc.name {
-            format!("{} <{}>", name, cc.email).parse()?
-        } else {
-            cc.email.parse()?
-        };
-        builder = builder.cc(mailbox);
+        builder = builder.cc(mailbox(cc)?);
     }
-
-    // Add BCC addresses
     for bcc in &message.bcc {
-        let mailbox: Mailbox = if let Some(name) = &bcc.name {
-            format!("{} <{}>", name, bcc.email).parse()?
-        } else {
-            bcc.email.parse()?
-        };
-        builder = builder.bcc(mailbox);
+        builder = builder.bcc(mailbox(bcc)?);
     }
-
-    // Add reply-to
     if let Some(reply_to) = &message.reply_to {
-        let mailbox: Mailbox = if let Some(name) = &reply_to.name {
-            format!("{} <{}>", name, reply_to.email).parse()?
-        } else {
-            reply_to.email.parse()?
-        };
-        builder = builder.reply_to(mailbox);
+        builder = builder.reply_to(mailbox(reply_to)?);
     }
 
     // Add subject
     builder = builder.subject(&message.subject);
 
-    // Build body (multipart if both HTML and text)
-    let lettre_message = match (&message.html, &message.text) {
-        (Some(html), Some(text)) => {
-            // Multipart: both HTML and text
-            builder.multipart(
-                MultiPart::alternative()
-                    .singlepart(
-                        SinglePart::builder()
-                            .header(ContentType::TEXT_PLAIN)
-                            .body(text.clone()),
-                    )
-                    .singlepart(
-                        SinglePart::builder()
-                            .header(ContentType::TEXT_HTML)
-                            .body(html.clone()),
-                    ),
-            )?
-        }
-        (Some(html), None) => {
-            // HTML only
-            builder.body(html.clone())?
-        }
-        (None, Some(text)) => {
-            // Text only
-            builder.body(text.clone())?
+    // Add custom headers
+    for (name, value) in &message.headers {
+        let name = HeaderName::new_from_ascii(name.clone())
+            .map_err(|_| MailError::InvalidMessage(format!("Invalid header name: {}", name)))?;
+        builder = builder.raw_header(HeaderValue::new(name, value.clone()));
+    }
+
+    let (inline, attachments): (Vec<_>, Vec<_>) =
+        message.attachments.iter().partition(|a| a.is_inline());
+
+    // HTML with the images it embeds
+    let html = match &message.html {
+        Some(html) if !inline.is_empty() => {
+            let mut related = MultiPart::related().singlepart(SinglePart::html(html.clone()));
+            for attachment in inline {
+                related = related.singlepart(lettre_attachment(attachment)?);
+            }
+            Some(Part::Multi(related))
         }
+        Some(html) => Some(Part::Single(SinglePart::html(html.clone()))),
+        None => None,
+    };
+
+    // Build body (multipart if both HTML and text)
+    let body = match (html, &message.text) {
+        (Some(html), Some(text)) => Part::Multi(
+            html.append_to(MultiPart::alternative().singlepart(SinglePart::plain(text.clone()))),
+        ),
+        (Some(html), None) => html,
+        (None, Some(text)) => Part::Single(SinglePart::plain(text.clone())),
         (None, None) => {
             return Err(MailError::InvalidMessage("No body content".into()));
         }
     };
 
+    // Regular attachments follow the body
+    let body = if attachments.is_empty() {
+        body
+    } else {
+        let mut mixed = body.append_to(MultiPart::mixed().build());
+        for attachment in attachments {
+            mixed = mixed.singlepart(lettre_attachment(attachment)?);
+        }
+        Part::Multi(mixed)
+    };
+
+    let lettre_message = match body {
+        Part::Single(part) => builder.singlepart(part)?,
+        Part::Multi(part) => builder.multipart(part)?,
+    };
+
     Ok(lettre_message)
 }
 
+/// Convert an address to a mailbox, quoting the name as needed
+pub(crate) fn mailbox(address: &crate::Address) -> Result<Mailbox, MailError> {
+    Ok(Mailbox::new(address.name.clone(), address.email.parse()?))
+}
+
+/// MIME part that is either single or nested multipart
+enum Part {
+    Single(SinglePart),
+    Multi(MultiPart),
+}
+
+impl Part {
+    fn append_to(self, multipart: MultiPart) -> MultiPart {
+        match self {
+            Part::Single(part) => multipart.singlepart(part),
+            Part::Multi(part) => multipart.multipart(part),
+        }
+    }
+}
+
+fn lettre_attachment(attachment: &crate::Attachment) -> Result<SinglePart, MailError> {
+    let content_type = ContentType::parse(&attachment.content_type).map_err(|_| {
+        MailError::InvalidMessage(format!("Invalid content type: {}", attachment.content_type))
+    })?;
+
+    let builder = match &attachment.content_id {
+        Some(content_id) => {
+            LettreAttachment::new_inline_with_name(content_id.clone(), attachment.filename.clone())
+        }
+        None => LettreAttachment::new(attachment.filename.clone()),
+    };
+    Ok(builder.body(attachment.data.clone(), content_type))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Address, MessageBuilder};
+    use crate::{Address, Attachment, MessageBuilder};
 
     #[test]
     fn test_convert_to_lettre() {
@@ -217,4 +327,52 @@ mod tests {
         let lettre_msg = convert_to_lettre(&message);
         assert!(lettre_msg.is_ok());
     }
+
+    #[test]
+    fn test_convert_attachments() {
+        let message = MessageBuilder::new()
+            .from(Address::new("sender@example.com"))
+            .to(Address::new("recipient@example.com"))
+            .subject("Invoice")
+            .html(r#"<img src="cid:logo"> Your invoice"#)
+            .text("Your invoice")
+            .embed("logo", Attachment::new("logo.png", "image/png", vec![1, 2]))
+            .attach(Attachment::new(
+                "invoice.pdf",
+                "application/pdf",
+                vec![3, 4],
+            ))
+            .header("X-Campaign", "billing")
+            .build()
+            .unwrap();
+
+        let raw = String::from_utf8(convert_to_lettre(&message).unwrap().formatted()).unwrap();
+        assert!(raw.contains("multipart/mixed"));
+        assert!(raw.contains("multipart/alternative"));
+        assert!(raw.contains("multipart/related"));
+        assert!(raw.contains("Content-ID: <logo>"));
+        assert!(raw.contains("Content-Disposition: attachment; filename=\"invoice.pdf\""));
+        assert!(raw.contains("X-Campaign: billing"));
+    }
+
+    #[test]
+    fn test_convert_html_only() {
+        let message = MessageBuilder::new()
+            .from(Address::new("sender@example.com"))
+            .to(Address::new("recipient@example.com"))
+            .subject("Test")
+            .html("<h1>Hello</h1>")
+            .build()
+            .unwrap();
+
+        let raw = String::from_utf8(convert_to_lettre(&message).unwrap().formatted()).unwrap();
+        assert!(raw.contains("Content-Type: text/html"));
+    }
+
+    #[test]
+    fn test_mailpit_config() {
+        let config = SmtpConfig::mailpit();
+        assert_eq!((config.host.as_str(), config.port), ("localhost", 1025));
+        assert_eq!(config.encryption, SmtpEncryption::None);
+    }
 }
@@ -91,6 +91,28 @@ impl MessageBuilder {
         self
     }
 
+    /// Embed an attachment in the HTML body, referenced as
+    /// `cid:<content_id>`
+    ///
+    /// ```
+    /// use rf_mail::{Address, Attachment, MessageBuilder};
+    ///
+    /// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let message = MessageBuilder::new()
+    ///     .from(Address::new("sender@example.com"))
+    ///     .to(Address::new("recipient@example.com"))
+    ///     .subject("Hello!")
+    ///     .html(r#"<img src="cid:logo"> Hello!"#)
+    ///     .embed("logo", Attachment::new("logo.png", "image/png", vec![0x89, 0x50]))
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn embed(mut self, content_id: impl Into<String>, attachment: Attachment) -> Self {
+        self.message.attachments.push(attachment.inline(content_id));
+        self
+    }
+
     /// Add custom header
     pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.message.headers.insert(key.into(), value.into());
@@ -101,7 +123,7 @@ impl MessageBuilder {
     pub fn build(self) -> Result<Message, MailError> {
         self.message
             .validate()
-            .map_err(|e| MailError::InvalidMessage(e))?;
+            .map_err(MailError::InvalidMessage)?;
 
         Ok(self.message)
     }
@@ -170,4 +192,21 @@ mod tests {
 
         assert_eq!(message.headers.get("X-Custom"), Some(&"value".to_string()));
     }
+
+    #[test]
+    fn test_builder_embed() {
+        let builder = || {
+            MessageBuilder::new()
+                .from(Address::new("sender@example.com"))
+                .to(Address::new("recipient@example.com"))
+                .subject("Test")
+                .embed("logo", Attachment::new("logo.png", "image/png", vec![1]))
+        };
+
+        let message = builder().html(r#"<img src="cid:logo">"#).build().unwrap();
+        assert_eq!(message.attachments[0].content_id, Some("logo".to_string()));
+
+        // Nothing to embed the image in
+        assert!(builder().text("Hello").build().is_err());
+    }
 }
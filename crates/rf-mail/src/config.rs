@@ -0,0 +1,167 @@
+//! Mail driver configuration
+
+#[cfg(feature = "mailgun")]
+use crate::backends::MailgunConfig;
+#[cfg(feature = "postmark")]
+use crate::backends::PostmarkConfig;
+#[cfg(feature = "ses")]
+use crate::backends::SesConfig;
+use crate::backends::{LogMailer, MemoryMailer, SmtpConfig, SmtpMailer};
+use crate::{MailError, Mailer};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Which mailer backend to use, with its settings
+///
+/// # Example
+///
+/// ```
+/// use rf_mail::MailConfig;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// // MAIL_DRIVER=log
+/// let mailer = MailConfig::from_env()?.mailer().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "driver", rename_all = "lowercase")]
+pub enum MailConfig {
+    /// SMTP server
+    Smtp(SmtpConfig),
+
+    /// Amazon SES API
+    #[cfg(feature = "ses")]
+    Ses(SesConfig),
+
+    /// Mailgun API
+    #[cfg(feature = "mailgun")]
+    Mailgun(MailgunConfig),
+
+    /// Postmark API
+    #[cfg(feature = "postmark")]
+    Postmark(PostmarkConfig),
+
+    /// Log messages, optionally writing `.eml` files to a directory
+    Log {
+        #[serde(default)]
+        directory: Option<PathBuf>,
+    },
+
+    /// Keep messages in memory
+    Memory,
+}
+
+impl MailConfig {
+    /// Load from `MAIL_DRIVER` and the driver's variables
+    ///
+    /// Drivers are `smtp` (default), `ses`, `mailgun`, `postmark`, `log`
+    /// (with `MAIL_LOG_PATH`), `mailpit` (SMTP on `localhost:1025` unless
+    /// `MAIL_HOST`/`MAIL_PORT` are set) and `memory`.
+    pub fn from_env() -> Result<Self, MailError> {
+        let driver = std::env::var("MAIL_DRIVER").unwrap_or_else(|_| "smtp".into());
+
+        match driver.as_str() {
+            "smtp" => Ok(MailConfig::Smtp(SmtpConfig::from_env()?)),
+            "mailpit" => {
+                let mut config = SmtpConfig::mailpit();
+                if let Ok(host) = std::env::var("MAIL_HOST") {
+                    config.host = host;
+                }
+                if let Ok(port) = std::env::var("MAIL_PORT") {
+                    config.port = port.parse().map_err(|_| {
+                        MailError::ConfigError(format!("Invalid MAIL_PORT: {}", port))
+                    })?;
+                }
+                if let Ok(from_address) = std::env::var("MAIL_FROM_ADDRESS") {
+                    config.from_address = from_address;
+                }
+                config.from_name = std::env::var("MAIL_FROM_NAME").ok();
+                Ok(MailConfig::Smtp(config))
+            }
+            #[cfg(feature = "ses")]
+            "ses" => Ok(MailConfig::Ses(SesConfig::from_env()?)),
+            #[cfg(feature = "mailgun")]
+            "mailgun" => Ok(MailConfig::Mailgun(MailgunConfig::from_env()?)),
+            #[cfg(feature = "postmark")]
+            "postmark" => Ok(MailConfig::Postmark(PostmarkConfig::from_env()?)),
+            "log" => Ok(MailConfig::Log {
+                directory: std::env::var("MAIL_LOG_PATH").ok().map(PathBuf::from),
+            }),
+            "memory" => Ok(MailConfig::Memory),
+            other if ["ses", "mailgun", "postmark"].contains(&other) => {
+                Err(MailError::ConfigError(format!(
+                    "rf-mail was built without the `{}` feature",
+                    other
+                )))
+            }
+            other => Err(MailError::ConfigError(format!(
+                "Unknown MAIL_DRIVER: {}",
+                other
+            ))),
+        }
+    }
+
+    /// Create the configured mailer
+    pub async fn mailer(&self) -> Result<Arc<dyn Mailer>, MailError> {
+        Ok(match self {
+            MailConfig::Smtp(config) => Arc::new(SmtpMailer::new(config.clone()).await?),
+            #[cfg(feature = "ses")]
+            MailConfig::Ses(config) => Arc::new(crate::backends::SesMailer::new(config.clone())),
+            #[cfg(feature = "mailgun")]
+            MailConfig::Mailgun(config) => {
+                Arc::new(crate::backends::MailgunMailer::new(config.clone()))
+            }
+            #[cfg(feature = "postmark")]
+            MailConfig::Postmark(config) => {
+                Arc::new(crate::backends::PostmarkMailer::new(config.clone()))
+            }
+            MailConfig::Log { directory } => {
+                let mut mailer = LogMailer::new();
+                if let Some(directory) = directory {
+                    mailer = mailer.directory(directory);
+                }
+                Arc::new(mailer)
+            }
+            MailConfig::Memory => Arc::new(MemoryMailer::new()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SmtpEncryption;
+
+    #[test]
+    fn test_deserialize() {
+        let config: MailConfig = serde_json::from_value(serde_json::json!({
+            "driver": "smtp",
+            "host": "smtp.example.com",
+            "port": 587,
+            "encryption": "starttls",
+            "username": "user",
+            "password": "secret",
+            "from_address": "noreply@example.com",
+            "from_name": null,
+        }))
+        .unwrap();
+
+        match config {
+            MailConfig::Smtp(smtp) => assert_eq!(smtp.encryption, SmtpEncryption::StartTls),
+            other => panic!("unexpected config: {:?}", other),
+        }
+
+        let config: MailConfig =
+            serde_json::from_value(serde_json::json!({ "driver": "log" })).unwrap();
+        assert!(matches!(config, MailConfig::Log { directory: None }));
+    }
+
+    #[tokio::test]
+    async fn test_mailer() {
+        let mailer = MailConfig::Smtp(SmtpConfig::mailpit()).mailer().await;
+        assert!(mailer.is_ok());
+        assert!(MailConfig::Memory.mailer().await.is_ok());
+    }
+}
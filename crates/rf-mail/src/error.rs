@@ -24,6 +24,10 @@ pub enum MailError {
     #[error("Template registration error: {0}")]
     TemplateError(#[from] handlebars::TemplateError),
 
+    /// MJML compilation error
+    #[error("MJML error: {0}")]
+    MjmlError(String),
+
     /// SMTP transport error
     #[error("SMTP error: {0}")]
     SmtpError(#[from] lettre::error::Error),
@@ -36,6 +40,11 @@ pub enum MailError {
     #[error("Address parse error: {0}")]
     AddressError(#[from] lettre::address::AddressError),
 
+    /// HTTP error talking to a mail API
+    #[cfg(any(feature = "ses", feature = "mailgun", feature = "postmark"))]
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
     /// IO error
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
@@ -49,6 +58,19 @@ pub enum MailError {
     ConfigError(String),
 }
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(MailError, {
+    Self::InvalidMessage(_) => (UNPROCESSABLE_ENTITY, "mail.invalid_message"),
+    Self::AddressError(_) => (UNPROCESSABLE_ENTITY, "mail.invalid_address"),
+    Self::SendFailed(_) => (BAD_GATEWAY, "mail.send_failed"),
+    Self::SmtpError(_) | Self::SmtpTransportError(_) => (BAD_GATEWAY, "mail.send_failed"),
+    Self::TemplateRenderError(_) => (INTERNAL_SERVER_ERROR, "mail.template"),
+    Self::TemplateError(_) | Self::MjmlError(_) => (INTERNAL_SERVER_ERROR, "mail.template"),
+    Self::ConfigError(_) => (INTERNAL_SERVER_ERROR, "mail.config"),
+    // I/O, serialization, and HTTP errors of API transports
+    _ => (INTERNAL_SERVER_ERROR, "mail.internal"),
+});
+
 // Implement Send + Sync for compatibility with async traits
 unsafe impl Send for MailError {}
 unsafe impl Sync for MailError {}
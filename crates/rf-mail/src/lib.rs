@@ -2,12 +2,16 @@
 //!
 //! # Features
 //!
-//! - Multiple backend support (SMTP, Memory, Mock)
+//! - Multiple backend support (SMTP, Amazon SES, Mailgun, Postmark, Log,
+//!   Memory, Mock), selected by [`MailConfig`]
 //! - Message builder with fluent API
+//! - Attachments and images embedded in the HTML body
 //! - Mailable trait for reusable email types
-//! - Template rendering with Handlebars
+//! - Template rendering with Handlebars and MJML
 //! - Common email types (Welcome, Password Reset)
-//! - Testing support with Memory and Mock backends
+//! - Testing support with the Fake, Memory and Mock backends
+//!
+//! The API drivers are behind the `ses`, `mailgun` and `postmark` features.
 //!
 //! # Quick Start
 //!
@@ -48,11 +52,88 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! # Templates and Embedded Images
+//!
+//! ```
+//! use async_trait::async_trait;
+//! use rf_mail::{
+//!     Address, Attachment, MailError, Mailable, Message, MessageBuilder, TemplateEngine,
+//! };
+//! use serde_json::json;
+//! use std::sync::Arc;
+//!
+//! struct InvoiceEmail {
+//!     templates: Arc<TemplateEngine>,
+//!     to: Address,
+//!     invoice: Vec<u8>,
+//! }
+//!
+//! #[async_trait]
+//! impl Mailable for InvoiceEmail {
+//!     async fn build(&self) -> Result<Message, MailError> {
+//!         let data = json!({"name": self.to.name});
+//!
+//!         MessageBuilder::new()
+//!             .from(Address::new("billing@example.com"))
+//!             .to(self.to.clone())
+//!             .subject("Your invoice")
+//!             .html(self.templates.render_html("invoice", &data).await?)
+//!             .text(self.templates.render("invoice_text", &data)?)
+//!             .embed("logo", Attachment::new("logo.png", "image/png", vec![0x89, 0x50]))
+//!             .attach(Attachment::new("invoice.pdf", "application/pdf", self.invoice.clone()))
+//!             .build()
+//!     }
+//! }
+//! ```
+//!
+//! # Drivers
+//!
+//! ```no_run
+//! use rf_mail::{MailConfig, Mailable, WelcomeEmail, Address};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! // MAIL_DRIVER=smtp|ses|mailgun|postmark|log|mailpit|memory
+//! let mailer = MailConfig::from_env()?.mailer().await?;
+//!
+//! WelcomeEmail {
+//!     to: Address::new("user@example.com"),
+//!     user_name: "John".into(),
+//!     app_name: "MyApp".into(),
+//! }
+//! .send(mailer.as_ref())
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Testing
+//!
+//! ```
+//! use rf_mail::{FakeMailer, Mailable, WelcomeEmail, Address};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let mailer = FakeMailer::new();
+//!
+//! WelcomeEmail {
+//!     to: Address::new("user@example.com"),
+//!     user_name: "John".into(),
+//!     app_name: "MyApp".into(),
+//! }
+//! .send(&mailer)
+//! .await?;
+//!
+//! mailer.assert_sent_to("user@example.com");
+//! mailer.assert_sent(|m| m.subject == "Welcome to MyApp!");
+//! # Ok(())
+//! # }
+//! ```
 
 mod address;
 mod attachment;
 mod backends;
 mod builder;
+mod config;
 mod error;
 pub mod mailables;
 mod mailer;
@@ -62,8 +143,17 @@ mod templates;
 // Re-exports
 pub use address::Address;
 pub use attachment::Attachment;
-pub use backends::{MemoryMailer, MockMailer, SmtpConfig, SmtpMailer};
+#[cfg(feature = "mailgun")]
+pub use backends::{MailgunConfig, MailgunMailer};
+#[cfg(feature = "postmark")]
+pub use backends::{PostmarkConfig, PostmarkMailer};
+#[cfg(feature = "ses")]
+pub use backends::{SesConfig, SesMailer};
+pub use backends::{
+    FakeMailer, LogMailer, MemoryMailer, MockMailer, SmtpConfig, SmtpEncryption, SmtpMailer,
+};
 pub use builder::MessageBuilder;
+pub use config::MailConfig;
 pub use error::{MailError, MailResult};
 pub use mailables::{PasswordResetEmail, WelcomeEmail};
 pub use mailer::{Mailable, Mailer};
@@ -73,6 +73,9 @@ impl Message {
         if self.html.is_none() && self.text.is_none() {
             return Err("Either HTML or text body is required".into());
         }
+        if self.html.is_none() && self.attachments.iter().any(|a| a.is_inline()) {
+            return Err("Inline attachments require an HTML body".into());
+        }
 
         Ok(())
     }
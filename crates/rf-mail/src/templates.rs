@@ -3,7 +3,11 @@
 use crate::MailError;
 use handlebars::Handlebars;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
 
 /// Template engine for email rendering
 ///
@@ -25,8 +29,35 @@ use std::collections::HashMap;
 /// # Ok(())
 /// # }
 /// ```
+///
+/// # MJML
+///
+/// Templates registered with [`register_mjml`](Self::register_mjml) are
+/// Handlebars templates producing [MJML](https://mjml.io), which
+/// [`render_html`](Self::render_html) compiles to responsive HTML with the
+/// `mjml` command line tool (`npm install -g mjml`).
+///
+/// ```no_run
+/// use rf_mail::TemplateEngine;
+/// use serde_json::json;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut engine = TemplateEngine::new();
+/// engine.register_mjml(
+///     "welcome",
+///     "<mjml><mj-body><mj-section><mj-column>\
+///      <mj-text>Hello, {{name}}!</mj-text>\
+///      </mj-column></mj-section></mj-body></mjml>",
+/// )?;
+///
+/// let html = engine.render_html("welcome", &json!({"name": "Alice"})).await?;
+/// # Ok(())
+/// # }
+/// ```
 pub struct TemplateEngine {
     handlebars: Handlebars<'static>,
+    mjml: HashSet<String>,
+    mjml_binary: PathBuf,
 }
 
 impl TemplateEngine {
@@ -34,9 +65,17 @@ impl TemplateEngine {
     pub fn new() -> Self {
         Self {
             handlebars: Handlebars::new(),
+            mjml: HashSet::new(),
+            mjml_binary: PathBuf::from("mjml"),
         }
     }
 
+    /// Path of the `mjml` binary (default: `mjml` from `PATH`)
+    pub fn with_mjml_binary(mut self, path: impl Into<PathBuf>) -> Self {
+        self.mjml_binary = path.into();
+        self
+    }
+
     /// Register a template by name
     ///
     /// # Example
@@ -56,6 +95,36 @@ impl TemplateEngine {
         Ok(())
     }
 
+    /// Register an MJML template by name
+    pub fn register_mjml(&mut self, name: &str, template: &str) -> Result<(), MailError> {
+        self.register_template(name, template)?;
+        self.mjml.insert(name.to_string());
+        Ok(())
+    }
+
+    /// Register a template from a file
+    ///
+    /// Files ending in `.mjml` are registered as MJML templates.
+    pub async fn register_template_file(
+        &mut self,
+        name: &str,
+        path: impl AsRef<Path>,
+    ) -> Result<(), MailError> {
+        let path = path.as_ref();
+        let template = tokio::fs::read_to_string(path).await?;
+
+        if path.extension().is_some_and(|ext| ext == "mjml") {
+            self.register_mjml(name, &template)
+        } else {
+            self.register_template(name, &template)
+        }
+    }
+
+    /// Check if a template is an MJML template
+    pub fn is_mjml(&self, name: &str) -> bool {
+        self.mjml.contains(name)
+    }
+
     /// Register multiple templates at once
     pub fn register_templates(
         &mut self,
@@ -88,6 +157,49 @@ impl TemplateEngine {
     pub fn render<T: Serialize>(&self, name: &str, data: &T) -> Result<String, MailError> {
         Ok(self.handlebars.render(name, data)?)
     }
+
+    /// Render a template to HTML, compiling MJML templates
+    pub async fn render_html<T: Serialize>(
+        &self,
+        name: &str,
+        data: &T,
+    ) -> Result<String, MailError> {
+        let rendered = self.render(name, data)?;
+        if !self.is_mjml(name) {
+            return Ok(rendered);
+        }
+        self.compile_mjml(&rendered).await
+    }
+
+    async fn compile_mjml(&self, mjml: &str) -> Result<String, MailError> {
+        let mut child = Command::new(&self.mjml_binary)
+            .args(["--stdin", "--stdout"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                MailError::MjmlError(format!(
+                    "failed to run {}: {}",
+                    self.mjml_binary.display(),
+                    e
+                ))
+            })?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(mjml.as_bytes()).await?;
+        }
+
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            return Err(MailError::MjmlError(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        String::from_utf8(output.stdout).map_err(|e| MailError::MjmlError(e.to_string()))
+    }
 }
 
 impl Default for TemplateEngine {
@@ -140,4 +252,23 @@ mod tests {
         assert!(result.contains("Alice"));
         assert!(result.contains("alice@example.com"));
     }
+
+    #[tokio::test]
+    async fn test_render_html() {
+        let mut engine = TemplateEngine::new().with_mjml_binary("/nonexistent/mjml");
+
+        engine.register_template("plain", "<p>{{name}}</p>").unwrap();
+        engine
+            .register_mjml("fancy", "<mjml><mj-body>{{name}}</mj-body></mjml>")
+            .unwrap();
+        assert!(!engine.is_mjml("plain"));
+        assert!(engine.is_mjml("fancy"));
+
+        let data = json!({"name": "Alice"});
+        assert_eq!(engine.render_html("plain", &data).await.unwrap(), "<p>Alice</p>");
+        assert!(matches!(
+            engine.render_html("fancy", &data).await,
+            Err(MailError::MjmlError(_))
+        ));
+    }
 }
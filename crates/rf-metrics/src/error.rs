@@ -0,0 +1,18 @@
+//! Error types for metrics
+
+use thiserror::Error;
+
+/// Metrics errors
+#[derive(Debug, Error)]
+pub enum MetricsError {
+    #[error("Prometheus error: {0}")]
+    Prometheus(#[from] prometheus::Error),
+
+    /// A metric with the name is already registered with another type or
+    /// other labels
+    #[error("Metric {0} is already registered with another type or labels")]
+    Conflict(String),
+}
+
+/// Result type for metrics
+pub type MetricsResult<T> = Result<T, MetricsError>;
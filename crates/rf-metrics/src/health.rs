@@ -0,0 +1,45 @@
+//! Health probes next to the metrics endpoint
+
+use crate::Metrics;
+use axum::Router;
+use rf_health::{health_router, HealthChecker};
+
+/// Router serving `/metrics` and the health probes `/health`,
+/// `/health/live` and `/health/ready` that rf-deploy's Kubernetes manifests
+/// point at
+///
+/// # Example
+///
+/// ```
+/// use rf_metrics::{observability_router, Metrics};
+/// use rf_metrics::health::{checks::MemoryCheck, HealthChecker};
+///
+/// let checker = HealthChecker::new().add_check(MemoryCheck::default());
+/// let app: axum::Router = observability_router(&Metrics::new(), checker);
+/// ```
+pub fn observability_router(metrics: &Metrics, checker: HealthChecker) -> Router {
+    metrics.router().merge(health_router(checker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request, http::StatusCode};
+    use rf_health::checks::AlwaysHealthyCheck;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_observability_router() {
+        let checker = HealthChecker::new().add_check(AlwaysHealthyCheck::new("app"));
+        let app = observability_router(&Metrics::new(), checker);
+
+        for uri in ["/metrics", "/health", "/health/live", "/health/ready"] {
+            let response = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! HTTP request metrics middleware
+
+use crate::{Metrics, MetricsResult};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use prometheus::{CounterVec, Gauge, HistogramVec};
+use std::time::Instant;
+
+/// Label of requests that matched no route, keeping unknown paths from
+/// creating a time series each
+pub const UNMATCHED_PATH: &str = "unmatched";
+
+/// Request count, latency and in-flight requests per route
+///
+/// Records `http_requests_total` and `http_request_duration_seconds` labeled
+/// by method, route pattern (e.g. `/users/{id}`) and status, plus
+/// `http_requests_in_flight`.
+///
+/// # Example
+///
+/// ```
+/// use axum::{middleware, routing::get, Router};
+/// use rf_metrics::{track_http_metrics, HttpMetrics, Metrics};
+///
+/// let metrics = Metrics::new();
+/// let http_metrics = HttpMetrics::new(&metrics)?;
+///
+/// let app: Router = Router::new()
+///     .route("/users/{id}", get(|| async { "user" }))
+///     .layer(middleware::from_fn_with_state(http_metrics, track_http_metrics))
+///     .merge(metrics.router());
+/// # Ok::<(), rf_metrics::MetricsError>(())
+/// ```
+#[derive(Clone)]
+pub struct HttpMetrics {
+    requests: CounterVec,
+    duration: HistogramVec,
+    in_flight: Gauge,
+}
+
+impl HttpMetrics {
+    /// Register the HTTP metrics on `metrics`
+    pub fn new(metrics: &Metrics) -> MetricsResult<Self> {
+        let labels = &["method", "path", "status"];
+        Ok(Self {
+            requests: metrics.counter_vec(
+                "http_requests_total",
+                "Total number of HTTP requests",
+                labels,
+            )?,
+            duration: metrics.histogram_vec(
+                "http_request_duration_seconds",
+                "HTTP request duration in seconds",
+                labels,
+            )?,
+            in_flight: metrics.gauge(
+                "http_requests_in_flight",
+                "Number of HTTP requests being served",
+            )?,
+        })
+    }
+
+    /// Record a finished request
+    pub fn record(&self, method: &str, path: &str, status: u16, seconds: f64) {
+        let status = status.to_string();
+        let labels = [method, path, status.as_str()];
+        self.requests.with_label_values(&labels).inc();
+        self.duration.with_label_values(&labels).observe(seconds);
+    }
+
+    pub(crate) async fn track(&self, req: Request, next: Next) -> Response {
+        let start = Instant::now();
+        let method = req.method().clone();
+        let path = req
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|p| p.as_str().to_string())
+            .unwrap_or_else(|| UNMATCHED_PATH.to_string());
+
+        self.in_flight.inc();
+        let response = next.run(req).await;
+        self.in_flight.dec();
+
+        self.record(
+            method.as_str(),
+            &path,
+            response.status().as_u16(),
+            start.elapsed().as_secs_f64(),
+        );
+        response
+    }
+}
+
+/// Middleware recording [`HttpMetrics`], for
+/// [`from_fn_with_state`](axum::middleware::from_fn_with_state)
+pub async fn track_http_metrics(
+    State(metrics): State<HttpMetrics>,
+    req: Request,
+    next: Next,
+) -> Response {
+    metrics.track(req, next).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_records_route_patterns() {
+        let metrics = Metrics::new();
+        let app = Router::new()
+            .route("/users/{id}", get(|| async { "user" }))
+            .layer(middleware::from_fn_with_state(
+                HttpMetrics::new(&metrics).unwrap(),
+                track_http_metrics,
+            ));
+
+        for uri in ["/users/1", "/users/2", "/missing"] {
+            app.clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+        }
+
+        let output = metrics.render().unwrap();
+        assert!(output
+            .contains(r#"http_requests_total{method="GET",path="/users/{id}",status="200"} 2"#));
+        assert!(
+            output.contains(r#"http_requests_total{method="GET",path="unmatched",status="404"} 1"#)
+        );
+        assert!(output.contains("http_requests_in_flight 0"));
+
+        let response = metrics
+            .router()
+            .oneshot(
+                Request::builder()
+                    .uri("/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
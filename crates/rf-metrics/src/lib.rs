@@ -1,74 +1,100 @@
 //! Metrics and monitoring for RustForge
 //!
 //! This crate provides Prometheus-compatible metrics:
-//! - HTTP request metrics (duration, count, status codes)
-//! - Custom counters and gauges
-//! - Histograms for timing
+//! - A [`Metrics`] facade creating counters, gauges and histograms on first use
+//! - HTTP request metrics per route (duration, count, status codes)
 //! - Metrics endpoint for Prometheus scraping
+//! - Health probes next to `/metrics` (`health` feature, see [`health`])
+//!
+//! # Quick Start
+//!
+//! ```
+//! use axum::{middleware, routing::get, Router};
+//! use rf_metrics::{track_http_metrics, HttpMetrics, Metrics};
+//!
+//! # fn example() -> Result<(), rf_metrics::MetricsError> {
+//! let metrics = Metrics::new();
+//!
+//! let app: Router = Router::new()
+//!     .route("/orders", get(|| async { "orders" }))
+//!     .layer(middleware::from_fn_with_state(
+//!         HttpMetrics::new(&metrics)?,
+//!         track_http_metrics,
+//!     ))
+//!     .merge(metrics.router());
+//!
+//! // Anywhere in the application
+//! metrics
+//!     .counter_vec("orders_placed_total", "Orders placed", &["payment"])?
+//!     .with_label_values(&["card"])
+//!     .inc();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The [`HTTP_REQUEST_COUNT`] family of globals, [`metrics_middleware`] and
+//! [`metrics_router`] use [`Metrics::global`], Prometheus' default registry.
+
+mod error;
+mod http;
+mod registry;
+
+/// Health checks and probes, re-exported from `rf-health`
+#[cfg(feature = "health")]
+pub mod health {
+    pub use rf_health::*;
+}
+
+#[cfg(feature = "health")]
+#[path = "health.rs"]
+mod observability;
+
+pub use error::{MetricsError, MetricsResult};
+pub use http::{track_http_metrics, HttpMetrics, UNMATCHED_PATH};
+pub use registry::Metrics;
+
+#[cfg(feature = "health")]
+pub use observability::observability_router;
 
 use axum::{
-    extract::MatchedPath,
     http::{Request, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
     Router,
 };
-use prometheus::{
-    register_counter_vec, register_gauge, register_histogram_vec, CounterVec, Encoder, Gauge,
-    HistogramVec, TextEncoder,
-};
-use std::time::Instant;
+use prometheus::{CounterVec, Gauge, HistogramVec};
 
 lazy_static::lazy_static! {
     /// HTTP request duration histogram
-    pub static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+    pub static ref HTTP_REQUEST_DURATION: HistogramVec = Metrics::global().histogram_vec(
         "http_request_duration_seconds",
         "HTTP request duration in seconds",
         &["method", "path", "status"]
     ).unwrap();
 
     /// HTTP request counter
-    pub static ref HTTP_REQUEST_COUNT: CounterVec = register_counter_vec!(
+    pub static ref HTTP_REQUEST_COUNT: CounterVec = Metrics::global().counter_vec(
         "http_requests_total",
         "Total number of HTTP requests",
         &["method", "path", "status"]
     ).unwrap();
 
     /// Active connections gauge
-    pub static ref ACTIVE_CONNECTIONS: Gauge = register_gauge!(
+    pub static ref ACTIVE_CONNECTIONS: Gauge = Metrics::global().gauge(
         "active_connections",
         "Number of active connections"
     ).unwrap();
+
+    static ref GLOBAL_HTTP_METRICS: HttpMetrics = HttpMetrics::new(Metrics::global()).unwrap();
 }
 
-/// Metrics middleware for Axum
+/// Metrics middleware for Axum, recording into [`Metrics::global`]
 pub async fn metrics_middleware(
     req: Request<axum::body::Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    let start = Instant::now();
-    let method = req.method().clone();
-    let path = req
-        .extensions()
-        .get::<MatchedPath>()
-        .map(|p| p.as_str().to_string())
-        .unwrap_or_else(|| req.uri().path().to_string());
-
     ACTIVE_CONNECTIONS.inc();
-
-    let response = next.run(req).await;
-
-    let duration = start.elapsed().as_secs_f64();
-    let status = response.status().as_u16().to_string();
-
-    HTTP_REQUEST_DURATION
-        .with_label_values(&[method.as_str(), &path, &status])
-        .observe(duration);
-
-    HTTP_REQUEST_COUNT
-        .with_label_values(&[method.as_str(), &path, &status])
-        .inc();
-
+    let response = GLOBAL_HTTP_METRICS.track(req, next).await;
     ACTIVE_CONNECTIONS.dec();
 
     Ok(response)
@@ -76,24 +102,16 @@ pub async fn metrics_middleware(
 
 /// Metrics endpoint handler
 pub async fn metrics_handler() -> impl IntoResponse {
-    let encoder = TextEncoder::new();
-    let metric_families = prometheus::gather();
-    let mut buffer = Vec::new();
-
-    if let Err(e) = encoder.encode(&metric_families, &mut buffer) {
-        eprintln!("Failed to encode metrics: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Failed to encode metrics".to_string(),
-        );
+    match Metrics::global().render() {
+        Ok(output) => (StatusCode::OK, output),
+        Err(e) => {
+            eprintln!("Failed to encode metrics: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to encode metrics".to_string(),
+            )
+        }
     }
-
-    let output = String::from_utf8(buffer).unwrap_or_else(|e| {
-        eprintln!("Failed to convert metrics to string: {}", e);
-        String::new()
-    });
-
-    (StatusCode::OK, output)
 }
 
 /// Create a router with metrics endpoint
@@ -198,7 +216,8 @@ impl Histogram {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use axum::{body::Body, http::Request, middleware};
+    use axum::body::Body;
+    use tower::ServiceExt;
 
     #[test]
     fn test_counter_creation() {
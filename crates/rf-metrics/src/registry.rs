@@ -0,0 +1,281 @@
+//! Metrics facade over a Prometheus registry
+
+use crate::{MetricsError, MetricsResult};
+use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
+use prometheus::{
+    CounterVec, Encoder, GaugeVec, HistogramOpts, HistogramVec, Opts, Registry, TextEncoder,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+#[derive(Clone)]
+enum Collector {
+    Counter(CounterVec),
+    Gauge(GaugeVec),
+    Histogram(HistogramVec),
+}
+
+struct Registered {
+    labels: Vec<String>,
+    collector: Collector,
+}
+
+/// Registry of an application's metrics
+///
+/// Metrics are created on first use and shared afterwards, so call sites
+/// can ask for a metric by name instead of passing handles around. Cheap to
+/// clone.
+///
+/// # Example
+///
+/// ```
+/// use rf_metrics::Metrics;
+///
+/// let metrics = Metrics::new().with_namespace("shop");
+///
+/// metrics.counter_vec("orders_total", "Orders placed", &["status"])?
+///     .with_label_values(&["paid"])
+///     .inc();
+/// metrics.gauge("cart_items", "Items in open carts")?.set(12.0);
+/// metrics.histogram("checkout_seconds", "Checkout duration")?.observe(0.8);
+///
+/// assert!(metrics.render()?.contains("shop_orders_total{status=\"paid\"} 1"));
+/// # Ok::<(), rf_metrics::MetricsError>(())
+/// ```
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    namespace: Option<String>,
+    registered: Arc<Mutex<HashMap<String, Registered>>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Create metrics with their own registry
+    pub fn new() -> Self {
+        Self::with_registry(Registry::new())
+    }
+
+    /// Create metrics on an existing registry
+    pub fn with_registry(registry: Registry) -> Self {
+        Self {
+            registry,
+            namespace: None,
+            registered: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Metrics on Prometheus' default registry, shared with the
+    /// [`HTTP_REQUEST_COUNT`](crate::HTTP_REQUEST_COUNT) family of globals
+    pub fn global() -> &'static Metrics {
+        static GLOBAL: OnceLock<Metrics> = OnceLock::new();
+        GLOBAL.get_or_init(|| Metrics::with_registry(prometheus::default_registry().clone()))
+    }
+
+    /// Prefix metric names with `namespace_`
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Underlying Prometheus registry
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Counter without labels
+    pub fn counter(&self, name: &str, help: &str) -> MetricsResult<prometheus::Counter> {
+        Ok(self.counter_vec(name, help, &[])?.with_label_values(&[]))
+    }
+
+    /// Counter with labels
+    pub fn counter_vec(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+    ) -> MetricsResult<CounterVec> {
+        let collector = self.get_or_register(name, labels, || {
+            let vec = CounterVec::new(self.opts(name, help), labels)?;
+            Ok(Collector::Counter(vec))
+        })?;
+        match collector {
+            Collector::Counter(vec) => Ok(vec),
+            _ => Err(MetricsError::Conflict(name.to_string())),
+        }
+    }
+
+    /// Gauge without labels
+    pub fn gauge(&self, name: &str, help: &str) -> MetricsResult<prometheus::Gauge> {
+        Ok(self.gauge_vec(name, help, &[])?.with_label_values(&[]))
+    }
+
+    /// Gauge with labels
+    pub fn gauge_vec(&self, name: &str, help: &str, labels: &[&str]) -> MetricsResult<GaugeVec> {
+        let collector = self.get_or_register(name, labels, || {
+            let vec = GaugeVec::new(self.opts(name, help), labels)?;
+            Ok(Collector::Gauge(vec))
+        })?;
+        match collector {
+            Collector::Gauge(vec) => Ok(vec),
+            _ => Err(MetricsError::Conflict(name.to_string())),
+        }
+    }
+
+    /// Histogram without labels, with the default buckets (5ms to 10s)
+    pub fn histogram(&self, name: &str, help: &str) -> MetricsResult<prometheus::Histogram> {
+        Ok(self.histogram_vec(name, help, &[])?.with_label_values(&[]))
+    }
+
+    /// Histogram with labels, with the default buckets (5ms to 10s)
+    pub fn histogram_vec(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+    ) -> MetricsResult<HistogramVec> {
+        self.histogram_vec_with_buckets(name, help, labels, prometheus::DEFAULT_BUCKETS.to_vec())
+    }
+
+    /// Histogram with labels and custom buckets
+    ///
+    /// The buckets only apply when the histogram is created.
+    pub fn histogram_vec_with_buckets(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+        buckets: Vec<f64>,
+    ) -> MetricsResult<HistogramVec> {
+        let collector = self.get_or_register(name, labels, || {
+            let opts = HistogramOpts::from(self.opts(name, help)).buckets(buckets);
+            Ok(Collector::Histogram(HistogramVec::new(opts, labels)?))
+        })?;
+        match collector {
+            Collector::Histogram(vec) => Ok(vec),
+            _ => Err(MetricsError::Conflict(name.to_string())),
+        }
+    }
+
+    /// All metrics in the Prometheus text format
+    pub fn render(&self) -> MetricsResult<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+
+    /// Router serving the metrics at `/metrics`
+    pub fn router(&self) -> Router {
+        let metrics = self.clone();
+        Router::new().route(
+            "/metrics",
+            get(move || async move {
+                match metrics.render() {
+                    Ok(body) => (
+                        StatusCode::OK,
+                        [("content-type", prometheus::TEXT_FORMAT)],
+                        body,
+                    )
+                        .into_response(),
+                    Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+                }
+            }),
+        )
+    }
+
+    fn opts(&self, name: &str, help: &str) -> Opts {
+        let opts = Opts::new(name, help);
+        match &self.namespace {
+            Some(namespace) => opts.namespace(namespace.clone()),
+            None => opts,
+        }
+    }
+
+    fn get_or_register(
+        &self,
+        name: &str,
+        labels: &[&str],
+        create: impl FnOnce() -> MetricsResult<Collector>,
+    ) -> MetricsResult<Collector> {
+        let mut registered = self.registered.lock().unwrap();
+        if let Some(existing) = registered.get(name) {
+            if existing.labels != labels {
+                return Err(MetricsError::Conflict(name.to_string()));
+            }
+            return Ok(existing.collector.clone());
+        }
+
+        let collector = create()?;
+        match &collector {
+            Collector::Counter(vec) => self.registry.register(Box::new(vec.clone()))?,
+            Collector::Gauge(vec) => self.registry.register(Box::new(vec.clone()))?,
+            Collector::Histogram(vec) => self.registry.register(Box::new(vec.clone()))?,
+        }
+        registered.insert(
+            name.to_string(),
+            Registered {
+                labels: labels.iter().map(|label| label.to_string()).collect(),
+                collector: collector.clone(),
+            },
+        );
+        Ok(collector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_are_shared_by_name() {
+        let metrics = Metrics::new();
+
+        metrics.counter("jobs_total", "Jobs").unwrap().inc();
+        metrics.counter("jobs_total", "Jobs").unwrap().inc();
+        assert_eq!(metrics.counter("jobs_total", "Jobs").unwrap().get(), 2.0);
+
+        // Clones share the registry
+        let clone = metrics.clone();
+        clone.gauge("queue_size", "Queue size").unwrap().set(3.0);
+        assert_eq!(
+            metrics.gauge("queue_size", "Queue size").unwrap().get(),
+            3.0
+        );
+    }
+
+    #[test]
+    fn test_conflicting_registrations() {
+        let metrics = Metrics::new();
+        metrics
+            .counter_vec("events_total", "Events", &["kind"])
+            .unwrap();
+
+        assert!(matches!(
+            metrics.gauge("events_total", "Events"),
+            Err(MetricsError::Conflict(_))
+        ));
+        assert!(matches!(
+            metrics.counter_vec("events_total", "Events", &["source"]),
+            Err(MetricsError::Conflict(_))
+        ));
+    }
+
+    #[test]
+    fn test_render_with_namespace() {
+        let metrics = Metrics::new().with_namespace("app");
+        metrics
+            .histogram_vec_with_buckets("latency_seconds", "Latency", &["op"], vec![0.1, 1.0])
+            .unwrap()
+            .with_label_values(&["read"])
+            .observe(0.05);
+
+        let output = metrics.render().unwrap();
+        assert!(output.contains("# HELP app_latency_seconds Latency"));
+        assert!(output.contains("app_latency_seconds_bucket{op=\"read\",le=\"0.1\"} 1"));
+    }
+}
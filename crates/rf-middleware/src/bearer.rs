@@ -0,0 +1,114 @@
+//! Bearer token guard
+
+use crate::{Middleware, MiddlewareService, Next};
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tower::Layer;
+
+/// Layer accepting only requests with `Authorization: Bearer <token>`,
+/// e.g. for admin APIs
+///
+/// The token is compared in constant time; other requests get
+/// `401 Unauthorized`.
+///
+/// ```
+/// use axum::{routing::get, Router};
+/// use rf_middleware::BearerTokenLayer;
+///
+/// let admin: Router = Router::new()
+///     .route("/stats", get(|| async { "42 users" }))
+///     .layer(BearerTokenLayer::new("secret-token"));
+/// ```
+#[derive(Clone)]
+pub struct BearerTokenLayer {
+    token: Arc<str>,
+}
+
+impl BearerTokenLayer {
+    /// Guard with `token`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `token` is empty or only whitespace, which would let
+    /// `Authorization: Bearer ` through.
+    pub fn new(token: impl Into<String>) -> Self {
+        let token = token.into();
+        assert!(!token.trim().is_empty(), "bearer token must not be empty");
+        Self {
+            token: Arc::from(token),
+        }
+    }
+
+    fn authorized(&self, req: &Request) -> bool {
+        req.headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .is_some_and(|provided| provided.as_bytes().ct_eq(self.token.as_bytes()).into())
+    }
+}
+
+impl Middleware for BearerTokenLayer {
+    async fn handle(self, req: Request, next: Next) -> Response {
+        if !self.authorized(&req) {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(serde_json::json!({ "error": "Unauthorized" })),
+            )
+                .into_response();
+        }
+        next.run(req).await
+    }
+}
+
+impl<S> Layer<S> for BearerTokenLayer {
+    type Service = BearerTokenService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+/// Service created by [`BearerTokenLayer`]
+pub type BearerTokenService<S> = MiddlewareService<BearerTokenLayer, S>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn status(app: &Router, authorization: Option<&str>) -> StatusCode {
+        let mut req = Request::builder().uri("/");
+        if let Some(authorization) = authorization {
+            req = req.header(header::AUTHORIZATION, authorization);
+        }
+        let req = req.body(Body::empty()).unwrap();
+        app.clone().oneshot(req).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_only_the_token_is_accepted() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(BearerTokenLayer::new("secret"));
+
+        assert_eq!(status(&app, Some("Bearer secret")).await, StatusCode::OK);
+        for rejected in ["Bearer secreT", "Bearer secret2", "Bearer ", "Basic secret"] {
+            assert_eq!(status(&app, Some(rejected)).await, StatusCode::UNAUTHORIZED);
+        }
+        assert_eq!(status(&app, None).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    #[should_panic(expected = "bearer token must not be empty")]
+    fn test_empty_token_is_rejected() {
+        BearerTokenLayer::new(" ");
+    }
+}
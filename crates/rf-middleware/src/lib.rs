@@ -0,0 +1,170 @@
+//! Tower plumbing for RustForge's axum middleware
+//!
+//! Framework layers such as rf-session's `SessionLayer` or rf-ratelimit's
+//! `RateLimitLayer` only describe what happens to a request. They implement
+//! [`Middleware`], and [`MiddlewareService`] turns them into a tower
+//! service: it forwards readiness to the wrapped service and hands the
+//! request to [`Middleware::handle`] along with a [`Next`] running the rest
+//! of the stack.
+//!
+//! ```
+//! use axum::{extract::Request, response::Response, routing::get, Router};
+//! use rf_middleware::{Middleware, MiddlewareService, Next};
+//! use tower::Layer;
+//!
+//! #[derive(Clone)]
+//! struct PoweredBy(&'static str);
+//!
+//! impl Middleware for PoweredBy {
+//!     async fn handle(self, req: Request, next: Next) -> Response {
+//!         let mut res = next.run(req).await;
+//!         res.headers_mut().insert("x-powered-by", self.0.parse().unwrap());
+//!         res
+//!     }
+//! }
+//!
+//! impl<S> Layer<S> for PoweredBy {
+//!     type Service = MiddlewareService<Self, S>;
+//!
+//!     fn layer(&self, inner: S) -> Self::Service {
+//!         MiddlewareService::new(self.clone(), inner)
+//!     }
+//! }
+//!
+//! let app: Router = Router::new()
+//!     .route("/", get(|| async { "Hello" }))
+//!     .layer(PoweredBy("RustForge"));
+//! ```
+//!
+//! [`BearerTokenLayer`] is built this way and guards the framework's admin
+//! APIs with a shared token.
+
+use axum::{extract::Request, response::Response};
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower::Service;
+
+mod bearer;
+
+pub use bearer::{BearerTokenLayer, BearerTokenService};
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Request handling of a layer
+///
+/// The layer is cloned for every request, so keep its state behind an
+/// `Arc`.
+pub trait Middleware: Clone + Send + 'static {
+    /// Handle `req`, passing it on through `next` unless responding early
+    fn handle(self, req: Request, next: Next) -> impl Future<Output = Response> + Send;
+}
+
+/// The rest of the middleware stack, up to and including the handler
+pub struct Next {
+    run: Box<dyn FnOnce(Request) -> BoxFuture<Response> + Send>,
+}
+
+impl Next {
+    /// Pass `req` on and wait for the response
+    pub async fn run(self, req: Request) -> Response {
+        (self.run)(req).await
+    }
+}
+
+/// Service running a [`Middleware`] in front of `inner`
+#[derive(Debug, Clone)]
+pub struct MiddlewareService<M, S> {
+    middleware: M,
+    inner: S,
+}
+
+impl<M, S> MiddlewareService<M, S> {
+    pub fn new(middleware: M, inner: S) -> Self {
+        Self { middleware, inner }
+    }
+}
+
+impl<M, S> Service<Request> for MiddlewareService<M, S>
+where
+    M: Middleware,
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        // Take the service that was polled ready, leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let next = Next {
+            run: Box::new(move |req| {
+                Box::pin(async move {
+                    match inner.call(req).await {
+                        Ok(res) => res,
+                        Err(never) => match never {},
+                    }
+                })
+            }),
+        };
+
+        let middleware = self.middleware.clone();
+        Box::pin(async move { Ok(middleware.handle(req, next).await) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, response::IntoResponse, routing::get, Router};
+    use tower::{Layer, ServiceExt};
+
+    #[derive(Clone)]
+    struct RequireHeader;
+
+    impl Middleware for RequireHeader {
+        async fn handle(self, req: Request, next: Next) -> Response {
+            if req.headers().contains_key("x-allowed") {
+                next.run(req).await
+            } else {
+                StatusCode::FORBIDDEN.into_response()
+            }
+        }
+    }
+
+    impl<S> Layer<S> for RequireHeader {
+        type Service = MiddlewareService<Self, S>;
+
+        fn layer(&self, inner: S) -> Self::Service {
+            MiddlewareService::new(self.clone(), inner)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_responds_or_passes_on() {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(RequireHeader);
+
+        let denied = Request::builder().uri("/").body(Body::empty()).unwrap();
+        let res = app.clone().oneshot(denied).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let allowed = Request::builder()
+            .uri("/")
+            .header("x-allowed", "1")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.oneshot(allowed).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,177 @@
+//! Migration commands
+
+use crate::migrator::on_database;
+use crate::{MigrateResult, Migration, Migrator};
+use sqlx::AnyPool;
+use std::fmt::Write;
+
+/// `up`, `down`, `redo`, `status` and `fresh` subcommands for an
+/// application's CLI
+///
+/// Commands run on each given database in turn, e.g. the central one and
+/// one per tenant.
+///
+/// ```ignore
+/// #[derive(clap::Subcommand)]
+/// enum Command {
+///     Serve,
+///     /// Database migrations
+///     #[command(subcommand)]
+///     Migrate(rf_migrate::MigrateCommand),
+/// }
+///
+/// match cli.command {
+///     Command::Migrate(command) => {
+///         let migrator = Migrator::from_dir("migrations")?;
+///         let pool = rf_migrate::connect(&std::env::var("DATABASE_URL")?).await?;
+///         return command.run(&migrator, [("default", &pool)]).await;
+///     }
+///     // …
+/// }
+/// ```
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum MigrateCommand {
+    /// Run pending migrations
+    Up,
+    /// Roll back the latest migrations
+    Down {
+        /// Number of migrations to roll back
+        #[arg(long, default_value_t = 1)]
+        steps: usize,
+    },
+    /// Roll back the latest migrations and run them again
+    Redo {
+        /// Number of migrations to redo
+        #[arg(long, default_value_t = 1)]
+        steps: usize,
+    },
+    /// Show which migrations ran
+    Status,
+    /// Drop all tables and run all migrations
+    Fresh,
+}
+
+impl MigrateCommand {
+    /// Run the command on each named database, printing its report
+    pub async fn run<'a>(
+        &self,
+        migrator: &Migrator,
+        databases: impl IntoIterator<Item = (&'a str, &'a AnyPool)>,
+    ) -> std::process::ExitCode {
+        let databases: Vec<_> = databases.into_iter().collect();
+        for &(database, pool) in &databases {
+            if databases.len() > 1 {
+                println!("{}:", database);
+            }
+            match self.execute(migrator, pool).await {
+                Ok(report) => print!("{}", report),
+                Err(e) => {
+                    eprintln!("{}", on_database(database, e));
+                    return std::process::ExitCode::FAILURE;
+                }
+            }
+        }
+        std::process::ExitCode::SUCCESS
+    }
+
+    async fn execute(&self, migrator: &Migrator, pool: &AnyPool) -> MigrateResult<String> {
+        match self {
+            MigrateCommand::Up => Ok(report(migrator.up(pool).await?, "Migrated", "migrate")),
+            MigrateCommand::Down { steps } => Ok(report(
+                migrator.down(pool, *steps).await?,
+                "Rolled back",
+                "roll back",
+            )),
+            MigrateCommand::Redo { steps } => Ok(report(
+                migrator.redo(pool, *steps).await?,
+                "Migrated",
+                "redo",
+            )),
+            MigrateCommand::Fresh => {
+                let migrated = migrator.fresh(pool).await?;
+                Ok(format!(
+                    "Dropped all tables\n{}",
+                    report(migrated, "Migrated", "migrate")
+                ))
+            }
+            MigrateCommand::Status => {
+                let mut report = String::new();
+                for status in migrator.status(pool).await? {
+                    let state = match status.batch {
+                        _ if status.missing => "Missing".to_string(),
+                        Some(batch) => format!("Batch {}", batch),
+                        None => "Pending".to_string(),
+                    };
+                    let _ = writeln!(report, "{:<10} {}_{}", state, status.version, status.name);
+                }
+                if report.is_empty() {
+                    report.push_str("No migrations\n");
+                }
+                Ok(report)
+            }
+        }
+    }
+}
+
+fn report(migrations: Vec<&Migration>, done: &str, verb: &str) -> String {
+    if migrations.is_empty() {
+        return format!("Nothing to {}\n", verb);
+    }
+    migrations
+        .iter()
+        .map(|migration| format!("{} {}\n", done, migration))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_commands() {
+        let pool = crate::connect("sqlite::memory:").await.unwrap();
+        let migrator = Migrator::new()
+            .migration(
+                Migration::new(1, "create_users", "CREATE TABLE users (id INTEGER)")
+                    .down("DROP TABLE users"),
+            )
+            .migration(Migration::new(
+                2,
+                "create_posts",
+                "CREATE TABLE posts (id INTEGER)",
+            ));
+
+        let status = MigrateCommand::Status
+            .execute(&migrator, &pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            status,
+            "Pending    1_create_users\nPending    2_create_posts\n"
+        );
+
+        let report = MigrateCommand::Up.execute(&migrator, &pool).await.unwrap();
+        assert_eq!(report, "Migrated 1_create_users\nMigrated 2_create_posts\n");
+        let report = MigrateCommand::Up.execute(&migrator, &pool).await.unwrap();
+        assert_eq!(report, "Nothing to migrate\n");
+
+        assert!(MigrateCommand::Down { steps: 1 }
+            .execute(&migrator, &pool)
+            .await
+            .is_err());
+
+        let report = MigrateCommand::Fresh
+            .execute(&migrator, &pool)
+            .await
+            .unwrap();
+        assert!(report.starts_with("Dropped all tables\nMigrated 1_create_users"));
+        let status = MigrateCommand::Status
+            .execute(&migrator, &pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            status,
+            "Batch 1    1_create_users\nBatch 1    2_create_posts\n"
+        );
+    }
+}
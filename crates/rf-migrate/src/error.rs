@@ -0,0 +1,40 @@
+//! Migration errors
+
+use thiserror::Error;
+
+/// Migration errors
+#[derive(Debug, Error)]
+pub enum MigrateError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Can't read migrations: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid migration: {0}")]
+    Invalid(String),
+
+    #[error("Migration {0} has no down migration")]
+    Irreversible(String),
+
+    #[error("Migration {0} is applied but no longer exists")]
+    Missing(String),
+
+    #[error("Migration {migration} failed: {source}")]
+    Failed {
+        migration: String,
+        source: sqlx::Error,
+    },
+
+    #[error("Unsupported database: {0}")]
+    UnsupportedDatabase(String),
+
+    #[error("Database {database}: {source}")]
+    OnDatabase {
+        database: String,
+        source: Box<MigrateError>,
+    },
+}
+
+/// Result type for migrations
+pub type MigrateResult<T> = Result<T, MigrateError>;
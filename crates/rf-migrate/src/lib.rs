@@ -0,0 +1,56 @@
+//! Database migrations for RustForge
+//!
+//! [`Migrator`] runs SQL migrations in order of their version and records
+//! them in a `schema_migrations` table, so each runs once per database.
+//! Migrations come from a directory of SQL files, as written by rf-cli-gen
+//! and the project wizard, or are added in code.
+//!
+//! # Features
+//!
+//! - `up`, `down`, `redo`, `status`, `fresh`, `reset` and `wipe`
+//! - Transactional migrations on Postgres and SQLite
+//! - Postgres, SQLite and MySQL (feature `mysql`) through sqlx's `Any`
+//!   driver
+//! - Several databases at once, see [`Migrator::up_all`]
+//! - `MigrateCommand` subcommands for an application's CLI (feature `cli`)
+//! - `TenantMigrations` migrating a database per tenant (feature `tenancy`)
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rf_migrate::Migrator;
+//!
+//! # async fn example() -> rf_migrate::MigrateResult<()> {
+//! // migrations/20240101120000_create_users_table.up.sql, .down.sql, ...
+//! let migrator = Migrator::from_dir("migrations")?;
+//! let pool = rf_migrate::connect("postgres://localhost/app").await?;
+//!
+//! migrator.up(&pool).await?;
+//! for status in migrator.status(&pool).await? {
+//!     println!("{} {} applied: {}", status.version, status.name, status.is_applied());
+//! }
+//! migrator.down(&pool, 1).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod error;
+mod migration;
+mod migrator;
+
+#[cfg(feature = "cli")]
+mod command;
+
+#[cfg(feature = "tenancy")]
+mod tenancy;
+
+pub use error::{MigrateError, MigrateResult};
+pub use migration::{Migration, NO_TRANSACTION};
+pub use migrator::{connect, Backend, MigrationStatus, Migrator, DEFAULT_TABLE};
+pub use sqlx::AnyPool;
+
+#[cfg(feature = "cli")]
+pub use command::MigrateCommand;
+
+#[cfg(feature = "tenancy")]
+pub use tenancy::TenantMigrations;
@@ -0,0 +1,178 @@
+//! Migrations and their discovery
+
+use crate::{MigrateError, MigrateResult};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// First line of SQL files that must not run in a transaction, e.g. for
+/// `CREATE INDEX CONCURRENTLY`
+pub const NO_TRANSACTION: &str = "-- no-transaction";
+
+/// A schema change, applied in order of its version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    /// Ordering key, usually a timestamp like `20240101120000`
+    pub version: i64,
+
+    pub name: String,
+
+    pub up: String,
+
+    /// SQL reverting `up`; irreversible without
+    pub down: Option<String>,
+
+    /// Run in a transaction where the database supports transactional DDL
+    pub transactional: bool,
+}
+
+impl Migration {
+    /// Create an irreversible, transactional migration
+    pub fn new(version: i64, name: impl Into<String>, up: impl Into<String>) -> Self {
+        Self {
+            version,
+            name: name.into(),
+            up: up.into(),
+            down: None,
+            transactional: true,
+        }
+    }
+
+    /// Set the SQL reverting the migration
+    pub fn down(mut self, down: impl Into<String>) -> Self {
+        self.down = Some(down.into());
+        self
+    }
+
+    /// Run outside of a transaction
+    pub fn no_transaction(mut self) -> Self {
+        self.transactional = false;
+        self
+    }
+
+    /// Whether the migration can be rolled back
+    pub fn is_reversible(&self) -> bool {
+        self.down.is_some()
+    }
+}
+
+impl fmt::Display for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}_{}", self.version, self.name)
+    }
+}
+
+/// Load the migrations of `dir`
+///
+/// Files are named `<version>_<name>.up.sql` with an optional
+/// `<version>_<name>.down.sql`, or `<version>_<name>.sql` for irreversible
+/// migrations, as written by rf-cli-gen and the project wizard. Other files
+/// are ignored.
+pub(crate) fn discover(dir: &Path) -> MigrateResult<Vec<Migration>> {
+    let mut ups = BTreeMap::new();
+    let mut downs = BTreeMap::new();
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(file) = path.file_name().and_then(|f| f.to_str()) else {
+            continue;
+        };
+        let (base, down) = if let Some(base) = file.strip_suffix(".down.sql") {
+            (base, true)
+        } else if let Some(base) = file.strip_suffix(".up.sql") {
+            (base, false)
+        } else if let Some(base) = file.strip_suffix(".sql") {
+            (base, false)
+        } else {
+            continue;
+        };
+
+        let key = parse_name(base)?;
+        let sql = std::fs::read_to_string(&path)?;
+        let target = if down { &mut downs } else { &mut ups };
+        if target.insert(key.clone(), sql).is_some() {
+            return Err(MigrateError::Invalid(format!(
+                "{}_{} exists twice",
+                key.0, key.1
+            )));
+        }
+    }
+
+    if let Some((version, name)) = downs.keys().find(|key| !ups.contains_key(*key)) {
+        return Err(MigrateError::Invalid(format!(
+            "{}_{}.down.sql has no up migration",
+            version, name
+        )));
+    }
+
+    Ok(ups
+        .into_iter()
+        .map(|((version, name), up)| {
+            let transactional = !up.trim_start().starts_with(NO_TRANSACTION);
+            Migration {
+                down: downs.remove(&(version, name.clone())),
+                version,
+                name,
+                up,
+                transactional,
+            }
+        })
+        .collect())
+}
+
+/// Split `20240101120000_create_users` into version and name
+fn parse_name(base: &str) -> MigrateResult<(i64, String)> {
+    let (version, name) = base.split_once('_').unwrap_or((base, ""));
+    let version = version.parse().map_err(|_| {
+        MigrateError::Invalid(format!(
+            "{} doesn't start with a numeric version like 20240101120000_",
+            base
+        ))
+    })?;
+    Ok((version, name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |file: &str, sql: &str| std::fs::write(dir.path().join(file), sql).unwrap();
+        write(
+            "20240102000000_add_index.up.sql",
+            "-- no-transaction\nCREATE INDEX;",
+        );
+        write("20240102000000_add_index.down.sql", "DROP INDEX;");
+        write(
+            "20240101000000_001_create_users_table.sql",
+            "CREATE TABLE users;",
+        );
+        write("README.md", "ignored");
+
+        let migrations = discover(dir.path()).unwrap();
+        assert_eq!(migrations.len(), 2);
+        assert_eq!(
+            migrations[0].to_string(),
+            "20240101000000_001_create_users_table"
+        );
+        assert!(!migrations[0].is_reversible());
+        assert!(migrations[0].transactional);
+        assert_eq!(migrations[1].down.as_deref(), Some("DROP INDEX;"));
+        assert!(!migrations[1].transactional);
+
+        write("20240103000000_orphan.down.sql", "DROP TABLE x;");
+        assert!(matches!(
+            discover(dir.path()),
+            Err(MigrateError::Invalid(_))
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("create_users.sql"), "").unwrap();
+        assert!(matches!(
+            discover(dir.path()),
+            Err(MigrateError::Invalid(_))
+        ));
+    }
+}
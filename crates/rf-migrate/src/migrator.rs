@@ -0,0 +1,626 @@
+//! Migration runner
+
+use crate::migration::discover;
+use crate::{MigrateError, MigrateResult, Migration};
+use chrono::{SecondsFormat, Utc};
+use sqlx::any::AnyPoolOptions;
+use sqlx::{AnyConnection, AnyPool, Connection, Executor, Row};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Default name of the table tracking applied migrations
+pub const DEFAULT_TABLE: &str = "schema_migrations";
+
+/// Connect to `database_url` with a single connection, e.g.
+/// `postgres://localhost/app` or `sqlite://app.db?mode=rwc`
+pub async fn connect(database_url: &str) -> MigrateResult<AnyPool> {
+    sqlx::any::install_default_drivers();
+    Ok(AnyPoolOptions::new()
+        .max_connections(1)
+        .connect(database_url)
+        .await?)
+}
+
+/// Database behind a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl Backend {
+    fn of(conn: &AnyConnection) -> MigrateResult<Self> {
+        match conn.backend_name() {
+            "PostgreSQL" => Ok(Self::Postgres),
+            "MySQL" => Ok(Self::MySql),
+            "SQLite" => Ok(Self::Sqlite),
+            name => Err(MigrateError::UnsupportedDatabase(name.to_string())),
+        }
+    }
+
+    /// Whether schema changes can be rolled back with a transaction;
+    /// MySQL commits implicitly on DDL
+    pub fn transactional_ddl(self) -> bool {
+        !matches!(self, Self::MySql)
+    }
+
+    /// Bind parameter `n`, counting from 1
+    fn placeholder(self, n: usize) -> String {
+        match self {
+            Self::Postgres => format!("${}", n),
+            Self::MySql | Self::Sqlite => "?".to_string(),
+        }
+    }
+
+    fn quote(self, identifier: &str) -> String {
+        match self {
+            Self::MySql => format!("`{}`", identifier.replace('`', "``")),
+            Self::Postgres | Self::Sqlite => format!("\"{}\"", identifier.replace('"', "\"\"")),
+        }
+    }
+}
+
+/// State of a migration in a database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+
+    pub name: String,
+
+    /// Batch the migration ran in, if applied
+    pub batch: Option<i64>,
+
+    /// When the migration ran (RFC 3339), if applied
+    pub applied_at: Option<String>,
+
+    /// Applied, but no longer among the migrations
+    pub missing: bool,
+}
+
+impl MigrationStatus {
+    pub fn is_applied(&self) -> bool {
+        self.batch.is_some()
+    }
+}
+
+/// Row of the tracking table
+struct Applied {
+    version: i64,
+    name: String,
+    batch: i64,
+    applied_at: String,
+}
+
+/// Runs migrations and tracks them in the `schema_migrations` table
+///
+/// Each migration runs in its own transaction along with its tracking row,
+/// except on MySQL or when marked [`no_transaction`](Migration::no_transaction).
+/// All migrations of one [`up`](Self::up) form a batch. Run migrations from
+/// one process at a time, e.g. a deploy step.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_migrate::{Migration, Migrator};
+///
+/// # async fn example() -> rf_migrate::MigrateResult<()> {
+/// let migrator = Migrator::from_dir("migrations")?.migration(
+///     Migration::new(20240301000000, "create_audit_log", "CREATE TABLE audit_log (id BIGINT)")
+///         .down("DROP TABLE audit_log"),
+/// );
+///
+/// let pool = rf_migrate::connect("postgres://localhost/app").await?;
+/// for migration in migrator.up(&pool).await? {
+///     println!("Migrated {}", migration);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+    table: String,
+}
+
+impl Default for Migrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Migrator {
+    /// Create a migrator without migrations
+    pub fn new() -> Self {
+        Self {
+            migrations: Vec::new(),
+            table: DEFAULT_TABLE.to_string(),
+        }
+    }
+
+    /// Load the SQL migrations of `dir`
+    ///
+    /// See [`Migration`] for programmatic ones. Files are named
+    /// `<version>_<name>.up.sql` with an optional `.down.sql`, or
+    /// `<version>_<name>.sql` for irreversible migrations. SQL starting with
+    /// `-- no-transaction` runs outside of a transaction.
+    pub fn from_dir(dir: impl AsRef<Path>) -> MigrateResult<Self> {
+        Ok(Self {
+            migrations: discover(dir.as_ref())?,
+            ..Self::new()
+        })
+    }
+
+    /// Add a migration
+    pub fn migration(mut self, migration: Migration) -> Self {
+        self.migrations.push(migration);
+        self.migrations.sort_by_key(|m| m.version);
+        self
+    }
+
+    /// Track migrations in a different table
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// All migrations, by version
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Every migration with its state, by version
+    pub async fn status(&self, pool: &AnyPool) -> MigrateResult<Vec<MigrationStatus>> {
+        let mut conn = pool.acquire().await?;
+        self.prepare(&mut conn).await?;
+        let applied = self.applied(&mut conn).await?;
+
+        let mut status: BTreeMap<i64, MigrationStatus> = self
+            .migrations
+            .iter()
+            .map(|m| {
+                let status = MigrationStatus {
+                    version: m.version,
+                    name: m.name.clone(),
+                    batch: None,
+                    applied_at: None,
+                    missing: false,
+                };
+                (m.version, status)
+            })
+            .collect();
+        for row in applied {
+            let entry = status
+                .entry(row.version)
+                .or_insert_with(|| MigrationStatus {
+                    version: row.version,
+                    name: row.name,
+                    batch: None,
+                    applied_at: None,
+                    missing: true,
+                });
+            entry.batch = Some(row.batch);
+            entry.applied_at = Some(row.applied_at);
+        }
+        Ok(status.into_values().collect())
+    }
+
+    /// Run all pending migrations as a new batch, returning them
+    pub async fn up(&self, pool: &AnyPool) -> MigrateResult<Vec<&Migration>> {
+        let mut conn = pool.acquire().await?;
+        let backend = self.prepare(&mut conn).await?;
+        let applied = self.applied(&mut conn).await?;
+
+        let batch = applied.iter().map(|row| row.batch).max().unwrap_or(0) + 1;
+        let versions: HashSet<i64> = applied.iter().map(|row| row.version).collect();
+        let pending: Vec<&Migration> = self
+            .migrations
+            .iter()
+            .filter(|m| !versions.contains(&m.version))
+            .collect();
+
+        for migration in &pending {
+            self.run(&mut conn, backend, migration, Change::Up(batch))
+                .await?;
+            tracing::info!(migration = %migration, batch, "Migrated");
+        }
+        Ok(pending)
+    }
+
+    /// Roll back the `steps` latest migrations, returning them
+    ///
+    /// Nothing is rolled back if one of them is irreversible or missing.
+    pub async fn down(&self, pool: &AnyPool, steps: usize) -> MigrateResult<Vec<&Migration>> {
+        let mut conn = pool.acquire().await?;
+        let backend = self.prepare(&mut conn).await?;
+        let mut applied = self.applied(&mut conn).await?;
+        applied.sort_by_key(|row| std::cmp::Reverse((row.batch, row.version)));
+
+        let targets = applied
+            .iter()
+            .take(steps)
+            .map(|row| {
+                let migration = self
+                    .migrations
+                    .iter()
+                    .find(|m| m.version == row.version)
+                    .ok_or_else(|| {
+                        MigrateError::Missing(format!("{}_{}", row.version, row.name))
+                    })?;
+                if !migration.is_reversible() {
+                    return Err(MigrateError::Irreversible(migration.to_string()));
+                }
+                Ok(migration)
+            })
+            .collect::<MigrateResult<Vec<_>>>()?;
+
+        for migration in &targets {
+            self.run(&mut conn, backend, migration, Change::Down)
+                .await?;
+            tracing::info!(migration = %migration, "Rolled back");
+        }
+        Ok(targets)
+    }
+
+    /// Roll back all migrations
+    pub async fn reset(&self, pool: &AnyPool) -> MigrateResult<Vec<&Migration>> {
+        self.down(pool, usize::MAX).await
+    }
+
+    /// Roll back the `steps` latest migrations and run them again
+    pub async fn redo(&self, pool: &AnyPool, steps: usize) -> MigrateResult<Vec<&Migration>> {
+        self.down(pool, steps).await?;
+        self.up(pool).await
+    }
+
+    /// Drop all tables and run all migrations
+    pub async fn fresh(&self, pool: &AnyPool) -> MigrateResult<Vec<&Migration>> {
+        self.wipe(pool).await?;
+        self.up(pool).await
+    }
+
+    /// Drop all tables of the database (Postgres: of the current schema),
+    /// including the tracking table
+    pub async fn wipe(&self, pool: &AnyPool) -> MigrateResult<()> {
+        let mut conn = pool.acquire().await?;
+        let backend = Backend::of(&conn)?;
+
+        let list = match backend {
+            Backend::Postgres => {
+                "SELECT tablename::text AS name FROM pg_tables WHERE schemaname = current_schema()"
+            }
+            Backend::MySql => {
+                "SELECT CAST(table_name AS CHAR) AS name FROM information_schema.tables
+                 WHERE table_schema = DATABASE() AND table_type = 'BASE TABLE'"
+            }
+            Backend::Sqlite => {
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'"
+            }
+        };
+        let tables: Vec<String> = (&mut *conn)
+            .fetch_all(sqlx::query(list))
+            .await?
+            .iter()
+            .map(|row| row.try_get("name"))
+            .collect::<Result<_, _>>()?;
+
+        // Drop in any order despite foreign keys
+        let (disable, enable) = match backend {
+            Backend::Postgres => (None, None),
+            Backend::MySql => (
+                Some("SET FOREIGN_KEY_CHECKS = 0"),
+                Some("SET FOREIGN_KEY_CHECKS = 1"),
+            ),
+            Backend::Sqlite => (
+                Some("PRAGMA foreign_keys = OFF"),
+                Some("PRAGMA foreign_keys = ON"),
+            ),
+        };
+        if let Some(sql) = disable {
+            (&mut *conn).execute(sqlx::raw_sql(sql)).await?;
+        }
+        for table in &tables {
+            let cascade = if backend == Backend::Postgres {
+                " CASCADE"
+            } else {
+                ""
+            };
+            let sql = format!("DROP TABLE IF EXISTS {}{}", backend.quote(table), cascade);
+            (&mut *conn).execute(sqlx::raw_sql(&sql)).await?;
+        }
+        if let Some(sql) = enable {
+            (&mut *conn).execute(sqlx::raw_sql(sql)).await?;
+        }
+
+        tracing::info!(tables = tables.len(), "Dropped all tables");
+        Ok(())
+    }
+
+    /// Run pending migrations on each named database, e.g. the central one
+    /// and one per tenant, returning how many ran on each
+    ///
+    /// Stops at the first database that fails.
+    pub async fn up_all<'a>(
+        &self,
+        databases: impl IntoIterator<Item = (&'a str, &'a AnyPool)>,
+    ) -> MigrateResult<Vec<(String, usize)>> {
+        let mut counts = Vec::new();
+        for (database, pool) in databases {
+            let count = self
+                .up(pool)
+                .await
+                .map_err(|e| on_database(database, e))?
+                .len();
+            counts.push((database.to_string(), count));
+        }
+        Ok(counts)
+    }
+
+    /// Check the migrations and create the tracking table
+    async fn prepare(&self, conn: &mut AnyConnection) -> MigrateResult<Backend> {
+        let mut versions = HashSet::new();
+        if let Some(duplicate) = self.migrations.iter().find(|m| !versions.insert(m.version)) {
+            return Err(MigrateError::Invalid(format!(
+                "Version {} is used twice",
+                duplicate.version
+            )));
+        }
+
+        let backend = Backend::of(conn)?;
+        let create = format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                version BIGINT PRIMARY KEY,
+                name VARCHAR(255) NOT NULL,
+                batch BIGINT NOT NULL,
+                applied_at VARCHAR(64) NOT NULL
+            )",
+            self.table
+        );
+        (&mut *conn).execute(sqlx::raw_sql(&create)).await?;
+        Ok(backend)
+    }
+
+    /// Applied migrations, by version
+    async fn applied(&self, conn: &mut AnyConnection) -> MigrateResult<Vec<Applied>> {
+        let select = format!(
+            "SELECT version, name, batch, applied_at FROM {} ORDER BY version",
+            self.table
+        );
+        (&mut *conn)
+            .fetch_all(sqlx::query(&select))
+            .await?
+            .iter()
+            .map(|row| {
+                Ok(Applied {
+                    version: row.try_get("version")?,
+                    name: row.try_get("name")?,
+                    batch: row.try_get("batch")?,
+                    applied_at: row.try_get("applied_at")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Run the SQL of `change` and update the tracking table, in one
+    /// transaction if possible
+    async fn run(
+        &self,
+        conn: &mut AnyConnection,
+        backend: Backend,
+        migration: &Migration,
+        change: Change,
+    ) -> MigrateResult<()> {
+        let sql = match change {
+            Change::Up(_) => migration.up.as_str(),
+            Change::Down => migration.down.as_deref().unwrap_or_default(),
+        };
+        let failed = |source| MigrateError::Failed {
+            migration: migration.to_string(),
+            source,
+        };
+
+        if migration.transactional && backend.transactional_ddl() {
+            let mut tx = conn.begin().await?;
+            (&mut *tx)
+                .execute(sqlx::raw_sql(sql))
+                .await
+                .map_err(failed)?;
+            self.track(&mut tx, backend, migration, change).await?;
+            tx.commit().await?;
+        } else {
+            (&mut *conn)
+                .execute(sqlx::raw_sql(sql))
+                .await
+                .map_err(failed)?;
+            self.track(conn, backend, migration, change).await?;
+        }
+        Ok(())
+    }
+
+    async fn track(
+        &self,
+        conn: &mut AnyConnection,
+        backend: Backend,
+        migration: &Migration,
+        change: Change,
+    ) -> MigrateResult<()> {
+        let p = |n| backend.placeholder(n);
+        match change {
+            Change::Up(batch) => {
+                let applied_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+                let insert = format!(
+                    "INSERT INTO {} (version, name, batch, applied_at) VALUES ({}, {}, {}, {})",
+                    self.table,
+                    p(1),
+                    p(2),
+                    p(3),
+                    p(4)
+                );
+                let query = sqlx::query(&insert)
+                    .bind(migration.version)
+                    .bind(&migration.name)
+                    .bind(batch)
+                    .bind(applied_at);
+                (&mut *conn).execute(query).await?;
+            }
+            Change::Down => {
+                let delete = format!("DELETE FROM {} WHERE version = {}", self.table, p(1));
+                let query = sqlx::query(&delete).bind(migration.version);
+                (&mut *conn).execute(query).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Change {
+    Up(i64),
+    Down,
+}
+
+pub(crate) fn on_database(database: &str, e: MigrateError) -> MigrateError {
+    MigrateError::OnDatabase {
+        database: database.to_string(),
+        source: Box::new(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn memory() -> AnyPool {
+        connect("sqlite::memory:").await.unwrap()
+    }
+
+    fn migrator() -> Migrator {
+        Migrator::new()
+            .migration(
+                Migration::new(2, "create_posts", "CREATE TABLE posts (id INTEGER)")
+                    .down("DROP TABLE posts"),
+            )
+            .migration(
+                Migration::new(1, "create_users", "CREATE TABLE users (id INTEGER)")
+                    .down("DROP TABLE users"),
+            )
+    }
+
+    async fn tables(pool: &AnyPool) -> Vec<String> {
+        sqlx::query(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+             ORDER BY name",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get("name"))
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn test_up_down_and_status() {
+        let pool = memory().await;
+        let migrator = migrator();
+
+        let ran = migrator.up(&pool).await.unwrap();
+        assert_eq!(ran.len(), 2);
+        assert_eq!(ran[0].to_string(), "1_create_users");
+        assert!(migrator.up(&pool).await.unwrap().is_empty());
+        assert_eq!(
+            tables(&pool).await,
+            vec!["posts", "schema_migrations", "users"]
+        );
+
+        // A later migration runs in a new batch
+        let migrator = migrator.migration(Migration::new(
+            3,
+            "create_tags",
+            "CREATE TABLE tags (id INTEGER)",
+        ));
+        migrator.up(&pool).await.unwrap();
+        let status = migrator.status(&pool).await.unwrap();
+        assert_eq!(
+            status.iter().map(|s| s.batch).collect::<Vec<_>>(),
+            vec![Some(1), Some(1), Some(2)]
+        );
+
+        // Irreversible migrations block rolling back
+        assert!(matches!(
+            migrator.down(&pool, 1).await,
+            Err(MigrateError::Irreversible(_))
+        ));
+
+        let migrator = self::migrator();
+        let status = migrator.status(&pool).await.unwrap();
+        assert!(status[2].missing);
+        assert!(matches!(
+            migrator.down(&pool, 1).await,
+            Err(MigrateError::Missing(_))
+        ));
+
+        sqlx::query("DELETE FROM schema_migrations WHERE version = 3")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let rolled_back = migrator.down(&pool, 1).await.unwrap();
+        assert_eq!(rolled_back[0].name, "create_posts");
+        let status = migrator.status(&pool).await.unwrap();
+        assert!(status[0].is_applied());
+        assert!(!status[1].is_applied());
+
+        migrator.redo(&pool, 1).await.unwrap();
+        assert_eq!(migrator.reset(&pool).await.unwrap().len(), 2);
+        assert_eq!(tables(&pool).await, vec!["schema_migrations", "tags"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_migration_rolls_back() {
+        let pool = memory().await;
+        let migrator = migrator().migration(Migration::new(
+            3,
+            "broken",
+            "CREATE TABLE comments (id INTEGER); INSERT INTO missing VALUES (1);",
+        ));
+
+        assert!(matches!(
+            migrator.up(&pool).await,
+            Err(MigrateError::Failed { .. })
+        ));
+        // Earlier migrations stay, the failed one left nothing behind
+        let status = migrator.status(&pool).await.unwrap();
+        assert!(status[1].is_applied());
+        assert!(!status[2].is_applied());
+        assert!(!tables(&pool).await.contains(&"comments".to_string()));
+
+        let duplicate = migrator.migration(Migration::new(1, "again", "SELECT 1"));
+        assert!(matches!(
+            duplicate.up(&pool).await,
+            Err(MigrateError::Invalid(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fresh_and_up_all() {
+        let (central, tenant) = (memory().await, memory().await);
+        let migrator = migrator();
+
+        migrator.up(&central).await.unwrap();
+        sqlx::query("CREATE TABLE leftovers (id INTEGER)")
+            .execute(&central)
+            .await
+            .unwrap();
+        assert_eq!(migrator.fresh(&central).await.unwrap().len(), 2);
+        assert!(!tables(&central).await.contains(&"leftovers".to_string()));
+
+        let counts = migrator
+            .up_all([("central", &central), ("tenant_a", &tenant)])
+            .await
+            .unwrap();
+        assert_eq!(
+            counts,
+            vec![("central".to_string(), 0), ("tenant_a".to_string(), 2)]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! A database per tenant
+
+use crate::{connect, MigrateError, MigrateResult, Migrator};
+use async_trait::async_trait;
+use rf_tenancy::{Tenant, TenantError, TenantProvisioner, TenantResult};
+use std::sync::Arc;
+
+/// Migrates the own database of each tenant
+///
+/// As a [`TenantProvisioner`] it migrates the database of new tenants and
+/// drops all tables of purged ones. The database itself must exist, or be
+/// created on connect like `sqlite://tenant.db?mode=rwc`.
+///
+/// ```no_run
+/// use rf_migrate::{Migrator, TenantMigrations};
+/// use rf_tenancy::TenantManager;
+///
+/// # fn example(store: impl rf_tenancy::TenantStore + 'static) -> rf_migrate::MigrateResult<()> {
+/// let migrations = TenantMigrations::new(Migrator::from_dir("migrations/tenant")?, |tenant| {
+///     format!("postgres://localhost/tenant_{}", tenant.id())
+/// });
+/// let manager = TenantManager::new(store).provisioner(migrations);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct TenantMigrations {
+    migrator: Arc<Migrator>,
+    database_url: Arc<dyn Fn(&Tenant) -> String + Send + Sync>,
+}
+
+impl TenantMigrations {
+    /// Run `migrator` on the database at `database_url(tenant)`
+    pub fn new(
+        migrator: Migrator,
+        database_url: impl Fn(&Tenant) -> String + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            migrator: Arc::new(migrator),
+            database_url: Arc::new(database_url),
+        }
+    }
+
+    /// Run pending migrations for `tenant`, returning how many ran
+    pub async fn migrate(&self, tenant: &Tenant) -> MigrateResult<usize> {
+        let pool = connect(&(self.database_url)(tenant)).await?;
+        let count = self.migrator.up(&pool).await?.len();
+        pool.close().await;
+        Ok(count)
+    }
+
+    /// Run pending migrations for each tenant, returning how many ran per
+    /// tenant ID
+    ///
+    /// Stops at the first tenant that fails.
+    pub async fn migrate_all(&self, tenants: &[Tenant]) -> MigrateResult<Vec<(String, usize)>> {
+        let mut counts = Vec::with_capacity(tenants.len());
+        for tenant in tenants {
+            let count = self
+                .migrate(tenant)
+                .await
+                .map_err(|e| crate::migrator::on_database(tenant.id(), e))?;
+            counts.push((tenant.id().to_string(), count));
+        }
+        Ok(counts)
+    }
+}
+
+fn provisioning_error(e: MigrateError) -> TenantError {
+    TenantError::Provisioning(e.to_string())
+}
+
+#[async_trait]
+impl TenantProvisioner for TenantMigrations {
+    async fn provision(&self, tenant: &Tenant) -> TenantResult<()> {
+        self.migrate(tenant).await.map_err(provisioning_error)?;
+        Ok(())
+    }
+
+    async fn deprovision(&self, tenant: &Tenant) -> TenantResult<()> {
+        let pool = connect(&(self.database_url)(tenant))
+            .await
+            .map_err(provisioning_error)?;
+        self.migrator
+            .wipe(&pool)
+            .await
+            .map_err(provisioning_error)?;
+        pool.close().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Migration;
+
+    #[tokio::test]
+    async fn test_database_per_tenant() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let migrations = TenantMigrations::new(
+            Migrator::new().migration(Migration::new(
+                1,
+                "create_notes",
+                "CREATE TABLE notes (id INTEGER)",
+            )),
+            move |tenant| {
+                format!(
+                    "sqlite://{}?mode=rwc",
+                    root.join(format!("{}.db", tenant.id())).display()
+                )
+            },
+        );
+        let (acme, globex) = (Tenant::new("acme", "Acme"), Tenant::new("globex", "Globex"));
+
+        migrations.provision(&acme).await.unwrap();
+        assert!(dir.path().join("acme.db").exists());
+
+        let counts = migrations
+            .migrate_all(&[acme.clone(), globex])
+            .await
+            .unwrap();
+        assert_eq!(
+            counts,
+            vec![("acme".to_string(), 0), ("globex".to_string(), 1)]
+        );
+
+        migrations.deprovision(&acme).await.unwrap();
+        assert_eq!(migrations.migrate(&acme).await.unwrap(), 1);
+    }
+}
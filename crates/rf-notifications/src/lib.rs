@@ -29,6 +29,14 @@ pub enum NotificationError {
 
 pub type NotificationResult<T> = Result<T, NotificationError>;
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(NotificationError, {
+    Self::RoutingError(_) => (INTERNAL_SERVER_ERROR, "notifications.routing"),
+    Self::ChannelError(_) => (INTERNAL_SERVER_ERROR, "notifications.channel"),
+    Self::TemplateError(_) => (INTERNAL_SERVER_ERROR, "notifications.template"),
+    Self::SendError(_) => (BAD_GATEWAY, "notifications.send_failed"),
+});
+
 /// Notification channels
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Channel {
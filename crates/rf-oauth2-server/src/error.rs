@@ -32,6 +32,18 @@ pub enum OAuth2Error {
     ServerError(String),
 }
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(OAuth2Error, {
+    Self::InvalidClient(_) => (UNAUTHORIZED, "oauth2.invalid_client"),
+    Self::InvalidGrant(_) => (BAD_REQUEST, "oauth2.invalid_grant"),
+    Self::InvalidScope(_) => (BAD_REQUEST, "oauth2.invalid_scope"),
+    Self::InvalidToken(_) => (UNAUTHORIZED, "oauth2.invalid_token"),
+    Self::UnauthorizedClient => (UNAUTHORIZED, "oauth2.unauthorized_client"),
+    Self::UnsupportedGrantType(_) => (BAD_REQUEST, "oauth2.unsupported_grant_type"),
+    Self::InvalidRequest(_) => (BAD_REQUEST, "oauth2.invalid_request"),
+    Self::ServerError(_) => (INTERNAL_SERVER_ERROR, "oauth2.server_error"),
+});
+
 /// Result type for OAuth2 operations
 pub type OAuth2Result<T> = Result<T, OAuth2Error>;
 
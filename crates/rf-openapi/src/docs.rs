@@ -0,0 +1,140 @@
+//! Serving the OpenAPI document and its viewers
+
+use crate::OpenApiResult;
+use axum::{
+    http::header,
+    response::{Html, IntoResponse},
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+use utoipa::openapi::OpenApi;
+
+/// Routes serving an OpenAPI document
+///
+/// Serves the document as JSON (default: `/openapi.json`), Swagger UI
+/// (default: `/docs`) and Redoc (default: `/redoc`). The viewers are
+/// small embedded pages loading their scripts from a CDN.
+///
+/// ```
+/// use axum::Router;
+/// use rf_openapi::Docs;
+/// use utoipa::openapi::OpenApiBuilder;
+///
+/// let openapi = OpenApiBuilder::new().build();
+/// let docs: Router = Docs::new().redoc(None).router(openapi)?;
+/// # Ok::<(), rf_openapi::OpenApiError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Docs {
+    openapi_path: String,
+    swagger_ui_path: Option<String>,
+    redoc_path: Option<String>,
+}
+
+impl Default for Docs {
+    fn default() -> Self {
+        Self {
+            openapi_path: "/openapi.json".to_string(),
+            swagger_ui_path: Some("/docs".to_string()),
+            redoc_path: Some("/redoc".to_string()),
+        }
+    }
+}
+
+impl Docs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the path of the JSON document
+    pub fn openapi_path(mut self, path: impl Into<String>) -> Self {
+        self.openapi_path = path.into();
+        self
+    }
+
+    /// Set the path of Swagger UI; `None` disables it
+    pub fn swagger_ui(mut self, path: Option<&str>) -> Self {
+        self.swagger_ui_path = path.map(str::to_string);
+        self
+    }
+
+    /// Set the path of Redoc; `None` disables it
+    pub fn redoc(mut self, path: Option<&str>) -> Self {
+        self.redoc_path = path.map(str::to_string);
+        self
+    }
+
+    /// Create the routes serving `openapi`
+    pub fn router<S: Clone + Send + Sync + 'static>(
+        &self,
+        openapi: OpenApi,
+    ) -> OpenApiResult<Router<S>> {
+        let title = escape(&openapi.info.title);
+        let json: Arc<str> = serde_json::to_string(&openapi)?.into();
+
+        let mut router = Router::new().route(
+            &self.openapi_path,
+            get(move || {
+                let json = json.clone();
+                async move { ([(header::CONTENT_TYPE, "application/json")], json.to_string()) }
+            }),
+        );
+
+        let url = escape(&self.openapi_path);
+        if let Some(path) = &self.swagger_ui_path {
+            let page = Html(swagger_ui_page(&title, &url));
+            router = router.route(path, get(move || async move { page.into_response() }));
+        }
+        if let Some(path) = &self.redoc_path {
+            let page = Html(redoc_page(&title, &url));
+            router = router.route(path, get(move || async move { page.into_response() }));
+        }
+        Ok(router)
+    }
+}
+
+fn swagger_ui_page(title: &str, url: &str) -> String {
+    format!(
+        r##"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>{title}</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({{ url: "{url}", dom_id: "#swagger-ui" }});
+  </script>
+</body>
+</html>
+"##
+    )
+}
+
+fn redoc_page(title: &str, url: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>{title}</title>
+</head>
+<body>
+  <redoc spec-url="{url}"></redoc>
+  <script src="https://cdn.redoc.ly/redoc/latest/bundles/redoc.standalone.js"></script>
+</body>
+</html>
+"#
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
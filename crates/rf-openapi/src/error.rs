@@ -0,0 +1,44 @@
+//! OpenAPI errors
+
+use std::fmt;
+use thiserror::Error;
+
+/// A route and its documentation disagreeing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteMismatch {
+    /// Routed, but missing from the document
+    Undocumented(String),
+
+    /// In the document, but not routed
+    NotRouted(String),
+}
+
+impl fmt::Display for RouteMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteMismatch::Undocumented(path) => write!(f, "{} is not documented", path),
+            RouteMismatch::NotRouted(path) => write!(f, "{} is documented but not routed", path),
+        }
+    }
+}
+
+/// OpenAPI errors
+#[derive(Debug, Error)]
+pub enum OpenApiError {
+    #[error("Routes don't match the OpenAPI document: {}", list(.0))]
+    Mismatch(Vec<RouteMismatch>),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+fn list(mismatches: &[RouteMismatch]) -> String {
+    mismatches
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Result type for OpenAPI operations
+pub type OpenApiResult<T> = Result<T, OpenApiError>;
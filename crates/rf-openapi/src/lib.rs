@@ -0,0 +1,59 @@
+//! OpenAPI documentation generated from axum routes and types
+//!
+//! Request and response types derive [`ToSchema`] (and [`IntoParams`] for
+//! query and path parameters), handlers are described with
+//! `#[utoipa::path]`, and [`ApiRouter`] assembles the OpenAPI 3.1 document
+//! while registering the handlers. [`Docs`] serves the document at
+//! `/openapi.json` with Swagger UI and Redoc, and
+//! [`ApiRouter::verify`] checks that routes and documentation match.
+//!
+//! The macros refer to `utoipa` by name, so bring it into scope with
+//! `use rf_openapi::utoipa;` when not depending on it directly.
+//!
+//! # Example
+//!
+//! ```
+//! use axum::{extract::Query, Json};
+//! use rf_openapi::{routes, utoipa, ApiRouter, Docs, IntoParams, ToSchema};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize, IntoParams)]
+//! struct Search {
+//!     /// Text to search for
+//!     q: String,
+//! }
+//!
+//! #[derive(Serialize, ToSchema)]
+//! struct Product {
+//!     id: u64,
+//!     name: String,
+//! }
+//!
+//! /// Search products
+//! #[utoipa::path(
+//!     get,
+//!     path = "/products",
+//!     params(Search),
+//!     responses((status = 200, body = [Product])),
+//!     tag = "products"
+//! )]
+//! async fn search(Query(search): Query<Search>) -> Json<Vec<Product>> {
+//!     Json(vec![Product { id: 1, name: search.q }])
+//! }
+//!
+//! let api = ApiRouter::new("Shop API", "1.0.0").routes(routes!(search));
+//! let app: axum::Router = api.into_router(Docs::new())?;
+//! # Ok::<(), rf_openapi::OpenApiError>(())
+//! ```
+
+mod docs;
+mod error;
+mod router;
+
+pub use docs::Docs;
+pub use error::{OpenApiError, OpenApiResult, RouteMismatch};
+pub use router::ApiRouter;
+
+pub use utoipa;
+pub use utoipa::{IntoParams, OpenApi, ToResponse, ToSchema};
+pub use utoipa_axum::routes;
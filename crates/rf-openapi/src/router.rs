@@ -0,0 +1,306 @@
+//! Router assembling the OpenAPI document from its handlers
+
+use crate::{Docs, OpenApiError, OpenApiResult, RouteMismatch};
+use axum::{
+    extract::Request,
+    response::IntoResponse,
+    routing::{MethodRouter, Route},
+    Router,
+};
+use std::convert::Infallible;
+use tower::{Layer, Service};
+use utoipa::openapi::{Info, OpenApi, OpenApiBuilder};
+use utoipa_axum::router::{OpenApiRouter, UtoipaMethodRouter};
+
+/// How a path was registered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// From a `#[utoipa::path]` handler
+    Documented,
+
+    /// Plain axum route, expected to be documented elsewhere
+    Plain,
+
+    /// Deliberately left out of the document
+    Undocumented,
+}
+
+/// Router building an OpenAPI 3.1 document from typed handlers
+///
+/// Handlers annotated with `#[utoipa::path]` are registered with
+/// [`routes`](Self::routes) and the [`routes!`](crate::routes) macro, which
+/// adds them to both the router and the document, including the schemas
+/// of their request and response types.
+///
+/// # Example
+///
+/// ```
+/// use axum::Json;
+/// use rf_openapi::{routes, utoipa, ApiRouter, Docs, ToSchema};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize, ToSchema)]
+/// struct User {
+///     id: u64,
+///     name: String,
+/// }
+///
+/// /// Get a user
+/// #[utoipa::path(get, path = "/users/{id}", responses((status = 200, body = User)))]
+/// async fn show_user(axum::extract::Path(id): axum::extract::Path<u64>) -> Json<User> {
+///     Json(User { id, name: "Ada".into() })
+/// }
+///
+/// let api = ApiRouter::new("Shop API", "1.0.0").routes(routes!(show_user));
+/// api.verify()?;
+///
+/// // Serves /users/{id}, /openapi.json, /docs and /redoc
+/// let app: axum::Router = api.into_router(Docs::new())?;
+/// # Ok::<(), rf_openapi::OpenApiError>(())
+/// ```
+pub struct ApiRouter<S = ()> {
+    inner: OpenApiRouter<S>,
+    paths: Vec<(String, Kind)>,
+}
+
+impl<S: Clone + Send + Sync + 'static> ApiRouter<S> {
+    /// Create a router documenting API `title` in `version`
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        let openapi = OpenApiBuilder::new()
+            .info(Info::new(title.into(), version.into()))
+            .build();
+        Self::with_openapi(openapi)
+    }
+
+    /// Create a router extending `openapi`, e.g. from `#[derive(OpenApi)]`
+    pub fn with_openapi(openapi: OpenApi) -> Self {
+        Self {
+            inner: OpenApiRouter::with_openapi(openapi),
+            paths: Vec::new(),
+        }
+    }
+
+    /// Add documented handlers created with [`routes!`](crate::routes)
+    pub fn routes(mut self, routes: UtoipaMethodRouter<S>) -> Self {
+        for path in routes.1.paths.keys() {
+            let path = if path.is_empty() { "/" } else { path };
+            self.paths.push((path.to_string(), Kind::Documented));
+        }
+        self.inner = self.inner.routes(routes);
+        self
+    }
+
+    /// Add a plain route, documented by other means, e.g. an
+    /// `#[derive(OpenApi)]` passed to [`with_openapi`](Self::with_openapi)
+    pub fn route(mut self, path: &str, method_router: MethodRouter<S>) -> Self {
+        self.paths.push((path.to_string(), Kind::Plain));
+        self.inner = self.inner.route(path, method_router);
+        self
+    }
+
+    /// Add a route deliberately left out of the document, e.g. a health
+    /// check
+    pub fn undocumented_route(mut self, path: &str, method_router: MethodRouter<S>) -> Self {
+        self.paths.push((path.to_string(), Kind::Undocumented));
+        self.inner = self.inner.route(path, method_router);
+        self
+    }
+
+    /// Nest the routes and documentation of `router` below `path`
+    pub fn nest(mut self, path: &str, router: ApiRouter<S>) -> Self {
+        self.paths.extend(
+            router
+                .paths
+                .into_iter()
+                .map(|(nested, kind)| (nested_path(path, &nested), kind)),
+        );
+        self.inner = self.inner.nest(path, router.inner);
+        self
+    }
+
+    /// Merge the routes and documentation of `router`
+    pub fn merge(mut self, router: ApiRouter<S>) -> Self {
+        self.paths.extend(router.paths);
+        self.inner = self.inner.merge(router.inner);
+        self
+    }
+
+    /// Pass through to [`axum::Router::layer`]
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<Route> + Clone + Send + Sync + 'static,
+        L::Service: Service<Request> + Clone + Send + Sync + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Error: Into<Infallible> + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        self.inner = self.inner.layer(layer);
+        self
+    }
+
+    /// Provide the state, like [`axum::Router::with_state`]
+    pub fn with_state<S2>(self, state: S) -> ApiRouter<S2> {
+        ApiRouter {
+            inner: self.inner.with_state(state),
+            paths: self.paths,
+        }
+    }
+
+    /// The document
+    pub fn openapi(&self) -> &OpenApi {
+        self.inner.get_openapi()
+    }
+
+    /// The document, e.g. to add security schemes or servers
+    pub fn openapi_mut(&mut self) -> &mut OpenApi {
+        self.inner.get_openapi_mut()
+    }
+
+    /// Routes and document paths that don't match up
+    pub fn mismatches(&self) -> Vec<RouteMismatch> {
+        let documented = &self.openapi().paths.paths;
+        let mut mismatches: Vec<RouteMismatch> = self
+            .paths
+            .iter()
+            .filter(|(path, kind)| *kind == Kind::Plain && !documented.contains_key(path))
+            .map(|(path, _)| RouteMismatch::Undocumented(path.clone()))
+            .collect();
+
+        mismatches.extend(
+            documented
+                .keys()
+                .filter(|path| !self.paths.iter().any(|(routed, _)| routed == *path))
+                .map(|path| RouteMismatch::NotRouted(path.clone())),
+        );
+        mismatches
+    }
+
+    /// Check that every route is documented and every documented path
+    /// routed, e.g. in a test
+    pub fn verify(&self) -> OpenApiResult<()> {
+        let mismatches = self.mismatches();
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(OpenApiError::Mismatch(mismatches))
+        }
+    }
+
+    /// Split into the router and the document
+    pub fn split_for_parts(self) -> (Router<S>, OpenApi) {
+        self.inner.split_for_parts()
+    }
+
+    /// Create the router, with the document served by `docs`
+    pub fn into_router(self, docs: Docs) -> OpenApiResult<Router<S>> {
+        let (router, openapi) = self.split_for_parts();
+        Ok(router.merge(docs.router(openapi)?))
+    }
+}
+
+/// Path of `path` nested below `prefix`, like axum nests routes
+fn nested_path(prefix: &str, path: &str) -> String {
+    let path = if path.is_empty() { "/" } else { path };
+    if prefix.ends_with('/') {
+        format!("{}{}", prefix, path.trim_start_matches('/'))
+    } else if path == "/" {
+        prefix.to_string()
+    } else {
+        format!("{}{}", prefix, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{routes, ToSchema};
+    use axum::{body::Body, extract::Path, http::StatusCode, routing::get, Json};
+    use serde::{Deserialize, Serialize};
+    use tower::ServiceExt;
+
+    #[derive(Serialize, Deserialize, ToSchema)]
+    struct Todo {
+        id: u64,
+        title: String,
+    }
+
+    #[utoipa::path(get, path = "/todos/{id}", responses((status = 200, body = Todo)))]
+    async fn show_todo(Path(id): Path<u64>) -> Json<Todo> {
+        Json(Todo {
+            id,
+            title: "Write docs".to_string(),
+        })
+    }
+
+    #[utoipa::path(post, path = "/todos", request_body = Todo, responses((status = 201)))]
+    async fn create_todo(Json(_todo): Json<Todo>) -> StatusCode {
+        StatusCode::CREATED
+    }
+
+    fn api() -> ApiRouter {
+        let todos = ApiRouter::new("Todos", "1.0.0").routes(routes!(show_todo, create_todo));
+        ApiRouter::new("Todos", "1.0.0")
+            .nest("/api", todos)
+            .undocumented_route("/health", get(|| async { "ok" }))
+    }
+
+    #[tokio::test]
+    async fn test_document_and_docs() {
+        let api = api();
+        api.verify().unwrap();
+
+        let app = api.into_router(Docs::new()).unwrap();
+        let response = app
+            .clone()
+            .oneshot(Request::get("/openapi.json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(json["openapi"], "3.1.0");
+        assert_eq!(json["info"]["title"], "Todos");
+        assert!(json["paths"]["/api/todos/{id}"]["get"].is_object());
+        assert!(json["paths"]["/api/todos"]["post"]["requestBody"].is_object());
+        assert!(json["components"]["schemas"]["Todo"].is_object());
+        assert!(json["paths"].get("/health").is_none());
+
+        for (uri, status) in [
+            ("/api/todos/1", StatusCode::OK),
+            ("/docs", StatusCode::OK),
+            ("/redoc", StatusCode::OK),
+            ("/health", StatusCode::OK),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), status, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_mismatches() {
+        #[derive(utoipa::OpenApi)]
+        #[openapi(paths(show_todo))]
+        struct Declared;
+
+        use utoipa::OpenApi as _;
+        let api = ApiRouter::<()>::with_openapi(Declared::openapi())
+            .route("/todos", get(|| async { "todos" }))
+            .route("/tags", get(|| async { "tags" }));
+
+        assert_eq!(
+            api.mismatches(),
+            vec![
+                RouteMismatch::Undocumented("/todos".to_string()),
+                RouteMismatch::Undocumented("/tags".to_string()),
+                RouteMismatch::NotRouted("/todos/{id}".to_string()),
+            ]
+        );
+        assert!(api.verify().is_err());
+    }
+}
@@ -0,0 +1,853 @@
+//! Connections and transactions
+
+use crate::listener::Listeners;
+use crate::pool::PoolOptions;
+use crate::replica::{self, LastWrite, Node, ReplicaOptions, Role};
+use crate::{
+    CircuitBreakerConfig, ConnectionStats, Dialect, Model, OrmError, OrmResult, QueryListener,
+    Repository, Value,
+};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use sqlx::any::{AnyPoolOptions, AnyQueryResult, AnyRow};
+use sqlx::{Any, AnyPool};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::Mutex;
+
+#[cfg(feature = "events")]
+use rf_events::EventDispatcher;
+
+/// Where queries run: a [`Db`] or a [`Transaction`]
+#[async_trait]
+pub trait Connection: Send + Sync {
+    fn dialect(&self) -> Dialect;
+
+    /// Run a query returning rows
+    async fn fetch_all(&self, sql: &str, values: Vec<Value>) -> OrmResult<Vec<AnyRow>>;
+
+    /// Run a statement
+    async fn execute(&self, sql: &str, values: Vec<Value>) -> OrmResult<AnyQueryResult>;
+
+    /// Dispatcher of model lifecycle events, see [`Db::events`]
+    #[cfg(feature = "events")]
+    fn events(&self) -> Option<&EventDispatcher> {
+        None
+    }
+}
+
+fn query(
+    sql: &str,
+    values: Vec<Value>,
+) -> sqlx::query::Query<'_, Any, sqlx::any::AnyArguments<'_>> {
+    tracing::debug!(sql, "Running query");
+    values
+        .into_iter()
+        .fold(sqlx::query(sql), |query, value| value.bind(query))
+}
+
+/// Database pool with its dialect
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_orm_lite::{Db, Model};
+/// # use rf_orm_lite::Value;
+/// # #[derive(Clone, sqlx::FromRow)]
+/// # struct User { id: Option<i64>, email: String }
+/// # impl Model for User {
+/// #     const TABLE: &'static str = "users";
+/// #     fn id(&self) -> Option<i64> { self.id }
+/// #     fn values(&self) -> Vec<(&'static str, Value)> {
+/// #         vec![("email", self.email.clone().into())]
+/// #     }
+/// # }
+///
+/// # async fn example() -> rf_orm_lite::OrmResult<()> {
+/// let db = Db::connect("postgres://localhost/app").await?;
+///
+/// let users = db.repository::<User>();
+/// let user = users
+///     .insert(&User { id: None, email: "ada@example.com".into() })
+///     .await?;
+/// let admins = users.get(User::query().where_eq("role", "admin")).await?;
+///
+/// db.transaction(|tx| {
+///     Box::pin(async move {
+///         let users = tx.repository::<User>();
+///         users.delete_many([1, 2]).await?;
+///         Ok(())
+///     })
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Read replicas
+///
+/// With [`replica`](Self::replica)s, plain `SELECT`s run on a healthy
+/// replica, in turn, and everything else on the primary. For a
+/// [`sticky_window`](Self::sticky_window) after a write, reads go to the
+/// primary too, so they see the write despite replication lag. Writes are
+/// tracked per [`scope`](Self::scope), usually one per request, see
+/// `sticky_reads` (feature `axum`).
+///
+/// [`check_replicas`](Self::check_replicas) takes replicas that don't
+/// answer or lag too far behind out of rotation until they recover; a
+/// replica that can't be reached during a read is taken out right away and
+/// the read runs on the primary.
+///
+/// ```no_run
+/// # async fn example() -> rf_orm_lite::OrmResult<()> {
+/// use rf_orm_lite::Db;
+/// use std::time::Duration;
+///
+/// let db = Db::connect("postgres://primary/app")
+///     .await?
+///     .connect_replica("postgres://replica-1/app")
+///     .await?
+///     .connect_replica("postgres://replica-2/app")
+///     .await?
+///     .max_replica_lag(Duration::from_secs(5));
+/// db.spawn_replica_checks(Duration::from_secs(10));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Pool metrics and circuit breaking
+///
+/// Each pool counts how long queries wait for a connection, how often the
+/// wait times out and how often all connections are in use, which also
+/// logs a warning; see [`connection_stats`](Self::connection_stats) and,
+/// with feature `metrics`, [`pool_metrics`](Self::pool_metrics).
+///
+/// With a [`circuit_breaker`](Self::circuit_breaker), a pool whose
+/// connections keep taking too long to acquire sheds load: queries fail
+/// right away with [`OrmError::CircuitOpen`], a 503 through rf-error,
+/// until a trial query gets a connection in time. Reads shed by a replica
+/// run on the primary.
+///
+/// ```no_run
+/// # async fn example() -> rf_orm_lite::OrmResult<()> {
+/// use rf_orm_lite::{CircuitBreakerConfig, Db};
+/// use std::time::Duration;
+///
+/// let db = Db::connect("postgres://localhost/app")
+///     .await?
+///     .circuit_breaker(CircuitBreakerConfig {
+///         max_acquire_time: Duration::from_millis(500),
+///         ..Default::default()
+///     });
+/// for stats in db.connection_stats() {
+///     println!("{}: {}/{} in use, waited {:?} on average",
+///         stats.name, stats.in_use, stats.max_size, stats.wait_avg);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Db {
+    primary: Arc<Node>,
+    replicas: Vec<Arc<Node>>,
+    next_replica: Arc<AtomicUsize>,
+    replica_options: Arc<ReplicaOptions>,
+    pool_options: Arc<PoolOptions>,
+    last_write: LastWrite,
+    dialect: Dialect,
+    transaction_attempts: u32,
+    listeners: Listeners,
+    #[cfg(feature = "events")]
+    events: Option<Arc<EventDispatcher>>,
+}
+
+impl Db {
+    /// Connect to `database_url`, e.g. `postgres://localhost/app` or
+    /// `sqlite://app.db?mode=rwc`
+    pub async fn connect(database_url: &str) -> OrmResult<Self> {
+        sqlx::any::install_default_drivers();
+        let dialect = Dialect::from_url(database_url)?;
+        let pool = AnyPoolOptions::new().connect(database_url).await?;
+        Ok(Self::with_dialect(pool, dialect))
+    }
+
+    /// Use an existing pool
+    pub fn new(pool: AnyPool) -> OrmResult<Self> {
+        let dialect = Dialect::from_url(pool.connect_options().database_url.as_str())?;
+        Ok(Self::with_dialect(pool, dialect))
+    }
+
+    fn with_dialect(pool: AnyPool, dialect: Dialect) -> Self {
+        Self {
+            primary: Arc::new(Node::new(pool, Role::Primary)),
+            replicas: Vec::new(),
+            next_replica: Arc::default(),
+            replica_options: Arc::default(),
+            pool_options: Arc::default(),
+            last_write: LastWrite::default(),
+            dialect,
+            transaction_attempts: 3,
+            listeners: Listeners::default(),
+            #[cfg(feature = "events")]
+            events: None,
+        }
+    }
+
+    /// Run transactions up to `attempts` times when they fail on
+    /// serialization conflicts or deadlocks (default: 3)
+    pub fn transaction_attempts(mut self, attempts: u32) -> Self {
+        self.transaction_attempts = attempts.max(1);
+        self
+    }
+
+    /// Notify `listener` of every query, including those in transactions
+    ///
+    /// ```no_run
+    /// # async fn example() -> rf_orm_lite::OrmResult<()> {
+    /// use rf_orm_lite::{Db, ExecutedQuery};
+    ///
+    /// let db = Db::connect("postgres://localhost/app")
+    ///     .await?
+    ///     .listen(|query: &ExecutedQuery<'_>| {
+    ///         tracing::info!(sql = query.sql, duration = ?query.duration, "Query");
+    ///     });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn listen(mut self, listener: impl QueryListener) -> Self {
+        self.listeners.push(Arc::new(listener));
+        self
+    }
+
+    /// Dispatch `ModelCreating`, `ModelCreated`, `ModelUpdating`,
+    /// `ModelUpdated`, `ModelDeleting` and `ModelDeleted` events when
+    /// repositories change records
+    ///
+    /// Listeners of the events before a change can cancel it by failing.
+    /// In transactions, events are dispatched before the commit. Batch
+    /// operations like [`Repository::delete_where`] dispatch no events.
+    #[cfg(feature = "events")]
+    pub fn events(mut self, dispatcher: Arc<EventDispatcher>) -> Self {
+        self.events = Some(dispatcher);
+        self
+    }
+
+    /// Read from `pool`, a replica of the primary
+    pub fn replica(mut self, pool: AnyPool) -> Self {
+        self.replicas.push(Arc::new(Node::new(pool, Role::Replica)));
+        self
+    }
+
+    /// Connect to the replica at `database_url` and read from it
+    pub async fn connect_replica(self, database_url: &str) -> OrmResult<Self> {
+        let pool = AnyPoolOptions::new().connect(database_url).await?;
+        Ok(self.replica(pool))
+    }
+
+    /// Read from the primary for `window` after a write of the scope
+    /// (default: 5 seconds)
+    pub fn sticky_window(mut self, window: Duration) -> Self {
+        Arc::make_mut(&mut self.replica_options).sticky_window = window;
+        self
+    }
+
+    /// Take replicas lagging further behind out of rotation (default: 10
+    /// seconds)
+    pub fn max_replica_lag(mut self, lag: Duration) -> Self {
+        Arc::make_mut(&mut self.replica_options).max_lag = lag;
+        self
+    }
+
+    /// Query returning the replication lag of a replica in seconds, as a
+    /// float
+    ///
+    /// Postgres replicas report their lag by default; on other databases,
+    /// health checks only check that replicas answer.
+    pub fn replica_lag_query(mut self, sql: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.replica_options).lag_query = Some(sql.into());
+        self
+    }
+
+    /// Fail queries fast while acquiring connections of a pool keeps
+    /// taking too long, see [Pool metrics and circuit breaking](Self#pool-metrics-and-circuit-breaking)
+    pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        Arc::make_mut(&mut self.pool_options).circuit_breaker = Some(config);
+        self
+    }
+
+    /// Export the pools' metrics to `metrics`: `db_pool_connections` (by
+    /// state, `in_use` or `idle`), `db_pool_acquire_seconds`,
+    /// `db_pool_timeouts_total`, `db_pool_exhausted_total`,
+    /// `db_pool_shed_total` and `db_pool_circuit_open`, labeled with the
+    /// database and its role
+    ///
+    /// The connection gauges are set whenever a query asks for a
+    /// connection.
+    #[cfg(feature = "metrics")]
+    pub fn pool_metrics(mut self, metrics: &rf_metrics::Metrics) -> Self {
+        match crate::pool::PoolMetrics::register(metrics) {
+            Ok(registered) => Arc::make_mut(&mut self.pool_options).metrics = Some(registered),
+            Err(e) => tracing::error!(error = %e, "Failed to register database pool metrics"),
+        }
+        self
+    }
+
+    /// The database with its own write tracking, so only writes made
+    /// through it make its reads go to the primary
+    pub fn scope(&self) -> Self {
+        Self {
+            last_write: LastWrite::default(),
+            ..self.clone()
+        }
+    }
+
+    /// [`scope`](Self::scope) continuing from a write at `last_write`,
+    /// e.g. stored in the session by an earlier request
+    pub fn scope_after(&self, last_write: SystemTime) -> Self {
+        Self {
+            last_write: LastWrite::at(last_write),
+            ..self.clone()
+        }
+    }
+
+    /// When the scope last wrote
+    pub fn last_write(&self) -> Option<SystemTime> {
+        self.last_write.get()
+    }
+
+    /// Check that the replicas answer and don't lag too far behind, taking
+    /// them out of rotation or back in
+    pub async fn check_replicas(&self) {
+        let checks = self
+            .replicas
+            .iter()
+            .map(|replica| replica.check(self.dialect, &self.replica_options));
+        futures::future::join_all(checks).await;
+    }
+
+    /// [`check_replicas`](Self::check_replicas) every `interval` in the
+    /// background
+    pub fn spawn_replica_checks(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let db = self.clone();
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                db.check_replicas().await;
+            }
+        })
+    }
+
+    /// Metrics of the primary and the replicas, in that order
+    pub fn connection_stats(&self) -> Vec<ConnectionStats> {
+        std::iter::once(&self.primary)
+            .chain(&self.replicas)
+            .map(|node| node.stats(&self.pool_options))
+            .collect()
+    }
+
+    /// Pool of the primary
+    pub fn pool(&self) -> &AnyPool {
+        &self.primary.pool
+    }
+
+    /// Repository of model `T`
+    pub fn repository<T: Model>(&self) -> Repository<'_, T> {
+        Repository::new(self)
+    }
+
+    /// Start a transaction, rolled back unless committed
+    pub async fn begin(&self) -> OrmResult<Transaction> {
+        Ok(Transaction {
+            inner: Mutex::new(self.primary.begin(&self.pool_options).await?),
+            primary: Arc::clone(&self.primary),
+            last_write: self.last_write.clone(),
+            dialect: self.dialect,
+            listeners: self.listeners.clone(),
+            #[cfg(feature = "events")]
+            events: self.events.clone(),
+        })
+    }
+
+    /// Run `f` in a transaction, committed if it succeeds
+    ///
+    /// On a serialization failure or deadlock the transaction is rolled
+    /// back and `f` runs again, up to
+    /// [`transaction_attempts`](Self::transaction_attempts) times.
+    pub async fn transaction<R, F>(&self, f: F) -> OrmResult<R>
+    where
+        R: Send,
+        F: for<'t> Fn(&'t Transaction) -> BoxFuture<'t, OrmResult<R>> + Send + Sync,
+    {
+        let mut attempt = 1;
+        loop {
+            let tx = self.begin().await?;
+            let result = match f(&tx).await {
+                Ok(value) => tx.commit().await.map(|_| value),
+                Err(e) => {
+                    tx.rollback().await?;
+                    Err(e)
+                }
+            };
+            match result {
+                Err(e) if e.is_serialization_failure() && attempt < self.transaction_attempts => {
+                    tracing::warn!(attempt, error = %e, "Retrying transaction");
+                    tokio::time::sleep(Duration::from_millis(10 << attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Healthy replica to read from next, unless the scope wrote recently
+    fn read_replica(&self) -> Option<&Node> {
+        if self.replicas.is_empty() || self.last_write.within(self.replica_options.sticky_window) {
+            return None;
+        }
+        let start = self.next_replica.fetch_add(1, Ordering::Relaxed);
+        (0..self.replicas.len())
+            .map(|i| &*self.replicas[(start + i) % self.replicas.len()])
+            .find(|replica| replica.is_healthy())
+    }
+
+    async fn fetch_on(&self, node: &Node, sql: &str, values: Vec<Value>) -> OrmResult<Vec<AnyRow>> {
+        let mut conn = node.acquire(&self.pool_options).await?;
+        let result = self
+            .listeners
+            .observe(sql, values, |values| {
+                query(sql, values).fetch_all(&mut *conn)
+            })
+            .await;
+        node.record(&result);
+        Ok(result?)
+    }
+}
+
+#[async_trait]
+impl Connection for Db {
+    fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    async fn fetch_all(&self, sql: &str, values: Vec<Value>) -> OrmResult<Vec<AnyRow>> {
+        if !replica::is_read(sql) {
+            let rows = self.fetch_on(&self.primary, sql, values).await?;
+            self.last_write.record();
+            return Ok(rows);
+        }
+
+        if let Some(replica) = self.read_replica() {
+            match self.fetch_on(replica, sql, values.clone()).await {
+                Err(OrmError::CircuitOpen { .. }) => {}
+                Err(OrmError::Database(e)) if replica::is_unavailable(&e) => {
+                    replica.mark_unhealthy(&e)
+                }
+                result => return result,
+            }
+        }
+        self.fetch_on(&self.primary, sql, values).await
+    }
+
+    async fn execute(&self, sql: &str, values: Vec<Value>) -> OrmResult<AnyQueryResult> {
+        let mut conn = self.primary.acquire(&self.pool_options).await?;
+        let result = self
+            .listeners
+            .observe(sql, values, |values| query(sql, values).execute(&mut *conn))
+            .await;
+        self.primary.record(&result);
+        let result = result?;
+        if !replica::is_read(sql) {
+            self.last_write.record();
+        }
+        Ok(result)
+    }
+
+    #[cfg(feature = "events")]
+    fn events(&self) -> Option<&EventDispatcher> {
+        self.events.as_deref()
+    }
+}
+
+/// Database transaction, see [`Db::begin`] and [`Db::transaction`]
+pub struct Transaction {
+    inner: Mutex<sqlx::Transaction<'static, Any>>,
+    primary: Arc<Node>,
+    last_write: LastWrite,
+    dialect: Dialect,
+    listeners: Listeners,
+    #[cfg(feature = "events")]
+    events: Option<Arc<EventDispatcher>>,
+}
+
+impl Transaction {
+    /// Repository of model `T` working in the transaction
+    pub fn repository<T: Model>(&self) -> Repository<'_, T> {
+        Repository::new(self)
+    }
+
+    /// Commit, making reads of the [`Db`] scope it was started from go to
+    /// the primary for the sticky window
+    pub async fn commit(self) -> OrmResult<()> {
+        self.inner.into_inner().commit().await?;
+        self.last_write.record();
+        Ok(())
+    }
+
+    pub async fn rollback(self) -> OrmResult<()> {
+        Ok(self.inner.into_inner().rollback().await?)
+    }
+}
+
+#[async_trait]
+impl Connection for Transaction {
+    fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    async fn fetch_all(&self, sql: &str, values: Vec<Value>) -> OrmResult<Vec<AnyRow>> {
+        let mut tx = self.inner.lock().await;
+        let rows = self
+            .listeners
+            .observe(sql, values, |values| {
+                query(sql, values).fetch_all(&mut **tx)
+            })
+            .await;
+        self.primary.record(&rows);
+        Ok(rows?)
+    }
+
+    async fn execute(&self, sql: &str, values: Vec<Value>) -> OrmResult<AnyQueryResult> {
+        let mut tx = self.inner.lock().await;
+        let result = self
+            .listeners
+            .observe(sql, values, |values| query(sql, values).execute(&mut **tx))
+            .await;
+        self.primary.record(&result);
+        Ok(result?)
+    }
+
+    #[cfg(feature = "events")]
+    fn events(&self) -> Option<&EventDispatcher> {
+        self.events.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CircuitState, ExecutedQuery, OrmError, Role};
+    use sqlx::error::{DatabaseError, ErrorKind};
+    use sqlx::Row;
+    use std::borrow::Cow;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Error as reported by Postgres for serialization conflicts
+    #[derive(Debug)]
+    struct SerializationFailure;
+
+    impl std::fmt::Display for SerializationFailure {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("could not serialize access")
+        }
+    }
+
+    impl std::error::Error for SerializationFailure {}
+
+    impl DatabaseError for SerializationFailure {
+        fn message(&self) -> &str {
+            "could not serialize access"
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some("40001".into())
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Other
+        }
+    }
+
+    async fn db() -> (Db, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+        let db = Db::connect(&url).await.unwrap();
+        db.execute("CREATE TABLE events (name TEXT NOT NULL)", vec![])
+            .await
+            .unwrap();
+        (db, dir)
+    }
+
+    async fn names(db: &Db) -> Vec<String> {
+        db.fetch_all("SELECT name FROM events ORDER BY name", vec![])
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect()
+    }
+
+    /// Primary with the events table, and a replica pool of a second
+    /// database with one event
+    async fn replicated() -> (Db, AnyPool, tempfile::TempDir) {
+        let (db, dir) = db().await;
+        let url = format!(
+            "sqlite://{}?mode=rwc",
+            dir.path().join("replica.db").display()
+        );
+        let pool = AnyPoolOptions::new().connect(&url).await.unwrap();
+        for sql in [
+            "CREATE TABLE events (name TEXT NOT NULL)",
+            "INSERT INTO events (name) VALUES ('replicated')",
+        ] {
+            sqlx::query(sql).execute(&pool).await.unwrap();
+        }
+        // A scope without the table's creation as write
+        (db.replica(pool.clone()).scope(), pool, dir)
+    }
+
+    #[tokio::test]
+    async fn test_reads_go_to_replicas_unless_sticky() {
+        let (db, _pool, _dir) = replicated().await;
+        assert_eq!(names(&db).await, vec!["replicated"]);
+        assert_eq!(db.last_write(), None);
+
+        db.execute("INSERT INTO events (name) VALUES ('a')", vec![])
+            .await
+            .unwrap();
+        assert!(db.last_write().is_some());
+        assert_eq!(names(&db).await, vec!["a"]);
+        assert_eq!(names(&db.scope()).await, vec!["replicated"]);
+        assert_eq!(
+            names(&db.clone().sticky_window(Duration::ZERO)).await,
+            vec!["replicated"]
+        );
+
+        let earlier = SystemTime::now() - Duration::from_secs(60);
+        assert_eq!(names(&db.scope_after(earlier)).await, vec!["replicated"]);
+        assert_eq!(names(&db.scope_after(SystemTime::now())).await, vec!["a"]);
+
+        let scope = db.scope();
+        let tx = scope.begin().await.unwrap();
+        tx.execute("INSERT INTO events (name) VALUES ('b')", vec![])
+            .await
+            .unwrap();
+        assert_eq!(names(&scope).await, vec!["replicated"]);
+        tx.commit().await.unwrap();
+        assert_eq!(names(&scope).await, vec!["a", "b"]);
+
+        let stats = db.connection_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].role, Role::Primary);
+        assert!(stats[0].name.ends_with("app.db"));
+        assert_eq!(stats[1].role, Role::Replica);
+        assert!(stats[1].name.ends_with("replica.db"));
+        assert_eq!(stats[1].queries, 5);
+        assert_eq!(stats[1].errors, 0);
+        assert!(stats[1].healthy);
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_replicas_fall_back_to_primary() {
+        let (db, pool, _dir) = replicated().await;
+        db.check_replicas().await;
+        let replica = &db.connection_stats()[1];
+        assert!(replica.healthy);
+        assert_eq!(replica.lag, None);
+
+        let lagging = db
+            .clone()
+            .replica_lag_query("SELECT 30.0")
+            .max_replica_lag(Duration::from_secs(10));
+        lagging.check_replicas().await;
+        assert!(!db.connection_stats()[1].healthy);
+        assert_eq!(db.connection_stats()[1].lag, Some(Duration::from_secs(30)));
+        assert_eq!(names(&db).await, Vec::<String>::new());
+
+        let db = db.replica_lag_query("SELECT 0.5");
+        db.check_replicas().await;
+        assert!(db.connection_stats()[1].healthy);
+        assert_eq!(names(&db).await, vec!["replicated"]);
+
+        // Unreachable during a read
+        pool.close().await;
+        assert_eq!(names(&db).await, Vec::<String>::new());
+        let replica = &db.connection_stats()[1];
+        assert!(!replica.healthy);
+        assert_eq!(replica.errors, 1);
+        db.check_replicas().await;
+        assert!(!db.connection_stats()[1].healthy);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_commits_or_rolls_back() {
+        let (db, _dir) = db().await;
+        db.transaction(|tx| {
+            Box::pin(async move {
+                tx.execute("INSERT INTO events (name) VALUES (?)", vec!["a".into()])
+                    .await?;
+                Ok(())
+            })
+        })
+        .await
+        .unwrap();
+
+        let result: OrmResult<()> = db
+            .transaction(|tx| {
+                Box::pin(async move {
+                    tx.execute("INSERT INTO events (name) VALUES (?)", vec!["b".into()])
+                        .await?;
+                    Err(OrmError::InvalidQuery("abort".to_string()))
+                })
+            })
+            .await;
+        assert!(result.is_err());
+
+        let tx = db.begin().await.unwrap();
+        tx.execute("INSERT INTO events (name) VALUES ('c')", vec![])
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(names(&db).await, vec!["a", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_sheds_queries() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+        sqlx::any::install_default_drivers();
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_millis(50))
+            .connect(&url)
+            .await
+            .unwrap();
+        let db = Db::new(pool.clone())
+            .unwrap()
+            .circuit_breaker(CircuitBreakerConfig {
+                max_acquire_time: Duration::from_secs(1),
+                failure_threshold: 1,
+                reset_timeout: Duration::from_millis(100),
+            });
+        db.execute("CREATE TABLE events (name TEXT NOT NULL)", vec![])
+            .await
+            .unwrap();
+        let stats = &db.connection_stats()[0];
+        assert_eq!((stats.acquires, stats.max_size), (1, 1));
+        assert_eq!(stats.circuit, CircuitState::Closed);
+
+        // The only connection is taken
+        let held = pool.acquire().await.unwrap();
+        let sql = "SELECT name FROM events";
+        assert!(matches!(
+            db.fetch_all(sql, vec![]).await,
+            Err(OrmError::Database(sqlx::Error::PoolTimedOut))
+        ));
+        assert!(matches!(
+            db.fetch_all(sql, vec![]).await,
+            Err(OrmError::CircuitOpen { .. })
+        ));
+        let stats = &db.connection_stats()[0];
+        assert_eq!((stats.timeouts, stats.exhausted, stats.shed), (1, 1, 1));
+        assert_eq!((stats.in_use, stats.errors), (1, 2));
+        assert!(stats.wait_max >= Duration::from_millis(50));
+        assert_eq!(stats.circuit, CircuitState::Open);
+
+        // A trial query closes the circuit again
+        drop(held);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(names(&db).await, Vec::<String>::new());
+        assert_eq!(db.connection_stats()[0].circuit, CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_query_listeners() {
+        let (db, _dir) = db().await;
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let db = db.listen({
+            let seen = Arc::clone(&seen);
+            move |query: &ExecutedQuery<'_>| {
+                let entry = (query.sql.to_string(), query.values.to_vec(), query.failed);
+                seen.lock().unwrap().push(entry);
+            }
+        });
+
+        db.execute("INSERT INTO events (name) VALUES (?)", vec!["a".into()])
+            .await
+            .unwrap();
+        let tx = db.begin().await.unwrap();
+        tx.fetch_all("SELECT name FROM events", vec![])
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        assert!(db.execute("SELECT * FROM missing", vec![]).await.is_err());
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(
+            *seen,
+            [
+                (
+                    "INSERT INTO events (name) VALUES (?)".to_string(),
+                    vec![Value::from("a")],
+                    false
+                ),
+                ("SELECT name FROM events".to_string(), vec![], false),
+                ("SELECT * FROM missing".to_string(), vec![], true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transaction_retries_serialization_failures() {
+        let (db, _dir) = db().await;
+        let attempts = Arc::new(AtomicU32::new(0));
+
+        let result = db
+            .transaction(|tx| {
+                let attempts = Arc::clone(&attempts);
+                Box::pin(async move {
+                    let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+                    tx.execute(
+                        "INSERT INTO events (name) VALUES (?)",
+                        vec![format!("attempt {}", attempt).into()],
+                    )
+                    .await?;
+                    if attempt < 3 {
+                        return Err(sqlx::Error::Database(Box::new(SerializationFailure)).into());
+                    }
+                    Ok(attempt)
+                })
+            })
+            .await;
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(names(&db).await, vec!["attempt 3"]);
+
+        // Gives up after the configured attempts
+        attempts.store(0, Ordering::SeqCst);
+        let result = db
+            .transaction_attempts(2)
+            .transaction(|_| {
+                let attempts = Arc::clone(&attempts);
+                Box::pin(async move {
+                    attempts.fetch_add(1, Ordering::SeqCst);
+                    Err::<(), _>(sqlx::Error::Database(Box::new(SerializationFailure)).into())
+                })
+            })
+            .await;
+        assert!(result.unwrap_err().is_serialization_failure());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,73 @@
+//! SQL dialects of the supported databases
+
+use crate::{OrmError, OrmResult};
+
+/// Database a query is compiled for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl Dialect {
+    /// Dialect of a database URL, e.g. `postgres://localhost/app`
+    pub fn from_url(url: &str) -> OrmResult<Self> {
+        let scheme = url.split(':').next().unwrap_or_default();
+        match scheme {
+            "postgres" | "postgresql" => Ok(Self::Postgres),
+            "mysql" | "mariadb" => Ok(Self::MySql),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => Err(OrmError::UnsupportedDatabase(scheme.to_string())),
+        }
+    }
+
+    /// Bind parameter `n`, counting from 1
+    pub fn placeholder(self, n: usize) -> String {
+        match self {
+            Self::Postgres => format!("${}", n),
+            Self::MySql | Self::Sqlite => "?".to_string(),
+        }
+    }
+
+    /// Quote an identifier; `users.id` is quoted per part and `*` is kept
+    pub fn quote(self, identifier: &str) -> String {
+        identifier
+            .split('.')
+            .map(|part| match (part, self) {
+                ("*", _) => part.to_string(),
+                (_, Self::MySql) => format!("`{}`", part.replace('`', "``")),
+                _ => format!("\"{}\"", part.replace('"', "\"\"")),
+            })
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// Whether `INSERT … RETURNING` is supported
+    pub fn supports_returning(self) -> bool {
+        !matches!(self, Self::MySql)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dialect() {
+        assert_eq!(
+            Dialect::from_url("postgresql://localhost/app").unwrap(),
+            Dialect::Postgres
+        );
+        assert_eq!(
+            Dialect::from_url("sqlite::memory:").unwrap(),
+            Dialect::Sqlite
+        );
+        assert!(Dialect::from_url("oracle://db").is_err());
+
+        assert_eq!(Dialect::Postgres.quote("users.*"), "\"users\".*");
+        assert_eq!(Dialect::MySql.quote("na`me"), "`na``me`");
+        assert_eq!(Dialect::Postgres.placeholder(2), "$2");
+        assert_eq!(Dialect::Sqlite.placeholder(2), "?");
+    }
+}
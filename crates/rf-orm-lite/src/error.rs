@@ -0,0 +1,115 @@
+//! Data access errors
+
+use thiserror::Error;
+
+/// Data access errors
+#[derive(Debug, Error)]
+pub enum OrmError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("No {table} record with ID {id}")]
+    NotFound { table: &'static str, id: i64 },
+
+    /// The record's version changed since it was read, see
+    /// [`Model::VERSION_COLUMN`](crate::Model::VERSION_COLUMN)
+    #[error("The {table} record with ID {id} was changed in the meantime")]
+    StaleRecord { table: &'static str, id: i64 },
+
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
+    #[error("Unsupported database: {0}")]
+    UnsupportedDatabase(String),
+
+    /// Waiting for connections of the database was too slow, so queries
+    /// fail fast for a while, see
+    /// [`Db::circuit_breaker`](crate::Db::circuit_breaker)
+    #[error("Circuit breaker open for database {database}")]
+    CircuitOpen { database: String },
+
+    /// A model event listener failed, cancelling the operation if it ran
+    /// before it
+    #[cfg(feature = "events")]
+    #[error("Model event listener failed: {0}")]
+    Event(#[from] rf_events::EventError),
+}
+
+impl OrmError {
+    /// Whether a transaction failed on a serialization conflict or deadlock
+    /// and may succeed when run again
+    pub fn is_serialization_failure(&self) -> bool {
+        let OrmError::Database(sqlx::Error::Database(error)) = self else {
+            return false;
+        };
+        matches!(
+            error.code().as_deref(),
+            // Postgres and MySQL SQLSTATEs, SQLite's SQLITE_BUSY codes
+            Some("40001" | "40P01" | "5" | "517")
+        )
+    }
+}
+
+/// Result type for data access
+pub type OrmResult<T> = Result<T, OrmError>;
+
+#[cfg(feature = "rf-error")]
+#[allow(unreachable_patterns)]
+impl rf_error::FrameworkError for OrmError {
+    fn status(&self) -> rf_error::StatusCode {
+        use rf_error::StatusCode;
+
+        match self {
+            Self::NotFound { .. } => StatusCode::NOT_FOUND,
+            Self::StaleRecord { .. } => StatusCode::CONFLICT,
+            Self::CircuitOpen { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound { .. } => "orm.not_found",
+            Self::StaleRecord { .. } => "orm.stale_record",
+            Self::Database(_) => "orm.database",
+            Self::InvalidQuery(_) => "orm.invalid_query",
+            Self::UnsupportedDatabase(_) => "orm.unsupported_database",
+            Self::CircuitOpen { .. } => "orm.circuit_open",
+            // Failed model event listeners
+            _ => "orm.internal",
+        }
+    }
+
+    fn metadata(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut metadata = serde_json::Map::new();
+        if let Self::NotFound { table, id } | Self::StaleRecord { table, id } = self {
+            metadata.insert("resource".into(), (*table).into());
+            metadata.insert("id".into(), (*id).into());
+        }
+        metadata
+    }
+}
+
+#[cfg(all(test, feature = "rf-error"))]
+mod tests {
+    use super::*;
+    use rf_error::{FrameworkError, StatusCode};
+
+    #[test]
+    fn test_problem_metadata() {
+        let error = OrmError::StaleRecord {
+            table: "posts",
+            id: 7,
+        };
+        let problem = error.to_problem();
+        assert_eq!(problem.status, 409);
+        assert_eq!(problem.extensions["resource"], "posts");
+        assert_eq!(problem.extensions["id"], 7);
+
+        let error = OrmError::CircuitOpen {
+            database: "localhost/app".into(),
+        };
+        assert_eq!(error.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(error.code(), "orm.circuit_open");
+    }
+}
@@ -0,0 +1,107 @@
+//! Lightweight data access for RustForge
+//!
+//! A [`Repository`] per [`Model`] covers the usual CRUD and batch
+//! operations, and a typed [`Query`] builder the rest, without the weight
+//! of a full ORM. Queries run on sqlx's `Any` driver, so the same code
+//! works on Postgres, SQLite and MySQL (feature `mysql`).
+//!
+//! # Features
+//!
+//! - Conditions, joins, ordering, limits and offsets compiled to each
+//!   database's SQL
+//! - Find, insert, update, save and delete, plus batch inserts, updates and
+//!   deletes
+//! - Optimistic locking with a version column, see
+//!   [`Model::VERSION_COLUMN`]
+//! - Soft deletes left out of queries unless asked for, see
+//!   [`Model::SOFT_DELETE_COLUMN`]
+//! - Creating, created, updating, updated, deleting and deleted events
+//!   through rf-events (feature `events`)
+//! - Transactions retried on serialization failures and deadlocks, see
+//!   [`Db::transaction`]
+//! - Query listeners seeing each query with its values and duration, see
+//!   [`Db::listen`]
+//! - Slow query warnings and N+1 detection per request, see [`QueryLog`]
+//!   and, with feature `axum`, `log_queries`
+//! - Read replicas with sticky reads after writes, health checks and
+//!   per-connection metrics, see [`Db`]
+//! - Pool wait times, timeouts and exhaustion warnings, exported to
+//!   rf-metrics (feature `metrics`), and circuit breakers shedding queries
+//!   while connections are too slow to get, see [`Db::circuit_breaker`]
+//! - Offset and cursor pages of rf-pagination, see `Repository::paginate`
+//!   and `Repository::cursor_paginate` (feature `pagination`)
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rf_orm_lite::{Db, Model, Op, Value};
+//!
+//! #[derive(Clone, sqlx::FromRow)]
+//! struct Order {
+//!     id: Option<i64>,
+//!     customer_id: i64,
+//!     total_cents: i64,
+//! }
+//!
+//! impl Model for Order {
+//!     const TABLE: &'static str = "orders";
+//!
+//!     fn id(&self) -> Option<i64> {
+//!         self.id
+//!     }
+//!
+//!     fn values(&self) -> Vec<(&'static str, Value)> {
+//!         vec![
+//!             ("customer_id", self.customer_id.into()),
+//!             ("total_cents", self.total_cents.into()),
+//!         ]
+//!     }
+//! }
+//!
+//! # async fn example() -> rf_orm_lite::OrmResult<()> {
+//! let db = Db::connect("postgres://localhost/shop").await?;
+//! let orders = db.repository::<Order>();
+//!
+//! let order = orders
+//!     .insert(&Order { id: None, customer_id: 7, total_cents: 4200 })
+//!     .await?;
+//! let large = orders
+//!     .get(Order::query().filter("total_cents", Op::Gt, 10_000).limit(20))
+//!     .await?;
+//! orders.delete(order.id.unwrap()).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod db;
+mod dialect;
+mod error;
+mod listener;
+mod model;
+#[cfg(feature = "pagination")]
+mod pagination;
+mod pool;
+mod query;
+mod query_log;
+mod replica;
+mod repository;
+mod value;
+
+pub use db::{Connection, Db, Transaction};
+pub use dialect::Dialect;
+pub use error::{OrmError, OrmResult};
+pub use listener::{ExecutedQuery, QueryListener};
+pub use model::Model;
+pub use pool::{CircuitBreakerConfig, CircuitState};
+pub use query::{Op, Order, Query};
+pub use query_log::{statement_shape, LoggedQuery, QueryLog, QueryReport, RepeatedQuery};
+pub use replica::{ConnectionStats, Role};
+pub use repository::Repository;
+pub use value::Value;
+
+#[cfg(feature = "axum")]
+pub use query_log::log_queries;
+#[cfg(feature = "axum")]
+pub use replica::sticky_reads;
+
+pub use sqlx;
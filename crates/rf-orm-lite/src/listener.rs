@@ -0,0 +1,71 @@
+//! Listeners notified of every query
+
+use crate::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A query that ran on a [`Db`](crate::Db) or one of its transactions
+#[derive(Debug, Clone, Copy)]
+pub struct ExecutedQuery<'a> {
+    pub sql: &'a str,
+    pub values: &'a [Value],
+    pub duration: Duration,
+    /// Whether the database returned an error
+    pub failed: bool,
+}
+
+/// Notified after each query, see [`Db::listen`](crate::Db::listen)
+///
+/// Listeners run on the task that ran the query, so they can find
+/// task-local request context, and should return quickly.
+pub trait QueryListener: Send + Sync + 'static {
+    fn query(&self, query: &ExecutedQuery<'_>);
+}
+
+impl<F> QueryListener for F
+where
+    F: Fn(&ExecutedQuery<'_>) + Send + Sync + 'static,
+{
+    fn query(&self, query: &ExecutedQuery<'_>) {
+        self(query)
+    }
+}
+
+/// Listeners of a [`Db`](crate::Db), shared with its transactions
+#[derive(Clone, Default)]
+pub(crate) struct Listeners(Vec<Arc<dyn QueryListener>>);
+
+impl Listeners {
+    pub(crate) fn push(&mut self, listener: Arc<dyn QueryListener>) {
+        self.0.push(listener);
+    }
+
+    /// Run `run` with `values`, notifying the listeners when it's done
+    pub(crate) async fn observe<T, E, F>(
+        &self,
+        sql: &str,
+        values: Vec<Value>,
+        run: impl FnOnce(Vec<Value>) -> F,
+    ) -> Result<T, E>
+    where
+        F: std::future::Future<Output = Result<T, E>>,
+    {
+        if self.0.is_empty() {
+            return run(values).await;
+        }
+
+        let bound = values.clone();
+        let started = Instant::now();
+        let result = run(values).await;
+        let query = ExecutedQuery {
+            sql,
+            values: &bound,
+            duration: started.elapsed(),
+            failed: result.is_err(),
+        };
+        for listener in &self.0 {
+            listener.query(&query);
+        }
+        result
+    }
+}
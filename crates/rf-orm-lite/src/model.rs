@@ -0,0 +1,77 @@
+//! Models mapped to tables
+
+use crate::{Query, Value};
+use sqlx::any::AnyRow;
+use sqlx::FromRow;
+
+/// Record of a table with an integer primary key
+///
+/// Rows are read with sqlx's [`FromRow`], usually derived. Column types
+/// are those of sqlx's `Any` driver: integers, floats, strings, bytes and
+/// booleans (SQLite: `BOOLEAN` columns). Models are cloned into the
+/// lifecycle events dispatched by repositories (feature `events`).
+///
+/// ```
+/// use rf_orm_lite::{Model, Value};
+///
+/// #[derive(Clone, sqlx::FromRow)]
+/// struct Post {
+///     id: Option<i64>,
+///     title: String,
+///     version: i64,
+/// }
+///
+/// impl Model for Post {
+///     const TABLE: &'static str = "posts";
+///     const VERSION_COLUMN: Option<&'static str> = Some("version");
+///     const SOFT_DELETE_COLUMN: Option<&'static str> = Some("deleted_at");
+///
+///     fn id(&self) -> Option<i64> {
+///         self.id
+///     }
+///
+///     fn values(&self) -> Vec<(&'static str, Value)> {
+///         vec![("title", self.title.clone().into())]
+///     }
+///
+///     fn version(&self) -> Option<i64> {
+///         Some(self.version)
+///     }
+/// }
+/// ```
+pub trait Model: for<'r> FromRow<'r, AnyRow> + Clone + Send + Sync + Unpin + 'static {
+    const TABLE: &'static str;
+
+    const PRIMARY_KEY: &'static str = "id";
+
+    /// Integer column counting updates, enabling optimistic locking
+    ///
+    /// It's set to 1 on insert and incremented on each update; updating a
+    /// record whose version changed since it was read fails with
+    /// [`OrmError::StaleRecord`](crate::OrmError::StaleRecord).
+    const VERSION_COLUMN: Option<&'static str> = None;
+
+    /// Timestamp column marking soft deleted records
+    ///
+    /// Deleting a record sets it instead of removing the row, and queries
+    /// leave such records out, see [`Query::with_trashed`].
+    const SOFT_DELETE_COLUMN: Option<&'static str> = None;
+
+    /// Primary key, `None` until inserted
+    fn id(&self) -> Option<i64>;
+
+    /// Columns written on insert and update, without the primary key and
+    /// the version column
+    fn values(&self) -> Vec<(&'static str, Value)>;
+
+    /// Version the record was read with, see
+    /// [`VERSION_COLUMN`](Self::VERSION_COLUMN)
+    fn version(&self) -> Option<i64> {
+        None
+    }
+
+    /// Query the model's table
+    fn query() -> Query<Self> {
+        Query::new()
+    }
+}
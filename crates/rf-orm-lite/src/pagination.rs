@@ -0,0 +1,161 @@
+//! Pages of records for list endpoints (feature `pagination`)
+
+use crate::{Model, Op, OrmError, OrmResult, Query, Repository};
+use rf_pagination::{CursorDirection, CursorPaginated, CursorParams, PageParams, Paginated};
+
+impl<T: Model> Repository<'_, T> {
+    /// The requested page of the records matching `query`, which must not
+    /// have a limit or offset
+    ///
+    /// ```ignore
+    /// async fn index(params: PageParams, uri: Uri) -> OrmResult<Paginated<Post>> {
+    ///     let page = db.repository::<Post>().paginate(Post::query(), params).await?;
+    ///     Ok(page.url(uri.to_string()))
+    /// }
+    /// ```
+    pub async fn paginate(&self, query: Query<T>, params: PageParams) -> OrmResult<Paginated<T>> {
+        let total = self.count(query.clone()).await?;
+        let items = self
+            .get(
+                query
+                    .offset(params.offset() as u64)
+                    .limit(params.limit() as u64),
+            )
+            .await?;
+        Ok(Paginated::new(items, params.paginator(total)))
+    }
+
+    /// The requested page of the records matching `query`, ordered by
+    /// primary key, with the primary keys as cursors
+    ///
+    /// `query` must not be ordered, limited or have `or_filter` conditions.
+    pub async fn cursor_paginate(
+        &self,
+        query: Query<T>,
+        params: &CursorParams,
+    ) -> OrmResult<CursorPaginated<T>> {
+        let paginator = params.paginator();
+        let query = match &paginator.cursor {
+            None => query.order_by(T::PRIMARY_KEY),
+            Some(cursor) => {
+                let id: i64 = cursor.value.parse().map_err(|_| {
+                    OrmError::InvalidQuery(format!("Invalid cursor: {}", cursor.value))
+                })?;
+                match cursor.direction {
+                    CursorDirection::After => query
+                        .filter(T::PRIMARY_KEY, Op::Gt, id)
+                        .order_by(T::PRIMARY_KEY),
+                    CursorDirection::Before => query
+                        .filter(T::PRIMARY_KEY, Op::Lt, id)
+                        .order_by_desc(T::PRIMARY_KEY),
+                }
+            }
+        };
+
+        let rows = self
+            .get(query.limit(paginator.fetch_limit() as u64))
+            .await?;
+        Ok(CursorPaginated::from_rows(rows, &paginator, |row| {
+            row.id().unwrap_or_default().to_string()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db, Value};
+    use rf_pagination::PageBounds;
+
+    #[derive(Debug, Clone, sqlx::FromRow)]
+    struct Post {
+        id: Option<i64>,
+        title: String,
+    }
+
+    impl Model for Post {
+        const TABLE: &'static str = "posts";
+
+        fn id(&self) -> Option<i64> {
+            self.id
+        }
+
+        fn values(&self) -> Vec<(&'static str, Value)> {
+            vec![("title", self.title.as_str().into())]
+        }
+    }
+
+    async fn db() -> (Db, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+        let db = Db::connect(&url).await.unwrap();
+        db.execute(
+            "CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT NOT NULL)",
+            vec![],
+        )
+        .await
+        .unwrap();
+        let posts: Vec<Post> = (1..=7)
+            .map(|i| Post {
+                id: None,
+                title: format!("Post {}", i),
+            })
+            .collect();
+        db.repository::<Post>().insert_many(&posts).await.unwrap();
+        (db, dir)
+    }
+
+    fn ids(posts: &[Post]) -> Vec<i64> {
+        posts.iter().filter_map(|post| post.id).collect()
+    }
+
+    #[tokio::test]
+    async fn test_paginate() {
+        let (db, _dir) = db().await;
+        let posts = db.repository::<Post>();
+        let params = PageParams::new(2, 3, PageBounds::default()).unwrap();
+
+        let page = posts
+            .paginate(Post::query().order_by("id"), params)
+            .await
+            .unwrap();
+        assert_eq!(ids(&page.items), [4, 5, 6]);
+        assert_eq!(page.paginator.total, 7);
+        assert_eq!(page.paginator.last_page, 3);
+
+        let page = posts
+            .paginate(Post::query().filter("id", Op::Gt, 5), params)
+            .await
+            .unwrap();
+        assert!(page.items.is_empty());
+        assert_eq!(page.paginator.total, 2);
+    }
+
+    #[tokio::test]
+    async fn test_cursor_paginate() {
+        let (db, _dir) = db().await;
+        let posts = db.repository::<Post>();
+        let bounds = PageBounds::default();
+
+        let params = CursorParams::new(None, None, 3, bounds).unwrap();
+        let page = posts.cursor_paginate(Post::query(), &params).await.unwrap();
+        assert_eq!(ids(&page.items), [1, 2, 3]);
+        assert_eq!(page.next_cursor.as_deref(), Some("3"));
+
+        let params = CursorParams::new(page.next_cursor, None, 3, bounds).unwrap();
+        let page = posts.cursor_paginate(Post::query(), &params).await.unwrap();
+        assert_eq!(ids(&page.items), [4, 5, 6]);
+        assert_eq!(page.prev_cursor.as_deref(), Some("4"));
+
+        let params = CursorParams::new(None, page.prev_cursor, 2, bounds).unwrap();
+        let page = posts.cursor_paginate(Post::query(), &params).await.unwrap();
+        assert_eq!(ids(&page.items), [2, 3]);
+        assert!(page.has_more);
+
+        let params = CursorParams::new(Some("x".to_string()), None, 2, bounds).unwrap();
+        assert!(matches!(
+            posts.cursor_paginate(Post::query(), &params).await,
+            Err(OrmError::InvalidQuery(_))
+        ));
+    }
+}
@@ -0,0 +1,392 @@
+//! Pool metrics and circuit breaking
+
+use crate::{OrmError, OrmResult, Role};
+use sqlx::AnyPool;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Least time between two pool exhaustion warnings of a pool
+const EXHAUSTION_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// When a pool's circuit breaker opens and for how long, see
+/// [`Db::circuit_breaker`](crate::Db::circuit_breaker)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// Acquiring a connection taking longer counts as a failure
+    pub max_acquire_time: Duration,
+
+    /// Consecutive slow or failed acquisitions that open the circuit
+    pub failure_threshold: u32,
+
+    /// How long the circuit stays open before a trial query is let through
+    pub reset_timeout: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            max_acquire_time: Duration::from_secs(1),
+            failure_threshold: 5,
+            reset_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// State of a pool's circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Queries run
+    Closed,
+    /// Queries fail fast until the reset timeout passed
+    Open,
+    /// A single trial query decides whether to close or reopen
+    HalfOpen,
+}
+
+/// Settings of all pools of a [`Db`](crate::Db)
+#[derive(Clone, Default)]
+pub(crate) struct PoolOptions {
+    pub(crate) circuit_breaker: Option<CircuitBreakerConfig>,
+    #[cfg(feature = "metrics")]
+    pub(crate) metrics: Option<PoolMetrics>,
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+}
+
+impl Breaker {
+    fn state(&self, config: &CircuitBreakerConfig) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(at) if at.elapsed() < config.reset_timeout => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+}
+
+/// Waits for connections of a pool and the circuit breaker they feed
+#[derive(Default)]
+pub(crate) struct PoolMonitor {
+    acquires: AtomicU64,
+    wait_us: AtomicU64,
+    max_wait_us: AtomicU64,
+    timeouts: AtomicU64,
+    exhausted: AtomicU64,
+    shed: AtomicU64,
+    last_warning: Mutex<Option<Instant>>,
+    breaker: Mutex<Breaker>,
+}
+
+/// Pool metrics of a [`ConnectionStats`](crate::ConnectionStats)
+pub(crate) struct PoolStats {
+    pub(crate) acquires: u64,
+    pub(crate) wait_avg: Duration,
+    pub(crate) wait_max: Duration,
+    pub(crate) timeouts: u64,
+    pub(crate) exhausted: u64,
+    pub(crate) shed: u64,
+    pub(crate) circuit: CircuitState,
+}
+
+impl PoolMonitor {
+    /// Run `acquire`, getting a connection of `pool`, unless the circuit is
+    /// open, recording how long it took
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub(crate) async fn acquire<T>(
+        &self,
+        name: &str,
+        role: Role,
+        pool: &AnyPool,
+        options: &PoolOptions,
+        acquire: impl Future<Output = Result<T, sqlx::Error>>,
+    ) -> OrmResult<T> {
+        #[cfg(feature = "metrics")]
+        let metrics = options.metrics.as_ref();
+        if let Some(config) = &options.circuit_breaker {
+            if !self.permit(config) {
+                self.shed.fetch_add(1, Ordering::Relaxed);
+                #[cfg(feature = "metrics")]
+                if let Some(metrics) = metrics {
+                    metrics.shed.with_label_values(&[name, role.label()]).inc();
+                }
+                return Err(OrmError::CircuitOpen {
+                    database: name.to_string(),
+                });
+            }
+        }
+
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = metrics {
+            metrics.observe_pool(name, role, pool);
+        }
+        if pool.num_idle() == 0 && pool.size() >= pool.options().get_max_connections() {
+            self.exhausted(name, pool);
+            #[cfg(feature = "metrics")]
+            if let Some(metrics) = metrics {
+                metrics
+                    .exhausted
+                    .with_label_values(&[name, role.label()])
+                    .inc();
+            }
+        }
+
+        let started = Instant::now();
+        let result = acquire.await;
+        let wait = started.elapsed();
+        let wait_us = wait.as_micros() as u64;
+        self.acquires.fetch_add(1, Ordering::Relaxed);
+        self.wait_us.fetch_add(wait_us, Ordering::Relaxed);
+        self.max_wait_us.fetch_max(wait_us, Ordering::Relaxed);
+        let timed_out = matches!(result, Err(sqlx::Error::PoolTimedOut));
+        if timed_out {
+            self.timeouts.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if let Some(config) = &options.circuit_breaker {
+            if result.is_err() || wait > config.max_acquire_time {
+                self.record_failure(name, config, wait);
+            } else {
+                self.record_success(name, config);
+            }
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = metrics {
+            let labels = [name, role.label()];
+            metrics
+                .acquire
+                .with_label_values(&labels)
+                .observe(wait.as_secs_f64());
+            if timed_out {
+                metrics.timeouts.with_label_values(&labels).inc();
+            }
+            if let Some(config) = &options.circuit_breaker {
+                let open = self.state(config) != CircuitState::Closed;
+                metrics
+                    .circuit_open
+                    .with_label_values(&labels)
+                    .set(if open { 1.0 } else { 0.0 });
+            }
+        }
+        Ok(result?)
+    }
+
+    /// Check if a query may acquire a connection
+    fn permit(&self, config: &CircuitBreakerConfig) -> bool {
+        let mut breaker = self.breaker.lock().unwrap();
+        match breaker.state(config) {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen if breaker.trial_in_flight => false,
+            CircuitState::HalfOpen => {
+                breaker.trial_in_flight = true;
+                true
+            }
+        }
+    }
+
+    /// Count a fast acquisition, closing the circuit if it was the trial
+    ///
+    /// Acquisitions started before the circuit opened leave it open.
+    fn record_success(&self, name: &str, config: &CircuitBreakerConfig) {
+        let mut breaker = self.breaker.lock().unwrap();
+        match breaker.state(config) {
+            CircuitState::Closed => breaker.failures = 0,
+            CircuitState::HalfOpen if breaker.trial_in_flight => {
+                tracing::info!(database = name, "Database circuit breaker closed");
+                *breaker = Breaker::default();
+            }
+            _ => {}
+        }
+    }
+
+    /// Count a slow or failed acquisition, opening the circuit at the
+    /// threshold or when the trial query failed
+    fn record_failure(&self, name: &str, config: &CircuitBreakerConfig, wait: Duration) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.failures += 1;
+        if breaker.trial_in_flight || breaker.failures >= config.failure_threshold {
+            if breaker.opened_at.is_none() || breaker.trial_in_flight {
+                tracing::warn!(
+                    database = name,
+                    failures = breaker.failures,
+                    ?wait,
+                    "Database circuit breaker opened, shedding queries"
+                );
+            }
+            breaker.opened_at = Some(Instant::now());
+            breaker.trial_in_flight = false;
+        }
+    }
+
+    /// Count that all connections were in use, warning at most every
+    /// [`EXHAUSTION_WARNING_INTERVAL`]
+    fn exhausted(&self, name: &str, pool: &AnyPool) {
+        self.exhausted.fetch_add(1, Ordering::Relaxed);
+        let mut last = self.last_warning.lock().unwrap();
+        if last.is_none_or(|at| at.elapsed() >= EXHAUSTION_WARNING_INTERVAL) {
+            *last = Some(Instant::now());
+            tracing::warn!(
+                database = name,
+                size = pool.size(),
+                "Database pool exhausted, queries are waiting for connections"
+            );
+        }
+    }
+
+    pub(crate) fn state(&self, config: &CircuitBreakerConfig) -> CircuitState {
+        self.breaker.lock().unwrap().state(config)
+    }
+
+    pub(crate) fn stats(&self, options: &PoolOptions) -> PoolStats {
+        let acquires = self.acquires.load(Ordering::Relaxed);
+        let wait_us = self.wait_us.load(Ordering::Relaxed);
+        PoolStats {
+            acquires,
+            wait_avg: Duration::from_micros(wait_us.checked_div(acquires).unwrap_or(0)),
+            wait_max: Duration::from_micros(self.max_wait_us.load(Ordering::Relaxed)),
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            exhausted: self.exhausted.load(Ordering::Relaxed),
+            shed: self.shed.load(Ordering::Relaxed),
+            circuit: options
+                .circuit_breaker
+                .as_ref()
+                .map_or(CircuitState::Closed, |config| self.state(config)),
+        }
+    }
+}
+
+impl Role {
+    #[cfg(feature = "metrics")]
+    fn label(self) -> &'static str {
+        match self {
+            Role::Primary => "primary",
+            Role::Replica => "replica",
+        }
+    }
+}
+
+/// Prometheus collectors of the pools, see
+/// [`Db::pool_metrics`](crate::Db::pool_metrics)
+#[cfg(feature = "metrics")]
+#[derive(Clone)]
+pub(crate) struct PoolMetrics {
+    connections: prometheus::GaugeVec,
+    acquire: prometheus::HistogramVec,
+    timeouts: prometheus::CounterVec,
+    exhausted: prometheus::CounterVec,
+    shed: prometheus::CounterVec,
+    circuit_open: prometheus::GaugeVec,
+}
+
+#[cfg(feature = "metrics")]
+impl PoolMetrics {
+    pub(crate) fn register(metrics: &rf_metrics::Metrics) -> rf_metrics::MetricsResult<Self> {
+        let labels = ["database", "role"];
+        Ok(Self {
+            connections: metrics.gauge_vec(
+                "db_pool_connections",
+                "Open connections of a database pool by state (in_use, idle)",
+                &["database", "role", "state"],
+            )?,
+            acquire: metrics.histogram_vec(
+                "db_pool_acquire_seconds",
+                "Time queries waited for a database connection",
+                &labels,
+            )?,
+            timeouts: metrics.counter_vec(
+                "db_pool_timeouts_total",
+                "Queries that timed out waiting for a database connection",
+                &labels,
+            )?,
+            exhausted: metrics.counter_vec(
+                "db_pool_exhausted_total",
+                "Queries that found all connections of a database pool in use",
+                &labels,
+            )?,
+            shed: metrics.counter_vec(
+                "db_pool_shed_total",
+                "Queries failed fast by an open database circuit breaker",
+                &labels,
+            )?,
+            circuit_open: metrics.gauge_vec(
+                "db_pool_circuit_open",
+                "Whether the circuit breaker of a database pool is open",
+                &labels,
+            )?,
+        })
+    }
+
+    /// Set the connection gauges of `pool`
+    fn observe_pool(&self, name: &str, role: Role, pool: &AnyPool) {
+        let (size, idle) = (pool.size(), pool.num_idle() as u32);
+        for (state, count) in [("in_use", size.saturating_sub(idle)), ("idle", idle)] {
+            self.connections
+                .with_label_values(&[name, role.label(), state])
+                .set(count.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_circuit_breaker() {
+        let monitor = PoolMonitor::default();
+        let config = CircuitBreakerConfig {
+            max_acquire_time: Duration::from_millis(100),
+            failure_threshold: 2,
+            reset_timeout: Duration::from_secs(10),
+        };
+
+        assert!(monitor.permit(&config));
+        monitor.record_failure("app", &config, Duration::from_secs(1));
+        assert_eq!(monitor.state(&config), CircuitState::Closed);
+        monitor.record_failure("app", &config, Duration::from_secs(1));
+        assert_eq!(monitor.state(&config), CircuitState::Open);
+        assert!(!monitor.permit(&config));
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(monitor.state(&config), CircuitState::HalfOpen);
+        assert!(monitor.permit(&config));
+        // Only one trial query at a time
+        assert!(!monitor.permit(&config));
+
+        // Failed trial reopens
+        monitor.record_failure("app", &config, Duration::from_secs(1));
+        assert_eq!(monitor.state(&config), CircuitState::Open);
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert!(monitor.permit(&config));
+        monitor.record_success("app", &config);
+        assert_eq!(monitor.state(&config), CircuitState::Closed);
+        assert!(monitor.permit(&config));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn test_pool_metrics() {
+        use crate::{Connection, Db};
+
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+        let metrics = rf_metrics::Metrics::new();
+        let db = Db::connect(&url).await.unwrap().pool_metrics(&metrics);
+        db.execute("CREATE TABLE events (name TEXT NOT NULL)", vec![])
+            .await
+            .unwrap();
+
+        let rendered = metrics.render().unwrap();
+        assert!(rendered.contains("db_pool_acquire_seconds_count"));
+        assert!(rendered.contains("role=\"primary\",state=\"idle\""));
+        assert!(!rendered.contains("db_pool_circuit_open"));
+    }
+}
@@ -0,0 +1,617 @@
+//! Typed query builder
+
+use crate::{Dialect, Model, OrmError, OrmResult, Value};
+use std::marker::PhantomData;
+
+/// Comparison operator of a condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Like,
+    NotLike,
+}
+
+impl Op {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "<>",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Like => "LIKE",
+            Op::NotLike => "NOT LIKE",
+        }
+    }
+}
+
+/// Sort direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+/// Which soft deleted records a query sees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trashed {
+    Without,
+    With,
+    Only,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Connector {
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    Compare {
+        column: String,
+        op: Op,
+        value: Value,
+    },
+    In {
+        column: String,
+        values: Vec<Value>,
+        negated: bool,
+    },
+    Null {
+        column: String,
+        negated: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Join {
+    kind: &'static str,
+    table: String,
+    left: String,
+    right: String,
+}
+
+/// Query on the table of model `T`
+///
+/// Conditions are combined with `AND`, or `OR` for the `or_*` methods, in
+/// the order they are added. Columns may be qualified with their table,
+/// e.g. `posts.user_id`. Soft deleted records of models with a
+/// [`SOFT_DELETE_COLUMN`](Model::SOFT_DELETE_COLUMN) are left out unless
+/// asked for with [`with_trashed`](Self::with_trashed) or
+/// [`only_trashed`](Self::only_trashed).
+///
+/// ```
+/// use rf_orm_lite::{Dialect, Model, Op, Query, Value};
+/// # #[derive(Clone, sqlx::FromRow)]
+/// # struct Post { id: i64 }
+/// # impl Model for Post {
+/// #     const TABLE: &'static str = "posts";
+/// #     fn id(&self) -> Option<i64> { Some(self.id) }
+/// #     fn values(&self) -> Vec<(&'static str, Value)> { vec![] }
+/// # }
+///
+/// let query = Post::query()
+///     .join("users", "users.id", "posts.user_id")
+///     .where_eq("users.active", true)
+///     .filter("posts.views", Op::Ge, 100)
+///     .order_by_desc("posts.published_at")
+///     .limit(10);
+///
+/// let (sql, values) = query.to_sql(Dialect::Postgres);
+/// assert_eq!(
+///     sql,
+///     "SELECT \"posts\".* FROM \"posts\" \
+///      INNER JOIN \"users\" ON \"users\".\"id\" = \"posts\".\"user_id\" \
+///      WHERE \"users\".\"active\" = $1 AND \"posts\".\"views\" >= $2 \
+///      ORDER BY \"posts\".\"published_at\" DESC LIMIT 10"
+/// );
+/// assert_eq!(values, vec![Value::Bool(true), Value::Int(100)]);
+/// ```
+#[derive(Debug)]
+pub struct Query<T> {
+    columns: Vec<String>,
+    joins: Vec<Join>,
+    conditions: Vec<(Connector, Condition)>,
+    orders: Vec<(String, Order)>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+    trashed: Trashed,
+    model: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Query<T> {
+    fn clone(&self) -> Self {
+        Self {
+            columns: self.columns.clone(),
+            joins: self.joins.clone(),
+            conditions: self.conditions.clone(),
+            orders: self.orders.clone(),
+            limit: self.limit,
+            offset: self.offset,
+            trashed: self.trashed,
+            model: PhantomData,
+        }
+    }
+}
+
+impl<T: Model> Default for Query<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Model> Query<T> {
+    /// Query all records
+    pub fn new() -> Self {
+        Self {
+            columns: Vec::new(),
+            joins: Vec::new(),
+            conditions: Vec::new(),
+            orders: Vec::new(),
+            limit: None,
+            offset: None,
+            trashed: Trashed::Without,
+            model: PhantomData,
+        }
+    }
+
+    /// Select these columns instead of all of the model's table
+    pub fn select(mut self, columns: &[&str]) -> Self {
+        self.columns = columns.iter().map(|column| column.to_string()).collect();
+        self
+    }
+
+    /// Add a condition
+    pub fn filter(self, column: &str, op: Op, value: impl Into<Value>) -> Self {
+        self.condition(
+            Connector::And,
+            Condition::Compare {
+                column: column.to_string(),
+                op,
+                value: value.into(),
+            },
+        )
+    }
+
+    /// Add an alternative condition
+    pub fn or_filter(self, column: &str, op: Op, value: impl Into<Value>) -> Self {
+        self.condition(
+            Connector::Or,
+            Condition::Compare {
+                column: column.to_string(),
+                op,
+                value: value.into(),
+            },
+        )
+    }
+
+    /// Add a `column = value` condition
+    pub fn where_eq(self, column: &str, value: impl Into<Value>) -> Self {
+        self.filter(column, Op::Eq, value)
+    }
+
+    /// Add an alternative `column = value` condition
+    pub fn or_where_eq(self, column: &str, value: impl Into<Value>) -> Self {
+        self.or_filter(column, Op::Eq, value)
+    }
+
+    /// Add a `column IN (…)` condition; never true without values
+    pub fn where_in<V: Into<Value>>(
+        self,
+        column: &str,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        self.condition(
+            Connector::And,
+            Condition::In {
+                column: column.to_string(),
+                values: values.into_iter().map(Into::into).collect(),
+                negated: false,
+            },
+        )
+    }
+
+    /// Add a `column NOT IN (…)` condition
+    pub fn where_not_in<V: Into<Value>>(
+        self,
+        column: &str,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        self.condition(
+            Connector::And,
+            Condition::In {
+                column: column.to_string(),
+                values: values.into_iter().map(Into::into).collect(),
+                negated: true,
+            },
+        )
+    }
+
+    pub fn where_null(self, column: &str) -> Self {
+        self.condition(
+            Connector::And,
+            Condition::Null {
+                column: column.to_string(),
+                negated: false,
+            },
+        )
+    }
+
+    pub fn where_not_null(self, column: &str) -> Self {
+        self.condition(
+            Connector::And,
+            Condition::Null {
+                column: column.to_string(),
+                negated: true,
+            },
+        )
+    }
+
+    /// Include soft deleted records
+    pub fn with_trashed(mut self) -> Self {
+        self.trashed = Trashed::With;
+        self
+    }
+
+    /// Only query soft deleted records
+    pub fn only_trashed(mut self) -> Self {
+        self.trashed = Trashed::Only;
+        self
+    }
+
+    /// Inner join `table` on `left = right`
+    pub fn join(self, table: &str, left: &str, right: &str) -> Self {
+        self.add_join("INNER JOIN", table, left, right)
+    }
+
+    /// Left join `table` on `left = right`
+    pub fn left_join(self, table: &str, left: &str, right: &str) -> Self {
+        self.add_join("LEFT JOIN", table, left, right)
+    }
+
+    pub fn order_by(mut self, column: &str) -> Self {
+        self.orders.push((column.to_string(), Order::Asc));
+        self
+    }
+
+    pub fn order_by_desc(mut self, column: &str) -> Self {
+        self.orders.push((column.to_string(), Order::Desc));
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Compile to a `SELECT` with its bound values
+    pub fn to_sql(&self, dialect: Dialect) -> (String, Vec<Value>) {
+        let mut sql = Sql::new(dialect);
+        sql.push("SELECT ");
+        if self.columns.is_empty() {
+            sql.push_identifier(&format!("{}.*", T::TABLE));
+        } else {
+            sql.push_identifiers(&self.columns);
+        }
+        self.push_from(&mut sql);
+
+        if !self.orders.is_empty() {
+            sql.push(" ORDER BY ");
+            for (i, (column, order)) in self.orders.iter().enumerate() {
+                if i > 0 {
+                    sql.push(", ");
+                }
+                sql.push_identifier(column);
+                if *order == Order::Desc {
+                    sql.push(" DESC");
+                }
+            }
+        }
+        match (self.limit, self.offset) {
+            (Some(limit), _) => sql.push(&format!(" LIMIT {}", limit)),
+            // MySQL and SQLite only know OFFSET after a LIMIT
+            (None, Some(_)) => match dialect {
+                Dialect::Postgres => {}
+                Dialect::MySql => sql.push(" LIMIT 18446744073709551615"),
+                Dialect::Sqlite => sql.push(" LIMIT -1"),
+            },
+            (None, None) => {}
+        }
+        if let Some(offset) = self.offset {
+            sql.push(&format!(" OFFSET {}", offset));
+        }
+        sql.finish()
+    }
+
+    /// Compile to a `SELECT COUNT(*)`, ignoring order, limit and offset
+    pub(crate) fn count_sql(&self, dialect: Dialect) -> (String, Vec<Value>) {
+        let mut sql = Sql::new(dialect);
+        sql.push("SELECT COUNT(*)");
+        self.push_from(&mut sql);
+        sql.finish()
+    }
+
+    /// Append the `WHERE` clause of an `UPDATE` or `DELETE`
+    pub(crate) fn push_where_only(&self, sql: &mut Sql) -> OrmResult<()> {
+        if !self.joins.is_empty() || self.limit.is_some() || self.offset.is_some() {
+            return Err(OrmError::InvalidQuery(
+                "Updates and deletes can't have joins, limits or offsets".to_string(),
+            ));
+        }
+        self.push_where(sql);
+        Ok(())
+    }
+
+    fn condition(mut self, connector: Connector, condition: Condition) -> Self {
+        self.conditions.push((connector, condition));
+        self
+    }
+
+    fn add_join(mut self, kind: &'static str, table: &str, left: &str, right: &str) -> Self {
+        self.joins.push(Join {
+            kind,
+            table: table.to_string(),
+            left: left.to_string(),
+            right: right.to_string(),
+        });
+        self
+    }
+
+    fn push_from(&self, sql: &mut Sql) {
+        sql.push(" FROM ");
+        sql.push_identifier(T::TABLE);
+        for join in &self.joins {
+            sql.push(&format!(" {} ", join.kind));
+            sql.push_identifier(&join.table);
+            sql.push(" ON ");
+            sql.push_identifier(&join.left);
+            sql.push(" = ");
+            sql.push_identifier(&join.right);
+        }
+        self.push_where(sql);
+    }
+
+    fn push_where(&self, sql: &mut Sql) {
+        let scope = match (T::SOFT_DELETE_COLUMN, self.trashed) {
+            (Some(column), Trashed::Without) => Some((column, false)),
+            (Some(column), Trashed::Only) => Some((column, true)),
+            (_, _) => None,
+        };
+        // The scope applies to all conditions, including alternatives
+        let grouped = scope.is_some()
+            && self
+                .conditions
+                .iter()
+                .any(|(connector, _)| *connector == Connector::Or);
+
+        if !self.conditions.is_empty() {
+            sql.push(if grouped { " WHERE (" } else { " WHERE " });
+        }
+        for (i, (connector, condition)) in self.conditions.iter().enumerate() {
+            if i > 0 {
+                sql.push(match connector {
+                    Connector::And => " AND ",
+                    Connector::Or => " OR ",
+                });
+            }
+            push_condition(sql, condition);
+        }
+        if grouped {
+            sql.push(")");
+        }
+
+        if let Some((column, trashed)) = scope {
+            sql.push(if self.conditions.is_empty() {
+                " WHERE "
+            } else {
+                " AND "
+            });
+            sql.push_identifier(&format!("{}.{}", T::TABLE, column));
+            sql.push(if trashed { " IS NOT NULL" } else { " IS NULL" });
+        }
+    }
+}
+
+fn push_condition(sql: &mut Sql, condition: &Condition) {
+    match condition {
+        Condition::Compare { column, op, value } => {
+            sql.push_identifier(column);
+            sql.push(&format!(" {} ", op.as_sql()));
+            sql.push_value(value.clone());
+        }
+        Condition::In {
+            values, negated, ..
+        } if values.is_empty() => sql.push(if *negated { "1 = 1" } else { "1 = 0" }),
+        Condition::In {
+            column,
+            values,
+            negated,
+        } => {
+            sql.push_identifier(column);
+            sql.push(if *negated { " NOT IN (" } else { " IN (" });
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    sql.push(", ");
+                }
+                sql.push_value(value.clone());
+            }
+            sql.push(")");
+        }
+        Condition::Null { column, negated } => {
+            sql.push_identifier(column);
+            sql.push(if *negated { " IS NOT NULL" } else { " IS NULL" });
+        }
+    }
+}
+
+/// SQL text and its bound values, numbering placeholders as it goes
+pub(crate) struct Sql {
+    dialect: Dialect,
+    text: String,
+    values: Vec<Value>,
+}
+
+impl Sql {
+    pub(crate) fn new(dialect: Dialect) -> Self {
+        Self {
+            dialect,
+            text: String::new(),
+            values: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    pub(crate) fn push_identifier(&mut self, identifier: &str) {
+        self.text.push_str(&self.dialect.quote(identifier));
+    }
+
+    /// Comma separated identifiers
+    pub(crate) fn push_identifiers(&mut self, identifiers: &[impl AsRef<str>]) {
+        for (i, identifier) in identifiers.iter().enumerate() {
+            if i > 0 {
+                self.push(", ");
+            }
+            self.push_identifier(identifier.as_ref());
+        }
+    }
+
+    pub(crate) fn push_value(&mut self, value: Value) {
+        self.values.push(value);
+        self.text
+            .push_str(&self.dialect.placeholder(self.values.len()));
+    }
+
+    pub(crate) fn finish(self) -> (String, Vec<Value>) {
+        (self.text, self.values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, sqlx::FromRow)]
+    struct User {
+        id: i64,
+    }
+
+    impl Model for User {
+        const TABLE: &'static str = "users";
+
+        fn id(&self) -> Option<i64> {
+            Some(self.id)
+        }
+
+        fn values(&self) -> Vec<(&'static str, Value)> {
+            vec![]
+        }
+    }
+
+    #[test]
+    fn test_conditions() {
+        let query = User::query()
+            .select(&["id", "name"])
+            .where_in("role", ["admin", "editor"])
+            .where_not_null("verified_at")
+            .or_filter("name", Op::Like, "a%")
+            .where_in("id", Vec::<i64>::new())
+            .order_by("name")
+            .order_by_desc("id");
+
+        let (sql, values) = query.to_sql(Dialect::MySql);
+        assert_eq!(
+            sql,
+            "SELECT `id`, `name` FROM `users` WHERE `role` IN (?, ?) \
+             AND `verified_at` IS NOT NULL OR `name` LIKE ? AND 1 = 0 \
+             ORDER BY `name`, `id` DESC"
+        );
+        assert_eq!(values.len(), 3);
+
+        let (sql, values) = query.count_sql(Dialect::Postgres);
+        assert_eq!(
+            sql,
+            "SELECT COUNT(*) FROM \"users\" WHERE \"role\" IN ($1, $2) \
+             AND \"verified_at\" IS NOT NULL OR \"name\" LIKE $3 AND 1 = 0"
+        );
+        assert_eq!(values[2], Value::Text("a%".to_string()));
+    }
+
+    #[test]
+    fn test_limit_and_offset() {
+        let query = User::query().where_null("deleted_at").offset(20);
+        assert_eq!(
+            query.to_sql(Dialect::Postgres).0,
+            "SELECT \"users\".* FROM \"users\" WHERE \"deleted_at\" IS NULL OFFSET 20"
+        );
+        assert_eq!(
+            query.clone().limit(10).to_sql(Dialect::Sqlite).0,
+            "SELECT \"users\".* FROM \"users\" WHERE \"deleted_at\" IS NULL LIMIT 10 OFFSET 20"
+        );
+        assert!(query
+            .to_sql(Dialect::Sqlite)
+            .0
+            .ends_with("LIMIT -1 OFFSET 20"));
+
+        let mut sql = Sql::new(Dialect::Sqlite);
+        assert!(query.push_where_only(&mut sql).is_err());
+    }
+
+    #[derive(Clone, sqlx::FromRow)]
+    struct Post {
+        id: i64,
+    }
+
+    impl Model for Post {
+        const TABLE: &'static str = "posts";
+        const SOFT_DELETE_COLUMN: Option<&'static str> = Some("deleted_at");
+
+        fn id(&self) -> Option<i64> {
+            Some(self.id)
+        }
+
+        fn values(&self) -> Vec<(&'static str, Value)> {
+            vec![]
+        }
+    }
+
+    #[test]
+    fn test_soft_delete_scope() {
+        assert_eq!(
+            Post::query().to_sql(Dialect::Sqlite).0,
+            "SELECT \"posts\".* FROM \"posts\" WHERE \"posts\".\"deleted_at\" IS NULL"
+        );
+        assert_eq!(
+            Post::query()
+                .where_eq("draft", true)
+                .or_where_eq("author_id", 1)
+                .only_trashed()
+                .count_sql(Dialect::MySql)
+                .0,
+            "SELECT COUNT(*) FROM `posts` WHERE (`draft` = ? OR `author_id` = ?) \
+             AND `posts`.`deleted_at` IS NOT NULL"
+        );
+        assert_eq!(
+            Post::query()
+                .where_eq("draft", true)
+                .with_trashed()
+                .to_sql(Dialect::Postgres)
+                .0,
+            "SELECT \"posts\".* FROM \"posts\" WHERE \"draft\" = $1"
+        );
+    }
+}
@@ -0,0 +1,332 @@
+//! Slow query and N+1 detection
+
+use crate::listener::{ExecutedQuery, QueryListener};
+use crate::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+tokio::task_local! {
+    static SCOPE: Arc<Mutex<Vec<LoggedQuery>>>;
+}
+
+/// Query listener logging every query with its duration, warning about
+/// slow queries and, per scope, about likely N+1 queries
+///
+/// A scope, usually a request, collects its queries; when it ends, statements
+/// of the same shape that ran more often than the limit are reported, as
+/// they usually come from loading relations one record at a time.
+///
+/// ```no_run
+/// use rf_orm_lite::{Connection, Db, QueryLog};
+/// use std::time::Duration;
+///
+/// # async fn example() -> rf_orm_lite::OrmResult<()> {
+/// let log = QueryLog::new()
+///     .slow_after(Duration::from_millis(250))
+///     .repeat_limit(10);
+/// let db = Db::connect("postgres://localhost/app")
+///     .await?
+///     .listen(log);
+///
+/// let (users, report) = log.scope(db.fetch_all("SELECT * FROM users", vec![])).await;
+/// for repeated in report.repeated() {
+///     println!("{} ran {} times", repeated.shape, repeated.count);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct QueryLog {
+    slow_after: Duration,
+    repeat_limit: usize,
+}
+
+impl QueryLog {
+    /// Log with a 100 ms slow query threshold and a repeat limit of 5
+    pub fn new() -> Self {
+        Self {
+            slow_after: Duration::from_millis(100),
+            repeat_limit: 5,
+        }
+    }
+
+    /// Warn about queries taking at least `threshold`
+    pub fn slow_after(mut self, threshold: Duration) -> Self {
+        self.slow_after = threshold;
+        self
+    }
+
+    /// Warn when a statement shape runs more than `limit` times in a scope
+    pub fn repeat_limit(mut self, limit: usize) -> Self {
+        self.repeat_limit = limit;
+        self
+    }
+
+    /// Run `future` collecting its queries, then warn about repeated
+    /// statements
+    pub async fn scope<F: Future>(&self, future: F) -> (F::Output, QueryReport) {
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let output = SCOPE.scope(Arc::clone(&queries), future).await;
+        let queries = std::mem::take(&mut *queries.lock().unwrap());
+
+        let report = QueryReport {
+            log: *self,
+            queries,
+        };
+        for repeated in report.repeated() {
+            tracing::warn!(
+                sql = %repeated.shape,
+                count = repeated.count,
+                total_ms = repeated.total.as_millis() as u64,
+                "Possible N+1 query"
+            );
+        }
+        (output, report)
+    }
+}
+
+/// Middleware running each request in a [`QueryLog::scope`], so N+1
+/// queries are reported per request (feature `axum`)
+///
+/// ```
+/// use axum::{middleware, routing::get, Router};
+/// use rf_orm_lite::{log_queries, QueryLog};
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|| async { "home" }))
+///     .layer(middleware::from_fn_with_state(QueryLog::new(), log_queries));
+/// ```
+#[cfg(feature = "axum")]
+pub async fn log_queries(
+    axum::extract::State(log): axum::extract::State<QueryLog>,
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use tracing::Instrument;
+
+    let span = tracing::info_span!("request", method = %req.method(), path = %req.uri().path());
+    let (response, _) = log.scope(next.run(req)).instrument(span).await;
+    response
+}
+
+impl Default for QueryLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QueryListener for QueryLog {
+    fn query(&self, query: &ExecutedQuery<'_>) {
+        let duration_ms = query.duration.as_millis() as u64;
+        if query.duration >= self.slow_after {
+            tracing::warn!(sql = query.sql, duration_ms, "Slow query");
+        } else {
+            tracing::debug!(sql = query.sql, duration_ms, "Query finished");
+        }
+
+        let _ = SCOPE.try_with(|queries| {
+            queries.lock().unwrap().push(LoggedQuery {
+                sql: query.sql.to_string(),
+                values: query.values.to_vec(),
+                duration: query.duration,
+                failed: query.failed,
+            });
+        });
+    }
+}
+
+/// A query run in a [`QueryLog::scope`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggedQuery {
+    pub sql: String,
+    pub values: Vec<Value>,
+    pub duration: Duration,
+    pub failed: bool,
+}
+
+/// Statements of one shape that ran more often than the repeat limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepeatedQuery {
+    /// The statement with literals and placeholders replaced by `?`, see
+    /// [`statement_shape`]
+    pub shape: String,
+    pub count: usize,
+    pub total: Duration,
+}
+
+/// Queries of a [`QueryLog::scope`], in the order they ran
+#[derive(Debug, Clone)]
+pub struct QueryReport {
+    log: QueryLog,
+    pub queries: Vec<LoggedQuery>,
+}
+
+impl QueryReport {
+    pub fn total_duration(&self) -> Duration {
+        self.queries.iter().map(|query| query.duration).sum()
+    }
+
+    /// Queries that took at least the slow query threshold
+    pub fn slow(&self) -> impl Iterator<Item = &LoggedQuery> {
+        let threshold = self.log.slow_after;
+        self.queries
+            .iter()
+            .filter(move |query| query.duration >= threshold)
+    }
+
+    /// Statement shapes that ran more often than the repeat limit, most
+    /// frequent first
+    pub fn repeated(&self) -> Vec<RepeatedQuery> {
+        let mut shapes: HashMap<String, RepeatedQuery> = HashMap::new();
+        for query in &self.queries {
+            let shape = statement_shape(&query.sql);
+            let repeated = shapes.entry(shape.clone()).or_insert(RepeatedQuery {
+                shape,
+                count: 0,
+                total: Duration::ZERO,
+            });
+            repeated.count += 1;
+            repeated.total += query.duration;
+        }
+
+        let mut repeated: Vec<_> = shapes
+            .into_values()
+            .filter(|repeated| repeated.count > self.log.repeat_limit)
+            .collect();
+        repeated.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.shape.cmp(&b.shape)));
+        repeated
+    }
+
+    /// The slow and repeated queries, described for people
+    pub fn warnings(&self) -> Vec<String> {
+        let slow = self.slow().map(|query| {
+            format!(
+                "Slow query ({} ms): {}",
+                query.duration.as_millis(),
+                query.sql
+            )
+        });
+        let repeated = self.repeated().into_iter().map(|repeated| {
+            format!(
+                "Possible N+1 query, ran {} times: {}",
+                repeated.count, repeated.shape
+            )
+        });
+        slow.chain(repeated).collect()
+    }
+}
+
+/// `sql` with string and number literals and bind placeholders replaced by
+/// `?`, lists of them collapsed and whitespace normalized, so statements
+/// differing only in their values have the same shape
+///
+/// ```
+/// use rf_orm_lite::statement_shape;
+///
+/// assert_eq!(
+///     statement_shape("SELECT * FROM posts\n WHERE user_id = $1 AND id IN (3, 4, 5)"),
+///     "SELECT * FROM posts WHERE user_id = ? AND id IN (?)"
+/// );
+/// ```
+pub fn statement_shape(sql: &str) -> String {
+    let mut shape = String::with_capacity(sql.len());
+    let mut chars = sql.trim().trim_end_matches(';').chars().peekable();
+    let mut in_word = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                // A doubled quote is a quote inside the literal
+                while let Some(c) = chars.next() {
+                    if c == '\'' && chars.peek() != Some(&'\'') {
+                        break;
+                    }
+                    if c == '\'' {
+                        chars.next();
+                    }
+                }
+                shape.push('?');
+            }
+            '$' | '?' if !in_word => {
+                while chars.next_if(|c| c.is_ascii_digit()).is_some() {}
+                shape.push('?');
+            }
+            c if c.is_ascii_digit() && !in_word => {
+                while chars.next_if(|c| c.is_ascii_digit() || *c == '.').is_some() {}
+                shape.push('?');
+            }
+            c if c.is_whitespace() => {
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                shape.push(' ');
+            }
+            c => shape.push(c),
+        }
+        in_word = c.is_alphanumeric() || c == '_';
+    }
+
+    let mut collapsed = shape.replace("?,?", "?, ?");
+    while collapsed.contains("?, ?") {
+        collapsed = collapsed.replace("?, ?", "?");
+    }
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Connection, Db};
+
+    #[test]
+    fn test_statement_shape() {
+        assert_eq!(
+            statement_shape("select * from users where name = 'O''Brien' and t1.age > 30;"),
+            "select * from users where name = ? and t1.age > ?"
+        );
+        assert_eq!(
+            statement_shape("INSERT INTO tags (name, post_id) VALUES (?, ?),(?,?)"),
+            "INSERT INTO tags (name, post_id) VALUES (?),(?)"
+        );
+        assert_eq!(
+            statement_shape("SELECT * FROM users WHERE id = $12"),
+            statement_shape("SELECT * FROM users WHERE id = 7")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scope_reports_repeated_and_slow_queries() {
+        let log = QueryLog::new()
+            .repeat_limit(2)
+            .slow_after(Duration::from_secs(3600));
+        let db = Db::connect("sqlite::memory:").await.unwrap().listen(log);
+
+        let (_, report) = log
+            .scope(async {
+                db.fetch_all("SELECT 1", vec![]).await.unwrap();
+                for id in 1..=3 {
+                    db.fetch_all("SELECT ? AS id", vec![id.into()])
+                        .await
+                        .unwrap();
+                }
+            })
+            .await;
+        db.fetch_all("SELECT 2", vec![]).await.unwrap();
+
+        assert_eq!(report.queries.len(), 4);
+        assert_eq!(report.queries[1].values, vec![Value::Int(1)]);
+        let repeated = report.repeated();
+        assert_eq!(repeated.len(), 1);
+        assert_eq!(
+            (repeated[0].shape.as_str(), repeated[0].count),
+            ("SELECT ? AS id", 3)
+        );
+        assert_eq!(report.slow().count(), 0);
+        assert_eq!(report.warnings().len(), 1);
+
+        let report = QueryReport {
+            log: log.slow_after(Duration::ZERO),
+            queries: report.queries,
+        };
+        assert_eq!(report.slow().count(), 4);
+    }
+}
@@ -0,0 +1,353 @@
+//! Read replicas and routing of reads
+
+use crate::pool::{CircuitState, PoolMonitor, PoolOptions};
+use crate::{Dialect, OrmResult};
+use sqlx::pool::PoolConnection;
+use sqlx::{Any, AnyPool, Row};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Longest a replica health check may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Replication delay of a Postgres standby in seconds: 0 while it has
+/// replayed all WAL it received, so an idle primary doesn't look like lag
+const POSTGRES_LAG_QUERY: &str = "SELECT CASE \
+     WHEN pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0 \
+     ELSE COALESCE(EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp()), 0) \
+     END::float8";
+
+/// Role of a database connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Primary,
+    Replica,
+}
+
+/// Metrics of a database connection, see
+/// [`Db::connection_stats`](crate::Db::connection_stats)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionStats {
+    /// Host, port and database, without credentials
+    pub name: String,
+    pub role: Role,
+    /// Whether reads are routed to it; the primary is always healthy
+    pub healthy: bool,
+    /// Replication lag at the last health check, if the database reports it
+    pub lag: Option<Duration>,
+    /// Queries run on it
+    pub queries: u64,
+    /// Queries that failed
+    pub errors: u64,
+    /// Open connections of the pool
+    pub size: u32,
+    /// Idle connections of the pool
+    pub idle: usize,
+    /// Connections running a query or held by a transaction
+    pub in_use: u32,
+    /// Most connections the pool opens
+    pub max_size: u32,
+    /// Connections handed out, including transactions
+    pub acquires: u64,
+    /// Average wait for a connection
+    pub wait_avg: Duration,
+    /// Longest wait for a connection
+    pub wait_max: Duration,
+    /// Waits for a connection that timed out
+    pub timeouts: u64,
+    /// Times all connections were in use when a query needed one
+    pub exhausted: u64,
+    /// Queries failed fast by the open circuit breaker
+    pub shed: u64,
+    /// Closed unless [`Db::circuit_breaker`](crate::Db::circuit_breaker)
+    /// opened it
+    pub circuit: CircuitState,
+}
+
+/// Settings of read routing, see [`Db::replica`](crate::Db::replica)
+#[derive(Debug, Clone)]
+pub(crate) struct ReplicaOptions {
+    pub(crate) sticky_window: Duration,
+    pub(crate) max_lag: Duration,
+    pub(crate) lag_query: Option<String>,
+}
+
+impl Default for ReplicaOptions {
+    fn default() -> Self {
+        Self {
+            sticky_window: Duration::from_secs(5),
+            max_lag: Duration::from_secs(10),
+            lag_query: None,
+        }
+    }
+}
+
+/// A pool with its health and metrics
+pub(crate) struct Node {
+    pub(crate) pool: AnyPool,
+    role: Role,
+    name: String,
+    healthy: AtomicBool,
+    /// Lag in milliseconds, `u64::MAX` if unknown
+    lag_ms: AtomicU64,
+    queries: AtomicU64,
+    errors: AtomicU64,
+    monitor: PoolMonitor,
+}
+
+impl Node {
+    pub(crate) fn new(pool: AnyPool, role: Role) -> Self {
+        let url = &pool.connect_options().database_url;
+        let name = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}{}", host, port, url.path()),
+            (Some(host), None) => format!("{}{}", host, url.path()),
+            (None, _) => url.path().to_string(),
+        };
+        Self {
+            pool,
+            role,
+            name,
+            healthy: AtomicBool::new(true),
+            lag_ms: AtomicU64::new(u64::MAX),
+            queries: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            monitor: PoolMonitor::default(),
+        }
+    }
+
+    pub(crate) fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    /// Connection of the pool, failing fast while the circuit is open
+    ///
+    /// Failing to get one counts as a failed query.
+    pub(crate) async fn acquire(&self, options: &PoolOptions) -> OrmResult<PoolConnection<Any>> {
+        let acquire = self.pool.acquire();
+        let result = self
+            .monitor
+            .acquire(&self.name, self.role, &self.pool, options, acquire)
+            .await;
+        if result.is_err() {
+            self.record(&result);
+        }
+        result
+    }
+
+    /// Transaction on a connection of the pool, see [`acquire`](Self::acquire)
+    pub(crate) async fn begin(
+        &self,
+        options: &PoolOptions,
+    ) -> OrmResult<sqlx::Transaction<'static, Any>> {
+        let begin = self.pool.begin();
+        let result = self
+            .monitor
+            .acquire(&self.name, self.role, &self.pool, options, begin)
+            .await;
+        if result.is_err() {
+            self.record(&result);
+        }
+        result
+    }
+
+    /// Count a query and whether it failed
+    pub(crate) fn record<T, E>(&self, result: &Result<T, E>) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        if result.is_err() {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Take the replica out of rotation until the next successful check
+    pub(crate) fn mark_unhealthy(&self, error: &dyn std::fmt::Display) {
+        if self.healthy.swap(false, Ordering::Relaxed) {
+            tracing::warn!(replica = %self.name, %error, "Replica unavailable, reading from primary");
+        }
+    }
+
+    /// Check that the replica answers and lags at most `max_lag`
+    pub(crate) async fn check(&self, dialect: Dialect, options: &ReplicaOptions) {
+        let lag_query = match (&options.lag_query, dialect) {
+            (Some(query), _) => Some(query.as_str()),
+            (None, Dialect::Postgres) => Some(POSTGRES_LAG_QUERY),
+            (None, _) => None,
+        };
+        let check = async {
+            match lag_query {
+                Some(query) => {
+                    let row = sqlx::query(query).fetch_one(&self.pool).await?;
+                    let seconds: f64 = row.try_get(0)?;
+                    Ok::<_, sqlx::Error>(Some(Duration::from_secs_f64(seconds.max(0.0))))
+                }
+                None => {
+                    sqlx::query("SELECT 1").execute(&self.pool).await?;
+                    Ok(None)
+                }
+            }
+        };
+
+        match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+            Ok(Ok(lag)) => {
+                let lag_ms = lag.map_or(u64::MAX, |lag| lag.as_millis() as u64);
+                self.lag_ms.store(lag_ms, Ordering::Relaxed);
+                if lag.is_some_and(|lag| lag > options.max_lag) {
+                    self.mark_unhealthy(&format_args!("lagging {:?} behind", lag.unwrap()));
+                } else if !self.healthy.swap(true, Ordering::Relaxed) {
+                    tracing::info!(replica = %self.name, "Replica available again");
+                }
+            }
+            Ok(Err(e)) => self.mark_unhealthy(&e),
+            Err(_) => self.mark_unhealthy(&"health check timed out"),
+        }
+    }
+
+    pub(crate) fn stats(&self, options: &PoolOptions) -> ConnectionStats {
+        let lag_ms = self.lag_ms.load(Ordering::Relaxed);
+        let pool = self.monitor.stats(options);
+        let (size, idle) = (self.pool.size(), self.pool.num_idle());
+        ConnectionStats {
+            name: self.name.clone(),
+            role: self.role,
+            healthy: self.is_healthy(),
+            lag: (lag_ms != u64::MAX).then(|| Duration::from_millis(lag_ms)),
+            queries: self.queries.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            size,
+            idle,
+            in_use: size.saturating_sub(idle as u32),
+            max_size: self.pool.options().get_max_connections(),
+            acquires: pool.acquires,
+            wait_avg: pool.wait_avg,
+            wait_max: pool.wait_max,
+            timeouts: pool.timeouts,
+            exhausted: pool.exhausted,
+            shed: pool.shed,
+            circuit: pool.circuit,
+        }
+    }
+}
+
+/// Time of the last write of a scope, in milliseconds since the epoch
+#[derive(Clone, Default)]
+pub(crate) struct LastWrite(Arc<AtomicU64>);
+
+impl LastWrite {
+    pub(crate) fn at(time: SystemTime) -> Self {
+        let last = Self::default();
+        last.0.store(millis(time), Ordering::Relaxed);
+        last
+    }
+
+    pub(crate) fn record(&self) {
+        self.0.store(millis(SystemTime::now()), Ordering::Relaxed);
+    }
+
+    pub(crate) fn get(&self) -> Option<SystemTime> {
+        match self.0.load(Ordering::Relaxed) {
+            0 => None,
+            ms => Some(UNIX_EPOCH + Duration::from_millis(ms)),
+        }
+    }
+
+    /// Whether the last write was less than `window` ago
+    pub(crate) fn within(&self, window: Duration) -> bool {
+        self.get().is_some_and(|time| {
+            SystemTime::now()
+                .duration_since(time)
+                .map_or(true, |elapsed| elapsed < window)
+        })
+    }
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as u64)
+}
+
+/// Middleware giving each request its own [`Db::scope`](crate::Db::scope),
+/// so a request's writes make only its own reads go to the primary
+/// (feature `axum`)
+///
+/// Handlers extract the scoped database with `Extension<Db>`.
+///
+/// ```
+/// use axum::{middleware, routing::get, Extension, Router};
+/// use rf_orm_lite::{sticky_reads, Db};
+///
+/// fn app(db: Db) -> Router {
+///     Router::new()
+///         .route("/", get(|Extension(_db): Extension<Db>| async move { "home" }))
+///         .layer(middleware::from_fn_with_state(db, sticky_reads))
+/// }
+/// ```
+#[cfg(feature = "axum")]
+pub async fn sticky_reads(
+    axum::extract::State(db): axum::extract::State<crate::Db>,
+    mut req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    req.extensions_mut().insert(db.scope());
+    next.run(req).await
+}
+
+/// Whether `sql` only reads, so it may run on a replica
+///
+/// Only plain `SELECT`s qualify; locking reads, CTEs (which may modify
+/// data) and everything else run on the primary.
+pub(crate) fn is_read(sql: &str) -> bool {
+    let sql = sql.trim_start().to_ascii_uppercase();
+    sql.starts_with("SELECT")
+        && !sql.contains(" FOR UPDATE")
+        && !sql.contains(" FOR SHARE")
+        && !sql.contains(" FOR NO KEY UPDATE")
+        && !sql.contains(" FOR KEY SHARE")
+        && !sql.contains(" LOCK IN SHARE MODE")
+}
+
+/// Whether `error` means the database couldn't be reached, rather than
+/// that the query failed
+pub(crate) fn is_unavailable(error: &sqlx::Error) -> bool {
+    matches!(
+        error,
+        sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_read() {
+        assert!(is_read("SELECT * FROM posts"));
+        assert!(is_read("  select count(*) from posts"));
+        assert!(!is_read("SELECT * FROM posts WHERE id = $1 FOR UPDATE"));
+        assert!(!is_read("select * from posts lock in share mode"));
+        assert!(!is_read(
+            "INSERT INTO posts (title) VALUES ($1) RETURNING *"
+        ));
+        assert!(!is_read(
+            "WITH moved AS (DELETE FROM posts RETURNING *) SELECT 1"
+        ));
+    }
+
+    #[test]
+    fn test_last_write() {
+        let last = LastWrite::default();
+        assert_eq!(last.get(), None);
+        assert!(!last.within(Duration::from_secs(60)));
+
+        last.record();
+        assert!(last.within(Duration::from_secs(60)));
+        assert!(!last.within(Duration::ZERO));
+
+        let old = LastWrite::at(SystemTime::now() - Duration::from_secs(120));
+        assert!(!old.within(Duration::from_secs(60)));
+        assert!(old.get().is_some());
+    }
+}
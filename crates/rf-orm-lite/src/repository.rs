@@ -0,0 +1,759 @@
+//! CRUD and batch operations on a model's table
+
+use crate::query::Sql;
+use crate::{Connection, Model, OrmError, OrmResult, Query, Value};
+use sqlx::Row;
+use std::marker::PhantomData;
+
+/// Bound values per statement of a batch insert, within every database's
+/// limit
+const MAX_PARAMETERS: usize = 999;
+
+/// Records of model `T` on a [`Db`](crate::Db) or in a
+/// [`Transaction`](crate::Transaction)
+///
+/// Reads leave soft deleted records out, see
+/// [`Model::SOFT_DELETE_COLUMN`]. With [`Db::events`](crate::Db::events),
+/// inserts, updates and deletes of single records dispatch model lifecycle
+/// events.
+pub struct Repository<'c, T> {
+    conn: &'c dyn Connection,
+    model: PhantomData<fn() -> T>,
+}
+
+/// Lifecycle event of a record
+#[derive(Clone, Copy)]
+enum Lifecycle {
+    Creating,
+    Created,
+    Updating,
+    Updated,
+    Deleting,
+    Deleted,
+}
+
+/// Value set by an `UPDATE`
+enum Set {
+    Value(Value),
+    Now,
+    Increment,
+}
+
+impl<'c, T: Model> Repository<'c, T> {
+    pub fn new(conn: &'c dyn Connection) -> Self {
+        Self {
+            conn,
+            model: PhantomData,
+        }
+    }
+
+    /// Record with primary key `id`
+    pub async fn find(&self, id: i64) -> OrmResult<Option<T>> {
+        self.first(T::query().where_eq(T::PRIMARY_KEY, id)).await
+    }
+
+    /// Record with primary key `id`, failing with
+    /// [`OrmError::NotFound`] if there is none
+    pub async fn find_or_fail(&self, id: i64) -> OrmResult<T> {
+        self.find(id).await?.ok_or(OrmError::NotFound {
+            table: T::TABLE,
+            id,
+        })
+    }
+
+    pub async fn all(&self) -> OrmResult<Vec<T>> {
+        self.get(T::query()).await
+    }
+
+    /// Records matching `query`
+    pub async fn get(&self, query: Query<T>) -> OrmResult<Vec<T>> {
+        let (sql, values) = query.to_sql(self.conn.dialect());
+        let rows = self.conn.fetch_all(&sql, values).await?;
+        Ok(rows.iter().map(T::from_row).collect::<Result<_, _>>()?)
+    }
+
+    /// First record matching `query`
+    pub async fn first(&self, query: Query<T>) -> OrmResult<Option<T>> {
+        Ok(self.get(query.limit(1)).await?.into_iter().next())
+    }
+
+    /// Number of records matching `query`
+    pub async fn count(&self, query: Query<T>) -> OrmResult<i64> {
+        let (sql, values) = query.count_sql(self.conn.dialect());
+        let rows = self.conn.fetch_all(&sql, values).await?;
+        Ok(rows
+            .first()
+            .map(|row| row.try_get(0))
+            .transpose()?
+            .unwrap_or(0))
+    }
+
+    pub async fn exists(&self, query: Query<T>) -> OrmResult<bool> {
+        Ok(self.count(query).await? > 0)
+    }
+
+    /// Insert a record, returning it as stored
+    pub async fn insert(&self, model: &T) -> OrmResult<T> {
+        self.dispatch(Lifecycle::Creating, model).await?;
+        let dialect = self.conn.dialect();
+        let mut values = model.values();
+        if let Some(version) = T::VERSION_COLUMN {
+            values.push((version, Value::Int(1)));
+        }
+
+        let mut sql = Sql::new(dialect);
+        sql.push("INSERT INTO ");
+        sql.push_identifier(T::TABLE);
+        if values.is_empty() && dialect.supports_returning() {
+            sql.push(" DEFAULT VALUES");
+        } else {
+            let columns: Vec<&str> = values.iter().map(|(column, _)| *column).collect();
+            push_values(&mut sql, &columns, [values]);
+        }
+
+        let stored = if dialect.supports_returning() {
+            sql.push(" RETURNING *");
+            let (sql, values) = sql.finish();
+            let rows = self.conn.fetch_all(&sql, values).await?;
+            let row = rows.first().ok_or_else(|| {
+                OrmError::InvalidQuery(format!("Inserting into {} returned no row", T::TABLE))
+            })?;
+            T::from_row(row)?
+        } else {
+            let (sql, values) = sql.finish();
+            let result = self.conn.execute(&sql, values).await?;
+            let id = result.last_insert_id().ok_or_else(|| {
+                OrmError::InvalidQuery(format!("Inserting into {} returned no ID", T::TABLE))
+            })?;
+            self.fetch(id).await?
+        };
+        self.dispatch(Lifecycle::Created, &stored).await?;
+        Ok(stored)
+    }
+
+    /// Insert records with as few statements as possible, returning how
+    /// many were inserted
+    ///
+    /// Run it in a [`Transaction`](crate::Transaction) to insert all records
+    /// or none.
+    pub async fn insert_many(&self, models: &[T]) -> OrmResult<u64> {
+        let Some(first) = models.first() else {
+            return Ok(0);
+        };
+        let mut columns: Vec<&'static str> = first
+            .values()
+            .into_iter()
+            .map(|(column, _)| column)
+            .collect();
+        columns.extend(T::VERSION_COLUMN);
+        if columns.is_empty() {
+            return Err(OrmError::InvalidQuery(format!(
+                "{} has no columns to insert",
+                T::TABLE
+            )));
+        }
+
+        let mut inserted = 0;
+        for chunk in models.chunks((MAX_PARAMETERS / columns.len()).max(1)) {
+            let mut rows = Vec::with_capacity(chunk.len());
+            for model in chunk {
+                let mut values = model.values();
+                if let Some(version) = T::VERSION_COLUMN {
+                    values.push((version, Value::Int(1)));
+                }
+                if !values.iter().map(|(column, _)| column).eq(&columns) {
+                    return Err(OrmError::InvalidQuery(format!(
+                        "All {} records of a batch insert need the same columns",
+                        T::TABLE
+                    )));
+                }
+                rows.push(values);
+            }
+
+            let mut sql = Sql::new(self.conn.dialect());
+            sql.push("INSERT INTO ");
+            sql.push_identifier(T::TABLE);
+            push_values(&mut sql, &columns, rows);
+            let (sql, values) = sql.finish();
+            inserted += self.conn.execute(&sql, values).await?.rows_affected();
+        }
+        Ok(inserted)
+    }
+
+    /// Update a record, returning it as stored
+    ///
+    /// With a [`VERSION_COLUMN`](Model::VERSION_COLUMN) this fails with
+    /// [`OrmError::StaleRecord`] if the record was updated since `model`
+    /// was read.
+    pub async fn update(&self, model: &T) -> OrmResult<T> {
+        let id = model.id().ok_or_else(|| {
+            OrmError::InvalidQuery(format!("Can't update a {} record without ID", T::TABLE))
+        })?;
+        self.dispatch(Lifecycle::Updating, model).await?;
+
+        let mut sql = Sql::new(self.conn.dialect());
+        let set = model
+            .values()
+            .into_iter()
+            .map(|(column, value)| (column, Set::Value(value)));
+        push_update::<T>(&mut sql, set.collect());
+        sql.push(" WHERE ");
+        sql.push_identifier(T::PRIMARY_KEY);
+        sql.push(" = ");
+        sql.push_value(Value::Int(id));
+        if let Some(column) = T::VERSION_COLUMN {
+            let version = model.version().ok_or_else(|| {
+                OrmError::InvalidQuery(format!("The {} record has no version", T::TABLE))
+            })?;
+            sql.push(" AND ");
+            sql.push_identifier(column);
+            sql.push(" = ");
+            sql.push_value(Value::Int(version));
+        }
+
+        let (sql, values) = sql.finish();
+        let updated = self.conn.execute(&sql, values).await?.rows_affected();
+        let stored = self.fetch(id).await?;
+        // MySQL counts unchanged rows as not updated, so only a version
+        // mismatch is an error
+        if updated == 0 && T::VERSION_COLUMN.is_some() && stored.version() != model.version() {
+            return Err(OrmError::StaleRecord {
+                table: T::TABLE,
+                id,
+            });
+        }
+        self.dispatch(Lifecycle::Updated, &stored).await?;
+        Ok(stored)
+    }
+
+    /// Insert a record without ID, update one with
+    pub async fn save(&self, model: &T) -> OrmResult<T> {
+        match model.id() {
+            Some(_) => self.update(model).await,
+            None => self.insert(model).await,
+        }
+    }
+
+    /// Set `values` on all records matching `query`, returning how many
+    /// were updated
+    pub async fn update_where(
+        &self,
+        query: Query<T>,
+        values: Vec<(&str, Value)>,
+    ) -> OrmResult<u64> {
+        let set = values
+            .into_iter()
+            .map(|(column, value)| (column, Set::Value(value)));
+        self.update_query(query, set.collect()).await
+    }
+
+    /// Delete the record with primary key `id`, returning whether it existed
+    ///
+    /// Records of models with a [`SOFT_DELETE_COLUMN`](Model::SOFT_DELETE_COLUMN)
+    /// are only marked as deleted.
+    pub async fn delete(&self, id: i64) -> OrmResult<bool> {
+        let query = T::query().where_eq(T::PRIMARY_KEY, id);
+        match T::SOFT_DELETE_COLUMN {
+            Some(column) => {
+                self.delete_one(query, |query| {
+                    self.update_query(query, vec![(column, Set::Now)])
+                })
+                .await
+            }
+            None => self.delete_one(query, |query| self.remove(query)).await,
+        }
+    }
+
+    /// Delete the records with these primary keys, returning how many
+    /// existed
+    pub async fn delete_many(&self, ids: impl IntoIterator<Item = i64>) -> OrmResult<u64> {
+        self.delete_where(T::query().where_in(T::PRIMARY_KEY, ids))
+            .await
+    }
+
+    /// Delete all records matching `query`, returning how many were deleted
+    pub async fn delete_where(&self, query: Query<T>) -> OrmResult<u64> {
+        match T::SOFT_DELETE_COLUMN {
+            Some(column) => self.update_query(query, vec![(column, Set::Now)]).await,
+            None => self.remove(query).await,
+        }
+    }
+
+    /// Remove the record with primary key `id`, even if soft deleted,
+    /// returning whether it existed
+    pub async fn force_delete(&self, id: i64) -> OrmResult<bool> {
+        let query = T::query().with_trashed().where_eq(T::PRIMARY_KEY, id);
+        self.delete_one(query, |query| self.remove(query)).await
+    }
+
+    /// Remove all records matching `query`, including soft deleted ones
+    /// if it asks for them
+    pub async fn force_delete_where(&self, query: Query<T>) -> OrmResult<u64> {
+        self.remove(query).await
+    }
+
+    /// Undo the soft delete of the record with primary key `id`, returning
+    /// whether it was soft deleted
+    pub async fn restore(&self, id: i64) -> OrmResult<bool> {
+        let column = T::SOFT_DELETE_COLUMN.ok_or_else(|| {
+            OrmError::InvalidQuery(format!("{} records aren't soft deleted", T::TABLE))
+        })?;
+        let query = T::query().only_trashed().where_eq(T::PRIMARY_KEY, id);
+        let Some(model) = self.first(query.clone()).await? else {
+            return Ok(false);
+        };
+
+        self.dispatch(Lifecycle::Updating, &model).await?;
+        self.update_query(query, vec![(column, Set::Value(Value::Null))])
+            .await?;
+        let restored = self.fetch(id).await?;
+        self.dispatch(Lifecycle::Updated, &restored).await?;
+        Ok(true)
+    }
+
+    /// Record with primary key `id`, even if soft deleted
+    async fn fetch(&self, id: i64) -> OrmResult<T> {
+        let query = T::query().with_trashed().where_eq(T::PRIMARY_KEY, id);
+        self.first(query).await?.ok_or(OrmError::NotFound {
+            table: T::TABLE,
+            id,
+        })
+    }
+
+    /// Delete the record matching `query` with `delete`, dispatching the
+    /// delete events if enabled
+    async fn delete_one<'a, F, Fut>(&'a self, query: Query<T>, delete: F) -> OrmResult<bool>
+    where
+        F: FnOnce(Query<T>) -> Fut,
+        Fut: std::future::Future<Output = OrmResult<u64>> + 'a,
+    {
+        if !self.has_events() {
+            return Ok(delete(query).await? > 0);
+        }
+
+        let Some(model) = self.first(query.clone()).await? else {
+            return Ok(false);
+        };
+        self.dispatch(Lifecycle::Deleting, &model).await?;
+        let deleted = delete(query).await? > 0;
+        if deleted {
+            self.dispatch(Lifecycle::Deleted, &model).await?;
+        }
+        Ok(deleted)
+    }
+
+    /// `DELETE` the records matching `query`
+    async fn remove(&self, query: Query<T>) -> OrmResult<u64> {
+        let mut sql = Sql::new(self.conn.dialect());
+        sql.push("DELETE FROM ");
+        sql.push_identifier(T::TABLE);
+        query.push_where_only(&mut sql)?;
+        let (sql, values) = sql.finish();
+        Ok(self.conn.execute(&sql, values).await?.rows_affected())
+    }
+
+    async fn update_query(&self, query: Query<T>, set: Vec<(&str, Set)>) -> OrmResult<u64> {
+        let mut sql = Sql::new(self.conn.dialect());
+        push_update::<T>(&mut sql, set);
+        query.push_where_only(&mut sql)?;
+        let (sql, values) = sql.finish();
+        Ok(self.conn.execute(&sql, values).await?.rows_affected())
+    }
+
+    fn has_events(&self) -> bool {
+        #[cfg(feature = "events")]
+        return self.conn.events().is_some();
+        #[cfg(not(feature = "events"))]
+        return false;
+    }
+
+    /// Dispatch the model event of `lifecycle`, if enabled
+    async fn dispatch(&self, lifecycle: Lifecycle, model: &T) -> OrmResult<()> {
+        #[cfg(feature = "events")]
+        if let Some(events) = self.conn.events() {
+            use rf_events::{
+                ModelCreated, ModelCreating, ModelDeleted, ModelDeleting, ModelUpdated,
+                ModelUpdating,
+            };
+
+            let model = model.clone();
+            match lifecycle {
+                Lifecycle::Creating => events.dispatch(ModelCreating::new(model)).await?,
+                Lifecycle::Created => events.dispatch(ModelCreated::new(model)).await?,
+                Lifecycle::Updating => events.dispatch(ModelUpdating::new(model)).await?,
+                Lifecycle::Updated => events.dispatch(ModelUpdated::new(model)).await?,
+                Lifecycle::Deleting => events.dispatch(ModelDeleting::new(model)).await?,
+                Lifecycle::Deleted => events.dispatch(ModelDeleted::new(model)).await?,
+            }
+        }
+        #[cfg(not(feature = "events"))]
+        let _ = (lifecycle, model);
+        Ok(())
+    }
+}
+
+/// ` (a, b) VALUES (?, ?), (?, ?)`
+fn push_values(
+    sql: &mut Sql,
+    columns: &[&str],
+    rows: impl IntoIterator<Item = Vec<(&'static str, Value)>>,
+) {
+    sql.push(" (");
+    sql.push_identifiers(columns);
+    sql.push(") VALUES ");
+    for (i, row) in rows.into_iter().enumerate() {
+        sql.push(if i > 0 { ", (" } else { "(" });
+        for (j, (_, value)) in row.into_iter().enumerate() {
+            if j > 0 {
+                sql.push(", ");
+            }
+            sql.push_value(value);
+        }
+        sql.push(")");
+    }
+}
+
+/// `UPDATE t SET a = ?, b = ?`, incrementing the version
+fn push_update<T: Model>(sql: &mut Sql, mut set: Vec<(&str, Set)>) {
+    set.extend(T::VERSION_COLUMN.map(|version| (version, Set::Increment)));
+    sql.push("UPDATE ");
+    sql.push_identifier(T::TABLE);
+    sql.push(" SET ");
+    for (i, (column, value)) in set.into_iter().enumerate() {
+        if i > 0 {
+            sql.push(", ");
+        }
+        sql.push_identifier(column);
+        sql.push(" = ");
+        match value {
+            Set::Value(value) => sql.push_value(value),
+            Set::Now => sql.push("CURRENT_TIMESTAMP"),
+            Set::Increment => {
+                sql.push_identifier(column);
+                sql.push(" + 1");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Db, Op};
+
+    #[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+    struct Post {
+        id: Option<i64>,
+        title: String,
+        views: i64,
+        version: i64,
+    }
+
+    impl Post {
+        fn new(title: &str, views: i64) -> Self {
+            Self {
+                id: None,
+                title: title.to_string(),
+                views,
+                version: 0,
+            }
+        }
+    }
+
+    impl Model for Post {
+        const TABLE: &'static str = "posts";
+        const VERSION_COLUMN: Option<&'static str> = Some("version");
+
+        fn id(&self) -> Option<i64> {
+            self.id
+        }
+
+        fn values(&self) -> Vec<(&'static str, Value)> {
+            vec![
+                ("title", self.title.clone().into()),
+                ("views", self.views.into()),
+            ]
+        }
+
+        fn version(&self) -> Option<i64> {
+            Some(self.version)
+        }
+    }
+
+    async fn db() -> (Db, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+        let db = Db::connect(&url).await.unwrap();
+        db.execute(
+            "CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT NOT NULL, \
+             views INTEGER NOT NULL, version INTEGER NOT NULL)",
+            vec![],
+        )
+        .await
+        .unwrap();
+        (db, dir)
+    }
+
+    #[tokio::test]
+    async fn test_crud() {
+        let (db, _dir) = db().await;
+        let posts = db.repository::<Post>();
+
+        let post = posts.insert(&Post::new("Hello", 10)).await.unwrap();
+        assert_eq!(post.id, Some(1));
+        assert_eq!(post.version, 1);
+        assert_eq!(posts.find_or_fail(1).await.unwrap(), post);
+        assert!(matches!(
+            posts.find_or_fail(2).await,
+            Err(OrmError::NotFound {
+                table: "posts",
+                id: 2
+            })
+        ));
+
+        let updated = posts
+            .update(&Post {
+                views: 11,
+                ..post.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(updated.views, 11);
+        assert_eq!(updated.version, 2);
+
+        let saved = posts.save(&Post::new("Second", 5)).await.unwrap();
+        assert_eq!(posts.count(Post::query()).await.unwrap(), 2);
+        assert!(posts.delete(saved.id.unwrap()).await.unwrap());
+        assert!(!posts.delete(saved.id.unwrap()).await.unwrap());
+        assert_eq!(posts.all().await.unwrap(), vec![updated]);
+    }
+
+    #[tokio::test]
+    async fn test_optimistic_locking() {
+        let (db, _dir) = db().await;
+        let posts = db.repository::<Post>();
+        let post = posts.insert(&Post::new("Hello", 0)).await.unwrap();
+
+        let mut first = post.clone();
+        first.title = "First".to_string();
+        posts.update(&first).await.unwrap();
+
+        let mut second = post;
+        second.title = "Second".to_string();
+        assert!(matches!(
+            posts.update(&second).await,
+            Err(OrmError::StaleRecord {
+                table: "posts",
+                id: 1
+            })
+        ));
+        assert_eq!(posts.find_or_fail(1).await.unwrap().title, "First");
+    }
+
+    #[tokio::test]
+    async fn test_batches_and_queries() {
+        let (db, _dir) = db().await;
+        let posts = db.repository::<Post>();
+        let batch: Vec<Post> = (0..700)
+            .map(|i| Post::new(&format!("Post {}", i), i))
+            .collect();
+        // More rows than fit into one statement
+        assert_eq!(posts.insert_many(&batch).await.unwrap(), 700);
+
+        let popular = posts
+            .get(
+                Post::query()
+                    .filter("views", Op::Ge, 695)
+                    .order_by_desc("views")
+                    .limit(3),
+            )
+            .await
+            .unwrap();
+        let views: Vec<i64> = popular.iter().map(|post| post.views).collect();
+        assert_eq!(views, vec![699, 698, 697]);
+
+        let page = posts
+            .get(Post::query().order_by("id").offset(10).limit(2))
+            .await
+            .unwrap();
+        assert_eq!(page[0].title, "Post 10");
+
+        let updated = posts
+            .update_where(
+                Post::query().filter("views", Op::Lt, 100),
+                vec![("title", "Old".into())],
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated, 100);
+        let old = posts
+            .first(Post::query().where_eq("title", "Old"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(old.version, 2);
+
+        assert_eq!(posts.delete_many([1, 2, 3, 9999]).await.unwrap(), 3);
+        assert_eq!(
+            posts
+                .delete_where(Post::query().where_eq("title", "Old"))
+                .await
+                .unwrap(),
+            97
+        );
+        assert!(!posts
+            .exists(Post::query().filter("title", Op::Like, "Old%"))
+            .await
+            .unwrap());
+        assert_eq!(posts.count(Post::query()).await.unwrap(), 600);
+    }
+
+    #[derive(Debug, Clone, sqlx::FromRow)]
+    struct Comment {
+        id: Option<i64>,
+        body: String,
+    }
+
+    impl Model for Comment {
+        const TABLE: &'static str = "comments";
+        const SOFT_DELETE_COLUMN: Option<&'static str> = Some("deleted_at");
+
+        fn id(&self) -> Option<i64> {
+            self.id
+        }
+
+        fn values(&self) -> Vec<(&'static str, Value)> {
+            vec![("body", self.body.clone().into())]
+        }
+    }
+
+    async fn comments(db: &Db) {
+        db.execute(
+            "CREATE TABLE comments (id INTEGER PRIMARY KEY, body TEXT NOT NULL, \
+             deleted_at TEXT NULL)",
+            vec![],
+        )
+        .await
+        .unwrap();
+    }
+
+    fn comment(body: &str) -> Comment {
+        Comment {
+            id: None,
+            body: body.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_soft_deletes() {
+        let (db, _dir) = db().await;
+        comments(&db).await;
+        let repo = db.repository::<Comment>();
+        let first = repo.insert(&comment("First")).await.unwrap();
+        let second = repo.insert(&comment("Second")).await.unwrap();
+        repo.insert(&comment("Third")).await.unwrap();
+
+        assert!(repo.delete(first.id.unwrap()).await.unwrap());
+        assert!(!repo.delete(first.id.unwrap()).await.unwrap());
+        assert!(repo.find(first.id.unwrap()).await.unwrap().is_none());
+        assert_eq!(repo.all().await.unwrap().len(), 2);
+        assert_eq!(
+            repo.count(Comment::query().with_trashed()).await.unwrap(),
+            3
+        );
+        let trashed = repo.get(Comment::query().only_trashed()).await.unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].body, "First");
+
+        assert!(repo.restore(first.id.unwrap()).await.unwrap());
+        assert!(!repo.restore(first.id.unwrap()).await.unwrap());
+        assert_eq!(repo.all().await.unwrap().len(), 3);
+
+        assert_eq!(repo.delete_where(Comment::query()).await.unwrap(), 3);
+        assert!(repo.force_delete(second.id.unwrap()).await.unwrap());
+        let left = repo
+            .force_delete_where(Comment::query().with_trashed())
+            .await
+            .unwrap();
+        assert_eq!(left, 2);
+
+        let posts = db.repository::<Post>();
+        assert!(matches!(
+            posts.restore(1).await,
+            Err(OrmError::InvalidQuery(_))
+        ));
+    }
+
+    #[cfg(feature = "events")]
+    #[tokio::test]
+    async fn test_lifecycle_events() {
+        use async_trait::async_trait;
+        use rf_events::{
+            Event, EventDispatcher, EventError, EventListenerFor, EventResult, ModelCreating,
+            WildcardListener,
+        };
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Recorder(Arc<Mutex<Vec<&'static str>>>);
+
+        #[async_trait]
+        impl WildcardListener for Recorder {
+            async fn handle(&self, event: &dyn Event) -> EventResult<()> {
+                self.0.lock().unwrap().push(event.name());
+                Ok(())
+            }
+        }
+
+        struct NoSpam;
+
+        #[async_trait]
+        impl EventListenerFor<ModelCreating<Comment>> for NoSpam {
+            async fn handle(&self, event: &ModelCreating<Comment>) -> EventResult<()> {
+                match event.model.body.contains("spam") {
+                    true => Err(EventError::DispatchError("Spam".to_string())),
+                    false => Ok(()),
+                }
+            }
+        }
+
+        let events = Arc::new(EventDispatcher::new());
+        let recorder = Recorder::default();
+        events.listen_any("model.*", recorder.clone()).await;
+        events.listen(NoSpam).await;
+
+        let (db, _dir) = db().await;
+        comments(&db).await;
+        let db = db.events(events);
+        let repo = db.repository::<Comment>();
+
+        let mut stored = repo.insert(&comment("Hello")).await.unwrap();
+        stored.body = "Hello again".to_string();
+        repo.save(&stored).await.unwrap();
+        repo.delete(stored.id.unwrap()).await.unwrap();
+        repo.restore(stored.id.unwrap()).await.unwrap();
+        assert!(matches!(
+            repo.insert(&comment("Buy spam")).await,
+            Err(OrmError::Event(_))
+        ));
+        assert_eq!(
+            repo.count(Comment::query().with_trashed()).await.unwrap(),
+            1
+        );
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            [
+                "model.creating",
+                "model.created",
+                "model.updating",
+                "model.updated",
+                "model.deleting",
+                "model.deleted",
+                "model.updating",
+                "model.updated",
+                "model.creating",
+            ]
+        );
+    }
+}
@@ -0,0 +1,36 @@
+//! Outbox errors
+
+use thiserror::Error;
+
+/// Outbox errors
+#[derive(Debug, Error)]
+pub enum OutboxError {
+    #[error("Database error: {0}")]
+    Database(#[from] rf_orm_lite::OrmError),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("No publisher for channel {0}")]
+    NoPublisher(String),
+
+    #[error("Publishing failed: {0}")]
+    Publish(String),
+}
+
+/// Result type for outbox operations
+pub type OutboxResult<T> = Result<T, OutboxError>;
+
+#[cfg(feature = "queue")]
+impl From<rf_queue::QueueError> for OutboxError {
+    fn from(e: rf_queue::QueueError) -> Self {
+        OutboxError::Publish(e.to_string())
+    }
+}
+
+#[cfg(feature = "webhooks")]
+impl From<rf_webhooks::WebhookError> for OutboxError {
+    fn from(e: rf_webhooks::WebhookError) -> Self {
+        OutboxError::Publish(e.to_string())
+    }
+}
@@ -0,0 +1,55 @@
+//! Transactional outbox for RustForge
+//!
+//! Sending a notification, job or webhook right after committing loses it
+//! when the process dies in between, and sends it anyway when the commit
+//! fails. An [`Outbox`] records such messages in the same transaction as
+//! the changes they are about, and a relay publishes them once committed.
+//!
+//! # Features
+//!
+//! - Messages written atomically with the caller's transaction through
+//!   rf-orm-lite, on Postgres, SQLite or MySQL
+//! - Relays leasing messages, so several processes can relay one outbox
+//! - Retries with exponential backoff, failed messages kept for retrying
+//!   by hand, published ones pruned after a retention period
+//! - Publishers per channel: closures, queue jobs (`queue` feature) and
+//!   webhook events (`webhooks` feature)
+//!
+//! Delivery is at least once: a relay crashing after publishing but before
+//! marking the message publishes it again. Consumers drop duplicates by
+//! [`OutboxMessage::idempotency_key`] or, for jobs, the job ID.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use rf_outbox::Outbox;
+//!
+//! let outbox = Outbox::new(db.clone()).queue(queue).webhooks(webhooks);
+//! outbox.install().await?;
+//!
+//! let tx = db.begin().await?;
+//! let order = tx.repository::<Order>().insert(&order).await?;
+//! Outbox::dispatch(&tx, &SendInvoice { order_id: order.id.unwrap() }).await?;
+//! Outbox::webhook(&tx, "order.created", &order_json).await?;
+//! tx.commit().await?;
+//!
+//! tokio::spawn(async move { outbox.run(Duration::from_secs(1)).await });
+//! ```
+
+mod error;
+mod message;
+mod outbox;
+
+#[cfg(feature = "queue")]
+mod queue;
+#[cfg(feature = "webhooks")]
+mod webhooks;
+
+pub use error::{OutboxError, OutboxResult};
+pub use message::{OutboxMessage, OUTBOX_TABLE};
+pub use outbox::{Outbox, Publisher, RelayReport};
+
+#[cfg(feature = "queue")]
+pub use queue::{QueuePublisher, QUEUE_CHANNEL};
+#[cfg(feature = "webhooks")]
+pub use webhooks::{WebhookPublisher, WEBHOOKS_CHANNEL};
@@ -0,0 +1,144 @@
+//! Messages stored in the outbox table
+
+use crate::OutboxResult;
+use rf_orm_lite::{Dialect, Model, Value};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Table the outbox is kept in
+pub const OUTBOX_TABLE: &str = "outbox_messages";
+
+/// Message waiting in the outbox, or published from it
+///
+/// Times are milliseconds since the epoch, 0 while not set.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct OutboxMessage {
+    pub id: Option<i64>,
+    /// Publisher the message goes to, e.g. `queue` or `webhooks`
+    pub channel: String,
+    /// What the message is about, e.g. the webhook event
+    pub topic: String,
+    /// JSON payload
+    pub payload: String,
+    /// Publishing attempts so far
+    pub attempts: i64,
+    /// Error of the last failed attempt, empty if there is none
+    pub last_error: String,
+    pub created_at: i64,
+    /// Earliest time of the next attempt
+    pub available_at: i64,
+    /// End of the lease of the relay publishing it
+    pub claimed_until: i64,
+    pub published_at: i64,
+    /// When publishing was given up
+    pub failed_at: i64,
+}
+
+impl OutboxMessage {
+    /// Message of `channel` about `topic` with a JSON payload
+    pub fn new(channel: &str, topic: &str, payload: impl Serialize) -> OutboxResult<Self> {
+        let now = now_millis();
+        Ok(Self {
+            id: None,
+            channel: channel.to_string(),
+            topic: topic.to_string(),
+            payload: serde_json::to_string(&payload)?,
+            attempts: 0,
+            last_error: String::new(),
+            created_at: now,
+            available_at: now,
+            claimed_until: 0,
+            published_at: 0,
+            failed_at: 0,
+        })
+    }
+
+    /// Deserialize the payload
+    pub fn decode<T: DeserializeOwned>(&self) -> OutboxResult<T> {
+        Ok(serde_json::from_str(&self.payload)?)
+    }
+
+    /// Key identifying the message across attempts, letting consumers drop
+    /// messages they already handled
+    pub fn idempotency_key(&self) -> String {
+        format!("outbox-{}", self.id.unwrap_or_default())
+    }
+
+    pub fn is_published(&self) -> bool {
+        self.published_at > 0
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.failed_at > 0
+    }
+}
+
+impl Model for OutboxMessage {
+    const TABLE: &'static str = OUTBOX_TABLE;
+
+    fn id(&self) -> Option<i64> {
+        self.id
+    }
+
+    fn values(&self) -> Vec<(&'static str, Value)> {
+        vec![
+            ("channel", self.channel.clone().into()),
+            ("topic", self.topic.clone().into()),
+            ("payload", self.payload.clone().into()),
+            ("attempts", self.attempts.into()),
+            ("last_error", self.last_error.clone().into()),
+            ("created_at", self.created_at.into()),
+            ("available_at", self.available_at.into()),
+            ("claimed_until", self.claimed_until.into()),
+            ("published_at", self.published_at.into()),
+            ("failed_at", self.failed_at.into()),
+        ]
+    }
+}
+
+/// Statements creating the outbox table and its index
+pub(crate) fn create_table(dialect: Dialect) -> Vec<String> {
+    let id = match dialect {
+        Dialect::Postgres => "BIGSERIAL PRIMARY KEY",
+        Dialect::MySql => "BIGINT AUTO_INCREMENT PRIMARY KEY",
+        Dialect::Sqlite => "INTEGER PRIMARY KEY AUTOINCREMENT",
+    };
+    let columns = format!(
+        "id {id},
+        channel VARCHAR(64) NOT NULL,
+        topic VARCHAR(255) NOT NULL,
+        payload TEXT NOT NULL,
+        attempts BIGINT NOT NULL,
+        last_error TEXT NOT NULL,
+        created_at BIGINT NOT NULL,
+        available_at BIGINT NOT NULL,
+        claimed_until BIGINT NOT NULL,
+        published_at BIGINT NOT NULL,
+        failed_at BIGINT NOT NULL"
+    );
+    let index = format!("{OUTBOX_TABLE}_pending");
+    match dialect {
+        // No CREATE INDEX IF NOT EXISTS
+        Dialect::MySql => vec![format!(
+            "CREATE TABLE IF NOT EXISTS {OUTBOX_TABLE} ({columns},
+            INDEX {index} (published_at, available_at))"
+        )],
+        _ => vec![
+            format!("CREATE TABLE IF NOT EXISTS {OUTBOX_TABLE} ({columns})"),
+            format!(
+                "CREATE INDEX IF NOT EXISTS {index} ON {OUTBOX_TABLE} (published_at, available_at)"
+            ),
+        ],
+    }
+}
+
+pub(crate) fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as i64)
+}
+
+pub(crate) fn duration_millis(duration: Duration) -> i64 {
+    duration.as_millis().min(i64::MAX as u128) as i64
+}
@@ -0,0 +1,528 @@
+//! Recording messages in transactions and relaying them after commit
+
+use crate::message::{create_table, duration_millis, now_millis};
+use crate::{OutboxError, OutboxMessage, OutboxResult};
+use async_trait::async_trait;
+use rf_orm_lite::{Connection, Db, Model, Op, Query, Repository};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Longest delay between attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+/// Longest error message kept with a message
+const MAX_ERROR_LEN: usize = 1000;
+
+/// Sends outbox messages of a channel on, e.g. to the queue
+///
+/// A message may be published more than once when a relay crashes before
+/// marking it published; consumers drop duplicates by
+/// [`OutboxMessage::idempotency_key`]. Closures taking the message
+/// implement it too.
+#[async_trait]
+pub trait Publisher: Send + Sync + 'static {
+    async fn publish(&self, message: &OutboxMessage) -> OutboxResult<()>;
+}
+
+#[async_trait]
+impl<F, Fut> Publisher for F
+where
+    F: Fn(OutboxMessage) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = OutboxResult<()>> + Send,
+{
+    async fn publish(&self, message: &OutboxMessage) -> OutboxResult<()> {
+        self(message.clone()).await
+    }
+}
+
+/// Outcome of a [`Outbox::relay`] run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RelayReport {
+    pub published: usize,
+    /// Failed attempts that will be retried
+    pub retried: usize,
+    /// Messages given up on after their last attempt
+    pub failed: usize,
+}
+
+impl RelayReport {
+    /// Messages attempted
+    pub fn attempted(&self) -> usize {
+        self.published + self.retried + self.failed
+    }
+}
+
+/// Transactional outbox
+///
+/// Messages recorded with [`Outbox::record`] are written in the caller's
+/// transaction, so they exist if and only if its changes were committed.
+/// The relay then hands them to the publisher of their channel, retrying
+/// failures with exponential backoff. Relays lease the messages they
+/// publish, so several processes may relay the same outbox.
+///
+/// ```no_run
+/// use rf_orm_lite::Db;
+/// use rf_outbox::{Outbox, OutboxMessage};
+/// use serde_json::json;
+/// use std::time::Duration;
+///
+/// # async fn example(db: Db) -> rf_outbox::OutboxResult<()> {
+/// let outbox = Outbox::new(db.clone()).publisher("mail", |message: OutboxMessage| async move {
+///     println!("Sending {} to {}", message.topic, message.payload);
+///     Ok(())
+/// });
+/// outbox.install().await?;
+///
+/// let tx = db.begin().await?;
+/// // ... insert the user in `tx`
+/// Outbox::record(&tx, "mail", "welcome", json!({ "user_id": 7 })).await?;
+/// tx.commit().await?;
+///
+/// tokio::spawn(async move { outbox.run(Duration::from_secs(1)).await });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Outbox {
+    db: Db,
+    publishers: HashMap<String, Arc<dyn Publisher>>,
+    batch_size: u64,
+    max_attempts: u32,
+    retry_backoff: Duration,
+    lease: Duration,
+    retain: Duration,
+}
+
+impl Outbox {
+    pub fn new(db: Db) -> Self {
+        Self {
+            db,
+            publishers: HashMap::new(),
+            batch_size: 100,
+            max_attempts: 10,
+            retry_backoff: Duration::from_secs(1),
+            lease: Duration::from_secs(60),
+            retain: Duration::from_secs(7 * 24 * 3600),
+        }
+    }
+
+    /// Publish messages of `channel` with `publisher`
+    pub fn publisher(mut self, channel: impl Into<String>, publisher: impl Publisher) -> Self {
+        self.publishers.insert(channel.into(), Arc::new(publisher));
+        self
+    }
+
+    /// Messages relayed per run (default: 100)
+    pub fn batch_size(mut self, size: u64) -> Self {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    /// Attempts before giving up on a message (default: 10)
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry, doubling with each further one up to
+    /// an hour (default: 1s)
+    pub fn retry_backoff(mut self, delay: Duration) -> Self {
+        self.retry_backoff = delay;
+        self
+    }
+
+    /// How long a relay may take to publish a message before another relay
+    /// may pick it up (default: 60s)
+    pub fn lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    /// How long published messages are kept before [`prune`](Self::prune)
+    /// deletes them (default: 7 days)
+    pub fn retain(mut self, retain: Duration) -> Self {
+        self.retain = retain;
+        self
+    }
+
+    /// Create the outbox table if it doesn't exist
+    pub async fn install(&self) -> OutboxResult<()> {
+        for sql in create_table(self.db.dialect()) {
+            self.db.execute(&sql, Vec::new()).await?;
+        }
+        Ok(())
+    }
+
+    /// Record a message of `channel` about `topic` on `conn`, usually the
+    /// transaction making the changes the message is about
+    pub async fn record(
+        conn: &dyn Connection,
+        channel: &str,
+        topic: &str,
+        payload: impl Serialize,
+    ) -> OutboxResult<OutboxMessage> {
+        let message = OutboxMessage::new(channel, topic, payload)?;
+        Ok(Repository::new(conn).insert(&message).await?)
+    }
+
+    /// Publish messages that are due, up to the batch size
+    pub async fn relay(&self) -> OutboxResult<RelayReport> {
+        let now = now_millis();
+        // Read from the primary, replicas may not have the latest attempts
+        let db = self.db.scope_after(SystemTime::now());
+        let due = db
+            .repository::<OutboxMessage>()
+            .get(
+                pending()
+                    .filter("available_at", Op::Le, now)
+                    .filter("claimed_until", Op::Lt, now)
+                    .order_by("id")
+                    .limit(self.batch_size),
+            )
+            .await?;
+
+        let mut report = RelayReport::default();
+        for message in due {
+            if !self.claim(&message).await? {
+                continue;
+            }
+            match self.publish(&message).await {
+                Ok(()) => {
+                    self.update(
+                        &message,
+                        vec![
+                            ("attempts", (message.attempts + 1).into()),
+                            ("published_at", now_millis().into()),
+                            ("claimed_until", 0i64.into()),
+                        ],
+                    )
+                    .await?;
+                    report.published += 1;
+                }
+                Err(e) => {
+                    if self.fail(&message, &e).await? {
+                        report.failed += 1;
+                    } else {
+                        report.retried += 1;
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Delete messages published longer than the retention ago, returning
+    /// how many were deleted
+    ///
+    /// Failed messages are kept until retried or deleted by hand.
+    pub async fn prune(&self) -> OutboxResult<u64> {
+        let before = now_millis() - duration_millis(self.retain);
+        let query = OutboxMessage::query()
+            .filter("published_at", Op::Gt, 0)
+            .filter("published_at", Op::Le, before);
+        Ok(self
+            .db
+            .repository::<OutboxMessage>()
+            .delete_where(query)
+            .await?)
+    }
+
+    /// Messages not published yet, including those being retried
+    pub async fn pending_count(&self) -> OutboxResult<i64> {
+        Ok(self
+            .db
+            .repository::<OutboxMessage>()
+            .count(pending())
+            .await?)
+    }
+
+    /// Messages given up on, latest first
+    pub async fn failed(&self, limit: u64) -> OutboxResult<Vec<OutboxMessage>> {
+        let query = OutboxMessage::query()
+            .filter("failed_at", Op::Gt, 0)
+            .order_by_desc("id")
+            .limit(limit);
+        Ok(self.db.repository::<OutboxMessage>().get(query).await?)
+    }
+
+    /// Publish a failed message again with fresh attempts, returning
+    /// whether there was one with the ID
+    pub async fn retry(&self, id: i64) -> OutboxResult<bool> {
+        let query = OutboxMessage::query()
+            .where_eq("id", id)
+            .filter("failed_at", Op::Gt, 0);
+        let updated = self
+            .db
+            .repository::<OutboxMessage>()
+            .update_where(
+                query,
+                vec![
+                    ("attempts", 0i64.into()),
+                    ("failed_at", 0i64.into()),
+                    ("available_at", now_millis().into()),
+                ],
+            )
+            .await?;
+        Ok(updated > 0)
+    }
+
+    /// [`relay`](Self::relay) and [`prune`](Self::prune) every `interval`,
+    /// forever
+    ///
+    /// Full batches are followed by the next one right away.
+    pub async fn run(&self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            loop {
+                match self.relay().await {
+                    Ok(report) if report.attempted() as u64 >= self.batch_size => continue,
+                    Ok(_) => break,
+                    Err(e) => {
+                        tracing::error!(error = %e, "Relaying outbox messages failed");
+                        break;
+                    }
+                }
+            }
+            if let Err(e) = self.prune().await {
+                tracing::error!(error = %e, "Pruning outbox messages failed");
+            }
+        }
+    }
+
+    /// Lease `message` to this relay, returning false if another relay got
+    /// it first
+    async fn claim(&self, message: &OutboxMessage) -> OutboxResult<bool> {
+        let now = now_millis();
+        let query = pending()
+            .where_eq("id", message.id.unwrap_or_default())
+            .filter("claimed_until", Op::Lt, now);
+        let claimed = self
+            .db
+            .repository::<OutboxMessage>()
+            .update_where(
+                query,
+                vec![("claimed_until", (now + duration_millis(self.lease)).into())],
+            )
+            .await?;
+        Ok(claimed == 1)
+    }
+
+    async fn publish(&self, message: &OutboxMessage) -> OutboxResult<()> {
+        let publisher = self
+            .publishers
+            .get(&message.channel)
+            .ok_or_else(|| OutboxError::NoPublisher(message.channel.clone()))?;
+        tokio::time::timeout(self.lease, publisher.publish(message))
+            .await
+            .map_err(|_| OutboxError::Publish("timed out".to_string()))?
+    }
+
+    /// Record a failed attempt, returning whether it was the last one
+    async fn fail(&self, message: &OutboxMessage, error: &OutboxError) -> OutboxResult<bool> {
+        let attempts = message.attempts + 1;
+        let mut last_error = error.to_string();
+        if last_error.len() > MAX_ERROR_LEN {
+            let end = (0..=MAX_ERROR_LEN)
+                .rev()
+                .find(|&i| last_error.is_char_boundary(i))
+                .unwrap_or(0);
+            last_error.truncate(end);
+        }
+
+        let now = now_millis();
+        let last = attempts >= i64::from(self.max_attempts);
+        let next = if last {
+            ("failed_at", now.into())
+        } else {
+            let delay = self
+                .retry_backoff
+                .saturating_mul(2u32.saturating_pow(attempts as u32 - 1))
+                .min(MAX_RETRY_DELAY);
+            ("available_at", (now + duration_millis(delay)).into())
+        };
+        if last {
+            tracing::error!(
+                id = message.id,
+                channel = %message.channel,
+                topic = %message.topic,
+                attempts,
+                %error,
+                "Giving up on outbox message"
+            );
+        } else {
+            tracing::warn!(
+                id = message.id,
+                channel = %message.channel,
+                topic = %message.topic,
+                attempts,
+                %error,
+                "Publishing outbox message failed, retrying later"
+            );
+        }
+
+        self.update(
+            message,
+            vec![
+                ("attempts", attempts.into()),
+                ("last_error", last_error.into()),
+                ("claimed_until", 0i64.into()),
+                next,
+            ],
+        )
+        .await?;
+        Ok(last)
+    }
+
+    async fn update(
+        &self,
+        message: &OutboxMessage,
+        values: Vec<(&str, rf_orm_lite::Value)>,
+    ) -> OutboxResult<()> {
+        let query = OutboxMessage::query().where_eq("id", message.id.unwrap_or_default());
+        self.db
+            .repository::<OutboxMessage>()
+            .update_where(query, values)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Messages neither published nor given up on
+fn pending() -> Query<OutboxMessage> {
+    OutboxMessage::query()
+        .where_eq("published_at", 0)
+        .where_eq("failed_at", 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    async fn outbox() -> (tempfile::TempDir, Db) {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+        let db = Db::connect(&url).await.unwrap();
+        Outbox::new(db.clone()).install().await.unwrap();
+        (dir, db)
+    }
+
+    fn recorder(sent: &Arc<Mutex<Vec<String>>>) -> impl Publisher {
+        let sent = sent.clone();
+        move |message: OutboxMessage| {
+            let sent = sent.clone();
+            async move {
+                sent.lock().unwrap().push(message.topic);
+                Ok(())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relays_committed_messages_once() {
+        let (_dir, db) = outbox().await;
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let outbox = Outbox::new(db.clone()).publisher("mail", recorder(&sent));
+
+        let tx = db.begin().await.unwrap();
+        Outbox::record(&tx, "mail", "welcome", json!({ "user_id": 7 }))
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+        let tx = db.begin().await.unwrap();
+        Outbox::record(&tx, "mail", "rolled_back", json!({}))
+            .await
+            .unwrap();
+        tx.rollback().await.unwrap();
+        assert_eq!(outbox.pending_count().await.unwrap(), 1);
+
+        let report = outbox.relay().await.unwrap();
+        assert_eq!(report.published, 1);
+        assert_eq!(outbox.relay().await.unwrap(), RelayReport::default());
+        assert_eq!(*sent.lock().unwrap(), ["welcome"]);
+        assert_eq!(outbox.pending_count().await.unwrap(), 0);
+
+        let message = db
+            .repository::<OutboxMessage>()
+            .find(1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(message.is_published());
+        assert_eq!(message.attempts, 1);
+        assert_eq!(message.decode::<serde_json::Value>().unwrap()["user_id"], 7);
+        assert_eq!(message.idempotency_key(), "outbox-1");
+
+        // Published messages are pruned after the retention
+        assert_eq!(outbox.prune().await.unwrap(), 0);
+        assert_eq!(outbox.retain(Duration::ZERO).prune().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_retried_then_given_up() {
+        let (_dir, db) = outbox().await;
+        let outbox = Outbox::new(db.clone())
+            .publisher("webhooks", |_: OutboxMessage| async {
+                Err(OutboxError::Publish("endpoint down".to_string()))
+            })
+            .retry_backoff(Duration::ZERO)
+            .max_attempts(2);
+        Outbox::record(&db, "webhooks", "order.shipped", json!({}))
+            .await
+            .unwrap();
+        Outbox::record(&db, "sms", "code", json!({})).await.unwrap();
+
+        let report = outbox.relay().await.unwrap();
+        assert_eq!((report.retried, report.failed), (2, 0));
+        let report = outbox.relay().await.unwrap();
+        assert_eq!((report.retried, report.failed), (0, 2));
+        assert_eq!(outbox.relay().await.unwrap().attempted(), 0);
+
+        let failed = outbox.failed(10).await.unwrap();
+        assert_eq!(failed.len(), 2);
+        assert_eq!(failed[0].last_error, "No publisher for channel sms");
+        assert_eq!(failed[1].last_error, "Publishing failed: endpoint down");
+        assert!(failed.iter().all(|message| message.attempts == 2));
+
+        // Retried messages get fresh attempts
+        assert!(outbox.retry(1).await.unwrap());
+        assert!(!outbox.retry(1).await.unwrap());
+        let outbox = outbox.publisher("webhooks", |_: OutboxMessage| async { Ok(()) });
+        assert_eq!(outbox.relay().await.unwrap().published, 1);
+        assert_eq!(outbox.pending_count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_backoff_and_leases_delay_messages() {
+        let (_dir, db) = outbox().await;
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let failing = Outbox::new(db.clone())
+            .publisher("mail", |_: OutboxMessage| async {
+                Err(OutboxError::Publish("smtp down".to_string()))
+            })
+            .retry_backoff(Duration::from_secs(60));
+        Outbox::record(&db, "mail", "welcome", json!({}))
+            .await
+            .unwrap();
+        assert_eq!(failing.relay().await.unwrap().retried, 1);
+
+        // Not due before the backoff passed
+        let outbox = Outbox::new(db.clone()).publisher("mail", recorder(&sent));
+        assert_eq!(outbox.relay().await.unwrap().attempted(), 0);
+
+        // Messages leased by another relay are left alone
+        let message = Outbox::record(&db, "mail", "reset", json!({}))
+            .await
+            .unwrap();
+        let lease = vec![("claimed_until", (now_millis() + 60_000).into())];
+        outbox.update(&message, lease).await.unwrap();
+        assert_eq!(outbox.relay().await.unwrap().attempted(), 0);
+        assert!(sent.lock().unwrap().is_empty());
+        assert_eq!(outbox.pending_count().await.unwrap(), 2);
+    }
+}
@@ -0,0 +1,94 @@
+//! Queue jobs dispatched through the outbox
+
+use crate::{Outbox, OutboxMessage, OutboxResult, Publisher};
+use async_trait::async_trait;
+use rf_orm_lite::Connection;
+use rf_queue::{Job, JobMetadata, Queue};
+use std::sync::Arc;
+
+/// Channel of queue jobs
+pub const QUEUE_CHANNEL: &str = "queue";
+
+/// Pushes jobs recorded with [`Outbox::dispatch`] to a queue (feature
+/// `queue`)
+///
+/// Jobs keep the ID they were recorded with, so a job published twice can
+/// be recognized by it.
+pub struct QueuePublisher {
+    queue: Arc<dyn Queue>,
+}
+
+impl QueuePublisher {
+    pub fn new(queue: impl Queue + 'static) -> Self {
+        Self {
+            queue: Arc::new(queue),
+        }
+    }
+}
+
+#[async_trait]
+impl Publisher for QueuePublisher {
+    async fn publish(&self, message: &OutboxMessage) -> OutboxResult<()> {
+        self.queue.push(message.decode::<JobMetadata>()?).await?;
+        Ok(())
+    }
+}
+
+impl Outbox {
+    /// Record `job` to be pushed to the queue once `conn` committed
+    pub async fn dispatch<J: Job>(conn: &dyn Connection, job: &J) -> OutboxResult<OutboxMessage> {
+        let metadata = JobMetadata::new(job)?;
+        Self::record(conn, QUEUE_CHANNEL, job.job_type(), &metadata).await
+    }
+
+    /// Publish recorded jobs to `queue`
+    pub fn queue(self, queue: impl Queue + 'static) -> Self {
+        self.publisher(QUEUE_CHANNEL, QueuePublisher::new(queue))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rf_orm_lite::Db;
+    use rf_queue::{MemoryQueue, QueueError};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct SendInvoice {
+        order_id: i64,
+    }
+
+    #[async_trait]
+    impl Job for SendInvoice {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "send_invoice"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_jobs_are_pushed_after_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("app.db").display());
+        let db = Db::connect(&url).await.unwrap();
+        let queue = MemoryQueue::new();
+        let outbox = Outbox::new(db.clone()).queue(queue.clone());
+        outbox.install().await.unwrap();
+
+        let tx = db.begin().await.unwrap();
+        let message = Outbox::dispatch(&tx, &SendInvoice { order_id: 7 })
+            .await
+            .unwrap();
+        assert_eq!(queue.size("default").await.unwrap(), 0);
+        tx.commit().await.unwrap();
+
+        assert_eq!(outbox.relay().await.unwrap().published, 1);
+        let job = queue.reserve("default").await.unwrap().unwrap();
+        assert_eq!(job.job_type, "send_invoice");
+        assert_eq!(job.id, message.decode::<JobMetadata>().unwrap().id);
+    }
+}
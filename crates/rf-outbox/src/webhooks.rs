@@ -0,0 +1,50 @@
+//! Webhook events dispatched through the outbox
+
+use crate::{Outbox, OutboxMessage, OutboxResult, Publisher};
+use async_trait::async_trait;
+use rf_orm_lite::Connection;
+use rf_webhooks::Webhooks;
+use serde::Serialize;
+
+/// Channel of webhook events
+pub const WEBHOOKS_CHANNEL: &str = "webhooks";
+
+/// Dispatches events recorded with [`Outbox::webhook`] to their
+/// subscriptions (feature `webhooks`)
+///
+/// Failed deliveries are retried by [`Webhooks`] itself; only store errors
+/// fail the message.
+pub struct WebhookPublisher {
+    webhooks: Webhooks,
+}
+
+impl WebhookPublisher {
+    pub fn new(webhooks: Webhooks) -> Self {
+        Self { webhooks }
+    }
+}
+
+#[async_trait]
+impl Publisher for WebhookPublisher {
+    async fn publish(&self, message: &OutboxMessage) -> OutboxResult<()> {
+        let payload: serde_json::Value = message.decode()?;
+        self.webhooks.dispatch(&message.topic, payload).await?;
+        Ok(())
+    }
+}
+
+impl Outbox {
+    /// Record webhook `event` to be dispatched once `conn` committed
+    pub async fn webhook(
+        conn: &dyn Connection,
+        event: &str,
+        payload: impl Serialize,
+    ) -> OutboxResult<OutboxMessage> {
+        Self::record(conn, WEBHOOKS_CHANNEL, event, payload).await
+    }
+
+    /// Dispatch recorded webhook events with `webhooks`
+    pub fn webhooks(self, webhooks: Webhooks) -> Self {
+        self.publisher(WEBHOOKS_CHANNEL, WebhookPublisher::new(webhooks))
+    }
+}
@@ -0,0 +1,107 @@
+//! Pages as async-graphql connections
+
+use crate::{
+    CursorPaginated, CursorParams, PageBounds, Paginated, PaginationError, PaginationResult,
+};
+use async_graphql::connection::{Connection, Edge};
+use async_graphql::OutputType;
+
+impl<T: OutputType> Paginated<T> {
+    /// Connection of the page, with the items' offsets as cursors
+    pub fn into_connection(self) -> Connection<usize, T> {
+        let offset = self.paginator.offset().max(0) as usize;
+        let mut connection = Connection::new(self.paginator.has_prev(), self.paginator.has_next());
+        connection.edges.extend(
+            self.items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| Edge::new(offset + index, item)),
+        );
+        connection
+    }
+}
+
+impl<T: OutputType> CursorPaginated<T> {
+    /// Connection of the page, with the items' cursors
+    pub fn into_connection(self) -> Connection<String, T> {
+        let (has_previous_page, has_next_page) =
+            (self.prev_cursor.is_some(), self.next_cursor.is_some());
+        let mut connection = Connection::new(has_previous_page, has_next_page);
+        connection.edges.extend(
+            self.cursors
+                .into_iter()
+                .zip(self.items)
+                .map(|(cursor, item)| Edge::new(cursor, item)),
+        );
+        connection
+    }
+}
+
+impl CursorParams {
+    /// Parameters of the `after`/`before`/`first`/`last` arguments of a
+    /// connection field
+    pub fn from_connection(
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+        bounds: PageBounds,
+    ) -> PaginationResult<Self> {
+        let per_page = match (first, last) {
+            (Some(_), Some(_)) => {
+                return Err(PaginationError::InvalidParameter(
+                    "first and last can't be combined".to_string(),
+                ))
+            }
+            (Some(size), None) | (None, Some(size)) => size.into(),
+            (None, None) => bounds.default_per_page,
+        };
+        Self::new(after, before, per_page, bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CursorPaginator, PageParams};
+
+    #[test]
+    fn test_connections() {
+        let params = PageParams {
+            page: 2,
+            per_page: 2,
+        };
+        let connection = Paginated::new(vec![30, 40], params.paginator(5)).into_connection();
+        assert!(connection.has_previous_page);
+        assert!(connection.has_next_page);
+        let cursors: Vec<usize> = connection.edges.iter().map(|edge| edge.cursor).collect();
+        assert_eq!(cursors, [2, 3]);
+
+        let paginator = CursorPaginator::new(2).unwrap();
+        let connection =
+            CursorPaginated::from_rows(vec![1, 2], &paginator, i32::to_string).into_connection();
+        assert!(!connection.has_previous_page);
+        assert!(!connection.has_next_page);
+        assert_eq!(connection.edges[1].cursor, "2");
+        assert_eq!(connection.edges[1].node, 2);
+    }
+
+    #[test]
+    fn test_from_connection() {
+        let bounds = PageBounds::default();
+        let params =
+            CursorParams::from_connection(None, Some("9".into()), None, Some(5), bounds).unwrap();
+        assert_eq!(params.per_page, 5);
+        assert_eq!(params.before.as_deref(), Some("9"));
+        assert_eq!(
+            CursorParams::from_connection(None, None, None, None, bounds)
+                .unwrap()
+                .per_page,
+            20
+        );
+
+        assert!(CursorParams::from_connection(None, None, Some(1), Some(1), bounds).is_err());
+        assert!(CursorParams::from_connection(None, None, Some(-1), None, bounds).is_err());
+        assert!(CursorParams::from_connection(None, None, Some(101), None, bounds).is_err());
+    }
+}
@@ -1,11 +1,30 @@
 //! Pagination utilities for RustForge
 //!
 //! This crate provides offset-based and cursor-based pagination with metadata and links.
+//!
+//! List endpoints take [`PageParams`] or [`CursorParams`], validated
+//! against [`PageBounds`], and return a [`Paginated`] or
+//! [`CursorPaginated`] page; rf-orm-lite repositories build them with its
+//! `pagination` feature. Optional features:
+//!
+//! - `axum`: the parameters as extractors, the pages as JSON responses with
+//!   a `Link` header
+//! - `graphql`: pages as async-graphql connections
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use thiserror::Error;
 
+#[cfg(feature = "graphql")]
+mod graphql;
+mod paginated;
+mod params;
+mod url;
+
+pub use paginated::{CursorPaginated, Paginated};
+pub use params::{CursorParams, PageBounds, PageParams};
+pub use url::page_url;
+
 /// Pagination errors
 #[derive(Debug, Error)]
 pub enum PaginationError {
@@ -17,10 +36,28 @@ pub enum PaginationError {
 
     #[error("Invalid cursor: {0}")]
     InvalidCursor(String),
+
+    #[error("Invalid pagination parameter: {0}")]
+    InvalidParameter(String),
 }
 
 pub type PaginationResult<T> = Result<T, PaginationError>;
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(PaginationError, {
+    Self::InvalidPage(_) => (BAD_REQUEST, "pagination.invalid_page"),
+    Self::InvalidPerPage(_) => (BAD_REQUEST, "pagination.invalid_per_page"),
+    Self::InvalidCursor(_) => (BAD_REQUEST, "pagination.invalid_cursor"),
+    Self::InvalidParameter(_) => (BAD_REQUEST, "pagination.invalid_parameter"),
+});
+
+#[cfg(feature = "axum")]
+impl axum::response::IntoResponse for PaginationError {
+    fn into_response(self) -> axum::response::Response {
+        (axum::http::StatusCode::BAD_REQUEST, self.to_string()).into_response()
+    }
+}
+
 /// Offset-based paginator
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Paginator {
@@ -147,24 +184,32 @@ pub struct PaginationLinks {
 }
 
 impl PaginationLinks {
-    /// Create pagination links with a base URL
-    pub fn new(base_url: &str, paginator: &Paginator) -> Self {
-        let first = Some(format!("{}?page=1", base_url));
-        let last = Some(format!("{}?page={}", base_url, paginator.last_page));
-        let prev = paginator
-            .prev_page()
-            .map(|p| format!("{}?page={}", base_url, p));
-        let next = paginator
-            .next_page()
-            .map(|p| format!("{}?page={}", base_url, p));
-
+    /// Links to the pages of `url`, keeping its parameters other than `page`
+    pub fn new(url: &str, paginator: &Paginator) -> Self {
         Self {
-            first,
-            last,
-            prev,
-            next,
+            first: Some(page_url(url, 1)),
+            last: Some(page_url(url, paginator.last_page.max(1))),
+            prev: paginator.prev_page().map(|p| page_url(url, p)),
+            next: paginator.next_page().map(|p| page_url(url, p)),
         }
     }
+
+    /// The links as `Link` header value (RFC 8288)
+    pub fn header(&self) -> String {
+        [
+            ("first", &self.first),
+            ("prev", &self.prev),
+            ("next", &self.next),
+            ("last", &self.last),
+        ]
+        .into_iter()
+        .filter_map(|(rel, url)| {
+            url.as_ref()
+                .map(|url| format!("<{}>; rel=\"{}\"", url, rel))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
 }
 
 /// Paginated response
@@ -257,6 +302,12 @@ impl CursorPaginator {
     pub fn limit(&self) -> i64 {
         self.per_page
     }
+
+    /// Rows to fetch: one more than the page size tells whether there are
+    /// more pages, see [`CursorPaginated::from_rows`]
+    pub fn fetch_limit(&self) -> i64 {
+        self.per_page + 1
+    }
 }
 
 /// Cursor-based paginated response
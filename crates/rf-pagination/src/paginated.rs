@@ -0,0 +1,403 @@
+//! Pages of items as returned by list endpoints
+
+use crate::url::with_params;
+use crate::{
+    CursorDirection, CursorPaginatedResponse, CursorPaginator, PaginatedResponse, PaginationLinks,
+    PaginationMeta, Paginator,
+};
+use serde::{Serialize, Serializer};
+
+/// A page of items with the paginator describing it
+///
+/// Serializes as `{"data": .., "meta": .., "links": ..}`, the links only
+/// with a [`url`](Self::url). As a response (feature `axum`), the links are
+/// also sent in a `Link` header.
+///
+/// ```
+/// use rf_pagination::{PageParams, Paginated};
+///
+/// let params = PageParams { page: 2, per_page: 2 };
+/// let page = Paginated::new(vec!["c", "d"], params.paginator(5)).url("/tags?per_page=2&page=2");
+///
+/// assert_eq!(
+///     page.link_header().unwrap(),
+///     "</tags?per_page=2&page=1>; rel=\"first\", </tags?per_page=2&page=1>; rel=\"prev\", \
+///      </tags?per_page=2&page=3>; rel=\"next\", </tags?per_page=2&page=3>; rel=\"last\""
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub paginator: Paginator,
+    url: Option<String>,
+}
+
+impl<T> Paginated<T> {
+    pub fn new(items: Vec<T>, paginator: Paginator) -> Self {
+        Self {
+            items,
+            paginator,
+            url: None,
+        }
+    }
+
+    /// Link to the pages of `url`, usually the request URI; its `page`
+    /// parameter is replaced and others kept
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// Convert the items, e.g. models to API resources
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Paginated<U> {
+        Paginated {
+            items: self.items.into_iter().map(f).collect(),
+            paginator: self.paginator,
+            url: self.url,
+        }
+    }
+
+    pub fn meta(&self) -> PaginationMeta {
+        PaginationMeta::from(self.paginator.clone())
+    }
+
+    /// Links to the first, previous, next and last page, given a URL
+    pub fn links(&self) -> Option<PaginationLinks> {
+        self.url
+            .as_deref()
+            .map(|url| PaginationLinks::new(url, &self.paginator))
+    }
+
+    /// `Link` header value of the [`links`](Self::links)
+    pub fn link_header(&self) -> Option<String> {
+        self.links().map(|links| links.header())
+    }
+}
+
+impl<T: Serialize> Serialize for Paginated<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Body<'a, T> {
+            data: &'a [T],
+            meta: PaginationMeta,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            links: Option<PaginationLinks>,
+        }
+
+        Body {
+            data: &self.items,
+            meta: self.meta(),
+            links: self.links(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<T> From<Paginated<T>> for PaginatedResponse<T> {
+    fn from(page: Paginated<T>) -> Self {
+        let links = page.links();
+        Self {
+            data: page.items,
+            meta: PaginationMeta::from(page.paginator),
+            links,
+        }
+    }
+}
+
+/// A page of items paginated by cursor
+///
+/// Cursors are opaque to this type: each item's cursor is taken from the
+/// item, e.g. its ID, and the next page starts after the last item's.
+/// Serializes as `{"data": .., "has_more": .., "next_cursor": ..,
+/// "prev_cursor": .., "links": ..}`, the links only with a
+/// [`url`](Self::url).
+#[derive(Debug, Clone)]
+pub struct CursorPaginated<T> {
+    pub items: Vec<T>,
+    pub per_page: i64,
+    /// Whether there are more items in the direction of pagination
+    pub has_more: bool,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+    pub(crate) cursors: Vec<String>,
+    url: Option<String>,
+}
+
+impl<T> CursorPaginated<T> {
+    /// Page of at most [`CursorPaginator::fetch_limit`] `rows`, fetched in
+    /// order when paginating forward and in reverse order when paginating
+    /// before a cursor
+    pub fn from_rows(
+        mut rows: Vec<T>,
+        paginator: &CursorPaginator,
+        cursor: impl Fn(&T) -> String,
+    ) -> Self {
+        let per_page = paginator.per_page;
+        let more = rows.len() as i64 > per_page;
+        rows.truncate(per_page.max(0) as usize);
+
+        let direction = paginator.cursor.as_ref().map(|cursor| cursor.direction);
+        let (has_prev, has_next) = match direction {
+            Some(CursorDirection::Before) => {
+                rows.reverse();
+                (more, true)
+            }
+            Some(CursorDirection::After) => (true, more),
+            None => (false, more),
+        };
+
+        let cursors: Vec<String> = rows.iter().map(cursor).collect();
+        Self {
+            per_page,
+            has_more: more,
+            next_cursor: cursors.last().filter(|_| has_next).cloned(),
+            prev_cursor: cursors.first().filter(|_| has_prev).cloned(),
+            items: rows,
+            cursors,
+            url: None,
+        }
+    }
+
+    /// Link to the pages of `url`, usually the request URI; its `after` and
+    /// `before` parameters are replaced and others kept
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// Convert the items, keeping their cursors
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> CursorPaginated<U> {
+        CursorPaginated {
+            items: self.items.into_iter().map(f).collect(),
+            per_page: self.per_page,
+            has_more: self.has_more,
+            next_cursor: self.next_cursor,
+            prev_cursor: self.prev_cursor,
+            cursors: self.cursors,
+            url: self.url,
+        }
+    }
+
+    /// Cursors of the items, in the same order
+    pub fn cursors(&self) -> &[String] {
+        &self.cursors
+    }
+
+    /// Links to the first, previous and next page, given a URL
+    pub fn links(&self) -> Option<PaginationLinks> {
+        let url = self.url.as_deref()?;
+        let page = |name, cursor: &Option<String>| {
+            cursor.as_deref().map(|cursor| {
+                with_params(
+                    url,
+                    &[("after", None), ("before", None), (name, Some(cursor))],
+                )
+            })
+        };
+        Some(PaginationLinks {
+            first: Some(with_params(url, &[("after", None), ("before", None)])),
+            last: None,
+            prev: page("before", &self.prev_cursor),
+            next: page("after", &self.next_cursor),
+        })
+    }
+
+    /// `Link` header value of the [`links`](Self::links)
+    pub fn link_header(&self) -> Option<String> {
+        self.links().map(|links| links.header())
+    }
+}
+
+impl<T: Serialize> Serialize for CursorPaginated<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Body<'a, T> {
+            data: &'a [T],
+            has_more: bool,
+            next_cursor: &'a Option<String>,
+            prev_cursor: &'a Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            links: Option<PaginationLinks>,
+        }
+
+        Body {
+            data: &self.items,
+            has_more: self.has_more,
+            next_cursor: &self.next_cursor,
+            prev_cursor: &self.prev_cursor,
+            links: self.links(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<T> From<CursorPaginated<T>> for CursorPaginatedResponse<T> {
+    fn from(page: CursorPaginated<T>) -> Self {
+        Self {
+            data: page.items,
+            has_more: page.has_more,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        }
+    }
+}
+
+#[cfg(feature = "axum")]
+mod response {
+    use super::*;
+    use axum::http::{header, HeaderValue};
+    use axum::response::{IntoResponse, Response};
+    use axum::Json;
+
+    fn respond(body: impl Serialize, link: Option<String>) -> Response {
+        let mut response = Json(body).into_response();
+        if let Some(link) = link.and_then(|link| HeaderValue::try_from(link).ok()) {
+            response.headers_mut().insert(header::LINK, link);
+        }
+        response
+    }
+
+    impl<T: Serialize> IntoResponse for Paginated<T> {
+        fn into_response(self) -> Response {
+            let link = self.link_header();
+            respond(self, link)
+        }
+    }
+
+    impl<T: Serialize> IntoResponse for CursorPaginated<T> {
+        fn into_response(self) -> Response {
+            let link = self.link_header();
+            respond(self, link)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rows(ids: impl IntoIterator<Item = i64>) -> Vec<i64> {
+        ids.into_iter().collect()
+    }
+
+    #[test]
+    fn test_paginated() {
+        let paginator = Paginator::new(3, 2, 2).unwrap();
+        let page = Paginated::new(vec![3], paginator.clone()).map(|id| json!({ "id": id }));
+        assert_eq!(
+            serde_json::to_value(&page).unwrap(),
+            json!({
+                "data": [{"id": 3}],
+                "meta": {
+                    "total": 3,
+                    "per_page": 2,
+                    "current_page": 2,
+                    "last_page": 2,
+                    "from": 3,
+                    "to": 3
+                }
+            })
+        );
+        assert!(page.link_header().is_none());
+
+        let page = page.url("/users?q=a");
+        assert_eq!(
+            page.link_header().unwrap(),
+            "</users?q=a&page=1>; rel=\"first\", </users?q=a&page=1>; rel=\"prev\", \
+             </users?q=a&page=2>; rel=\"last\""
+        );
+        let response = PaginatedResponse::from(page);
+        assert_eq!(response.links.unwrap().next, None);
+    }
+
+    #[test]
+    fn test_cursor_paginated_forward() {
+        let first = CursorPaginator::new(2).unwrap();
+        assert_eq!(first.fetch_limit(), 3);
+        let page = CursorPaginated::from_rows(rows([1, 2, 3]), &first, i64::to_string);
+        assert_eq!(page.items, [1, 2]);
+        assert!(page.has_more);
+        assert_eq!(page.next_cursor.as_deref(), Some("2"));
+        assert_eq!(page.prev_cursor, None);
+
+        let last = CursorPaginator::new(2).unwrap().after("2".to_string());
+        let page = CursorPaginated::from_rows(rows([3]), &last, i64::to_string)
+            .url("/posts?after=2&per_page=2");
+        assert!(!page.has_more);
+        assert_eq!(page.cursors(), ["3"]);
+        assert_eq!(
+            serde_json::to_value(&page).unwrap(),
+            json!({
+                "data": [3],
+                "has_more": false,
+                "next_cursor": null,
+                "prev_cursor": "3",
+                "links": {
+                    "first": "/posts?per_page=2",
+                    "last": null,
+                    "prev": "/posts?per_page=2&before=3",
+                    "next": null
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_cursor_paginated_backward() {
+        let paginator = CursorPaginator::new(2).unwrap().before("10".to_string());
+        // Fetched in reverse: 9, 8, 7
+        let page = CursorPaginated::from_rows(rows([9, 8, 7]), &paginator, i64::to_string)
+            .url("/posts?before=10");
+        assert_eq!(page.items, [8, 9]);
+        assert_eq!(page.prev_cursor.as_deref(), Some("8"));
+        assert_eq!(page.next_cursor.as_deref(), Some("9"));
+        assert_eq!(
+            page.link_header().unwrap(),
+            "</posts>; rel=\"first\", </posts?before=8>; rel=\"prev\", </posts?after=9>; rel=\"next\""
+        );
+        assert_eq!(CursorPaginatedResponse::from(page).data, [8, 9]);
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn test_list_endpoint() {
+        use crate::{PageBounds, PageParams};
+        use axum::{body::Body, http::Request, http::StatusCode, routing::get, Extension, Router};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route(
+                "/numbers",
+                get(|params: PageParams, request: Request<Body>| async move {
+                    let items = (params.offset() + 1..=(params.offset() + params.limit()).min(25))
+                        .collect();
+                    Paginated::new(items, params.paginator(25)).url(request.uri().to_string())
+                }),
+            )
+            .layer(Extension(PageBounds::new(10, 20)));
+        let call = |uri: &str| {
+            app.clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
+
+        let response = call("/numbers?page=3").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["link"],
+            "</numbers?page=1>; rel=\"first\", </numbers?page=2>; rel=\"prev\", \
+             </numbers?page=3>; rel=\"last\""
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["data"], json!([21, 22, 23, 24, 25]));
+        assert_eq!(body["links"]["next"], json!(null));
+
+        for uri in ["/numbers?per_page=21", "/numbers?page=x"] {
+            let response = call(uri).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Pagination parameters of list requests
+
+use crate::url::query_pairs;
+use crate::{CursorPaginator, PaginationError, PaginationResult, Paginator};
+use serde::Serialize;
+
+/// Page sizes clients may ask for
+///
+/// Handlers extracting [`PageParams`] or [`CursorParams`] use the bounds
+/// of the request's extensions, or the defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageBounds {
+    /// Page size without `per_page` (default: 20)
+    pub default_per_page: i64,
+    /// Largest accepted `per_page` (default: 100)
+    pub max_per_page: i64,
+}
+
+impl PageBounds {
+    pub fn new(default_per_page: i64, max_per_page: i64) -> Self {
+        Self {
+            default_per_page,
+            max_per_page,
+        }
+    }
+}
+
+impl Default for PageBounds {
+    fn default() -> Self {
+        Self::new(20, 100)
+    }
+}
+
+/// Validated `page` and `per_page` query parameters
+///
+/// ```
+/// use rf_pagination::{PageBounds, PageParams};
+///
+/// let params = PageParams::from_query("page=3&per_page=25", PageBounds::default()).unwrap();
+/// assert_eq!(params.offset(), 50);
+/// assert!(PageParams::from_query("per_page=500", PageBounds::default()).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PageParams {
+    /// Requested page (1-indexed)
+    pub page: i64,
+    pub per_page: i64,
+}
+
+impl PageParams {
+    /// Check `page` and `per_page` against `bounds`
+    pub fn new(page: i64, per_page: i64, bounds: PageBounds) -> PaginationResult<Self> {
+        if page <= 0 {
+            return Err(PaginationError::InvalidPage(page));
+        }
+        check_per_page(per_page, bounds)?;
+        Ok(Self { page, per_page })
+    }
+
+    /// Parameters of a query string; missing ones take their defaults
+    pub fn from_query(query: &str, bounds: PageBounds) -> PaginationResult<Self> {
+        let mut page = 1;
+        let mut per_page = bounds.default_per_page;
+        for (name, value) in query_pairs(query) {
+            match name.as_str() {
+                "page" => page = number(&name, &value)?,
+                "per_page" => per_page = number(&name, &value)?,
+                _ => {}
+            }
+        }
+        Self::new(page, per_page, bounds)
+    }
+
+    /// Get the offset for SQL queries
+    pub fn offset(&self) -> i64 {
+        (self.page - 1) * self.per_page
+    }
+
+    /// Get the limit for SQL queries
+    pub fn limit(&self) -> i64 {
+        self.per_page
+    }
+
+    /// Paginator of the requested page among `total` items
+    pub fn paginator(&self, total: i64) -> Paginator {
+        let last_page = (total + self.per_page - 1) / self.per_page;
+        Paginator {
+            total,
+            per_page: self.per_page,
+            current_page: self.page,
+            last_page,
+        }
+    }
+}
+
+impl Default for PageParams {
+    fn default() -> Self {
+        Self {
+            page: 1,
+            per_page: PageBounds::default().default_per_page,
+        }
+    }
+}
+
+/// Validated `after`/`before` and `per_page` query parameters
+///
+/// `after` continues with the items following the cursor, `before` goes
+/// back to the items preceding it; they can't be combined.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CursorParams {
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub per_page: i64,
+}
+
+impl CursorParams {
+    /// Check the cursors and `per_page` against `bounds`
+    pub fn new(
+        after: Option<String>,
+        before: Option<String>,
+        per_page: i64,
+        bounds: PageBounds,
+    ) -> PaginationResult<Self> {
+        if after.is_some() && before.is_some() {
+            return Err(PaginationError::InvalidCursor(
+                "after and before can't be combined".to_string(),
+            ));
+        }
+        if [&after, &before]
+            .into_iter()
+            .flatten()
+            .any(String::is_empty)
+        {
+            return Err(PaginationError::InvalidCursor("empty cursor".to_string()));
+        }
+        check_per_page(per_page, bounds)?;
+        Ok(Self {
+            after,
+            before,
+            per_page,
+        })
+    }
+
+    /// Parameters of a query string; missing ones take their defaults
+    pub fn from_query(query: &str, bounds: PageBounds) -> PaginationResult<Self> {
+        let mut after = None;
+        let mut before = None;
+        let mut per_page = bounds.default_per_page;
+        for (name, value) in query_pairs(query) {
+            match name.as_str() {
+                "after" => after = Some(value),
+                "before" => before = Some(value),
+                "per_page" => per_page = number(&name, &value)?,
+                _ => {}
+            }
+        }
+        Self::new(after, before, per_page, bounds)
+    }
+
+    /// Paginator of the requested page
+    pub fn paginator(&self) -> CursorPaginator {
+        let paginator = CursorPaginator {
+            per_page: self.per_page,
+            cursor: None,
+        };
+        match (&self.after, &self.before) {
+            (Some(after), _) => paginator.after(after.clone()),
+            (_, Some(before)) => paginator.before(before.clone()),
+            _ => paginator,
+        }
+    }
+}
+
+fn check_per_page(per_page: i64, bounds: PageBounds) -> PaginationResult<()> {
+    if per_page <= 0 || per_page > bounds.max_per_page {
+        return Err(PaginationError::InvalidPerPage(per_page));
+    }
+    Ok(())
+}
+
+fn number(name: &str, value: &str) -> PaginationResult<i64> {
+    value
+        .parse()
+        .map_err(|_| PaginationError::InvalidParameter(format!("{} must be a number", name)))
+}
+
+#[cfg(feature = "axum")]
+mod extract {
+    use super::*;
+    use axum::extract::FromRequestParts;
+    use axum::http::request::Parts;
+
+    fn bounds(parts: &Parts) -> PageBounds {
+        parts
+            .extensions
+            .get::<PageBounds>()
+            .copied()
+            .unwrap_or_default()
+    }
+
+    impl<S: Send + Sync> FromRequestParts<S> for PageParams {
+        type Rejection = PaginationError;
+
+        async fn from_request_parts(
+            parts: &mut Parts,
+            _state: &S,
+        ) -> Result<Self, Self::Rejection> {
+            Self::from_query(parts.uri.query().unwrap_or(""), bounds(parts))
+        }
+    }
+
+    impl<S: Send + Sync> FromRequestParts<S> for CursorParams {
+        type Rejection = PaginationError;
+
+        async fn from_request_parts(
+            parts: &mut Parts,
+            _state: &S,
+        ) -> Result<Self, Self::Rejection> {
+            Self::from_query(parts.uri.query().unwrap_or(""), bounds(parts))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_params() {
+        let bounds = PageBounds::new(10, 50);
+        assert_eq!(
+            PageParams::from_query("", bounds).unwrap(),
+            PageParams {
+                page: 1,
+                per_page: 10
+            }
+        );
+
+        let params = PageParams::from_query("q=x&page=3&per_page=50", bounds).unwrap();
+        assert_eq!((params.offset(), params.limit()), (100, 50));
+        let paginator = params.paginator(101);
+        assert_eq!(paginator.last_page, 3);
+        assert_eq!(paginator.to(), 101);
+        assert_eq!(params.paginator(0).last_page, 0);
+
+        for query in ["page=0", "page=-2", "per_page=0", "per_page=51", "page=two"] {
+            assert!(PageParams::from_query(query, bounds).is_err(), "{}", query);
+        }
+        assert!(matches!(
+            PageParams::from_query("per_page=x", bounds),
+            Err(PaginationError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn test_cursor_params() {
+        let bounds = PageBounds::default();
+        let params = CursorParams::from_query("after=abc%3D&per_page=5", bounds).unwrap();
+        let paginator = params.paginator();
+        assert_eq!(paginator.per_page, 5);
+        let cursor = paginator.cursor.unwrap();
+        assert_eq!(cursor.value, "abc=");
+        assert_eq!(cursor.direction, crate::CursorDirection::After);
+
+        assert!(CursorParams::from_query("", bounds)
+            .unwrap()
+            .paginator()
+            .cursor
+            .is_none());
+        for query in ["after=1&before=2", "before=", "per_page=101"] {
+            assert!(
+                CursorParams::from_query(query, bounds).is_err(),
+                "{}",
+                query
+            );
+        }
+    }
+}
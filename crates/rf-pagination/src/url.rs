@@ -0,0 +1,126 @@
+//! Query strings of pagination links and parameters
+
+/// `url` with its `page` parameter set to `page`, keeping the others
+///
+/// ```
+/// use rf_pagination::page_url;
+///
+/// assert_eq!(page_url("/tags?page=4&q=rust", 5), "/tags?q=rust&page=5");
+/// ```
+pub fn page_url(url: &str, page: i64) -> String {
+    with_params(url, &[("page", Some(&page.to_string()))])
+}
+
+/// `url` without the parameters of `params`, then with those that have a
+/// value appended
+pub(crate) fn with_params(url: &str, params: &[(&str, Option<&str>)]) -> String {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let mut pairs: Vec<String> = query
+        .split('&')
+        .filter(|pair| {
+            let name = pair.split_once('=').map_or(*pair, |(name, _)| name);
+            !pair.is_empty() && !params.iter().any(|(param, _)| decode(name) == *param)
+        })
+        .map(str::to_string)
+        .collect();
+    for (name, value) in params {
+        if let Some(value) = value {
+            pairs.push(format!("{}={}", encode(name), encode(value)));
+        }
+    }
+
+    if pairs.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}?{}", path, pairs.join("&"))
+    }
+}
+
+/// Decoded name and value pairs of a query string
+pub(crate) fn query_pairs(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(name), decode(value))
+        })
+        .collect()
+}
+
+/// Percent-encode everything but unreserved characters
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Decode percent escapes and `+` for spaces; invalid escapes are kept
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_params() {
+        assert_eq!(page_url("/tags", 2), "/tags?page=2");
+        assert_eq!(
+            page_url("/tags?page&per_page=50&q=a+b", 3),
+            "/tags?per_page=50&q=a+b&page=3"
+        );
+        assert_eq!(
+            with_params(
+                "/posts?before=x&q=1",
+                &[
+                    ("before", None),
+                    ("after", Some("2024-01-01T00:00:00+00:00"))
+                ]
+            ),
+            "/posts?q=1&after=2024-01-01T00%3A00%3A00%2B00%3A00"
+        );
+        assert_eq!(with_params("/posts?after=1", &[("after", None)]), "/posts");
+    }
+
+    #[test]
+    fn test_query_pairs() {
+        assert_eq!(
+            query_pairs("page=2&q=a+b%26c&flag&bad=%zz"),
+            vec![
+                ("page".to_string(), "2".to_string()),
+                ("q".to_string(), "a b&c".to_string()),
+                ("flag".to_string(), String::new()),
+                ("bad".to_string(), "%zz".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+//! Progress kept in rf-cache
+
+use crate::{Progress, ProgressError, ProgressResult, ProgressStore};
+use async_trait::async_trait;
+use rf_cache::Cache;
+use std::time::Duration;
+
+/// Store keeping progress in a cache, e.g. Redis shared by web servers and
+/// workers
+pub struct CacheProgressStore<C> {
+    cache: C,
+}
+
+impl<C: Cache> CacheProgressStore<C> {
+    pub fn new(cache: C) -> Self {
+        Self { cache }
+    }
+}
+
+fn key(id: &str) -> String {
+    format!("progress:{}", id)
+}
+
+fn storage_error(e: rf_cache::CacheError) -> ProgressError {
+    ProgressError::Storage(e.to_string())
+}
+
+#[async_trait]
+impl<C: Cache> ProgressStore for CacheProgressStore<C> {
+    async fn get(&self, id: &str) -> ProgressResult<Option<Progress>> {
+        self.cache.get(&key(id)).await.map_err(storage_error)
+    }
+
+    async fn put(&self, progress: &Progress, ttl: Duration) -> ProgressResult<()> {
+        self.cache
+            .set(&key(&progress.id), progress, ttl)
+            .await
+            .map_err(storage_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProgressTracker;
+    use rf_cache::MemoryCache;
+
+    #[tokio::test]
+    async fn test_cache_store() {
+        let tracker = ProgressTracker::new(CacheProgressStore::new(MemoryCache::new()));
+        let mut job = tracker.start().await.unwrap();
+        job.step(1, "Loading rows").await.unwrap();
+
+        let progress = tracker.get(job.id()).await.unwrap();
+        assert_eq!(progress.message.as_deref(), Some("Loading rows"));
+        assert!(tracker.get("unknown").await.is_err());
+    }
+}
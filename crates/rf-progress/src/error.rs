@@ -0,0 +1,19 @@
+//! Error types for job progress
+
+use thiserror::Error;
+
+/// Progress error types
+#[derive(Debug, Error)]
+pub enum ProgressError {
+    #[error("Job not found: {0}")]
+    NotFound(String),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Progress result type
+pub type ProgressResult<T> = Result<T, ProgressError>;
@@ -0,0 +1,173 @@
+//! Status routes for axum
+
+use crate::{Progress, ProgressError, ProgressTracker};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use std::convert::Infallible;
+use std::time::Duration;
+
+/// How often streams look for updates in the store
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+impl IntoResponse for ProgressError {
+    fn into_response(self) -> Response {
+        let (status, error) = match self {
+            ProgressError::NotFound(_) => (StatusCode::NOT_FOUND, "job_not_found"),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, "progress_unavailable"),
+        };
+        let body = serde_json::json!({ "error": error, "message": self.to_string() });
+        (status, Json(body)).into_response()
+    }
+}
+
+/// Routes for clients following a job
+///
+/// - `GET /jobs/{id}`: the current [`Progress`] as JSON
+/// - `GET /jobs/{id}/events`: a server-sent `progress` event per update,
+///   ending once the job finished
+///
+/// Job IDs are random UUIDs; mount the router behind authentication when
+/// results are sensitive.
+///
+/// ```no_run
+/// use rf_progress::{progress_router, ProgressTracker};
+///
+/// let tracker = ProgressTracker::memory();
+/// let app: axum::Router = axum::Router::new().nest("/api", progress_router(tracker));
+/// ```
+pub fn progress_router(tracker: ProgressTracker) -> Router {
+    Router::new()
+        .route("/jobs/{id}", get(status))
+        .route("/jobs/{id}/events", get(events))
+        .with_state(tracker)
+}
+
+async fn status(
+    State(tracker): State<ProgressTracker>,
+    Path(id): Path<String>,
+) -> Result<Json<Progress>, ProgressError> {
+    Ok(Json(tracker.get(&id).await?))
+}
+
+async fn events(
+    State(tracker): State<ProgressTracker>,
+    Path(id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ProgressError> {
+    tracker.get(&id).await?;
+    let watch = Watch {
+        tracker,
+        id,
+        last: None,
+        finished: false,
+    };
+    let stream = futures::stream::unfold(watch, next_update).map(Ok);
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+struct Watch {
+    tracker: ProgressTracker,
+    id: String,
+    last: Option<DateTime<Utc>>,
+    finished: bool,
+}
+
+/// Wait for the next update of the watched job
+async fn next_update(mut watch: Watch) -> Option<(Event, Watch)> {
+    if watch.finished {
+        return None;
+    }
+    loop {
+        match watch.tracker.get(&watch.id).await {
+            Ok(progress) if watch.last != Some(progress.updated_at) => {
+                watch.last = Some(progress.updated_at);
+                watch.finished = progress.is_finished();
+                let event = Event::default()
+                    .event("progress")
+                    .json_data(&progress)
+                    .ok()?;
+                return Some((event, watch));
+            }
+            Ok(_) => {}
+            // Expired while being watched
+            Err(ProgressError::NotFound(_)) => return None,
+            Err(e) => tracing::warn!(job = %watch.id, error = %e, "Failed to read job progress"),
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::Request};
+    use tower::ServiceExt;
+
+    async fn get_body(app: &Router, uri: &str) -> (StatusCode, String) {
+        let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        let status = res.status();
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_status() {
+        let tracker = ProgressTracker::memory();
+        let app = progress_router(tracker.clone());
+        let mut job = tracker.start().await.unwrap();
+        job.percent(30, "Indexing").await.unwrap();
+
+        let (status, body) = get_body(&app, &format!("/jobs/{}", job.id())).await;
+        assert_eq!(status, StatusCode::OK);
+        let progress: Progress = serde_json::from_str(&body).unwrap();
+        assert_eq!(progress.percent, 30);
+
+        let (status, body) = get_body(&app, "/jobs/unknown").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(body.contains("job_not_found"));
+    }
+
+    #[tokio::test]
+    async fn test_events_stream_until_finished() {
+        let tracker = ProgressTracker::memory();
+        let app = progress_router(tracker.clone());
+        let mut job = tracker.start().await.unwrap();
+
+        let stream = tokio::spawn({
+            let (app, uri) = (app.clone(), format!("/jobs/{}/events", job.id()));
+            async move { get_body(&app, &uri).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        job.advance("Rendering").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        job.complete("report.pdf").await.unwrap();
+
+        let (status, body) = stream.await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        let statuses: Vec<_> = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str::<Progress>(data).unwrap().status)
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                crate::JobStatus::Pending,
+                crate::JobStatus::Running,
+                crate::JobStatus::Completed
+            ]
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! Progress of long-running jobs for RustForge
+//!
+//! Exports, imports, image processing and AI generations run in the
+//! background for a while. They report steps, percentages and messages
+//! through a [`JobProgress`], clients follow along through the status
+//! routes instead of waiting on a spinner.
+//!
+//! # Features
+//!
+//! - Steps or percentages, messages, and a result or error when finished
+//! - Progress kept in memory, in rf-cache (`cache` feature) or in Postgres
+//!   (`postgres` feature), expiring a while after the last update
+//! - Axum routes with the status as JSON and a server-sent event stream of
+//!   updates (`axum` feature)
+//!
+//! # Example
+//!
+//! ```ignore
+//! use rf_progress::{progress_router, ProgressTracker};
+//!
+//! let tracker = ProgressTracker::new(CacheProgressStore::new(redis_cache));
+//!
+//! // Starting the job
+//! let progress = tracker.start().await?;
+//! queue.push(ExportOrders { progress_id: progress.id().to_string() }).await?;
+//!
+//! // In the job
+//! let mut progress = tracker.job(&self.progress_id).await?;
+//! progress.steps(rows.len() as u32).await?;
+//! for row in rows {
+//!     progress.advance(format!("Order {}", row.id)).await?;
+//! }
+//! progress.complete(json!({ "url": url })).await?;
+//!
+//! // Clients follow GET /jobs/{id} or GET /jobs/{id}/events
+//! let app = Router::new().merge(progress_router(tracker));
+//! ```
+
+mod error;
+mod progress;
+mod store;
+mod tracker;
+
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "axum")]
+mod http;
+#[cfg(feature = "postgres")]
+mod postgres;
+
+pub use error::{ProgressError, ProgressResult};
+pub use progress::{JobStatus, Progress};
+pub use store::{MemoryProgressStore, ProgressStore};
+pub use tracker::{JobProgress, ProgressTracker};
+
+#[cfg(feature = "cache")]
+pub use cache::CacheProgressStore;
+#[cfg(feature = "axum")]
+pub use http::progress_router;
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresProgressStore;
@@ -0,0 +1,117 @@
+//! Progress kept in Postgres
+
+use crate::{Progress, ProgressError, ProgressResult, ProgressStore};
+use async_trait::async_trait;
+use sqlx::{PgPool, Row};
+use std::time::Duration;
+
+/// Store keeping progress in a Postgres table, see
+/// [`migrate`](Self::migrate)
+///
+/// Expired rows are skipped by reads and deleted whenever a job finishes.
+///
+/// ```no_run
+/// use rf_progress::{PostgresProgressStore, ProgressTracker};
+///
+/// # async fn example(pool: sqlx::PgPool) -> rf_progress::ProgressResult<()> {
+/// let store = PostgresProgressStore::new(pool);
+/// store.migrate().await?;
+/// let tracker = ProgressTracker::new(store);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct PostgresProgressStore {
+    pool: PgPool,
+    table: String,
+}
+
+impl PostgresProgressStore {
+    /// Store in the `job_progress` table
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            table: "job_progress".to_string(),
+        }
+    }
+
+    /// Use a different table
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Create the table if it doesn't exist
+    pub async fn migrate(&self) -> ProgressResult<()> {
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id TEXT PRIMARY KEY,
+                progress JSONB NOT NULL,
+                expires_at TIMESTAMPTZ NOT NULL
+            )",
+            self.table
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(storage_error)?;
+        Ok(())
+    }
+}
+
+fn storage_error(e: sqlx::Error) -> ProgressError {
+    ProgressError::Storage(e.to_string())
+}
+
+#[async_trait]
+impl ProgressStore for PostgresProgressStore {
+    async fn get(&self, id: &str) -> ProgressResult<Option<Progress>> {
+        let row = sqlx::query(&format!(
+            "SELECT progress::text AS progress FROM {} WHERE id = $1 AND expires_at > now()",
+            self.table
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(storage_error)?;
+
+        match row {
+            Some(row) => {
+                let progress: String = row.try_get("progress").map_err(storage_error)?;
+                Ok(Some(serde_json::from_str(&progress)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn put(&self, progress: &Progress, ttl: Duration) -> ProgressResult<()> {
+        let expires_at = chrono::Duration::from_std(ttl)
+            .ok()
+            .and_then(|ttl| progress.updated_at.checked_add_signed(ttl))
+            .unwrap_or(chrono::DateTime::<chrono::Utc>::MAX_UTC);
+        sqlx::query(&format!(
+            "INSERT INTO {} (id, progress, expires_at) VALUES ($1, $2::jsonb, $3)
+             ON CONFLICT (id) DO UPDATE SET
+                progress = EXCLUDED.progress,
+                expires_at = EXCLUDED.expires_at",
+            self.table
+        ))
+        .bind(&progress.id)
+        .bind(serde_json::to_string(progress)?)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(storage_error)?;
+
+        // Finished jobs are written once more, a good moment to clean up
+        if progress.is_finished() {
+            sqlx::query(&format!(
+                "DELETE FROM {} WHERE expires_at <= now()",
+                self.table
+            ))
+            .execute(&self.pool)
+            .await
+            .map_err(storage_error)?;
+        }
+        Ok(())
+    }
+}
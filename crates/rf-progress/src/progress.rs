@@ -0,0 +1,63 @@
+//! Progress snapshots
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Stage of a tracked job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Dispatched, not picked up yet
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Progress of a job as clients see it
+///
+/// Serialized as is by the status routes:
+///
+/// ```json
+/// {"id": "…", "status": "running", "step": 2, "total_steps": 4, "percent": 50,
+///  "message": "Rendering PDF", "result": null, "error": null, …}
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    pub id: String,
+    pub status: JobStatus,
+    /// Current step, counting from 1
+    pub step: u32,
+    pub total_steps: Option<u32>,
+    /// 0 to 100
+    pub percent: u8,
+    pub message: Option<String>,
+    /// What the job produced, e.g. a download URL, once completed
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Progress {
+    pub(crate) fn new(id: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id,
+            status: JobStatus::Pending,
+            step: 0,
+            total_steps: None,
+            percent: 0,
+            message: None,
+            result: None,
+            error: None,
+            started_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Completed or failed
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status, JobStatus::Completed | JobStatus::Failed)
+    }
+}
@@ -0,0 +1,52 @@
+//! Progress storage
+
+use crate::{Progress, ProgressResult};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+
+/// Where progress is kept between the job and the status routes
+///
+/// Use a shared store (cache or database) when jobs run on other instances
+/// than the web servers.
+#[async_trait]
+pub trait ProgressStore: Send + Sync {
+    /// Latest progress of job `id`, `None` if unknown or expired
+    async fn get(&self, id: &str) -> ProgressResult<Option<Progress>>;
+
+    /// Save `progress`, kept for `ttl` after its last update
+    async fn put(&self, progress: &Progress, ttl: Duration) -> ProgressResult<()>;
+}
+
+/// In-process store, for tests and single-instance applications
+#[derive(Default)]
+pub struct MemoryProgressStore {
+    jobs: RwLock<HashMap<String, (Progress, Instant)>>,
+}
+
+impl MemoryProgressStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ProgressStore for MemoryProgressStore {
+    async fn get(&self, id: &str) -> ProgressResult<Option<Progress>> {
+        let jobs = self.jobs.read().await;
+        Ok(jobs
+            .get(id)
+            .filter(|(_, expires)| *expires > Instant::now())
+            .map(|(progress, _)| progress.clone()))
+    }
+
+    async fn put(&self, progress: &Progress, ttl: Duration) -> ProgressResult<()> {
+        let mut jobs = self.jobs.write().await;
+        let now = Instant::now();
+        jobs.retain(|_, (_, expires)| *expires > now);
+        jobs.insert(progress.id.clone(), (progress.clone(), now + ttl));
+        Ok(())
+    }
+}
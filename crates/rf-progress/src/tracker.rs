@@ -0,0 +1,224 @@
+//! Reporting progress from jobs
+
+use crate::{
+    JobStatus, MemoryProgressStore, Progress, ProgressError, ProgressResult, ProgressStore,
+};
+use chrono::Utc;
+use serde::Serialize;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Creates and looks up tracked jobs
+///
+/// The web request starting a job calls [`start`](Self::start) and passes
+/// the ID to the job and to the client; the job reports through the
+/// [`JobProgress`] it gets from [`job`](Self::job), the client polls or
+/// streams the status routes.
+///
+/// ```
+/// use rf_progress::ProgressTracker;
+///
+/// # async fn example() -> rf_progress::ProgressResult<()> {
+/// let tracker = ProgressTracker::memory();
+/// let id = tracker.start().await?.id().to_string();
+///
+/// // In the job
+/// let mut progress = tracker.job(&id).await?;
+/// progress.steps(2).await?;
+/// progress.advance("Loading orders").await?;
+/// progress.advance("Writing CSV").await?;
+/// progress.complete(serde_json::json!({ "url": "/exports/orders.csv" })).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ProgressTracker {
+    store: Arc<dyn ProgressStore>,
+    ttl: Duration,
+}
+
+impl ProgressTracker {
+    pub fn new(store: impl ProgressStore + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+            ttl: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    /// Tracker keeping progress in memory
+    pub fn memory() -> Self {
+        Self::new(MemoryProgressStore::new())
+    }
+
+    /// How long progress is kept after the last update (default: 24 hours)
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Track a new job, pending until it reports
+    pub async fn start(&self) -> ProgressResult<JobProgress> {
+        let progress = Progress::new(uuid::Uuid::new_v4().to_string());
+        self.store.put(&progress, self.ttl).await?;
+        Ok(JobProgress {
+            tracker: self.clone(),
+            progress,
+        })
+    }
+
+    /// Handle to report the progress of job `id`
+    pub async fn job(&self, id: &str) -> ProgressResult<JobProgress> {
+        Ok(JobProgress {
+            tracker: self.clone(),
+            progress: self.get(id).await?,
+        })
+    }
+
+    /// Latest progress of job `id`
+    pub async fn get(&self, id: &str) -> ProgressResult<Progress> {
+        self.store
+            .get(id)
+            .await?
+            .ok_or_else(|| ProgressError::NotFound(id.to_string()))
+    }
+}
+
+/// Reports the progress of one job
+pub struct JobProgress {
+    tracker: ProgressTracker,
+    progress: Progress,
+}
+
+impl JobProgress {
+    pub fn id(&self) -> &str {
+        &self.progress.id
+    }
+
+    /// Progress as last reported
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    /// Announce how many steps the job takes, so steps set the percentage
+    pub async fn steps(&mut self, total: u32) -> ProgressResult<()> {
+        self.progress.total_steps = Some(total);
+        self.progress.percent = percent(self.progress.step, total);
+        self.save().await
+    }
+
+    /// Report that step `step` started
+    pub async fn step(&mut self, step: u32, message: impl Into<String>) -> ProgressResult<()> {
+        self.progress.status = JobStatus::Running;
+        self.progress.step = step;
+        if let Some(total) = self.progress.total_steps {
+            self.progress.percent = percent(step, total);
+        }
+        self.progress.message = Some(message.into());
+        self.save().await
+    }
+
+    /// Report that the next step started
+    pub async fn advance(&mut self, message: impl Into<String>) -> ProgressResult<()> {
+        self.step(self.progress.step + 1, message).await
+    }
+
+    /// Report a percentage directly, for jobs without distinct steps
+    pub async fn percent(&mut self, percent: u8, message: impl Into<String>) -> ProgressResult<()> {
+        self.progress.status = JobStatus::Running;
+        self.progress.percent = percent.min(100);
+        self.progress.message = Some(message.into());
+        self.save().await
+    }
+
+    /// Mark the job completed with its `result`
+    pub async fn complete(&mut self, result: impl Serialize) -> ProgressResult<()> {
+        self.progress.status = JobStatus::Completed;
+        self.progress.percent = 100;
+        self.progress.result = Some(serde_json::to_value(result)?);
+        self.save().await
+    }
+
+    /// Mark the job failed
+    pub async fn fail(&mut self, error: impl Display) -> ProgressResult<()> {
+        self.progress.status = JobStatus::Failed;
+        self.progress.error = Some(error.to_string());
+        self.save().await
+    }
+
+    async fn save(&mut self) -> ProgressResult<()> {
+        self.progress.updated_at = Utc::now();
+        self.tracker
+            .store
+            .put(&self.progress, self.tracker.ttl)
+            .await?;
+        if self.progress.is_finished() {
+            let status = self.progress.status;
+            tracing::debug!(job = %self.progress.id, ?status, "Job finished");
+        }
+        Ok(())
+    }
+}
+
+/// Share of `step` in `total`, the current step counting as started
+fn percent(step: u32, total: u32) -> u8 {
+    if total == 0 {
+        return 0;
+    }
+    let done = step.saturating_sub(1).min(total) as u64;
+    (done * 100 / total as u64) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_steps() {
+        let tracker = ProgressTracker::memory();
+        let id = tracker.start().await.unwrap().id().to_string();
+        assert_eq!(tracker.get(&id).await.unwrap().status, JobStatus::Pending);
+
+        let mut job = tracker.job(&id).await.unwrap();
+        job.steps(4).await.unwrap();
+        job.advance("Loading").await.unwrap();
+        job.advance("Rendering").await.unwrap();
+        job.advance("Compressing").await.unwrap();
+
+        let progress = tracker.get(&id).await.unwrap();
+        assert_eq!(progress.status, JobStatus::Running);
+        assert_eq!((progress.step, progress.percent), (3, 50));
+        assert_eq!(progress.message.as_deref(), Some("Compressing"));
+
+        job.complete("done").await.unwrap();
+        let progress = tracker.get(&id).await.unwrap();
+        assert!(progress.is_finished());
+        assert_eq!(progress.percent, 100);
+        assert_eq!(progress.result, Some(serde_json::json!("done")));
+    }
+
+    #[tokio::test]
+    async fn test_percent_and_failure() {
+        let tracker = ProgressTracker::memory();
+        let mut job = tracker.start().await.unwrap();
+        job.percent(140, "Generating").await.unwrap();
+        assert_eq!(job.progress().percent, 100);
+
+        job.fail("Model unavailable").await.unwrap();
+        let progress = tracker.get(job.id()).await.unwrap();
+        assert_eq!(progress.status, JobStatus::Failed);
+        assert_eq!(progress.error.as_deref(), Some("Model unavailable"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_progress_expires() {
+        let tracker = ProgressTracker::memory().ttl(Duration::from_secs(60));
+        let job = tracker.start().await.unwrap();
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert!(matches!(
+            tracker.get(job.id()).await,
+            Err(ProgressError::NotFound(_))
+        ));
+    }
+}
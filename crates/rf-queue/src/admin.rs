@@ -0,0 +1,394 @@
+//! rf-admin integration
+
+use crate::error::QueueError;
+use crate::job::JobMetadata;
+use crate::monitor::{QueueMonitor, QueueStats};
+use async_trait::async_trait;
+use rf_admin::{
+    AdminError, AdminList, AdminResource, AdminResult, AdminWidget, FieldConfig, FieldType,
+    ListParams, ResourceAction, WidgetKind,
+};
+use serde_json::json;
+
+/// Exposes the dead-letter queues as an rf-admin resource
+///
+/// Failed jobs are read-only; the `retry` action moves a job back to its
+/// queue and deleting forgets it.
+///
+/// # Example
+///
+/// ```ignore
+/// let panel = AdminPanel::new()
+///     .resource(Arc::new(FailedJobsResource::new(monitor.clone())))
+///     .widget(Arc::new(QueueWidget::overview(monitor.clone())))
+///     .widget(Arc::new(QueueWidget::throughput(monitor)));
+/// ```
+pub struct FailedJobsResource {
+    monitor: QueueMonitor,
+}
+
+impl FailedJobsResource {
+    pub fn new(monitor: QueueMonitor) -> Self {
+        Self { monitor }
+    }
+
+    async fn find(&self, id: &str) -> AdminResult<JobMetadata> {
+        self.monitor
+            .failed_jobs()
+            .await
+            .map_err(admin_error)?
+            .into_iter()
+            .find(|job| job.id == id)
+            .ok_or_else(|| AdminError::ResourceNotFound(id.to_string()))
+    }
+}
+
+fn admin_error(e: QueueError) -> AdminError {
+    match e {
+        QueueError::JobNotFound(id) => AdminError::ResourceNotFound(id),
+        _ => AdminError::DatabaseError(e.to_string()),
+    }
+}
+
+fn to_value(job: &JobMetadata) -> serde_json::Value {
+    json!({
+        "id": job.id,
+        "job_type": job.job_type,
+        "queue": job.queue,
+        "attempts": job.attempts,
+        "last_error": job.last_error,
+        "created_at": job.created_at,
+        "payload": serde_json::from_slice::<serde_json::Value>(&job.data).unwrap_or_default(),
+    })
+}
+
+fn read_only() -> AdminError {
+    AdminError::ValidationError("Failed jobs are read-only".to_string())
+}
+
+#[async_trait]
+impl AdminResource for FailedJobsResource {
+    fn name(&self) -> &str {
+        "failed-jobs"
+    }
+
+    fn label(&self) -> &str {
+        "Failed Jobs"
+    }
+
+    fn fields(&self) -> Vec<FieldConfig> {
+        vec![
+            FieldConfig::new("id", "ID"),
+            FieldConfig::new("job_type", "Job").searchable().sortable(),
+            FieldConfig::new("queue", "Queue").sortable(),
+            FieldConfig::new("attempts", "Attempts").field_type(FieldType::Number),
+            FieldConfig::new("last_error", "Error").searchable(),
+            FieldConfig::new("created_at", "Dispatched")
+                .field_type(FieldType::DateTime)
+                .sortable(),
+            FieldConfig::new("payload", "Payload")
+                .field_type(FieldType::TextArea)
+                .list_display(false),
+        ]
+    }
+
+    async fn list(&self, params: ListParams) -> AdminResult<AdminList> {
+        let mut jobs = self.monitor.failed_jobs().await.map_err(admin_error)?;
+
+        if let Some(search) = params.search.as_deref().filter(|s| !s.is_empty()) {
+            let search = search.to_lowercase();
+            jobs.retain(|job| {
+                job.job_type.to_lowercase().contains(&search)
+                    || job
+                        .last_error
+                        .as_deref()
+                        .is_some_and(|error| error.to_lowercase().contains(&search))
+            });
+        }
+
+        match params.sort.as_deref() {
+            Some("job_type") => jobs.sort_by(|a, b| a.job_type.cmp(&b.job_type)),
+            Some("queue") => jobs.sort_by(|a, b| a.queue.cmp(&b.queue)),
+            Some("created_at") => jobs.sort_by_key(|job| job.created_at),
+            _ => {}
+        }
+        if params.order.as_deref() == Some("desc") {
+            jobs.reverse();
+        }
+
+        let page = params.page.unwrap_or(1).max(1);
+        let per_page = params.per_page.unwrap_or(25).max(1);
+        let total = jobs.len() as u64;
+
+        let data = jobs
+            .iter()
+            .skip(((page - 1) * per_page) as usize)
+            .take(per_page as usize)
+            .map(to_value)
+            .collect();
+
+        Ok(AdminList::new(data, total, page, per_page))
+    }
+
+    async fn get(&self, id: &str) -> AdminResult<serde_json::Value> {
+        Ok(to_value(&self.find(id).await?))
+    }
+
+    async fn create(&self, _data: serde_json::Value) -> AdminResult<serde_json::Value> {
+        Err(read_only())
+    }
+
+    async fn update(&self, _id: &str, _data: serde_json::Value) -> AdminResult<serde_json::Value> {
+        Err(read_only())
+    }
+
+    async fn delete(&self, id: &str) -> AdminResult<()> {
+        self.monitor.queue().forget(id).await.map_err(admin_error)
+    }
+
+    fn menu_group(&self) -> Option<&str> {
+        Some("Queues")
+    }
+
+    fn icon(&self) -> Option<&str> {
+        Some("alert-triangle")
+    }
+
+    fn actions(&self) -> Vec<ResourceAction> {
+        vec![ResourceAction::new("retry", "Retry")]
+    }
+
+    async fn run_action(&self, action: &str, id: &str) -> AdminResult<serde_json::Value> {
+        if action != "retry" {
+            return Err(AdminError::ResourceNotFound(format!(
+                "{}/actions/{}",
+                self.name(),
+                action
+            )));
+        }
+
+        let job = self.find(id).await?;
+        self.monitor
+            .queue()
+            .requeue(id)
+            .await
+            .map_err(admin_error)?;
+        tracing::info!(job_id = %id, job_type = %job.job_type, "Failed job retried from admin");
+        Ok(json!({ "id": id, "queue": job.queue }))
+    }
+}
+
+/// Dashboard widget showing queue metrics
+pub struct QueueWidget {
+    monitor: QueueMonitor,
+    view: View,
+}
+
+#[derive(Clone, Copy)]
+enum View {
+    Overview,
+    Queues,
+    Throughput,
+    JobTypes,
+    Slowest,
+}
+
+impl QueueWidget {
+    /// Pending and failed jobs in total, and jobs per minute
+    pub fn overview(monitor: QueueMonitor) -> Self {
+        Self::new(monitor, View::Overview)
+    }
+
+    /// Pending and failed jobs per queue
+    pub fn queues(monitor: QueueMonitor) -> Self {
+        Self::new(monitor, View::Queues)
+    }
+
+    /// Jobs finished per minute over the last hour
+    pub fn throughput(monitor: QueueMonitor) -> Self {
+        Self::new(monitor, View::Throughput)
+    }
+
+    /// Attempts, failure rate and durations per job type
+    pub fn job_types(monitor: QueueMonitor) -> Self {
+        Self::new(monitor, View::JobTypes)
+    }
+
+    /// Slowest attempts
+    pub fn slowest(monitor: QueueMonitor) -> Self {
+        Self::new(monitor, View::Slowest)
+    }
+
+    fn new(monitor: QueueMonitor, view: View) -> Self {
+        Self { monitor, view }
+    }
+
+    fn render(&self, stats: QueueStats) -> serde_json::Value {
+        match self.view {
+            View::Overview => json!({
+                "Pending": stats.queues.iter().map(|q| q.pending).sum::<usize>(),
+                "Failed": stats.queues.iter().map(|q| q.failed).sum::<usize>(),
+                "Jobs per minute": stats.per_minute(),
+            }),
+            View::Queues => json!(stats.queues),
+            View::Throughput => json!(stats.throughput),
+            View::JobTypes => stats
+                .job_types
+                .iter()
+                .map(|job| {
+                    json!({
+                        "job_type": job.job_type,
+                        "completed": job.completed,
+                        "retried": job.retried,
+                        "failed": job.failed,
+                        "failure_rate": (job.failure_rate() * 1000.0).round() / 10.0,
+                        "average_duration_ms": job.average_duration_ms(),
+                        "max_duration_ms": job.max_duration_ms,
+                    })
+                })
+                .collect(),
+            View::Slowest => json!(stats.slowest),
+        }
+    }
+}
+
+#[async_trait]
+impl AdminWidget for QueueWidget {
+    fn name(&self) -> &str {
+        match self.view {
+            View::Overview => "queue-overview",
+            View::Queues => "queue-depths",
+            View::Throughput => "queue-throughput",
+            View::JobTypes => "queue-job-types",
+            View::Slowest => "queue-slowest-jobs",
+        }
+    }
+
+    fn label(&self) -> &str {
+        match self.view {
+            View::Overview => "Queues",
+            View::Queues => "Queue Depths",
+            View::Throughput => "Throughput",
+            View::JobTypes => "Jobs",
+            View::Slowest => "Slowest Jobs",
+        }
+    }
+
+    fn kind(&self) -> WidgetKind {
+        match self.view {
+            View::Overview => WidgetKind::Metric,
+            View::Throughput => WidgetKind::Chart,
+            View::Queues | View::JobTypes | View::Slowest => WidgetKind::Table,
+        }
+    }
+
+    async fn data(&self) -> AdminResult<serde_json::Value> {
+        let stats = self.monitor.stats().await.map_err(admin_error)?;
+        Ok(self.render(stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job::Job;
+    use crate::memory::MemoryQueue;
+    use crate::queue::Queue;
+    use serde::{Deserialize, Serialize};
+    use std::sync::Arc;
+
+    #[derive(Serialize, Deserialize)]
+    struct SendInvoice {
+        invoice: u32,
+    }
+
+    #[async_trait]
+    impl Job for SendInvoice {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "send_invoice"
+        }
+    }
+
+    /// Monitor with one failed job per invoice number
+    async fn failed(invoices: &[u32]) -> (Arc<MemoryQueue>, QueueMonitor, Vec<String>) {
+        let queue = Arc::new(MemoryQueue::new());
+        let mut ids = Vec::new();
+        for &invoice in invoices {
+            let metadata = JobMetadata::new(&SendInvoice { invoice }).unwrap();
+            queue.push(metadata).await.unwrap();
+            let job = queue.reserve("default").await.unwrap().unwrap();
+            queue.fail(&job.id, "SMTP unavailable").await.unwrap();
+            ids.push(job.id);
+        }
+        let monitor = QueueMonitor::new(queue.clone());
+        (queue, monitor, ids)
+    }
+
+    #[tokio::test]
+    async fn test_list_and_get_failed_jobs() {
+        let (_, monitor, ids) = failed(&[1, 2, 3]).await;
+        let resource = FailedJobsResource::new(monitor);
+
+        let list = resource
+            .list(ListParams {
+                page: Some(2),
+                per_page: Some(2),
+                search: Some("smtp".to_string()),
+                sort: None,
+                order: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!((list.total, list.data.len()), (3, 1));
+        assert_eq!(list.data[0]["id"], ids[2]);
+
+        let job = resource.get(&ids[0]).await.unwrap();
+        assert_eq!(job["payload"]["invoice"], 1);
+        assert_eq!(job["last_error"], "SMTP unavailable");
+        assert!(matches!(
+            resource.update(&ids[0], json!({})).await,
+            Err(AdminError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retry_and_delete() {
+        let (queue, monitor, ids) = failed(&[1, 2]).await;
+        let resource = FailedJobsResource::new(monitor);
+
+        resource.run_action("retry", &ids[0]).await.unwrap();
+        assert_eq!(queue.size("default").await.unwrap(), 1);
+        resource.delete(&ids[1]).await.unwrap();
+        assert!(queue.failed("default").await.unwrap().is_empty());
+
+        assert!(matches!(
+            resource.run_action("retry", &ids[1]).await,
+            Err(AdminError::ResourceNotFound(_))
+        ));
+        assert!(resource.run_action("archive", &ids[0]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_widgets() {
+        let (queue, monitor, _) = failed(&[1]).await;
+        queue
+            .push(JobMetadata::new(&SendInvoice { invoice: 2 }).unwrap())
+            .await
+            .unwrap();
+
+        let overview = QueueWidget::overview(monitor.clone());
+        assert_eq!(overview.kind(), WidgetKind::Metric);
+        let data = overview.data().await.unwrap();
+        assert_eq!(
+            (data["Pending"].as_u64(), data["Failed"].as_u64()),
+            (Some(1), Some(1))
+        );
+
+        let depths = QueueWidget::queues(monitor).data().await.unwrap();
+        assert_eq!(depths[0]["name"], "default");
+    }
+}
@@ -0,0 +1,507 @@
+//! Job batches
+
+use crate::chain::ChainStep;
+use crate::error::{QueueError, QueueResult};
+use crate::job::JobMetadata;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// Jobs dispatched together, with callbacks once they are done
+///
+/// The jobs run in parallel (fan-out). When all of them are processed,
+/// `then` runs if none failed (fan-in) and `finally` runs in any case. The
+/// first job failing for good runs `catch` and cancels the batch, unless
+/// failures are allowed; jobs of a cancelled batch that didn't start yet
+/// are skipped. Callbacks are jobs themselves, so they survive restarts
+/// like any other job. Dispatch batches with [`Bus::batch`](crate::Bus::batch).
+///
+/// ```
+/// use rf_queue::{Batch, JobMetadata};
+/// # use rf_queue::{Job, QueueError};
+/// # use serde::{Deserialize, Serialize};
+/// # #[derive(Serialize, Deserialize)]
+/// # struct ImportRows { from: u32 }
+/// # #[async_trait::async_trait]
+/// # impl Job for ImportRows {
+/// #     async fn handle(&self) -> Result<(), QueueError> { Ok(()) }
+/// #     fn job_type(&self) -> &'static str { "import_rows" }
+/// # }
+/// # #[derive(Serialize, Deserialize)]
+/// # struct NotifyImported;
+/// # #[async_trait::async_trait]
+/// # impl Job for NotifyImported {
+/// #     async fn handle(&self) -> Result<(), QueueError> { Ok(()) }
+/// #     fn job_type(&self) -> &'static str { "notify_imported" }
+/// # }
+///
+/// # fn example() -> Result<(), QueueError> {
+/// let mut batch = Batch::new("import users.csv");
+/// for from in (0..10_000).step_by(1_000) {
+///     batch = batch.job(JobMetadata::new(&ImportRows { from })?);
+/// }
+/// let batch = batch.then(JobMetadata::new(&NotifyImported)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Batch {
+    name: String,
+    jobs: Vec<JobMetadata>,
+    callbacks: Callbacks,
+    allow_failures: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Callbacks {
+    then: Option<JobMetadata>,
+    catch: Option<JobMetadata>,
+    finally: Option<JobMetadata>,
+}
+
+impl Batch {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            jobs: Vec::new(),
+            callbacks: Callbacks::default(),
+            allow_failures: false,
+        }
+    }
+
+    /// Add a job to the batch
+    pub fn job(mut self, metadata: JobMetadata) -> Self {
+        self.jobs.push(metadata);
+        self
+    }
+
+    /// Run `metadata` once all jobs succeeded
+    pub fn then(mut self, metadata: JobMetadata) -> Self {
+        self.callbacks.then = Some(metadata);
+        self
+    }
+
+    /// Run `metadata` when the first job fails for good
+    pub fn catch(mut self, metadata: JobMetadata) -> Self {
+        self.callbacks.catch = Some(metadata);
+        self
+    }
+
+    /// Run `metadata` once all jobs were processed, failed or not
+    pub fn finally(mut self, metadata: JobMetadata) -> Self {
+        self.callbacks.finally = Some(metadata);
+        self
+    }
+
+    /// Keep running the other jobs when one fails; `then` still runs once
+    /// all are processed
+    pub fn allow_failures(mut self) -> Self {
+        self.allow_failures = true;
+        self
+    }
+
+    /// Split into the stored record and the jobs to push
+    pub(crate) fn into_record(
+        self,
+        continuation: Vec<ChainStep>,
+        chain_catch: Option<Box<JobMetadata>>,
+    ) -> (BatchRecord, Vec<JobMetadata>) {
+        let record = BatchRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: self.name,
+            total_jobs: self.jobs.len(),
+            pending_jobs: self.jobs.len(),
+            failed_jobs: 0,
+            failed_job_ids: Vec::new(),
+            allow_failures: self.allow_failures,
+            created_at: Utc::now(),
+            cancelled_at: None,
+            finished_at: None,
+            callbacks: self.callbacks,
+            continuation,
+            chain_catch,
+        };
+        (record, self.jobs)
+    }
+}
+
+/// State of a dispatched batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRecord {
+    pub id: String,
+    pub name: String,
+    pub total_jobs: usize,
+    /// Jobs not processed yet
+    pub pending_jobs: usize,
+    pub failed_jobs: usize,
+    pub failed_job_ids: Vec<String>,
+    pub allow_failures: bool,
+    pub created_at: DateTime<Utc>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    callbacks: Callbacks,
+    /// Rest of the chain the batch is a step of
+    #[serde(default)]
+    continuation: Vec<ChainStep>,
+    #[serde(default)]
+    chain_catch: Option<Box<JobMetadata>>,
+}
+
+/// What happened to a job of a batch or chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobOutcome {
+    Succeeded,
+    /// Failed for good
+    Failed,
+    /// Skipped because the batch was cancelled
+    Skipped,
+}
+
+impl JobOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobOutcome::Succeeded => "succeeded",
+            JobOutcome::Failed => "failed",
+            JobOutcome::Skipped => "skipped",
+        }
+    }
+
+    #[cfg(feature = "postgres-backend")]
+    pub(crate) fn parse(outcome: &str) -> Option<Self> {
+        match outcome {
+            "succeeded" => Some(JobOutcome::Succeeded),
+            "failed" => Some(JobOutcome::Failed),
+            "skipped" => Some(JobOutcome::Skipped),
+            _ => None,
+        }
+    }
+
+    /// Whether this outcome is recorded over `previous`
+    ///
+    /// Only the first outcome of a job counts, so a job delivered twice
+    /// is ignored, except for a success after failing for good: the job
+    /// was retried by hand and its failure no longer counts.
+    pub fn replaces(self, previous: Option<JobOutcome>) -> bool {
+        match previous {
+            None => true,
+            Some(previous) => previous == JobOutcome::Failed && self == JobOutcome::Succeeded,
+        }
+    }
+}
+
+/// Follow-up work after recording a job outcome
+#[derive(Debug, Default)]
+pub(crate) struct Settled {
+    pub jobs: Vec<JobMetadata>,
+    pub continuation: Option<(Vec<ChainStep>, Option<Box<JobMetadata>>)>,
+}
+
+impl BatchRecord {
+    /// Jobs processed so far, failed ones included
+    pub fn processed_jobs(&self) -> usize {
+        self.total_jobs - self.pending_jobs
+    }
+
+    /// Percentage of processed jobs
+    pub fn progress(&self) -> u8 {
+        if self.total_jobs == 0 {
+            return 100;
+        }
+        (self.processed_jobs() * 100 / self.total_jobs) as u8
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished_at.is_some()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled_at.is_some()
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.failed_jobs > 0
+    }
+
+    /// Update the counters for a processed job, returning the callbacks due
+    ///
+    /// Stores call this while holding the batch locked, with the outcome
+    /// recorded for the job before, so only one worker sees the batch
+    /// finish. A job succeeding after failing for good is no longer counted
+    /// as failed, but the callbacks run for its failure are not undone.
+    pub(crate) fn record(
+        &mut self,
+        job_id: &str,
+        outcome: JobOutcome,
+        previous: Option<JobOutcome>,
+    ) -> Settled {
+        let mut settled = Settled::default();
+        if previous == Some(JobOutcome::Failed) {
+            self.failed_jobs = self.failed_jobs.saturating_sub(1);
+            self.failed_job_ids.retain(|id| id != job_id);
+            return settled;
+        }
+        self.pending_jobs = self.pending_jobs.saturating_sub(1);
+
+        if outcome == JobOutcome::Failed {
+            self.failed_jobs += 1;
+            self.failed_job_ids.push(job_id.to_string());
+            if self.failed_jobs == 1 {
+                settled.jobs.extend(self.callbacks.catch.clone());
+                if !self.allow_failures {
+                    self.cancelled_at = Some(Utc::now());
+                    settled
+                        .jobs
+                        .extend(self.chain_catch.clone().map(|job| *job));
+                }
+            }
+        }
+
+        if self.pending_jobs == 0 && !self.is_finished() {
+            settled.merge(self.finish());
+        }
+        settled
+    }
+
+    /// Mark the batch finished, returning the callbacks due
+    pub(crate) fn finish(&mut self) -> Settled {
+        let mut settled = Settled::default();
+        self.finished_at = Some(Utc::now());
+        if !self.has_failures() || self.allow_failures {
+            settled.jobs.extend(self.callbacks.then.clone());
+            if !self.continuation.is_empty() {
+                settled.continuation = Some((
+                    std::mem::take(&mut self.continuation),
+                    self.chain_catch.clone(),
+                ));
+            }
+        }
+        settled.jobs.extend(self.callbacks.finally.clone());
+        settled
+    }
+}
+
+impl Settled {
+    fn merge(&mut self, other: Settled) {
+        self.jobs.extend(other.jobs);
+        if other.continuation.is_some() {
+            self.continuation = other.continuation;
+        }
+    }
+}
+
+/// Where batch state is kept
+///
+/// Shared by the dispatching application and all workers, so use a
+/// database backed store unless everything runs in one process.
+#[async_trait]
+pub trait BatchStore: Send + Sync {
+    /// Save a new batch
+    async fn create(&self, batch: &BatchRecord) -> QueueResult<()>;
+
+    /// Look up a batch
+    async fn find(&self, id: &str) -> QueueResult<Option<BatchRecord>>;
+
+    /// Apply `update` to the batch and save it
+    ///
+    /// Implementations lock the batch meanwhile, so concurrent workers
+    /// update it one after the other.
+    async fn update(
+        &self,
+        id: &str,
+        update: &mut (dyn for<'b> FnMut(&'b mut BatchRecord) + Send),
+    ) -> QueueResult<()>;
+
+    /// Record the outcome of job `job_id` of batch `id`, then apply
+    /// `update` to the batch with the outcome recorded before
+    ///
+    /// Outcomes are kept per job, apart from the batch, in the same
+    /// transaction. Nothing happens when `outcome` doesn't
+    /// [replace](JobOutcome::replaces) the recorded one.
+    async fn record(
+        &self,
+        id: &str,
+        job_id: &str,
+        outcome: JobOutcome,
+        update: &mut (dyn for<'b> FnMut(&'b mut BatchRecord, Option<JobOutcome>) + Send),
+    ) -> QueueResult<()>;
+
+    /// Record the outcome of job `job_id`, not part of a batch, returning
+    /// whether it [replaced](JobOutcome::replaces) the recorded one
+    ///
+    /// Workers record jobs continuing a chain, so the chain moves on once
+    /// when such a job is delivered twice.
+    async fn record_job(&self, job_id: &str, outcome: JobOutcome) -> QueueResult<bool>;
+}
+
+/// In-process batch store
+#[derive(Default)]
+pub struct MemoryBatchStore {
+    batches: Mutex<HashMap<String, BatchRecord>>,
+    outcomes: Mutex<HashMap<String, JobOutcome>>,
+}
+
+impl MemoryBatchStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl BatchStore for MemoryBatchStore {
+    async fn create(&self, batch: &BatchRecord) -> QueueResult<()> {
+        self.batches
+            .lock()
+            .await
+            .insert(batch.id.clone(), batch.clone());
+        Ok(())
+    }
+
+    async fn find(&self, id: &str) -> QueueResult<Option<BatchRecord>> {
+        Ok(self.batches.lock().await.get(id).cloned())
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        update: &mut (dyn for<'b> FnMut(&'b mut BatchRecord) + Send),
+    ) -> QueueResult<()> {
+        let mut batches = self.batches.lock().await;
+        let batch = batches
+            .get_mut(id)
+            .ok_or_else(|| QueueError::BatchNotFound(id.to_string()))?;
+        update(batch);
+        Ok(())
+    }
+
+    async fn record(
+        &self,
+        id: &str,
+        job_id: &str,
+        outcome: JobOutcome,
+        update: &mut (dyn for<'b> FnMut(&'b mut BatchRecord, Option<JobOutcome>) + Send),
+    ) -> QueueResult<()> {
+        let mut batches = self.batches.lock().await;
+        let batch = batches
+            .get_mut(id)
+            .ok_or_else(|| QueueError::BatchNotFound(id.to_string()))?;
+        let mut outcomes = self.outcomes.lock().await;
+        let previous = outcomes.get(job_id).copied();
+        if outcome.replaces(previous) {
+            outcomes.insert(job_id.to_string(), outcome);
+            update(batch, previous);
+        }
+        Ok(())
+    }
+
+    async fn record_job(&self, job_id: &str, outcome: JobOutcome) -> QueueResult<bool> {
+        let mut outcomes = self.outcomes.lock().await;
+        let replaced = outcome.replaces(outcomes.get(job_id).copied());
+        if replaced {
+            outcomes.insert(job_id.to_string(), outcome);
+        }
+        Ok(replaced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job::Job;
+
+    #[derive(Serialize, Deserialize)]
+    struct Import;
+
+    #[async_trait]
+    impl Job for Import {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "import"
+        }
+    }
+
+    fn job() -> JobMetadata {
+        JobMetadata::new(&Import).unwrap()
+    }
+
+    async fn record(
+        store: &MemoryBatchStore,
+        batch_id: &str,
+        job_id: &str,
+        outcome: JobOutcome,
+    ) -> Settled {
+        let mut settled = Settled::default();
+        store
+            .record(batch_id, job_id, outcome, &mut |batch, previous| {
+                settled = batch.record(job_id, outcome, previous);
+            })
+            .await
+            .unwrap();
+        settled
+    }
+
+    #[tokio::test]
+    async fn test_record_counts_each_job_once() {
+        let store = MemoryBatchStore::new();
+        let (batch, jobs) = Batch::new("import")
+            .job(job())
+            .job(job())
+            .job(job())
+            .allow_failures()
+            .then(job())
+            .into_record(Vec::new(), None);
+        store.create(&batch).await.unwrap();
+        let id = &batch.id;
+
+        let settled = record(&store, id, &jobs[0].id, JobOutcome::Succeeded).await;
+        assert!(settled.jobs.is_empty());
+        // Delivered again: not counted twice
+        let settled = record(&store, id, &jobs[0].id, JobOutcome::Succeeded).await;
+        assert!(settled.jobs.is_empty());
+        record(&store, id, &jobs[1].id, JobOutcome::Failed).await;
+        record(&store, id, &jobs[1].id, JobOutcome::Failed).await;
+        let batch = store.find(id).await.unwrap().unwrap();
+        assert_eq!(batch.pending_jobs, 1);
+        assert_eq!(batch.failed_jobs, 1);
+        assert_eq!(batch.failed_job_ids, [jobs[1].id.clone()]);
+        assert!(!batch.is_finished());
+
+        let settled = record(&store, id, &jobs[2].id, JobOutcome::Succeeded).await;
+        assert!(store.find(id).await.unwrap().unwrap().is_finished());
+        assert_eq!(settled.jobs.len(), 1);
+        let settled = record(&store, id, &jobs[2].id, JobOutcome::Succeeded).await;
+        assert!(settled.jobs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_success_replaces_failure() {
+        let store = MemoryBatchStore::new();
+        let (batch, jobs) = Batch::new("import")
+            .job(job())
+            .job(job())
+            .into_record(Vec::new(), None);
+        store.create(&batch).await.unwrap();
+
+        record(&store, &batch.id, &jobs[0].id, JobOutcome::Failed).await;
+        // Retried by hand after failing for good
+        record(&store, &batch.id, &jobs[0].id, JobOutcome::Succeeded).await;
+        record(&store, &batch.id, &jobs[0].id, JobOutcome::Failed).await;
+        let batch = store.find(&batch.id).await.unwrap().unwrap();
+        assert_eq!((batch.pending_jobs, batch.failed_jobs), (1, 0));
+        assert!(batch.failed_job_ids.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_record_job_outside_batch() {
+        let store = MemoryBatchStore::new();
+        assert!(store.record_job("a", JobOutcome::Succeeded).await.unwrap());
+        assert!(!store.record_job("a", JobOutcome::Succeeded).await.unwrap());
+        assert!(store.record_job("b", JobOutcome::Failed).await.unwrap());
+        assert!(store.record_job("b", JobOutcome::Succeeded).await.unwrap());
+        assert!(!store.record_job("b", JobOutcome::Failed).await.unwrap());
+    }
+}
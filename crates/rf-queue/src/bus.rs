@@ -0,0 +1,745 @@
+//! Dispatching jobs, chains, batches and sagas
+
+use crate::batch::{Batch, BatchRecord, BatchStore, JobOutcome, Settled};
+use crate::chain::{Chain, ChainStep};
+use crate::error::{QueueError, QueueResult};
+use crate::job::JobMetadata;
+use crate::queue::Queue;
+use crate::saga::{Saga, SagaRecord, SagaState, SagaStore};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Dispatches jobs, [`Chain`]s, [`Batch`]es and [`Saga`]s to a queue
+///
+/// Workers continue chains and settle batches when they are given the
+/// same [`BatchStore`] with [`Worker::batches`](crate::Worker::batches),
+/// and move sagas along with the same [`SagaStore`] given to
+/// [`Worker::sagas`](crate::Worker::sagas).
+///
+/// ```no_run
+/// use rf_queue::{Batch, Bus, JobMetadata, MemoryBatchStore, MemoryQueue, Queue, Worker};
+/// use std::sync::Arc;
+/// # use rf_queue::{Job, QueueError};
+/// # use serde::{Deserialize, Serialize};
+/// # #[derive(Serialize, Deserialize)]
+/// # struct ResizeImage { id: u64 }
+/// # #[async_trait::async_trait]
+/// # impl Job for ResizeImage {
+/// #     async fn handle(&self) -> Result<(), QueueError> { Ok(()) }
+/// #     fn job_type(&self) -> &'static str { "resize_image" }
+/// # }
+///
+/// # async fn example() -> Result<(), QueueError> {
+/// let queue: Arc<dyn Queue> = Arc::new(MemoryQueue::new());
+/// let batches = Arc::new(MemoryBatchStore::new());
+/// let bus = Bus::new(Arc::clone(&queue)).batches(batches.clone());
+///
+/// let batch = bus
+///     .batch(
+///         Batch::new("gallery")
+///             .job(JobMetadata::new(&ResizeImage { id: 1 })?)
+///             .job(JobMetadata::new(&ResizeImage { id: 2 })?),
+///     )
+///     .await?;
+///
+/// // Later, e.g. in a status endpoint
+/// let progress = bus.find_batch(&batch.id).await?.progress();
+///
+/// let worker = Worker::new(queue).batches(batches).register::<ResizeImage>();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Bus {
+    queue: Arc<dyn Queue>,
+    batches: Option<Arc<dyn BatchStore>>,
+    sagas: Option<Arc<dyn SagaStore>>,
+}
+
+impl Bus {
+    pub fn new(queue: Arc<dyn Queue>) -> Self {
+        Self {
+            queue,
+            batches: None,
+            sagas: None,
+        }
+    }
+
+    /// Keep batch state in `store`, needed to dispatch batches
+    pub fn batches(mut self, store: Arc<dyn BatchStore>) -> Self {
+        self.batches = Some(store);
+        self
+    }
+
+    /// Keep saga state in `store`, needed to dispatch sagas
+    pub fn sagas(mut self, store: Arc<dyn SagaStore>) -> Self {
+        self.sagas = Some(store);
+        self
+    }
+
+    /// Push a single job
+    pub async fn dispatch(&self, metadata: JobMetadata) -> QueueResult<String> {
+        self.queue.push(metadata).await
+    }
+
+    /// Dispatch the first step of `chain`
+    pub async fn chain(&self, chain: Chain) -> QueueResult<()> {
+        self.continue_chain(chain.steps, chain.catch).await
+    }
+
+    /// Store and dispatch `batch`
+    pub async fn batch(&self, batch: Batch) -> QueueResult<BatchRecord> {
+        self.dispatch_batch(batch, Vec::new(), None).await
+    }
+
+    /// Current state of batch `id`
+    pub async fn find_batch(&self, id: &str) -> QueueResult<BatchRecord> {
+        self.store()?
+            .find(id)
+            .await?
+            .ok_or_else(|| QueueError::BatchNotFound(id.to_string()))
+    }
+
+    /// Cancel batch `id`; its jobs that didn't start yet are skipped
+    pub async fn cancel_batch(&self, id: &str) -> QueueResult<()> {
+        self.store()?
+            .update(id, &mut |batch| {
+                batch.cancelled_at.get_or_insert_with(chrono::Utc::now);
+            })
+            .await
+    }
+
+    /// Store and start `saga`
+    pub async fn saga(&self, saga: Saga) -> QueueResult<SagaRecord> {
+        let store = self.saga_store()?;
+        let mut record = saga.into_record();
+        let job = record.start();
+
+        // Stored first, so workers find the saga of the first step
+        store.create(&record).await?;
+        if let Some(job) = job {
+            self.queue.push(job).await?;
+        }
+        tracing::info!(saga_id = %record.id, saga = %record.name, "Saga started");
+        Ok(record)
+    }
+
+    /// Current state of saga `id`
+    pub async fn find_saga(&self, id: &str) -> QueueResult<SagaRecord> {
+        self.saga_store()?
+            .find(id)
+            .await?
+            .ok_or_else(|| QueueError::SagaNotFound(id.to_string()))
+    }
+
+    /// Sagas in `state`, or all of them, oldest first
+    pub async fn list_sagas(&self, state: Option<SagaState>) -> QueueResult<Vec<SagaRecord>> {
+        self.saga_store()?.list(state).await
+    }
+
+    /// Dispatch again the pending job of sagas that made no progress for
+    /// `stale_after`, returning how many were resumed
+    ///
+    /// Covers jobs lost in a crash between saving a saga and pushing its
+    /// next job. Run it on startup or periodically, with `stale_after`
+    /// well above the time a step takes, as the step may still be running.
+    pub async fn resume_sagas(&self, stale_after: Duration) -> QueueResult<usize> {
+        let store = self.saga_store()?;
+        let cutoff = chrono::Utc::now()
+            - chrono::Duration::from_std(stale_after)
+                .map_err(|e| QueueError::ConfigError(format!("Invalid duration: {}", e)))?;
+
+        let mut resumed = 0;
+        for state in [SagaState::Running, SagaState::Compensating] {
+            for saga in store.list(Some(state)).await? {
+                if saga.updated_at > cutoff {
+                    continue;
+                }
+                let mut job = None;
+                store
+                    .update(&saga.id, &mut |saga| {
+                        // Checked again, another process may have resumed it
+                        if !saga.is_finished() && saga.updated_at <= cutoff {
+                            job = saga.redispatch();
+                        }
+                    })
+                    .await?;
+                if let Some(job) = job {
+                    tracing::info!(saga_id = %saga.id, job_id = %job.id, "Resuming saga");
+                    self.queue.push(job).await?;
+                    resumed += 1;
+                }
+            }
+        }
+        Ok(resumed)
+    }
+
+    fn store(&self) -> QueueResult<&Arc<dyn BatchStore>> {
+        self.batches
+            .as_ref()
+            .ok_or_else(|| QueueError::ConfigError("No batch store configured".to_string()))
+    }
+
+    fn saga_store(&self) -> QueueResult<&Arc<dyn SagaStore>> {
+        self.sagas
+            .as_ref()
+            .ok_or_else(|| QueueError::ConfigError("No saga store configured".to_string()))
+    }
+
+    async fn continue_chain(
+        &self,
+        mut steps: Vec<ChainStep>,
+        catch: Option<Box<JobMetadata>>,
+    ) -> QueueResult<()> {
+        if steps.is_empty() {
+            return Ok(());
+        }
+        let rest = steps.split_off(1);
+        match steps.remove(0) {
+            ChainStep::Job { mut job } => {
+                job.chain = rest;
+                job.chain_catch = catch;
+                self.queue.push(*job).await?;
+            }
+            ChainStep::Batch { batch } => {
+                self.dispatch_batch(*batch, rest, catch).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn dispatch_batch(
+        &self,
+        batch: Batch,
+        continuation: Vec<ChainStep>,
+        chain_catch: Option<Box<JobMetadata>>,
+    ) -> QueueResult<BatchRecord> {
+        let store = self.store()?;
+        let (mut record, jobs) = batch.into_record(continuation, chain_catch);
+
+        if jobs.is_empty() {
+            let settled = record.finish();
+            store.create(&record).await?;
+            self.settle(settled).await?;
+            return Ok(record);
+        }
+
+        // Stored first, so workers find the batch of the first job
+        store.create(&record).await?;
+        for mut job in jobs {
+            job.batch_id = Some(record.id.clone());
+            self.queue.push(job).await?;
+        }
+        tracing::info!(batch_id = %record.id, jobs = record.total_jobs, "Batch dispatched");
+        Ok(record)
+    }
+
+    /// Dispatch the callbacks due after a batch update
+    async fn settle(&self, settled: Settled) -> QueueResult<()> {
+        for job in settled.jobs {
+            self.queue.push(job).await?;
+        }
+        if let Some((steps, catch)) = settled.continuation {
+            Box::pin(self.continue_chain(steps, catch)).await?;
+        }
+        Ok(())
+    }
+
+    /// Record the outcome of a job, returning whether it was recorded
+    /// rather than ignored as delivered twice
+    ///
+    /// Jobs outside a batch are only recorded when they continue a chain
+    /// and a batch store is configured; otherwise their outcome counts.
+    async fn record(&self, metadata: &JobMetadata, outcome: JobOutcome) -> QueueResult<bool> {
+        let Some(batch_id) = &metadata.batch_id else {
+            return match &self.batches {
+                Some(store) if !metadata.chain.is_empty() => {
+                    store.record_job(&metadata.id, outcome).await
+                }
+                _ => Ok(true),
+            };
+        };
+
+        let mut recorded = false;
+        let mut settled = Settled::default();
+        self.store()?
+            .record(batch_id, &metadata.id, outcome, &mut |batch, previous| {
+                recorded = true;
+                settled = batch.record(&metadata.id, outcome, previous);
+            })
+            .await?;
+        self.settle(settled).await?;
+        Ok(recorded)
+    }
+
+    /// Record the outcome of a saga job and dispatch the job due next
+    async fn record_saga(
+        &self,
+        saga_id: &str,
+        job_id: &str,
+        outcome: Result<(), &str>,
+    ) -> QueueResult<()> {
+        let mut next = None;
+        let mut finished = None;
+        self.saga_store()?
+            .update(saga_id, &mut |saga| {
+                let before = saga.state;
+                next = saga.record(job_id, outcome);
+                if saga.state != before && saga.is_finished() {
+                    finished = Some(saga.state);
+                }
+            })
+            .await?;
+        match finished {
+            Some(SagaState::Completed) => tracing::info!(saga_id = %saga_id, "Saga completed"),
+            Some(state) => {
+                tracing::warn!(saga_id = %saga_id, state = state.as_str(), "Saga did not complete")
+            }
+            None => {}
+        }
+        if let Some(job) = next {
+            self.queue.push(job).await?;
+        }
+        Ok(())
+    }
+
+    /// Whether `metadata` belongs to a cancelled batch and must not run
+    pub(crate) async fn is_cancelled(&self, metadata: &JobMetadata) -> QueueResult<bool> {
+        let Some(batch_id) = &metadata.batch_id else {
+            return Ok(false);
+        };
+        Ok(self.find_batch(batch_id).await?.is_cancelled())
+    }
+
+    /// Continue the chain or batch of a job that succeeded, or was skipped
+    ///
+    /// A job delivered twice continues its chain once. Sagas ignore the
+    /// outcome of jobs they no longer wait for on their own.
+    pub(crate) async fn job_succeeded(
+        &self,
+        metadata: &JobMetadata,
+        skipped: bool,
+    ) -> QueueResult<()> {
+        let outcome = if skipped {
+            JobOutcome::Skipped
+        } else {
+            JobOutcome::Succeeded
+        };
+        if !self.record(metadata, outcome).await? {
+            tracing::debug!(job_id = %metadata.id, "Job outcome already recorded");
+            return Ok(());
+        }
+        if !skipped {
+            if let Some(saga_id) = &metadata.saga_id {
+                self.record_saga(saga_id, &metadata.id, Ok(())).await?;
+            }
+            self.continue_chain(metadata.chain.clone(), metadata.chain_catch.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Stop the chain, record the failure in the batch or compensate the
+    /// saga of a job that failed for good
+    pub(crate) async fn job_failed(&self, metadata: &JobMetadata) -> QueueResult<()> {
+        if !self.record(metadata, JobOutcome::Failed).await? {
+            tracing::debug!(job_id = %metadata.id, "Job outcome already recorded");
+            return Ok(());
+        }
+        if let Some(saga_id) = &metadata.saga_id {
+            let error = metadata.last_error.as_deref().unwrap_or("Job failed");
+            self.record_saga(saga_id, &metadata.id, Err(error)).await?;
+        }
+        if let Some(catch) = &metadata.chain_catch {
+            self.queue.push((**catch).clone()).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::MemoryBatchStore;
+    use crate::job::{Backoff, Job};
+    use crate::memory::MemoryQueue;
+    use crate::saga::{MemorySagaStore, StepState};
+    use crate::worker::Worker;
+    use async_trait::async_trait;
+    use serde::{Deserialize, Serialize};
+    use std::time::Duration;
+    use tokio::sync::Mutex;
+
+    #[derive(Serialize, Deserialize)]
+    struct Step {
+        name: String,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Job for Step {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "step"
+        }
+
+        fn max_retries(&self) -> u32 {
+            1
+        }
+
+        fn backoff(&self) -> Backoff {
+            Backoff::None
+        }
+    }
+
+    fn step(name: &str) -> JobMetadata {
+        JobMetadata::new(&Step {
+            name: name.to_string(),
+            fail: false,
+        })
+        .unwrap()
+    }
+
+    fn failing(name: &str) -> JobMetadata {
+        JobMetadata::new(&Step {
+            name: name.to_string(),
+            fail: true,
+        })
+        .unwrap()
+    }
+
+    struct Setup {
+        queue: Arc<MemoryQueue>,
+        store: Arc<MemoryBatchStore>,
+        sagas: Arc<MemorySagaStore>,
+        bus: Bus,
+    }
+
+    fn setup() -> Setup {
+        let queue = Arc::new(MemoryQueue::new());
+        let store = Arc::new(MemoryBatchStore::new());
+        let sagas = Arc::new(MemorySagaStore::new());
+        let bus = Bus::new(Arc::clone(&queue) as Arc<dyn Queue>)
+            .batches(store.clone())
+            .sagas(sagas.clone());
+        Setup {
+            queue,
+            store,
+            sagas,
+            bus,
+        }
+    }
+
+    /// Run one worker until `expected` steps ran and the queue is drained,
+    /// returning the names of the steps in order
+    async fn run(setup: &Setup, expected: usize) -> Vec<String> {
+        let ran = Arc::new(Mutex::new(Vec::new()));
+        let worker = Worker::new(Arc::clone(&setup.queue) as Arc<dyn Queue>)
+            .batches(setup.store.clone())
+            .sagas(setup.sagas.clone())
+            .poll_interval(Duration::from_millis(5))
+            .handle({
+                let ran = Arc::clone(&ran);
+                move |step: Step| {
+                    let ran = Arc::clone(&ran);
+                    Box::pin(async move {
+                        ran.lock().await.push(step.name);
+                        if step.fail {
+                            return Err(QueueError::JobFailed("boom".to_string()));
+                        }
+                        Ok(())
+                    })
+                }
+            });
+
+        let signal = {
+            let (ran, queue) = (Arc::clone(&ran), Arc::clone(&setup.queue));
+            async move {
+                while ran.lock().await.len() < expected || queue.size("default").await.unwrap() > 0
+                {
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), worker.start_with_shutdown(signal))
+            .await
+            .expect("worker should shut down")
+            .unwrap();
+        let ran = ran.lock().await.clone();
+        ran
+    }
+
+    #[tokio::test]
+    async fn test_chain_runs_in_order_and_stops_on_failure() {
+        let setup = setup();
+        setup
+            .bus
+            .chain(
+                Chain::new()
+                    .job(step("one"))
+                    .job(step("two"))
+                    .job(step("three")),
+            )
+            .await
+            .unwrap();
+        assert_eq!(run(&setup, 3).await, ["one", "two", "three"]);
+
+        setup
+            .bus
+            .chain(
+                Chain::new()
+                    .job(step("one"))
+                    .job(failing("two"))
+                    .job(step("three"))
+                    .catch(step("caught")),
+            )
+            .await
+            .unwrap();
+        assert_eq!(run(&setup, 3).await, ["one", "two", "caught"]);
+    }
+
+    #[tokio::test]
+    async fn test_redelivered_job_continues_once() {
+        let setup = setup();
+        let mut first = step("one");
+        first.chain = vec![ChainStep::Job {
+            job: Box::new(step("two")),
+        }];
+        setup.bus.job_succeeded(&first, false).await.unwrap();
+        setup.bus.job_succeeded(&first, false).await.unwrap();
+        assert_eq!(setup.queue.size("default").await.unwrap(), 1);
+        setup.queue.clear("default").await.unwrap();
+
+        setup.bus.saga(checkout(step("ship"))).await.unwrap();
+        let reserve = setup.queue.reserve("default").await.unwrap().unwrap();
+        setup.bus.job_succeeded(&reserve, false).await.unwrap();
+        setup.bus.job_succeeded(&reserve, false).await.unwrap();
+        assert_eq!(setup.queue.size("default").await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_callbacks() {
+        let setup = setup();
+        let batch = setup
+            .bus
+            .batch(
+                Batch::new("import")
+                    .job(step("a"))
+                    .job(step("b"))
+                    .then(step("then"))
+                    .catch(step("catch"))
+                    .finally(step("finally")),
+            )
+            .await
+            .unwrap();
+        assert_eq!(setup.bus.find_batch(&batch.id).await.unwrap().progress(), 0);
+
+        assert_eq!(run(&setup, 4).await, ["a", "b", "then", "finally"]);
+        let batch = setup.bus.find_batch(&batch.id).await.unwrap();
+        assert!(batch.is_finished());
+        assert_eq!((batch.progress(), batch.failed_jobs), (100, 0));
+    }
+
+    #[tokio::test]
+    async fn test_batch_failure_cancels_remaining_jobs() {
+        let setup = setup();
+        let batch = setup
+            .bus
+            .batch(
+                Batch::new("import")
+                    .job(failing("a"))
+                    .job(step("b"))
+                    .job(step("c"))
+                    .then(step("then"))
+                    .catch(step("catch"))
+                    .finally(step("finally")),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(run(&setup, 3).await, ["a", "catch", "finally"]);
+        let batch = setup.bus.find_batch(&batch.id).await.unwrap();
+        assert!(batch.is_cancelled() && batch.is_finished());
+        assert_eq!(batch.failed_job_ids.len(), 1);
+        assert_eq!(setup.queue.failed("default").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_allowing_failures() {
+        let setup = setup();
+        setup
+            .bus
+            .batch(
+                Batch::new("import")
+                    .job(failing("a"))
+                    .job(step("b"))
+                    .then(step("then"))
+                    .catch(step("catch"))
+                    .allow_failures(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(run(&setup, 4).await, ["a", "b", "catch", "then"]);
+    }
+
+    #[tokio::test]
+    async fn test_chain_waits_for_batch() {
+        let setup = setup();
+        setup
+            .bus
+            .chain(
+                Chain::new()
+                    .job(step("fetch"))
+                    .batch(Batch::new("resize").job(step("small")).job(step("large")))
+                    .batch(Batch::new("empty"))
+                    .job(step("publish")),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(run(&setup, 4).await, ["fetch", "small", "large", "publish"]);
+    }
+
+    fn checkout(ship: JobMetadata) -> Saga {
+        Saga::new("checkout")
+            .step("reserve", step("reserve"))
+            .compensate(step("release"))
+            .step("notify", step("notify"))
+            .step("charge", step("charge"))
+            .compensate(failing("refund"))
+            .step("ship", ship)
+    }
+
+    #[tokio::test]
+    async fn test_saga_completes() {
+        let setup = setup();
+        let saga = setup.bus.saga(checkout(step("ship"))).await.unwrap();
+        assert_eq!(
+            setup.bus.find_saga(&saga.id).await.unwrap().state,
+            SagaState::Running
+        );
+
+        assert_eq!(
+            run(&setup, 4).await,
+            ["reserve", "notify", "charge", "ship"]
+        );
+        let saga = setup.bus.find_saga(&saga.id).await.unwrap();
+        assert_eq!(saga.state, SagaState::Completed);
+        assert!(saga.finished_at.is_some() && saga.current_step().is_none());
+        assert_eq!(saga.completed_steps(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_saga_compensates_in_reverse_order() {
+        let setup = setup();
+        let saga = setup
+            .bus
+            .saga(
+                Saga::new("checkout")
+                    .step("reserve", step("reserve"))
+                    .compensate(step("release"))
+                    .step("notify", step("notify"))
+                    .step("charge", step("charge"))
+                    .compensate(step("refund"))
+                    .step("ship", failing("ship")),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            run(&setup, 6).await,
+            ["reserve", "notify", "charge", "ship", "refund", "release"]
+        );
+        let saga = setup.bus.find_saga(&saga.id).await.unwrap();
+        assert_eq!(saga.state, SagaState::Compensated);
+        assert_eq!(
+            saga.error.as_deref(),
+            Some("ship: Job execution failed: boom")
+        );
+        let states: Vec<_> = saga.steps.iter().map(|step| step.state).collect();
+        assert_eq!(
+            states,
+            [
+                StepState::Compensated,
+                StepState::Completed,
+                StepState::Compensated,
+                StepState::Failed
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_saga_fails_when_compensation_fails() {
+        let setup = setup();
+        let saga = setup.bus.saga(checkout(failing("ship"))).await.unwrap();
+
+        assert_eq!(
+            run(&setup, 5).await,
+            ["reserve", "notify", "charge", "ship", "refund"]
+        );
+        let saga = setup.bus.find_saga(&saga.id).await.unwrap();
+        assert_eq!(saga.state, SagaState::Failed);
+        assert_eq!(saga.steps[2].state, StepState::CompensationFailed);
+        assert_eq!(saga.steps[0].state, StepState::Completed);
+        assert_eq!(
+            setup
+                .bus
+                .list_sagas(Some(SagaState::Failed))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume_sagas_dispatches_lost_jobs() {
+        let setup = setup();
+        let saga = setup.bus.saga(checkout(step("ship"))).await.unwrap();
+
+        // The first job is lost, e.g. the process crashed before pushing it
+        setup.queue.clear("default").await.unwrap();
+        assert_eq!(
+            setup
+                .bus
+                .resume_sagas(Duration::from_secs(60))
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(setup.bus.resume_sagas(Duration::ZERO).await.unwrap(), 1);
+
+        assert_eq!(
+            run(&setup, 4).await,
+            ["reserve", "notify", "charge", "ship"]
+        );
+        let saga = setup.bus.find_saga(&saga.id).await.unwrap();
+        assert_eq!(saga.state, SagaState::Completed);
+        assert_eq!(setup.bus.resume_sagas(Duration::ZERO).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_batch_requires_store() {
+        let bus = Bus::new(Arc::new(MemoryQueue::new()));
+        assert!(matches!(
+            bus.batch(Batch::new("import").job(step("a"))).await,
+            Err(QueueError::ConfigError(_))
+        ));
+        assert!(matches!(
+            setup().bus.cancel_batch("unknown").await,
+            Err(QueueError::BatchNotFound(_))
+        ));
+        assert!(matches!(
+            bus.saga(Saga::new("checkout")).await,
+            Err(QueueError::ConfigError(_))
+        ));
+        assert!(matches!(
+            setup().bus.find_saga("unknown").await,
+            Err(QueueError::SagaNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,80 @@
+//! Job chains
+
+use crate::batch::Batch;
+use crate::job::JobMetadata;
+use serde::{Deserialize, Serialize};
+
+/// Jobs run one after the other, each once the previous one succeeded
+///
+/// The rest of the chain travels with the running job, so a chain survives
+/// restarts. When a job fails for good the chain stops and `catch` runs.
+/// A [`Batch`] step fans out into parallel jobs; the chain continues once
+/// the batch finished without failures.
+///
+/// ```
+/// use rf_queue::{Batch, Chain, JobMetadata};
+/// # use rf_queue::{Job, QueueError};
+/// # use serde::{Deserialize, Serialize};
+/// # #[derive(Serialize, Deserialize)]
+/// # struct Step(String);
+/// # #[async_trait::async_trait]
+/// # impl Job for Step {
+/// #     async fn handle(&self) -> Result<(), QueueError> { Ok(()) }
+/// #     fn job_type(&self) -> &'static str { "step" }
+/// # }
+///
+/// # fn example() -> Result<(), QueueError> {
+/// # let step = |name: &str| JobMetadata::new(&Step(name.to_string()));
+/// let chain = Chain::new()
+///     .job(step("download")?)
+///     .batch(
+///         Batch::new("thumbnails")
+///             .job(step("small")?)
+///             .job(step("large")?),
+///     )
+///     .job(step("publish")?)
+///     .catch(step("notify failure")?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Chain {
+    pub(crate) steps: Vec<ChainStep>,
+    pub(crate) catch: Option<Box<JobMetadata>>,
+}
+
+/// Step of a [`Chain`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChainStep {
+    Job { job: Box<JobMetadata> },
+    Batch { batch: Box<Batch> },
+}
+
+impl Chain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `metadata` next
+    pub fn job(mut self, metadata: JobMetadata) -> Self {
+        self.steps.push(ChainStep::Job {
+            job: Box::new(metadata),
+        });
+        self
+    }
+
+    /// Run the jobs of `batch` next, in parallel
+    pub fn batch(mut self, batch: Batch) -> Self {
+        self.steps.push(ChainStep::Batch {
+            batch: Box::new(batch),
+        });
+        self
+    }
+
+    /// Run `metadata` when a job of the chain fails for good
+    pub fn catch(mut self, metadata: JobMetadata) -> Self {
+        self.catch = Some(Box::new(metadata));
+        self
+    }
+}
@@ -23,6 +23,12 @@ pub enum QueueError {
     #[error("Job not found: {0}")]
     JobNotFound(String),
 
+    #[error("Batch not found: {0}")]
+    BatchNotFound(String),
+
+    #[error("Saga not found: {0}")]
+    SagaNotFound(String),
+
     #[error("Worker error: {0}")]
     WorkerError(String),
 
@@ -30,5 +36,19 @@ pub enum QueueError {
     ConfigError(String),
 }
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(QueueError, {
+    Self::JobNotFound(_) => (NOT_FOUND, "queue.job_not_found"),
+    Self::BatchNotFound(_) => (NOT_FOUND, "queue.batch_not_found"),
+    Self::SagaNotFound(_) => (NOT_FOUND, "queue.saga_not_found"),
+    Self::JobFailed(_) => (INTERNAL_SERVER_ERROR, "queue.job_failed"),
+    Self::SerializationError(_) => (INTERNAL_SERVER_ERROR, "queue.serialization"),
+    Self::DeserializationError(_) => (INTERNAL_SERVER_ERROR, "queue.deserialization"),
+    Self::BackendError(_) => (INTERNAL_SERVER_ERROR, "queue.backend"),
+    Self::Timeout(_) => (INTERNAL_SERVER_ERROR, "queue.timeout"),
+    Self::WorkerError(_) => (INTERNAL_SERVER_ERROR, "queue.worker"),
+    Self::ConfigError(_) => (INTERNAL_SERVER_ERROR, "queue.config"),
+});
+
 /// Result type for queue operations
 pub type QueueResult<T> = Result<T, QueueError>;
@@ -0,0 +1,269 @@
+//! Failed-job store
+
+use crate::error::{QueueError, QueueResult};
+use crate::job::JobMetadata;
+use crate::queue::Queue;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// A job that used up its retries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedJob {
+    pub id: String,
+    pub queue: String,
+    pub job_type: String,
+    /// The job as last attempted, payload included
+    pub metadata: JobMetadata,
+    /// Error of the last attempt
+    pub exception: String,
+    pub attempts: u32,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl FailedJob {
+    pub fn new(metadata: JobMetadata, exception: impl Into<String>) -> Self {
+        Self {
+            id: metadata.id.clone(),
+            queue: metadata.queue.clone(),
+            job_type: metadata.job_type.clone(),
+            attempts: metadata.attempts,
+            exception: exception.into(),
+            failed_at: Utc::now(),
+            metadata,
+        }
+    }
+}
+
+/// Where workers keep jobs that failed for good
+///
+/// Give it to workers with [`Worker::failed_jobs`](crate::Worker::failed_jobs)
+/// and jobs land here instead of the queue backend's dead-letter queue.
+#[async_trait]
+pub trait FailedJobStore: Send + Sync {
+    /// Keep a failed job
+    async fn log(&self, job: FailedJob) -> QueueResult<()>;
+
+    /// All failed jobs, latest failure first
+    async fn all(&self) -> QueueResult<Vec<FailedJob>>;
+
+    /// Look up a failed job
+    async fn find(&self, id: &str) -> QueueResult<Option<FailedJob>>;
+
+    /// Delete a failed job, returning whether it existed
+    async fn forget(&self, id: &str) -> QueueResult<bool>;
+
+    /// Delete the jobs that failed before `before`, returning how many
+    async fn prune(&self, before: DateTime<Utc>) -> QueueResult<usize>;
+}
+
+/// In-process failed-job store
+#[derive(Default)]
+pub struct MemoryFailedJobStore {
+    jobs: Mutex<Vec<FailedJob>>,
+}
+
+impl MemoryFailedJobStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl FailedJobStore for MemoryFailedJobStore {
+    async fn log(&self, job: FailedJob) -> QueueResult<()> {
+        let mut jobs = self.jobs.lock().await;
+        jobs.retain(|failed| failed.id != job.id);
+        jobs.push(job);
+        Ok(())
+    }
+
+    async fn all(&self) -> QueueResult<Vec<FailedJob>> {
+        let mut jobs = self.jobs.lock().await.clone();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.failed_at));
+        Ok(jobs)
+    }
+
+    async fn find(&self, id: &str) -> QueueResult<Option<FailedJob>> {
+        let jobs = self.jobs.lock().await;
+        Ok(jobs.iter().find(|job| job.id == id).cloned())
+    }
+
+    async fn forget(&self, id: &str) -> QueueResult<bool> {
+        let mut jobs = self.jobs.lock().await;
+        let count = jobs.len();
+        jobs.retain(|job| job.id != id);
+        Ok(jobs.len() < count)
+    }
+
+    async fn prune(&self, before: DateTime<Utc>) -> QueueResult<usize> {
+        let mut jobs = self.jobs.lock().await;
+        let count = jobs.len();
+        jobs.retain(|job| job.failed_at >= before);
+        Ok(count - jobs.len())
+    }
+}
+
+/// Lists, retries and prunes the jobs of a [`FailedJobStore`]
+///
+/// ```no_run
+/// use rf_queue::{FailedJobs, MemoryFailedJobStore, MemoryQueue};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// # async fn example() -> rf_queue::QueueResult<()> {
+/// let failed = FailedJobs::new(
+///     Arc::new(MemoryFailedJobStore::new()),
+///     Arc::new(MemoryQueue::new()),
+/// );
+/// for job in failed.list().await? {
+///     println!("{} {}: {}", job.id, job.job_type, job.exception);
+/// }
+/// failed.retry_all().await?;
+/// failed.prune(Duration::from_secs(30 * 24 * 60 * 60)).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct FailedJobs {
+    store: Arc<dyn FailedJobStore>,
+    queue: Arc<dyn Queue>,
+}
+
+impl FailedJobs {
+    /// Manage the jobs of `store`, retrying them on `queue`
+    pub fn new(store: Arc<dyn FailedJobStore>, queue: Arc<dyn Queue>) -> Self {
+        Self { store, queue }
+    }
+
+    /// All failed jobs, latest failure first
+    pub async fn list(&self) -> QueueResult<Vec<FailedJob>> {
+        self.store.all().await
+    }
+
+    /// Look up failed job `id`
+    pub async fn find(&self, id: &str) -> QueueResult<FailedJob> {
+        self.store
+            .find(id)
+            .await?
+            .ok_or_else(|| QueueError::JobNotFound(id.to_string()))
+    }
+
+    /// Push failed job `id` back to its queue with its attempts reset
+    pub async fn retry(&self, id: &str) -> QueueResult<()> {
+        let mut metadata = self.find(id).await?.metadata;
+        metadata.reset();
+        self.queue.push(metadata).await?;
+        self.store.forget(id).await?;
+        tracing::info!(job_id = %id, "Failed job pushed back to the queue");
+        Ok(())
+    }
+
+    /// Retry every failed job, returning how many were pushed
+    pub async fn retry_all(&self) -> QueueResult<usize> {
+        let jobs = self.list().await?;
+        for job in &jobs {
+            self.retry(&job.id).await?;
+        }
+        Ok(jobs.len())
+    }
+
+    /// Delete failed job `id`
+    pub async fn forget(&self, id: &str) -> QueueResult<()> {
+        match self.store.forget(id).await? {
+            true => Ok(()),
+            false => Err(QueueError::JobNotFound(id.to_string())),
+        }
+    }
+
+    /// Delete the jobs that failed more than `older_than` ago, returning
+    /// how many
+    pub async fn prune(&self, older_than: Duration) -> QueueResult<usize> {
+        let older_than = chrono::Duration::from_std(older_than)
+            .map_err(|e| QueueError::ConfigError(format!("Invalid age: {}", e)))?;
+        self.store.prune(Utc::now() - older_than).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job::Job;
+    use crate::memory::MemoryQueue;
+
+    #[derive(Serialize, Deserialize)]
+    struct Charge {
+        order: u32,
+    }
+
+    #[async_trait]
+    impl Job for Charge {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "charge"
+        }
+    }
+
+    fn failed_job(order: u32, age: chrono::Duration) -> FailedJob {
+        let mut metadata = JobMetadata::new(&Charge { order }).unwrap();
+        metadata.attempts = 3;
+        let mut job = FailedJob::new(metadata, "Card declined");
+        job.failed_at -= age;
+        job
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        let store = Arc::new(MemoryFailedJobStore::new());
+        let queue = Arc::new(MemoryQueue::new());
+        let failed = FailedJobs::new(store.clone(), queue.clone());
+
+        let job = failed_job(1, chrono::Duration::zero());
+        let id = job.id.clone();
+        store.log(job).await.unwrap();
+        assert_eq!(failed.find(&id).await.unwrap().exception, "Card declined");
+
+        failed.retry(&id).await.unwrap();
+        let retried = queue.reserve("default").await.unwrap().unwrap();
+        assert_eq!((retried.id.as_str(), retried.attempts), (id.as_str(), 1));
+        assert!(failed.list().await.unwrap().is_empty());
+        assert!(matches!(
+            failed.retry(&id).await,
+            Err(QueueError::JobNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_list_and_prune() {
+        let store = Arc::new(MemoryFailedJobStore::new());
+        let failed = FailedJobs::new(store.clone(), Arc::new(MemoryQueue::new()));
+        for (order, days) in [(1, 40), (2, 1), (3, 31)] {
+            store
+                .log(failed_job(order, chrono::Duration::days(days)))
+                .await
+                .unwrap();
+        }
+
+        let orders: Vec<_> = failed
+            .list()
+            .await
+            .unwrap()
+            .iter()
+            .map(|job| job.metadata.deserialize::<Charge>().unwrap().order)
+            .collect();
+        assert_eq!(orders, [2, 3, 1]);
+
+        let pruned = failed
+            .prune(Duration::from_secs(30 * 24 * 60 * 60))
+            .await
+            .unwrap();
+        assert_eq!(pruned, 2);
+        assert_eq!(failed.retry_all().await.unwrap(), 1);
+    }
+}
@@ -1,8 +1,10 @@
 //! Job trait and types
 
+use crate::chain::ChainStep;
 use crate::error::QueueError;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::Duration;
 
 /// Job trait for queue jobs
@@ -63,6 +65,67 @@ pub trait Job: Send + Sync + Serialize + for<'de> Deserialize<'de> {
     fn priority(&self) -> i32 {
         0
     }
+
+    /// Delay before each retry of a failed attempt
+    fn backoff(&self) -> Backoff {
+        Backoff::default()
+    }
+}
+
+/// Delay between retries of a failed job
+///
+/// The default doubles the delay after every attempt, starting at one
+/// second and capped at five minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Backoff {
+    /// Retry right away
+    None,
+    /// Wait the same time before every retry
+    Fixed { delay_secs: u64 },
+    /// Double the delay after every attempt, up to `max_secs`
+    Exponential { base_secs: u64, max_secs: u64 },
+}
+
+impl Backoff {
+    /// Wait `delay` before every retry
+    pub fn fixed(delay: Duration) -> Self {
+        Backoff::Fixed {
+            delay_secs: delay.as_secs(),
+        }
+    }
+
+    /// Wait `base`, then twice as long after every attempt, up to `max`
+    pub fn exponential(base: Duration, max: Duration) -> Self {
+        Backoff::Exponential {
+            base_secs: base.as_secs(),
+            max_secs: max.as_secs(),
+        }
+    }
+
+    /// Delay before retrying after `attempts` attempts
+    pub fn delay(&self, attempts: u32) -> Duration {
+        match *self {
+            Backoff::None => Duration::ZERO,
+            Backoff::Fixed { delay_secs } => Duration::from_secs(delay_secs),
+            Backoff::Exponential {
+                base_secs,
+                max_secs,
+            } => {
+                let factor = 2u64.saturating_pow(attempts.saturating_sub(1));
+                Duration::from_secs(base_secs.saturating_mul(factor).min(max_secs))
+            }
+        }
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff::Exponential {
+            base_secs: 1,
+            max_secs: 300,
+        }
+    }
 }
 
 /// Job metadata stored in queue
@@ -100,6 +163,31 @@ pub struct JobMetadata {
 
     /// Last error message
     pub last_error: Option<String>,
+
+    /// Delay between retries
+    #[serde(default)]
+    pub backoff: Backoff,
+
+    /// Context carried from the dispatcher to the worker, e.g. the trace
+    /// context
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    /// Batch the job belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+
+    /// Rest of the chain, dispatched once the job succeeded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chain: Vec<ChainStep>,
+
+    /// Dispatched when a job of the chain fails for good
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_catch: Option<Box<JobMetadata>>,
+
+    /// Saga the job is a step or compensation of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saga_id: Option<String>,
 }
 
 impl JobMetadata {
@@ -120,16 +208,30 @@ impl JobMetadata {
             created_at: chrono::Utc::now(),
             execute_at: None,
             last_error: None,
+            backoff: job.backoff(),
+            headers: HashMap::new(),
+            batch_id: None,
+            chain: Vec::new(),
+            chain_catch: None,
+            saga_id: None,
         })
     }
 
     /// Create delayed job metadata
     pub fn new_delayed<J: Job>(job: &J, delay: Duration) -> Result<Self, QueueError> {
         let mut metadata = Self::new(job)?;
-        metadata.execute_at = Some(chrono::Utc::now() + chrono::Duration::from_std(delay).unwrap());
+        metadata.delay(delay)?;
         Ok(metadata)
     }
 
+    /// Postpone execution until `delay` from now
+    pub fn delay(&mut self, delay: Duration) -> Result<(), QueueError> {
+        let delay = chrono::Duration::from_std(delay)
+            .map_err(|e| QueueError::ConfigError(format!("Invalid delay: {}", e)))?;
+        self.execute_at = Some(chrono::Utc::now() + delay);
+        Ok(())
+    }
+
     /// Check if job should be executed now
     pub fn should_execute(&self) -> bool {
         if let Some(execute_at) = self.execute_at {
@@ -154,6 +256,18 @@ impl JobMetadata {
         self.last_error = Some(error);
     }
 
+    /// Delay before the next retry, following the job's backoff
+    pub fn retry_delay(&self) -> Duration {
+        self.backoff.delay(self.attempts)
+    }
+
+    /// Reset attempts and error, e.g. when requeueing a dead job
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+        self.last_error = None;
+        self.execute_at = None;
+    }
+
     /// Deserialize job data
     pub fn deserialize<J: Job>(&self) -> Result<J, QueueError> {
         serde_json::from_slice(&self.data)
@@ -263,4 +377,26 @@ mod tests {
         assert_eq!(metadata.attempts, 3);
         assert!(!metadata.can_retry());
     }
+
+    #[test]
+    fn test_backoff() {
+        let backoff = Backoff::exponential(Duration::from_secs(2), Duration::from_secs(10));
+        assert_eq!(backoff.delay(1), Duration::from_secs(2));
+        assert_eq!(backoff.delay(2), Duration::from_secs(4));
+        assert_eq!(backoff.delay(3), Duration::from_secs(8));
+        assert_eq!(backoff.delay(4), Duration::from_secs(10));
+        assert_eq!(backoff.delay(100), Duration::from_secs(10));
+
+        assert_eq!(Backoff::fixed(Duration::from_secs(5)).delay(3), Duration::from_secs(5));
+        assert_eq!(Backoff::None.delay(3), Duration::ZERO);
+
+        // Metadata stored before backoffs existed
+        let job = TestJob {
+            message: "test".to_string(),
+        };
+        let mut value = serde_json::to_value(JobMetadata::new(&job).unwrap()).unwrap();
+        value.as_object_mut().unwrap().remove("backoff");
+        let metadata: JobMetadata = serde_json::from_value(value).unwrap();
+        assert_eq!(metadata.backoff, Backoff::default());
+    }
 }
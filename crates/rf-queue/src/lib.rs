@@ -5,11 +5,20 @@
 //! ## Features
 //!
 //! - **Type-Safe Jobs**: Define jobs with the `Job` trait
-//! - **Multiple Backends**: Memory (dev) and Redis (production)
-//! - **Job Retries**: Automatic retry with configurable attempts
+//! - **Multiple Backends**: Memory (dev), Redis (`redis-backend`) and
+//!   Postgres with `SKIP LOCKED` (`postgres-backend`)
+//! - **Job Retries**: Automatic retry with configurable attempts and backoff
+//! - **Timeouts**: Attempts running longer than the job's timeout fail
 //! - **Delayed Jobs**: Schedule jobs for future execution
-//! - **Worker Pool**: Concurrent job processing
+//! - **Worker Pool**: Concurrent job processing with graceful shutdown
+//! - **Dead-Letter Queue**: Inspect, requeue or forget permanently failed jobs
+//! - **Failed-Job Store**: Keep failed jobs in a database, retry or prune them
+//!   with [`FailedJobs`]
 //! - **Priority Queues**: Job prioritization support
+//! - **Chains and Batches**: Sequential and parallel workflows with callbacks
+//! - **Sagas**: Multi-step operations with compensations, resumed after a crash
+//! - **Monitoring**: Queue depths, throughput, failure rates and slowest jobs,
+//!   shown in rf-admin with the `admin` feature
 //!
 //! ## Quick Start
 //!
@@ -54,9 +63,14 @@
 //! // Start worker
 //! let worker = Worker::new(Arc::clone(&queue) as Arc<dyn Queue>)
 //!     .concurrency(5)
-//!     .handle(|job: SendEmailJob| Box::pin(async move { job.handle().await }));
-//!
-//! // worker.start().await?;
+//!     .register::<SendEmailJob>();
+//!
+//! // Run until Ctrl+C, letting running jobs finish
+//! worker
+//!     .start_with_shutdown(async {
+//!         let _ = tokio::signal::ctrl_c().await;
+//!     })
+//!     .await?;
 //! # Ok(())
 //! # }
 //! ```
@@ -85,15 +99,142 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! ## Dead-Letter Queue
+//!
+//! Jobs that fail on their last attempt are kept as failed jobs:
+//!
+//! ```no_run
+//! # use rf_queue::{MemoryQueue, Queue};
+//! # async fn example() -> Result<(), rf_queue::QueueError> {
+//! # let queue = MemoryQueue::new();
+//! for job in queue.failed("default").await? {
+//!     println!("{} failed: {:?}", job.id, job.last_error);
+//! }
+//! queue.requeue_all("default").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Chains and Batches
+//!
+//! A [`Chain`] runs jobs one after the other, a [`Batch`] runs them in
+//! parallel with `then`/`catch`/`finally` callbacks. Dispatch both with a
+//! [`Bus`]; workers sharing its [`BatchStore`] move them along:
+//!
+//! ```no_run
+//! # use rf_queue::{Job, QueueError};
+//! # use serde::{Deserialize, Serialize};
+//! # #[derive(Serialize, Deserialize)]
+//! # struct Step(String);
+//! # #[async_trait::async_trait]
+//! # impl Job for Step {
+//! #     async fn handle(&self) -> Result<(), QueueError> { Ok(()) }
+//! #     fn job_type(&self) -> &'static str { "step" }
+//! # }
+//! use rf_queue::{Batch, Bus, Chain, JobMetadata, MemoryBatchStore, MemoryQueue, Queue, Worker};
+//! use std::sync::Arc;
+//!
+//! # async fn example() -> Result<(), QueueError> {
+//! # let step = |name: &str| JobMetadata::new(&Step(name.to_string()));
+//! let queue: Arc<dyn Queue> = Arc::new(MemoryQueue::new());
+//! let batches = Arc::new(MemoryBatchStore::new());
+//! let bus = Bus::new(Arc::clone(&queue)).batches(batches.clone());
+//!
+//! bus.chain(
+//!     Chain::new()
+//!         .job(step("fetch")?)
+//!         .batch(Batch::new("resize").job(step("small")?).job(step("large")?))
+//!         .job(step("publish")?),
+//! )
+//! .await?;
+//!
+//! let worker = Worker::new(queue).batches(batches).register::<Step>();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Sagas
+//!
+//! A [`Saga`] runs steps one after the other like a chain, but when a step
+//! fails for good the completed ones are undone by their compensations, in
+//! reverse order. Its state is kept in a [`SagaStore`] for status pages and
+//! to resume sagas that stalled in a crash:
+//!
+//! ```no_run
+//! # use rf_queue::{Job, QueueError};
+//! # use serde::{Deserialize, Serialize};
+//! # #[derive(Serialize, Deserialize)]
+//! # struct Step(String);
+//! # #[async_trait::async_trait]
+//! # impl Job for Step {
+//! #     async fn handle(&self) -> Result<(), QueueError> { Ok(()) }
+//! #     fn job_type(&self) -> &'static str { "step" }
+//! # }
+//! use rf_queue::{Bus, JobMetadata, MemoryQueue, MemorySagaStore, Queue, Saga, Worker};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! # async fn example() -> Result<(), QueueError> {
+//! # let step = |name: &str| JobMetadata::new(&Step(name.to_string()));
+//! let queue: Arc<dyn Queue> = Arc::new(MemoryQueue::new());
+//! let sagas = Arc::new(MemorySagaStore::new());
+//! let bus = Bus::new(Arc::clone(&queue)).sagas(sagas.clone());
+//!
+//! let saga = bus
+//!     .saga(
+//!         Saga::new("checkout")
+//!             .step("reserve stock", step("reserve")?)
+//!             .compensate(step("release")?)
+//!             .step("charge payment", step("charge")?)
+//!             .compensate(step("refund")?)
+//!             .step("ship order", step("ship")?),
+//!     )
+//!     .await?;
+//!
+//! // On startup, continue sagas whose next job got lost
+//! bus.resume_sagas(Duration::from_secs(600)).await?;
+//!
+//! let worker = Worker::new(queue).sagas(sagas).register::<Step>();
+//!
+//! // Later, e.g. in a status endpoint
+//! let state = bus.find_saga(&saga.id).await?.state;
+//! # Ok(())
+//! # }
+//! ```
 
+#[cfg(feature = "admin")]
+mod admin;
+mod batch;
+mod bus;
+mod chain;
 mod error;
+mod failed;
 mod job;
 mod memory;
+mod monitor;
+#[cfg(feature = "postgres-backend")]
+mod postgres;
 mod queue;
+#[cfg(feature = "redis-backend")]
+mod redis;
+mod saga;
 mod worker;
 
+#[cfg(feature = "admin")]
+pub use admin::{FailedJobsResource, QueueWidget};
+pub use batch::{Batch, BatchRecord, BatchStore, JobOutcome, MemoryBatchStore};
+pub use bus::Bus;
+pub use chain::{Chain, ChainStep};
 pub use error::{QueueError, QueueResult};
-pub use job::{Job, JobMetadata};
+pub use failed::{FailedJob, FailedJobStore, FailedJobs, MemoryFailedJobStore};
+pub use job::{Backoff, Job, JobMetadata};
 pub use memory::MemoryQueue;
+pub use monitor::{JobTypeStats, QueueDepth, QueueMonitor, QueueStats, SlowJob, Throughput};
+#[cfg(feature = "postgres-backend")]
+pub use postgres::{PostgresBatchStore, PostgresFailedJobStore, PostgresQueue, PostgresSagaStore};
 pub use queue::Queue;
+#[cfg(feature = "redis-backend")]
+pub use redis::RedisQueue;
+pub use saga::{MemorySagaStore, Saga, SagaRecord, SagaState, SagaStep, SagaStore, StepState};
 pub use worker::Worker;
@@ -12,7 +12,8 @@ use tokio::sync::Mutex;
 #[derive(Clone)]
 pub struct MemoryQueue {
     queues: Arc<Mutex<HashMap<String, VecDeque<JobMetadata>>>>,
-    failed: Arc<Mutex<HashMap<String, JobMetadata>>>,
+    reserved: Arc<Mutex<HashMap<String, JobMetadata>>>,
+    failed: Arc<Mutex<Vec<JobMetadata>>>,
 }
 
 impl MemoryQueue {
@@ -20,9 +21,19 @@ impl MemoryQueue {
     pub fn new() -> Self {
         Self {
             queues: Arc::new(Mutex::new(HashMap::new())),
-            failed: Arc::new(Mutex::new(HashMap::new())),
+            reserved: Arc::new(Mutex::new(HashMap::new())),
+            failed: Arc::new(Mutex::new(Vec::new())),
         }
     }
+
+    async fn take_failed(&self, job_id: &str) -> QueueResult<JobMetadata> {
+        let mut failed = self.failed.lock().await;
+        let pos = failed
+            .iter()
+            .position(|j| j.id == job_id)
+            .ok_or_else(|| QueueError::JobNotFound(job_id.to_string()))?;
+        Ok(failed.remove(pos))
+    }
 }
 
 impl Default for MemoryQueue {
@@ -55,6 +66,10 @@ impl Queue for MemoryQueue {
             if let Some(pos) = queue_jobs.iter().position(|j| j.should_execute()) {
                 let mut metadata = queue_jobs.remove(pos).unwrap();
                 metadata.mark_attempt();
+                self.reserved
+                    .lock()
+                    .await
+                    .insert(metadata.id.clone(), metadata.clone());
                 return Ok(Some(metadata));
             }
         }
@@ -63,17 +78,22 @@ impl Queue for MemoryQueue {
     }
 
     async fn complete(&self, job_id: &str) -> QueueResult<()> {
+        self.reserved.lock().await.remove(job_id);
         tracing::debug!(job_id = %job_id, "Job completed");
         Ok(())
     }
 
     async fn fail(&self, job_id: &str, error: &str) -> QueueResult<()> {
-        // Store in failed jobs
-        let mut failed = self.failed.lock().await;
+        let mut metadata = self
+            .reserved
+            .lock()
+            .await
+            .remove(job_id)
+            .ok_or_else(|| QueueError::JobNotFound(job_id.to_string()))?;
+        metadata.mark_error(error.to_string());
+        self.failed.lock().await.push(metadata);
 
-        // We don't have the full metadata here, so just log
         tracing::warn!(job_id = %job_id, error = %error, "Job failed");
-
         Ok(())
     }
 
@@ -84,6 +104,7 @@ impl Queue for MemoryQueue {
             ));
         }
 
+        self.reserved.lock().await.remove(&metadata.id);
         self.push(metadata).await?;
         Ok(())
     }
@@ -98,6 +119,23 @@ impl Queue for MemoryQueue {
         queues.remove(queue);
         Ok(())
     }
+
+    async fn failed(&self, queue: &str) -> QueueResult<Vec<JobMetadata>> {
+        let failed = self.failed.lock().await;
+        Ok(failed.iter().filter(|j| j.queue == queue).cloned().collect())
+    }
+
+    async fn requeue(&self, job_id: &str) -> QueueResult<()> {
+        let mut metadata = self.take_failed(job_id).await?;
+        metadata.reset();
+        self.push(metadata).await?;
+        Ok(())
+    }
+
+    async fn forget(&self, job_id: &str) -> QueueResult<()> {
+        self.take_failed(job_id).await?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -166,4 +204,35 @@ mod tests {
         queue.clear("default").await.unwrap();
         assert_eq!(queue.size("default").await.unwrap(), 0);
     }
+
+    #[tokio::test]
+    async fn test_dead_letter_queue() {
+        let queue = MemoryQueue::new();
+        let job = TestJob {
+            message: "test".to_string(),
+        };
+
+        let id = queue.push(JobMetadata::new(&job).unwrap()).await.unwrap();
+        queue.reserve("default").await.unwrap().unwrap();
+        queue.fail(&id, "boom").await.unwrap();
+
+        let failed = queue.failed("default").await.unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].last_error.as_deref(), Some("boom"));
+        assert_eq!(queue.size("default").await.unwrap(), 0);
+
+        queue.requeue(&id).await.unwrap();
+        assert!(queue.failed("default").await.unwrap().is_empty());
+        let reserved = queue.reserve("default").await.unwrap().unwrap();
+        assert_eq!(reserved.attempts, 1);
+        assert_eq!(reserved.last_error, None);
+
+        queue.fail(&id, "boom").await.unwrap();
+        queue.forget(&id).await.unwrap();
+        assert!(queue.failed("default").await.unwrap().is_empty());
+        assert!(matches!(
+            queue.requeue(&id).await,
+            Err(QueueError::JobNotFound(_))
+        ));
+    }
 }
@@ -0,0 +1,363 @@
+//! Queue introspection
+
+use crate::error::QueueResult;
+use crate::job::JobMetadata;
+use crate::queue::Queue;
+use chrono::{DateTime, DurationRound, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Minutes of throughput history kept
+const HISTORY_MINUTES: usize = 60;
+
+/// Slowest jobs kept
+const SLOWEST_JOBS: usize = 10;
+
+/// Collects job metrics from workers and reports the state of the queues
+///
+/// Metrics are kept in memory by each process; give every worker of the
+/// process a clone with [`Worker::monitor`](crate::Worker::monitor).
+///
+/// ```no_run
+/// use rf_queue::{MemoryQueue, Queue, QueueMonitor, Worker};
+/// use std::sync::Arc;
+///
+/// # async fn example() -> rf_queue::QueueResult<()> {
+/// let queue: Arc<dyn Queue> = Arc::new(MemoryQueue::new());
+/// let monitor = QueueMonitor::new(Arc::clone(&queue)).queues(vec!["default".into()]);
+/// let worker = Worker::new(queue).monitor(monitor.clone());
+///
+/// let stats = monitor.stats().await?;
+/// println!("{} jobs waiting", stats.queues[0].pending);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct QueueMonitor {
+    queue: Arc<dyn Queue>,
+    queues: Vec<String>,
+    metrics: Arc<Mutex<Metrics>>,
+}
+
+/// How an attempt of a job ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Attempt {
+    Completed,
+    Retried,
+    Failed,
+}
+
+#[derive(Default)]
+struct Metrics {
+    job_types: HashMap<String, JobTypeStats>,
+    history: VecDeque<Throughput>,
+    slowest: Vec<SlowJob>,
+}
+
+/// State of the monitored queues and metrics of the jobs run so far
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueStats {
+    pub queues: Vec<QueueDepth>,
+    /// Per job type, most failures first
+    pub job_types: Vec<JobTypeStats>,
+    /// Jobs processed per minute over the last hour, oldest first
+    pub throughput: Vec<Throughput>,
+    /// Slowest attempts, slowest first
+    pub slowest: Vec<SlowJob>,
+}
+
+/// Jobs waiting in a queue and in its dead-letter queue
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueDepth {
+    pub name: String,
+    pub pending: usize,
+    pub failed: usize,
+}
+
+/// Attempts of a job type
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JobTypeStats {
+    pub job_type: String,
+    pub completed: u64,
+    /// Failed attempts that were retried
+    pub retried: u64,
+    /// Jobs that failed for good
+    pub failed: u64,
+    pub total_duration_ms: u64,
+    pub max_duration_ms: u64,
+}
+
+/// Jobs finished within a minute
+#[derive(Debug, Clone, Serialize)]
+pub struct Throughput {
+    pub minute: DateTime<Utc>,
+    pub completed: u64,
+    pub failed: u64,
+}
+
+/// A slow attempt
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowJob {
+    pub job_id: String,
+    pub job_type: String,
+    pub queue: String,
+    pub duration_ms: u64,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl JobTypeStats {
+    fn attempts(&self) -> u64 {
+        self.completed + self.retried + self.failed
+    }
+
+    /// Share of attempts that failed, retried ones included
+    pub fn failure_rate(&self) -> f64 {
+        match self.attempts() {
+            0 => 0.0,
+            attempts => (self.retried + self.failed) as f64 / attempts as f64,
+        }
+    }
+
+    pub fn average_duration_ms(&self) -> u64 {
+        self.total_duration_ms
+            .checked_div(self.attempts())
+            .unwrap_or(0)
+    }
+}
+
+impl QueueStats {
+    /// Jobs finished in the last complete minute
+    pub fn per_minute(&self) -> u64 {
+        let Ok(current) = Utc::now().duration_trunc(chrono::Duration::minutes(1)) else {
+            return 0;
+        };
+        let last = current - chrono::Duration::minutes(1);
+        self.throughput
+            .iter()
+            .rev()
+            .find(|point| point.minute == last)
+            .map(|point| point.completed + point.failed)
+            .unwrap_or(0)
+    }
+}
+
+impl QueueMonitor {
+    /// Monitor `queue`, reporting the depth of the `default` queue
+    pub fn new(queue: Arc<dyn Queue>) -> Self {
+        Self {
+            queue,
+            queues: vec!["default".to_string()],
+            metrics: Arc::default(),
+        }
+    }
+
+    /// Set the queues to report the depth of
+    pub fn queues(mut self, queues: Vec<String>) -> Self {
+        self.queues = queues;
+        self
+    }
+
+    /// The monitored queue backend
+    pub fn queue(&self) -> &Arc<dyn Queue> {
+        &self.queue
+    }
+
+    /// Current depths and metrics
+    pub async fn stats(&self) -> QueueResult<QueueStats> {
+        let mut queues = Vec::with_capacity(self.queues.len());
+        for name in &self.queues {
+            queues.push(QueueDepth {
+                name: name.clone(),
+                pending: self.queue.size(name).await?,
+                failed: self.queue.failed(name).await?.len(),
+            });
+        }
+
+        let metrics = self.metrics.lock().expect("queue metrics lock poisoned");
+        let mut job_types: Vec<_> = metrics.job_types.values().cloned().collect();
+        job_types.sort_by(|a, b| {
+            (b.failed, b.retried)
+                .cmp(&(a.failed, a.retried))
+                .then_with(|| a.job_type.cmp(&b.job_type))
+        });
+
+        Ok(QueueStats {
+            queues,
+            job_types,
+            throughput: metrics.history.iter().cloned().collect(),
+            slowest: metrics.slowest.clone(),
+        })
+    }
+
+    /// Dead jobs of all monitored queues, oldest failure first per queue
+    pub async fn failed_jobs(&self) -> QueueResult<Vec<JobMetadata>> {
+        let mut failed = Vec::new();
+        for name in &self.queues {
+            failed.extend(self.queue.failed(name).await?);
+        }
+        Ok(failed)
+    }
+
+    /// Record an attempt of `metadata` taking `duration`
+    pub(crate) fn record(&self, metadata: &JobMetadata, attempt: Attempt, duration: Duration) {
+        let now = Utc::now();
+        let duration_ms = duration.as_millis() as u64;
+        let mut metrics = self.metrics.lock().expect("queue metrics lock poisoned");
+
+        let stats = metrics
+            .job_types
+            .entry(metadata.job_type.clone())
+            .or_insert_with(|| JobTypeStats {
+                job_type: metadata.job_type.clone(),
+                ..Default::default()
+            });
+        match attempt {
+            Attempt::Completed => stats.completed += 1,
+            Attempt::Retried => stats.retried += 1,
+            Attempt::Failed => stats.failed += 1,
+        }
+        stats.total_duration_ms += duration_ms;
+        stats.max_duration_ms = stats.max_duration_ms.max(duration_ms);
+
+        if attempt != Attempt::Retried {
+            metrics.count(now, attempt == Attempt::Failed);
+        }
+        metrics.track_slow(SlowJob {
+            job_id: metadata.id.clone(),
+            job_type: metadata.job_type.clone(),
+            queue: metadata.queue.clone(),
+            duration_ms,
+            finished_at: now,
+        });
+    }
+}
+
+impl Metrics {
+    fn count(&mut self, now: DateTime<Utc>, failed: bool) {
+        let minute = now
+            .duration_trunc(chrono::Duration::minutes(1))
+            .unwrap_or(now);
+        if self.history.back().map(|point| point.minute) != Some(minute) {
+            self.history.push_back(Throughput {
+                minute,
+                completed: 0,
+                failed: 0,
+            });
+        }
+        let horizon = minute - chrono::Duration::minutes(HISTORY_MINUTES as i64);
+        while self
+            .history
+            .front()
+            .is_some_and(|point| point.minute <= horizon)
+        {
+            self.history.pop_front();
+        }
+
+        let point = self.history.back_mut().expect("current minute was pushed");
+        if failed {
+            point.failed += 1;
+        } else {
+            point.completed += 1;
+        }
+    }
+
+    fn track_slow(&mut self, job: SlowJob) {
+        if self.slowest.len() == SLOWEST_JOBS
+            && self
+                .slowest
+                .last()
+                .is_some_and(|slow| slow.duration_ms >= job.duration_ms)
+        {
+            return;
+        }
+        let pos = self
+            .slowest
+            .partition_point(|slow| slow.duration_ms >= job.duration_ms);
+        self.slowest.insert(pos, job);
+        self.slowest.truncate(SLOWEST_JOBS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job::Job;
+    use crate::memory::MemoryQueue;
+    use crate::QueueError;
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize)]
+    struct Report;
+
+    #[async_trait::async_trait]
+    impl Job for Report {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "report"
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Mail;
+
+    #[async_trait::async_trait]
+    impl Job for Mail {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "mail"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stats() {
+        let queue = Arc::new(MemoryQueue::new());
+        let monitor = QueueMonitor::new(queue.clone());
+        queue.push(JobMetadata::new(&Mail).unwrap()).await.unwrap();
+
+        let report = JobMetadata::new(&Report).unwrap();
+        let mail = JobMetadata::new(&Mail).unwrap();
+        monitor.record(&report, Attempt::Completed, Duration::from_millis(300));
+        monitor.record(&mail, Attempt::Completed, Duration::from_millis(10));
+        monitor.record(&mail, Attempt::Retried, Duration::from_millis(20));
+        monitor.record(&mail, Attempt::Failed, Duration::from_millis(30));
+
+        let stats = monitor.stats().await.unwrap();
+        assert_eq!(stats.queues[0].pending, 1);
+
+        let mail = &stats.job_types[0];
+        assert_eq!(mail.job_type, "mail");
+        assert_eq!((mail.completed, mail.retried, mail.failed), (1, 1, 1));
+        assert!((mail.failure_rate() - 2.0 / 3.0).abs() < f64::EPSILON);
+        assert_eq!((mail.average_duration_ms(), mail.max_duration_ms), (20, 30));
+
+        let point = stats.throughput.last().unwrap();
+        assert_eq!((point.completed, point.failed), (2, 1));
+        assert_eq!(stats.slowest[0].job_type, "report");
+        assert_eq!(stats.slowest.len(), 4);
+    }
+
+    #[test]
+    fn test_slowest_jobs_are_bounded() {
+        let mut metrics = Metrics::default();
+        for duration_ms in 0..30 {
+            metrics.track_slow(SlowJob {
+                job_id: duration_ms.to_string(),
+                job_type: "report".to_string(),
+                queue: "default".to_string(),
+                duration_ms,
+                finished_at: Utc::now(),
+            });
+        }
+
+        let durations: Vec<_> = metrics.slowest.iter().map(|job| job.duration_ms).collect();
+        assert_eq!(durations, (20..30).rev().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,1098 @@
+//! Postgres queue backend
+
+use crate::batch::{BatchRecord, BatchStore, JobOutcome};
+use crate::error::{QueueError, QueueResult};
+use crate::failed::{FailedJob, FailedJobStore};
+use crate::job::JobMetadata;
+use crate::queue::Queue;
+use crate::saga::{SagaRecord, SagaState, SagaStore};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgPoolOptions, PgPool, Row};
+use std::time::Duration;
+
+/// Postgres-backed queue
+///
+/// Jobs are rows of a single table, see [`migrate`](Self::migrate). Workers
+/// reserve with `FOR UPDATE SKIP LOCKED`, so any number of them can poll
+/// the same queue without blocking each other. Higher priorities run first.
+/// Jobs reserved longer than [`retry_after`](Self::retry_after) are handed
+/// out again, so a crashed worker doesn't lose them.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_queue::PostgresQueue;
+///
+/// # async fn example() -> Result<(), rf_queue::QueueError> {
+/// let queue = PostgresQueue::connect("postgres://localhost/app")
+///     .await?
+///     .table("jobs");
+/// queue.migrate().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct PostgresQueue {
+    pool: PgPool,
+    table: String,
+    retry_after: Duration,
+}
+
+impl PostgresQueue {
+    /// Create a queue on an existing pool, in the `queue_jobs` table
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            table: "queue_jobs".to_string(),
+            retry_after: Duration::from_secs(90),
+        }
+    }
+
+    /// Connect to `database_url`
+    pub async fn connect(database_url: &str) -> QueueResult<Self> {
+        let pool = PgPoolOptions::new()
+            .connect(database_url)
+            .await
+            .map_err(backend_error)?;
+        Ok(Self::new(pool))
+    }
+
+    /// Use a different table
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// How long a job may stay reserved before it is released again
+    /// (default: 90 seconds); keep it above the longest job timeout
+    pub fn retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    /// Create the jobs table and its index if they don't exist
+    pub async fn migrate(&self) -> QueueResult<()> {
+        let table = &self.table;
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {table} (
+                id TEXT PRIMARY KEY,
+                queue TEXT NOT NULL,
+                payload JSONB NOT NULL,
+                priority INTEGER NOT NULL DEFAULT 0,
+                status TEXT NOT NULL DEFAULT 'pending',
+                available_at TIMESTAMPTZ NOT NULL,
+                reserved_until TIMESTAMPTZ,
+                failed_at TIMESTAMPTZ,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )"
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+
+        sqlx::query(&format!(
+            "CREATE INDEX IF NOT EXISTS {table}_queue_idx ON {table} (queue, status, priority DESC, available_at)"
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+
+    /// Insert a job, or replace it when retried
+    async fn upsert(&self, metadata: &JobMetadata) -> QueueResult<()> {
+        sqlx::query(&format!(
+            "INSERT INTO {} (id, queue, payload, priority, status, available_at)
+             VALUES ($1, $2, $3::jsonb, $4, 'pending', $5)
+             ON CONFLICT (id) DO UPDATE SET
+                payload = EXCLUDED.payload,
+                status = 'pending',
+                available_at = EXCLUDED.available_at,
+                reserved_until = NULL,
+                failed_at = NULL",
+            self.table
+        ))
+        .bind(&metadata.id)
+        .bind(&metadata.queue)
+        .bind(payload(metadata)?)
+        .bind(metadata.priority)
+        .bind(metadata.execute_at.unwrap_or_else(chrono::Utc::now))
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Queue for PostgresQueue {
+    async fn push(&self, metadata: JobMetadata) -> QueueResult<String> {
+        self.upsert(&metadata).await?;
+        tracing::debug!(job_id = %metadata.id, "Job pushed to Postgres queue");
+        Ok(metadata.id)
+    }
+
+    async fn reserve(&self, queue: &str) -> QueueResult<Option<JobMetadata>> {
+        let mut tx = self.pool.begin().await.map_err(backend_error)?;
+
+        let row = sqlx::query(&format!(
+            "SELECT payload::text AS payload FROM {}
+             WHERE queue = $1
+               AND ((status = 'pending' AND available_at <= now())
+                 OR (status = 'reserved' AND reserved_until <= now()))
+             ORDER BY priority DESC, available_at, created_at
+             LIMIT 1
+             FOR UPDATE SKIP LOCKED",
+            self.table
+        ))
+        .bind(queue)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(backend_error)?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let data: String = row.get("payload");
+        let mut metadata = JobMetadata::from_bytes(data.as_bytes())?;
+        metadata.mark_attempt();
+
+        let reserved_until = chrono::Utc::now()
+            + chrono::Duration::from_std(self.retry_after).map_err(backend_error)?;
+        sqlx::query(&format!(
+            "UPDATE {} SET status = 'reserved', reserved_until = $2, payload = $3::jsonb
+             WHERE id = $1",
+            self.table
+        ))
+        .bind(&metadata.id)
+        .bind(reserved_until)
+        .bind(payload(&metadata)?)
+        .execute(&mut *tx)
+        .await
+        .map_err(backend_error)?;
+
+        tx.commit().await.map_err(backend_error)?;
+        Ok(Some(metadata))
+    }
+
+    async fn complete(&self, job_id: &str) -> QueueResult<()> {
+        sqlx::query(&format!("DELETE FROM {} WHERE id = $1", self.table))
+            .bind(job_id)
+            .execute(&self.pool)
+            .await
+            .map_err(backend_error)?;
+
+        tracing::debug!(job_id = %job_id, "Job completed");
+        Ok(())
+    }
+
+    async fn fail(&self, job_id: &str, error: &str) -> QueueResult<()> {
+        let result = sqlx::query(&format!(
+            "UPDATE {} SET status = 'failed', failed_at = now(), reserved_until = NULL,
+                payload = jsonb_set(payload, '{{last_error}}', to_jsonb($2::text))
+             WHERE id = $1",
+            self.table
+        ))
+        .bind(job_id)
+        .bind(error)
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        if result.rows_affected() == 0 {
+            return Err(QueueError::JobNotFound(job_id.to_string()));
+        }
+
+        tracing::warn!(job_id = %job_id, error = %error, "Job failed");
+        Ok(())
+    }
+
+    async fn retry(&self, metadata: JobMetadata) -> QueueResult<()> {
+        if !metadata.can_retry() {
+            return Err(QueueError::JobFailed("Max retries exceeded".to_string()));
+        }
+
+        self.upsert(&metadata).await
+    }
+
+    async fn size(&self, queue: &str) -> QueueResult<usize> {
+        let count: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM {} WHERE queue = $1 AND status = 'pending'",
+            self.table
+        ))
+        .bind(queue)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        Ok(count as usize)
+    }
+
+    async fn clear(&self, queue: &str) -> QueueResult<()> {
+        sqlx::query(&format!(
+            "DELETE FROM {} WHERE queue = $1 AND status = 'pending'",
+            self.table
+        ))
+        .bind(queue)
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn failed(&self, queue: &str) -> QueueResult<Vec<JobMetadata>> {
+        let payloads: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT payload::text FROM {} WHERE queue = $1 AND status = 'failed'
+             ORDER BY failed_at",
+            self.table
+        ))
+        .bind(queue)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(backend_error)?;
+
+        payloads
+            .iter()
+            .map(|payload| JobMetadata::from_bytes(payload.as_bytes()))
+            .collect()
+    }
+
+    async fn requeue(&self, job_id: &str) -> QueueResult<()> {
+        let result = sqlx::query(&format!(
+            "UPDATE {} SET status = 'pending', available_at = now(), failed_at = NULL,
+                payload = payload || '{{\"attempts\": 0, \"last_error\": null, \"execute_at\": null}}'::jsonb
+             WHERE id = $1 AND status = 'failed'",
+            self.table
+        ))
+        .bind(job_id)
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        if result.rows_affected() == 0 {
+            return Err(QueueError::JobNotFound(job_id.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn forget(&self, job_id: &str) -> QueueResult<()> {
+        let result = sqlx::query(&format!(
+            "DELETE FROM {} WHERE id = $1 AND status = 'failed'",
+            self.table
+        ))
+        .bind(job_id)
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        if result.rows_affected() == 0 {
+            return Err(QueueError::JobNotFound(job_id.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Postgres-backed batch store
+///
+/// Batches are rows of a single table, see [`migrate`](Self::migrate), and
+/// job outcomes rows of a second one with a `_jobs` suffix. Updates lock
+/// the batch row, so workers finishing jobs of the same batch at once
+/// settle it one after the other.
+///
+/// ```no_run
+/// use rf_queue::{Bus, PostgresBatchStore, PostgresQueue};
+/// use std::sync::Arc;
+///
+/// # async fn example() -> Result<(), rf_queue::QueueError> {
+/// let queue = PostgresQueue::connect("postgres://localhost/app").await?;
+/// let batches = PostgresBatchStore::connect("postgres://localhost/app").await?;
+/// batches.migrate().await?;
+/// let bus = Bus::new(Arc::new(queue)).batches(Arc::new(batches));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct PostgresBatchStore {
+    pool: PgPool,
+    table: String,
+}
+
+impl PostgresBatchStore {
+    /// Create a store on an existing pool, in the `job_batches` table
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            table: "job_batches".to_string(),
+        }
+    }
+
+    /// Connect to `database_url`
+    pub async fn connect(database_url: &str) -> QueueResult<Self> {
+        let pool = PgPoolOptions::new()
+            .connect(database_url)
+            .await
+            .map_err(backend_error)?;
+        Ok(Self::new(pool))
+    }
+
+    /// Use a different table
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Create the batches and job outcomes tables if they don't exist
+    pub async fn migrate(&self) -> QueueResult<()> {
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id TEXT PRIMARY KEY,
+                payload JSONB NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )",
+            self.table
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {}_jobs (
+                job_id TEXT PRIMARY KEY,
+                batch_id TEXT,
+                outcome TEXT NOT NULL,
+                recorded_at TIMESTAMPTZ NOT NULL DEFAULT now()
+            )",
+            self.table
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+
+    /// Outcome recorded for `job_id`, locking its row
+    async fn recorded_outcome(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        job_id: &str,
+    ) -> QueueResult<Option<JobOutcome>> {
+        let outcome: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT outcome FROM {}_jobs WHERE job_id = $1 FOR UPDATE",
+            self.table
+        ))
+        .bind(job_id)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(backend_error)?;
+
+        outcome
+            .map(|outcome| {
+                JobOutcome::parse(&outcome).ok_or_else(|| {
+                    QueueError::DeserializationError(format!("Unknown job outcome: {}", outcome))
+                })
+            })
+            .transpose()
+    }
+
+    async fn save_outcome(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        batch_id: Option<&str>,
+        job_id: &str,
+        outcome: JobOutcome,
+    ) -> QueueResult<()> {
+        sqlx::query(&format!(
+            "INSERT INTO {}_jobs (job_id, batch_id, outcome) VALUES ($1, $2, $3)
+             ON CONFLICT (job_id) DO UPDATE SET outcome = EXCLUDED.outcome, recorded_at = now()",
+            self.table
+        ))
+        .bind(job_id)
+        .bind(batch_id)
+        .bind(outcome.as_str())
+        .execute(&mut **tx)
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BatchStore for PostgresBatchStore {
+    async fn create(&self, batch: &BatchRecord) -> QueueResult<()> {
+        sqlx::query(&format!(
+            "INSERT INTO {} (id, payload) VALUES ($1, $2::jsonb)",
+            self.table
+        ))
+        .bind(&batch.id)
+        .bind(batch_payload(batch)?)
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn find(&self, id: &str) -> QueueResult<Option<BatchRecord>> {
+        let payload: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT payload::text FROM {} WHERE id = $1",
+            self.table
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(backend_error)?;
+
+        payload.map(|payload| parse_batch(&payload)).transpose()
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        update: &mut (dyn for<'b> FnMut(&'b mut BatchRecord) + Send),
+    ) -> QueueResult<()> {
+        let mut tx = self.pool.begin().await.map_err(backend_error)?;
+
+        let payload: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT payload::text FROM {} WHERE id = $1 FOR UPDATE",
+            self.table
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(backend_error)?;
+        let mut batch =
+            parse_batch(&payload.ok_or_else(|| QueueError::BatchNotFound(id.to_string()))?)?;
+        update(&mut batch);
+
+        sqlx::query(&format!(
+            "UPDATE {} SET payload = $2::jsonb WHERE id = $1",
+            self.table
+        ))
+        .bind(id)
+        .bind(batch_payload(&batch)?)
+        .execute(&mut *tx)
+        .await
+        .map_err(backend_error)?;
+
+        tx.commit().await.map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn record(
+        &self,
+        id: &str,
+        job_id: &str,
+        outcome: JobOutcome,
+        update: &mut (dyn for<'b> FnMut(&'b mut BatchRecord, Option<JobOutcome>) + Send),
+    ) -> QueueResult<()> {
+        let mut tx = self.pool.begin().await.map_err(backend_error)?;
+
+        let payload: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT payload::text FROM {} WHERE id = $1 FOR UPDATE",
+            self.table
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(backend_error)?;
+        let mut batch =
+            parse_batch(&payload.ok_or_else(|| QueueError::BatchNotFound(id.to_string()))?)?;
+
+        let previous = self.recorded_outcome(&mut tx, job_id).await?;
+        if !outcome.replaces(previous) {
+            return Ok(());
+        }
+        self.save_outcome(&mut tx, Some(id), job_id, outcome)
+            .await?;
+        update(&mut batch, previous);
+
+        sqlx::query(&format!(
+            "UPDATE {} SET payload = $2::jsonb WHERE id = $1",
+            self.table
+        ))
+        .bind(id)
+        .bind(batch_payload(&batch)?)
+        .execute(&mut *tx)
+        .await
+        .map_err(backend_error)?;
+
+        tx.commit().await.map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn record_job(&self, job_id: &str, outcome: JobOutcome) -> QueueResult<bool> {
+        let mut tx = self.pool.begin().await.map_err(backend_error)?;
+
+        // Claims the row first, so a job delivered to two workers at once
+        // is recorded by one of them
+        let inserted = sqlx::query(&format!(
+            "INSERT INTO {}_jobs (job_id, outcome) VALUES ($1, $2)
+             ON CONFLICT (job_id) DO NOTHING",
+            self.table
+        ))
+        .bind(job_id)
+        .bind(outcome.as_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(backend_error)?
+        .rows_affected()
+            == 1;
+
+        let replaced = inserted || {
+            let previous = self.recorded_outcome(&mut tx, job_id).await?;
+            let replaces = outcome.replaces(previous);
+            if replaces {
+                self.save_outcome(&mut tx, None, job_id, outcome).await?;
+            }
+            replaces
+        };
+
+        tx.commit().await.map_err(backend_error)?;
+        Ok(replaced)
+    }
+}
+
+/// Postgres-backed saga store
+///
+/// Sagas are rows of a single table, see [`migrate`](Self::migrate), with
+/// the state in a column for listing. Updates lock the row, so a saga is
+/// moved along by one worker at a time.
+///
+/// ```no_run
+/// use rf_queue::{Bus, PostgresQueue, PostgresSagaStore};
+/// use std::sync::Arc;
+///
+/// # async fn example() -> Result<(), rf_queue::QueueError> {
+/// let queue = PostgresQueue::connect("postgres://localhost/app").await?;
+/// let sagas = PostgresSagaStore::connect("postgres://localhost/app").await?;
+/// sagas.migrate().await?;
+/// let bus = Bus::new(Arc::new(queue)).sagas(Arc::new(sagas));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct PostgresSagaStore {
+    pool: PgPool,
+    table: String,
+}
+
+impl PostgresSagaStore {
+    /// Create a store on an existing pool, in the `sagas` table
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            table: "sagas".to_string(),
+        }
+    }
+
+    /// Connect to `database_url`
+    pub async fn connect(database_url: &str) -> QueueResult<Self> {
+        let pool = PgPoolOptions::new()
+            .connect(database_url)
+            .await
+            .map_err(backend_error)?;
+        Ok(Self::new(pool))
+    }
+
+    /// Use a different table
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Create the sagas table if it doesn't exist
+    pub async fn migrate(&self) -> QueueResult<()> {
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                state TEXT NOT NULL,
+                payload JSONB NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL,
+                updated_at TIMESTAMPTZ NOT NULL
+            )",
+            self.table
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+
+        sqlx::query(&format!(
+            "CREATE INDEX IF NOT EXISTS {0}_state_idx ON {0} (state, created_at)",
+            self.table
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SagaStore for PostgresSagaStore {
+    async fn create(&self, saga: &SagaRecord) -> QueueResult<()> {
+        sqlx::query(&format!(
+            "INSERT INTO {} (id, name, state, payload, created_at, updated_at)
+             VALUES ($1, $2, $3, $4::jsonb, $5, $6)",
+            self.table
+        ))
+        .bind(&saga.id)
+        .bind(&saga.name)
+        .bind(saga.state.as_str())
+        .bind(saga_payload(saga)?)
+        .bind(saga.created_at)
+        .bind(saga.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn find(&self, id: &str) -> QueueResult<Option<SagaRecord>> {
+        let payload: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT payload::text FROM {} WHERE id = $1",
+            self.table
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(backend_error)?;
+
+        payload.map(|payload| parse_saga(&payload)).transpose()
+    }
+
+    async fn list(&self, state: Option<SagaState>) -> QueueResult<Vec<SagaRecord>> {
+        let payloads: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT payload::text FROM {}
+             WHERE $1::text IS NULL OR state = $1
+             ORDER BY created_at",
+            self.table
+        ))
+        .bind(state.map(|state| state.as_str()))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(backend_error)?;
+
+        payloads.iter().map(|payload| parse_saga(payload)).collect()
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        update: &mut (dyn for<'s> FnMut(&'s mut SagaRecord) + Send),
+    ) -> QueueResult<()> {
+        let mut tx = self.pool.begin().await.map_err(backend_error)?;
+
+        let payload: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT payload::text FROM {} WHERE id = $1 FOR UPDATE",
+            self.table
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(backend_error)?;
+        let mut saga =
+            parse_saga(&payload.ok_or_else(|| QueueError::SagaNotFound(id.to_string()))?)?;
+        update(&mut saga);
+
+        sqlx::query(&format!(
+            "UPDATE {} SET state = $2, payload = $3::jsonb, updated_at = $4 WHERE id = $1",
+            self.table
+        ))
+        .bind(id)
+        .bind(saga.state.as_str())
+        .bind(saga_payload(&saga)?)
+        .bind(saga.updated_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(backend_error)?;
+
+        tx.commit().await.map_err(backend_error)?;
+        Ok(())
+    }
+}
+
+/// Postgres-backed failed-job store
+///
+/// Failed jobs are rows of the `failed_jobs` table, see
+/// [`migrate`](Self::migrate), with the job type and the error in columns
+/// for querying by hand.
+///
+/// ```no_run
+/// use rf_queue::{MemoryQueue, PostgresFailedJobStore, Worker};
+/// use std::sync::Arc;
+///
+/// # async fn example() -> Result<(), rf_queue::QueueError> {
+/// let store = PostgresFailedJobStore::connect("postgres://localhost/app").await?;
+/// store.migrate().await?;
+/// let worker = Worker::new(Arc::new(MemoryQueue::new())).failed_jobs(Arc::new(store));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct PostgresFailedJobStore {
+    pool: PgPool,
+    table: String,
+}
+
+impl PostgresFailedJobStore {
+    /// Create a store on an existing pool, in the `failed_jobs` table
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            table: "failed_jobs".to_string(),
+        }
+    }
+
+    /// Connect to `database_url`
+    pub async fn connect(database_url: &str) -> QueueResult<Self> {
+        let pool = PgPoolOptions::new()
+            .connect(database_url)
+            .await
+            .map_err(backend_error)?;
+        Ok(Self::new(pool))
+    }
+
+    /// Use a different table
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Create the failed jobs table and its index if they don't exist
+    pub async fn migrate(&self) -> QueueResult<()> {
+        let table = &self.table;
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {table} (
+                id TEXT PRIMARY KEY,
+                queue TEXT NOT NULL,
+                job_type TEXT NOT NULL,
+                payload JSONB NOT NULL,
+                exception TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                failed_at TIMESTAMPTZ NOT NULL
+            )"
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+
+        sqlx::query(&format!(
+            "CREATE INDEX IF NOT EXISTS {table}_failed_at_idx ON {table} (failed_at)"
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+
+    fn select(&self) -> String {
+        format!(
+            "SELECT id, queue, job_type, payload::text AS payload, exception, attempts, failed_at
+             FROM {}",
+            self.table
+        )
+    }
+}
+
+fn failed_job(row: &sqlx::postgres::PgRow) -> QueueResult<FailedJob> {
+    let payload: String = row.get("payload");
+    let attempts: i32 = row.get("attempts");
+    Ok(FailedJob {
+        id: row.get("id"),
+        queue: row.get("queue"),
+        job_type: row.get("job_type"),
+        metadata: JobMetadata::from_bytes(payload.as_bytes())?,
+        exception: row.get("exception"),
+        attempts: attempts as u32,
+        failed_at: row.get("failed_at"),
+    })
+}
+
+#[async_trait]
+impl FailedJobStore for PostgresFailedJobStore {
+    async fn log(&self, job: FailedJob) -> QueueResult<()> {
+        sqlx::query(&format!(
+            "INSERT INTO {} (id, queue, job_type, payload, exception, attempts, failed_at)
+             VALUES ($1, $2, $3, $4::jsonb, $5, $6, $7)
+             ON CONFLICT (id) DO UPDATE SET
+                payload = EXCLUDED.payload,
+                exception = EXCLUDED.exception,
+                attempts = EXCLUDED.attempts,
+                failed_at = EXCLUDED.failed_at",
+            self.table
+        ))
+        .bind(&job.id)
+        .bind(&job.queue)
+        .bind(&job.job_type)
+        .bind(payload(&job.metadata)?)
+        .bind(&job.exception)
+        .bind(job.attempts as i32)
+        .bind(job.failed_at)
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn all(&self) -> QueueResult<Vec<FailedJob>> {
+        sqlx::query(&format!("{} ORDER BY failed_at DESC", self.select()))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(backend_error)?
+            .iter()
+            .map(failed_job)
+            .collect()
+    }
+
+    async fn find(&self, id: &str) -> QueueResult<Option<FailedJob>> {
+        sqlx::query(&format!("{} WHERE id = $1", self.select()))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(backend_error)?
+            .as_ref()
+            .map(failed_job)
+            .transpose()
+    }
+
+    async fn forget(&self, id: &str) -> QueueResult<bool> {
+        let result = sqlx::query(&format!("DELETE FROM {} WHERE id = $1", self.table))
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(backend_error)?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn prune(&self, before: DateTime<Utc>) -> QueueResult<usize> {
+        let result = sqlx::query(&format!("DELETE FROM {} WHERE failed_at < $1", self.table))
+            .bind(before)
+            .execute(&self.pool)
+            .await
+            .map_err(backend_error)?;
+        Ok(result.rows_affected() as usize)
+    }
+}
+
+fn batch_payload(batch: &BatchRecord) -> QueueResult<String> {
+    serde_json::to_string(batch).map_err(|e| QueueError::SerializationError(e.to_string()))
+}
+
+fn parse_batch(payload: &str) -> QueueResult<BatchRecord> {
+    serde_json::from_str(payload).map_err(|e| QueueError::DeserializationError(e.to_string()))
+}
+
+fn saga_payload(saga: &SagaRecord) -> QueueResult<String> {
+    serde_json::to_string(saga).map_err(|e| QueueError::SerializationError(e.to_string()))
+}
+
+fn parse_saga(payload: &str) -> QueueResult<SagaRecord> {
+    serde_json::from_str(payload).map_err(|e| QueueError::DeserializationError(e.to_string()))
+}
+
+fn payload(metadata: &JobMetadata) -> QueueResult<String> {
+    serde_json::to_string(metadata).map_err(|e| QueueError::SerializationError(e.to_string()))
+}
+
+fn backend_error(e: impl std::fmt::Display) -> QueueError {
+    QueueError::BackendError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job::Job;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct TestJob {
+        urgent: bool,
+    }
+
+    #[async_trait]
+    impl Job for TestJob {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "test_job"
+        }
+
+        fn priority(&self) -> i32 {
+            if self.urgent {
+                10
+            } else {
+                0
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Postgres
+    async fn test_postgres_queue() {
+        let url = std::env::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/rf_queue_test".to_string());
+        let queue = PostgresQueue::connect(&url)
+            .await
+            .unwrap()
+            .table("rf_queue_test_jobs");
+        queue.migrate().await.unwrap();
+        queue.clear("default").await.unwrap();
+
+        queue
+            .push(JobMetadata::new(&TestJob { urgent: false }).unwrap())
+            .await
+            .unwrap();
+        let urgent = queue
+            .push(JobMetadata::new(&TestJob { urgent: true }).unwrap())
+            .await
+            .unwrap();
+        let delayed = JobMetadata::new_delayed(&TestJob { urgent: true }, Duration::from_secs(60));
+        queue.push(delayed.unwrap()).await.unwrap();
+        assert_eq!(queue.size("default").await.unwrap(), 3);
+
+        let reserved = queue.reserve("default").await.unwrap().unwrap();
+        assert_eq!(reserved.id, urgent);
+        assert_eq!(reserved.attempts, 1);
+
+        queue.fail(&urgent, "boom").await.unwrap();
+        let failed = queue.failed("default").await.unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].last_error.as_deref(), Some("boom"));
+
+        queue.requeue(&urgent).await.unwrap();
+        let reserved = queue.reserve("default").await.unwrap().unwrap();
+        assert_eq!(reserved.id, urgent);
+        assert_eq!(reserved.attempts, 1);
+        assert_eq!(reserved.last_error, None);
+        queue.complete(&urgent).await.unwrap();
+
+        queue.clear("default").await.unwrap();
+        assert_eq!(queue.size("default").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Postgres
+    async fn test_postgres_batch_store() {
+        let url = std::env::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/rf_queue_test".to_string());
+        let store = PostgresBatchStore::connect(&url)
+            .await
+            .unwrap()
+            .table("rf_queue_test_batches");
+        store.migrate().await.unwrap();
+
+        let (batch, _) = crate::Batch::new("import")
+            .job(JobMetadata::new(&TestJob { urgent: false }).unwrap())
+            .into_record(Vec::new(), None);
+        store.create(&batch).await.unwrap();
+
+        store
+            .update(&batch.id, &mut |batch| {
+                batch.pending_jobs -= 1;
+            })
+            .await
+            .unwrap();
+        let found = store.find(&batch.id).await.unwrap().unwrap();
+        assert_eq!(found.name, "import");
+        assert_eq!(found.progress(), 100);
+
+        let job_id = uuid::Uuid::new_v4().to_string();
+        assert!(store.record_job(&job_id, JobOutcome::Failed).await.unwrap());
+        assert!(store
+            .record_job(&job_id, JobOutcome::Succeeded)
+            .await
+            .unwrap());
+        assert!(!store
+            .record_job(&job_id, JobOutcome::Succeeded)
+            .await
+            .unwrap());
+
+        assert!(matches!(
+            store.update("unknown", &mut |_| {}).await,
+            Err(QueueError::BatchNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Postgres
+    async fn test_postgres_saga_store() {
+        let url = std::env::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/rf_queue_test".to_string());
+        let store = PostgresSagaStore::connect(&url)
+            .await
+            .unwrap()
+            .table("rf_queue_test_sagas");
+        store.migrate().await.unwrap();
+
+        let mut saga = crate::Saga::new("checkout")
+            .step(
+                "charge",
+                JobMetadata::new(&TestJob { urgent: false }).unwrap(),
+            )
+            .into_record();
+        let job = saga.start().unwrap();
+        store.create(&saga).await.unwrap();
+        assert!(store
+            .list(Some(SagaState::Running))
+            .await
+            .unwrap()
+            .iter()
+            .any(|found| found.id == saga.id));
+
+        store
+            .update(&saga.id, &mut |saga| {
+                saga.record(&job.id, Ok(()));
+            })
+            .await
+            .unwrap();
+        let found = store.find(&saga.id).await.unwrap().unwrap();
+        assert_eq!(found.state, SagaState::Completed);
+        assert!(store
+            .list(Some(SagaState::Completed))
+            .await
+            .unwrap()
+            .iter()
+            .any(|found| found.id == saga.id));
+
+        assert!(matches!(
+            store.update("unknown", &mut |_| {}).await,
+            Err(QueueError::SagaNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Postgres
+    async fn test_postgres_failed_job_store() {
+        let url = std::env::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/rf_queue_test".to_string());
+        let store = PostgresFailedJobStore::connect(&url)
+            .await
+            .unwrap()
+            .table("rf_queue_test_failed_jobs");
+        store.migrate().await.unwrap();
+        store.prune(Utc::now()).await.unwrap();
+
+        let metadata = JobMetadata::new(&TestJob { urgent: true }).unwrap();
+        let mut old = FailedJob::new(metadata, "boom");
+        old.failed_at -= chrono::Duration::days(40);
+        store.log(old.clone()).await.unwrap();
+        let recent = FailedJob::new(
+            JobMetadata::new(&TestJob { urgent: false }).unwrap(),
+            "boom",
+        );
+        store.log(recent.clone()).await.unwrap();
+
+        let all = store.all().await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].id, recent.id);
+        assert_eq!(all[1].metadata.priority, 10);
+        assert_eq!(
+            store.find(&old.id).await.unwrap().unwrap().exception,
+            "boom"
+        );
+
+        let month_ago = Utc::now() - chrono::Duration::days(30);
+        assert_eq!(store.prune(month_ago).await.unwrap(), 1);
+        assert!(store.forget(&recent.id).await.unwrap());
+        assert!(!store.forget(&recent.id).await.unwrap());
+    }
+}
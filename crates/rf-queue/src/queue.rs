@@ -5,6 +5,10 @@ use crate::job::JobMetadata;
 use async_trait::async_trait;
 
 /// Queue backend trait
+///
+/// Reserving a job increments its attempts. A reserved job is then either
+/// completed, retried or failed; failed jobs go to the dead-letter queue,
+/// from where they can be inspected, requeued or forgotten.
 #[async_trait]
 pub trait Queue: Send + Sync {
     /// Push a job to the queue
@@ -16,10 +20,10 @@ pub trait Queue: Send + Sync {
     /// Mark a job as completed
     async fn complete(&self, job_id: &str) -> QueueResult<()>;
 
-    /// Mark a job as failed
+    /// Mark a job as failed, moving it to the dead-letter queue
     async fn fail(&self, job_id: &str, error: &str) -> QueueResult<()>;
 
-    /// Retry a failed job
+    /// Retry a failed job, at its `execute_at` if set
     async fn retry(&self, metadata: JobMetadata) -> QueueResult<()>;
 
     /// Get job count for a queue
@@ -27,4 +31,22 @@ pub trait Queue: Send + Sync {
 
     /// Clear a queue
     async fn clear(&self, queue: &str) -> QueueResult<()>;
+
+    /// Dead jobs of a queue, oldest failure first
+    async fn failed(&self, queue: &str) -> QueueResult<Vec<JobMetadata>>;
+
+    /// Move a dead job back to its queue with its attempts reset
+    async fn requeue(&self, job_id: &str) -> QueueResult<()>;
+
+    /// Delete a dead job
+    async fn forget(&self, job_id: &str) -> QueueResult<()>;
+
+    /// Requeue every dead job of a queue, returning how many were moved
+    async fn requeue_all(&self, queue: &str) -> QueueResult<usize> {
+        let failed = self.failed(queue).await?;
+        for metadata in &failed {
+            self.requeue(&metadata.id).await?;
+        }
+        Ok(failed.len())
+    }
 }
@@ -0,0 +1,426 @@
+//! Redis queue backend
+
+use crate::error::{QueueError, QueueResult};
+use crate::job::JobMetadata;
+use crate::queue::Queue;
+use async_trait::async_trait;
+use deadpool_redis::{Config, Connection, Pool, Runtime};
+use redis::AsyncCommands;
+use std::time::Duration;
+
+/// Moves due delayed and expired reserved jobs to the ready list, then
+/// reserves the first ready job
+const RESERVE_SCRIPT: &str = r#"
+local function migrate(from, to, now)
+    local ids = redis.call('ZRANGEBYSCORE', from, '-inf', now)
+    for _, id in ipairs(ids) do
+        redis.call('ZREM', from, id)
+        redis.call('RPUSH', to, id)
+    end
+end
+migrate(KEYS[2], KEYS[1], ARGV[1])
+migrate(KEYS[3], KEYS[1], ARGV[1])
+local id = redis.call('LPOP', KEYS[1])
+if id then
+    redis.call('ZADD', KEYS[3], ARGV[2], id)
+end
+return id
+"#;
+
+/// Takes a job id out of one of its queue's sorted sets and, only if it was
+/// there, stores the job and pushes it to the ready list (or the delayed set
+/// when given a score)
+const MOVE_SCRIPT: &str = r#"
+if redis.call('ZREM', KEYS[1], ARGV[1]) == 0 then
+    return 0
+end
+redis.call('HSET', KEYS[2], ARGV[1], ARGV[2])
+if ARGV[3] == '' then
+    redis.call('RPUSH', KEYS[3], ARGV[1])
+else
+    redis.call('ZADD', KEYS[4], ARGV[3], ARGV[1])
+end
+return 1
+"#;
+
+/// Redis-backed queue
+///
+/// Job payloads are kept in the `{prefix}:jobs` hash. Each queue has a
+/// ready list and sorted sets of delayed, reserved and failed job ids under
+/// `{prefix}:queue:{name}`. Jobs reserved longer than
+/// [`retry_after`](Self::retry_after) are handed out again, so a crashed
+/// worker doesn't lose them.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_queue::RedisQueue;
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), rf_queue::QueueError> {
+/// let queue = RedisQueue::new("redis://localhost")
+///     .await?
+///     .retry_after(Duration::from_secs(120));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RedisQueue {
+    pool: Pool,
+    prefix: String,
+    retry_after: Duration,
+}
+
+impl RedisQueue {
+    /// Create new Redis queue with the default `queues` prefix
+    pub async fn new(redis_url: &str) -> QueueResult<Self> {
+        Self::with_prefix(redis_url, "queues").await
+    }
+
+    /// Create new Redis queue with a custom key prefix
+    pub async fn with_prefix(redis_url: &str, prefix: &str) -> QueueResult<Self> {
+        let pool = Config::from_url(redis_url)
+            .create_pool(Some(Runtime::Tokio1))
+            .map_err(backend_error)?;
+
+        // Test connection
+        let mut conn = pool.get().await.map_err(backend_error)?;
+        redis::cmd("PING")
+            .query_async::<_, String>(&mut conn)
+            .await
+            .map_err(backend_error)?;
+
+        Ok(Self {
+            pool,
+            prefix: prefix.to_string(),
+            retry_after: Duration::from_secs(90),
+        })
+    }
+
+    /// How long a job may stay reserved before it is released again
+    /// (default: 90 seconds); keep it above the longest job timeout
+    pub fn retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    fn jobs_key(&self) -> String {
+        format!("{}:jobs", self.prefix)
+    }
+
+    fn queue_key(&self, queue: &str, set: Option<&str>) -> String {
+        match set {
+            Some(set) => format!("{}:queue:{}:{}", self.prefix, queue, set),
+            None => format!("{}:queue:{}", self.prefix, queue),
+        }
+    }
+
+    async fn conn(&self) -> QueueResult<Connection> {
+        self.pool.get().await.map_err(backend_error)
+    }
+
+    async fn load(&self, conn: &mut Connection, job_id: &str) -> QueueResult<JobMetadata> {
+        let data: Option<Vec<u8>> = conn
+            .hget(self.jobs_key(), job_id)
+            .await
+            .map_err(backend_error)?;
+        let data = data.ok_or_else(|| QueueError::JobNotFound(job_id.to_string()))?;
+        JobMetadata::from_bytes(&data)
+    }
+
+    async fn store(&self, conn: &mut Connection, metadata: &JobMetadata) -> QueueResult<()> {
+        conn.hset(self.jobs_key(), &metadata.id, metadata.to_bytes()?)
+            .await
+            .map_err(backend_error)
+    }
+
+    /// Remove a job id from one of the sorted sets of its queue, failing if
+    /// it wasn't there
+    async fn take(
+        &self,
+        conn: &mut Connection,
+        queue: &str,
+        set: &str,
+        job_id: &str,
+    ) -> QueueResult<()> {
+        let removed: usize = conn
+            .zrem(self.queue_key(queue, Some(set)), job_id)
+            .await
+            .map_err(backend_error)?;
+        if removed == 0 {
+            return Err(QueueError::JobNotFound(job_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Atomically take a job out of the sorted set `set` and push it again,
+    /// failing without pushing if it wasn't in the set
+    async fn move_back(
+        &self,
+        conn: &mut Connection,
+        set: &str,
+        metadata: &JobMetadata,
+    ) -> QueueResult<()> {
+        let delayed_until = metadata
+            .execute_at
+            .filter(|_| !metadata.should_execute())
+            .map(|execute_at| execute_at.timestamp_millis().to_string());
+        let moved: bool = redis::Script::new(MOVE_SCRIPT)
+            .key(self.queue_key(&metadata.queue, Some(set)))
+            .key(self.jobs_key())
+            .key(self.queue_key(&metadata.queue, None))
+            .key(self.queue_key(&metadata.queue, Some("delayed")))
+            .arg(&metadata.id)
+            .arg(metadata.to_bytes()?)
+            .arg(delayed_until.unwrap_or_default())
+            .invoke_async(conn)
+            .await
+            .map_err(backend_error)?;
+        if !moved {
+            return Err(QueueError::JobNotFound(metadata.id.clone()));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Queue for RedisQueue {
+    async fn push(&self, metadata: JobMetadata) -> QueueResult<String> {
+        let mut conn = self.conn().await?;
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .hset(self.jobs_key(), &metadata.id, metadata.to_bytes()?);
+        match metadata.execute_at.filter(|_| !metadata.should_execute()) {
+            Some(execute_at) => pipe.zadd(
+                self.queue_key(&metadata.queue, Some("delayed")),
+                &metadata.id,
+                execute_at.timestamp_millis(),
+            ),
+            None => pipe.rpush(self.queue_key(&metadata.queue, None), &metadata.id),
+        };
+        pipe.query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(backend_error)?;
+
+        tracing::debug!(job_id = %metadata.id, "Job pushed to Redis queue");
+        Ok(metadata.id)
+    }
+
+    async fn reserve(&self, queue: &str) -> QueueResult<Option<JobMetadata>> {
+        let mut conn = self.conn().await?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let deadline = now + self.retry_after.as_millis() as i64;
+
+        let job_id: Option<String> = redis::Script::new(RESERVE_SCRIPT)
+            .key(self.queue_key(queue, None))
+            .key(self.queue_key(queue, Some("delayed")))
+            .key(self.queue_key(queue, Some("reserved")))
+            .arg(now)
+            .arg(deadline)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(backend_error)?;
+        let Some(job_id) = job_id else {
+            return Ok(None);
+        };
+
+        let mut metadata = match self.load(&mut conn, &job_id).await {
+            Ok(metadata) => metadata,
+            Err(QueueError::JobNotFound(_)) => {
+                // Forgotten while waiting
+                let _ = self.take(&mut conn, queue, "reserved", &job_id).await;
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        metadata.mark_attempt();
+        self.store(&mut conn, &metadata).await?;
+        Ok(Some(metadata))
+    }
+
+    async fn complete(&self, job_id: &str) -> QueueResult<()> {
+        let mut conn = self.conn().await?;
+        let metadata = self.load(&mut conn, job_id).await?;
+        redis::pipe()
+            .atomic()
+            .zrem(self.queue_key(&metadata.queue, Some("reserved")), job_id)
+            .hdel(self.jobs_key(), job_id)
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(backend_error)?;
+
+        tracing::debug!(job_id = %job_id, "Job completed");
+        Ok(())
+    }
+
+    async fn fail(&self, job_id: &str, error: &str) -> QueueResult<()> {
+        let mut conn = self.conn().await?;
+        let mut metadata = self.load(&mut conn, job_id).await?;
+        metadata.mark_error(error.to_string());
+        redis::pipe()
+            .atomic()
+            .hset(self.jobs_key(), job_id, metadata.to_bytes()?)
+            .zrem(self.queue_key(&metadata.queue, Some("reserved")), job_id)
+            .zadd(
+                self.queue_key(&metadata.queue, Some("failed")),
+                job_id,
+                chrono::Utc::now().timestamp_millis(),
+            )
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(backend_error)?;
+
+        tracing::warn!(job_id = %job_id, error = %error, "Job failed");
+        Ok(())
+    }
+
+    async fn retry(&self, metadata: JobMetadata) -> QueueResult<()> {
+        if !metadata.can_retry() {
+            return Err(QueueError::JobFailed("Max retries exceeded".to_string()));
+        }
+
+        // A job whose reservation was already given up (completed, failed or
+        // released to another worker) isn't pushed a second time
+        let mut conn = self.conn().await?;
+        self.move_back(&mut conn, "reserved", &metadata).await?;
+        tracing::debug!(job_id = %metadata.id, "Job released for retry");
+        Ok(())
+    }
+
+    async fn size(&self, queue: &str) -> QueueResult<usize> {
+        let mut conn = self.conn().await?;
+        let (ready, delayed): (usize, usize) = redis::pipe()
+            .llen(self.queue_key(queue, None))
+            .zcard(self.queue_key(queue, Some("delayed")))
+            .query_async(&mut conn)
+            .await
+            .map_err(backend_error)?;
+        Ok(ready + delayed)
+    }
+
+    async fn clear(&self, queue: &str) -> QueueResult<()> {
+        let mut conn = self.conn().await?;
+        let ready_key = self.queue_key(queue, None);
+        let delayed_key = self.queue_key(queue, Some("delayed"));
+        let (mut ids, delayed): (Vec<String>, Vec<String>) = redis::pipe()
+            .lrange(&ready_key, 0, -1)
+            .zrange(&delayed_key, 0, -1)
+            .query_async(&mut conn)
+            .await
+            .map_err(backend_error)?;
+        ids.extend(delayed);
+
+        let mut pipe = redis::pipe();
+        pipe.atomic().del(&ready_key).del(&delayed_key);
+        if !ids.is_empty() {
+            pipe.hdel(self.jobs_key(), ids);
+        }
+        pipe.query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(backend_error)
+    }
+
+    async fn failed(&self, queue: &str) -> QueueResult<Vec<JobMetadata>> {
+        let mut conn = self.conn().await?;
+        let ids: Vec<String> = conn
+            .zrange(self.queue_key(queue, Some("failed")), 0, -1)
+            .await
+            .map_err(backend_error)?;
+
+        let mut failed = Vec::with_capacity(ids.len());
+        for id in ids {
+            match self.load(&mut conn, &id).await {
+                Ok(metadata) => failed.push(metadata),
+                Err(QueueError::JobNotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(failed)
+    }
+
+    async fn requeue(&self, job_id: &str) -> QueueResult<()> {
+        let mut conn = self.conn().await?;
+        let mut metadata = self.load(&mut conn, job_id).await?;
+        metadata.reset();
+        self.move_back(&mut conn, "failed", &metadata).await
+    }
+
+    async fn forget(&self, job_id: &str) -> QueueResult<()> {
+        let mut conn = self.conn().await?;
+        let metadata = self.load(&mut conn, job_id).await?;
+        self.take(&mut conn, &metadata.queue, "failed", job_id)
+            .await?;
+        conn.hdel::<_, _, ()>(self.jobs_key(), job_id)
+            .await
+            .map_err(backend_error)
+    }
+}
+
+fn backend_error(e: impl std::fmt::Display) -> QueueError {
+    QueueError::BackendError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job::Job;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct TestJob {
+        message: String,
+    }
+
+    #[async_trait]
+    impl Job for TestJob {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "test_job"
+        }
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Redis
+    async fn test_redis_queue() {
+        let queue = RedisQueue::with_prefix("redis://localhost", "rf_queue_test")
+            .await
+            .unwrap();
+        queue.clear("default").await.unwrap();
+        let job = TestJob {
+            message: "test".to_string(),
+        };
+
+        let delayed = JobMetadata::new_delayed(&job, Duration::from_secs(60)).unwrap();
+        queue.push(delayed).await.unwrap();
+        let id = queue.push(JobMetadata::new(&job).unwrap()).await.unwrap();
+        assert_eq!(queue.size("default").await.unwrap(), 2);
+
+        let reserved = queue.reserve("default").await.unwrap().unwrap();
+        assert_eq!(reserved.id, id);
+        assert_eq!(reserved.attempts, 1);
+        assert!(queue.reserve("default").await.unwrap().is_none());
+
+        // Retrying the same reservation twice pushes the job once
+        queue.retry(reserved.clone()).await.unwrap();
+        assert!(queue.retry(reserved).await.is_err());
+        assert_eq!(queue.size("default").await.unwrap(), 2);
+        let reserved = queue.reserve("default").await.unwrap().unwrap();
+        assert_eq!(reserved.attempts, 2);
+
+        queue.fail(&id, "boom").await.unwrap();
+        let failed = queue.failed("default").await.unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].last_error.as_deref(), Some("boom"));
+
+        assert_eq!(queue.requeue_all("default").await.unwrap(), 1);
+        let reserved = queue.reserve("default").await.unwrap().unwrap();
+        assert_eq!(reserved.attempts, 1);
+        queue.complete(&id).await.unwrap();
+
+        queue.clear("default").await.unwrap();
+        assert_eq!(queue.size("default").await.unwrap(), 0);
+    }
+}
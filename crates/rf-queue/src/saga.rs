@@ -0,0 +1,433 @@
+//! Sagas
+
+use crate::error::{QueueError, QueueResult};
+use crate::job::JobMetadata;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// Steps of a distributed operation, undone when one of them fails
+///
+/// Steps run one after the other. When a step fails for good, the
+/// compensations of the steps completed before it run in reverse order,
+/// e.g. refunding a payment after shipping failed. A compensation failing
+/// for good leaves the saga [`Failed`](SagaState::Failed) for someone to
+/// look into. The state is kept in a [`SagaStore`] after every step, so a
+/// saga continues where it stopped after a crash, see
+/// [`Bus::resume_sagas`](crate::Bus::resume_sagas).
+///
+/// Steps and compensations may run more than once, so make them
+/// idempotent. Dispatch sagas with [`Bus::saga`](crate::Bus::saga).
+///
+/// ```
+/// use rf_queue::{JobMetadata, Saga};
+/// # use rf_queue::{Job, QueueError};
+/// # use serde::{Deserialize, Serialize};
+/// # #[derive(Serialize, Deserialize)]
+/// # struct Step(String);
+/// # #[async_trait::async_trait]
+/// # impl Job for Step {
+/// #     async fn handle(&self) -> Result<(), QueueError> { Ok(()) }
+/// #     fn job_type(&self) -> &'static str { "step" }
+/// # }
+///
+/// # fn example() -> Result<(), QueueError> {
+/// # let step = |name: &str| JobMetadata::new(&Step(name.to_string()));
+/// let saga = Saga::new("checkout")
+///     .step("reserve stock", step("reserve")?)
+///     .compensate(step("release")?)
+///     .step("charge payment", step("charge")?)
+///     .compensate(step("refund")?)
+///     .step("ship order", step("ship")?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Saga {
+    name: String,
+    steps: Vec<SagaStep>,
+}
+
+impl Saga {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Run `action` next
+    pub fn step(mut self, name: impl Into<String>, action: JobMetadata) -> Self {
+        self.steps.push(SagaStep {
+            name: name.into(),
+            state: StepState::Pending,
+            error: None,
+            action,
+            compensation: None,
+        });
+        self
+    }
+
+    /// Undo the step added last with `compensation`; does nothing before
+    /// the first step
+    pub fn compensate(mut self, compensation: JobMetadata) -> Self {
+        if let Some(step) = self.steps.last_mut() {
+            step.compensation = Some(compensation);
+        }
+        self
+    }
+
+    /// Build the stored record
+    pub(crate) fn into_record(self) -> SagaRecord {
+        let now = Utc::now();
+        SagaRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: self.name,
+            state: SagaState::Running,
+            steps: self.steps,
+            error: None,
+            created_at: now,
+            updated_at: now,
+            finished_at: None,
+        }
+    }
+}
+
+/// State of a saga
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaState {
+    /// Steps are running
+    Running,
+    /// All steps succeeded
+    Completed,
+    /// A step failed, the completed ones are being undone
+    Compensating,
+    /// A step failed and the completed ones were undone
+    Compensated,
+    /// A compensation failed for good
+    Failed,
+}
+
+impl SagaState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SagaState::Running => "running",
+            SagaState::Completed => "completed",
+            SagaState::Compensating => "compensating",
+            SagaState::Compensated => "compensated",
+            SagaState::Failed => "failed",
+        }
+    }
+
+    /// Whether the saga is done, successfully or not
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, SagaState::Running | SagaState::Compensating)
+    }
+}
+
+/// State of a saga step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepState {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Compensating,
+    Compensated,
+    CompensationFailed,
+}
+
+/// Step of a dispatched saga
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SagaStep {
+    pub name: String,
+    pub state: StepState,
+    /// Error of the step or its compensation
+    pub error: Option<String>,
+    action: JobMetadata,
+    compensation: Option<JobMetadata>,
+}
+
+impl SagaStep {
+    pub fn has_compensation(&self) -> bool {
+        self.compensation.is_some()
+    }
+}
+
+/// State of a dispatched saga
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SagaRecord {
+    pub id: String,
+    pub name: String,
+    pub state: SagaState,
+    pub steps: Vec<SagaStep>,
+    /// Error of the step that made the saga compensate
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl SagaRecord {
+    pub fn is_finished(&self) -> bool {
+        self.state.is_finished()
+    }
+
+    /// Step running or being compensated right now
+    pub fn current_step(&self) -> Option<&SagaStep> {
+        self.steps
+            .iter()
+            .find(|step| matches!(step.state, StepState::Running | StepState::Compensating))
+    }
+
+    /// Steps completed so far, compensated ones included
+    pub fn completed_steps(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| {
+                matches!(
+                    step.state,
+                    StepState::Completed | StepState::Compensating | StepState::Compensated
+                )
+            })
+            .count()
+    }
+
+    /// Start the first step, returning its job
+    pub(crate) fn start(&mut self) -> Option<JobMetadata> {
+        self.run_step(0)
+    }
+
+    /// Record the outcome of job `job_id`, returning the job due next
+    ///
+    /// Outcomes of jobs the saga doesn't wait for, e.g. of a step that ran
+    /// twice, are ignored. Stores call this while holding the saga locked.
+    pub(crate) fn record(
+        &mut self,
+        job_id: &str,
+        outcome: Result<(), &str>,
+    ) -> Option<JobMetadata> {
+        let index = self.steps.iter().position(|step| match step.state {
+            StepState::Running => step.action.id == job_id,
+            StepState::Compensating => step
+                .compensation
+                .as_ref()
+                .is_some_and(|job| job.id == job_id),
+            _ => false,
+        })?;
+        self.updated_at = Utc::now();
+        let step = &mut self.steps[index];
+
+        match (step.state, outcome) {
+            (StepState::Running, Ok(())) => {
+                step.state = StepState::Completed;
+                self.run_step(index + 1)
+            }
+            (StepState::Running, Err(error)) => {
+                step.state = StepState::Failed;
+                step.error = Some(error.to_string());
+                self.error = Some(format!("{}: {}", step.name, error));
+                self.state = SagaState::Compensating;
+                self.compensate_next()
+            }
+            (_, Ok(())) => {
+                step.state = StepState::Compensated;
+                self.compensate_next()
+            }
+            (_, Err(error)) => {
+                step.state = StepState::CompensationFailed;
+                step.error = Some(error.to_string());
+                self.finish(SagaState::Failed);
+                None
+            }
+        }
+    }
+
+    /// Job the saga waits for, under a new id so a late outcome of the
+    /// previous one is ignored
+    pub(crate) fn redispatch(&mut self) -> Option<JobMetadata> {
+        let step = self
+            .steps
+            .iter_mut()
+            .find(|step| matches!(step.state, StepState::Running | StepState::Compensating))?;
+        let job = match step.state {
+            StepState::Running => &mut step.action,
+            _ => step.compensation.as_mut()?,
+        };
+        job.id = uuid::Uuid::new_v4().to_string();
+        let job = job.clone();
+        self.updated_at = Utc::now();
+        Some(self.job(job))
+    }
+
+    fn run_step(&mut self, index: usize) -> Option<JobMetadata> {
+        let Some(step) = self.steps.get_mut(index) else {
+            self.finish(SagaState::Completed);
+            return None;
+        };
+        step.state = StepState::Running;
+        let job = step.action.clone();
+        Some(self.job(job))
+    }
+
+    /// Start the compensation of the last completed step that has one
+    fn compensate_next(&mut self) -> Option<JobMetadata> {
+        let step = self
+            .steps
+            .iter_mut()
+            .rev()
+            .find(|step| step.state == StepState::Completed && step.compensation.is_some());
+        let Some(step) = step else {
+            self.finish(SagaState::Compensated);
+            return None;
+        };
+        step.state = StepState::Compensating;
+        let job = step.compensation.clone()?;
+        Some(self.job(job))
+    }
+
+    fn finish(&mut self, state: SagaState) {
+        self.state = state;
+        self.finished_at = Some(Utc::now());
+    }
+
+    fn job(&self, mut job: JobMetadata) -> JobMetadata {
+        job.saga_id = Some(self.id.clone());
+        job
+    }
+}
+
+/// Where saga state is kept
+///
+/// Shared by the dispatching application and all workers, so use a
+/// database backed store unless everything runs in one process.
+#[async_trait]
+pub trait SagaStore: Send + Sync {
+    /// Save a new saga
+    async fn create(&self, saga: &SagaRecord) -> QueueResult<()>;
+
+    /// Look up a saga
+    async fn find(&self, id: &str) -> QueueResult<Option<SagaRecord>>;
+
+    /// Sagas in `state`, or all of them, oldest first
+    async fn list(&self, state: Option<SagaState>) -> QueueResult<Vec<SagaRecord>>;
+
+    /// Apply `update` to the saga and save it
+    ///
+    /// Implementations lock the saga meanwhile, so concurrent workers
+    /// update it one after the other.
+    async fn update(
+        &self,
+        id: &str,
+        update: &mut (dyn for<'s> FnMut(&'s mut SagaRecord) + Send),
+    ) -> QueueResult<()>;
+}
+
+/// In-process saga store
+#[derive(Default)]
+pub struct MemorySagaStore {
+    sagas: Mutex<HashMap<String, SagaRecord>>,
+}
+
+impl MemorySagaStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SagaStore for MemorySagaStore {
+    async fn create(&self, saga: &SagaRecord) -> QueueResult<()> {
+        self.sagas
+            .lock()
+            .await
+            .insert(saga.id.clone(), saga.clone());
+        Ok(())
+    }
+
+    async fn find(&self, id: &str) -> QueueResult<Option<SagaRecord>> {
+        Ok(self.sagas.lock().await.get(id).cloned())
+    }
+
+    async fn list(&self, state: Option<SagaState>) -> QueueResult<Vec<SagaRecord>> {
+        let mut sagas: Vec<_> = self
+            .sagas
+            .lock()
+            .await
+            .values()
+            .filter(|saga| state.is_none_or(|state| saga.state == state))
+            .cloned()
+            .collect();
+        sagas.sort_by_key(|saga| saga.created_at);
+        Ok(sagas)
+    }
+
+    async fn update(
+        &self,
+        id: &str,
+        update: &mut (dyn for<'s> FnMut(&'s mut SagaRecord) + Send),
+    ) -> QueueResult<()> {
+        let mut sagas = self.sagas.lock().await;
+        let saga = sagas
+            .get_mut(id)
+            .ok_or_else(|| QueueError::SagaNotFound(id.to_string()))?;
+        update(saga);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job::Job;
+
+    #[derive(Serialize, Deserialize)]
+    struct Step;
+
+    #[async_trait]
+    impl Job for Step {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "step"
+        }
+    }
+
+    fn job() -> JobMetadata {
+        JobMetadata::new(&Step).unwrap()
+    }
+
+    #[test]
+    fn test_record_ignores_stale_outcomes() {
+        let mut saga = Saga::new("checkout")
+            .step("reserve", job())
+            .compensate(job())
+            .step("charge", job())
+            .into_record();
+
+        let reserve = saga.start().unwrap();
+        assert_eq!(reserve.saga_id.as_deref(), Some(saga.id.as_str()));
+        let charge = saga.record(&reserve.id, Ok(())).unwrap();
+        assert!(saga.record(&reserve.id, Ok(())).is_none());
+        assert_eq!(saga.current_step().unwrap().name, "charge");
+
+        // The charge job got lost; the one dispatched again counts
+        let again = saga.redispatch().unwrap();
+        assert_ne!(again.id, charge.id);
+        assert!(saga.record(&charge.id, Err("late")).is_none());
+
+        let release = saga.record(&again.id, Err("declined")).unwrap();
+        assert_eq!(saga.state, SagaState::Compensating);
+        assert_eq!(saga.error.as_deref(), Some("charge: declined"));
+        assert!(saga.record(&release.id, Ok(())).is_none());
+        assert_eq!(saga.state, SagaState::Compensated);
+        assert!(saga.is_finished() && saga.redispatch().is_none());
+        assert_eq!(saga.completed_steps(), 1);
+    }
+}
@@ -1,34 +1,56 @@
 //! Worker for processing queued jobs
 
+use crate::batch::BatchStore;
+use crate::bus::Bus;
 use crate::error::{QueueError, QueueResult};
+use crate::failed::{FailedJob, FailedJobStore};
 use crate::job::{Job, JobMetadata};
+use crate::monitor::{Attempt, QueueMonitor};
 use crate::queue::Queue;
-use std::collections::HashMap;
+use crate::saga::SagaStore;
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::watch;
 use tokio::time::sleep;
+use tracing::{Instrument, Span};
 
-type JobHandler = Arc<dyn Fn(Vec<u8>) -> JobHandlerFuture + Send + Sync>;
+type JobHandler = Arc<dyn Fn(&JobMetadata) -> Option<JobHandlerFuture> + Send + Sync>;
 type JobHandlerFuture = std::pin::Pin<Box<dyn std::future::Future<Output = Result<(), QueueError>> + Send>>;
+type SpanFactory = Arc<dyn Fn(&JobMetadata) -> Span + Send + Sync>;
 
 /// Worker for processing jobs from queue
+///
+/// Runs `concurrency` loops polling the queues in order. Failed attempts
+/// are retried after the job's backoff until its retries are used up,
+/// then the job goes to the dead-letter queue, or to the failed-job store
+/// when one is set. Chains continue, batches settle and sagas move on or
+/// compensate once their jobs are done, see [`Bus`].
 pub struct Worker {
     queue: Arc<dyn Queue>,
-    handlers: HashMap<String, JobHandler>,
+    bus: Bus,
+    monitor: Option<QueueMonitor>,
+    failed_jobs: Option<Arc<dyn FailedJobStore>>,
+    handlers: Vec<JobHandler>,
     concurrency: usize,
     queue_names: Vec<String>,
     poll_interval: Duration,
+    span: SpanFactory,
 }
 
 impl Worker {
     /// Create new worker
     pub fn new(queue: Arc<dyn Queue>) -> Self {
         Self {
+            bus: Bus::new(Arc::clone(&queue)),
             queue,
-            handlers: HashMap::new(),
+            monitor: None,
+            failed_jobs: None,
+            handlers: Vec::new(),
             concurrency: 1,
             queue_names: vec!["default".to_string()],
             poll_interval: Duration::from_secs(1),
+            span: Arc::new(job_span),
         }
     }
 
@@ -50,54 +72,121 @@ impl Worker {
         self
     }
 
-    /// Register a job handler
-    pub fn handle<J: Job + 'static>(mut self, handler: impl Fn(J) -> JobHandlerFuture + Send + Sync + 'static) -> Self {
-        let job_type = std::any::type_name::<J>();
+    /// Settle batches in `store`, the one the batches were dispatched with
+    pub fn batches(mut self, store: Arc<dyn BatchStore>) -> Self {
+        self.bus = self.bus.batches(store);
+        self
+    }
 
-        let handler_fn = Arc::new(move |data: Vec<u8>| -> JobHandlerFuture {
-            let job: J = match serde_json::from_slice(&data) {
-                Ok(j) => j,
-                Err(e) => {
-                    return Box::pin(async move {
-                        Err(QueueError::DeserializationError(e.to_string()))
-                    });
-                }
-            };
+    /// Move sagas along in `store`, the one the sagas were dispatched with
+    pub fn sagas(mut self, store: Arc<dyn SagaStore>) -> Self {
+        self.bus = self.bus.sagas(store);
+        self
+    }
 
-            handler(job)
+    /// Report every attempt to `monitor`
+    pub fn monitor(mut self, monitor: QueueMonitor) -> Self {
+        self.monitor = Some(monitor);
+        self
+    }
+
+    /// Keep jobs that used up their retries in `store` instead of the
+    /// queue backend's dead-letter queue
+    pub fn failed_jobs(mut self, store: Arc<dyn FailedJobStore>) -> Self {
+        self.failed_jobs = Some(store);
+        self
+    }
+
+    /// Set the span each job runs in, e.g. one continuing the trace of
+    /// the request that dispatched it
+    pub fn span(mut self, span: impl Fn(&JobMetadata) -> Span + Send + Sync + 'static) -> Self {
+        self.span = Arc::new(span);
+        self
+    }
+
+    /// Register a job handler
+    ///
+    /// The handler receives the jobs whose payload deserializes into `J`
+    /// and whose `job_type` matches.
+    pub fn handle<J: Job + 'static>(mut self, handler: impl Fn(J) -> JobHandlerFuture + Send + Sync + 'static) -> Self {
+        let handler_fn = Arc::new(move |metadata: &JobMetadata| -> Option<JobHandlerFuture> {
+            let job: J = serde_json::from_slice(&metadata.data).ok()?;
+            if job.job_type() != metadata.job_type {
+                return None;
+            }
+            Some(handler(job))
         });
 
-        self.handlers.insert(job_type.to_string(), handler_fn);
+        self.handlers.push(handler_fn);
         self
     }
 
+    /// Register a job type, running its [`Job::handle`]
+    pub fn register<J: Job + 'static>(self) -> Self {
+        self.handle(|job: J| Box::pin(async move { job.handle().await }))
+    }
+
     /// Start processing jobs
     pub async fn start(self) -> QueueResult<()> {
+        self.start_with_shutdown(std::future::pending()).await
+    }
+
+    /// Start processing jobs until `signal` completes
+    ///
+    /// Jobs in progress are finished before this returns.
+    ///
+    /// ```no_run
+    /// # use rf_queue::{MemoryQueue, Worker};
+    /// # use std::sync::Arc;
+    /// # async fn example() -> rf_queue::QueueResult<()> {
+    /// let worker = Worker::new(Arc::new(MemoryQueue::new())).concurrency(4);
+    /// worker
+    ///     .start_with_shutdown(async {
+    ///         let _ = tokio::signal::ctrl_c().await;
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn start_with_shutdown(
+        self,
+        signal: impl Future<Output = ()> + Send,
+    ) -> QueueResult<()> {
         let worker = Arc::new(self);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
         let mut handles = vec![];
 
         for _ in 0..worker.concurrency {
             let worker_clone = Arc::clone(&worker);
+            let shutdown = shutdown_rx.clone();
             let handle = tokio::spawn(async move {
-                worker_clone.run_loop().await
+                worker_clone.run_loop(shutdown).await
             });
             handles.push(handle);
         }
 
+        signal.await;
+        tracing::info!("Worker shutting down, waiting for running jobs");
+        let _ = shutdown_tx.send(true);
+
         // Wait for all workers
         for handle in handles {
             handle.await.map_err(|e| QueueError::WorkerError(e.to_string()))??;
         }
 
+        tracing::info!("Worker stopped");
         Ok(())
     }
 
-    async fn run_loop(&self) -> QueueResult<()> {
-        loop {
+    async fn run_loop(&self, mut shutdown: watch::Receiver<bool>) -> QueueResult<()> {
+        while !*shutdown.borrow() {
             let mut processed = false;
 
             // Try each queue
             for queue_name in &self.queue_names {
+                if *shutdown.borrow() {
+                    break;
+                }
                 if let Some(metadata) = self.queue.reserve(queue_name).await? {
                     processed = true;
                     self.process_job(metadata).await;
@@ -106,12 +195,22 @@ impl Worker {
 
             // Sleep if no jobs processed
             if !processed {
-                sleep(self.poll_interval).await;
+                tokio::select! {
+                    _ = sleep(self.poll_interval) => {}
+                    _ = shutdown.changed() => {}
+                }
             }
         }
+
+        Ok(())
+    }
+
+    async fn process_job(&self, metadata: JobMetadata) {
+        let span = (self.span)(&metadata);
+        self.run_job(metadata).instrument(span).await
     }
 
-    async fn process_job(&self, mut metadata: JobMetadata) {
+    async fn run_job(&self, mut metadata: JobMetadata) {
         let job_id = metadata.id.clone();
         let job_type = metadata.job_type.clone();
 
@@ -122,19 +221,39 @@ impl Worker {
             "Processing job"
         );
 
+        match self.bus.is_cancelled(&metadata).await {
+            Ok(false) => {}
+            Ok(true) => {
+                tracing::info!(job_id = %job_id, "Batch cancelled, skipping job");
+                let _ = self.queue.complete(&job_id).await;
+                self.follow_up(self.bus.job_succeeded(&metadata, true).await);
+                return;
+            }
+            Err(e) => tracing::warn!(job_id = %job_id, error = %e, "Failed to look up batch"),
+        }
+
         // Find handler
-        let handler = match self.handlers.get(&job_type) {
-            Some(h) => h,
+        let future = match self.handlers.iter().find_map(|handler| handler(&metadata)) {
+            Some(future) => future,
             None => {
                 tracing::error!(job_type = %job_type, "No handler registered for job type");
-                let _ = self.queue.fail(&job_id, "No handler registered").await;
+                self.dead_letter(&metadata, "No handler registered").await;
+                self.record(&metadata, Attempt::Failed, Duration::ZERO);
+                self.follow_up(self.bus.job_failed(&metadata).await);
                 return;
             }
         };
 
         // Execute job
         let start = std::time::Instant::now();
-        let result = handler(metadata.data.clone()).await;
+        let result = if metadata.timeout_secs > 0 {
+            let timeout = Duration::from_secs(metadata.timeout_secs);
+            tokio::time::timeout(timeout, future)
+                .await
+                .unwrap_or(Err(QueueError::Timeout(metadata.timeout_secs)))
+        } else {
+            future.await
+        };
         let duration = start.elapsed();
 
         match result {
@@ -145,6 +264,8 @@ impl Worker {
                     "Job completed successfully"
                 );
                 let _ = self.queue.complete(&job_id).await;
+                self.record(&metadata, Attempt::Completed, duration);
+                self.follow_up(self.bus.job_succeeded(&metadata, false).await);
             }
             Err(e) => {
                 let error_msg = e.to_string();
@@ -158,26 +279,74 @@ impl Worker {
                 metadata.mark_error(error_msg.clone());
 
                 if metadata.can_retry() {
+                    let delay = metadata.retry_delay();
                     tracing::info!(
                         job_id = %job_id,
                         attempt = metadata.attempts + 1,
                         max_retries = metadata.max_retries,
+                        delay_secs = delay.as_secs(),
                         "Retrying job"
                     );
+                    self.record(&metadata, Attempt::Retried, duration);
+                    if !delay.is_zero() {
+                        let _ = metadata.delay(delay);
+                    }
                     let _ = self.queue.retry(metadata).await;
                 } else {
                     tracing::error!(job_id = %job_id, "Max retries exceeded, job failed permanently");
-                    let _ = self.queue.fail(&job_id, &error_msg).await;
+                    self.dead_letter(&metadata, &error_msg).await;
+                    self.record(&metadata, Attempt::Failed, duration);
+                    self.follow_up(self.bus.job_failed(&metadata).await);
                 }
             }
         }
     }
+
+    /// Move a job that failed for good out of the queue
+    async fn dead_letter(&self, metadata: &JobMetadata, error: &str) {
+        if let Some(store) = &self.failed_jobs {
+            match store.log(FailedJob::new(metadata.clone(), error)).await {
+                Ok(()) => {
+                    let _ = self.queue.complete(&metadata.id).await;
+                    return;
+                }
+                Err(e) => {
+                    tracing::error!(job_id = %metadata.id, error = %e, "Failed to store failed job")
+                }
+            }
+        }
+        let _ = self.queue.fail(&metadata.id, error).await;
+    }
+
+    fn record(&self, metadata: &JobMetadata, attempt: Attempt, duration: Duration) {
+        if let Some(monitor) = &self.monitor {
+            monitor.record(metadata, attempt, duration);
+        }
+    }
+
+    /// Log failures to continue a chain or settle a batch; the job itself
+    /// is done either way
+    fn follow_up(&self, result: QueueResult<()>) {
+        if let Err(e) = result {
+            tracing::error!(error = %e, "Failed to dispatch follow-up jobs");
+        }
+    }
+}
+
+/// Default span of a job
+fn job_span(metadata: &JobMetadata) -> Span {
+    tracing::info_span!(
+        "queue.job",
+        job_id = %metadata.id,
+        job_type = %metadata.job_type,
+        queue = %metadata.queue,
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::job::Job;
+    use crate::job::Backoff;
     use crate::memory::MemoryQueue;
     use async_trait::async_trait;
     use serde::{Deserialize, Serialize};
@@ -205,6 +374,58 @@ mod tests {
         fn max_retries(&self) -> u32 {
             2
         }
+
+        fn timeout(&self) -> Duration {
+            Duration::from_secs(1)
+        }
+
+        fn backoff(&self) -> Backoff {
+            Backoff::None
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct SlowJob;
+
+    #[async_trait]
+    impl Job for SlowJob {
+        async fn handle(&self) -> Result<(), QueueError> {
+            sleep(Duration::from_secs(5)).await;
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "slow_job"
+        }
+
+        fn max_retries(&self) -> u32 {
+            1
+        }
+
+        fn timeout(&self) -> Duration {
+            Duration::from_secs(1)
+        }
+    }
+
+    /// Run a worker until `done` holds for the queue
+    async fn run_until<F>(worker: Worker, queue: Arc<MemoryQueue>, done: F)
+    where
+        F: Fn(usize, usize) -> bool + Send,
+    {
+        let signal = async move {
+            loop {
+                let size = queue.size("default").await.unwrap();
+                let failed = queue.failed("default").await.unwrap().len();
+                if done(size, failed) {
+                    break;
+                }
+                sleep(Duration::from_millis(5)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), worker.start_with_shutdown(signal))
+            .await
+            .expect("worker should shut down")
+            .unwrap();
     }
 
     #[tokio::test]
@@ -240,4 +461,105 @@ mod tests {
 
         assert!(*processed.lock().await, "Job should have been processed");
     }
+
+    #[tokio::test]
+    async fn test_failed_job_is_dead_lettered() {
+        let queue = Arc::new(MemoryQueue::new());
+        let job = TestJob {
+            message: "test".to_string(),
+            should_fail: true,
+        };
+        queue.push(JobMetadata::new(&job).unwrap()).await.unwrap();
+
+        let worker = Worker::new(Arc::clone(&queue) as Arc<dyn Queue>)
+            .concurrency(2)
+            .poll_interval(Duration::from_millis(5))
+            .register::<TestJob>();
+        run_until(worker, Arc::clone(&queue), |_, failed| failed == 1).await;
+
+        let failed = queue.failed("default").await.unwrap();
+        assert_eq!(failed[0].attempts, 2);
+        assert_eq!(
+            failed[0].last_error.as_deref(),
+            Some("Job execution failed: Intentional failure")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_job_is_stored() {
+        let queue = Arc::new(MemoryQueue::new());
+        let store = Arc::new(crate::failed::MemoryFailedJobStore::new());
+        let job = TestJob {
+            message: "test".to_string(),
+            should_fail: true,
+        };
+        queue.push(JobMetadata::new(&job).unwrap()).await.unwrap();
+
+        let worker = Worker::new(Arc::clone(&queue) as Arc<dyn Queue>)
+            .poll_interval(Duration::from_millis(5))
+            .failed_jobs(store.clone())
+            .register::<TestJob>();
+        let signal = {
+            let store = Arc::clone(&store);
+            async move {
+                while store.all().await.unwrap().is_empty() {
+                    sleep(Duration::from_millis(5)).await;
+                }
+            }
+        };
+        worker.start_with_shutdown(signal).await.unwrap();
+
+        let failed = store.all().await.unwrap();
+        assert_eq!(failed[0].attempts, 2);
+        assert_eq!(failed[0].exception, "Job execution failed: Intentional failure");
+        assert!(queue.failed("default").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_job_timeout_and_unknown_type() {
+        let queue = Arc::new(MemoryQueue::new());
+        queue.push(JobMetadata::new(&SlowJob).unwrap()).await.unwrap();
+        let job = TestJob {
+            message: "test".to_string(),
+            should_fail: false,
+        };
+        queue.push(JobMetadata::new(&job).unwrap()).await.unwrap();
+
+        let worker = Worker::new(Arc::clone(&queue) as Arc<dyn Queue>)
+            .poll_interval(Duration::from_millis(5))
+            .register::<SlowJob>();
+        run_until(worker, Arc::clone(&queue), |_, failed| failed == 2).await;
+
+        let failed = queue.failed("default").await.unwrap();
+        assert_eq!(failed[0].last_error.as_deref(), Some("Job timeout after 1s"));
+        assert_eq!(failed[1].job_type, "test_job");
+        assert_eq!(failed[1].last_error.as_deref(), Some("No handler registered"));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_finishes_running_job() {
+        let queue = Arc::new(MemoryQueue::new());
+        let job = TestJob {
+            message: "test".to_string(),
+            should_fail: false,
+        };
+        queue.push(JobMetadata::new(&job).unwrap()).await.unwrap();
+
+        let finished = Arc::new(tokio::sync::Mutex::new(false));
+        let finished_clone = Arc::clone(&finished);
+        let worker = Worker::new(Arc::clone(&queue) as Arc<dyn Queue>)
+            .poll_interval(Duration::from_millis(5))
+            .handle(move |_job: TestJob| {
+                let finished = Arc::clone(&finished_clone);
+                Box::pin(async move {
+                    sleep(Duration::from_millis(50)).await;
+                    *finished.lock().await = true;
+                    Ok(())
+                })
+            });
+
+        // Shut down as soon as the job is reserved
+        run_until(worker, Arc::clone(&queue), |size, _| size == 0).await;
+        assert!(*finished.lock().await, "Running job should have finished");
+    }
 }
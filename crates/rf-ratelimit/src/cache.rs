@@ -0,0 +1,501 @@
+//! Rate limiter on top of any rf-cache backend
+
+use crate::{Algorithm, LimitInfo, LimitResult, RateLimitConfig, RateLimitError, RateLimiter};
+use async_trait::async_trait;
+use rf_cache::Cache;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    time::Duration,
+};
+use tokio::sync::Mutex;
+
+/// Lock stripes serializing updates to the same key within this process
+const STRIPES: usize = 64;
+
+/// Attempts at storing an update before giving up, each lost to an update
+/// from another instance
+const MAX_ATTEMPTS: usize = 32;
+
+/// Rate limiter storing its state in an [`rf_cache::Cache`]
+///
+/// Works with every cache backend, so a shared Redis cache gives distributed
+/// limits without a dedicated Redis limiter. Supports both
+/// [`Algorithm::SlidingWindow`] and [`Algorithm::TokenBucket`].
+///
+/// Updates are optimistic: the new state is stored with
+/// [`Cache::compare_and_set`] (or [`Cache::add`] for a new key) and
+/// recomputed if another instance changed the state in between, so
+/// instances sharing a backend with atomic compare-and-set (memory, Redis)
+/// never lose updates. Within a process, updates to a key are serialized
+/// locally so they don't conflict with each other.
+///
+/// # Example
+///
+/// ```
+/// use rf_cache::MemoryCache;
+/// use rf_ratelimit::{Algorithm, CacheRateLimiter, RateLimitConfig, RateLimiter};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let config = RateLimitConfig::per_minute(60).with_algorithm(Algorithm::TokenBucket);
+/// let limiter = CacheRateLimiter::new(MemoryCache::new(), config);
+///
+/// let result = limiter.check("api-key:abc").await?;
+/// assert!(result.allowed);
+/// # Ok(())
+/// # }
+/// ```
+pub struct CacheRateLimiter<C: Cache> {
+    cache: C,
+    config: RateLimitConfig,
+    stripes: Vec<Mutex<()>>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct WindowState {
+    window_start: i64,
+    current: u64,
+    previous: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BucketState {
+    tokens: f64,
+    updated_at: i64,
+}
+
+/// Outcome of evaluating the state at one instant
+struct Decision {
+    allowed: bool,
+    remaining: u64,
+    reset_in_ms: i64,
+    retry_in_ms: Option<i64>,
+}
+
+impl<C: Cache> CacheRateLimiter<C> {
+    /// Create new cache-backed rate limiter
+    pub fn new(cache: C, config: RateLimitConfig) -> Self {
+        Self {
+            cache,
+            config,
+            stripes: (0..STRIPES).map(|_| Mutex::new(())).collect(),
+        }
+    }
+
+    fn get_key(&self, key: &str) -> String {
+        format!("{}:{}", self.config.key_prefix, key)
+    }
+
+    fn stripe(&self, key: &str) -> &Mutex<()> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        &self.stripes[hasher.finish() as usize % STRIPES]
+    }
+
+    fn window_ms(&self) -> i64 {
+        (self.config.window.as_millis() as i64).max(1)
+    }
+
+    /// State is kept for two windows: the sliding window needs the previous one
+    fn state_ttl(&self) -> Duration {
+        (self.config.window * 2).max(Duration::from_secs(1))
+    }
+
+    async fn evaluate(
+        &self,
+        key: &str,
+        now: i64,
+        consume: bool,
+    ) -> Result<Decision, RateLimitError> {
+        let cache_key = self.get_key(key);
+        let _guard = self.stripe(&cache_key).lock().await;
+
+        for _ in 0..MAX_ATTEMPTS {
+            if let Some(decision) = self.decide(&cache_key, now, consume).await? {
+                return Ok(decision);
+            }
+        }
+        Err(RateLimitError::BackendError(format!(
+            "too many concurrent updates of '{}'",
+            cache_key
+        )))
+    }
+
+    /// Read the state, apply the algorithm and store the new state, `None`
+    /// if the state changed before it was stored
+    async fn decide(
+        &self,
+        cache_key: &str,
+        now: i64,
+        consume: bool,
+    ) -> Result<Option<Decision>, RateLimitError> {
+        match self.config.algorithm {
+            Algorithm::SlidingWindow => {
+                let current: Option<WindowState> =
+                    self.cache.get(cache_key).await.map_err(backend_error)?;
+                let (state, decision) = sliding_window(
+                    current.clone().unwrap_or_default(),
+                    now,
+                    self.window_ms(),
+                    self.config.max_requests,
+                    consume,
+                );
+                if !(decision.allowed && consume) {
+                    return Ok(Some(decision));
+                }
+                let stored = self.store(cache_key, current.as_ref(), &state).await?;
+                Ok(stored.then_some(decision))
+            }
+            Algorithm::TokenBucket => {
+                let current: Option<BucketState> =
+                    self.cache.get(cache_key).await.map_err(backend_error)?;
+                let (state, decision) = token_bucket(
+                    current.clone(),
+                    now,
+                    self.window_ms(),
+                    self.config.max_requests,
+                    consume,
+                );
+                if !consume {
+                    return Ok(Some(decision));
+                }
+                let stored = self.store(cache_key, current.as_ref(), &state).await?;
+                Ok(stored.then_some(decision))
+            }
+        }
+    }
+
+    /// Replace `current` with `state`, returning whether it was still current
+    async fn store<T: Serialize + Sync>(
+        &self,
+        cache_key: &str,
+        current: Option<&T>,
+        state: &T,
+    ) -> Result<bool, RateLimitError> {
+        let stored = match current {
+            Some(current) => {
+                self.cache
+                    .compare_and_set(cache_key, current, state, self.state_ttl())
+                    .await
+            }
+            None => self.cache.add(cache_key, state, self.state_ttl()).await,
+        };
+        stored.map_err(backend_error)
+    }
+
+    fn result(&self, decision: Decision) -> LimitResult {
+        let reset_at = chrono::Utc::now() + chrono::Duration::milliseconds(decision.reset_in_ms);
+
+        LimitResult {
+            allowed: decision.allowed,
+            limit: self.config.max_requests,
+            remaining: decision.remaining,
+            reset_after: ceil_secs(decision.reset_in_ms),
+            reset_at,
+            retry_after: decision.retry_in_ms.map(|ms| ceil_secs(ms).max(1)),
+        }
+    }
+}
+
+#[async_trait]
+impl<C: Cache> RateLimiter for CacheRateLimiter<C> {
+    async fn check(&self, key: &str) -> Result<LimitResult, RateLimitError> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let decision = self.evaluate(key, now, true).await?;
+
+        tracing::debug!(
+            key = %key,
+            allowed = decision.allowed,
+            remaining = decision.remaining,
+            algorithm = ?self.config.algorithm,
+            "Rate limit check (cache)"
+        );
+
+        Ok(self.result(decision))
+    }
+
+    async fn reset(&self, key: &str) -> Result<(), RateLimitError> {
+        self.cache
+            .delete(&self.get_key(key))
+            .await
+            .map_err(backend_error)?;
+
+        tracing::debug!(key = %key, "Rate limit reset (cache)");
+        Ok(())
+    }
+
+    async fn info(&self, key: &str) -> Result<LimitInfo, RateLimitError> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let result = self.result(self.evaluate(key, now, false).await?);
+
+        Ok(LimitInfo {
+            limit: result.limit,
+            remaining: result.remaining,
+            reset_at: result.reset_at,
+        })
+    }
+}
+
+fn backend_error(e: rf_cache::CacheError) -> RateLimitError {
+    RateLimitError::BackendError(e.to_string())
+}
+
+fn ceil_secs(ms: i64) -> u64 {
+    (ms.max(0) as u64).div_ceil(1000)
+}
+
+/// Sliding window counter: the previous fixed window's count is weighted by
+/// how much of it still overlaps the sliding window
+fn sliding_window(
+    mut state: WindowState,
+    now: i64,
+    window: i64,
+    limit: u64,
+    consume: bool,
+) -> (WindowState, Decision) {
+    let window_start = now - now.rem_euclid(window);
+
+    if state.window_start != window_start {
+        state.previous = if state.window_start == window_start - window {
+            state.current
+        } else {
+            0
+        };
+        state.current = 0;
+        state.window_start = window_start;
+    }
+
+    let elapsed = now - window_start;
+    let weight = 1.0 - elapsed as f64 / window as f64;
+    let estimated = (state.previous as f64 * weight).floor() as u64 + state.current;
+
+    let allowed = estimated < limit;
+    if allowed && consume {
+        state.current += 1;
+    }
+    let used = estimated + u64::from(allowed && consume);
+
+    let reset_in_ms = window - elapsed;
+    let decision = Decision {
+        allowed,
+        remaining: limit.saturating_sub(used),
+        reset_in_ms,
+        retry_in_ms: (!allowed).then_some(reset_in_ms),
+    };
+    (state, decision)
+}
+
+/// Token bucket holding up to `limit` tokens, refilled at `limit / window`
+fn token_bucket(
+    state: Option<BucketState>,
+    now: i64,
+    window: i64,
+    limit: u64,
+    consume: bool,
+) -> (BucketState, Decision) {
+    let capacity = limit as f64;
+    let rate = capacity / window as f64;
+
+    let mut state = state.unwrap_or(BucketState {
+        tokens: capacity,
+        updated_at: now,
+    });
+    let elapsed = (now - state.updated_at).max(0) as f64;
+    state.tokens = (state.tokens + elapsed * rate).min(capacity);
+    state.updated_at = now;
+
+    let allowed = state.tokens >= 1.0;
+    if allowed && consume {
+        state.tokens -= 1.0;
+    }
+
+    let reset_in_ms = if rate > 0.0 {
+        ((capacity - state.tokens) / rate).ceil() as i64
+    } else {
+        window
+    };
+    let retry_in_ms = (!allowed && rate > 0.0).then(|| ((1.0 - state.tokens) / rate).ceil() as i64);
+
+    let decision = Decision {
+        allowed,
+        remaining: state.tokens.floor() as u64,
+        reset_in_ms,
+        retry_in_ms,
+    };
+    (state, decision)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rf_cache::{CacheResult, MemoryCache};
+    use serde::de::DeserializeOwned;
+
+    /// Shared cache with network-like latency on reads and writes
+    #[derive(Clone)]
+    struct SlowCache(MemoryCache);
+
+    #[async_trait]
+    impl Cache for SlowCache {
+        async fn get<T: DeserializeOwned + Send>(&self, key: &str) -> CacheResult<Option<T>> {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            self.0.get(key).await
+        }
+
+        async fn set<T: Serialize + Sync>(
+            &self,
+            key: &str,
+            value: &T,
+            ttl: Duration,
+        ) -> CacheResult<()> {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            self.0.set(key, value, ttl).await
+        }
+
+        async fn add<T: Serialize + Sync>(
+            &self,
+            key: &str,
+            value: &T,
+            ttl: Duration,
+        ) -> CacheResult<bool> {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            self.0.add(key, value, ttl).await
+        }
+
+        async fn compare_and_set<T: Serialize + Sync>(
+            &self,
+            key: &str,
+            current: &T,
+            value: &T,
+            ttl: Duration,
+        ) -> CacheResult<bool> {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            self.0.compare_and_set(key, current, value, ttl).await
+        }
+
+        async fn delete(&self, key: &str) -> CacheResult<()> {
+            self.0.delete(key).await
+        }
+
+        async fn exists(&self, key: &str) -> CacheResult<bool> {
+            self.0.exists(key).await
+        }
+
+        async fn flush(&self) -> CacheResult<()> {
+            self.0.flush().await
+        }
+    }
+
+    #[test]
+    fn test_sliding_window_weights_previous_window() {
+        let window = 1000;
+        let mut state = WindowState::default();
+
+        for _ in 0..10 {
+            let (next, decision) = sliding_window(state, 500, window, 10, true);
+            assert!(decision.allowed);
+            state = next;
+        }
+        let (next, decision) = sliding_window(state, 900, window, 10, true);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_in_ms, Some(100));
+        state = next;
+
+        // Halfway into the next window half of the previous count still applies
+        let (next, decision) = sliding_window(state, 1500, window, 10, true);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 4);
+        state = next;
+
+        // Two windows later everything has expired
+        let (_, decision) = sliding_window(state, 3100, window, 10, true);
+        assert_eq!(decision.remaining, 9);
+    }
+
+    #[test]
+    fn test_token_bucket_refills() {
+        let window = 1000;
+        let mut state = None;
+
+        for _ in 0..5 {
+            let (next, decision) = token_bucket(state, 0, window, 5, true);
+            assert!(decision.allowed);
+            state = Some(next);
+        }
+        let (next, decision) = token_bucket(state, 0, window, 5, true);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_in_ms, Some(200));
+
+        // One token is back after a fifth of the window
+        let (_, decision) = token_bucket(Some(next), 200, window, 5, true);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cache_rate_limiter() {
+        for algorithm in [Algorithm::SlidingWindow, Algorithm::TokenBucket] {
+            let config = RateLimitConfig::per_minute(3).with_algorithm(algorithm);
+            let limiter = CacheRateLimiter::new(MemoryCache::new(), config);
+
+            for _ in 0..3 {
+                assert!(limiter.check("user:1").await.unwrap().allowed);
+            }
+
+            let denied = limiter.check("user:1").await.unwrap();
+            assert!(!denied.allowed);
+            assert!(denied.retry_after.is_some());
+            assert!(limiter.check("user:2").await.unwrap().allowed);
+
+            // info doesn't consume
+            assert_eq!(limiter.info("user:2").await.unwrap().remaining, 2);
+            assert_eq!(limiter.info("user:2").await.unwrap().remaining, 2);
+
+            limiter.reset("user:1").await.unwrap();
+            assert!(limiter.check("user:1").await.unwrap().allowed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_partial_tokens_are_stored() {
+        let config = RateLimitConfig::per_minute(7).with_algorithm(Algorithm::TokenBucket);
+        let limiter = CacheRateLimiter::new(MemoryCache::new(), config);
+
+        for _ in 0..7 {
+            assert!(limiter.evaluate("user:1", 0, true).await.unwrap().allowed);
+        }
+        // Fractions of a token, like 0.000116.., must compare equal to
+        // the stored state once read back
+        for now in [1, 8, 10, 16] {
+            let decision = limiter.evaluate("user:1", now, true).await.unwrap();
+            assert!(!decision.allowed);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_instances_sharing_a_cache_do_not_lose_updates() {
+        for algorithm in [Algorithm::SlidingWindow, Algorithm::TokenBucket] {
+            let cache = SlowCache(MemoryCache::new());
+            let config = RateLimitConfig::per_minute(20).with_algorithm(algorithm);
+            // Two limiters with their own local locks, like two app instances
+            let instances = [
+                std::sync::Arc::new(CacheRateLimiter::new(cache.clone(), config.clone())),
+                std::sync::Arc::new(CacheRateLimiter::new(cache, config)),
+            ];
+
+            let tasks: Vec<_> = (0..60)
+                .map(|i| {
+                    let limiter = instances[i % 2].clone();
+                    tokio::spawn(async move { limiter.check("shared").await.unwrap().allowed })
+                })
+                .collect();
+
+            let mut allowed = 0;
+            for task in tasks {
+                allowed += usize::from(task.await.unwrap());
+            }
+            assert_eq!(allowed, 20);
+        }
+    }
+}
@@ -3,7 +3,40 @@
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+/// Rate limiting algorithm
+///
+/// Only [`crate::CacheRateLimiter`] supports both; the memory and Redis
+/// limiters always use an exact sliding window log.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    /// Weighted sliding window over the current and previous fixed window
+    #[default]
+    SlidingWindow,
+    /// Bucket of `max_requests` tokens refilled evenly over the window,
+    /// allowing short bursts
+    TokenBucket,
+}
+
 /// Rate limit configuration
+///
+/// Use the constructors (`per_minute`, `custom`, ...) and `with_*` methods,
+/// or fill in the rest of a struct literal with `..Default::default()`:
+///
+/// ```
+/// use rf_ratelimit::RateLimitConfig;
+/// use std::time::Duration;
+///
+/// let config = RateLimitConfig {
+///     max_requests: 5,
+///     window: Duration::from_secs(10),
+///     ..Default::default()
+/// };
+/// assert_eq!(config.key_prefix, "ratelimit");
+/// ```
+///
+/// Struct literals listing every field stopped compiling when `algorithm`
+/// was added; new fields will keep being added with defaults.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RateLimitConfig {
     /// Maximum number of requests
@@ -15,6 +48,10 @@ pub struct RateLimitConfig {
 
     /// Key prefix for storage
     pub key_prefix: String,
+
+    /// Limiting algorithm
+    #[serde(default)]
+    pub algorithm: Algorithm,
 }
 
 impl RateLimitConfig {
@@ -24,6 +61,7 @@ impl RateLimitConfig {
             max_requests,
             window: Duration::from_secs(60),
             key_prefix: "ratelimit".into(),
+            algorithm: Algorithm::default(),
         }
     }
 
@@ -33,6 +71,7 @@ impl RateLimitConfig {
             max_requests,
             window: Duration::from_secs(3600),
             key_prefix: "ratelimit".into(),
+            algorithm: Algorithm::default(),
         }
     }
 
@@ -42,6 +81,7 @@ impl RateLimitConfig {
             max_requests,
             window: Duration::from_secs(1),
             key_prefix: "ratelimit".into(),
+            algorithm: Algorithm::default(),
         }
     }
 
@@ -51,6 +91,7 @@ impl RateLimitConfig {
             max_requests,
             window,
             key_prefix: "ratelimit".into(),
+            algorithm: Algorithm::default(),
         }
     }
 
@@ -59,6 +100,12 @@ impl RateLimitConfig {
         self.key_prefix = prefix.into();
         self
     }
+
+    /// Set limiting algorithm
+    pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
 }
 
 impl Default for RateLimitConfig {
@@ -20,3 +20,10 @@ pub enum RateLimitError {
     #[error("Rate limit error: {0}")]
     Other(String),
 }
+
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(RateLimitError, {
+    Self::InvalidConfig(_) => (INTERNAL_SERVER_ERROR, "ratelimit.config"),
+    Self::BackendError(_) => (INTERNAL_SERVER_ERROR, "ratelimit.backend"),
+    Self::Other(_) => (INTERNAL_SERVER_ERROR, "ratelimit.internal"),
+});
@@ -4,10 +4,12 @@
 //!
 //! # Features
 //!
-//! - Sliding window algorithm
+//! - Sliding window and token bucket algorithms
 //! - Memory backend for development/testing
 //! - Redis backend for production (optional feature)
-//! - Axum middleware integration
+//! - Backend on any rf-cache store (optional `cache-backend` feature)
+//! - Axum middleware integration with IP, user id and API key strategies
+//! - Per-route limits
 //! - Rate limit headers (X-RateLimit-*)
 //!
 //! # Quick Start
@@ -32,6 +34,8 @@
 //! # }
 //! ```
 
+#[cfg(feature = "cache-backend")]
+mod cache;
 mod config;
 mod error;
 mod limiter;
@@ -41,11 +45,13 @@ pub mod middleware;
 #[cfg(feature = "redis-backend")]
 mod redis;
 
-pub use config::RateLimitConfig;
+#[cfg(feature = "cache-backend")]
+pub use cache::CacheRateLimiter;
+pub use config::{Algorithm, RateLimitConfig};
 pub use error::{RateLimitError, RateLimitResult};
 pub use limiter::{LimitInfo, LimitResult, RateLimiter};
 pub use memory::MemoryRateLimiter;
-pub use middleware::RateLimitLayer;
+pub use middleware::{KeyStrategy, RateLimitLayer, RateLimitService};
 
 #[cfg(feature = "redis-backend")]
 pub use redis::RedisRateLimiter;
@@ -1,36 +1,143 @@
 //! Axum middleware for rate limiting
+//!
+//! [`RateLimitLayer`] checks every request against a [`RateLimiter`],
+//! picked by path prefix, under a key chosen by a [`KeyStrategy`]. Allowed
+//! responses get `X-RateLimit-*` headers; rejected requests are answered
+//! with `429 Too Many Requests` and `Retry-After`. When the limiter's
+//! backend fails the request goes through, so an unreachable cache doesn't
+//! take the application down.
 
 use crate::{LimitResult, RateLimiter};
 use axum::{
-    extract::Request,
-    http::{HeaderMap, HeaderValue, StatusCode},
+    extract::{ConnectInfo, Request},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
-use std::sync::Arc;
+use rf_middleware::{Middleware, MiddlewareService};
+use std::{
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+use tower::Layer;
+
+type KeyExtractor = Arc<dyn Fn(&Request) -> Option<String> + Send + Sync>;
+
+/// How requests are grouped into rate limit buckets
+#[derive(Clone)]
+pub enum KeyStrategy {
+    /// Client IP from `ConnectInfo<SocketAddr>`
+    Ip,
+    /// Client IP from `X-Forwarded-For` / `X-Real-IP`, falling back to
+    /// `ConnectInfo`; only use behind a proxy that sets these headers
+    ForwardedIp,
+    /// Value of an API key header such as `X-Api-Key`
+    ApiKey(HeaderName),
+    /// Authenticated user id, resolved from the request (e.g. an auth
+    /// extension); anonymous requests fall back to the client IP
+    UserId(KeyExtractor),
+    /// A single bucket shared by all requests
+    Global,
+    /// Custom key resolution
+    Custom(KeyExtractor),
+}
+
+impl KeyStrategy {
+    /// Limit by the `X-Api-Key` header
+    pub fn api_key() -> Self {
+        Self::ApiKey(HeaderName::from_static("x-api-key"))
+    }
+
+    /// Limit by user id
+    ///
+    /// ```ignore
+    /// KeyStrategy::user_id(|req| req.extensions().get::<AuthUser>().map(|u| u.id.to_string()))
+    /// ```
+    pub fn user_id<F>(resolve: F) -> Self
+    where
+        F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+    {
+        Self::UserId(Arc::new(resolve))
+    }
+
+    /// Custom key resolution; `None` falls back to the client IP
+    pub fn custom<F>(resolve: F) -> Self
+    where
+        F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+    {
+        Self::Custom(Arc::new(resolve))
+    }
+
+    /// Bucket key for a request, namespaced by strategy
+    pub fn key(&self, req: &Request) -> String {
+        let key = match self {
+            Self::Ip => connect_ip(req).map(|ip| format!("ip:{}", ip)),
+            Self::ForwardedIp => forwarded_ip(req)
+                .or_else(|| connect_ip(req))
+                .map(|ip| format!("ip:{}", ip)),
+            Self::ApiKey(header) => req
+                .headers()
+                .get(header)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| format!("key:{}", v)),
+            Self::UserId(resolve) => resolve(req).map(|id| format!("user:{}", id)),
+            Self::Global => Some("global".to_string()),
+            Self::Custom(resolve) => resolve(req),
+        };
+
+        key.or_else(|| connect_ip(req).map(|ip| format!("ip:{}", ip)))
+            .unwrap_or_else(|| "ip:unknown".to_string())
+    }
+}
+
+fn connect_ip(req: &Request) -> Option<IpAddr> {
+    req.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip())
+}
+
+fn forwarded_ip(req: &Request) -> Option<IpAddr> {
+    let headers = req.headers();
+    headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .or_else(|| headers.get("x-real-ip").and_then(|v| v.to_str().ok()))
+        .and_then(|v| v.trim().parse().ok())
+}
 
 /// Rate limit middleware layer
 ///
+/// Usable directly as a tower layer or through `axum::middleware::from_fn`.
+/// Requests are keyed by client IP unless another [`KeyStrategy`] is set;
+/// path prefixes can get their own limiter with [`RateLimitLayer::route`].
+///
 /// # Example
 ///
 /// ```ignore
 /// use rf_ratelimit::*;
-/// use axum::{Router, routing::get};
+/// use axum::{Router, routing::{get, post}};
 ///
-/// let config = RateLimitConfig::per_minute(60);
-/// let limiter = Arc::new(MemoryRateLimiter::new(config));
-/// let layer = RateLimitLayer::new(limiter);
+/// let api = Arc::new(MemoryRateLimiter::new(RateLimitConfig::per_minute(60)));
+/// let login = Arc::new(MemoryRateLimiter::new(
+///     RateLimitConfig::per_minute(5).with_prefix("ratelimit:login"),
+/// ));
 ///
 /// let app = Router::new()
 ///     .route("/api/users", get(get_users))
-///     .layer(axum::middleware::from_fn(move |req, next| {
-///         layer.clone().handle(req, next)
-///     }));
+///     .route("/login", post(login_handler))
+///     .layer(
+///         RateLimitLayer::new(api)
+///             .with_strategy(KeyStrategy::api_key())
+///             .route("/login", login),
+///     );
 /// ```
 #[derive(Clone)]
 pub struct RateLimitLayer {
     limiter: Arc<dyn RateLimiter>,
     key_extractor: Arc<dyn Fn(&Request) -> String + Send + Sync>,
+    routes: Arc<Vec<(String, Arc<dyn RateLimiter>)>>,
 }
 
 impl RateLimitLayer {
@@ -38,11 +145,8 @@ impl RateLimitLayer {
     pub fn new(limiter: Arc<dyn RateLimiter>) -> Self {
         Self {
             limiter,
-            key_extractor: Arc::new(|_req| {
-                // For now, use a default key
-                // In production, extract from ConnectInfo<SocketAddr>
-                "default".to_string()
-            }),
+            key_extractor: Arc::new(|req| KeyStrategy::Ip.key(req)),
+            routes: Arc::new(Vec::new()),
         }
     }
 
@@ -55,15 +159,48 @@ impl RateLimitLayer {
         self
     }
 
+    /// Set key extraction strategy
+    pub fn with_strategy(self, strategy: KeyStrategy) -> Self {
+        self.with_key_extractor(move |req| strategy.key(req))
+    }
+
+    /// Use a different limiter for paths under `prefix`
+    ///
+    /// Prefixes match whole path segments (`/api` covers `/api` and
+    /// `/api/users`, not `/apix`) and the longest matching prefix wins. Give
+    /// each limiter its own key prefix so routes don't share counters.
+    pub fn route(mut self, prefix: impl Into<String>, limiter: Arc<dyn RateLimiter>) -> Self {
+        Arc::make_mut(&mut self.routes).push((prefix.into(), limiter));
+        self
+    }
+
+    fn limiter_for(&self, path: &str) -> &Arc<dyn RateLimiter> {
+        self.routes
+            .iter()
+            .filter(|(prefix, _)| matches_prefix(path, prefix))
+            .max_by_key(|(prefix, _)| prefix.trim_end_matches('/').len())
+            .map(|(_, limiter)| limiter)
+            .unwrap_or(&self.limiter)
+    }
+
     /// Handle middleware request
     pub async fn handle(self, req: Request, next: Next) -> Response {
+        self.process(req, |req| next.run(req)).await
+    }
+
+    async fn process<F, Fut>(&self, req: Request, next: F) -> Response
+    where
+        F: FnOnce(Request) -> Fut,
+        Fut: Future<Output = Response>,
+    {
         let key = (self.key_extractor)(&req);
+        let limiter = self.limiter_for(req.uri().path());
 
-        match self.limiter.check(&key).await {
+        match limiter.check(&key).await {
             Ok(result) => {
                 if result.allowed {
                     // Request allowed - add headers and continue
-                    let mut response = next.run(req).await;
+                    let mut response = next(req).await;
                     add_rate_limit_headers(response.headers_mut(), &result);
                     response
                 } else {
@@ -74,12 +211,29 @@ impl RateLimitLayer {
             Err(e) => {
                 tracing::error!("Rate limit check failed: {}", e);
                 // On error, allow request but log
-                next.run(req).await
+                next(req).await
             }
         }
     }
 }
 
+impl Middleware for RateLimitLayer {
+    async fn handle(self, req: Request, next: rf_middleware::Next) -> Response {
+        self.process(req, |req| next.run(req)).await
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+/// Service created by [`RateLimitLayer`]
+pub type RateLimitService<S> = MiddlewareService<RateLimitLayer, S>;
+
 /// Add rate limit headers to response
 fn add_rate_limit_headers(headers: &mut HeaderMap, result: &LimitResult) {
     if let Ok(value) = HeaderValue::from_str(&result.limit.to_string()) {
@@ -117,9 +271,21 @@ fn rate_limit_exceeded_response(result: &LimitResult) -> Response {
     response
 }
 
+/// Whether `path` is `prefix` itself or lies below it
+fn matches_prefix(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{MemoryRateLimiter, RateLimitConfig};
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
 
     #[tokio::test]
     async fn test_rate_limit_headers() {
@@ -140,6 +306,88 @@ mod tests {
         assert!(headers.contains_key("X-RateLimit-Reset"));
     }
 
+    #[test]
+    fn test_key_strategies() {
+        let req = || {
+            let mut req = Request::builder()
+                .header("x-api-key", "abc")
+                .header("x-forwarded-for", "203.0.113.7, 10.0.0.1")
+                .body(Body::empty())
+                .unwrap();
+            req.extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 1234))));
+            req
+        };
+
+        assert_eq!(KeyStrategy::Ip.key(&req()), "ip:10.0.0.1");
+        assert_eq!(KeyStrategy::ForwardedIp.key(&req()), "ip:203.0.113.7");
+        assert_eq!(KeyStrategy::api_key().key(&req()), "key:abc");
+        assert_eq!(KeyStrategy::Global.key(&req()), "global");
+
+        // Anonymous users fall back to their IP
+        let user = KeyStrategy::user_id(|req| {
+            req.headers().get("x-user").and_then(|v| v.to_str().ok()).map(String::from)
+        });
+        assert_eq!(user.key(&req()), "ip:10.0.0.1");
+    }
+
+    #[tokio::test]
+    async fn test_layer_applies_route_limits() {
+        let default = Arc::new(MemoryRateLimiter::new(RateLimitConfig::per_minute(10)));
+        let login = Arc::new(MemoryRateLimiter::new(
+            RateLimitConfig::per_minute(1).with_prefix("ratelimit:login"),
+        ));
+
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route("/login", get(|| async { "ok" }))
+            .layer(
+                RateLimitLayer::new(default)
+                    .with_strategy(KeyStrategy::Global)
+                    .route("/login", login),
+            );
+
+        let call = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let first = call("/login").await;
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(first.headers().get("X-RateLimit-Limit").unwrap(), "1");
+
+        let second = call("/login").await;
+        assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(second.headers().contains_key("Retry-After"));
+
+        assert_eq!(call("/").await.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_route_prefixes_match_segments() {
+        let limiter = || -> Arc<dyn RateLimiter> {
+            Arc::new(MemoryRateLimiter::new(RateLimitConfig::per_minute(10)))
+        };
+        let (default, api, admin, root) = (limiter(), limiter(), limiter(), limiter());
+        let layer = RateLimitLayer::new(default)
+            .route("/api/", api.clone())
+            .route("/api/admin", admin.clone())
+            .route("/", root.clone());
+        let uses =
+            |path, limiter: &Arc<dyn RateLimiter>| Arc::ptr_eq(layer.limiter_for(path), limiter);
+
+        assert!(uses("/api", &api));
+        assert!(uses("/api/users", &api));
+        assert!(uses("/api/admin/users", &admin));
+        assert!(uses("/api/administrators", &api));
+        assert!(uses("/apix", &root));
+        assert!(uses("/", &root));
+    }
+
     #[tokio::test]
     async fn test_rate_limit_exceeded_response() {
         let result = LimitResult {
@@ -13,7 +13,7 @@ use redis::AsyncCommands;
 /// # Example
 ///
 /// ```no_run
-/// use rf_ratelimit::{RedisRateLimiter, RateLimitConfig};
+/// use rf_ratelimit::{RateLimitConfig, RateLimiter, RedisRateLimiter};
 ///
 /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
 /// let config = RateLimitConfig::per_minute(60);
@@ -206,6 +206,7 @@ mod tests {
             max_requests: 5,
             window: Duration::from_secs(60),
             key_prefix: "test".to_string(),
+            ..Default::default()
         };
 
         let limiter = RedisRateLimiter::new("redis://localhost", config)
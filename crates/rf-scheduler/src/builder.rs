@@ -0,0 +1,170 @@
+//! Fluent task registration
+
+use crate::schedule::{parse_time, CatchUp, TaskSchedule};
+use crate::{Scheduler, SchedulerError, SchedulerResult, Task, TaskOptions};
+use chrono::Weekday;
+use chrono_tz::Tz;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Registration of a task, started with [`Scheduler::job`]
+///
+/// The task is added to the scheduler when the builder is dropped, i.e. at
+/// the end of the statement. An invalid schedule is reported by
+/// [`Scheduler::start`].
+///
+/// ```no_run
+/// # use rf_scheduler::{Scheduler, Task};
+/// # use async_trait::async_trait;
+/// # struct CleanupTask;
+/// # #[async_trait]
+/// # impl Task for CleanupTask {
+/// #     async fn run(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> { Ok(()) }
+/// #     fn name(&self) -> &str { "cleanup" }
+/// # }
+/// # let scheduler = Scheduler::new();
+/// use std::time::Duration;
+///
+/// scheduler
+///     .job(CleanupTask)
+///     .daily_at("03:00")
+///     .timezone(chrono_tz::Europe::Zurich)
+///     .jitter(Duration::from_secs(30));
+/// ```
+pub struct TaskBuilder<'a> {
+    scheduler: &'a Scheduler,
+    task: Arc<dyn Task>,
+    schedule: Option<SchedulerResult<TaskSchedule>>,
+    timezone: Option<Tz>,
+    jitter: Duration,
+    prevent_overlap: bool,
+    catch_up: CatchUp,
+}
+
+impl<'a> TaskBuilder<'a> {
+    pub(crate) fn new(scheduler: &'a Scheduler, task: Arc<dyn Task>) -> Self {
+        let prevent_overlap = task.prevent_overlap();
+        let catch_up = task.catch_up();
+        Self {
+            scheduler,
+            task,
+            schedule: None,
+            timezone: None,
+            jitter: Duration::ZERO,
+            prevent_overlap,
+            catch_up,
+        }
+    }
+
+    /// Run on a cron expression or interval, see [`TaskSchedule::parse`]
+    pub fn cron(mut self, expression: &str) -> Self {
+        self.schedule = Some(TaskSchedule::parse(expression));
+        self
+    }
+
+    /// Run every `interval`
+    pub fn every(mut self, interval: Duration) -> Self {
+        self.schedule = Some(TaskSchedule::every(interval));
+        self
+    }
+
+    pub fn every_minute(self) -> Self {
+        self.cron("* * * * *")
+    }
+
+    pub fn every_five_minutes(self) -> Self {
+        self.cron("*/5 * * * *")
+    }
+
+    pub fn every_fifteen_minutes(self) -> Self {
+        self.cron("*/15 * * * *")
+    }
+
+    pub fn hourly(self) -> Self {
+        self.hourly_at(0)
+    }
+
+    /// Run every hour at `minute`
+    pub fn hourly_at(self, minute: u32) -> Self {
+        self.cron(&format!("{} * * * *", minute))
+    }
+
+    pub fn daily(self) -> Self {
+        self.daily_at("00:00")
+    }
+
+    /// Run every day at `time` (HH:MM format)
+    pub fn daily_at(self, time: &str) -> Self {
+        self.at(time, "* * *")
+    }
+
+    /// Run every week on `day` at `time` (HH:MM format)
+    pub fn weekly_on(self, day: Weekday, time: &str) -> Self {
+        self.at(time, &format!("* * {}", day))
+    }
+
+    /// Run every month on `day` at `time` (HH:MM format)
+    pub fn monthly_on(self, day: u32, time: &str) -> Self {
+        self.at(time, &format!("{} * *", day))
+    }
+
+    fn at(mut self, time: &str, days: &str) -> Self {
+        match parse_time(time) {
+            Ok((hour, minute)) => self.cron(&format!("{} {} {}", minute, hour, days)),
+            Err(e) => {
+                self.schedule = Some(Err(e));
+                self
+            }
+        }
+    }
+
+    /// Read the schedule in `timezone` instead of the scheduler's
+    pub fn timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = Some(timezone);
+        self
+    }
+
+    /// Delay each run by a random time up to `max`, spreading the load of
+    /// many instances or tasks sharing a schedule
+    pub fn jitter(mut self, max: Duration) -> Self {
+        self.jitter = max;
+        self
+    }
+
+    /// Skip runs while the previous one is still going
+    pub fn without_overlapping(mut self) -> Self {
+        self.prevent_overlap = true;
+        self
+    }
+
+    /// Allow runs to overlap
+    pub fn allow_overlapping(mut self) -> Self {
+        self.prevent_overlap = false;
+        self
+    }
+
+    /// Handle missed occurrences with `policy` instead of the task's
+    pub fn catch_up(mut self, policy: CatchUp) -> Self {
+        self.catch_up = policy;
+        self
+    }
+}
+
+impl Drop for TaskBuilder<'_> {
+    fn drop(&mut self) {
+        let schedule = self.schedule.take().unwrap_or_else(|| {
+            Err(SchedulerError::InvalidCron(format!(
+                "No schedule given for task {}",
+                self.task.name()
+            )))
+        });
+        let options = schedule.map(|schedule| TaskOptions {
+            schedule,
+            timezone: self.timezone,
+            jitter: self.jitter,
+            prevent_overlap: self.prevent_overlap,
+            catch_up: self.catch_up,
+        });
+        self.scheduler.register(Arc::clone(&self.task), options);
+    }
+}
@@ -0,0 +1,313 @@
+//! Run history of scheduled tasks
+
+use crate::{SchedulerError, SchedulerResult};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use tokio::sync::Mutex;
+
+/// One run, or skipped run, of a task
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskRun {
+    pub task: String,
+    /// The occurrence of the schedule this run belongs to
+    pub scheduled_for: DateTime<Utc>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub outcome: RunOutcome,
+    /// Captured with [`output`](crate::output) while the task ran
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+impl TaskRun {
+    pub fn duration(&self) -> chrono::Duration {
+        self.finished_at - self.started_at
+    }
+}
+
+/// How a run ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum RunOutcome {
+    Succeeded,
+    Failed(String),
+    /// Not run, e.g. because the previous run is still going or another
+    /// instance took the occurrence
+    Skipped(String),
+}
+
+impl RunOutcome {
+    pub fn status(&self) -> RunStatus {
+        match self {
+            RunOutcome::Succeeded => RunStatus::Succeeded,
+            RunOutcome::Failed(_) => RunStatus::Failed,
+            RunOutcome::Skipped(_) => RunStatus::Skipped,
+        }
+    }
+
+    /// Why the run failed or was skipped
+    pub fn reason(&self) -> Option<&str> {
+        match self {
+            RunOutcome::Succeeded => None,
+            RunOutcome::Failed(reason) | RunOutcome::Skipped(reason) => Some(reason),
+        }
+    }
+}
+
+/// [`RunOutcome`] without the reason
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+impl RunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunStatus::Succeeded => "succeeded",
+            RunStatus::Failed => "failed",
+            RunStatus::Skipped => "skipped",
+        }
+    }
+}
+
+impl FromStr for RunStatus {
+    type Err = SchedulerError;
+
+    fn from_str(status: &str) -> SchedulerResult<Self> {
+        match status {
+            "succeeded" => Ok(RunStatus::Succeeded),
+            "failed" => Ok(RunStatus::Failed),
+            "skipped" => Ok(RunStatus::Skipped),
+            _ => Err(SchedulerError::History(format!(
+                "Unknown run status: {}",
+                status
+            ))),
+        }
+    }
+}
+
+/// Filter of [`RunHistory::query`]
+///
+/// Matches the 50 most recent runs of all tasks by default.
+///
+/// ```
+/// use rf_scheduler::{HistoryQuery, RunStatus};
+///
+/// let failed_reports = HistoryQuery::new()
+///     .task("report")
+///     .status(RunStatus::Failed)
+///     .limit(10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct HistoryQuery {
+    pub task: Option<String>,
+    pub status: Option<RunStatus>,
+    /// Earliest occurrence
+    pub since: Option<DateTime<Utc>>,
+    /// Latest occurrence
+    pub until: Option<DateTime<Utc>>,
+    pub limit: usize,
+}
+
+impl HistoryQuery {
+    pub fn new() -> Self {
+        Self {
+            task: None,
+            status: None,
+            since: None,
+            until: None,
+            limit: 50,
+        }
+    }
+
+    /// Only runs of `task`
+    pub fn task(mut self, task: impl Into<String>) -> Self {
+        self.task = Some(task.into());
+        self
+    }
+
+    /// Only runs with `status`
+    pub fn status(mut self, status: RunStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Only runs scheduled at or after `since`
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Only runs scheduled at or before `until`
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Return at most `limit` runs
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Whether `run` passes the filter
+    pub fn matches(&self, run: &TaskRun) -> bool {
+        self.task.as_ref().is_none_or(|task| *task == run.task)
+            && self
+                .status
+                .is_none_or(|status| status == run.outcome.status())
+            && self.since.is_none_or(|since| run.scheduled_for >= since)
+            && self.until.is_none_or(|until| run.scheduled_for <= until)
+    }
+}
+
+impl Default for HistoryQuery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Storage for task runs
+#[async_trait]
+pub trait RunHistory: Send + Sync {
+    /// Record a finished or skipped run
+    async fn record(&self, run: TaskRun);
+
+    /// Most recent runs of a task, newest first
+    async fn recent(&self, task: &str, limit: usize) -> Vec<TaskRun>;
+
+    /// Runs matching `query`, latest occurrence first
+    ///
+    /// Runs of the same occurrence and start time are ordered by task name,
+    /// descending, so results are stable across backends.
+    async fn query(&self, query: &HistoryQuery) -> Vec<TaskRun>;
+}
+
+/// In-memory run history keeping the last runs of each task
+pub struct MemoryRunHistory {
+    capacity: usize,
+    runs: Mutex<HashMap<String, VecDeque<TaskRun>>>,
+}
+
+impl MemoryRunHistory {
+    /// Keep up to `capacity` runs per task
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            runs: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for MemoryRunHistory {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+#[async_trait]
+impl RunHistory for MemoryRunHistory {
+    async fn record(&self, run: TaskRun) {
+        let mut runs = self.runs.lock().await;
+        let task_runs = runs.entry(run.task.clone()).or_default();
+        task_runs.push_front(run);
+        task_runs.truncate(self.capacity);
+    }
+
+    async fn recent(&self, task: &str, limit: usize) -> Vec<TaskRun> {
+        let runs = self.runs.lock().await;
+        runs.get(task)
+            .map(|task_runs| task_runs.iter().take(limit).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    async fn query(&self, query: &HistoryQuery) -> Vec<TaskRun> {
+        let runs = self.runs.lock().await;
+        let mut matching: Vec<_> = runs
+            .values()
+            .flatten()
+            .filter(|run| query.matches(run))
+            .cloned()
+            .collect();
+        matching.sort_by(|a, b| {
+            (b.scheduled_for, b.started_at, &b.task).cmp(&(a.scheduled_for, a.started_at, &a.task))
+        });
+        matching.truncate(query.limit);
+        matching
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(task: &str, minute: i64) -> TaskRun {
+        let at = DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::minutes(minute);
+        TaskRun {
+            task: task.to_string(),
+            scheduled_for: at,
+            started_at: at,
+            finished_at: at,
+            outcome: RunOutcome::Succeeded,
+            output: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_history() {
+        let history = MemoryRunHistory::new(2);
+        history.record(run("cleanup", 1)).await;
+        history.record(run("cleanup", 2)).await;
+        history.record(run("cleanup", 3)).await;
+        history.record(run("report", 1)).await;
+
+        let recent = history.recent("cleanup", 10).await;
+        assert_eq!(recent, vec![run("cleanup", 3), run("cleanup", 2)]);
+        assert_eq!(history.recent("cleanup", 1).await.len(), 1);
+        assert!(history.recent("unknown", 10).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_query() {
+        let history = MemoryRunHistory::default();
+        for minute in 1..=3 {
+            history.record(run("cleanup", minute)).await;
+        }
+        let mut failed = run("report", 2);
+        failed.outcome = RunOutcome::Failed("Mailer down".to_string());
+        history.record(failed.clone()).await;
+
+        let tasks = |runs: Vec<TaskRun>| -> Vec<(String, i64)> {
+            runs.into_iter()
+                .map(|run| (run.task, run.scheduled_for.timestamp() / 60))
+                .collect()
+        };
+        let all = history.query(&HistoryQuery::new().limit(3)).await;
+        assert_eq!(
+            tasks(all),
+            [
+                ("cleanup".into(), 3),
+                ("report".into(), 2),
+                ("cleanup".into(), 2)
+            ]
+        );
+
+        let since = DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::minutes(2);
+        let query = HistoryQuery::new().task("cleanup").since(since);
+        assert_eq!(
+            tasks(history.query(&query).await),
+            [("cleanup".into(), 3), ("cleanup".into(), 2)]
+        );
+
+        let query = HistoryQuery::new().status(RunStatus::Failed);
+        assert_eq!(history.query(&query).await, vec![failed]);
+        assert_eq!("failed".parse::<RunStatus>().unwrap(), RunStatus::Failed);
+        assert!("lost".parse::<RunStatus>().is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Leader election between scheduler instances
+
+use crate::SchedulerResult;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Lease on the leadership of the scheduler instances sharing it
+///
+/// With [`Scheduler::leader_election`](crate::Scheduler::leader_election)
+/// only the instance holding the lease runs tasks. The leader renews its
+/// lease while it runs; when it dies the lease expires and another instance
+/// takes over.
+#[async_trait]
+pub trait LeaderLease: Send + Sync {
+    /// Take the lease for `owner` unless another owner holds it, or renew it
+    /// if `owner` does, returning whether `owner` leads; the lease expires
+    /// after `ttl` unless renewed
+    async fn lead(&self, owner: &str, ttl: Duration) -> SchedulerResult<bool>;
+
+    /// Give up the lease if `owner` holds it
+    async fn resign(&self, owner: &str) -> SchedulerResult<()>;
+}
+
+/// Whether a scheduler instance leads, see [`Scheduler::leadership`]
+///
+/// [`Scheduler::leadership`]: crate::Scheduler::leadership
+#[derive(Clone, Default)]
+pub struct Leadership {
+    status: Arc<Mutex<LeaderStatus>>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<LeaderMetrics>,
+}
+
+/// Leadership of an instance at one point in time
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LeaderStatus {
+    pub leader: bool,
+    /// When the instance last became leader, while it leads
+    pub since: Option<DateTime<Utc>>,
+    /// How often the instance became or stopped being leader
+    pub changes: u64,
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Clone)]
+struct LeaderMetrics {
+    leader: prometheus::Gauge,
+    changes: prometheus::Counter,
+}
+
+impl Leadership {
+    pub fn is_leader(&self) -> bool {
+        self.status().leader
+    }
+
+    pub fn status(&self) -> LeaderStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Record whether the instance leads at `now`, returning whether that
+    /// changed
+    pub(crate) fn update(&self, leader: bool, now: DateTime<Utc>) -> bool {
+        let mut status = self.status.lock().unwrap();
+        if status.leader == leader {
+            return false;
+        }
+        status.leader = leader;
+        status.since = leader.then_some(now);
+        status.changes += 1;
+
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.leader.set(if leader { 1.0 } else { 0.0 });
+            metrics.changes.inc();
+        }
+        true
+    }
+
+    /// Export the status as `scheduler_leader` (1 while leading) and
+    /// `scheduler_leader_changes_total`
+    #[cfg(feature = "metrics")]
+    pub(crate) fn register(&mut self, metrics: &rf_metrics::Metrics) {
+        let registered = metrics
+            .gauge(
+                "scheduler_leader",
+                "Whether this instance is the scheduler leader",
+            )
+            .and_then(|leader| {
+                let changes = metrics.counter(
+                    "scheduler_leader_changes_total",
+                    "Times this instance became or stopped being the scheduler leader",
+                )?;
+                Ok(LeaderMetrics { leader, changes })
+            });
+        match registered {
+            Ok(registered) => self.metrics = Some(registered),
+            Err(e) => tracing::error!(error = %e, "Failed to register scheduler metrics"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leadership_changes() {
+        let leadership = Leadership::default();
+        let now = Utc::now();
+        assert!(!leadership.update(false, now));
+        assert!(leadership.update(true, now));
+        assert!(!leadership.update(true, now));
+        assert_eq!(
+            leadership.clone().status(),
+            LeaderStatus {
+                leader: true,
+                since: Some(now),
+                changes: 1,
+            }
+        );
+
+        assert!(leadership.update(false, now));
+        assert_eq!(leadership.status().since, None);
+        assert_eq!(leadership.status().changes, 2);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_leadership_metrics() {
+        let metrics = rf_metrics::Metrics::new();
+        let mut leadership = Leadership::default();
+        leadership.register(&metrics);
+        leadership.update(true, Utc::now());
+
+        let rendered = metrics.render().unwrap();
+        assert!(rendered.contains("scheduler_leader 1"));
+        assert!(rendered.contains("scheduler_leader_changes_total 1"));
+    }
+}
@@ -4,10 +4,19 @@
 //!
 //! ## Features
 //!
-//! - **Cron Expressions**: Full cron syntax support
-//! - **Simple Intervals**: Hourly, daily, weekly shortcuts
-//! - **Overlap Prevention**: Prevent concurrent task execution
-//! - **Error Handling**: Automatic error logging and retry
+//! - **Cron Expressions**: Full cron syntax support, read in any timezone
+//! - **Simple Intervals**: `@every 5m`, hourly, daily, weekly shortcuts
+//! - **Overlap Prevention**: Prevent concurrent task execution, across
+//!   instances with a shared [`TaskLock`] such as [`CacheLock`]
+//!   (`cache-backend` feature)
+//! - **Jitter**: Spread runs sharing a schedule
+//! - **Run History**: Every run and skipped run is recorded with its
+//!   duration and captured [`output`], in memory or in Postgres
+//!   (`postgres` feature)
+//! - **Leader Election**: Only one of several instances runs tasks, with
+//!   failover to another when it dies, see [`Scheduler::leader_election`]
+//! - **Catching Up**: Occurrences missed while the scheduler was down are
+//!   skipped, run once or all run, see [`CatchUp`]
 //! - **Async Tasks**: Full async/await support
 //!
 //! ## Quick Start
@@ -31,7 +40,10 @@
 //! }
 //!
 //! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-//! let scheduler = Scheduler::new();
+//! let scheduler = Scheduler::new().timezone(chrono_tz::Europe::Zurich);
+//!
+//! // Every day at 03:00 Zurich time
+//! scheduler.job(CleanupTask).daily_at("03:00");
 //!
 //! // Cron: Every day at midnight
 //! scheduler.schedule("0 0 * * *", CleanupTask).await?;
@@ -43,15 +55,91 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! ## Running on Several Instances
+//!
+//! With a lock shared by all instances, each occurrence of a task runs on
+//! one instance only:
+//!
+//! ```ignore
+//! use rf_cache::RedisCache;
+//! use rf_scheduler::{CacheLock, Scheduler};
+//!
+//! let cache = RedisCache::new("redis://localhost").await?;
+//! let scheduler = Scheduler::new().lock(CacheLock::new(cache));
+//! ```
+//!
+//! ## Electing a Leader
+//!
+//! Instead of locking every occurrence, the instances can elect a leader
+//! which alone runs tasks. Leases are held in an [`rf_cache::Cache`]
+//! (`cache-backend` feature), e.g. Redis, or as a Postgres advisory lock
+//! (`postgres` feature):
+//!
+//! ```ignore
+//! use rf_scheduler::{PostgresLeaderLease, Scheduler};
+//!
+//! let scheduler = Scheduler::new()
+//!     .leader_election(PostgresLeaderLease::new(pool))
+//!     .leader_metrics(&metrics);
+//! let leadership = scheduler.leadership();
+//! ```
+//!
+//! An instance becoming leader catches up on the occurrences missed since
+//! the last recorded runs, like a restarted one.
+//!
+//! ## Runs That Must Not Be Missed
+//!
+//! With a persisted history, the scheduler looks up the last run of each
+//! task on start. Occurrences missed in between are recorded as skipped,
+//! or run with [`CatchUp::RunOnce`] and [`CatchUp::RunAll`]:
+//!
+//! ```ignore
+//! use rf_scheduler::{CatchUp, HistoryQuery, PostgresRunHistory, RunStatus, Scheduler};
+//!
+//! let history = PostgresRunHistory::connect("postgres://localhost/app").await?;
+//! history.migrate().await?;
+//! let scheduler = Scheduler::new().history(history);
+//! scheduler
+//!     .job(RetentionReport)
+//!     .daily_at("02:00")
+//!     .catch_up(CatchUp::RunAll);
+//!
+//! let failed = scheduler
+//!     .runs(&HistoryQuery::new().task("retention-report").status(RunStatus::Failed))
+//!     .await;
+//! ```
+
+mod builder;
+mod history;
+mod leader;
+mod lock;
+#[cfg(feature = "postgres")]
+mod postgres;
+mod schedule;
+
+pub use builder::TaskBuilder;
+pub use chrono_tz::Tz;
+pub use history::{HistoryQuery, MemoryRunHistory, RunHistory, RunOutcome, RunStatus, TaskRun};
+pub use leader::{LeaderLease, LeaderStatus, Leadership};
+#[cfg(feature = "cache-backend")]
+pub use lock::CacheLock;
+pub use lock::TaskLock;
+#[cfg(feature = "postgres")]
+pub use postgres::{PostgresLeaderLease, PostgresRunHistory};
+pub use schedule::{CatchUp, TaskSchedule};
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use cron::Schedule;
-use std::collections::HashMap;
-use std::str::FromStr;
+use rand::Rng;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::future::Future;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tokio::time::{sleep, Duration};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration, Instant};
 
 pub use thiserror::Error;
 
@@ -66,8 +154,23 @@ pub enum SchedulerError {
 
     #[error("Task already running: {0}")]
     TaskRunning(String),
+
+    #[error("Lock error: {0}")]
+    Lock(String),
+
+    #[error("Run history error: {0}")]
+    History(String),
 }
 
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(SchedulerError, {
+    Self::InvalidCron(_) => (UNPROCESSABLE_ENTITY, "scheduler.invalid_cron"),
+    Self::TaskRunning(_) => (CONFLICT, "scheduler.task_running"),
+    Self::TaskFailed(_) => (INTERNAL_SERVER_ERROR, "scheduler.task_failed"),
+    Self::Lock(_) => (INTERNAL_SERVER_ERROR, "scheduler.lock"),
+    Self::History(_) => (INTERNAL_SERVER_ERROR, "scheduler.history"),
+});
+
 /// Result type for scheduler operations
 pub type SchedulerResult<T> = Result<T, SchedulerError>;
 
@@ -84,51 +187,197 @@ pub trait Task: Send + Sync {
     fn prevent_overlap(&self) -> bool {
         true
     }
+
+    /// What to do with missed occurrences (default: skip them)
+    fn catch_up(&self) -> CatchUp {
+        CatchUp::Skip
+    }
+}
+
+/// Output kept per run at most
+const MAX_OUTPUT: usize = 64 * 1024;
+
+/// Recorded for occurrences that weren't caught up on
+const MISSED: &str = "Missed occurrence";
+
+tokio::task_local! {
+    static OUTPUT: RefCell<String>;
+}
+
+/// Capture `line` as output of the running task, kept with its run in the
+/// history
+///
+/// Only lines written from within [`Task::run`] are captured, up to 64 KiB
+/// per run.
+pub fn output(line: impl std::fmt::Display) {
+    let _ = OUTPUT.try_with(|output| {
+        let mut output = output.borrow_mut();
+        if output.len() < MAX_OUTPUT {
+            let _ = writeln!(output, "{}", line);
+        }
+    });
+}
+
+/// How a registered task runs
+#[derive(Clone)]
+pub(crate) struct TaskOptions {
+    pub(crate) schedule: TaskSchedule,
+    pub(crate) timezone: Option<Tz>,
+    pub(crate) jitter: Duration,
+    pub(crate) prevent_overlap: bool,
+    pub(crate) catch_up: CatchUp,
 }
 
 struct ScheduledTask {
-    schedule: Schedule,
     task: Arc<dyn Task>,
-    last_run: Option<DateTime<Utc>>,
-    running: bool,
+    options: TaskOptions,
+    next_run: Option<DateTime<Utc>>,
 }
 
 /// Task scheduler
 pub struct Scheduler {
-    tasks: Arc<Mutex<Vec<ScheduledTask>>>,
-    running_tasks: Arc<Mutex<HashMap<String, bool>>>,
+    tasks: std::sync::Mutex<Vec<ScheduledTask>>,
+    errors: std::sync::Mutex<Vec<SchedulerError>>,
+    timezone: Tz,
+    runner: Arc<Runner>,
+    leader_lease: Option<Box<dyn LeaderLease>>,
+    leader_ttl: Duration,
+    leadership: Leadership,
+}
+
+/// State shared by the runs of all tasks
+struct Runner {
+    running_tasks: Mutex<HashSet<String>>,
+    lock: Option<Box<dyn TaskLock>>,
+    lock_ttl: Duration,
+    history: Arc<dyn RunHistory>,
+    /// Identifies this instance as lock owner
+    owner: String,
 }
 
 impl Scheduler {
     /// Create new scheduler
     pub fn new() -> Self {
         Self {
-            tasks: Arc::new(Mutex::new(Vec::new())),
-            running_tasks: Arc::new(Mutex::new(HashMap::new())),
+            tasks: std::sync::Mutex::new(Vec::new()),
+            errors: std::sync::Mutex::new(Vec::new()),
+            timezone: chrono_tz::UTC,
+            runner: Arc::new(Runner {
+                running_tasks: Mutex::new(HashSet::new()),
+                lock: None,
+                lock_ttl: Duration::from_secs(60 * 60),
+                history: Arc::new(MemoryRunHistory::default()),
+                owner: format!("{:016x}", rand::random::<u64>()),
+            }),
+            leader_lease: None,
+            leader_ttl: Duration::from_secs(30),
+            leadership: Leadership::default(),
         }
     }
 
-    /// Schedule task with cron expression (supports 5 or 6 field cron)
-    pub async fn schedule(&self, cron: &str, task: impl Task + 'static) -> SchedulerResult<()> {
-        // Add seconds field if not present (cron crate requires 6 fields)
-        let cron_expr = if cron.split_whitespace().count() == 5 {
-            format!("0 {}", cron)
-        } else {
-            cron.to_string()
-        };
+    /// Read schedules in `timezone` unless a task sets its own (default: UTC)
+    pub fn timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Share occurrences and overlap locks with other instances
+    pub fn lock(mut self, lock: impl TaskLock + 'static) -> Self {
+        self.runner_mut().lock = Some(Box::new(lock));
+        self
+    }
+
+    /// How long locks are held at most, in case an instance dies while
+    /// running a task (default: one hour)
+    pub fn lock_ttl(mut self, ttl: Duration) -> Self {
+        self.runner_mut().lock_ttl = ttl;
+        self
+    }
+
+    /// Record runs in `history` (default: the last 100 runs of each task,
+    /// in memory)
+    pub fn history(mut self, history: impl RunHistory + 'static) -> Self {
+        self.runner_mut().history = Arc::new(history);
+        self
+    }
+
+    /// Run tasks only while this instance holds `lease`, shared with the
+    /// other instances
+    pub fn leader_election(mut self, lease: impl LeaderLease + 'static) -> Self {
+        self.leader_lease = Some(Box::new(lease));
+        self
+    }
+
+    /// How long a leader lease lasts without renewal, i.e. how long the
+    /// instances wait for a dead leader (default: 30 seconds); leaders renew
+    /// it every third of that
+    pub fn leader_ttl(mut self, ttl: Duration) -> Self {
+        self.leader_ttl = ttl;
+        self
+    }
+
+    /// Export the leadership of this instance to `metrics`, see
+    /// [`Leadership`]
+    #[cfg(feature = "metrics")]
+    pub fn leader_metrics(mut self, metrics: &rf_metrics::Metrics) -> Self {
+        self.leadership.register(metrics);
+        self
+    }
 
-        let schedule = Schedule::from_str(&cron_expr)
-            .map_err(|e| SchedulerError::InvalidCron(e.to_string()))?;
+    /// Whether this instance leads; an instance without leader election
+    /// leads once started
+    pub fn leadership(&self) -> Leadership {
+        self.leadership.clone()
+    }
+
+    fn runner_mut(&mut self) -> &mut Runner {
+        Arc::get_mut(&mut self.runner).expect("Scheduler is configured before use")
+    }
+
+    /// Register a task with the fluent API
+    ///
+    /// ```no_run
+    /// # use rf_scheduler::{Scheduler, Task};
+    /// # use async_trait::async_trait;
+    /// # struct CleanupTask;
+    /// # #[async_trait]
+    /// # impl Task for CleanupTask {
+    /// #     async fn run(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> { Ok(()) }
+    /// #     fn name(&self) -> &str { "cleanup" }
+    /// # }
+    /// # let scheduler = Scheduler::new();
+    /// scheduler.job(CleanupTask).daily_at("03:00");
+    /// ```
+    pub fn job(&self, task: impl Task + 'static) -> TaskBuilder<'_> {
+        TaskBuilder::new(self, Arc::new(task))
+    }
+
+    pub(crate) fn register(&self, task: Arc<dyn Task>, options: SchedulerResult<TaskOptions>) {
+        match options {
+            Ok(options) => self.tasks.lock().unwrap().push(ScheduledTask {
+                task,
+                options,
+                next_run: None,
+            }),
+            Err(e) => {
+                tracing::error!(task = task.name(), error = %e, "Invalid task schedule");
+                self.errors.lock().unwrap().push(e);
+            }
+        }
+    }
 
-        let scheduled = ScheduledTask {
+    /// Schedule task with cron expression (supports 5 or 6 field cron)
+    pub async fn schedule(&self, cron: &str, task: impl Task + 'static) -> SchedulerResult<()> {
+        let schedule = TaskSchedule::parse(cron)?;
+        let task: Arc<dyn Task> = Arc::new(task);
+        let options = TaskOptions {
             schedule,
-            task: Arc::new(task),
-            last_run: None,
-            running: false,
+            timezone: None,
+            jitter: Duration::ZERO,
+            prevent_overlap: task.prevent_overlap(),
+            catch_up: task.catch_up(),
         };
-
-        let mut tasks = self.tasks.lock().await;
-        tasks.push(scheduled);
+        self.register(task, Ok(options));
 
         Ok(())
     }
@@ -140,14 +389,9 @@ impl Scheduler {
 
     /// Schedule task to run daily at specific time (HH:MM format)
     pub async fn daily_at(&self, time: &str, task: impl Task + 'static) -> SchedulerResult<()> {
-        let parts: Vec<&str> = time.split(':').collect();
-        if parts.len() != 2 {
-            return Err(SchedulerError::InvalidCron(
-                "Time must be in HH:MM format".to_string(),
-            ));
-        }
+        let (hour, minute) = schedule::parse_time(time)?;
 
-        let cron = format!("{} {} * * *", parts[1], parts[0]);
+        let cron = format!("{} {} * * *", minute, hour);
         self.schedule(&cron, task).await
     }
 
@@ -156,70 +400,348 @@ impl Scheduler {
         self.daily_at("00:00", task).await.unwrap();
     }
 
+    /// Most recent runs of a task, newest first
+    pub async fn recent_runs(&self, task: &str, limit: usize) -> Vec<TaskRun> {
+        self.runner.history.recent(task, limit).await
+    }
+
+    /// Runs matching `query`, latest occurrence first
+    pub async fn runs(&self, query: &HistoryQuery) -> Vec<TaskRun> {
+        self.runner.history.query(query).await
+    }
+
+    /// The history runs are recorded in, to query it once the scheduler is
+    /// started
+    pub fn run_history(&self) -> Arc<dyn RunHistory> {
+        Arc::clone(&self.runner.history)
+    }
+
     /// Start the scheduler
+    ///
+    /// Fails right away if a task was registered with an invalid schedule.
     pub async fn start(self) -> SchedulerResult<()> {
+        self.start_with_shutdown(std::future::pending()).await
+    }
+
+    /// Start the scheduler until `signal` completes
+    ///
+    /// No runs are started after `signal` completes, the runs in progress
+    /// are finished before this returns.
+    pub async fn start_with_shutdown(
+        self,
+        signal: impl Future<Output = ()> + Send,
+    ) -> SchedulerResult<()> {
+        if let Some(error) = self.errors.lock().unwrap().drain(..).next() {
+            return Err(error);
+        }
+        tracing::info!(
+            tasks = self.tasks.lock().unwrap().len(),
+            "Scheduler started"
+        );
+
+        tokio::pin!(signal);
+        let mut running: Vec<JoinHandle<()>> = vec![];
+        if self.leader_lease.is_none() {
+            self.leadership.update(true, Utc::now());
+            running.extend(self.catch_up(Utc::now()).await);
+        }
+        let mut next_election = Instant::now();
         loop {
-            let mut tasks = self.tasks.lock().await;
+            running.retain(|handle| !handle.is_finished());
+            let mut wait = Duration::MAX;
+            if let Some(lease) = &self.leader_lease {
+                if Instant::now() >= next_election {
+                    next_election = Instant::now() + self.leader_ttl / 3;
+                    running.extend(self.campaign(lease.as_ref(), Utc::now()).await);
+                }
+                wait = next_election.saturating_duration_since(Instant::now());
+            }
+            if self.leadership.is_leader() {
+                running.extend(self.tick(Utc::now()));
+                wait = wait.min(self.until_next_run(Utc::now()));
+            }
+            tokio::select! {
+                _ = sleep(wait) => {}
+                _ = &mut signal => break,
+            }
+        }
+
+        // Let another instance take over right away
+        if let (Some(lease), true) = (&self.leader_lease, self.leadership.is_leader()) {
+            if let Err(e) = lease.resign(&self.runner.owner).await {
+                tracing::warn!(error = %e, "Failed to give up scheduler leadership");
+            }
+        }
+
+        tracing::info!(
+            running = running.len(),
+            "Scheduler shutting down, waiting for running tasks"
+        );
+        for handle in running {
+            let _ = handle.await;
+        }
+        tracing::info!("Scheduler stopped");
+        Ok(())
+    }
+
+    /// Take or renew the leadership; on becoming leader, tasks are planned
+    /// and missed occurrences caught up on, on losing it they are unplanned
+    async fn campaign(&self, lease: &dyn LeaderLease, now: DateTime<Utc>) -> Vec<JoinHandle<()>> {
+        let leader = lease
+            .lead(&self.runner.owner, self.leader_ttl)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!(error = %e, "Scheduler leader election failed");
+                false
+            });
+        if !self.leadership.update(leader, now) {
+            return vec![];
+        }
+
+        if leader {
+            tracing::info!(owner = %self.runner.owner, "Became scheduler leader");
+            return self.catch_up(now).await;
+        }
+        tracing::warn!(owner = %self.runner.owner, "Lost scheduler leadership");
+        for scheduled in self.tasks.lock().unwrap().iter_mut() {
+            scheduled.next_run = None;
+        }
+        vec![]
+    }
+
+    /// Plan the first run of every task and handle the occurrences missed
+    /// since its last recorded run
+    async fn catch_up(&self, now: DateTime<Utc>) -> Vec<JoinHandle<()>> {
+        let tasks: Vec<_> = self
+            .tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|scheduled| (Arc::clone(&scheduled.task), scheduled.options.clone()))
+            .collect();
+        let mut handles = vec![];
+        let mut next_runs = Vec::with_capacity(tasks.len());
+
+        for (task, options) in tasks {
+            let timezone = options.timezone.unwrap_or(self.timezone);
+            next_runs.push(options.schedule.next_after(now, timezone));
+
+            let last = HistoryQuery::new().task(task.name()).limit(1);
+            let Some(last) = self.runner.history.query(&last).await.pop() else {
+                continue;
+            };
+            let missed = options.schedule.between(last.scheduled_for, now, timezone);
+            if missed.is_empty() {
+                continue;
+            }
+
+            tracing::warn!(
+                task = task.name(),
+                missed = missed.len(),
+                since = %last.scheduled_for,
+                policy = ?options.catch_up,
+                "Scheduled task missed occurrences"
+            );
+            let (run, skip) = options.catch_up.split(missed);
+            let runner = Arc::clone(&self.runner);
+            handles.push(tokio::spawn(runner.run_missed(task, options, run, skip)));
+        }
+
+        let mut tasks = self.tasks.lock().unwrap();
+        for (scheduled, next_run) in tasks.iter_mut().zip(next_runs) {
+            scheduled.next_run = next_run;
+        }
+        handles
+    }
+
+    /// Spawn the runs of all tasks due at `now` and plan their next runs
+    ///
+    /// Tasks are first planned on the tick after they are registered.
+    fn tick(&self, now: DateTime<Utc>) -> Vec<JoinHandle<()>> {
+        let mut tasks = self.tasks.lock().unwrap();
+        let mut handles = vec![];
+
+        for scheduled in tasks.iter_mut() {
+            let timezone = scheduled.options.timezone.unwrap_or(self.timezone);
+            let schedule = &scheduled.options.schedule;
+            let Some(next) = scheduled.next_run else {
+                scheduled.next_run = schedule.next_after(now, timezone);
+                continue;
+            };
+            if next > now {
+                continue;
+            }
+
+            // Later occurrences already due were missed, e.g. as the host slept
+            let missed = schedule.between(next, now, timezone);
+            scheduled.next_run = schedule.next_after(now, timezone);
+
+            let runner = Arc::clone(&self.runner);
+            let task = Arc::clone(&scheduled.task);
+            let options = scheduled.options.clone();
+            if missed.is_empty() {
+                handles.push(tokio::spawn(runner.run(task, options, next)));
+            } else {
+                let (mut run, skip) = options.catch_up.split(missed);
+                run.insert(0, next);
+                handles.push(tokio::spawn(runner.run_missed(task, options, run, skip)));
+            }
+        }
+
+        handles
+    }
+
+    /// Time until the next planned run, checking at least every minute
+    fn until_next_run(&self, now: DateTime<Utc>) -> Duration {
+        let tasks = self.tasks.lock().unwrap();
+        let next = tasks.iter().filter_map(|scheduled| scheduled.next_run).min();
+        next.and_then(|next| (next - now).to_std().ok())
+            .unwrap_or(Duration::ZERO)
+            .clamp(Duration::from_millis(10), Duration::from_secs(60))
+    }
+}
+
+impl Runner {
+    async fn run(
+        self: Arc<Self>,
+        task: Arc<dyn Task>,
+        options: TaskOptions,
+        occurrence: DateTime<Utc>,
+    ) {
+        let task_name = task.name().to_string();
+
+        if !options.jitter.is_zero() {
+            let delay = rand::thread_rng().gen_range(Duration::ZERO..=options.jitter);
+            sleep(delay).await;
+        }
+
+        if let Err(reason) = self.claim(&task_name, occurrence, options.prevent_overlap).await {
+            tracing::warn!(task = %task_name, reason = %reason, "Skipping scheduled task");
             let now = Utc::now();
+            let skipped = RunOutcome::Skipped(reason);
+            self.record(&task_name, occurrence, now, skipped, None)
+                .await;
+            return;
+        }
 
-            for scheduled in tasks.iter_mut() {
-                // Check if task should run
-                if let Some(next) = scheduled.schedule.upcoming(Utc).next() {
-                    if next <= now && !scheduled.running {
-                        // Check if task ran recently
-                        if let Some(last) = scheduled.last_run {
-                            if (now - last).num_seconds() < 60 {
-                                continue;
-                            }
-                        }
-
-                        // Check overlap
-                        if scheduled.task.prevent_overlap() {
-                            let mut running = self.running_tasks.lock().await;
-                            if running.get(scheduled.task.name()).copied().unwrap_or(false) {
-                                tracing::warn!(
-                                    task = scheduled.task.name(),
-                                    "Task still running, skipping"
-                                );
-                                continue;
-                            }
-                            running.insert(scheduled.task.name().to_string(), true);
-                        }
-
-                        // Run task
-                        scheduled.running = true;
-                        scheduled.last_run = Some(now);
-
-                        let task = Arc::clone(&scheduled.task);
-                        let running_tasks = Arc::clone(&self.running_tasks);
-
-                        tokio::spawn(async move {
-                            let task_name = task.name().to_string();
-
-                            tracing::info!(task = %task_name, "Running scheduled task");
-
-                            match task.run().await {
-                                Ok(_) => {
-                                    tracing::info!(task = %task_name, "Task completed successfully");
-                                }
-                                Err(e) => {
-                                    tracing::error!(task = %task_name, error = %e, "Task failed");
-                                }
-                            }
-
-                            // Mark as not running
-                            let mut running = running_tasks.lock().await;
-                            running.remove(&task_name);
-                        });
-
-                        scheduled.running = false;
-                    }
-                }
+        tracing::info!(task = %task_name, "Running scheduled task");
+        let started_at = Utc::now();
+
+        let (result, output) = OUTPUT
+            .scope(RefCell::new(String::new()), async {
+                let result = task.run().await;
+                (result, OUTPUT.with(|output| output.take()))
+            })
+            .await;
+        let outcome = match result {
+            Ok(_) => {
+                tracing::info!(task = %task_name, "Task completed successfully");
+                RunOutcome::Succeeded
+            }
+            Err(e) => {
+                tracing::error!(task = %task_name, error = %e, "Task failed");
+                RunOutcome::Failed(e.to_string())
             }
+        };
+
+        self.release(&task_name, options.prevent_overlap).await;
+        let output = Some(output).filter(|output| !output.is_empty());
+        self.record(&task_name, occurrence, started_at, outcome, output)
+            .await;
+    }
+
+    /// Record the `skip` occurrences as missed, then run the `run`
+    /// occurrences one after the other
+    async fn run_missed(
+        self: Arc<Self>,
+        task: Arc<dyn Task>,
+        options: TaskOptions,
+        run: Vec<DateTime<Utc>>,
+        skip: Vec<DateTime<Utc>>,
+    ) {
+        for occurrence in skip {
+            let skipped = RunOutcome::Skipped(MISSED.to_string());
+            self.record(task.name(), occurrence, Utc::now(), skipped, None)
+                .await;
+        }
+        for occurrence in run {
+            Arc::clone(&self)
+                .run(Arc::clone(&task), options.clone(), occurrence)
+                .await;
+        }
+    }
 
-            drop(tasks);
-            sleep(Duration::from_secs(30)).await;
+    /// Take the occurrence and, for tasks that mustn't overlap, the running
+    /// lock; the error says why the run is skipped
+    async fn claim(
+        &self,
+        task_name: &str,
+        occurrence: DateTime<Utc>,
+        prevent_overlap: bool,
+    ) -> Result<(), String> {
+        if prevent_overlap && !self.running_tasks.lock().await.insert(task_name.to_string()) {
+            return Err("Previous run still in progress".to_string());
+        }
+        let Some(lock) = &self.lock else {
+            return Ok(());
+        };
+
+        let claimed: SchedulerResult<Option<&str>> = async {
+            let occurrence_key = format!("{}:{}", task_name, occurrence.timestamp());
+            if !lock.acquire(&occurrence_key, &self.owner, self.lock_ttl).await? {
+                return Ok(Some("Run by another instance"));
+            }
+            let running_key = format!("{}:running", task_name);
+            if prevent_overlap && !lock.acquire(&running_key, &self.owner, self.lock_ttl).await? {
+                return Ok(Some("Previous run still in progress on another instance"));
+            }
+            Ok(None)
+        }
+        .await;
+
+        let reason = match claimed {
+            Ok(None) => return Ok(()),
+            Ok(Some(reason)) => reason.to_string(),
+            Err(e) => e.to_string(),
+        };
+        if prevent_overlap {
+            self.running_tasks.lock().await.remove(task_name);
         }
+        Err(reason)
+    }
+
+    async fn release(&self, task_name: &str, prevent_overlap: bool) {
+        if !prevent_overlap {
+            return;
+        }
+        self.running_tasks.lock().await.remove(task_name);
+
+        if let Some(lock) = &self.lock {
+            let running_key = format!("{}:running", task_name);
+            if let Err(e) = lock.release(&running_key, &self.owner).await {
+                tracing::warn!(task = %task_name, error = %e, "Failed to release task lock");
+            }
+        }
+    }
+
+    async fn record(
+        &self,
+        task_name: &str,
+        occurrence: DateTime<Utc>,
+        started_at: DateTime<Utc>,
+        outcome: RunOutcome,
+        output: Option<String>,
+    ) {
+        self.history
+            .record(TaskRun {
+                task: task_name.to_string(),
+                scheduled_for: occurrence,
+                started_at,
+                finished_at: Utc::now(),
+                outcome,
+                output,
+            })
+            .await;
     }
 }
 
@@ -232,6 +754,8 @@ impl Default for Scheduler {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::{Datelike, TimeZone};
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     struct TestTask {
         name: String,
@@ -292,4 +816,414 @@ mod tests {
 
         // Just check they don't panic
     }
+
+    struct CountingTask {
+        runs: Arc<AtomicUsize>,
+        duration: Duration,
+    }
+
+    #[async_trait]
+    impl Task for CountingTask {
+        async fn run(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            sleep(self.duration).await;
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "counting"
+        }
+    }
+
+    fn counting(duration: Duration) -> (CountingTask, Arc<AtomicUsize>) {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let task = CountingTask {
+            runs: Arc::clone(&runs),
+            duration,
+        };
+        (task, runs)
+    }
+
+    fn at(h: u32, m: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 15, h, m, 0).unwrap()
+    }
+
+    async fn run_tick(scheduler: &Scheduler, now: DateTime<Utc>) {
+        for handle in scheduler.tick(now) {
+            handle.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fluent_registration() {
+        let scheduler = Scheduler::new();
+        let (task, runs) = counting(Duration::ZERO);
+        scheduler.job(task).daily_at("03:00");
+
+        run_tick(&scheduler, at(12, 0)).await;
+        run_tick(&scheduler, at(23, 59)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+
+        let occurrence = Utc.with_ymd_and_hms(2024, 1, 16, 3, 0, 0).unwrap();
+        run_tick(&scheduler, occurrence).await;
+        run_tick(&scheduler, occurrence).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        let history = scheduler.recent_runs("counting", 10).await;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].scheduled_for, occurrence);
+        assert_eq!(history[0].outcome, RunOutcome::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_schedule_fails_start() {
+        let scheduler = Scheduler::new();
+        scheduler
+            .job(TestTask {
+                name: "test".to_string(),
+            })
+            .daily_at("25:00");
+        assert!(matches!(
+            scheduler.start().await,
+            Err(SchedulerError::InvalidCron(_))
+        ));
+
+        let scheduler = Scheduler::new();
+        let _ = scheduler.job(TestTask {
+            name: "unscheduled".to_string(),
+        });
+        assert!(scheduler.start().await.is_err());
+    }
+
+    struct SlowTask {
+        started: Arc<tokio::sync::Notify>,
+        finished: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Task for SlowTask {
+        async fn run(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.started.notify_one();
+            sleep(Duration::from_millis(200)).await;
+            self.finished.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "slow"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_finishes_running_tasks() {
+        let started = Arc::new(tokio::sync::Notify::new());
+        let finished = Arc::new(AtomicUsize::new(0));
+        let scheduler = Scheduler::new();
+        scheduler
+            .job(SlowTask {
+                started: Arc::clone(&started),
+                finished: Arc::clone(&finished),
+            })
+            .every(Duration::from_secs(1));
+
+        let signal = async move { started.notified().await };
+        tokio::time::timeout(Duration::from_secs(5), scheduler.start_with_shutdown(signal))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(finished.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_overlap_prevention() {
+        let scheduler = Scheduler::new();
+        let (task, runs) = counting(Duration::from_millis(100));
+        scheduler.job(task).every_minute();
+
+        run_tick(&scheduler, at(12, 0)).await;
+        let first = scheduler.tick(at(12, 1));
+        run_tick(&scheduler, at(12, 2)).await;
+        for handle in first {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        let history = scheduler.recent_runs("counting", 10).await;
+        assert_eq!(history[0].outcome, RunOutcome::Succeeded);
+        assert_eq!(
+            history[1].outcome,
+            RunOutcome::Skipped("Previous run still in progress".to_string())
+        );
+
+        // Overlapping runs are allowed on request
+        let scheduler = Scheduler::new();
+        let (task, runs) = counting(Duration::from_millis(100));
+        scheduler.job(task).every_minute().allow_overlapping();
+        run_tick(&scheduler, at(12, 0)).await;
+        let first = scheduler.tick(at(12, 1));
+        run_tick(&scheduler, at(12, 2)).await;
+        for handle in first {
+            handle.await.unwrap();
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_jitter_delays_run() {
+        let scheduler = Scheduler::new();
+        let (task, runs) = counting(Duration::ZERO);
+        scheduler
+            .job(task)
+            .every(Duration::from_secs(60))
+            .jitter(Duration::from_millis(50));
+
+        run_tick(&scheduler, at(12, 0)).await;
+        let started = std::time::Instant::now();
+        run_tick(&scheduler, at(12, 1)).await;
+        assert!(started.elapsed() <= Duration::from_secs(1));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    fn day(d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, d, 3, 0, 0).unwrap()
+    }
+
+    /// Runs and recorded statuses of a daily task last run on the 15th and
+    /// started on the 18th at noon
+    async fn catch_up(policy: CatchUp) -> (usize, Vec<(u32, RunStatus)>) {
+        let history = MemoryRunHistory::default();
+        history
+            .record(TaskRun {
+                task: "counting".to_string(),
+                scheduled_for: day(15),
+                started_at: day(15),
+                finished_at: day(15),
+                outcome: RunOutcome::Succeeded,
+                output: None,
+            })
+            .await;
+        let scheduler = Scheduler::new().history(history);
+        let (task, runs) = counting(Duration::ZERO);
+        scheduler.job(task).daily_at("03:00").catch_up(policy);
+
+        let now = Utc.with_ymd_and_hms(2024, 1, 18, 12, 0, 0).unwrap();
+        for handle in scheduler.catch_up(now).await {
+            handle.await.unwrap();
+        }
+        let since = HistoryQuery::new().since(day(16));
+        let recorded: Vec<_> = scheduler
+            .runs(&since)
+            .await
+            .iter()
+            .rev()
+            .map(|run| (run.scheduled_for.day(), run.outcome.status()))
+            .collect();
+
+        // The first run is planned after now
+        run_tick(&scheduler, now).await;
+        (runs.load(Ordering::SeqCst), recorded)
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_after_downtime() {
+        use RunStatus::*;
+
+        let (runs, recorded) = catch_up(CatchUp::Skip).await;
+        assert_eq!(runs, 0);
+        assert_eq!(recorded, [(16, Skipped), (17, Skipped), (18, Skipped)]);
+
+        let (runs, recorded) = catch_up(CatchUp::RunOnce).await;
+        assert_eq!(runs, 1);
+        assert_eq!(recorded, [(16, Skipped), (17, Skipped), (18, Succeeded)]);
+
+        let (runs, recorded) = catch_up(CatchUp::RunAll).await;
+        assert_eq!(runs, 3);
+        assert_eq!(
+            recorded,
+            [(16, Succeeded), (17, Succeeded), (18, Succeeded)]
+        );
+
+        // Without recorded runs there is nothing to catch up on
+        let scheduler = Scheduler::new();
+        let (task, runs) = counting(Duration::ZERO);
+        scheduler
+            .job(task)
+            .daily_at("03:00")
+            .catch_up(CatchUp::RunAll);
+        assert!(scheduler.catch_up(day(18)).await.is_empty());
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_missed_while_running() {
+        let scheduler = Scheduler::new();
+        let (task, runs) = counting(Duration::ZERO);
+        scheduler.job(task).every_minute();
+        run_tick(&scheduler, at(12, 0)).await;
+        run_tick(&scheduler, at(12, 3)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        let skipped = HistoryQuery::new().status(RunStatus::Skipped);
+        let skipped: Vec<_> = scheduler.runs(&skipped).await;
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[0].scheduled_for, at(12, 3));
+        assert_eq!(skipped[0].outcome, RunOutcome::Skipped(MISSED.to_string()));
+
+        let scheduler = Scheduler::new();
+        let (task, runs) = counting(Duration::ZERO);
+        scheduler.job(task).every_minute().catch_up(CatchUp::RunAll);
+        run_tick(&scheduler, at(12, 0)).await;
+        run_tick(&scheduler, at(12, 3)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+
+    /// Lease shared by the schedulers of a test
+    #[derive(Clone, Default)]
+    struct SharedLease(Arc<std::sync::Mutex<Option<String>>>);
+
+    #[async_trait]
+    impl LeaderLease for SharedLease {
+        async fn lead(&self, owner: &str, _ttl: Duration) -> SchedulerResult<bool> {
+            let mut holder = self.0.lock().unwrap();
+            Ok(holder.get_or_insert_with(|| owner.to_string()) == owner)
+        }
+
+        async fn resign(&self, owner: &str) -> SchedulerResult<()> {
+            let mut holder = self.0.lock().unwrap();
+            if holder.as_deref() == Some(owner) {
+                *holder = None;
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_leader_failover() {
+        let lease = SharedLease::default();
+        let first = Scheduler::new();
+        let (task, first_runs) = counting(Duration::ZERO);
+        first.job(task).daily_at("03:00");
+
+        let history = MemoryRunHistory::default();
+        history
+            .record(TaskRun {
+                task: "counting".to_string(),
+                scheduled_for: day(15),
+                started_at: day(15),
+                finished_at: day(15),
+                outcome: RunOutcome::Succeeded,
+                output: None,
+            })
+            .await;
+        let second = Scheduler::new().history(history);
+        let (task, second_runs) = counting(Duration::ZERO);
+        second
+            .job(task)
+            .daily_at("03:00")
+            .catch_up(CatchUp::RunOnce);
+
+        let now = at(12, 0);
+        assert!(first.campaign(&lease, now).await.is_empty());
+        assert!(second.campaign(&lease, now).await.is_empty());
+        assert!(first.leadership().is_leader());
+        assert_eq!(second.leadership().status(), LeaderStatus::default());
+
+        // The leader died, its lease expired
+        *lease.0.lock().unwrap() = None;
+        let now = Utc.with_ymd_and_hms(2024, 1, 18, 12, 0, 0).unwrap();
+        for handle in second.campaign(&lease, now).await {
+            handle.await.unwrap();
+        }
+        let status = second.leadership().status();
+        assert_eq!((status.leader, status.since), (true, Some(now)));
+        assert_eq!(second_runs.load(Ordering::SeqCst), 1);
+
+        // The old leader steps down and stops running tasks
+        first.campaign(&lease, now).await;
+        assert_eq!(first.leadership().status().changes, 2);
+        assert!(first.tasks.lock().unwrap()[0].next_run.is_none());
+        assert_eq!(first_runs.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_leader_resigns_on_shutdown() {
+        let lease = SharedLease::default();
+        let scheduler = Scheduler::new().leader_election(lease.clone());
+        let leadership = scheduler.leadership();
+        let (task, _) = counting(Duration::ZERO);
+        scheduler.job(task).every_minute();
+
+        let signal = sleep(Duration::from_millis(50));
+        scheduler.start_with_shutdown(signal).await.unwrap();
+        assert_eq!(leadership.status().changes, 1);
+        assert!(lease.0.lock().unwrap().is_none());
+    }
+
+    struct ReportTask;
+
+    #[async_trait]
+    impl Task for ReportTask {
+        async fn run(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            output("Exported 42 rows");
+            output(format_args!("Skipped {} rows", 3));
+            Err("Upload failed".into())
+        }
+
+        fn name(&self) -> &str {
+            "report"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_output_is_recorded() {
+        let scheduler = Scheduler::new();
+        scheduler.job(ReportTask).every_minute();
+        run_tick(&scheduler, at(12, 0)).await;
+        run_tick(&scheduler, at(12, 1)).await;
+
+        let run = &scheduler.recent_runs("report", 1).await[0];
+        assert_eq!(run.outcome, RunOutcome::Failed("Upload failed".to_string()));
+        assert_eq!(
+            run.output.as_deref(),
+            Some("Exported 42 rows\nSkipped 3 rows\n")
+        );
+        assert!(run.duration() >= chrono::Duration::zero());
+
+        // Outside of a run, output goes nowhere
+        output("ignored");
+    }
+
+    #[cfg(feature = "cache-backend")]
+    #[tokio::test]
+    async fn test_one_instance_runs_occurrence() {
+        use rf_cache::MemoryCache;
+
+        let cache = MemoryCache::new();
+        let (task, runs) = counting(Duration::ZERO);
+        let first = Scheduler::new().lock(CacheLock::new(cache.clone()));
+        first.job(task).every_minute();
+        let second = Scheduler::new().lock(CacheLock::new(cache));
+        second
+            .job(CountingTask {
+                runs: Arc::clone(&runs),
+                duration: Duration::ZERO,
+            })
+            .every_minute();
+
+        for scheduler in [&first, &second] {
+            run_tick(scheduler, at(12, 0)).await;
+        }
+        for scheduler in [&first, &second] {
+            run_tick(scheduler, at(12, 1)).await;
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            second.recent_runs("counting", 1).await[0].outcome,
+            RunOutcome::Skipped("Run by another instance".to_string())
+        );
+
+        // The running lock was released, so the next occurrence runs
+        run_tick(&second, at(12, 2)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
 }
@@ -0,0 +1,156 @@
+//! Locks shared by scheduler instances
+
+#[cfg(feature = "cache-backend")]
+use crate::leader::LeaderLease;
+use crate::SchedulerResult;
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Lock preventing several scheduler instances from running the same task
+///
+/// Keys are scoped by the scheduler, e.g. `cleanup:1718409600` for an
+/// occurrence of the `cleanup` task and `cleanup:running` while it runs.
+#[async_trait]
+pub trait TaskLock: Send + Sync {
+    /// Take `key` for `owner` unless it is held, returning whether it was
+    /// taken; the lock expires after `ttl`
+    async fn acquire(&self, key: &str, owner: &str, ttl: Duration) -> SchedulerResult<bool>;
+
+    /// Give up `key` if `owner` holds it
+    async fn release(&self, key: &str, owner: &str) -> SchedulerResult<()>;
+}
+
+/// [`TaskLock`] and [`LeaderLease`] stored in an [`rf_cache::Cache`]
+///
+/// Use a shared backend such as `RedisCache` so all instances see the
+/// same locks. The leader lease is the `leader` key.
+///
+/// Renewals and releases go through
+/// [`compare_and_set`](rf_cache::Cache::compare_and_set) and
+/// [`compare_and_delete`](rf_cache::Cache::compare_and_delete), so
+/// they only touch a lock the owner still holds. These are atomic with
+/// `MemoryCache` and `RedisCache`; wrappers are atomic as far as the cache
+/// they wrap is. Other caches fall back to separate reads and writes and
+/// are not safe for leader election.
+///
+/// ```
+/// use rf_cache::MemoryCache;
+/// use rf_scheduler::{CacheLock, Scheduler};
+///
+/// let scheduler = Scheduler::new().lock(CacheLock::new(MemoryCache::new()));
+/// ```
+#[cfg(feature = "cache-backend")]
+pub struct CacheLock<C> {
+    cache: C,
+    prefix: String,
+}
+
+#[cfg(feature = "cache-backend")]
+impl<C: rf_cache::Cache> CacheLock<C> {
+    /// Create a lock with the default `scheduler` key prefix
+    pub fn new(cache: C) -> Self {
+        Self {
+            cache,
+            prefix: "scheduler".to_string(),
+        }
+    }
+
+    /// Use a different key prefix
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}:{}", self.prefix, key)
+    }
+}
+
+#[cfg(feature = "cache-backend")]
+#[async_trait]
+impl<C: rf_cache::Cache> TaskLock for CacheLock<C> {
+    async fn acquire(&self, key: &str, owner: &str, ttl: Duration) -> SchedulerResult<bool> {
+        self.cache
+            .add(&self.key(key), &owner, ttl)
+            .await
+            .map_err(lock_error)
+    }
+
+    async fn release(&self, key: &str, owner: &str) -> SchedulerResult<()> {
+        self.cache
+            .compare_and_delete(&self.key(key), &owner)
+            .await
+            .map_err(lock_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cache-backend")]
+#[async_trait]
+impl<C: rf_cache::Cache> LeaderLease for CacheLock<C> {
+    async fn lead(&self, owner: &str, ttl: Duration) -> SchedulerResult<bool> {
+        let key = self.key("leader");
+        if self
+            .cache
+            .add(&key, &owner, ttl)
+            .await
+            .map_err(lock_error)?
+        {
+            return Ok(true);
+        }
+        // Renew only while still holding the lease
+        self.cache
+            .compare_and_set(&key, &owner, &owner, ttl)
+            .await
+            .map_err(lock_error)
+    }
+
+    async fn resign(&self, owner: &str) -> SchedulerResult<()> {
+        TaskLock::release(self, "leader", owner).await
+    }
+}
+
+#[cfg(feature = "cache-backend")]
+fn lock_error(e: rf_cache::CacheError) -> crate::SchedulerError {
+    crate::SchedulerError::Lock(e.to_string())
+}
+
+#[cfg(all(test, feature = "cache-backend"))]
+mod tests {
+    use super::*;
+    use rf_cache::MemoryCache;
+
+    #[tokio::test]
+    async fn test_cache_lock() {
+        let lock = CacheLock::new(MemoryCache::new());
+        let ttl = Duration::from_secs(60);
+
+        assert!(lock.acquire("cleanup:running", "a", ttl).await.unwrap());
+        assert!(!lock.acquire("cleanup:running", "b", ttl).await.unwrap());
+
+        // Only the holder releases
+        lock.release("cleanup:running", "b").await.unwrap();
+        assert!(!lock.acquire("cleanup:running", "b", ttl).await.unwrap());
+        lock.release("cleanup:running", "a").await.unwrap();
+        assert!(lock.acquire("cleanup:running", "b", ttl).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_cache_leader_lease() {
+        let lease = CacheLock::new(MemoryCache::new());
+        let long = Duration::from_secs(60);
+
+        assert!(lease.lead("a", Duration::from_millis(200)).await.unwrap());
+        assert!(!lease.lead("b", long).await.unwrap());
+        // Renewing keeps the lease past its first expiry
+        assert!(lease.lead("a", long).await.unwrap());
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(!lease.lead("b", long).await.unwrap());
+
+        // Without renewals the lease expires
+        lease.resign("a").await.unwrap();
+        assert!(lease.lead("b", Duration::from_millis(20)).await.unwrap());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(lease.lead("a", long).await.unwrap());
+    }
+}
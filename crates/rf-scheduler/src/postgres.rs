@@ -0,0 +1,322 @@
+//! Postgres run history and leader lease
+
+use crate::history::{HistoryQuery, RunHistory, RunOutcome, RunStatus, TaskRun};
+use crate::leader::LeaderLease;
+use crate::{SchedulerError, SchedulerResult};
+use async_trait::async_trait;
+use sqlx::{postgres::PgPoolOptions, Connection, PgConnection, PgPool, Row};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Run history kept in Postgres
+///
+/// Runs are kept until deleted, so the history survives restarts and
+/// missed occurrences are caught up on, see [`CatchUp`](crate::CatchUp).
+///
+/// ```no_run
+/// use rf_scheduler::{PostgresRunHistory, Scheduler};
+///
+/// # async fn example() -> rf_scheduler::SchedulerResult<()> {
+/// let history = PostgresRunHistory::connect("postgres://localhost/app").await?;
+/// history.migrate().await?;
+/// let scheduler = Scheduler::new().history(history);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct PostgresRunHistory {
+    pool: PgPool,
+    table: String,
+}
+
+impl PostgresRunHistory {
+    /// Create a history on an existing pool, in the `schedule_runs` table
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            table: "schedule_runs".to_string(),
+        }
+    }
+
+    /// Connect to `database_url`
+    pub async fn connect(database_url: &str) -> SchedulerResult<Self> {
+        let pool = PgPoolOptions::new()
+            .connect(database_url)
+            .await
+            .map_err(history_error)?;
+        Ok(Self::new(pool))
+    }
+
+    /// Use a different table
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Create the runs table and its index if they don't exist
+    pub async fn migrate(&self) -> SchedulerResult<()> {
+        let table = &self.table;
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {table} (
+                id BIGSERIAL PRIMARY KEY,
+                task TEXT NOT NULL,
+                scheduled_for TIMESTAMPTZ NOT NULL,
+                started_at TIMESTAMPTZ NOT NULL,
+                finished_at TIMESTAMPTZ NOT NULL,
+                status TEXT NOT NULL,
+                reason TEXT,
+                output TEXT
+            )"
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(history_error)?;
+
+        sqlx::query(&format!(
+            "CREATE INDEX IF NOT EXISTS {table}_task_idx ON {table} (task, scheduled_for)"
+        ))
+        .execute(&self.pool)
+        .await
+        .map_err(history_error)?;
+        Ok(())
+    }
+
+    async fn fetch(&self, query: &HistoryQuery) -> SchedulerResult<Vec<TaskRun>> {
+        sqlx::query(&format!(
+            "SELECT task, scheduled_for, started_at, finished_at, status, reason, output
+             FROM {}
+             WHERE ($1::text IS NULL OR task = $1)
+               AND ($2::text IS NULL OR status = $2)
+               AND ($3::timestamptz IS NULL OR scheduled_for >= $3)
+               AND ($4::timestamptz IS NULL OR scheduled_for <= $4)
+             ORDER BY scheduled_for DESC, started_at DESC, task DESC
+             LIMIT $5",
+            self.table
+        ))
+        .bind(query.task.as_deref())
+        .bind(query.status.map(|status| status.as_str()))
+        .bind(query.since)
+        .bind(query.until)
+        .bind(i64::try_from(query.limit).unwrap_or(i64::MAX))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(history_error)?
+        .iter()
+        .map(task_run)
+        .collect()
+    }
+}
+
+fn history_error(e: sqlx::Error) -> SchedulerError {
+    SchedulerError::History(e.to_string())
+}
+
+fn task_run(row: &sqlx::postgres::PgRow) -> SchedulerResult<TaskRun> {
+    let status: String = row.get("status");
+    let reason: Option<String> = row.get("reason");
+    let reason = reason.unwrap_or_default();
+    let outcome = match status.parse()? {
+        RunStatus::Succeeded => RunOutcome::Succeeded,
+        RunStatus::Failed => RunOutcome::Failed(reason),
+        RunStatus::Skipped => RunOutcome::Skipped(reason),
+    };
+    Ok(TaskRun {
+        task: row.get("task"),
+        scheduled_for: row.get("scheduled_for"),
+        started_at: row.get("started_at"),
+        finished_at: row.get("finished_at"),
+        outcome,
+        output: row.get("output"),
+    })
+}
+
+#[async_trait]
+impl RunHistory for PostgresRunHistory {
+    async fn record(&self, run: TaskRun) {
+        let result = sqlx::query(&format!(
+            "INSERT INTO {}
+                (task, scheduled_for, started_at, finished_at, status, reason, output)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            self.table
+        ))
+        .bind(&run.task)
+        .bind(run.scheduled_for)
+        .bind(run.started_at)
+        .bind(run.finished_at)
+        .bind(run.outcome.status().as_str())
+        .bind(run.outcome.reason())
+        .bind(&run.output)
+        .execute(&self.pool)
+        .await;
+        if let Err(e) = result {
+            tracing::error!(task = %run.task, error = %e, "Failed to record task run");
+        }
+    }
+
+    async fn recent(&self, task: &str, limit: usize) -> Vec<TaskRun> {
+        self.query(&HistoryQuery::new().task(task).limit(limit))
+            .await
+    }
+
+    async fn query(&self, query: &HistoryQuery) -> Vec<TaskRun> {
+        self.fetch(query).await.unwrap_or_else(|e| {
+            tracing::error!(error = %e, "Failed to query task runs");
+            Vec::new()
+        })
+    }
+}
+
+/// [`LeaderLease`] held as a Postgres advisory lock
+///
+/// The leader keeps the lock on a connection of its own, taken out of the
+/// pool. When the leader dies, Postgres drops the lock with the session and
+/// another instance takes over on its next attempt; the lease TTL is not
+/// used.
+///
+/// ```no_run
+/// use rf_scheduler::{PostgresLeaderLease, Scheduler};
+///
+/// # async fn example(pool: sqlx::PgPool) {
+/// let scheduler = Scheduler::new().leader_election(PostgresLeaderLease::new(pool));
+/// # }
+/// ```
+pub struct PostgresLeaderLease {
+    pool: PgPool,
+    key: i64,
+    connection: Mutex<Option<PgConnection>>,
+}
+
+impl PostgresLeaderLease {
+    /// Create a lease on the default advisory lock key
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            key: 0x0072_6673_6368_6564,
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// Use a different advisory lock key, e.g. for separate clusters
+    /// sharing a database
+    pub fn key(mut self, key: i64) -> Self {
+        self.key = key;
+        self
+    }
+}
+
+#[async_trait]
+impl LeaderLease for PostgresLeaderLease {
+    async fn lead(&self, _owner: &str, _ttl: Duration) -> SchedulerResult<bool> {
+        let mut held = self.connection.lock().await;
+        if let Some(connection) = held.as_mut() {
+            if connection.ping().await.is_ok() {
+                return Ok(true);
+            }
+            // The session and its lock are gone
+            tracing::warn!("Lost the connection holding the scheduler leader lock");
+            *held = None;
+        }
+
+        let mut connection = self.pool.acquire().await.map_err(lock_error)?.detach();
+        let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+            .bind(self.key)
+            .fetch_one(&mut connection)
+            .await
+            .map_err(lock_error)?;
+        if locked {
+            *held = Some(connection);
+        } else {
+            let _ = connection.close().await;
+        }
+        Ok(locked)
+    }
+
+    async fn resign(&self, _owner: &str) -> SchedulerResult<()> {
+        let Some(mut connection) = self.connection.lock().await.take() else {
+            return Ok(());
+        };
+        sqlx::query("SELECT pg_advisory_unlock($1)")
+            .bind(self.key)
+            .execute(&mut connection)
+            .await
+            .map_err(lock_error)?;
+        connection.close().await.map_err(lock_error)
+    }
+}
+
+fn lock_error(e: sqlx::Error) -> SchedulerError {
+    SchedulerError::Lock(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    #[tokio::test]
+    #[ignore] // Requires Postgres
+    async fn test_postgres_run_history() {
+        let url = std::env::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/rf_scheduler_test".to_string());
+        let history = PostgresRunHistory::connect(&url)
+            .await
+            .unwrap()
+            .table("rf_scheduler_test_runs");
+        history.migrate().await.unwrap();
+        sqlx::query("TRUNCATE rf_scheduler_test_runs")
+            .execute(&history.pool)
+            .await
+            .unwrap();
+
+        let at = |minute| DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::minutes(minute);
+        for (minute, outcome) in [
+            (1, RunOutcome::Succeeded),
+            (2, RunOutcome::Failed("Disk full".to_string())),
+            (3, RunOutcome::Skipped("Missed occurrence".to_string())),
+        ] {
+            history
+                .record(TaskRun {
+                    task: "backup".to_string(),
+                    scheduled_for: at(minute),
+                    started_at: at(minute),
+                    finished_at: at(minute) + chrono::Duration::seconds(5),
+                    outcome,
+                    output: Some("Copied 3 files\n".to_string()),
+                })
+                .await;
+        }
+
+        let recent = history.recent("backup", 2).await;
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].scheduled_for, at(3));
+        assert_eq!(recent[1].duration(), chrono::Duration::seconds(5));
+
+        let failed = HistoryQuery::new().status(RunStatus::Failed);
+        let failed = history.query(&failed).await;
+        assert_eq!(
+            failed[0].outcome,
+            RunOutcome::Failed("Disk full".to_string())
+        );
+        assert_eq!(failed[0].output.as_deref(), Some("Copied 3 files\n"));
+        assert!(history.recent("unknown", 10).await.is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires Postgres
+    async fn test_postgres_leader_lease() {
+        let url = std::env::var("DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost/rf_scheduler_test".to_string());
+        let pool = PgPoolOptions::new().connect(&url).await.unwrap();
+        let ttl = Duration::from_secs(30);
+        let first = PostgresLeaderLease::new(pool.clone()).key(42);
+        let second = PostgresLeaderLease::new(pool).key(42);
+
+        assert!(first.lead("a", ttl).await.unwrap());
+        assert!(first.lead("a", ttl).await.unwrap());
+        assert!(!second.lead("b", ttl).await.unwrap());
+
+        first.resign("a").await.unwrap();
+        assert!(second.lead("b", ttl).await.unwrap());
+        second.resign("b").await.unwrap();
+    }
+}
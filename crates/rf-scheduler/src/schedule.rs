@@ -0,0 +1,255 @@
+//! Schedule expressions
+
+use crate::{SchedulerError, SchedulerResult};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Missed occurrences handled at most per task, the latest ones are kept
+const MAX_MISSED: usize = 1000;
+
+/// When a task runs
+///
+/// Either a cron expression, evaluated in the task's timezone, or a fixed
+/// interval. Intervals are aligned to the Unix epoch, so every instance of
+/// the scheduler computes the same occurrences; `@every 5m` runs at :00,
+/// :05, :10 and so on.
+///
+/// ```
+/// use rf_scheduler::TaskSchedule;
+///
+/// // Weekdays at 09:30
+/// assert!(TaskSchedule::parse("30 9 * * Mon-Fri").is_ok());
+/// // Every 90 seconds
+/// assert!(TaskSchedule::parse("@every 90s").is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub enum TaskSchedule {
+    Cron(Box<cron::Schedule>),
+    Interval(Duration),
+}
+
+impl TaskSchedule {
+    /// Parse a cron expression with 5 or 6 fields, a macro like `@daily`,
+    /// or an interval like `@every 5m` (units `s`, `m`, `h` and `d`)
+    pub fn parse(expression: &str) -> SchedulerResult<Self> {
+        let expression = expression.trim();
+        if let Some(interval) = expression.strip_prefix("@every") {
+            return Self::every(parse_interval(interval.trim())?);
+        }
+
+        // Add seconds field if not present (cron crate requires 6 fields)
+        let cron_expr = if expression.split_whitespace().count() == 5 {
+            format!("0 {}", expression)
+        } else {
+            expression.to_string()
+        };
+
+        cron::Schedule::from_str(&cron_expr)
+            .map(|schedule| TaskSchedule::Cron(Box::new(schedule)))
+            .map_err(|e| SchedulerError::InvalidCron(e.to_string()))
+    }
+
+    /// Run every `interval`
+    pub fn every(interval: Duration) -> SchedulerResult<Self> {
+        if interval.is_zero() {
+            return Err(SchedulerError::InvalidCron(
+                "Interval must not be zero".to_string(),
+            ));
+        }
+        Ok(TaskSchedule::Interval(interval))
+    }
+
+    /// First run strictly after `after`, with cron fields read in `timezone`
+    pub fn next_after(&self, after: DateTime<Utc>, timezone: Tz) -> Option<DateTime<Utc>> {
+        match self {
+            TaskSchedule::Cron(schedule) => schedule
+                .after(&after.with_timezone(&timezone))
+                .next()
+                .map(|next| next.with_timezone(&Utc)),
+            TaskSchedule::Interval(interval) => {
+                let interval = i64::try_from(interval.as_millis()).ok()?;
+                let elapsed = after.timestamp_millis().div_euclid(interval);
+                DateTime::from_timestamp_millis((elapsed + 1).checked_mul(interval)?)
+            }
+        }
+    }
+
+    /// Occurrences after `after` up to and including `until`, oldest first
+    pub(crate) fn between(
+        &self,
+        after: DateTime<Utc>,
+        until: DateTime<Utc>,
+        timezone: Tz,
+    ) -> Vec<DateTime<Utc>> {
+        let mut occurrences = VecDeque::new();
+        let mut current = after;
+        while let Some(next) = self
+            .next_after(current, timezone)
+            .filter(|next| *next <= until)
+        {
+            if occurrences.len() == MAX_MISSED {
+                occurrences.pop_front();
+            }
+            occurrences.push_back(next);
+            current = next;
+        }
+        occurrences.into()
+    }
+}
+
+/// What to do with the occurrences of a task missed while the scheduler
+/// was down or the host slept
+///
+/// Occurrences missed before a restart are found in the run history, so
+/// catching up on them needs a persisted [`RunHistory`](crate::RunHistory).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CatchUp {
+    /// Record missed occurrences as skipped
+    #[default]
+    Skip,
+    /// Run once for the latest missed occurrence and record the others as
+    /// skipped
+    RunOnce,
+    /// Run every missed occurrence, oldest first
+    RunAll,
+}
+
+impl CatchUp {
+    /// Split `missed` into the occurrences to run and to skip
+    pub(crate) fn split(
+        self,
+        mut missed: Vec<DateTime<Utc>>,
+    ) -> (Vec<DateTime<Utc>>, Vec<DateTime<Utc>>) {
+        match self {
+            CatchUp::Skip => (Vec::new(), missed),
+            CatchUp::RunOnce => (missed.pop().into_iter().collect(), missed),
+            CatchUp::RunAll => (missed, Vec::new()),
+        }
+    }
+}
+
+fn parse_interval(interval: &str) -> SchedulerResult<Duration> {
+    let invalid = || SchedulerError::InvalidCron(format!("Invalid interval: {:?}", interval));
+
+    let split = interval.len().checked_sub(1).ok_or_else(invalid)?;
+    let (amount, unit) = interval.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    Ok(Duration::from_secs(amount * seconds))
+}
+
+/// Parse `HH:MM` into hour and minute
+pub(crate) fn parse_time(time: &str) -> SchedulerResult<(u32, u32)> {
+    let invalid = || SchedulerError::InvalidCron("Time must be in HH:MM format".to_string());
+
+    let (hour, minute) = time.split_once(':').ok_or_else(invalid)?;
+    let hour: u32 = hour.parse().map_err(|_| invalid())?;
+    let minute: u32 = minute.parse().map_err(|_| invalid())?;
+    if hour > 23 || minute > 59 {
+        return Err(invalid());
+    }
+    Ok((hour, minute))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn test_cron_in_timezone() {
+        let schedule = TaskSchedule::parse("0 3 * * *").unwrap();
+        let after = utc(2024, 1, 15, 12, 0);
+
+        assert_eq!(
+            schedule.next_after(after, chrono_tz::UTC),
+            Some(utc(2024, 1, 16, 3, 0))
+        );
+        // 03:00 in Zurich is 02:00 UTC in winter and 01:00 UTC in summer
+        assert_eq!(
+            schedule.next_after(after, chrono_tz::Europe::Zurich),
+            Some(utc(2024, 1, 16, 2, 0))
+        );
+        assert_eq!(
+            schedule.next_after(utc(2024, 7, 15, 12, 0), chrono_tz::Europe::Zurich),
+            Some(utc(2024, 7, 16, 1, 0))
+        );
+    }
+
+    #[test]
+    fn test_interval() {
+        let schedule = TaskSchedule::parse("@every 90s").unwrap();
+        let after = utc(2024, 1, 15, 12, 0) + chrono::Duration::seconds(10);
+        assert_eq!(
+            schedule.next_after(after, chrono_tz::UTC),
+            Some(utc(2024, 1, 15, 12, 1) + chrono::Duration::seconds(30))
+        );
+
+        assert!(matches!(
+            TaskSchedule::parse("@every 2h").unwrap(),
+            TaskSchedule::Interval(d) if d == Duration::from_secs(7200)
+        ));
+        assert!(TaskSchedule::parse("@every 0s").is_err());
+        assert!(TaskSchedule::parse("@every soon").is_err());
+        assert!(TaskSchedule::parse("@every").is_err());
+    }
+
+    #[test]
+    fn test_missed_occurrences() {
+        let schedule = TaskSchedule::parse("0 3 * * *").unwrap();
+        let missed = schedule.between(
+            utc(2024, 1, 15, 3, 0),
+            utc(2024, 1, 18, 3, 0),
+            chrono_tz::UTC,
+        );
+        assert_eq!(
+            missed,
+            [
+                utc(2024, 1, 16, 3, 0),
+                utc(2024, 1, 17, 3, 0),
+                utc(2024, 1, 18, 3, 0)
+            ]
+        );
+
+        assert_eq!(
+            CatchUp::Skip.split(missed.clone()),
+            (vec![], missed.clone())
+        );
+        assert_eq!(
+            CatchUp::RunOnce.split(missed.clone()),
+            (vec![missed[2]], missed[..2].to_vec())
+        );
+        assert_eq!(CatchUp::RunAll.split(missed.clone()), (missed, vec![]));
+
+        // Only the latest occurrences are kept
+        let every_second = TaskSchedule::parse("@every 1s").unwrap();
+        let missed = every_second.between(
+            utc(2024, 1, 15, 0, 0),
+            utc(2024, 1, 15, 1, 0),
+            chrono_tz::UTC,
+        );
+        assert_eq!(missed.len(), MAX_MISSED);
+        assert_eq!(missed.last(), Some(&utc(2024, 1, 15, 1, 0)));
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("03:00").unwrap(), (3, 0));
+        assert_eq!(parse_time("23:59").unwrap(), (23, 59));
+        assert!(parse_time("24:00").is_err());
+        assert!(parse_time("3").is_err());
+    }
+}
@@ -0,0 +1,117 @@
+//! Search index maintenance commands
+
+use crate::{Search, SearchResult};
+
+/// `reindex` and `flush` subcommands for an application's CLI
+///
+/// Indexes are rebuilt from the importers registered with
+/// [`Search::importer`].
+///
+/// ```ignore
+/// #[derive(clap::Subcommand)]
+/// enum Command {
+///     Serve,
+///     /// Search index maintenance
+///     #[command(subcommand)]
+///     Search(rf_search::SearchCommand),
+/// }
+///
+/// match cli.command {
+///     Command::Search(command) => return command.run(&search).await,
+///     // …
+/// }
+/// ```
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum SearchCommand {
+    /// Rebuild indexes from their importers
+    Reindex {
+        /// Index to rebuild; all indexes when omitted
+        index: Option<String>,
+    },
+    /// Remove all documents of an index
+    Flush {
+        /// Index to flush
+        index: String,
+    },
+}
+
+impl SearchCommand {
+    /// Run the command, printing its report
+    pub async fn run(&self, search: &Search) -> std::process::ExitCode {
+        match self.execute(search).await {
+            Ok(report) => {
+                print!("{}", report);
+                std::process::ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::ExitCode::FAILURE
+            }
+        }
+    }
+
+    async fn execute(&self, search: &Search) -> SearchResult<String> {
+        match self {
+            SearchCommand::Reindex { index } => {
+                let counts = match index {
+                    Some(index) => vec![(index.clone(), search.reindex_index(index).await?)],
+                    None => search.reindex_all().await?,
+                };
+                Ok(counts
+                    .iter()
+                    .map(|(index, count)| format!("Indexed {} documents into {}\n", count, index))
+                    .collect())
+            }
+            SearchCommand::Flush { index } => {
+                search.flush_index(index).await?;
+                Ok(format!("Flushed {}\n", index))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Document, Query, Searchable};
+
+    struct Tag(&'static str);
+
+    impl Searchable for Tag {
+        fn index_name() -> &'static str {
+            "tags"
+        }
+
+        fn to_document(&self) -> Document {
+            Document::new(self.0).field("name", self.0)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commands() {
+        let search = Search::memory()
+            .importer::<Tag, _, _>(|| async { Ok(vec![Tag("rust"), Tag("web")]) });
+
+        let report = SearchCommand::Reindex { index: None }
+            .execute(&search)
+            .await
+            .unwrap();
+        assert_eq!(report, "Indexed 2 documents into tags\n");
+
+        SearchCommand::Flush {
+            index: "tags".into(),
+        }
+        .execute(&search)
+        .await
+        .unwrap();
+        let results = search.search::<Tag>(&Query::new("")).await.unwrap();
+        assert_eq!(results.total, 0);
+
+        assert!(SearchCommand::Reindex {
+            index: Some("users".into())
+        }
+        .execute(&search)
+        .await
+        .is_err());
+    }
+}
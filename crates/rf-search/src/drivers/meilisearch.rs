@@ -0,0 +1,322 @@
+//! Meilisearch driver
+
+use super::SearchDriver;
+use crate::{
+    Document, Filter, Query, SearchError, SearchHit, SearchResult, SearchResults, SearchSchema,
+    SortOrder,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Meilisearch configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeilisearchConfig {
+    /// Server URL, e.g. `http://localhost:7700`
+    pub host: String,
+
+    /// API key
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+impl MeilisearchConfig {
+    /// Load from `MEILISEARCH_HOST` and `MEILISEARCH_KEY`
+    pub fn from_env() -> SearchResult<Self> {
+        Ok(Self {
+            host: std::env::var("MEILISEARCH_HOST").map_err(|_| {
+                SearchError::IndexError("MEILISEARCH_HOST is not set".to_string())
+            })?,
+            key: std::env::var("MEILISEARCH_KEY").ok(),
+        })
+    }
+}
+
+/// Meilisearch driver
+///
+/// Documents are stored flat, with their fields and metadata next to the
+/// `id`, so IDs may only contain letters, digits, `-` and `_`. Meilisearch
+/// applies writes asynchronously; they become searchable shortly after the
+/// calls return.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_search::{MeilisearchConfig, MeilisearchDriver, Search};
+///
+/// # fn example() -> rf_search::SearchResult<()> {
+/// let search = Search::new(MeilisearchDriver::new(MeilisearchConfig::from_env()?));
+/// # Ok(())
+/// # }
+/// ```
+pub struct MeilisearchDriver {
+    config: MeilisearchConfig,
+    client: reqwest::Client,
+}
+
+impl MeilisearchDriver {
+    /// Create new Meilisearch driver
+    pub fn new(config: MeilisearchConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}{}", self.config.host.trim_end_matches('/'), path);
+        let request = self.client.request(method, url);
+        match &self.config.key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    /// Send a write, failing on an unsuccessful response
+    async fn write(&self, request: reqwest::RequestBuilder) -> SearchResult<()> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        Err(SearchError::IndexError(format!(
+            "Meilisearch responded with {}: {}",
+            status, body
+        )))
+    }
+}
+
+#[async_trait]
+impl SearchDriver for MeilisearchDriver {
+    async fn configure(&self, index: &str, schema: &SearchSchema) -> SearchResult<()> {
+        let mut settings = json!({
+            "filterableAttributes": schema.filterable,
+            "sortableAttributes": schema.sortable,
+        });
+        if !schema.searchable.is_empty() {
+            settings["searchableAttributes"] = json!(schema.searchable);
+        }
+
+        let path = format!("/indexes/{}/settings", index);
+        self.write(self.request(reqwest::Method::PATCH, &path).json(&settings))
+            .await
+    }
+
+    async fn upsert(&self, index: &str, documents: Vec<Document>) -> SearchResult<()> {
+        if documents.is_empty() {
+            return Ok(());
+        }
+        let documents: Vec<Value> = documents.into_iter().map(to_json).collect();
+
+        let path = format!("/indexes/{}/documents?primaryKey=id", index);
+        self.write(self.request(reqwest::Method::POST, &path).json(&documents))
+            .await
+    }
+
+    async fn delete(&self, index: &str, ids: &[String]) -> SearchResult<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let path = format!("/indexes/{}/documents/delete-batch", index);
+        self.write(self.request(reqwest::Method::POST, &path).json(ids))
+            .await
+    }
+
+    async fn flush(&self, index: &str) -> SearchResult<()> {
+        let path = format!("/indexes/{}/documents", index);
+        self.write(self.request(reqwest::Method::DELETE, &path))
+            .await
+    }
+
+    async fn search(&self, index: &str, query: &Query) -> SearchResult<SearchResults> {
+        let path = format!("/indexes/{}/search", index);
+        let response = self
+            .request(reqwest::Method::POST, &path)
+            .json(&search_body(query))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            // The index doesn't exist yet
+            return Ok(query.apply(Vec::new()));
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(SearchError::QueryError(format!(
+                "Meilisearch responded with {}: {}",
+                status, body
+            )));
+        }
+
+        let response: SearchResponse = response.json().await?;
+        Ok(SearchResults {
+            hits: response.hits.into_iter().map(from_json).collect(),
+            total: response.estimated_total_hits,
+            facets: response.facet_distribution,
+            offset: query.offset,
+            limit: query.limit,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResponse {
+    hits: Vec<Map<String, Value>>,
+    #[serde(default)]
+    estimated_total_hits: usize,
+    #[serde(default)]
+    facet_distribution: HashMap<String, HashMap<String, usize>>,
+}
+
+fn search_body(query: &Query) -> Value {
+    let mut body = json!({
+        "q": query.text,
+        "offset": query.offset,
+        "limit": query.limit,
+        "showRankingScore": true,
+    });
+    if !query.filters.is_empty() {
+        body["filter"] = json!(filter_expression(&query.filters));
+    }
+    if !query.facets.is_empty() {
+        body["facets"] = json!(query.facets);
+    }
+    if !query.sort.is_empty() {
+        let sort: Vec<String> = query
+            .sort
+            .iter()
+            .map(|(field, order)| match order {
+                SortOrder::Asc => format!("{}:asc", field),
+                SortOrder::Desc => format!("{}:desc", field),
+            })
+            .collect();
+        body["sort"] = json!(sort);
+    }
+    body
+}
+
+/// Filters in Meilisearch's filter syntax
+fn filter_expression(filters: &[Filter]) -> String {
+    filters
+        .iter()
+        .map(|filter| match filter {
+            Filter::Equals(field, value) => format!("{} = {}", field, literal(value)),
+            Filter::In(field, values) => {
+                let values: Vec<String> = values.iter().map(literal).collect();
+                format!("{} IN [{}]", field, values.join(", "))
+            }
+            Filter::Range { field, min, max } => {
+                let bounds: Vec<String> = [
+                    min.map(|min| format!("{} >= {}", field, min)),
+                    max.map(|max| format!("{} <= {}", field, max)),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                format!("({})", bounds.join(" AND "))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+fn literal(value: &Value) -> String {
+    match value {
+        Value::String(value) => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+        value => value.to_string(),
+    }
+}
+
+fn to_json(document: Document) -> Value {
+    let mut object = Map::new();
+    object.extend(document.metadata);
+    object.extend(
+        document
+            .fields
+            .into_iter()
+            .map(|(name, value)| (name, Value::String(value))),
+    );
+    object.insert("id".to_string(), Value::String(document.id));
+    Value::Object(object)
+}
+
+/// Hit of a flat document; string values become fields, the rest metadata
+fn from_json(mut object: Map<String, Value>) -> SearchHit {
+    let score = object
+        .remove("_rankingScore")
+        .and_then(|score| score.as_f64())
+        .unwrap_or_default() as f32;
+    let id = match object.remove("id") {
+        Some(Value::String(id)) => id,
+        Some(id) => id.to_string(),
+        None => String::new(),
+    };
+
+    let mut document = Document::new(id);
+    for (name, value) in object {
+        match value {
+            Value::String(value) => {
+                document.fields.insert(name, value);
+            }
+            value => {
+                document.metadata.insert(name, value);
+            }
+        }
+    }
+    SearchHit::from_document(document, score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_body() {
+        let query = Query::new("rust")
+            .filter("status", "say \"hi\"")
+            .filter_in("year", [2023, 2024])
+            .at_least("price", 10.0)
+            .facet("status")
+            .sort("price", SortOrder::Desc)
+            .page(3, 20);
+
+        assert_eq!(
+            search_body(&query),
+            json!({
+                "q": "rust",
+                "offset": 40,
+                "limit": 20,
+                "showRankingScore": true,
+                "filter": r#"status = "say \"hi\"" AND year IN [2023, 2024] AND (price >= 10)"#,
+                "facets": ["status"],
+                "sort": ["price:desc"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_document_round_trip() {
+        let document = Document::new("7")
+            .field("title", "Hello")
+            .meta("views", 12)
+            .unwrap();
+        let mut object = match to_json(document) {
+            Value::Object(object) => object,
+            _ => unreachable!(),
+        };
+        assert_eq!(object["id"], "7");
+        object.insert("_rankingScore".to_string(), json!(0.5));
+
+        let hit = from_json(object);
+        assert_eq!(hit.id, "7");
+        assert_eq!(hit.score, 0.5);
+        assert_eq!(hit.fields["title"], "Hello");
+        assert_eq!(hit.metadata["views"], 12);
+    }
+}
@@ -0,0 +1,73 @@
+//! In-memory search driver
+
+use super::SearchDriver;
+use crate::{Document, Query, SearchEngine, SearchResult, SearchResults, SearchSchema};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// In-memory driver keeping a [`SearchEngine`] per index
+///
+/// Suitable for tests and small data sets; the indexes are lost on restart.
+#[derive(Default)]
+pub struct MemoryDriver {
+    indexes: RwLock<HashMap<String, SearchEngine>>,
+}
+
+impl MemoryDriver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of documents in an index
+    pub fn count(&self, index: &str) -> usize {
+        let indexes = self.indexes.read().unwrap();
+        indexes.get(index).map(SearchEngine::count).unwrap_or(0)
+    }
+}
+
+#[async_trait]
+impl SearchDriver for MemoryDriver {
+    async fn configure(&self, index: &str, _schema: &SearchSchema) -> SearchResult<()> {
+        let mut indexes = self.indexes.write().unwrap();
+        indexes.entry(index.to_string()).or_default();
+        Ok(())
+    }
+
+    async fn upsert(&self, index: &str, documents: Vec<Document>) -> SearchResult<()> {
+        let mut indexes = self.indexes.write().unwrap();
+        let engine = indexes.entry(index.to_string()).or_default();
+        for document in documents {
+            engine.index(document)?;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, index: &str, ids: &[String]) -> SearchResult<()> {
+        let mut indexes = self.indexes.write().unwrap();
+        if let Some(engine) = indexes.get_mut(index) {
+            for id in ids {
+                // Unknown IDs are fine
+                let _ = engine.remove(id);
+            }
+        }
+        Ok(())
+    }
+
+    async fn flush(&self, index: &str) -> SearchResult<()> {
+        let mut indexes = self.indexes.write().unwrap();
+        if let Some(engine) = indexes.get_mut(index) {
+            *engine = SearchEngine::new();
+        }
+        Ok(())
+    }
+
+    async fn search(&self, index: &str, query: &Query) -> SearchResult<SearchResults> {
+        let indexes = self.indexes.read().unwrap();
+        let hits = indexes
+            .get(index)
+            .map(|engine| engine.matches(&query.text))
+            .unwrap_or_default();
+        Ok(query.apply(hits))
+    }
+}
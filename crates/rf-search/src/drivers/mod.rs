@@ -0,0 +1,35 @@
+//! Search driver implementations
+
+mod memory;
+#[cfg(feature = "meilisearch")]
+mod meilisearch;
+#[cfg(feature = "tantivy")]
+mod tantivy;
+
+pub use self::memory::MemoryDriver;
+#[cfg(feature = "meilisearch")]
+pub use self::meilisearch::{MeilisearchConfig, MeilisearchDriver};
+#[cfg(feature = "tantivy")]
+pub use self::tantivy::TantivyDriver;
+
+use crate::{Document, Query, SearchResult, SearchResults, SearchSchema};
+use async_trait::async_trait;
+
+/// Stores documents in named indexes and searches them
+#[async_trait]
+pub trait SearchDriver: Send + Sync {
+    /// Apply the settings of an index, creating it if needed
+    async fn configure(&self, index: &str, schema: &SearchSchema) -> SearchResult<()>;
+
+    /// Add documents, replacing those with the same IDs
+    async fn upsert(&self, index: &str, documents: Vec<Document>) -> SearchResult<()>;
+
+    /// Remove documents by ID; unknown IDs are ignored
+    async fn delete(&self, index: &str, ids: &[String]) -> SearchResult<()>;
+
+    /// Remove all documents of an index
+    async fn flush(&self, index: &str) -> SearchResult<()>;
+
+    /// Search an index; unknown indexes have no results
+    async fn search(&self, index: &str, query: &Query) -> SearchResult<SearchResults>;
+}
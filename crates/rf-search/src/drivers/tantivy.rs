@@ -0,0 +1,265 @@
+//! Embedded Tantivy driver
+
+use super::SearchDriver;
+use crate::{Document, Query, SearchError, SearchHit, SearchResult, SearchResults, SearchSchema};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tantivy::{
+    collector::TopDocs,
+    doc,
+    query::{AllQuery, QueryParser},
+    schema::{
+        Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value as _, STORED,
+        STRING,
+    },
+    Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, TantivyError, Term,
+};
+
+/// Memory of each index writer
+const WRITER_MEMORY: usize = 50_000_000;
+
+impl From<TantivyError> for SearchError {
+    fn from(e: TantivyError) -> Self {
+        SearchError::IndexError(e.to_string())
+    }
+}
+
+/// Embedded Tantivy driver
+///
+/// Fields are searched with English stemming and BM25 ranking. Filters,
+/// facets and sorting are applied to the stored documents of the matches,
+/// which suits indexes of up to some hundred thousand documents. Writes are
+/// committed before the calls return and block the calling thread while
+/// doing so.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_search::{Search, TantivyDriver};
+///
+/// # fn example() -> rf_search::SearchResult<()> {
+/// let search = Search::new(TantivyDriver::open("storage/search")?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TantivyDriver {
+    directory: Option<PathBuf>,
+    indexes: Mutex<HashMap<String, Arc<TantivyIndex>>>,
+}
+
+struct TantivyIndex {
+    index: Index,
+    writer: Mutex<IndexWriter>,
+    reader: IndexReader,
+    id: Field,
+    content: Field,
+    document: Field,
+}
+
+impl TantivyDriver {
+    /// Store each index in a subdirectory of `directory`
+    pub fn open(directory: impl Into<PathBuf>) -> SearchResult<Self> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)
+            .map_err(|e| SearchError::IndexError(e.to_string()))?;
+        Ok(Self {
+            directory: Some(directory),
+            indexes: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Keep the indexes in memory
+    pub fn in_memory() -> Self {
+        Self {
+            directory: None,
+            indexes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn index(&self, name: &str) -> SearchResult<Arc<TantivyIndex>> {
+        let mut indexes = self.indexes.lock().unwrap();
+        if let Some(index) = indexes.get(name) {
+            return Ok(index.clone());
+        }
+
+        let index = Arc::new(self.create(name)?);
+        indexes.insert(name.to_string(), index.clone());
+        Ok(index)
+    }
+
+    fn create(&self, name: &str) -> SearchResult<TantivyIndex> {
+        let mut builder = Schema::builder();
+        let id = builder.add_text_field("id", STRING | STORED);
+        let content = builder.add_text_field(
+            "content",
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer("en_stem")
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            ),
+        );
+        let document = builder.add_text_field("document", STORED);
+        let schema = builder.build();
+
+        let index = match &self.directory {
+            Some(directory) => {
+                let path = directory.join(name);
+                std::fs::create_dir_all(&path)
+                    .map_err(|e| SearchError::IndexError(e.to_string()))?;
+                let directory = tantivy::directory::MmapDirectory::open(path)
+                    .map_err(|e| SearchError::IndexError(e.to_string()))?;
+                Index::open_or_create(directory, schema)?
+            }
+            None => Index::create_in_ram(schema),
+        };
+        let writer = index.writer(WRITER_MEMORY)?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+
+        Ok(TantivyIndex {
+            index,
+            writer: Mutex::new(writer),
+            reader,
+            id,
+            content,
+            document,
+        })
+    }
+}
+
+impl TantivyIndex {
+    /// Apply changes to the writer, then commit them and make them
+    /// searchable
+    fn write(&self, change: impl FnOnce(&mut IndexWriter) -> SearchResult<()>) -> SearchResult<()> {
+        let mut writer = self.writer.lock().unwrap();
+        change(&mut writer)?;
+        writer.commit()?;
+        self.reader.reload()?;
+        Ok(())
+    }
+
+    fn hit(&self, document: TantivyDocument, score: f32) -> SearchResult<SearchHit> {
+        let json = document
+            .get_first(self.document)
+            .and_then(|value| value.as_str())
+            .ok_or_else(|| SearchError::IndexError("Document without source".to_string()))?;
+        Ok(SearchHit::from_document(serde_json::from_str(json)?, score))
+    }
+}
+
+#[async_trait]
+impl SearchDriver for TantivyDriver {
+    async fn configure(&self, index: &str, _schema: &SearchSchema) -> SearchResult<()> {
+        self.index(index).map(|_| ())
+    }
+
+    async fn upsert(&self, index: &str, documents: Vec<Document>) -> SearchResult<()> {
+        let index = self.index(index)?;
+        index.write(|writer| {
+            for document in documents {
+                let content: Vec<&str> = document.fields.values().map(String::as_str).collect();
+                writer.delete_term(Term::from_field_text(index.id, &document.id));
+                writer.add_document(doc!(
+                    index.id => document.id.clone(),
+                    index.content => content.join("\n"),
+                    index.document => serde_json::to_string(&document)?,
+                ))?;
+            }
+            Ok(())
+        })
+    }
+
+    async fn delete(&self, index: &str, ids: &[String]) -> SearchResult<()> {
+        let index = self.index(index)?;
+        index.write(|writer| {
+            for id in ids {
+                writer.delete_term(Term::from_field_text(index.id, id));
+            }
+            Ok(())
+        })
+    }
+
+    async fn flush(&self, index: &str) -> SearchResult<()> {
+        let index = self.index(index)?;
+        index.write(|writer| {
+            writer.delete_all_documents()?;
+            Ok(())
+        })
+    }
+
+    async fn search(&self, index: &str, query: &Query) -> SearchResult<SearchResults> {
+        let index = self.index(index)?;
+        let searcher = index.reader.searcher();
+
+        let matcher: Box<dyn tantivy::query::Query> = if query.text.trim().is_empty() {
+            Box::new(AllQuery)
+        } else {
+            // Lenient, since the text usually comes from users
+            let parser = QueryParser::for_index(&index.index, vec![index.content]);
+            parser.parse_query_lenient(&query.text).0
+        };
+
+        let limit = (searcher.num_docs() as usize).max(1);
+        let hits = searcher
+            .search(&matcher, &TopDocs::with_limit(limit))?
+            .into_iter()
+            .map(|(score, address)| index.hit(searcher.doc(address)?, score))
+            .collect::<SearchResult<Vec<_>>>()?;
+        Ok(query.apply(hits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tantivy_driver() {
+        let driver = TantivyDriver::in_memory();
+        driver
+            .upsert(
+                "posts",
+                vec![
+                    Document::new("1")
+                        .field("title", "Rust programming")
+                        .meta("lang", "en")
+                        .unwrap(),
+                    Document::new("2")
+                        .field("title", "Programs in Go")
+                        .meta("lang", "de")
+                        .unwrap(),
+                ],
+            )
+            .await
+            .unwrap();
+
+        // Stemmed: "programs" matches "programming"
+        let results = driver.search("posts", &Query::new("programs")).await.unwrap();
+        assert_eq!(results.total, 2);
+
+        let results = driver
+            .search("posts", &Query::new("programs").filter("lang", "en"))
+            .await
+            .unwrap();
+        assert_eq!(results.ids(), vec!["1"]);
+        assert_eq!(results.hits[0].fields["title"], "Rust programming");
+
+        // Replaced by ID
+        driver
+            .upsert("posts", vec![Document::new("1").field("title", "Cooking")])
+            .await
+            .unwrap();
+        let results = driver.search("posts", &Query::new("rust")).await.unwrap();
+        assert!(results.hits.is_empty());
+
+        driver.delete("posts", &["2".to_string()]).await.unwrap();
+        assert_eq!(driver.search("posts", &Query::new("")).await.unwrap().total, 1);
+
+        driver.flush("posts").await.unwrap();
+        assert_eq!(driver.search("posts", &Query::new("")).await.unwrap().total, 0);
+    }
+}
@@ -0,0 +1,146 @@
+//! Index syncing on model events
+
+use crate::{Search, SearchError, Searchable};
+use async_trait::async_trait;
+use rf_events::{
+    EventDispatcher, EventError, EventListenerFor, EventResult, ModelCreated, ModelDeleted,
+    ModelUpdated,
+};
+use std::marker::PhantomData;
+
+/// Keeps search indexes in sync with rf-events model events
+///
+/// ```
+/// # use rf_search::{Document, Searchable};
+/// # struct Post { id: u64 }
+/// # impl Searchable for Post {
+/// #     fn index_name() -> &'static str { "posts" }
+/// #     fn to_document(&self) -> Document { Document::new(self.id.to_string()) }
+/// # }
+/// use rf_events::{EventDispatcher, ModelCreated};
+/// use rf_search::{Search, SearchSyncExt};
+///
+/// # async fn example() -> rf_events::EventResult<()> {
+/// let search = Search::memory();
+/// let dispatcher = EventDispatcher::new();
+/// dispatcher.sync_search::<Post>(search.clone()).await;
+///
+/// // After saving the post
+/// dispatcher.dispatch(ModelCreated::new(Post { id: 1 })).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait SearchSyncExt {
+    /// Index models of `M` when created or updated and remove them when
+    /// deleted
+    async fn sync_search<M: Searchable>(&self, search: Search);
+}
+
+#[async_trait]
+impl SearchSyncExt for EventDispatcher {
+    async fn sync_search<M: Searchable>(&self, search: Search) {
+        self.listen::<ModelCreated<M>, _>(SyncIndex::<M>::new(search.clone()))
+            .await;
+        self.listen::<ModelUpdated<M>, _>(SyncIndex::<M>::new(search.clone()))
+            .await;
+        self.listen::<ModelDeleted<M>, _>(SyncIndex::<M>::new(search))
+            .await;
+    }
+}
+
+struct SyncIndex<M> {
+    search: Search,
+    _model: PhantomData<fn() -> M>,
+}
+
+impl<M> SyncIndex<M> {
+    fn new(search: Search) -> Self {
+        Self {
+            search,
+            _model: PhantomData,
+        }
+    }
+}
+
+fn listener_error(e: SearchError) -> EventError {
+    EventError::ListenerError(format!("Search index sync failed: {}", e))
+}
+
+#[async_trait]
+impl<M: Searchable> EventListenerFor<ModelCreated<M>> for SyncIndex<M> {
+    async fn handle(&self, event: &ModelCreated<M>) -> EventResult<()> {
+        self.search.index(&event.model).await.map_err(listener_error)
+    }
+}
+
+#[async_trait]
+impl<M: Searchable> EventListenerFor<ModelUpdated<M>> for SyncIndex<M> {
+    async fn handle(&self, event: &ModelUpdated<M>) -> EventResult<()> {
+        self.search.index(&event.model).await.map_err(listener_error)
+    }
+}
+
+#[async_trait]
+impl<M: Searchable> EventListenerFor<ModelDeleted<M>> for SyncIndex<M> {
+    async fn handle(&self, event: &ModelDeleted<M>) -> EventResult<()> {
+        self.search.remove(&event.model).await.map_err(listener_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Document, Query};
+
+    struct Product {
+        id: u64,
+        name: &'static str,
+    }
+
+    impl Searchable for Product {
+        fn index_name() -> &'static str {
+            "products"
+        }
+
+        fn to_document(&self) -> Document {
+            Document::new(self.id.to_string()).field("name", self.name)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_on_model_events() {
+        let search = Search::memory();
+        let dispatcher = EventDispatcher::new();
+        dispatcher.sync_search::<Product>(search.clone()).await;
+        let find = |text: &'static str| {
+            let search = search.clone();
+            async move {
+                search
+                    .search::<Product>(&Query::new(text))
+                    .await
+                    .unwrap()
+                    .total
+            }
+        };
+
+        dispatcher
+            .dispatch(ModelCreated::new(Product { id: 1, name: "Desk" }))
+            .await
+            .unwrap();
+        assert_eq!(find("desk").await, 1);
+
+        dispatcher
+            .dispatch(ModelUpdated::new(Product { id: 1, name: "Lamp" }))
+            .await
+            .unwrap();
+        assert_eq!(find("desk").await, 0);
+        assert_eq!(find("lamp").await, 1);
+
+        dispatcher
+            .dispatch(ModelDeleted::new(Product { id: 1, name: "Lamp" }))
+            .await
+            .unwrap();
+        assert_eq!(find("").await, 0);
+    }
+}
@@ -1,14 +1,97 @@
 //! Full-Text Search for RustForge
 //!
-//! This crate provides an in-memory full-text search engine.
+//! This crate provides full-text search over application models:
+//!
+//! - **[`Searchable`]** models describe their index, document and schema
+//! - **[`Search`]** indexes models and runs typed [`Query`]s with filters,
+//!   facets, sorting and pagination
+//! - **Drivers**: in-memory [`MemoryDriver`], `MeilisearchDriver`
+//!   (`meilisearch` feature) and embedded `TantivyDriver` (`tantivy`
+//!   feature)
+//! - **Index syncing** on rf-events model events (`events` feature)
+//! - **Reindexing** from registered importers, also as a CLI command
+//!   (`cli` feature)
+//!
+//! ```
+//! use rf_search::{Document, Query, Search, SearchSchema, Searchable};
+//!
+//! struct Post {
+//!     id: u64,
+//!     title: String,
+//!     category: String,
+//!     published: bool,
+//! }
+//!
+//! impl Searchable for Post {
+//!     fn index_name() -> &'static str {
+//!         "posts"
+//!     }
+//!
+//!     fn to_document(&self) -> Document {
+//!         Document::new(self.id.to_string())
+//!             .field("title", &self.title)
+//!             .meta("category", &self.category)
+//!             .unwrap()
+//!     }
+//!
+//!     fn search_schema() -> SearchSchema {
+//!         SearchSchema::new().searchable(["title"]).filterable(["category"])
+//!     }
+//!
+//!     fn should_be_searchable(&self) -> bool {
+//!         self.published
+//!     }
+//! }
+//!
+//! # async fn example() -> rf_search::SearchResult<()> {
+//! let search = Search::memory();
+//! search
+//!     .index(&Post {
+//!         id: 1,
+//!         title: "Rust web development".into(),
+//!         category: "rust".into(),
+//!         published: true,
+//!     })
+//!     .await?;
+//!
+//! let results = search
+//!     .search::<Post>(&Query::new("rust").filter("category", "rust").facet("category"))
+//!     .await?;
+//! assert_eq!(results.ids(), vec!["1"]);
+//! assert_eq!(results.facets["category"]["rust"], 1);
+//! # Ok(())
+//! # }
+//! ```
 
 use async_trait::async_trait;
 use rust_stemmers::{Algorithm, Stemmer};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use thiserror::Error;
 use unicode_segmentation::UnicodeSegmentation;
 
+#[cfg(feature = "cli")]
+mod command;
+mod drivers;
+#[cfg(feature = "events")]
+mod events;
+mod query;
+mod search;
+mod searchable;
+
+#[cfg(feature = "cli")]
+pub use command::SearchCommand;
+pub use drivers::{MemoryDriver, SearchDriver};
+#[cfg(feature = "meilisearch")]
+pub use drivers::{MeilisearchConfig, MeilisearchDriver};
+#[cfg(feature = "tantivy")]
+pub use drivers::TantivyDriver;
+#[cfg(feature = "events")]
+pub use events::SearchSyncExt;
+pub use query::{Filter, Query, SearchResults, SortOrder};
+pub use search::Search;
+pub use searchable::{SearchSchema, Searchable};
+
 /// Search errors
 #[derive(Debug, Error)]
 pub enum SearchError {
@@ -20,18 +103,26 @@ pub enum SearchError {
 
     #[error("Query error: {0}")]
     QueryError(String),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[cfg(feature = "meilisearch")]
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
 }
 
 pub type SearchResult<T> = Result<T, SearchError>;
 
-/// Searchable document
-pub trait Searchable {
-    /// Get document ID
-    fn id(&self) -> &str;
-
-    /// Get searchable fields
-    fn searchable_fields(&self) -> Vec<String>;
-}
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(SearchError, {
+    Self::DocumentNotFound(_) => (NOT_FOUND, "search.document_not_found"),
+    Self::QueryError(_) => (BAD_REQUEST, "search.invalid_query"),
+    Self::IndexError(_) => (INTERNAL_SERVER_ERROR, "search.index"),
+    Self::Serialization(_) => (INTERNAL_SERVER_ERROR, "search.serialization"),
+    // HTTP errors of the Meilisearch backend
+    _ => (BAD_GATEWAY, "search.backend"),
+});
 
 /// Document to be indexed
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,42 +169,24 @@ pub struct SearchHit {
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
-/// Search query
-pub struct Query {
-    text: String,
-    fuzzy: Option<f32>,
-    limit: usize,
-    offset: usize,
-}
+impl SearchHit {
+    /// Value of a metadata entry or field, metadata first
+    pub fn value(&self, name: &str) -> Option<serde_json::Value> {
+        self.metadata.get(name).cloned().or_else(|| {
+            self.fields
+                .get(name)
+                .map(|value| serde_json::Value::String(value.clone()))
+        })
+    }
 
-impl Query {
-    /// Create a new query
-    pub fn new(text: impl Into<String>) -> Self {
+    pub(crate) fn from_document(document: Document, score: f32) -> Self {
         Self {
-            text: text.into(),
-            fuzzy: None,
-            limit: 10,
-            offset: 0,
+            id: document.id,
+            score,
+            fields: document.fields,
+            metadata: document.metadata,
         }
     }
-
-    /// Enable fuzzy matching
-    pub fn fuzzy(mut self, threshold: f32) -> Self {
-        self.fuzzy = Some(threshold);
-        self
-    }
-
-    /// Set result limit
-    pub fn limit(mut self, limit: usize) -> Self {
-        self.limit = limit;
-        self
-    }
-
-    /// Set result offset
-    pub fn offset(mut self, offset: usize) -> Self {
-        self.offset = offset;
-        self
-    }
 }
 
 /// Tokenizer for splitting text into terms
@@ -139,7 +212,8 @@ impl Tokenizer {
 /// Inverted index for fast searching
 #[derive(Default)]
 struct InvertedIndex {
-    index: HashMap<String, HashSet<String>>,
+    // Term -> document ID -> occurrences
+    index: HashMap<String, HashMap<String, usize>>,
 }
 
 impl InvertedIndex {
@@ -150,13 +224,15 @@ impl InvertedIndex {
     }
 
     fn add_term(&mut self, term: &str, doc_id: &str) {
-        self.index
+        *self
+            .index
             .entry(term.to_string())
-            .or_insert_with(HashSet::new)
-            .insert(doc_id.to_string());
+            .or_default()
+            .entry(doc_id.to_string())
+            .or_default() += 1;
     }
 
-    fn get_documents(&self, term: &str) -> Option<&HashSet<String>> {
+    fn get_documents(&self, term: &str) -> Option<&HashMap<String, usize>> {
         self.index.get(term)
     }
 
@@ -164,6 +240,7 @@ impl InvertedIndex {
         for docs in self.index.values_mut() {
             docs.remove(doc_id);
         }
+        self.index.retain(|_, docs| !docs.is_empty());
     }
 }
 
@@ -184,12 +261,15 @@ impl SearchEngine {
         }
     }
 
-    /// Index a document
+    /// Index a document, replacing the document with the same ID
     pub fn index(&mut self, document: Document) -> SearchResult<()> {
         let doc_id = document.id.clone();
+        if self.documents.contains_key(&doc_id) {
+            self.index.remove_document(&doc_id);
+        }
 
         // Tokenize and index all fields
-        for (field_name, field_value) in &document.fields {
+        for field_value in document.fields.values() {
             let tokens = self.tokenizer.tokenize(field_value);
             for token in tokens {
                 self.index.add_term(&token, &doc_id);
@@ -210,16 +290,29 @@ impl SearchEngine {
     }
 
     /// Search for documents
+    ///
+    /// An empty query text matches all documents.
     pub fn search(&self, query: &Query) -> SearchResult<Vec<SearchHit>> {
-        let tokens = self.tokenizer.tokenize(&query.text);
+        Ok(query.apply(self.matches(&query.text)).hits)
+    }
 
-        // Find matching documents
-        let mut doc_scores: HashMap<String, f32> = HashMap::new();
+    /// Documents matching `text`, by score descending
+    pub(crate) fn matches(&self, text: &str) -> Vec<SearchHit> {
+        let tokens = self.tokenizer.tokenize(text);
+        if tokens.is_empty() {
+            return self
+                .documents
+                .values()
+                .map(|doc| SearchHit::from_document(doc.clone(), 0.0))
+                .collect();
+        }
 
+        // Score documents by term occurrences
+        let mut doc_scores: HashMap<&str, f32> = HashMap::new();
         for token in &tokens {
             if let Some(docs) = self.index.get_documents(token) {
-                for doc_id in docs {
-                    *doc_scores.entry(doc_id.clone()).or_insert(0.0) += 1.0;
+                for (doc_id, count) in docs {
+                    *doc_scores.entry(doc_id.as_str()).or_insert(0.0) += *count as f32;
                 }
             }
         }
@@ -228,22 +321,15 @@ impl SearchEngine {
         let mut hits: Vec<SearchHit> = doc_scores
             .into_iter()
             .filter_map(|(id, score)| {
-                self.documents.get(&id).map(|doc| SearchHit {
-                    id: id.clone(),
-                    score,
-                    fields: doc.fields.clone(),
-                    metadata: doc.metadata.clone(),
-                })
+                self.documents
+                    .get(id)
+                    .map(|doc| SearchHit::from_document(doc.clone(), score))
             })
             .collect();
 
         // Sort by score descending
-        hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
-
-        // Apply pagination
-        let start = query.offset;
-        let end = (query.offset + query.limit).min(hits.len());
-        Ok(hits[start..end].to_vec())
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+        hits
     }
 
     /// Get document count
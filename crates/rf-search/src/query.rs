@@ -0,0 +1,374 @@
+//! Typed search queries and their results
+
+use crate::SearchHit;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Condition on a document value
+///
+/// Values are looked up in the document's metadata, then its fields. Array
+/// values match when any element does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Value equals
+    Equals(String, Value),
+
+    /// Value is one of
+    In(String, Vec<Value>),
+
+    /// Number within inclusive bounds
+    Range {
+        field: String,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+}
+
+impl Filter {
+    /// Name of the filtered value
+    pub fn field(&self) -> &str {
+        match self {
+            Filter::Equals(field, _) | Filter::In(field, _) => field,
+            Filter::Range { field, .. } => field,
+        }
+    }
+
+    fn matches(&self, hit: &SearchHit) -> bool {
+        let Some(value) = hit.value(self.field()) else {
+            return false;
+        };
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+
+        values.iter().any(|value| match self {
+            Filter::Equals(_, expected) => value == expected,
+            Filter::In(_, expected) => expected.contains(value),
+            Filter::Range { min, max, .. } => value.as_f64().is_some_and(|number| {
+                min.is_none_or(|min| number >= min) && max.is_none_or(|max| number <= max)
+            }),
+        })
+    }
+}
+
+/// Sort direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Search query
+///
+/// Results are ordered by relevance unless sorted. An empty text matches
+/// all documents, e.g. to list them by filter.
+///
+/// ```
+/// use rf_search::{Query, SortOrder};
+///
+/// let query = Query::new("rust async")
+///     .filter("status", "published")
+///     .filter_in("category", ["web", "cli"])
+///     .between("price", 10.0, 50.0)
+///     .facet("category")
+///     .sort("published_at", SortOrder::Desc)
+///     .page(2, 20);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Query {
+    pub(crate) text: String,
+    pub(crate) fuzzy: Option<f32>,
+    pub(crate) limit: usize,
+    pub(crate) offset: usize,
+    pub(crate) filters: Vec<Filter>,
+    pub(crate) facets: Vec<String>,
+    pub(crate) sort: Vec<(String, SortOrder)>,
+}
+
+impl Query {
+    /// Create a new query
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            fuzzy: None,
+            limit: 10,
+            offset: 0,
+            filters: Vec::new(),
+            facets: Vec::new(),
+            sort: Vec::new(),
+        }
+    }
+
+    /// Enable fuzzy matching
+    pub fn fuzzy(mut self, threshold: f32) -> Self {
+        self.fuzzy = Some(threshold);
+        self
+    }
+
+    /// Set result limit
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Set result offset
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Return page `page` (starting at 1) of `per_page` results
+    pub fn page(self, page: usize, per_page: usize) -> Self {
+        self.offset(page.saturating_sub(1) * per_page)
+            .limit(per_page)
+    }
+
+    /// Only match documents whose `field` equals `value`
+    pub fn filter(mut self, field: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.filters
+            .push(Filter::Equals(field.into(), value.into()));
+        self
+    }
+
+    /// Only match documents whose `field` is one of `values`
+    pub fn filter_in<V: Into<Value>>(
+        mut self,
+        field: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        let values = values.into_iter().map(Into::into).collect();
+        self.filters.push(Filter::In(field.into(), values));
+        self
+    }
+
+    /// Only match documents whose `field` is between `min` and `max`
+    pub fn between(self, field: impl Into<String>, min: f64, max: f64) -> Self {
+        self.range(field, Some(min), Some(max))
+    }
+
+    /// Only match documents whose `field` is at least `min`
+    pub fn at_least(self, field: impl Into<String>, min: f64) -> Self {
+        self.range(field, Some(min), None)
+    }
+
+    /// Only match documents whose `field` is at most `max`
+    pub fn at_most(self, field: impl Into<String>, max: f64) -> Self {
+        self.range(field, None, Some(max))
+    }
+
+    fn range(mut self, field: impl Into<String>, min: Option<f64>, max: Option<f64>) -> Self {
+        self.filters.push(Filter::Range {
+            field: field.into(),
+            min,
+            max,
+        });
+        self
+    }
+
+    /// Count the values of `field` among all matches
+    pub fn facet(mut self, field: impl Into<String>) -> Self {
+        self.facets.push(field.into());
+        self
+    }
+
+    /// Sort by `field`; later sorts break ties of earlier ones
+    pub fn sort(mut self, field: impl Into<String>, order: SortOrder) -> Self {
+        self.sort.push((field.into(), order));
+        self
+    }
+
+    /// Query text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Filters
+    pub fn filters(&self) -> &[Filter] {
+        &self.filters
+    }
+
+    /// Filter, facet, sort and paginate `hits` ordered by relevance
+    pub(crate) fn apply(&self, hits: Vec<SearchHit>) -> SearchResults {
+        let mut hits: Vec<SearchHit> = hits
+            .into_iter()
+            .filter(|hit| self.filters.iter().all(|filter| filter.matches(hit)))
+            .collect();
+
+        let mut facets = HashMap::new();
+        for field in &self.facets {
+            let counts: &mut HashMap<String, usize> = facets.entry(field.clone()).or_default();
+            for value in hits.iter().filter_map(|hit| hit.value(field)) {
+                let values = match value {
+                    Value::Array(values) => values,
+                    value => vec![value],
+                };
+                for value in values {
+                    *counts.entry(facet_key(value)).or_default() += 1;
+                }
+            }
+        }
+
+        if !self.sort.is_empty() {
+            // Stable, so relevance breaks remaining ties
+            hits.sort_by(|a, b| {
+                self.sort
+                    .iter()
+                    .map(|(field, order)| {
+                        let ordering = compare(a.value(field), b.value(field));
+                        match order {
+                            SortOrder::Asc => ordering,
+                            SortOrder::Desc => ordering.reverse(),
+                        }
+                    })
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+
+        let total = hits.len();
+        let hits = hits
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit)
+            .collect();
+        SearchResults {
+            hits,
+            total,
+            facets,
+            offset: self.offset,
+            limit: self.limit,
+        }
+    }
+}
+
+fn facet_key(value: Value) -> String {
+    match value {
+        Value::String(value) => value,
+        value => value.to_string(),
+    }
+}
+
+/// Numbers before strings before other values; missing values last
+fn compare(a: Option<Value>, b: Option<Value>) -> Ordering {
+    match (a, b) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => {
+            let (a, b) = (
+                a.as_f64().unwrap_or_default(),
+                b.as_f64().unwrap_or_default(),
+            );
+            a.total_cmp(&b)
+        }
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(&b),
+        (Some(Value::Number(_)), Some(_)) => Ordering::Less,
+        (Some(_), Some(Value::Number(_))) => Ordering::Greater,
+        (Some(Value::String(_)), Some(_)) => Ordering::Less,
+        (Some(_), Some(Value::String(_))) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        _ => Ordering::Equal,
+    }
+}
+
+/// A page of search results
+#[derive(Debug, Clone, Default)]
+pub struct SearchResults {
+    /// Hits of the page
+    pub hits: Vec<SearchHit>,
+
+    /// Number of matches across all pages; estimated by some drivers
+    pub total: usize,
+
+    /// Facet -> value -> number of matches
+    pub facets: HashMap<String, HashMap<String, usize>>,
+
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl SearchResults {
+    /// Document IDs of the hits, e.g. to load the models
+    pub fn ids(&self) -> Vec<&str> {
+        self.hits.iter().map(|hit| hit.id.as_str()).collect()
+    }
+
+    /// Number of pages
+    pub fn total_pages(&self) -> usize {
+        if self.limit == 0 {
+            return 0;
+        }
+        self.total.div_ceil(self.limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Document;
+    use serde_json::json;
+
+    fn hit(id: &str, category: &str, price: f64, tags: Value) -> SearchHit {
+        let document = Document::new(id)
+            .field("title", format!("Post {}", id))
+            .meta("category", category)
+            .unwrap()
+            .meta("price", price)
+            .unwrap()
+            .meta("tags", tags)
+            .unwrap();
+        SearchHit::from_document(document, 1.0)
+    }
+
+    fn hits() -> Vec<SearchHit> {
+        vec![
+            hit("1", "web", 10.0, json!(["rust", "axum"])),
+            hit("2", "cli", 25.0, json!(["rust"])),
+            hit("3", "web", 40.0, json!(["go"])),
+        ]
+    }
+
+    #[test]
+    fn test_filters() {
+        let results = Query::new("").filter("category", "web").apply(hits());
+        assert_eq!(results.ids(), vec!["1", "3"]);
+
+        let results = Query::new("")
+            .filter_in("category", ["cli", "tui"])
+            .apply(hits());
+        assert_eq!(results.ids(), vec!["2"]);
+
+        let results = Query::new("").between("price", 10.0, 25.0).apply(hits());
+        assert_eq!(results.ids(), vec!["1", "2"]);
+
+        // Arrays match by element; fields are filterable too
+        let results = Query::new("")
+            .filter("tags", "rust")
+            .filter("title", "Post 2")
+            .apply(hits());
+        assert_eq!(results.ids(), vec!["2"]);
+
+        assert!(Query::new("")
+            .filter("missing", 1)
+            .apply(hits())
+            .hits
+            .is_empty());
+    }
+
+    #[test]
+    fn test_facets_sort_and_pages() {
+        let results = Query::new("")
+            .facet("category")
+            .facet("tags")
+            .sort("price", SortOrder::Desc)
+            .page(2, 2)
+            .apply(hits());
+
+        // Facets count all matches, not only the page
+        assert_eq!(results.facets["category"]["web"], 2);
+        assert_eq!(results.facets["tags"]["rust"], 2);
+        assert_eq!(results.ids(), vec!["1"]);
+        assert_eq!(results.total, 3);
+        assert_eq!(results.total_pages(), 2);
+    }
+}
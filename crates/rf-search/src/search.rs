@@ -0,0 +1,327 @@
+//! Search facade over a driver
+
+use crate::{
+    Document, MemoryDriver, Query, SearchDriver, SearchError, SearchResult, SearchResults,
+    SearchSchema, Searchable,
+};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+/// Number of documents sent to the driver at once when reindexing
+const IMPORT_CHUNK_SIZE: usize = 500;
+
+type Loader = Arc<
+    dyn Fn() -> Pin<Box<dyn Future<Output = SearchResult<Vec<Document>>> + Send>> + Send + Sync,
+>;
+
+#[derive(Clone)]
+struct Importer {
+    schema: SearchSchema,
+    load: Loader,
+}
+
+/// Indexes [`Searchable`] models and searches them
+///
+/// Cheap to clone.
+///
+/// # Example
+///
+/// ```
+/// # use rf_search::{Document, Searchable};
+/// # struct Post { id: u64, title: String }
+/// # impl Searchable for Post {
+/// #     fn index_name() -> &'static str { "posts" }
+/// #     fn to_document(&self) -> Document {
+/// #         Document::new(self.id.to_string()).field("title", &self.title)
+/// #     }
+/// # }
+/// # async fn load_posts() -> Vec<Post> {
+/// #     vec![Post { id: 1, title: "Hello".into() }]
+/// # }
+/// use rf_search::{Query, Search};
+///
+/// # async fn example() -> rf_search::SearchResult<()> {
+/// let search = Search::memory()
+///     .prefix("prod_")
+///     // Loads all posts when reindexing
+///     .importer::<Post, _, _>(|| async { Ok(load_posts().await) });
+///
+/// search.reindex_all().await?;
+/// let results = search.search::<Post>(&Query::new("hello").page(1, 20)).await?;
+/// assert_eq!(results.total, 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Search {
+    driver: Arc<dyn SearchDriver>,
+    prefix: String,
+    importers: Arc<RwLock<BTreeMap<String, Importer>>>,
+}
+
+impl Search {
+    /// Create a search over `driver`
+    pub fn new(driver: impl SearchDriver + 'static) -> Self {
+        Self {
+            driver: Arc::new(driver),
+            prefix: String::new(),
+            importers: Arc::new(RwLock::new(BTreeMap::new())),
+        }
+    }
+
+    /// Create a search over a [`MemoryDriver`]
+    pub fn memory() -> Self {
+        Self::new(MemoryDriver::new())
+    }
+
+    /// Prefix index names, e.g. to share a server between environments
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Register how to load all models of `M` for reindexing
+    pub fn importer<M, F, Fut>(self, load: F) -> Self
+    where
+        M: Searchable,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = SearchResult<Vec<M>>> + Send + 'static,
+    {
+        let load: Loader = Arc::new(move || {
+            let models = load();
+            Box::pin(async move {
+                Ok(models
+                    .await?
+                    .iter()
+                    .filter(|model| model.should_be_searchable())
+                    .map(Searchable::to_document)
+                    .collect())
+            })
+        });
+
+        self.importers.write().unwrap().insert(
+            M::index_name().to_string(),
+            Importer {
+                schema: M::search_schema(),
+                load,
+            },
+        );
+        self
+    }
+
+    /// Underlying driver
+    pub fn driver(&self) -> &dyn SearchDriver {
+        self.driver.as_ref()
+    }
+
+    /// Full name of the index of `M`
+    pub fn index_name<M: Searchable>(&self) -> String {
+        self.full_name(M::index_name())
+    }
+
+    /// Names of the indexes with importers, without prefix
+    pub fn indexes(&self) -> Vec<String> {
+        self.importers.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Apply the [`SearchSchema`] of `M` to its index
+    pub async fn configure<M: Searchable>(&self) -> SearchResult<()> {
+        self.driver
+            .configure(&self.index_name::<M>(), &M::search_schema())
+            .await
+    }
+
+    /// Add or update a model, or remove it if it
+    /// [shouldn't be searchable](Searchable::should_be_searchable)
+    pub async fn index<M: Searchable>(&self, model: &M) -> SearchResult<()> {
+        let document = model.to_document();
+        if !model.should_be_searchable() {
+            return self
+                .driver
+                .delete(&self.index_name::<M>(), &[document.id])
+                .await;
+        }
+        self.driver
+            .upsert(&self.index_name::<M>(), vec![document])
+            .await
+    }
+
+    /// Add or update models, removing those that shouldn't be searchable
+    pub async fn index_many<M: Searchable>(&self, models: &[M]) -> SearchResult<()> {
+        let (searchable, removed): (Vec<&M>, Vec<&M>) = models
+            .iter()
+            .partition(|model| model.should_be_searchable());
+        let index = self.index_name::<M>();
+
+        let removed: Vec<String> = removed.iter().map(|model| model.to_document().id).collect();
+        self.driver.delete(&index, &removed).await?;
+        let documents = searchable.iter().map(|model| model.to_document()).collect();
+        self.driver.upsert(&index, documents).await
+    }
+
+    /// Remove a model
+    pub async fn remove<M: Searchable>(&self, model: &M) -> SearchResult<()> {
+        self.driver
+            .delete(&self.index_name::<M>(), &[model.to_document().id])
+            .await
+    }
+
+    /// Remove all models of `M`
+    pub async fn flush<M: Searchable>(&self) -> SearchResult<()> {
+        self.driver.flush(&self.index_name::<M>()).await
+    }
+
+    /// Remove all documents of an index by name, without prefix
+    pub async fn flush_index(&self, index: &str) -> SearchResult<()> {
+        self.driver.flush(&self.full_name(index)).await
+    }
+
+    /// Search the models of `M`
+    pub async fn search<M: Searchable>(&self, query: &Query) -> SearchResult<SearchResults> {
+        self.driver.search(&self.index_name::<M>(), query).await
+    }
+
+    /// Replace the index of `M` with `models`, returning the number indexed
+    pub async fn reindex<M: Searchable>(&self, models: &[M]) -> SearchResult<usize> {
+        let documents = models
+            .iter()
+            .filter(|model| model.should_be_searchable())
+            .map(Searchable::to_document)
+            .collect();
+        self.rebuild(M::index_name(), &M::search_schema(), documents)
+            .await
+    }
+
+    /// Rebuild an index, by name without prefix, from its importer,
+    /// returning the number of documents indexed
+    pub async fn reindex_index(&self, index: &str) -> SearchResult<usize> {
+        let importer = self
+            .importers
+            .read()
+            .unwrap()
+            .get(index)
+            .cloned()
+            .ok_or_else(|| SearchError::IndexError(format!("No importer for index {}", index)))?;
+
+        let documents = (importer.load)().await?;
+        self.rebuild(index, &importer.schema, documents).await
+    }
+
+    /// Rebuild all indexes with importers, returning the number of
+    /// documents indexed per index
+    pub async fn reindex_all(&self) -> SearchResult<Vec<(String, usize)>> {
+        let mut counts = Vec::new();
+        for index in self.indexes() {
+            let count = self.reindex_index(&index).await?;
+            counts.push((index, count));
+        }
+        Ok(counts)
+    }
+
+    async fn rebuild(
+        &self,
+        index: &str,
+        schema: &SearchSchema,
+        documents: Vec<Document>,
+    ) -> SearchResult<usize> {
+        let index = self.full_name(index);
+        let count = documents.len();
+
+        self.driver.flush(&index).await?;
+        self.driver.configure(&index, schema).await?;
+        for chunk in documents.chunks(IMPORT_CHUNK_SIZE) {
+            self.driver.upsert(&index, chunk.to_vec()).await?;
+        }
+
+        tracing::info!(index = %index, count, "Search index rebuilt");
+        Ok(count)
+    }
+
+    fn full_name(&self, index: &str) -> String {
+        format!("{}{}", self.prefix, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Post {
+        id: u64,
+        title: String,
+        published: bool,
+    }
+
+    impl Searchable for Post {
+        fn index_name() -> &'static str {
+            "posts"
+        }
+
+        fn to_document(&self) -> Document {
+            Document::new(self.id.to_string()).field("title", &self.title)
+        }
+
+        fn should_be_searchable(&self) -> bool {
+            self.published
+        }
+    }
+
+    fn post(id: u64, title: &str, published: bool) -> Post {
+        Post {
+            id,
+            title: title.to_string(),
+            published,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_and_search() {
+        let search = Search::memory().prefix("test_");
+        assert_eq!(search.index_name::<Post>(), "test_posts");
+
+        search.index(&post(1, "Rust traits", true)).await.unwrap();
+        search
+            .index_many(&[post(2, "Rust macros", true), post(3, "Rust drafts", false)])
+            .await
+            .unwrap();
+        let results = search.search::<Post>(&Query::new("rust")).await.unwrap();
+        assert_eq!(results.total, 2);
+
+        // Unpublishing removes the post
+        search.index(&post(1, "Rust traits", false)).await.unwrap();
+        let results = search.search::<Post>(&Query::new("rust")).await.unwrap();
+        assert_eq!(results.ids(), vec!["2"]);
+
+        search.remove(&post(2, "Rust macros", true)).await.unwrap();
+        let results = search.search::<Post>(&Query::new("")).await.unwrap();
+        assert_eq!(results.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_reindex_all() {
+        let search = Search::memory().importer::<Post, _, _>(|| async {
+            Ok(vec![
+                post(1, "One", true),
+                post(2, "Two", true),
+                post(3, "Three", false),
+            ])
+        });
+        // Stale documents are dropped
+        search.index(&post(9, "Stale", true)).await.unwrap();
+
+        assert_eq!(search.indexes(), vec!["posts"]);
+        assert_eq!(
+            search.reindex_all().await.unwrap(),
+            vec![("posts".to_string(), 2)]
+        );
+        let results = search.search::<Post>(&Query::new("")).await.unwrap();
+        assert_eq!(results.total, 2);
+
+        assert!(matches!(
+            search.reindex_index("users").await,
+            Err(SearchError::IndexError(_))
+        ));
+    }
+}
@@ -0,0 +1,76 @@
+//! Models that can be searched
+
+use crate::Document;
+
+/// A model indexed for search
+///
+/// See the [crate docs](crate) for an example.
+pub trait Searchable: Send + Sync + 'static {
+    /// Name of the model's index, e.g. `posts`
+    fn index_name() -> &'static str
+    where
+        Self: Sized;
+
+    /// Document indexed for the model
+    ///
+    /// Its ID identifies the model in the index, so it must be stable.
+    /// Fields are searched as text; metadata is kept for filters, facets
+    /// and sorting.
+    fn to_document(&self) -> Document;
+
+    /// Fields searched, filtered and sorted by
+    fn search_schema() -> SearchSchema
+    where
+        Self: Sized,
+    {
+        SearchSchema::default()
+    }
+
+    /// Whether the model belongs in the index, e.g. only published posts
+    ///
+    /// Models that stop being searchable are removed when indexed.
+    fn should_be_searchable(&self) -> bool {
+        true
+    }
+}
+
+/// Settings of an index
+///
+/// Meilisearch only filters, facets and sorts by declared fields and
+/// searches the declared searchable fields in order of importance. The
+/// embedded drivers accept any field.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchSchema {
+    /// Searched fields, most important first; all fields when empty
+    pub searchable: Vec<String>,
+
+    /// Fields usable in filters and facets
+    pub filterable: Vec<String>,
+
+    /// Fields usable for sorting
+    pub sortable: Vec<String>,
+}
+
+impl SearchSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the searched fields, most important first
+    pub fn searchable<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.searchable = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the fields usable in filters and facets
+    pub fn filterable<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.filterable = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the fields usable for sorting
+    pub fn sortable<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.sortable = fields.into_iter().map(Into::into).collect();
+        self
+    }
+}
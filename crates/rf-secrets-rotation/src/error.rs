@@ -0,0 +1,51 @@
+//! Key rotation errors
+
+use crate::SecretKind;
+use rf_audit::AuditError;
+use rf_encryption::EncryptionError;
+use thiserror::Error;
+
+/// Key rotation errors
+#[derive(Debug, Error)]
+pub enum RotationError {
+    #[error("{0} is not configured")]
+    NotConfigured(SecretKind),
+
+    #[error("{0} has no version {1}")]
+    UnknownVersion(SecretKind, u32),
+
+    #[error("Version {1} is the current {0} and can't be retired")]
+    CurrentVersion(SecretKind, u32),
+
+    #[error("Unknown ciphertext store {0}")]
+    UnknownStore(String),
+
+    #[error("Store error: {0}")]
+    Store(String),
+
+    #[error("Encryption error: {0}")]
+    Encryption(#[from] EncryptionError),
+
+    #[error("Audit error: {0}")]
+    Audit(#[from] AuditError),
+
+    #[cfg(feature = "jwt")]
+    #[error("JWT error: {0}")]
+    Jwt(#[from] rf_auth::AuthError),
+}
+
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(RotationError, {
+    Self::UnknownVersion(..) => (NOT_FOUND, "secrets.unknown_version"),
+    Self::CurrentVersion(..) => (CONFLICT, "secrets.current_version"),
+    Self::NotConfigured(_) => (INTERNAL_SERVER_ERROR, "secrets.not_configured"),
+    Self::UnknownStore(_) => (INTERNAL_SERVER_ERROR, "secrets.unknown_store"),
+    Self::Store(_) => (INTERNAL_SERVER_ERROR, "secrets.store"),
+    Self::Encryption(_) => (INTERNAL_SERVER_ERROR, "secrets.encryption"),
+    Self::Audit(_) => (INTERNAL_SERVER_ERROR, "secrets.audit"),
+    // JWT signing errors
+    _ => (INTERNAL_SERVER_ERROR, "secrets.internal"),
+});
+
+/// Result type for key rotation
+pub type RotationResult<T> = Result<T, RotationError>;
@@ -0,0 +1,27 @@
+//! Key rotation for RustForge
+//!
+//! Rotates `APP_KEY` and JWT secrets without downtime, e.g. for an annual
+//! key rotation policy:
+//!
+//! 1. [`KeyRotation::rotate`] adds a new version of a secret. The current
+//!    version encrypts and signs, previous versions still decrypt and verify.
+//! 2. Values encrypted with a previous application key are re-encrypted
+//!    lazily when read, and for every registered [`CiphertextStore`] in
+//!    batches, with [`RotationProgress`] reported along the way.
+//! 3. [`KeyRotation::retire`] drops versions nothing uses any more.
+//!
+//! Each step is written to the audit log.
+//!
+//! # Features
+//!
+//! - `jwt`: JWT managers accepting tokens signed with previous secrets
+
+mod error;
+mod rotation;
+mod secrets;
+mod store;
+
+pub use error::{RotationError, RotationResult};
+pub use rotation::{Decrypted, KeyRotation, RotationProgress, RotationReport};
+pub use secrets::{KeyVersion, SecretKind, SecretVersions};
+pub use store::{Ciphertext, CiphertextStore, MemoryCiphertextStore};
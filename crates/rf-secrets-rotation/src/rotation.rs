@@ -0,0 +1,678 @@
+//! Rotation manager
+
+use crate::{CiphertextStore, RotationError, RotationResult, SecretKind, SecretVersions};
+use rf_audit::{AuditAction, AuditEntry, AuditLogger};
+use rf_encryption::{AppKey, Encrypter};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+type ProgressCallback = Arc<dyn Fn(&RotationProgress) + Send + Sync>;
+
+/// Progress of re-encrypting stored ciphertexts
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RotationProgress {
+    /// Store being processed
+    pub store: Option<String>,
+    pub total: u64,
+    pub processed: u64,
+    pub reencrypted: u64,
+    /// Values that could not be decrypted with any active key
+    pub failed: u64,
+    /// Values the application changed while they were re-encrypted, left
+    /// as written
+    pub skipped: u64,
+    pub finished: bool,
+}
+
+impl RotationProgress {
+    /// Processed share of all values, from 0 to 100
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            return 100.0;
+        }
+        self.processed as f64 * 100.0 / self.total as f64
+    }
+}
+
+/// Outcome of [`KeyRotation::rotate`]
+#[derive(Debug, Clone)]
+pub struct RotationReport {
+    pub kind: SecretKind,
+
+    /// The new current version
+    pub version: u32,
+
+    /// Re-encryption of stored values, for the application key
+    pub reencryption: Option<RotationProgress>,
+}
+
+/// A decrypted value and, if it was encrypted with a previous key, the
+/// value encrypted with the current one for writing back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decrypted {
+    pub plaintext: String,
+    pub reencrypted: Option<String>,
+}
+
+/// Rotates `APP_KEY` and JWT secrets without downtime
+///
+/// Every secret has several active versions: the current one encrypts and
+/// signs, previous ones still decrypt and verify. Values encrypted with a
+/// previous key are re-encrypted lazily when read through
+/// [`KeyRotation::decrypt`] or [`KeyRotation::read`], and eagerly for all
+/// registered stores by [`KeyRotation::rotate`]. Once nothing uses an old
+/// version any more, [`KeyRotation::retire`] drops it.
+///
+/// Rotations, re-encryptions and retirements are written to the audit log.
+/// Persist [`SecretVersions::env_values`] after a rotation so restarts and
+/// other instances pick up the new version.
+///
+/// ```
+/// use rf_encryption::AppKey;
+/// use rf_secrets_rotation::{KeyRotation, MemoryCiphertextStore, SecretKind, SecretVersions};
+///
+/// # async fn example() -> rf_secrets_rotation::RotationResult<()> {
+/// let ssn = MemoryCiphertextStore::new("users.ssn");
+/// let rotation = KeyRotation::new(SecretVersions::new(SecretKind::AppKey, AppKey::generate()))?
+///     .store(ssn.clone());
+/// ssn.insert("1", rotation.encrypt("078-05-1120")?);
+///
+/// let report = rotation.rotate(SecretKind::AppKey).await?;
+/// assert_eq!(report.version, 2);
+/// assert_eq!(report.reencryption.unwrap().reencrypted, 1);
+///
+/// rotation.retire(SecretKind::AppKey, 1).await?;
+/// assert_eq!(rotation.read("users.ssn", "1").await?.as_deref(), Some("078-05-1120"));
+/// # Ok(())
+/// # }
+/// ```
+pub struct KeyRotation {
+    secrets: RwLock<HashMap<SecretKind, SecretVersions>>,
+    encrypter: RwLock<Arc<Encrypter>>,
+    stores: Vec<Arc<dyn CiphertextStore>>,
+    audit: Arc<AuditLogger>,
+    batch_size: usize,
+    progress: Mutex<RotationProgress>,
+    on_progress: Option<ProgressCallback>,
+}
+
+impl KeyRotation {
+    /// Manager for the versions of the application key
+    pub fn new(app_key: SecretVersions) -> RotationResult<Self> {
+        if app_key.kind() != SecretKind::AppKey {
+            return Err(RotationError::NotConfigured(SecretKind::AppKey));
+        }
+
+        Ok(Self {
+            encrypter: RwLock::new(Arc::new(encrypter(&app_key)?)),
+            secrets: RwLock::new(HashMap::from([(SecretKind::AppKey, app_key)])),
+            stores: Vec::new(),
+            audit: Arc::new(AuditLogger::new()),
+            batch_size: 500,
+            progress: Mutex::new(RotationProgress::default()),
+            on_progress: None,
+        })
+    }
+
+    /// Manager for `APP_KEY` and, if set, `JWT_SECRET`, with their previous
+    /// versions
+    pub fn from_env() -> RotationResult<Self> {
+        let rotation = Self::new(SecretVersions::from_env(SecretKind::AppKey)?)?;
+        match SecretVersions::from_env(SecretKind::JwtSecret) {
+            Ok(jwt) => Ok(rotation.jwt_secret(jwt)),
+            Err(RotationError::NotConfigured(_)) => Ok(rotation),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Manage the versions of the JWT secret as well
+    pub fn jwt_secret(self, versions: SecretVersions) -> Self {
+        self.write_secrets().insert(SecretKind::JwtSecret, versions);
+        self
+    }
+
+    /// Re-encrypt the values of `store` on rotation
+    pub fn store(mut self, store: impl CiphertextStore + 'static) -> Self {
+        self.stores.push(Arc::new(store));
+        self
+    }
+
+    /// Write audit entries to `logger` (default: in memory)
+    pub fn audit(mut self, logger: Arc<AuditLogger>) -> Self {
+        self.audit = logger;
+        self
+    }
+
+    /// Number of values re-encrypted per batch (default: 500)
+    pub fn batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    /// Call `callback` after every batch of a re-encryption
+    pub fn on_progress(
+        mut self,
+        callback: impl Fn(&RotationProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Active versions of a secret
+    pub fn versions(&self, kind: SecretKind) -> Option<SecretVersions> {
+        self.read_secrets().get(&kind).cloned()
+    }
+
+    /// Encrypter for the active versions of the application key
+    pub fn encrypter(&self) -> Arc<Encrypter> {
+        self.encrypter
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// JWT manager signing with the current JWT secret and accepting tokens
+    /// signed with previous ones
+    #[cfg(feature = "jwt")]
+    pub fn jwt_manager(&self) -> RotationResult<rf_auth::JwtManager> {
+        let versions = self
+            .versions(SecretKind::JwtSecret)
+            .ok_or(RotationError::NotConfigured(SecretKind::JwtSecret))?;
+        Ok(jwt_manager(&versions)?)
+    }
+
+    /// Encrypt with the current application key
+    pub fn encrypt(&self, value: &str) -> RotationResult<String> {
+        Ok(self.encrypter().encrypt_string(value)?)
+    }
+
+    /// Decrypt with any active application key, re-encrypting values
+    /// encrypted with a previous one
+    pub fn decrypt(&self, payload: &str) -> RotationResult<Decrypted> {
+        let encrypter = self.encrypter();
+        let plaintext = encrypter.decrypt_string(payload)?;
+        let reencrypted = if encrypter.needs_rotation(payload) {
+            Some(encrypter.encrypt_string(&plaintext)?)
+        } else {
+            None
+        };
+
+        Ok(Decrypted {
+            plaintext,
+            reencrypted,
+        })
+    }
+
+    /// Read and decrypt a value of a registered store, writing it back
+    /// re-encrypted if it used a previous key
+    pub async fn read(&self, store: &str, id: &str) -> RotationResult<Option<String>> {
+        let store = self
+            .stores
+            .iter()
+            .find(|s| s.name() == store)
+            .ok_or_else(|| RotationError::UnknownStore(store.to_string()))?;
+        let Some(payload) = store.get(id).await? else {
+            return Ok(None);
+        };
+
+        let decrypted = self.decrypt(&payload)?;
+        if let Some(reencrypted) = decrypted.reencrypted {
+            // A value written meanwhile is newer than ours, keep it
+            store.update_if(id, &payload, reencrypted).await?;
+        }
+        Ok(Some(decrypted.plaintext))
+    }
+
+    /// Rotate to a newly generated secret
+    ///
+    /// Rotating the application key re-encrypts the values of all
+    /// registered stores; JWT secrets just start signing with the new
+    /// version while tokens signed with older ones stay valid.
+    pub async fn rotate(&self, kind: SecretKind) -> RotationResult<RotationReport> {
+        self.rotate_to(kind, AppKey::generate()).await
+    }
+
+    /// Rotate to `secret`
+    pub async fn rotate_to(
+        &self,
+        kind: SecretKind,
+        secret: impl Into<String>,
+    ) -> RotationResult<RotationReport> {
+        let mut versions = self
+            .versions(kind)
+            .ok_or(RotationError::NotConfigured(kind))?;
+        let version = versions.push(secret);
+        self.activate(versions)?;
+
+        tracing::info!(secret = %kind, version, "Rotated secret");
+        self.log(kind, "key_rotated", version, Vec::new()).await?;
+
+        let reencryption = match kind {
+            SecretKind::AppKey => Some(self.reencrypt().await?),
+            SecretKind::JwtSecret => None,
+        };
+        Ok(RotationReport {
+            kind,
+            version,
+            reencryption,
+        })
+    }
+
+    /// Re-encrypt all values of the registered stores that use a previous
+    /// application key
+    pub async fn reencrypt(&self) -> RotationResult<RotationProgress> {
+        let encrypter = self.encrypter();
+        let mut total = 0;
+        for store in &self.stores {
+            total += store.count().await?;
+        }
+        self.update_progress(|progress| {
+            *progress = RotationProgress {
+                total,
+                ..RotationProgress::default()
+            }
+        });
+
+        for store in &self.stores {
+            let mut after: Option<String> = None;
+            loop {
+                let batch = store.fetch(after.as_deref(), self.batch_size).await?;
+                let Some(last) = batch.last() else {
+                    break;
+                };
+                after = Some(last.id.clone());
+
+                let (mut reencrypted, mut failed, mut skipped) = (0, 0, 0);
+                for ciphertext in &batch {
+                    if !encrypter.needs_rotation(&ciphertext.payload) {
+                        continue;
+                    }
+                    match encrypter.rotate(&ciphertext.payload) {
+                        Ok(payload) => {
+                            if store
+                                .update_if(&ciphertext.id, &ciphertext.payload, payload)
+                                .await?
+                            {
+                                reencrypted += 1;
+                            } else {
+                                skipped += 1;
+                            }
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                store = store.name(),
+                                id = %ciphertext.id,
+                                error = %e,
+                                "Failed to re-encrypt value"
+                            );
+                            failed += 1;
+                        }
+                    }
+                }
+
+                self.update_progress(|progress| {
+                    progress.store = Some(store.name().to_string());
+                    progress.processed += batch.len() as u64;
+                    progress.reencrypted += reencrypted;
+                    progress.failed += failed;
+                    progress.skipped += skipped;
+                });
+            }
+        }
+
+        let progress = self.update_progress(|progress| {
+            progress.store = None;
+            progress.finished = true;
+        });
+        let version = self
+            .versions(SecretKind::AppKey)
+            .map_or(0, |v| v.current().version);
+        self.log(
+            SecretKind::AppKey,
+            "values_reencrypted",
+            version,
+            vec![
+                ("reencrypted", progress.reencrypted.to_string()),
+                ("failed", progress.failed.to_string()),
+                ("skipped", progress.skipped.to_string()),
+            ],
+        )
+        .await?;
+        Ok(progress)
+    }
+
+    /// Stop accepting an old version of a secret
+    ///
+    /// Values still encrypted with it can't be decrypted any more, so
+    /// check that the last re-encryption had no failures first.
+    pub async fn retire(&self, kind: SecretKind, version: u32) -> RotationResult<()> {
+        let mut versions = self
+            .versions(kind)
+            .ok_or(RotationError::NotConfigured(kind))?;
+        versions.retire(version)?;
+        self.activate(versions)?;
+
+        tracing::info!(secret = %kind, version, "Retired secret version");
+        self.log(kind, "key_retired", version, Vec::new()).await
+    }
+
+    /// Progress of the running or last re-encryption
+    pub fn progress(&self) -> RotationProgress {
+        self.progress
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Validate and switch to new versions of a secret
+    fn activate(&self, versions: SecretVersions) -> RotationResult<()> {
+        match versions.kind() {
+            SecretKind::AppKey => {
+                let encrypter = Arc::new(encrypter(&versions)?);
+                *self.encrypter.write().unwrap_or_else(|e| e.into_inner()) = encrypter;
+            }
+            #[cfg(feature = "jwt")]
+            SecretKind::JwtSecret => {
+                jwt_manager(&versions)?;
+            }
+            #[cfg(not(feature = "jwt"))]
+            SecretKind::JwtSecret => {}
+        }
+        self.write_secrets().insert(versions.kind(), versions);
+        Ok(())
+    }
+
+    fn update_progress(&self, update: impl FnOnce(&mut RotationProgress)) -> RotationProgress {
+        let progress = {
+            let mut progress = self.progress.lock().unwrap_or_else(|e| e.into_inner());
+            update(&mut progress);
+            progress.clone()
+        };
+        if let Some(callback) = &self.on_progress {
+            callback(&progress);
+        }
+        progress
+    }
+
+    async fn log(
+        &self,
+        kind: SecretKind,
+        action: &str,
+        version: u32,
+        metadata: Vec<(&str, String)>,
+    ) -> RotationResult<()> {
+        let mut entry =
+            AuditEntry::new("secret", kind.as_str(), AuditAction::Custom(action.into()))
+                .metadata("version", version.to_string());
+        for (key, value) in metadata {
+            entry = entry.metadata(key, value);
+        }
+        Ok(self.audit.log(entry).await?)
+    }
+
+    fn read_secrets(&self) -> std::sync::RwLockReadGuard<'_, HashMap<SecretKind, SecretVersions>> {
+        self.secrets.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_secrets(
+        &self,
+    ) -> std::sync::RwLockWriteGuard<'_, HashMap<SecretKind, SecretVersions>> {
+        self.secrets.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn encrypter(versions: &SecretVersions) -> RotationResult<Encrypter> {
+    let current = AppKey::parse(versions.current().secret())?;
+    let previous = versions
+        .previous_versions()
+        .map(|v| AppKey::parse(v.secret()))
+        .collect::<Result<_, _>>()?;
+    Ok(Encrypter::new(current, previous))
+}
+
+#[cfg(feature = "jwt")]
+fn jwt_manager(versions: &SecretVersions) -> rf_auth::AuthResult<rf_auth::JwtManager> {
+    let mut manager = rf_auth::JwtManager::new(versions.current().secret())?;
+    for version in versions.previous_versions() {
+        manager = manager.previous_secret(version.secret())?;
+    }
+    Ok(manager)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Ciphertext, MemoryCiphertextStore};
+    use rf_audit::AuditQuery;
+
+    const OLD_KEY: &str = "the-old-application-key";
+
+    fn rotation() -> KeyRotation {
+        KeyRotation::new(SecretVersions::new(SecretKind::AppKey, OLD_KEY)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_lazy_reencryption_on_read() {
+        let store = MemoryCiphertextStore::new("users.ssn");
+        let rotation = rotation().store(store.clone());
+        let old = rotation.encrypt("secret").unwrap();
+        store.insert("1", old.clone());
+
+        // Nothing to do while the key is current
+        assert_eq!(rotation.decrypt(&old).unwrap().reencrypted, None);
+
+        let mut versions = rotation.versions(SecretKind::AppKey).unwrap();
+        versions.push("the-new-application-key");
+        rotation.activate(versions).unwrap();
+
+        let decrypted = rotation.decrypt(&old).unwrap();
+        assert_eq!(decrypted.plaintext, "secret");
+        assert!(decrypted.reencrypted.is_some());
+
+        assert_eq!(
+            rotation.read("users.ssn", "1").await.unwrap().as_deref(),
+            Some("secret")
+        );
+        let stored = store.get("1").await.unwrap().unwrap();
+        assert_ne!(stored, old);
+        assert!(!rotation.encrypter().needs_rotation(&stored));
+
+        assert_eq!(rotation.read("users.ssn", "2").await.unwrap(), None);
+        assert!(matches!(
+            rotation.read("posts.body", "1").await,
+            Err(RotationError::UnknownStore(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_reencrypts_with_progress() {
+        let store = MemoryCiphertextStore::new("users.ssn");
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let seen = batches.clone();
+        let audit = Arc::new(AuditLogger::new());
+        let rotation = rotation()
+            .store(store.clone())
+            .batch_size(2)
+            .audit(audit.clone())
+            .on_progress(move |progress| seen.lock().unwrap().push(progress.processed));
+
+        for id in 0..5 {
+            store.insert(id.to_string(), rotation.encrypt("value").unwrap());
+        }
+        store.insert("9", "v1.0000000000000000.AAAA");
+
+        let report = rotation.rotate(SecretKind::AppKey).await.unwrap();
+        assert_eq!(report.version, 2);
+        let progress = report.reencryption.unwrap();
+        assert_eq!(
+            (
+                progress.total,
+                progress.processed,
+                progress.reencrypted,
+                progress.failed
+            ),
+            (6, 6, 5, 1)
+        );
+        assert!(progress.finished);
+        assert_eq!(progress.percent(), 100.0);
+        assert_eq!(rotation.progress(), progress);
+        assert_eq!(*batches.lock().unwrap(), vec![0, 2, 4, 6, 6]);
+
+        // Old key retired, values still readable
+        rotation.retire(SecretKind::AppKey, 1).await.unwrap();
+        assert_eq!(
+            rotation.read("users.ssn", "3").await.unwrap().as_deref(),
+            Some("value")
+        );
+
+        let actions: Vec<AuditAction> = audit
+            .query(AuditQuery::new().model_type("secret"))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.action)
+            .collect();
+        assert_eq!(actions.len(), 3);
+        for action in ["key_rotated", "values_reencrypted", "key_retired"] {
+            assert!(actions.contains(&AuditAction::Custom(action.into())));
+        }
+    }
+
+    /// Store the application writes to right after every batch is fetched
+    struct WritingStore {
+        inner: MemoryCiphertextStore,
+        written: String,
+    }
+
+    #[async_trait::async_trait]
+    impl CiphertextStore for WritingStore {
+        fn name(&self) -> &str {
+            self.inner.name()
+        }
+
+        async fn count(&self) -> RotationResult<u64> {
+            self.inner.count().await
+        }
+
+        async fn fetch(
+            &self,
+            after: Option<&str>,
+            limit: usize,
+        ) -> RotationResult<Vec<Ciphertext>> {
+            let batch = self.inner.fetch(after, limit).await?;
+            if let Some(first) = batch.first() {
+                self.inner.insert(first.id.clone(), self.written.clone());
+            }
+            Ok(batch)
+        }
+
+        async fn get(&self, id: &str) -> RotationResult<Option<String>> {
+            self.inner.get(id).await
+        }
+
+        async fn update_if(
+            &self,
+            id: &str,
+            expected: &str,
+            payload: String,
+        ) -> RotationResult<bool> {
+            self.inner.update_if(id, expected, payload).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reencrypt_keeps_values_written_meanwhile() {
+        let store = MemoryCiphertextStore::new("users.ssn");
+        let rotation = rotation();
+        for id in 0..4 {
+            store.insert(id.to_string(), rotation.encrypt("old").unwrap());
+        }
+        let written = rotation.encrypt("new").unwrap();
+        let rotation = rotation
+            .store(WritingStore {
+                inner: store.clone(),
+                written: written.clone(),
+            })
+            .batch_size(2);
+
+        let progress = rotation
+            .rotate(SecretKind::AppKey)
+            .await
+            .unwrap()
+            .reencryption
+            .unwrap();
+        assert_eq!(
+            (progress.processed, progress.reencrypted, progress.skipped),
+            (4, 2, 2)
+        );
+
+        // The first value of each batch was written by the application
+        // after the fetch and is still the new one
+        for id in ["0", "2"] {
+            assert_eq!(store.get(id).await.unwrap(), Some(written.clone()));
+        }
+        for id in ["1", "3"] {
+            let stored = store.get(id).await.unwrap().unwrap();
+            assert!(!rotation.encrypter().needs_rotation(&stored));
+            assert_eq!(rotation.decrypt(&stored).unwrap().plaintext, "old");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_and_unconfigured_secrets() {
+        let rotation = rotation();
+        assert!(rotation
+            .rotate_to(SecretKind::AppKey, "short")
+            .await
+            .is_err());
+        assert_eq!(
+            rotation
+                .versions(SecretKind::AppKey)
+                .unwrap()
+                .versions()
+                .len(),
+            1
+        );
+
+        assert!(matches!(
+            rotation.rotate(SecretKind::JwtSecret).await,
+            Err(RotationError::NotConfigured(SecretKind::JwtSecret))
+        ));
+        assert!(matches!(
+            rotation.retire(SecretKind::AppKey, 1).await,
+            Err(RotationError::CurrentVersion(..))
+        ));
+        assert!(KeyRotation::new(SecretVersions::new(SecretKind::JwtSecret, OLD_KEY)).is_err());
+    }
+
+    #[cfg(feature = "jwt")]
+    #[tokio::test]
+    async fn test_jwt_rotation() {
+        let rotation = rotation().jwt_secret(SecretVersions::new(
+            SecretKind::JwtSecret,
+            "old-secret-with-at-least-32-bytes!",
+        ));
+        let pair = rotation.jwt_manager().unwrap().issue("42").await.unwrap();
+
+        let report = rotation.rotate(SecretKind::JwtSecret).await.unwrap();
+        assert_eq!(report.version, 2);
+        assert!(report.reencryption.is_none());
+        assert_eq!(
+            rotation
+                .jwt_manager()
+                .unwrap()
+                .verify(&pair.access_token)
+                .unwrap()
+                .sub,
+            "42"
+        );
+
+        rotation.retire(SecretKind::JwtSecret, 1).await.unwrap();
+        assert!(rotation
+            .jwt_manager()
+            .unwrap()
+            .verify(&pair.access_token)
+            .is_err());
+    }
+}
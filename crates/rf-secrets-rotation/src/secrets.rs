@@ -0,0 +1,202 @@
+//! Versioned secrets
+
+use crate::{RotationError, RotationResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Secrets that can be rotated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretKind {
+    /// `APP_KEY`, encrypting and signing application data
+    AppKey,
+
+    /// `JWT_SECRET`, signing access and refresh tokens
+    JwtSecret,
+}
+
+impl SecretKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecretKind::AppKey => "app_key",
+            SecretKind::JwtSecret => "jwt_secret",
+        }
+    }
+
+    /// Variables holding the current secret and previous ones
+    pub fn env_vars(&self) -> (&'static str, &'static str) {
+        match self {
+            SecretKind::AppKey => ("APP_KEY", "APP_PREVIOUS_KEYS"),
+            SecretKind::JwtSecret => ("JWT_SECRET", "JWT_PREVIOUS_SECRETS"),
+        }
+    }
+}
+
+impl fmt::Display for SecretKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.env_vars().0)
+    }
+}
+
+/// One version of a secret
+#[derive(Clone)]
+pub struct KeyVersion {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    secret: String,
+}
+
+impl KeyVersion {
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+}
+
+impl fmt::Debug for KeyVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyVersion")
+            .field("version", &self.version)
+            .field("created_at", &self.created_at)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Active versions of a secret
+///
+/// The newest version is current and used for new ciphertexts and tokens;
+/// older versions stay active for reading until they are retired.
+#[derive(Debug, Clone)]
+pub struct SecretVersions {
+    kind: SecretKind,
+    versions: Vec<KeyVersion>,
+}
+
+impl SecretVersions {
+    /// A secret with a single version
+    pub fn new(kind: SecretKind, secret: impl Into<String>) -> Self {
+        let mut versions = Self {
+            kind,
+            versions: Vec::new(),
+        };
+        versions.push(secret);
+        versions
+    }
+
+    /// Versions from the environment, e.g. `APP_KEY` and
+    /// `APP_PREVIOUS_KEYS` (comma separated, most recent first)
+    pub fn from_env(kind: SecretKind) -> RotationResult<Self> {
+        let (current, previous) = kind.env_vars();
+        let current = std::env::var(current).map_err(|_| RotationError::NotConfigured(kind))?;
+        let previous = std::env::var(previous).unwrap_or_default();
+
+        let mut previous: Vec<&str> = previous
+            .split(',')
+            .map(str::trim)
+            .filter(|secret| !secret.is_empty())
+            .collect();
+        previous.reverse();
+
+        let mut versions = Self {
+            kind,
+            versions: Vec::new(),
+        };
+        for secret in previous {
+            versions.push(secret);
+        }
+        versions.push(current);
+        Ok(versions)
+    }
+
+    /// Values for the variables read by [`SecretVersions::from_env`], to
+    /// persist a rotation
+    pub fn env_values(&self) -> (String, String) {
+        let previous: Vec<&str> = self.previous_versions().map(|v| v.secret()).collect();
+        (self.current().secret.clone(), previous.join(","))
+    }
+
+    pub fn kind(&self) -> SecretKind {
+        self.kind
+    }
+
+    /// The current version
+    pub fn current(&self) -> &KeyVersion {
+        self.versions
+            .last()
+            .expect("a secret always has a current version")
+    }
+
+    /// Older versions that are still active, most recent first
+    pub fn previous_versions(&self) -> impl Iterator<Item = &KeyVersion> {
+        self.versions.iter().rev().skip(1)
+    }
+
+    /// All active versions, oldest first
+    pub fn versions(&self) -> &[KeyVersion] {
+        &self.versions
+    }
+
+    /// Make `secret` the current version, returning its version number
+    pub fn push(&mut self, secret: impl Into<String>) -> u32 {
+        let version = self.versions.last().map_or(1, |v| v.version + 1);
+        self.versions.push(KeyVersion {
+            version,
+            created_at: Utc::now(),
+            secret: secret.into(),
+        });
+        version
+    }
+
+    /// Stop accepting an old version
+    pub fn retire(&mut self, version: u32) -> RotationResult<()> {
+        if version == self.current().version {
+            return Err(RotationError::CurrentVersion(self.kind, version));
+        }
+        let before = self.versions.len();
+        self.versions.retain(|v| v.version != version);
+        if self.versions.len() == before {
+            return Err(RotationError::UnknownVersion(self.kind, version));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions() {
+        let mut secrets = SecretVersions::new(SecretKind::AppKey, "zeroth");
+        assert_eq!(secrets.current().secret(), "zeroth");
+        assert_eq!(secrets.current().version, 1);
+
+        assert_eq!(secrets.push("first"), 2);
+        assert_eq!(secrets.push("second"), 3);
+        assert_eq!(secrets.current().secret(), "second");
+        let previous: Vec<_> = secrets.previous_versions().map(|v| v.secret()).collect();
+        assert_eq!(previous, vec!["first", "zeroth"]);
+
+        assert!(matches!(
+            secrets.retire(3),
+            Err(RotationError::CurrentVersion(SecretKind::AppKey, 3))
+        ));
+        assert!(matches!(
+            secrets.retire(7),
+            Err(RotationError::UnknownVersion(..))
+        ));
+        secrets.retire(1).unwrap();
+        assert_eq!(secrets.versions().len(), 2);
+        assert_eq!(
+            secrets.env_values(),
+            ("second".to_string(), "first".to_string())
+        );
+    }
+
+    #[test]
+    fn test_debug_hides_secrets() {
+        let secrets = SecretVersions::new(SecretKind::JwtSecret, "top-secret");
+        assert!(!format!("{:?}", secrets).contains("top-secret"));
+        assert_eq!(SecretKind::JwtSecret.to_string(), "JWT_SECRET");
+    }
+}
@@ -0,0 +1,104 @@
+//! Stored ciphertexts
+
+use crate::RotationResult;
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// An encrypted value and the id of the record holding it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ciphertext {
+    pub id: String,
+    pub payload: String,
+}
+
+/// Encrypted values kept somewhere, e.g. a column of a table
+///
+/// [`KeyRotation`](crate::KeyRotation) walks a store in batches of ids to
+/// re-encrypt values with the current key.
+#[async_trait]
+pub trait CiphertextStore: Send + Sync {
+    /// Name used in progress and audit entries, e.g. `users.ssn`
+    fn name(&self) -> &str;
+
+    /// Number of values
+    async fn count(&self) -> RotationResult<u64>;
+
+    /// Up to `limit` values with ids after `after`, ordered by id
+    async fn fetch(&self, after: Option<&str>, limit: usize) -> RotationResult<Vec<Ciphertext>>;
+
+    /// The value of a record
+    async fn get(&self, id: &str) -> RotationResult<Option<String>>;
+
+    /// Replace the value of a record if it is still `expected`
+    ///
+    /// Returns `false` without writing if the record changed or is gone,
+    /// e.g. because the application wrote a new value after it was read.
+    /// SQL stores do this with `UPDATE .. WHERE id = $1 AND payload = $2`.
+    async fn update_if(&self, id: &str, expected: &str, payload: String) -> RotationResult<bool>;
+}
+
+/// In-memory ciphertext store, for tests
+#[derive(Clone)]
+pub struct MemoryCiphertextStore {
+    name: String,
+    values: Arc<Mutex<BTreeMap<String, String>>>,
+}
+
+impl MemoryCiphertextStore {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            values: Arc::default(),
+        }
+    }
+
+    pub fn insert(&self, id: impl Into<String>, payload: impl Into<String>) {
+        self.lock().insert(id.into(), payload.into());
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, String>> {
+        self.values.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl CiphertextStore for MemoryCiphertextStore {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn count(&self) -> RotationResult<u64> {
+        Ok(self.lock().len() as u64)
+    }
+
+    async fn fetch(&self, after: Option<&str>, limit: usize) -> RotationResult<Vec<Ciphertext>> {
+        let values = self.lock();
+        let range = match after {
+            Some(after) => values
+                .range::<str, _>((std::ops::Bound::Excluded(after), std::ops::Bound::Unbounded)),
+            None => values.range::<str, _>(..),
+        };
+        Ok(range
+            .take(limit)
+            .map(|(id, payload)| Ciphertext {
+                id: id.clone(),
+                payload: payload.clone(),
+            })
+            .collect())
+    }
+
+    async fn get(&self, id: &str) -> RotationResult<Option<String>> {
+        Ok(self.lock().get(id).cloned())
+    }
+
+    async fn update_if(&self, id: &str, expected: &str, payload: String) -> RotationResult<bool> {
+        match self.lock().get_mut(id) {
+            Some(current) if current == expected => {
+                *current = payload;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
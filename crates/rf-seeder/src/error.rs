@@ -0,0 +1,31 @@
+//! Seeding errors
+
+use thiserror::Error;
+
+/// Error returned by seeders and persist callbacks
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Seeding errors
+#[derive(Debug, Error)]
+pub enum SeedError {
+    #[error("Seeder {seeder} failed: {message}")]
+    Failed { seeder: String, message: String },
+
+    #[error("Refusing to seed in production; use force to seed anyway")]
+    Production,
+
+    #[error("Seeder {seeder} doesn't run in the {environment} environment")]
+    NotAllowed { seeder: String, environment: String },
+
+    #[error("Unknown seeder: {0}")]
+    UnknownSeeder(String),
+
+    #[error("Seeder dependency cycle: {0}")]
+    Cycle(String),
+
+    #[error("Persisting failed: {0}")]
+    Persist(String),
+}
+
+/// Result type for seeding
+pub type SeedResult<T> = Result<T, SeedError>;
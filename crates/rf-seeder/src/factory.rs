@@ -0,0 +1,387 @@
+//! Model factories with fake data
+
+use crate::{BoxError, SeedError, SeedResult};
+use fake::Dummy;
+use futures::future::BoxFuture;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+type Definition<T> = Arc<dyn Fn(&mut Generator) -> T + Send + Sync>;
+type State<T> = Arc<dyn Fn(&mut T, &mut Generator) + Send + Sync>;
+type BeforeCreate<T> = Arc<dyn Fn(T) -> BoxFuture<'static, SeedResult<T>> + Send + Sync>;
+type Persist<T> = Arc<dyn Fn(T) -> BoxFuture<'static, Result<T, BoxError>> + Send + Sync>;
+type AfterCreate<T> = Arc<dyn Fn(&T) -> BoxFuture<'static, SeedResult<()>> + Send + Sync>;
+
+/// Source of fake data for one model
+pub struct Generator {
+    index: usize,
+    rng: StdRng,
+}
+
+impl Generator {
+    /// Position of the model among all made by the factory, from 0, e.g.
+    /// for unique emails
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Fake value, e.g. `g.fake(&Name(EN))` or `g.fake(&(18..80))`
+    pub fn fake<U: Dummy<F>, F>(&mut self, faker: &F) -> U {
+        U::dummy_with_rng(faker, &mut self.rng)
+    }
+
+    /// Random element of `values`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` is empty.
+    pub fn pick<V: Clone>(&mut self, values: &[V]) -> V {
+        values
+            .choose(&mut self.rng)
+            .expect("pick needs at least one value")
+            .clone()
+    }
+
+    /// The random number generator
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+}
+
+/// Factory making models with fake data
+///
+/// `make` only builds models; `create` also saves them through the
+/// [`persist_with`](Factory::persist_with) callback and creates their
+/// related models. Factories are cheap to clone and each modifier returns
+/// a new factory, so states can be combined freely.
+///
+/// # Example
+///
+/// ```
+/// use rf_seeder::faker::internet::en::SafeEmail;
+/// use rf_seeder::faker::name::en::Name;
+/// use rf_seeder::Factory;
+///
+/// #[derive(Debug, Clone)]
+/// struct User {
+///     id: u64,
+///     name: String,
+///     email: String,
+///     admin: bool,
+/// }
+///
+/// # async fn example() -> rf_seeder::SeedResult<()> {
+/// let users = Factory::new(|g| User {
+///     id: 0,
+///     name: g.fake(&Name()),
+///     email: format!("user{}@example.com", g.index()),
+///     admin: false,
+/// })
+/// .persist_with(|mut user: User| async move {
+///     // user.insert(&pool).await?
+///     user.id = 1;
+///     Ok(user)
+/// });
+///
+/// let admin = users.clone().state(|u, _| u.admin = true).create().await?;
+/// let guests = users.create_many(10).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Factory<T> {
+    definition: Definition<T>,
+    states: Vec<State<T>>,
+    before_create: Vec<BeforeCreate<T>>,
+    persist: Option<Persist<T>>,
+    after_create: Vec<AfterCreate<T>>,
+    seed: Option<u64>,
+    counter: Arc<AtomicUsize>,
+}
+
+impl<T> Clone for Factory<T> {
+    fn clone(&self) -> Self {
+        Self {
+            definition: self.definition.clone(),
+            states: self.states.clone(),
+            before_create: self.before_create.clone(),
+            persist: self.persist.clone(),
+            after_create: self.after_create.clone(),
+            seed: self.seed,
+            counter: self.counter.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Factory<T> {
+    /// Create a factory making models with `definition`
+    pub fn new(definition: impl Fn(&mut Generator) -> T + Send + Sync + 'static) -> Self {
+        Self {
+            definition: Arc::new(definition),
+            states: Vec::new(),
+            before_create: Vec::new(),
+            persist: None,
+            after_create: Vec::new(),
+            seed: None,
+            counter: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Modify made models, e.g. `.state(|u, _| u.admin = true)`
+    pub fn state(mut self, state: impl Fn(&mut T, &mut Generator) + Send + Sync + 'static) -> Self {
+        self.states.push(Arc::new(state));
+        self
+    }
+
+    /// Make the same fake data on every run, e.g. for snapshot tests
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Save created models with `persist`, which returns the saved model,
+    /// e.g. with its generated ID
+    pub fn persist_with<F, Fut>(mut self, persist: F) -> Self
+    where
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, BoxError>> + Send + 'static,
+    {
+        self.persist = Some(Arc::new(move |item| Box::pin(persist(item))));
+        self
+    }
+
+    /// Create `count` models of `children` for each created model, linked
+    /// with `link`, e.g. `.has(posts, 3, |user, post| post.user_id = user.id)`
+    pub fn has<C: Send + 'static>(
+        mut self,
+        children: Factory<C>,
+        count: usize,
+        link: impl Fn(&T, &mut C) + Send + Sync + 'static,
+    ) -> Self {
+        self.after_create.push(Arc::new(move |parent| {
+            let mut items = children.make_many(count);
+            for item in &mut items {
+                link(parent, item);
+            }
+
+            let children = children.clone();
+            Box::pin(async move {
+                for item in items {
+                    children.save(item).await?;
+                }
+                Ok(())
+            })
+        }));
+        self
+    }
+
+    /// Create a model of `parent` for each created model, linked with
+    /// `link`, e.g. `.belongs_to(users, |user, post| post.user_id = user.id)`
+    pub fn belongs_to<P: Send + 'static>(
+        mut self,
+        parent: Factory<P>,
+        link: impl Fn(&P, &mut T) + Send + Sync + 'static,
+    ) -> Self {
+        let link = Arc::new(link);
+        self.before_create.push(Arc::new(move |mut item| {
+            let parent = parent.clone();
+            let link = link.clone();
+            Box::pin(async move {
+                let parent = parent.create().await?;
+                link(&parent, &mut item);
+                Ok(item)
+            })
+        }));
+        self
+    }
+
+    /// Make a model without saving it
+    pub fn make(&self) -> T {
+        let index = self.counter.fetch_add(1, Ordering::Relaxed);
+        let rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(index as u64)),
+            None => StdRng::from_entropy(),
+        };
+
+        let mut generator = Generator { index, rng };
+        let mut item = (self.definition)(&mut generator);
+        for state in &self.states {
+            state(&mut item, &mut generator);
+        }
+        item
+    }
+
+    /// Make `count` models without saving them
+    pub fn make_many(&self, count: usize) -> Vec<T> {
+        (0..count).map(|_| self.make()).collect()
+    }
+
+    /// Make and save a model, with its related models
+    pub async fn create(&self) -> SeedResult<T> {
+        self.save(self.make()).await
+    }
+
+    /// Make and save `count` models, with their related models
+    pub async fn create_many(&self, count: usize) -> SeedResult<Vec<T>> {
+        let mut created = Vec::with_capacity(count);
+        for item in self.make_many(count) {
+            created.push(self.save(item).await?);
+        }
+        Ok(created)
+    }
+
+    async fn save(&self, mut item: T) -> SeedResult<T> {
+        for hook in &self.before_create {
+            item = hook(item).await?;
+        }
+        if let Some(persist) = &self.persist {
+            item = persist(item)
+                .await
+                .map_err(|e| SeedError::Persist(e.to_string()))?;
+        }
+        for hook in &self.after_create {
+            hook(&item).await?;
+        }
+        Ok(item)
+    }
+}
+
+/// Models with a default factory
+///
+/// ```
+/// use rf_seeder::{Factory, HasFactory};
+///
+/// struct Tag {
+///     name: String,
+/// }
+///
+/// impl HasFactory for Tag {
+///     fn factory() -> Factory<Self> {
+///         Factory::new(|g| Tag {
+///             name: format!("tag-{}", g.index()),
+///         })
+///     }
+/// }
+///
+/// let tags = Tag::factory().make_many(3);
+/// assert_eq!(tags[2].name, "tag-2");
+/// ```
+pub trait HasFactory: Sized + Send + 'static {
+    fn factory() -> Factory<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fake::faker::name::en::Name;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct User {
+        id: usize,
+        name: String,
+        age: u8,
+        admin: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Post {
+        id: usize,
+        user_id: usize,
+    }
+
+    fn users() -> Factory<User> {
+        Factory::new(|g| User {
+            id: 0,
+            name: g.fake(&Name()),
+            age: g.fake(&(18..80)),
+            admin: false,
+        })
+    }
+
+    /// Factory saving to `table`, assigning IDs from 1
+    fn persisted<T, F>(factory: Factory<T>, table: &Arc<Mutex<Vec<T>>>, set_id: F) -> Factory<T>
+    where
+        T: Clone + Send + 'static,
+        F: Fn(&mut T, usize) + Send + Sync + 'static,
+    {
+        let table = table.clone();
+        let set_id = Arc::new(set_id);
+        factory.persist_with(move |mut item: T| {
+            let table = table.clone();
+            let set_id = set_id.clone();
+            async move {
+                let mut table = table.lock().unwrap();
+                set_id(&mut item, table.len() + 1);
+                table.push(item.clone());
+                Ok(item)
+            }
+        })
+    }
+
+    #[test]
+    fn test_make() {
+        let first = users().seed(42).make_many(3);
+        assert_eq!(first, users().seed(42).make_many(3));
+        assert_ne!(first[0], first[1]);
+        assert!(first
+            .iter()
+            .all(|u| (18..80).contains(&u.age) && !u.name.is_empty()));
+
+        let admins = users()
+            .state(|u, _| u.admin = true)
+            .state(|u, g| u.name = format!("admin{}", g.index()));
+        let made = admins.make_many(2);
+        assert!(made.iter().all(|u| u.admin));
+        assert_eq!(made[1].name, "admin1");
+    }
+
+    #[tokio::test]
+    async fn test_create_with_relationships() {
+        let user_table = Arc::new(Mutex::new(Vec::new()));
+        let post_table = Arc::new(Mutex::new(Vec::new()));
+        let users = persisted(users(), &user_table, |u, id| u.id = id);
+        let posts = persisted(
+            Factory::new(|_| Post { id: 0, user_id: 0 }),
+            &post_table,
+            |p, id| p.id = id,
+        );
+
+        let created = users
+            .clone()
+            .has(posts.clone(), 3, |u, p| p.user_id = u.id)
+            .create_many(2)
+            .await
+            .unwrap();
+        assert_eq!(created.iter().map(|u| u.id).collect::<Vec<_>>(), vec![1, 2]);
+        let user_ids: Vec<_> = post_table
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|p| p.user_id)
+            .collect();
+        assert_eq!(user_ids, vec![1, 1, 1, 2, 2, 2]);
+
+        let post = posts
+            .belongs_to(users, |u, p| p.user_id = u.id)
+            .create()
+            .await
+            .unwrap();
+        assert_eq!(post.id, 7);
+        assert_eq!(post.user_id, 3);
+        assert_eq!(user_table.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_persist_error() {
+        let failing =
+            users().persist_with(|_| async { Err::<User, _>("connection refused".into()) });
+        assert!(matches!(failing.create().await, Err(SeedError::Persist(_))));
+
+        // Without a persist callback, create only runs the hooks
+        assert_eq!(users().create_many(2).await.unwrap().len(), 2);
+    }
+}
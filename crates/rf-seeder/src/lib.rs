@@ -0,0 +1,56 @@
+//! Database seeding and model factories for RustForge
+//!
+//! [`Seeder`]s fill the database, e.g. with reference data or demo
+//! content. [`Seeders`] runs them in order, after the seeders they depend
+//! on, and only in the environments they are meant for.
+//!
+//! [`Factory`] makes models with fake data from [`faker`], in states and
+//! with related models, for seeders and integration tests alike.
+//!
+//! # Example
+//!
+//! ```
+//! use rf_seeder::faker::company::en::CompanyName;
+//! use rf_seeder::{BoxError, Factory, Seeder, Seeders};
+//!
+//! struct Company {
+//!     name: String,
+//! }
+//!
+//! struct DemoCompanies;
+//!
+//! #[async_trait::async_trait]
+//! impl Seeder for DemoCompanies {
+//!     fn environments(&self) -> &[&str] {
+//!         &["development"]
+//!     }
+//!
+//!     async fn run(&self) -> Result<(), BoxError> {
+//!         let companies = Factory::new(|g| Company {
+//!             name: g.fake(&CompanyName()),
+//!         });
+//!         companies.create_many(25).await?;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! # async fn example() -> rf_seeder::SeedResult<()> {
+//! Seeders::new()
+//!     .environment("development")
+//!     .seeder(DemoCompanies)
+//!     .run_all()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod error;
+mod factory;
+mod seeder;
+
+pub use error::{BoxError, SeedError, SeedResult};
+pub use factory::{Factory, Generator, HasFactory};
+pub use seeder::{SeedReport, Seeder, Seeders};
+
+pub use fake;
+pub use fake::faker;
@@ -0,0 +1,311 @@
+//! Seeders and their runner
+
+use crate::{BoxError, SeedError, SeedResult};
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Fills the database with data
+///
+/// ```
+/// use rf_seeder::{BoxError, Seeder};
+///
+/// struct DemoUsers;
+///
+/// #[async_trait::async_trait]
+/// impl Seeder for DemoUsers {
+///     fn environments(&self) -> &[&str] {
+///         &["development", "testing"]
+///     }
+///
+///     fn depends_on(&self) -> Vec<String> {
+///         vec!["Roles".to_string()]
+///     }
+///
+///     async fn run(&self) -> Result<(), BoxError> {
+///         // UserFactory::new().create_many(50).await?;
+///         Ok(())
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait Seeder: Send + Sync {
+    /// Name to run the seeder by (default: the type name)
+    fn name(&self) -> String {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name).to_string()
+    }
+
+    /// Environments the seeder runs in; all when empty
+    fn environments(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Names of seeders to run first
+    fn depends_on(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    async fn run(&self) -> Result<(), BoxError>;
+}
+
+/// Seeders that ran and were skipped
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeedReport {
+    pub ran: Vec<String>,
+
+    /// Skipped as they don't run in the environment
+    pub skipped: Vec<String>,
+}
+
+/// Runs seeders in order
+///
+/// Seeders run in the order they were added, after the seeders they
+/// depend on. Seeders not meant for the environment are skipped, and
+/// nothing runs in `production` unless forced.
+///
+/// ```
+/// # use rf_seeder::{BoxError, Seeder, Seeders};
+/// # struct Roles;
+/// # #[async_trait::async_trait]
+/// # impl Seeder for Roles {
+/// #     async fn run(&self) -> Result<(), BoxError> { Ok(()) }
+/// # }
+/// # async fn example() -> rf_seeder::SeedResult<()> {
+/// // Environment from APP_ENV
+/// let report = Seeders::new().seeder(Roles).run_all().await?;
+/// assert_eq!(report.ran, vec!["Roles"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Seeders {
+    seeders: Vec<Arc<dyn Seeder>>,
+    environment: String,
+    force: bool,
+}
+
+impl Default for Seeders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Seeders {
+    /// Create a runner for the environment in `APP_ENV` (default:
+    /// `development`)
+    pub fn new() -> Self {
+        Self {
+            seeders: Vec::new(),
+            environment: std::env::var("APP_ENV").unwrap_or_else(|_| "development".to_string()),
+            force: false,
+        }
+    }
+
+    /// Add a seeder
+    pub fn seeder(mut self, seeder: impl Seeder + 'static) -> Self {
+        self.seeders.push(Arc::new(seeder));
+        self
+    }
+
+    /// Set the environment
+    pub fn environment(mut self, environment: impl Into<String>) -> Self {
+        self.environment = environment.into();
+        self
+    }
+
+    /// Allow seeding in production
+    pub fn force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Names of the seeders, in the order added
+    pub fn names(&self) -> Vec<String> {
+        self.seeders.iter().map(|s| s.name()).collect()
+    }
+
+    /// Run all seeders
+    pub async fn run_all(&self) -> SeedResult<SeedReport> {
+        let names = self.names();
+        self.execute(&names, false).await
+    }
+
+    /// Run the seeder `name` and the seeders it depends on
+    ///
+    /// Fails if `name` doesn't run in the environment.
+    pub async fn run(&self, name: &str) -> SeedResult<SeedReport> {
+        self.execute(&[name.to_string()], true).await
+    }
+
+    async fn execute(&self, names: &[String], explicit: bool) -> SeedResult<SeedReport> {
+        if self.environment == "production" && !self.force {
+            return Err(SeedError::Production);
+        }
+
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        for name in names {
+            self.visit(name, &mut Vec::new(), &mut visited, &mut order)?;
+        }
+
+        let mut report = SeedReport::default();
+        for seeder in order {
+            let name = seeder.name();
+            let environments = seeder.environments();
+            if !environments.is_empty() && !environments.contains(&self.environment.as_str()) {
+                if explicit && names.contains(&name) {
+                    return Err(SeedError::NotAllowed {
+                        seeder: name,
+                        environment: self.environment.clone(),
+                    });
+                }
+                report.skipped.push(name);
+                continue;
+            }
+
+            tracing::info!(seeder = %name, "Seeding");
+            seeder.run().await.map_err(|e| SeedError::Failed {
+                seeder: name.clone(),
+                message: e.to_string(),
+            })?;
+            report.ran.push(name);
+        }
+        Ok(report)
+    }
+
+    /// Depth-first topological sort, dependencies first
+    fn visit(
+        &self,
+        name: &str,
+        path: &mut Vec<String>,
+        visited: &mut HashSet<String>,
+        order: &mut Vec<Arc<dyn Seeder>>,
+    ) -> SeedResult<()> {
+        if visited.contains(name) {
+            return Ok(());
+        }
+        if path.iter().any(|n| n == name) {
+            path.push(name.to_string());
+            return Err(SeedError::Cycle(path.join(" -> ")));
+        }
+
+        let seeder = self
+            .seeders
+            .iter()
+            .find(|s| s.name() == name)
+            .ok_or_else(|| SeedError::UnknownSeeder(name.to_string()))?;
+
+        path.push(name.to_string());
+        for dependency in seeder.depends_on() {
+            self.visit(&dependency, path, visited, order)?;
+        }
+        path.pop();
+
+        visited.insert(name.to_string());
+        order.push(seeder.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    struct Recording {
+        name: &'static str,
+        depends_on: Vec<&'static str>,
+        environments: &'static [&'static str],
+        log: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait]
+    impl Seeder for Recording {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn environments(&self) -> &[&str] {
+            self.environments
+        }
+
+        fn depends_on(&self) -> Vec<String> {
+            self.depends_on.iter().map(|d| d.to_string()).collect()
+        }
+
+        async fn run(&self) -> Result<(), BoxError> {
+            if self.name == "Broken" {
+                return Err("duplicate key".into());
+            }
+            self.log.lock().unwrap().push(self.name);
+            Ok(())
+        }
+    }
+
+    fn seeders(log: &Arc<Mutex<Vec<&'static str>>>) -> Seeders {
+        let seeder = |name, depends_on, environments| Recording {
+            name,
+            depends_on,
+            environments,
+            log: log.clone(),
+        };
+        Seeders::new()
+            .environment("testing")
+            .seeder(seeder("Posts", vec!["Users"], &[]))
+            .seeder(seeder("Users", vec!["Roles"], &[]))
+            .seeder(seeder("Roles", vec![], &[]))
+            .seeder(seeder("Demo", vec![], &["development"]))
+    }
+
+    #[tokio::test]
+    async fn test_order_and_environments() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let report = seeders(&log).run_all().await.unwrap();
+        assert_eq!(report.ran, vec!["Roles", "Users", "Posts"]);
+        assert_eq!(report.skipped, vec!["Demo"]);
+        assert_eq!(*log.lock().unwrap(), vec!["Roles", "Users", "Posts"]);
+
+        let report = seeders(&log).run("Users").await.unwrap();
+        assert_eq!(report.ran, vec!["Roles", "Users"]);
+
+        assert!(matches!(
+            seeders(&log).run("Demo").await,
+            Err(SeedError::NotAllowed { .. })
+        ));
+        assert!(matches!(
+            seeders(&log).run("Comments").await,
+            Err(SeedError::UnknownSeeder(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_guards_and_failures() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let production = seeders(&log).environment("production");
+        assert!(matches!(
+            production.run_all().await,
+            Err(SeedError::Production)
+        ));
+        assert!(log.lock().unwrap().is_empty());
+        assert!(production.force(true).run_all().await.is_ok());
+
+        let seeder = |name, depends_on| Recording {
+            name,
+            depends_on,
+            environments: &[],
+            log: log.clone(),
+        };
+        let cyclic = Seeders::new()
+            .seeder(seeder("A", vec!["B"]))
+            .seeder(seeder("B", vec!["A"]));
+        match cyclic.run_all().await {
+            Err(SeedError::Cycle(path)) => assert_eq!(path, "A -> B -> A"),
+            other => panic!("expected a cycle, got {:?}", other),
+        }
+
+        let broken = Seeders::new().seeder(seeder("Broken", vec![]));
+        let error = broken.run_all().await.unwrap_err();
+        assert_eq!(error.to_string(), "Seeder Broken failed: duplicate key");
+    }
+}
@@ -0,0 +1,237 @@
+//! Session configuration
+
+use crate::{SessionError, SessionResult};
+use cookie::Key;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use std::time::Duration;
+
+/// Minimum length of the secret signing or encrypting cookies
+pub const MIN_SECRET_LENGTH: usize = 32;
+
+/// How the session cookie is protected
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CookieMode {
+    /// Session ID in clear text
+    Plain,
+
+    /// Session ID with a signature, so clients can't forge one
+    Signed,
+
+    /// Session ID encrypted and authenticated
+    #[default]
+    Encrypted,
+}
+
+/// `SameSite` attribute of the session cookie
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SameSite {
+    Strict,
+    #[default]
+    Lax,
+    None,
+}
+
+impl From<SameSite> for cookie::SameSite {
+    fn from(same_site: SameSite) -> Self {
+        match same_site {
+            SameSite::Strict => cookie::SameSite::Strict,
+            SameSite::Lax => cookie::SameSite::Lax,
+            SameSite::None => cookie::SameSite::None,
+        }
+    }
+}
+
+/// Session configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Name of the session cookie
+    pub cookie_name: String,
+
+    /// Sessions expire after this long without a request (default: 2 hours)
+    #[serde(with = "humantime_serde")]
+    pub idle_timeout: Duration,
+
+    /// Sessions expire this long after they started, however active
+    /// (default: 12 hours)
+    #[serde(with = "humantime_serde")]
+    pub absolute_timeout: Option<Duration>,
+
+    /// How the cookie is protected (default: encrypted)
+    pub cookie_mode: CookieMode,
+
+    /// Secret signing or encrypting the cookie, at least
+    /// [`MIN_SECRET_LENGTH`] bytes
+    pub secret: Option<String>,
+
+    /// Only send the cookie over HTTPS
+    pub secure: bool,
+
+    /// Hide the cookie from JavaScript
+    pub http_only: bool,
+
+    pub same_site: SameSite,
+
+    pub path: String,
+
+    pub domain: Option<String>,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            cookie_name: "rustforge_session".to_string(),
+            idle_timeout: Duration::from_secs(2 * 60 * 60),
+            absolute_timeout: Some(Duration::from_secs(12 * 60 * 60)),
+            cookie_mode: CookieMode::Encrypted,
+            secret: None,
+            secure: true,
+            http_only: true,
+            same_site: SameSite::Lax,
+            path: "/".to_string(),
+            domain: None,
+        }
+    }
+}
+
+impl SessionConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load from `SESSION_SECRET`, `SESSION_COOKIE`, `SESSION_IDLE_TIMEOUT`,
+    /// `SESSION_ABSOLUTE_TIMEOUT` (durations like `30m`, `none` disables)
+    /// and `SESSION_SECURE_COOKIE`
+    pub fn from_env() -> SessionResult<Self> {
+        let mut config = Self {
+            secret: std::env::var("SESSION_SECRET").ok(),
+            ..Self::default()
+        };
+
+        if let Ok(name) = std::env::var("SESSION_COOKIE") {
+            config.cookie_name = name;
+        }
+        if let Ok(timeout) = std::env::var("SESSION_IDLE_TIMEOUT") {
+            config.idle_timeout = parse_duration("SESSION_IDLE_TIMEOUT", &timeout)?;
+        }
+        if let Ok(timeout) = std::env::var("SESSION_ABSOLUTE_TIMEOUT") {
+            config.absolute_timeout = match timeout.as_str() {
+                "none" => None,
+                timeout => Some(parse_duration("SESSION_ABSOLUTE_TIMEOUT", timeout)?),
+            };
+        }
+        if let Ok(secure) = std::env::var("SESSION_SECURE_COOKIE") {
+            config.secure = secure != "false";
+        }
+        Ok(config)
+    }
+
+    /// Set the cookie name
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    /// Set the idle timeout
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Set the absolute timeout; `None` lets active sessions live forever
+    pub fn absolute_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.absolute_timeout = timeout;
+        self
+    }
+
+    /// Set how the cookie is protected
+    pub fn cookie_mode(mut self, mode: CookieMode) -> Self {
+        self.cookie_mode = mode;
+        self
+    }
+
+    /// Set the secret signing or encrypting the cookie
+    pub fn secret(mut self, secret: impl Into<String>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    /// Allow the cookie over plain HTTP, e.g. in local development
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Set the `SameSite` attribute
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = same_site;
+        self
+    }
+
+    /// Set the cookie domain, e.g. to share sessions with subdomains
+    pub fn domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// Cookie key derived from the secret; `None` for plain cookies
+    pub(crate) fn key(&self) -> SessionResult<Option<Key>> {
+        if self.cookie_mode == CookieMode::Plain {
+            return Ok(None);
+        }
+
+        let secret = self.secret.as_deref().ok_or_else(|| {
+            SessionError::Config("A secret is required for signed or encrypted cookies".into())
+        })?;
+        if secret.len() < MIN_SECRET_LENGTH {
+            return Err(SessionError::Config(format!(
+                "The session secret must be at least {} bytes",
+                MIN_SECRET_LENGTH
+            )));
+        }
+        Ok(Some(Key::from(&Sha512::digest(secret.as_bytes())[..])))
+    }
+}
+
+fn parse_duration(name: &str, value: &str) -> SessionResult<Duration> {
+    humantime_serde::re::humantime::parse_duration(value)
+        .map_err(|e| SessionError::Config(format!("{} is invalid: {}", name, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_requires_long_secret() {
+        assert!(SessionConfig::new().key().is_err());
+        assert!(SessionConfig::new().secret("short").key().is_err());
+        assert!(SessionConfig::new()
+            .secret("a".repeat(MIN_SECRET_LENGTH))
+            .key()
+            .unwrap()
+            .is_some());
+        assert!(SessionConfig::new()
+            .cookie_mode(CookieMode::Plain)
+            .key()
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let config: SessionConfig = serde_json::from_value(serde_json::json!({
+            "idle_timeout": "30m",
+            "absolute_timeout": null,
+            "cookie_mode": "signed",
+        }))
+        .unwrap();
+        assert_eq!(config.idle_timeout, Duration::from_secs(30 * 60));
+        assert_eq!(config.absolute_timeout, None);
+        assert_eq!(config.cookie_mode, CookieMode::Signed);
+        assert_eq!(config.cookie_name, "rustforge_session");
+    }
+}
@@ -0,0 +1,419 @@
+//! CSRF protection
+//!
+//! Every session gets a random token, created on first use. Templates put
+//! it into forms through [`CsrfToken`] or [`Session::csrf_field`], scripts
+//! send it in the `X-CSRF-Token` header, and [`CsrfLayer`] rejects unsafe
+//! requests whose token doesn't match the session's. Tokens are compared
+//! in constant time.
+
+use crate::{Session, SessionError, SessionResult};
+use axum::{
+    body::Body,
+    extract::{FromRequestParts, Request},
+    http::{header, request::Parts, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use rand::distributions::{Alphanumeric, DistString};
+use rf_middleware::{Middleware, MiddlewareService, Next};
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tower::Layer;
+
+/// Session key of the CSRF token
+const CSRF_TOKEN: &str = "_csrf.token";
+
+/// Form field carrying the token
+pub const CSRF_FIELD: &str = "_token";
+
+/// Header carrying the token, e.g. for requests sent by scripts
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+impl Session {
+    /// CSRF token of the session, created on first use
+    pub async fn csrf_token(&self) -> SessionResult<String> {
+        match self.get::<String>(CSRF_TOKEN).await? {
+            Some(token) => Ok(token),
+            None => self.rotate_csrf_token().await,
+        }
+    }
+
+    /// Hidden form field with the CSRF token
+    pub async fn csrf_field(&self) -> SessionResult<String> {
+        Ok(hidden_field(&self.csrf_token().await?))
+    }
+
+    /// Replace the CSRF token, returning the new one
+    ///
+    /// Call after logging a user in, along with [`Session::regenerate`].
+    pub async fn rotate_csrf_token(&self) -> SessionResult<String> {
+        let token = Alphanumeric.sample_string(&mut rand::thread_rng(), 40);
+        self.put(CSRF_TOKEN, &token).await?;
+        Ok(token)
+    }
+}
+
+fn hidden_field(token: &str) -> String {
+    // Tokens are alphanumeric, nothing to escape
+    format!(
+        r#"<input type="hidden" name="{}" value="{}">"#,
+        CSRF_FIELD, token
+    )
+}
+
+/// CSRF token of the current request's session, for forms and templates
+///
+/// ```
+/// use rf_session::CsrfToken;
+///
+/// async fn edit_profile(csrf: CsrfToken) -> String {
+///     format!(r#"<form method="post">{}<button>Save</button></form>"#, csrf.field())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    /// The token itself, e.g. for a `<meta name="csrf-token">` tag
+    pub fn token(&self) -> &str {
+        &self.0
+    }
+
+    /// Hidden form field with the token
+    pub fn field(&self) -> String {
+        hidden_field(&self.0)
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for CsrfToken {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let session = Session::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let token = session
+            .csrf_token()
+            .await
+            .map_err(IntoResponse::into_response)?;
+        Ok(CsrfToken(token))
+    }
+}
+
+/// Layer rejecting unsafe requests without the session's CSRF token
+///
+/// `POST`, `PUT`, `PATCH` and `DELETE` requests must carry the token in
+/// the `X-CSRF-Token` header or, for URL-encoded forms, the `_token`
+/// field; others are rejected with `403 Forbidden`. Multipart forms must
+/// send the header. Goes inside the [`SessionLayer`](crate::SessionLayer).
+///
+/// ```
+/// use axum::{routing::post, Router};
+/// use rf_session::{CsrfLayer, MemoryStore, SessionConfig, SessionLayer};
+///
+/// # fn main() -> rf_session::SessionResult<()> {
+/// let config = SessionConfig::new().secret("a secret of at least 32 bytes length");
+/// let app: Router = Router::new()
+///     .route("/profile", post(|| async { "Saved" }))
+///     .route("/api/orders", post(|| async { "Created" }))
+///     .route("/webhooks/stripe", post(|| async { "Received" }))
+///     .layer(
+///         CsrfLayer::new()
+///             .exempt("/webhooks/*")
+///             .exempt_content_type("application/json"),
+///     )
+///     .layer(SessionLayer::new(MemoryStore::default(), config)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CsrfLayer {
+    inner: Arc<CsrfConfig>,
+}
+
+struct CsrfConfig {
+    exempt_paths: Vec<String>,
+    exempt_content_types: Vec<String>,
+    rotate: bool,
+    form_limit: usize,
+}
+
+impl CsrfLayer {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(CsrfConfig {
+                exempt_paths: vec![],
+                exempt_content_types: vec![],
+                rotate: false,
+                form_limit: 2 * 1024 * 1024,
+            }),
+        }
+    }
+
+    /// Don't check requests to `path`; a trailing `*` matches any suffix
+    pub fn exempt(mut self, path: impl Into<String>) -> Self {
+        self.config_mut().exempt_paths.push(path.into());
+        self
+    }
+
+    /// Don't check requests with this content type
+    ///
+    /// Browsers don't send cross-site `application/json` requests without
+    /// a CORS preflight, so JSON APIs can be exempted.
+    pub fn exempt_content_type(mut self, content_type: impl Into<String>) -> Self {
+        let content_type = content_type.into().to_ascii_lowercase();
+        self.config_mut().exempt_content_types.push(content_type);
+        self
+    }
+
+    /// Replace the token after every checked request (default: off)
+    ///
+    /// Forms open in other tabs then carry an outdated token.
+    pub fn rotate(mut self, rotate: bool) -> Self {
+        self.config_mut().rotate = rotate;
+        self
+    }
+
+    /// Largest URL-encoded form searched for the token (default: 2 MiB)
+    pub fn form_limit(mut self, limit: usize) -> Self {
+        self.config_mut().form_limit = limit;
+        self
+    }
+
+    fn config_mut(&mut self) -> &mut CsrfConfig {
+        Arc::get_mut(&mut self.inner).expect("CsrfLayer is configured before use")
+    }
+
+    /// Whether `req` needs a token
+    fn checks(&self, req: &Request) -> bool {
+        if matches!(
+            *req.method(),
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+        ) {
+            return false;
+        }
+
+        let path = req.uri().path();
+        let exempt_path = self.inner.exempt_paths.iter().any(|exempt| {
+            exempt
+                .strip_suffix('*')
+                .map_or(path == exempt, |prefix| path.starts_with(prefix))
+        });
+        !exempt_path && !self.inner.exempt_content_types.contains(&media_type(req))
+    }
+
+    /// Check the token of `req`, handing back the request with its body
+    async fn verify(&self, session: &Session, req: Request) -> Result<Request, Response> {
+        let expected = session
+            .get::<String>(CSRF_TOKEN)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        let header = req
+            .headers()
+            .get(CSRF_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let (provided, req) = match header {
+            Some(token) => (Some(token), req),
+            None if media_type(&req) == "application/x-www-form-urlencoded" => {
+                self.form_token(req).await?
+            }
+            None => (None, req),
+        };
+
+        match (expected, provided) {
+            (Some(expected), Some(provided))
+                if bool::from(expected.as_bytes().ct_eq(provided.as_bytes())) =>
+            {
+                Ok(req)
+            }
+            _ => {
+                tracing::warn!(path = %req.uri().path(), "CSRF token mismatch");
+                Err(SessionError::CsrfTokenMismatch.into_response())
+            }
+        }
+    }
+
+    /// Token in the `_token` field of a URL-encoded form
+    async fn form_token(&self, req: Request) -> Result<(Option<String>, Request), Response> {
+        let (parts, body) = req.into_parts();
+        let bytes = axum::body::to_bytes(body, self.inner.form_limit)
+            .await
+            .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE.into_response())?;
+
+        let token = form_urlencoded::parse(&bytes)
+            .find(|(name, _)| name == CSRF_FIELD)
+            .map(|(_, value)| value.into_owned());
+        Ok((token, Request::from_parts(parts, Body::from(bytes))))
+    }
+}
+
+impl Default for CsrfLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lowercase content type without parameters
+fn media_type(req: &Request) -> String {
+    req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+impl Middleware for CsrfLayer {
+    async fn handle(self, req: Request, next: Next) -> Response {
+        if !self.checks(&req) {
+            return next.run(req).await;
+        }
+
+        let Some(session) = req
+            .extensions()
+            .get::<tower_sessions::Session>()
+            .cloned()
+            .map(Session::from)
+        else {
+            tracing::error!("CsrfLayer needs the SessionLayer around it");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        };
+
+        let req = match self.verify(&session, req).await {
+            Ok(req) => req,
+            Err(res) => return res,
+        };
+        if self.inner.rotate {
+            if let Err(e) = session.rotate_csrf_token().await {
+                return e.into_response();
+            }
+        }
+        next.run(req).await
+    }
+}
+
+impl<S> Layer<S> for CsrfLayer {
+    type Service = CsrfService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+/// Service created by [`CsrfLayer`]
+pub type CsrfService<S> = MiddlewareService<CsrfLayer, S>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryStore, SessionConfig, SessionLayer};
+    use axum::{
+        body::to_bytes,
+        routing::{get, post},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    fn app(csrf: CsrfLayer) -> Router {
+        let config = SessionConfig::new().secret("0123456789abcdef0123456789abcdef");
+        Router::new()
+            .route(
+                "/form",
+                get(|csrf: CsrfToken| async move { csrf.token().to_string() }),
+            )
+            .route("/profile", post(|| async { "Saved" }))
+            .route("/api/orders", post(|| async { "Created" }))
+            .route("/webhooks/stripe", post(|| async { "Received" }))
+            .layer(csrf)
+            .layer(SessionLayer::new(MemoryStore::default(), config).unwrap())
+    }
+
+    async fn send(app: &Router, req: axum::http::request::Builder, body: &str) -> Response {
+        app.clone()
+            .oneshot(req.body(Body::from(body.to_string())).unwrap())
+            .await
+            .unwrap()
+    }
+
+    /// Session cookie and CSRF token of a new session
+    async fn form(app: &Router) -> (String, String) {
+        let res = send(app, Request::builder().uri("/form"), "").await;
+        let cookie = res.headers()[header::SET_COOKIE].to_str().unwrap();
+        let cookie = cookie.split(';').next().unwrap().to_string();
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        (cookie, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn post_to(uri: &str, cookie: &str) -> axum::http::request::Builder {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header(header::COOKIE, cookie)
+    }
+
+    #[tokio::test]
+    async fn test_token_in_header_or_form() {
+        let app = app(CsrfLayer::new());
+        let (cookie, token) = form(&app).await;
+        assert_eq!(token.len(), 40);
+
+        let res = send(&app, post_to("/profile", &cookie), "").await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let req = post_to("/profile", &cookie).header(CSRF_HEADER, "forged");
+        assert_eq!(send(&app, req, "").await.status(), StatusCode::FORBIDDEN);
+
+        let req = post_to("/profile", &cookie).header(CSRF_HEADER, &token);
+        assert_eq!(send(&app, req, "").await.status(), StatusCode::OK);
+
+        let req = post_to("/profile", &cookie)
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+        let body = format!("name=Ann&_token={}", token);
+        assert_eq!(send(&app, req, &body).await.status(), StatusCode::OK);
+
+        // No session, no token
+        let req = Request::builder().method("POST").uri("/profile");
+        assert_eq!(send(&app, req, "").await.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_exemptions() {
+        let app = app(CsrfLayer::new()
+            .exempt("/webhooks/*")
+            .exempt_content_type("Application/JSON"));
+
+        let req = Request::builder().method("POST").uri("/webhooks/stripe");
+        assert_eq!(send(&app, req, "").await.status(), StatusCode::OK);
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("/api/orders")
+            .header(header::CONTENT_TYPE, "application/json; charset=utf-8");
+        assert_eq!(send(&app, req, "{}").await.status(), StatusCode::OK);
+
+        let req = Request::builder().method("POST").uri("/api/orders");
+        assert_eq!(send(&app, req, "").await.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_rotation() {
+        let app = app(CsrfLayer::new().rotate(true));
+        let (cookie, token) = form(&app).await;
+
+        let req = post_to("/profile", &cookie).header(CSRF_HEADER, &token);
+        assert_eq!(send(&app, req, "").await.status(), StatusCode::OK);
+        let req = post_to("/profile", &cookie).header(CSRF_HEADER, &token);
+        assert_eq!(send(&app, req, "").await.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_field() {
+        let csrf = CsrfToken("abc".to_string());
+        assert_eq!(
+            csrf.field(),
+            r#"<input type="hidden" name="_token" value="abc">"#
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Session errors
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use thiserror::Error;
+
+/// Session errors
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("Session store error: {0}")]
+    Store(String),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("CSRF token mismatch")]
+    CsrfTokenMismatch,
+}
+
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(SessionError, {
+    Self::Store(_) => (INTERNAL_SERVER_ERROR, "session.store"),
+    Self::Config(_) => (INTERNAL_SERVER_ERROR, "session.config"),
+    Self::Serialization(_) => (INTERNAL_SERVER_ERROR, "session.serialization"),
+    Self::CsrfTokenMismatch => (FORBIDDEN, "session.csrf_token_mismatch"),
+});
+
+/// Result type for session operations
+pub type SessionResult<T> = Result<T, SessionError>;
+
+impl From<tower_sessions::session::Error> for SessionError {
+    fn from(e: tower_sessions::session::Error) -> Self {
+        match e {
+            tower_sessions::session::Error::SerdeJson(e) => SessionError::Serialization(e),
+            tower_sessions::session::Error::Store(e) => SessionError::Store(e.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+impl From<redis::RedisError> for SessionError {
+    fn from(e: redis::RedisError) -> Self {
+        SessionError::Store(e.to_string())
+    }
+}
+
+#[cfg(feature = "database")]
+impl From<sqlx::Error> for SessionError {
+    fn from(e: sqlx::Error) -> Self {
+        SessionError::Store(e.to_string())
+    }
+}
+
+impl IntoResponse for SessionError {
+    fn into_response(self) -> Response {
+        if let SessionError::CsrfTokenMismatch = self {
+            let body = json!({
+                "error": "csrf_token_mismatch",
+                "message": "The CSRF token is missing or invalid",
+            });
+            return (StatusCode::FORBIDDEN, Json(body)).into_response();
+        }
+
+        tracing::error!(error = %self, "Session error");
+        let body = json!({
+            "error": "session_error",
+            "message": "The session could not be processed",
+        });
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response()
+    }
+}
@@ -0,0 +1,474 @@
+//! Session middleware
+
+use crate::{CookieMode, Session, SessionConfig, SessionError, SessionResult};
+use async_trait::async_trait;
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use cookie::{Cookie, CookieJar, Key};
+use rf_middleware::{Middleware, MiddlewareService, Next};
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tower::Layer;
+use tower_sessions::{
+    session::{Id, Record},
+    session_store, Expiry, SessionStore,
+};
+
+/// Unix timestamp of the session start, for the absolute timeout
+const CREATED_AT: &str = "_session.created_at";
+
+/// Layer loading the [`Session`] of every request and storing it afterwards
+///
+/// The session ID travels in a cookie, signed or encrypted with the
+/// configured secret. Sessions are only stored and the cookie only set once
+/// they hold data; the cookie of invalidated or expired sessions is removed.
+///
+/// # Example
+///
+/// ```
+/// use axum::{routing::get, Router};
+/// use rf_session::{MemoryStore, Session, SessionConfig, SessionLayer};
+///
+/// async fn visits(session: Session) -> String {
+///     let visits: u64 = session.get("visits").await.unwrap().unwrap_or(0) + 1;
+///     session.put("visits", visits).await.unwrap();
+///     format!("{} visits", visits)
+/// }
+///
+/// # fn main() -> rf_session::SessionResult<()> {
+/// let config = SessionConfig::new().secret("a secret of at least 32 bytes length");
+/// let app: Router = Router::new()
+///     .route("/", get(visits))
+///     .layer(SessionLayer::new(MemoryStore::default(), config)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SessionLayer {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    config: SessionConfig,
+    key: Option<Key>,
+    store: Arc<DynStore>,
+}
+
+impl SessionLayer {
+    /// Keep sessions in `store`
+    ///
+    /// Fails when signed or encrypted cookies are configured without a
+    /// sufficiently long secret.
+    pub fn new(store: impl SessionStore, config: SessionConfig) -> SessionResult<Self> {
+        let key = config.key()?;
+        Ok(Self {
+            inner: Arc::new(Inner {
+                config,
+                key,
+                store: Arc::new(DynStore(Box::new(store))),
+            }),
+        })
+    }
+
+    /// Session ID from the request cookies; `None` when missing or tampered
+    /// with
+    fn session_id(&self, jar: &CookieJar) -> Option<Id> {
+        let name = self.inner.config.cookie_name.as_str();
+        jar.get(name)?;
+        let cookie = match (&self.inner.key, self.inner.config.cookie_mode) {
+            (Some(key), CookieMode::Signed) => jar.signed(key).get(name),
+            (Some(key), _) => jar.private(key).get(name),
+            (None, _) => jar.get(name).cloned(),
+        };
+
+        let id = cookie.and_then(|cookie| cookie.value().parse().ok());
+        if id.is_none() {
+            tracing::warn!("Ignoring invalid session cookie");
+        }
+        id
+    }
+
+    /// Drop sessions past the absolute timeout
+    async fn enforce_absolute_timeout(
+        &self,
+        session: &tower_sessions::Session,
+    ) -> SessionResult<()> {
+        let Some(timeout) = self.inner.config.absolute_timeout else {
+            return Ok(());
+        };
+        let Some(created_at) = session.get::<i64>(CREATED_AT).await? else {
+            return Ok(());
+        };
+
+        let age = OffsetDateTime::now_utc().unix_timestamp() - created_at;
+        if age >= 0 && age as u64 >= timeout.as_secs() {
+            session.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Store the session after the handler ran and update the cookie
+    async fn finish(
+        &self,
+        session: &tower_sessions::Session,
+        had_cookie: bool,
+        res: &mut Response,
+    ) -> SessionResult<()> {
+        // Keep the session as it was before a failed request
+        if res.status().is_server_error() {
+            return Ok(());
+        }
+
+        Session::from(session.clone()).age_flash().await?;
+
+        // New sessions without data, invalidated and expired ones
+        if session.is_empty().await {
+            if had_cookie {
+                let mut cookie = self.cookie(String::new());
+                cookie.make_removal();
+                append_cookie(res, &cookie);
+            }
+            return Ok(());
+        }
+
+        let now = OffsetDateTime::now_utc();
+        let created_at = match session.get::<i64>(CREATED_AT).await? {
+            Some(created_at) => created_at,
+            None => {
+                session.insert(CREATED_AT, now.unix_timestamp()).await?;
+                now.unix_timestamp()
+            }
+        };
+
+        let config = &self.inner.config;
+        let mut expires_at = now.saturating_add(to_time(config.idle_timeout));
+        if let Some(timeout) = config.absolute_timeout {
+            let created_at = OffsetDateTime::from_unix_timestamp(created_at).unwrap_or(now);
+            expires_at = expires_at.min(created_at.saturating_add(to_time(timeout)));
+        }
+
+        // Saved on every request, so the idle timeout restarts
+        session.set_expiry(Some(Expiry::AtDateTime(expires_at)));
+        session.save().await?;
+
+        let id = session
+            .id()
+            .ok_or_else(|| SessionError::Store("The store did not assign an ID".into()))?;
+        let mut cookie = self.cookie(id.to_string());
+        cookie.set_max_age((expires_at - now).max(time::Duration::ZERO));
+
+        let mut jar = CookieJar::new();
+        match (&self.inner.key, config.cookie_mode) {
+            (Some(key), CookieMode::Signed) => jar.signed_mut(key).add(cookie),
+            (Some(key), _) => jar.private_mut(key).add(cookie),
+            (None, _) => jar.add(cookie),
+        }
+        for cookie in jar.delta() {
+            append_cookie(res, cookie);
+        }
+        Ok(())
+    }
+
+    fn cookie(&self, value: String) -> Cookie<'static> {
+        let config = &self.inner.config;
+        let mut cookie = Cookie::build((config.cookie_name.clone(), value))
+            .path(config.path.clone())
+            .secure(config.secure)
+            .http_only(config.http_only)
+            .same_site(config.same_site.into())
+            .build();
+        if let Some(domain) = &config.domain {
+            cookie.set_domain(domain.clone());
+        }
+        cookie
+    }
+}
+
+fn request_cookies(req: &Request) -> CookieJar {
+    let mut jar = CookieJar::new();
+    for value in req.headers().get_all(header::COOKIE) {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+        for cookie in Cookie::split_parse(value.to_string()).flatten() {
+            jar.add_original(cookie);
+        }
+    }
+    jar
+}
+
+fn to_time(duration: std::time::Duration) -> time::Duration {
+    time::Duration::try_from(duration).unwrap_or(time::Duration::MAX)
+}
+
+fn append_cookie(res: &mut Response, cookie: &Cookie<'_>) {
+    if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+        res.headers_mut().append(header::SET_COOKIE, value);
+    }
+}
+
+impl Middleware for SessionLayer {
+    async fn handle(self, mut req: Request, next: Next) -> Response {
+        let jar = request_cookies(&req);
+        let had_cookie = jar.get(&self.inner.config.cookie_name).is_some();
+        let id = self.session_id(&jar);
+
+        let idle_timeout = to_time(self.inner.config.idle_timeout);
+        let session = tower_sessions::Session::new(
+            id,
+            self.inner.store.clone(),
+            Some(Expiry::OnInactivity(idle_timeout)),
+        );
+        if id.is_some() {
+            if let Err(e) = self.enforce_absolute_timeout(&session).await {
+                return e.into_response();
+            }
+        }
+
+        req.extensions_mut().insert(session.clone());
+        let mut res = next.run(req).await;
+
+        if let Err(e) = self.finish(&session, had_cookie, &mut res).await {
+            return e.into_response();
+        }
+        res
+    }
+}
+
+impl<S> Layer<S> for SessionLayer {
+    type Service = SessionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+/// Service created by [`SessionLayer`]
+pub type SessionService<S> = MiddlewareService<SessionLayer, S>;
+
+/// Type-erased store, so the layer's type doesn't depend on it
+#[derive(Debug)]
+struct DynStore(Box<dyn SessionStore>);
+
+#[async_trait]
+impl SessionStore for DynStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        self.0.create(record).await
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        self.0.save(record).await
+    }
+
+    async fn load(&self, id: &Id) -> session_store::Result<Option<Record>> {
+        self.0.load(id).await
+    }
+
+    async fn delete(&self, id: &Id) -> session_store::Result<()> {
+        self.0.delete(id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStore;
+    use axum::{
+        body::{to_bytes, Body},
+        http::StatusCode,
+        routing::{get, post},
+        Router,
+    };
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    fn app(config: SessionConfig) -> Router {
+        Router::new()
+            .route(
+                "/name",
+                get(|session: Session| async move {
+                    session
+                        .get::<String>("name")
+                        .await
+                        .unwrap()
+                        .unwrap_or_default()
+                })
+                .post(|session: Session| async move {
+                    session.put("name", "Ann").await.unwrap();
+                }),
+            )
+            .route(
+                "/flash",
+                get(|session: Session| async move {
+                    session
+                        .get::<String>("status")
+                        .await
+                        .unwrap()
+                        .unwrap_or_default()
+                })
+                .post(|session: Session| async move {
+                    session.flash("status", "Saved").await.unwrap();
+                }),
+            )
+            .route(
+                "/flashed",
+                get(|session: Session| async move {
+                    serde_json::Value::from(session.flashed().await.unwrap()).to_string()
+                }),
+            )
+            .route(
+                "/login",
+                post(|session: Session| async move {
+                    session.regenerate().await.unwrap();
+                }),
+            )
+            .route(
+                "/logout",
+                post(|session: Session| async move {
+                    session.invalidate().await.unwrap();
+                }),
+            )
+            .layer(SessionLayer::new(MemoryStore::default(), config).unwrap())
+    }
+
+    fn config() -> SessionConfig {
+        SessionConfig::new().secret(SECRET)
+    }
+
+    /// Send a request, returning the body and the `name=value` part of the
+    /// session cookie set
+    async fn send(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        cookie: Option<&str>,
+    ) -> (String, Option<String>) {
+        let mut req = Request::builder().method(method).uri(uri);
+        if let Some(cookie) = cookie {
+            req = req.header(header::COOKIE, cookie);
+        }
+        let res = app
+            .clone()
+            .oneshot(req.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let cookie = res.headers().get(header::SET_COOKIE).map(|value| {
+            value
+                .to_str()
+                .unwrap()
+                .split(';')
+                .next()
+                .unwrap()
+                .to_string()
+        });
+        let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        (String::from_utf8(body.to_vec()).unwrap(), cookie)
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let app = app(config());
+
+        let (_, cookie) = send(&app, "GET", "/name", None).await;
+        assert!(cookie.is_none(), "empty sessions set no cookie");
+
+        let (_, cookie) = send(&app, "POST", "/name", None).await;
+        let cookie = cookie.unwrap();
+        assert!(cookie.starts_with("rustforge_session="));
+
+        let (body, _) = send(&app, "GET", "/name", Some(&cookie)).await;
+        assert_eq!(body, "Ann");
+
+        let tampered = format!("{}x", cookie);
+        let (body, _) = send(&app, "GET", "/name", Some(&tampered)).await;
+        assert_eq!(body, "");
+    }
+
+    #[tokio::test]
+    async fn test_cookie_modes() {
+        for mode in [CookieMode::Plain, CookieMode::Signed] {
+            let app = app(config().cookie_mode(mode));
+            let (_, cookie) = send(&app, "POST", "/name", None).await;
+            let cookie = cookie.unwrap();
+            let (body, _) = send(&app, "GET", "/name", Some(&cookie)).await;
+            assert_eq!(body, "Ann");
+        }
+
+        assert!(SessionLayer::new(MemoryStore::default(), SessionConfig::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_flash_lasts_one_request() {
+        let app = app(config());
+
+        let (_, cookie) = send(&app, "POST", "/flash", None).await;
+        let cookie = cookie.unwrap();
+
+        let (body, _) = send(&app, "GET", "/flash", Some(&cookie)).await;
+        assert_eq!(body, "Saved");
+        let (body, _) = send(&app, "GET", "/flash", Some(&cookie)).await;
+        assert_eq!(body, "");
+    }
+
+    #[tokio::test]
+    async fn test_flashed_lists_previous_flashes() {
+        let app = app(config());
+
+        let (_, cookie) = send(&app, "POST", "/flash", None).await;
+        let cookie = cookie.unwrap();
+
+        let (body, _) = send(&app, "GET", "/flashed", Some(&cookie)).await;
+        assert_eq!(body, r#"{"status":"Saved"}"#);
+        let (body, _) = send(&app, "GET", "/flashed", Some(&cookie)).await;
+        assert_eq!(body, "{}");
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_replaces_id() {
+        let app = app(config().cookie_mode(CookieMode::Plain));
+
+        let (_, old) = send(&app, "POST", "/name", None).await;
+        let old = old.unwrap();
+        let (_, new) = send(&app, "POST", "/login", Some(&old)).await;
+        let new = new.unwrap();
+        assert_ne!(old, new);
+
+        let (body, _) = send(&app, "GET", "/name", Some(&new)).await;
+        assert_eq!(body, "Ann");
+        let (body, _) = send(&app, "GET", "/name", Some(&old)).await;
+        assert_eq!(body, "");
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_removes_cookie() {
+        let app = app(config());
+
+        let (_, cookie) = send(&app, "POST", "/name", None).await;
+        let cookie = cookie.unwrap();
+        let (_, removal) = send(&app, "POST", "/logout", Some(&cookie)).await;
+        assert_eq!(removal.unwrap(), "rustforge_session=");
+
+        let (body, _) = send(&app, "GET", "/name", Some(&cookie)).await;
+        assert_eq!(body, "");
+    }
+
+    #[tokio::test]
+    async fn test_timeouts() {
+        let idle = app(config().idle_timeout(Duration::ZERO));
+        let (_, cookie) = send(&idle, "POST", "/name", None).await;
+        let (body, _) = send(&idle, "GET", "/name", Some(&cookie.unwrap())).await;
+        assert_eq!(body, "");
+
+        let absolute = app(config().absolute_timeout(Some(Duration::ZERO)));
+        let (_, cookie) = send(&absolute, "POST", "/name", None).await;
+        let (body, _) = send(&absolute, "GET", "/name", Some(&cookie.unwrap())).await;
+        assert_eq!(body, "");
+    }
+}
@@ -0,0 +1,71 @@
+//! Session management for RustForge
+//!
+//! Sessions are identified by a cookie, encrypted by default, and kept in a
+//! pluggable store. [`SessionLayer`] loads the session of every request and
+//! stores it afterwards; handlers read and write it through the [`Session`]
+//! extractor with typed `get`/`put`.
+//!
+//! # Features
+//!
+//! - Plain, signed or encrypted session cookies, see [`CookieMode`]
+//! - Idle and absolute timeouts
+//! - Flash data lasting until the end of the next request
+//! - [`Session::regenerate`] after login against session fixation
+//! - CSRF tokens checked on unsafe requests by [`CsrfLayer`], and put in
+//!   forms with [`CsrfToken`]
+//!
+//! # Stores
+//!
+//! - [`MemoryStore`] for tests and single-instance apps
+//! - `RedisStore` (feature `redis`) with sessions expiring in Redis
+//! - `PostgresStore` (feature `database`) with a `sessions` table
+//!
+//! Any tower-sessions [`SessionStore`] works as well.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use axum::{routing::post, Router};
+//! use rf_session::{MemoryStore, Session, SessionConfig, SessionLayer, SessionResult};
+//!
+//! async fn login(session: Session) -> SessionResult<()> {
+//!     // After checking the credentials
+//!     session.regenerate().await?;
+//!     session.put("user_id", 42).await?;
+//!     session.flash("status", "Welcome back!").await
+//! }
+//!
+//! # fn main() -> SessionResult<()> {
+//! // Reads SESSION_SECRET, SESSION_IDLE_TIMEOUT, ...
+//! let config = SessionConfig::from_env()?;
+//! let app: Router = Router::new()
+//!     .route("/login", post(login))
+//!     .layer(SessionLayer::new(MemoryStore::default(), config)?);
+//! # Ok(())
+//! # }
+//! ```
+
+mod config;
+mod csrf;
+mod error;
+mod layer;
+mod session;
+
+#[cfg(feature = "redis")]
+mod redis;
+
+#[cfg(feature = "database")]
+mod postgres;
+
+pub use config::{CookieMode, SameSite, SessionConfig, MIN_SECRET_LENGTH};
+pub use csrf::{CsrfLayer, CsrfService, CsrfToken, CSRF_FIELD, CSRF_HEADER};
+pub use error::{SessionError, SessionResult};
+pub use layer::{SessionLayer, SessionService};
+pub use session::Session;
+pub use tower_sessions::{ExpiredDeletion, MemoryStore, SessionStore};
+
+#[cfg(feature = "redis")]
+pub use self::redis::RedisStore;
+
+#[cfg(feature = "database")]
+pub use postgres::PostgresStore;
@@ -0,0 +1,167 @@
+//! Postgres session store
+
+use crate::SessionResult;
+use async_trait::async_trait;
+use sqlx::{postgres::PgPoolOptions, PgPool, Row};
+use time::OffsetDateTime;
+use tower_sessions::{
+    session::{Id, Record},
+    session_store, ExpiredDeletion, SessionStore,
+};
+
+/// Postgres-backed session store
+///
+/// Sessions are rows of a single table, see [`migrate`](Self::migrate).
+/// Expired rows are skipped when loading; remove them periodically with
+/// [`ExpiredDeletion::delete_expired`], e.g. from a scheduled task.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_session::{PostgresStore, SessionConfig, SessionLayer};
+///
+/// # async fn example() -> rf_session::SessionResult<()> {
+/// let store = PostgresStore::connect("postgres://localhost/app").await?;
+/// store.migrate().await?;
+/// let layer = SessionLayer::new(store, SessionConfig::from_env()?)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PostgresStore {
+    pool: PgPool,
+    table: String,
+}
+
+impl PostgresStore {
+    /// Create a store on an existing pool, in the `sessions` table
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            table: "sessions".to_string(),
+        }
+    }
+
+    /// Connect to `database_url`
+    pub async fn connect(database_url: &str) -> SessionResult<Self> {
+        let pool = PgPoolOptions::new().connect(database_url).await?;
+        Ok(Self::new(pool))
+    }
+
+    /// Use a different table
+    pub fn table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Create the sessions table and its index if they don't exist
+    pub async fn migrate(&self) -> SessionResult<()> {
+        let table = &self.table;
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {table} (
+                id TEXT PRIMARY KEY,
+                data TEXT NOT NULL,
+                expires_at BIGINT NOT NULL
+            )"
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(&format!(
+            "CREATE INDEX IF NOT EXISTS {table}_expires_at_idx ON {table} (expires_at)"
+        ))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+fn encode(record: &Record) -> session_store::Result<String> {
+    serde_json::to_string(record).map_err(|e| session_store::Error::Encode(e.to_string()))
+}
+
+fn backend_error(e: sqlx::Error) -> session_store::Error {
+    session_store::Error::Backend(e.to_string())
+}
+
+#[async_trait]
+impl SessionStore for PostgresStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        loop {
+            let result = sqlx::query(&format!(
+                "INSERT INTO {} (id, data, expires_at) VALUES ($1, $2, $3)
+                 ON CONFLICT (id) DO NOTHING",
+                self.table
+            ))
+            .bind(record.id.to_string())
+            .bind(encode(record)?)
+            .bind(record.expiry_date.unix_timestamp())
+            .execute(&self.pool)
+            .await
+            .map_err(backend_error)?;
+            if result.rows_affected() > 0 {
+                return Ok(());
+            }
+
+            // Pick another ID on the unlikely collision
+            record.id = Id::default();
+        }
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        sqlx::query(&format!(
+            "INSERT INTO {} (id, data, expires_at) VALUES ($1, $2, $3)
+             ON CONFLICT (id) DO UPDATE SET
+                data = EXCLUDED.data,
+                expires_at = EXCLUDED.expires_at",
+            self.table
+        ))
+        .bind(record.id.to_string())
+        .bind(encode(record)?)
+        .bind(record.expiry_date.unix_timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn load(&self, id: &Id) -> session_store::Result<Option<Record>> {
+        let row = sqlx::query(&format!(
+            "SELECT data FROM {} WHERE id = $1 AND expires_at > $2",
+            self.table
+        ))
+        .bind(id.to_string())
+        .bind(OffsetDateTime::now_utc().unix_timestamp())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(backend_error)?;
+
+        row.map(|row| serde_json::from_str(row.get("data")))
+            .transpose()
+            .map_err(|e| session_store::Error::Decode(e.to_string()))
+    }
+
+    async fn delete(&self, id: &Id) -> session_store::Result<()> {
+        sqlx::query(&format!("DELETE FROM {} WHERE id = $1", self.table))
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExpiredDeletion for PostgresStore {
+    async fn delete_expired(&self) -> session_store::Result<()> {
+        sqlx::query(&format!(
+            "DELETE FROM {} WHERE expires_at <= $1",
+            self.table
+        ))
+        .bind(OffsetDateTime::now_utc().unix_timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(backend_error)?;
+        Ok(())
+    }
+}
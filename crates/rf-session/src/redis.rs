@@ -0,0 +1,125 @@
+//! Redis session store
+
+use crate::SessionResult;
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use tower_sessions::{
+    session::{Id, Record},
+    session_store, SessionStore,
+};
+
+/// Redis-backed session store
+///
+/// Sessions are JSON strings expiring with the session, so Redis removes
+/// them without a cleanup task.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_session::{RedisStore, SessionConfig, SessionLayer};
+///
+/// # async fn example() -> rf_session::SessionResult<()> {
+/// let store = RedisStore::connect("redis://localhost").await?;
+/// let layer = SessionLayer::new(store, SessionConfig::from_env()?)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RedisStore {
+    conn: ConnectionManager,
+    prefix: String,
+}
+
+impl RedisStore {
+    /// Create a store on an existing connection, with the key prefix
+    /// `session:`
+    pub fn new(conn: ConnectionManager) -> Self {
+        Self {
+            conn,
+            prefix: "session:".to_string(),
+        }
+    }
+
+    /// Connect to `redis_url`
+    pub async fn connect(redis_url: &str) -> SessionResult<Self> {
+        let client = redis::Client::open(redis_url)?;
+        Ok(Self::new(client.get_connection_manager().await?))
+    }
+
+    /// Use a different key prefix, separating applications sharing a Redis
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    fn key(&self, id: &Id) -> String {
+        format!("{}{}", self.prefix, id)
+    }
+
+    /// Store `record`; with `only_new`, only if its ID is unused
+    async fn set(&self, record: &Record, only_new: bool) -> session_store::Result<bool> {
+        let data = serde_json::to_string(record)
+            .map_err(|e| session_store::Error::Encode(e.to_string()))?;
+
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(self.key(&record.id)).arg(data);
+        if only_new {
+            cmd.arg("NX");
+        }
+        cmd.arg("EXAT").arg(record.expiry_date.unix_timestamp());
+
+        let reply: Option<String> = cmd
+            .query_async(&mut self.conn.clone())
+            .await
+            .map_err(backend_error)?;
+        Ok(reply.is_some())
+    }
+}
+
+impl std::fmt::Debug for RedisStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisStore")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl SessionStore for RedisStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        // Pick another ID on the unlikely collision
+        while !self.set(record, true).await? {
+            record.id = Id::default();
+        }
+        Ok(())
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        self.set(record, false).await?;
+        Ok(())
+    }
+
+    async fn load(&self, id: &Id) -> session_store::Result<Option<Record>> {
+        let data: Option<String> = redis::cmd("GET")
+            .arg(self.key(id))
+            .query_async(&mut self.conn.clone())
+            .await
+            .map_err(backend_error)?;
+
+        data.map(|data| serde_json::from_str(&data))
+            .transpose()
+            .map_err(|e| session_store::Error::Decode(e.to_string()))
+    }
+
+    async fn delete(&self, id: &Id) -> session_store::Result<()> {
+        redis::cmd("DEL")
+            .arg(self.key(id))
+            .query_async::<_, ()>(&mut self.conn.clone())
+            .await
+            .map_err(backend_error)
+    }
+}
+
+fn backend_error(e: redis::RedisError) -> session_store::Error {
+    session_store::Error::Backend(e.to_string())
+}
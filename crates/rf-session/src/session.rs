@@ -0,0 +1,189 @@
+//! Session extractor
+
+use crate::SessionResult;
+use axum::extract::FromRequestParts;
+use axum::http::{request::Parts, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Keys flashed during the current request
+const FLASH_NEW: &str = "_flash.new";
+
+/// Keys flashed by the previous request, removed at the end of this one
+const FLASH_OLD: &str = "_flash.old";
+
+/// Session of the current request
+///
+/// Extracted in handlers behind a [`SessionLayer`](crate::SessionLayer).
+/// Values are stored as JSON, so any serde type can be put and read back.
+///
+/// ```
+/// use rf_session::{Session, SessionResult};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Default, Serialize, Deserialize)]
+/// struct Cart {
+///     items: Vec<u64>,
+/// }
+///
+/// async fn add_to_cart(session: Session) -> SessionResult<String> {
+///     let mut cart: Cart = session.get("cart").await?.unwrap_or_default();
+///     cart.items.push(42);
+///     session.put("cart", &cart).await?;
+///     session.flash("status", "Added to cart").await?;
+///     Ok(format!("{} items", cart.items.len()))
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session {
+    inner: tower_sessions::Session,
+}
+
+impl Session {
+    /// Value of `key`
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> SessionResult<Option<T>> {
+        Ok(self.inner.get(key).await?)
+    }
+
+    /// Store `value` under `key`
+    pub async fn put<T: Serialize>(&self, key: &str, value: T) -> SessionResult<()> {
+        Ok(self.inner.insert(key, value).await?)
+    }
+
+    /// Whether `key` has a value
+    pub async fn has(&self, key: &str) -> SessionResult<bool> {
+        Ok(self.inner.get_value(key).await?.is_some())
+    }
+
+    /// Remove `key`, returning its value
+    pub async fn pull<T: DeserializeOwned>(&self, key: &str) -> SessionResult<Option<T>> {
+        Ok(self.inner.remove(key).await?)
+    }
+
+    /// Remove `key`
+    pub async fn forget(&self, key: &str) -> SessionResult<()> {
+        self.inner.remove_value(key).await?;
+        Ok(())
+    }
+
+    /// Store `value` under `key` until the end of the next request, e.g. a
+    /// status message shown after a redirect
+    pub async fn flash<T: Serialize>(&self, key: &str, value: T) -> SessionResult<()> {
+        self.put(key, value).await?;
+
+        let mut new = self.flash_keys(FLASH_NEW).await?;
+        if !new.iter().any(|k| k == key) {
+            new.push(key.to_string());
+        }
+        self.put(FLASH_NEW, new).await?;
+
+        let mut old = self.flash_keys(FLASH_OLD).await?;
+        if old.iter().any(|k| k == key) {
+            old.retain(|k| k != key);
+            self.put(FLASH_OLD, old).await?;
+        }
+        Ok(())
+    }
+
+    /// Data flashed by the previous request, e.g. for views showing status
+    /// messages
+    pub async fn flashed(&self) -> SessionResult<serde_json::Map<String, serde_json::Value>> {
+        let mut flashed = serde_json::Map::new();
+        for key in self.flash_keys(FLASH_OLD).await? {
+            if let Some(value) = self.get(&key).await? {
+                flashed.insert(key, value);
+            }
+        }
+        Ok(flashed)
+    }
+
+    /// Keep all flash data for one more request
+    pub async fn reflash(&self) -> SessionResult<()> {
+        let old = self.flash_keys(FLASH_OLD).await?;
+        let keys: Vec<&str> = old.iter().map(String::as_str).collect();
+        self.keep(&keys).await
+    }
+
+    /// Keep the flash data of `keys` for one more request
+    pub async fn keep(&self, keys: &[&str]) -> SessionResult<()> {
+        let mut old = self.flash_keys(FLASH_OLD).await?;
+        let mut new = self.flash_keys(FLASH_NEW).await?;
+        for key in keys {
+            if old.iter().any(|k| k == key) {
+                old.retain(|k| k != key);
+                new.push(key.to_string());
+            }
+        }
+        self.put(FLASH_OLD, old).await?;
+        self.put(FLASH_NEW, new).await
+    }
+
+    /// Give the session a new ID, keeping its data
+    ///
+    /// Call after logging a user in, so an ID planted before the login
+    /// can't be used to take over the session (session fixation).
+    pub async fn regenerate(&self) -> SessionResult<()> {
+        Ok(self.inner.cycle_id().await?)
+    }
+
+    /// Remove all data and the session itself, e.g. on logout
+    pub async fn invalidate(&self) -> SessionResult<()> {
+        Ok(self.inner.flush().await?)
+    }
+
+    /// Session ID, once the session is stored
+    pub fn id(&self) -> Option<String> {
+        self.inner.id().map(|id| id.to_string())
+    }
+
+    /// The underlying tower-sessions session, e.g. for rf-auth's
+    /// `SessionGuard`
+    pub fn inner(&self) -> &tower_sessions::Session {
+        &self.inner
+    }
+
+    /// Remove the flash data of the previous request and age the data of
+    /// this one
+    pub(crate) async fn age_flash(&self) -> SessionResult<()> {
+        let old = self.flash_keys(FLASH_OLD).await?;
+        let new = self.flash_keys(FLASH_NEW).await?;
+        if old.is_empty() && new.is_empty() {
+            return Ok(());
+        }
+
+        for key in &old {
+            self.forget(key).await?;
+        }
+        self.forget(FLASH_NEW).await?;
+        if new.is_empty() {
+            self.forget(FLASH_OLD).await
+        } else {
+            self.put(FLASH_OLD, new).await
+        }
+    }
+
+    async fn flash_keys(&self, list: &str) -> SessionResult<Vec<String>> {
+        Ok(self.get(list).await?.unwrap_or_default())
+    }
+}
+
+impl From<tower_sessions::Session> for Session {
+    fn from(inner: tower_sessions::Session) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Session {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<tower_sessions::Session>()
+            .cloned()
+            .map(Session::from)
+            .ok_or((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Can't extract session. Is `SessionLayer` enabled?",
+            ))
+    }
+}
@@ -0,0 +1,371 @@
+//! Shutdown orchestration
+
+use crate::report::{ComponentReport, ShutdownReport, StopOutcome};
+use crate::signal::{self, ShutdownSignal};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio::time::{timeout_at, Instant};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+type StopFuture = Pin<Box<dyn Future<Output = Result<(), BoxError>> + Send>>;
+
+struct Component {
+    name: String,
+    timeout: Option<Duration>,
+    start: Box<dyn FnOnce(ShutdownSignal) -> StopFuture + Send>,
+}
+
+struct Hook {
+    name: String,
+    run: Box<dyn FnOnce() -> StopFuture + Send>,
+}
+
+/// Runs the components of an application and stops them together
+///
+/// Components (HTTP server, queue workers, schedulers) are started by
+/// [`run`](Self::run) and told to stop on SIGTERM or SIGINT, or as soon as
+/// one of them stops on its own. Each gets its drain timeout to finish
+/// in-flight work before it is aborted. Hooks, e.g. flushing buffers, run
+/// after all components stopped.
+///
+/// ```no_run
+/// use axum::{routing::get, Router};
+/// use rf_shutdown::ShutdownController;
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let app: Router = Router::new().route("/", get(|| async { "Hello" }));
+/// let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
+///
+/// let report = ShutdownController::new()
+///     .drain_timeout(Duration::from_secs(25))
+///     .spawn("http", |signal| async move {
+///         axum::serve(listener, app)
+///             .with_graceful_shutdown(signal.cancelled())
+///             .await
+///     })
+///     .on_shutdown("metrics", || async {
+///         // Push the last metrics
+///         Ok::<_, std::io::Error>(())
+///     })
+///     .run()
+///     .await;
+/// report.into_result()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ShutdownController {
+    tx: watch::Sender<bool>,
+    drain_timeout: Duration,
+    hook_timeout: Duration,
+    components: Vec<Component>,
+    hooks: Vec<Hook>,
+}
+
+impl ShutdownController {
+    pub fn new() -> Self {
+        Self {
+            tx: watch::channel(false).0,
+            drain_timeout: Duration::from_secs(30),
+            hook_timeout: Duration::from_secs(5),
+            components: vec![],
+            hooks: vec![],
+        }
+    }
+
+    /// How long components get to stop (default: 30 seconds)
+    ///
+    /// Keep it below the pod's `terminationGracePeriodSeconds`.
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// How long each hook may run (default: 5 seconds)
+    pub fn hook_timeout(mut self, timeout: Duration) -> Self {
+        self.hook_timeout = timeout;
+        self
+    }
+
+    /// Signal for parts of the application not run by the controller,
+    /// e.g. a readiness check
+    pub fn signal(&self) -> ShutdownSignal {
+        ShutdownSignal::new(self.tx.subscribe())
+    }
+
+    /// Run a component until the signal it is given completes
+    pub fn spawn<F, Fut, E>(self, name: impl Into<String>, start: F) -> Self
+    where
+        F: FnOnce(ShutdownSignal) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        self.component(name.into(), None, start)
+    }
+
+    /// Run a component with its own drain timeout
+    pub fn spawn_with_timeout<F, Fut, E>(
+        self,
+        name: impl Into<String>,
+        timeout: Duration,
+        start: F,
+    ) -> Self
+    where
+        F: FnOnce(ShutdownSignal) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        self.component(name.into(), Some(timeout), start)
+    }
+
+    fn component<F, Fut, E>(mut self, name: String, timeout: Option<Duration>, start: F) -> Self
+    where
+        F: FnOnce(ShutdownSignal) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        self.components.push(Component {
+            name,
+            timeout,
+            start: Box::new(move |signal| {
+                Box::pin(async move { start(signal).await.map_err(Into::into) })
+            }),
+        });
+        self
+    }
+
+    /// Run `hook` once all components stopped
+    pub fn on_shutdown<F, Fut, E>(mut self, name: impl Into<String>, hook: F) -> Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        self.hooks.push(Hook {
+            name: name.into(),
+            run: Box::new(move || Box::pin(async move { hook().await.map_err(Into::into) })),
+        });
+        self
+    }
+
+    /// Run the components until SIGTERM or SIGINT, then stop them
+    pub async fn run(self) -> ShutdownReport {
+        self.run_until(signal::terminate()).await
+    }
+
+    /// Run the components until `signal` completes, then stop them
+    pub async fn run_until(self, signal: impl Future<Output = ()> + Send) -> ShutdownReport {
+        let (exited_tx, mut exited_rx) = mpsc::unbounded_channel();
+        let mut running = vec![];
+
+        for component in self.components {
+            let future = (component.start)(ShutdownSignal::new(self.tx.subscribe()));
+            let exited = exited_tx.clone();
+            let name = component.name.clone();
+            let handle = tokio::spawn(async move {
+                let result = future.await;
+                let _ = exited.send(name);
+                result
+            });
+            let timeout = component.timeout.unwrap_or(self.drain_timeout);
+            running.push((component.name, timeout, handle));
+        }
+        tracing::info!(components = running.len(), "Application started");
+
+        tokio::select! {
+            _ = signal => tracing::info!("Shutting down"),
+            Some(name) = exited_rx.recv() => {
+                tracing::warn!(component = %name, "Component stopped unexpectedly, shutting down");
+            }
+        }
+        let _ = self.tx.send(true);
+
+        // Components drain in parallel, their timeouts count from here
+        let started = Instant::now();
+        let mut report = ShutdownReport::default();
+        for (name, timeout, handle) in running {
+            report
+                .components
+                .push(stop(name, handle, started, started + timeout).await);
+        }
+        for hook in self.hooks {
+            let handle = tokio::spawn((hook.run)());
+            let hook_started = Instant::now();
+            report
+                .components
+                .push(stop(hook.name, handle, started, hook_started + self.hook_timeout).await);
+        }
+
+        if report.is_clean() {
+            tracing::info!("Shutdown complete");
+        }
+        report
+    }
+}
+
+impl Default for ShutdownController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Wait for `handle` until `deadline`, aborting it if it is still running
+async fn stop(
+    name: String,
+    mut handle: JoinHandle<Result<(), BoxError>>,
+    started: Instant,
+    deadline: Instant,
+) -> ComponentReport {
+    let outcome = match timeout_at(deadline, &mut handle).await {
+        Ok(Ok(Ok(()))) => StopOutcome::Stopped,
+        Ok(Ok(Err(e))) => StopOutcome::Failed(e.to_string()),
+        Ok(Err(e)) => StopOutcome::Failed(e.to_string()),
+        Err(_) => {
+            handle.abort();
+            StopOutcome::TimedOut
+        }
+    };
+    if outcome != StopOutcome::Stopped {
+        tracing::error!(component = %name, outcome = %outcome, "Component did not stop cleanly");
+    }
+
+    ComponentReport {
+        name,
+        outcome,
+        elapsed: started.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn after(millis: u64) -> impl Future<Output = ()> + Send {
+        tokio::time::sleep(Duration::from_millis(millis))
+    }
+
+    #[tokio::test]
+    async fn test_components_stop_on_signal() {
+        let stopped = Arc::new(Mutex::new(vec![]));
+        let log = Arc::clone(&stopped);
+        let controller = ShutdownController::new().spawn("worker", move |signal| async move {
+            signal.cancelled().await;
+            log.lock().unwrap().push("worker");
+            Ok::<_, std::io::Error>(())
+        });
+        let readiness = controller.signal();
+        assert!(!readiness.is_shutting_down());
+
+        let report = controller.run_until(after(10)).await;
+        assert!(report.is_clean());
+        assert_eq!(report.components[0].name, "worker");
+        assert_eq!(*stopped.lock().unwrap(), vec!["worker"]);
+        assert!(readiness.is_shutting_down());
+    }
+
+    #[tokio::test]
+    async fn test_reports_failed_and_timed_out_components() {
+        let report = ShutdownController::new()
+            .drain_timeout(Duration::from_millis(50))
+            .spawn("stuck", |_| {
+                std::future::pending::<Result<(), std::io::Error>>()
+            })
+            .spawn_with_timeout("slow", Duration::from_secs(5), |signal| async move {
+                signal.cancelled().await;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok::<_, std::io::Error>(())
+            })
+            .spawn("broken", |signal| async move {
+                signal.cancelled().await;
+                Err("connection lost")
+            })
+            .run_until(after(10))
+            .await;
+
+        let outcomes: Vec<_> = report
+            .components
+            .iter()
+            .map(|c| c.outcome.clone())
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                StopOutcome::TimedOut,
+                StopOutcome::Stopped,
+                StopOutcome::Failed("connection lost".to_string()),
+            ]
+        );
+        assert!(!report.is_clean());
+        assert_eq!(
+            report.into_result().unwrap_err().to_string(),
+            "Components failed to stop cleanly: stuck, broken"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stopped_component_shuts_down_the_others() {
+        let report = ShutdownController::new()
+            .spawn("server", |_| async { Err("address in use") })
+            .spawn("worker", |signal| async move {
+                signal.cancelled().await;
+                Ok::<_, std::io::Error>(())
+            })
+            .run_until(std::future::pending())
+            .await;
+
+        assert_eq!(
+            report.components[0].outcome,
+            StopOutcome::Failed("address in use".to_string())
+        );
+        assert!(report.components[1].is_clean());
+    }
+
+    #[tokio::test]
+    async fn test_hooks_run_after_components() {
+        let order = Arc::new(Mutex::new(vec![]));
+        let (component, hook) = (Arc::clone(&order), Arc::clone(&order));
+
+        let report = ShutdownController::new()
+            .hook_timeout(Duration::from_millis(50))
+            .on_shutdown("flush", move || async move {
+                hook.lock().unwrap().push("flush");
+                Ok::<_, std::io::Error>(())
+            })
+            .on_shutdown("hanging", || {
+                std::future::pending::<Result<(), std::io::Error>>()
+            })
+            .spawn("worker", move |signal| async move {
+                signal.cancelled().await;
+                component.lock().unwrap().push("worker");
+                Ok::<_, std::io::Error>(())
+            })
+            .run_until(after(10))
+            .await;
+
+        assert_eq!(*order.lock().unwrap(), vec!["worker", "flush"]);
+        let names: Vec<_> = report.failures().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["hanging"]);
+    }
+
+    #[tokio::test]
+    async fn test_http_server_drains() {
+        use axum::{routing::get, Router};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let app: Router = Router::new().route("/", get(|| async { "Hello" }));
+
+        let report = ShutdownController::new()
+            .spawn("http", |signal| async move {
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(signal.cancelled())
+                    .await
+            })
+            .run_until(after(10))
+            .await;
+        assert!(report.is_clean());
+    }
+}
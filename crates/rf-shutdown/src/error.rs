@@ -0,0 +1,11 @@
+//! Shutdown errors
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ShutdownError {
+    #[error("Components failed to stop cleanly: {}", .0.join(", "))]
+    Unclean(Vec<String>),
+}
+
+pub type ShutdownResult<T> = Result<T, ShutdownError>;
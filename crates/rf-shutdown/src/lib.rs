@@ -0,0 +1,55 @@
+//! Graceful shutdown for RustForge
+//!
+//! A [`ShutdownController`] runs the long-lived parts of an application
+//! and stops them together on SIGTERM or SIGINT, so rolling deployments
+//! don't cut off requests or jobs in progress.
+//!
+//! # Features
+//!
+//! - One [`ShutdownSignal`] per component, fitting axum's
+//!   `with_graceful_shutdown`
+//! - Drain timeouts per component, components still running after theirs
+//!   are aborted
+//! - Hooks running after all components stopped, e.g. to flush buffers
+//! - A [`ShutdownReport`] naming the components that failed to stop
+//!   cleanly
+//! - rf-queue workers (feature `queue`) and rf-scheduler (feature
+//!   `scheduler`) as components
+//!
+//! # Example
+//!
+//! ```ignore
+//! use rf_shutdown::ShutdownController;
+//!
+//! let report = ShutdownController::new()
+//!     .drain_timeout(Duration::from_secs(25))
+//!     .spawn("http", |signal| async move {
+//!         axum::serve(listener, app)
+//!             .with_graceful_shutdown(signal.cancelled())
+//!             .await
+//!     })
+//!     .worker("queue", worker)
+//!     .scheduler(scheduler)
+//!     .on_shutdown("cache", move || async move { write_behind.flush().await })
+//!     .run()
+//!     .await;
+//!
+//! if !report.is_clean() {
+//!     std::process::exit(1);
+//! }
+//! ```
+
+mod controller;
+mod error;
+mod report;
+mod signal;
+
+#[cfg(feature = "queue")]
+mod queue;
+#[cfg(feature = "scheduler")]
+mod scheduler;
+
+pub use controller::ShutdownController;
+pub use error::{ShutdownError, ShutdownResult};
+pub use report::{ComponentReport, ShutdownReport, StopOutcome};
+pub use signal::{terminate, ShutdownSignal};
@@ -0,0 +1,34 @@
+//! rf-queue workers as components
+
+use crate::ShutdownController;
+use rf_queue::Worker;
+
+impl ShutdownController {
+    /// Run `worker` as the component `name`, finishing running jobs on
+    /// shutdown
+    pub fn worker(self, name: impl Into<String>, worker: Worker) -> Self {
+        self.spawn(name, |signal| {
+            worker.start_with_shutdown(signal.cancelled())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rf_queue::MemoryQueue;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_worker_stops() {
+        let worker =
+            Worker::new(Arc::new(MemoryQueue::new())).poll_interval(Duration::from_secs(60));
+        let report = ShutdownController::new()
+            .worker("emails", worker)
+            .run_until(tokio::time::sleep(Duration::from_millis(10)))
+            .await;
+        assert!(report.is_clean());
+        assert_eq!(report.components[0].name, "emails");
+    }
+}
@@ -0,0 +1,70 @@
+//! What happened to each component during shutdown
+
+use crate::error::{ShutdownError, ShutdownResult};
+use std::fmt;
+use std::time::Duration;
+
+/// How a component or hook stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopOutcome {
+    /// Stopped within its drain timeout
+    Stopped,
+    /// Returned an error or panicked
+    Failed(String),
+    /// Still running after its drain timeout, and was aborted
+    TimedOut,
+}
+
+/// Outcome of one component or hook
+#[derive(Debug, Clone)]
+pub struct ComponentReport {
+    pub name: String,
+    pub outcome: StopOutcome,
+    /// Time from the start of shutdown until it stopped
+    pub elapsed: Duration,
+}
+
+impl ComponentReport {
+    pub fn is_clean(&self) -> bool {
+        self.outcome == StopOutcome::Stopped
+    }
+}
+
+/// Outcome of a shutdown, components first and hooks after them, each in
+/// registration order
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownReport {
+    pub components: Vec<ComponentReport>,
+}
+
+impl ShutdownReport {
+    /// Whether every component and hook stopped cleanly
+    pub fn is_clean(&self) -> bool {
+        self.components.iter().all(ComponentReport::is_clean)
+    }
+
+    /// Components and hooks that failed or timed out
+    pub fn failures(&self) -> impl Iterator<Item = &ComponentReport> {
+        self.components.iter().filter(|report| !report.is_clean())
+    }
+
+    /// Fails naming the components that didn't stop cleanly
+    pub fn into_result(self) -> ShutdownResult<()> {
+        let failed: Vec<String> = self.failures().map(|report| report.name.clone()).collect();
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(ShutdownError::Unclean(failed))
+        }
+    }
+}
+
+impl fmt::Display for StopOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopOutcome::Stopped => write!(f, "stopped"),
+            StopOutcome::Failed(error) => write!(f, "failed: {}", error),
+            StopOutcome::TimedOut => write!(f, "timed out"),
+        }
+    }
+}
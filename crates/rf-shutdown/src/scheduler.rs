@@ -0,0 +1,14 @@
+//! rf-scheduler as a component
+
+use crate::ShutdownController;
+use rf_scheduler::Scheduler;
+
+impl ShutdownController {
+    /// Run `scheduler` as the component `scheduler`, finishing running
+    /// tasks on shutdown
+    pub fn scheduler(self, scheduler: Scheduler) -> Self {
+        self.spawn("scheduler", |signal| {
+            scheduler.start_with_shutdown(signal.cancelled())
+        })
+    }
+}
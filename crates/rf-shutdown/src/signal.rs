@@ -0,0 +1,55 @@
+//! Shutdown signals
+
+use tokio::sync::watch;
+
+/// Tells a component that the application is shutting down
+///
+/// Cheap to clone, every component gets its own.
+#[derive(Clone, Debug)]
+pub struct ShutdownSignal {
+    rx: watch::Receiver<bool>,
+}
+
+impl ShutdownSignal {
+    pub(crate) fn new(rx: watch::Receiver<bool>) -> Self {
+        Self { rx }
+    }
+
+    /// Whether shutdown has started, e.g. to fail readiness checks
+    pub fn is_shutting_down(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /// Completes once shutdown starts
+    ///
+    /// Fits `with_graceful_shutdown` of axum and `start_with_shutdown` of
+    /// rf-queue workers and rf-scheduler.
+    pub async fn cancelled(mut self) {
+        // A dropped controller shuts everything down as well
+        let _ = self.rx.wait_for(|stopping| *stopping).await;
+    }
+}
+
+/// Completes on SIGTERM or SIGINT (Ctrl+C)
+///
+/// Kubernetes sends SIGTERM when a pod is stopped.
+pub async fn terminate() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = sigterm.recv() => tracing::info!("Received SIGTERM"),
+                    _ = tokio::signal::ctrl_c() => tracing::info!("Received SIGINT"),
+                }
+                return;
+            }
+            Err(e) => tracing::warn!(error = %e, "Failed to listen for SIGTERM"),
+        }
+    }
+
+    let _ = tokio::signal::ctrl_c().await;
+    tracing::info!("Received SIGINT");
+}
@@ -20,7 +20,30 @@ pub enum StorageError {
     #[error("Invalid path: {0}")]
     InvalidPath(String),
 
+    /// No disk with this name
+    #[error("Unknown disk: {0}")]
+    UnknownDisk(String),
+
+    /// Operation not supported by the backend
+    #[error("Not supported by this storage backend: {0}")]
+    Unsupported(String),
+
+    /// Signed URL rejected
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
     /// Storage error
     #[error("Storage error: {0}")]
     Other(String),
 }
+
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(StorageError, {
+    Self::FileNotFound(_) => (NOT_FOUND, "storage.file_not_found"),
+    Self::InvalidPath(_) => (BAD_REQUEST, "storage.invalid_path"),
+    Self::InvalidSignature(_) => (FORBIDDEN, "storage.invalid_signature"),
+    Self::IoError(_) => (INTERNAL_SERVER_ERROR, "storage.io"),
+    Self::UnknownDisk(_) => (INTERNAL_SERVER_ERROR, "storage.unknown_disk"),
+    Self::Unsupported(_) => (INTERNAL_SERVER_ERROR, "storage.unsupported"),
+    Self::Other(_) => (INTERNAL_SERVER_ERROR, "storage.internal"),
+});
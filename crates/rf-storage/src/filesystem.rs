@@ -0,0 +1,128 @@
+//! Filesystem trait definition
+
+use crate::{StorageError, StorageResult};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::pin::Pin;
+use std::time::Duration;
+
+/// Chunks of a file streamed into [`Filesystem::put_stream`] or out of
+/// [`Filesystem::read_stream`]
+pub type ByteStream<'a> = Pin<Box<dyn Stream<Item = std::io::Result<Bytes>> + Send + 'a>>;
+
+/// Who may access a file through its public URL
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// Readable by anyone with the URL
+    Public,
+
+    /// Only reachable through temporary URLs or the application
+    #[default]
+    Private,
+}
+
+/// Storage backend, a disk of the [`Storage`](crate::Storage) facade
+#[async_trait]
+pub trait Filesystem: Send + Sync {
+    /// Store file at path with contents
+    async fn put(&self, path: &str, contents: Vec<u8>) -> Result<(), StorageError>;
+
+    /// Store the contents of a local file at path
+    async fn put_file(&self, path: &str, source: &Path) -> Result<(), StorageError> {
+        let contents = tokio::fs::read(source).await?;
+        self.put(path, contents).await
+    }
+
+    /// Store a stream of chunks at path, returning the number of bytes
+    ///
+    /// If the stream fails, nothing is stored and its error is returned as
+    /// [`StorageError::IoError`]. The default implementation buffers the whole
+    /// stream; backends write chunks as they arrive.
+    async fn put_stream(&self, path: &str, mut stream: ByteStream<'_>) -> StorageResult<u64> {
+        let mut contents = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            contents.extend_from_slice(&chunk?);
+        }
+        let size = contents.len() as u64;
+        self.put(path, contents).await?;
+        Ok(size)
+    }
+
+    /// Store file at path with the given visibility
+    async fn put_with_visibility(
+        &self,
+        path: &str,
+        contents: Vec<u8>,
+        visibility: Visibility,
+    ) -> StorageResult<()> {
+        self.put(path, contents).await?;
+        self.set_visibility(path, visibility).await
+    }
+
+    /// Get file contents
+    async fn get(&self, path: &str) -> Result<Vec<u8>, StorageError>;
+
+    /// Read file contents as a stream of chunks
+    ///
+    /// The default implementation reads the whole file first; backends
+    /// stream it from the source.
+    async fn read_stream(&self, path: &str) -> StorageResult<ByteStream<'static>> {
+        let contents = self.get(path).await?;
+        Ok(Box::pin(futures::stream::once(async move {
+            Ok(Bytes::from(contents))
+        })))
+    }
+
+    /// Delete file
+    async fn delete(&self, path: &str) -> Result<(), StorageError>;
+
+    /// Check if file exists
+    async fn exists(&self, path: &str) -> Result<bool, StorageError>;
+
+    /// Get file size in bytes
+    async fn size(&self, path: &str) -> Result<u64, StorageError>;
+
+    /// List files in directory (with prefix)
+    async fn list(&self, path: &str) -> Result<Vec<String>, StorageError>;
+
+    /// Get public URL for file
+    fn url(&self, path: &str) -> String;
+
+    /// Get a URL granting access to a private file for `expires_in`
+    ///
+    /// Backends that can't sign URLs return [`StorageError::Unsupported`].
+    fn temporary_url(&self, path: &str, expires_in: Duration) -> StorageResult<String> {
+        let _ = expires_in;
+        Err(StorageError::Unsupported(format!(
+            "temporary URLs for '{}'",
+            path
+        )))
+    }
+
+    /// Get the visibility of a file
+    async fn visibility(&self, path: &str) -> StorageResult<Visibility> {
+        Err(StorageError::Unsupported(format!("visibility of '{}'", path)))
+    }
+
+    /// Change the visibility of a file
+    async fn set_visibility(&self, path: &str, visibility: Visibility) -> StorageResult<()> {
+        let _ = visibility;
+        Err(StorageError::Unsupported(format!("visibility of '{}'", path)))
+    }
+
+    /// Copy file
+    async fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        let contents = self.get(from).await?;
+        self.put(to, contents).await
+    }
+
+    /// Move file
+    async fn move_file(&self, from: &str, to: &str) -> Result<(), StorageError> {
+        self.copy(from, to).await?;
+        self.delete(from).await
+    }
+}
@@ -4,18 +4,20 @@
 //!
 //! # Features
 //!
-//! - Storage trait for backend abstraction
-//! - Local filesystem storage
-//! - In-memory storage for testing
-//! - Async file operations
+//! - [`Storage`] facade with named disks from [`StorageConfig`]
+//! - [`Filesystem`] trait for backend abstraction
+//! - Local filesystem, S3 and in-memory storage for testing
+//! - Streaming reads and writes
+//! - Public and private [`Visibility`]
+//! - Temporary URLs: S3 presigned URLs, signed links for local files
 //!
 //! # Quick Start
 //!
 //! ```
-//! use rf_storage::{MemoryStorage, Storage};
+//! use rf_storage::{Filesystem, MemoryStorage, Storage};
 //!
 //! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-//! let storage = MemoryStorage::new();
+//! let storage = Storage::new("local", MemoryStorage::new());
 //!
 //! // Store file
 //! storage.put("test.txt", b"Hello, World!".to_vec()).await?;
@@ -31,13 +33,17 @@
 //! ```
 
 mod error;
+mod filesystem;
 mod local;
 mod memory;
 mod s3;
+mod signer;
 mod storage;
 
 pub use error::{StorageError, StorageResult};
+pub use filesystem::{ByteStream, Filesystem, Visibility};
 pub use local::LocalStorage;
 pub use memory::MemoryStorage;
-pub use s3::{S3Config, S3Storage};
-pub use storage::Storage;
+pub use s3::{PostPolicy, PresignedPost, S3Config, S3Storage};
+pub use signer::UrlSigner;
+pub use storage::{DiskConfig, Storage, StorageConfig};
@@ -1,18 +1,27 @@
 //! Local filesystem storage backend
 
-use crate::{Storage, StorageError};
+use crate::{ByteStream, Filesystem, StorageError, StorageResult, UrlSigner, Visibility};
 use async_trait::async_trait;
+use bytes::Bytes;
+use futures::StreamExt;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Size of the chunks of [`LocalStorage::read_stream`](Filesystem::read_stream)
+const READ_CHUNK_SIZE: usize = 64 * 1024;
 
 /// Local filesystem storage
 ///
-/// Stores files in the local filesystem with path security.
+/// Stores files in the local filesystem with path security. On Unix,
+/// visibility maps to file permissions: `0644` for public and `0600` for
+/// private files.
 ///
 /// # Example
 ///
 /// ```no_run
-/// use rf_storage::{LocalStorage, Storage};
+/// use rf_storage::{Filesystem, LocalStorage};
 ///
 /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
 /// let storage = LocalStorage::new("./storage", "http://localhost:3000").await?;
@@ -25,6 +34,7 @@ use tokio::fs;
 pub struct LocalStorage {
     root: PathBuf,
     public_url: String,
+    signer: Option<UrlSigner>,
 }
 
 impl LocalStorage {
@@ -45,9 +55,20 @@ impl LocalStorage {
         Ok(Self {
             root,
             public_url: public_url.into(),
+            signer: None,
         })
     }
 
+    /// Enable temporary URLs signed with `signer`
+    ///
+    /// The links point to `{public_url}/storage/{path}` with `expires` and
+    /// `signature` query parameters; serve them with a route that checks
+    /// the signature with the same signer instead of a static file service.
+    pub fn with_signer(mut self, signer: UrlSigner) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
     /// Resolve path and check for security violations
     fn resolve_path(&self, path: &str) -> Result<PathBuf, StorageError> {
         let normalized = path.trim_start_matches('/');
@@ -80,7 +101,7 @@ impl LocalStorage {
 }
 
 #[async_trait]
-impl Storage for LocalStorage {
+impl Filesystem for LocalStorage {
     async fn put(&self, path: &str, contents: Vec<u8>) -> Result<(), StorageError> {
         let full_path = self.resolve_path(path)?;
 
@@ -100,6 +121,58 @@ impl Storage for LocalStorage {
         Ok(())
     }
 
+    async fn put_file(&self, path: &str, source: &Path) -> Result<(), StorageError> {
+        let full_path = self.resolve_path(path)?;
+
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // Copy instead of loading the file into memory
+        fs::copy(source, &full_path).await?;
+
+        tracing::debug!(path = %path, source = ?source, "File copied to local storage");
+
+        Ok(())
+    }
+
+    async fn put_stream(&self, path: &str, mut stream: ByteStream<'_>) -> StorageResult<u64> {
+        let full_path = self.resolve_path(path)?;
+
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // Write next to the target and rename, so readers never see a
+        // partial file
+        let mut partial = full_path.clone().into_os_string();
+        partial.push(format!(".{}.part", uuid::Uuid::new_v4().simple()));
+        let partial = PathBuf::from(partial);
+
+        let mut file = fs::File::create(&partial).await?;
+        let mut size = 0;
+        let written: std::io::Result<()> = async {
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                file.write_all(&chunk).await?;
+                size += chunk.len() as u64;
+            }
+            file.flush().await
+        }
+        .await;
+        drop(file);
+
+        if let Err(e) = written {
+            let _ = fs::remove_file(&partial).await;
+            return Err(e.into());
+        }
+        fs::rename(&partial, &full_path).await?;
+
+        tracing::debug!(path = %path, size, "File streamed to local storage");
+
+        Ok(size)
+    }
+
     async fn get(&self, path: &str) -> Result<Vec<u8>, StorageError> {
         let full_path = self.resolve_path(path)?;
 
@@ -110,6 +183,25 @@ impl Storage for LocalStorage {
         Ok(fs::read(&full_path).await?)
     }
 
+    async fn read_stream(&self, path: &str) -> StorageResult<ByteStream<'static>> {
+        let full_path = self.resolve_path(path)?;
+
+        if !full_path.exists() {
+            return Err(StorageError::FileNotFound(path.into()));
+        }
+
+        let file = fs::File::open(&full_path).await?;
+        Ok(Box::pin(futures::stream::try_unfold(file, |mut file| async move {
+            let mut chunk = vec![0; READ_CHUNK_SIZE];
+            let read = file.read(&mut chunk).await?;
+            if read == 0 {
+                return Ok(None);
+            }
+            chunk.truncate(read);
+            Ok(Some((Bytes::from(chunk), file)))
+        })))
+    }
+
     async fn delete(&self, path: &str) -> Result<(), StorageError> {
         let full_path = self.resolve_path(path)?;
 
@@ -167,6 +259,49 @@ impl Storage for LocalStorage {
             path.trim_start_matches('/')
         )
     }
+
+    fn temporary_url(&self, path: &str, expires_in: Duration) -> StorageResult<String> {
+        let signer = self.signer.as_ref().ok_or_else(|| {
+            StorageError::Unsupported("temporary URLs need LocalStorage::with_signer".into())
+        })?;
+        Ok(format!("{}?{}", self.url(path), signer.sign(path, expires_in)))
+    }
+
+    #[cfg(unix)]
+    async fn visibility(&self, path: &str) -> StorageResult<Visibility> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let full_path = self.resolve_path(path)?;
+
+        if !full_path.exists() {
+            return Err(StorageError::FileNotFound(path.into()));
+        }
+
+        let mode = fs::metadata(&full_path).await?.permissions().mode();
+        Ok(if mode & 0o004 != 0 {
+            Visibility::Public
+        } else {
+            Visibility::Private
+        })
+    }
+
+    #[cfg(unix)]
+    async fn set_visibility(&self, path: &str, visibility: Visibility) -> StorageResult<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let full_path = self.resolve_path(path)?;
+
+        if !full_path.exists() {
+            return Err(StorageError::FileNotFound(path.into()));
+        }
+
+        let mode = match visibility {
+            Visibility::Public => 0o644,
+            Visibility::Private => 0o600,
+        };
+        fs::set_permissions(&full_path, std::fs::Permissions::from_mode(mode)).await?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -190,6 +325,86 @@ mod tests {
         assert_eq!(contents, b"Hello, World!");
     }
 
+    #[tokio::test]
+    async fn test_local_storage_put_file() {
+        let dir = tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path().join("root"), "http://localhost:3000")
+            .await
+            .unwrap();
+        let source = dir.path().join("chunk.bin");
+        fs::write(&source, b"assembled").await.unwrap();
+
+        storage.put_file("videos/a.mp4", &source).await.unwrap();
+        assert_eq!(storage.get("videos/a.mp4").await.unwrap(), b"assembled");
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_put_stream() {
+        let dir = tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path(), "http://localhost:3000")
+            .await
+            .unwrap();
+
+        let chunks = futures::stream::iter(["big ", "file"].map(|c| Ok(bytes::Bytes::from(c))));
+        let size = storage.put_stream("videos/a.mp4", Box::pin(chunks)).await.unwrap();
+        assert_eq!(size, 8);
+        assert_eq!(storage.get("videos/a.mp4").await.unwrap(), b"big file");
+
+        // A broken stream leaves nothing behind
+        let chunks = futures::stream::iter([
+            Ok(bytes::Bytes::from("partial")),
+            Err(std::io::Error::other("connection reset")),
+        ]);
+        assert!(storage.put_stream("videos/b.mp4", Box::pin(chunks)).await.is_err());
+        assert_eq!(storage.list("videos").await.unwrap(), vec!["videos/a.mp4"]);
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_read_stream() {
+        let dir = tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path(), "http://localhost:3000")
+            .await
+            .unwrap();
+        let contents = vec![7u8; READ_CHUNK_SIZE + 10];
+        storage.put("backup.tar", contents.clone()).await.unwrap();
+
+        let chunks: Vec<Bytes> = storage
+            .read_stream("backup.tar")
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks.concat(), contents);
+
+        assert!(matches!(
+            storage.read_stream("missing.tar").await,
+            Err(StorageError::FileNotFound(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_local_storage_visibility() {
+        let dir = tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path(), "http://localhost:3000")
+            .await
+            .unwrap();
+
+        storage
+            .put_with_visibility("invoice.pdf", b"pdf".to_vec(), Visibility::Private)
+            .await
+            .unwrap();
+        assert_eq!(storage.visibility("invoice.pdf").await.unwrap(), Visibility::Private);
+
+        storage
+            .set_visibility("invoice.pdf", Visibility::Public)
+            .await
+            .unwrap();
+        assert_eq!(storage.visibility("invoice.pdf").await.unwrap(), Visibility::Public);
+    }
+
     #[tokio::test]
     async fn test_local_storage_exists() {
         let dir = tempdir().unwrap();
@@ -268,6 +483,25 @@ mod tests {
         assert_eq!(url, "https://example.com/storage/documents/test.pdf");
     }
 
+    #[tokio::test]
+    async fn test_local_storage_temporary_url() {
+        let dir = tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path(), "https://example.com")
+            .await
+            .unwrap();
+        assert!(matches!(
+            storage.temporary_url("private/report.pdf", Duration::from_secs(60)),
+            Err(StorageError::Unsupported(_))
+        ));
+
+        let storage = storage.with_signer(UrlSigner::new("secret"));
+        let url = storage
+            .temporary_url("private/report.pdf", Duration::from_secs(60))
+            .unwrap();
+        assert!(url.starts_with("https://example.com/storage/private/report.pdf?expires="));
+        assert!(url.contains("&signature="));
+    }
+
     #[tokio::test]
     async fn test_local_storage_nested_directories() {
         let dir = tempdir().unwrap();
@@ -1,6 +1,6 @@
 //! In-memory storage backend for testing
 
-use crate::{Storage, StorageError};
+use crate::{Filesystem, StorageError, StorageResult, Visibility};
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -10,7 +10,7 @@ use std::sync::{Arc, Mutex};
 /// # Example
 ///
 /// ```
-/// use rf_storage::{MemoryStorage, Storage};
+/// use rf_storage::{Filesystem, MemoryStorage};
 ///
 /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
 /// let storage = MemoryStorage::new();
@@ -26,22 +26,21 @@ use std::sync::{Arc, Mutex};
 #[derive(Clone)]
 pub struct MemoryStorage {
     files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    visibility: Arc<Mutex<HashMap<String, Visibility>>>,
     public_url: String,
 }
 
 impl MemoryStorage {
     /// Create new memory storage
     pub fn new() -> Self {
-        Self {
-            files: Arc::new(Mutex::new(HashMap::new())),
-            public_url: "http://localhost:3000".into(),
-        }
+        Self::with_url("http://localhost:3000")
     }
 
     /// Create with custom public URL
     pub fn with_url(public_url: impl Into<String>) -> Self {
         Self {
             files: Arc::new(Mutex::new(HashMap::new())),
+            visibility: Arc::new(Mutex::new(HashMap::new())),
             public_url: public_url.into(),
         }
     }
@@ -59,6 +58,7 @@ impl MemoryStorage {
     /// Clear all files
     pub fn clear(&self) {
         self.files.lock().unwrap().clear();
+        self.visibility.lock().unwrap().clear();
     }
 }
 
@@ -69,7 +69,7 @@ impl Default for MemoryStorage {
 }
 
 #[async_trait]
-impl Storage for MemoryStorage {
+impl Filesystem for MemoryStorage {
     async fn put(&self, path: &str, contents: Vec<u8>) -> Result<(), StorageError> {
         let size = contents.len();
         self.files.lock().unwrap().insert(path.to_string(), contents);
@@ -98,6 +98,7 @@ impl Storage for MemoryStorage {
             .unwrap()
             .remove(path)
             .ok_or_else(|| StorageError::FileNotFound(path.to_string()))?;
+        self.visibility.lock().unwrap().remove(path);
 
         tracing::debug!(path = %path, "File deleted from memory");
 
@@ -145,6 +146,25 @@ impl Storage for MemoryStorage {
             path.trim_start_matches('/')
         )
     }
+
+    async fn visibility(&self, path: &str) -> StorageResult<Visibility> {
+        if !self.exists(path).await? {
+            return Err(StorageError::FileNotFound(path.to_string()));
+        }
+        let visibility = self.visibility.lock().unwrap().get(path).copied();
+        Ok(visibility.unwrap_or_default())
+    }
+
+    async fn set_visibility(&self, path: &str, visibility: Visibility) -> StorageResult<()> {
+        if !self.exists(path).await? {
+            return Err(StorageError::FileNotFound(path.to_string()));
+        }
+        self.visibility
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), visibility);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -240,6 +260,24 @@ mod tests {
         assert_eq!(url, "https://example.com/storage/documents/test.pdf");
     }
 
+    #[tokio::test]
+    async fn test_memory_storage_visibility() {
+        let storage = MemoryStorage::new();
+
+        storage
+            .put_with_visibility("avatar.png", b"png".to_vec(), Visibility::Public)
+            .await
+            .unwrap();
+        storage.put("invoice.pdf", b"pdf".to_vec()).await.unwrap();
+
+        assert_eq!(storage.visibility("avatar.png").await.unwrap(), Visibility::Public);
+        assert_eq!(storage.visibility("invoice.pdf").await.unwrap(), Visibility::Private);
+        assert!(storage
+            .set_visibility("missing.txt", Visibility::Public)
+            .await
+            .is_err());
+    }
+
     #[tokio::test]
     async fn test_memory_storage_clear() {
         let storage = MemoryStorage::new();
@@ -0,0 +1,126 @@
+//! Signed, expiring URLs for backends without native presigning
+
+use crate::{StorageError, StorageResult};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signs and verifies temporary file URLs
+///
+/// The signature covers the file path and the expiry time, so a link can't
+/// be reused for another file or extended. Use the same key on every
+/// instance that serves the links.
+///
+/// ```
+/// use rf_storage::UrlSigner;
+/// use std::time::Duration;
+///
+/// let signer = UrlSigner::new("app-key");
+/// let query = signer.sign("invoices/1.pdf", Duration::from_secs(300));
+/// assert!(query.starts_with("expires="));
+/// ```
+#[derive(Clone)]
+pub struct UrlSigner {
+    key: Vec<u8>,
+}
+
+impl UrlSigner {
+    /// Create a signer with a secret key
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        Self {
+            key: key.as_ref().to_vec(),
+        }
+    }
+
+    /// Query string (`expires=…&signature=…`) granting access to `path`
+    /// for `expires_in`
+    pub fn sign(&self, path: &str, expires_in: Duration) -> String {
+        let expires = now() + expires_in.as_secs();
+        format!(
+            "expires={}&signature={}",
+            expires,
+            self.signature(path, expires)
+        )
+    }
+
+    /// Check a signature produced by [`UrlSigner::sign`]
+    pub fn verify(&self, path: &str, expires: u64, signature: &str) -> StorageResult<()> {
+        let signature = hex::decode(signature)
+            .map_err(|_| StorageError::InvalidSignature("malformed signature".into()))?;
+        self.mac(path, expires)
+            .verify_slice(&signature)
+            .map_err(|_| StorageError::InvalidSignature("signature mismatch".into()))?;
+
+        if expires < now() {
+            return Err(StorageError::InvalidSignature("link expired".into()));
+        }
+        Ok(())
+    }
+
+    fn signature(&self, path: &str, expires: u64) -> String {
+        hex::encode(self.mac(path, expires).finalize().into_bytes())
+    }
+
+    fn mac(&self, path: &str, expires: u64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(path.trim_start_matches('/').as_bytes());
+        mac.update(b"\n");
+        mac.update(expires.to_string().as_bytes());
+        mac
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(query: &str) -> (u64, String) {
+        let mut expires = 0;
+        let mut signature = String::new();
+        for pair in query.split('&') {
+            match pair.split_once('=') {
+                Some(("expires", value)) => expires = value.parse().unwrap(),
+                Some(("signature", value)) => signature = value.to_string(),
+                _ => {}
+            }
+        }
+        (expires, signature)
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = UrlSigner::new("secret");
+        let (expires, signature) = parts(&signer.sign("/docs/a.pdf", Duration::from_secs(60)));
+
+        assert!(signer.verify("docs/a.pdf", expires, &signature).is_ok());
+        // Other file, extended expiry, other key
+        assert!(signer.verify("docs/b.pdf", expires, &signature).is_err());
+        assert!(signer
+            .verify("docs/a.pdf", expires + 60, &signature)
+            .is_err());
+        assert!(UrlSigner::new("other")
+            .verify("docs/a.pdf", expires, &signature)
+            .is_err());
+        assert!(signer.verify("docs/a.pdf", expires, "zz").is_err());
+    }
+
+    #[test]
+    fn test_expired() {
+        let signer = UrlSigner::new("secret");
+        let expires = now() - 1;
+        let signature = signer.signature("a.txt", expires);
+        assert!(matches!(
+            signer.verify("a.txt", expires, &signature),
+            Err(StorageError::InvalidSignature(_))
+        ));
+    }
+}
@@ -1,41 +1,363 @@
-//! Storage trait definition
+//! Storage facade with named disks
 
-use crate::StorageError;
+use crate::{
+    ByteStream, Filesystem, LocalStorage, MemoryStorage, S3Config, S3Storage, StorageError,
+    StorageResult, UrlSigner, Visibility,
+};
 use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Configuration of a disk
+#[derive(Clone, Deserialize)]
+#[serde(tag = "driver", rename_all = "lowercase")]
+pub enum DiskConfig {
+    /// [`LocalStorage`] below `root`; a `signing_key` enables temporary URLs
+    Local {
+        root: PathBuf,
+        url: String,
+        #[serde(default)]
+        signing_key: Option<String>,
+    },
+
+    /// [`S3Storage`]
+    S3(S3Config),
+
+    /// [`MemoryStorage`], for tests
+    Memory {
+        #[serde(default = "default_memory_url")]
+        url: String,
+    },
+}
+
+fn default_memory_url() -> String {
+    "http://localhost:3000".to_string()
+}
+
+impl DiskConfig {
+    /// Create the disk
+    pub async fn build(&self) -> StorageResult<Arc<dyn Filesystem>> {
+        Ok(match self {
+            DiskConfig::Local {
+                root,
+                url,
+                signing_key,
+            } => {
+                let storage = LocalStorage::new(root, url.clone()).await?;
+                match signing_key {
+                    Some(key) => Arc::new(storage.with_signer(UrlSigner::new(key))),
+                    None => Arc::new(storage),
+                }
+            }
+            DiskConfig::S3(config) => Arc::new(S3Storage::new(config.clone())),
+            DiskConfig::Memory { url } => Arc::new(MemoryStorage::with_url(url.clone())),
+        })
+    }
+}
+
+/// Named disks and the default one, e.g. from a `[storage]` config section
+///
+/// ```
+/// use rf_storage::StorageConfig;
+///
+/// let config: StorageConfig = serde_json::from_value(serde_json::json!({
+///     "default": "local",
+///     "disks": {
+///         "local": { "driver": "local", "root": "storage/app", "url": "https://example.com" },
+///         "s3": {
+///             "driver": "s3",
+///             "bucket": "uploads",
+///             "region": "eu-central-1",
+///             "access_key": "key",
+///             "secret_key": "secret"
+///         }
+///     }
+/// }))
+/// .unwrap();
+/// ```
+#[derive(Clone, Deserialize)]
+pub struct StorageConfig {
+    /// Name of the default disk
+    pub default: String,
+
+    pub disks: HashMap<String, DiskConfig>,
+}
+
+/// Storage facade over named disks
+///
+/// The facade itself is a [`Filesystem`] working on the default disk, so
+/// `storage.put(..)` writes there and `storage.disk("s3")?.put(..)` to
+/// another disk. Clones share their disks.
+///
+/// # Example
+///
+/// ```
+/// use rf_storage::{Filesystem, MemoryStorage, Storage};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let storage = Storage::new("local", MemoryStorage::new())
+///     .with_disk("backups", MemoryStorage::new());
+///
+/// storage.put("reports/q1.csv", b"id,total".to_vec()).await?;
+/// storage
+///     .copy_between("local", "reports/q1.csv", "backups", "2024/q1.csv")
+///     .await?;
+/// assert!(storage.disk("backups")?.exists("2024/q1.csv").await?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Storage {
+    default: String,
+    disks: Arc<RwLock<HashMap<String, Arc<dyn Filesystem>>>>,
+}
+
+impl Storage {
+    /// Create a facade with `disk` as default disk `name`
+    pub fn new(name: impl Into<String>, disk: impl Filesystem + 'static) -> Self {
+        let name = name.into();
+        let disk: Arc<dyn Filesystem> = Arc::new(disk);
+        Self {
+            disks: Arc::new(RwLock::new(HashMap::from([(name.clone(), disk)]))),
+            default: name,
+        }
+    }
+
+    /// Create the disks of `config`
+    pub async fn from_config(config: &StorageConfig) -> StorageResult<Self> {
+        let mut disks = HashMap::with_capacity(config.disks.len());
+        for (name, disk) in &config.disks {
+            disks.insert(name.clone(), disk.build().await?);
+        }
+        if !disks.contains_key(&config.default) {
+            return Err(StorageError::UnknownDisk(config.default.clone()));
+        }
+
+        Ok(Self {
+            default: config.default.clone(),
+            disks: Arc::new(RwLock::new(disks)),
+        })
+    }
+
+    /// Add disk `name`
+    pub fn with_disk(self, name: impl Into<String>, disk: impl Filesystem + 'static) -> Self {
+        self.register(name, disk);
+        self
+    }
+
+    /// Add disk `name`, replacing an existing one
+    pub fn register(&self, name: impl Into<String>, disk: impl Filesystem + 'static) {
+        self.register_arc(name, Arc::new(disk));
+    }
+
+    /// Add an already shared disk
+    pub fn register_arc(&self, name: impl Into<String>, disk: Arc<dyn Filesystem>) {
+        self.disks
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name.into(), disk);
+    }
+
+    /// Replace disk `name` with an empty memory disk, returned for
+    /// assertions in tests
+    pub fn fake(&self, name: impl Into<String>) -> MemoryStorage {
+        let disk = MemoryStorage::new();
+        self.register(name, disk.clone());
+        disk
+    }
+
+    /// Disk `name`
+    pub fn disk(&self, name: &str) -> StorageResult<Arc<dyn Filesystem>> {
+        self.disks
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(name)
+            .cloned()
+            .ok_or_else(|| StorageError::UnknownDisk(name.to_string()))
+    }
+
+    /// The default disk
+    pub fn default_disk(&self) -> Arc<dyn Filesystem> {
+        // Disks are never removed, so the default one always exists
+        self.disk(&self.default)
+            .expect("the default disk is registered on creation")
+    }
+
+    /// Name of the default disk
+    pub fn default_disk_name(&self) -> &str {
+        &self.default
+    }
+
+    /// Names of all disks, sorted
+    pub fn disk_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .disks
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Stream a file from one disk to another, returning its size
+    pub async fn copy_between(
+        &self,
+        from_disk: &str,
+        from: &str,
+        to_disk: &str,
+        to: &str,
+    ) -> StorageResult<u64> {
+        let stream = self.disk(from_disk)?.read_stream(from).await?;
+        self.disk(to_disk)?.put_stream(to, stream).await
+    }
+}
 
-/// Storage backend trait
 #[async_trait]
-pub trait Storage: Send + Sync {
-    /// Store file at path with contents
-    async fn put(&self, path: &str, contents: Vec<u8>) -> Result<(), StorageError>;
+impl Filesystem for Storage {
+    async fn put(&self, path: &str, contents: Vec<u8>) -> StorageResult<()> {
+        self.default_disk().put(path, contents).await
+    }
+
+    async fn put_file(&self, path: &str, source: &Path) -> StorageResult<()> {
+        self.default_disk().put_file(path, source).await
+    }
+
+    async fn put_stream(&self, path: &str, stream: ByteStream<'_>) -> StorageResult<u64> {
+        self.default_disk().put_stream(path, stream).await
+    }
+
+    async fn put_with_visibility(
+        &self,
+        path: &str,
+        contents: Vec<u8>,
+        visibility: Visibility,
+    ) -> StorageResult<()> {
+        self.default_disk()
+            .put_with_visibility(path, contents, visibility)
+            .await
+    }
 
-    /// Get file contents
-    async fn get(&self, path: &str) -> Result<Vec<u8>, StorageError>;
+    async fn get(&self, path: &str) -> StorageResult<Vec<u8>> {
+        self.default_disk().get(path).await
+    }
 
-    /// Delete file
-    async fn delete(&self, path: &str) -> Result<(), StorageError>;
+    async fn read_stream(&self, path: &str) -> StorageResult<ByteStream<'static>> {
+        self.default_disk().read_stream(path).await
+    }
 
-    /// Check if file exists
-    async fn exists(&self, path: &str) -> Result<bool, StorageError>;
+    async fn delete(&self, path: &str) -> StorageResult<()> {
+        self.default_disk().delete(path).await
+    }
 
-    /// Get file size in bytes
-    async fn size(&self, path: &str) -> Result<u64, StorageError>;
+    async fn exists(&self, path: &str) -> StorageResult<bool> {
+        self.default_disk().exists(path).await
+    }
 
-    /// List files in directory (with prefix)
-    async fn list(&self, path: &str) -> Result<Vec<String>, StorageError>;
+    async fn size(&self, path: &str) -> StorageResult<u64> {
+        self.default_disk().size(path).await
+    }
 
-    /// Get public URL for file
-    fn url(&self, path: &str) -> String;
+    async fn list(&self, path: &str) -> StorageResult<Vec<String>> {
+        self.default_disk().list(path).await
+    }
+
+    fn url(&self, path: &str) -> String {
+        self.default_disk().url(path)
+    }
 
-    /// Copy file
-    async fn copy(&self, from: &str, to: &str) -> Result<(), StorageError> {
-        let contents = self.get(from).await?;
-        self.put(to, contents).await
+    fn temporary_url(&self, path: &str, expires_in: Duration) -> StorageResult<String> {
+        self.default_disk().temporary_url(path, expires_in)
     }
 
-    /// Move file
-    async fn move_file(&self, from: &str, to: &str) -> Result<(), StorageError> {
-        self.copy(from, to).await?;
-        self.delete(from).await
+    async fn visibility(&self, path: &str) -> StorageResult<Visibility> {
+        self.default_disk().visibility(path).await
+    }
+
+    async fn set_visibility(&self, path: &str, visibility: Visibility) -> StorageResult<()> {
+        self.default_disk().set_visibility(path, visibility).await
+    }
+
+    async fn copy(&self, from: &str, to: &str) -> StorageResult<()> {
+        self.default_disk().copy(from, to).await
+    }
+
+    async fn move_file(&self, from: &str, to: &str) -> StorageResult<()> {
+        self.default_disk().move_file(from, to).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_from_config() {
+        let dir = tempdir().unwrap();
+        let config: StorageConfig = serde_json::from_value(serde_json::json!({
+            "default": "local",
+            "disks": {
+                "local": {
+                    "driver": "local",
+                    "root": dir.path(),
+                    "url": "https://example.com",
+                    "signing_key": "secret"
+                },
+                "testing": { "driver": "memory" }
+            }
+        }))
+        .unwrap();
+
+        let storage = Storage::from_config(&config).await.unwrap();
+        assert_eq!(storage.disk_names(), vec!["local", "testing"]);
+        assert_eq!(storage.default_disk_name(), "local");
+
+        storage.put("a.txt", b"local".to_vec()).await.unwrap();
+        assert!(dir.path().join("a.txt").exists());
+        assert!(storage
+            .temporary_url("a.txt", Duration::from_secs(60))
+            .unwrap()
+            .contains("signature="));
+        assert!(!storage
+            .disk("testing")
+            .unwrap()
+            .exists("a.txt")
+            .await
+            .unwrap());
+
+        assert!(matches!(
+            storage.disk("s3"),
+            Err(StorageError::UnknownDisk(_))
+        ));
+
+        let config = StorageConfig {
+            default: "missing".to_string(),
+            disks: config.disks,
+        };
+        assert!(Storage::from_config(&config).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fake_and_copy_between() {
+        let storage = Storage::new("local", MemoryStorage::new());
+        let backups = storage.fake("backups");
+
+        storage.put("db.sql", b"dump".to_vec()).await.unwrap();
+        let size = storage
+            .copy_between("local", "db.sql", "backups", "nightly/db.sql")
+            .await
+            .unwrap();
+
+        assert_eq!(size, 4);
+        assert_eq!(backups.get("nightly/db.sql").await.unwrap(), b"dump");
+        assert!(storage
+            .copy_between("local", "missing.sql", "backups", "x.sql")
+            .await
+            .is_err());
     }
 }
@@ -0,0 +1,152 @@
+//! Spans around rf-cache operations
+
+use async_trait::async_trait;
+use rf_cache::{Cache, CacheResult};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{field, Instrument, Span};
+
+/// Cache running every operation in a client span
+///
+/// Spans record the operation, the key or number of keys, and for reads
+/// whether they hit.
+///
+/// ```
+/// use rf_cache::MemoryCache;
+/// use rf_telemetry::TracedCache;
+///
+/// let cache = TracedCache::new(MemoryCache::new());
+/// ```
+pub struct TracedCache<C> {
+    inner: C,
+}
+
+impl<C: Cache> TracedCache<C> {
+    pub fn new(inner: C) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+fn span(operation: &'static str, key: Option<&str>, keys: Option<usize>) -> Span {
+    tracing::info_span!(
+        "cache",
+        otel.name = %format!("cache {}", operation),
+        otel.kind = "client",
+        cache.operation = operation,
+        cache.key = key,
+        cache.keys = keys,
+        cache.hit = field::Empty,
+        cache.hits = field::Empty,
+    )
+}
+
+#[async_trait]
+impl<C: Cache> Cache for TracedCache<C> {
+    async fn get<T: DeserializeOwned + Send>(&self, key: &str) -> CacheResult<Option<T>> {
+        let span = span("get", Some(key), None);
+        let value = self.inner.get(key).instrument(span.clone()).await?;
+        span.record("cache.hit", value.is_some());
+        Ok(value)
+    }
+
+    async fn set<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<()> {
+        let span = span("set", Some(key), None);
+        self.inner.set(key, value, ttl).instrument(span).await
+    }
+
+    async fn add<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let span = span("add", Some(key), None);
+        self.inner.add(key, value, ttl).instrument(span).await
+    }
+
+    async fn compare_and_set<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+        value: &T,
+        ttl: Duration,
+    ) -> CacheResult<bool> {
+        let span = span("compare_and_set", Some(key), None);
+        self.inner
+            .compare_and_set(key, current, value, ttl)
+            .instrument(span)
+            .await
+    }
+
+    async fn compare_and_delete<T: Serialize + Sync>(
+        &self,
+        key: &str,
+        current: &T,
+    ) -> CacheResult<bool> {
+        let span = span("compare_and_delete", Some(key), None);
+        self.inner
+            .compare_and_delete(key, current)
+            .instrument(span)
+            .await
+    }
+
+    async fn delete(&self, key: &str) -> CacheResult<()> {
+        let span = span("delete", Some(key), None);
+        self.inner.delete(key).instrument(span).await
+    }
+
+    async fn exists(&self, key: &str) -> CacheResult<bool> {
+        let span = span("exists", Some(key), None);
+        let exists = self.inner.exists(key).instrument(span.clone()).await?;
+        span.record("cache.hit", exists);
+        Ok(exists)
+    }
+
+    async fn ttl(&self, key: &str) -> CacheResult<Option<Duration>> {
+        let span = span("ttl", Some(key), None);
+        let ttl = self.inner.ttl(key).instrument(span.clone()).await?;
+        span.record("cache.hit", ttl.is_some());
+        Ok(ttl)
+    }
+
+    async fn flush(&self) -> CacheResult<()> {
+        self.inner
+            .flush()
+            .instrument(span("flush", None, None))
+            .await
+    }
+
+    async fn get_many<T: DeserializeOwned + Send>(
+        &self,
+        keys: &[&str],
+    ) -> CacheResult<HashMap<String, T>> {
+        let span = span("get_many", None, Some(keys.len()));
+        let values = self.inner.get_many(keys).instrument(span.clone()).await?;
+        span.record("cache.hits", values.len());
+        Ok(values)
+    }
+
+    async fn set_many<T: Serialize + Sync>(
+        &self,
+        items: &[(&str, T)],
+        ttl: Duration,
+    ) -> CacheResult<()> {
+        let span = span("set_many", None, Some(items.len()));
+        self.inner.set_many(items, ttl).instrument(span).await
+    }
+
+    async fn delete_many(&self, keys: &[&str]) -> CacheResult<()> {
+        let span = span("delete_many", None, Some(keys.len()));
+        self.inner.delete_many(keys).instrument(span).await
+    }
+}
@@ -0,0 +1,14 @@
+//! Telemetry errors
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TelemetryError {
+    #[error("Failed to set up the OTLP exporter: {0}")]
+    Exporter(#[from] opentelemetry::trace::TraceError),
+
+    #[error("Failed to install the tracing subscriber: {0}")]
+    Subscriber(String),
+}
+
+pub type TelemetryResult<T> = Result<T, TelemetryError>;
@@ -0,0 +1,163 @@
+//! OTLP exporter and tracing subscriber setup
+
+use crate::{TelemetryError, TelemetryResult};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{self, Sampler};
+use opentelemetry_sdk::{runtime, Resource};
+use std::time::Duration;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Where and how spans are exported
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    service_name: String,
+    endpoint: String,
+    sample_ratio: f64,
+    timeout: Duration,
+    filter: String,
+    attributes: Vec<(String, String)>,
+}
+
+impl TelemetryConfig {
+    /// Export spans of `service_name` to a collector on
+    /// `http://localhost:4317`, sampling every trace
+    pub fn new(service_name: impl Into<String>) -> Self {
+        Self {
+            service_name: service_name.into(),
+            endpoint: "http://localhost:4317".to_string(),
+            sample_ratio: 1.0,
+            timeout: Duration::from_secs(10),
+            filter: "info".to_string(),
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Read the standard `OTEL_SERVICE_NAME`,
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT` and `OTEL_TRACES_SAMPLER_ARG` variables
+    /// and `RUST_LOG`, defaulting to `default_service_name`
+    pub fn from_env(default_service_name: &str) -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let mut config =
+            Self::new(var("OTEL_SERVICE_NAME").unwrap_or_else(|| default_service_name.to_string()));
+        if let Some(endpoint) = var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            config = config.endpoint(endpoint);
+        }
+        if let Some(ratio) = var("OTEL_TRACES_SAMPLER_ARG").and_then(|arg| arg.parse().ok()) {
+            config = config.sample_ratio(ratio);
+        }
+        if let Some(filter) = var("RUST_LOG") {
+            config = config.filter(filter);
+        }
+        config
+    }
+
+    /// gRPC endpoint of the OTLP collector
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Share of new traces to sample, between 0 and 1; traces continued
+    /// from a caller follow the caller's decision
+    pub fn sample_ratio(mut self, ratio: f64) -> Self {
+        self.sample_ratio = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Timeout for exporting a batch of spans
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Which spans and events are recorded, in `RUST_LOG` syntax
+    pub fn filter(mut self, filter: impl Into<String>) -> Self {
+        self.filter = filter.into();
+        self
+    }
+
+    /// Resource attribute added to every span, e.g. `deployment.environment`
+    pub fn attribute(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn service_name(&self) -> &str {
+        &self.service_name
+    }
+}
+
+/// Flushes and shuts down the exporter when dropped
+///
+/// Keep it alive in `main` until the application exits.
+#[must_use = "spans are only flushed until the guard is dropped"]
+pub struct TelemetryGuard {
+    _private: (),
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        opentelemetry::global::shutdown_tracer_provider();
+    }
+}
+
+/// Export `tracing` spans over OTLP and print events to stdout
+///
+/// Installs the global tracing subscriber and W3C trace context propagator,
+/// so it can only be called once. Must run inside a Tokio runtime, which
+/// exports the spans in batches.
+///
+/// ```no_run
+/// use rf_telemetry::TelemetryConfig;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let _telemetry = rf_telemetry::init(TelemetryConfig::from_env("shop"))?;
+///     // Serve requests, run workers...
+///     Ok(())
+/// }
+/// ```
+pub fn init(config: TelemetryConfig) -> TelemetryResult<TelemetryGuard> {
+    let mut attributes = vec![KeyValue::new("service.name", config.service_name.clone())];
+    attributes.extend(
+        config
+            .attributes
+            .iter()
+            .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+    );
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio)));
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(&config.endpoint)
+                .with_timeout(config.timeout),
+        )
+        .with_trace_config(
+            trace::config()
+                .with_sampler(sampler)
+                .with_resource(Resource::new(attributes)),
+        )
+        .install_batch(runtime::Tokio)?;
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::new(&config.filter))
+        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()
+        .map_err(|e| TelemetryError::Subscriber(e.to_string()))?;
+
+    tracing::info!(
+        service = %config.service_name,
+        endpoint = %config.endpoint,
+        "Exporting traces over OTLP"
+    );
+    Ok(TelemetryGuard { _private: () })
+}
@@ -0,0 +1,96 @@
+//! Client spans and trace headers for outbound HTTP
+
+use crate::propagation::inject_headers;
+use async_trait::async_trait;
+use rf_http_client::{HttpResult, Request, Response, Transport};
+use tracing::{field, Instrument};
+
+/// Transport sending every request in a client span, with the trace
+/// context in its headers
+///
+/// Retries of a request get a span each, under the caller's span.
+///
+/// ```
+/// use rf_http_client::{HttpClient, HttpClientConfig, ReqwestTransport};
+/// use rf_telemetry::TracedTransport;
+///
+/// let client = HttpClient::new(HttpClientConfig::default())
+///     .transport(TracedTransport::new(ReqwestTransport::default()));
+/// ```
+pub struct TracedTransport<T> {
+    inner: T,
+}
+
+impl<T: Transport> TracedTransport<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl<T: Transport> Transport for TracedTransport<T> {
+    async fn send(&self, mut request: Request) -> HttpResult<Response> {
+        let span = tracing::info_span!(
+            "http.client",
+            otel.name = %request.method,
+            otel.kind = "client",
+            otel.status_code = field::Empty,
+            http.request.method = %request.method,
+            server.address = request.url.host_str(),
+            url.full = %redacted_url(&request),
+            http.response.status_code = field::Empty,
+        );
+        inject_headers(&span, &mut request.headers);
+
+        let result = self.inner.send(request).instrument(span.clone()).await;
+        match &result {
+            Ok(response) => {
+                span.record("http.response.status_code", response.status().as_u16());
+                if response.status().is_server_error() {
+                    span.record("otel.status_code", "ERROR");
+                }
+            }
+            Err(_) => {
+                span.record("otel.status_code", "ERROR");
+            }
+        }
+        result
+    }
+}
+
+/// URL without credentials and query string, which may hold secrets
+fn redacted_url(request: &Request) -> String {
+    let mut url = request.url.clone();
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url.set_query(None);
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use rf_http_client::{FakeResponse, HttpClient, HttpClientConfig, HttpFake};
+
+    #[tokio::test]
+    async fn test_sends_trace_context() {
+        let _guard = tracing::subscriber::set_default(testing::subscriber());
+        let fake = HttpFake::new().get("*", FakeResponse::json(200, serde_json::json!({})));
+        let client = HttpClient::new(HttpClientConfig::default())
+            .transport(TracedTransport::new(fake.clone()));
+
+        client
+            .get("https://api.example.com/users?token=secret")
+            .send()
+            .instrument(tracing::info_span!("request"))
+            .await
+            .unwrap();
+        fake.assert_sent(|r| {
+            r.headers["traceparent"]
+                .to_str()
+                .unwrap()
+                .starts_with("00-")
+        });
+    }
+}
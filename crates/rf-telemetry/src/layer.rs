@@ -0,0 +1,128 @@
+//! Server span per request
+
+use crate::propagation::set_parent_from_headers;
+use axum::extract::{MatchedPath, Request};
+use axum::response::Response;
+use rf_middleware::{Middleware, MiddlewareService, Next};
+use tower::Layer;
+use tracing::{field, Instrument};
+
+/// Runs each request in an `http.request` server span
+///
+/// The span is named after the method and route template, e.g.
+/// `GET /users/{id}`, so requests to the same route group together, and
+/// continues the trace of a caller sending a `traceparent` header.
+/// Responses with a 5xx status mark the span as failed.
+///
+/// Add it with [`Router::layer`](axum::Router::layer) so the route
+/// template is known; requests matching no route are named after their
+/// method only.
+///
+/// ```
+/// use axum::{routing::get, Router};
+/// use rf_telemetry::TraceLayer;
+///
+/// let app: Router = Router::new()
+///     .route("/users/{id}", get(|| async { "user" }))
+///     .layer(TraceLayer::new());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TraceLayer {
+    _private: (),
+}
+
+impl TraceLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Middleware for TraceLayer {
+    async fn handle(self, req: Request, next: Next) -> Response {
+        let route = req
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|path| path.as_str().to_string());
+        let method = req.method().as_str().to_string();
+        let name = match &route {
+            Some(route) => format!("{} {}", method, route),
+            None => method.clone(),
+        };
+        let span = tracing::info_span!(
+            "http.request",
+            otel.name = %name,
+            otel.kind = "server",
+            otel.status_code = field::Empty,
+            http.request.method = %method,
+            http.route = route.as_deref(),
+            url.path = %req.uri().path(),
+            http.response.status_code = field::Empty,
+        );
+        set_parent_from_headers(&span, req.headers());
+
+        let response = next.run(req).instrument(span.clone()).await;
+        let status = response.status();
+        span.record("http.response.status_code", status.as_u16());
+        if status.is_server_error() {
+            span.record("otel.status_code", "ERROR");
+        }
+        response
+    }
+}
+
+impl<S> Layer<S> for TraceLayer {
+    type Service = TraceService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+/// Service created by [`TraceLayer`]
+pub type TraceService<S> = MiddlewareService<TraceLayer, S>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use axum::Router;
+    use opentelemetry::trace::TraceContextExt;
+    use tower::ServiceExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    async fn trace_id() -> String {
+        let context = tracing::Span::current().context();
+        context.span().span_context().trace_id().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_continues_trace() {
+        let _guard = tracing::subscriber::set_default(testing::subscriber());
+        let app = Router::new()
+            .route("/users/{id}", get(trace_id))
+            .route("/fail", get(|| async { StatusCode::INTERNAL_SERVER_ERROR }))
+            .layer(TraceLayer::new());
+
+        let request = Request::get("/users/7")
+            .header(
+                "traceparent",
+                format!("00-{}-00f067aa0ba902b7-01", TRACE_ID),
+            )
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        assert_eq!(body, TRACE_ID);
+
+        let request = Request::get("/fail").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
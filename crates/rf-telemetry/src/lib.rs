@@ -0,0 +1,92 @@
+//! OpenTelemetry tracing for RustForge
+//!
+//! Exports `tracing` spans over OTLP and carries the trace context across
+//! the framework's async boundaries, so a request can be followed from the
+//! HTTP handler into the jobs, cache calls, outbound requests and
+//! notifications it causes.
+//!
+//! # Features
+//!
+//! - OTLP exporter and subscriber setup, see [`init`]
+//! - Server spans per request named after the route template, continuing
+//!   the caller's W3C trace context, see [`TraceLayer`]
+//! - Trace context propagated into rf-queue jobs (feature `queue`)
+//! - Spans around rf-cache operations (feature `cache`)
+//! - Client spans and `traceparent` headers on rf-http-client requests
+//!   (feature `http-client`)
+//! - Spans around rf-notifications sends (feature `notifications`)
+//!
+//! # Example
+//!
+//! ```no_run
+//! use axum::{routing::get, Router};
+//! use rf_telemetry::{TelemetryConfig, TraceLayer};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let _telemetry = rf_telemetry::init(
+//!         TelemetryConfig::new("shop")
+//!             .endpoint("http://collector:4317")
+//!             .sample_ratio(0.25),
+//!     )?;
+//!
+//!     let app: Router = Router::new()
+//!         .route("/orders/{id}", get(|| async { "order" }))
+//!         .layer(TraceLayer::new());
+//!     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
+//!     axum::serve(listener, app).await?;
+//!     Ok(())
+//! }
+//! ```
+
+mod error;
+mod exporter;
+mod layer;
+pub mod propagation;
+
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "http-client")]
+mod http_client;
+#[cfg(feature = "notifications")]
+mod notifications;
+#[cfg(feature = "queue")]
+mod queue;
+
+pub use error::{TelemetryError, TelemetryResult};
+pub use exporter::{init, TelemetryConfig, TelemetryGuard};
+pub use layer::{TraceLayer, TraceService};
+
+#[cfg(feature = "cache")]
+pub use cache::TracedCache;
+#[cfg(feature = "http-client")]
+pub use http_client::TracedTransport;
+#[cfg(feature = "notifications")]
+pub use notifications::{NotificationTelemetryExt, TracedChannel};
+#[cfg(feature = "queue")]
+pub use queue::{job_span, TracedQueue, WorkerTelemetryExt};
+
+pub use opentelemetry;
+pub use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+#[cfg(test)]
+mod testing {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use std::sync::OnceLock;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Subscriber giving spans trace IDs, without exporting them
+    pub fn subscriber() -> impl tracing::Subscriber + Send + Sync {
+        // Tracers only hold a weak reference to their provider
+        static PROVIDER: OnceLock<TracerProvider> = OnceLock::new();
+        let tracer = PROVIDER
+            .get_or_init(|| TracerProvider::builder().build())
+            .tracer("rf-telemetry");
+        tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(tracer))
+    }
+
+    pub fn traced<R>(f: impl FnOnce() -> R) -> R {
+        tracing::subscriber::with_default(subscriber(), f)
+    }
+}
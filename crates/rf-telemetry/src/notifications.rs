@@ -0,0 +1,68 @@
+//! Spans around notification sends
+
+use async_trait::async_trait;
+use rf_notifications::{
+    Channel, ChannelHandler, Notifiable, Notification, NotificationManager, NotificationResult,
+};
+use std::sync::Arc;
+use tracing::{field, Instrument};
+
+/// Channel handler sending every notification in a span
+///
+/// ```
+/// use rf_notifications::{Channel, EmailChannel, NotificationManager};
+/// use rf_telemetry::NotificationTelemetryExt;
+/// use std::sync::Arc;
+///
+/// let mut notifications = NotificationManager::new();
+/// notifications.register_traced_channel(Channel::Email, Arc::new(EmailChannel::new()));
+/// ```
+pub struct TracedChannel {
+    channel: Channel,
+    inner: Arc<dyn ChannelHandler>,
+}
+
+impl TracedChannel {
+    pub fn new(channel: Channel, inner: Arc<dyn ChannelHandler>) -> Self {
+        Self { channel, inner }
+    }
+}
+
+#[async_trait]
+impl ChannelHandler for TracedChannel {
+    async fn send(
+        &self,
+        notification: &dyn Notification,
+        notifiable: &dyn Notifiable,
+    ) -> NotificationResult<()> {
+        let channel = format!("{:?}", self.channel).to_lowercase();
+        let span = tracing::info_span!(
+            "notification.send",
+            otel.name = %format!("notify {}", channel),
+            otel.status_code = field::Empty,
+            notification.channel = %channel,
+            notification.notifiable = %notifiable.id(),
+        );
+        let result = self
+            .inner
+            .send(notification, notifiable)
+            .instrument(span.clone())
+            .await;
+        if result.is_err() {
+            span.record("otel.status_code", "ERROR");
+        }
+        result
+    }
+}
+
+/// Register channels wrapped in a [`TracedChannel`]
+pub trait NotificationTelemetryExt {
+    fn register_traced_channel(&mut self, channel: Channel, handler: Arc<dyn ChannelHandler>);
+}
+
+impl NotificationTelemetryExt for NotificationManager {
+    fn register_traced_channel(&mut self, channel: Channel, handler: Arc<dyn ChannelHandler>) {
+        let traced = TracedChannel::new(channel.clone(), handler);
+        self.register_channel(channel, Arc::new(traced));
+    }
+}
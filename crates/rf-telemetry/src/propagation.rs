@@ -0,0 +1,93 @@
+//! W3C trace context propagation across process and task boundaries
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use std::collections::HashMap;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Trace context of `span` as `traceparent` and `tracestate` entries
+///
+/// Empty if the span isn't exported, e.g. before [`init`](crate::init).
+pub fn inject(span: &Span) -> HashMap<String, String> {
+    let mut carrier = HashMap::new();
+    TraceContextPropagator::new().inject_context(&span.context(), &mut carrier);
+    carrier
+}
+
+/// Make `span` a child of the trace context in `carrier`, if any
+pub fn set_parent(span: &Span, carrier: &HashMap<String, String>) {
+    let context = TraceContextPropagator::new().extract(carrier);
+    span.set_parent(context);
+}
+
+/// Add the trace context of `span` to outgoing `headers`
+pub fn inject_headers(span: &Span, headers: &mut HeaderMap) {
+    TraceContextPropagator::new().inject_context(&span.context(), &mut HeaderInjector(headers));
+}
+
+/// Make `span` a child of the trace context in incoming `headers`, if any
+pub fn set_parent_from_headers(span: &Span, headers: &HeaderMap) {
+    let context = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+    span.set_parent(context);
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::traced;
+    use opentelemetry::trace::TraceContextExt;
+
+    #[test]
+    fn test_round_trip() {
+        traced(|| {
+            let parent = tracing::info_span!("parent");
+            let carrier = inject(&parent);
+            assert!(carrier["traceparent"].starts_with("00-"));
+
+            let mut headers = HeaderMap::new();
+            inject_headers(&parent, &mut headers);
+            assert_eq!(headers["traceparent"], carrier["traceparent"].as_str());
+
+            let trace_id = parent.context().span().span_context().trace_id();
+            let child = tracing::info_span!("child");
+            set_parent(&child, &carrier);
+            assert_eq!(child.context().span().span_context().trace_id(), trace_id);
+
+            let other = tracing::info_span!("other");
+            set_parent_from_headers(&other, &headers);
+            assert_eq!(other.context().span().span_context().trace_id(), trace_id);
+        });
+    }
+
+    #[test]
+    fn test_untraced_span() {
+        assert!(inject(&tracing::info_span!("untraced")).is_empty());
+    }
+}
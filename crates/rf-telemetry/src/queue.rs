@@ -0,0 +1,150 @@
+//! Trace context carried from dispatch into rf-queue jobs
+
+use crate::propagation::{inject, set_parent};
+use async_trait::async_trait;
+use rf_queue::{JobMetadata, Queue, QueueResult, Worker};
+use tracing::Span;
+
+/// Queue adding the current trace context to every pushed job
+///
+/// Workers set up with [`WorkerTelemetryExt::traced`] continue the trace,
+/// so a job shows up under the request that dispatched it.
+///
+/// ```
+/// use rf_queue::MemoryQueue;
+/// use rf_telemetry::TracedQueue;
+/// use std::sync::Arc;
+///
+/// let queue = Arc::new(TracedQueue::new(MemoryQueue::new()));
+/// ```
+pub struct TracedQueue<Q> {
+    inner: Q,
+}
+
+impl<Q: Queue> TracedQueue<Q> {
+    pub fn new(inner: Q) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &Q {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<Q: Queue> Queue for TracedQueue<Q> {
+    async fn push(&self, mut metadata: JobMetadata) -> QueueResult<String> {
+        metadata.headers.extend(inject(&Span::current()));
+        self.inner.push(metadata).await
+    }
+
+    async fn reserve(&self, queue: &str) -> QueueResult<Option<JobMetadata>> {
+        self.inner.reserve(queue).await
+    }
+
+    async fn complete(&self, job_id: &str) -> QueueResult<()> {
+        self.inner.complete(job_id).await
+    }
+
+    async fn fail(&self, job_id: &str, error: &str) -> QueueResult<()> {
+        self.inner.fail(job_id, error).await
+    }
+
+    async fn retry(&self, metadata: JobMetadata) -> QueueResult<()> {
+        self.inner.retry(metadata).await
+    }
+
+    async fn size(&self, queue: &str) -> QueueResult<usize> {
+        self.inner.size(queue).await
+    }
+
+    async fn clear(&self, queue: &str) -> QueueResult<()> {
+        self.inner.clear(queue).await
+    }
+
+    async fn failed(&self, queue: &str) -> QueueResult<Vec<JobMetadata>> {
+        self.inner.failed(queue).await
+    }
+
+    async fn requeue(&self, job_id: &str) -> QueueResult<()> {
+        self.inner.requeue(job_id).await
+    }
+
+    async fn forget(&self, job_id: &str) -> QueueResult<()> {
+        self.inner.forget(job_id).await
+    }
+
+    async fn requeue_all(&self, queue: &str) -> QueueResult<usize> {
+        self.inner.requeue_all(queue).await
+    }
+}
+
+/// Consumer span of a job, continuing the trace it was dispatched in
+pub fn job_span(metadata: &JobMetadata) -> Span {
+    let span = tracing::info_span!(
+        "queue.job",
+        otel.name = %format!("process {}", metadata.job_type),
+        otel.kind = "consumer",
+        messaging.system = "rf-queue",
+        messaging.destination.name = %metadata.queue,
+        messaging.message.id = %metadata.id,
+        job.attempt = metadata.attempts,
+    );
+    set_parent(&span, &metadata.headers);
+    span
+}
+
+/// Run a [`Worker`]'s jobs in [`job_span`]s
+pub trait WorkerTelemetryExt {
+    fn traced(self) -> Self;
+}
+
+impl WorkerTelemetryExt for Worker {
+    fn traced(self) -> Self {
+        self.span(job_span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use opentelemetry::trace::TraceContextExt;
+    use rf_queue::{Job, MemoryQueue, QueueError};
+    use serde::{Deserialize, Serialize};
+    use tracing::Instrument;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    #[derive(Serialize, Deserialize)]
+    struct SendEmail;
+
+    #[async_trait]
+    impl Job for SendEmail {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "send_email"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_job_continues_trace() {
+        let _guard = tracing::subscriber::set_default(testing::subscriber());
+        let queue = TracedQueue::new(MemoryQueue::new());
+        let metadata = JobMetadata::new(&SendEmail).unwrap();
+
+        let request = tracing::info_span!("request");
+        queue
+            .push(metadata)
+            .instrument(request.clone())
+            .await
+            .unwrap();
+        let reserved = queue.reserve("default").await.unwrap().unwrap();
+        assert!(reserved.headers.contains_key("traceparent"));
+
+        let trace_id = |span: &Span| span.context().span().span_context().trace_id();
+        assert_eq!(trace_id(&job_span(&reserved)), trace_id(&request));
+    }
+}
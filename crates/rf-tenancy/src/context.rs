@@ -0,0 +1,146 @@
+//! Task-local tenant context
+//!
+//! [`TenantLayer`](crate::TenantLayer) runs each request inside a scope for
+//! its tenant, so repositories, jobs and other services can read the tenant
+//! with [`TenantContext::current`] instead of taking it as a parameter.
+//! Scopes don't cross `tokio::spawn`; use [`TenantContext::spawn`] or
+//! [`TenantContext::propagate`] to carry the tenant into new tasks.
+
+use crate::{Tenant, TenantError, TenantResult};
+use std::future::Future;
+use tokio::task::JoinHandle;
+
+tokio::task_local! {
+    static CURRENT: Tenant;
+}
+
+/// Access to the tenant of the running task
+pub struct TenantContext;
+
+impl TenantContext {
+    /// Tenant of the current scope
+    pub fn current() -> Option<Tenant> {
+        CURRENT.try_with(Tenant::clone).ok()
+    }
+
+    /// Tenant of the current scope, or [`TenantError::Unidentified`]
+    pub fn require() -> TenantResult<Tenant> {
+        Self::current().ok_or(TenantError::Unidentified)
+    }
+
+    /// Call `f` with the current tenant without cloning it
+    pub fn with<R>(f: impl FnOnce(&Tenant) -> R) -> Option<R> {
+        CURRENT.try_with(f).ok()
+    }
+
+    /// Whether a tenant scope is active
+    pub fn is_set() -> bool {
+        CURRENT.try_with(|_| ()).is_ok()
+    }
+
+    /// Run `fut` with `tenant` as the current tenant
+    pub async fn scope<F: Future>(tenant: Tenant, fut: F) -> F::Output {
+        CURRENT.scope(tenant, fut).await
+    }
+
+    /// Run a synchronous closure with `tenant` as the current tenant
+    pub fn sync_scope<R>(tenant: Tenant, f: impl FnOnce() -> R) -> R {
+        CURRENT.sync_scope(tenant, f)
+    }
+
+    /// Bind `fut` to the tenant of the caller's scope, if any
+    ///
+    /// The tenant is captured when this is called, not when `fut` is first
+    /// polled, so the result can be handed to another task or executor.
+    pub fn propagate<F: Future>(fut: F) -> impl Future<Output = F::Output> {
+        let tenant = Self::current();
+        async move {
+            match tenant {
+                Some(tenant) => CURRENT.scope(tenant, fut).await,
+                None => fut.await,
+            }
+        }
+    }
+
+    /// `tokio::spawn` keeping the current tenant
+    pub fn spawn<F>(fut: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        tokio::spawn(Self::propagate(fut))
+    }
+
+    /// `tokio::task::spawn_blocking` keeping the current tenant
+    pub fn spawn_blocking<F, R>(f: F) -> JoinHandle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let tenant = Self::current();
+        tokio::task::spawn_blocking(move || match tenant {
+            Some(tenant) => CURRENT.sync_scope(tenant, f),
+            None => f(),
+        })
+    }
+}
+
+/// Run `fut` with `tenant` as the current tenant
+///
+/// Shorthand for [`TenantContext::scope`].
+pub async fn with_tenant<F: Future>(tenant: Tenant, fut: F) -> F::Output {
+    TenantContext::scope(tenant, fut).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn current_id() -> Option<String> {
+        TenantContext::with(|tenant| tenant.id().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_scope() {
+        assert!(TenantContext::current().is_none());
+        assert!(TenantContext::require().is_err());
+
+        let id = with_tenant(Tenant::new("acme", "Acme"), async {
+            assert!(TenantContext::is_set());
+            // Nested scopes shadow the outer one
+            let inner = with_tenant(Tenant::new("globex", "Globex"), async { current_id() }).await;
+            assert_eq!(inner.as_deref(), Some("globex"));
+            TenantContext::require().unwrap().id().to_string()
+        })
+        .await;
+
+        assert_eq!(id, "acme");
+        assert!(!TenantContext::is_set());
+    }
+
+    #[tokio::test]
+    async fn test_spawned_tasks() {
+        with_tenant(Tenant::new("acme", "Acme"), async {
+            // Plain tokio::spawn loses the scope
+            let lost = tokio::spawn(async { current_id() }).await.unwrap();
+            assert_eq!(lost, None);
+
+            let kept = TenantContext::spawn(async { current_id() }).await.unwrap();
+            assert_eq!(kept.as_deref(), Some("acme"));
+
+            let blocking = TenantContext::spawn_blocking(current_id).await.unwrap();
+            assert_eq!(blocking.as_deref(), Some("acme"));
+        })
+        .await;
+
+        // Without a scope there is nothing to propagate
+        let none = TenantContext::spawn(async { current_id() }).await.unwrap();
+        assert_eq!(none, None);
+    }
+
+    #[test]
+    fn test_sync_scope() {
+        let id = TenantContext::sync_scope(Tenant::new("acme", "Acme"), current_id);
+        assert_eq!(id.as_deref(), Some("acme"));
+    }
+}
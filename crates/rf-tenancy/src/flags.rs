@@ -0,0 +1,119 @@
+//! Tenant-aware feature flag evaluation
+//!
+//! A tenant can pin a flag on or off with a `features.<flag>` setting; flags
+//! without an override are evaluated by rf-feature-flags with the tenant as
+//! the targeting context (group `tenant:<id>`).
+
+use crate::{Tenant, TenantContext};
+use async_trait::async_trait;
+use rf_feature_flags::{FeatureFlagResult, FeatureFlags, FlagContext};
+
+/// Feature flag evaluation layered under tenant overrides
+#[async_trait]
+pub trait TenantFlags {
+    /// Evaluate `flag` for `tenant`, honouring its override
+    async fn enabled_for_tenant(
+        &self,
+        flag: &str,
+        tenant: &Tenant,
+        context: FlagContext,
+    ) -> FeatureFlagResult<bool>;
+
+    /// Evaluate `flag` for the tenant of the current [`TenantContext`]
+    ///
+    /// Without a tenant scope the flag is evaluated for `context` as is.
+    async fn enabled_for_current_tenant(
+        &self,
+        flag: &str,
+        context: FlagContext,
+    ) -> FeatureFlagResult<bool>;
+}
+
+#[async_trait]
+impl TenantFlags for FeatureFlags {
+    async fn enabled_for_tenant(
+        &self,
+        flag: &str,
+        tenant: &Tenant,
+        context: FlagContext,
+    ) -> FeatureFlagResult<bool> {
+        if let Some(enabled) = tenant.settings().flag_override(flag) {
+            return Ok(enabled);
+        }
+        self.evaluate(flag, &context.tenant(tenant.id())).await
+    }
+
+    async fn enabled_for_current_tenant(
+        &self,
+        flag: &str,
+        context: FlagContext,
+    ) -> FeatureFlagResult<bool> {
+        match TenantContext::current() {
+            Some(tenant) => self.enabled_for_tenant(flag, &tenant, context).await,
+            None => self.evaluate(flag, &context).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_tenant, TenantSettings};
+    use rf_feature_flags::FlagConfig;
+
+    #[tokio::test]
+    async fn test_tenant_overrides() {
+        let flags = FeatureFlags::new();
+        flags
+            .set_config(FlagConfig::new("new_ui").for_groups(vec!["tenant:acme".to_string()]))
+            .await
+            .unwrap();
+
+        let acme = Tenant::new("acme", "Acme");
+        let globex = Tenant::new("globex", "Globex");
+        let pinned = Tenant::new("initech", "Initech")
+            .with_settings(TenantSettings::new().set("features.new_ui", true));
+        let opted_out = Tenant::new("acme", "Acme")
+            .with_settings(TenantSettings::new().set("features.new_ui", false));
+
+        let ctx = FlagContext::new;
+        assert!(flags
+            .enabled_for_tenant("new_ui", &acme, ctx())
+            .await
+            .unwrap());
+        assert!(!flags
+            .enabled_for_tenant("new_ui", &globex, ctx())
+            .await
+            .unwrap());
+        assert!(flags
+            .enabled_for_tenant("new_ui", &pinned, ctx())
+            .await
+            .unwrap());
+        assert!(!flags
+            .enabled_for_tenant("new_ui", &opted_out, ctx())
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_current_tenant() {
+        let flags = FeatureFlags::new();
+        flags
+            .enable_for_groups("beta", vec!["tenant:acme".to_string()])
+            .await
+            .unwrap();
+
+        assert!(!flags
+            .enabled_for_current_tenant("beta", FlagContext::new())
+            .await
+            .unwrap());
+        let enabled = with_tenant(Tenant::new("acme", "Acme"), async {
+            flags
+                .enabled_for_current_tenant("beta", FlagContext::new())
+                .await
+                .unwrap()
+        })
+        .await;
+        assert!(enabled);
+    }
+}
@@ -5,10 +5,19 @@
 //! ## Features
 //!
 //! - **Tenant Identification**: Domain, subdomain, header-based
-//! - **Tenant Context**: Request-scoped tenant information
-//! - **Tenant Middleware**: Automatic tenant detection
-//! - **Tenant Scoping**: Query-level tenant filtering
-//! - **Cross-tenant Prevention**: Automatic isolation
+//! - **Tenant Context**: Task-local current tenant via [`TenantContext`]
+//! - **Tenant Middleware**: Identification once per request, with a
+//!   configurable fallback for unknown tenants
+//! - **Tenant Scoping**: Query-level tenant filtering for sqlx (feature
+//!   `sqlx`) and SeaORM (feature `sea-orm`)
+//! - **Cross-tenant Prevention**: [`TenantGuard`] catches unscoped queries on
+//!   tenant-owned tables
+//! - **Tenant Settings**: Typed per-tenant settings layered over global
+//!   defaults, with feature flag overrides (feature `feature-flags`)
+//! - **Tenant Lifecycle**: Provisioning, suspension and (soft) deletion with
+//!   lifecycle events via [`TenantManager`]
+//! - **Central Registry**: Tenant definitions in a central store, synced to
+//!   every instance by [`TenantRegistry`]
 //!
 //! ## Quick Start
 //!
@@ -21,20 +30,53 @@
 //! }
 //!
 //! # async fn example() {
-//! let app = Router::new()
+//! let tenants = InMemoryTenantResolver::new();
+//! tenants.add_tenant(Tenant::with_domain("acme", "Acme", "acme.example.com")).await;
+//!
+//! let app: Router = Router::new()
 //!     .route("/", get(handler))
-//!     .layer(TenantLayer::by_domain());
+//!     .layer(TenantLayer::by_domain(tenants));
 //! # }
 //! ```
 
+mod context;
+#[cfg(feature = "feature-flags")]
+mod flags;
+mod manager;
+mod middleware;
+mod registry;
+mod scope;
+#[cfg(feature = "sea-orm")]
+pub mod sea;
+mod settings;
+#[cfg(feature = "sqlx")]
+pub mod sql;
+
+pub use context::{with_tenant, TenantContext};
+#[cfg(feature = "feature-flags")]
+pub use flags::TenantFlags;
+pub use manager::{
+    SuspendReason, TenantEvent, TenantManager, TenantProvisioner, TenantStatus, TenantStore,
+};
+pub use middleware::{TenantFallback, TenantLayer, TenantService};
+#[cfg(feature = "http-registry")]
+pub use registry::HttpTenantSource;
+#[cfg(feature = "sqlx")]
+pub use registry::PgTenantStore;
+pub use registry::{tenant_registry_router, TenantRegistry, TenantSnapshot, TenantSource};
+pub use scope::{tenant_column, GuardMode, TenantGuard, TENANT_COLUMN};
+pub use settings::{global_settings, install_global_settings, TenantSetting, TenantSettings};
+
 use async_trait::async_trait;
 use axum::{
-    extract::{FromRef, FromRequestParts},
     http::{header, request::Parts, StatusCode},
     response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 use thiserror::Error;
 use tokio::sync::RwLock;
 
@@ -44,6 +86,9 @@ pub enum TenantError {
     #[error("Tenant not found")]
     NotFound,
 
+    #[error("No tenant identified")]
+    Unidentified,
+
     #[error("Invalid tenant identifier: {0}")]
     InvalidIdentifier(String),
 
@@ -52,17 +97,45 @@ pub enum TenantError {
 
     #[error("Tenant identification failed: {0}")]
     IdentificationFailed(String),
+
+    #[error("Unscoped query: {0}")]
+    UnscopedQuery(String),
+
+    #[error("Tenant is suspended")]
+    Suspended(SuspendReason),
+
+    #[error("Tenant already exists: {0}")]
+    AlreadyExists(String),
+
+    #[error("Invalid tenant state transition: {0}")]
+    InvalidTransition(String),
+
+    #[error("Tenant provisioning failed: {0}")]
+    Provisioning(String),
+
+    #[error("Invalid tenant setting: {0}")]
+    InvalidSetting(String),
+
+    #[error("Tenant registry error: {0}")]
+    Registry(String),
 }
 
 impl IntoResponse for TenantError {
     fn into_response(self) -> Response {
         let (status, message) = match self {
             TenantError::NotFound => (StatusCode::NOT_FOUND, self.to_string()),
+            TenantError::Unidentified => (StatusCode::BAD_REQUEST, self.to_string()),
             TenantError::InvalidIdentifier(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             TenantError::CrossTenantAccess => (StatusCode::FORBIDDEN, self.to_string()),
-            TenantError::IdentificationFailed(_) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, self.to_string())
+            TenantError::Suspended(reason) => (reason.status_code(), self.to_string()),
+            TenantError::AlreadyExists(_) | TenantError::InvalidTransition(_) => {
+                (StatusCode::CONFLICT, self.to_string())
             }
+            TenantError::IdentificationFailed(_)
+            | TenantError::UnscopedQuery(_)
+            | TenantError::Provisioning(_)
+            | TenantError::InvalidSetting(_)
+            | TenantError::Registry(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
         };
 
         (status, message).into_response()
@@ -72,12 +145,59 @@ impl IntoResponse for TenantError {
 /// Result type for tenant operations
 pub type TenantResult<T> = Result<T, TenantError>;
 
+#[cfg(feature = "rf-error")]
+impl rf_error::FrameworkError for TenantError {
+    fn status(&self) -> StatusCode {
+        match self {
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Unidentified | Self::InvalidIdentifier(_) => StatusCode::BAD_REQUEST,
+            Self::CrossTenantAccess => StatusCode::FORBIDDEN,
+            Self::Suspended(reason) => reason.status_code(),
+            Self::AlreadyExists(_) | Self::InvalidTransition(_) => StatusCode::CONFLICT,
+            Self::IdentificationFailed(_)
+            | Self::UnscopedQuery(_)
+            | Self::Provisioning(_)
+            | Self::InvalidSetting(_)
+            | Self::Registry(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound => "tenancy.not_found",
+            Self::Unidentified => "tenancy.unidentified",
+            Self::InvalidIdentifier(_) => "tenancy.invalid_identifier",
+            Self::CrossTenantAccess => "tenancy.cross_tenant_access",
+            Self::Suspended(_) => "tenancy.suspended",
+            Self::AlreadyExists(_) => "tenancy.already_exists",
+            Self::InvalidTransition(_) => "tenancy.invalid_transition",
+            Self::IdentificationFailed(_) => "tenancy.identification_failed",
+            Self::UnscopedQuery(_) => "tenancy.unscoped_query",
+            Self::Provisioning(_) => "tenancy.provisioning",
+            Self::InvalidSetting(_) => "tenancy.invalid_setting",
+            Self::Registry(_) => "tenancy.registry",
+        }
+    }
+
+    fn metadata(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut metadata = serde_json::Map::new();
+        if let Self::Suspended(reason) = self {
+            metadata.insert("reason".into(), serde_json::json!(reason));
+        }
+        metadata
+    }
+}
+
 /// Tenant information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tenant {
     id: String,
     name: String,
     domain: Option<String>,
+    #[serde(default)]
+    status: TenantStatus,
+    #[serde(default)]
+    settings: TenantSettings,
 }
 
 impl Tenant {
@@ -87,6 +207,8 @@ impl Tenant {
             id: id.into(),
             name: name.into(),
             domain: None,
+            status: TenantStatus::Active,
+            settings: TenantSettings::new(),
         }
     }
 
@@ -100,6 +222,8 @@ impl Tenant {
             id: id.into(),
             name: name.into(),
             domain: Some(domain.into()),
+            status: TenantStatus::Active,
+            settings: TenantSettings::new(),
         }
     }
 
@@ -117,6 +241,36 @@ impl Tenant {
     pub fn domain(&self) -> Option<&str> {
         self.domain.as_deref()
     }
+
+    /// Replace the tenant's settings
+    pub fn with_settings(mut self, settings: TenantSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Get tenant settings
+    pub fn settings(&self) -> &TenantSettings {
+        &self.settings
+    }
+
+    /// Resolve a typed setting; see [`TenantSettings::setting`]
+    pub fn setting<T: TenantSetting>(&self) -> TenantResult<T> {
+        self.settings.setting()
+    }
+
+    /// Get tenant lifecycle status
+    pub fn status(&self) -> &TenantStatus {
+        &self.status
+    }
+
+    /// Whether the tenant can serve requests
+    pub fn is_active(&self) -> bool {
+        self.status == TenantStatus::Active
+    }
+
+    pub(crate) fn set_status(&mut self, status: TenantStatus) {
+        self.status = status;
+    }
 }
 
 /// Tenant identifier strategy
@@ -127,13 +281,15 @@ pub trait TenantIdentifier: Send + Sync {
 }
 
 /// Domain-based tenant identification
+///
+/// The port of the `Host` header is ignored.
 #[derive(Clone)]
 pub struct DomainIdentifier {
-    resolver: Arc<InMemoryTenantResolver>,
+    resolver: Arc<dyn TenantResolver>,
 }
 
 impl DomainIdentifier {
-    pub fn new(resolver: InMemoryTenantResolver) -> Self {
+    pub fn new(resolver: impl TenantResolver + 'static) -> Self {
         Self {
             resolver: Arc::new(resolver),
         }
@@ -147,9 +303,10 @@ impl TenantIdentifier for DomainIdentifier {
             .headers
             .get(header::HOST)
             .and_then(|v| v.to_str().ok())
-            .ok_or_else(|| TenantError::IdentificationFailed("No host header".to_string()))?;
+            .ok_or(TenantError::Unidentified)?;
+        let domain = host.rsplit_once(':').map_or(host, |(domain, _)| domain);
 
-        self.resolver.resolve_by_domain(host).await
+        self.resolver.resolve_by_domain(domain).await
     }
 }
 
@@ -157,11 +314,11 @@ impl TenantIdentifier for DomainIdentifier {
 #[derive(Clone)]
 pub struct HeaderIdentifier {
     header_name: String,
-    resolver: Arc<InMemoryTenantResolver>,
+    resolver: Arc<dyn TenantResolver>,
 }
 
 impl HeaderIdentifier {
-    pub fn new(header_name: impl Into<String>, resolver: InMemoryTenantResolver) -> Self {
+    pub fn new(header_name: impl Into<String>, resolver: impl TenantResolver + 'static) -> Self {
         Self {
             header_name: header_name.into(),
             resolver: Arc::new(resolver),
@@ -176,12 +333,7 @@ impl TenantIdentifier for HeaderIdentifier {
         let tenant_id = headers
             .get(&self.header_name)
             .and_then(|v| v.to_str().ok())
-            .ok_or_else(|| {
-                TenantError::IdentificationFailed(format!(
-                    "Header '{}' not found",
-                    self.header_name
-                ))
-            })?;
+            .ok_or(TenantError::Unidentified)?;
 
         self.resolver.resolve_by_id(tenant_id).await
     }
@@ -201,18 +353,22 @@ pub trait TenantResolver: Send + Sync {
 #[derive(Clone)]
 pub struct InMemoryTenantResolver {
     tenants: Arc<RwLock<Vec<Tenant>>>,
+    /// Bumped on every change, used as the [`TenantSource`] version
+    revision: Arc<AtomicU64>,
 }
 
 impl InMemoryTenantResolver {
     pub fn new() -> Self {
         Self {
             tenants: Arc::new(RwLock::new(Vec::new())),
+            revision: Arc::new(AtomicU64::new(0)),
         }
     }
 
     pub async fn add_tenant(&self, tenant: Tenant) {
         let mut tenants = self.tenants.write().await;
         tenants.push(tenant);
+        self.revision.fetch_add(1, Ordering::AcqRel);
     }
 }
 
@@ -243,59 +399,38 @@ impl TenantResolver for InMemoryTenantResolver {
     }
 }
 
-/// Tenant layer for Axum
-#[derive(Clone)]
-pub struct TenantLayer {
-    identifier_type: TenantIdentifierType,
-}
-
-#[derive(Clone)]
-enum TenantIdentifierType {
-    Domain(DomainIdentifier),
-    Header(HeaderIdentifier),
-}
-
-impl TenantLayer {
-    /// Create tenant layer with domain-based identification
-    pub fn by_domain() -> Self {
-        Self {
-            identifier_type: TenantIdentifierType::Domain(DomainIdentifier::new(
-                InMemoryTenantResolver::new(),
-            )),
+#[async_trait]
+impl TenantStore for InMemoryTenantResolver {
+    async fn save(&self, tenant: Tenant) -> TenantResult<()> {
+        let mut tenants = self.tenants.write().await;
+        match tenants.iter_mut().find(|t| t.id() == tenant.id()) {
+            Some(existing) => *existing = tenant,
+            None => tenants.push(tenant),
         }
+        self.revision.fetch_add(1, Ordering::AcqRel);
+        Ok(())
     }
 
-    /// Create tenant layer with header-based identification
-    pub fn by_header(header_name: impl Into<String>) -> Self {
-        Self {
-            identifier_type: TenantIdentifierType::Header(HeaderIdentifier::new(
-                header_name,
-                InMemoryTenantResolver::new(),
-            )),
-        }
+    async fn remove(&self, id: &str) -> TenantResult<()> {
+        let mut tenants = self.tenants.write().await;
+        tenants.retain(|t| t.id() != id);
+        self.revision.fetch_add(1, Ordering::AcqRel);
+        Ok(())
     }
 
-    async fn identify(&self, parts: &Parts) -> TenantResult<Tenant> {
-        match &self.identifier_type {
-            TenantIdentifierType::Domain(id) => id.identify(parts).await,
-            TenantIdentifierType::Header(id) => id.identify(parts).await,
-        }
+    async fn all(&self) -> TenantResult<Vec<Tenant>> {
+        Ok(self.tenants.read().await.clone())
     }
 }
 
-// Note: Axum extractor implementation removed due to complexity with FromRef trait
-// Users can manually call TenantLayer::identify() in their handlers
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use axum::{
-        body::Body,
-        http::{Request, StatusCode},
-        routing::get,
-        Router,
-    };
-    use tower::ServiceExt;
+    use axum::http::{Request, StatusCode};
+
+    fn parts() -> Parts {
+        Request::new(()).into_parts().0
+    }
 
     #[tokio::test]
     async fn test_tenant_creation() {
@@ -316,9 +451,7 @@ mod tests {
     #[tokio::test]
     async fn test_in_memory_resolver_by_id() {
         let resolver = InMemoryTenantResolver::new();
-        resolver
-            .add_tenant(Tenant::new("1", "Tenant 1"))
-            .await;
+        resolver.add_tenant(Tenant::new("1", "Tenant 1")).await;
 
         let tenant = resolver.resolve_by_id("1").await.unwrap();
         assert_eq!(tenant.id(), "1");
@@ -353,7 +486,7 @@ mod tests {
 
         let identifier = HeaderIdentifier::new("X-Tenant-Id", resolver);
 
-        let mut parts = Parts::default();
+        let mut parts = parts();
         parts
             .headers
             .insert("X-Tenant-Id", "tenant-123".parse().unwrap());
@@ -367,7 +500,7 @@ mod tests {
         let resolver = InMemoryTenantResolver::new();
         let identifier = HeaderIdentifier::new("X-Tenant-Id", resolver);
 
-        let parts = Parts::default();
+        let parts = parts();
 
         let result = identifier.identify(&parts).await;
         assert!(result.is_err());
@@ -376,15 +509,9 @@ mod tests {
     #[tokio::test]
     async fn test_multiple_tenants() {
         let resolver = InMemoryTenantResolver::new();
-        resolver
-            .add_tenant(Tenant::new("1", "Tenant 1"))
-            .await;
-        resolver
-            .add_tenant(Tenant::new("2", "Tenant 2"))
-            .await;
-        resolver
-            .add_tenant(Tenant::new("3", "Tenant 3"))
-            .await;
+        resolver.add_tenant(Tenant::new("1", "Tenant 1")).await;
+        resolver.add_tenant(Tenant::new("2", "Tenant 2")).await;
+        resolver.add_tenant(Tenant::new("3", "Tenant 3")).await;
 
         let tenant1 = resolver.resolve_by_id("1").await.unwrap();
         let tenant2 = resolver.resolve_by_id("2").await.unwrap();
@@ -408,14 +535,16 @@ mod tests {
         let err = TenantError::CrossTenantAccess;
         let response = err.into_response();
         assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let err = TenantError::Suspended(SuspendReason::Billing);
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
     }
 
     #[tokio::test]
     async fn test_concurrent_tenant_access() {
         let resolver = InMemoryTenantResolver::new();
-        resolver
-            .add_tenant(Tenant::new("1", "Tenant 1"))
-            .await;
+        resolver.add_tenant(Tenant::new("1", "Tenant 1")).await;
 
         // Simulate concurrent access
         let handles: Vec<_> = (0..10)
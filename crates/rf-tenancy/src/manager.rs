@@ -0,0 +1,443 @@
+//! Tenant lifecycle management
+//!
+//! [`TenantManager`] provisions, suspends, soft-deletes and purges tenants.
+//! Per-tenant resources (schemas, migrations, seed data, caches) are set up
+//! and torn down by [`TenantProvisioner`]s; every transition is published as
+//! a [`TenantEvent`] for billing, audit and other subscribers.
+//!
+//! ```text
+//! create ──▶ Active ◀──▶ Suspended
+//!              │            │
+//!              └──▶ Deleted ◀┘ ──(grace period)──▶ purge
+//!                     │
+//!                  restore ──▶ Active
+//! ```
+
+use crate::{Tenant, TenantError, TenantResolver, TenantResult, TenantSettings};
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::sync::broadcast;
+
+/// Lifecycle state of a tenant
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TenantStatus {
+    #[default]
+    Active,
+    /// Requests are rejected until the tenant is reactivated
+    Suspended { reason: SuspendReason },
+    /// Soft-deleted; data is kept until `purge_at`
+    Deleted { purge_at: SystemTime },
+}
+
+/// Why a tenant was suspended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuspendReason {
+    /// Unpaid invoices; requests get `402 Payment Required`
+    Billing,
+    /// Suspended by an operator; requests get `403 Forbidden`
+    Administrative,
+}
+
+impl SuspendReason {
+    /// Response status for requests to a tenant suspended for this reason
+    pub fn status_code(self) -> StatusCode {
+        match self {
+            Self::Billing => StatusCode::PAYMENT_REQUIRED,
+            Self::Administrative => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+/// Lifecycle transition, published by [`TenantManager`]
+#[derive(Debug, Clone)]
+pub enum TenantEvent {
+    Created(Tenant),
+    Suspended(Tenant, SuspendReason),
+    Reactivated(Tenant),
+    Deleted(Tenant),
+    Restored(Tenant),
+    SettingsUpdated(Tenant),
+    /// The tenant and its data are gone
+    Purged(Tenant),
+}
+
+impl TenantEvent {
+    /// Tenant the event is about
+    pub fn tenant(&self) -> &Tenant {
+        match self {
+            Self::Created(tenant)
+            | Self::Suspended(tenant, _)
+            | Self::Reactivated(tenant)
+            | Self::Deleted(tenant)
+            | Self::Restored(tenant)
+            | Self::SettingsUpdated(tenant)
+            | Self::Purged(tenant) => tenant,
+        }
+    }
+}
+
+/// Persistent tenant definitions
+#[async_trait]
+pub trait TenantStore: TenantResolver {
+    /// Insert or replace a tenant
+    async fn save(&self, tenant: Tenant) -> TenantResult<()>;
+
+    /// Remove a tenant
+    async fn remove(&self, id: &str) -> TenantResult<()>;
+
+    /// All tenants, in any state
+    async fn all(&self) -> TenantResult<Vec<Tenant>>;
+}
+
+/// Sets up and tears down the resources of a tenant
+#[async_trait]
+pub trait TenantProvisioner: Send + Sync {
+    /// Prepare a new tenant, e.g. create its schema, run migrations, seed
+    async fn provision(&self, tenant: &Tenant) -> TenantResult<()>;
+
+    /// Drop the tenant's data and purge its cached state
+    async fn deprovision(&self, tenant: &Tenant) -> TenantResult<()>;
+}
+
+/// Creates, suspends, deletes and purges tenants
+#[derive(Clone)]
+pub struct TenantManager {
+    store: Arc<dyn TenantStore>,
+    provisioners: Vec<Arc<dyn TenantProvisioner>>,
+    grace_period: Duration,
+    events: broadcast::Sender<TenantEvent>,
+}
+
+impl TenantManager {
+    /// Default time between soft and hard deletion
+    pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+    /// Create a manager for the tenants in `store`
+    pub fn new(store: impl TenantStore + 'static) -> Self {
+        let (events, _) = broadcast::channel(64);
+        Self {
+            store: Arc::new(store),
+            provisioners: Vec::new(),
+            grace_period: Self::DEFAULT_GRACE_PERIOD,
+            events,
+        }
+    }
+
+    /// Add a provisioner; they run in order and are torn down in reverse
+    pub fn provisioner(mut self, provisioner: impl TenantProvisioner + 'static) -> Self {
+        self.provisioners.push(Arc::new(provisioner));
+        self
+    }
+
+    /// Set how long soft-deleted tenants can be restored
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Receive lifecycle events
+    pub fn subscribe(&self) -> broadcast::Receiver<TenantEvent> {
+        self.events.subscribe()
+    }
+
+    /// Provision and store a new tenant
+    ///
+    /// If a provisioner fails, the ones that already ran are rolled back and
+    /// the tenant is not stored.
+    pub async fn create(&self, mut tenant: Tenant) -> TenantResult<Tenant> {
+        match self.store.resolve_by_id(tenant.id()).await {
+            Ok(_) => return Err(TenantError::AlreadyExists(tenant.id().to_string())),
+            Err(TenantError::NotFound) => {}
+            Err(e) => return Err(e),
+        }
+        tenant.set_status(TenantStatus::Active);
+
+        for (done, provisioner) in self.provisioners.iter().enumerate() {
+            if let Err(e) = provisioner.provision(&tenant).await {
+                for provisioner in self.provisioners[..done].iter().rev() {
+                    if let Err(e) = provisioner.deprovision(&tenant).await {
+                        tracing::error!("Rolling back tenant '{}' failed: {}", tenant.id(), e);
+                    }
+                }
+                return Err(e);
+            }
+        }
+
+        self.store.save(tenant.clone()).await?;
+        self.publish(TenantEvent::Created(tenant.clone()));
+        Ok(tenant)
+    }
+
+    /// Reject the tenant's requests until it is reactivated
+    pub async fn suspend(&self, id: &str, reason: SuspendReason) -> TenantResult<Tenant> {
+        let tenant = self
+            .transition(id, TenantStatus::Suspended { reason }, |status| {
+                !matches!(status, TenantStatus::Deleted { .. })
+            })
+            .await?;
+        self.publish(TenantEvent::Suspended(tenant.clone(), reason));
+        Ok(tenant)
+    }
+
+    /// Lift a suspension
+    pub async fn reactivate(&self, id: &str) -> TenantResult<Tenant> {
+        let tenant = self
+            .transition(id, TenantStatus::Active, |status| {
+                matches!(status, TenantStatus::Suspended { .. })
+            })
+            .await?;
+        self.publish(TenantEvent::Reactivated(tenant.clone()));
+        Ok(tenant)
+    }
+
+    /// Soft-delete a tenant; it is purged after the grace period
+    pub async fn delete(&self, id: &str) -> TenantResult<Tenant> {
+        let purge_at = SystemTime::now() + self.grace_period;
+        let tenant = self
+            .transition(id, TenantStatus::Deleted { purge_at }, |status| {
+                !matches!(status, TenantStatus::Deleted { .. })
+            })
+            .await?;
+        self.publish(TenantEvent::Deleted(tenant.clone()));
+        Ok(tenant)
+    }
+
+    /// Undo a soft delete within the grace period
+    pub async fn restore(&self, id: &str) -> TenantResult<Tenant> {
+        let now = SystemTime::now();
+        let tenant = self
+            .transition(
+                id,
+                TenantStatus::Active,
+                |status| matches!(status, TenantStatus::Deleted { purge_at } if *purge_at > now),
+            )
+            .await?;
+        self.publish(TenantEvent::Restored(tenant.clone()));
+        Ok(tenant)
+    }
+
+    /// Replace the settings of a tenant
+    pub async fn update_settings(
+        &self,
+        id: &str,
+        settings: TenantSettings,
+    ) -> TenantResult<Tenant> {
+        let tenant = self.store.resolve_by_id(id).await?.with_settings(settings);
+        self.store.save(tenant.clone()).await?;
+        self.publish(TenantEvent::SettingsUpdated(tenant.clone()));
+        Ok(tenant)
+    }
+
+    /// Hard-delete a tenant: deprovision its resources and remove it
+    pub async fn purge(&self, id: &str) -> TenantResult<()> {
+        let tenant = self.store.resolve_by_id(id).await?;
+        for provisioner in self.provisioners.iter().rev() {
+            provisioner.deprovision(&tenant).await?;
+        }
+        self.store.remove(id).await?;
+        self.publish(TenantEvent::Purged(tenant));
+        Ok(())
+    }
+
+    /// Purge soft-deleted tenants whose grace period is over
+    ///
+    /// Returns the purged tenant ids. Run it periodically, e.g. from the
+    /// scheduler.
+    pub async fn purge_expired(&self) -> TenantResult<Vec<String>> {
+        let now = SystemTime::now();
+        let mut purged = Vec::new();
+        for tenant in self.store.all().await? {
+            if matches!(tenant.status(), TenantStatus::Deleted { purge_at } if *purge_at <= now) {
+                self.purge(tenant.id()).await?;
+                purged.push(tenant.id().to_string());
+            }
+        }
+        Ok(purged)
+    }
+
+    async fn transition(
+        &self,
+        id: &str,
+        status: TenantStatus,
+        allowed: impl FnOnce(&TenantStatus) -> bool,
+    ) -> TenantResult<Tenant> {
+        let mut tenant = self.store.resolve_by_id(id).await?;
+        if !allowed(tenant.status()) {
+            return Err(TenantError::InvalidTransition(format!(
+                "tenant '{}' is {:?}",
+                id,
+                tenant.status()
+            )));
+        }
+        tenant.set_status(status);
+        self.store.save(tenant.clone()).await?;
+        Ok(tenant)
+    }
+
+    fn publish(&self, event: TenantEvent) {
+        // No subscribers is fine
+        let _ = self.events.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryTenantResolver;
+    use std::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    struct Recorder {
+        calls: Arc<Mutex<Vec<String>>>,
+        fail_on: Option<&'static str>,
+    }
+
+    #[async_trait]
+    impl TenantProvisioner for Recorder {
+        async fn provision(&self, tenant: &Tenant) -> TenantResult<()> {
+            if self.fail_on == Some(tenant.id()) {
+                return Err(TenantError::Provisioning("migration failed".to_string()));
+            }
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("provision {}", tenant.id()));
+            Ok(())
+        }
+
+        async fn deprovision(&self, tenant: &Tenant) -> TenantResult<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("deprovision {}", tenant.id()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle() {
+        let store = InMemoryTenantResolver::new();
+        let recorder = Recorder::default();
+        let manager = TenantManager::new(store.clone()).provisioner(recorder.clone());
+        let mut events = manager.subscribe();
+
+        manager.create(Tenant::new("acme", "Acme")).await.unwrap();
+        assert!(matches!(
+            manager.create(Tenant::new("acme", "Acme")).await,
+            Err(TenantError::AlreadyExists(_))
+        ));
+
+        let tenant = manager
+            .suspend("acme", SuspendReason::Billing)
+            .await
+            .unwrap();
+        assert!(!tenant.is_active());
+        assert_eq!(
+            store.resolve_by_id("acme").await.unwrap().status(),
+            &TenantStatus::Suspended {
+                reason: SuspendReason::Billing
+            }
+        );
+        manager.reactivate("acme").await.unwrap();
+        assert!(manager.reactivate("acme").await.is_err());
+
+        manager.delete("acme").await.unwrap();
+        assert!(manager
+            .suspend("acme", SuspendReason::Administrative)
+            .await
+            .is_err());
+        manager.restore("acme").await.unwrap();
+        assert!(store.resolve_by_id("acme").await.unwrap().is_active());
+
+        manager
+            .update_settings("acme", TenantSettings::new().set("limits.max_seats", 5))
+            .await
+            .unwrap();
+        assert!(store
+            .resolve_by_id("acme")
+            .await
+            .unwrap()
+            .settings()
+            .contains("limits.max_seats"));
+
+        manager.purge("acme").await.unwrap();
+        assert!(matches!(
+            store.resolve_by_id("acme").await,
+            Err(TenantError::NotFound)
+        ));
+        assert_eq!(
+            *recorder.calls.lock().unwrap(),
+            ["provision acme", "deprovision acme"]
+        );
+
+        let mut kinds = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            assert_eq!(event.tenant().id(), "acme");
+            kinds.push(
+                format!("{:?}", event)
+                    .split('(')
+                    .next()
+                    .unwrap()
+                    .to_string(),
+            );
+        }
+        assert_eq!(
+            kinds,
+            [
+                "Created",
+                "Suspended",
+                "Reactivated",
+                "Deleted",
+                "Restored",
+                "SettingsUpdated",
+                "Purged"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_provisioning_rolls_back() {
+        let store = InMemoryTenantResolver::new();
+        let first = Recorder::default();
+        let second = Recorder {
+            fail_on: Some("acme"),
+            ..Default::default()
+        };
+        let manager = TenantManager::new(store.clone())
+            .provisioner(first.clone())
+            .provisioner(second);
+
+        let result = manager.create(Tenant::new("acme", "Acme")).await;
+        assert!(matches!(result, Err(TenantError::Provisioning(_))));
+        assert!(store.resolve_by_id("acme").await.is_err());
+        assert_eq!(
+            *first.calls.lock().unwrap(),
+            ["provision acme", "deprovision acme"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_purge_expired() {
+        let store = InMemoryTenantResolver::new();
+        let manager = TenantManager::new(store.clone()).grace_period(Duration::ZERO);
+        manager.create(Tenant::new("acme", "Acme")).await.unwrap();
+        manager
+            .create(Tenant::new("globex", "Globex"))
+            .await
+            .unwrap();
+
+        manager.delete("acme").await.unwrap();
+        // The grace period is over, so it can't be restored
+        assert!(manager.restore("acme").await.is_err());
+
+        assert_eq!(manager.purge_expired().await.unwrap(), ["acme"]);
+        assert_eq!(store.all().await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,349 @@
+//! Tenant identification middleware and extractor
+
+use crate::{
+    DomainIdentifier, HeaderIdentifier, Tenant, TenantContext, TenantError, TenantIdentifier,
+    TenantResolver, TenantStatus,
+};
+use axum::{
+    extract::{FromRequestParts, OptionalFromRequestParts, Request},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use rf_middleware::{Middleware, MiddlewareService, Next};
+use std::{convert::Infallible, sync::Arc};
+use tower::Layer;
+
+/// What to do with requests no tenant was identified for
+///
+/// Applies when the identifier finds nothing ([`TenantError::NotFound`] or
+/// [`TenantError::Unidentified`]); other errors are always rejected.
+#[derive(Debug, Clone, Default)]
+pub enum TenantFallback {
+    /// Respond with the identification error
+    #[default]
+    Reject,
+    /// Continue as the given tenant
+    Default(Tenant),
+    /// Continue without a tenant; `Option<Tenant>` extractors get `None`
+    Continue,
+}
+
+/// Marks a request the layer ran on without identifying a tenant
+#[derive(Clone)]
+struct Unidentified;
+
+/// Identifies the tenant of each request
+///
+/// Identification runs once per request; the [`Tenant`] is stored in the
+/// request extensions, where the [`Tenant`] extractor and other middleware
+/// read it, and the rest of the request runs in a [`TenantContext`] scope.
+/// Requests that already carry a tenant are not identified again.
+///
+/// Suspended tenants are rejected with [`TenantError::Suspended`];
+/// soft-deleted tenants are treated as not found.
+///
+/// ```no_run
+/// use rf_tenancy::*;
+/// use axum::{Router, routing::get};
+///
+/// # async fn example() {
+/// let tenants = InMemoryTenantResolver::new();
+/// tenants.add_tenant(Tenant::with_domain("acme", "Acme", "acme.example.com")).await;
+///
+/// let app: Router = Router::new()
+///     .route("/", get(|tenant: Tenant| async move { tenant.name().to_string() }))
+///     .layer(TenantLayer::by_domain(tenants).default_tenant(Tenant::new("main", "Main")));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct TenantLayer {
+    identifier: Arc<dyn TenantIdentifier>,
+    fallback: TenantFallback,
+}
+
+impl TenantLayer {
+    /// Create tenant layer with a custom identification strategy
+    pub fn new(identifier: impl TenantIdentifier + 'static) -> Self {
+        Self {
+            identifier: Arc::new(identifier),
+            fallback: TenantFallback::default(),
+        }
+    }
+
+    /// Create tenant layer with domain-based identification
+    pub fn by_domain(resolver: impl TenantResolver + 'static) -> Self {
+        Self::new(DomainIdentifier::new(resolver))
+    }
+
+    /// Create tenant layer with header-based identification
+    pub fn by_header(
+        header_name: impl Into<String>,
+        resolver: impl TenantResolver + 'static,
+    ) -> Self {
+        Self::new(HeaderIdentifier::new(header_name, resolver))
+    }
+
+    /// Set how unidentified requests are handled
+    pub fn fallback(mut self, fallback: TenantFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Serve unidentified requests as `tenant`
+    pub fn default_tenant(self, tenant: Tenant) -> Self {
+        self.fallback(TenantFallback::Default(tenant))
+    }
+
+    /// Identify the tenant and store it in the request, or build the
+    /// rejection
+    async fn identify(&self, req: Request) -> Result<Request, Response> {
+        if req.extensions().get::<Tenant>().is_some() {
+            return Ok(req);
+        }
+
+        let (mut parts, body) = req.into_parts();
+        let identified =
+            self.identifier
+                .identify(&parts)
+                .await
+                .and_then(|tenant| match tenant.status() {
+                    TenantStatus::Active => Ok(tenant),
+                    TenantStatus::Suspended { reason } => Err(TenantError::Suspended(*reason)),
+                    TenantStatus::Deleted { .. } => Err(TenantError::NotFound),
+                });
+        match identified {
+            Ok(tenant) => {
+                parts.extensions.insert(tenant);
+            }
+            Err(TenantError::NotFound | TenantError::Unidentified) if !self.rejects() => {
+                match &self.fallback {
+                    TenantFallback::Default(tenant) => {
+                        parts.extensions.insert(tenant.clone());
+                    }
+                    _ => {
+                        parts.extensions.insert(Unidentified);
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::debug!("Tenant identification failed: {}", e);
+                return Err(e.into_response());
+            }
+        }
+        Ok(Request::from_parts(parts, body))
+    }
+
+    fn rejects(&self) -> bool {
+        matches!(self.fallback, TenantFallback::Reject)
+    }
+}
+
+impl Middleware for TenantLayer {
+    async fn handle(self, req: Request, next: Next) -> Response {
+        let req = match self.identify(req).await {
+            Ok(req) => req,
+            Err(rejection) => return rejection,
+        };
+        match req.extensions().get::<Tenant>().cloned() {
+            Some(tenant) => TenantContext::scope(tenant, next.run(req)).await,
+            None => next.run(req).await,
+        }
+    }
+}
+
+impl<S> Layer<S> for TenantLayer {
+    type Service = TenantService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MiddlewareService::new(self.clone(), inner)
+    }
+}
+
+/// Service created by [`TenantLayer`]
+pub type TenantService<S> = MiddlewareService<TenantLayer, S>;
+
+impl<S> FromRequestParts<S> for Tenant
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(tenant) = parts.extensions.get::<Tenant>() {
+            return Ok(tenant.clone());
+        }
+        if parts.extensions.get::<Unidentified>().is_some() {
+            return Err(TenantError::Unidentified.into_response());
+        }
+        Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "TenantLayer is not installed",
+        )
+            .into_response())
+    }
+}
+
+impl<S> OptionalFromRequestParts<S> for Tenant
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(parts.extensions.get::<Tenant>().cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryTenantResolver;
+    use axum::{body::Body, routing::get, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    async fn tenants() -> InMemoryTenantResolver {
+        let resolver = InMemoryTenantResolver::new();
+        resolver
+            .add_tenant(Tenant::with_domain("acme", "Acme", "acme.example.com"))
+            .await;
+        resolver
+    }
+
+    fn app(layer: TenantLayer) -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|tenant: Tenant| async move { tenant.id().to_string() }),
+            )
+            .route(
+                "/optional",
+                get(|tenant: Option<Tenant>| async move {
+                    tenant.map_or("none".to_string(), |t| t.id().to_string())
+                }),
+            )
+            .layer(layer)
+    }
+
+    async fn get_body(app: Router, uri: &str, host: &str) -> (StatusCode, String) {
+        let request = Request::builder()
+            .uri(uri)
+            .header("host", host)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_layer_identifies_tenant() {
+        let app = app(TenantLayer::by_domain(tenants().await));
+
+        let (status, body) = get_body(app.clone(), "/", "acme.example.com:8080").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "acme");
+
+        let (status, _) = get_body(app, "/", "unknown.example.com").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_default_tenant_fallback() {
+        let layer =
+            TenantLayer::by_domain(tenants().await).default_tenant(Tenant::new("main", "Main"));
+        let (status, body) = get_body(app(layer), "/", "unknown.example.com").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "main");
+    }
+
+    #[tokio::test]
+    async fn test_continue_fallback() {
+        let layer = TenantLayer::by_header("X-Tenant-Id", tenants().await)
+            .fallback(TenantFallback::Continue);
+
+        let (status, body) = get_body(app(layer.clone()), "/optional", "localhost").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "none");
+
+        let (status, _) = get_body(app(layer), "/", "localhost").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_handler_runs_in_tenant_context() {
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    TenantContext::current()
+                        .map(|t| t.id().to_string())
+                        .unwrap_or_default()
+                }),
+            )
+            .layer(TenantLayer::by_domain(tenants().await));
+
+        let (_, body) = get_body(app, "/", "acme.example.com").await;
+        assert_eq!(body, "acme");
+    }
+
+    #[tokio::test]
+    async fn test_extractor_without_layer() {
+        let app = Router::new().route(
+            "/",
+            get(|tenant: Tenant| async move { tenant.id().to_string() }),
+        );
+        let (status, body) = get_body(app, "/", "acme.example.com").await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body, "TenantLayer is not installed");
+    }
+
+    #[tokio::test]
+    async fn test_inactive_tenants() {
+        let tenants = tenants().await;
+        let manager = crate::TenantManager::new(tenants.clone());
+        let app = app(TenantLayer::by_domain(tenants));
+
+        manager
+            .suspend("acme", crate::SuspendReason::Billing)
+            .await
+            .unwrap();
+        let (status, _) = get_body(app.clone(), "/", "acme.example.com").await;
+        assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+
+        manager.reactivate("acme").await.unwrap();
+        manager.delete("acme").await.unwrap();
+        let (status, _) = get_body(app, "/", "acme.example.com").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    struct CountingIdentifier(Arc<AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl TenantIdentifier for CountingIdentifier {
+        async fn identify(&self, _parts: &Parts) -> crate::TenantResult<Tenant> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(Tenant::new("acme", "Acme"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_identifies_once_per_request() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let layer = TenantLayer::new(CountingIdentifier(calls.clone()));
+        let app = Router::new()
+            .route("/", get(|_a: Tenant, _b: Tenant| async { "ok" }))
+            .layer(layer.clone())
+            .layer(layer);
+
+        let (status, _) = get_body(app, "/", "localhost").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,601 @@
+//! Central tenant registry
+//!
+//! In multi-instance deployments tenant definitions live in one central
+//! [`TenantSource`]: a PostgreSQL table ([`PgTenantStore`], feature `sqlx`)
+//! or a control-plane API ([`HttpTenantSource`], feature `http-registry`).
+//! Every instance keeps a local snapshot in a [`TenantRegistry`] and resolves
+//! tenants from it; [`TenantRegistry::watch`] re-syncs periodically, so a
+//! tenant added on one node reaches all others without a redeploy.
+//!
+//! Sources are versioned: a sync only transfers the tenants if the version
+//! changed. [`tenant_registry_router`] serves any source to
+//! [`HttpTenantSource`] clients, using the version as `ETag`.
+//!
+//! ```no_run
+//! use rf_tenancy::*;
+//! use std::time::Duration;
+//!
+//! # async fn example(source: impl TenantSource + 'static) -> TenantResult<()> {
+//! let registry = TenantRegistry::new(source);
+//! registry.sync().await?;
+//! registry.watch(Duration::from_secs(30));
+//!
+//! let layer = TenantLayer::by_domain(registry);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{InMemoryTenantResolver, Tenant, TenantError, TenantResolver, TenantResult};
+use async_trait::async_trait;
+use axum::{
+    extract::State,
+    http::{
+        header::{ETAG, IF_NONE_MATCH},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{atomic::Ordering, Arc, RwLock},
+    time::Duration,
+};
+
+/// All tenant definitions at one version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantSnapshot {
+    pub version: String,
+    pub tenants: Vec<Tenant>,
+}
+
+/// Central store of tenant definitions
+#[async_trait]
+pub trait TenantSource: Send + Sync {
+    /// Fetch all tenants, or `None` if they are still at `known_version`
+    async fn fetch(&self, known_version: Option<&str>) -> TenantResult<Option<TenantSnapshot>>;
+}
+
+#[async_trait]
+impl TenantSource for InMemoryTenantResolver {
+    async fn fetch(&self, known_version: Option<&str>) -> TenantResult<Option<TenantSnapshot>> {
+        let tenants = self.tenants.read().await;
+        let version = self.revision.load(Ordering::Acquire).to_string();
+        if known_version == Some(version.as_str()) {
+            return Ok(None);
+        }
+        Ok(Some(TenantSnapshot {
+            version,
+            tenants: tenants.clone(),
+        }))
+    }
+}
+
+/// Snapshot indexed for lookups
+struct Index {
+    version: String,
+    by_id: HashMap<String, Tenant>,
+    by_domain: HashMap<String, String>,
+}
+
+impl Index {
+    fn new(snapshot: TenantSnapshot) -> Self {
+        let by_domain = snapshot
+            .tenants
+            .iter()
+            .filter_map(|t| Some((t.domain()?.to_string(), t.id().to_string())))
+            .collect();
+        let by_id = snapshot
+            .tenants
+            .into_iter()
+            .map(|t| (t.id().to_string(), t))
+            .collect();
+        Self {
+            version: snapshot.version,
+            by_id,
+            by_domain,
+        }
+    }
+}
+
+/// Locally cached snapshot of a [`TenantSource`]
+///
+/// Resolves tenants without hitting the source. Until the first successful
+/// [`sync`](TenantRegistry::sync), resolving fails with
+/// [`TenantError::Registry`].
+#[derive(Clone)]
+pub struct TenantRegistry {
+    source: Arc<dyn TenantSource>,
+    index: Arc<RwLock<Option<Arc<Index>>>>,
+}
+
+impl TenantRegistry {
+    /// Create an empty registry for `source`
+    pub fn new(source: impl TenantSource + 'static) -> Self {
+        Self {
+            source: Arc::new(source),
+            index: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Fetch the tenants if the source changed since the last sync
+    ///
+    /// Returns whether the snapshot was replaced.
+    pub async fn sync(&self) -> TenantResult<bool> {
+        let known = self.version();
+        let Some(snapshot) = self.source.fetch(known.as_deref()).await? else {
+            return Ok(false);
+        };
+
+        let index = Index::new(snapshot);
+        tracing::debug!(
+            version = %index.version,
+            tenants = index.by_id.len(),
+            "Tenant registry synced"
+        );
+        *self.index.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(index));
+        Ok(true)
+    }
+
+    /// Sync with the source periodically
+    ///
+    /// Failed syncs are logged and the last snapshot stays in use. Requires
+    /// a Tokio runtime.
+    pub fn watch(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let registry = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = registry.sync().await {
+                    tracing::error!(error = %e, "Failed to sync tenant registry");
+                }
+            }
+        })
+    }
+
+    /// Version of the current snapshot, `None` before the first sync
+    pub fn version(&self) -> Option<String> {
+        self.current().map(|index| index.version.clone())
+    }
+
+    /// All tenants of the current snapshot
+    pub fn tenants(&self) -> Vec<Tenant> {
+        self.current()
+            .map(|index| index.by_id.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn current(&self) -> Option<Arc<Index>> {
+        self.index.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn synced(&self) -> TenantResult<Arc<Index>> {
+        self.current()
+            .ok_or_else(|| TenantError::Registry("registry has not been synced".to_string()))
+    }
+}
+
+#[async_trait]
+impl TenantResolver for TenantRegistry {
+    async fn resolve_by_id(&self, id: &str) -> TenantResult<Tenant> {
+        self.synced()?
+            .by_id
+            .get(id)
+            .cloned()
+            .ok_or(TenantError::NotFound)
+    }
+
+    async fn resolve_by_domain(&self, domain: &str) -> TenantResult<Tenant> {
+        let index = self.synced()?;
+        index
+            .by_domain
+            .get(domain)
+            .and_then(|id| index.by_id.get(id))
+            .cloned()
+            .ok_or(TenantError::NotFound)
+    }
+}
+
+/// Serve `source` to [`HttpTenantSource`] clients
+///
+/// `GET /` returns the [`TenantSnapshot`] as JSON with the version as
+/// `ETag`, or `304 Not Modified` if it matches `If-None-Match`. Protect the
+/// route with your own auth middleware.
+pub fn tenant_registry_router(source: impl TenantSource + 'static) -> Router {
+    let source: Arc<dyn TenantSource> = Arc::new(source);
+    Router::new().route("/", get(snapshot)).with_state(source)
+}
+
+async fn snapshot(State(source): State<Arc<dyn TenantSource>>, headers: HeaderMap) -> Response {
+    let known = headers
+        .get(IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim_start_matches("W/").trim_matches('"'));
+
+    match source.fetch(known).await {
+        Ok(None) => {
+            let etag = format!("\"{}\"", known.unwrap_or_default());
+            (StatusCode::NOT_MODIFIED, [(ETAG, etag)]).into_response()
+        }
+        Ok(Some(snapshot)) => {
+            let etag = format!("\"{}\"", snapshot.version);
+            ([(ETAG, etag)], Json(snapshot)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to load tenant snapshot");
+            e.into_response()
+        }
+    }
+}
+
+#[cfg(feature = "http-registry")]
+pub use self::http::HttpTenantSource;
+
+#[cfg(feature = "http-registry")]
+mod http {
+    use super::*;
+
+    /// Control-plane API serving [`tenant_registry_router`]
+    pub struct HttpTenantSource {
+        client: reqwest::Client,
+        url: String,
+        token: Option<String>,
+    }
+
+    impl HttpTenantSource {
+        pub fn new(url: impl Into<String>) -> Self {
+            Self {
+                client: reqwest::Client::builder()
+                    .timeout(Duration::from_secs(10))
+                    .build()
+                    .unwrap_or_default(),
+                url: url.into(),
+                token: None,
+            }
+        }
+
+        /// Send `Authorization: Bearer <token>` with every request
+        pub fn bearer_token(mut self, token: impl Into<String>) -> Self {
+            self.token = Some(token.into());
+            self
+        }
+    }
+
+    #[async_trait]
+    impl TenantSource for HttpTenantSource {
+        async fn fetch(&self, known_version: Option<&str>) -> TenantResult<Option<TenantSnapshot>> {
+            let mut request = self.client.get(&self.url);
+            if let Some(version) = known_version {
+                request = request.header(IF_NONE_MATCH, format!("\"{}\"", version));
+            }
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+
+            let response = request.send().await.map_err(registry_error)?;
+            if response.status() == StatusCode::NOT_MODIFIED {
+                return Ok(None);
+            }
+            let snapshot = response
+                .error_for_status()
+                .map_err(registry_error)?
+                .json()
+                .await
+                .map_err(registry_error)?;
+            Ok(Some(snapshot))
+        }
+    }
+
+    fn registry_error(e: reqwest::Error) -> TenantError {
+        TenantError::Registry(e.to_string())
+    }
+}
+
+#[cfg(feature = "sqlx")]
+pub use self::pg::PgTenantStore;
+
+#[cfg(feature = "sqlx")]
+mod pg {
+    use super::*;
+    use crate::TenantStore;
+    use sqlx::{PgPool, Row};
+
+    /// PostgreSQL tenant store
+    ///
+    /// Uses the `tenants` table and `tenant_revisions` sequence, created by
+    /// [`PgTenantStore::migrate`]. Tenants are stored as JSON text; every
+    /// change advances the sequence, whose value is the snapshot version.
+    /// Use it with a [`TenantManager`](crate::TenantManager) on the control
+    /// plane and as the [`TenantSource`] of every instance's registry.
+    #[derive(Clone)]
+    pub struct PgTenantStore {
+        pool: PgPool,
+    }
+
+    impl PgTenantStore {
+        pub fn new(pool: PgPool) -> Self {
+            Self { pool }
+        }
+
+        /// Create the table and sequence if they don't exist
+        pub async fn migrate(&self) -> TenantResult<()> {
+            for statement in [
+                "CREATE SEQUENCE IF NOT EXISTS tenant_revisions",
+                "CREATE TABLE IF NOT EXISTS tenants (
+                    id TEXT PRIMARY KEY,
+                    domain TEXT UNIQUE,
+                    data TEXT NOT NULL,
+                    revision BIGINT NOT NULL
+                )",
+            ] {
+                sqlx::query(statement)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(store_error)?;
+            }
+            Ok(())
+        }
+
+        async fn version(&self) -> TenantResult<String> {
+            let row = sqlx::query("SELECT last_value, is_called FROM tenant_revisions")
+                .fetch_one(&self.pool)
+                .await
+                .map_err(store_error)?;
+            let version: i64 = if row.get("is_called") {
+                row.get("last_value")
+            } else {
+                0
+            };
+            Ok(version.to_string())
+        }
+
+        async fn find(&self, column: &str, value: &str) -> TenantResult<Tenant> {
+            let row = sqlx::query(&format!("SELECT data FROM tenants WHERE {} = $1", column))
+                .bind(value)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(store_error)?
+                .ok_or(TenantError::NotFound)?;
+            parse_tenant(row.get("data"))
+        }
+    }
+
+    #[async_trait]
+    impl TenantResolver for PgTenantStore {
+        async fn resolve_by_id(&self, id: &str) -> TenantResult<Tenant> {
+            self.find("id", id).await
+        }
+
+        async fn resolve_by_domain(&self, domain: &str) -> TenantResult<Tenant> {
+            self.find("domain", domain).await
+        }
+    }
+
+    #[async_trait]
+    impl TenantStore for PgTenantStore {
+        async fn save(&self, tenant: Tenant) -> TenantResult<()> {
+            let data =
+                serde_json::to_string(&tenant).map_err(|e| TenantError::Registry(e.to_string()))?;
+
+            sqlx::query(
+                "INSERT INTO tenants (id, domain, data, revision)
+                 VALUES ($1, $2, $3, nextval('tenant_revisions'))
+                 ON CONFLICT (id) DO UPDATE SET
+                    domain = EXCLUDED.domain,
+                    data = EXCLUDED.data,
+                    revision = EXCLUDED.revision",
+            )
+            .bind(tenant.id())
+            .bind(tenant.domain())
+            .bind(data)
+            .execute(&self.pool)
+            .await
+            .map_err(store_error)?;
+            Ok(())
+        }
+
+        async fn remove(&self, id: &str) -> TenantResult<()> {
+            let mut tx = self.pool.begin().await.map_err(store_error)?;
+            let deleted = sqlx::query("DELETE FROM tenants WHERE id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(store_error)?;
+            if deleted.rows_affected() > 0 {
+                sqlx::query("SELECT nextval('tenant_revisions')")
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(store_error)?;
+            }
+            tx.commit().await.map_err(store_error)?;
+            Ok(())
+        }
+
+        async fn all(&self) -> TenantResult<Vec<Tenant>> {
+            let rows = sqlx::query("SELECT data FROM tenants ORDER BY id")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(store_error)?;
+            rows.iter()
+                .map(|row| parse_tenant(row.get("data")))
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl TenantSource for PgTenantStore {
+        async fn fetch(&self, known_version: Option<&str>) -> TenantResult<Option<TenantSnapshot>> {
+            // Read the version first: a change racing with the query below
+            // shows up as a newer version on the next sync
+            let version = self.version().await?;
+            if known_version == Some(version.as_str()) {
+                return Ok(None);
+            }
+            Ok(Some(TenantSnapshot {
+                version,
+                tenants: self.all().await?,
+            }))
+        }
+    }
+
+    fn parse_tenant(data: &str) -> TenantResult<Tenant> {
+        serde_json::from_str(data).map_err(|e| TenantError::Registry(e.to_string()))
+    }
+
+    fn store_error(e: sqlx::Error) -> TenantError {
+        TenantError::Registry(e.to_string())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[tokio::test]
+        #[ignore] // Requires PostgreSQL
+        async fn test_pg_store() {
+            let pool = PgPool::connect("postgres://localhost/rustforge_test")
+                .await
+                .unwrap();
+            let store = PgTenantStore::new(pool);
+            store.migrate().await.unwrap();
+            store.remove("test-acme").await.unwrap();
+
+            let registry = TenantRegistry::new(store.clone());
+            registry.sync().await.unwrap();
+            assert!(!registry.sync().await.unwrap());
+
+            store
+                .save(Tenant::with_domain(
+                    "test-acme",
+                    "Acme",
+                    "test-acme.example.com",
+                ))
+                .await
+                .unwrap();
+            assert!(registry.sync().await.unwrap());
+            let tenant = registry
+                .resolve_by_domain("test-acme.example.com")
+                .await
+                .unwrap();
+            assert_eq!(tenant.id(), "test-acme");
+
+            store.remove("test-acme").await.unwrap();
+            assert!(registry.sync().await.unwrap());
+            assert!(registry.resolve_by_id("test-acme").await.is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TenantManager, TenantStore};
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_registry_sync() {
+        let central = InMemoryTenantResolver::new();
+        let registry = TenantRegistry::new(central.clone());
+
+        assert!(matches!(
+            registry.resolve_by_id("acme").await,
+            Err(TenantError::Registry(_))
+        ));
+        assert!(registry.sync().await.unwrap());
+        assert!(!registry.sync().await.unwrap());
+        assert!(matches!(
+            registry.resolve_by_id("acme").await,
+            Err(TenantError::NotFound)
+        ));
+
+        // A tenant created on the control plane reaches the instance
+        TenantManager::new(central.clone())
+            .create(Tenant::with_domain("acme", "Acme", "acme.example.com"))
+            .await
+            .unwrap();
+        assert!(registry.sync().await.unwrap());
+        let tenant = registry
+            .resolve_by_domain("acme.example.com")
+            .await
+            .unwrap();
+        assert_eq!(tenant.id(), "acme");
+
+        central.remove("acme").await.unwrap();
+        registry.sync().await.unwrap();
+        assert!(registry.tenants().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_watch() {
+        let central = InMemoryTenantResolver::new();
+        let registry = TenantRegistry::new(central.clone());
+        let handle = registry.watch(Duration::from_secs(30));
+
+        central.add_tenant(Tenant::new("acme", "Acme")).await;
+        tokio::time::sleep(Duration::from_secs(31)).await;
+        assert!(registry.resolve_by_id("acme").await.is_ok());
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_router_etag() {
+        let central = InMemoryTenantResolver::new();
+        central.add_tenant(Tenant::new("acme", "Acme")).await;
+        let app = tenant_registry_router(central.clone());
+
+        let response = app
+            .clone()
+            .oneshot(axum::http::Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let etag = response.headers()[ETAG].clone();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let snapshot: TenantSnapshot = serde_json::from_slice(&body).unwrap();
+        assert_eq!(format!("\"{}\"", snapshot.version), etag.to_str().unwrap());
+        assert_eq!(snapshot.tenants.len(), 1);
+
+        let request = || {
+            axum::http::Request::get("/")
+                .header(IF_NONE_MATCH, etag.clone())
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        central.add_tenant(Tenant::new("globex", "Globex")).await;
+        let response = app.oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[cfg(feature = "http-registry")]
+    #[tokio::test]
+    async fn test_http_source() {
+        let central = InMemoryTenantResolver::new();
+        central.add_tenant(Tenant::new("acme", "Acme")).await;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().nest("/tenants", tenant_registry_router(central.clone()));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let registry = TenantRegistry::new(
+            HttpTenantSource::new(format!("http://{}/tenants", addr)).bearer_token("secret"),
+        );
+        assert!(registry.sync().await.unwrap());
+        assert!(!registry.sync().await.unwrap());
+        assert_eq!(registry.resolve_by_id("acme").await.unwrap().name(), "Acme");
+
+        central.add_tenant(Tenant::new("globex", "Globex")).await;
+        assert!(registry.sync().await.unwrap());
+        assert_eq!(registry.tenants().len(), 2);
+    }
+}
@@ -0,0 +1,317 @@
+//! Cross-tenant query guard
+//!
+//! Tenant-owned tables are registered with a [`TenantGuard`]. Queries going
+//! through the guarded helpers (`rf_tenancy::sql` with feature `sqlx`,
+//! `rf_tenancy::sea` with feature `sea-orm`) are checked before they run:
+//! touching a tenant-owned table outside of a [`TenantContext`] scope, or
+//! without filtering on the tenant column, is a violation. Filtering means a
+//! `tenant_id = ...` or `tenant_id IN (...)` predicate after `WHERE`, `ON`
+//! or `AND`; inserts name the column instead. What happens then depends on
+//! the [`GuardMode`].
+//!
+//! ```
+//! use rf_tenancy::{GuardMode, TenantGuard};
+//!
+//! TenantGuard::new()
+//!     .tables(["posts", "comments"])
+//!     .mode(GuardMode::Error)
+//!     .install();
+//! ```
+
+use crate::{TenantContext, TenantError, TenantResult};
+use std::{
+    collections::HashSet,
+    future::Future,
+    sync::{Arc, RwLock},
+};
+
+/// Default column holding the tenant id
+pub const TENANT_COLUMN: &str = "tenant_id";
+
+static GUARD: RwLock<Option<Arc<TenantGuard>>> = RwLock::new(None);
+
+tokio::task_local! {
+    static BYPASS: ();
+}
+
+/// Reaction to an unscoped query on a tenant-owned table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardMode {
+    /// Don't check
+    Off,
+    /// Log a warning and run the query
+    Warn,
+    /// Fail with [`TenantError::UnscopedQuery`]
+    Error,
+    /// Panic, for tests and development
+    Panic,
+}
+
+impl Default for GuardMode {
+    /// `Panic` in debug builds, `Error` in release builds
+    fn default() -> Self {
+        if cfg!(debug_assertions) {
+            Self::Panic
+        } else {
+            Self::Error
+        }
+    }
+}
+
+/// Tenant-owned tables and how unscoped queries on them are handled
+#[derive(Debug, Clone)]
+pub struct TenantGuard {
+    column: String,
+    tables: HashSet<String>,
+    mode: GuardMode,
+}
+
+impl TenantGuard {
+    /// Guard without tables, using [`TENANT_COLUMN`]
+    pub fn new() -> Self {
+        Self {
+            column: TENANT_COLUMN.to_string(),
+            tables: HashSet::new(),
+            mode: GuardMode::default(),
+        }
+    }
+
+    /// Set the tenant id column
+    pub fn column(mut self, column: impl Into<String>) -> Self {
+        self.column = column.into();
+        self
+    }
+
+    /// Register tenant-owned tables
+    pub fn tables<I, T>(mut self, tables: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.tables
+            .extend(tables.into_iter().map(|t| t.into().to_lowercase()));
+        self
+    }
+
+    /// Set the guard mode
+    pub fn mode(mut self, mode: GuardMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Make this the guard of the process
+    pub fn install(self) {
+        *GUARD.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(self));
+    }
+
+    /// Remove the installed guard
+    pub fn uninstall() {
+        *GUARD.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// The installed guard
+    pub fn installed() -> Option<Arc<TenantGuard>> {
+        GUARD.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Check `sql` against the installed guard; passes if none is installed
+    pub fn check_sql(sql: &str) -> TenantResult<()> {
+        match Self::installed() {
+            Some(guard) => guard.check(sql),
+            None => Ok(()),
+        }
+    }
+
+    /// Run `fut` without guard checks, e.g. for cross-tenant admin queries
+    pub async fn bypass<F: Future>(fut: F) -> F::Output {
+        BYPASS.scope((), fut).await
+    }
+
+    /// Check a query, applying the guard mode to violations
+    pub fn check(&self, sql: &str) -> TenantResult<()> {
+        if self.mode == GuardMode::Off || BYPASS.try_with(|_| ()).is_ok() {
+            return Ok(());
+        }
+
+        let Some(violation) = self.violation(sql) else {
+            return Ok(());
+        };
+        match self.mode {
+            GuardMode::Off => Ok(()),
+            GuardMode::Warn => {
+                tracing::warn!("{}", violation);
+                Ok(())
+            }
+            GuardMode::Error => Err(violation),
+            GuardMode::Panic => panic!("{}", violation),
+        }
+    }
+
+    fn violation(&self, sql: &str) -> Option<TenantError> {
+        let tokens = tokens(sql);
+        let table = tables(&tokens).find(|table| self.tables.contains(*table))?;
+
+        if !TenantContext::is_set() {
+            return Some(TenantError::UnscopedQuery(format!(
+                "'{}' queried without a tenant scope",
+                table
+            )));
+        }
+        if !self.filters(&tokens) {
+            return Some(TenantError::UnscopedQuery(format!(
+                "'{}' queried without filtering on {}",
+                table, self.column
+            )));
+        }
+        None
+    }
+
+    /// Whether the statement filters on the tenant column, or names it in
+    /// the column list of an insert
+    fn filters(&self, tokens: &[String]) -> bool {
+        if tokens.first().is_some_and(|token| token == "insert") {
+            return tokens
+                .iter()
+                .skip_while(|token| *token != "into")
+                .take_while(|token| !matches!(token.as_str(), "values" | "select" | "default"))
+                .any(|token| column_name(token) == self.column);
+        }
+        tokens.windows(3).any(|predicate| {
+            matches!(predicate[0].as_str(), "where" | "on" | "and")
+                && column_name(&predicate[1]) == self.column
+                && matches!(predicate[2].as_str(), "=" | "in")
+        })
+    }
+}
+
+impl Default for TenantGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Column holding the tenant id, from the installed guard
+pub fn tenant_column() -> String {
+    TenantGuard::installed().map_or_else(|| TENANT_COLUMN.to_string(), |g| g.column.clone())
+}
+
+/// Lowercased words and comparison operators of a statement, with quotes
+/// removed
+fn tokens(sql: &str) -> Vec<String> {
+    let word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '.' | '"' | '`');
+    let operator = |c: char| matches!(c, '=' | '<' | '>' | '!');
+
+    let mut tokens = Vec::new();
+    let mut rest = sql;
+    while let Some(c) = rest.chars().next() {
+        let len = if word(c) {
+            rest.find(|c| !word(c)).unwrap_or(rest.len())
+        } else if operator(c) {
+            rest.find(|c| !operator(c)).unwrap_or(rest.len())
+        } else {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        };
+        tokens.push(rest[..len].replace(['"', '`'], "").to_lowercase());
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+/// Tables following `FROM`, `JOIN`, `INTO` and `UPDATE`, without schema
+fn tables(tokens: &[String]) -> impl Iterator<Item = &str> {
+    tokens
+        .windows(2)
+        .filter(|pair| matches!(pair[0].as_str(), "from" | "join" | "into" | "update"))
+        .map(|pair| column_name(&pair[1]))
+}
+
+/// `posts.tenant_id` → `tenant_id`
+fn column_name(token: &str) -> &str {
+    token.rsplit('.').next().unwrap_or(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_tenant, Tenant};
+
+    fn guard() -> TenantGuard {
+        TenantGuard::new()
+            .tables(["posts", "comments"])
+            .mode(GuardMode::Error)
+    }
+
+    #[tokio::test]
+    async fn test_unscoped_queries() {
+        let guard = guard();
+        let sql = "SELECT * FROM posts WHERE id = $1";
+
+        // No tenant scope
+        assert!(matches!(
+            guard.check(sql),
+            Err(TenantError::UnscopedQuery(_))
+        ));
+
+        with_tenant(Tenant::new("acme", "Acme"), async {
+            // Scope but no tenant filter
+            assert!(guard.check(sql).is_err());
+            assert!(guard
+                .check("SELECT p.* FROM public.\"posts\" p WHERE p.\"tenant_id\" = $1")
+                .is_ok());
+            assert!(guard
+                .check("UPDATE comments SET body = $1 WHERE tenant_id = $2")
+                .is_ok());
+        })
+        .await;
+
+        with_tenant(Tenant::new("acme", "Acme"), async {
+            // Mentioning the column doesn't scope the query
+            for sql in [
+                "SELECT tenant_id, body FROM posts",
+                "SELECT body FROM posts ORDER BY tenant_id",
+                "SELECT body FROM posts WHERE tenant_id <> $1",
+                "UPDATE posts SET tenant_id = $1 WHERE id = $2",
+                "INSERT INTO posts (title) SELECT title FROM posts WHERE tenant_id = $1",
+            ] {
+                assert!(guard.check(sql).is_err(), "{}", sql);
+            }
+            assert!(guard
+                .check("SELECT * FROM posts WHERE id = $1 AND tenant_id IN ($2, $3)")
+                .is_ok());
+            assert!(guard
+                .check(
+                    "SELECT * FROM plans JOIN posts p ON p.plan_id = plans.id AND p.tenant_id = $1"
+                )
+                .is_ok());
+            assert!(guard
+                .check("INSERT INTO posts (tenant_id, title) VALUES ($1, $2)")
+                .is_ok());
+        })
+        .await;
+
+        // Tables that aren't tenant-owned pass
+        assert!(guard.check("SELECT * FROM plans").is_ok());
+        assert!(guard
+            .check("SELECT * FROM plans JOIN posts ON posts.plan_id = plans.id")
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bypass_and_modes() {
+        let sql = "DELETE FROM posts";
+        assert!(TenantGuard::bypass(async { guard().check(sql) })
+            .await
+            .is_ok());
+        assert!(guard().mode(GuardMode::Warn).check(sql).is_ok());
+        assert!(guard().mode(GuardMode::Off).check(sql).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "without a tenant scope")]
+    fn test_panic_mode() {
+        let _ = guard()
+            .mode(GuardMode::Panic)
+            .check("INSERT INTO posts (title) VALUES ($1)");
+    }
+}
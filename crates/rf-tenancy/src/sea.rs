@@ -0,0 +1,106 @@
+//! Tenant scoping for SeaORM
+//!
+//! ```ignore
+//! use rf_tenancy::sea::TenantScoped;
+//!
+//! let posts = post::Entity::find().scoped()?.all(&db).await?;
+//! ```
+
+use crate::{tenant_column, TenantContext, TenantGuard, TenantResult};
+use sea_orm::{
+    sea_query::{Alias, Expr},
+    DbBackend, QueryFilter, QueryTrait,
+};
+
+/// Tenant filter for SeaORM selects, updates and deletes
+pub trait TenantScoped: QueryFilter + Sized {
+    /// Filter on the current tenant
+    fn scoped(self) -> TenantResult<Self> {
+        let tenant = TenantContext::require()?;
+        Ok(self.filter(Expr::col(Alias::new(tenant_column())).eq(tenant.id())))
+    }
+}
+
+impl<Q: QueryFilter> TenantScoped for Q {}
+
+/// Check a query against the installed [`TenantGuard`]
+pub trait TenantGuarded: QueryTrait + Sized {
+    /// Pass the query through unchanged if the guard allows it
+    fn guarded(self, backend: DbBackend) -> TenantResult<Self> {
+        TenantGuard::check_sql(&self.build(backend).to_string())?;
+        Ok(self)
+    }
+}
+
+impl<Q: QueryTrait> TenantGuarded for Q {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_tenant, Tenant, TenantError};
+    use sea_orm::{entity::prelude::*, EntityTrait};
+
+    mod invoice {
+        use sea_orm::entity::prelude::*;
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+        #[sea_orm(table_name = "invoices")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i64,
+            pub tenant_id: String,
+            pub total: i64,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    #[tokio::test]
+    async fn test_scoped() {
+        assert!(matches!(
+            invoice::Entity::find().scoped(),
+            Err(TenantError::Unidentified)
+        ));
+
+        let sql = with_tenant(Tenant::new("acme", "Acme"), async {
+            invoice::Entity::delete_many()
+                .filter(invoice::Column::Total.eq(0))
+                .scoped()
+                .unwrap()
+                .build(DbBackend::Postgres)
+                .to_string()
+        })
+        .await;
+        assert_eq!(
+            sql,
+            r#"DELETE FROM "invoices" WHERE "invoices"."total" = 0 AND "tenant_id" = 'acme'"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_guarded() {
+        let guard = TenantGuard::new()
+            .tables(["invoices"])
+            .mode(crate::GuardMode::Error);
+        let select = invoice::Entity::find()
+            .build(DbBackend::Postgres)
+            .to_string();
+        assert!(guard.check(&select).is_err());
+
+        with_tenant(Tenant::new("acme", "Acme"), async {
+            let scoped = invoice::Entity::find()
+                .scoped()
+                .unwrap()
+                .build(DbBackend::Postgres)
+                .to_string();
+            assert!(guard.check(&scoped).is_ok());
+        })
+        .await;
+
+        // `invoices` isn't registered with the installed guard, if any
+        assert!(invoice::Entity::find().guarded(DbBackend::Sqlite).is_ok());
+    }
+}
@@ -0,0 +1,322 @@
+//! Per-tenant settings
+//!
+//! Each [`Tenant`](crate::Tenant) carries a [`TenantSettings`] bag of JSON
+//! values (limits, branding, enabled modules, flag overrides). Settings are
+//! read through typed [`TenantSetting`]s, which resolve in three layers:
+//!
+//! 1. the tenant's own value,
+//! 2. the global value from [`install_global_settings`],
+//! 3. the setting's `Default`.
+//!
+//! ```
+//! use rf_tenancy::{Tenant, TenantSetting, TenantSettings};
+//! use serde::Deserialize;
+//!
+//! #[derive(Clone, Default, Deserialize)]
+//! struct MaxSeats(u32);
+//!
+//! impl TenantSetting for MaxSeats {
+//!     const KEY: &'static str = "limits.max_seats";
+//! }
+//!
+//! let tenant = Tenant::new("acme", "Acme")
+//!     .with_settings(TenantSettings::new().set("limits.max_seats", 25));
+//! assert_eq!(tenant.setting::<MaxSeats>().unwrap().0, 25);
+//! ```
+
+use crate::{TenantError, TenantResult};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+static GLOBAL: RwLock<Option<Arc<TenantSettings>>> = RwLock::new(None);
+
+/// Bumped whenever the global settings change, invalidating cached values
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// A typed tenant setting
+///
+/// `KEY` is a dot-separated path into the settings bag, e.g.
+/// `"branding.primary_color"`.
+pub trait TenantSetting: DeserializeOwned + Default + Clone + Send + Sync + 'static {
+    const KEY: &'static str;
+}
+
+type Cache = Arc<RwLock<HashMap<TypeId, (u64, Arc<dyn Any + Send + Sync>)>>>;
+
+/// Settings bag of a tenant
+///
+/// Typed values are deserialized once and cached until the bag or the
+/// global settings change.
+#[derive(Clone, Default)]
+pub struct TenantSettings {
+    values: Map<String, Value>,
+    cache: Cache,
+}
+
+impl TenantSettings {
+    /// Empty settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Settings from a JSON object
+    pub fn from_json(values: Map<String, Value>) -> Self {
+        Self {
+            values,
+            cache: Cache::default(),
+        }
+    }
+
+    /// Set the value at a dot-separated `key`, creating parent objects
+    ///
+    /// Values that don't serialize to JSON are ignored.
+    pub fn set(mut self, key: &str, value: impl Serialize) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    /// Set the value at a dot-separated `key` in place
+    pub fn insert(&mut self, key: &str, value: impl Serialize) {
+        let Ok(value) = serde_json::to_value(value) else {
+            tracing::warn!("Tenant setting '{}' is not serializable", key);
+            return;
+        };
+
+        let mut segments = key.split('.').peekable();
+        let mut map = &mut self.values;
+        while let Some(segment) = segments.next() {
+            if segments.peek().is_none() {
+                map.insert(segment.to_string(), value);
+                break;
+            }
+            let entry = map
+                .entry(segment)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                *entry = Value::Object(Map::new());
+            }
+            map = entry.as_object_mut().expect("just made an object");
+        }
+        // Don't share the cache with clones taken before the change
+        self.cache = Cache::default();
+    }
+
+    /// Raw value at a dot-separated `key`
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let mut segments = key.split('.');
+        let first = self.values.get(segments.next()?)?;
+        segments.try_fold(first, |value, segment| value.get(segment))
+    }
+
+    /// Whether a value is set at `key`
+    pub fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Whether module `name` is enabled (`modules.<name>`), if set
+    pub fn module_enabled(&self, name: &str) -> Option<bool> {
+        self.get(&format!("modules.{}", name))?.as_bool()
+    }
+
+    /// Feature flag override for `flag` (`features.<flag>`), if set
+    pub fn flag_override(&self, flag: &str) -> Option<bool> {
+        self.get(&format!("features.{}", flag))?.as_bool()
+    }
+
+    /// All values as a JSON object
+    pub fn as_json(&self) -> &Map<String, Value> {
+        &self.values
+    }
+
+    /// Resolve a typed setting: tenant value, then global value, then default
+    pub fn setting<T: TenantSetting>(&self) -> TenantResult<T> {
+        let generation = GENERATION.load(Ordering::Acquire);
+        let cached = self
+            .cache
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&TypeId::of::<T>())
+            .filter(|(cached_at, _)| *cached_at == generation)
+            .and_then(|(_, value)| value.downcast_ref::<T>().cloned());
+        if let Some(value) = cached {
+            return Ok(value);
+        }
+
+        let value = self.resolve::<T>()?;
+        self.cache
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(TypeId::of::<T>(), (generation, Arc::new(value.clone())));
+        Ok(value)
+    }
+
+    fn resolve<T: TenantSetting>(&self) -> TenantResult<T> {
+        let global = global_settings();
+        let raw = self
+            .get(T::KEY)
+            .or_else(|| global.as_ref().and_then(|g| g.get(T::KEY)));
+
+        match raw {
+            Some(value) => T::deserialize(value)
+                .map_err(|e| TenantError::InvalidSetting(format!("{}: {}", T::KEY, e))),
+            None => Ok(T::default()),
+        }
+    }
+}
+
+impl fmt::Debug for TenantSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.values, f)
+    }
+}
+
+impl PartialEq for TenantSettings {
+    fn eq(&self, other: &Self) -> bool {
+        self.values == other.values
+    }
+}
+
+impl Serialize for TenantSettings {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.values.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TenantSettings {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Map::deserialize(deserializer).map(Self::from_json)
+    }
+}
+
+/// Make `settings` the global layer under every tenant's settings
+pub fn install_global_settings(settings: TenantSettings) {
+    *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(settings));
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// The installed global settings
+pub fn global_settings() -> Option<Arc<TenantSettings>> {
+    GLOBAL.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tenant;
+
+    #[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+    struct MaxSeats(u32);
+
+    impl TenantSetting for MaxSeats {
+        const KEY: &'static str = "limits.max_seats";
+    }
+
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    struct Branding {
+        primary_color: String,
+        #[serde(default)]
+        logo_url: Option<String>,
+    }
+
+    impl Default for Branding {
+        fn default() -> Self {
+            Self {
+                primary_color: "#000000".to_string(),
+                logo_url: None,
+            }
+        }
+    }
+
+    impl TenantSetting for Branding {
+        const KEY: &'static str = "branding";
+    }
+
+    #[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+    struct StorageQuota(u64);
+
+    impl TenantSetting for StorageQuota {
+        const KEY: &'static str = "limits.storage_quota";
+    }
+
+    #[test]
+    fn test_typed_settings() {
+        let tenant = Tenant::new("acme", "Acme").with_settings(
+            TenantSettings::new()
+                .set("limits.max_seats", 25)
+                .set("branding.primary_color", "#ff0000")
+                .set("modules.billing", true),
+        );
+
+        assert_eq!(tenant.setting::<MaxSeats>().unwrap(), MaxSeats(25));
+        assert_eq!(
+            tenant.setting::<Branding>().unwrap().primary_color,
+            "#ff0000"
+        );
+        assert_eq!(tenant.settings().module_enabled("billing"), Some(true));
+        assert_eq!(tenant.settings().module_enabled("crm"), None);
+
+        // Unset settings fall back to the default
+        let plain = Tenant::new("globex", "Globex");
+        assert_eq!(plain.setting::<Branding>().unwrap(), Branding::default());
+    }
+
+    #[test]
+    fn test_invalid_setting() {
+        let tenant = Tenant::new("acme", "Acme")
+            .with_settings(TenantSettings::new().set("limits.max_seats", "lots"));
+        assert!(matches!(
+            tenant.setting::<MaxSeats>(),
+            Err(TenantError::InvalidSetting(_))
+        ));
+    }
+
+    #[test]
+    fn test_global_layer_and_cache() {
+        let tenant = Tenant::new("acme", "Acme");
+        assert_eq!(tenant.setting::<StorageQuota>().unwrap(), StorageQuota(0));
+
+        // Installing global settings invalidates cached values
+        install_global_settings(TenantSettings::new().set("limits.storage_quota", 1024));
+        assert_eq!(
+            tenant.setting::<StorageQuota>().unwrap(),
+            StorageQuota(1024)
+        );
+
+        let tenant = tenant.with_settings(TenantSettings::new().set("limits.storage_quota", 10));
+        assert_eq!(tenant.setting::<StorageQuota>().unwrap(), StorageQuota(10));
+    }
+
+    #[test]
+    fn test_insert_invalidates_cache() {
+        let mut settings = TenantSettings::new().set("limits.max_seats", 5);
+        let before = settings.clone();
+        assert_eq!(before.setting::<MaxSeats>().unwrap(), MaxSeats(5));
+
+        settings.insert("limits.max_seats", 50);
+        assert_eq!(settings.setting::<MaxSeats>().unwrap(), MaxSeats(50));
+        assert_eq!(before.setting::<MaxSeats>().unwrap(), MaxSeats(5));
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let tenant = Tenant::new("acme", "Acme")
+            .with_settings(TenantSettings::new().set("features.new_ui", false));
+        let json = serde_json::to_string(&tenant).unwrap();
+        let tenant: Tenant = serde_json::from_str(&json).unwrap();
+        assert_eq!(tenant.settings().flag_override("new_ui"), Some(false));
+
+        // Tenants stored before settings existed still load
+        let tenant: Tenant =
+            serde_json::from_str(r#"{"id":"a","name":"A","domain":null}"#).unwrap();
+        assert_eq!(tenant.settings(), &TenantSettings::new());
+    }
+}
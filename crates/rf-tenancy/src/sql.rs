@@ -0,0 +1,152 @@
+//! Tenant scoping for sqlx
+//!
+//! [`TenantQueryBuilderExt`] appends the tenant filter of the current
+//! [`TenantContext`] to a `QueryBuilder`. [`query`], [`query_as`] and
+//! [`query_scalar`] are drop-in replacements for their sqlx counterparts
+//! that run the SQL past the installed [`TenantGuard`] first.
+
+use crate::{tenant_column, TenantContext, TenantGuard, TenantResult};
+use sqlx::{
+    database::HasArguments,
+    query::{Query, QueryAs, QueryScalar},
+    Database, Encode, FromRow, QueryBuilder, Type,
+};
+
+/// Tenant filters for `sqlx::QueryBuilder`
+pub trait TenantQueryBuilderExt {
+    /// Append ` WHERE tenant_id = <current tenant>`
+    fn where_tenant(&mut self) -> TenantResult<&mut Self>;
+
+    /// Append ` AND tenant_id = <current tenant>`
+    fn and_tenant(&mut self) -> TenantResult<&mut Self>;
+}
+
+impl<'args, DB> TenantQueryBuilderExt for QueryBuilder<'args, DB>
+where
+    DB: Database,
+    String: 'args + Encode<'args, DB> + Type<DB> + Send,
+{
+    fn where_tenant(&mut self) -> TenantResult<&mut Self> {
+        push_tenant(self, "WHERE")
+    }
+
+    fn and_tenant(&mut self) -> TenantResult<&mut Self> {
+        push_tenant(self, "AND")
+    }
+}
+
+fn push_tenant<'a, 'args, DB>(
+    builder: &'a mut QueryBuilder<'args, DB>,
+    keyword: &str,
+) -> TenantResult<&'a mut QueryBuilder<'args, DB>>
+where
+    DB: Database,
+    String: 'args + Encode<'args, DB> + Type<DB> + Send,
+{
+    let tenant = TenantContext::require()?;
+    builder
+        .push(format!(" {} {} = ", keyword, tenant_column()))
+        .push_bind(tenant.id().to_string());
+    Ok(builder)
+}
+
+/// `sqlx::query` checked by the installed [`TenantGuard`]
+pub fn query<DB>(sql: &str) -> TenantResult<Query<'_, DB, <DB as HasArguments<'_>>::Arguments>>
+where
+    DB: Database,
+{
+    TenantGuard::check_sql(sql)?;
+    Ok(sqlx::query(sql))
+}
+
+/// `sqlx::query_as` checked by the installed [`TenantGuard`]
+pub fn query_as<'q, DB, O>(
+    sql: &'q str,
+) -> TenantResult<QueryAs<'q, DB, O, <DB as HasArguments<'q>>::Arguments>>
+where
+    DB: Database,
+    O: for<'r> FromRow<'r, DB::Row>,
+{
+    TenantGuard::check_sql(sql)?;
+    Ok(sqlx::query_as(sql))
+}
+
+/// `sqlx::query_scalar` checked by the installed [`TenantGuard`]
+pub fn query_scalar<'q, DB, O>(
+    sql: &'q str,
+) -> TenantResult<QueryScalar<'q, DB, O, <DB as HasArguments<'q>>::Arguments>>
+where
+    DB: Database,
+    (O,): for<'r> FromRow<'r, DB::Row>,
+{
+    TenantGuard::check_sql(sql)?;
+    Ok(sqlx::query_scalar(sql))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{with_tenant, GuardMode, Tenant, TenantError};
+    use sqlx::{Sqlite, SqlitePool};
+
+    async fn pool() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE notes (id INTEGER PRIMARY KEY, tenant_id TEXT, body TEXT)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO notes (tenant_id, body) VALUES ('acme', 'a'), ('acme', 'b'), ('globex', 'c')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_where_tenant() {
+        let pool = pool().await;
+
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT body FROM notes");
+        assert!(matches!(
+            builder.where_tenant(),
+            Err(TenantError::Unidentified)
+        ));
+
+        let bodies: Vec<String> = with_tenant(Tenant::new("acme", "Acme"), async {
+            let mut builder = QueryBuilder::<Sqlite>::new("SELECT body FROM notes");
+            builder.where_tenant().unwrap().push(" ORDER BY body");
+            assert_eq!(
+                builder.sql(),
+                "SELECT body FROM notes WHERE tenant_id = ? ORDER BY body"
+            );
+            builder.build_query_scalar().fetch_all(&pool).await.unwrap()
+        })
+        .await;
+        assert_eq!(bodies, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_guarded_queries() {
+        TenantGuard::new()
+            .tables(["notes"])
+            .mode(GuardMode::Error)
+            .install();
+        let pool = pool().await;
+
+        let unscoped = query_scalar::<Sqlite, i64>("SELECT COUNT(*) FROM notes");
+        assert!(matches!(unscoped, Err(TenantError::UnscopedQuery(_))));
+
+        let count = with_tenant(Tenant::new("globex", "Globex"), async {
+            query_scalar::<Sqlite, i64>("SELECT COUNT(*) FROM notes WHERE tenant_id = ?")
+                .unwrap()
+                .bind("globex")
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        })
+        .await;
+        assert_eq!(count, 1);
+    }
+}
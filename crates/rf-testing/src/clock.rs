@@ -0,0 +1,110 @@
+//! Clocks for time travel in tests
+
+use chrono::{DateTime, Duration, Utc};
+use std::sync::{Arc, Mutex};
+
+/// Source of the current time
+///
+/// Take an `Arc<dyn Clock>` instead of calling `Utc::now()` directly, so
+/// tests can swap in a [`TestClock`].
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Frozen clock that only moves when told to
+///
+/// Clones share their time. Timers are separate: pause and advance them
+/// with `tokio::time::pause` and `tokio::time::advance`.
+///
+/// # Example
+///
+/// ```
+/// use chrono::Duration;
+/// use rf_testing::{Clock, TestClock};
+///
+/// let clock = TestClock::new();
+/// let issued_at = clock.now();
+///
+/// clock.travel(Duration::days(31));
+/// assert_eq!(clock.now() - issued_at, Duration::days(31));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TestClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestClock {
+    /// Create a clock frozen at the current time
+    pub fn new() -> Self {
+        Self::at(Utc::now())
+    }
+
+    /// Create a clock frozen at `time`
+    pub fn at(time: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(time)),
+        }
+    }
+
+    /// Move the clock by `duration`; negative durations travel back
+    pub fn travel(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+
+    /// Move the clock to `time`
+    pub fn travel_to(&self, time: DateTime<Utc>) {
+        *self.now.lock().unwrap() = time;
+    }
+
+    /// Move the clock back to the current time
+    pub fn travel_back(&self) {
+        self.travel_to(Utc::now());
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_travel() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let clock = TestClock::at(start);
+        let shared: Arc<dyn Clock> = Arc::new(clock.clone());
+
+        clock.travel(Duration::hours(2));
+        assert_eq!(shared.now(), start + Duration::hours(2));
+
+        clock.travel(-Duration::days(1));
+        assert_eq!(shared.now(), start - Duration::hours(22));
+
+        clock.travel_to(start);
+        assert_eq!(shared.now(), start);
+
+        clock.travel_back();
+        assert!(shared.now() > start);
+    }
+}
@@ -0,0 +1,108 @@
+//! Rolled back database transactions for tests
+
+use crate::{TestError, TestResult};
+use sqlx::{Database, Pool, Transaction};
+use std::ops::{Deref, DerefMut};
+
+/// Transaction rolled back at the end of a test
+///
+/// Run the code under test on the transaction instead of the pool, and
+/// whatever it writes is gone once the transaction is dropped, even if
+/// the test panics. Transactions the code begins itself become savepoints.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_testing::TestTransaction;
+/// use sqlx::PgPool;
+///
+/// # async fn example(pool: PgPool) -> rf_testing::TestResult<()> {
+/// let mut tx = TestTransaction::begin(&pool).await?;
+///
+/// sqlx::query("INSERT INTO users (email) VALUES ('a@example.com')")
+///     .execute(&mut *tx)
+///     .await
+///     .unwrap();
+///
+/// // Rolled back when `tx` is dropped
+/// # Ok(())
+/// # }
+/// ```
+pub struct TestTransaction<DB: Database> {
+    tx: Transaction<'static, DB>,
+}
+
+impl<DB: Database> TestTransaction<DB> {
+    /// Begin a transaction on a connection of `pool`
+    pub async fn begin(pool: &Pool<DB>) -> TestResult<Self> {
+        Ok(Self {
+            tx: pool.begin().await.map_err(database_error)?,
+        })
+    }
+
+    /// Roll back now instead of on drop
+    pub async fn rollback(self) -> TestResult<()> {
+        self.tx.rollback().await.map_err(database_error)
+    }
+}
+
+impl<DB: Database> Deref for TestTransaction<DB> {
+    type Target = DB::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tx
+    }
+}
+
+impl<DB: Database> DerefMut for TestTransaction<DB> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tx
+    }
+}
+
+fn database_error(error: sqlx::Error) -> TestError {
+    TestError::DatabaseError(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::SqlitePool;
+
+    async fn count(pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM users")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rolled_back() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE users (email TEXT NOT NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let mut tx = TestTransaction::begin(&pool).await.unwrap();
+        sqlx::query("INSERT INTO users (email) VALUES ('a@example.com')")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        drop(tx);
+        assert_eq!(count(&pool).await, 0);
+
+        let mut tx = TestTransaction::begin(&pool).await.unwrap();
+        sqlx::query("INSERT INTO users (email) VALUES ('b@example.com')")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        tx.rollback().await.unwrap();
+        assert_eq!(count(&pool).await, 0);
+    }
+}
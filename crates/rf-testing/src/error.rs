@@ -16,6 +16,9 @@ pub enum TestError {
 
     #[error("Test setup error: {0}")]
     SetupError(String),
+
+    #[error("Database error: {0}")]
+    DatabaseError(String),
 }
 
 /// Test result type
@@ -3,7 +3,7 @@
 //! Factories provide a convenient way to generate test data with the builder pattern.
 
 use async_trait::async_trait;
-use fake::{Fake, Faker};
+use fake::Fake;
 use std::collections::HashMap;
 
 /// Factory trait for creating test data
@@ -101,8 +101,12 @@ impl FakeData {
 
     /// Generate a fake address
     pub fn address() -> String {
-        use fake::faker::address::en::StreetAddress;
-        StreetAddress().fake()
+        use fake::faker::address::en::{BuildingNumber, StreetName};
+        format!(
+            "{} {}",
+            BuildingNumber().fake::<String>(),
+            StreetName().fake::<String>()
+        )
     }
 
     /// Generate a fake company name
@@ -165,7 +169,7 @@ mod tests {
     #[test]
     fn test_fake_data_number() {
         let n = FakeData::number(1, 100);
-        assert!(n >= 1 && n <= 100);
+        assert!((1..=100).contains(&n));
     }
 
     #[test]
@@ -0,0 +1,185 @@
+//! Fake event listener
+
+use async_trait::async_trait;
+use rf_events::{Event, EventDispatcher, EventResult, WildcardListener};
+use std::sync::{Arc, Mutex};
+
+/// Dispatched event recorded by [`FakeEvents`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DispatchedEvent {
+    pub name: String,
+    pub aggregate_id: Option<String>,
+}
+
+/// Recorder of dispatched events
+///
+/// [`dispatcher`](Self::dispatcher) creates a dispatcher that only
+/// records, so no real listener runs; [`spy_on`](Self::spy_on) records
+/// the events of a dispatcher whose listeners still run.
+///
+/// # Example
+///
+/// ```
+/// use rf_events::Event;
+/// use rf_testing::FakeEvents;
+///
+/// struct OrderShipped;
+///
+/// impl Event for OrderShipped {
+///     fn name(&self) -> &'static str {
+///         "order.shipped"
+///     }
+/// }
+///
+/// # async fn example() -> rf_events::EventResult<()> {
+/// let events = FakeEvents::new();
+/// let dispatcher = events.dispatcher().await;
+///
+/// dispatcher.dispatch(OrderShipped).await?;
+///
+/// events.assert_dispatched("order.shipped");
+/// events.assert_not_dispatched("order.cancelled");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct FakeEvents {
+    dispatched: Arc<Mutex<Vec<DispatchedEvent>>>,
+}
+
+impl FakeEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a dispatcher recording its events without listeners
+    pub async fn dispatcher(&self) -> EventDispatcher {
+        let dispatcher = EventDispatcher::new();
+        self.spy_on(&dispatcher).await;
+        dispatcher
+    }
+
+    /// Record the events of `dispatcher`
+    pub async fn spy_on(&self, dispatcher: &EventDispatcher) {
+        dispatcher.listen_any("*", self.clone()).await;
+    }
+
+    /// All dispatched events
+    pub fn dispatched(&self) -> Vec<DispatchedEvent> {
+        self.dispatched.lock().unwrap().clone()
+    }
+
+    /// Assert an event `name` was dispatched
+    pub fn assert_dispatched(&self, name: &str) {
+        assert!(
+            self.count(|e| e.name == name) > 0,
+            "Expected {} to be dispatched, dispatched: {:?}",
+            name,
+            self.names()
+        );
+    }
+
+    /// Assert an event `name` of aggregate `aggregate_id` was dispatched
+    pub fn assert_dispatched_for(&self, name: &str, aggregate_id: &str) {
+        assert!(
+            self.count(|e| e.name == name && e.aggregate_id.as_deref() == Some(aggregate_id)) > 0,
+            "Expected {} to be dispatched for {}, dispatched: {:?}",
+            name,
+            aggregate_id,
+            self.dispatched()
+        );
+    }
+
+    /// Assert event `name` was dispatched `count` times
+    pub fn assert_dispatched_times(&self, name: &str, count: usize) {
+        let dispatched = self.count(|e| e.name == name);
+        assert_eq!(
+            dispatched, count,
+            "Expected {} to be dispatched {} times, got {}",
+            name, count, dispatched
+        );
+    }
+
+    /// Assert no event `name` was dispatched
+    pub fn assert_not_dispatched(&self, name: &str) {
+        self.assert_dispatched_times(name, 0);
+    }
+
+    /// Assert no event was dispatched
+    pub fn assert_nothing_dispatched(&self) {
+        let names = self.names();
+        assert!(
+            names.is_empty(),
+            "Expected no events, dispatched: {:?}",
+            names
+        );
+    }
+
+    /// Forget the dispatched events
+    pub fn clear(&self) {
+        self.dispatched.lock().unwrap().clear();
+    }
+
+    fn count(&self, predicate: impl Fn(&DispatchedEvent) -> bool) -> usize {
+        self.dispatched
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| predicate(e))
+            .count()
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.dispatched
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|e| e.name.clone())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl WildcardListener for FakeEvents {
+    async fn handle(&self, event: &dyn Event) -> EventResult<()> {
+        self.dispatched.lock().unwrap().push(DispatchedEvent {
+            name: event.name().to_string(),
+            aggregate_id: event.aggregate_id(),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct PostPublished(u64);
+
+    impl Event for PostPublished {
+        fn name(&self) -> &'static str {
+            "post.published"
+        }
+
+        fn aggregate_id(&self) -> Option<String> {
+            Some(format!("post-{}", self.0))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spy_on() {
+        let dispatcher = EventDispatcher::new();
+        let events = FakeEvents::new();
+        events.spy_on(&dispatcher).await;
+        events.assert_nothing_dispatched();
+
+        for id in [1, 2] {
+            dispatcher.dispatch(PostPublished(id)).await.unwrap();
+        }
+
+        events.assert_dispatched("post.published");
+        events.assert_dispatched_times("post.published", 2);
+        events.assert_dispatched_for("post.published", "post-2");
+        events.assert_not_dispatched("post.deleted");
+    }
+}
@@ -0,0 +1,19 @@
+//! Fakes recording what the code under test did, with assertions
+//!
+//! Failed assertions panic with what was recorded instead.
+
+#[cfg(feature = "events")]
+mod events;
+#[cfg(feature = "notifications")]
+mod notifications;
+#[cfg(feature = "queue")]
+mod queue;
+
+#[cfg(feature = "events")]
+pub use events::{DispatchedEvent, FakeEvents};
+#[cfg(feature = "notifications")]
+pub use notifications::{FakeNotifications, SentNotification};
+#[cfg(feature = "queue")]
+pub use queue::FakeQueue;
+#[cfg(feature = "mail")]
+pub use rf_mail::FakeMailer;
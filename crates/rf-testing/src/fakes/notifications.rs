@@ -0,0 +1,270 @@
+//! Fake notification channels
+
+use async_trait::async_trait;
+use rf_notifications::{
+    Channel, ChannelHandler, DatabaseNotification, MailMessage, Notifiable, Notification,
+    NotificationManager, NotificationResult, PushMessage, SmsMessage,
+};
+use std::sync::{Arc, Mutex};
+
+/// Notification recorded by [`FakeNotifications`], rendered for its channel
+#[derive(Debug, Clone)]
+pub struct SentNotification {
+    /// [`Notifiable::id`] of the recipient
+    pub notifiable_id: String,
+    pub channel: Channel,
+    pub mail: Option<MailMessage>,
+    pub sms: Option<SmsMessage>,
+    pub push: Option<PushMessage>,
+    pub database: Option<DatabaseNotification>,
+}
+
+/// Channels recording notifications instead of sending them
+///
+/// # Example
+///
+/// ```
+/// use rf_notifications::{
+///     Channel, MailMessage, Notifiable, Notification, NotificationManager, NotificationResult,
+/// };
+/// use rf_testing::FakeNotifications;
+///
+/// struct User;
+///
+/// impl Notifiable for User {
+///     fn id(&self) -> String {
+///         "42".to_string()
+///     }
+/// }
+///
+/// struct InvoicePaid;
+///
+/// impl Notification for InvoicePaid {
+///     fn via(&self, _notifiable: &dyn Notifiable) -> Vec<Channel> {
+///         vec![Channel::Email]
+///     }
+///
+///     fn to_mail(&self, _notifiable: &dyn Notifiable) -> NotificationResult<MailMessage> {
+///         Ok(MailMessage::new().subject("Invoice paid"))
+///     }
+/// }
+///
+/// # async fn example() -> NotificationResult<()> {
+/// let notifications = FakeNotifications::new();
+/// let mut manager = NotificationManager::new();
+/// notifications.register(&mut manager);
+///
+/// manager.send(&InvoicePaid, &User).await?;
+///
+/// notifications.assert_notification_sent_to(&User);
+/// notifications.assert_sent_via(&User, Channel::Email);
+/// notifications.assert_sent(|n| n.mail.as_ref().is_some_and(|m| m.subject == "Invoice paid"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct FakeNotifications {
+    sent: Arc<Mutex<Vec<SentNotification>>>,
+}
+
+impl FakeNotifications {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace every channel of `manager` with a recording one
+    pub fn register(&self, manager: &mut NotificationManager) {
+        for channel in [
+            Channel::Email,
+            Channel::Sms,
+            Channel::Push,
+            Channel::Database,
+        ] {
+            manager.register_channel(
+                channel.clone(),
+                Arc::new(FakeChannel {
+                    channel,
+                    sent: self.sent.clone(),
+                }),
+            );
+        }
+    }
+
+    /// All sent notifications, one per channel
+    pub fn sent(&self) -> Vec<SentNotification> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// Assert a notification was sent to `notifiable`
+    pub fn assert_notification_sent_to(&self, notifiable: &dyn Notifiable) {
+        let id = notifiable.id();
+        assert!(
+            self.count(|n| n.notifiable_id == id) > 0,
+            "Expected a notification to be sent to {}, sent to: {:?}",
+            id,
+            self.recipients()
+        );
+    }
+
+    /// Assert no notification was sent to `notifiable`
+    pub fn assert_notification_not_sent_to(&self, notifiable: &dyn Notifiable) {
+        let id = notifiable.id();
+        assert_eq!(
+            self.count(|n| n.notifiable_id == id),
+            0,
+            "Expected no notification to be sent to {}",
+            id
+        );
+    }
+
+    /// Assert a notification was sent to `notifiable` via `channel`
+    pub fn assert_sent_via(&self, notifiable: &dyn Notifiable, channel: Channel) {
+        let id = notifiable.id();
+        assert!(
+            self.count(|n| n.notifiable_id == id && n.channel == channel) > 0,
+            "Expected a notification to be sent to {} via {:?}, sent: {:?}",
+            id,
+            channel,
+            self.sent()
+        );
+    }
+
+    /// Assert a sent notification matches `predicate`
+    pub fn assert_sent(&self, predicate: impl Fn(&SentNotification) -> bool) {
+        assert!(
+            self.count(predicate) > 0,
+            "No sent notification matches, sent: {:?}",
+            self.sent()
+        );
+    }
+
+    /// Assert `count` notifications were sent, counting each channel
+    pub fn assert_sent_count(&self, count: usize) {
+        let sent = self.count(|_| true);
+        assert_eq!(
+            sent, count,
+            "Expected {} notifications, got {}",
+            count, sent
+        );
+    }
+
+    /// Assert no notification was sent
+    pub fn assert_nothing_sent(&self) {
+        let recipients = self.recipients();
+        assert!(
+            recipients.is_empty(),
+            "Expected no notifications, sent to: {:?}",
+            recipients
+        );
+    }
+
+    /// Forget the sent notifications
+    pub fn clear(&self) {
+        self.sent.lock().unwrap().clear();
+    }
+
+    fn count(&self, predicate: impl Fn(&SentNotification) -> bool) -> usize {
+        self.sent
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|n| predicate(n))
+            .count()
+    }
+
+    fn recipients(&self) -> Vec<String> {
+        self.sent
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|n| n.notifiable_id.clone())
+            .collect()
+    }
+}
+
+/// Channel handler recording into a [`FakeNotifications`]
+struct FakeChannel {
+    channel: Channel,
+    sent: Arc<Mutex<Vec<SentNotification>>>,
+}
+
+#[async_trait]
+impl ChannelHandler for FakeChannel {
+    async fn send(
+        &self,
+        notification: &dyn Notification,
+        notifiable: &dyn Notifiable,
+    ) -> NotificationResult<()> {
+        let mut sent = SentNotification {
+            notifiable_id: notifiable.id(),
+            channel: self.channel.clone(),
+            mail: None,
+            sms: None,
+            push: None,
+            database: None,
+        };
+        // Rendering errors surface like they would on the real channel
+        match self.channel {
+            Channel::Email => sent.mail = Some(notification.to_mail(notifiable)?),
+            Channel::Sms => sent.sms = Some(notification.to_sms(notifiable)?),
+            Channel::Push => sent.push = Some(notification.to_push(notifiable)?),
+            Channel::Database => sent.database = Some(notification.to_database(notifiable)?),
+        }
+
+        self.sent.lock().unwrap().push(sent);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct User(&'static str);
+
+    impl Notifiable for User {
+        fn id(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    struct LoginAlert;
+
+    impl Notification for LoginAlert {
+        fn via(&self, _notifiable: &dyn Notifiable) -> Vec<Channel> {
+            vec![Channel::Sms, Channel::Database]
+        }
+
+        fn to_sms(&self, _notifiable: &dyn Notifiable) -> NotificationResult<SmsMessage> {
+            Ok(SmsMessage::new("+41790000000", "New login"))
+        }
+
+        fn to_database(
+            &self,
+            _notifiable: &dyn Notifiable,
+        ) -> NotificationResult<DatabaseNotification> {
+            Ok(DatabaseNotification::new().title("New login"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_assertions() {
+        let notifications = FakeNotifications::new();
+        let mut manager = NotificationManager::new();
+        notifications.register(&mut manager);
+        notifications.assert_nothing_sent();
+
+        manager.send(&LoginAlert, &User("1")).await.unwrap();
+
+        notifications.assert_notification_sent_to(&User("1"));
+        notifications.assert_notification_not_sent_to(&User("2"));
+        notifications.assert_sent_via(&User("1"), Channel::Sms);
+        notifications.assert_sent_via(&User("1"), Channel::Database);
+        notifications.assert_sent(|n| n.sms.as_ref().is_some_and(|m| m.body == "New login"));
+        notifications.assert_sent_count(2);
+
+        let result =
+            std::panic::catch_unwind(|| notifications.assert_sent_via(&User("1"), Channel::Push));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! Fake queue
+
+use async_trait::async_trait;
+use rf_queue::{Job, JobMetadata, Queue, QueueError, QueueResult};
+use std::sync::{Arc, Mutex};
+
+/// Queue recording pushed jobs without running them
+///
+/// # Example
+///
+/// ```
+/// use rf_queue::{Job, JobMetadata, Queue, QueueError};
+/// use rf_testing::FakeQueue;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct SendInvoice {
+///     order_id: u64,
+/// }
+///
+/// #[async_trait::async_trait]
+/// impl Job for SendInvoice {
+///     async fn handle(&self) -> Result<(), QueueError> {
+///         Ok(())
+///     }
+///
+///     fn job_type(&self) -> &'static str {
+///         "send_invoice"
+///     }
+/// }
+///
+/// # async fn example() -> Result<(), QueueError> {
+/// let queue = FakeQueue::new();
+/// queue.push(JobMetadata::new(&SendInvoice { order_id: 7 })?).await?;
+///
+/// queue.assert_pushed("send_invoice");
+/// queue.assert_pushed_matching(|job: &SendInvoice| job.order_id == 7);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct FakeQueue {
+    pushed: Arc<Mutex<Vec<JobMetadata>>>,
+}
+
+impl FakeQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All pushed jobs
+    pub fn pushed(&self) -> Vec<JobMetadata> {
+        self.pushed.lock().unwrap().clone()
+    }
+
+    /// Pushed jobs of type `J`
+    pub fn jobs<J: Job>(&self) -> Vec<J> {
+        self.pushed
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|metadata| {
+                serde_json::from_slice::<J>(&metadata.data)
+                    .ok()
+                    .filter(|job| job.job_type() == metadata.job_type)
+            })
+            .collect()
+    }
+
+    /// Assert a job of `job_type` was pushed
+    pub fn assert_pushed(&self, job_type: &str) {
+        assert!(
+            self.count(|m| m.job_type == job_type) > 0,
+            "Expected a {} job to be pushed, pushed: {:?}",
+            job_type,
+            self.job_types()
+        );
+    }
+
+    /// Assert a job of `job_type` was pushed on `queue`
+    pub fn assert_pushed_on(&self, queue: &str, job_type: &str) {
+        assert!(
+            self.count(|m| m.queue == queue && m.job_type == job_type) > 0,
+            "Expected a {} job to be pushed on {}, pushed: {:?}",
+            job_type,
+            queue,
+            self.job_types()
+        );
+    }
+
+    /// Assert a pushed job of type `J` matches `predicate`
+    pub fn assert_pushed_matching<J: Job>(&self, predicate: impl Fn(&J) -> bool) {
+        assert!(
+            self.jobs::<J>().iter().any(predicate),
+            "Expected a matching {} job to be pushed, pushed: {:?}",
+            std::any::type_name::<J>(),
+            self.job_types()
+        );
+    }
+
+    /// Assert `count` jobs of `job_type` were pushed
+    pub fn assert_pushed_times(&self, job_type: &str, count: usize) {
+        let pushed = self.count(|m| m.job_type == job_type);
+        assert_eq!(
+            pushed, count,
+            "Expected {} {} jobs to be pushed, got {}",
+            count, job_type, pushed
+        );
+    }
+
+    /// Assert no job of `job_type` was pushed
+    pub fn assert_not_pushed(&self, job_type: &str) {
+        self.assert_pushed_times(job_type, 0);
+    }
+
+    /// Assert no job was pushed
+    pub fn assert_nothing_pushed(&self) {
+        let pushed = self.job_types();
+        assert!(pushed.is_empty(), "Expected no jobs, pushed: {:?}", pushed);
+    }
+
+    /// Forget the pushed jobs of all queues
+    pub fn reset(&self) {
+        self.pushed.lock().unwrap().clear();
+    }
+
+    fn count(&self, predicate: impl Fn(&JobMetadata) -> bool) -> usize {
+        self.pushed
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| predicate(m))
+            .count()
+    }
+
+    fn job_types(&self) -> Vec<String> {
+        self.pushed
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|m| m.job_type.clone())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Queue for FakeQueue {
+    async fn push(&self, metadata: JobMetadata) -> QueueResult<String> {
+        let id = metadata.id.clone();
+        self.pushed.lock().unwrap().push(metadata);
+        Ok(id)
+    }
+
+    /// Pushed jobs are never handed out, so workers stay idle
+    async fn reserve(&self, _queue: &str) -> QueueResult<Option<JobMetadata>> {
+        Ok(None)
+    }
+
+    async fn complete(&self, _job_id: &str) -> QueueResult<()> {
+        Ok(())
+    }
+
+    async fn fail(&self, _job_id: &str, _error: &str) -> QueueResult<()> {
+        Ok(())
+    }
+
+    async fn retry(&self, _metadata: JobMetadata) -> QueueResult<()> {
+        Ok(())
+    }
+
+    async fn size(&self, queue: &str) -> QueueResult<usize> {
+        Ok(self.count(|m| m.queue == queue))
+    }
+
+    async fn clear(&self, queue: &str) -> QueueResult<()> {
+        self.pushed.lock().unwrap().retain(|m| m.queue != queue);
+        Ok(())
+    }
+
+    async fn failed(&self, _queue: &str) -> QueueResult<Vec<JobMetadata>> {
+        Ok(Vec::new())
+    }
+
+    async fn requeue(&self, job_id: &str) -> QueueResult<()> {
+        Err(QueueError::JobNotFound(job_id.to_string()))
+    }
+
+    async fn forget(&self, job_id: &str) -> QueueResult<()> {
+        Err(QueueError::JobNotFound(job_id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Reindex {
+        id: u64,
+    }
+
+    #[async_trait]
+    impl Job for Reindex {
+        async fn handle(&self) -> Result<(), QueueError> {
+            Ok(())
+        }
+
+        fn job_type(&self) -> &'static str {
+            "reindex"
+        }
+
+        fn queue(&self) -> &str {
+            "search"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_assertions() {
+        let queue = FakeQueue::new();
+        queue.assert_nothing_pushed();
+
+        for id in [1, 2] {
+            queue
+                .push(JobMetadata::new(&Reindex { id }).unwrap())
+                .await
+                .unwrap();
+        }
+
+        queue.assert_pushed("reindex");
+        queue.assert_pushed_on("search", "reindex");
+        queue.assert_pushed_times("reindex", 2);
+        queue.assert_pushed_matching(|job: &Reindex| job.id == 2);
+        queue.assert_not_pushed("send_email");
+        assert_eq!(queue.size("search").await.unwrap(), 2);
+        assert!(queue.reserve("search").await.unwrap().is_none());
+
+        let result = std::panic::catch_unwind(|| queue.assert_pushed_on("default", "reindex"));
+        assert!(result.is_err());
+
+        queue.clear("search").await.unwrap();
+        queue.assert_nothing_pushed();
+    }
+}
@@ -2,7 +2,9 @@
 
 use axum::{body::Body, Router};
 use bytes::Bytes;
-use http::{Request, StatusCode};
+use http::{HeaderMap, HeaderName, HeaderValue, Request, StatusCode};
+use serde::Serialize;
+use serde_json::Value;
 use tower::util::ServiceExt;
 
 /// HTTP test client
@@ -28,76 +30,100 @@ use tower::util::ServiceExt;
 /// response.assert_ok().assert_json_path("name", "Test").await;
 /// # }
 /// ```
+#[derive(Clone)]
 pub struct HttpTester {
     app: Router,
+    headers: HeaderMap,
 }
 
 impl HttpTester {
     /// Create new HTTP tester
     pub fn new(app: Router) -> Self {
-        Self { app }
+        Self {
+            app,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Send `name: value` with every request
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name or value isn't a valid header.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(
+            HeaderName::try_from(name).expect("invalid header name"),
+            HeaderValue::try_from(value).expect("invalid header value"),
+        );
+        self
+    }
+
+    /// Send `Authorization: Bearer <token>` with every request
+    pub fn with_bearer_token(self, token: &str) -> Self {
+        self.with_header("authorization", &format!("Bearer {}", token))
     }
 
     /// Make GET request
     pub async fn get(&self, uri: &str) -> TestResponse {
-        self.request(
-            Request::builder()
-                .uri(uri)
-                .method("GET")
-                .body(Body::empty())
-                .unwrap(),
-        )
-        .await
+        self.send("GET", uri, None).await
     }
 
     /// Make POST request with JSON body
     pub async fn post(&self, uri: &str, body: serde_json::Value) -> TestResponse {
-        let body_str = serde_json::to_string(&body).unwrap();
+        self.post_json(uri, &body).await
+    }
 
-        self.request(
-            Request::builder()
-                .uri(uri)
-                .method("POST")
-                .header("content-type", "application/json")
-                .body(Body::from(body_str))
-                .unwrap(),
-        )
-        .await
+    /// Make POST request with `body` serialized to JSON
+    pub async fn post_json<T: Serialize + ?Sized>(&self, uri: &str, body: &T) -> TestResponse {
+        self.send("POST", uri, Some(to_json(body))).await
     }
 
     /// Make PUT request with JSON body
     pub async fn put(&self, uri: &str, body: serde_json::Value) -> TestResponse {
-        let body_str = serde_json::to_string(&body).unwrap();
+        self.put_json(uri, &body).await
+    }
 
-        self.request(
-            Request::builder()
-                .uri(uri)
-                .method("PUT")
-                .header("content-type", "application/json")
-                .body(Body::from(body_str))
-                .unwrap(),
-        )
-        .await
+    /// Make PUT request with `body` serialized to JSON
+    pub async fn put_json<T: Serialize + ?Sized>(&self, uri: &str, body: &T) -> TestResponse {
+        self.send("PUT", uri, Some(to_json(body))).await
+    }
+
+    /// Make PATCH request with `body` serialized to JSON
+    pub async fn patch_json<T: Serialize + ?Sized>(&self, uri: &str, body: &T) -> TestResponse {
+        self.send("PATCH", uri, Some(to_json(body))).await
     }
 
     /// Make DELETE request
     pub async fn delete(&self, uri: &str) -> TestResponse {
-        self.request(
-            Request::builder()
-                .uri(uri)
-                .method("DELETE")
-                .body(Body::empty())
-                .unwrap(),
-        )
-        .await
-    }
-
-    /// Make custom request
-    async fn request(&self, req: Request<Body>) -> TestResponse {
+        self.send("DELETE", uri, None).await
+    }
+
+    /// Make custom request, with the default headers added
+    pub async fn request(&self, mut req: Request<Body>) -> TestResponse {
+        for (name, value) in &self.headers {
+            if !req.headers().contains_key(name) {
+                req.headers_mut().insert(name, value.clone());
+            }
+        }
         let response = self.app.clone().oneshot(req).await.unwrap();
 
         TestResponse::new(response)
     }
+
+    async fn send(&self, method: &str, uri: &str, json: Option<String>) -> TestResponse {
+        let builder = Request::builder().uri(uri).method(method);
+        let req = match json {
+            Some(json) => builder
+                .header("content-type", "application/json")
+                .body(Body::from(json)),
+            None => builder.body(Body::empty()),
+        };
+        self.request(req.unwrap()).await
+    }
+}
+
+fn to_json<T: Serialize + ?Sized>(body: &T) -> String {
+    serde_json::to_string(body).expect("request body must serialize to JSON")
 }
 
 /// Test response wrapper with assertion methods
@@ -198,16 +224,29 @@ impl TestResponse {
         self
     }
 
-    /// Assert JSON contains a specific path
-    pub async fn assert_json_path(mut self, path: &str, expected: &str) -> Self {
-        let json: serde_json::Value = self.json().await;
-
-        let value = json.get(path);
-        assert!(value.is_some(), "JSON missing path: {}", path);
-
-        let value_str = value.unwrap().as_str().unwrap_or("");
-        assert_eq!(value_str, expected, "JSON path mismatch for {}", path);
+    /// Assert the JSON value at `path` equals `expected`
+    ///
+    /// Paths are dot-separated keys, with numbers indexing arrays, e.g.
+    /// `data.0.email`.
+    pub async fn assert_json_path(mut self, path: &str, expected: impl Into<Value>) -> Self {
+        let json: Value = self.json().await;
+        let expected = expected.into();
+
+        match json_path(&json, path) {
+            Some(actual) => assert_eq!(actual, &expected, "JSON path mismatch for {}", path),
+            None => panic!("JSON missing path: {}\nJSON: {}", path, json),
+        }
+        self
+    }
 
+    /// Assert there is no JSON value at `path`
+    pub async fn assert_json_missing(mut self, path: &str) -> Self {
+        let json: Value = self.json().await;
+        assert!(
+            json_path(&json, path).is_none(),
+            "JSON has unexpected path: {}",
+            path
+        );
         self
     }
 
@@ -236,6 +275,16 @@ impl TestResponse {
     }
 }
 
+/// Value at a dot-separated path
+fn json_path<'a>(json: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .try_fold(json, |value, segment| match value {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            value => value.get(segment),
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -302,7 +351,35 @@ mod tests {
         let app = Router::new().route("/user", get(handler));
         let client = HttpTester::new(app);
 
-        // Note: This is a simplified test. Real path navigation would need more work
-        // For now we test single-level paths
+        client
+            .get("/user")
+            .await
+            .assert_json_path("user.name", "Alice")
+            .await
+            .assert_json_path("user.age", 30)
+            .await
+            .assert_json_missing("user.email")
+            .await;
+    }
+
+    #[test]
+    fn test_json_path_arrays() {
+        let json = json!({"data": [{"id": 1}, {"id": 2}]});
+        assert_eq!(json_path(&json, "data.1.id"), Some(&json!(2)));
+        assert_eq!(json_path(&json, "data.2.id"), None);
+        assert_eq!(json_path(&json, "data.first"), None);
+    }
+
+    #[tokio::test]
+    async fn test_default_headers() {
+        async fn handler(headers: http::HeaderMap) -> String {
+            headers["authorization"].to_str().unwrap().to_string()
+        }
+
+        let app = Router::new().route("/me", axum::routing::patch(handler));
+        let client = HttpTester::new(app).with_bearer_token("secret");
+
+        let mut response = client.patch_json("/me", &json!({"name": "Bob"})).await;
+        assert_eq!(response.body().await.as_ref(), b"Bearer secret");
     }
 }
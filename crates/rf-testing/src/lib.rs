@@ -8,6 +8,10 @@
 //! - HTTP testing with fluent API
 //! - Custom assertions for common patterns
 //! - Test response helpers
+//! - Time travel with [`TestClock`]
+//! - Transactions rolled back after each test (`database` feature)
+//! - Fakes with assertions for the queue (`queue`), mail (`mail`),
+//!   notifications (`notifications`) and events (`events`)
 //!
 //! # Quick Start
 //!
@@ -57,13 +61,28 @@
 //! assert_in_range(5, 1, 10);
 //! ```
 
+mod clock;
+#[cfg(feature = "database")]
+mod database;
 mod error;
+mod fakes;
 mod http;
 pub mod assertions;
 pub mod factory;
 pub mod seeder;
 
+pub use clock::{Clock, SystemClock, TestClock};
+#[cfg(feature = "database")]
+pub use database::TestTransaction;
 pub use error::{TestError, TestResult};
+#[cfg(feature = "events")]
+pub use fakes::{DispatchedEvent, FakeEvents};
+#[cfg(feature = "mail")]
+pub use fakes::FakeMailer;
+#[cfg(feature = "notifications")]
+pub use fakes::{FakeNotifications, SentNotification};
+#[cfg(feature = "queue")]
+pub use fakes::FakeQueue;
 pub use http::{HttpTester, TestResponse};
 pub use factory::{Factory, FactoryBuilder, FakeData};
 pub use seeder::{Seeder, DatabaseSeeder};
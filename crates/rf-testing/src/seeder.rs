@@ -26,6 +26,7 @@ impl DatabaseSeeder {
     }
 
     /// Add a seeder
+    #[allow(clippy::should_implement_trait)]
     pub fn add<S: Seeder + 'static>(mut self, seeder: S) -> Self {
         self.seeders.push(Arc::new(seeder));
         self
@@ -0,0 +1,817 @@
+//! Direct-to-S3 uploads
+//!
+//! Large files shouldn't pass through the application. The client asks
+//! [`DirectUploads::prepare`] for a presigned `PUT` URL or `POST` form, uploads
+//! the file straight to the bucket and then reports back with the token it
+//! got; [`DirectUploads::complete`] checks the object and registers it in the
+//! [`UploadRepository`].
+//!
+//! The presigned request pins the key, the content type, the exact size and
+//! the SHA-256 of the content, which S3 verifies, so the registered file is
+//! what the client announced. Before registering, the object goes through the
+//! same checks as uploads passing through the application: its type is
+//! sniffed from the content and the installed [`ScanPolicy`](crate::ScanPolicy)
+//! scans it. The object is streamed for these checks; only its first bytes
+//! are kept, unless a scanner needs all of it. Each token completes one
+//! upload only.
+//!
+//! ```no_run
+//! use axum::Router;
+//! use rf_storage::{S3Config, S3Storage, UrlSigner};
+//! use rf_upload::{DirectUploads, FieldRules, InMemoryUploadStore, UploadRepository};
+//!
+//! # fn example(config: S3Config) {
+//! let uploads = DirectUploads::new(
+//!     S3Storage::new(config),
+//!     UploadRepository::new(InMemoryUploadStore::new(), "s3"),
+//!     UrlSigner::new("app-key"),
+//! )
+//! .rules(FieldRules::new().allow("video/").max_size(5 * 1024 * 1024 * 1024));
+//!
+//! let app: Router = Router::new().nest("/uploads/direct", uploads.router());
+//! # }
+//! ```
+
+use crate::{
+    sanitize_filename, scan::check_signature, ContentHasher, Disks, FieldRules, FileUpload,
+    ScanPolicy, UploadError, UploadRecord, UploadRepository, UploadResult, UploadedFile,
+};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Extension, Json, Router,
+};
+use base64::{
+    engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD},
+    Engine,
+};
+use futures_util::StreamExt;
+use mime::Mime;
+use rf_storage::{Filesystem, PostPolicy, S3Storage, UrlSigner};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Time a client has to report a finished upload after the presigned
+/// request expired
+const COMPLETION_GRACE: Duration = Duration::from_secs(60 * 60);
+
+/// Leading bytes of an object kept to sniff its type
+const SNIFF_LEN: usize = 8 * 1024;
+
+/// The user uploading, for handlers behind [`DirectUploads::router`]
+///
+/// Insert it as a request extension (e.g. from the authentication
+/// middleware) to record the owner and to make sure only they can complete
+/// their upload.
+#[derive(Debug, Clone)]
+pub struct UploadOwner(pub String);
+
+/// How the client sends the file to S3
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DirectUploadMethod {
+    /// `PUT` the raw content to the URL, with the returned headers
+    #[default]
+    Put,
+    /// `POST` a multipart form with the returned fields and a `file` field
+    Post,
+}
+
+/// A file the client wants to upload
+#[derive(Debug, Clone, Deserialize)]
+pub struct DirectUploadRequest {
+    pub filename: String,
+    pub content_type: String,
+    pub size: u64,
+    /// Hex-encoded SHA-256 of the content
+    pub sha256: String,
+    #[serde(default)]
+    pub method: DirectUploadMethod,
+}
+
+/// Instructions for uploading a file to S3
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectUpload {
+    pub method: DirectUploadMethod,
+    pub url: String,
+    /// Headers to send with a `PUT`
+    pub headers: BTreeMap<String, String>,
+    /// Form fields to send with a `POST`
+    pub fields: BTreeMap<String, String>,
+    pub key: String,
+    /// Pass to [`DirectUploads::complete`] once the upload finished
+    pub token: String,
+    /// Unix timestamp after which S3 rejects the upload
+    pub expires_at: u64,
+}
+
+/// What a token grants, signed so the client can't change it
+#[derive(Serialize, Deserialize)]
+struct PendingUpload {
+    key: String,
+    filename: String,
+    content_type: String,
+    size: u64,
+    sha256: String,
+    owner: Option<String>,
+}
+
+/// Presigned uploads to S3, registered in an [`UploadRepository`]
+///
+/// `s3` presigns the requests; the repository's disk must be registered in
+/// [`Disks`] for the same bucket, it is used to check uploaded objects.
+#[derive(Clone)]
+pub struct DirectUploads {
+    inner: Arc<Inner>,
+}
+
+#[derive(Clone)]
+struct Inner {
+    s3: S3Storage,
+    repository: UploadRepository,
+    signer: UrlSigner,
+    rules: FieldRules,
+    expires_in: Duration,
+    max_scan_size: u64,
+}
+
+impl DirectUploads {
+    pub fn new(s3: S3Storage, repository: UploadRepository, signer: UrlSigner) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                s3,
+                repository,
+                signer,
+                rules: FieldRules::new(),
+                expires_in: Duration::from_secs(15 * 60),
+                max_scan_size: 100 * 1024 * 1024,
+            }),
+        }
+    }
+
+    /// Allowed types and maximum size of uploaded files
+    pub fn rules(mut self, rules: FieldRules) -> Self {
+        Arc::make_mut(&mut self.inner).rules = rules;
+        self
+    }
+
+    /// Validity of presigned requests (default: 15 minutes)
+    pub fn expires_in(mut self, expires_in: Duration) -> Self {
+        Arc::make_mut(&mut self.inner).expires_in = expires_in;
+        self
+    }
+
+    /// Largest object read into memory for the installed scanner
+    /// (default: 100 MiB)
+    ///
+    /// Scanners get the whole file, so while one is installed, larger
+    /// uploads are rejected on completion. Without a scanner objects are
+    /// streamed whatever their size.
+    pub fn max_scan_size(mut self, bytes: u64) -> Self {
+        Arc::make_mut(&mut self.inner).max_scan_size = bytes;
+        self
+    }
+
+    /// Routes for preparing (`POST /`) and completing (`POST /complete`)
+    /// uploads, with JSON bodies
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", post(prepare))
+            .route("/complete", post(complete))
+            .with_state(self.clone())
+    }
+
+    /// Presign the upload of a file for `owner`
+    pub fn prepare(
+        &self,
+        request: &DirectUploadRequest,
+        owner: Option<&str>,
+    ) -> UploadResult<DirectUpload> {
+        let inner = &self.inner;
+        let content_type: Mime = request
+            .content_type
+            .parse()
+            .map_err(|_| UploadError::InvalidMimeType(request.content_type.clone()))?;
+        if !inner.rules.allows(&content_type) {
+            return Err(UploadError::InvalidMimeType(content_type.to_string()));
+        }
+        if let Some(max) = inner.rules.max_size.filter(|max| request.size > *max) {
+            return Err(UploadError::FileTooLarge(request.size, max));
+        }
+        let checksum = match hex::decode(&request.sha256) {
+            Ok(digest) if digest.len() == 32 => BASE64.encode(digest),
+            _ => return Err(UploadError::InvalidChecksum(request.sha256.clone())),
+        };
+
+        let filename = sanitize_filename(&request.filename);
+        let key = inner.repository.blob_path(&filename);
+        let content_type = content_type.to_string();
+        let mut headers = BTreeMap::new();
+        let mut fields = BTreeMap::new();
+        let url = match request.method {
+            DirectUploadMethod::Put => {
+                headers.insert("Content-Type".to_string(), content_type.clone());
+                headers.insert("Content-Length".to_string(), request.size.to_string());
+                headers.insert("x-amz-checksum-sha256".to_string(), checksum);
+                let signed: Vec<(&str, &str)> = headers
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str()))
+                    .collect();
+                inner.s3.presign_put(&key, inner.expires_in, &signed)?
+            }
+            DirectUploadMethod::Post => {
+                let policy = PostPolicy::new(&key)
+                    .content_length_range(request.size, request.size)
+                    .field("Content-Type", &content_type)
+                    .field("x-amz-checksum-sha256", checksum);
+                let post = inner
+                    .s3
+                    .presign_post(&policy, inner.expires_in, chrono::Utc::now())?;
+                fields = post.fields;
+                post.url
+            }
+        };
+
+        let pending = PendingUpload {
+            key: key.clone(),
+            filename,
+            content_type,
+            size: request.size,
+            sha256: request.sha256.to_ascii_lowercase(),
+            owner: owner.map(str::to_string),
+        };
+        Ok(DirectUpload {
+            method: request.method,
+            url,
+            headers,
+            fields,
+            key,
+            token: self.token(&pending)?,
+            expires_at: now() + inner.expires_in.as_secs(),
+        })
+    }
+
+    /// Register the file uploaded with `token` for `owner`
+    ///
+    /// Fails if the object isn't in the bucket yet. An object with another
+    /// size or checksum, a type that doesn't match its content or the rules,
+    /// or that the installed [`ScanPolicy`](crate::ScanPolicy) rejects is
+    /// deleted. Once the object was found, the token is used up, even if the
+    /// checks fail.
+    ///
+    /// The object is streamed for the checks. While a scanner is installed
+    /// it is read into memory, up to [`DirectUploads::max_scan_size`].
+    pub async fn complete(&self, token: &str, owner: Option<&str>) -> UploadResult<UploadRecord> {
+        let pending = self.verify(token)?;
+        if pending.owner.as_deref() != owner {
+            return Err(UploadError::InvalidToken(
+                "upload belongs to another user".into(),
+            ));
+        }
+
+        let repository = &self.inner.repository;
+        let storage = Disks::get(repository.disk())?;
+        if !storage.exists(&pending.key).await? {
+            return Err(UploadError::UploadNotFound(pending.key));
+        }
+        if !repository.claim(&pending.key).await? {
+            return Err(UploadError::InvalidToken(
+                "upload was completed already".into(),
+            ));
+        }
+        let size = storage.size(&pending.key).await?;
+        if size != pending.size {
+            storage.delete(&pending.key).await?;
+            return Err(UploadError::SizeMismatch(size, pending.size));
+        }
+        let mime_type = match self.check_object(storage.as_ref(), &pending).await {
+            Ok(mime_type) => mime_type,
+            Err(e) => {
+                storage.delete(&pending.key).await?;
+                return Err(e);
+            }
+        };
+
+        let file = UploadedFile {
+            filename: pending.filename,
+            path: PathBuf::from(&pending.key),
+            disk: Some(repository.disk().to_string()),
+            size,
+            mime_type: mime_type.to_string(),
+        };
+        repository
+            .register(&file, &pending.sha256, pending.owner.as_deref())
+            .await
+    }
+
+    /// Checks buffered uploads get: the checksum, the type sniffed from the
+    /// content against the rules and the installed scanner
+    async fn check_object(
+        &self,
+        storage: &dyn Filesystem,
+        pending: &PendingUpload,
+    ) -> UploadResult<Mime> {
+        let policy = ScanPolicy::installed();
+        let keep = match &policy {
+            Some(_) if pending.size > self.inner.max_scan_size => {
+                return Err(UploadError::FileTooLarge(
+                    pending.size,
+                    self.inner.max_scan_size,
+                ));
+            }
+            Some(_) => pending.size as usize,
+            None => SNIFF_LEN,
+        };
+        let (sha256, content) = read_object(storage, &pending.key, keep).await?;
+        if sha256 != pending.sha256 {
+            return Err(UploadError::InvalidChecksum(pending.sha256.clone()));
+        }
+
+        let declared: Mime = pending
+            .content_type
+            .parse()
+            .map_err(|_| UploadError::InvalidMimeType(pending.content_type.clone()))?;
+        let mime_type = check_signature(&declared, &content)?;
+        if !self.inner.rules.allows(&mime_type) {
+            return Err(UploadError::InvalidMimeType(mime_type.to_string()));
+        }
+        if let Some(policy) = policy {
+            let upload = FileUpload::from_bytes(&pending.filename, mime_type.clone(), content);
+            policy.check(&upload).await?;
+        }
+        Ok(mime_type)
+    }
+
+    /// `payload.expires.signature`, valid a while longer than the
+    /// presigned request so slow uploads can still be completed
+    fn token(&self, pending: &PendingUpload) -> UploadResult<String> {
+        let payload =
+            serde_json::to_string(pending).map_err(|e| UploadError::InvalidToken(e.to_string()))?;
+        let query = self
+            .inner
+            .signer
+            .sign(&payload, self.inner.expires_in + COMPLETION_GRACE);
+        let mut expires = "";
+        let mut signature = "";
+        for pair in query.split('&') {
+            match pair.split_once('=') {
+                Some(("expires", value)) => expires = value,
+                Some(("signature", value)) => signature = value,
+                _ => {}
+            }
+        }
+        Ok(format!(
+            "{}.{}.{}",
+            URL_SAFE_NO_PAD.encode(payload),
+            expires,
+            signature
+        ))
+    }
+
+    fn verify(&self, token: &str) -> UploadResult<PendingUpload> {
+        let malformed = || UploadError::InvalidToken("malformed token".into());
+        let mut parts = token.splitn(3, '.');
+        let (Some(payload), Some(expires), Some(signature)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(malformed());
+        };
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload)
+            .ok()
+            .and_then(|payload| String::from_utf8(payload).ok())
+            .ok_or_else(malformed)?;
+        let expires = expires.parse().map_err(|_| malformed())?;
+
+        self.inner
+            .signer
+            .verify(&payload, expires, signature)
+            .map_err(|e| UploadError::InvalidToken(e.to_string()))?;
+        serde_json::from_str(&payload).map_err(|_| malformed())
+    }
+}
+
+#[derive(Deserialize)]
+struct CompleteRequest {
+    token: String,
+}
+
+async fn prepare(
+    State(uploads): State<DirectUploads>,
+    owner: Option<Extension<UploadOwner>>,
+    Json(request): Json<DirectUploadRequest>,
+) -> Response {
+    let owner = owner.map(|Extension(UploadOwner(owner))| owner);
+    match uploads.prepare(&request, owner.as_deref()) {
+        Ok(upload) => Json(upload).into_response(),
+        Err(e) => error_response(e),
+    }
+}
+
+async fn complete(
+    State(uploads): State<DirectUploads>,
+    owner: Option<Extension<UploadOwner>>,
+    Json(request): Json<CompleteRequest>,
+) -> Response {
+    let owner = owner.map(|Extension(UploadOwner(owner))| owner);
+    match uploads.complete(&request.token, owner.as_deref()).await {
+        Ok(record) => (StatusCode::CREATED, Json(record)).into_response(),
+        Err(e) => error_response(e),
+    }
+}
+
+fn error_response(error: UploadError) -> Response {
+    let status = match &error {
+        UploadError::FileTooLarge(..) => StatusCode::PAYLOAD_TOO_LARGE,
+        UploadError::InvalidMimeType(_) | UploadError::MimeMismatch(..) => {
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        }
+        UploadError::InvalidChecksum(_)
+        | UploadError::SizeMismatch(..)
+        | UploadError::Infected(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        UploadError::InvalidToken(_) => StatusCode::FORBIDDEN,
+        UploadError::UploadNotFound(_) => StatusCode::NOT_FOUND,
+        _ => {
+            tracing::error!("Direct upload failed: {}", error);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    (
+        status,
+        Json(serde_json::json!({ "error": error.to_string() })),
+    )
+        .into_response()
+}
+
+/// SHA-256 of the object at `key` and its first `keep` bytes
+async fn read_object(
+    storage: &dyn Filesystem,
+    key: &str,
+    keep: usize,
+) -> UploadResult<(String, Vec<u8>)> {
+    let mut stream = storage.read_stream(key).await?;
+    let mut hasher = ContentHasher::new();
+    let mut head = Vec::with_capacity(keep.min(SNIFF_LEN));
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        hasher.update(&chunk);
+        let take = keep.saturating_sub(head.len()).min(chunk.len());
+        head.extend_from_slice(&chunk[..take]);
+    }
+    Ok((hasher.finish(), head))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryUploadStore;
+    use axum::{body::Body, http::Request};
+    use rf_storage::{Filesystem, MemoryStorage, S3Config};
+    use std::collections::HashMap;
+    use tower::ServiceExt;
+
+    const HELLO_SHA256: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    fn uploads(disk: &str) -> (DirectUploads, MemoryStorage) {
+        // The bucket is simulated by a memory disk
+        let storage = MemoryStorage::new();
+        Disks::register(disk, storage.clone());
+        let s3 = S3Storage::new(S3Config {
+            bucket: "uploads".to_string(),
+            region: "eu-central-1".to_string(),
+            endpoint: None,
+            access_key: "access".to_string(),
+            secret_key: "secret".to_string(),
+            path_style: false,
+        });
+        let repository = UploadRepository::new(InMemoryUploadStore::new(), disk);
+        let uploads = DirectUploads::new(s3, repository, UrlSigner::new("app-key"))
+            .rules(FieldRules::new().allow("text/").max_size(1024));
+        (uploads, storage)
+    }
+
+    fn request(method: DirectUploadMethod) -> DirectUploadRequest {
+        DirectUploadRequest {
+            filename: "hello world.txt".to_string(),
+            content_type: "text/plain".to_string(),
+            size: 11,
+            sha256: HELLO_SHA256.to_string(),
+            method,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prepare() {
+        let (uploads, _) = uploads("direct-prepare");
+
+        let put = uploads
+            .prepare(&request(DirectUploadMethod::Put), None)
+            .unwrap();
+        assert!(put.key.starts_with("uploads/") && put.key.ends_with(".txt"));
+        assert!(put.url.contains(&put.key));
+        assert!(put.url.contains(
+            "X-Amz-SignedHeaders=content-length%3Bcontent-type%3Bhost%3Bx-amz-checksum-sha256"
+        ));
+        assert_eq!(
+            put.headers["x-amz-checksum-sha256"],
+            "uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek="
+        );
+
+        let post = uploads
+            .prepare(&request(DirectUploadMethod::Post), None)
+            .unwrap();
+        assert_eq!(post.fields["key"], post.key);
+        assert!(post.fields.contains_key("policy"));
+        assert!(post.headers.is_empty());
+
+        let mut large = request(DirectUploadMethod::Put);
+        large.size = 2048;
+        assert!(matches!(
+            uploads.prepare(&large, None),
+            Err(UploadError::FileTooLarge(2048, 1024))
+        ));
+        let mut image = request(DirectUploadMethod::Put);
+        image.content_type = "image/png".to_string();
+        assert!(matches!(
+            uploads.prepare(&image, None),
+            Err(UploadError::InvalidMimeType(_))
+        ));
+        let mut checksum = request(DirectUploadMethod::Put);
+        checksum.sha256 = "abc".to_string();
+        assert!(matches!(
+            uploads.prepare(&checksum, None),
+            Err(UploadError::InvalidChecksum(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_complete() {
+        let (uploads, storage) = uploads("direct-complete");
+        let first = uploads
+            .prepare(&request(DirectUploadMethod::Put), Some("alice"))
+            .unwrap();
+
+        assert!(matches!(
+            uploads.complete(&first.token, Some("alice")).await,
+            Err(UploadError::UploadNotFound(_))
+        ));
+        storage
+            .put(&first.key, b"hello world".to_vec())
+            .await
+            .unwrap();
+        assert!(matches!(
+            uploads.complete(&first.token, Some("bob")).await,
+            Err(UploadError::InvalidToken(_))
+        ));
+        let tampered = format!("{}0", first.token);
+        assert!(matches!(
+            uploads.complete(&tampered, Some("alice")).await,
+            Err(UploadError::InvalidToken(_))
+        ));
+
+        let record = uploads.complete(&first.token, Some("alice")).await.unwrap();
+        assert_eq!(record.hash, HELLO_SHA256);
+        assert_eq!(record.filename, "hello_world.txt");
+        assert_eq!(record.path, first.key);
+        assert_eq!(record.owner.as_deref(), Some("alice"));
+
+        // Tokens are single-use
+        assert!(matches!(
+            uploads.complete(&first.token, Some("alice")).await,
+            Err(UploadError::InvalidToken(_))
+        ));
+        assert_eq!(
+            uploads
+                .inner
+                .repository
+                .for_owner("alice")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // The same content again is deduplicated
+        let second = uploads
+            .prepare(&request(DirectUploadMethod::Put), Some("alice"))
+            .unwrap();
+        storage
+            .put(&second.key, b"hello world".to_vec())
+            .await
+            .unwrap();
+        let record = uploads
+            .complete(&second.token, Some("alice"))
+            .await
+            .unwrap();
+        assert_eq!(record.path, first.key);
+        assert!(!storage.exists(&second.key).await.unwrap());
+
+        // Wrong size
+        let third = uploads
+            .prepare(&request(DirectUploadMethod::Put), None)
+            .unwrap();
+        storage.put(&third.key, b"hello".to_vec()).await.unwrap();
+        assert!(matches!(
+            uploads.complete(&third.token, None).await,
+            Err(UploadError::SizeMismatch(5, 11))
+        ));
+        assert!(!storage.exists(&third.key).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_complete_checks_content() {
+        const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let (uploads, storage) = uploads("direct-content");
+
+        // A PNG announced as text
+        let mut hasher = ContentHasher::new();
+        hasher.update(PNG);
+        let disguised = DirectUploadRequest {
+            filename: "notes.txt".to_string(),
+            content_type: "text/plain".to_string(),
+            size: PNG.len() as u64,
+            sha256: hasher.finish(),
+            method: DirectUploadMethod::Put,
+        };
+        let upload = uploads.prepare(&disguised, None).unwrap();
+        storage.put(&upload.key, PNG.to_vec()).await.unwrap();
+        assert!(matches!(
+            uploads.complete(&upload.token, None).await,
+            Err(UploadError::MimeMismatch(declared, detected))
+                if declared == "text/plain" && detected == "image/png"
+        ));
+        assert!(!storage.exists(&upload.key).await.unwrap());
+
+        // Content that doesn't match the announced checksum
+        let upload = uploads
+            .prepare(&request(DirectUploadMethod::Put), None)
+            .unwrap();
+        storage
+            .put(&upload.key, b"HELLO WORLD".to_vec())
+            .await
+            .unwrap();
+        assert!(matches!(
+            uploads.complete(&upload.token, None).await,
+            Err(UploadError::InvalidChecksum(_))
+        ));
+        assert!(!storage.exists(&upload.key).await.unwrap());
+    }
+
+    /// A bucket behind a local server speaking the S3 object API
+    async fn fake_bucket() -> (S3Storage, Arc<std::sync::Mutex<HashMap<String, Vec<u8>>>>) {
+        use axum::http::{HeaderMap, Method, StatusCode, Uri};
+
+        type Objects = Arc<std::sync::Mutex<HashMap<String, Vec<u8>>>>;
+        async fn handle(
+            State(objects): State<Objects>,
+            method: Method,
+            uri: Uri,
+            headers: HeaderMap,
+            body: bytes::Bytes,
+        ) -> Response {
+            if !headers.contains_key("authorization") {
+                return StatusCode::FORBIDDEN.into_response();
+            }
+            let key = uri.path().trim_start_matches("/uploads/").to_string();
+            let mut objects = objects.lock().unwrap();
+            match method {
+                Method::PUT => {
+                    objects.insert(key, body.to_vec());
+                    StatusCode::OK.into_response()
+                }
+                Method::GET | Method::HEAD => match objects.get(&key) {
+                    Some(object) => object.clone().into_response(),
+                    None => StatusCode::NOT_FOUND.into_response(),
+                },
+                Method::DELETE => {
+                    objects.remove(&key);
+                    StatusCode::NO_CONTENT.into_response()
+                }
+                _ => StatusCode::NOT_IMPLEMENTED.into_response(),
+            }
+        }
+
+        let objects = Objects::default();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().fallback(handle).with_state(objects.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let s3 = S3Storage::new(S3Config {
+            bucket: "uploads".to_string(),
+            region: "eu-central-1".to_string(),
+            endpoint: Some(endpoint),
+            access_key: "access".to_string(),
+            secret_key: "secret".to_string(),
+            path_style: true,
+        });
+        (s3, objects)
+    }
+
+    #[tokio::test]
+    async fn test_complete_on_s3() {
+        let (s3, objects) = fake_bucket().await;
+        Disks::register("direct-s3", s3.clone());
+        let repository = UploadRepository::new(InMemoryUploadStore::new(), "direct-s3");
+        let uploads = DirectUploads::new(s3.clone(), repository, UrlSigner::new("app-key"))
+            .rules(FieldRules::new().allow("text/").max_size(1024));
+
+        let upload = uploads
+            .prepare(&request(DirectUploadMethod::Put), None)
+            .unwrap();
+        assert!(matches!(
+            uploads.complete(&upload.token, None).await,
+            Err(UploadError::UploadNotFound(_))
+        ));
+
+        // What the client sends to the presigned URL
+        s3.put(&upload.key, b"hello world".to_vec()).await.unwrap();
+        let record = uploads.complete(&upload.token, None).await.unwrap();
+        assert_eq!(record.hash, HELLO_SHA256);
+        assert_eq!(record.size, 11);
+        assert_eq!(record.path, upload.key);
+
+        // Rejected objects are deleted from the bucket
+        let upload = uploads
+            .prepare(&request(DirectUploadMethod::Put), None)
+            .unwrap();
+        s3.put(&upload.key, b"HELLO WORLD".to_vec()).await.unwrap();
+        assert!(matches!(
+            uploads.complete(&upload.token, None).await,
+            Err(UploadError::InvalidChecksum(_))
+        ));
+        assert!(!objects.lock().unwrap().contains_key(&upload.key));
+    }
+
+    #[tokio::test]
+    async fn test_read_object_keeps_only_the_head() {
+        let storage = MemoryStorage::new();
+        let content: Vec<u8> = (0..SNIFF_LEN * 3).map(|i| i as u8).collect();
+        storage.put("video.mp4", content.clone()).await.unwrap();
+        let mut hasher = ContentHasher::new();
+        hasher.update(&content);
+
+        let (sha256, head) = read_object(&storage, "video.mp4", SNIFF_LEN).await.unwrap();
+        assert_eq!(sha256, hasher.finish());
+        assert_eq!(head, &content[..SNIFF_LEN]);
+    }
+
+    #[tokio::test]
+    async fn test_router() {
+        let (uploads, storage) = uploads("direct-router");
+        let router = uploads.router();
+        let send = |uri: &str, body: serde_json::Value| {
+            router.clone().oneshot(
+                Request::post(uri)
+                    .header("content-type", "application/json")
+                    .extension(UploadOwner("alice".to_string()))
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+        };
+
+        let response = send(
+            "/",
+            serde_json::json!({
+                "filename": "a.txt",
+                "content_type": "text/plain",
+                "size": 11,
+                "sha256": HELLO_SHA256,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let upload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(upload["method"], "put");
+
+        let key = upload["key"].as_str().unwrap();
+        storage.put(key, b"hello world".to_vec()).await.unwrap();
+        let response = send("/complete", serde_json::json!({ "token": upload["token"] }))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let response = send("/complete", serde_json::json!({ "token": "x.1.y" }))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,101 @@
+//! Named storage disks
+//!
+//! Files stored with [`FileUpload::store_on`](crate::FileUpload::store_on)
+//! remember the name of their disk, so [`UploadedFile::url`] and
+//! [`UploadedFile::temporary_url`] can find the backend later, e.g. after
+//! the record was loaded from the database.
+//!
+//! ```no_run
+//! use rf_storage::{LocalStorage, UrlSigner};
+//! use rf_upload::Disks;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let local = LocalStorage::new("storage/app", "https://example.com").await?;
+//! Disks::register("local", local.with_signer(UrlSigner::new("app-key")));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Applications configuring an [`rf_storage::Storage`] facade register all
+//! of its disks at once with [`Disks::register_storage`].
+
+use crate::{UploadError, UploadResult, UploadedFile};
+use rf_storage::{Filesystem, Storage};
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock, RwLock},
+    time::Duration,
+};
+
+type Registry = RwLock<HashMap<String, Arc<dyn Filesystem>>>;
+
+fn registry() -> &'static Registry {
+    static DISKS: OnceLock<Registry> = OnceLock::new();
+    DISKS.get_or_init(Default::default)
+}
+
+/// Registry of storage backends by name
+pub struct Disks;
+
+impl Disks {
+    /// Register `storage` as disk `name`, replacing an existing one
+    pub fn register(name: impl Into<String>, storage: impl Filesystem + 'static) {
+        Self::register_arc(name, Arc::new(storage));
+    }
+
+    /// Register an already shared storage backend
+    pub fn register_arc(name: impl Into<String>, storage: Arc<dyn Filesystem>) {
+        registry()
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name.into(), storage);
+    }
+
+    /// Storage backend of disk `name`
+    pub fn get(name: &str) -> UploadResult<Arc<dyn Filesystem>> {
+        registry()
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(name)
+            .cloned()
+            .ok_or_else(|| UploadError::UnknownDisk(name.to_string()))
+    }
+
+    /// Register every disk of `storage` under its name
+    pub fn register_storage(storage: &Storage) {
+        for name in storage.disk_names() {
+            if let Ok(disk) = storage.disk(&name) {
+                Self::register_arc(name, disk);
+            }
+        }
+    }
+}
+
+impl UploadedFile {
+    /// Storage key of the file on its disk
+    pub fn key(&self) -> String {
+        self.path.to_string_lossy().replace('\\', "/")
+    }
+
+    /// Public URL of the file
+    pub fn url(&self) -> UploadResult<String> {
+        Ok(self.storage()?.url(&self.key()))
+    }
+
+    /// URL granting access to the file for `expires_in`
+    ///
+    /// S3 disks return a presigned URL; local disks need a
+    /// [`UrlSigner`](rf_storage::UrlSigner) and a
+    /// [`signed_file_router`](crate::signed_file_router) serving the links.
+    pub fn temporary_url(&self, expires_in: Duration) -> UploadResult<String> {
+        Ok(self.storage()?.temporary_url(&self.key(), expires_in)?)
+    }
+
+    pub(crate) fn storage(&self) -> UploadResult<Arc<dyn Filesystem>> {
+        let disk = self
+            .disk
+            .as_deref()
+            .ok_or_else(|| UploadError::NotOnDisk(self.path.display().to_string()))?;
+        Disks::get(disk)
+    }
+}
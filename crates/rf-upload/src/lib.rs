@@ -1,6 +1,60 @@
 //! File upload utilities for RustForge
 //!
 //! This crate provides file upload handling, validation, and image processing.
+//!
+//! Files stored on a named [`Disks`] entry (any `rf_storage::Filesystem`
+//! backend) can hand out public and time-limited URLs via
+//! [`UploadedFile::url`] and [`UploadedFile::temporary_url`].
+//!
+//! Large files can be uploaded in chunks and resumed after interruptions
+//! with the tus protocol, see [`TusServer`].
+//!
+//! [`FileUpload::validate_content`] checks the declared content type against
+//! the file signature, and an installed [`ScanPolicy`] scans every upload for
+//! malware before it is stored.
+//!
+//! With the "image-processing" feature, [`ImageVariants`] installed for a
+//! disk generate thumbnails and converted copies of uploaded images. With
+//! "progress" as well, `GenerateVariants::run_tracked` reports the variants
+//! as rf-progress steps.
+//!
+//! [`UploadRepository`] keeps upload records and stores identical content
+//! only once.
+//!
+//! [`MultipartUploads::parse`] reads forms with several files and text
+//! fields, applying [`FieldRules`] per field. Large files can be streamed
+//! straight to storage with [`FileUpload::stream_field`].
+//!
+//! [`DirectUploads`] lets browsers upload straight to S3 with presigned
+//! requests and registers the files in an [`UploadRepository`] afterwards.
+
+mod direct;
+mod disk;
+mod multipart;
+mod repository;
+mod scan;
+mod serve;
+mod stream;
+mod tus;
+#[cfg(feature = "image-processing")]
+mod variants;
+
+pub use direct::{
+    DirectUpload, DirectUploadMethod, DirectUploadRequest, DirectUploads, UploadOwner,
+};
+pub use disk::Disks;
+pub use multipart::{FieldError, FieldRules, MultipartConfig, MultipartUploads};
+pub use repository::{
+    ContentHasher, InMemoryUploadStore, StoredBlob, UploadRecord, UploadRepository, UploadStore,
+};
+#[cfg(feature = "sqlx")]
+pub use repository::PgUploadStore;
+pub use scan::{ClamdScanner, FileScanner, ScanPolicy, ScanResult};
+pub use serve::signed_file_router;
+pub use stream::StreamedUpload;
+pub use tus::{TusConfig, TusServer, TusUpload, TUS_VERSION};
+#[cfg(feature = "image-processing")]
+pub use variants::{GenerateVariants, ImageVariants, Variant, VariantFormat};
 
 use axum::extract::Multipart;
 use bytes::Bytes;
@@ -16,6 +70,15 @@ pub enum UploadError {
     #[error("Invalid MIME type: {0}")]
     InvalidMimeType(String),
 
+    #[error("Content does not match MIME type {0} (detected: {1})")]
+    MimeMismatch(String, String),
+
+    #[error("Infected file: {0}")]
+    Infected(String),
+
+    #[error("Scanner error: {0}")]
+    Scanner(String),
+
     #[error("File too large: {0} bytes (max: {1} bytes)")]
     FileTooLarge(u64, u64),
 
@@ -28,12 +91,138 @@ pub enum UploadError {
     #[error("Multipart error: {0}")]
     Multipart(String),
 
+    #[error("Invalid upload: {}", join_errors(.0))]
+    Invalid(Vec<FieldError>),
+
     #[error("Image processing error: {0}")]
     ImageProcessing(String),
+
+    #[error("Unknown image variant: {0}")]
+    UnknownVariant(String),
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] rf_storage::StorageError),
+
+    #[error("Database error: {0}")]
+    Database(String),
+
+    #[error("Unknown disk: {0}")]
+    UnknownDisk(String),
+
+    #[error("File is not stored on a disk: {0}")]
+    NotOnDisk(String),
+
+    #[error("Upload not found: {0}")]
+    UploadNotFound(String),
+
+    #[error("Upload offset mismatch: at {0}, chunk starts at {1}")]
+    OffsetMismatch(u64, u64),
+
+    #[error("Invalid checksum: {0}")]
+    InvalidChecksum(String),
+
+    #[error("Uploaded size mismatch: {0} bytes (expected: {1} bytes)")]
+    SizeMismatch(u64, u64),
+
+    #[error("Invalid upload token: {0}")]
+    InvalidToken(String),
+
+    #[error("Progress error: {0}")]
+    Progress(String),
 }
 
 pub type UploadResult<T> = Result<T, UploadError>;
 
+#[cfg(feature = "rf-error")]
+impl rf_error::FrameworkError for UploadError {
+    fn status(&self) -> rf_error::StatusCode {
+        use rf_error::StatusCode;
+
+        match self {
+            Self::FileTooLarge(..) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::InvalidMimeType(_) | Self::MimeMismatch(..) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::Infected(_)
+            | Self::NoFile
+            | Self::Invalid(_)
+            | Self::UnknownVariant(_)
+            | Self::InvalidChecksum(_)
+            | Self::SizeMismatch(..) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Multipart(_) => StatusCode::BAD_REQUEST,
+            Self::UploadNotFound(_) => StatusCode::NOT_FOUND,
+            Self::OffsetMismatch(..) => StatusCode::CONFLICT,
+            Self::InvalidToken(_) => StatusCode::FORBIDDEN,
+            Self::Storage(error) => error.status(),
+            Self::Scanner(_)
+            | Self::Io(_)
+            | Self::ImageProcessing(_)
+            | Self::Database(_)
+            | Self::UnknownDisk(_)
+            | Self::NotOnDisk(_)
+            | Self::Progress(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidMimeType(_) => "upload.invalid_mime_type",
+            Self::MimeMismatch(..) => "upload.mime_mismatch",
+            Self::Infected(_) => "upload.infected",
+            Self::Scanner(_) => "upload.scanner",
+            Self::FileTooLarge(..) => "upload.file_too_large",
+            Self::NoFile => "upload.no_file",
+            Self::Io(_) => "upload.io",
+            Self::Multipart(_) => "upload.multipart",
+            Self::Invalid(_) => "upload.invalid",
+            Self::ImageProcessing(_) => "upload.image_processing",
+            Self::UnknownVariant(_) => "upload.unknown_variant",
+            Self::Storage(error) => error.code(),
+            Self::Database(_) => "upload.database",
+            Self::UnknownDisk(_) => "upload.unknown_disk",
+            Self::NotOnDisk(_) => "upload.not_on_disk",
+            Self::UploadNotFound(_) => "upload.not_found",
+            Self::OffsetMismatch(..) => "upload.offset_mismatch",
+            Self::InvalidChecksum(_) => "upload.invalid_checksum",
+            Self::SizeMismatch(..) => "upload.size_mismatch",
+            Self::InvalidToken(_) => "upload.invalid_token",
+            Self::Progress(_) => "upload.progress",
+        }
+    }
+
+    fn metadata(&self) -> serde_json::Map<String, serde_json::Value> {
+        use serde_json::{json, Map, Value};
+
+        let metadata = match self {
+            Self::FileTooLarge(size, max) => json!({ "size": size, "max_size": max }),
+            Self::OffsetMismatch(offset, _) => json!({ "offset": offset }),
+            Self::Invalid(errors) => {
+                let mut fields = Map::new();
+                for error in errors {
+                    let messages = fields
+                        .entry(error.field.clone())
+                        .or_insert_with(|| Value::Array(Vec::new()));
+                    if let Value::Array(messages) = messages {
+                        messages.push(error.error.to_string().into());
+                    }
+                }
+                json!({ "errors": fields })
+            }
+            _ => return Map::new(),
+        };
+        match metadata {
+            Value::Object(metadata) => metadata,
+            _ => Map::new(),
+        }
+    }
+}
+
+fn join_errors(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 /// File upload configuration
 #[derive(Debug, Clone)]
 pub struct UploadConfig {
@@ -60,8 +249,11 @@ impl Default for UploadConfig {
 pub struct UploadedFile {
     /// Original filename
     pub filename: String,
-    /// Stored path
+    /// Stored path; the storage key for files on a disk
     pub path: PathBuf,
+    /// Disk the file is stored on, see [`Disks`]
+    #[serde(default)]
+    pub disk: Option<String>,
     /// File size in bytes
     pub size: u64,
     /// MIME type
@@ -76,6 +268,7 @@ impl UploadedFile {
 }
 
 /// File upload handler
+#[derive(Clone)]
 pub struct FileUpload {
     filename: String,
     content: Bytes,
@@ -83,6 +276,19 @@ pub struct FileUpload {
 }
 
 impl FileUpload {
+    /// Create from in-memory contents
+    pub fn from_bytes(
+        filename: impl Into<String>,
+        mime_type: Mime,
+        content: impl Into<Bytes>,
+    ) -> Self {
+        Self {
+            filename: filename.into(),
+            content: content.into(),
+            mime_type,
+        }
+    }
+
     /// Create from multipart field
     pub async fn from_multipart(multipart: &mut Multipart) -> UploadResult<Self> {
         let field = multipart
@@ -157,6 +363,7 @@ impl FileUpload {
 
     /// Store file to disk
     pub async fn store<P: AsRef<Path>>(self, directory: P) -> UploadResult<UploadedFile> {
+        self.scan().await?;
         let dir = directory.as_ref();
         tokio::fs::create_dir_all(dir).await?;
 
@@ -172,6 +379,7 @@ impl FileUpload {
         Ok(UploadedFile {
             filename,
             path,
+            disk: None,
             size: self.content.len() as u64,
             mime_type: self.mime_type.to_string(),
         })
@@ -183,6 +391,7 @@ impl FileUpload {
         directory: P,
         filename: &str,
     ) -> UploadResult<UploadedFile> {
+        self.scan().await?;
         let dir = directory.as_ref();
         tokio::fs::create_dir_all(dir).await?;
 
@@ -196,12 +405,40 @@ impl FileUpload {
         Ok(UploadedFile {
             filename,
             path,
+            disk: None,
             size: self.content.len() as u64,
             mime_type: self.mime_type.to_string(),
         })
     }
 }
 
+impl FileUpload {
+    /// Store file in `directory` of a registered disk
+    pub async fn store_on(self, disk: &str, directory: &str) -> UploadResult<UploadedFile> {
+        self.scan().await?;
+        let filename = sanitize_filename(&self.filename);
+        let directory = directory.trim_matches('/');
+        let key = if directory.is_empty() {
+            filename.clone()
+        } else {
+            format!("{}/{}", directory, filename)
+        };
+
+        let size = self.content.len() as u64;
+        Disks::get(disk)?.put(&key, self.content.to_vec()).await?;
+        #[cfg(feature = "image-processing")]
+        variants::on_stored(disk, &key, self.mime_type.essence_str()).await?;
+
+        Ok(UploadedFile {
+            filename,
+            path: PathBuf::from(key),
+            disk: Some(disk.to_string()),
+            size,
+            mime_type: self.mime_type.to_string(),
+        })
+    }
+}
+
 /// Sanitize filename for security
 fn sanitize_filename(filename: &str) -> String {
     filename
@@ -323,6 +560,7 @@ mod tests {
         let file = UploadedFile {
             filename: "test.jpg".to_string(),
             path: PathBuf::from("uploads/test.jpg"),
+            disk: None,
             size: 1024,
             mime_type: "image/jpeg".to_string(),
         };
@@ -412,5 +650,47 @@ mod tests {
         let uploaded = result.unwrap();
         assert!(uploaded.path.exists());
         assert_eq!(uploaded.size, 13);
+        assert!(matches!(uploaded.url(), Err(UploadError::NotOnDisk(_))));
+    }
+
+    #[tokio::test]
+    async fn test_store_on_disk() {
+        Disks::register(
+            "memory-test",
+            rf_storage::MemoryStorage::with_url("https://cdn.test"),
+        );
+        let upload = FileUpload::from_bytes("my photo.jpg", mime::IMAGE_JPEG, vec![1, 2, 3]);
+
+        let file = upload.store_on("memory-test", "/avatars/").await.unwrap();
+        assert_eq!(file.key(), "avatars/my_photo.jpg");
+        assert_eq!(file.disk.as_deref(), Some("memory-test"));
+        assert_eq!(
+            file.url().unwrap(),
+            "https://cdn.test/storage/avatars/my_photo.jpg"
+        );
+        // The memory backend can't sign URLs
+        assert!(matches!(
+            file.temporary_url(std::time::Duration::from_secs(60)),
+            Err(UploadError::Storage(rf_storage::StorageError::Unsupported(_)))
+        ));
+
+        let missing = FileUpload::from_bytes("a.txt", mime::TEXT_PLAIN, "a");
+        assert!(matches!(
+            missing.store_on("nope", "").await,
+            Err(UploadError::UnknownDisk(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_store_on_storage_disk() {
+        use rf_storage::Filesystem;
+
+        let storage = rf_storage::Storage::new("facade-local", rf_storage::MemoryStorage::new());
+        let exports = storage.fake("facade-exports");
+        Disks::register_storage(&storage);
+
+        let upload = FileUpload::from_bytes("report.csv", mime::TEXT_CSV, "id,total");
+        upload.store_on("facade-exports", "").await.unwrap();
+        assert_eq!(exports.get("report.csv").await.unwrap(), b"id,total");
     }
 }
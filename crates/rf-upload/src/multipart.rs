@@ -0,0 +1,529 @@
+//! Multi-file multipart forms
+//!
+//! [`MultipartUploads::parse`] reads a whole `multipart/form-data` body in
+//! one pass: files are checked against the [`FieldRules`] of their field and
+//! text fields are collected for [`MultipartUploads::fields_as`]. All rule
+//! violations are reported together in [`UploadError::Invalid`].
+//!
+//! ```no_run
+//! use axum::extract::Multipart;
+//! use rf_upload::{FieldRules, MultipartConfig, MultipartUploads, UploadResult};
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Album {
+//!     title: String,
+//! }
+//!
+//! async fn create_album(mut multipart: Multipart) -> UploadResult<()> {
+//!     let config = MultipartConfig::new()
+//!         .field("cover", FieldRules::new().allow("image/").max_size(2 << 20).required())
+//!         .field("photos", FieldRules::new().allow("image/").max_count(20));
+//!
+//!     let mut form = MultipartUploads::parse(&mut multipart, &config).await?;
+//!     let album: Album = form.fields_as()?;
+//!     let photos = form.take_files("photos");
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{stream, FileUpload, StreamedUpload, UploadConfig, UploadError, UploadResult};
+use axum::extract::Multipart;
+use bytes::BytesMut;
+use mime::Mime;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+
+/// Rules for the files of one form field
+#[derive(Debug, Clone, Default)]
+pub struct FieldRules {
+    allowed_mime_types: Vec<String>,
+    pub(crate) max_size: Option<u64>,
+    max_count: Option<usize>,
+    required: bool,
+    pub(crate) check_content: bool,
+    stream_to: Option<(String, String)>,
+}
+
+impl FieldRules {
+    /// Any number of files of any type and size
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow MIME types starting with `prefix`, e.g. `image/`
+    pub fn allow(mut self, prefix: impl Into<String>) -> Self {
+        self.allowed_mime_types.push(prefix.into());
+        self
+    }
+
+    /// Maximum size of each file in bytes
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Maximum number of files
+    pub fn max_count(mut self, count: usize) -> Self {
+        self.max_count = Some(count);
+        self
+    }
+
+    /// Require at least one file
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Check the declared types against the file contents, see
+    /// [`FileUpload::validate_content`]
+    pub fn check_content(mut self) -> Self {
+        self.check_content = true;
+        self
+    }
+
+    /// Stream files to `directory` on `disk` instead of buffering them,
+    /// see [`FileUpload::stream_field`]
+    pub fn stream_to(mut self, disk: impl Into<String>, directory: impl Into<String>) -> Self {
+        self.stream_to = Some((disk.into(), directory.into()));
+        self
+    }
+
+    /// Whether `mime_type` is allowed
+    pub(crate) fn allows(&self, mime_type: &Mime) -> bool {
+        let mime_type = mime_type.to_string();
+        self.allowed_mime_types.is_empty()
+            || self
+                .allowed_mime_types
+                .iter()
+                .any(|prefix| mime_type.starts_with(prefix.as_str()))
+    }
+
+    fn validate(&self, upload: FileUpload) -> UploadResult<FileUpload> {
+        let allowed: Vec<&str> = self.allowed_mime_types.iter().map(String::as_str).collect();
+        if self.check_content {
+            upload.validate_content(&allowed)
+        } else {
+            upload.validate_mime_type(&allowed)
+        }
+    }
+}
+
+/// Allowed types and maximum size of the config
+impl From<&UploadConfig> for FieldRules {
+    fn from(config: &UploadConfig) -> Self {
+        Self {
+            allowed_mime_types: config.allowed_mime_types.clone(),
+            max_size: config.max_size,
+            ..Self::default()
+        }
+    }
+}
+
+/// Rules for a multipart form
+#[derive(Debug, Clone)]
+pub struct MultipartConfig {
+    fields: HashMap<String, FieldRules>,
+    other_files: Option<FieldRules>,
+    max_total_size: Option<u64>,
+    max_text_size: u64,
+}
+
+impl Default for MultipartConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultipartConfig {
+    /// Accepts text fields and rejects files in fields without rules
+    pub fn new() -> Self {
+        Self {
+            fields: HashMap::new(),
+            other_files: None,
+            max_total_size: None,
+            max_text_size: 64 * 1024, // 64KB
+        }
+    }
+
+    /// Accept files in field `name` according to `rules`
+    pub fn field(mut self, name: impl Into<String>, rules: FieldRules) -> Self {
+        self.fields.insert(name.into(), rules);
+        self
+    }
+
+    /// Accept files in fields without rules of their own
+    pub fn other_files(mut self, rules: FieldRules) -> Self {
+        self.other_files = Some(rules);
+        self
+    }
+
+    /// Maximum size of all files together in bytes
+    pub fn max_total_size(mut self, bytes: u64) -> Self {
+        self.max_total_size = Some(bytes);
+        self
+    }
+
+    /// Maximum size of each text field in bytes (default: 64KB)
+    pub fn max_text_size(mut self, bytes: u64) -> Self {
+        self.max_text_size = bytes;
+        self
+    }
+}
+
+/// A rule violation of one form field
+#[derive(Debug)]
+pub struct FieldError {
+    pub field: String,
+    pub error: UploadError,
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.error)
+    }
+}
+
+/// Files and text fields of a multipart form
+#[derive(Default)]
+pub struct MultipartUploads {
+    files: HashMap<String, Vec<FileUpload>>,
+    stored: HashMap<String, Vec<StreamedUpload>>,
+    fields: Vec<(String, String)>,
+}
+
+impl MultipartUploads {
+    /// Read all fields of `multipart`, validating files against `config`
+    ///
+    /// Malformed bodies fail with [`UploadError::Multipart`]; rule
+    /// violations are collected into [`UploadError::Invalid`].
+    pub async fn parse(multipart: &mut Multipart, config: &MultipartConfig) -> UploadResult<Self> {
+        let mut uploads = Self::default();
+        let mut errors = Vec::new();
+        let mut total_size = 0;
+
+        while let Some(mut field) = multipart
+            .next_field()
+            .await
+            .map_err(|e| UploadError::Multipart(e.to_string()))?
+        {
+            let name = field.name().unwrap_or_default().to_string();
+
+            let Some(filename) = field.file_name().map(str::to_string) else {
+                match read_limited(&mut field, config.max_text_size).await? {
+                    Ok(value) => match String::from_utf8(value.to_vec()) {
+                        Ok(value) => uploads.fields.push((name, value)),
+                        Err(_) => errors.push(field_error(
+                            name,
+                            UploadError::Multipart("text field is not UTF-8".into()),
+                        )),
+                    },
+                    Err(size) => errors.push(field_error(
+                        name,
+                        UploadError::FileTooLarge(size, config.max_text_size),
+                    )),
+                }
+                continue;
+            };
+
+            let Some(rules) = config.fields.get(&name).or(config.other_files.as_ref()) else {
+                errors.push(field_error(
+                    name,
+                    UploadError::Multipart("unexpected file".into()),
+                ));
+                continue;
+            };
+            let count = uploads.files.get(&name).map_or(0, Vec::len)
+                + uploads.stored.get(&name).map_or(0, Vec::len);
+            if rules.max_count.is_some_and(|max| count >= max) {
+                errors.push(field_error(
+                    name,
+                    UploadError::Multipart(format!("more than {} files", count)),
+                ));
+                continue;
+            }
+
+            let remaining = config
+                .max_total_size
+                .map(|max| max.saturating_sub(total_size));
+            let limit = match (rules.max_size, remaining) {
+                (Some(max), Some(remaining)) => max.min(remaining),
+                (max, remaining) => max.or(remaining).unwrap_or(u64::MAX),
+            };
+
+            if let Some((disk, directory)) = &rules.stream_to {
+                match stream::stream_field(field, disk, directory, rules, Some(limit)).await {
+                    Ok(streamed) => {
+                        total_size += streamed.file.size;
+                        uploads.stored.entry(name).or_default().push(streamed);
+                    }
+                    Err(UploadError::FileTooLarge(size, _)) => {
+                        errors.push(field_error(name, size_error(rules, size)))
+                    }
+                    Err(e @ (UploadError::InvalidMimeType(_) | UploadError::MimeMismatch(..))) => {
+                        errors.push(field_error(name, e))
+                    }
+                    Err(e) => return Err(e),
+                }
+                continue;
+            }
+
+            let mime_type: Mime = field
+                .content_type()
+                .and_then(|c| c.parse().ok())
+                .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+
+            let content = match read_limited(&mut field, limit).await? {
+                Ok(content) => content,
+                Err(size) => {
+                    errors.push(field_error(name, size_error(rules, size)));
+                    continue;
+                }
+            };
+            total_size += content.len() as u64;
+
+            match rules.validate(FileUpload::from_bytes(
+                filename,
+                mime_type,
+                content.freeze(),
+            )) {
+                Ok(upload) => uploads.files.entry(name).or_default().push(upload),
+                Err(error) => errors.push(field_error(name, error)),
+            }
+        }
+
+        let mut required: Vec<_> = config
+            .fields
+            .iter()
+            .filter(|(name, rules)| rules.required && !uploads.files.contains_key(*name))
+            .filter(|(name, _)| !errors.iter().any(|e| &e.field == *name))
+            .map(|(name, _)| field_error(name.clone(), UploadError::NoFile))
+            .collect();
+        required.sort_by(|a, b| a.field.cmp(&b.field));
+        errors.extend(required);
+
+        if errors.is_empty() {
+            Ok(uploads)
+        } else {
+            Err(UploadError::Invalid(errors))
+        }
+    }
+
+    /// First file of field `name`
+    pub fn file(&self, name: &str) -> Option<&FileUpload> {
+        self.files(name).first()
+    }
+
+    /// Files of field `name`
+    pub fn files(&self, name: &str) -> &[FileUpload] {
+        self.files.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Take the files of field `name`, e.g. to store them
+    pub fn take_files(&mut self, name: &str) -> Vec<FileUpload> {
+        self.files.remove(name).unwrap_or_default()
+    }
+
+    /// Files of field `name` that were streamed to a disk, see
+    /// [`FieldRules::stream_to`]
+    pub fn stored(&self, name: &str) -> &[StreamedUpload] {
+        self.stored.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// First value of text field `name`
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// All values of text field `name`
+    pub fn field_values(&self, name: &str) -> Vec<&str> {
+        self.fields
+            .iter()
+            .filter(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    /// Deserialize the text fields like a URL-encoded form
+    pub fn fields_as<T: DeserializeOwned>(&self) -> UploadResult<T> {
+        let encoded = serde_urlencoded::to_string(&self.fields)
+            .map_err(|e| UploadError::Multipart(e.to_string()))?;
+        serde_urlencoded::from_str(&encoded).map_err(|e| UploadError::Multipart(e.to_string()))
+    }
+}
+
+/// Error for a file cut off at `size` bytes
+fn size_error(rules: &FieldRules, size: u64) -> UploadError {
+    match rules.max_size {
+        Some(max) if size > max => UploadError::FileTooLarge(size, max),
+        _ => UploadError::Multipart("form exceeds its total size limit".into()),
+    }
+}
+
+fn field_error(field: String, error: UploadError) -> FieldError {
+    FieldError { field, error }
+}
+
+/// Read a field up to `limit` bytes, or the size read when it exceeds it
+async fn read_limited(
+    field: &mut axum::extract::multipart::Field<'_>,
+    limit: u64,
+) -> UploadResult<Result<BytesMut, u64>> {
+    let mut content = BytesMut::new();
+    while let Some(chunk) = field
+        .chunk()
+        .await
+        .map_err(|e| UploadError::Multipart(e.to_string()))?
+    {
+        content.extend_from_slice(&chunk);
+        if content.len() as u64 > limit {
+            return Ok(Err(content.len() as u64));
+        }
+    }
+    Ok(Ok(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, extract::FromRequest, http::Request};
+    use serde::Deserialize;
+
+    const BOUNDARY: &str = "rustforge-boundary";
+
+    enum Part<'a> {
+        Text(&'a str, &'a str),
+        File(&'a str, &'a str, &'a str, &'a [u8]),
+    }
+
+    async fn multipart(parts: &[Part<'_>]) -> Multipart {
+        let mut body = Vec::new();
+        for part in parts {
+            body.extend_from_slice(format!("--{}\r\n", BOUNDARY).as_bytes());
+            match part {
+                Part::Text(name, value) => {
+                    body.extend_from_slice(
+                        format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name)
+                            .as_bytes(),
+                    );
+                    body.extend_from_slice(value.as_bytes());
+                }
+                Part::File(name, filename, mime, content) => {
+                    body.extend_from_slice(
+                        format!(
+                            "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+                             Content-Type: {}\r\n\r\n",
+                            name, filename, mime
+                        )
+                        .as_bytes(),
+                    );
+                    body.extend_from_slice(content);
+                }
+            }
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+
+        let request = Request::post("/")
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={}", BOUNDARY),
+            )
+            .body(Body::from(body))
+            .unwrap();
+        Multipart::from_request(request, &()).await.unwrap()
+    }
+
+    #[derive(Deserialize)]
+    struct Album {
+        title: String,
+        year: u32,
+    }
+
+    #[tokio::test]
+    async fn test_parse_files_and_fields() {
+        let config = MultipartConfig::new()
+            .field("cover", FieldRules::new().allow("image/").required())
+            .field("photos", FieldRules::new().allow("image/").max_count(3));
+        let mut form = multipart(&[
+            Part::Text("title", "Summer"),
+            Part::File("cover", "cover.png", "image/png", b"png"),
+            Part::File("photos", "a.jpg", "image/jpeg", b"a"),
+            Part::Text("year", "2024"),
+            Part::File("photos", "b.jpg", "image/jpeg", b"b"),
+            Part::Text("tags", "beach"),
+            Part::Text("tags", "sun"),
+        ])
+        .await;
+
+        let mut uploads = MultipartUploads::parse(&mut form, &config).await.unwrap();
+        assert_eq!(uploads.file("cover").unwrap().filename(), "cover.png");
+        assert_eq!(uploads.field("title"), Some("Summer"));
+        assert_eq!(uploads.field_values("tags"), vec!["beach", "sun"]);
+
+        let album: Album = uploads.fields_as().unwrap();
+        assert_eq!(album.title, "Summer");
+        assert_eq!(album.year, 2024);
+
+        let photos = uploads.take_files("photos");
+        assert_eq!(photos.len(), 2);
+        assert_eq!(photos[1].filename(), "b.jpg");
+        assert!(uploads.files("photos").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_errors() {
+        let config = MultipartConfig::new()
+            .field("cover", FieldRules::new().required())
+            .field("avatar", FieldRules::new().allow("image/").max_size(4))
+            .field("docs", FieldRules::new().max_count(1))
+            .max_text_size(8);
+        let mut form = multipart(&[
+            Part::File("avatar", "a.exe", "application/x-msdownload", b"MZ"),
+            Part::File("avatar", "big.png", "image/png", b"too large"),
+            Part::File("docs", "1.txt", "text/plain", b"1"),
+            Part::File("docs", "2.txt", "text/plain", b"2"),
+            Part::File("other", "x.txt", "text/plain", b"x"),
+            Part::Text("bio", "far too long"),
+        ])
+        .await;
+
+        let Err(UploadError::Invalid(errors)) = MultipartUploads::parse(&mut form, &config).await
+        else {
+            panic!("expected field errors");
+        };
+        let fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            ["avatar", "avatar", "docs", "other", "bio", "cover"]
+        );
+        assert!(matches!(errors[0].error, UploadError::InvalidMimeType(_)));
+        assert!(matches!(errors[1].error, UploadError::FileTooLarge(9, 4)));
+        assert!(matches!(errors[5].error, UploadError::NoFile));
+        assert!(UploadError::Invalid(errors)
+            .to_string()
+            .contains("cover: No file provided"));
+    }
+
+    #[tokio::test]
+    async fn test_total_size() {
+        let config = MultipartConfig::new()
+            .other_files(FieldRules::new())
+            .max_total_size(5);
+        let mut form = multipart(&[
+            Part::File("a", "a.txt", "text/plain", b"abc"),
+            Part::File("b", "b.txt", "text/plain", b"abc"),
+        ])
+        .await;
+
+        let Err(UploadError::Invalid(errors)) = MultipartUploads::parse(&mut form, &config).await
+        else {
+            panic!("expected field errors");
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "b");
+    }
+}
@@ -0,0 +1,826 @@
+//! Upload records with content deduplication
+//!
+//! [`UploadRepository`] stores each distinct content once. Uploads are
+//! hashed with SHA-256; an upload whose content is already stored becomes
+//! another reference to the existing blob instead of a new file. Deleting
+//! an upload drops its reference, and [`UploadRepository::cleanup_orphans`]
+//! removes blobs nobody references anymore.
+//!
+//! ```no_run
+//! use rf_upload::{FileUpload, InMemoryUploadStore, UploadRepository};
+//!
+//! # async fn example(upload: FileUpload) -> rf_upload::UploadResult<()> {
+//! let uploads = UploadRepository::new(InMemoryUploadStore::new(), "local").directory("files");
+//!
+//! let record = uploads.store(upload, Some("user-42")).await?;
+//! let files = uploads.for_owner("user-42").await?;
+//! uploads.delete(&record.id).await?;
+//! uploads.cleanup_orphans().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Disks, FileUpload, UploadResult, UploadedFile};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::io::AsyncReadExt;
+
+/// Incremental SHA-256 of upload content
+#[derive(Clone, Default)]
+pub struct ContentHasher(Sha256);
+
+impl ContentHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    /// Hex-encoded digest
+    pub fn finish(self) -> String {
+        hex::encode(self.0.finalize())
+    }
+}
+
+/// Stored content, shared by all uploads with the same hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredBlob {
+    pub hash: String,
+    pub disk: String,
+    pub path: String,
+    pub size: u64,
+    pub mime_type: String,
+    /// Number of upload records using this blob
+    pub references: u64,
+}
+
+/// A stored upload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadRecord {
+    pub id: String,
+    /// Hex-encoded SHA-256 of the content
+    pub hash: String,
+    pub filename: String,
+    pub owner: Option<String>,
+    pub disk: String,
+    pub path: String,
+    pub size: u64,
+    pub mime_type: String,
+    /// Unix timestamp
+    pub created_at: u64,
+}
+
+impl UploadRecord {
+    /// The stored file, for URLs and variants
+    pub fn file(&self) -> UploadedFile {
+        UploadedFile {
+            filename: self.filename.clone(),
+            path: PathBuf::from(&self.path),
+            disk: Some(self.disk.clone()),
+            size: self.size,
+            mime_type: self.mime_type.clone(),
+        }
+    }
+}
+
+/// Persistence for [`UploadRepository`]
+///
+/// Reference counts must be changed atomically; two uploads of the same
+/// content may race.
+#[async_trait]
+pub trait UploadStore: Send + Sync {
+    /// Add a reference to the blob with `hash`, if there is one
+    async fn acquire_blob(&self, hash: &str) -> UploadResult<Option<StoredBlob>>;
+
+    /// Insert a new blob; `false` if one with the same hash exists
+    async fn insert_blob(&self, blob: &StoredBlob) -> UploadResult<bool>;
+
+    /// Drop a reference, returning the references left
+    async fn release_blob(&self, hash: &str) -> UploadResult<u64>;
+
+    /// Blobs without references
+    async fn orphaned_blobs(&self) -> UploadResult<Vec<StoredBlob>>;
+
+    /// Remove a blob if it still has no references
+    async fn remove_blob(&self, hash: &str) -> UploadResult<bool>;
+
+    async fn insert_record(&self, record: &UploadRecord) -> UploadResult<()>;
+
+    async fn record(&self, id: &str) -> UploadResult<Option<UploadRecord>>;
+
+    /// Records of `owner`, oldest first
+    async fn records_for_owner(&self, owner: &str) -> UploadResult<Vec<UploadRecord>>;
+
+    async fn remove_record(&self, id: &str) -> UploadResult<Option<UploadRecord>>;
+
+    /// Mark the direct upload stored at `key` as completed; `false` if it
+    /// already was
+    async fn claim_upload(&self, key: &str) -> UploadResult<bool>;
+}
+
+/// In-memory upload store for development and tests
+#[derive(Clone, Default)]
+pub struct InMemoryUploadStore {
+    state: Arc<Mutex<MemoryState>>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    blobs: HashMap<String, StoredBlob>,
+    records: HashMap<String, UploadRecord>,
+    claimed: HashSet<String>,
+}
+
+impl InMemoryUploadStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl UploadStore for InMemoryUploadStore {
+    async fn acquire_blob(&self, hash: &str) -> UploadResult<Option<StoredBlob>> {
+        Ok(self.state().blobs.get_mut(hash).map(|blob| {
+            blob.references += 1;
+            blob.clone()
+        }))
+    }
+
+    async fn insert_blob(&self, blob: &StoredBlob) -> UploadResult<bool> {
+        let mut state = self.state();
+        if state.blobs.contains_key(&blob.hash) {
+            return Ok(false);
+        }
+        state.blobs.insert(blob.hash.clone(), blob.clone());
+        Ok(true)
+    }
+
+    async fn release_blob(&self, hash: &str) -> UploadResult<u64> {
+        Ok(self.state().blobs.get_mut(hash).map_or(0, |blob| {
+            blob.references = blob.references.saturating_sub(1);
+            blob.references
+        }))
+    }
+
+    async fn orphaned_blobs(&self) -> UploadResult<Vec<StoredBlob>> {
+        Ok(self
+            .state()
+            .blobs
+            .values()
+            .filter(|blob| blob.references == 0)
+            .cloned()
+            .collect())
+    }
+
+    async fn remove_blob(&self, hash: &str) -> UploadResult<bool> {
+        let mut state = self.state();
+        if state
+            .blobs
+            .get(hash)
+            .is_some_and(|blob| blob.references == 0)
+        {
+            state.blobs.remove(hash);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    async fn insert_record(&self, record: &UploadRecord) -> UploadResult<()> {
+        self.state()
+            .records
+            .insert(record.id.clone(), record.clone());
+        Ok(())
+    }
+
+    async fn record(&self, id: &str) -> UploadResult<Option<UploadRecord>> {
+        Ok(self.state().records.get(id).cloned())
+    }
+
+    async fn records_for_owner(&self, owner: &str) -> UploadResult<Vec<UploadRecord>> {
+        let mut records: Vec<_> = self
+            .state()
+            .records
+            .values()
+            .filter(|record| record.owner.as_deref() == Some(owner))
+            .cloned()
+            .collect();
+        records.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        Ok(records)
+    }
+
+    async fn remove_record(&self, id: &str) -> UploadResult<Option<UploadRecord>> {
+        Ok(self.state().records.remove(id))
+    }
+
+    async fn claim_upload(&self, key: &str) -> UploadResult<bool> {
+        Ok(self.state().claimed.insert(key.to_string()))
+    }
+}
+
+/// Deduplicating upload storage on a disk
+#[derive(Clone)]
+pub struct UploadRepository {
+    store: Arc<dyn UploadStore>,
+    disk: String,
+    directory: String,
+}
+
+impl UploadRepository {
+    /// Repository storing content on `disk`, see [`Disks`]
+    pub fn new(store: impl UploadStore + 'static, disk: impl Into<String>) -> Self {
+        Self {
+            store: Arc::new(store),
+            disk: disk.into(),
+            directory: "uploads".to_string(),
+        }
+    }
+
+    /// Name of the disk content is stored on
+    pub fn disk(&self) -> &str {
+        &self.disk
+    }
+
+    /// Directory on the disk for stored content (default: `uploads`)
+    pub fn directory(mut self, directory: impl Into<String>) -> Self {
+        self.directory = directory.into();
+        self
+    }
+
+    /// Store an upload for `owner`, reusing identical content
+    pub async fn store(
+        &self,
+        upload: FileUpload,
+        owner: Option<&str>,
+    ) -> UploadResult<UploadRecord> {
+        upload.scan().await?;
+        let mut hasher = ContentHasher::new();
+        hasher.update(&upload.content);
+        let hash = hasher.finish();
+
+        let blob = match self.store.acquire_blob(&hash).await? {
+            Some(blob) => blob,
+            None => {
+                let path = self.blob_path(&upload.filename);
+                let storage = Disks::get(&self.disk)?;
+                storage.put(&path, upload.content.to_vec()).await?;
+                self.insert_blob(hash, path, upload.size(), upload.mime_type.to_string())
+                    .await?
+            }
+        };
+        self.insert_record(blob, &upload.filename, owner).await
+    }
+
+    /// Store a local file, hashing it while it is read
+    ///
+    /// For large files, e.g. assembled [`TusServer`](crate::TusServer)
+    /// uploads, that shouldn't be loaded into memory.
+    pub async fn store_file(
+        &self,
+        source: &Path,
+        filename: &str,
+        mime_type: &str,
+        owner: Option<&str>,
+    ) -> UploadResult<UploadRecord> {
+        let mut file = tokio::fs::File::open(source).await?;
+        let mut hasher = ContentHasher::new();
+        let mut buffer = vec![0; 64 * 1024];
+        let mut size = 0;
+        loop {
+            let read = file.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            size += read as u64;
+        }
+        let hash = hasher.finish();
+
+        let blob = match self.store.acquire_blob(&hash).await? {
+            Some(blob) => blob,
+            None => {
+                let path = self.blob_path(filename);
+                Disks::get(&self.disk)?.put_file(&path, source).await?;
+                self.insert_blob(hash, path, size, mime_type.to_string())
+                    .await?
+            }
+        };
+        self.insert_record(blob, filename, owner).await
+    }
+
+    /// Register content that already is on the repository's disk
+    ///
+    /// For files clients uploaded directly, see
+    /// [`DirectUploads`](crate::DirectUploads), which checks and scans the
+    /// content before registering it; `register` trusts `hash` and doesn't
+    /// read the file. If the same content is stored already, the new copy is
+    /// deleted and the existing one referenced.
+    pub async fn register(
+        &self,
+        file: &UploadedFile,
+        hash: &str,
+        owner: Option<&str>,
+    ) -> UploadResult<UploadRecord> {
+        let path = file.key();
+        let blob = match self.store.acquire_blob(hash).await? {
+            Some(blob) => {
+                if blob.path != path {
+                    Disks::get(&self.disk)?.delete(&path).await?;
+                }
+                blob
+            }
+            None => {
+                self.insert_blob(hash.to_string(), path, file.size, file.mime_type.clone())
+                    .await?
+            }
+        };
+        self.insert_record(blob, &file.filename, owner).await
+    }
+
+    /// Claim the direct upload at `key`, so its token can't be used twice
+    pub(crate) async fn claim(&self, key: &str) -> UploadResult<bool> {
+        self.store.claim_upload(key).await
+    }
+
+    pub async fn find(&self, id: &str) -> UploadResult<Option<UploadRecord>> {
+        self.store.record(id).await
+    }
+
+    /// All uploads of `owner`, oldest first
+    pub async fn for_owner(&self, owner: &str) -> UploadResult<Vec<UploadRecord>> {
+        self.store.records_for_owner(owner).await
+    }
+
+    /// Delete an upload record
+    ///
+    /// The content stays on the disk until
+    /// [`UploadRepository::cleanup_orphans`] finds it unreferenced.
+    pub async fn delete(&self, id: &str) -> UploadResult<bool> {
+        match self.store.remove_record(id).await? {
+            Some(record) => {
+                self.store.release_blob(&record.hash).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Remove unreferenced content from its disk, returning how many blobs
+    /// were removed
+    pub async fn cleanup_orphans(&self) -> UploadResult<usize> {
+        let mut removed = 0;
+        for blob in self.store.orphaned_blobs().await? {
+            // Only delete the file if nobody acquired the blob meanwhile
+            if !self.store.remove_blob(&blob.hash).await? {
+                continue;
+            }
+            match Disks::get(&blob.disk)?.delete(&blob.path).await {
+                Ok(()) | Err(rf_storage::StorageError::FileNotFound(_)) => removed += 1,
+                Err(e) => {
+                    tracing::error!(path = %blob.path, "Failed to delete orphaned upload: {}", e)
+                }
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Each blob gets a fresh path, so a blob removed by
+    /// [`UploadRepository::cleanup_orphans`] never shares its file with a
+    /// re-upload of the same content
+    pub(crate) fn blob_path(&self, filename: &str) -> String {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let name = match Path::new(filename).extension().and_then(|e| e.to_str()) {
+            Some(extension) => format!("{}.{}", id, crate::sanitize_filename(extension)),
+            None => id,
+        };
+        match self.directory.trim_matches('/') {
+            "" => name,
+            directory => format!("{}/{}", directory, name),
+        }
+    }
+
+    async fn insert_blob(
+        &self,
+        hash: String,
+        path: String,
+        size: u64,
+        mime_type: String,
+    ) -> UploadResult<StoredBlob> {
+        let blob = StoredBlob {
+            hash,
+            disk: self.disk.clone(),
+            path,
+            size,
+            mime_type,
+            references: 1,
+        };
+        if self.store.insert_blob(&blob).await? {
+            return Ok(blob);
+        }
+
+        // The same content was stored concurrently; use that copy
+        let _ = Disks::get(&self.disk)?.delete(&blob.path).await;
+        match self.store.acquire_blob(&blob.hash).await? {
+            Some(existing) => Ok(existing),
+            None => Err(crate::UploadError::Database(format!(
+                "blob {} vanished while storing",
+                blob.hash
+            ))),
+        }
+    }
+
+    async fn insert_record(
+        &self,
+        blob: StoredBlob,
+        filename: &str,
+        owner: Option<&str>,
+    ) -> UploadResult<UploadRecord> {
+        let record = UploadRecord {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            hash: blob.hash,
+            filename: crate::sanitize_filename(filename),
+            owner: owner.map(str::to_string),
+            disk: blob.disk,
+            path: blob.path,
+            size: blob.size,
+            mime_type: blob.mime_type,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        if let Err(e) = self.store.insert_record(&record).await {
+            self.store.release_blob(&record.hash).await?;
+            return Err(e);
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+pub use pg::PgUploadStore;
+
+#[cfg(feature = "sqlx")]
+mod pg {
+    use super::*;
+    use crate::UploadError;
+    use sqlx::{postgres::PgRow, PgPool, Row};
+
+    const BLOB_COLUMNS: &str = "hash, disk, path, size, mime_type, refs";
+    const RECORD_COLUMNS: &str =
+        "id, hash, filename, owner, disk, path, size, mime_type, created_at";
+
+    /// PostgreSQL upload store
+    ///
+    /// Uses the `upload_blobs`, `uploads` and `upload_claims` tables created
+    /// by [`PgUploadStore::migrate`].
+    #[derive(Clone)]
+    pub struct PgUploadStore {
+        pool: PgPool,
+    }
+
+    impl PgUploadStore {
+        pub fn new(pool: PgPool) -> Self {
+            Self { pool }
+        }
+
+        /// Create the tables if they don't exist
+        pub async fn migrate(&self) -> UploadResult<()> {
+            for statement in [
+                "CREATE TABLE IF NOT EXISTS upload_blobs (
+                    hash TEXT PRIMARY KEY,
+                    disk TEXT NOT NULL,
+                    path TEXT NOT NULL,
+                    size BIGINT NOT NULL,
+                    mime_type TEXT NOT NULL,
+                    refs BIGINT NOT NULL
+                )",
+                "CREATE TABLE IF NOT EXISTS uploads (
+                    id TEXT PRIMARY KEY,
+                    hash TEXT NOT NULL REFERENCES upload_blobs (hash),
+                    filename TEXT NOT NULL,
+                    owner TEXT,
+                    disk TEXT NOT NULL,
+                    path TEXT NOT NULL,
+                    size BIGINT NOT NULL,
+                    mime_type TEXT NOT NULL,
+                    created_at BIGINT NOT NULL
+                )",
+                "CREATE INDEX IF NOT EXISTS uploads_owner ON uploads (owner, created_at)",
+                "CREATE TABLE IF NOT EXISTS upload_claims (
+                    key TEXT PRIMARY KEY,
+                    claimed_at BIGINT NOT NULL
+                )",
+            ] {
+                sqlx::query(statement)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(store_error)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl UploadStore for PgUploadStore {
+        async fn acquire_blob(&self, hash: &str) -> UploadResult<Option<StoredBlob>> {
+            let row = sqlx::query(&format!(
+                "UPDATE upload_blobs SET refs = refs + 1 WHERE hash = $1 RETURNING {}",
+                BLOB_COLUMNS
+            ))
+            .bind(hash)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(store_error)?;
+            Ok(row.as_ref().map(blob))
+        }
+
+        async fn insert_blob(&self, blob: &StoredBlob) -> UploadResult<bool> {
+            let result = sqlx::query(&format!(
+                "INSERT INTO upload_blobs ({}) VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (hash) DO NOTHING",
+                BLOB_COLUMNS
+            ))
+            .bind(&blob.hash)
+            .bind(&blob.disk)
+            .bind(&blob.path)
+            .bind(blob.size as i64)
+            .bind(&blob.mime_type)
+            .bind(blob.references as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(store_error)?;
+            Ok(result.rows_affected() == 1)
+        }
+
+        async fn release_blob(&self, hash: &str) -> UploadResult<u64> {
+            let refs: Option<i64> = sqlx::query_scalar(
+                "UPDATE upload_blobs SET refs = refs - 1 WHERE hash = $1 AND refs > 0 RETURNING refs",
+            )
+            .bind(hash)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(store_error)?;
+            Ok(refs.unwrap_or_default() as u64)
+        }
+
+        async fn orphaned_blobs(&self) -> UploadResult<Vec<StoredBlob>> {
+            let rows = sqlx::query(&format!(
+                "SELECT {} FROM upload_blobs WHERE refs = 0",
+                BLOB_COLUMNS
+            ))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(store_error)?;
+            Ok(rows.iter().map(blob).collect())
+        }
+
+        async fn remove_blob(&self, hash: &str) -> UploadResult<bool> {
+            let result = sqlx::query("DELETE FROM upload_blobs WHERE hash = $1 AND refs = 0")
+                .bind(hash)
+                .execute(&self.pool)
+                .await
+                .map_err(store_error)?;
+            Ok(result.rows_affected() == 1)
+        }
+
+        async fn insert_record(&self, record: &UploadRecord) -> UploadResult<()> {
+            sqlx::query(&format!(
+                "INSERT INTO uploads ({}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+                RECORD_COLUMNS
+            ))
+            .bind(&record.id)
+            .bind(&record.hash)
+            .bind(&record.filename)
+            .bind(&record.owner)
+            .bind(&record.disk)
+            .bind(&record.path)
+            .bind(record.size as i64)
+            .bind(&record.mime_type)
+            .bind(record.created_at as i64)
+            .execute(&self.pool)
+            .await
+            .map_err(store_error)?;
+            Ok(())
+        }
+
+        async fn record(&self, id: &str) -> UploadResult<Option<UploadRecord>> {
+            let row = sqlx::query(&format!(
+                "SELECT {} FROM uploads WHERE id = $1",
+                RECORD_COLUMNS
+            ))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(store_error)?;
+            Ok(row.as_ref().map(record))
+        }
+
+        async fn records_for_owner(&self, owner: &str) -> UploadResult<Vec<UploadRecord>> {
+            let rows = sqlx::query(&format!(
+                "SELECT {} FROM uploads WHERE owner = $1 ORDER BY created_at, id",
+                RECORD_COLUMNS
+            ))
+            .bind(owner)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(store_error)?;
+            Ok(rows.iter().map(record).collect())
+        }
+
+        async fn remove_record(&self, id: &str) -> UploadResult<Option<UploadRecord>> {
+            let row = sqlx::query(&format!(
+                "DELETE FROM uploads WHERE id = $1 RETURNING {}",
+                RECORD_COLUMNS
+            ))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(store_error)?;
+            Ok(row.as_ref().map(record))
+        }
+
+        async fn claim_upload(&self, key: &str) -> UploadResult<bool> {
+            let result = sqlx::query(
+                "INSERT INTO upload_claims (key, claimed_at) VALUES ($1, $2)
+                 ON CONFLICT (key) DO NOTHING",
+            )
+            .bind(key)
+            .bind(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or_default(),
+            )
+            .execute(&self.pool)
+            .await
+            .map_err(store_error)?;
+            Ok(result.rows_affected() == 1)
+        }
+    }
+
+    fn blob(row: &PgRow) -> StoredBlob {
+        StoredBlob {
+            hash: row.get("hash"),
+            disk: row.get("disk"),
+            path: row.get("path"),
+            size: row.get::<i64, _>("size") as u64,
+            mime_type: row.get("mime_type"),
+            references: row.get::<i64, _>("refs") as u64,
+        }
+    }
+
+    fn record(row: &PgRow) -> UploadRecord {
+        UploadRecord {
+            id: row.get("id"),
+            hash: row.get("hash"),
+            filename: row.get("filename"),
+            owner: row.get("owner"),
+            disk: row.get("disk"),
+            path: row.get("path"),
+            size: row.get::<i64, _>("size") as u64,
+            mime_type: row.get("mime_type"),
+            created_at: row.get::<i64, _>("created_at") as u64,
+        }
+    }
+
+    fn store_error(e: sqlx::Error) -> UploadError {
+        UploadError::Database(e.to_string())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use rf_storage::MemoryStorage;
+
+        #[tokio::test]
+        #[ignore] // Requires PostgreSQL
+        async fn test_pg_store() {
+            let pool = PgPool::connect("postgres://localhost/rustforge_test")
+                .await
+                .unwrap();
+            let store = PgUploadStore::new(pool);
+            store.migrate().await.unwrap();
+            Disks::register("pg-uploads", MemoryStorage::new());
+            let uploads = UploadRepository::new(store, "pg-uploads");
+
+            let owner = uuid::Uuid::new_v4().to_string();
+            let a = FileUpload::from_bytes("a.txt", mime::TEXT_PLAIN, owner.clone());
+            let first = uploads.store(a.clone(), Some(&owner)).await.unwrap();
+            let second = uploads.store(a, Some(&owner)).await.unwrap();
+            assert_eq!(first.path, second.path);
+            assert_eq!(uploads.for_owner(&owner).await.unwrap().len(), 2);
+
+            assert!(uploads.delete(&first.id).await.unwrap());
+            assert!(uploads.delete(&second.id).await.unwrap());
+            assert!(uploads.cleanup_orphans().await.unwrap() >= 1);
+            assert!(uploads.find(&first.id).await.unwrap().is_none());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rf_storage::{Filesystem, MemoryStorage};
+
+    fn repository(disk: &str) -> (UploadRepository, MemoryStorage) {
+        let storage = MemoryStorage::new();
+        Disks::register(disk, storage.clone());
+        let uploads = UploadRepository::new(InMemoryUploadStore::new(), disk).directory("files");
+        (uploads, storage)
+    }
+
+    #[test]
+    fn test_content_hasher() {
+        let mut hasher = ContentHasher::new();
+        hasher.update(b"hello ");
+        hasher.update(b"world");
+        assert_eq!(
+            hasher.finish(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deduplication() {
+        let (uploads, storage) = repository("repository-dedupe");
+        let report = FileUpload::from_bytes("report.pdf", mime::APPLICATION_PDF, "%PDF-1.7");
+
+        let first = uploads.store(report.clone(), Some("alice")).await.unwrap();
+        let second = uploads.store(report, Some("bob")).await.unwrap();
+        let other = FileUpload::from_bytes("notes.txt", mime::TEXT_PLAIN, "notes");
+        let third = uploads.store(other, Some("alice")).await.unwrap();
+
+        assert_eq!(first.hash, second.hash);
+        assert_eq!(first.path, second.path);
+        assert_ne!(first.id, second.id);
+        assert!(first.path.starts_with("files/") && first.path.ends_with(".pdf"));
+        assert_eq!(storage.count(), 2);
+
+        let alice: Vec<_> = uploads
+            .for_owner("alice")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|record| record.id)
+            .collect();
+        assert_eq!(alice.len(), 2);
+        assert!(alice.contains(&first.id) && alice.contains(&third.id));
+        assert_eq!(
+            uploads
+                .find(&second.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .file()
+                .key(),
+            second.path
+        );
+
+        // Still referenced by bob
+        assert!(uploads.delete(&first.id).await.unwrap());
+        assert_eq!(uploads.cleanup_orphans().await.unwrap(), 0);
+        assert!(storage.exists(&second.path).await.unwrap());
+
+        assert!(uploads.delete(&second.id).await.unwrap());
+        assert!(!uploads.delete(&second.id).await.unwrap());
+        assert_eq!(uploads.cleanup_orphans().await.unwrap(), 1);
+        assert!(!storage.exists(&second.path).await.unwrap());
+        assert_eq!(storage.count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_store_file() {
+        let (uploads, storage) = repository("repository-files");
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("chunk.bin");
+        tokio::fs::write(&source, "hello world").await.unwrap();
+
+        let record = uploads
+            .store_file(&source, "greeting.txt", "text/plain", None)
+            .await
+            .unwrap();
+        assert_eq!(record.size, 11);
+        assert_eq!(
+            record.hash,
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+
+        let same = FileUpload::from_bytes("copy.txt", mime::TEXT_PLAIN, "hello world");
+        assert_eq!(uploads.store(same, None).await.unwrap().path, record.path);
+        assert_eq!(storage.count(), 1);
+    }
+}
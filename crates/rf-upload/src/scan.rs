@@ -0,0 +1,349 @@
+//! Content validation and malware scanning
+//!
+//! The content type a client declares is only a claim.
+//! [`FileUpload::validate_content`] checks it against the file signature
+//! (magic bytes), and an installed [`ScanPolicy`] runs a [`FileScanner`]
+//! before every `store*` call.
+//!
+//! ```no_run
+//! use rf_upload::{ClamdScanner, ScanPolicy};
+//!
+//! ScanPolicy::new(ClamdScanner::new("127.0.0.1:3310"))
+//!     .quarantine_on("quarantine", "infected")
+//!     .install();
+//! ```
+
+use crate::{sanitize_filename, Disks, FileUpload, UploadError, UploadResult};
+use async_trait::async_trait;
+use mime::Mime;
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Outcome of a malware scan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanResult {
+    Clean,
+    /// Infected, with the name of the detected threat
+    Infected(String),
+}
+
+/// Malware scanner run on uploads before they are stored
+#[async_trait]
+pub trait FileScanner: Send + Sync {
+    async fn scan(&self, filename: &str, content: &[u8]) -> UploadResult<ScanResult>;
+}
+
+/// Scanner using a clamd daemon over TCP (`INSTREAM` command)
+pub struct ClamdScanner {
+    address: String,
+    timeout: Duration,
+}
+
+impl ClamdScanner {
+    /// Connect to clamd at `address`, e.g. `127.0.0.1:3310`
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Maximum time for a scan, including the connection
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn instream(&self, content: &[u8]) -> std::io::Result<String> {
+        // clamd's default StreamMaxLength is 25MB, chunks stay well below
+        const CHUNK_SIZE: usize = 64 * 1024;
+
+        let mut stream = tokio::net::TcpStream::connect(&self.address).await?;
+        stream.write_all(b"zINSTREAM\0").await?;
+        for chunk in content.chunks(CHUNK_SIZE) {
+            stream
+                .write_all(&(chunk.len() as u32).to_be_bytes())
+                .await?;
+            stream.write_all(chunk).await?;
+        }
+        stream.write_all(&0u32.to_be_bytes()).await?;
+        stream.flush().await?;
+
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply).await?;
+        Ok(String::from_utf8_lossy(&reply)
+            .trim_end_matches(['\0', '\n'])
+            .to_string())
+    }
+}
+
+#[async_trait]
+impl FileScanner for ClamdScanner {
+    async fn scan(&self, _filename: &str, content: &[u8]) -> UploadResult<ScanResult> {
+        let reply = tokio::time::timeout(self.timeout, self.instream(content))
+            .await
+            .map_err(|_| UploadError::Scanner("clamd timed out".into()))?
+            .map_err(|e| UploadError::Scanner(format!("clamd: {}", e)))?;
+
+        // "stream: OK", "stream: <threat> FOUND" or "<reason> ERROR"
+        let status = reply.strip_prefix("stream: ").unwrap_or(&reply);
+        if status == "OK" {
+            Ok(ScanResult::Clean)
+        } else if let Some(threat) = status.strip_suffix(" FOUND") {
+            Ok(ScanResult::Infected(threat.to_string()))
+        } else {
+            Err(UploadError::Scanner(format!("clamd: {}", reply)))
+        }
+    }
+}
+
+/// Scanner and quarantine settings applied to every stored upload
+pub struct ScanPolicy {
+    scanner: Arc<dyn FileScanner>,
+    quarantine: Option<(String, String)>,
+}
+
+static POLICY: RwLock<Option<Arc<ScanPolicy>>> = RwLock::new(None);
+
+impl ScanPolicy {
+    pub fn new(scanner: impl FileScanner + 'static) -> Self {
+        Self {
+            scanner: Arc::new(scanner),
+            quarantine: None,
+        }
+    }
+
+    /// Keep infected files in `directory` of `disk` for inspection instead
+    /// of dropping them
+    pub fn quarantine_on(mut self, disk: impl Into<String>, directory: impl Into<String>) -> Self {
+        self.quarantine = Some((disk.into(), directory.into()));
+        self
+    }
+
+    /// Scan all uploads stored from now on
+    pub fn install(self) {
+        *POLICY.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(self));
+    }
+
+    /// Stop scanning uploads
+    pub fn uninstall() {
+        *POLICY.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    pub(crate) fn installed() -> Option<Arc<ScanPolicy>> {
+        POLICY.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub(crate) async fn check(&self, upload: &FileUpload) -> UploadResult<()> {
+        let threat = match self.scanner.scan(&upload.filename, &upload.content).await? {
+            ScanResult::Clean => return Ok(()),
+            ScanResult::Infected(threat) => threat,
+        };
+        tracing::warn!(filename = %upload.filename, threat = %threat, "Infected upload rejected");
+
+        if let Some((disk, directory)) = &self.quarantine {
+            let key = format!(
+                "{}/{}-{}",
+                directory.trim_matches('/'),
+                uuid::Uuid::new_v4().simple(),
+                sanitize_filename(&upload.filename)
+            );
+            // The upload is rejected either way
+            if let Err(e) = quarantine(disk, &key, upload).await {
+                tracing::error!("Failed to quarantine {}: {}", upload.filename, e);
+            }
+        }
+        Err(UploadError::Infected(threat))
+    }
+}
+
+async fn quarantine(disk: &str, key: &str, upload: &FileUpload) -> UploadResult<()> {
+    Ok(Disks::get(disk)?.put(key, upload.content.to_vec()).await?)
+}
+
+/// Check a declared MIME type against the leading bytes of the content,
+/// returning the type to use
+pub(crate) fn check_signature(declared: &Mime, head: &[u8]) -> UploadResult<Mime> {
+    let essence = declared.essence_str();
+
+    match infer::get(head).and_then(|kind| kind.mime_type().parse::<Mime>().ok()) {
+        Some(detected) => {
+            if essence != mime::APPLICATION_OCTET_STREAM.essence_str()
+                && canonical_mime(essence) != canonical_mime(detected.essence_str())
+            {
+                return Err(UploadError::MimeMismatch(
+                    essence.to_string(),
+                    detected.to_string(),
+                ));
+            }
+            Ok(detected)
+        }
+        None if infer::is_mime_supported(canonical_mime(essence)) => Err(
+            UploadError::MimeMismatch(essence.to_string(), "unknown".to_string()),
+        ),
+        None => Ok(declared.clone()),
+    }
+}
+
+/// Spellings clients use for the types `infer` reports
+fn canonical_mime(mime: &str) -> &str {
+    match mime {
+        "image/jpg" | "image/pjpeg" => "image/jpeg",
+        "image/x-png" => "image/png",
+        "audio/mp3" => "audio/mpeg",
+        "audio/wav" | "audio/wave" => "audio/x-wav",
+        "application/x-zip-compressed" => "application/zip",
+        "application/x-pdf" => "application/pdf",
+        other => other,
+    }
+}
+
+impl FileUpload {
+    /// MIME type detected from the file signature, if it has a known one
+    pub fn detected_mime_type(&self) -> Option<Mime> {
+        infer::get(&self.content).and_then(|kind| kind.mime_type().parse().ok())
+    }
+
+    /// Validate the declared MIME type against the file contents
+    ///
+    /// Files with a known signature must match their declared type; a
+    /// declared `application/octet-stream` is replaced by the detected type.
+    /// Files without a known signature (text, CSV, JSON, ...) are rejected
+    /// if they claim a type that has one. The resulting type is then checked
+    /// against `allowed` like [`FileUpload::validate_mime_type`].
+    pub fn validate_content(mut self, allowed: &[&str]) -> UploadResult<Self> {
+        self.mime_type = check_signature(&self.mime_type, &self.content)?;
+        self.validate_mime_type(allowed)
+    }
+
+    /// Run the installed [`ScanPolicy`], if any
+    pub(crate) async fn scan(&self) -> UploadResult<()> {
+        match ScanPolicy::installed() {
+            Some(policy) => policy.check(self).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use rf_storage::{Filesystem, MemoryStorage};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    const EICAR: &str = "X5O!P%@AP[4\\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*";
+
+    fn upload(name: &str, mime: &str, content: &'static [u8]) -> FileUpload {
+        FileUpload::from_bytes(name, mime.parse().unwrap(), Bytes::from_static(content))
+    }
+
+    #[test]
+    fn test_validate_content() {
+        let png = upload("a.png", "image/png", PNG)
+            .validate_content(&["image/"])
+            .unwrap();
+        assert_eq!(png.mime_type().as_ref(), "image/png");
+
+        // Octet-stream takes the detected type
+        let png = upload("a", "application/octet-stream", PNG)
+            .validate_content(&[])
+            .unwrap();
+        assert_eq!(png.mime_type().as_ref(), "image/png");
+
+        // A script renamed to .jpg
+        assert!(matches!(
+            upload("a.jpg", "image/jpeg", b"<?php system($_GET['c']);").validate_content(&[]),
+            Err(UploadError::MimeMismatch(declared, detected))
+                if declared == "image/jpeg" && detected == "unknown"
+        ));
+        // A PNG claiming to be a PDF
+        assert!(matches!(
+            upload("a.pdf", "application/pdf", PNG).validate_content(&[]),
+            Err(UploadError::MimeMismatch(_, detected)) if detected == "image/png"
+        ));
+        // Aliases and types without a signature
+        assert!(upload("a.png", "image/x-png", PNG)
+            .validate_content(&[])
+            .is_ok());
+        assert!(upload("a.csv", "text/csv", b"a,b\n1,2")
+            .validate_content(&["text/"])
+            .is_ok());
+        assert!(matches!(
+            upload("a.png", "image/png", PNG).validate_content(&["application/pdf"]),
+            Err(UploadError::InvalidMimeType(_))
+        ));
+    }
+
+    struct Signatures;
+
+    #[async_trait]
+    impl FileScanner for Signatures {
+        async fn scan(&self, _filename: &str, content: &[u8]) -> UploadResult<ScanResult> {
+            if content.windows(5).any(|w| w == b"EICAR") {
+                Ok(ScanResult::Infected("Eicar-Signature".into()))
+            } else {
+                Ok(ScanResult::Clean)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_before_store() {
+        let quarantine = MemoryStorage::new();
+        Disks::register("scan-quarantine", quarantine.clone());
+        Disks::register("scan-files", MemoryStorage::new());
+        ScanPolicy::new(Signatures)
+            .quarantine_on("scan-quarantine", "infected")
+            .install();
+
+        let infected = upload("virus.txt", "text/plain", EICAR.as_bytes());
+        assert!(matches!(
+            infected.store_on("scan-files", "docs").await,
+            Err(UploadError::Infected(threat)) if threat == "Eicar-Signature"
+        ));
+        let quarantined = quarantine.list("infected").await.unwrap();
+        assert_eq!(quarantined.len(), 1);
+        assert!(quarantined[0].ends_with("-virus.txt"));
+
+        let clean = upload("notes.txt", "text/plain", b"hello");
+        assert!(clean.store_on("scan-files", "docs").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_clamd_scanner() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        tokio::spawn(async move {
+            for reply in ["stream: OK\0", "stream: Eicar-Signature FOUND\0"] {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut command = [0u8; 10];
+                socket.read_exact(&mut command).await.unwrap();
+                assert_eq!(&command, b"zINSTREAM\0");
+                loop {
+                    let len = socket.read_u32().await.unwrap() as usize;
+                    if len == 0 {
+                        break;
+                    }
+                    let mut chunk = vec![0; len];
+                    socket.read_exact(&mut chunk).await.unwrap();
+                }
+                socket.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+
+        let scanner = ClamdScanner::new(address);
+        assert_eq!(
+            scanner.scan("a", b"hello").await.unwrap(),
+            ScanResult::Clean
+        );
+        assert_eq!(
+            scanner.scan("b", EICAR.as_bytes()).await.unwrap(),
+            ScanResult::Infected("Eicar-Signature".into())
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! Serving files behind signed, expiring links
+
+use crate::Disks;
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use rf_storage::{StorageError, UrlSigner};
+use serde::Deserialize;
+use std::sync::Arc;
+
+struct Signed {
+    disk: String,
+    signer: UrlSigner,
+}
+
+#[derive(Deserialize)]
+struct SignedQuery {
+    expires: Option<u64>,
+    signature: Option<String>,
+}
+
+/// Route serving the temporary URLs of a local disk
+///
+/// Mount it where the disk's public URL points to (`/storage` for
+/// [`LocalStorage`](rf_storage::LocalStorage)) and use the signer the disk
+/// was configured with. Links with a missing, wrong or expired signature
+/// get `403 Forbidden`.
+///
+/// ```no_run
+/// use axum::Router;
+/// use rf_storage::UrlSigner;
+/// use rf_upload::signed_file_router;
+///
+/// let app: Router = Router::new()
+///     .nest("/storage", signed_file_router("local", UrlSigner::new("app-key")));
+/// ```
+pub fn signed_file_router(disk: impl Into<String>, signer: UrlSigner) -> Router {
+    let state = Arc::new(Signed {
+        disk: disk.into(),
+        signer,
+    });
+    Router::new().route("/*path", get(serve)).with_state(state)
+}
+
+async fn serve(
+    State(signed): State<Arc<Signed>>,
+    Path(path): Path<String>,
+    Query(query): Query<SignedQuery>,
+) -> Response {
+    let (Some(expires), Some(signature)) = (query.expires, query.signature) else {
+        return StatusCode::FORBIDDEN.into_response();
+    };
+    if let Err(e) = signed.signer.verify(&path, expires, &signature) {
+        return (StatusCode::FORBIDDEN, e.to_string()).into_response();
+    }
+
+    let contents = match Disks::get(&signed.disk) {
+        Ok(storage) => storage.get(&path).await,
+        Err(e) => {
+            tracing::error!("Signed file route: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    match contents {
+        Ok(contents) => {
+            let mime = mime_guess::from_path(&path).first_or_octet_stream();
+            (
+                [
+                    (header::CONTENT_TYPE, mime.to_string()),
+                    (header::CACHE_CONTROL, "private, no-store".to_string()),
+                ],
+                contents,
+            )
+                .into_response()
+        }
+        Err(StorageError::FileNotFound(_) | StorageError::InvalidPath(_)) => {
+            StatusCode::NOT_FOUND.into_response()
+        }
+        Err(e) => {
+            tracing::error!("Signed file route: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileUpload, UploadedFile};
+    use axum::body::Body;
+    use bytes::Bytes;
+    use rf_storage::LocalStorage;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn status(app: Router, url: &str) -> StatusCode {
+        let request = axum::http::Request::get(url).body(Body::empty()).unwrap();
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_temporary_local_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let signer = UrlSigner::new("secret");
+        let storage = LocalStorage::new(dir.path(), "https://example.com")
+            .await
+            .unwrap()
+            .with_signer(signer.clone());
+        Disks::register("serve-test", storage);
+
+        let file = FileUpload::from_bytes("report.pdf", mime::APPLICATION_PDF, Bytes::from("%PDF"))
+            .store_on("serve-test", "reports")
+            .await
+            .unwrap();
+        assert_eq!(
+            file.url().unwrap(),
+            "https://example.com/storage/reports/report.pdf"
+        );
+
+        let url = file.temporary_url(Duration::from_secs(60)).unwrap();
+        let local = url.trim_start_matches("https://example.com/storage");
+        let app = signed_file_router("serve-test", signer);
+
+        assert_eq!(status(app.clone(), local).await, StatusCode::OK);
+        assert_eq!(
+            status(app.clone(), "/reports/report.pdf").await,
+            StatusCode::FORBIDDEN
+        );
+        let other = local.replace("report.pdf", "other.pdf");
+        assert_eq!(status(app.clone(), &other).await, StatusCode::FORBIDDEN);
+
+        // Signed link to a file that is gone
+        let missing = UploadedFile {
+            path: "reports/missing.pdf".into(),
+            ..file
+        };
+        let url = missing.temporary_url(Duration::from_secs(60)).unwrap();
+        let local = url.trim_start_matches("https://example.com/storage");
+        assert_eq!(status(app, local).await, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,350 @@
+//! Streaming multipart files to storage
+//!
+//! [`FileUpload::from_multipart`] buffers the whole file, which is fine for
+//! avatars but not for videos. [`FileUpload::stream_field`] pipes a field
+//! into [`Filesystem::put_stream`](rf_storage::Filesystem::put_stream) instead,
+//! hashing and counting the chunks as they pass, so memory use stays at a
+//! chunk (or an S3 part) regardless of the file size.
+//! [`FileUpload::stream_reader`] does the same for files from other
+//! sources.
+
+use crate::{
+    sanitize_filename, scan::check_signature, ContentHasher, Disks, FieldRules, FileUpload,
+    UploadError, UploadResult, UploadedFile,
+};
+use axum::extract::multipart::Field;
+use bytes::{Bytes, BytesMut};
+use futures_util::{stream, Stream, StreamExt};
+use mime::Mime;
+use std::{
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// A file streamed to a disk
+#[derive(Debug, Clone)]
+pub struct StreamedUpload {
+    pub file: UploadedFile,
+    /// Hex-encoded SHA-256 of the content
+    pub sha256: String,
+}
+
+struct Progress {
+    hasher: ContentHasher,
+    size: u64,
+    mime_type: Mime,
+    checked: bool,
+    /// Why the stream was cut off
+    error: Option<UploadError>,
+}
+
+impl FileUpload {
+    /// Stream a multipart file field to `directory` on `disk`
+    ///
+    /// The rules of the field are enforced while streaming: a file of a type
+    /// that isn't allowed or beyond `max_size` is aborted and nothing is
+    /// stored. With [`FieldRules::check_content`], the signature is checked
+    /// on the first chunk. The installed [`ScanPolicy`](crate::ScanPolicy)
+    /// needs the whole content and doesn't run on streamed files; scan them
+    /// after storing if needed.
+    pub async fn stream_field(
+        field: Field<'_>,
+        disk: &str,
+        directory: &str,
+        rules: &FieldRules,
+    ) -> UploadResult<StreamedUpload> {
+        stream_field(field, disk, directory, rules, rules.max_size).await
+    }
+
+    /// Stream a file from any reader, e.g. a GraphQL upload, to
+    /// `directory` on `disk`
+    ///
+    /// Enforces `rules` like [`FileUpload::stream_field`]; without a
+    /// declared MIME type, the file is `application/octet-stream`.
+    pub async fn stream_reader<R>(
+        reader: R,
+        filename: &str,
+        mime_type: Option<Mime>,
+        disk: &str,
+        directory: &str,
+        rules: &FieldRules,
+    ) -> UploadResult<StreamedUpload>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let chunks = stream::unfold(reader, |mut reader| async move {
+            let mut buffer = BytesMut::with_capacity(CHUNK_SIZE);
+            match reader.read_buf(&mut buffer).await {
+                Ok(0) => None,
+                Ok(_) => Some((Ok(buffer.freeze()), reader)),
+                Err(e) => Some((Err(UploadError::Io(e)), reader)),
+            }
+        });
+        stream_chunks(
+            filename,
+            mime_type,
+            chunks,
+            disk,
+            directory,
+            rules,
+            rules.max_size,
+        )
+        .await
+    }
+}
+
+/// Stream `field` to the disk, cutting it off beyond `limit` bytes
+pub(crate) async fn stream_field(
+    field: Field<'_>,
+    disk: &str,
+    directory: &str,
+    rules: &FieldRules,
+    limit: Option<u64>,
+) -> UploadResult<StreamedUpload> {
+    let filename = field.file_name().ok_or(UploadError::NoFile)?.to_string();
+    let declared = field.content_type().and_then(|c| c.parse().ok());
+    let chunks = field.map(|chunk| chunk.map_err(|e| UploadError::Multipart(e.to_string())));
+    stream_chunks(&filename, declared, chunks, disk, directory, rules, limit).await
+}
+
+/// Read size of [`FileUpload::stream_reader`]
+const CHUNK_SIZE: usize = 64 * 1024;
+
+async fn stream_chunks<S>(
+    filename: &str,
+    declared: Option<Mime>,
+    chunks: S,
+    disk: &str,
+    directory: &str,
+    rules: &FieldRules,
+    limit: Option<u64>,
+) -> UploadResult<StreamedUpload>
+where
+    S: Stream<Item = UploadResult<Bytes>> + Send,
+{
+    let filename = sanitize_filename(filename);
+    let declared = declared.unwrap_or(mime::APPLICATION_OCTET_STREAM);
+    if !rules.check_content && !rules.allows(&declared) {
+        return Err(UploadError::InvalidMimeType(declared.to_string()));
+    }
+
+    let directory = directory.trim_matches('/');
+    let key = if directory.is_empty() {
+        filename.clone()
+    } else {
+        format!("{}/{}", directory, filename)
+    };
+    let storage = Disks::get(disk)?;
+
+    let progress = Arc::new(Mutex::new(Progress {
+        hasher: ContentHasher::new(),
+        size: 0,
+        mime_type: declared,
+        checked: !rules.check_content,
+        error: None,
+    }));
+    let state = progress.clone();
+    let chunks = chunks.map(move |chunk| {
+        let mut progress = state.lock().unwrap_or_else(|e| e.into_inner());
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => return Err(progress.abort(e)),
+        };
+
+        if !progress.checked && !chunk.is_empty() {
+            progress.checked = true;
+            match check_signature(&progress.mime_type, &chunk) {
+                Ok(mime_type) if rules.allows(&mime_type) => progress.mime_type = mime_type,
+                Ok(mime_type) => {
+                    return Err(progress.abort(UploadError::InvalidMimeType(mime_type.to_string())))
+                }
+                Err(e) => return Err(progress.abort(e)),
+            }
+        }
+
+        progress.size += chunk.len() as u64;
+        if let Some(max) = limit.filter(|max| progress.size > *max) {
+            let size = progress.size;
+            return Err(progress.abort(UploadError::FileTooLarge(size, max)));
+        }
+        progress.hasher.update(&chunk);
+        Ok(chunk)
+    });
+
+    let stored = storage.put_stream(&key, Box::pin(chunks)).await;
+    let mut progress = progress.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(error) = progress.error.take() {
+        return Err(error);
+    }
+    let size = stored?;
+
+    Ok(StreamedUpload {
+        file: UploadedFile {
+            filename,
+            path: PathBuf::from(key),
+            disk: Some(disk.to_string()),
+            size,
+            mime_type: progress.mime_type.to_string(),
+        },
+        sha256: std::mem::take(&mut progress.hasher).finish(),
+    })
+}
+
+impl Progress {
+    /// Remember why the upload was aborted; storage only sees an I/O error
+    fn abort(&mut self, error: UploadError) -> io::Error {
+        let message = error.to_string();
+        self.error = Some(error);
+        io::Error::other(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MultipartConfig, MultipartUploads};
+    use axum::{body::Body, extract::FromRequest, extract::Multipart, http::Request};
+    use rf_storage::LocalStorage;
+
+    async fn multipart(files: &[(&str, &str, &[u8])]) -> Multipart {
+        let mut body = Vec::new();
+        for (filename, mime, content) in files {
+            body.extend_from_slice(
+                format!(
+                    "--b\r\nContent-Disposition: form-data; name=\"video\"; filename=\"{}\"\r\n\
+                     Content-Type: {}\r\n\r\n",
+                    filename, mime
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(content);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(b"--b--\r\n");
+
+        let request = Request::post("/")
+            .header("content-type", "multipart/form-data; boundary=b")
+            .body(Body::from(body))
+            .unwrap();
+        Multipart::from_request(request, &()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stream_field() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path(), "http://localhost")
+            .await
+            .unwrap();
+        Disks::register("stream-local", storage);
+        let storage = Disks::get("stream-local").unwrap();
+
+        let mut form = multipart(&[
+            ("clip one.mp4", "video/mp4", b"hello world"),
+            ("big.mp4", "video/mp4", &[0; 64]),
+            ("fake.png", "image/png", b"not a png"),
+        ])
+        .await;
+        let rules = FieldRules::new()
+            .allow("video/")
+            .allow("image/")
+            .max_size(32);
+
+        let field = form.next_field().await.unwrap().unwrap();
+        let streamed = FileUpload::stream_field(field, "stream-local", "videos", &rules)
+            .await
+            .unwrap();
+        assert_eq!(streamed.file.key(), "videos/clip_one.mp4");
+        assert_eq!(streamed.file.size, 11);
+        assert_eq!(
+            streamed.sha256,
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+        assert_eq!(
+            storage.get("videos/clip_one.mp4").await.unwrap(),
+            b"hello world"
+        );
+
+        let field = form.next_field().await.unwrap().unwrap();
+        assert!(matches!(
+            FileUpload::stream_field(field, "stream-local", "videos", &rules).await,
+            Err(UploadError::FileTooLarge(_, 32))
+        ));
+        assert!(!storage.exists("videos/big.mp4").await.unwrap());
+
+        let field = form.next_field().await.unwrap().unwrap();
+        let rules = rules.check_content();
+        assert!(matches!(
+            FileUpload::stream_field(field, "stream-local", "videos", &rules).await,
+            Err(UploadError::MimeMismatch(..))
+        ));
+        assert_eq!(storage.list("videos").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_reader() {
+        Disks::register("stream-reader", rf_storage::MemoryStorage::new());
+        let rules = FieldRules::new().allow("text/").max_size(8);
+
+        let streamed = FileUpload::stream_reader(
+            &b"hello"[..],
+            "notes 1.txt",
+            Some(mime::TEXT_PLAIN),
+            "stream-reader",
+            "notes",
+            &rules,
+        )
+        .await
+        .unwrap();
+        assert_eq!(streamed.file.key(), "notes/notes_1.txt");
+        assert_eq!(streamed.file.size, 5);
+
+        assert!(matches!(
+            FileUpload::stream_reader(
+                &[0; 16][..],
+                "big.txt",
+                Some(mime::TEXT_PLAIN),
+                "stream-reader",
+                "notes",
+                &rules
+            )
+            .await,
+            Err(UploadError::FileTooLarge(_, 8))
+        ));
+        assert!(matches!(
+            FileUpload::stream_reader(&b"data"[..], "blob", None, "stream-reader", "notes", &rules)
+                .await,
+            Err(UploadError::InvalidMimeType(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_streamed_form_fields() {
+        Disks::register("stream-memory", rf_storage::MemoryStorage::new());
+        let config = MultipartConfig::new()
+            .field(
+                "video",
+                FieldRules::new()
+                    .max_count(1)
+                    .stream_to("stream-memory", "videos"),
+            )
+            .max_total_size(1024);
+        let mut form = multipart(&[
+            ("a.mp4", "video/mp4", b"first"),
+            ("b.mp4", "video/mp4", b"second"),
+        ])
+        .await;
+
+        let Err(UploadError::Invalid(errors)) = MultipartUploads::parse(&mut form, &config).await
+        else {
+            panic!("expected a count error");
+        };
+        assert_eq!(errors.len(), 1);
+
+        let mut form = multipart(&[("a.mp4", "video/mp4", b"first")]).await;
+        let uploads = MultipartUploads::parse(&mut form, &config).await.unwrap();
+        assert!(uploads.files("video").is_empty());
+        assert_eq!(uploads.stored("video")[0].file.key(), "videos/a.mp4");
+    }
+}
@@ -0,0 +1,747 @@
+//! Resumable uploads with the tus protocol
+//!
+//! [`TusServer::router`] implements tus 1.0.0 with the `creation`,
+//! `expiration` and `termination` extensions. Chunks are appended to a file
+//! in [`TusConfig::temp_dir`], together with a JSON record of the upload, so
+//! uploads survive restarts. When the last byte arrives the file is moved to
+//! the configured disk and the upload's [`UploadedFile`] is published to
+//! [`TusServer::subscribe`] receivers.
+//!
+//! ```no_run
+//! use axum::Router;
+//! use rf_upload::{TusConfig, TusServer};
+//! use std::time::Duration;
+//!
+//! let tus = TusServer::new(TusConfig {
+//!     endpoint: "/uploads".to_string(),
+//!     ..Default::default()
+//! });
+//! tus.spawn_cleanup(Duration::from_secs(3600));
+//!
+//! let app: Router = Router::new().nest("/uploads", tus.router());
+//! ```
+
+use crate::{sanitize_filename, Disks, UploadError, UploadResult, UploadedFile};
+use axum::{
+    body::Body,
+    extract::{Path as UrlPath, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{options, post},
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{io::AsyncWriteExt, sync::broadcast};
+
+/// Supported tus protocol version
+pub const TUS_VERSION: &str = "1.0.0";
+
+const TUS_EXTENSIONS: &str = "creation,expiration,termination";
+const OFFSET_CONTENT_TYPE: &str = "application/offset+octet-stream";
+
+const TUS_RESUMABLE: HeaderName = HeaderName::from_static("tus-resumable");
+const TUS_VERSION_HEADER: HeaderName = HeaderName::from_static("tus-version");
+const TUS_EXTENSION: HeaderName = HeaderName::from_static("tus-extension");
+const TUS_MAX_SIZE: HeaderName = HeaderName::from_static("tus-max-size");
+const UPLOAD_LENGTH: HeaderName = HeaderName::from_static("upload-length");
+const UPLOAD_OFFSET: HeaderName = HeaderName::from_static("upload-offset");
+const UPLOAD_METADATA: HeaderName = HeaderName::from_static("upload-metadata");
+const UPLOAD_EXPIRES: HeaderName = HeaderName::from_static("upload-expires");
+
+/// Resumable upload configuration
+#[derive(Debug, Clone)]
+pub struct TusConfig {
+    /// URL the router is mounted at, used in `Location` headers
+    pub endpoint: String,
+    /// Directory for partial uploads
+    pub temp_dir: PathBuf,
+    /// Disk completed uploads are stored on, see [`Disks`]
+    pub disk: String,
+    /// Directory on the disk
+    pub directory: String,
+    /// Maximum upload size in bytes
+    pub max_size: Option<u64>,
+    /// How long an upload is kept after its last chunk
+    pub expiry: Duration,
+}
+
+impl Default for TusConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "/files".to_string(),
+            temp_dir: PathBuf::from("storage/tus"),
+            disk: "local".to_string(),
+            directory: "uploads".to_string(),
+            max_size: Some(5 * 1024 * 1024 * 1024), // 5GB
+            expiry: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// State of a resumable upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TusUpload {
+    pub id: String,
+    /// Total size in bytes
+    pub length: u64,
+    /// Bytes received so far
+    pub offset: u64,
+    /// Decoded `Upload-Metadata`, e.g. `filename` and `filetype`
+    pub metadata: HashMap<String, String>,
+    /// Unix timestamp after which the upload is discarded
+    pub expires_at: u64,
+    /// The stored file, once the upload is complete
+    pub file: Option<UploadedFile>,
+}
+
+impl TusUpload {
+    /// Whether all bytes were received and the file was stored
+    pub fn is_complete(&self) -> bool {
+        self.file.is_some()
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expires_at <= now()
+    }
+}
+
+/// tus endpoint and the uploads it manages
+#[derive(Clone)]
+pub struct TusServer {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    config: TusConfig,
+    /// One writer per upload at a time
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    completed: broadcast::Sender<TusUpload>,
+}
+
+impl TusServer {
+    pub fn new(config: TusConfig) -> Self {
+        let (completed, _) = broadcast::channel(64);
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                locks: Mutex::new(HashMap::new()),
+                completed,
+            }),
+        }
+    }
+
+    /// Router implementing the tus protocol, to be nested at
+    /// [`TusConfig::endpoint`]
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/", options(discover).post(create))
+            .route(
+                "/:id",
+                post(unsupported)
+                    .head(status)
+                    .patch(append)
+                    .delete(terminate),
+            )
+            .with_state(self.clone())
+    }
+
+    /// Receive uploads as they complete
+    pub fn subscribe(&self) -> broadcast::Receiver<TusUpload> {
+        self.inner.completed.subscribe()
+    }
+
+    /// Look up an upload, e.g. to attach its file once the client reports
+    /// the upload id
+    pub async fn upload(&self, id: &str) -> UploadResult<Option<TusUpload>> {
+        Ok(self.load(id).await?.filter(|upload| !upload.is_expired()))
+    }
+
+    /// Start a new upload of `length` bytes
+    pub async fn create(
+        &self,
+        length: u64,
+        metadata: HashMap<String, String>,
+    ) -> UploadResult<TusUpload> {
+        if let Some(max) = self.inner.config.max_size {
+            if length > max {
+                return Err(UploadError::FileTooLarge(length, max));
+            }
+        }
+
+        let upload = TusUpload {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            length,
+            offset: 0,
+            metadata,
+            expires_at: self.expires_at(),
+            file: None,
+        };
+        tokio::fs::create_dir_all(&self.inner.config.temp_dir).await?;
+        tokio::fs::File::create(self.chunk_path(&upload.id)).await?;
+        self.save(&upload).await?;
+
+        if length == 0 {
+            return self.complete(upload).await;
+        }
+        Ok(upload)
+    }
+
+    /// Append a chunk starting at `offset`
+    ///
+    /// Progress is saved even if the stream breaks off, so the client can
+    /// resume from the last received byte.
+    pub async fn append<S, E>(&self, id: &str, offset: u64, chunks: S) -> UploadResult<TusUpload>
+    where
+        S: futures_util::Stream<Item = Result<bytes::Bytes, E>> + Unpin,
+        E: std::fmt::Display,
+    {
+        let lock = self.lock(id);
+        let _guard = lock.lock().await;
+
+        let mut upload = self
+            .upload(id)
+            .await?
+            .ok_or_else(|| UploadError::UploadNotFound(id.to_string()))?;
+        if upload.is_complete() || offset != upload.offset {
+            return Err(UploadError::OffsetMismatch(upload.offset, offset));
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(self.chunk_path(id))
+            .await?;
+        let result = write_chunks(&mut file, &mut upload, chunks).await;
+        file.flush().await?;
+
+        upload.expires_at = self.expires_at();
+        self.save(&upload).await?;
+        result?;
+
+        if upload.offset == upload.length {
+            upload = self.complete(upload).await?;
+        }
+        Ok(upload)
+    }
+
+    /// Discard an upload and its partial data
+    pub async fn terminate(&self, id: &str) -> UploadResult<()> {
+        let lock = self.lock(id);
+        let _guard = lock.lock().await;
+
+        if self.load(id).await?.is_none() {
+            return Err(UploadError::UploadNotFound(id.to_string()));
+        }
+        self.remove(id).await;
+        Ok(())
+    }
+
+    /// Remove expired uploads, returning how many were removed
+    ///
+    /// Files of completed uploads stay on their disk.
+    pub async fn cleanup_expired(&self) -> UploadResult<usize> {
+        let mut entries = match tokio::fs::read_dir(&self.inner.config.temp_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut removed = 0;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if matches!(self.load(id).await, Ok(Some(upload)) if upload.is_expired()) {
+                self.remove(id).await;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Remove expired uploads periodically. Requires a Tokio runtime.
+    pub fn spawn_cleanup(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let server = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = server.cleanup_expired().await {
+                    tracing::error!("Failed to clean up expired uploads: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Move the assembled file to the disk
+    async fn complete(&self, mut upload: TusUpload) -> UploadResult<TusUpload> {
+        let config = &self.inner.config;
+        let filename = sanitize_filename(
+            upload
+                .metadata
+                .get("filename")
+                .map_or(upload.id.as_str(), String::as_str),
+        );
+        let mime_type = upload.metadata.get("filetype").cloned().unwrap_or_else(|| {
+            mime_guess::from_path(&filename)
+                .first_or_octet_stream()
+                .to_string()
+        });
+        let directory = config.directory.trim_matches('/');
+        let key = if directory.is_empty() {
+            format!("{}-{}", upload.id, filename)
+        } else {
+            format!("{}/{}-{}", directory, upload.id, filename)
+        };
+
+        let chunk_path = self.chunk_path(&upload.id);
+        Disks::get(&config.disk)?
+            .put_file(&key, &chunk_path)
+            .await?;
+        tokio::fs::remove_file(&chunk_path).await?;
+
+        upload.file = Some(UploadedFile {
+            filename,
+            path: PathBuf::from(key),
+            disk: Some(config.disk.clone()),
+            size: upload.length,
+            mime_type,
+        });
+        self.save(&upload).await?;
+
+        // No subscribers is fine
+        let _ = self.inner.completed.send(upload.clone());
+        Ok(upload)
+    }
+
+    fn lock(&self, id: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut locks = self.inner.locks.lock().unwrap_or_else(|e| e.into_inner());
+        // Forget locks nobody holds
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(id.to_string()).or_default().clone()
+    }
+
+    async fn load(&self, id: &str) -> UploadResult<Option<TusUpload>> {
+        if !valid_id(id) {
+            return Ok(None);
+        }
+        match tokio::fs::read(self.record_path(id)).await {
+            Ok(json) => Ok(Some(serde_json::from_slice(&json).map_err(|e| {
+                UploadError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+            })?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save(&self, upload: &TusUpload) -> UploadResult<()> {
+        let json = serde_json::to_vec(upload).map_err(std::io::Error::from)?;
+        tokio::fs::write(self.record_path(&upload.id), json).await?;
+        Ok(())
+    }
+
+    async fn remove(&self, id: &str) {
+        let _ = tokio::fs::remove_file(self.chunk_path(id)).await;
+        let _ = tokio::fs::remove_file(self.record_path(id)).await;
+    }
+
+    fn chunk_path(&self, id: &str) -> PathBuf {
+        self.inner.config.temp_dir.join(format!("{}.bin", id))
+    }
+
+    fn record_path(&self, id: &str) -> PathBuf {
+        self.inner.config.temp_dir.join(format!("{}.json", id))
+    }
+
+    fn expires_at(&self) -> u64 {
+        now() + self.inner.config.expiry.as_secs()
+    }
+}
+
+async fn write_chunks<S, E>(
+    file: &mut tokio::fs::File,
+    upload: &mut TusUpload,
+    mut chunks: S,
+) -> UploadResult<()>
+where
+    S: futures_util::Stream<Item = Result<bytes::Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(|e| {
+            UploadError::Io(std::io::Error::new(
+                std::io::ErrorKind::ConnectionAborted,
+                e.to_string(),
+            ))
+        })?;
+        let end = upload.offset + chunk.len() as u64;
+        if end > upload.length {
+            return Err(UploadError::FileTooLarge(end, upload.length));
+        }
+        file.write_all(&chunk).await?;
+        upload.offset = end;
+    }
+    Ok(())
+}
+
+/// Upload ids are generated UUIDs; anything else could escape the temp dir
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// `Upload-Metadata`: comma-separated `key base64(value)` pairs
+fn parse_metadata(header: &str) -> Option<HashMap<String, String>> {
+    header
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once(' ').unwrap_or((pair, ""));
+            let value = BASE64.decode(value.trim()).ok()?;
+            Some((key.to_string(), String::from_utf8(value).ok()?))
+        })
+        .collect()
+}
+
+fn tus_response(status: StatusCode, headers: &[(HeaderName, String)]) -> Response {
+    let mut response = status.into_response();
+    let map = response.headers_mut();
+    map.insert(TUS_RESUMABLE, HeaderValue::from_static(TUS_VERSION));
+    for (name, value) in headers {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            map.insert(name.clone(), value);
+        }
+    }
+    response
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &HeaderName) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Reject requests for another protocol version
+fn version_mismatch(headers: &HeaderMap) -> Option<Response> {
+    if header(headers, &TUS_RESUMABLE) == Some(TUS_VERSION) {
+        return None;
+    }
+    Some(tus_response(
+        StatusCode::PRECONDITION_FAILED,
+        &[(TUS_VERSION_HEADER, TUS_VERSION.to_string())],
+    ))
+}
+
+fn expires_header(upload: &TusUpload) -> (HeaderName, String) {
+    let expires = UNIX_EPOCH + Duration::from_secs(upload.expires_at);
+    (UPLOAD_EXPIRES, httpdate::fmt_http_date(expires))
+}
+
+fn error_response(error: UploadError) -> Response {
+    let status = match &error {
+        UploadError::UploadNotFound(_) => StatusCode::NOT_FOUND,
+        UploadError::OffsetMismatch(..) => StatusCode::CONFLICT,
+        UploadError::FileTooLarge(..) => StatusCode::PAYLOAD_TOO_LARGE,
+        _ => {
+            tracing::error!("Resumable upload failed: {}", error);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    tus_response(status, &[])
+}
+
+async fn discover(State(server): State<TusServer>) -> Response {
+    let mut headers = vec![
+        (TUS_VERSION_HEADER, TUS_VERSION.to_string()),
+        (TUS_EXTENSION, TUS_EXTENSIONS.to_string()),
+    ];
+    if let Some(max) = server.inner.config.max_size {
+        headers.push((TUS_MAX_SIZE, max.to_string()));
+    }
+    tus_response(StatusCode::NO_CONTENT, &headers)
+}
+
+async fn create(State(server): State<TusServer>, headers: HeaderMap) -> Response {
+    if let Some(rejection) = version_mismatch(&headers) {
+        return rejection;
+    }
+    let Some(length) = header(&headers, &UPLOAD_LENGTH).and_then(|v| v.parse().ok()) else {
+        return tus_response(StatusCode::BAD_REQUEST, &[]);
+    };
+    let metadata = match header(&headers, &UPLOAD_METADATA) {
+        Some(value) => match parse_metadata(value) {
+            Some(metadata) => metadata,
+            None => return tus_response(StatusCode::BAD_REQUEST, &[]),
+        },
+        None => HashMap::new(),
+    };
+
+    match server.create(length, metadata).await {
+        Ok(upload) => {
+            let location = format!(
+                "{}/{}",
+                server.inner.config.endpoint.trim_end_matches('/'),
+                upload.id
+            );
+            tus_response(
+                StatusCode::CREATED,
+                &[(header::LOCATION, location), expires_header(&upload)],
+            )
+        }
+        Err(e) => error_response(e),
+    }
+}
+
+async fn status(
+    State(server): State<TusServer>,
+    UrlPath(id): UrlPath<String>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(rejection) = version_mismatch(&headers) {
+        return rejection;
+    }
+    match server.upload(&id).await {
+        Ok(Some(upload)) => tus_response(
+            StatusCode::OK,
+            &[
+                (UPLOAD_OFFSET, upload.offset.to_string()),
+                (UPLOAD_LENGTH, upload.length.to_string()),
+                (header::CACHE_CONTROL, "no-store".to_string()),
+                expires_header(&upload),
+            ],
+        ),
+        Ok(None) => tus_response(StatusCode::NOT_FOUND, &[]),
+        Err(e) => error_response(e),
+    }
+}
+
+async fn append(
+    State(server): State<TusServer>,
+    UrlPath(id): UrlPath<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    if let Some(rejection) = version_mismatch(&headers) {
+        return rejection;
+    }
+    if header(&headers, &header::CONTENT_TYPE) != Some(OFFSET_CONTENT_TYPE) {
+        return tus_response(StatusCode::UNSUPPORTED_MEDIA_TYPE, &[]);
+    }
+    let Some(offset) = header(&headers, &UPLOAD_OFFSET).and_then(|v| v.parse().ok()) else {
+        return tus_response(StatusCode::BAD_REQUEST, &[]);
+    };
+
+    match server.append(&id, offset, body.into_data_stream()).await {
+        Ok(upload) => tus_response(
+            StatusCode::NO_CONTENT,
+            &[
+                (UPLOAD_OFFSET, upload.offset.to_string()),
+                expires_header(&upload),
+            ],
+        ),
+        Err(e) => error_response(e),
+    }
+}
+
+async fn terminate(
+    State(server): State<TusServer>,
+    UrlPath(id): UrlPath<String>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(rejection) = version_mismatch(&headers) {
+        return rejection;
+    }
+    match server.terminate(&id).await {
+        Ok(()) => tus_response(StatusCode::NO_CONTENT, &[]),
+        Err(e) => error_response(e),
+    }
+}
+
+async fn unsupported() -> Response {
+    tus_response(StatusCode::METHOD_NOT_ALLOWED, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use rf_storage::{Filesystem, MemoryStorage};
+    use tower::ServiceExt;
+
+    fn server(dir: &std::path::Path, disk: &str) -> TusServer {
+        TusServer::new(TusConfig {
+            endpoint: "/files/".to_string(),
+            temp_dir: dir.to_path_buf(),
+            disk: disk.to_string(),
+            directory: "videos".to_string(),
+            max_size: Some(1024),
+            expiry: Duration::from_secs(60),
+        })
+    }
+
+    fn request(method: &str, uri: &str) -> axum::http::request::Builder {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(TUS_RESUMABLE, TUS_VERSION)
+    }
+
+    async fn send(router: &Router, request: Request<Body>) -> Response {
+        router.clone().oneshot(request).await.unwrap()
+    }
+
+    fn patch(uri: &str, offset: u64, body: &'static str) -> Request<Body> {
+        request("PATCH", uri)
+            .header(header::CONTENT_TYPE, OFFSET_CONTENT_TYPE)
+            .header(UPLOAD_OFFSET, offset)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_resumable_upload() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = MemoryStorage::new();
+        Disks::register("tus-test", storage.clone());
+        let tus = server(dir.path(), "tus-test");
+        let mut completed = tus.subscribe();
+        let router = tus.router();
+
+        let response = send(&router, Request::options("/").body(Body::empty()).unwrap()).await;
+        assert_eq!(response.headers()[TUS_MAX_SIZE], "1024");
+
+        let metadata = format!(
+            "filename {},filetype {}",
+            BASE64.encode("clip.mp4"),
+            BASE64.encode("video/mp4")
+        );
+        let response = send(
+            &router,
+            request("POST", "/")
+                .header(UPLOAD_LENGTH, 11)
+                .header(UPLOAD_METADATA, metadata)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert!(response.headers().contains_key(UPLOAD_EXPIRES));
+        let location = response.headers()[header::LOCATION].to_str().unwrap();
+        let uri = location.trim_start_matches("/files");
+        assert!(location.starts_with("/files/"));
+
+        let response = send(&router, patch(uri, 0, "hello")).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.headers()[UPLOAD_OFFSET], "5");
+
+        // Resuming from the wrong offset
+        let response = send(&router, patch(uri, 0, "hello")).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = send(&router, request("HEAD", uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(response.headers()[UPLOAD_OFFSET], "5");
+        assert_eq!(response.headers()[UPLOAD_LENGTH], "11");
+
+        let response = send(&router, patch(uri, 5, " world")).await;
+        assert_eq!(response.headers()[UPLOAD_OFFSET], "11");
+
+        let upload = completed.try_recv().unwrap();
+        let file = upload.file.unwrap();
+        assert_eq!(file.filename, "clip.mp4");
+        assert_eq!(file.mime_type, "video/mp4");
+        assert_eq!(storage.get(&file.key()).await.unwrap(), b"hello world");
+        assert!(file.key().starts_with("videos/"));
+        assert!(!tus.chunk_path(&upload.id).exists());
+        assert!(tus.upload(&upload.id).await.unwrap().unwrap().is_complete());
+    }
+
+    #[tokio::test]
+    async fn test_protocol_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let router = server(dir.path(), "unused").router();
+
+        let response = send(
+            &router,
+            Request::post("/")
+                .header(UPLOAD_LENGTH, 10)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+        let response = send(
+            &router,
+            request("POST", "/")
+                .header(UPLOAD_LENGTH, 2048)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = send(&router, patch("/..%2Fescape", 0, "x")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = send(
+            &router,
+            request("PATCH", "/abc")
+                .header(UPLOAD_OFFSET, 0)
+                .body(Body::from("x"))
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn test_overlong_chunk_and_termination() {
+        let dir = tempfile::tempdir().unwrap();
+        let tus = server(dir.path(), "unused");
+        let upload = tus.create(3, HashMap::new()).await.unwrap();
+
+        let chunks =
+            futures_util::stream::iter([Ok::<_, std::io::Error>(bytes::Bytes::from("toolong"))]);
+        assert!(matches!(
+            tus.append(&upload.id, 0, chunks).await,
+            Err(UploadError::FileTooLarge(7, 3))
+        ));
+
+        tus.terminate(&upload.id).await.unwrap();
+        assert!(tus.upload(&upload.id).await.unwrap().is_none());
+        assert!(matches!(
+            tus.terminate(&upload.id).await,
+            Err(UploadError::UploadNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tus = server(dir.path(), "unused");
+        Arc::get_mut(&mut tus.inner).unwrap().config.expiry = Duration::ZERO;
+
+        let upload = tus.create(10, HashMap::new()).await.unwrap();
+        assert!(tus.upload(&upload.id).await.unwrap().is_none());
+        assert_eq!(tus.cleanup_expired().await.unwrap(), 1);
+        assert!(!tus.chunk_path(&upload.id).exists());
+        assert!(!tus.record_path(&upload.id).exists());
+    }
+}
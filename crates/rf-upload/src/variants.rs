@@ -0,0 +1,530 @@
+//! Named image variants (requires "image-processing" feature)
+//!
+//! Variants are installed per disk. Images stored on that disk with
+//! [`FileUpload::store_on`](crate::FileUpload::store_on) get every variant
+//! generated and stored next to the original, under
+//! `{directory}/variants/{name}/`.
+//!
+//! ```no_run
+//! use rf_upload::{ImageVariants, Variant, VariantFormat};
+//!
+//! ImageVariants::new()
+//!     .variant(Variant::new("thumb").fill(150, 150))
+//!     .variant(Variant::new("medium").fit(800, 800))
+//!     .variant(Variant::new("avif").format(VariantFormat::Avif { quality: 80, speed: 6 }))
+//!     .background()
+//!     .install("public");
+//! ```
+//!
+//! Afterwards `file.variant_url("thumb")` points at the thumbnail.
+
+use crate::{image_processing::ResizeMode, Disks, UploadError, UploadResult, UploadedFile};
+use image::{
+    codecs::{avif::AvifEncoder, jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+    DynamicImage, ImageFormat,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    sync::{Arc, OnceLock, RwLock},
+    time::Duration,
+};
+
+/// Encoding of a variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariantFormat {
+    /// Same format as the original
+    Original,
+    Jpeg {
+        quality: u8,
+    },
+    Png,
+    /// Lossless WebP; the `image` crate has no lossy WebP encoder
+    WebP,
+    /// AVIF; `speed` ranges from 1 (slow, smallest) to 10 (fast)
+    Avif {
+        quality: u8,
+        speed: u8,
+    },
+}
+
+/// A named rendition of uploaded images
+#[derive(Debug, Clone)]
+pub struct Variant {
+    name: String,
+    resize: Option<(u32, u32, ResizeMode)>,
+    format: VariantFormat,
+}
+
+impl Variant {
+    /// Variant keeping the original size and format
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            resize: None,
+            format: VariantFormat::Original,
+        }
+    }
+
+    /// Fit within `width` x `height`, preserving the aspect ratio
+    pub fn fit(self, width: u32, height: u32) -> Self {
+        self.resize(width, height, ResizeMode::Fit)
+    }
+
+    /// Fill `width` x `height`, cropping what doesn't fit
+    pub fn fill(self, width: u32, height: u32) -> Self {
+        self.resize(width, height, ResizeMode::Fill)
+    }
+
+    pub fn resize(mut self, width: u32, height: u32, mode: ResizeMode) -> Self {
+        self.resize = Some((width, height, mode));
+        self
+    }
+
+    pub fn format(mut self, format: VariantFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Storage key of this variant of the original at `key`
+    pub fn key(&self, key: &str) -> String {
+        let (directory, filename) = match key.rsplit_once('/') {
+            Some((directory, filename)) => (format!("{}/", directory), filename),
+            None => (String::new(), key),
+        };
+        let filename = match self.extension() {
+            Some(extension) => {
+                let stem = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem);
+                format!("{}.{}", stem, extension)
+            }
+            None => filename.to_string(),
+        };
+        format!("{}variants/{}/{}", directory, self.name, filename)
+    }
+
+    fn extension(&self) -> Option<&'static str> {
+        match self.format {
+            VariantFormat::Original => None,
+            VariantFormat::Jpeg { .. } => Some("jpg"),
+            VariantFormat::Png => Some("png"),
+            VariantFormat::WebP => Some("webp"),
+            VariantFormat::Avif { .. } => Some("avif"),
+        }
+    }
+
+    fn render(&self, image: &DynamicImage, original: ImageFormat) -> image::ImageResult<Vec<u8>> {
+        let image = match self.resize {
+            Some((width, height, mode)) => {
+                let filter = image::imageops::FilterType::Lanczos3;
+                match mode {
+                    ResizeMode::Fit => image.resize(width, height, filter),
+                    ResizeMode::Fill => image.resize_to_fill(width, height, filter),
+                    ResizeMode::Exact => image.resize_exact(width, height, filter),
+                }
+            }
+            None => image.clone(),
+        };
+
+        let mut bytes = Vec::new();
+        match self.format {
+            VariantFormat::Original => image.write_to(&mut Cursor::new(&mut bytes), original)?,
+            VariantFormat::Png => image.write_with_encoder(PngEncoder::new(&mut bytes))?,
+            VariantFormat::WebP => {
+                image.write_with_encoder(WebPEncoder::new_lossless(&mut bytes))?
+            }
+            VariantFormat::Jpeg { quality } => {
+                // JPEG has no alpha channel
+                DynamicImage::ImageRgb8(image.to_rgb8())
+                    .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, quality))?
+            }
+            VariantFormat::Avif { quality, speed } => image.write_with_encoder(
+                AvifEncoder::new_with_speed_quality(&mut bytes, speed, quality),
+            )?,
+        }
+        Ok(bytes)
+    }
+}
+
+#[derive(Clone)]
+enum Processing {
+    Inline,
+    Background,
+    Dispatch(Arc<dyn Fn(GenerateVariants) + Send + Sync>),
+}
+
+/// Variants generated for images stored on a disk
+#[derive(Clone)]
+pub struct ImageVariants {
+    variants: Vec<Variant>,
+    processing: Processing,
+}
+
+type Registry = RwLock<HashMap<String, Arc<ImageVariants>>>;
+
+fn registry() -> &'static Registry {
+    static VARIANTS: OnceLock<Registry> = OnceLock::new();
+    VARIANTS.get_or_init(Default::default)
+}
+
+impl Default for ImageVariants {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImageVariants {
+    /// No variants, generated inline while storing the upload
+    pub fn new() -> Self {
+        Self {
+            variants: Vec::new(),
+            processing: Processing::Inline,
+        }
+    }
+
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.variants.retain(|v| v.name != variant.name);
+        self.variants.push(variant);
+        self
+    }
+
+    /// Generate variants in a spawned task instead of delaying the upload
+    pub fn background(mut self) -> Self {
+        self.processing = Processing::Background;
+        self
+    }
+
+    /// Hand a [`GenerateVariants`] job to a queue instead, e.g. an
+    /// `rf_jobs` job calling [`GenerateVariants::run`]
+    pub fn dispatch_with(
+        mut self,
+        dispatch: impl Fn(GenerateVariants) + Send + Sync + 'static,
+    ) -> Self {
+        self.processing = Processing::Dispatch(Arc::new(dispatch));
+        self
+    }
+
+    /// Use these variants for images stored on `disk`
+    pub fn install(self, disk: impl Into<String>) {
+        registry()
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(disk.into(), Arc::new(self));
+    }
+
+    /// Variants installed for `disk`
+    pub fn for_disk(disk: &str) -> Option<Arc<ImageVariants>> {
+        registry()
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(disk)
+            .cloned()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Variant> {
+        self.variants.iter().find(|v| v.name == name)
+    }
+
+    /// Render and store all variants of the image at `key`, returning
+    /// their keys
+    pub async fn generate(&self, disk: &str, key: &str) -> UploadResult<Vec<String>> {
+        let storage = Disks::get(disk)?;
+        let rendered = self.render(storage.get(key).await?, key).await?;
+
+        let mut keys = Vec::with_capacity(rendered.len());
+        for (key, bytes) in rendered {
+            storage.put(&key, bytes).await?;
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
+    /// Render all variants of `original`, with their keys
+    async fn render(&self, original: Vec<u8>, key: &str) -> UploadResult<Vec<(String, Vec<u8>)>> {
+        let variants = self.variants.clone();
+        let key = key.to_string();
+        tokio::task::spawn_blocking(move || {
+            let format = image::guess_format(&original)?;
+            let image = image::load_from_memory_with_format(&original, format)?;
+            variants
+                .iter()
+                .map(|variant| Ok((variant.key(&key), variant.render(&image, format)?)))
+                .collect::<image::ImageResult<Vec<_>>>()
+        })
+        .await
+        .map_err(|e| UploadError::ImageProcessing(e.to_string()))?
+        .map_err(|e| UploadError::ImageProcessing(e.to_string()))
+    }
+}
+
+/// Job generating the variants of a stored image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerateVariants {
+    pub disk: String,
+    pub key: String,
+}
+
+impl GenerateVariants {
+    /// Generate the variants installed for the job's disk
+    pub async fn run(&self) -> UploadResult<Vec<String>> {
+        match ImageVariants::for_disk(&self.disk) {
+            Some(variants) => variants.generate(&self.disk, &self.key).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Like [`run`](Self::run), reporting each stored variant to `progress`
+    /// and completing it with the variant keys
+    #[cfg(feature = "progress")]
+    pub async fn run_tracked(
+        &self,
+        progress: &mut rf_progress::JobProgress,
+    ) -> UploadResult<Vec<String>> {
+        let result = self.generate_tracked(progress).await;
+        match &result {
+            Ok(keys) => progress.complete(keys).await,
+            Err(e) => progress.fail(e).await,
+        }
+        .map_err(progress_error)?;
+        result
+    }
+
+    #[cfg(feature = "progress")]
+    async fn generate_tracked(
+        &self,
+        progress: &mut rf_progress::JobProgress,
+    ) -> UploadResult<Vec<String>> {
+        let Some(variants) = ImageVariants::for_disk(&self.disk) else {
+            return Ok(Vec::new());
+        };
+        let storage = Disks::get(&self.disk)?;
+        progress
+            .steps(variants.variants.len() as u32 + 1)
+            .await
+            .map_err(progress_error)?;
+        progress
+            .advance("Rendering variants")
+            .await
+            .map_err(progress_error)?;
+        let rendered = variants
+            .render(storage.get(&self.key).await?, &self.key)
+            .await?;
+
+        let mut keys = Vec::with_capacity(rendered.len());
+        for (key, bytes) in rendered {
+            progress
+                .advance(format!("Storing {}", key))
+                .await
+                .map_err(progress_error)?;
+            storage.put(&key, bytes).await?;
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+}
+
+#[cfg(feature = "progress")]
+fn progress_error(e: rf_progress::ProgressError) -> UploadError {
+    UploadError::Progress(e.to_string())
+}
+
+/// Generate variants after an upload was stored on `disk`
+pub(crate) async fn on_stored(disk: &str, key: &str, mime_type: &str) -> UploadResult<()> {
+    let Some(variants) = ImageVariants::for_disk(disk) else {
+        return Ok(());
+    };
+    let decodable = ImageFormat::from_mime_type(mime_type).is_some_and(|f| f.reading_enabled());
+    if !decodable || variants.variants.is_empty() {
+        return Ok(());
+    }
+
+    let job = GenerateVariants {
+        disk: disk.to_string(),
+        key: key.to_string(),
+    };
+    match &variants.processing {
+        Processing::Inline => {
+            variants.generate(disk, key).await?;
+        }
+        Processing::Background => {
+            tokio::spawn(async move {
+                if let Err(e) = job.run().await {
+                    tracing::error!(key = %job.key, "Failed to generate image variants: {}", e);
+                }
+            });
+        }
+        Processing::Dispatch(dispatch) => dispatch(job),
+    }
+    Ok(())
+}
+
+impl UploadedFile {
+    /// Storage key of variant `name`
+    pub fn variant_key(&self, name: &str) -> UploadResult<String> {
+        let disk = self
+            .disk
+            .as_deref()
+            .ok_or_else(|| UploadError::NotOnDisk(self.path.display().to_string()))?;
+        let variants = ImageVariants::for_disk(disk)
+            .ok_or_else(|| UploadError::UnknownVariant(name.to_string()))?;
+        let variant = variants
+            .get(name)
+            .ok_or_else(|| UploadError::UnknownVariant(name.to_string()))?;
+        Ok(variant.key(&self.key()))
+    }
+
+    /// Public URL of variant `name`
+    pub fn variant_url(&self, name: &str) -> UploadResult<String> {
+        let key = self.variant_key(name)?;
+        Ok(self.storage()?.url(&key))
+    }
+
+    /// Temporary URL of variant `name`, see [`UploadedFile::temporary_url`]
+    pub fn variant_temporary_url(&self, name: &str, expires_in: Duration) -> UploadResult<String> {
+        let key = self.variant_key(name)?;
+        Ok(self.storage()?.temporary_url(&key, expires_in)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileUpload;
+    use image::{Rgba, RgbaImage};
+    use rf_storage::MemoryStorage;
+    use std::sync::Mutex;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(width, height, Rgba([200, 40, 40, 255]));
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(image)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    fn dimensions(bytes: &[u8]) -> (u32, u32) {
+        let image = image::load_from_memory(bytes).unwrap();
+        (image.width(), image.height())
+    }
+
+    #[test]
+    fn test_variant_key() {
+        let thumb = Variant::new("thumb").fill(150, 150);
+        assert_eq!(thumb.key("avatars/me.png"), "avatars/variants/thumb/me.png");
+
+        let webp = Variant::new("webp").format(VariantFormat::WebP);
+        assert_eq!(webp.key("me.v2.png"), "variants/webp/me.v2.webp");
+    }
+
+    #[tokio::test]
+    async fn test_inline_variants() {
+        let storage = MemoryStorage::with_url("https://cdn.test");
+        Disks::register("variants-inline", storage.clone());
+        ImageVariants::new()
+            .variant(Variant::new("thumb").fill(150, 150))
+            .variant(Variant::new("medium").fit(80, 80))
+            .variant(Variant::new("webp").format(VariantFormat::WebP))
+            .variant(Variant::new("jpeg").format(VariantFormat::Jpeg { quality: 80 }))
+            .install("variants-inline");
+
+        let file = FileUpload::from_bytes("photo.png", mime::IMAGE_PNG, png(300, 200))
+            .store_on("variants-inline", "photos")
+            .await
+            .unwrap();
+
+        let files = storage.files();
+        assert_eq!(
+            dimensions(&files["photos/variants/thumb/photo.png"]),
+            (150, 150)
+        );
+        assert_eq!(
+            dimensions(&files["photos/variants/medium/photo.png"]),
+            (80, 53)
+        );
+        assert_eq!(
+            image::guess_format(&files["photos/variants/webp/photo.webp"]).unwrap(),
+            ImageFormat::WebP
+        );
+        assert_eq!(
+            image::guess_format(&files["photos/variants/jpeg/photo.jpg"]).unwrap(),
+            ImageFormat::Jpeg
+        );
+        assert_eq!(
+            file.variant_url("thumb").unwrap(),
+            "https://cdn.test/storage/photos/variants/thumb/photo.png"
+        );
+        assert!(matches!(
+            file.variant_url("huge"),
+            Err(UploadError::UnknownVariant(_))
+        ));
+
+        // Not an image
+        FileUpload::from_bytes("notes.txt", mime::TEXT_PLAIN, "hello")
+            .store_on("variants-inline", "photos")
+            .await
+            .unwrap();
+        assert_eq!(storage.count(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_dispatched_variants() {
+        let storage = MemoryStorage::new();
+        Disks::register("variants-queued", storage.clone());
+        let queued = Arc::new(Mutex::new(Vec::new()));
+        let queue = queued.clone();
+        ImageVariants::new()
+            .variant(Variant::new("thumb").fit(10, 10))
+            .dispatch_with(move |job| queue.lock().unwrap().push(job))
+            .install("variants-queued");
+
+        FileUpload::from_bytes("a.png", mime::IMAGE_PNG, png(40, 20))
+            .store_on("variants-queued", "")
+            .await
+            .unwrap();
+        assert_eq!(storage.count(), 1);
+
+        let job = queued.lock().unwrap().pop().unwrap();
+        // Jobs travel through the queue serialized
+        let job: GenerateVariants =
+            serde_json::from_str(&serde_json::to_string(&job).unwrap()).unwrap();
+        assert_eq!(job.run().await.unwrap(), vec!["variants/thumb/a.png"]);
+        assert_eq!(
+            dimensions(&storage.files()["variants/thumb/a.png"]),
+            (10, 5)
+        );
+    }
+
+    #[cfg(feature = "progress")]
+    #[tokio::test]
+    async fn test_tracked_variants() {
+        use rf_progress::{JobStatus, ProgressTracker};
+        use rf_storage::Filesystem;
+
+        let storage = MemoryStorage::new();
+        Disks::register("variants-tracked", storage.clone());
+        ImageVariants::new()
+            .variant(Variant::new("thumb").fit(10, 10))
+            .variant(Variant::new("small").fit(20, 20))
+            .install("variants-tracked");
+        storage.put("a.png", png(40, 20)).await.unwrap();
+
+        let tracker = ProgressTracker::memory();
+        let mut progress = tracker.start().await.unwrap();
+        let job = GenerateVariants {
+            disk: "variants-tracked".to_string(),
+            key: "a.png".to_string(),
+        };
+        let keys = job.run_tracked(&mut progress).await.unwrap();
+        assert_eq!(keys, vec!["variants/thumb/a.png", "variants/small/a.png"]);
+
+        let progress = tracker.get(progress.id()).await.unwrap();
+        assert_eq!(progress.status, JobStatus::Completed);
+        assert_eq!((progress.step, progress.total_steps), (3, Some(3)));
+        assert_eq!(progress.result, Some(serde_json::json!(keys)));
+    }
+}
@@ -25,7 +25,7 @@ impl ValidationErrors {
     pub fn add(&mut self, field: impl Into<String>, error: FieldError) {
         self.errors
             .entry(field.into())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(error);
     }
 
@@ -94,34 +94,159 @@ impl FieldError {
 }
 
 /// Convert validator::ValidationErrors to our ValidationErrors
+///
+/// Errors of nested structs and lists are keyed by their path, e.g.
+/// `address.city` or `items[0].name`. Errors of `schema` (cross-field)
+/// validators are keyed `__all__`.
 impl From<validator::ValidationErrors> for ValidationErrors {
     fn from(errors: validator::ValidationErrors) -> Self {
         let mut validation_errors = ValidationErrors::new();
+        collect_errors(&mut validation_errors, None, &errors);
+        validation_errors
+    }
+}
+
+fn collect_errors(
+    validation_errors: &mut ValidationErrors,
+    prefix: Option<&str>,
+    errors: &validator::ValidationErrors,
+) {
+    use validator::ValidationErrorsKind;
+
+    for (field, kind) in errors.errors() {
+        let path = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, field),
+            None => field.to_string(),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(field_errors) => {
+                for error in field_errors {
+                    validation_errors.add(path.clone(), field_error(&path, error));
+                }
+            }
+            ValidationErrorsKind::Struct(nested) => {
+                collect_errors(validation_errors, Some(&path), nested);
+            }
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    let item = format!("{}[{}]", path, index);
+                    collect_errors(validation_errors, Some(&item), nested);
+                }
+            }
+        }
+    }
+}
+
+fn field_error(field: &str, error: &validator::ValidationError) -> FieldError {
+    // validator's params are HashMap<Cow<str>, Value>, convert to HashMap<String, Value>
+    let params: HashMap<String, serde_json::Value> = error
+        .params
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect();
+
+    let message = match &error.message {
+        Some(message) => message.to_string(),
+        None => default_message(&error.code, &params)
+            .unwrap_or_else(|| format!("Validation failed for field '{}'", field)),
+    };
+
+    let mut field_error = FieldError::new(error.code.to_string(), message);
+    if !params.is_empty() {
+        field_error.params = Some(params);
+    }
+    field_error
+}
+
+/// English message for the built-in rules
+fn default_message(code: &str, params: &HashMap<String, serde_json::Value>) -> Option<String> {
+    let param = |name: &str| params.get(name).map(|value| value.to_string());
+
+    let message = match code {
+        "required" => "This field is required".to_string(),
+        "email" => "Invalid email address".to_string(),
+        "url" => "Invalid URL".to_string(),
+        "uuid" => "Invalid UUID".to_string(),
+        "regex" => "Invalid format".to_string(),
+        "not_blank" => "Must not be blank".to_string(),
+        "must_match" => match param("other") {
+            Some(other) => format!("Must match {}", other.trim_matches('"')),
+            None => "Fields do not match".to_string(),
+        },
+        "length" => match (param("equal"), param("min"), param("max")) {
+            (Some(equal), _, _) => format!("Must be exactly {} characters long", equal),
+            (None, Some(min), Some(max)) => {
+                format!("Must be between {} and {} characters long", min, max)
+            }
+            (None, Some(min), None) => format!("Must be at least {} characters long", min),
+            (None, None, Some(max)) => format!("Must be at most {} characters long", max),
+            (None, None, None) => return None,
+        },
+        "range" => match (param("min"), param("max")) {
+            (Some(min), Some(max)) => format!("Must be between {} and {}", min, max),
+            (Some(min), None) => format!("Must be at least {}", min),
+            (None, Some(max)) => format!("Must be at most {}", max),
+            (None, None) => return None,
+        },
+        _ => return None,
+    };
+    Some(message)
+}
+
+#[cfg(feature = "i18n")]
+impl ValidationErrors {
+    /// Translate the messages into `locale`
+    ///
+    /// Messages are looked up as `validation.<code>`, e.g.
+    /// `validation.length`, with the error's params and the `field` name as
+    /// interpolation data. Field names are translated first if the catalog
+    /// has `validation.attributes.<field>`. Errors without a translation
+    /// keep their message.
+    pub fn localize(&mut self, locale: &rf_i18n::Locale) {
+        for (field, field_errors) in self.errors.iter_mut() {
+            let attribute = locale
+                .try_t(&format!("validation.attributes.{}", field), None)
+                .unwrap_or_else(|_| field.clone());
 
-        for (field, field_errors) in errors.field_errors() {
             for error in field_errors {
-                let mut field_error = FieldError::new(
-                    error.code.to_string(),
-                    error.message.as_ref()
-                        .map(|m| m.to_string())
-                        .unwrap_or_else(|| format!("Validation failed for field '{}'", field)),
-                );
-
-                // validator's params are HashMap<Cow<str>, Value>, convert to HashMap<String, Value>
-                if !error.params.is_empty() {
-                    let converted_params: HashMap<String, serde_json::Value> = error
-                        .params
-                        .iter()
-                        .map(|(k, v)| (k.to_string(), v.clone()))
-                        .collect();
-                    field_error.params = Some(converted_params);
+                let mut data = serde_json::Map::new();
+                if let Some(params) = &error.params {
+                    data.extend(params.iter().map(|(k, v)| (k.clone(), v.clone())));
                 }
+                data.insert("field".to_string(), attribute.clone().into());
 
-                validation_errors.add(field, field_error);
+                let key = format!("validation.{}", error.code);
+                if let Ok(message) = locale.try_t(&key, Some(data.into())) {
+                    error.message = message;
+                }
             }
         }
+    }
+}
 
-        validation_errors
+/// Coded `BAD_USER_INPUT` error for GraphQL resolvers
+///
+/// Details are in the `fields` extension, by field path:
+/// `{"email": [{"code": "email", "message": "…", "params": {…}}]}`
+#[cfg(feature = "graphql")]
+impl rf_graphql::Problem for ValidationErrors {
+    fn code(&self) -> &'static str {
+        "BAD_USER_INPUT"
+    }
+
+    fn message(&self) -> String {
+        let mut fields: Vec<_> = self.errors.keys().map(String::as_str).collect();
+        fields.sort_unstable();
+        format!("Validation failed: {}", fields.join(", "))
+    }
+
+    fn details(&self, extensions: &mut rf_graphql::async_graphql::ErrorExtensionValues) {
+        if let Ok(fields) = serde_json::to_value(&self.errors) {
+            if let Ok(fields) = rf_graphql::async_graphql::Value::from_json(fields) {
+                extensions.set("fields", fields);
+            }
+        }
     }
 }
 
@@ -142,6 +267,7 @@ impl From<ValidationErrors> for AppError {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use validator::Validate;
 
     #[test]
     fn test_validation_errors_creation() {
@@ -183,4 +309,99 @@ mod tests {
         assert!(json.contains("email"));
         assert!(json.contains("Invalid email"));
     }
+
+    #[derive(Validate)]
+    struct Address {
+        #[validate(length(min = 2))]
+        city: String,
+    }
+
+    #[derive(Validate)]
+    #[validate(schema(function = "check_passwords", skip_on_field_errors = false))]
+    struct Signup {
+        #[validate(email)]
+        email: String,
+        #[validate(range(min = 18))]
+        age: u32,
+        #[validate(nested)]
+        address: Address,
+        #[validate(nested)]
+        previous: Vec<Address>,
+        password: String,
+        password_confirmation: String,
+    }
+
+    fn check_passwords(signup: &Signup) -> Result<(), validator::ValidationError> {
+        if signup.password != signup.password_confirmation {
+            return Err(validator::ValidationError::new("passwords_mismatch"));
+        }
+        Ok(())
+    }
+
+    fn signup() -> Signup {
+        Signup {
+            email: "nope".to_string(),
+            age: 16,
+            address: Address {
+                city: "X".to_string(),
+            },
+            previous: vec![
+                Address {
+                    city: "Zurich".to_string(),
+                },
+                Address {
+                    city: "Y".to_string(),
+                },
+            ],
+            password: "secret".to_string(),
+            password_confirmation: "other".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_from_validator_errors() {
+        let errors: ValidationErrors = signup().validate().unwrap_err().into();
+
+        assert_eq!(errors.get("email").unwrap()[0].message, "Invalid email address");
+        assert_eq!(errors.get("age").unwrap()[0].message, "Must be at least 18");
+        assert_eq!(
+            errors.get("address.city").unwrap()[0].message,
+            "Must be at least 2 characters long"
+        );
+        assert!(errors.get("previous[0].city").is_none());
+        assert!(errors.get("previous[1].city").is_some());
+        assert_eq!(errors.get("__all__").unwrap()[0].code, "passwords_mismatch");
+    }
+
+    #[cfg(feature = "i18n")]
+    #[test]
+    fn test_localize() {
+        use rf_i18n::{I18n, Locale, TranslationCatalog};
+        use std::sync::Arc;
+
+        let de = TranslationCatalog::new("de")
+            .load_json(
+                r#"{"validation": {
+                    "email": "{{field}} ist keine gültige E-Mail-Adresse",
+                    "range": "{{field}} muss mindestens {{min}} sein",
+                    "attributes": {"age": "Alter"}
+                }}"#,
+            )
+            .unwrap();
+        let locale = Locale::new("de", Arc::new(I18n::new("en").add_catalog(de)));
+
+        let mut errors: ValidationErrors = signup().validate().unwrap_err().into();
+        errors.localize(&locale);
+
+        assert_eq!(
+            errors.get("email").unwrap()[0].message,
+            "email ist keine gültige E-Mail-Adresse"
+        );
+        assert_eq!(errors.get("age").unwrap()[0].message, "Alter muss mindestens 18 sein");
+        // No translation
+        assert_eq!(
+            errors.get("address.city").unwrap()[0].message,
+            "Must be at least 2 characters long"
+        );
+    }
 }
@@ -1,12 +1,16 @@
 //! Axum extractors for automatic validation
 //!
-//! Provides ValidatedJson and ValidatedForm extractors that automatically
-//! validate request bodies before passing them to handlers.
+//! Provides ValidatedJson and ValidatedQuery extractors that automatically
+//! validate request bodies and query strings before passing them to
+//! handlers.
+//!
+//! With the `i18n` feature, error messages are translated into the
+//! `rf_i18n::Locale` that `LocaleLayer` put into the request extensions.
 
 use crate::error::ValidationErrors;
 use axum::{
-    extract::{FromRequest, Request},
-    http::StatusCode,
+    extract::{FromRequest, FromRequestParts, Query, Request},
+    http::{request::Parts, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -46,25 +50,99 @@ where
     type Rejection = ValidationRejection;
 
     async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let localizer = Localizer::from_extensions(req.extensions());
+
         // Extract JSON body
         let Json(value) = Json::<T>::from_request(req, state)
             .await
             .map_err(|err| ValidationRejection::JsonError(err.to_string()))?;
 
-        // Validate
-        value
-            .validate()
-            .map_err(|e| ValidationRejection::ValidationError(e.into()))?;
-
+        validate(&value, localizer)?;
         Ok(ValidatedJson(value))
     }
 }
 
+/// Query string extractor with automatic validation
+///
+/// # Example
+///
+/// ```ignore
+/// use rf_validation::{ValidatedQuery, Validate};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize, Validate)]
+/// struct Pagination {
+///     #[validate(range(min = 1))]
+///     page: u32,
+///
+///     #[validate(range(min = 1, max = 100))]
+///     per_page: u32,
+/// }
+///
+/// async fn list_users(
+///     ValidatedQuery(pagination): ValidatedQuery<Pagination>,
+/// ) -> String {
+///     format!("Page {}", pagination.page)
+/// }
+/// ```
+pub struct ValidatedQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate + Send,
+    S: Send + Sync,
+{
+    type Rejection = ValidationRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|err| ValidationRejection::QueryError(err.to_string()))?;
+
+        validate(&value, Localizer::from_extensions(&parts.extensions))?;
+        Ok(ValidatedQuery(value))
+    }
+}
+
+/// Translates error messages into the request locale (feature `i18n`)
+struct Localizer {
+    #[cfg(feature = "i18n")]
+    locale: Option<rf_i18n::Locale>,
+}
+
+impl Localizer {
+    #[cfg_attr(not(feature = "i18n"), allow(unused_variables))]
+    fn from_extensions(extensions: &axum::http::Extensions) -> Self {
+        Self {
+            #[cfg(feature = "i18n")]
+            locale: extensions.get::<rf_i18n::Locale>().cloned(),
+        }
+    }
+
+    #[cfg_attr(not(feature = "i18n"), allow(unused_variables))]
+    fn localize(&self, errors: &mut ValidationErrors) {
+        #[cfg(feature = "i18n")]
+        if let Some(locale) = &self.locale {
+            errors.localize(locale);
+        }
+    }
+}
+
+fn validate<T: Validate>(value: &T, localizer: Localizer) -> Result<(), ValidationRejection> {
+    value.validate().map_err(|e| {
+        let mut errors = ValidationErrors::from(e);
+        localizer.localize(&mut errors);
+        ValidationRejection::ValidationError(errors)
+    })
+}
+
 /// Validation rejection type
 #[derive(Debug)]
 pub enum ValidationRejection {
     /// JSON deserialization error
     JsonError(String),
+    /// Query string deserialization error
+    QueryError(String),
     /// Validation error
     ValidationError(ValidationErrors),
 }
@@ -81,6 +159,15 @@ impl IntoResponse for ValidationRejection {
             )
                 .into_response(),
 
+            ValidationRejection::QueryError(msg) => (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": "Invalid query string",
+                    "message": msg,
+                })),
+            )
+                .into_response(),
+
             ValidationRejection::ValidationError(errors) => (
                 StatusCode::UNPROCESSABLE_ENTITY,
                 Json(serde_json::json!({
@@ -101,6 +188,38 @@ impl IntoResponse for ValidationRejection {
 mod tests {
     use super::*;
 
+    #[derive(Debug, serde::Deserialize, Validate)]
+    struct Pagination {
+        #[validate(range(min = 1, max = 100))]
+        per_page: u32,
+    }
+
+    async fn query(uri: &str) -> Result<ValidatedQuery<Pagination>, ValidationRejection> {
+        let (mut parts, _) = Request::builder()
+            .uri(uri)
+            .body(axum::body::Body::empty())
+            .unwrap()
+            .into_parts();
+        ValidatedQuery::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_validated_query() {
+        let ValidatedQuery(pagination) = query("/users?per_page=20").await.unwrap();
+        assert_eq!(pagination.per_page, 20);
+
+        match query("/users?per_page=500").await {
+            Err(ValidationRejection::ValidationError(errors)) => {
+                assert_eq!(errors.get("per_page").unwrap()[0].code, "range");
+            }
+            _ => panic!("expected validation error"),
+        }
+
+        let rejection = query("/users?per_page=many").await.err().unwrap();
+        assert!(matches!(rejection, ValidationRejection::QueryError(_)));
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
     #[test]
     fn test_validation_rejection_debug() {
         let rejection = ValidationRejection::JsonError("test error".to_string());
@@ -7,8 +7,13 @@
 //!
 //! - **Declarative Validation**: Use `#[derive(Validate)]` from validator crate
 //! - **30+ Built-in Rules**: Email, URL, length, range, regex, and more
-//! - **Axum Integration**: ValidatedJson extractor with automatic validation
-//! - **Field-Level Errors**: Detailed error messages per field
+//! - **Axum Integration**: ValidatedJson and ValidatedQuery extractors with
+//!   automatic validation
+//! - **Field-Level Errors**: Detailed error messages per field, with nested
+//!   fields keyed by path like `address.city`
+//! - **Localized Messages**: Translated through rf-i18n (`i18n` feature)
+//! - **GraphQL Errors**: Coded `BAD_USER_INPUT` errors for rf-graphql
+//!   resolvers (`graphql` feature)
 //! - **Type-Safe**: Compile-time validation rule checking
 //! - **RFC 7807 Compatible**: Standard error responses
 //!
@@ -52,9 +57,30 @@
 //! - **range(min, max)**: Numeric range
 //! - **regex**: Custom regex pattern
 //! - **contains**: String contains substring
-//! - **custom**: Custom validation function
+//! - **required**: `Option` field must be `Some`
+//! - **must_match**: Field equals another field
+//! - **nested**: Validate a nested struct or list of structs
+//! - **custom**: Custom validation function, see [`rules`] for ready-made
+//!   ones like `rules::uuid`
+//! - **schema**: Cross-field validation function on the struct, reported
+//!   under `__all__`
 //! - And many more!
 //!
+//! ## Localized Messages
+//!
+//! With the `i18n` feature, the extractors translate messages into the
+//! `rf_i18n::Locale` set by `LocaleLayer`. Messages are looked up as
+//! `validation.<code>` with the rule's params and `field` as data:
+//!
+//! ```json
+//! {
+//!   "validation": {
+//!     "length": "{{field}} muss mindestens {{min}} Zeichen lang sein",
+//!     "attributes": { "password": "Passwort" }
+//!   }
+//! }
+//! ```
+//!
 //! ## Error Responses
 //!
 //! Validation errors are returned as RFC 7807-compatible JSON:
@@ -78,10 +104,11 @@
 
 pub mod error;
 pub mod extractor;
+pub mod rules;
 
 // Re-export main types
 pub use error::{FieldError, ValidationErrors};
-pub use extractor::{ValidatedJson, ValidationRejection};
+pub use extractor::{ValidatedJson, ValidatedQuery, ValidationRejection};
 
 // Re-export validator traits and derive macro
 pub use validator::Validate;
@@ -90,7 +117,7 @@ pub use validator::Validate;
 pub mod prelude {
     pub use crate::{
         error::{FieldError, ValidationErrors},
-        extractor::{ValidatedJson, ValidationRejection},
+        extractor::{ValidatedJson, ValidatedQuery, ValidationRejection},
     };
     pub use validator::Validate;
 }
@@ -0,0 +1,52 @@
+//! Validation rules for use with `#[validate(custom(...))]`
+//!
+//! Rules not covered by the `validator` derive itself.
+//!
+//! ```ignore
+//! use rf_validation::Validate;
+//!
+//! #[derive(Validate)]
+//! struct AssignTask {
+//!     #[validate(custom(function = "rf_validation::rules::uuid"))]
+//!     assignee_id: String,
+//!
+//!     #[validate(custom(function = "rf_validation::rules::not_blank"))]
+//!     title: String,
+//! }
+//! ```
+
+use validator::ValidationError;
+
+/// Value must be a UUID, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8`
+pub fn uuid(value: &str) -> Result<(), ValidationError> {
+    match ::uuid::Uuid::parse_str(value) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(ValidationError::new("uuid")),
+    }
+}
+
+/// Value must contain something besides whitespace
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        Err(ValidationError::new("not_blank"))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uuid() {
+        assert!(uuid("67e55044-10b1-426f-9247-bb680e5fe0c8").is_ok());
+        assert_eq!(uuid("not-a-uuid").unwrap_err().code, "uuid");
+    }
+
+    #[test]
+    fn test_not_blank() {
+        assert!(not_blank("a").is_ok());
+        assert_eq!(not_blank("  ").unwrap_err().code, "not_blank");
+    }
+}
@@ -0,0 +1,128 @@
+//! Authenticated user from rf-auth
+
+use crate::{ViewComposer, ViewError, ViewResult};
+use async_trait::async_trait;
+use axum::http::request::Parts;
+use rf_auth::{Auth, AuthError, Authenticatable};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::marker::PhantomData;
+
+/// Composer adding the user authenticated by rf-auth's [`Auth`] extension
+/// as `auth.user`, `null` for guests
+///
+/// ```handlebars
+/// {{#if auth.user}}Signed in as {{auth.user.name}}{{else}}<a href="/login">Sign in</a>{{/if}}
+/// ```
+pub struct AuthUser<U> {
+    user: PhantomData<fn() -> U>,
+}
+
+impl<U> AuthUser<U> {
+    /// Add the user of type `U`
+    pub fn new() -> Self {
+        Self { user: PhantomData }
+    }
+}
+
+impl<U> Default for AuthUser<U> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<U: Authenticatable + Serialize> ViewComposer for AuthUser<U> {
+    async fn compose(
+        &self,
+        _view: &str,
+        request: &Parts,
+        data: &mut Map<String, Value>,
+    ) -> ViewResult<()> {
+        let auth = request.extensions.get::<Auth<U>>().ok_or_else(|| {
+            ViewError::Composer("Auth extension is missing from the router".to_string())
+        })?;
+        let user = match auth.authenticate(request).await {
+            Ok(current) => serde_json::to_value(&current.user)
+                .map_err(|e| ViewError::Composer(e.to_string()))?,
+            Err(
+                AuthError::Unauthenticated | AuthError::TokenExpired | AuthError::InvalidToken(_),
+            ) => Value::Null,
+            Err(e) => return Err(ViewError::Composer(e.to_string())),
+        };
+        let mut auth = Map::new();
+        auth.insert("user".to_string(), user);
+        data.insert("auth".to_string(), Value::Object(auth));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Views;
+    use axum::http::Request;
+    use rf_auth::{AuthResult, SessionGuard, UserProvider};
+    use std::sync::Arc;
+    use tower_sessions::{MemoryStore, Session};
+
+    #[derive(Debug, Clone, Serialize)]
+    struct User {
+        id: u32,
+        name: String,
+    }
+
+    impl Authenticatable for User {
+        fn auth_id(&self) -> String {
+            self.id.to_string()
+        }
+    }
+
+    struct Users;
+
+    #[async_trait]
+    impl UserProvider for Users {
+        type User = User;
+
+        async fn find_by_id(&self, id: &str) -> AuthResult<Option<User>> {
+            Ok((id == "7").then(|| User {
+                id: 7,
+                name: "Ada".to_string(),
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auth_user() {
+        let views = Views::new()
+            .template(
+                "home",
+                "{{#if auth.user}}{{auth.user.name}}{{else}}guest{{/if}}",
+            )
+            .unwrap()
+            .composer("*", AuthUser::<User>::new());
+
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        let (mut request, ()) = Request::new(()).into_parts();
+        request.extensions.insert(Auth::new(Users));
+        request.extensions.insert(session.clone());
+        assert_eq!(
+            views.render_request("home", &request, ()).await.unwrap(),
+            "guest"
+        );
+
+        SessionGuard::login(
+            &session,
+            &User {
+                id: 7,
+                name: "Ada".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            views.render_request("home", &request, ()).await.unwrap(),
+            "Ada"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! View composers adding shared data to views
+
+use crate::ViewResult;
+use async_trait::async_trait;
+use axum::http::request::Parts;
+use serde_json::{Map, Value};
+
+/// Adds data to views rendered for a request, e.g. the authenticated user
+/// or flash messages every layout shows
+///
+/// Data given to [`View::render`](crate::View::render) wins over composed
+/// data of the same key. Plain closures are composers too:
+///
+/// ```
+/// use axum::http::request::Parts;
+/// use rf_view::Views;
+/// use serde_json::{Map, Value};
+///
+/// let views = Views::new().composer("*", |request: &Parts, data: &mut Map<String, Value>| {
+///     data.insert("path".to_string(), request.uri.path().into());
+/// });
+/// ```
+#[async_trait]
+pub trait ViewComposer: Send + Sync + 'static {
+    /// Add data for rendering `view` in response to `request`
+    async fn compose(
+        &self,
+        view: &str,
+        request: &Parts,
+        data: &mut Map<String, Value>,
+    ) -> ViewResult<()>;
+}
+
+#[async_trait]
+impl<F> ViewComposer for F
+where
+    F: Fn(&Parts, &mut Map<String, Value>) + Send + Sync + 'static,
+{
+    async fn compose(
+        &self,
+        _view: &str,
+        request: &Parts,
+        data: &mut Map<String, Value>,
+    ) -> ViewResult<()> {
+        self(request, data);
+        Ok(())
+    }
+}
+
+/// Whether `pattern` selects `view`: `*` selects all views, `users/*` all
+/// views below `users/`, anything else only the view of that name
+pub(crate) fn matches(pattern: &str, view: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => view.starts_with(prefix),
+        None => pattern == view,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("*", "users/show"));
+        assert!(matches("users/*", "users/show"));
+        assert!(matches("users/*", "users/admin/edit"));
+        assert!(!matches("users/*", "posts/show"));
+        assert!(matches("users/show", "users/show"));
+        assert!(!matches("users/show", "users/showcase"));
+    }
+}
@@ -0,0 +1,54 @@
+//! View errors
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use thiserror::Error;
+
+/// View errors
+#[derive(Debug, Error)]
+pub enum ViewError {
+    #[error("View not found: {0}")]
+    NotFound(String),
+
+    #[error("Template error: {0}")]
+    Template(#[from] Box<handlebars::TemplateError>),
+
+    #[error("Render error: {0}")]
+    Render(#[from] Box<handlebars::RenderError>),
+
+    /// The data of a view isn't a JSON object
+    #[error("Invalid view data: {0}")]
+    Data(String),
+
+    #[error("View composer failed: {0}")]
+    Composer(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<handlebars::TemplateError> for ViewError {
+    fn from(error: handlebars::TemplateError) -> Self {
+        ViewError::Template(Box::new(error))
+    }
+}
+
+impl From<handlebars::RenderError> for ViewError {
+    fn from(error: handlebars::RenderError) -> Self {
+        ViewError::Render(Box::new(error))
+    }
+}
+
+/// Result type for view operations
+pub type ViewResult<T> = Result<T, ViewError>;
+
+/// A page that can't be rendered is a bug: log it and answer 500 without
+/// leaking template details
+impl IntoResponse for ViewError {
+    fn into_response(self) -> Response {
+        tracing::error!(error = %self, "Rendering a view failed");
+        (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error").into_response()
+    }
+}
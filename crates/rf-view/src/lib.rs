@@ -0,0 +1,69 @@
+//! Server-rendered views for RustForge applications
+//!
+//! Handlebars templates with layouts, partials and components, rendered
+//! from axum handlers.
+//!
+//! # Features
+//!
+//! - Templates loaded from a directory, named by their path, e.g.
+//!   `views/users/show.hbs` as `users/show`, and reloaded on change in
+//!   debug builds
+//! - Templates compiled into release binaries with [`embed_views!`]
+//! - Layouts and components as partials with `{{> @partial-block}}`, see
+//!   [`Views`]
+//! - [`View`] extractor rendering HTML responses
+//! - Data shared by all views, and [`ViewComposer`]s adding data per
+//!   request
+//! - The authenticated user with rf-auth (feature `auth`) and flash
+//!   messages with rf-session (feature `session`)
+//!
+//! # Example
+//!
+//! ```no_run
+//! use axum::{response::Html, routing::get, Extension, Router};
+//! use axum::http::request::Parts;
+//! use rf_view::{View, ViewResult, Views};
+//! use serde_json::{json, Map, Value};
+//!
+//! async fn home(view: View) -> ViewResult<Html<String>> {
+//!     view.render("home", json!({"products": ["Tea", "Coffee"]})).await
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let views = Views::from_directory("views")?
+//!         .share("app_name", "Shop")
+//!         .composer("*", |request: &Parts, data: &mut Map<String, Value>| {
+//!             data.insert("path".to_string(), request.uri.path().into());
+//!         });
+//!
+//!     let app: Router = Router::new()
+//!         .route("/", get(home))
+//!         .layer(Extension(views));
+//!     let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await?;
+//!     axum::serve(listener, app).await?;
+//!     Ok(())
+//! }
+//! ```
+
+mod composer;
+mod error;
+mod response;
+mod views;
+
+#[cfg(feature = "auth")]
+mod auth;
+#[cfg(feature = "session")]
+mod session;
+
+pub use composer::ViewComposer;
+pub use error::{ViewError, ViewResult};
+pub use response::View;
+pub use views::{Views, TEMPLATE_EXTENSION};
+
+#[cfg(feature = "auth")]
+pub use auth::AuthUser;
+#[cfg(feature = "session")]
+pub use session::FlashMessages;
+
+pub use handlebars;
@@ -0,0 +1,159 @@
+//! Rendering views in handlers
+
+use crate::{ViewResult, Views};
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, Request, StatusCode},
+    response::Html,
+};
+use serde::Serialize;
+
+/// Renders views for the current request, running the view composers
+///
+/// Extracted in handlers of a router with the [`Views`] as extension.
+///
+/// ```
+/// use axum::{extract::Path, routing::get, Extension, Router};
+/// use axum::response::Html;
+/// use rf_view::{View, ViewResult, Views};
+/// use serde_json::json;
+///
+/// async fn show(view: View, Path(id): Path<u64>) -> ViewResult<Html<String>> {
+///     view.render("users/show", json!({"user": {"id": id}})).await
+/// }
+///
+/// # fn example() -> ViewResult<()> {
+/// let views = Views::new().template("users/show", "User {{user.id}}")?;
+/// let app: Router = Router::new()
+///     .route("/users/{id}", get(show))
+///     .layer(Extension(views));
+/// # Ok(())
+/// # }
+/// ```
+pub struct View {
+    views: Views,
+    request: Parts,
+}
+
+impl View {
+    /// Render the template `name` as HTML, with `data` over the composed
+    /// and shared data
+    pub async fn render(&self, name: &str, data: impl Serialize) -> ViewResult<Html<String>> {
+        let html = self.views.render_request(name, &self.request, data).await?;
+        Ok(Html(html))
+    }
+
+    /// The registry views are rendered from
+    pub fn views(&self) -> &Views {
+        &self.views
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for View {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let views = parts.extensions.get::<Views>().cloned().ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Can't extract view. Is the `Views` extension installed?",
+        ))?;
+
+        // Composers look at the request after the handler took its body
+        let (mut request, ()) = Request::new(()).into_parts();
+        request.method = parts.method.clone();
+        request.uri = parts.uri.clone();
+        request.version = parts.version;
+        request.headers = parts.headers.clone();
+        request.extensions = parts.extensions.clone();
+        Ok(Self { views, request })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ViewError;
+    use axum::{body::Body, routing::get, Extension, Router};
+    use serde_json::{json, Map, Value};
+    use tower::ServiceExt;
+
+    async fn get_page(app: Router, uri: &str) -> (StatusCode, String) {
+        let response = app
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_render_with_composers() {
+        let views = Views::new()
+            .template("layouts/app", "[{{app}} {{path}}] {{> @partial-block}}")
+            .unwrap()
+            .template(
+                "users/show",
+                "{{#> layouts/app}}{{name}} ({{role}}){{/layouts/app}}",
+            )
+            .unwrap()
+            .template("posts/show", "{{#> layouts/app}}{{role}}{{/layouts/app}}")
+            .unwrap()
+            .share("app", "Shop")
+            .composer("*", |request: &Parts, data: &mut Map<String, Value>| {
+                data.insert("path".to_string(), request.uri.path().into());
+            })
+            .composer("users/*", |_: &Parts, data: &mut Map<String, Value>| {
+                data.insert("role".to_string(), "guest".into());
+                data.insert("name".to_string(), "nobody".into());
+            });
+
+        let app =
+            Router::new()
+                .route(
+                    "/users",
+                    get(|view: View| async move {
+                        view.render("users/show", json!({"name": "Ada"})).await
+                    }),
+                )
+                .route(
+                    "/posts",
+                    get(|view: View| async move { view.render("posts/show", ()).await }),
+                )
+                .route(
+                    "/broken",
+                    get(|view: View| async move { view.render("missing", ()).await }),
+                )
+                .layer(Extension(views));
+
+        assert_eq!(
+            get_page(app.clone(), "/users").await,
+            (StatusCode::OK, "[Shop /users] Ada (guest)".to_string())
+        );
+        assert_eq!(
+            get_page(app.clone(), "/posts").await,
+            (StatusCode::OK, "[Shop /posts] ".to_string())
+        );
+        let (status, body) = get_page(app, "/broken").await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(!body.contains("missing"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_extension() {
+        let app = Router::new().route(
+            "/",
+            get(|view: View| async move { view.render("home", ()).await }),
+        );
+        assert_eq!(
+            get_page(app, "/").await.0,
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert!(matches!(
+            Views::new().render("home", ()),
+            Err(ViewError::NotFound(_))
+        ));
+    }
+}
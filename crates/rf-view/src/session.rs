@@ -0,0 +1,78 @@
+//! Flash messages from rf-session
+
+use crate::{ViewComposer, ViewError, ViewResult};
+use async_trait::async_trait;
+use axum::http::request::Parts;
+use rf_session::Session;
+use serde_json::{Map, Value};
+
+/// Composer adding the data flashed by the previous request as `flash`
+///
+/// ```handlebars
+/// {{#if flash.status}}<div class="alert">{{flash.status}}</div>{{/if}}
+/// ```
+///
+/// Without a session, e.g. outside of a `SessionLayer`, `flash` is empty.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlashMessages;
+
+#[async_trait]
+impl ViewComposer for FlashMessages {
+    async fn compose(
+        &self,
+        _view: &str,
+        request: &Parts,
+        data: &mut Map<String, Value>,
+    ) -> ViewResult<()> {
+        let flashed = match request.extensions.get::<tower_sessions::Session>() {
+            Some(session) => Session::from(session.clone())
+                .flashed()
+                .await
+                .map_err(|e| ViewError::Composer(e.to_string()))?,
+            None => Map::new(),
+        };
+        data.insert("flash".to_string(), Value::Object(flashed));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Views;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower_sessions::MemoryStore;
+
+    #[tokio::test]
+    async fn test_flash_messages() {
+        let views = Views::new()
+            .template(
+                "home",
+                "{{#if flash.status}}{{flash.status}}{{else}}-{{/if}}",
+            )
+            .unwrap()
+            .composer("*", FlashMessages);
+
+        let (mut request, ()) = Request::new(()).into_parts();
+        assert_eq!(
+            views.render_request("home", &request, ()).await.unwrap(),
+            "-"
+        );
+
+        let session = tower_sessions::Session::new(None, Arc::new(MemoryStore::default()), None);
+        Session::from(session.clone())
+            .put("_flash.old", ["status"])
+            .await
+            .unwrap();
+        Session::from(session.clone())
+            .put("status", "Saved")
+            .await
+            .unwrap();
+        request.extensions.insert(session);
+        assert_eq!(
+            views.render_request("home", &request, ()).await.unwrap(),
+            "Saved"
+        );
+    }
+}
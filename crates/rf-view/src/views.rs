@@ -0,0 +1,316 @@
+//! Template registry
+
+use crate::composer::{matches, ViewComposer};
+use crate::{ViewError, ViewResult};
+use axum::http::request::Parts;
+use handlebars::{DirectorySourceOptions, Handlebars, HelperDef};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard};
+
+/// Extension of template files
+pub const TEMPLATE_EXTENSION: &str = ".hbs";
+
+/// Handlebars templates of an application, named by their path below the
+/// views directory without extension, e.g. `users/show`
+///
+/// Every template is also a partial, which makes layouts and components
+/// plain templates:
+///
+/// ```handlebars
+/// {{!-- layouts/app.hbs --}}
+/// <title>{{> title}}</title>
+/// <main>{{> @partial-block}}</main>
+///
+/// {{!-- components/alert.hbs --}}
+/// <div class="alert alert-{{type}}">{{> @partial-block}}</div>
+///
+/// {{!-- users/show.hbs --}}
+/// {{#> layouts/app}}
+///   {{#*inline "title"}}{{user.name}}{{/inline}}
+///   {{#> components/alert type="info"}}Welcome back{{/components/alert}}
+/// {{/layouts/app}}
+/// ```
+///
+/// Cloning is cheap; clones share the templates.
+#[derive(Clone)]
+pub struct Views {
+    registry: Arc<RwLock<Handlebars<'static>>>,
+    directory: Option<PathBuf>,
+    hot_reload: bool,
+    shared: Arc<Map<String, Value>>,
+    composers: Arc<Vec<(String, Arc<dyn ViewComposer>)>>,
+}
+
+impl Views {
+    /// Create a registry without templates
+    pub fn new() -> Self {
+        Self {
+            registry: Arc::new(RwLock::new(Handlebars::new())),
+            directory: None,
+            hot_reload: false,
+            shared: Arc::default(),
+            composers: Arc::default(),
+        }
+    }
+
+    /// Load the `.hbs` templates below `directory`
+    ///
+    /// In debug builds, templates are reloaded from disk on every render,
+    /// see [`hot_reload`](Self::hot_reload).
+    pub fn from_directory(directory: impl AsRef<Path>) -> ViewResult<Self> {
+        let directory = directory.as_ref();
+        if !directory.is_dir() {
+            return Err(ViewError::NotFound(format!(
+                "{} is not a directory",
+                directory.display()
+            )));
+        }
+
+        let views = Self {
+            directory: Some(directory.to_path_buf()),
+            ..Self::new()
+        };
+        views.scan()?;
+        Ok(views.hot_reload(cfg!(debug_assertions)))
+    }
+
+    /// Register templates compiled into the binary, usually with
+    /// [`embed_views!`](crate::embed_views)
+    pub fn embedded(templates: &[(&str, &str)]) -> ViewResult<Self> {
+        templates
+            .iter()
+            .try_fold(Self::new(), |views, (name, source)| {
+                views.template(name, source)
+            })
+    }
+
+    /// Register the template `name`
+    pub fn template(self, name: &str, source: &str) -> ViewResult<Self> {
+        self.write().register_template_string(name, source)?;
+        Ok(self)
+    }
+
+    /// Pick up changed and new templates of the directory on every render
+    ///
+    /// Meant for development, as every render reads the whole directory.
+    pub fn hot_reload(mut self, enabled: bool) -> Self {
+        self.hot_reload = enabled && self.directory.is_some();
+        self
+    }
+
+    /// Register a Handlebars helper, e.g. for formatting dates
+    pub fn helper(self, name: &str, helper: impl HelperDef + Send + Sync + 'static) -> Self {
+        self.write().register_helper(name, Box::new(helper));
+        self
+    }
+
+    /// Make `value` available to every view as `key`, e.g. the
+    /// application's name
+    pub fn share(mut self, key: &str, value: impl Into<Value>) -> Self {
+        Arc::make_mut(&mut self.shared).insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Run `composer` for the views selected by `pattern` when they are
+    /// rendered for a request
+    ///
+    /// `*` selects all views, `users/*` the views below `users/`, anything
+    /// else the view of that name.
+    pub fn composer(mut self, pattern: &str, composer: impl ViewComposer) -> Self {
+        Arc::make_mut(&mut self.composers).push((pattern.to_string(), Arc::new(composer)));
+        self
+    }
+
+    /// Whether the template `name` is registered
+    pub fn has(&self, name: &str) -> bool {
+        self.read().has_template(name)
+    }
+
+    /// Render the template `name` with `data`, an object, and the shared
+    /// data, but no composers
+    ///
+    /// For views outside of requests, e.g. emails. Handlers use
+    /// [`View`](crate::View).
+    pub fn render(&self, name: &str, data: impl Serialize) -> ViewResult<String> {
+        let mut view = (*self.shared).clone();
+        view.extend(object(data)?);
+        self.render_data(name, &view)
+    }
+
+    /// Render the template `name` for `request` with the shared data,
+    /// composed data and `data`, in increasing precedence
+    pub(crate) async fn render_request(
+        &self,
+        name: &str,
+        request: &Parts,
+        data: impl Serialize,
+    ) -> ViewResult<String> {
+        let data = object(data)?;
+        let mut view = (*self.shared).clone();
+        for (pattern, composer) in self.composers.iter() {
+            if matches(pattern, name) {
+                composer.compose(name, request, &mut view).await?;
+            }
+        }
+        view.extend(data);
+        self.render_data(name, &view)
+    }
+
+    fn render_data(&self, name: &str, data: &Map<String, Value>) -> ViewResult<String> {
+        if self.hot_reload {
+            self.scan()?;
+        }
+        if !self.has(name) {
+            return Err(ViewError::NotFound(name.to_string()));
+        }
+        Ok(self.read().render(name, data)?)
+    }
+
+    /// Register the templates of the directory, replacing changed ones
+    fn scan(&self) -> ViewResult<()> {
+        if let Some(directory) = &self.directory {
+            let options = DirectorySourceOptions {
+                tpl_extension: TEMPLATE_EXTENSION.to_string(),
+                ..Default::default()
+            };
+            self.write()
+                .register_templates_directory(directory, options)?;
+        }
+        Ok(())
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Handlebars<'static>> {
+        self.registry.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Handlebars<'static>> {
+        self.registry
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for Views {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `data` as a JSON object; nothing (`()` or `None`) is an empty one
+fn object(data: impl Serialize) -> ViewResult<Map<String, Value>> {
+    match serde_json::to_value(data).map_err(|e| ViewError::Data(e.to_string()))? {
+        Value::Object(map) => Ok(map),
+        Value::Null => Ok(Map::new()),
+        other => Err(ViewError::Data(format!(
+            "expected an object, got {}",
+            other
+        ))),
+    }
+}
+
+/// Templates of a directory compiled into the binary, for
+/// [`Views::embedded`]
+///
+/// The directory is relative to the crate's `Cargo.toml`; each name is a
+/// template path without the `.hbs` extension.
+///
+/// ```ignore
+/// let views = if cfg!(debug_assertions) {
+///     Views::from_directory("views")?
+/// } else {
+///     Views::embedded(rf_view::embed_views!("views", ["layouts/app", "users/show"]))?
+/// };
+/// ```
+#[macro_export]
+macro_rules! embed_views {
+    ($directory:literal, [$($name:literal),* $(,)?]) => {
+        &[$((
+            $name,
+            include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $directory, "/", $name, ".hbs")),
+        )),*]
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write(dir: &Path, name: &str, source: &str) {
+        let path = dir.join(format!("{}{}", name, TEMPLATE_EXTENSION));
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, source).unwrap();
+    }
+
+    #[test]
+    fn test_layouts_and_components() {
+        let views = Views::embedded(&[
+            (
+                "layouts/app",
+                "<title>{{> title}} - {{app}}</title><main>{{> @partial-block}}</main>",
+            ),
+            (
+                "components/alert",
+                "<div class=\"alert-{{type}}\">{{> @partial-block}}</div>",
+            ),
+            (
+                "users/show",
+                "{{#> layouts/app}}{{#*inline \"title\"}}{{user.name}}{{/inline}}\
+                 {{#> components/alert type=\"info\"}}Hi {{user.name}}{{/components/alert}}\
+                 {{/layouts/app}}",
+            ),
+        ])
+        .unwrap()
+        .share("app", "Shop");
+
+        let html = views
+            .render("users/show", json!({"user": {"name": "Ada <3"}}))
+            .unwrap();
+        assert_eq!(
+            html,
+            "<title>Ada &lt;3 - Shop</title><main><div class=\"alert-info\">Hi Ada &lt;3</div></main>"
+        );
+        assert!(matches!(
+            views.render("users/edit", ()),
+            Err(ViewError::NotFound(_))
+        ));
+        assert!(matches!(
+            views.render("users/show", [1, 2]),
+            Err(ViewError::Data(_))
+        ));
+    }
+
+    #[test]
+    fn test_directory_and_hot_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "users/show", "Hello {{name}}");
+
+        let views = Views::from_directory(dir.path()).unwrap().hot_reload(true);
+        assert!(views.has("users/show"));
+        assert_eq!(
+            views.render("users/show", json!({"name": "Ada"})).unwrap(),
+            "Hello Ada"
+        );
+
+        write(
+            dir.path(),
+            "users/show",
+            "{{> partials/greeting}}, {{name}}",
+        );
+        write(dir.path(), "partials/greeting", "Welcome");
+        write(dir.path(), "users/new", "New user");
+        assert_eq!(
+            views.render("users/show", json!({"name": "Ada"})).unwrap(),
+            "Welcome, Ada"
+        );
+        assert_eq!(views.render("users/new", ()).unwrap(), "New user");
+
+        let cached = Views::from_directory(dir.path()).unwrap().hot_reload(false);
+        write(dir.path(), "users/new", "Changed");
+        assert_eq!(cached.render("users/new", ()).unwrap(), "New user");
+
+        assert!(Views::from_directory(dir.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Deliveries, their attempts and the retry schedule
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Response bodies are logged up to this many bytes
+pub const MAX_LOGGED_BODY: usize = 1024;
+
+/// State of a delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    /// Waiting for its first attempt or a retry
+    Pending,
+
+    /// Answered with a 2xx response
+    Succeeded,
+
+    /// All attempts failed
+    Failed,
+}
+
+/// Event sent to one subscription, with the log of its attempts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    pub id: Uuid,
+
+    pub subscription_id: Uuid,
+
+    pub event: String,
+
+    pub payload: Value,
+
+    pub status: DeliveryStatus,
+
+    pub attempts: Vec<Attempt>,
+
+    /// When the next retry is due, while pending
+    pub next_attempt_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl Delivery {
+    /// Create a pending delivery, due now
+    pub fn new(subscription_id: Uuid, event: impl Into<String>, payload: Value) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            subscription_id,
+            event: event.into(),
+            payload,
+            status: DeliveryStatus::Pending,
+            attempts: Vec::new(),
+            next_attempt_at: Some(now),
+            created_at: now,
+        }
+    }
+
+    /// The most recent attempt
+    pub fn last_attempt(&self) -> Option<&Attempt> {
+        self.attempts.last()
+    }
+
+    /// Request body: the event, delivery ID and payload as JSON
+    pub(crate) fn body(&self) -> Vec<u8> {
+        let body = serde_json::json!({
+            "id": self.id,
+            "event": self.event,
+            "created_at": self.created_at,
+            "data": self.payload,
+        });
+        // Serializing a `Value` can't fail
+        serde_json::to_vec(&body).unwrap_or_default()
+    }
+}
+
+/// One attempt to deliver
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attempt {
+    pub attempted_at: DateTime<Utc>,
+
+    /// Response status, if the endpoint answered
+    pub response_status: Option<u16>,
+
+    /// Start of the response body, up to [`MAX_LOGGED_BODY`] bytes
+    pub response_body: Option<String>,
+
+    /// Why the request failed without a response, e.g. a timeout
+    pub error: Option<String>,
+
+    pub duration_ms: u64,
+}
+
+impl Attempt {
+    /// Whether the endpoint answered with a 2xx response
+    pub fn succeeded(&self) -> bool {
+        self.response_status
+            .is_some_and(|status| (200..300).contains(&status))
+    }
+}
+
+/// Truncate `body` to [`MAX_LOGGED_BODY`] bytes on a char boundary
+pub(crate) fn truncate_body(body: String) -> String {
+    if body.len() <= MAX_LOGGED_BODY {
+        return body;
+    }
+    let mut end = MAX_LOGGED_BODY;
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    body[..end].to_string()
+}
+
+/// Delays between the attempts of failed deliveries
+///
+/// ```
+/// use rf_webhooks::RetrySchedule;
+/// use std::time::Duration;
+///
+/// // 1m, 2m, 4m, 8m, then 10m until 8 retries
+/// let schedule = RetrySchedule::exponential(Duration::from_secs(60), 8)
+///     .max_delay(Duration::from_secs(600));
+/// assert_eq!(schedule.delay(3), Some(Duration::from_secs(480)));
+/// assert_eq!(schedule.delay(5), Some(Duration::from_secs(600)));
+/// assert_eq!(schedule.delay(8), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetrySchedule {
+    delays: Vec<Duration>,
+}
+
+impl RetrySchedule {
+    /// Retry after each of `delays` in turn
+    pub fn new(delays: impl IntoIterator<Item = Duration>) -> Self {
+        Self {
+            delays: delays.into_iter().collect(),
+        }
+    }
+
+    /// Retry `retries` times, doubling the delay from `base` each time
+    pub fn exponential(base: Duration, retries: u32) -> Self {
+        Self::new((0..retries).map(|retry| base.saturating_mul(2u32.saturating_pow(retry))))
+    }
+
+    /// Never retry
+    pub fn none() -> Self {
+        Self::new([])
+    }
+
+    /// Cap each delay at `max`
+    pub fn max_delay(mut self, max: Duration) -> Self {
+        for delay in &mut self.delays {
+            *delay = (*delay).min(max);
+        }
+        self
+    }
+
+    /// Delay before retry `retry` (starting at 0); `None` once exhausted
+    pub fn delay(&self, retry: u32) -> Option<Duration> {
+        self.delays.get(retry as usize).copied()
+    }
+
+    /// Number of retries
+    pub fn retries(&self) -> u32 {
+        self.delays.len() as u32
+    }
+}
+
+impl Default for RetrySchedule {
+    /// 1 minute, doubling up to 8 retries (about 4 hours in total)
+    fn default() -> Self {
+        Self::exponential(Duration::from_secs(60), 8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedules() {
+        let schedule = RetrySchedule::default();
+        assert_eq!(schedule.retries(), 8);
+        assert_eq!(schedule.delay(0), Some(Duration::from_secs(60)));
+        assert_eq!(schedule.delay(7), Some(Duration::from_secs(60 * 128)));
+
+        let schedule = RetrySchedule::new([Duration::from_secs(5), Duration::from_secs(30)]);
+        assert_eq!(schedule.delay(1), Some(Duration::from_secs(30)));
+        assert_eq!(schedule.delay(2), None);
+        assert_eq!(RetrySchedule::none().delay(0), None);
+    }
+
+    #[test]
+    fn test_truncate_body() {
+        assert_eq!(truncate_body("ok".to_string()), "ok");
+        let long = "ä".repeat(MAX_LOGGED_BODY);
+        let truncated = truncate_body(long);
+        assert!(truncated.len() <= MAX_LOGGED_BODY);
+        assert!(truncated.chars().all(|c| c == 'ä'));
+    }
+}
@@ -0,0 +1,41 @@
+//! Webhook errors
+
+use thiserror::Error;
+
+/// Webhook errors
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("Webhook subscription not found: {0}")]
+    SubscriptionNotFound(String),
+
+    #[error("Invalid webhook URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("Invalid webhook signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Webhook store error: {0}")]
+    Store(String),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(WebhookError, {
+    Self::SubscriptionNotFound(_) => (NOT_FOUND, "webhooks.subscription_not_found"),
+    Self::InvalidUrl(_) => (UNPROCESSABLE_ENTITY, "webhooks.invalid_url"),
+    Self::InvalidSignature(_) => (UNPROCESSABLE_ENTITY, "webhooks.invalid_signature"),
+    Self::Store(_) => (INTERNAL_SERVER_ERROR, "webhooks.store"),
+    Self::Serialization(_) => (INTERNAL_SERVER_ERROR, "webhooks.serialization"),
+});
+
+/// Result type for webhook operations
+pub type WebhookResult<T> = Result<T, WebhookError>;
+
+#[cfg(feature = "database")]
+impl From<sqlx::Error> for WebhookError {
+    fn from(e: sqlx::Error) -> Self {
+        WebhookError::Store(e.to_string())
+    }
+}
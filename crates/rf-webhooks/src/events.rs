@@ -0,0 +1,113 @@
+//! rf-events as the event source
+
+use crate::{WebhookError, Webhooks};
+use async_trait::async_trait;
+use rf_events::{Event, EventDispatcher, EventError, EventListenerFor, EventResult};
+use serde::Serialize;
+use std::marker::PhantomData;
+
+/// Sends rf-events events to webhook subscriptions
+///
+/// ```
+/// use rf_events::{Event, EventDispatcher};
+/// use rf_webhooks::{MemoryWebhookStore, WebhookEventsExt, Webhooks};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct OrderShipped {
+///     order_id: u64,
+/// }
+///
+/// impl Event for OrderShipped {}
+///
+/// # async fn example() -> rf_events::EventResult<()> {
+/// let webhooks = Webhooks::new(MemoryWebhookStore::new());
+/// let dispatcher = EventDispatcher::new();
+/// dispatcher
+///     .forward_webhooks::<OrderShipped>("order.shipped", webhooks)
+///     .await;
+///
+/// // POSTs `{"order_id": 42}` to subscriptions of `order.shipped`
+/// dispatcher.dispatch(OrderShipped { order_id: 42 }).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait WebhookEventsExt {
+    /// Send events of `E` as webhook event `name`, with the serialized
+    /// event as payload
+    async fn forward_webhooks<E: Event + Serialize>(&self, name: &str, webhooks: Webhooks);
+}
+
+#[async_trait]
+impl WebhookEventsExt for EventDispatcher {
+    async fn forward_webhooks<E: Event + Serialize>(&self, name: &str, webhooks: Webhooks) {
+        self.listen::<E, _>(Forward::<E> {
+            name: name.to_string(),
+            webhooks,
+            _event: PhantomData,
+        })
+        .await;
+    }
+}
+
+struct Forward<E> {
+    name: String,
+    webhooks: Webhooks,
+    _event: PhantomData<fn() -> E>,
+}
+
+fn listener_error(e: WebhookError) -> EventError {
+    EventError::ListenerError(format!("Webhook dispatch failed: {}", e))
+}
+
+#[async_trait]
+impl<E: Event + Serialize> EventListenerFor<E> for Forward<E> {
+    async fn handle(&self, event: &E) -> EventResult<()> {
+        self.webhooks
+            .dispatch(&self.name, event)
+            .await
+            .map(|_| ())
+            .map_err(listener_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryWebhookStore, Subscription};
+    use rf_http_client::{FakeResponse, HttpClient, HttpFake};
+
+    #[derive(Serialize)]
+    struct UserRegistered {
+        email: String,
+    }
+
+    impl Event for UserRegistered {}
+
+    #[tokio::test]
+    async fn test_forwards_events() {
+        let fake = HttpFake::new().on("*", FakeResponse::ok());
+        let webhooks = Webhooks::new(MemoryWebhookStore::new())
+            .client(HttpClient::default().transport(fake.clone()));
+        webhooks
+            .subscribe(Subscription::new("https://a.com/hook", ["user.*"]))
+            .await
+            .unwrap();
+
+        let dispatcher = EventDispatcher::new();
+        dispatcher
+            .forward_webhooks::<UserRegistered>("user.registered", webhooks)
+            .await;
+        dispatcher
+            .dispatch(UserRegistered {
+                email: "ada@example.com".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let body: serde_json::Value = fake.recorded()[0].json().unwrap();
+        assert_eq!(body["event"], "user.registered");
+        assert_eq!(body["data"]["email"], "ada@example.com");
+    }
+}
@@ -0,0 +1,87 @@
+//! Outgoing webhooks for RustForge
+//!
+//! Endpoints subscribe to events by name; [`Webhooks::dispatch`] POSTs each
+//! event as signed JSON to the matching subscriptions and logs every
+//! attempt with its response.
+//!
+//! # Features
+//!
+//! - HMAC-SHA256 signatures over timestamp and body, see [`signature`]
+//! - Retries with exponential backoff, see [`RetrySchedule`]
+//! - Delivery log with response codes and bodies
+//! - Subscriptions disabled after repeated failures
+//! - Test pings via [`Webhooks::ping`]
+//! - rf-events as event source via `WebhookEventsExt` (feature `events`)
+//! - Management API via `webhook_admin_router` (feature `axum`)
+//!
+//! # Stores
+//!
+//! - [`MemoryWebhookStore`] for tests and single-instance apps
+//! - `PostgresWebhookStore` (feature `database`)
+//!
+//! # Payload
+//!
+//! ```json
+//! {"id": "<delivery id>", "event": "order.shipped", "created_at": "...", "data": {...}}
+//! ```
+//!
+//! sent with the `X-Webhook-Id`, `X-Webhook-Event`, `X-Webhook-Timestamp`
+//! and `X-Webhook-Signature` headers. Receivers check the signature with
+//! [`signature::verify`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rf_webhooks::{MemoryWebhookStore, Subscription, Webhooks};
+//! use serde_json::json;
+//! use std::time::Duration;
+//!
+//! # async fn example() -> rf_webhooks::WebhookResult<()> {
+//! let webhooks = Webhooks::new(MemoryWebhookStore::new());
+//! let subscription = webhooks
+//!     .subscribe(Subscription::new("https://crm.example.com/hooks", ["customer.*"]))
+//!     .await?;
+//! // Share `subscription.secret` with the receiver
+//!
+//! tokio::spawn({
+//!     let webhooks = webhooks.clone();
+//!     async move { webhooks.run_retries(Duration::from_secs(30)).await }
+//! });
+//!
+//! webhooks
+//!     .dispatch("customer.created", json!({"id": 7, "name": "Ada"}))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod delivery;
+mod error;
+pub mod signature;
+mod store;
+mod subscription;
+mod webhooks;
+
+#[cfg(feature = "events")]
+mod events;
+
+#[cfg(feature = "axum")]
+mod router;
+
+#[cfg(feature = "database")]
+mod postgres;
+
+pub use delivery::{Attempt, Delivery, DeliveryStatus, RetrySchedule, MAX_LOGGED_BODY};
+pub use error::{WebhookError, WebhookResult};
+pub use store::{MemoryWebhookStore, WebhookStore};
+pub use subscription::{generate_secret, Subscription};
+pub use webhooks::{Webhooks, PING_EVENT};
+
+#[cfg(feature = "events")]
+pub use events::WebhookEventsExt;
+
+#[cfg(feature = "axum")]
+pub use router::webhook_admin_router;
+
+#[cfg(feature = "database")]
+pub use postgres::PostgresWebhookStore;
@@ -0,0 +1,240 @@
+//! Postgres webhook store
+
+use crate::{Delivery, Subscription, WebhookResult, WebhookStore};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use sqlx::{postgres::PgPoolOptions, PgPool, Row};
+use uuid::Uuid;
+
+/// Postgres-backed webhook store
+///
+/// Subscriptions and deliveries are JSONB rows of two tables, see
+/// [`migrate`](Self::migrate). Deliveries are removed with their
+/// subscription.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_webhooks::{PostgresWebhookStore, Webhooks};
+///
+/// # async fn example() -> rf_webhooks::WebhookResult<()> {
+/// let store = PostgresWebhookStore::connect("postgres://localhost/app").await?;
+/// store.migrate().await?;
+/// let webhooks = Webhooks::new(store);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct PostgresWebhookStore {
+    pool: PgPool,
+    subscriptions: String,
+    deliveries: String,
+}
+
+impl PostgresWebhookStore {
+    /// Create a store on an existing pool, in the `webhook_subscriptions`
+    /// and `webhook_deliveries` tables
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            subscriptions: "webhook_subscriptions".to_string(),
+            deliveries: "webhook_deliveries".to_string(),
+        }
+    }
+
+    /// Connect to `database_url`
+    pub async fn connect(database_url: &str) -> WebhookResult<Self> {
+        let pool = PgPoolOptions::new().connect(database_url).await?;
+        Ok(Self::new(pool))
+    }
+
+    /// Use different tables
+    pub fn tables(
+        mut self,
+        subscriptions: impl Into<String>,
+        deliveries: impl Into<String>,
+    ) -> Self {
+        self.subscriptions = subscriptions.into();
+        self.deliveries = deliveries.into();
+        self
+    }
+
+    /// Create the tables and their indexes if they don't exist
+    pub async fn migrate(&self) -> WebhookResult<()> {
+        let (subscriptions, deliveries) = (&self.subscriptions, &self.deliveries);
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {subscriptions} (
+                id TEXT PRIMARY KEY,
+                data JSONB NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL
+            )"
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {deliveries} (
+                id TEXT PRIMARY KEY,
+                subscription_id TEXT NOT NULL
+                    REFERENCES {subscriptions} (id) ON DELETE CASCADE,
+                status TEXT NOT NULL,
+                next_attempt_at TIMESTAMPTZ,
+                data JSONB NOT NULL,
+                created_at TIMESTAMPTZ NOT NULL
+            )"
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(&format!(
+            "CREATE INDEX IF NOT EXISTS {deliveries}_subscription_idx
+             ON {deliveries} (subscription_id, created_at DESC)"
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(&format!(
+            "CREATE INDEX IF NOT EXISTS {deliveries}_due_idx
+             ON {deliveries} (next_attempt_at) WHERE status = 'pending'"
+        ))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+fn decode<T: DeserializeOwned>(row: &sqlx::postgres::PgRow) -> WebhookResult<T> {
+    let data: String = row.try_get("data")?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+fn status(delivery: &Delivery) -> WebhookResult<String> {
+    // Unit variants serialize to plain strings
+    Ok(serde_json::to_value(delivery.status)?
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
+
+#[async_trait]
+impl WebhookStore for PostgresWebhookStore {
+    async fn save_subscription(&self, subscription: &Subscription) -> WebhookResult<()> {
+        sqlx::query(&format!(
+            "INSERT INTO {} (id, data, created_at) VALUES ($1, $2::jsonb, $3)
+             ON CONFLICT (id) DO UPDATE SET data = EXCLUDED.data",
+            self.subscriptions
+        ))
+        .bind(subscription.id.to_string())
+        .bind(serde_json::to_string(subscription)?)
+        .bind(subscription.created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn subscription(&self, id: Uuid) -> WebhookResult<Option<Subscription>> {
+        let row = sqlx::query(&format!(
+            "SELECT data::text AS data FROM {} WHERE id = $1",
+            self.subscriptions
+        ))
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(decode).transpose()
+    }
+
+    async fn subscriptions(&self) -> WebhookResult<Vec<Subscription>> {
+        sqlx::query(&format!(
+            "SELECT data::text AS data FROM {} ORDER BY created_at",
+            self.subscriptions
+        ))
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(decode)
+        .collect()
+    }
+
+    async fn delete_subscription(&self, id: Uuid) -> WebhookResult<bool> {
+        let result = sqlx::query(&format!("DELETE FROM {} WHERE id = $1", self.subscriptions))
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn save_delivery(&self, delivery: &Delivery) -> WebhookResult<()> {
+        sqlx::query(&format!(
+            "INSERT INTO {} (id, subscription_id, status, next_attempt_at, data, created_at)
+             VALUES ($1, $2, $3, $4, $5::jsonb, $6)
+             ON CONFLICT (id) DO UPDATE SET
+                status = EXCLUDED.status,
+                next_attempt_at = EXCLUDED.next_attempt_at,
+                data = EXCLUDED.data",
+            self.deliveries
+        ))
+        .bind(delivery.id.to_string())
+        .bind(delivery.subscription_id.to_string())
+        .bind(status(delivery)?)
+        .bind(delivery.next_attempt_at)
+        .bind(serde_json::to_string(delivery)?)
+        .bind(delivery.created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delivery(&self, id: Uuid) -> WebhookResult<Option<Delivery>> {
+        let row = sqlx::query(&format!(
+            "SELECT data::text AS data FROM {} WHERE id = $1",
+            self.deliveries
+        ))
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(decode).transpose()
+    }
+
+    async fn deliveries(
+        &self,
+        subscription_id: Uuid,
+        limit: usize,
+    ) -> WebhookResult<Vec<Delivery>> {
+        sqlx::query(&format!(
+            "SELECT data::text AS data FROM {}
+             WHERE subscription_id = $1
+             ORDER BY created_at DESC
+             LIMIT $2",
+            self.deliveries
+        ))
+        .bind(subscription_id.to_string())
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(decode)
+        .collect()
+    }
+
+    async fn due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> WebhookResult<Vec<Delivery>> {
+        sqlx::query(&format!(
+            "SELECT data::text AS data FROM {}
+             WHERE status = 'pending' AND next_attempt_at <= $1
+             ORDER BY next_attempt_at
+             LIMIT $2",
+            self.deliveries
+        ))
+        .bind(now)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(decode)
+        .collect()
+    }
+}
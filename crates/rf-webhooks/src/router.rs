@@ -0,0 +1,265 @@
+//! Token-protected REST API for managing subscriptions
+
+use crate::{Delivery, Subscription, WebhookError, Webhooks};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use rf_middleware::BearerTokenLayer;
+use serde::Deserialize;
+use uuid::Uuid;
+
+impl IntoResponse for WebhookError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            WebhookError::SubscriptionNotFound(_) => StatusCode::NOT_FOUND,
+            WebhookError::InvalidUrl(_) | WebhookError::InvalidSignature(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            WebhookError::Store(_) | WebhookError::Serialization(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+        let body = serde_json::json!({ "error": self.to_string() });
+        (status, Json(body)).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SubscribeBody {
+    url: String,
+    events: Vec<String>,
+    description: Option<String>,
+    secret: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeliveriesQuery {
+    limit: Option<usize>,
+}
+
+/// Build the webhook management router
+///
+/// Every route requires `Authorization: Bearer <token>`.
+///
+/// | Method | Path | Action |
+/// |--------|------|--------|
+/// | GET | `/webhooks` | list subscriptions |
+/// | POST | `/webhooks` | subscribe, generating a secret unless given |
+/// | GET | `/webhooks/{id}` | show a subscription |
+/// | DELETE | `/webhooks/{id}` | unsubscribe |
+/// | POST | `/webhooks/{id}/enable` | enable, resetting failures |
+/// | POST | `/webhooks/{id}/disable` | disable |
+/// | POST | `/webhooks/{id}/ping` | send a test ping |
+/// | GET | `/webhooks/{id}/deliveries?limit=` | latest deliveries (default: 50) |
+///
+/// # Panics
+///
+/// Panics if `token` is empty.
+///
+/// # Example
+///
+/// ```ignore
+/// let app = Router::new().nest("/admin", webhook_admin_router(webhooks, "secret-token"));
+/// ```
+pub fn webhook_admin_router(webhooks: Webhooks, token: impl Into<String>) -> Router {
+    Router::new()
+        .route("/webhooks", get(list_subscriptions).post(subscribe))
+        .route("/webhooks/{id}", get(show_subscription).delete(unsubscribe))
+        .route("/webhooks/{id}/enable", post(enable))
+        .route("/webhooks/{id}/disable", post(disable))
+        .route("/webhooks/{id}/ping", post(ping))
+        .route("/webhooks/{id}/deliveries", get(deliveries))
+        .layer(BearerTokenLayer::new(token))
+        .with_state(webhooks)
+}
+
+async fn list_subscriptions(
+    State(webhooks): State<Webhooks>,
+) -> Result<Json<Vec<Subscription>>, WebhookError> {
+    Ok(Json(webhooks.subscriptions().await?))
+}
+
+async fn subscribe(
+    State(webhooks): State<Webhooks>,
+    Json(body): Json<SubscribeBody>,
+) -> Result<impl IntoResponse, WebhookError> {
+    let mut subscription = Subscription::new(body.url, body.events);
+    subscription.description = body.description;
+    if let Some(secret) = body.secret {
+        subscription = subscription.secret(secret);
+    }
+
+    let subscription = webhooks.subscribe(subscription).await?;
+    Ok((StatusCode::CREATED, Json(subscription)))
+}
+
+async fn show_subscription(
+    State(webhooks): State<Webhooks>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Subscription>, WebhookError> {
+    Ok(Json(webhooks.subscription(id).await?))
+}
+
+async fn unsubscribe(
+    State(webhooks): State<Webhooks>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, WebhookError> {
+    webhooks.unsubscribe(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn enable(
+    State(webhooks): State<Webhooks>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Subscription>, WebhookError> {
+    Ok(Json(webhooks.enable(id).await?))
+}
+
+async fn disable(
+    State(webhooks): State<Webhooks>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Subscription>, WebhookError> {
+    Ok(Json(webhooks.disable(id).await?))
+}
+
+async fn ping(
+    State(webhooks): State<Webhooks>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Delivery>, WebhookError> {
+    Ok(Json(webhooks.ping(id).await?))
+}
+
+async fn deliveries(
+    State(webhooks): State<Webhooks>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DeliveriesQuery>,
+) -> Result<Json<Vec<Delivery>>, WebhookError> {
+    webhooks.subscription(id).await?;
+    Ok(Json(
+        webhooks.deliveries(id, query.limit.unwrap_or(50)).await?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryWebhookStore;
+    use axum::{body::Body, extract::Request, http::header};
+    use rf_http_client::{FakeResponse, HttpClient, HttpFake};
+    use tower::ServiceExt;
+
+    fn request(method: &str, uri: &str, body: Option<serde_json::Value>) -> Request {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .header(header::CONTENT_TYPE, "application/json");
+
+        match body {
+            Some(body) => builder.body(Body::from(body.to_string())).unwrap(),
+            None => builder.body(Body::empty()).unwrap(),
+        }
+    }
+
+    async fn json(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requires_token() {
+        let app = webhook_admin_router(Webhooks::new(MemoryWebhookStore::new()), "secret");
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/webhooks")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_manage_and_ping() {
+        let fake = HttpFake::new().on("*", FakeResponse::ok());
+        let webhooks = Webhooks::new(MemoryWebhookStore::new())
+            .client(HttpClient::default().transport(fake.clone()));
+        let app = webhook_admin_router(webhooks.clone(), "secret");
+
+        let created = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/webhooks",
+                Some(serde_json::json!({ "url": "https://a.com/hook", "events": ["*"] })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(created.status(), StatusCode::CREATED);
+        let subscription = json(created).await;
+        assert!(subscription["secret"]
+            .as_str()
+            .unwrap()
+            .starts_with("whsec_"));
+        let id = subscription["id"].as_str().unwrap();
+
+        let pinged = app
+            .clone()
+            .oneshot(request("POST", &format!("/webhooks/{}/ping", id), None))
+            .await
+            .unwrap();
+        assert_eq!(json(pinged).await["status"], "succeeded");
+
+        let log = app
+            .clone()
+            .oneshot(request(
+                "GET",
+                &format!("/webhooks/{}/deliveries", id),
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json(log).await[0]["attempts"][0]["response_status"], 200);
+
+        let disabled = app
+            .clone()
+            .oneshot(request("POST", &format!("/webhooks/{}/disable", id), None))
+            .await
+            .unwrap();
+        assert_eq!(json(disabled).await["active"], false);
+
+        let invalid = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/webhooks",
+                Some(serde_json::json!({ "url": "not a url", "events": ["*"] })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(invalid.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let deleted = app
+            .clone()
+            .oneshot(request("DELETE", &format!("/webhooks/{}", id), None))
+            .await
+            .unwrap();
+        assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+
+        let missing = app
+            .oneshot(request("GET", &format!("/webhooks/{}", id), None))
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+}
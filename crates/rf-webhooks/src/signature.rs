@@ -0,0 +1,99 @@
+//! Payload signatures
+
+use crate::{WebhookError, WebhookResult};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+
+/// ID of the delivery, the same across retries
+pub const ID_HEADER: &str = "X-Webhook-Id";
+
+/// Name of the event
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+
+/// Unix time the payload was signed at
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+
+/// `sha256=` followed by the hex HMAC of `{timestamp}.{body}`
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// Sign `body` sent at `timestamp` with a subscription secret
+///
+/// The timestamp is part of the signature, so receivers can reject replayed
+/// requests.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    format!(
+        "sha256={}",
+        hex::encode(mac(secret, timestamp, body).finalize().into_bytes())
+    )
+}
+
+/// Verify a signature on the receiving side
+///
+/// Fails if the signature doesn't match or `timestamp` is further than
+/// `tolerance` from now.
+///
+/// ```
+/// use rf_webhooks::signature::{sign, verify};
+/// use std::time::Duration;
+///
+/// let timestamp = chrono::Utc::now().timestamp();
+/// let signature = sign("whsec_test", timestamp, b"{}");
+/// assert!(verify("whsec_test", timestamp, b"{}", &signature, Duration::from_secs(300)).is_ok());
+/// assert!(verify("other", timestamp, b"{}", &signature, Duration::from_secs(300)).is_err());
+/// ```
+pub fn verify(
+    secret: &str,
+    timestamp: i64,
+    body: &[u8],
+    signature: &str,
+    tolerance: Duration,
+) -> WebhookResult<()> {
+    let age = chrono::Utc::now().timestamp().abs_diff(timestamp);
+    if age > tolerance.as_secs() {
+        return Err(WebhookError::InvalidSignature(
+            "Timestamp outside of the tolerance".into(),
+        ));
+    }
+
+    let expected = signature
+        .strip_prefix("sha256=")
+        .and_then(|hex| hex::decode(hex).ok())
+        .ok_or_else(|| WebhookError::InvalidSignature("Malformed signature".into()))?;
+
+    // Constant-time comparison
+    mac(secret, timestamp, body)
+        .verify_slice(&expected)
+        .map_err(|_| WebhookError::InvalidSignature("Signature mismatch".into()))
+}
+
+fn mac(secret: &str, timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let now = chrono::Utc::now().timestamp();
+        let tolerance = Duration::from_secs(300);
+        let signature = sign("secret", now, b"{\"a\":1}");
+        assert!(signature.starts_with("sha256="));
+        assert_eq!(signature.len(), "sha256=".len() + 64);
+
+        assert!(verify("secret", now, b"{\"a\":1}", &signature, tolerance).is_ok());
+        assert!(verify("secret", now, b"{\"a\":2}", &signature, tolerance).is_err());
+        assert!(verify("secret", now, b"{\"a\":1}", "sha256=zz", tolerance).is_err());
+
+        let old = now - 600;
+        let signature = sign("secret", old, b"{}");
+        assert!(verify("secret", old, b"{}", &signature, tolerance).is_err());
+    }
+}
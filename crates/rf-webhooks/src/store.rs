@@ -0,0 +1,154 @@
+//! Storage of subscriptions and deliveries
+
+use crate::{Delivery, DeliveryStatus, Subscription, WebhookResult};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+/// Stores subscriptions and the delivery log
+#[async_trait]
+pub trait WebhookStore: Send + Sync {
+    /// Insert or update a subscription
+    async fn save_subscription(&self, subscription: &Subscription) -> WebhookResult<()>;
+
+    async fn subscription(&self, id: Uuid) -> WebhookResult<Option<Subscription>>;
+
+    /// All subscriptions, oldest first
+    async fn subscriptions(&self) -> WebhookResult<Vec<Subscription>>;
+
+    /// Delete a subscription and its deliveries; `false` if it didn't exist
+    async fn delete_subscription(&self, id: Uuid) -> WebhookResult<bool>;
+
+    /// Insert or update a delivery
+    async fn save_delivery(&self, delivery: &Delivery) -> WebhookResult<()>;
+
+    async fn delivery(&self, id: Uuid) -> WebhookResult<Option<Delivery>>;
+
+    /// Latest deliveries to a subscription, newest first
+    async fn deliveries(&self, subscription_id: Uuid, limit: usize)
+        -> WebhookResult<Vec<Delivery>>;
+
+    /// Pending deliveries due at `now`, most overdue first
+    async fn due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> WebhookResult<Vec<Delivery>>;
+}
+
+/// In-memory store, for tests and single-process apps
+#[derive(Clone, Default)]
+pub struct MemoryWebhookStore {
+    subscriptions: Arc<RwLock<HashMap<Uuid, Subscription>>>,
+    deliveries: Arc<RwLock<HashMap<Uuid, Delivery>>>,
+}
+
+impl MemoryWebhookStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl WebhookStore for MemoryWebhookStore {
+    async fn save_subscription(&self, subscription: &Subscription) -> WebhookResult<()> {
+        self.subscriptions
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(subscription.id, subscription.clone());
+        Ok(())
+    }
+
+    async fn subscription(&self, id: Uuid) -> WebhookResult<Option<Subscription>> {
+        Ok(self
+            .subscriptions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&id)
+            .cloned())
+    }
+
+    async fn subscriptions(&self) -> WebhookResult<Vec<Subscription>> {
+        let mut subscriptions: Vec<Subscription> = self
+            .subscriptions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        subscriptions.sort_by_key(|s| s.created_at);
+        Ok(subscriptions)
+    }
+
+    async fn delete_subscription(&self, id: Uuid) -> WebhookResult<bool> {
+        let removed = self
+            .subscriptions
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&id)
+            .is_some();
+        self.deliveries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|_, delivery| delivery.subscription_id != id);
+        Ok(removed)
+    }
+
+    async fn save_delivery(&self, delivery: &Delivery) -> WebhookResult<()> {
+        self.deliveries
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(delivery.id, delivery.clone());
+        Ok(())
+    }
+
+    async fn delivery(&self, id: Uuid) -> WebhookResult<Option<Delivery>> {
+        Ok(self
+            .deliveries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&id)
+            .cloned())
+    }
+
+    async fn deliveries(
+        &self,
+        subscription_id: Uuid,
+        limit: usize,
+    ) -> WebhookResult<Vec<Delivery>> {
+        let mut deliveries: Vec<Delivery> = self
+            .deliveries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|delivery| delivery.subscription_id == subscription_id)
+            .cloned()
+            .collect();
+        deliveries.sort_by_key(|d| std::cmp::Reverse(d.created_at));
+        deliveries.truncate(limit);
+        Ok(deliveries)
+    }
+
+    async fn due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> WebhookResult<Vec<Delivery>> {
+        let mut deliveries: Vec<Delivery> = self
+            .deliveries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|delivery| {
+                delivery.status == DeliveryStatus::Pending
+                    && delivery.next_attempt_at.is_some_and(|at| at <= now)
+            })
+            .cloned()
+            .collect();
+        deliveries.sort_by_key(|d| d.next_attempt_at);
+        deliveries.truncate(limit);
+        Ok(deliveries)
+    }
+}
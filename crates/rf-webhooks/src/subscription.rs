@@ -0,0 +1,106 @@
+//! Webhook subscriptions
+
+use chrono::{DateTime, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Endpoint receiving events
+///
+/// Event patterns are exact names like `order.shipped`, prefixes like
+/// `order.*` or `*` for all events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub id: Uuid,
+
+    pub url: String,
+
+    /// Secret signing the payloads, shared with the receiver
+    pub secret: String,
+
+    /// Patterns of the events sent
+    pub events: Vec<String>,
+
+    pub description: Option<String>,
+
+    /// Inactive subscriptions receive no events
+    pub active: bool,
+
+    /// Failed deliveries since the last successful one
+    pub consecutive_failures: u32,
+
+    /// When the subscription was disabled after repeated failures
+    pub disabled_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+impl Subscription {
+    /// Create an active subscription with a generated secret
+    pub fn new<S: Into<String>>(
+        url: impl Into<String>,
+        events: impl IntoIterator<Item = S>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            url: url.into(),
+            secret: generate_secret(),
+            events: events.into_iter().map(Into::into).collect(),
+            description: None,
+            active: true,
+            consecutive_failures: 0,
+            disabled_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Use `secret` instead of a generated one
+    pub fn secret(mut self, secret: impl Into<String>) -> Self {
+        self.secret = secret.into();
+        self
+    }
+
+    /// Set the description
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Whether `event` matches one of the patterns
+    pub fn listens_to(&self, event: &str) -> bool {
+        self.events
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => event.starts_with(prefix),
+                None => pattern == event,
+            })
+    }
+}
+
+/// Random secret like `whsec_…`
+pub fn generate_secret() -> String {
+    let random: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+    format!("whsec_{}", random)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listens_to() {
+        let subscription = Subscription::new("https://a.com/hook", ["order.*", "user.created"]);
+        assert!(subscription.listens_to("order.shipped"));
+        assert!(subscription.listens_to("user.created"));
+        assert!(!subscription.listens_to("user.deleted"));
+        assert!(Subscription::new("https://a.com/hook", ["*"]).listens_to("anything"));
+
+        assert!(subscription.secret.starts_with("whsec_"));
+        assert_ne!(subscription.secret, generate_secret());
+    }
+}
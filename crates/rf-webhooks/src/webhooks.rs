@@ -0,0 +1,545 @@
+//! Webhooks facade
+
+use crate::delivery::truncate_body;
+use crate::signature::{sign, EVENT_HEADER, ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::{
+    Attempt, Delivery, DeliveryStatus, RetrySchedule, Subscription, WebhookError, WebhookResult,
+    WebhookStore,
+};
+use chrono::Utc;
+use rf_http_client::{HttpClient, RetryPolicy, Url};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Name of the event sent by [`Webhooks::ping`]
+pub const PING_EVENT: &str = "webhook.ping";
+
+/// Due deliveries retried per [`Webhooks::retry_due`] call
+const RETRY_BATCH: usize = 100;
+
+/// Outgoing webhooks
+///
+/// Events are POSTed as JSON to every active subscription listening to
+/// them, signed with the subscription's secret (see
+/// [`signature`](crate::signature)). Deliveries answered without a 2xx
+/// response are retried on the [`RetrySchedule`] by
+/// [`retry_due`](Self::retry_due) or [`run_retries`](Self::run_retries).
+/// After [`disable_after`](Self::disable_after) failed attempts in a row a
+/// subscription is disabled until [`enable`](Self::enable)d again.
+///
+/// # Example
+///
+/// ```
+/// use rf_http_client::{FakeResponse, HttpClient, HttpFake};
+/// use rf_webhooks::{MemoryWebhookStore, Subscription, Webhooks};
+/// use serde_json::json;
+///
+/// # async fn example() -> rf_webhooks::WebhookResult<()> {
+/// let fake = HttpFake::new().post("https://shop.example.com/*", FakeResponse::ok());
+/// let webhooks = Webhooks::new(MemoryWebhookStore::new())
+///     .client(HttpClient::default().transport(fake.clone()));
+///
+/// let subscription = webhooks
+///     .subscribe(Subscription::new("https://shop.example.com/hooks", ["order.*"]))
+///     .await?;
+/// webhooks.dispatch("order.shipped", json!({"order_id": 42})).await?;
+///
+/// let deliveries = webhooks.deliveries(subscription.id, 10).await?;
+/// assert_eq!(deliveries[0].last_attempt().unwrap().response_status, Some(200));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Webhooks {
+    store: Arc<dyn WebhookStore>,
+    client: HttpClient,
+    schedule: RetrySchedule,
+    disable_after: Option<u32>,
+    timeout: Duration,
+}
+
+impl Webhooks {
+    /// Create webhooks on `store`
+    pub fn new(store: impl WebhookStore + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+            client: HttpClient::default(),
+            schedule: RetrySchedule::default(),
+            disable_after: Some(20),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Send with `client`, e.g. one faked in tests
+    pub fn client(mut self, client: HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Set the retry schedule (default: 1 minute, doubling up to 8 retries)
+    pub fn retry_schedule(mut self, schedule: RetrySchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// Disable subscriptions after this many failed attempts in a row
+    /// (default: 20); `None` never disables them
+    pub fn disable_after(mut self, failures: Option<u32>) -> Self {
+        self.disable_after = failures;
+        self
+    }
+
+    /// Set the timeout per attempt (default: 10 seconds)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The underlying store
+    pub fn store(&self) -> &Arc<dyn WebhookStore> {
+        &self.store
+    }
+
+    /// Add a subscription
+    pub async fn subscribe(&self, subscription: Subscription) -> WebhookResult<Subscription> {
+        let url = Url::parse(&subscription.url)
+            .map_err(|e| WebhookError::InvalidUrl(format!("{}: {}", subscription.url, e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(WebhookError::InvalidUrl(format!(
+                "{}: only http and https are supported",
+                subscription.url
+            )));
+        }
+
+        self.store.save_subscription(&subscription).await?;
+        tracing::info!(
+            subscription = %subscription.id,
+            url = %subscription.url,
+            "Webhook subscribed"
+        );
+        Ok(subscription)
+    }
+
+    /// Remove a subscription and its delivery log
+    pub async fn unsubscribe(&self, id: Uuid) -> WebhookResult<()> {
+        if !self.store.delete_subscription(id).await? {
+            return Err(WebhookError::SubscriptionNotFound(id.to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn subscription(&self, id: Uuid) -> WebhookResult<Subscription> {
+        self.store
+            .subscription(id)
+            .await?
+            .ok_or_else(|| WebhookError::SubscriptionNotFound(id.to_string()))
+    }
+
+    pub async fn subscriptions(&self) -> WebhookResult<Vec<Subscription>> {
+        self.store.subscriptions().await
+    }
+
+    /// Activate a subscription, resetting its failure count
+    pub async fn enable(&self, id: Uuid) -> WebhookResult<Subscription> {
+        let mut subscription = self.subscription(id).await?;
+        subscription.active = true;
+        subscription.consecutive_failures = 0;
+        subscription.disabled_at = None;
+        self.store.save_subscription(&subscription).await?;
+        Ok(subscription)
+    }
+
+    /// Stop sending events to a subscription
+    pub async fn disable(&self, id: Uuid) -> WebhookResult<Subscription> {
+        let mut subscription = self.subscription(id).await?;
+        subscription.active = false;
+        self.store.save_subscription(&subscription).await?;
+        Ok(subscription)
+    }
+
+    /// Latest deliveries to a subscription, newest first
+    pub async fn deliveries(&self, id: Uuid, limit: usize) -> WebhookResult<Vec<Delivery>> {
+        self.store.deliveries(id, limit).await
+    }
+
+    /// Send `event` to all active subscriptions listening to it
+    ///
+    /// First attempts are made concurrently; failed ones are left pending
+    /// for [`retry_due`](Self::retry_due). Only store errors are returned.
+    pub async fn dispatch(
+        &self,
+        event: &str,
+        payload: impl Serialize,
+    ) -> WebhookResult<Vec<Delivery>> {
+        let payload = serde_json::to_value(payload)?;
+        let subscriptions = self
+            .store
+            .subscriptions()
+            .await?
+            .into_iter()
+            .filter(|subscription| subscription.active && subscription.listens_to(event));
+
+        let deliveries = subscriptions.map(|subscription| {
+            let delivery = Delivery::new(subscription.id, event, payload.clone());
+            self.deliver(subscription, delivery)
+        });
+        futures::future::join_all(deliveries)
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    /// Send a [`PING_EVENT`] to check an endpoint
+    ///
+    /// Disabled subscriptions are pinged too, e.g. before enabling them
+    /// again. Pings are neither retried nor counted as failures.
+    pub async fn ping(&self, id: Uuid) -> WebhookResult<Delivery> {
+        let subscription = self.subscription(id).await?;
+        let mut delivery =
+            Delivery::new(id, PING_EVENT, serde_json::json!({ "subscription_id": id }));
+
+        let attempt = self.send(&subscription, &delivery).await;
+        delivery.status = if attempt.succeeded() {
+            DeliveryStatus::Succeeded
+        } else {
+            DeliveryStatus::Failed
+        };
+        delivery.next_attempt_at = None;
+        delivery.attempts.push(attempt);
+        self.store.save_delivery(&delivery).await?;
+        Ok(delivery)
+    }
+
+    /// Retry pending deliveries that are due, returning how many were
+    /// attempted
+    ///
+    /// Run this from one process only, e.g. via
+    /// [`run_retries`](Self::run_retries) or a scheduled task.
+    pub async fn retry_due(&self) -> WebhookResult<usize> {
+        let due = self.store.due_deliveries(Utc::now(), RETRY_BATCH).await?;
+        let mut attempted = 0;
+        for mut delivery in due {
+            match self.store.subscription(delivery.subscription_id).await? {
+                Some(subscription) if subscription.active => {
+                    self.deliver(subscription, delivery).await?;
+                    attempted += 1;
+                }
+                // Give up on deliveries to disabled subscriptions
+                _ => {
+                    delivery.status = DeliveryStatus::Failed;
+                    delivery.next_attempt_at = None;
+                    self.store.save_delivery(&delivery).await?;
+                }
+            }
+        }
+        Ok(attempted)
+    }
+
+    /// Call [`retry_due`](Self::retry_due) every `interval`, forever
+    pub async fn run_retries(&self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.retry_due().await {
+                tracing::error!(error = %e, "Retrying webhook deliveries failed");
+            }
+        }
+    }
+
+    /// Attempt a delivery, schedule its retry and track the subscription's
+    /// failures
+    async fn deliver(
+        &self,
+        subscription: Subscription,
+        mut delivery: Delivery,
+    ) -> WebhookResult<Delivery> {
+        let attempt = self.send(&subscription, &delivery).await;
+        let succeeded = attempt.succeeded();
+        delivery.attempts.push(attempt);
+
+        let retry = delivery.attempts.len() as u32 - 1;
+        match self.schedule.delay(retry) {
+            _ if succeeded => {
+                delivery.status = DeliveryStatus::Succeeded;
+                delivery.next_attempt_at = None;
+            }
+            Some(delay) => {
+                delivery.next_attempt_at = Some(Utc::now() + delay);
+            }
+            None => {
+                delivery.status = DeliveryStatus::Failed;
+                delivery.next_attempt_at = None;
+                tracing::warn!(
+                    delivery = %delivery.id,
+                    subscription = %subscription.id,
+                    event = %delivery.event,
+                    "Webhook delivery failed permanently"
+                );
+            }
+        }
+        self.store.save_delivery(&delivery).await?;
+        self.record_result(subscription.id, succeeded).await?;
+        Ok(delivery)
+    }
+
+    /// Update the failure count, disabling the subscription if it's
+    /// reached
+    async fn record_result(&self, id: Uuid, succeeded: bool) -> WebhookResult<()> {
+        // Reload, as deliveries to the same subscription run concurrently
+        let Some(mut subscription) = self.store.subscription(id).await? else {
+            return Ok(());
+        };
+
+        if succeeded {
+            if subscription.consecutive_failures == 0 {
+                return Ok(());
+            }
+            subscription.consecutive_failures = 0;
+        } else {
+            subscription.consecutive_failures += 1;
+            let exceeded = self
+                .disable_after
+                .is_some_and(|limit| subscription.consecutive_failures >= limit);
+            if exceeded && subscription.active {
+                subscription.active = false;
+                subscription.disabled_at = Some(Utc::now());
+                tracing::warn!(
+                    subscription = %id,
+                    url = %subscription.url,
+                    failures = subscription.consecutive_failures,
+                    "Webhook subscription disabled after repeated failures"
+                );
+            }
+        }
+        self.store.save_subscription(&subscription).await
+    }
+
+    /// POST the signed delivery once
+    async fn send(&self, subscription: &Subscription, delivery: &Delivery) -> Attempt {
+        let body = delivery.body();
+        let timestamp = Utc::now().timestamp();
+        let signature = sign(&subscription.secret, timestamp, &body);
+        let attempted_at = Utc::now();
+        let started = Instant::now();
+
+        let result = self
+            .client
+            .post(&subscription.url)
+            .header("Content-Type", "application/json")
+            .header(ID_HEADER, &delivery.id.to_string())
+            .header(EVENT_HEADER, &delivery.event)
+            .header(TIMESTAMP_HEADER, &timestamp.to_string())
+            .header(SIGNATURE_HEADER, &signature)
+            .body(body)
+            .timeout(self.timeout)
+            // Retries follow the schedule instead
+            .retry(RetryPolicy::none())
+            .send()
+            .await;
+
+        let mut attempt = Attempt {
+            attempted_at,
+            response_status: None,
+            response_body: None,
+            error: None,
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        match result {
+            Ok(response) => {
+                attempt.response_status = Some(response.status().as_u16());
+                attempt.response_body = Some(truncate_body(response.text()));
+            }
+            Err(e) => attempt.error = Some(e.to_string()),
+        }
+
+        tracing::debug!(
+            delivery = %delivery.id,
+            url = %subscription.url,
+            status = ?attempt.response_status,
+            "Webhook sent"
+        );
+        attempt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::verify;
+    use crate::MemoryWebhookStore;
+    use rf_http_client::{FakeResponse, HttpFake};
+    use serde_json::json;
+
+    fn webhooks(fake: &HttpFake) -> Webhooks {
+        Webhooks::new(MemoryWebhookStore::new())
+            .client(HttpClient::default().transport(fake.clone()))
+            .retry_schedule(RetrySchedule::new([Duration::ZERO, Duration::ZERO]))
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_signs_and_filters() {
+        let fake = HttpFake::new().post("*", FakeResponse::text(202, "queued"));
+        let webhooks = webhooks(&fake);
+        let orders = webhooks
+            .subscribe(Subscription::new("https://a.com/hook", ["order.*"]).secret("s3cret"))
+            .await
+            .unwrap();
+        webhooks
+            .subscribe(Subscription::new("https://b.com/hook", ["user.created"]))
+            .await
+            .unwrap();
+        let disabled = webhooks
+            .subscribe(Subscription::new("https://c.com/hook", ["*"]))
+            .await
+            .unwrap();
+        webhooks.disable(disabled.id).await.unwrap();
+
+        let deliveries = webhooks
+            .dispatch("order.shipped", json!({"order_id": 42}))
+            .await
+            .unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, DeliveryStatus::Succeeded);
+        fake.assert_sent_count(1);
+
+        let request = &fake.recorded()[0];
+        assert_eq!(request.url.as_str(), "https://a.com/hook");
+        let header = |name: &str| request.headers[name].to_str().unwrap().to_string();
+        assert_eq!(header(EVENT_HEADER), "order.shipped");
+        assert_eq!(header(ID_HEADER), deliveries[0].id.to_string());
+        let body = request.body.as_deref().unwrap();
+        let timestamp = header(TIMESTAMP_HEADER).parse().unwrap();
+        verify(
+            "s3cret",
+            timestamp,
+            body,
+            &header(SIGNATURE_HEADER),
+            Duration::from_secs(60),
+        )
+        .unwrap();
+        let body: serde_json::Value = request.json().unwrap();
+        assert_eq!(body["data"]["order_id"], 42);
+
+        let log = webhooks.deliveries(orders.id, 10).await.unwrap();
+        let attempt = log[0].last_attempt().unwrap();
+        assert_eq!(attempt.response_status, Some(202));
+        assert_eq!(attempt.response_body.as_deref(), Some("queued"));
+
+        assert!(matches!(
+            webhooks
+                .subscribe(Subscription::new("ftp://a.com", ["*"]))
+                .await,
+            Err(WebhookError::InvalidUrl(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_schedule_exhausted() {
+        let fake = HttpFake::new().sequence(
+            "*",
+            [
+                FakeResponse::status(500),
+                FakeResponse::connection_error(),
+                FakeResponse::status(200),
+            ],
+        );
+        let webhooks = webhooks(&fake);
+        let subscription = webhooks
+            .subscribe(Subscription::new("https://a.com/hook", ["*"]))
+            .await
+            .unwrap();
+
+        let delivery = webhooks.dispatch("a", json!({})).await.unwrap().remove(0);
+        assert_eq!(delivery.status, DeliveryStatus::Pending);
+        assert!(delivery.next_attempt_at.is_some());
+
+        assert_eq!(webhooks.retry_due().await.unwrap(), 1);
+        assert_eq!(webhooks.retry_due().await.unwrap(), 1);
+        assert_eq!(webhooks.retry_due().await.unwrap(), 0);
+
+        let delivery = webhooks
+            .store()
+            .delivery(delivery.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Succeeded);
+        assert_eq!(delivery.attempts.len(), 3);
+        assert_eq!(delivery.attempts[0].response_status, Some(500));
+        assert!(delivery.attempts[1].error.is_some());
+        assert_eq!(
+            webhooks
+                .subscription(subscription.id)
+                .await
+                .unwrap()
+                .consecutive_failures,
+            0
+        );
+
+        // Without retries left the delivery fails
+        let fake = HttpFake::new().on("*", FakeResponse::status(503));
+        let webhooks = webhooks.client(HttpClient::default().transport(fake.clone()));
+        let delivery = webhooks.dispatch("b", json!({})).await.unwrap().remove(0);
+        webhooks.retry_due().await.unwrap();
+        webhooks.retry_due().await.unwrap();
+        let delivery = webhooks
+            .store()
+            .delivery(delivery.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(delivery.status, DeliveryStatus::Failed);
+        assert_eq!(delivery.next_attempt_at, None);
+    }
+
+    #[tokio::test]
+    async fn test_disables_after_repeated_failures() {
+        let fake = HttpFake::new().on("*", FakeResponse::status(410));
+        let webhooks = webhooks(&fake)
+            .retry_schedule(RetrySchedule::none())
+            .disable_after(Some(2));
+        let subscription = webhooks
+            .subscribe(Subscription::new("https://a.com/hook", ["*"]))
+            .await
+            .unwrap();
+
+        webhooks.dispatch("a", json!({})).await.unwrap();
+        assert!(webhooks.subscription(subscription.id).await.unwrap().active);
+        webhooks.dispatch("a", json!({})).await.unwrap();
+
+        let disabled = webhooks.subscription(subscription.id).await.unwrap();
+        assert!(!disabled.active);
+        assert!(disabled.disabled_at.is_some());
+        assert!(webhooks.dispatch("a", json!({})).await.unwrap().is_empty());
+
+        let enabled = webhooks.enable(subscription.id).await.unwrap();
+        assert!(enabled.active);
+        assert_eq!(enabled.consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let fake = HttpFake::new().on("*", FakeResponse::status(500));
+        let webhooks = webhooks(&fake).disable_after(Some(1));
+        let subscription = webhooks
+            .subscribe(Subscription::new("https://a.com/hook", ["order.*"]))
+            .await
+            .unwrap();
+
+        let ping = webhooks.ping(subscription.id).await.unwrap();
+        assert_eq!(ping.event, PING_EVENT);
+        assert_eq!(ping.status, DeliveryStatus::Failed);
+        fake.assert_sent(|r| r.headers[EVENT_HEADER] == PING_EVENT);
+
+        // Not retried and not counted
+        assert_eq!(webhooks.retry_due().await.unwrap(), 0);
+        assert!(webhooks.subscription(subscription.id).await.unwrap().active);
+
+        assert!(matches!(
+            webhooks.ping(Uuid::new_v4()).await,
+            Err(WebhookError::SubscriptionNotFound(_))
+        ));
+    }
+}
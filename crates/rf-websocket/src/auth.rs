@@ -0,0 +1,162 @@
+//! Connection authentication and channel join authorization
+
+use crate::{Channel, UserId};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Authenticates connections
+///
+/// The token is taken from the `token` query parameter, since browsers
+/// can't set headers on WebSocket requests, or an `Authorization: Bearer`
+/// header. Connections without a token are guests and can only join public
+/// channels.
+#[async_trait]
+pub trait Authenticator: Send + Sync {
+    /// Resolve a token to a user ID
+    async fn authenticate(&self, token: &str) -> Result<UserId, String>;
+}
+
+#[async_trait]
+impl<F> Authenticator for F
+where
+    F: Fn(&str) -> Result<UserId, String> + Send + Sync,
+{
+    async fn authenticate(&self, token: &str) -> Result<UserId, String> {
+        self(token)
+    }
+}
+
+/// Decides whether a user may join a private or presence channel
+#[async_trait]
+pub trait ChannelAuthorizer: Send + Sync {
+    /// `None` denies the join. For presence channels the returned value is
+    /// shared with the other members, e.g. `{"name": "Ann"}`; private
+    /// channels ignore it.
+    async fn authorize(&self, user_id: &UserId, channel: &Channel) -> Option<serde_json::Value>;
+}
+
+#[async_trait]
+impl<F> ChannelAuthorizer for F
+where
+    F: Fn(&UserId, &Channel) -> Option<serde_json::Value> + Send + Sync,
+{
+    async fn authorize(&self, user_id: &UserId, channel: &Channel) -> Option<serde_json::Value> {
+        self(user_id, channel)
+    }
+}
+
+/// Join authorization callbacks by channel name pattern
+///
+/// Patterns match the channel name without its `private-`/`presence-`
+/// prefix and may contain `*` wildcards. The first matching pattern decides;
+/// channels matching none can't be joined.
+///
+/// ```
+/// use rf_websocket::Channels;
+/// use serde_json::json;
+///
+/// let channels = Channels::new()
+///     .channel("orders.*", |user_id: &String, channel: &rf_websocket::Channel| {
+///         // Users may only follow their own orders
+///         let owner = channel.name().trim_start_matches("orders.");
+///         (owner == user_id).then(|| json!({}))
+///     })
+///     .channel("chat.*", |user_id: &String, _: &rf_websocket::Channel| {
+///         Some(json!({ "id": user_id }))
+///     });
+/// ```
+#[derive(Clone, Default)]
+pub struct Channels {
+    rules: Vec<(String, Arc<dyn ChannelAuthorizer>)>,
+}
+
+impl Channels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the authorizer of channels matching `pattern`
+    pub fn channel(mut self, pattern: &str, authorizer: impl ChannelAuthorizer + 'static) -> Self {
+        self.rules.push((pattern.to_string(), Arc::new(authorizer)));
+        self
+    }
+
+    /// Authorize `user_id` to join a private or presence channel
+    pub async fn authorize(
+        &self,
+        user_id: &UserId,
+        channel: &Channel,
+    ) -> Option<serde_json::Value> {
+        let (_, authorizer) = self
+            .rules
+            .iter()
+            .find(|(pattern, _)| wildcard_match(pattern, channel.name()))?;
+        authorizer.authorize(user_id, channel).await
+    }
+}
+
+/// `*` matches any characters
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_channels_authorize() {
+        let channels = Channels::new()
+            .channel("orders.*", |user_id: &UserId, channel: &Channel| {
+                (channel.name() == format!("orders.{}", user_id)).then(|| json!({}))
+            })
+            .channel("chat", |user_id: &UserId, _: &Channel| {
+                Some(json!({ "id": user_id }))
+            });
+        let user = "1".to_string();
+
+        assert!(channels
+            .authorize(&user, &Channel::private("orders.1"))
+            .await
+            .is_some());
+        assert!(channels
+            .authorize(&user, &Channel::private("orders.2"))
+            .await
+            .is_none());
+        assert_eq!(
+            channels.authorize(&user, &Channel::presence("chat")).await,
+            Some(json!({ "id": "1" }))
+        );
+        // No matching pattern
+        assert!(channels
+            .authorize(&user, &Channel::private("admin"))
+            .await
+            .is_none());
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("orders.*", "orders.1"));
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("team.*.chat", "team.7.chat"));
+        assert!(!wildcard_match("orders.*", "users.1"));
+        assert!(!wildcard_match("chat", "chat.1"));
+    }
+}
@@ -0,0 +1,165 @@
+//! Backends fanning out messages and tracking presence across instances
+
+use crate::{Envelope, Member, WebSocketResult};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+/// Capacity of the local fan-out channel; slower connections skip
+/// messages beyond it
+pub(crate) const FANOUT_CAPACITY: usize = 1024;
+
+/// Fans out messages to the connections of all instances and stores
+/// presence
+///
+/// A user with several connections (e.g. browser tabs) is a single member;
+/// [`join`](Backend::join) and [`leave`](Backend::leave) report whether the
+/// user's first connection joined or last one left.
+#[async_trait]
+pub trait Backend: Send + Sync {
+    /// Deliver a message to the subscribers of its channel on all instances
+    async fn publish(&self, envelope: Envelope) -> WebSocketResult<()>;
+
+    /// Messages published by any instance
+    fn messages(&self) -> broadcast::Receiver<Envelope>;
+
+    /// Add a connection of `member` to a presence channel, returning
+    /// whether the user wasn't a member before
+    async fn join(&self, channel: &str, member: &Member) -> WebSocketResult<bool>;
+
+    /// Remove a connection of `user_id` from a presence channel, returning
+    /// whether it was the user's last one
+    async fn leave(&self, channel: &str, user_id: &str) -> WebSocketResult<bool>;
+
+    /// Members of a presence channel
+    async fn members(&self, channel: &str) -> WebSocketResult<Vec<Member>>;
+}
+
+/// In-process backend
+///
+/// Suitable for development and single-instance deployments.
+pub struct MemoryBackend {
+    sender: broadcast::Sender<Envelope>,
+    // Channel -> user ID -> member and connection count
+    presence: Mutex<HashMap<String, HashMap<String, (Member, usize)>>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(FANOUT_CAPACITY);
+        Self {
+            sender,
+            presence: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for MemoryBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Backend for MemoryBackend {
+    async fn publish(&self, envelope: Envelope) -> WebSocketResult<()> {
+        // No receivers is ok
+        let _ = self.sender.send(envelope);
+        Ok(())
+    }
+
+    fn messages(&self) -> broadcast::Receiver<Envelope> {
+        self.sender.subscribe()
+    }
+
+    async fn join(&self, channel: &str, member: &Member) -> WebSocketResult<bool> {
+        let mut presence = self.presence.lock().unwrap();
+        let entry = presence
+            .entry(channel.to_string())
+            .or_default()
+            .entry(member.user_id.clone())
+            .or_insert_with(|| (member.clone(), 0));
+        entry.0 = member.clone();
+        entry.1 += 1;
+        Ok(entry.1 == 1)
+    }
+
+    async fn leave(&self, channel: &str, user_id: &str) -> WebSocketResult<bool> {
+        let mut presence = self.presence.lock().unwrap();
+        let Some(members) = presence.get_mut(channel) else {
+            return Ok(false);
+        };
+        let Some((_, count)) = members.get_mut(user_id) else {
+            return Ok(false);
+        };
+
+        *count -= 1;
+        if *count > 0 {
+            return Ok(false);
+        }
+        members.remove(user_id);
+        if members.is_empty() {
+            presence.remove(channel);
+        }
+        Ok(true)
+    }
+
+    async fn members(&self, channel: &str) -> WebSocketResult<Vec<Member>> {
+        let presence = self.presence.lock().unwrap();
+        let mut members: Vec<Member> = presence
+            .get(channel)
+            .map(|members| members.values().map(|(member, _)| member.clone()).collect())
+            .unwrap_or_default();
+        members.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+        Ok(members)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ServerMessage;
+
+    fn member(user_id: &str) -> Member {
+        Member {
+            user_id: user_id.into(),
+            info: serde_json::Value::Null,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_presence_counts_connections() {
+        let backend = MemoryBackend::new();
+
+        assert!(backend.join("presence-chat", &member("1")).await.unwrap());
+        // Second tab of the same user
+        assert!(!backend.join("presence-chat", &member("1")).await.unwrap());
+        assert!(backend.join("presence-chat", &member("2")).await.unwrap());
+        assert_eq!(backend.members("presence-chat").await.unwrap().len(), 2);
+
+        assert!(!backend.leave("presence-chat", "1").await.unwrap());
+        assert!(backend.leave("presence-chat", "1").await.unwrap());
+        assert!(!backend.leave("presence-chat", "1").await.unwrap());
+        assert_eq!(
+            backend.members("presence-chat").await.unwrap(),
+            vec![member("2")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_publish() {
+        let backend = MemoryBackend::new();
+        let mut messages = backend.messages();
+
+        backend
+            .publish(Envelope {
+                channel: "news".into(),
+                message: ServerMessage::Pong,
+                except: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(messages.recv().await.unwrap().channel, "news");
+    }
+}
@@ -0,0 +1,74 @@
+//! Broadcasting events from the application
+
+use crate::{Backend, Channel, Envelope, Member, ServerMessage, WebSocketResult};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Sends events to the subscribers of a channel on all instances
+///
+/// Obtained from [`WebSocketServer::broadcaster`](crate::WebSocketServer::broadcaster);
+/// cheap to clone into handlers and jobs.
+#[derive(Clone)]
+pub struct Broadcaster {
+    backend: Arc<dyn Backend>,
+}
+
+impl Broadcaster {
+    pub(crate) fn new(backend: Arc<dyn Backend>) -> Self {
+        Self { backend }
+    }
+
+    /// Send an event to all subscribers of `channel`
+    pub async fn broadcast<T: Serialize>(
+        &self,
+        channel: &Channel,
+        event: &str,
+        data: &T,
+    ) -> WebSocketResult<()> {
+        self.send(channel, event, data, None).await
+    }
+
+    /// Send an event to all subscribers of `channel` except one connection,
+    /// usually the one whose request triggered the event
+    pub async fn broadcast_except<T: Serialize>(
+        &self,
+        channel: &Channel,
+        event: &str,
+        data: &T,
+        connection_id: &str,
+    ) -> WebSocketResult<()> {
+        self.send(channel, event, data, Some(connection_id.to_string()))
+            .await
+    }
+
+    /// Members of a presence channel
+    pub async fn members(&self, channel: &Channel) -> WebSocketResult<Vec<Member>> {
+        self.backend.members(&channel.full_name()).await
+    }
+
+    async fn send<T: Serialize>(
+        &self,
+        channel: &Channel,
+        event: &str,
+        data: &T,
+        except: Option<String>,
+    ) -> WebSocketResult<()> {
+        let full_name = channel.full_name();
+        let message = ServerMessage::Event {
+            channel: full_name.clone(),
+            event: event.to_string(),
+            data: serde_json::to_value(data)?,
+        };
+
+        self.backend
+            .publish(Envelope {
+                channel: full_name,
+                message,
+                except,
+            })
+            .await?;
+
+        tracing::debug!(channel = %channel, event, "Event broadcasted");
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! Channel types
+
+use crate::{WebSocketError, WebSocketResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const PRIVATE_PREFIX: &str = "private-";
+const PRESENCE_PREFIX: &str = "presence-";
+
+/// Channel a connection subscribes to
+///
+/// On the wire channels are identified by their full name; the prefix
+/// selects the kind: `private-orders.1`, `presence-chat.lobby` and plain
+/// `news` for public channels.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Channel {
+    /// Anyone can subscribe
+    Public(String),
+
+    /// Subscribing requires an authenticated and authorized user
+    Private(String),
+
+    /// Private channel that tracks who's subscribed
+    Presence(String),
+}
+
+impl Channel {
+    /// Create public channel
+    pub fn public(name: impl Into<String>) -> Self {
+        Self::Public(name.into())
+    }
+
+    /// Create private channel
+    pub fn private(name: impl Into<String>) -> Self {
+        Self::Private(name.into())
+    }
+
+    /// Create presence channel
+    pub fn presence(name: impl Into<String>) -> Self {
+        Self::Presence(name.into())
+    }
+
+    /// Parse a full channel name
+    pub fn parse(full_name: &str) -> WebSocketResult<Self> {
+        let channel = if let Some(name) = full_name.strip_prefix(PRIVATE_PREFIX) {
+            Self::private(name)
+        } else if let Some(name) = full_name.strip_prefix(PRESENCE_PREFIX) {
+            Self::presence(name)
+        } else {
+            Self::public(full_name)
+        };
+
+        if channel.name().is_empty() || full_name.chars().any(char::is_whitespace) {
+            return Err(WebSocketError::InvalidChannel(full_name.to_string()));
+        }
+        Ok(channel)
+    }
+
+    /// Channel name without the prefix
+    pub fn name(&self) -> &str {
+        match self {
+            Channel::Public(name) => name,
+            Channel::Private(name) => name,
+            Channel::Presence(name) => name,
+        }
+    }
+
+    /// Channel name with the prefix, as used on the wire
+    pub fn full_name(&self) -> String {
+        self.to_string()
+    }
+
+    /// Check if channel requires authorization
+    pub fn requires_auth(&self) -> bool {
+        !self.is_public()
+    }
+
+    /// Check if channel is public
+    pub fn is_public(&self) -> bool {
+        matches!(self, Channel::Public(_))
+    }
+
+    /// Check if channel is a presence channel
+    pub fn is_presence(&self) -> bool {
+        matches!(self, Channel::Presence(_))
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Channel::Public(name) => write!(f, "{}", name),
+            Channel::Private(name) => write!(f, "{}{}", PRIVATE_PREFIX, name),
+            Channel::Presence(name) => write!(f, "{}{}", PRESENCE_PREFIX, name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_channel() {
+        assert_eq!(Channel::parse("news").unwrap(), Channel::public("news"));
+        assert_eq!(
+            Channel::parse("private-orders.1").unwrap(),
+            Channel::private("orders.1")
+        );
+        assert_eq!(
+            Channel::parse("presence-chat").unwrap(),
+            Channel::presence("chat")
+        );
+        assert!(Channel::parse("").is_err());
+        assert!(Channel::parse("private-").is_err());
+        assert!(Channel::parse("with space").is_err());
+    }
+
+    #[test]
+    fn test_full_name_roundtrip() {
+        for name in ["news", "private-orders.1", "presence-chat"] {
+            assert_eq!(Channel::parse(name).unwrap().full_name(), name);
+        }
+        assert!(Channel::presence("chat").requires_auth());
+        assert!(!Channel::public("news").requires_auth());
+    }
+}
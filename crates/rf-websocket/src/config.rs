@@ -0,0 +1,58 @@
+//! WebSocket server configuration
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Heartbeat, reconnect and limit settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebSocketConfig {
+    /// Interval of the ping frames sent to clients
+    #[serde(with = "humantime_serde")]
+    pub heartbeat_interval: Duration,
+
+    /// Connections the server hears nothing from for this long are closed
+    #[serde(with = "humantime_serde")]
+    pub client_timeout: Duration,
+
+    /// How long the subscriptions of a dropped connection are kept for the
+    /// client to resume them, without presence members leaving and
+    /// rejoining. Zero disables resuming.
+    #[serde(with = "humantime_serde")]
+    pub reconnect_grace: Duration,
+
+    /// Maximum channels per connection
+    pub max_channels: usize,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(25),
+            client_timeout: Duration::from_secs(60),
+            reconnect_grace: Duration::from_secs(10),
+            max_channels: 100,
+        }
+    }
+}
+
+impl WebSocketConfig {
+    /// Set the ping interval and the timeout of silent clients
+    pub fn heartbeat(mut self, interval: Duration, client_timeout: Duration) -> Self {
+        self.heartbeat_interval = interval;
+        self.client_timeout = client_timeout;
+        self
+    }
+
+    /// Set how long dropped connections can be resumed
+    pub fn reconnect_grace(mut self, grace: Duration) -> Self {
+        self.reconnect_grace = grace;
+        self
+    }
+
+    /// Set the maximum channels per connection
+    pub fn max_channels(mut self, max_channels: usize) -> Self {
+        self.max_channels = max_channels;
+        self
+    }
+}
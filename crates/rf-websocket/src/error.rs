@@ -0,0 +1,37 @@
+//! Error types for WebSocket channels
+
+use thiserror::Error;
+
+/// WebSocket error types
+#[derive(Debug, Error)]
+pub enum WebSocketError {
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Invalid channel: {0}")]
+    InvalidChannel(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Backend error: {0}")]
+    Backend(String),
+}
+
+#[cfg(feature = "rf-error")]
+rf_error::framework_error!(WebSocketError, {
+    Self::InvalidChannel(_) => (BAD_REQUEST, "websocket.invalid_channel"),
+    Self::Unauthorized(_) => (FORBIDDEN, "websocket.forbidden"),
+    Self::Serialization(_) => (INTERNAL_SERVER_ERROR, "websocket.serialization"),
+    Self::Backend(_) => (INTERNAL_SERVER_ERROR, "websocket.backend"),
+});
+
+#[cfg(feature = "redis-backend")]
+impl From<redis::RedisError> for WebSocketError {
+    fn from(e: redis::RedisError) -> Self {
+        WebSocketError::Backend(e.to_string())
+    }
+}
+
+/// WebSocket result type
+pub type WebSocketResult<T> = Result<T, WebSocketError>;
@@ -0,0 +1,86 @@
+//! WebSocket channels for RustForge
+//!
+//! Clients connect over WebSocket and subscribe to channels; the application
+//! broadcasts events to them from handlers, jobs or listeners.
+//!
+//! # Features
+//!
+//! - Public, private and presence channels
+//! - Join authorization callbacks by channel pattern
+//! - Presence members shared across connections and instances
+//! - Fan-out across instances via Redis Pub/Sub (`redis-backend` feature)
+//! - Axum upgrade handler with token authentication
+//! - Heartbeats, client timeouts and resuming dropped connections
+//! - Model change broadcasting, driven by rf-events model events (`events` feature)
+//!
+//! # Quick Start
+//!
+//! ```no_run
+//! use rf_websocket::{websocket_router, Channel, Channels, UserId, WebSocketServer};
+//! use serde_json::json;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let server = WebSocketServer::default()
+//!     .authenticator(|token: &str| {
+//!         // Resolve a session or API token to a user ID
+//!         Ok::<_, String>(token.to_string())
+//!     })
+//!     .channels(
+//!         Channels::new()
+//!             .channel("orders.*", |user_id: &UserId, channel: &Channel| {
+//!                 (channel.name() == format!("orders.{}", user_id)).then(|| json!({}))
+//!             })
+//!             .channel("chat", |user_id: &UserId, _: &Channel| {
+//!                 Some(json!({ "id": user_id }))
+//!             }),
+//!     );
+//!
+//! let broadcaster = server.broadcaster();
+//! let app = axum::Router::new().merge(websocket_router(server));
+//!
+//! // Later, e.g. in a handler
+//! broadcaster
+//!     .broadcast(&Channel::private("orders.1"), "order.shipped", &json!({"id": 1}))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Protocol
+//!
+//! Clients connect to `/ws?token=...` and exchange JSON messages tagged with
+//! `type`, see [`ClientMessage`] and [`ServerMessage`]. The server pings every
+//! `heartbeat_interval` and closes connections silent for `client_timeout`.
+//! A client that lost its connection reconnects with
+//! `/ws?token=...&resume=<connection_id>` within `reconnect_grace` to get its
+//! subscriptions back without presence members leaving and rejoining.
+
+mod auth;
+mod backend;
+mod broadcaster;
+mod channel;
+mod config;
+mod error;
+mod model;
+mod protocol;
+mod server;
+mod websocket;
+
+#[cfg(feature = "redis-backend")]
+mod redis;
+
+pub use auth::{Authenticator, ChannelAuthorizer, Channels};
+pub use backend::{Backend, MemoryBackend};
+pub use broadcaster::Broadcaster;
+pub use channel::Channel;
+pub use config::WebSocketConfig;
+pub use error::{WebSocketError, WebSocketResult};
+#[cfg(feature = "events")]
+pub use model::BroadcastChangesExt;
+pub use model::BroadcastsChanges;
+pub use protocol::{ClientMessage, ConnectionId, Envelope, Member, ServerMessage, UserId};
+pub use server::WebSocketServer;
+pub use websocket::{websocket_router, ws_handler, ConnectParams};
+
+#[cfg(feature = "redis-backend")]
+pub use redis::RedisBackend;
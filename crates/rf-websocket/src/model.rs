@@ -0,0 +1,305 @@
+//! Broadcasting model changes
+
+use crate::{Broadcaster, Channel, Channels, UserId, WebSocketResult};
+use serde::Serialize;
+
+/// Model whose changes are broadcast to WebSocket clients
+///
+/// Created, updated and deleted models are sent as `{name}.created`,
+/// `{name}.updated` and `{name}.deleted` events on the private channels
+/// `{name}` and `{name}.{key}`, so an admin list can follow all posts while
+/// a detail page follows one. Register the channels with
+/// [`Channels::model`] so users can join them.
+///
+/// ```
+/// use rf_websocket::{BroadcastsChanges, Channel, UserId};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Post {
+///     id: u64,
+///     title: String,
+/// }
+///
+/// impl BroadcastsChanges for Post {
+///     fn broadcast_name() -> &'static str {
+///         "posts"
+///     }
+///
+///     fn broadcast_key(&self) -> String {
+///         self.id.to_string()
+///     }
+///
+///     fn authorize(user_id: &UserId, _channel: &Channel) -> bool {
+///         user_id.starts_with("admin:")
+///     }
+/// }
+/// ```
+pub trait BroadcastsChanges: Serialize + Send + Sync + 'static {
+    /// Channel and event name prefix, e.g. `posts`
+    fn broadcast_name() -> &'static str;
+
+    /// Key of the model's own channel, usually its ID
+    fn broadcast_key(&self) -> String;
+
+    /// Channels the changes are sent on
+    fn broadcast_on(&self) -> Vec<Channel> {
+        let name = Self::broadcast_name();
+        vec![
+            Channel::private(name),
+            Channel::private(format!("{}.{}", name, self.broadcast_key())),
+        ]
+    }
+
+    /// Event payload, the serialized model unless overridden, e.g. to
+    /// leave out fields clients shouldn't see
+    fn broadcast_with(&self) -> serde_json::Result<serde_json::Value> {
+        serde_json::to_value(self)
+    }
+
+    /// Whether `user_id` may follow changes on `channel`; nobody by default
+    fn authorize(_user_id: &UserId, _channel: &Channel) -> bool {
+        false
+    }
+}
+
+impl Channels {
+    /// Let users join the channels of `M` that [`BroadcastsChanges::authorize`]
+    /// allows
+    pub fn model<M: BroadcastsChanges>(self) -> Self {
+        let authorize = |user_id: &UserId, channel: &Channel| {
+            M::authorize(user_id, channel).then(|| serde_json::json!({}))
+        };
+        let name = M::broadcast_name();
+        self.channel(name, authorize)
+            .channel(&format!("{}.*", name), authorize)
+    }
+}
+
+impl Broadcaster {
+    /// Send a change of `model`, e.g. `created`, on its channels
+    pub async fn broadcast_change<M: BroadcastsChanges>(
+        &self,
+        model: &M,
+        change: &str,
+    ) -> WebSocketResult<()> {
+        let event = format!("{}.{}", M::broadcast_name(), change);
+        let data = model.broadcast_with()?;
+        for channel in model.broadcast_on() {
+            self.broadcast(&channel, &event, &data).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "events")]
+pub use events::BroadcastChangesExt;
+
+#[cfg(feature = "events")]
+mod events {
+    use super::BroadcastsChanges;
+    use crate::Broadcaster;
+    use async_trait::async_trait;
+    use rf_events::{
+        EventDispatcher, EventError, EventListenerFor, EventResult, ModelCreated, ModelDeleted,
+        ModelUpdated,
+    };
+    use std::marker::PhantomData;
+
+    /// Broadcasts rf-events model events of models implementing
+    /// [`BroadcastsChanges`]
+    ///
+    /// ```
+    /// # use rf_websocket::{BroadcastsChanges, UserId, Channel};
+    /// # #[derive(serde::Serialize)]
+    /// # struct Post { id: u64 }
+    /// # impl BroadcastsChanges for Post {
+    /// #     fn broadcast_name() -> &'static str { "posts" }
+    /// #     fn broadcast_key(&self) -> String { self.id.to_string() }
+    /// # }
+    /// use rf_events::{EventDispatcher, ModelCreated};
+    /// use rf_websocket::{BroadcastChangesExt, Channels, WebSocketServer};
+    ///
+    /// # async fn example() -> rf_events::EventResult<()> {
+    /// let server = WebSocketServer::default().channels(Channels::new().model::<Post>());
+    /// let dispatcher = EventDispatcher::new();
+    /// dispatcher.broadcast_changes::<Post>(server.broadcaster()).await;
+    ///
+    /// // After saving the post
+    /// dispatcher.dispatch(ModelCreated::new(Post { id: 1 })).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[async_trait]
+    pub trait BroadcastChangesExt {
+        /// Broadcast models of `M` when created, updated or deleted
+        async fn broadcast_changes<M: BroadcastsChanges>(&self, broadcaster: Broadcaster);
+    }
+
+    #[async_trait]
+    impl BroadcastChangesExt for EventDispatcher {
+        async fn broadcast_changes<M: BroadcastsChanges>(&self, broadcaster: Broadcaster) {
+            self.listen::<ModelCreated<M>, _>(BroadcastChange::<M>::new(broadcaster.clone()))
+                .await;
+            self.listen::<ModelUpdated<M>, _>(BroadcastChange::<M>::new(broadcaster.clone()))
+                .await;
+            self.listen::<ModelDeleted<M>, _>(BroadcastChange::<M>::new(broadcaster))
+                .await;
+        }
+    }
+
+    struct BroadcastChange<M> {
+        broadcaster: Broadcaster,
+        _model: PhantomData<fn() -> M>,
+    }
+
+    impl<M: BroadcastsChanges> BroadcastChange<M> {
+        fn new(broadcaster: Broadcaster) -> Self {
+            Self {
+                broadcaster,
+                _model: PhantomData,
+            }
+        }
+
+        async fn send(&self, model: &M, change: &str) -> EventResult<()> {
+            self.broadcaster
+                .broadcast_change(model, change)
+                .await
+                .map_err(|e| {
+                    EventError::ListenerError(format!("Broadcasting model change failed: {}", e))
+                })
+        }
+    }
+
+    #[async_trait]
+    impl<M: BroadcastsChanges> EventListenerFor<ModelCreated<M>> for BroadcastChange<M> {
+        async fn handle(&self, event: &ModelCreated<M>) -> EventResult<()> {
+            self.send(&event.model, "created").await
+        }
+    }
+
+    #[async_trait]
+    impl<M: BroadcastsChanges> EventListenerFor<ModelUpdated<M>> for BroadcastChange<M> {
+        async fn handle(&self, event: &ModelUpdated<M>) -> EventResult<()> {
+            self.send(&event.model, "updated").await
+        }
+    }
+
+    #[async_trait]
+    impl<M: BroadcastsChanges> EventListenerFor<ModelDeleted<M>> for BroadcastChange<M> {
+        async fn handle(&self, event: &ModelDeleted<M>) -> EventResult<()> {
+            self.send(&event.model, "deleted").await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Backend, MemoryBackend, ServerMessage};
+    use std::sync::Arc;
+
+    #[derive(Serialize)]
+    struct Post {
+        id: u64,
+        title: &'static str,
+    }
+
+    impl BroadcastsChanges for Post {
+        fn broadcast_name() -> &'static str {
+            "posts"
+        }
+
+        fn broadcast_key(&self) -> String {
+            self.id.to_string()
+        }
+
+        fn authorize(user_id: &UserId, channel: &Channel) -> bool {
+            user_id == "admin" || channel.name() == format!("posts.{}", user_id)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_change() {
+        let backend = Arc::new(MemoryBackend::new());
+        let mut messages = backend.messages();
+        let broadcaster = Broadcaster::new(backend);
+
+        let post = Post {
+            id: 7,
+            title: "Hello",
+        };
+        broadcaster
+            .broadcast_change(&post, "updated")
+            .await
+            .unwrap();
+
+        for channel in ["private-posts", "private-posts.7"] {
+            let envelope = messages.recv().await.unwrap();
+            assert_eq!(envelope.channel, channel);
+            let ServerMessage::Event { event, data, .. } = envelope.message else {
+                panic!("expected an event");
+            };
+            assert_eq!(event, "posts.updated");
+            assert_eq!(data, serde_json::json!({ "id": 7, "title": "Hello" }));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_channels_authorization() {
+        let channels = Channels::new().model::<Post>();
+        let allowed = |user: &str, channel: &str| {
+            let channels = channels.clone();
+            let (user, channel) = (user.to_string(), Channel::private(channel));
+            async move { channels.authorize(&user, &channel).await.is_some() }
+        };
+
+        assert!(allowed("admin", "posts").await);
+        assert!(allowed("7", "posts.7").await);
+        assert!(!allowed("7", "posts").await);
+        assert!(!allowed("7", "posts.8").await);
+        assert!(!allowed("admin", "comments").await);
+    }
+
+    #[cfg(feature = "events")]
+    #[tokio::test]
+    async fn test_model_events_are_broadcast() {
+        use rf_events::{EventDispatcher, ModelCreated, ModelDeleted};
+
+        let backend = Arc::new(MemoryBackend::new());
+        let mut messages = backend.messages();
+        let dispatcher = EventDispatcher::new();
+        dispatcher
+            .broadcast_changes::<Post>(Broadcaster::new(backend))
+            .await;
+
+        let post = || Post {
+            id: 1,
+            title: "Hello",
+        };
+        dispatcher
+            .dispatch(ModelCreated::new(post()))
+            .await
+            .unwrap();
+        dispatcher
+            .dispatch(ModelDeleted::new(post()))
+            .await
+            .unwrap();
+
+        let mut events = vec![];
+        while let Ok(envelope) = messages.try_recv() {
+            if let ServerMessage::Event { event, .. } = envelope.message {
+                events.push(format!("{} {}", envelope.channel, event));
+            }
+        }
+        assert_eq!(
+            events,
+            vec![
+                "private-posts posts.created",
+                "private-posts.1 posts.created",
+                "private-posts posts.deleted",
+                "private-posts.1 posts.deleted",
+            ]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Messages exchanged with clients and between instances
+//!
+//! Messages are JSON objects tagged with `type`:
+//!
+//! ```text
+//! -> {"type":"subscribe","channel":"presence-chat"}
+//! <- {"type":"subscribed","channel":"presence-chat","members":[{"user_id":"1","info":{"name":"Ann"}}]}
+//! <- {"type":"event","channel":"presence-chat","event":"message.sent","data":{"text":"Hi"}}
+//! -> {"type":"ping"}
+//! <- {"type":"pong"}
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// Connection ID type
+pub type ConnectionId = String;
+
+/// User ID type
+pub type UserId = String;
+
+/// Member of a presence channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Member {
+    pub user_id: UserId,
+
+    /// Returned by the channel's authorizer, e.g. the user's name
+    #[serde(default)]
+    pub info: serde_json::Value,
+}
+
+/// Message from a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe {
+        channel: String,
+    },
+
+    Unsubscribe {
+        channel: String,
+    },
+
+    /// Event sent to the other subscribers of a private or presence
+    /// channel, e.g. typing indicators. The event name is prefixed with
+    /// `client-`.
+    Whisper {
+        channel: String,
+        event: String,
+        #[serde(default)]
+        data: serde_json::Value,
+    },
+
+    /// Application-level heartbeat for clients that can't send ping frames
+    Ping,
+}
+
+/// Message to a client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// First message of a connection
+    Connected {
+        connection_id: ConnectionId,
+        /// Whether a previous connection was resumed
+        resumed: bool,
+        /// Channels restored from the previous connection
+        channels: Vec<String>,
+        /// Seconds between server pings; clients should reconnect when
+        /// they hear nothing for about twice as long
+        heartbeat_interval: u64,
+    },
+
+    Subscribed {
+        channel: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        members: Option<Vec<Member>>,
+    },
+
+    SubscriptionError {
+        channel: String,
+        message: String,
+    },
+
+    Unsubscribed {
+        channel: String,
+    },
+
+    Event {
+        channel: String,
+        event: String,
+        data: serde_json::Value,
+    },
+
+    MemberAdded {
+        channel: String,
+        member: Member,
+    },
+
+    MemberRemoved {
+        channel: String,
+        user_id: UserId,
+    },
+
+    Pong,
+
+    Error {
+        message: String,
+    },
+}
+
+/// Message fanned out to all instances, delivered to the local subscribers
+/// of its channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    /// Full channel name
+    pub channel: String,
+
+    pub message: ServerMessage,
+
+    /// Connection that doesn't receive the message, usually its sender
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub except: Option<ConnectionId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_client_message_deserialization() {
+        let msg: ClientMessage =
+            serde_json::from_str(r#"{"type":"subscribe","channel":"news"}"#).unwrap();
+        assert!(matches!(msg, ClientMessage::Subscribe { channel } if channel == "news"));
+
+        let msg: ClientMessage = serde_json::from_str(r#"{"type":"ping"}"#).unwrap();
+        assert!(matches!(msg, ClientMessage::Ping));
+    }
+
+    #[test]
+    fn test_server_message_serialization() {
+        let msg = ServerMessage::Subscribed {
+            channel: "news".into(),
+            members: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&msg).unwrap(),
+            json!({"type": "subscribed", "channel": "news"})
+        );
+
+        let msg = ServerMessage::MemberRemoved {
+            channel: "presence-chat".into(),
+            user_id: "1".into(),
+        };
+        assert_eq!(
+            serde_json::to_value(&msg).unwrap(),
+            json!({"type": "member_removed", "channel": "presence-chat", "user_id": "1"})
+        );
+    }
+}
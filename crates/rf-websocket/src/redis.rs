@@ -0,0 +1,180 @@
+//! Redis-backed fan-out for multi-instance deployments
+
+use crate::backend::FANOUT_CAPACITY;
+use crate::{Backend, Envelope, Member, WebSocketResult};
+use async_trait::async_trait;
+use futures::StreamExt;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+const LEAVE_SCRIPT: &str = r#"
+local count = redis.call('HINCRBY', KEYS[1], ARGV[1], -1)
+if count <= 0 then
+    redis.call('HDEL', KEYS[1], ARGV[1])
+    redis.call('HDEL', KEYS[2], ARGV[1])
+end
+if count == 0 then
+    return 1
+end
+return 0
+"#;
+
+/// Redis-backed backend
+///
+/// Messages are published to a Redis Pub/Sub channel that every instance
+/// listens on; the listener reconnects when the connection drops. Presence
+/// is stored in Redis hashes, so members are shared across instances.
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_websocket::{RedisBackend, WebSocketServer};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let server = WebSocketServer::new(RedisBackend::connect("redis://localhost").await?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct RedisBackend {
+    conn: ConnectionManager,
+    sender: broadcast::Sender<Envelope>,
+    prefix: String,
+    listener: JoinHandle<()>,
+}
+
+impl RedisBackend {
+    /// Connect with the default key prefix `rf-websocket`
+    pub async fn connect(redis_url: &str) -> WebSocketResult<Self> {
+        Self::with_prefix(redis_url, "rf-websocket").await
+    }
+
+    /// Connect with a key prefix, separating applications sharing a Redis
+    pub async fn with_prefix(redis_url: &str, prefix: &str) -> WebSocketResult<Self> {
+        let client = redis::Client::open(redis_url)?;
+        let conn = client.get_connection_manager().await?;
+        let (sender, _) = broadcast::channel(FANOUT_CAPACITY);
+
+        let events_key = format!("{}:events", prefix);
+        let listener = tokio::spawn(listen(client, events_key, sender.clone()));
+
+        Ok(Self {
+            conn,
+            sender,
+            prefix: prefix.to_string(),
+            listener,
+        })
+    }
+
+    fn events_key(&self) -> String {
+        format!("{}:events", self.prefix)
+    }
+
+    fn count_key(&self, channel: &str) -> String {
+        format!("{}:presence:{}:connections", self.prefix, channel)
+    }
+
+    fn members_key(&self, channel: &str) -> String {
+        format!("{}:presence:{}:members", self.prefix, channel)
+    }
+}
+
+impl Drop for RedisBackend {
+    fn drop(&mut self) {
+        self.listener.abort();
+    }
+}
+
+/// Forward messages from Redis to the local connections, reconnecting
+/// after errors
+async fn listen(client: redis::Client, events_key: String, sender: broadcast::Sender<Envelope>) {
+    loop {
+        match client.get_async_connection().await {
+            Ok(conn) => {
+                let mut pubsub = conn.into_pubsub();
+                if let Err(e) = pubsub.subscribe(&events_key).await {
+                    tracing::warn!(error = %e, "Failed to subscribe to Redis channel");
+                } else {
+                    let mut messages = pubsub.on_message();
+                    while let Some(msg) = messages.next().await {
+                        let payload: String = match msg.get_payload() {
+                            Ok(payload) => payload,
+                            Err(e) => {
+                                tracing::warn!(error = %e, "Invalid Redis message");
+                                continue;
+                            }
+                        };
+                        match serde_json::from_str::<Envelope>(&payload) {
+                            Ok(envelope) => {
+                                let _ = sender.send(envelope);
+                            }
+                            Err(e) => tracing::warn!(error = %e, "Invalid WebSocket envelope"),
+                        }
+                    }
+                    tracing::warn!("Redis Pub/Sub connection closed, reconnecting");
+                }
+            }
+            Err(e) => tracing::warn!(error = %e, "Failed to connect to Redis, retrying"),
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+#[async_trait]
+impl Backend for RedisBackend {
+    async fn publish(&self, envelope: Envelope) -> WebSocketResult<()> {
+        let payload = serde_json::to_string(&envelope)?;
+        let _: () = self
+            .conn
+            .clone()
+            .publish(self.events_key(), payload)
+            .await?;
+        Ok(())
+    }
+
+    fn messages(&self) -> broadcast::Receiver<Envelope> {
+        self.sender.subscribe()
+    }
+
+    async fn join(&self, channel: &str, member: &Member) -> WebSocketResult<bool> {
+        let info = serde_json::to_string(&member.info)?;
+        let (count,): (i64,) = redis::pipe()
+            .atomic()
+            .hincr(self.count_key(channel), &member.user_id, 1)
+            .hset(self.members_key(channel), &member.user_id, info)
+            .ignore()
+            .query_async(&mut self.conn.clone())
+            .await?;
+        Ok(count == 1)
+    }
+
+    async fn leave(&self, channel: &str, user_id: &str) -> WebSocketResult<bool> {
+        let left: i64 = redis::Script::new(LEAVE_SCRIPT)
+            .key(self.count_key(channel))
+            .key(self.members_key(channel))
+            .arg(user_id)
+            .invoke_async(&mut self.conn.clone())
+            .await?;
+        Ok(left == 1)
+    }
+
+    async fn members(&self, channel: &str) -> WebSocketResult<Vec<Member>> {
+        let stored: HashMap<String, String> =
+            self.conn.clone().hgetall(self.members_key(channel)).await?;
+
+        let mut members = stored
+            .into_iter()
+            .map(|(user_id, info)| {
+                Ok(Member {
+                    user_id,
+                    info: serde_json::from_str(&info)?,
+                })
+            })
+            .collect::<WebSocketResult<Vec<_>>>()?;
+        members.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+        Ok(members)
+    }
+}
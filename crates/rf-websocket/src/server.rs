@@ -0,0 +1,607 @@
+//! Server state and the channel logic of connections
+
+use crate::{
+    Authenticator, Backend, Broadcaster, Channel, Channels, ClientMessage, ConnectionId, Envelope,
+    Member, MemoryBackend, ServerMessage, UserId, WebSocketConfig,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Subscriptions of a dropped connection, kept for the client to resume
+struct Suspended {
+    user_id: Option<UserId>,
+    channels: HashMap<String, Channel>,
+    token: uuid::Uuid,
+}
+
+/// WebSocket server: backend, authentication, channel authorization and
+/// configuration, shared by all connections
+///
+/// Cheap to clone; serve it with [`websocket_router`](crate::websocket_router).
+#[derive(Clone)]
+pub struct WebSocketServer {
+    pub(crate) backend: Arc<dyn Backend>,
+    authenticator: Option<Arc<dyn Authenticator>>,
+    channels: Channels,
+    pub(crate) config: WebSocketConfig,
+    suspended: Arc<Mutex<HashMap<ConnectionId, Suspended>>>,
+    connections: Arc<AtomicUsize>,
+}
+
+impl Default for WebSocketServer {
+    fn default() -> Self {
+        Self::new(MemoryBackend::new())
+    }
+}
+
+impl WebSocketServer {
+    /// Create server on `backend`
+    pub fn new(backend: impl Backend + 'static) -> Self {
+        Self {
+            backend: Arc::new(backend),
+            authenticator: None,
+            channels: Channels::new(),
+            config: WebSocketConfig::default(),
+            suspended: Arc::new(Mutex::new(HashMap::new())),
+            connections: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Authenticate connections with `authenticator`
+    pub fn authenticator(mut self, authenticator: impl Authenticator + 'static) -> Self {
+        self.authenticator = Some(Arc::new(authenticator));
+        self
+    }
+
+    /// Authorize joins of private and presence channels with `channels`
+    pub fn channels(mut self, channels: Channels) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    /// Set the configuration
+    pub fn config(mut self, config: WebSocketConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Broadcaster sending events to this server's channels
+    pub fn broadcaster(&self) -> Broadcaster {
+        Broadcaster::new(self.backend.clone())
+    }
+
+    /// Number of open connections on this instance
+    pub fn connection_count(&self) -> usize {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    /// Resolve the token of a connecting client; no token means a guest
+    pub(crate) async fn authenticate(&self, token: Option<&str>) -> Result<Option<UserId>, String> {
+        match (token, &self.authenticator) {
+            (Some(token), Some(authenticator)) => authenticator.authenticate(token).await.map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Open a connection, resuming the suspended connection `resume` of the
+    /// same user if it's still within the reconnect grace
+    pub(crate) fn connect(
+        &self,
+        user_id: Option<UserId>,
+        resume: Option<&str>,
+    ) -> (Connection, ServerMessage) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+
+        let resumed = resume.and_then(|id| {
+            let mut suspended = self.suspended.lock().unwrap();
+            match suspended.get(id) {
+                Some(session) if session.user_id == user_id => suspended
+                    .remove(id)
+                    .map(|session| (id.to_string(), session.channels)),
+                _ => None,
+            }
+        });
+
+        let was_resumed = resumed.is_some();
+        let (id, channels) =
+            resumed.unwrap_or_else(|| (uuid::Uuid::new_v4().to_string(), HashMap::new()));
+
+        let mut channel_names: Vec<String> = channels.keys().cloned().collect();
+        channel_names.sort();
+        let connected = ServerMessage::Connected {
+            connection_id: id.clone(),
+            resumed: was_resumed,
+            channels: channel_names,
+            heartbeat_interval: self.config.heartbeat_interval.as_secs(),
+        };
+
+        tracing::debug!(connection_id = %id, user_id = ?user_id, resumed = was_resumed, "WebSocket connected");
+
+        let connection = Connection {
+            id,
+            user_id,
+            channels,
+            server: self.clone(),
+        };
+        (connection, connected)
+    }
+
+    /// Remove a connection from a channel, announcing presence members that
+    /// left
+    async fn leave(&self, user_id: Option<&UserId>, full_name: &str, channel: &Channel) {
+        let Some(user_id) = user_id.filter(|_| channel.is_presence()) else {
+            return;
+        };
+
+        match self.backend.leave(full_name, user_id).await {
+            Ok(true) => {
+                self.publish(
+                    full_name,
+                    ServerMessage::MemberRemoved {
+                        channel: full_name.to_string(),
+                        user_id: user_id.clone(),
+                    },
+                    None,
+                )
+                .await;
+            }
+            Ok(false) => {}
+            Err(e) => tracing::warn!(channel = %full_name, error = %e, "Failed to leave presence"),
+        }
+    }
+
+    async fn publish(&self, channel: &str, message: ServerMessage, except: Option<ConnectionId>) {
+        let envelope = Envelope {
+            channel: channel.to_string(),
+            message,
+            except,
+        };
+        if let Err(e) = self.backend.publish(envelope).await {
+            tracing::warn!(channel, error = %e, "Failed to publish WebSocket message");
+        }
+    }
+}
+
+/// Channel state of a single connection
+pub(crate) struct Connection {
+    pub(crate) id: ConnectionId,
+    user_id: Option<UserId>,
+    // Full channel name -> channel
+    channels: HashMap<String, Channel>,
+    server: WebSocketServer,
+}
+
+impl Connection {
+    /// Handle a client message, returning the reply
+    pub(crate) async fn handle(&mut self, message: ClientMessage) -> Option<ServerMessage> {
+        match message {
+            ClientMessage::Subscribe { channel } => Some(self.subscribe(channel).await),
+            ClientMessage::Unsubscribe { channel } => Some(self.unsubscribe(channel).await),
+            ClientMessage::Whisper {
+                channel,
+                event,
+                data,
+            } => self.whisper(channel, event, data).await,
+            ClientMessage::Ping => Some(ServerMessage::Pong),
+        }
+    }
+
+    /// Message of `envelope` if this connection should receive it
+    pub(crate) fn deliver<'a>(&self, envelope: &'a Envelope) -> Option<&'a ServerMessage> {
+        let subscribed = self.channels.contains_key(&envelope.channel);
+        let excluded = envelope.except.as_deref() == Some(self.id.as_str());
+        (subscribed && !excluded).then_some(&envelope.message)
+    }
+
+    /// Close the connection. Dropped connections, as opposed to ones the
+    /// client closed, keep their subscriptions for the reconnect grace.
+    pub(crate) async fn close(self, dropped: bool) {
+        let server = self.server.clone();
+        server.connections.fetch_sub(1, Ordering::Relaxed);
+
+        let grace = server.config.reconnect_grace;
+        if !dropped || grace.is_zero() || self.channels.is_empty() {
+            for (full_name, channel) in &self.channels {
+                server
+                    .leave(self.user_id.as_ref(), full_name, channel)
+                    .await;
+            }
+            tracing::debug!(connection_id = %self.id, "WebSocket closed");
+            return;
+        }
+
+        let token = uuid::Uuid::new_v4();
+        server.suspended.lock().unwrap().insert(
+            self.id.clone(),
+            Suspended {
+                user_id: self.user_id,
+                channels: self.channels,
+                token,
+            },
+        );
+        tracing::debug!(connection_id = %self.id, "WebSocket dropped, awaiting reconnect");
+
+        let id = self.id;
+        tokio::spawn(async move {
+            tokio::time::sleep(grace).await;
+
+            let expired = {
+                let mut suspended = server.suspended.lock().unwrap();
+                match suspended.get(&id) {
+                    Some(session) if session.token == token => suspended.remove(&id),
+                    _ => None,
+                }
+            };
+            if let Some(session) = expired {
+                for (full_name, channel) in &session.channels {
+                    server
+                        .leave(session.user_id.as_ref(), full_name, channel)
+                        .await;
+                }
+                tracing::debug!(connection_id = %id, "WebSocket reconnect grace expired");
+            }
+        });
+    }
+
+    async fn subscribe(&mut self, full_name: String) -> ServerMessage {
+        let error = |message: &str| ServerMessage::SubscriptionError {
+            channel: full_name.clone(),
+            message: message.to_string(),
+        };
+
+        let channel = match Channel::parse(&full_name) {
+            Ok(channel) => channel,
+            Err(e) => return error(&e.to_string()),
+        };
+        let backend = &self.server.backend;
+
+        if !self.channels.contains_key(&full_name) {
+            if self.channels.len() >= self.server.config.max_channels {
+                return error("Too many channels");
+            }
+
+            let info = if channel.requires_auth() {
+                let Some(user_id) = &self.user_id else {
+                    return error("Unauthenticated");
+                };
+                match self.server.channels.authorize(user_id, &channel).await {
+                    Some(info) => info,
+                    None => return error("Unauthorized"),
+                }
+            } else {
+                serde_json::Value::Null
+            };
+
+            if let (true, Some(user_id)) = (channel.is_presence(), &self.user_id) {
+                let member = Member {
+                    user_id: user_id.clone(),
+                    info,
+                };
+                match backend.join(&full_name, &member).await {
+                    Ok(true) => {
+                        let added = ServerMessage::MemberAdded {
+                            channel: full_name.clone(),
+                            member,
+                        };
+                        self.server
+                            .publish(&full_name, added, Some(self.id.clone()))
+                            .await;
+                    }
+                    Ok(false) => {}
+                    Err(e) => return error(&e.to_string()),
+                }
+            }
+
+            tracing::debug!(connection_id = %self.id, channel = %full_name, "Subscribed");
+            self.channels.insert(full_name.clone(), channel.clone());
+        }
+
+        let members = if channel.is_presence() {
+            match backend.members(&full_name).await {
+                Ok(members) => Some(members),
+                Err(e) => return error(&e.to_string()),
+            }
+        } else {
+            None
+        };
+
+        ServerMessage::Subscribed {
+            channel: full_name,
+            members,
+        }
+    }
+
+    async fn unsubscribe(&mut self, full_name: String) -> ServerMessage {
+        if let Some(channel) = self.channels.remove(&full_name) {
+            self.server
+                .leave(self.user_id.as_ref(), &full_name, &channel)
+                .await;
+            tracing::debug!(connection_id = %self.id, channel = %full_name, "Unsubscribed");
+        }
+        ServerMessage::Unsubscribed { channel: full_name }
+    }
+
+    async fn whisper(
+        &self,
+        full_name: String,
+        event: String,
+        data: serde_json::Value,
+    ) -> Option<ServerMessage> {
+        match self.channels.get(&full_name) {
+            Some(channel) if channel.requires_auth() => {}
+            _ => {
+                return Some(ServerMessage::Error {
+                    message: format!(
+                        "Client events require a subscribed private or presence channel: {}",
+                        full_name
+                    ),
+                })
+            }
+        }
+
+        let event = if event.starts_with("client-") {
+            event
+        } else {
+            format!("client-{}", event)
+        };
+        let message = ServerMessage::Event {
+            channel: full_name.clone(),
+            event,
+            data,
+        };
+        self.server
+            .publish(&full_name, message, Some(self.id.clone()))
+            .await;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::sync::broadcast;
+
+    fn server() -> WebSocketServer {
+        WebSocketServer::default()
+            .channels(
+                Channels::new()
+                    .channel("orders.*", |user_id: &UserId, channel: &Channel| {
+                        (channel.name() == format!("orders.{}", user_id)).then(|| json!({}))
+                    })
+                    .channel("chat", |user_id: &UserId, _: &Channel| {
+                        Some(json!({ "name": format!("user {}", user_id) }))
+                    }),
+            )
+            .config(WebSocketConfig::default().reconnect_grace(Duration::from_secs(5)))
+    }
+
+    fn subscribe(channel: &str) -> ClientMessage {
+        ClientMessage::Subscribe {
+            channel: channel.into(),
+        }
+    }
+
+    /// Envelopes published so far
+    fn drain(messages: &mut broadcast::Receiver<Envelope>) -> Vec<Envelope> {
+        std::iter::from_fn(|| messages.try_recv().ok()).collect()
+    }
+
+    /// Messages of `envelopes` the connection receives
+    fn received(envelopes: &[Envelope], conn: &Connection) -> Vec<ServerMessage> {
+        envelopes
+            .iter()
+            .filter_map(|envelope| conn.deliver(envelope).cloned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_public_channel_and_broadcast() {
+        let server = server();
+        let mut messages = server.backend.messages();
+        let (mut conn, connected) = server.connect(None, None);
+        assert!(matches!(
+            connected,
+            ServerMessage::Connected { resumed: false, .. }
+        ));
+
+        let reply = conn.handle(subscribe("news")).await.unwrap();
+        assert_eq!(
+            reply,
+            ServerMessage::Subscribed {
+                channel: "news".into(),
+                members: None
+            }
+        );
+
+        let broadcaster = server.broadcaster();
+        broadcaster
+            .broadcast(
+                &Channel::public("news"),
+                "article.published",
+                &json!({"id": 1}),
+            )
+            .await
+            .unwrap();
+        broadcaster
+            .broadcast(&Channel::public("other"), "ignored", &json!({}))
+            .await
+            .unwrap();
+        broadcaster
+            .broadcast_except(&Channel::public("news"), "own", &json!({}), &conn.id)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            received(&drain(&mut messages), &conn),
+            vec![ServerMessage::Event {
+                channel: "news".into(),
+                event: "article.published".into(),
+                data: json!({"id": 1}),
+            }]
+        );
+        assert_eq!(
+            conn.handle(ClientMessage::Ping).await,
+            Some(ServerMessage::Pong)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_private_channel_authorization() {
+        let server = server();
+
+        let (mut guest, _) = server.connect(None, None);
+        assert!(matches!(
+            guest.handle(subscribe("private-orders.1")).await,
+            Some(ServerMessage::SubscriptionError { message, .. }) if message == "Unauthenticated"
+        ));
+
+        let (mut user, _) = server.connect(Some("1".into()), None);
+        assert!(matches!(
+            user.handle(subscribe("private-orders.1")).await,
+            Some(ServerMessage::Subscribed { .. })
+        ));
+        assert!(matches!(
+            user.handle(subscribe("private-orders.2")).await,
+            Some(ServerMessage::SubscriptionError { message, .. }) if message == "Unauthorized"
+        ));
+        // No pattern matches
+        assert!(matches!(
+            user.handle(subscribe("private-admin")).await,
+            Some(ServerMessage::SubscriptionError { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_presence_channel() {
+        let server = server();
+        let mut messages = server.backend.messages();
+
+        let (mut ann, _) = server.connect(Some("1".into()), None);
+        let (mut bob, _) = server.connect(Some("2".into()), None);
+        ann.handle(subscribe("presence-chat")).await;
+        drain(&mut messages);
+
+        let reply = bob.handle(subscribe("presence-chat")).await.unwrap();
+        let ServerMessage::Subscribed {
+            members: Some(members),
+            ..
+        } = reply
+        else {
+            panic!("expected members, got {:?}", reply);
+        };
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].info, json!({"name": "user 1"}));
+
+        // Ann hears Bob joining, Bob doesn't hear himself
+        let envelopes = drain(&mut messages);
+        assert!(matches!(
+            received(&envelopes, &ann).as_slice(),
+            [ServerMessage::MemberAdded { member, .. }] if member.user_id == "2"
+        ));
+        assert!(received(&envelopes, &bob).is_empty());
+
+        // Whispers reach the others only
+        bob.handle(ClientMessage::Whisper {
+            channel: "presence-chat".into(),
+            event: "typing".into(),
+            data: json!({}),
+        })
+        .await;
+        let envelopes = drain(&mut messages);
+        assert!(matches!(
+            received(&envelopes, &ann).as_slice(),
+            [ServerMessage::Event { event, .. }] if event == "client-typing"
+        ));
+        assert!(received(&envelopes, &bob).is_empty());
+
+        // A deliberate close leaves immediately
+        bob.close(false).await;
+        assert!(matches!(
+            received(&drain(&mut messages), &ann).as_slice(),
+            [ServerMessage::MemberRemoved { user_id, .. }] if user_id == "2"
+        ));
+        assert_eq!(server.connection_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_whisper_requires_private_channel() {
+        let server = server();
+        let (mut conn, _) = server.connect(None, None);
+        conn.handle(subscribe("news")).await;
+
+        let reply = conn
+            .handle(ClientMessage::Whisper {
+                channel: "news".into(),
+                event: "typing".into(),
+                data: json!({}),
+            })
+            .await;
+        assert!(matches!(reply, Some(ServerMessage::Error { .. })));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resume_within_grace() {
+        let server = server();
+        let mut messages = server.backend.messages();
+
+        let (mut ann, _) = server.connect(Some("1".into()), None);
+        ann.handle(subscribe("presence-chat")).await;
+        let id = ann.id.clone();
+        ann.close(true).await;
+
+        // Another user can't take over the session
+        let (other, _) = server.connect(Some("2".into()), Some(&id));
+        assert_ne!(other.id, id);
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let (ann, connected) = server.connect(Some("1".into()), Some(&id));
+        assert_eq!(
+            connected,
+            ServerMessage::Connected {
+                connection_id: id.clone(),
+                resumed: true,
+                channels: vec!["presence-chat".into()],
+                heartbeat_interval: 25,
+            }
+        );
+
+        // The member never left
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        let members = server
+            .broadcaster()
+            .members(&Channel::presence("chat"))
+            .await
+            .unwrap();
+        assert_eq!(members.len(), 1);
+        assert!(!drain(&mut messages)
+            .iter()
+            .any(|e| matches!(e.message, ServerMessage::MemberRemoved { .. })));
+        assert_eq!(ann.channels.len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_grace_expiry_leaves_channels() {
+        let server = server();
+        let (mut ann, _) = server.connect(Some("1".into()), None);
+        ann.handle(subscribe("presence-chat")).await;
+        let id = ann.id.clone();
+        ann.close(true).await;
+
+        tokio::time::sleep(Duration::from_secs(6)).await;
+        let members = server
+            .broadcaster()
+            .members(&Channel::presence("chat"))
+            .await
+            .unwrap();
+        assert!(members.is_empty());
+
+        let (_, connected) = server.connect(Some("1".into()), Some(&id));
+        assert!(matches!(
+            connected,
+            ServerMessage::Connected { resumed: false, .. }
+        ));
+    }
+}
@@ -0,0 +1,246 @@
+//! Axum upgrade handler and the connection loop
+
+use crate::{ClientMessage, ServerMessage, UserId, WebSocketServer};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use futures::{
+    sink::SinkExt,
+    stream::{SplitSink, StreamExt},
+};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{Instant, MissedTickBehavior};
+
+/// Query parameters of the upgrade request
+#[derive(Debug, Default, Deserialize)]
+pub struct ConnectParams {
+    /// Token passed to the [`Authenticator`](crate::Authenticator)
+    pub token: Option<String>,
+
+    /// ID of a dropped connection to resume
+    pub resume: Option<String>,
+}
+
+/// WebSocket upgrade handler
+///
+/// Rejects the upgrade with `401` when a token is given but invalid.
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(server): State<WebSocketServer>,
+    Query(params): Query<ConnectParams>,
+    headers: HeaderMap,
+) -> Response {
+    let token = params.token.or_else(|| {
+        headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_string)
+    });
+
+    let user_id = match server.authenticate(token.as_deref()).await {
+        Ok(user_id) => user_id,
+        Err(message) => return (StatusCode::UNAUTHORIZED, message).into_response(),
+    };
+
+    ws.on_upgrade(move |socket| handle_socket(socket, server, user_id, params.resume))
+}
+
+async fn send(
+    sender: &mut SplitSink<WebSocket, Message>,
+    message: &ServerMessage,
+) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(message).expect("server messages serialize");
+    sender.send(Message::Text(json.into())).await
+}
+
+/// Handle WebSocket connection
+async fn handle_socket(
+    socket: WebSocket,
+    server: WebSocketServer,
+    user_id: Option<UserId>,
+    resume: Option<String>,
+) {
+    // Listen before connecting, so no message of a resumed channel is missed
+    let mut messages = server.backend.messages();
+    let (mut connection, connected) = server.connect(user_id, resume.as_deref());
+    let (mut sender, mut receiver) = socket.split();
+
+    let config = server.config.clone();
+    let mut heartbeat = tokio::time::interval_at(
+        Instant::now() + config.heartbeat_interval,
+        config.heartbeat_interval,
+    );
+    heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_seen = Instant::now();
+
+    let dropped = if send(&mut sender, &connected).await.is_err() {
+        true
+    } else {
+        loop {
+            tokio::select! {
+                incoming = receiver.next() => match incoming {
+                    Some(Ok(Message::Text(text))) => {
+                        last_seen = Instant::now();
+                        let reply = match serde_json::from_str::<ClientMessage>(&text) {
+                            Ok(message) => connection.handle(message).await,
+                            Err(e) => Some(ServerMessage::Error {
+                                message: format!("Invalid message: {}", e),
+                            }),
+                        };
+                        if let Some(reply) = reply {
+                            if send(&mut sender, &reply).await.is_err() {
+                                break true;
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) => break false,
+                    // Pongs and any other frame prove the client is alive
+                    Some(Ok(_)) => last_seen = Instant::now(),
+                    Some(Err(_)) | None => break true,
+                },
+                envelope = messages.recv() => match envelope {
+                    Ok(envelope) => {
+                        if let Some(message) = connection.deliver(&envelope) {
+                            if send(&mut sender, message).await.is_err() {
+                                break true;
+                            }
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(
+                            connection_id = %connection.id,
+                            skipped,
+                            "WebSocket connection lagging, messages skipped"
+                        );
+                    }
+                    Err(RecvError::Closed) => break true,
+                },
+                _ = heartbeat.tick() => {
+                    if last_seen.elapsed() > config.client_timeout {
+                        tracing::debug!(connection_id = %connection.id, "WebSocket client timed out");
+                        break true;
+                    }
+                    if sender.send(Message::Ping(Default::default())).await.is_err() {
+                        break true;
+                    }
+                }
+            }
+        }
+    };
+
+    connection.close(dropped).await;
+}
+
+/// Create WebSocket router serving `server` at `/ws`
+///
+/// # Example
+///
+/// ```no_run
+/// use rf_websocket::{websocket_router, WebSocketServer};
+///
+/// # async fn example() {
+/// let server = WebSocketServer::default();
+/// let broadcaster = server.broadcaster();
+///
+/// let app = axum::Router::new().merge(websocket_router(server));
+/// # }
+/// ```
+pub fn websocket_router(server: WebSocketServer) -> Router {
+    Router::new()
+        .route("/ws", get(ws_handler))
+        .with_state(server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Channel, Channels};
+    use serde_json::{json, Value};
+    use tokio_tungstenite::tungstenite;
+
+    async fn serve(server: WebSocketServer) -> std::net::SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, websocket_router(server))
+                .await
+                .unwrap();
+        });
+        addr
+    }
+
+    async fn next_json<S>(socket: &mut S) -> Value
+    where
+        S: futures::Stream<Item = Result<tungstenite::Message, tungstenite::Error>> + Unpin,
+    {
+        loop {
+            match socket.next().await.unwrap().unwrap() {
+                tungstenite::Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+                _ => continue,
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_websocket_end_to_end() {
+        let server = WebSocketServer::default()
+            .authenticator(|token: &str| match token {
+                "secret" => Ok("1".to_string()),
+                _ => Err("Invalid token".to_string()),
+            })
+            .channels(
+                Channels::new().channel("orders.*", |_: &UserId, _: &Channel| Some(json!({}))),
+            );
+        let broadcaster = server.broadcaster();
+        let addr = serve(server).await;
+
+        // Invalid tokens are rejected before upgrading
+        let rejected =
+            tokio_tungstenite::connect_async(format!("ws://{}/ws?token=wrong", addr)).await;
+        assert!(rejected.is_err());
+
+        let (mut socket, _) =
+            tokio_tungstenite::connect_async(format!("ws://{}/ws?token=secret", addr))
+                .await
+                .unwrap();
+        let connected = next_json(&mut socket).await;
+        assert_eq!(connected["type"], "connected");
+
+        socket
+            .send(tungstenite::Message::Text(
+                json!({"type": "subscribe", "channel": "private-orders.1"})
+                    .to_string()
+                    .into(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(next_json(&mut socket).await["type"], "subscribed");
+
+        broadcaster
+            .broadcast(
+                &Channel::private("orders.1"),
+                "order.shipped",
+                &json!({"id": 1}),
+            )
+            .await
+            .unwrap();
+        let event = next_json(&mut socket).await;
+        assert_eq!(event["event"], "order.shipped");
+        assert_eq!(event["data"]["id"], 1);
+
+        socket
+            .send(tungstenite::Message::Text("not json".into()))
+            .await
+            .unwrap();
+        assert_eq!(next_json(&mut socket).await["type"], "error");
+    }
+}
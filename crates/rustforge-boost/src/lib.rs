@@ -146,6 +146,52 @@ impl RustForgeBoost {
         })
     }
 
+    /// Generate code like [`generate_code`](Self::generate_code), reporting
+    /// each stage to `progress` so clients can follow long generations
+    #[cfg(feature = "progress")]
+    pub async fn generate_code_tracked(
+        &self,
+        prompt: &str,
+        context: &Context,
+        progress: &mut rf_progress::JobProgress,
+    ) -> Result<GeneratedCode> {
+        let result = self.generate_code_steps(prompt, context, progress).await;
+        match &result {
+            Ok(generated) => progress.complete(generated).await?,
+            Err(e) => progress.fail(e).await?,
+        }
+        result
+    }
+
+    #[cfg(feature = "progress")]
+    async fn generate_code_steps(
+        &self,
+        prompt: &str,
+        context: &Context,
+        progress: &mut rf_progress::JobProgress,
+    ) -> Result<GeneratedCode> {
+        progress.steps(4).await?;
+        progress.advance("Preparing prompt").await?;
+        let enhanced_prompt = self.enhance_prompt(prompt, context).await?;
+
+        progress.advance("Generating code").await?;
+        let generated = self.ai_provider.generate(&enhanced_prompt, context).await?;
+        let processed = self.post_process_code(&generated)?;
+
+        progress.advance("Explaining code").await?;
+        let explanation = self.generate_explanation(&processed).await?;
+
+        progress.advance("Generating tests").await?;
+        let tests = self.generate_tests(&processed, context).await?;
+
+        Ok(GeneratedCode {
+            code: processed,
+            language: "rust".to_string(),
+            explanation,
+            tests,
+        })
+    }
+
     /// Generate documentation for code
     pub async fn generate_docs(&self, code: &str, doc_type: DocType) -> Result<String> {
         let prompt = match doc_type {
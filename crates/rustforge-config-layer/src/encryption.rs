@@ -0,0 +1,188 @@
+//! Encryption for cached config artifacts
+//!
+//! `Config::cache()` output contains database and mail credentials, so it is
+//! sealed with AES-256-GCM before it touches the disk. Keys are derived from
+//! `APP_KEY`; keys listed in `APP_PREVIOUS_KEYS` (comma separated) can still
+//! decrypt artifacts written before a rotation.
+//!
+//! Artifact layout: `RFCC` magic, version byte, 8-byte key id, 12-byte nonce,
+//! ciphertext with authentication tag. The header is authenticated as well.
+
+use anyhow::{anyhow, bail, Result};
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+
+const MAGIC: &[u8; 4] = b"RFCC";
+const VERSION: u8 = 1;
+const KEY_ID_LEN: usize = 8;
+const HEADER_LEN: usize = MAGIC.len() + 1 + KEY_ID_LEN;
+
+/// A single derived cache key
+struct CacheKey {
+    id: [u8; KEY_ID_LEN],
+    key: LessSafeKey,
+}
+
+impl CacheKey {
+    fn derive(app_key: &str) -> Result<Self> {
+        let material = match app_key.strip_prefix("base64:") {
+            Some(encoded) => base64::engine::general_purpose::STANDARD.decode(encoded)?,
+            None => app_key.as_bytes().to_vec(),
+        };
+        if material.is_empty() {
+            bail!("application key is empty");
+        }
+
+        // Domain-separate the cache key from other uses of APP_KEY
+        let mut input = b"rustforge:config-cache:".to_vec();
+        input.extend_from_slice(&material);
+        let derived = digest(&SHA256, &input);
+
+        let mut id = [0u8; KEY_ID_LEN];
+        id.copy_from_slice(&digest(&SHA256, derived.as_ref()).as_ref()[..KEY_ID_LEN]);
+
+        let key = UnboundKey::new(&AES_256_GCM, derived.as_ref())
+            .map_err(|_| anyhow!("invalid cache key"))?;
+
+        Ok(Self {
+            id,
+            key: LessSafeKey::new(key),
+        })
+    }
+}
+
+/// Current and previous keys used for cached config artifacts
+pub struct CacheKeys {
+    current: CacheKey,
+    previous: Vec<CacheKey>,
+}
+
+impl CacheKeys {
+    /// Keys from an application key and previously used keys
+    pub fn new(app_key: &str, previous: &[&str]) -> Result<Self> {
+        Ok(Self {
+            current: CacheKey::derive(app_key)?,
+            previous: previous
+                .iter()
+                .map(|key| CacheKey::derive(key))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Keys from `APP_KEY` and `APP_PREVIOUS_KEYS`
+    pub fn from_env() -> Result<Self> {
+        let app_key = std::env::var("APP_KEY").map_err(|_| anyhow!("APP_KEY is not set"))?;
+        let previous = std::env::var("APP_PREVIOUS_KEYS").unwrap_or_default();
+        let previous: Vec<&str> = previous
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .collect();
+
+        Self::new(&app_key, &previous)
+    }
+
+    /// Encrypt with the current key
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("failed to generate nonce"))?;
+
+        let mut out = Vec::with_capacity(HEADER_LEN + NONCE_LEN + plaintext.len() + 16);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&self.current.id);
+        let header = out.clone();
+        out.extend_from_slice(&nonce);
+
+        let mut in_out = plaintext.to_vec();
+        self.current
+            .key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(&header),
+                &mut in_out,
+            )
+            .map_err(|_| anyhow!("failed to encrypt config cache"))?;
+        out.extend_from_slice(&in_out);
+
+        Ok(out)
+    }
+
+    /// Decrypt and verify an artifact
+    ///
+    /// Returns the plaintext and whether it was sealed with a previous key
+    /// (and should therefore be re-written with the current one).
+    pub fn open(&self, data: &[u8]) -> Result<(Vec<u8>, bool)> {
+        if data.len() < HEADER_LEN + NONCE_LEN || &data[..MAGIC.len()] != MAGIC {
+            bail!("not an encrypted config cache");
+        }
+        if data[MAGIC.len()] != VERSION {
+            bail!("unsupported config cache version {}", data[MAGIC.len()]);
+        }
+
+        let (header, rest) = data.split_at(HEADER_LEN);
+        let key_id = &header[MAGIC.len() + 1..];
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let (key, rotated) = if key_id == self.current.id {
+            (&self.current, false)
+        } else {
+            let key = self
+                .previous
+                .iter()
+                .find(|key| key_id == key.id)
+                .ok_or_else(|| anyhow!("config cache was encrypted with an unknown key"))?;
+            (key, true)
+        };
+
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| anyhow!("invalid config cache nonce"))?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = key
+            .key
+            .open_in_place(nonce, Aad::from(header), &mut in_out)
+            .map_err(|_| anyhow!("config cache failed integrity check"))?;
+
+        Ok((plaintext.to_vec(), rotated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_roundtrip() {
+        let keys = CacheKeys::new("base64:c2VjcmV0LWtleS1mb3ItdGVzdHM=", &[]).unwrap();
+        let sealed = keys.seal(b"db-password").unwrap();
+
+        assert!(!sealed.windows(11).any(|w| w == b"db-password"));
+        assert_eq!(keys.open(&sealed).unwrap(), (b"db-password".to_vec(), false));
+    }
+
+    #[test]
+    fn test_previous_key_still_decrypts() {
+        let old = CacheKeys::new("old-key", &[]).unwrap();
+        let sealed = old.seal(b"payload").unwrap();
+
+        let rotated = CacheKeys::new("new-key", &["old-key"]).unwrap();
+        assert_eq!(rotated.open(&sealed).unwrap(), (b"payload".to_vec(), true));
+
+        let unrelated = CacheKeys::new("new-key", &[]).unwrap();
+        assert!(unrelated.open(&sealed).is_err());
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let keys = CacheKeys::new("key", &[]).unwrap();
+        let mut sealed = keys.seal(b"payload").unwrap();
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+
+        assert!(keys.open(&sealed).is_err());
+    }
+}
@@ -1,31 +1,51 @@
 use anyhow::Result;
+use arc_swap::ArcSwap;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
-use std::sync::RwLock;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+pub mod encryption;
+pub mod sources;
+
+pub use encryption::CacheKeys;
+pub use sources::{
+    ConfigSource, ConsulSource, EtcdSource, Fetch, HttpSource, RemoteConfig, Revision,
+    S3Credentials, SnapshotStore,
+};
 
 /// Global config instance
-static CONFIG: Lazy<RwLock<Config>> = Lazy::new(|| {
-    RwLock::new(Config::default())
-});
+///
+/// Readers get a cheap `Arc` snapshot without locking; writers swap in a new
+/// copy of the config (copy-on-write).
+static CONFIG: Lazy<ArcSwap<Config>> = Lazy::new(|| ArcSwap::from_pointee(Config::default()));
+
+/// Serializes test overrides of the global config
+static OVERRIDE_LOCK: Mutex<()> = Mutex::new(());
 
 /// Typed configuration system with Laravel-like API
 ///
 /// Usage:
 /// ```rust
-/// config::app().name
-/// config::database().host
-/// config::get("app.name")
-/// config::set("app.debug", true)
+/// use rustforge_config_layer as config;
+///
+/// let name = config::app().name.clone();
+/// let connection = config::database().default.clone();
+/// let debug = config::get("app.debug");
+/// config::set("app.debug", true).unwrap();
 /// ```
+///
+/// Sections are shared behind `Arc`, so handing out a section or cloning the
+/// whole config never deep-copies it.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Config {
-    app: AppConfig,
-    database: DatabaseConfig,
-    cache: CacheConfig,
-    queue: QueueConfig,
-    mail: MailConfig,
-    auth: AuthConfig,
+    app: Arc<AppConfig>,
+    database: Arc<DatabaseConfig>,
+    cache: Arc<CacheConfig>,
+    queue: Arc<QueueConfig>,
+    mail: Arc<MailConfig>,
+    auth: Arc<AuthConfig>,
     services: HashMap<String, ServiceConfig>,
     custom: HashMap<String, serde_json::Value>,
 }
@@ -230,20 +250,7 @@ impl Config {
                     .unwrap_or("");
 
                 let contents = std::fs::read_to_string(&file_path)?;
-
-                match file_name {
-                    "app" => config.app = toml::from_str(&contents)?,
-                    "database" => config.database = toml::from_str(&contents)?,
-                    "cache" => config.cache = toml::from_str(&contents)?,
-                    "queue" => config.queue = toml::from_str(&contents)?,
-                    "mail" => config.mail = toml::from_str(&contents)?,
-                    "auth" => config.auth = toml::from_str(&contents)?,
-                    _ => {
-                        // Load as service config
-                        let service_config: ServiceConfig = toml::from_str(&contents)?;
-                        config.services.insert(file_name.to_string(), service_config);
-                    }
-                }
+                config.apply_section(file_name, toml::from_str(&contents)?)?;
             }
         }
 
@@ -253,46 +260,150 @@ impl Config {
         Ok(config)
     }
 
+    /// Replace a whole config section (`app`, `database`, ... or a service name)
+    fn apply_section(&mut self, name: &str, value: toml::Value) -> Result<()> {
+        match name {
+            "app" => self.app = Arc::new(value.try_into()?),
+            "database" => self.database = Arc::new(value.try_into()?),
+            "cache" => self.cache = Arc::new(value.try_into()?),
+            "queue" => self.queue = Arc::new(value.try_into()?),
+            "mail" => self.mail = Arc::new(value.try_into()?),
+            "auth" => self.auth = Arc::new(value.try_into()?),
+            _ => {
+                // Load as service config
+                let service_config: ServiceConfig = value.try_into()?;
+                self.services.insert(name.to_string(), service_config);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Current value of a config section as TOML
+    fn section(&self, name: &str) -> Option<toml::Value> {
+        match name {
+            "app" => toml::Value::try_from(&*self.app).ok(),
+            "database" => toml::Value::try_from(&*self.database).ok(),
+            "cache" => toml::Value::try_from(&*self.cache).ok(),
+            "queue" => toml::Value::try_from(&*self.queue).ok(),
+            "mail" => toml::Value::try_from(&*self.mail).ok(),
+            "auth" => toml::Value::try_from(&*self.auth).ok(),
+            _ => self
+                .services
+                .get(name)
+                .and_then(|service| toml::Value::try_from(service).ok()),
+        }
+    }
+
+    /// Deep-merge a TOML section on top of the current values
+    fn merge_section(&mut self, name: &str, value: toml::Value) -> Result<()> {
+        let merged = match self.section(name) {
+            Some(mut base) => {
+                merge_toml(&mut base, value);
+                base
+            }
+            None => value,
+        };
+
+        self.apply_section(name, merged)
+    }
+
     /// Apply environment variable overrides
     fn apply_env_overrides(&mut self) {
         // Override with environment variables
         if let Ok(name) = std::env::var("APP_NAME") {
-            self.app.name = name;
+            Arc::make_mut(&mut self.app).name = name;
         }
         if let Ok(debug) = std::env::var("APP_DEBUG") {
-            self.app.debug = debug.parse().unwrap_or(false);
+            Arc::make_mut(&mut self.app).debug = debug.parse().unwrap_or(false);
         }
         if let Ok(url) = std::env::var("APP_URL") {
-            self.app.url = url;
+            Arc::make_mut(&mut self.app).url = url;
         }
         if let Ok(port) = std::env::var("APP_PORT") {
             if let Ok(port) = port.parse() {
-                self.app.port = port;
+                Arc::make_mut(&mut self.app).port = port;
             }
         }
 
         // Database overrides
-        if let Ok(db_url) = std::env::var("DATABASE_URL") {
+        if let Ok(_db_url) = std::env::var("DATABASE_URL") {
             // Parse DATABASE_URL and update config
             // This is simplified - real implementation would parse the URL properly
         }
     }
 
-    /// Cache configuration for production
+    /// Cache configuration for production, encrypted with `APP_KEY`
     pub fn cache(&self) -> Result<Vec<u8>> {
-        Ok(bincode::serialize(self)?)
+        self.cache_with(&CacheKeys::from_env()?)
+    }
+
+    /// Cache configuration encrypted with the given keys
+    pub fn cache_with(&self, keys: &CacheKeys) -> Result<Vec<u8>> {
+        keys.seal(&serde_json::to_vec(self)?)
     }
 
     /// Load cached configuration
     pub fn from_cache(data: &[u8]) -> Result<Self> {
-        Ok(bincode::deserialize(data)?)
+        Self::from_cache_with(data, &CacheKeys::from_env()?)
+    }
+
+    /// Load cached configuration encrypted with the given keys
+    pub fn from_cache_with(data: &[u8], keys: &CacheKeys) -> Result<Self> {
+        let (plaintext, _) = keys.open(data)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// Load the cached artifact at `cache_path`, falling back to `config_dir`
+    ///
+    /// When the artifact is missing, fails the integrity check or was written
+    /// with a rotated-out key, the TOML files are read again and the cache is
+    /// rewritten with the current key.
+    pub fn load_cached(
+        cache_path: impl AsRef<Path>,
+        config_dir: impl AsRef<Path>,
+        keys: &CacheKeys,
+    ) -> Result<Self> {
+        let cache_path = cache_path.as_ref();
+
+        if let Ok(data) = std::fs::read(cache_path) {
+            if let Ok((plaintext, rotated)) = keys.open(&data) {
+                if let Ok(config) = serde_json::from_slice::<Self>(&plaintext) {
+                    if rotated {
+                        std::fs::write(cache_path, config.cache_with(keys)?)?;
+                    }
+                    return Ok(config);
+                }
+            }
+        }
+
+        let config = Self::load_from_dir(config_dir)?;
+        std::fs::write(cache_path, config.cache_with(keys)?)?;
+        Ok(config)
+    }
+}
+
+/// Recursively merge `overlay` into `base`; tables merge, everything else replaces
+fn merge_toml(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
     }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            app: AppConfig {
+            app: Arc::new(AppConfig {
                 name: "RustForge".to_string(),
                 env: Environment::Development,
                 debug: true,
@@ -302,28 +413,28 @@ impl Default for Config {
                 cipher: "AES-256-CBC".to_string(),
                 timezone: "UTC".to_string(),
                 locale: "en".to_string(),
-            },
-            database: DatabaseConfig {
+            }),
+            database: Arc::new(DatabaseConfig {
                 default: "postgres".to_string(),
                 connections: HashMap::new(),
-            },
-            cache: CacheConfig {
+            }),
+            cache: Arc::new(CacheConfig {
                 default: "redis".to_string(),
                 stores: HashMap::new(),
-            },
-            queue: QueueConfig {
+            }),
+            queue: Arc::new(QueueConfig {
                 default: "sync".to_string(),
                 connections: HashMap::new(),
-            },
-            mail: MailConfig {
+            }),
+            mail: Arc::new(MailConfig {
                 default: "smtp".to_string(),
                 mailers: HashMap::new(),
                 from: MailAddress {
                     address: "noreply@example.com".to_string(),
                     name: "RustForge".to_string(),
                 },
-            },
-            auth: AuthConfig {
+            }),
+            auth: Arc::new(AuthConfig {
                 defaults: AuthDefaults {
                     guard: "web".to_string(),
                     passwords: "users".to_string(),
@@ -331,7 +442,7 @@ impl Default for Config {
                 guards: HashMap::new(),
                 providers: HashMap::new(),
                 passwords: HashMap::new(),
-            },
+            }),
             services: HashMap::new(),
             custom: HashMap::new(),
         }
@@ -340,40 +451,52 @@ impl Default for Config {
 
 // Public API functions (Laravel-style)
 
+/// Get a snapshot of the whole configuration
+///
+/// The snapshot is never mutated; later `set`/`init` calls publish a new one.
+pub fn current() -> Arc<Config> {
+    CONFIG.load_full()
+}
+
+/// Replace the global configuration
+pub(crate) fn replace(config: Config) {
+    CONFIG.store(Arc::new(config));
+}
+
 /// Get app configuration
-pub fn app() -> AppConfig {
-    CONFIG.read().unwrap().app.clone()
+pub fn app() -> Arc<AppConfig> {
+    CONFIG.load().app.clone()
 }
 
 /// Get database configuration
-pub fn database() -> DatabaseConfig {
-    CONFIG.read().unwrap().database.clone()
+pub fn database() -> Arc<DatabaseConfig> {
+    CONFIG.load().database.clone()
 }
 
 /// Get cache configuration
-pub fn cache() -> CacheConfig {
-    CONFIG.read().unwrap().cache.clone()
+pub fn cache() -> Arc<CacheConfig> {
+    CONFIG.load().cache.clone()
 }
 
 /// Get queue configuration
-pub fn queue() -> QueueConfig {
-    CONFIG.read().unwrap().queue.clone()
+pub fn queue() -> Arc<QueueConfig> {
+    CONFIG.load().queue.clone()
 }
 
 /// Get mail configuration
-pub fn mail() -> MailConfig {
-    CONFIG.read().unwrap().mail.clone()
+pub fn mail() -> Arc<MailConfig> {
+    CONFIG.load().mail.clone()
 }
 
 /// Get auth configuration
-pub fn auth() -> AuthConfig {
-    CONFIG.read().unwrap().auth.clone()
+pub fn auth() -> Arc<AuthConfig> {
+    CONFIG.load().auth.clone()
 }
 
 /// Get configuration value by key (dot notation)
 pub fn get(key: &str) -> Option<serde_json::Value> {
     let parts: Vec<&str> = key.split('.').collect();
-    let config = CONFIG.read().unwrap();
+    let config = CONFIG.load();
 
     match parts[0] {
         "app" => match parts.get(1) {
@@ -381,6 +504,7 @@ pub fn get(key: &str) -> Option<serde_json::Value> {
             Some(&"debug") => Some(json!(config.app.debug)),
             Some(&"url") => Some(json!(config.app.url)),
             Some(&"port") => Some(json!(config.app.port)),
+            Some(&"env") => Some(json!(config.app.env)),
             _ => None,
         },
         "database" => match parts.get(1) {
@@ -392,32 +516,48 @@ pub fn get(key: &str) -> Option<serde_json::Value> {
 }
 
 /// Set configuration value by key
+///
+/// Copy-on-write: readers holding an older snapshot are unaffected.
 pub fn set(key: &str, value: impl Serialize) -> Result<()> {
-    let mut config = CONFIG.write().unwrap();
     let json_value = serde_json::to_value(value)?;
 
-    let parts: Vec<&str> = key.split('.').collect();
+    CONFIG.rcu(|current| {
+        let mut config = Config::clone(current);
+        config.set_value(key, &json_value);
+        config
+    });
 
-    match parts[0] {
-        "app" => match parts.get(1) {
-            Some(&"name") => {
-                if let Some(s) = json_value.as_str() {
-                    config.app.name = s.to_string();
-                }
-            },
-            Some(&"debug") => {
-                if let Some(b) = json_value.as_bool() {
-                    config.app.debug = b;
-                }
+    Ok(())
+}
+
+impl Config {
+    fn set_value(&mut self, key: &str, json_value: &serde_json::Value) {
+        let parts: Vec<&str> = key.split('.').collect();
+
+        match parts[0] {
+            "app" => match parts.get(1) {
+                Some(&"name") => {
+                    if let Some(s) = json_value.as_str() {
+                        Arc::make_mut(&mut self.app).name = s.to_string();
+                    }
+                },
+                Some(&"debug") => {
+                    if let Some(b) = json_value.as_bool() {
+                        Arc::make_mut(&mut self.app).debug = b;
+                    }
+                },
+                Some(&"env") => {
+                    if let Ok(env) = serde_json::from_value(json_value.clone()) {
+                        Arc::make_mut(&mut self.app).env = env;
+                    }
+                },
+                _ => {}
             },
-            _ => {}
-        },
-        _ => {
-            config.custom.insert(key.to_string(), json_value);
+            _ => {
+                self.custom.insert(key.to_string(), json_value.clone());
+            }
         }
     }
-
-    Ok(())
 }
 
 /// Check if configuration key exists
@@ -428,25 +568,56 @@ pub fn has(key: &str) -> bool {
 /// Initialize configuration from directory
 pub fn init(path: impl AsRef<Path>) -> Result<()> {
     let config = Config::load_from_dir(path)?;
-    *CONFIG.write().unwrap() = config;
+    replace(config);
     Ok(())
 }
 
+/// Guard returned by [`test_override`]
+///
+/// Restores the configuration that was active when it was created on drop.
+pub struct ConfigOverride {
+    previous: Arc<Config>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Drop for ConfigOverride {
+    fn drop(&mut self) {
+        CONFIG.store(self.previous.clone());
+    }
+}
+
+/// Scope config changes to a test
+///
+/// Overrides are serialized across threads, so parallel tests using this guard
+/// never observe each other's values.
+///
+/// ```rust,ignore
+/// let _config = config::test_override();
+/// config::set("app.debug", false)?;
+/// ```
+pub fn test_override() -> ConfigOverride {
+    let lock = OVERRIDE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    ConfigOverride {
+        previous: CONFIG.load_full(),
+        _lock: lock,
+    }
+}
+
 /// Environment check helpers
 pub fn is_production() -> bool {
-    matches!(CONFIG.read().unwrap().app.env, Environment::Production)
+    matches!(CONFIG.load().app.env, Environment::Production)
 }
 
 pub fn is_development() -> bool {
-    matches!(CONFIG.read().unwrap().app.env, Environment::Development)
+    matches!(CONFIG.load().app.env, Environment::Development)
 }
 
 pub fn is_local() -> bool {
-    matches!(CONFIG.read().unwrap().app.env, Environment::Local)
+    matches!(CONFIG.load().app.env, Environment::Local)
 }
 
 pub fn environment() -> Environment {
-    CONFIG.read().unwrap().app.env.clone()
+    CONFIG.load().app.env.clone()
 }
 
 // Macro for easy config access
@@ -504,18 +675,23 @@ impl ConfigBuilder for AppConfig {
     }
 }
 
+// Re-exports for convenience
+pub use serde_json::json;
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_config_get_set() {
+        let _config = test_override();
         set("custom.key", "value").unwrap();
         assert_eq!(get("custom.key"), Some(json!("value")));
     }
 
     #[test]
     fn test_config_macro() {
+        let _config = test_override();
         set("test.value", 42).unwrap();
         assert_eq!(config!("test.value"), Some(json!(42)));
         assert_eq!(config!("missing.value", json!("default")), json!("default"));
@@ -523,14 +699,55 @@ mod tests {
 
     #[test]
     fn test_environment_helpers() {
-        let mut config = CONFIG.write().unwrap();
-        config.app.env = Environment::Production;
-        drop(config);
+        let _config = test_override();
+        set("app.env", Environment::Production).unwrap();
 
         assert!(is_production());
         assert!(!is_development());
     }
-}
 
-// Re-exports for convenience
-pub use serde_json::json;
\ No newline at end of file
+    #[test]
+    fn test_load_cached_falls_back_to_toml() {
+        let dir = std::env::temp_dir().join(format!("rf-config-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("app.toml"), toml::to_string(&*Config::default().app).unwrap())
+            .unwrap();
+        let cache_path = dir.join("config.cache");
+        std::fs::write(&cache_path, b"corrupted").unwrap();
+
+        let keys = CacheKeys::new("test-key", &[]).unwrap();
+        let config = Config::load_cached(&cache_path, &dir, &keys).unwrap();
+        assert_eq!(config.app.name, "RustForge");
+
+        // The rewritten artifact decrypts and no longer contains plaintext
+        let data = std::fs::read(&cache_path).unwrap();
+        assert!(Config::from_cache_with(&data, &keys).is_ok());
+        assert!(!data.windows(9).any(|w| w == b"RustForge"));
+    }
+
+    #[test]
+    fn test_cache_roundtrip_with_json_values() {
+        let mut config = Config::default();
+        config.set_value("feature.limits", &json!({ "max": 5 }));
+        let keys = CacheKeys::new("test-key", &[]).unwrap();
+
+        let cached = Config::from_cache_with(&config.cache_with(&keys).unwrap(), &keys).unwrap();
+        assert_eq!(
+            cached.custom.get("feature.limits"),
+            Some(&json!({ "max": 5 }))
+        );
+    }
+
+    #[test]
+    fn test_override_restores_previous_config() {
+        let before = app();
+        {
+            let _config = test_override();
+            set("app.name", "Overridden").unwrap();
+            assert_eq!(app().name, "Overridden");
+            // Snapshots taken earlier are unaffected by copy-on-write
+            assert_eq!(before.name, "RustForge");
+        }
+        assert_eq!(app().name, before.name);
+    }
+}
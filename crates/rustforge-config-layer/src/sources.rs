@@ -0,0 +1,939 @@
+//! Remote configuration sources
+//!
+//! Layers TOML documents fetched from HTTP/S3, Consul KV or etcd on top of the
+//! local config directory. Each document uses the same section names as the
+//! files in `config/` (`[app]`, `[database]`, ...), and sections are deep-merged
+//! in source order. Environment variables are applied last.
+//!
+//! Usage:
+//! ```rust,ignore
+//! let remote = RemoteConfig::new("config")
+//!     .source(ConsulSource::new("http://consul:8500", "myapp/config"))
+//!     .source(HttpSource::s3(
+//!         "my-bucket",
+//!         "eu-central-1",
+//!         "myapp/prod.toml",
+//!         S3Credentials::from_env()?,
+//!     ))
+//!     .encrypted_snapshot_dir("storage/config-snapshots", CacheKeys::from_env()?)
+//!     .interval(Duration::from_secs(30))
+//!     .install()
+//!     .await?;
+//!
+//! remote.spawn();
+//! ```
+//!
+//! Snapshots of the last good documents are written owner-only (`0600`);
+//! `encrypted_snapshot_dir` also seals them with the config cache keys, so
+//! remote secrets never sit on disk in plaintext.
+
+use super::{replace, CacheKeys, Config};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Revision marker used for conditional polling
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Revision {
+    /// HTTP entity tag (HTTP and S3 sources)
+    ETag(String),
+    /// Monotonic index (Consul index, etcd mod revision)
+    Index(u64),
+}
+
+/// Result of a single fetch
+#[derive(Debug, Clone)]
+pub enum Fetch {
+    /// The document has not changed since the given revision
+    NotModified,
+    /// A new document is available
+    Updated {
+        document: toml::Table,
+        revision: Option<Revision>,
+    },
+}
+
+/// A remote source of configuration
+#[async_trait]
+pub trait ConfigSource: Send + Sync {
+    /// Stable name, used for logging and snapshot file names
+    fn name(&self) -> &str;
+
+    /// Fetch the document, skipping the transfer when `since` is still current
+    async fn fetch(&self, since: Option<&Revision>) -> Result<Fetch>;
+}
+
+/// Parse a remote TOML document into its sections
+fn parse_document(source: &str, body: &str) -> Result<toml::Table> {
+    toml::from_str(body).with_context(|| format!("invalid config document from {}", source))
+}
+
+/// Plain HTTP(S) source, also used for S3 objects
+///
+/// Uses `If-None-Match` with the last seen ETag so unchanged documents are not
+/// transferred again.
+pub struct HttpSource {
+    name: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    s3: Option<S3Signer>,
+    client: reqwest::Client,
+}
+
+impl HttpSource {
+    pub fn new(url: impl Into<String>) -> Self {
+        let url = url.into();
+        Self {
+            name: url.clone(),
+            url,
+            headers: Vec::new(),
+            s3: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// S3 object addressed via its virtual-hosted URL
+    ///
+    /// Requests are signed with AWS Signature Version 4. Public objects and
+    /// pre-signed URLs can be read with [`HttpSource::new`] instead.
+    pub fn s3(bucket: &str, region: &str, key: &str, credentials: S3Credentials) -> Self {
+        let key = key.trim_start_matches('/');
+        let signer = S3Signer {
+            host: format!("{}.s3.{}.amazonaws.com", bucket, region),
+            path: format!("/{}", uri_encode_path(key)),
+            region: region.to_string(),
+            credentials,
+        };
+
+        let mut source = Self::new(format!("https://{}{}", signer.host, signer.path))
+            .named(format!("s3://{}/{}", bucket, key));
+        source.s3 = Some(signer);
+        source
+    }
+
+    /// Override the source name
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Add a request header (e.g. `Authorization`)
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+#[async_trait]
+impl ConfigSource for HttpSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch(&self, since: Option<&Revision>) -> Result<Fetch> {
+        let mut request = self.client.get(&self.url);
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(signer) = &self.s3 {
+            for (name, value) in signer.sign(Utc::now()) {
+                request = request.header(name, value);
+            }
+        }
+        if let Some(Revision::ETag(etag)) = since {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag.as_str());
+        }
+
+        let response = request.send().await?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(Fetch::NotModified);
+        }
+        let response = response.error_for_status()?;
+
+        let revision = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(|etag| Revision::ETag(etag.to_string()));
+        let body = response.text().await?;
+
+        Ok(Fetch::Updated {
+            document: parse_document(&self.name, &body)?,
+            revision,
+        })
+    }
+}
+
+/// AWS credentials used to sign S3 requests
+#[derive(Clone)]
+pub struct S3Credentials {
+    pub access_key: String,
+    pub secret_key: String,
+    /// Session token of temporary credentials
+    pub session_token: Option<String>,
+}
+
+impl S3Credentials {
+    pub fn new(access_key: impl Into<String>, secret_key: impl Into<String>) -> Self {
+        Self {
+            access_key: access_key.into(),
+            secret_key: secret_key.into(),
+            session_token: None,
+        }
+    }
+
+    /// Load from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`
+    pub fn from_env() -> Result<Self> {
+        let required =
+            |name: &str| std::env::var(name).with_context(|| format!("{} is not set", name));
+
+        Ok(Self {
+            access_key: required("AWS_ACCESS_KEY_ID")?,
+            secret_key: required("AWS_SECRET_ACCESS_KEY")?,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+
+    /// Attach a session token
+    pub fn session_token(mut self, token: impl Into<String>) -> Self {
+        self.session_token = Some(token.into());
+        self
+    }
+}
+
+/// SigV4 signing for GET requests against a single S3 object
+struct S3Signer {
+    host: String,
+    path: String,
+    region: String,
+    credentials: S3Credentials,
+}
+
+impl S3Signer {
+    /// SigV4 headers for a GET of the object at `now`
+    fn sign(&self, now: DateTime<Utc>) -> Vec<(&'static str, String)> {
+        let date = now.format("%Y%m%d").to_string();
+        let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex::encode(Sha256::digest(b""));
+
+        let mut headers = vec![
+            ("host", self.host.clone()),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", timestamp.clone()),
+        ];
+        if let Some(token) = &self.credentials.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value))
+            .collect();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_request = format!(
+            "GET\n{}\n\n{}\n{}\n{}",
+            self.path, canonical_headers, signed_headers, payload_hash
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            timestamp,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let signing_key = [date.as_str(), &self.region, "s3", "aws4_request"]
+            .iter()
+            .fold(
+                format!("AWS4{}", self.credentials.secret_key).into_bytes(),
+                |key, part| hmac(&key, part.as_bytes()),
+            );
+        let signature = hex::encode(hmac(&signing_key, string_to_sign.as_bytes()));
+
+        // reqwest sets the host header from the URL
+        headers.retain(|(name, _)| *name != "host");
+        headers.push((
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                self.credentials.access_key, scope, signed_headers, signature
+            ),
+        ));
+        headers
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode an object key for the URL path, keeping `/` separators
+fn uri_encode_path(key: &str) -> String {
+    let mut encoded = String::with_capacity(key.len());
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Consul KV source
+///
+/// Uses blocking queries: once an index is known, the request waits up to
+/// `wait` for the key to change before returning.
+pub struct ConsulSource {
+    name: String,
+    address: String,
+    key: String,
+    token: Option<String>,
+    wait: Duration,
+    client: reqwest::Client,
+}
+
+impl ConsulSource {
+    pub fn new(address: impl Into<String>, key: impl Into<String>) -> Self {
+        let key = key.into();
+        Self {
+            name: format!("consul:{}", key),
+            address: address.into().trim_end_matches('/').to_string(),
+            key,
+            token: None,
+            wait: Duration::from_secs(30),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// ACL token sent as `X-Consul-Token`
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Maximum blocking-query wait time
+    pub fn wait(mut self, wait: Duration) -> Self {
+        self.wait = wait;
+        self
+    }
+}
+
+#[async_trait]
+impl ConfigSource for ConsulSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch(&self, since: Option<&Revision>) -> Result<Fetch> {
+        let mut url = format!("{}/v1/kv/{}?raw=true", self.address, self.key);
+        let since_index = match since {
+            Some(Revision::Index(index)) => Some(*index),
+            _ => None,
+        };
+        if let Some(index) = since_index {
+            url.push_str(&format!("&index={}&wait={}s", index, self.wait.as_secs()));
+        }
+
+        let mut request = self.client.get(&url);
+        if let Some(token) = &self.token {
+            request = request.header("X-Consul-Token", token.as_str());
+        }
+
+        let response = request.send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            bail!("consul key '{}' not found", self.key);
+        }
+        let response = response.error_for_status()?;
+
+        let index = response
+            .headers()
+            .get("X-Consul-Index")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+
+        // A blocking query that times out returns the same index
+        if index.is_some() && index == since_index {
+            return Ok(Fetch::NotModified);
+        }
+
+        let body = response.text().await?;
+        Ok(Fetch::Updated {
+            document: parse_document(&self.name, &body)?,
+            revision: index.map(Revision::Index),
+        })
+    }
+}
+
+/// etcd v3 source (JSON gateway)
+///
+/// Polls with `min_mod_revision`, so the value is only transferred when the
+/// key changed since the last fetch.
+pub struct EtcdSource {
+    name: String,
+    address: String,
+    key: String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct EtcdRangeResponse {
+    #[serde(default)]
+    kvs: Vec<EtcdKeyValue>,
+    /// Keys in the range before `min_mod_revision` filtering (omitted when 0)
+    #[serde(default)]
+    count: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EtcdKeyValue {
+    value: String,
+    mod_revision: String,
+}
+
+impl EtcdSource {
+    pub fn new(address: impl Into<String>, key: impl Into<String>) -> Self {
+        let key = key.into();
+        Self {
+            name: format!("etcd:{}", key),
+            address: address.into().trim_end_matches('/').to_string(),
+            key,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Range request for the key, only returning its value when it was
+    /// modified after `since`
+    fn range_request(&self, since: Option<&Revision>) -> serde_json::Value {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let mut body = serde_json::json!({ "key": engine.encode(&self.key) });
+        if let Some(Revision::Index(revision)) = since {
+            body["min_mod_revision"] = (revision + 1).to_string().into();
+        }
+        body
+    }
+
+    fn read_range(&self, response: EtcdRangeResponse) -> Result<Fetch> {
+        let count: u64 = response.count.as_deref().unwrap_or("0").parse()?;
+        if count == 0 {
+            bail!("etcd key '{}' not found", self.key);
+        }
+
+        // The key exists but was filtered out by `min_mod_revision`
+        let Some(kv) = response.kvs.into_iter().next() else {
+            return Ok(Fetch::NotModified);
+        };
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let contents = String::from_utf8(engine.decode(kv.value)?)?;
+        Ok(Fetch::Updated {
+            document: parse_document(&self.name, &contents)?,
+            revision: Some(Revision::Index(kv.mod_revision.parse()?)),
+        })
+    }
+}
+
+#[async_trait]
+impl ConfigSource for EtcdSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch(&self, since: Option<&Revision>) -> Result<Fetch> {
+        let response: EtcdRangeResponse = self
+            .client
+            .post(format!("{}/v3/kv/range", self.address))
+            .json(&self.range_request(since))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        self.read_range(response)
+    }
+}
+
+/// Last good document of a source, persisted on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub revision: Option<Revision>,
+    pub document: toml::Table,
+}
+
+/// Directory of per-source snapshots used when a source is unreachable
+///
+/// Snapshots hold the remote documents, credentials included. Files are
+/// created readable by the owner only (`0600` on Unix); a store created with
+/// [`SnapshotStore::encrypted`] additionally seals them with AES-256-GCM, like
+/// `Config::cache_with`.
+pub struct SnapshotStore {
+    dir: PathBuf,
+    keys: Option<CacheKeys>,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            keys: None,
+        }
+    }
+
+    /// Store whose snapshots are encrypted with `keys`
+    pub fn encrypted(dir: impl AsRef<Path>, keys: CacheKeys) -> Self {
+        Self {
+            keys: Some(keys),
+            ..Self::new(dir)
+        }
+    }
+
+    fn path(&self, source: &str) -> PathBuf {
+        let file_name: String = source
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.json", file_name))
+    }
+
+    /// Persist a snapshot (written to a temp file and renamed into place)
+    pub fn save(&self, source: &str, snapshot: &Snapshot) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let mut data = serde_json::to_vec(snapshot)?;
+        if let Some(keys) = &self.keys {
+            data = keys.seal(&data)?;
+        }
+
+        let path = self.path(source);
+        let tmp = path.with_extension("json.tmp");
+        write_private(&tmp, &data)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Load the last good snapshot, if any
+    pub fn load(&self, source: &str) -> Result<Option<Snapshot>> {
+        let path = self.path(source);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let mut data = std::fs::read(&path)?;
+        if let Some(keys) = &self.keys {
+            data = keys.open(&data)?.0;
+        }
+        Ok(Some(serde_json::from_slice(&data)?))
+    }
+}
+
+/// Write a file only its owner can read
+fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        // `mode` only applies to new files; tighten a leftover temp file too
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(data)?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct Layer {
+    revision: Option<Revision>,
+    document: Option<toml::Table>,
+}
+
+/// Layered configuration: local directory, then remote sources, then env
+pub struct RemoteConfig {
+    local_dir: PathBuf,
+    sources: Vec<Box<dyn ConfigSource>>,
+    snapshots: Option<SnapshotStore>,
+    interval: Duration,
+    layers: Mutex<Vec<Layer>>,
+}
+
+impl RemoteConfig {
+    pub fn new(local_dir: impl AsRef<Path>) -> Self {
+        Self {
+            local_dir: local_dir.as_ref().to_path_buf(),
+            sources: Vec::new(),
+            snapshots: None,
+            interval: Duration::from_secs(30),
+            layers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add a source; later sources override earlier ones
+    pub fn source(mut self, source: impl ConfigSource + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// Persist last good documents in `dir` and fall back to them on failure
+    pub fn snapshot_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.snapshots = Some(SnapshotStore::new(dir));
+        self
+    }
+
+    /// Like [`RemoteConfig::snapshot_dir`], encrypting the snapshots with `keys`
+    pub fn encrypted_snapshot_dir(mut self, dir: impl AsRef<Path>, keys: CacheKeys) -> Self {
+        self.snapshots = Some(SnapshotStore::encrypted(dir, keys));
+        self
+    }
+
+    /// Polling interval used by [`RemoteConfig::spawn`]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Fetch every source and build the merged configuration
+    ///
+    /// A source that cannot be reached falls back to its snapshot; without a
+    /// snapshot the error is returned.
+    pub async fn load(&self) -> Result<Config> {
+        let mut layers = self.layers.lock().await;
+        layers.resize_with(self.sources.len(), Layer::default);
+
+        for (source, layer) in self.sources.iter().zip(layers.iter_mut()) {
+            if let Err(err) = self.refresh(source.as_ref(), layer).await {
+                let snapshot = match &self.snapshots {
+                    Some(store) => store.load(source.name())?,
+                    None => None,
+                };
+                let snapshot = snapshot.ok_or_else(|| {
+                    err.context(format!("config source '{}' unavailable", source.name()))
+                })?;
+
+                tracing::warn!(
+                    source = source.name(),
+                    "config source unavailable, using last good snapshot"
+                );
+                layer.revision = snapshot.revision;
+                layer.document = Some(snapshot.document);
+            }
+        }
+
+        self.build(&layers)
+    }
+
+    /// Load the configuration and make it the global config
+    pub async fn install(self) -> Result<Arc<Self>> {
+        let config = self.load().await?;
+        replace(config);
+        Ok(Arc::new(self))
+    }
+
+    /// Poll all sources in the background, swapping the global config on change
+    ///
+    /// Failed polls keep the last good configuration in place.
+    pub fn spawn(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let this = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(this.interval);
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+                match this.poll().await {
+                    Ok(Some(config)) => replace(config),
+                    Ok(None) => {}
+                    Err(err) => tracing::warn!(error = %err, "config reload failed"),
+                }
+            }
+        })
+    }
+
+    /// Poll all sources once; returns a new config if anything changed
+    pub async fn poll(&self) -> Result<Option<Config>> {
+        let mut layers = self.layers.lock().await;
+        layers.resize_with(self.sources.len(), Layer::default);
+
+        let mut changed = false;
+        for (source, layer) in self.sources.iter().zip(layers.iter_mut()) {
+            match self.refresh(source.as_ref(), layer).await {
+                Ok(updated) => changed |= updated,
+                Err(err) => {
+                    tracing::warn!(source = source.name(), error = %err, "config source poll failed")
+                }
+            }
+        }
+
+        if !changed {
+            return Ok(None);
+        }
+        self.build(&layers).map(Some)
+    }
+
+    /// Fetch one source into its layer; returns whether the document changed
+    async fn refresh(&self, source: &dyn ConfigSource, layer: &mut Layer) -> Result<bool> {
+        // Without a document we need a full fetch, whatever revision we saw
+        let since = layer.document.as_ref().and(layer.revision.as_ref());
+
+        match source.fetch(since).await? {
+            Fetch::NotModified => Ok(false),
+            Fetch::Updated { document, revision } => {
+                if let Some(store) = &self.snapshots {
+                    let snapshot = Snapshot {
+                        revision: revision.clone(),
+                        document: document.clone(),
+                    };
+                    if let Err(err) = store.save(source.name(), &snapshot) {
+                        tracing::warn!(source = source.name(), error = %err, "failed to persist config snapshot");
+                    }
+                }
+                layer.revision = revision;
+                layer.document = Some(document);
+                Ok(true)
+            }
+        }
+    }
+
+    fn build(&self, layers: &[Layer]) -> Result<Config> {
+        let mut config = if self.local_dir.exists() {
+            Config::load_from_dir(&self.local_dir)?
+        } else {
+            Config::default()
+        };
+
+        for document in layers.iter().filter_map(|layer| layer.document.as_ref()) {
+            merge_document(&mut config, document)?;
+        }
+
+        // Environment always wins over remote values
+        config.apply_env_overrides();
+        Ok(config)
+    }
+}
+
+/// Merge every section of a remote document into the config
+fn merge_document(config: &mut Config, document: &toml::Table) -> Result<()> {
+    for (section, value) in document {
+        config
+            .merge_section(section, value.clone())
+            .with_context(|| format!("invalid remote config section '{}'", section))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    struct StaticSource {
+        body: &'static str,
+        fail: AtomicBool,
+    }
+
+    #[async_trait]
+    impl ConfigSource for StaticSource {
+        fn name(&self) -> &str {
+            "static"
+        }
+
+        async fn fetch(&self, since: Option<&Revision>) -> Result<Fetch> {
+            if self.fail.load(Ordering::SeqCst) {
+                bail!("unreachable");
+            }
+            if since == Some(&Revision::Index(1)) {
+                return Ok(Fetch::NotModified);
+            }
+            Ok(Fetch::Updated {
+                document: toml::from_str(self.body)?,
+                revision: Some(Revision::Index(1)),
+            })
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rf-config-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_merge_document_is_deep() {
+        let mut config = Config::default();
+        let document: toml::Table = toml::from_str("[app]\nname = \"Remote\"").unwrap();
+
+        merge_document(&mut config, &document).unwrap();
+
+        assert_eq!(config.app.name, "Remote");
+        assert_eq!(config.app.port, 3000);
+    }
+
+    #[test]
+    fn test_s3_requests_are_signed() {
+        let source = HttpSource::s3(
+            "my-bucket",
+            "eu-central-1",
+            "myapp/prod config.toml",
+            S3Credentials::new("AKIDEXAMPLE", "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY")
+                .session_token("token"),
+        );
+        assert_eq!(
+            source.url,
+            "https://my-bucket.s3.eu-central-1.amazonaws.com/myapp/prod%20config.toml"
+        );
+        assert_eq!(source.name(), "s3://my-bucket/myapp/prod config.toml");
+
+        let signer = source.s3.as_ref().unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap();
+        let headers = signer.sign(now);
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v.as_str())
+        };
+
+        assert_eq!(header("x-amz-date"), Some("20240115T120000Z"));
+        assert_eq!(header("x-amz-security-token"), Some("token"));
+        assert_eq!(header("host"), None);
+        assert!(header("authorization").unwrap().starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240115/eu-central-1/s3/aws4_request, \
+             SignedHeaders=host;x-amz-content-sha256;x-amz-date;x-amz-security-token, Signature="
+        ));
+        assert_eq!(signer.sign(now), headers);
+        assert_ne!(signer.sign(now + chrono::Duration::seconds(1)), headers);
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let store = SnapshotStore::new(temp_dir("snapshot"));
+        let snapshot = Snapshot {
+            revision: Some(Revision::ETag("\"abc\"".to_string())),
+            document: toml::from_str("[app]\ndebug = false").unwrap(),
+        };
+
+        store.save("consul:myapp/config", &snapshot).unwrap();
+        let loaded = store.load("consul:myapp/config").unwrap().unwrap();
+
+        assert_eq!(loaded.revision, snapshot.revision);
+        assert_eq!(loaded.document, snapshot.document);
+        assert!(store.load("missing").unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_snapshots_are_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let store = SnapshotStore::new(temp_dir("permissions"));
+        let snapshot = Snapshot {
+            revision: None,
+            document: toml::from_str("[app]\ndebug = false").unwrap(),
+        };
+
+        store.save("static", &snapshot).unwrap();
+        let mode = std::fs::metadata(store.path("static"))
+            .unwrap()
+            .permissions()
+            .mode();
+
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_encrypted_snapshots() {
+        let dir = temp_dir("encrypted");
+        let keys = || CacheKeys::new("test-key", &[]).unwrap();
+        let store = SnapshotStore::encrypted(&dir, keys());
+        let snapshot = Snapshot {
+            revision: Some(Revision::Index(7)),
+            document: toml::from_str("[database]\nurl = \"postgres://secret\"").unwrap(),
+        };
+
+        store.save("consul:myapp/config", &snapshot).unwrap();
+        let raw = std::fs::read(store.path("consul:myapp/config")).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("secret"));
+
+        let loaded = store.load("consul:myapp/config").unwrap().unwrap();
+        assert_eq!(loaded.document, snapshot.document);
+
+        let reopened = SnapshotStore::encrypted(&dir, keys());
+        assert!(reopened.load("consul:myapp/config").unwrap().is_some());
+        let other = SnapshotStore::encrypted(&dir, CacheKeys::new("other-key", &[]).unwrap());
+        assert!(other.load("consul:myapp/config").is_err());
+    }
+
+    #[test]
+    fn test_etcd_polls_by_revision() {
+        let source = EtcdSource::new("http://etcd:2379", "myapp/config");
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        assert!(source.range_request(None).get("min_mod_revision").is_none());
+        assert_eq!(
+            source.range_request(Some(&Revision::Index(41)))["min_mod_revision"],
+            "42"
+        );
+
+        let response = |json: serde_json::Value| serde_json::from_value(json).unwrap();
+        let updated = source
+            .read_range(response(serde_json::json!({
+                "kvs": [{ "value": engine.encode("[app]\ndebug = true"), "mod_revision": "42" }],
+                "count": "1",
+            })))
+            .unwrap();
+        match updated {
+            Fetch::Updated { document, revision } => {
+                assert_eq!(document["app"]["debug"].as_bool(), Some(true));
+                assert_eq!(revision, Some(Revision::Index(42)));
+            }
+            Fetch::NotModified => panic!("expected an update"),
+        }
+
+        let unchanged = source.read_range(response(serde_json::json!({ "count": "1" })));
+        assert!(matches!(unchanged.unwrap(), Fetch::NotModified));
+        assert!(source.read_range(response(serde_json::json!({}))).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_snapshot() {
+        let dir = temp_dir("fallback");
+        let source = StaticSource {
+            body: "[app]\nurl = \"https://remote.example\"",
+            fail: AtomicBool::new(false),
+        };
+
+        let remote = RemoteConfig::new(dir.join("missing")).snapshot_dir(&dir).source(source);
+        remote.load().await.unwrap();
+        assert!(remote.poll().await.unwrap().is_none());
+
+        let failing = StaticSource {
+            body: "",
+            fail: AtomicBool::new(true),
+        };
+        let config = RemoteConfig::new(dir.join("missing"))
+            .snapshot_dir(&dir)
+            .source(failing)
+            .load()
+            .await
+            .unwrap();
+
+        assert_eq!(config.app.url, "https://remote.example");
+    }
+}
@@ -336,18 +336,23 @@ impl ProjectWizard {
         if self.features.database {
             dependencies.insert("sea-orm", r#"{ version = "0.12", features = ["runtime-tokio-rustls", "sqlx-postgres"] }"#);
             dependencies.insert("sqlx", r#"{ version = "0.7", features = ["runtime-tokio-rustls", "postgres"] }"#);
+            dependencies.insert("rf-migrate", r#"{ version = "0.1", features = ["cli"] }"#);
         }
 
         if self.features.authentication {
             dependencies.insert("jsonwebtoken", "9.2");
             dependencies.insert("argon2", "0.5");
-            dependencies.insert("tower-sessions", "0.12");
+            dependencies.insert("rf-session", "0.1");
         }
 
         if self.features.cache {
             dependencies.insert("redis", r#"{ version = "0.25", features = ["tokio-comp", "connection-manager"] }"#);
         }
 
+        if matches!(self.project_type, ProjectType::WebSocketServer) {
+            dependencies.insert("rf-websocket", "0.1");
+        }
+
         if self.features.graphql {
             dependencies.insert("async-graphql", r#"{ version = "7.0", features = ["chrono"] }"#);
             dependencies.insert("async-graphql-axum", "7.0");
@@ -705,63 +710,45 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {{
     }
 
     fn generate_websocket_main(&self) -> String {
-        format!(r#"use rustforge::websocket::prelude::*;
-use axum::{{
-    extract::ws::{{WebSocket, WebSocketUpgrade}},
-    response::IntoResponse,
-    Router, routing::get,
-}};
+        format!(r#"use axum::{{response::Html, routing::{{get, post}}, Json, Router}};
+use rf_websocket::{{websocket_router, Broadcaster, Channel, Channels, UserId, WebSocketServer}};
+use serde_json::{{json, Value}};
 use std::net::SocketAddr;
-use futures::{{SinkExt, StreamExt}};
-
-async fn ws_handler(ws: WebSocketUpgrade) -> impl IntoResponse {{
-    ws.on_upgrade(handle_socket)
-}}
-
-async fn handle_socket(mut socket: WebSocket) {{
-    println!("New WebSocket connection");
-
-    while let Some(msg) = socket.recv().await {{
-        if let Ok(msg) = msg {{
-            match msg {{
-                axum::extract::ws::Message::Text(text) => {{
-                    println!("Received: {{text}}");
-                    if socket
-                        .send(axum::extract::ws::Message::Text(format!("Echo: {{text}}")))
-                        .await
-                        .is_err()
-                    {{
-                        break;
-                    }}
-                }}
-                axum::extract::ws::Message::Close(_) => break,
-                _ => {{}}
-            }}
-        }} else {{
-            break;
-        }}
-    }}
-
-    println!("WebSocket connection closed");
-}}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {{
     tracing_subscriber::fmt::init();
 
+    let server = WebSocketServer::default()
+        // Resolve `/ws?token=...` to a user ID; connections without a token are guests
+        .authenticator(|token: &str| Ok::<_, String>(token.to_string()))
+        .channels(
+            Channels::new()
+                .channel("chat", |user_id: &UserId, _: &Channel| Some(json!({{ "id": user_id }}))),
+        );
+    let broadcaster = server.broadcaster();
+
     let app = Router::new()
-        .route("/ws", get(ws_handler))
+        .merge(websocket_router(server))
+        .route("/broadcast", post(move |Json(data): Json<Value>| broadcast(broadcaster, data)))
         .route("/", get(|| async {{ Html(include_str!("../static/index.html")) }}));
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
     tracing::info!("🔌 WebSocket server running on http://{{addr}}");
 
-    axum::Server::bind(&addr)
-        .serve(app.into_make_service())
-        .await?;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await?;
 
     Ok(())
 }}
+
+async fn broadcast(broadcaster: Broadcaster, data: Value) -> Json<Value> {{
+    let sent = broadcaster
+        .broadcast(&Channel::public("news"), "news.published", &data)
+        .await
+        .is_ok();
+    Json(json!({{ "sent": sent }}))
+}}
 "#)
     }
 
@@ -844,24 +831,44 @@ pub struct UserInfo {
     pub name: String,
 }
 
+#[derive(Serialize)]
+struct Claims {
+    sub: String,
+    exp: u64,
+}
+
 pub async fn login(Json(req): Json<LoginRequest>) -> Result<Json<LoginResponse>, StatusCode> {
     // TODO: Verify credentials from database
 
-    let claims = UserInfo {
+    let user = UserInfo {
         id: 1,
         email: req.email.clone(),
         name: "User".to_string(),
     };
 
+    let secret = std::env::var("JWT_SECRET").map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let expiration: u64 = std::env::var("JWT_EXPIRATION")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(86400);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .as_secs();
+
+    let claims = Claims {
+        sub: user.id.to_string(),
+        exp: now + expiration,
+    };
     let token = encode(
         &Header::default(),
         &claims,
-        &EncodingKey::from_secret("secret".as_ref()),
+        &EncodingKey::from_secret(secret.as_bytes()),
     ).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(LoginResponse {
         token,
-        user: claims,
+        user,
     }))
 }
 "#,
@@ -953,6 +960,7 @@ pub type User = Model;
             env_content.push_str("\n# Authentication\n");
             env_content.push_str("JWT_SECRET=your-secret-key-change-this\n");
             env_content.push_str("JWT_EXPIRATION=86400\n");
+            env_content.push_str("SESSION_SECRET=change-this-to-at-least-32-random-bytes\n");
         }
 
         fs::write(path.join(".env"), env_content)?;
@@ -1311,7 +1319,7 @@ jobs:
 
         // Create initial migration
         let timestamp = chrono::Utc::now().format("%Y%m%d%H%M%S");
-        let migration_name = format!("{}_{}_create_users_table.sql", timestamp, "001");
+        let migration_name = format!("{}_create_users_table", timestamp);
 
         let migration_content = match self.database.as_ref().map(|d| &d.driver) {
             Some(DatabaseDriver::PostgreSQL) => r#"-- Create users table
@@ -1356,8 +1364,16 @@ CREATE INDEX idx_users_created_at ON users(created_at);
             _ => "",
         };
 
+        // Reversible pair in the layout rf-migrate runs
         if !migration_content.is_empty() {
-            fs::write(migrations_path.join(migration_name), migration_content)?;
+            fs::write(
+                migrations_path.join(format!("{}.up.sql", migration_name)),
+                migration_content,
+            )?;
+            fs::write(
+                migrations_path.join(format!("{}.down.sql", migration_name)),
+                "DROP TABLE users;\n",
+            )?;
         }
 
         Ok(())